@@ -0,0 +1,4 @@
+// 新增或修改迁移文件时重新编译，使 sqlx::migrate! 嵌入最新的迁移
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+}
@@ -31,6 +31,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         default_temperature: 0.8,
         enable_fallback: true,
         fallback_providers: vec![Provider::Ollama, Provider::Ali],
+        ..Default::default()
     };
 
     // 使用数据库版本创建dispatcher
@@ -66,6 +67,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             images: None,
             tool_calls: None,
             tool_name: None,
+            tool_call_id: None,
         }
     ];
 
@@ -147,6 +149,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     images: None,
                     tool_calls: None,
                     tool_name: None,
+                    tool_call_id: None,
                 }],
             ).with_temperature(0.5).with_max_tokens(50);
 
@@ -74,6 +74,7 @@ async fn basic_example(model: &str) -> Result<()> {
             images: None,
             tool_calls: None,
             tool_name: None,
+            tool_call_id: None,
         },
         Message {
             role: "user".to_string(),
@@ -82,6 +83,7 @@ async fn basic_example(model: &str) -> Result<()> {
             images: None,
             tool_calls: None,
             tool_name: None,
+            tool_call_id: None,
         },
     ];
     
@@ -122,6 +124,7 @@ async fn streaming_example(model: &str) -> Result<()> {
             images: None,
             tool_calls: None,
             tool_name: None,
+            tool_call_id: None,
         },
     ];
     
@@ -167,6 +170,7 @@ async fn custom_config_example(model: &str) -> Result<()> {
             images: None,
             tool_calls: None,
             tool_name: None,
+            tool_call_id: None,
         },
     ];
     
@@ -227,6 +231,7 @@ async fn tool_example(model: &str) -> Result<()> {
             images: None,
             tool_calls: None,
             tool_name: None,
+            tool_call_id: None,
         },
         Message {
             role: "user".to_string(),
@@ -235,6 +240,7 @@ async fn tool_example(model: &str) -> Result<()> {
             images: None,
             tool_calls: None,
             tool_name: None,
+            tool_call_id: None,
         },
     ];
     
@@ -68,6 +68,7 @@ async fn main() -> Result<()> {
             images: None,
             tool_calls: None,
             tool_name: None,
+            tool_call_id: None,
         },
     ];
     
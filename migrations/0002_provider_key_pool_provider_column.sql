@@ -0,0 +1,2 @@
+-- provider_key_pools 的供应商列在初始表结构中误写为 provider_，与代码中使用的列名不一致
+ALTER TABLE provider_key_pools RENAME COLUMN provider_ TO provider;
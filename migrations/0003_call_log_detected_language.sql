@@ -0,0 +1,2 @@
+-- 按语言路由：请求提示词检测出的语言
+ALTER TABLE call_logs ADD COLUMN detected_language TEXT; -- ISO 639-3 language code of the prompt
@@ -0,0 +1,13 @@
+-- 关键词/敏感词黑名单，tenant_id 为 NULL 表示全局规则
+CREATE TABLE IF NOT EXISTS blocklist_entries (
+    id TEXT PRIMARY KEY,
+    tenant_id TEXT,
+    pattern TEXT NOT NULL,
+    action TEXT NOT NULL DEFAULT 'block', -- block, mask, flag
+    scope TEXT NOT NULL DEFAULT 'both',   -- prompt, response, both
+    is_active BOOLEAN DEFAULT 1,
+    created_at TEXT DEFAULT (datetime('now', 'localtime')),
+    updated_at TEXT DEFAULT (datetime('now', 'localtime'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_blocklist_entries_tenant ON blocklist_entries(tenant_id);
@@ -0,0 +1,2 @@
+-- 供应商专属配置(JSON)，例如Azure的部署映射
+ALTER TABLE providers ADD COLUMN config TEXT;
@@ -0,0 +1,12 @@
+-- 调用记录的用量、费用和供应商账单信息
+ALTER TABLE call_logs ADD COLUMN tokens_input INTEGER DEFAULT 0;
+ALTER TABLE call_logs ADD COLUMN cost REAL DEFAULT 0;          -- 按模型单价计算的费用
+ALTER TABLE call_logs ADD COLUMN provider TEXT;
+ALTER TABLE call_logs ADD COLUMN provider_request_id TEXT;     -- 供应商返回的请求 ID
+ALTER TABLE call_logs ADD COLUMN provider_usage TEXT;          -- 供应商返回的原始用量(JSON)，用于账单对账
+ALTER TABLE call_logs ADD COLUMN key_id TEXT;                  -- 使用的 API Key ID（provider_key_pools.id）
+ALTER TABLE call_logs ADD COLUMN request_summary TEXT;         -- 请求摘要（截断的最后一条用户消息）
+ALTER TABLE call_logs ADD COLUMN finish_reason TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_call_logs_provider ON call_logs(provider);
+CREATE INDEX IF NOT EXISTS idx_call_logs_key_id ON call_logs(key_id);
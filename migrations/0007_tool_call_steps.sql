@@ -0,0 +1,16 @@
+-- 工具调用审计：模型发起的每次工具调用（输入、输出、耗时），按 call_log_id 关联到发起调用的模型请求
+CREATE TABLE IF NOT EXISTS tool_call_steps (
+    id TEXT PRIMARY KEY,
+    call_log_id TEXT NOT NULL,
+    step_index INTEGER NOT NULL,     -- 同一次会话中的执行顺序
+    tool_call_id TEXT,               -- 模型返回的 tool_call id
+    tool_name TEXT NOT NULL,
+    arguments TEXT,                  -- 工具输入(JSON)
+    output TEXT,                     -- 工具输出
+    error_message TEXT,
+    latency_ms INTEGER DEFAULT 0,
+    created_at TEXT DEFAULT (datetime('now', 'localtime')),
+    FOREIGN KEY(call_log_id) REFERENCES call_logs(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_tool_call_steps_call_log ON tool_call_steps(call_log_id);
@@ -0,0 +1,12 @@
+-- 按供应商/模型/天汇总的用量和费用，用于月度预算限额
+CREATE TABLE IF NOT EXISTS usage_stats (
+    day TEXT NOT NULL,               -- YYYY-MM-DD（本地时间）
+    provider TEXT NOT NULL,
+    model TEXT NOT NULL,
+    request_count INTEGER NOT NULL DEFAULT 0,
+    tokens_input INTEGER NOT NULL DEFAULT 0,
+    tokens_output INTEGER NOT NULL DEFAULT 0,
+    cost REAL NOT NULL DEFAULT 0,
+    updated_at TEXT DEFAULT (datetime('now', 'localtime')),
+    PRIMARY KEY(day, provider, model)
+);
@@ -0,0 +1,9 @@
+-- 按调用方 Key/天汇总的请求数和 token 数，由内存中的配额计数定期写回
+CREATE TABLE IF NOT EXISTS consumer_usage (
+    day TEXT NOT NULL,               -- YYYY-MM-DD（本地时间）
+    consumer_id TEXT NOT NULL,       -- 调用方 Key 的指纹
+    request_count INTEGER NOT NULL DEFAULT 0,
+    tokens INTEGER NOT NULL DEFAULT 0,
+    updated_at TEXT DEFAULT (datetime('now', 'localtime')),
+    PRIMARY KEY(day, consumer_id)
+);
@@ -0,0 +1,25 @@
+-- 项目（工作空间）：供应商、模型、API Key 和调用记录都归属一个项目，default 为默认项目
+CREATE TABLE IF NOT EXISTS projects (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    is_active BOOLEAN DEFAULT 1,
+    created_at TEXT DEFAULT (datetime('now', 'localtime')),
+    updated_at TEXT DEFAULT (datetime('now', 'localtime'))
+);
+
+INSERT OR IGNORE INTO projects (id, name, description) VALUES ('default', '默认项目', '未绑定项目的网关 Key 和资源归属的项目');
+
+-- 网关 Key（调用方 Key 指纹）与项目的绑定，未绑定的 Key 属于 default 项目
+CREATE TABLE IF NOT EXISTS project_gateway_keys (
+    consumer_id TEXT PRIMARY KEY,
+    project_id TEXT NOT NULL,
+    created_at TEXT DEFAULT (datetime('now', 'localtime')),
+    FOREIGN KEY(project_id) REFERENCES projects(id)
+);
+
+-- 已有的资源都归属 default 项目；default 项目的供应商和模型所有项目共享
+ALTER TABLE models ADD COLUMN project_id TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE providers ADD COLUMN project_id TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE provider_key_pools ADD COLUMN project_id TEXT NOT NULL DEFAULT 'default'; -- 只有该项目的请求会使用这个 Key
+ALTER TABLE call_logs ADD COLUMN project_id TEXT NOT NULL DEFAULT 'default';          -- 发起请求的项目
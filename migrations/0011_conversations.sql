@@ -0,0 +1,26 @@
+-- 会话：网关保存多轮对话历史，无状态客户端只需发送新消息
+CREATE TABLE IF NOT EXISTS conversations (
+    id TEXT PRIMARY KEY,
+    title TEXT,
+    model TEXT NOT NULL,             -- 对话使用的模型（与 /v1/chat/completions 的 model 相同）
+    system_prompt TEXT,
+    max_context_tokens INTEGER,      -- 发送给模型的历史 token 上限，NULL 表示不截断
+    truncation TEXT NOT NULL DEFAULT 'drop_oldest', -- 超出上限时的截断策略：drop_oldest、keep_first_turn
+    project_id TEXT NOT NULL DEFAULT 'default',     -- 创建会话的项目，其他项目不可见
+    created_at TEXT DEFAULT (datetime('now', 'localtime')),
+    updated_at TEXT DEFAULT (datetime('now', 'localtime'))
+);
+
+CREATE TABLE IF NOT EXISTS conversation_messages (
+    id TEXT PRIMARY KEY,
+    conversation_id TEXT NOT NULL,
+    seq INTEGER NOT NULL,            -- 会话内的消息顺序，从 1 开始
+    role TEXT NOT NULL,              -- user、assistant
+    content TEXT NOT NULL,
+    tokens INTEGER NOT NULL DEFAULT 0, -- 消息的 token 数（助手消息取上游返回的 completion_tokens，其余为估算值）
+    created_at TEXT DEFAULT (datetime('now', 'localtime')),
+    FOREIGN KEY(conversation_id) REFERENCES conversations(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_conversation_messages_conversation ON conversation_messages(conversation_id, seq);
+CREATE INDEX IF NOT EXISTS idx_conversations_project ON conversations(project_id);
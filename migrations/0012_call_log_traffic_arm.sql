@@ -0,0 +1,4 @@
+-- A/B 流量拆分：调用记录所属的分组（逻辑模型名:分组名），未参与拆分的请求为 NULL
+ALTER TABLE call_logs ADD COLUMN traffic_arm TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_call_logs_traffic_arm ON call_logs(traffic_arm);
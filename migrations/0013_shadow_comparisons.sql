@@ -0,0 +1,16 @@
+-- 影子流量：主请求与镜像到另一个供应商的请求的响应和耗时，供离线比较
+CREATE TABLE IF NOT EXISTS shadow_comparisons (
+    id TEXT PRIMARY KEY,
+    primary_provider TEXT NOT NULL,
+    primary_model TEXT NOT NULL,
+    primary_latency_ms INTEGER NOT NULL, -- in milliseconds
+    primary_response TEXT,
+    shadow_provider TEXT NOT NULL,
+    shadow_model TEXT NOT NULL,
+    shadow_latency_ms INTEGER NOT NULL,
+    shadow_response TEXT,
+    shadow_error TEXT,
+    created_at TEXT DEFAULT (datetime('now', 'localtime'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_shadow_comparisons_created_at ON shadow_comparisons(created_at);
@@ -0,0 +1,18 @@
+-- 管理接口审计日志：供应商、模型、API Key 的创建/更新/删除，记录操作人和变更前后的快照
+CREATE TABLE IF NOT EXISTS audit_logs (
+    id TEXT PRIMARY KEY,
+    actor TEXT NOT NULL,
+    action TEXT NOT NULL,          -- create / update / delete
+    resource_type TEXT NOT NULL,   -- provider / model / api_key
+    resource_id TEXT,
+    method TEXT NOT NULL,
+    path TEXT NOT NULL,
+    status_code INTEGER NOT NULL,
+    before_snapshot TEXT,          -- JSON，不含密钥
+    after_snapshot TEXT,           -- JSON，不含密钥
+    created_at TEXT DEFAULT (datetime('now', 'localtime'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_logs_created_at ON audit_logs(created_at);
+CREATE INDEX IF NOT EXISTS idx_audit_logs_resource ON audit_logs(resource_type, resource_id);
+CREATE INDEX IF NOT EXISTS idx_audit_logs_actor ON audit_logs(actor);
@@ -0,0 +1,18 @@
+-- 调用日志按天汇总：保留期任务删除原始日志前，把这些日志按天（UTC，与 call_logs.created_at 一致）、
+-- 供应商、模型和项目汇总到这里，供长期趋势统计使用；缺失的供应商和模型记为空字符串
+CREATE TABLE IF NOT EXISTS call_log_daily_stats (
+    day TEXT NOT NULL,                 -- YYYY-MM-DD
+    provider TEXT NOT NULL DEFAULT '',
+    model_id TEXT NOT NULL DEFAULT '',
+    project_id TEXT NOT NULL DEFAULT 'default',
+    total_calls INTEGER NOT NULL DEFAULT 0,
+    error_count INTEGER NOT NULL DEFAULT 0,
+    tokens_input INTEGER NOT NULL DEFAULT 0,
+    tokens_output INTEGER NOT NULL DEFAULT 0,
+    cost REAL NOT NULL DEFAULT 0,
+    total_duration INTEGER NOT NULL DEFAULT 0, -- in milliseconds, sum of all calls
+    updated_at TEXT DEFAULT (datetime('now', 'localtime')),
+    PRIMARY KEY (day, provider, model_id, project_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_call_log_daily_stats_day ON call_log_daily_stats(day);
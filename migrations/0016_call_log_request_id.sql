@@ -0,0 +1,4 @@
+-- 网关请求 ID（X-Request-Id），同一请求 fallback 产生的多条调用记录共用；旧记录为 NULL
+ALTER TABLE call_logs ADD COLUMN request_id TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_call_logs_request_id ON call_logs(request_id);
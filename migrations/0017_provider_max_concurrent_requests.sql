@@ -0,0 +1,2 @@
+-- 供应商最大并发请求数，超出后排队或返回 provider_overloaded；NULL 表示不限制
+ALTER TABLE providers ADD COLUMN max_concurrent_requests INTEGER;
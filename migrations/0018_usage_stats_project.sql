@@ -0,0 +1,20 @@
+-- 用量统计按项目区分，月度预算按项目分别计算；已有的统计归属 default 项目。
+-- SQLite 不能修改主键，重建表后迁移数据
+CREATE TABLE usage_stats_by_project (
+    day TEXT NOT NULL,               -- YYYY-MM-DD（本地时间）
+    project_id TEXT NOT NULL DEFAULT 'default',
+    provider TEXT NOT NULL,
+    model TEXT NOT NULL,
+    request_count INTEGER NOT NULL DEFAULT 0,
+    tokens_input INTEGER NOT NULL DEFAULT 0,
+    tokens_output INTEGER NOT NULL DEFAULT 0,
+    cost REAL NOT NULL DEFAULT 0,
+    updated_at TEXT DEFAULT (datetime('now', 'localtime')),
+    PRIMARY KEY(day, project_id, provider, model)
+);
+
+INSERT INTO usage_stats_by_project (day, project_id, provider, model, request_count, tokens_input, tokens_output, cost, updated_at)
+SELECT day, 'default', provider, model, request_count, tokens_input, tokens_output, cost, updated_at FROM usage_stats;
+
+DROP TABLE usage_stats;
+ALTER TABLE usage_stats_by_project RENAME TO usage_stats;
@@ -0,0 +1,17 @@
+//! # 对外 API 类型
+//!
+//! Web 接口的 DTO 以及 `DispatchRequest`/`DispatchResponse` 等调度类型统一定义在这里，
+//! 外部客户端和嵌入网关的库调用方共用同一套类型。类型按版本划分模块（`v1`），
+//! 同一版本内遵循只增不改的演进规则：
+//! - 新增字段必须是 `Option` 或带 `#[serde(default)]`，旧版本的请求体可以直接反序列化
+//! - 不删除、不重命名已有字段，不修改字段类型和枚举的序列化取值
+//! - 需要不兼容的修改时新建版本模块（如 `v2`），旧版本保留到调用方迁移完成
+//!
+//! 默认忽略未知字段，以便旧客户端读取新版本的响应。开启 `strict-api-types` feature 后
+//! 结构体拒绝未知字段，适合希望尽早发现拼写错误或协议漂移的调用方。
+//! `Message` 同时用于解析供应商的响应，始终忽略未知字段
+
+pub mod v1;
+
+/// 当前 API 类型版本
+pub const CURRENT_VERSION: &str = "v1";
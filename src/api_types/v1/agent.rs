@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::api_types::v1::chat_completion::{ChatCompletionMessage, ChatCompletionResponseMessage, ChatCompletionUsage};
+
+/// 由网关执行工具调用的 Agent 对话请求
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct AgentChatRequest {
+    pub model: String,                      // 与 /v1/chat/completions 的 model 相同，可带 provider/ 前缀
+    pub messages: Vec<ChatCompletionMessage>,
+    #[serde(default)]
+    pub tools: Vec<AgentToolDefinition>,
+    pub max_iterations: Option<u32>,        // 最多调用模型的轮数，默认 5，最大 20
+    pub tool_timeout_ms: Option<u64>,       // 单次工具执行的超时，默认 10000，最大 60000
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+    pub user: Option<String>,
+}
+
+/// Agent 可用的工具：提供 webhook_url 时为 Webhook 工具，否则使用同名的内置工具
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct AgentToolDefinition {
+    pub name: String,
+    pub description: Option<String>,       // 内置工具不传时使用内置的描述
+    pub parameters: Option<Value>,          // 参数 JSON Schema，内置工具不传时使用内置的定义
+    pub webhook_url: Option<String>,        // 网关把 {request_id, tool_call_id, name, arguments} POST 到该地址，响应体作为工具结果
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct AgentChatResponse {
+    pub id: String,
+    pub object: String,                     // 固定为 "agent.chat"
+    pub created: i64,
+    pub model: String,
+    pub message: ChatCompletionResponseMessage, // 最后一轮的助手消息
+    pub finish_reason: String,              // 模型的结束原因；达到最大轮数时为 max_iterations
+    pub iterations: u32,                    // 调用模型的轮数
+    pub steps: Vec<AgentStepResponse>,      // 按执行顺序的工具调用
+    pub usage: Option<ChatCompletionUsage>, // 各轮用量之和
+}
+
+/// 一次工具执行，output 和 error 只有一个有值
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct AgentStepResponse {
+    pub iteration: u32,
+    pub tool_call_id: String,
+    pub name: String,
+    pub arguments: Value,
+    pub output: Option<String>,
+    pub error: Option<String>,
+    pub latency_ms: u64,
+    pub call_log_id: Option<String>,        // 发起该调用的模型请求的调用记录
+}
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
 pub struct ApiKeyResponse {
     pub id: String,
     pub provider: String,
@@ -10,27 +11,45 @@ pub struct ApiKeyResponse {
     pub last_used_at: Option<String>,
     pub rate_limit_per_minute: Option<i64>,
     pub rate_limit_per_hour: Option<i64>,
+    pub project_id: String,
     pub created_at: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
 pub struct CreateApiKeyRequest {
     pub provider_id: String,
     pub api_key: String,
     pub rate_limit_per_minute: Option<i64>,
     pub rate_limit_per_hour: Option<i64>,
+    pub project_id: Option<String>, // 所属项目，只有该项目的请求会使用这个 Key，默认 default
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
 pub struct UpdateApiKeyRequest {
     pub is_active: Option<bool>,
     pub rate_limit_per_minute: Option<i64>,
     pub rate_limit_per_hour: Option<i64>,
+    pub project_id: Option<String>,
 }
 
+/// 旧版 API Key 列表响应，`GET /api/providers/:id/api-keys` 已改为返回 [`Page`](super::page::Page)
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
 pub struct ApiKeyListResponse {
     pub provider_id: String,
     pub provider_name: String,
     pub keys: Vec<ApiKeyResponse>,
 }
+
+/// `GET /api/providers/:id/api-keys` 的过滤条件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct ApiKeyListFilter {
+    pub provider_id: String,
+    /// 供应商名称（provider_key_pools.provider）
+    pub provider: String,
+    pub active: Option<bool>,
+    pub project_id: Option<String>,
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+use crate::api_types::v1::chat_completion::{ChatCompletionRequest, ChatCompletionResponse, OpenAIErrorBody};
+
+/// 提交批量 Chat Completion 任务
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct BatchChatRequest {
+    pub requests: Vec<BatchChatItem>,
+    pub concurrency: Option<usize>,         // 同时执行的请求数，默认 4
+}
+
+/// 批量任务中的单个请求
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct BatchChatItem {
+    pub custom_id: Option<String>,          // 调用方自定义的标识，原样返回
+    pub body: ChatCompletionRequest,        // 与 /v1/chat/completions 的请求体相同，不支持 stream
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct BatchChatJobResponse {
+    pub id: String,
+    pub object: String,                     // 固定为 "batch"
+    pub status: String,                     // in_progress、completed
+    pub total: usize,
+    pub succeeded: usize,                   // 完成前为 0
+    pub failed: usize,
+    pub created_at: String,
+    pub completed_at: Option<String>,
+    pub results: Option<Vec<BatchChatResult>>, // 任务完成后按提交顺序返回
+}
+
+/// 单个请求的结果，response 和 error 只有一个有值
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct BatchChatResult {
+    pub index: usize,
+    pub custom_id: Option<String>,
+    pub response: Option<ChatCompletionResponse>,
+    pub error: Option<OpenAIErrorBody>,
+}
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct CreateBlocklistEntryRequest {
+    pub tenant_id: Option<String>, // 为空表示全局规则
+    pub pattern: String,
+    pub action: String,            // block, mask, flag
+    pub scope: Option<String>,     // prompt, response, both（默认both）
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct UpdateBlocklistEntryRequest {
+    pub pattern: Option<String>,
+    pub action: Option<String>,
+    pub scope: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct BlocklistQuery {
+    pub tenant_id: Option<String>,
+    pub global_only: Option<bool>,
+}
@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct InvalidateCacheRequest {
+    pub prefix: String,       // 要失效的 key 前缀，例如 `model:openai:`；空字符串表示全部
+    pub reload: Option<bool>, // 失效后是否从数据库重新预加载模型和 API Key，默认 true
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InvalidateCacheResponse {
+    pub prefix: String,
+    pub invalidated: u64,
+    pub reloaded: bool,
+}
@@ -0,0 +1,260 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::api_types::v1::{ContextStrategy, Message, ResponseFormat, Tool, ToolCall};
+
+/// OpenAI 兼容的 Chat Completion 请求
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct ChatCompletionRequest {
+    pub model: String,
+    pub messages: Vec<ChatCompletionMessage>,
+    pub stream: Option<bool>,
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+    pub top_p: Option<f32>,
+    pub frequency_penalty: Option<f32>,
+    pub presence_penalty: Option<f32>,
+    pub stop: Option<StopSequence>,
+    pub user: Option<String>,
+    /// 可供模型调用的工具
+    #[serde(default)]
+    pub tools: Option<Vec<Tool>>,
+    /// 工具选择策略："auto"、"none"、"required" 或指定函数的对象
+    #[serde(default)]
+    pub tool_choice: Option<Value>,
+    /// 结构化输出格式：text、json_object 或 json_schema
+    #[serde(default)]
+    pub response_format: Option<ResponseFormat>,
+    /// 网关扩展：校验返回的 JSON 是否符合 response_format，不符合时要求模型修正一次
+    #[serde(default)]
+    pub validate_response: Option<bool>,
+    /// 网关扩展：历史超出上下文窗口时的处理方式：error、truncate_oldest 或 summarize
+    #[serde(default)]
+    pub context_strategy: Option<ContextStrategy>,
+    /// 网关扩展：单次请求的费用上限，按提示词和 max_tokens 预估的费用超出时拒绝
+    #[serde(default)]
+    pub max_cost: Option<f64>,
+}
+
+/// stop 参数既可以是单个字符串也可以是字符串数组
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum StopSequence {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+impl StopSequence {
+    pub fn into_vec(self) -> Vec<String> {
+        match self {
+            StopSequence::Single(stop) => vec![stop],
+            StopSequence::Multiple(stops) => stops,
+        }
+    }
+}
+
+/// OpenAI 格式的消息，content 可以是字符串、内容块数组或 null
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct ChatCompletionMessage {
+    pub role: String,
+    #[serde(default)]
+    pub content: Option<Value>,
+    pub name: Option<String>,
+    /// 助手消息中的工具调用，arguments 为 JSON 字符串
+    #[serde(default)]
+    pub tool_calls: Option<Vec<ToolCall>>,
+    /// 工具消息对应的工具调用 ID
+    #[serde(default)]
+    pub tool_call_id: Option<String>,
+}
+
+impl ChatCompletionMessage {
+    /// 转换为内部通用消息结构，图片内容块会被放入 images
+    pub fn into_message(self) -> Message {
+        let mut text = String::new();
+        let mut images = Vec::new();
+
+        match self.content {
+            Some(Value::String(content)) => text = content,
+            Some(Value::Array(parts)) => {
+                for part in parts {
+                    match part.get("type").and_then(Value::as_str) {
+                        Some("text") => {
+                            if let Some(t) = part.get("text").and_then(Value::as_str) {
+                                if !text.is_empty() {
+                                    text.push('\n');
+                                }
+                                text.push_str(t);
+                            }
+                        }
+                        Some("image_url") => {
+                            let url = part
+                                .get("image_url")
+                                .and_then(|i| i.get("url").or(Some(i)))
+                                .and_then(Value::as_str);
+                            if let Some(url) = url {
+                                images.push(url.to_string());
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        let mut message = match self.role.as_str() {
+            "tool" => Message::tool(text, self.name.unwrap_or_default()),
+            role => Message {
+                role: role.to_string(),
+                ..Message::user(text)
+            },
+        };
+        if !images.is_empty() {
+            message = message.with_images(images);
+        }
+        if let Some(tool_call_id) = self.tool_call_id {
+            message = message.with_tool_call_id(tool_call_id);
+        }
+        message.tool_calls = self.tool_calls;
+        message
+    }
+}
+
+/// OpenAI 兼容的 Chat Completion 响应
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct ChatCompletionResponse {
+    pub id: String,
+    pub object: String,
+    pub created: i64,
+    pub model: String,
+    pub choices: Vec<ChatCompletionChoice>,
+    pub usage: Option<ChatCompletionUsage>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct ChatCompletionChoice {
+    pub index: u32,
+    pub message: ChatCompletionResponseMessage,
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct ChatCompletionResponseMessage {
+    pub role: String,
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ChatCompletionToolCall>>,
+}
+
+/// OpenAI 格式的工具调用，流式响应中带 index
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct ChatCompletionToolCall {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<u32>,
+    pub id: String,
+    #[serde(rename = "type")]
+    pub tool_type: String,
+    pub function: ChatCompletionFunctionCall,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct ChatCompletionFunctionCall {
+    pub name: String,
+    /// 参数 JSON 字符串
+    pub arguments: String,
+}
+
+impl ChatCompletionToolCall {
+    /// 转换为 OpenAI 格式，上游没有返回 ID 时（如 Ollama）生成一个
+    pub fn from_tool_call(call: ToolCall, index: Option<u32>) -> Self {
+        Self {
+            index,
+            id: call.id.unwrap_or_else(|| format!("call_{}", uuid::Uuid::new_v4().simple())),
+            tool_type: call.tool_type.unwrap_or_else(|| "function".to_string()),
+            function: ChatCompletionFunctionCall {
+                arguments: serde_json::to_string(&call.function.arguments).unwrap_or_else(|_| "{}".to_string()),
+                name: call.function.name,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct ChatCompletionUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+}
+
+/// OpenAI 格式的错误响应
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct OpenAIErrorResponse {
+    pub error: OpenAIErrorBody,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct OpenAIErrorBody {
+    pub message: String,
+    #[serde(rename = "type")]
+    pub error_type: String,
+    /// 出错的请求字段，没有时为 null
+    #[serde(default)]
+    pub param: Option<String>,
+    pub code: Option<String>,
+}
+
+/// 流式响应中的单个分块（SSE data）
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct ChatCompletionChunk {
+    pub id: String,
+    pub object: String,
+    pub created: i64,
+    pub model: String,
+    pub choices: Vec<ChatCompletionChunkChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<ChatCompletionUsage>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct ChatCompletionChunkChoice {
+    pub index: u32,
+    pub delta: ChatCompletionDelta,
+    pub finish_reason: Option<String>,
+}
+
+/// 增量消息，role 只在第一个分块中出现
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct ChatCompletionDelta {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ChatCompletionToolCall>>,
+}
+
+/// 取消进行中请求的结果
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct CancelRequestResponse {
+    /// 被取消的请求 ID
+    pub id: String,
+    /// 固定为 `request`
+    pub object: String,
+    /// 固定为 `cancelled`
+    pub status: String,
+}
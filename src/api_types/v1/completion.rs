@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+use crate::api_types::v1::chat_completion::{ChatCompletionUsage, StopSequence};
+
+/// OpenAI 兼容的 Completions（文本补全）请求
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct CompletionCreateRequest {
+    pub model: String,
+    pub prompt: String,
+    /// 插入到生成内容之后的文本（代码补全）
+    #[serde(default)]
+    pub suffix: Option<String>,
+    pub stream: Option<bool>,
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+    pub top_p: Option<f32>,
+    pub stop: Option<StopSequence>,
+    pub user: Option<String>,
+}
+
+/// Completions 响应，流式输出的每个分块结构相同（text 为增量）
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct CompletionResponse {
+    pub id: String,
+    pub object: String,                     // 固定为 "text_completion"
+    pub created: i64,
+    pub model: String,
+    pub choices: Vec<CompletionChoice>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<ChatCompletionUsage>, // 流式输出中仅在最后一块出现
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct CompletionChoice {
+    pub text: String,
+    pub index: u32,
+    pub finish_reason: Option<String>,
+}
@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+
+use crate::api_types::v1::chat_completion::ChatCompletionUsage;
+
+/// 创建会话
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct CreateConversationRequest {
+    pub model: String,                      // 与 /v1/chat/completions 的 model 相同，可带 provider/ 前缀
+    pub title: Option<String>,
+    pub system_prompt: Option<String>,
+    pub max_context_tokens: Option<u32>,    // 发送给模型的历史 token 上限，不传表示不截断
+    pub truncation: Option<String>,         // 截断策略：drop_oldest（默认）、keep_first_turn
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct ConversationResponse {
+    pub id: String,
+    pub title: Option<String>,
+    pub model: String,
+    pub system_prompt: Option<String>,
+    pub max_context_tokens: Option<u32>,
+    pub truncation: String,
+    pub project_id: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct ConversationMessageResponse {
+    pub id: String,
+    pub seq: i64,
+    pub role: String,
+    pub content: String,
+    pub tokens: i64,
+    pub created_at: String,
+}
+
+/// 会话详情，包含全部消息
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct ConversationDetailResponse {
+    pub conversation: ConversationResponse,
+    pub messages: Vec<ConversationMessageResponse>,
+}
+
+/// 向会话发送一条用户消息，网关带上历史调用模型并保存回复
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct SendMessageRequest {
+    pub content: String,
+    pub model: Option<String>,              // 本次使用的模型，不传时使用会话的模型
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct SendMessageResponse {
+    pub conversation_id: String,
+    pub message: ConversationMessageResponse,   // 保存的助手回复
+    pub finish_reason: Option<String>,
+    pub usage: Option<ChatCompletionUsage>,
+    pub truncated_messages: usize,              // 因超出 token 上限未发送的历史消息数
+}
@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct UpdateDegradationRequest {
+    pub enabled: Option<bool>,           // 手动开启/关闭降级模式
+    pub fallback_message: Option<String>, // 缓存未命中时返回的兜底消息
+}
@@ -0,0 +1,417 @@
+//! # 调度请求/响应类型（v1）
+//!
+//! `LLMDispatcher` 的输入输出类型，嵌入网关的库调用方与 HTTP 客户端共用
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::api_types::v1::{Message, Tool, ToolCall};
+
+// 定义供应商枚举
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum Provider {
+    Ollama,
+    Ali,
+    OpenAI,
+    Azure,
+    Claude,
+    Gemini,
+    Custom(String),     // 通过 ProviderFactory 注册的插件供应商（小写类型名）
+}
+
+impl Provider {
+    // 供应商名称（与数据库 providers.name 一致）
+    pub fn as_str(&self) -> &str {
+        match self {
+            Provider::Ollama => "ollama",
+            Provider::Ali => "ali",
+            Provider::OpenAI => "openai",
+            Provider::Azure => "azure",
+            Provider::Claude => "claude",
+            Provider::Gemini => "gemini",
+            Provider::Custom(name) => name,
+        }
+    }
+
+    // 从供应商名称解析，非内置名称作为插件供应商
+    pub fn from_name_or_custom(name: &str) -> Provider {
+        Self::from_name(name).unwrap_or_else(|| Provider::Custom(name.trim().to_lowercase()))
+    }
+
+    // 从供应商名称解析（不区分大小写）
+    pub fn from_name(name: &str) -> Option<Provider> {
+        match name.trim().to_lowercase().as_str() {
+            "ollama" => Some(Provider::Ollama),
+            "ali" | "dashscope" | "qwen" => Some(Provider::Ali),
+            "openai" => Some(Provider::OpenAI),
+            "azure" | "azure-openai" => Some(Provider::Azure),
+            "claude" | "anthropic" => Some(Provider::Claude),
+            "gemini" | "google" => Some(Provider::Gemini),
+            _ => None,
+        }
+    }
+}
+
+// 定义请求参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct DispatchRequest {
+    pub provider: Provider,
+    pub model: String,
+    pub messages: Vec<Message>,
+    pub stream: Option<bool>,               // 是否流式，默认false
+    pub temperature: Option<f32>,           // 控制随机性，0.0-2.0
+    pub max_tokens: Option<u32>,           // 最大生成token数
+    pub top_p: Option<f32>,                // nucleus sampling参数
+    pub frequency_penalty: Option<f32>,     // 频率惩罚
+    pub presence_penalty: Option<f32>,      // 存在惩罚
+    pub stop: Option<Vec<String>>,         // 停止词
+    pub timeout_ms: Option<u64>,           // 请求超时时间(毫秒)
+    pub retry_count: Option<u32>,          // 重试次数
+    pub context_window: Option<u32>,       // 上下文窗口大小
+    pub user: Option<String>,              // 终端用户标识（OpenAI user 元数据）
+    pub tenant_id: Option<String>,         // 租户标识
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,          // 可供模型调用的工具
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<Value>,        // 工具选择策略："auto"、"none"、"required" 或指定函数（OpenAI 格式）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>, // 结构化输出格式
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validate_response: Option<bool>,   // 是否在网关侧校验返回的 JSON，不符合时要求模型修正一次
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_strategy: Option<ContextStrategy>, // 历史超出上下文窗口时的处理方式，默认返回错误
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pin_provider: Option<bool>,        // 为 true 时不按语言和路由脚本改写供应商和模型
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<bool>,            // 为 false 时失败后不切换备选供应商，默认按网关配置
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_cost: Option<f64>,             // 单次请求的费用上限，预估费用超出时拒绝，单位与模型单价一致
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,        // 网关请求 ID，未指定时沿用 HTTP 请求的 X-Request-Id，都没有时自动生成
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<RequestPriority>, // 排队时的优先级，默认 normal；批量任务默认 batch
+}
+
+/// 请求优先级：准入队列和供应商并发上限排队时，优先放行高优先级的请求，同一优先级内按到达顺序
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestPriority {
+    /// 离线批量任务
+    Batch,
+    /// 普通请求
+    #[default]
+    Normal,
+    /// 交互式请求
+    High,
+}
+
+impl RequestPriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Batch => "batch",
+            Self::Normal => "normal",
+            Self::High => "high",
+        }
+    }
+}
+
+impl std::str::FromStr for RequestPriority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "high" => Ok(Self::High),
+            "normal" => Ok(Self::Normal),
+            "batch" | "low" => Ok(Self::Batch),
+            other => Err(format!("priority must be high, normal or batch, got `{}`", other)),
+        }
+    }
+}
+
+/// 历史超出上下文窗口时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextStrategy {
+    /// 返回 context_length_exceeded 错误
+    #[default]
+    Error,
+    /// 丢弃最早的非 system 消息
+    #[serde(alias = "truncate-oldest")]
+    TruncateOldest,
+    /// 用低成本模型把较早的消息压缩为摘要
+    Summarize,
+}
+
+/// 结构化输出格式（OpenAI `response_format` 格式）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// 普通文本（默认）
+    Text,
+    /// 任意合法的 JSON 对象
+    JsonObject,
+    /// 符合指定 JSON Schema 的 JSON
+    JsonSchema { json_schema: JsonSchemaFormat },
+}
+
+/// `json_schema` 格式的 Schema 定义
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct JsonSchemaFormat {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub schema: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
+}
+
+impl ResponseFormat {
+    /// 要求返回 JSON 时为 true
+    pub fn is_json(&self) -> bool {
+        !matches!(self, ResponseFormat::Text)
+    }
+
+    /// 指定的 JSON Schema
+    pub fn schema(&self) -> Option<&Value> {
+        match self {
+            ResponseFormat::JsonSchema { json_schema } => Some(&json_schema.schema),
+            _ => None,
+        }
+    }
+}
+
+// 定义响应结构
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct DispatchResponse {
+    pub content: String,
+    pub provider: Provider,
+    pub model: String,
+    pub usage: Option<TokenUsage>,
+    pub finish_reason: Option<String>,
+    pub request_id: Option<String>,         // 网关请求 ID（与 X-Request-Id、调用记录和日志一致）
+    pub created_at: String,
+    pub total_duration: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,  // 模型要求调用的工具（finish_reason 通常为 tool_calls）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<Message>,           // 完整的助手消息（含工具调用、思维过程），仅聊天接口返回
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<Value>,                 // 提供商响应体（按客户端响应结构序列化）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warnings: Option<Vec<String>>,      // 网关调整了请求参数时的说明，例如按模型约束截断的 max_tokens
+}
+
+// Token使用统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct TokenUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+}
+
+// 流式输出的增量块
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct StreamChunk {
+    pub content: String,
+    pub finish_reason: Option<String>,
+    pub usage: Option<TokenUsage>,          // 仅在最后一块中出现
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,  // 参数完整的工具调用，拼接完成后随某一块返回
+}
+
+impl StreamChunk {
+    pub fn delta(content: String) -> Self {
+        Self { content, finish_reason: None, usage: None, tool_calls: None }
+    }
+
+    pub fn finished(finish_reason: Option<String>, usage: Option<TokenUsage>) -> Self {
+        Self { content: String::new(), finish_reason, usage, tool_calls: None }
+    }
+
+    pub fn with_tool_calls(mut self, tool_calls: Option<Vec<ToolCall>>) -> Self {
+        self.tool_calls = tool_calls;
+        self
+    }
+}
+
+// 便捷方法
+impl DispatchRequest {
+    pub fn new(provider: Provider, model: String, messages: Vec<Message>) -> Self {
+        Self {
+            provider,
+            model,
+            messages,
+            stream: None,
+            temperature: None,
+            max_tokens: None,
+            top_p: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            stop: None,
+            timeout_ms: None,
+            retry_count: None,
+            context_window: None,
+            user: None,
+            tenant_id: None,
+            tools: None,
+            tool_choice: None,
+            response_format: None,
+            validate_response: None,
+            context_strategy: None,
+            pin_provider: None,
+            fallback: None,
+            max_cost: None,
+            request_id: None,
+            priority: None,
+        }
+    }
+
+    pub fn with_stream(mut self, stream: bool) -> Self {
+        self.stream = Some(stream);
+        self
+    }
+
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    pub fn with_top_p(mut self, top_p: f32) -> Self {
+        self.top_p = Some(top_p);
+        self
+    }
+
+    pub fn with_stop(mut self, stop: Vec<String>) -> Self {
+        self.stop = Some(stop);
+        self
+    }
+
+    pub fn with_user(mut self, user: String) -> Self {
+        self.user = Some(user);
+        self
+    }
+
+    pub fn with_tenant_id(mut self, tenant_id: String) -> Self {
+        self.tenant_id = Some(tenant_id);
+        self
+    }
+
+    pub fn with_request_id(mut self, request_id: String) -> Self {
+        self.request_id = Some(request_id);
+        self
+    }
+
+    pub fn with_tools(mut self, tools: Vec<Tool>) -> Self {
+        self.tools = Some(tools);
+        self
+    }
+
+    pub fn with_tool_choice(mut self, tool_choice: Value) -> Self {
+        self.tool_choice = Some(tool_choice);
+        self
+    }
+
+    pub fn with_response_format(mut self, response_format: ResponseFormat) -> Self {
+        self.response_format = Some(response_format);
+        self
+    }
+
+    pub fn with_validate_response(mut self, validate_response: bool) -> Self {
+        self.validate_response = Some(validate_response);
+        self
+    }
+
+    pub fn with_context_strategy(mut self, context_strategy: ContextStrategy) -> Self {
+        self.context_strategy = Some(context_strategy);
+        self
+    }
+
+    pub fn with_pin_provider(mut self, pin_provider: bool) -> Self {
+        self.pin_provider = Some(pin_provider);
+        self
+    }
+
+    pub fn with_fallback(mut self, fallback: bool) -> Self {
+        self.fallback = Some(fallback);
+        self
+    }
+
+    pub fn with_max_cost(mut self, max_cost: f64) -> Self {
+        self.max_cost = Some(max_cost);
+        self
+    }
+}
+
+// 文本补全请求参数（不套用对话模板，直接续写提示词）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct CompletionRequest {
+    pub provider: Provider,
+    pub model: String,
+    pub prompt: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suffix: Option<String>,            // 插入到生成内容之后的文本（代码补全）
+    pub temperature: Option<f32>,           // 控制随机性，0.0-2.0
+    pub max_tokens: Option<u32>,           // 最大生成token数
+    pub top_p: Option<f32>,                // nucleus sampling参数
+    pub stop: Option<Vec<String>>,         // 停止词
+    pub timeout_ms: Option<u64>,           // 请求超时时间(毫秒)
+    pub retry_count: Option<u32>,          // 重试次数
+    pub user: Option<String>,              // 终端用户标识
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,         // 租户标识，用于按租户生效的黑名单规则
+}
+
+impl CompletionRequest {
+    pub fn new(provider: Provider, model: String, prompt: String) -> Self {
+        Self {
+            provider,
+            model,
+            prompt,
+            suffix: None,
+            temperature: None,
+            max_tokens: None,
+            top_p: None,
+            stop: None,
+            timeout_ms: None,
+            retry_count: None,
+            user: None,
+            tenant_id: None,
+        }
+    }
+
+    pub fn with_suffix(mut self, suffix: String) -> Self {
+        self.suffix = Some(suffix);
+        self
+    }
+
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    pub fn with_stop(mut self, stop: Vec<String>) -> Self {
+        self.stop = Some(stop);
+        self
+    }
+
+    pub fn with_tenant_id(mut self, tenant_id: String) -> Self {
+        self.tenant_id = Some(tenant_id);
+        self
+    }
+}
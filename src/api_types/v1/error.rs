@@ -0,0 +1,150 @@
+//! # 网关错误码
+//!
+//! OpenAI 兼容接口返回的错误类别。每个错误码对应固定的 HTTP 状态码和 OpenAI 错误体中的
+//! `type`/`code`，OpenAI SDK 按状态码决定是否重试（408、409、429 和 5xx 会自动退避重试），
+//! 因此可重试的错误必须返回对应的状态码
+
+use serde::{Deserialize, Serialize};
+
+use crate::api_types::v1::chat_completion::{OpenAIErrorBody, OpenAIErrorResponse};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum GatewayErrorCode {
+    /// 请求参数不合法
+    InvalidRequest,
+    /// 请求体超过大小上限
+    RequestTooLarge,
+    /// 未能在时限内接收完请求体
+    RequestTimeout,
+    /// 提示词和回复的 token 数超过模型的上下文窗口
+    ContextLengthExceeded,
+    /// 预估费用超过请求的 max_cost
+    CostLimitExceeded,
+    /// 请求的供应商不受支持
+    UnsupportedProvider,
+    /// 模型不存在或未启用
+    ModelNotFound,
+    /// 要取消的请求不存在或已结束
+    RequestNotFound,
+    /// 会话不存在或不属于当前项目
+    ConversationNotFound,
+    /// 批量任务不存在、已过期或不属于当前项目
+    BatchNotFound,
+    /// 请求已被取消
+    RequestCancelled,
+    /// 未携带网关 Key 或 Key 未绑定项目（开启 `projects.require_bound_key` 时）
+    InvalidApiKey,
+    /// 网关 Key 绑定的项目已停用
+    ProjectDisabled,
+    /// 内容命中黑名单或上游内容审核
+    ContentPolicyViolation,
+    /// 触发限流
+    RateLimitExceeded,
+    /// 上游请求超时
+    Timeout,
+    /// 供应商当月费用已达到预算上限
+    BudgetExceeded,
+    /// 模型健康检查失败，暂不调度
+    ModelUnhealthy,
+    /// 供应商的并发请求数已达上限
+    ProviderOverloaded,
+    /// 网关同时处理的请求数已达上限，排队已满或排队超时
+    GatewayOverloaded,
+    /// 网关尚未就绪
+    ServiceUnavailable,
+    /// 上游返回错误或无法连接
+    UpstreamError,
+}
+
+impl GatewayErrorCode {
+    /// HTTP 状态码
+    pub fn status_code(self) -> u16 {
+        match self {
+            Self::InvalidRequest | Self::ContextLengthExceeded | Self::CostLimitExceeded | Self::UnsupportedProvider | Self::ContentPolicyViolation => 400,
+            Self::InvalidApiKey => 401,
+            Self::RequestTimeout => 408,
+            Self::ProjectDisabled => 403,
+            Self::ModelNotFound | Self::RequestNotFound | Self::ConversationNotFound | Self::BatchNotFound => 404,
+            Self::RequestTooLarge => 413,
+            Self::RequestCancelled => 499,
+            Self::RateLimitExceeded | Self::BudgetExceeded => 429,
+            Self::UpstreamError => 502,
+            Self::ModelUnhealthy | Self::ProviderOverloaded | Self::GatewayOverloaded | Self::ServiceUnavailable => 503,
+            Self::Timeout => 504,
+        }
+    }
+
+    /// OpenAI 错误体中的 `type`
+    pub fn error_type(self) -> &'static str {
+        match self {
+            Self::InvalidRequest
+            | Self::RequestTooLarge
+            | Self::RequestTimeout
+            | Self::ContextLengthExceeded
+            | Self::CostLimitExceeded
+            | Self::UnsupportedProvider
+            | Self::ModelNotFound
+            | Self::RequestNotFound
+            | Self::ConversationNotFound
+            | Self::BatchNotFound
+            | Self::RequestCancelled
+            | Self::InvalidApiKey
+            | Self::ProjectDisabled
+            | Self::ContentPolicyViolation => "invalid_request_error",
+            Self::RateLimitExceeded => "rate_limit_error",
+            Self::BudgetExceeded => "insufficient_quota",
+            Self::Timeout
+            | Self::ModelUnhealthy
+            | Self::ProviderOverloaded
+            | Self::GatewayOverloaded
+            | Self::ServiceUnavailable
+            | Self::UpstreamError => "server_error",
+        }
+    }
+
+    /// OpenAI 错误体中的 `code`，普通的参数错误没有错误码
+    pub fn code(self) -> Option<&'static str> {
+        match self {
+            Self::InvalidRequest => None,
+            Self::RequestTooLarge => Some("request_too_large"),
+            Self::RequestTimeout => Some("request_timeout"),
+            Self::ContextLengthExceeded => Some("context_length_exceeded"),
+            Self::CostLimitExceeded => Some("cost_limit_exceeded"),
+            Self::UnsupportedProvider => Some("unsupported_provider"),
+            Self::ModelNotFound => Some("model_not_found"),
+            Self::RequestNotFound => Some("request_not_found"),
+            Self::ConversationNotFound => Some("conversation_not_found"),
+            Self::BatchNotFound => Some("batch_not_found"),
+            Self::RequestCancelled => Some("request_cancelled"),
+            Self::InvalidApiKey => Some("invalid_api_key"),
+            Self::ProjectDisabled => Some("project_disabled"),
+            Self::ContentPolicyViolation => Some("content_policy_violation"),
+            Self::RateLimitExceeded => Some("rate_limit_exceeded"),
+            Self::BudgetExceeded => Some("insufficient_quota"),
+            Self::Timeout => Some("timeout"),
+            Self::ModelUnhealthy => Some("model_unhealthy"),
+            Self::ProviderOverloaded => Some("provider_overloaded"),
+            Self::GatewayOverloaded => Some("gateway_overloaded"),
+            Self::ServiceUnavailable => Some("service_unavailable"),
+            Self::UpstreamError => Some("upstream_error"),
+        }
+    }
+
+    /// 客户端是否可以稍后重试（与 OpenAI SDK 的自动重试规则一致）
+    pub fn is_retryable(self) -> bool {
+        matches!(self.status_code(), 408 | 409 | 429) || self.status_code() >= 500
+    }
+
+    /// 构造 OpenAI 格式的错误体，`param` 为出错的请求字段
+    pub fn to_openai_error(self, message: impl Into<String>, param: Option<&str>) -> OpenAIErrorResponse {
+        OpenAIErrorResponse {
+            error: OpenAIErrorBody {
+                message: message.into(),
+                error_type: self.error_type().to_string(),
+                param: param.map(str::to_string),
+                code: self.code().map(str::to_string),
+            },
+        }
+    }
+}
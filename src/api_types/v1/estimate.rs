@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+/// 请求的费用预估，费用单位与模型单价一致
+///
+/// 回复 token 数按请求的 `max_tokens` 计算（即最坏情况），未指定 `max_tokens` 时只计算提示词费用
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct CostEstimate {
+    pub object: String,                     // 固定为 "cost_estimate"
+    pub provider: String,
+    pub model: String,
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,             // 即请求的 max_tokens
+    pub prompt_cost: f64,
+    pub completion_cost: f64,
+    pub estimated_cost: f64,
+    pub priced: bool,                       // 模型未配置单价时为 false，费用按 0 计算
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_cost: Option<f64>,              // 请求指定的费用上限
+    pub exceeds_max_cost: bool,             // 预估费用超过 max_cost 时为 true，调度时会被拒绝
+}
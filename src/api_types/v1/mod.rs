@@ -0,0 +1,28 @@
+//! # API 类型 v1
+
+pub mod dispatch;
+pub mod chat_completion;
+pub mod completion;
+pub mod provider;
+pub mod model;
+pub mod api_key;
+pub mod blocklist;
+pub mod degradation;
+pub mod error;
+pub mod prompt_cache;
+pub mod usage;
+pub mod project;
+pub mod conversation;
+pub mod batch;
+pub mod ws_chat;
+pub mod ollama;
+pub mod page;
+pub mod estimate;
+pub mod cache;
+pub mod agent;
+
+pub use error::GatewayErrorCode;
+pub use estimate::CostEstimate;
+pub use dispatch::{CompletionRequest, ContextStrategy, DispatchRequest, DispatchResponse, JsonSchemaFormat, Provider, RequestPriority, ResponseFormat, StreamChunk, TokenUsage};
+pub use crate::llm_api::utils::msg_structure::{Function, Message, ToolCall};
+pub use crate::llm_api::utils::tool_structure::{Tool, ToolFunction};
@@ -9,6 +9,7 @@ pub enum ModelType {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
 pub struct CreateModelRequest {
     pub provider_id: String,
     pub name: String,
@@ -20,9 +21,11 @@ pub struct CreateModelRequest {
     pub auto_start: bool,       // 是否立即启动
     pub custom_model: bool,     // 是否为自定义模型
     pub config: Option<String>, // 额外配置JSON
+    pub project_id: Option<String>, // 所属项目，默认 default（所有项目共享）
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
 pub struct UpdateModelRequest {
     pub display_name: Option<String>,
     pub base_url: Option<String>,
@@ -30,9 +33,11 @@ pub struct UpdateModelRequest {
     pub cost_per_token_input: Option<f64>,
     pub cost_per_token_output: Option<f64>,
     pub config: Option<String>,
+    pub project_id: Option<String>, // 改为归属其他项目
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
 pub struct ModelResponse {
     pub id: String,
     pub name: String,
@@ -46,11 +51,13 @@ pub struct ModelResponse {
     pub last_health_check: Option<String>,
     pub cost_per_token_input: Option<f64>,
     pub cost_per_token_output: Option<f64>,
+    pub project_id: String,
     pub created_at: Option<String>,
     pub updated_at: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
 pub struct ModelSummary {
     pub id: String,
     pub name: String,
@@ -60,6 +67,7 @@ pub struct ModelSummary {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
 pub struct ModelTemplate {
     pub name: String,
     pub display_name: String,
@@ -70,7 +78,17 @@ pub struct ModelTemplate {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
 pub struct ModelTemplateResponse {
     pub provider: String,
     pub templates: Vec<ModelTemplate>,
 }
+
+/// `GET /api/models` 的过滤条件
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct ModelListFilter {
+    pub provider: Option<String>,
+    pub active: Option<bool>,
+    pub project_id: Option<String>,
+}
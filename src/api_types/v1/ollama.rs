@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+pub use crate::llm_api::ollama::client::{OllamaModelDetails, OllamaModelInfo, OllamaPullProgress};
+
+/// 拉取 Ollama 模型
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct PullOllamaModelRequest {
+    pub name: String,                       // 例如 "qwen2.5:7b"
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct OllamaModelListResponse {
+    pub base_url: String,                   // 实际访问的 Ollama 地址
+    pub models: Vec<String>,
+}
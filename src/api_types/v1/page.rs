@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+/// 列表接口的分页响应
+///
+/// 按创建时间从新到旧排列；把 `next_cursor` 作为 `cursor` 参数传回获取下一页，为 null 表示没有更多数据。
+/// `total` 是符合过滤条件的总数，`filter` 回显本次生效的过滤条件
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct Page<T, F> {
+    pub data: Vec<T>,
+    pub total: i64,
+    pub limit: i64,
+    pub next_cursor: Option<String>,
+    pub filter: F,
+}
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct CreateProjectRequest {
+    pub id: String,                  // 项目ID，例如团队名（字母、数字、- 和 _）
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct UpdateProjectRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub is_active: Option<bool>,     // 停用后绑定到该项目的网关 Key 被拒绝
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct ProjectResponse {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub is_active: bool,
+    pub gateway_key_count: usize,    // 绑定的网关 Key 数量
+    pub created_at: String,
+}
+
+/// 绑定网关 Key：`api_key` 为调用方使用的 Key 原文（只保存指纹），也可以直接给出 `consumer_id`
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct BindGatewayKeyRequest {
+    pub api_key: Option<String>,
+    pub consumer_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct GatewayKeyBindingResponse {
+    pub consumer_id: String,
+    pub project_id: String,
+    pub created_at: String,
+}
@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct PromptCacheWarmupRequest {
+    pub provider: String,
+    pub model: String,
+    pub system_prompt: String,
+    pub interval_secs: Option<u64>, // 定期重新预热的间隔（秒），不填只预热一次
+}
@@ -1,36 +1,50 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
 pub struct CreateProviderRequest {
     pub name: String,           // provider名称 (ollama, ali, openai等)
     pub display_name: String,   // 显示名称
     pub base_url: Option<String>, // 基础URL
     pub api_key: Option<String>,  // API Key (可选)
     pub description: Option<String>, // 描述
+    pub config: Option<Value>,    // 供应商专属配置，例如Azure的api_version和deployments
+    pub project_id: Option<String>, // 所属项目，默认 default（所有项目共享）
+    pub max_concurrent_requests: Option<i64>, // 最大并发请求数，为空表示不限制
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
 pub struct UpdateProviderRequest {
     pub display_name: Option<String>,
     pub base_url: Option<String>,
     pub api_key: Option<String>,  // 如果提供，将添加新的API key到key pool
     pub description: Option<String>,
+    pub config: Option<Value>,
     pub is_active: Option<bool>,
+    pub project_id: Option<String>, // 改为归属其他项目
+    pub max_concurrent_requests: Option<i64>, // 小于等于 0 时取消并发限制
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
 pub struct ProviderResponse {
     pub id: String,
     pub name: String,
     pub display_name: String,
     pub base_url: Option<String>,
     pub description: Option<String>,
+    pub config: Option<Value>,
     pub is_active: bool,
+    pub project_id: String,
+    pub max_concurrent_requests: Option<i64>,
     pub model_count: usize,     // 关联的模型数量
     pub created_at: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
 pub struct ProviderSummary {
     pub id: String,
     pub name: String,
@@ -39,9 +53,18 @@ pub struct ProviderSummary {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
 pub struct AddApiKeyRequest {
     pub provider_id: String,
     pub api_key: String,
     pub rate_limit_per_minute: Option<i64>,
     pub rate_limit_per_hour: Option<i64>,
 }
+
+/// `GET /api/providers` 的过滤条件
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct ProviderListFilter {
+    pub active: Option<bool>,
+    pub project_id: Option<String>,
+}
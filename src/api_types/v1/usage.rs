@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct UpdateBudgetRequest {
+    pub monthly_budget: f64, // 每月费用上限，与模型单价同一计价单位
+}
+
+/// 预算接口的查询参数，未指定项目时为 default 项目
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct BudgetQuery {
+    pub project_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct UpdateConsumerQuotaRequest {
+    #[serde(default)]
+    pub requests_per_minute: Option<u64>, // 每分钟请求数上限，省略表示不限制
+    #[serde(default)]
+    pub tokens_per_day: Option<u64>, // 每天 token 数上限（本地时间零点重置），省略表示不限制
+}
+
+/// 已配置的调用方配额，`consumer_id` 为 `default` 时是未单独配置的调用方使用的配额
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub struct ConsumerQuotaResponse {
+    pub consumer_id: String,
+    pub requests_per_minute: Option<u64>,
+    pub tokens_per_day: Option<u64>,
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+use crate::api_types::v1::chat_completion::{ChatCompletionChunk, ChatCompletionRequest, OpenAIErrorBody};
+
+/// 客户端通过 WebSocket 发送的消息
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub enum WsClientMessage {
+    /// 发起一次流式对话，id 由客户端指定，在同一连接内唯一
+    Chat {
+        id: String,
+        request: Box<ChatCompletionRequest>, // 与 /v1/chat/completions 的请求体相同，始终以流式返回
+    },
+    /// 取消进行中的对话
+    Cancel {
+        id: String,
+    },
+}
+
+/// 服务端通过 WebSocket 返回的消息
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[cfg_attr(feature = "strict-api-types", serde(deny_unknown_fields))]
+pub enum WsServerMessage {
+    /// 对话的一个 OpenAI 格式分块
+    Chunk {
+        id: String,
+        chunk: ChatCompletionChunk,
+    },
+    /// 对话出错或被取消；无法解析的消息 id 为 null
+    Error {
+        id: Option<String>,
+        error: OpenAIErrorBody,
+    },
+    /// 对话结束，之后不再返回该 id 的消息
+    Done {
+        id: String,
+    },
+}
@@ -0,0 +1,44 @@
+//! # API Key 完整性审计命令
+//!
+//! 校验数据库中所有 API Key 能否解密、解密结果是否与存储的哈希一致，默认停用未通过校验的 Key。
+//! 用法：`cargo run --bin key_audit [-- --dry-run]`，存在未通过校验的 Key 时以状态码 1 退出
+
+use project_rust_learn::{
+    config::init_gateway_config,
+    dao::{init_db, init_sqlite_pool, SQLITE_POOL},
+    dao::provider_key_pool::{audit_key_pool_integrity, deactivate_corrupted_keys, master_keyring},
+};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let dry_run = std::env::args().skip(1).any(|arg| arg == "--dry-run");
+    let config = init_gateway_config()?;
+    master_keyring()?;
+
+    init_sqlite_pool(&config.database.url).await;
+    init_db(&config.database.init_sql_path).await?;
+    let pool = SQLITE_POOL.get().ok_or("Database pool not initialized")?;
+
+    let mut report = audit_key_pool_integrity(pool).await?;
+    if !dry_run {
+        deactivate_corrupted_keys(pool, &mut report).await?;
+    }
+
+    println!("🔐 API Key 完整性审计{}", if dry_run { "（dry run，不停用 Key）" } else { "" });
+    println!("   总数: {}  通过: {}  解密失败: {}  哈希不一致: {}  无法校验: {}  已停用: {}",
+        report.total_keys, report.healthy_keys, report.decrypt_failures, report.hash_mismatches,
+        report.unverifiable_keys, report.deactivated_keys);
+    for failure in &report.failures {
+        println!("   ❌ {}:{} {}{}{}",
+            failure.provider,
+            failure.key_pool_id,
+            failure.issue,
+            failure.detail.as_deref().map(|d| format!(" ({})", d)).unwrap_or_default(),
+            if failure.deactivated { " → 已停用" } else { "" });
+    }
+
+    if !report.is_healthy() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -1,39 +1,41 @@
 //! # LLM Web管理界面启动程序
 //!
-//! 启动可视化的LLM模型和Provider管理界面
+//! 启动可视化的LLM模型和Provider管理界面，配置来自 `gateway.toml` 和环境变量（见 `config` 模块）
 
-use std::net::SocketAddr;
 use project_rust_learn::{
+    config::init_gateway_config,
+    llm_api::utils::redaction::set_redactor,
     web::WebServer,
     logger,
 };
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // 读取启动配置
+    let config = init_gateway_config()?;
+
+    // 日志初始化前设置脱敏规则
+    set_redactor(config.redaction.redactor()?);
+
     // 初始化日志
-    let _logger = logger::init_logger(logger::LogConfig::default())?;
+    let _logger = logger::init_logger(config.logging.log_config())?;
 
     println!("🚀 启动 LLM Web管理界面...");
 
-    // 配置参数
-    let db_url = std::env::var("DATABASE_URL")
-        .unwrap_or_else(|_| "sqlite://data/app.db".to_string());
-    let init_sql_path = std::env::var("INIT_SQL_PATH")
-        .unwrap_or_else(|_| "data/init.sql".to_string());
-    let bind_addr = std::env::var("BIND_ADDR")
-        .unwrap_or_else(|_| "127.0.0.1:8080".to_string());
-
-    println!("📊 数据库: {}", db_url);
-    println!("📄 初始化脚本: {}", init_sql_path);
-    println!("🌐 绑定地址: {}", bind_addr);
+    println!("📊 数据库: {}", config.database.url);
+    println!("📄 附加SQL脚本: {}", config.database.init_sql_path);
+    println!("🌐 绑定地址: {}", config.server.bind_addr);
 
     // 解析地址
-    let addr: SocketAddr = bind_addr.parse()
-        .map_err(|e| format!("Invalid bind address: {}", e))?;
+    let addr = config.server.socket_addr()?;
 
     // 创建并启动Web服务器
-    let web_server = WebServer::new(db_url, init_sql_path);
-    web_server.start(addr).await?;
+    let web_server = WebServer::from_config(config);
+    let result = web_server.start(addr).await;
+
+    // 导出剩余的链路数据
+    logger::shutdown_tracing();
+    result?;
 
     Ok(())
 }
@@ -0,0 +1,500 @@
+//! # 启动配置
+//!
+//! 从 `gateway.toml` 读取网关的启动配置（数据库、监听地址、缓存、默认超时和重试、流量拆分、影子流量、
+//! 供应商默认地址、日志、脱敏规则、Agent Webhook 白名单），再用环境变量覆盖。配置文件路径可通过 `GATEWAY_CONFIG` 指定，
+//! 未指定时读取当前目录下的 `gateway.toml`，文件不存在时全部使用默认值。
+//!
+//! 支持的环境变量：
+//!
+//! | 环境变量 | 配置项 |
+//! |---------|--------|
+//! | `DATABASE_URL` | `database.url` |
+//! | `INIT_SQL_PATH` | `database.init_sql_path` |
+//! | `BIND_ADDR` | `server.bind_addr` |
+//! | `ADMIN_ROUTE_TIMEOUT_SECS` | `server.admin_timeout_secs` |
+//! | `CHAT_ROUTE_TIMEOUT_SECS` | `server.chat_timeout_secs` |
+//! | `CACHE_TTL_SECS` | `cache.ttl_secs` |
+//! | `CACHE_MAX_ENTRIES` | `cache.max_entries` |
+//! | `DISPATCH_TIMEOUT_MS` | `dispatcher.default_timeout_ms` |
+//! | `DISPATCH_RETRY_COUNT` | `dispatcher.default_retry_count` |
+//! | `DISPATCH_LOAD_BALANCE` | `dispatcher.load_balance_policy` |
+//! | `OLLAMA_BASE_URL` / `OPENAI_BASE_URL` / `ALI_BASE_URL` | `providers.*_base_url` |
+//! | `LOG_LEVEL` / `LOG_DIR` / `LOG_JSON` | `logging.level` / `logging.dir` / `logging.json` |
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::str::FromStr;
+use anyhow::{anyhow, bail, Context, Result};
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use tracing::error;
+
+use crate::llm_api::ali::client::AliClient;
+use crate::llm_api::utils::agent_loop::WebhookPolicy;
+use crate::llm_api::dispatcher::{DispatchConfig, Provider};
+use crate::llm_api::openai::client::OpenAIClient;
+use crate::llm_api::utils::load_balancer::LoadBalancePolicy;
+use crate::llm_api::utils::redaction::Redactor;
+use crate::llm_api::utils::shadow::ShadowMirror;
+use crate::llm_api::utils::traffic_split::{TrafficArm, TrafficSplit};
+use crate::logger::{LogConfig, LogLevel};
+
+/// 未通过 `GATEWAY_CONFIG` 指定时读取的配置文件
+pub const DEFAULT_CONFIG_PATH: &str = "gateway.toml";
+
+/// 未配置时 Ollama 的默认地址
+pub const DEFAULT_OLLAMA_BASE_URL: &str = "http://localhost:11434";
+
+/// 全局启动配置，由 [`init_gateway_config`] 设置
+pub static GATEWAY_CONFIG: OnceCell<GatewayConfig> = OnceCell::new();
+
+/// 网关启动配置
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GatewayConfig {
+    pub database: DatabaseConfig,
+    pub server: ServerConfig,
+    pub admission: AdmissionConfig,
+    pub cache: CacheConfig,
+    pub dispatcher: DispatcherConfig,
+    pub providers: ProvidersConfig,
+    pub logging: LoggingConfig,
+    pub redaction: RedactionConfig,
+    pub agent: AgentConfig,
+    pub projects: ProjectsConfig,
+}
+
+/// 数据库配置
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// SQLite 连接地址
+    pub url: String,
+    /// 迁移之后执行的附加 SQL 脚本，不存在时跳过
+    pub init_sql_path: String,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: "sqlite://data/app.db".to_string(),
+            init_sql_path: "data/init.sql".to_string(),
+        }
+    }
+}
+
+/// Web 服务配置
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// 监听地址
+    pub bind_addr: String,
+    /// 管理接口（CRUD、统计）超时（秒）
+    pub admin_timeout_secs: u64,
+    /// 对话接口超时（秒），流式请求只限制到开始返回响应为止
+    pub chat_timeout_secs: u64,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind_addr: "127.0.0.1:8080".to_string(),
+            admin_timeout_secs: 10,
+            chat_timeout_secs: 300,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// 解析后的监听地址
+    pub fn socket_addr(&self) -> Result<SocketAddr> {
+        self.bind_addr.parse().with_context(|| format!("Invalid bind address `{}`", self.bind_addr))
+    }
+}
+
+/// 对话接口的全局准入控制，`max_concurrent_requests` 为 0 时关闭
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdmissionConfig {
+    /// 同时处理的对话请求数上限
+    pub max_concurrent_requests: usize,
+    /// 达到上限后允许排队的请求数，队列已满时直接返回 503
+    pub max_queue_depth: usize,
+    /// 排队等待的最长时间（毫秒），超时返回 503
+    pub max_wait_ms: u64,
+}
+
+impl Default for AdmissionConfig {
+    fn default() -> Self {
+        Self { max_concurrent_requests: 0, max_queue_depth: 100, max_wait_ms: 5_000 }
+    }
+}
+
+/// 内存缓存配置
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    /// 缓存项过期时间（秒）
+    pub ttl_secs: u64,
+    /// 最大缓存项数
+    pub max_entries: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self { ttl_secs: 3600, max_entries: 1000 }
+    }
+}
+
+/// 调度器默认参数，请求未指定时使用
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DispatcherConfig {
+    /// 默认请求超时（毫秒）
+    pub default_timeout_ms: u64,
+    /// 默认重试次数
+    pub default_retry_count: u32,
+    /// 多个供应商提供同一模型时的选择策略：first、cheapest、fastest、sticky
+    pub load_balance_policy: LoadBalancePolicy,
+    /// 供应商达到并发上限（providers.max_concurrent_requests）时排队等待的最长时间（毫秒），0 时立即失败
+    pub concurrency_wait_ms: u64,
+    /// 逻辑模型名 -> A/B 分组，按百分比把该模型名的请求分到不同的供应商/模型
+    pub traffic_splits: HashMap<String, Vec<TrafficArm>>,
+    /// 影子流量，`percent` 为 0 时关闭
+    pub shadow: ShadowConfig,
+}
+
+/// 影子流量：按比例把请求异步镜像到另一个供应商，记录两者的响应和耗时
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShadowConfig {
+    /// 镜像的请求百分比（0-100）
+    pub percent: u32,
+    /// 镜像请求发往的供应商
+    pub provider: String,
+    /// 镜像请求使用的模型，为空时与主请求相同
+    pub model: String,
+    /// 只镜像发往这些供应商的请求，为空时镜像全部
+    pub source_providers: Vec<String>,
+}
+
+impl ShadowConfig {
+    /// 转换为调度配置，关闭时返回 None
+    pub fn mirror(&self) -> Option<ShadowMirror> {
+        if self.percent == 0 {
+            return None;
+        }
+        Some(ShadowMirror {
+            provider: Provider::from_name_or_custom(&self.provider),
+            model: Some(self.model.trim().to_string()).filter(|model| !model.is_empty()),
+            percent: self.percent,
+            source_providers: self.source_providers.iter().map(|name| Provider::from_name_or_custom(name)).collect(),
+        })
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.percent > 100 {
+            bail!("dispatcher.shadow.percent must be between 0 and 100, got {}", self.percent);
+        }
+        if self.percent > 0 && self.provider.trim().is_empty() {
+            bail!("dispatcher.shadow.provider must be set when dispatcher.shadow.percent is greater than 0");
+        }
+        Ok(())
+    }
+}
+
+impl Default for DispatcherConfig {
+    fn default() -> Self {
+        let defaults = DispatchConfig::default();
+        Self {
+            default_timeout_ms: defaults.default_timeout_ms,
+            default_retry_count: defaults.default_retry_count,
+            load_balance_policy: defaults.load_balance_policy,
+            concurrency_wait_ms: defaults.concurrency_wait_ms,
+            traffic_splits: HashMap::new(),
+            shadow: ShadowConfig::default(),
+        }
+    }
+}
+
+impl DispatcherConfig {
+    /// 在默认调度配置上应用超时、重试次数、负载均衡策略、流量拆分和影子流量
+    pub fn dispatch_config(&self) -> DispatchConfig {
+        DispatchConfig {
+            default_timeout_ms: self.default_timeout_ms,
+            default_retry_count: self.default_retry_count,
+            load_balance_policy: self.load_balance_policy,
+            concurrency_wait_ms: self.concurrency_wait_ms,
+            // 无效的拆分已在 validate 中报错
+            traffic_splits: self.traffic_splits.iter()
+                .filter_map(|(model, arms)| Some((model.clone(), TrafficSplit::new(arms.clone()).ok()?)))
+                .collect(),
+            shadow: self.shadow.mirror(),
+            ..Default::default()
+        }
+    }
+
+    fn validate_traffic_splits(&self) -> Result<()> {
+        for (model, arms) in &self.traffic_splits {
+            TrafficSplit::new(arms.clone())
+                .with_context(|| format!("Invalid dispatcher.traffic_splits.{}", model))?;
+        }
+        Ok(())
+    }
+}
+
+/// 供应商未在 providers 表配置 base_url 时使用的地址
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProvidersConfig {
+    pub ollama_base_url: String,
+    pub openai_base_url: String,
+    pub ali_base_url: String,
+}
+
+impl Default for ProvidersConfig {
+    fn default() -> Self {
+        Self {
+            ollama_base_url: DEFAULT_OLLAMA_BASE_URL.to_string(),
+            openai_base_url: OpenAIClient::DEFAULT_BASE_URL.to_string(),
+            ali_base_url: AliClient::DEFAULT_BASE_URL.to_string(),
+        }
+    }
+}
+
+impl ProvidersConfig {
+    /// 按供应商类型查找默认地址，没有默认地址的供应商返回 None
+    pub fn base_url(&self, provider: &str) -> Option<&str> {
+        match provider {
+            "ollama" => Some(&self.ollama_base_url),
+            "openai" => Some(&self.openai_base_url),
+            "ali" => Some(&self.ali_base_url),
+            _ => None,
+        }
+    }
+}
+
+/// 日志配置，链路导出仍通过 `OTEL_*` 环境变量配置
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    /// 日志级别：error、warn、info、debug、trace
+    pub level: String,
+    /// 日志文件目录
+    pub dir: String,
+    /// 日志文件名前缀
+    pub file_prefix: String,
+    /// 是否输出到控制台
+    pub console: bool,
+    /// 是否使用 JSON 格式
+    pub json: bool,
+    /// 滚动策略：daily、hourly
+    pub rotation: String,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        let defaults = LogConfig::default();
+        Self {
+            level: <&str>::from(defaults.level).to_string(),
+            dir: defaults.log_dir,
+            file_prefix: defaults.file_prefix,
+            console: defaults.console_output,
+            json: defaults.json_format,
+            rotation: defaults.rotation,
+        }
+    }
+}
+
+impl LoggingConfig {
+    /// 转换为日志系统的配置
+    pub fn log_config(&self) -> LogConfig {
+        LogConfig {
+            level: self.level.parse().unwrap_or(LogLevel::Info),
+            log_dir: self.dir.clone(),
+            file_prefix: self.file_prefix.clone(),
+            console_output: self.console,
+            json_format: self.json,
+            rotation: self.rotation.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// 写入调用记录和日志前的脱敏规则
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RedactionConfig {
+    /// 是否脱敏，关闭时内置规则和自定义规则都不生效
+    pub enabled: bool,
+    /// 在内置的邮箱、手机号、API Key 规则之外追加的正则
+    pub patterns: Vec<String>,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self { enabled: true, patterns: Vec::new() }
+    }
+}
+
+impl RedactionConfig {
+    /// 按配置创建脱敏规则
+    pub fn redactor(&self) -> Result<Redactor> {
+        if !self.enabled {
+            return Ok(Redactor::disabled());
+        }
+        self.patterns.iter().try_fold(Redactor::builtin(), |redactor, pattern| {
+            redactor.with_pattern(pattern)
+                .with_context(|| format!("Invalid redaction pattern `{}`", pattern))
+        })
+    }
+}
+
+/// `/v1/agent/chat` 的 Webhook 工具出站限制
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AgentConfig {
+    /// 允许 Webhook 工具访问的主机名，`*.example.com` 匹配所有子域名；为空时不接受 Webhook 工具
+    pub webhook_allowed_hosts: Vec<String>,
+    /// 是否允许 Webhook 主机解析到内网、回环或链路本地地址，只应在本地开发时开启
+    pub allow_private_webhook_addresses: bool,
+}
+
+/// 项目隔离
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectsConfig {
+    /// 对话接口是否只接受已绑定项目的网关 Key；开启后未携带 Key 或 Key 未绑定项目的请求返回 401，
+    /// 关闭时这些请求属于 default 项目
+    pub require_bound_key: bool,
+}
+
+impl AgentConfig {
+    /// 转换为 Agent 循环使用的 Webhook 出站策略
+    pub fn webhook_policy(&self) -> WebhookPolicy {
+        WebhookPolicy {
+            allowed_hosts: self.webhook_allowed_hosts.clone(),
+            allow_private_addresses: self.allow_private_webhook_addresses,
+        }
+    }
+}
+
+impl GatewayConfig {
+    /// 读取配置文件并应用环境变量覆盖
+    ///
+    /// 设置了 `GATEWAY_CONFIG` 时文件必须存在；使用默认路径时文件不存在则全部使用默认值
+    pub fn load() -> Result<Self> {
+        let explicit = std::env::var("GATEWAY_CONFIG").ok().filter(|path| !path.trim().is_empty());
+        let path = explicit.clone().unwrap_or_else(|| DEFAULT_CONFIG_PATH.to_string());
+        let config = if explicit.is_some() || Path::new(&path).exists() {
+            Self::from_file(&path)?
+        } else {
+            Self::default()
+        };
+        config.with_env_overrides(|name| std::env::var(name).ok())
+    }
+
+    /// 读取配置文件，不应用环境变量
+    pub fn from_file(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file `{}`", path))?;
+        Self::from_toml_str(&content).with_context(|| format!("Invalid config file `{}`", path))
+    }
+
+    /// 解析 TOML 格式的配置，未出现的配置项使用默认值
+    pub fn from_toml_str(content: &str) -> Result<Self> {
+        let config: Self = toml::from_str(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// 用 `lookup` 查到的环境变量覆盖配置，值为空的变量忽略
+    pub fn with_env_overrides(mut self, lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let var = |name: &str| lookup(name).map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        let parse = |name: &str, target: &mut u64| -> Result<()> {
+            if let Some(value) = var(name) {
+                *target = value.parse().with_context(|| format!("Invalid {}: `{}`", name, value))?;
+            }
+            Ok(())
+        };
+
+        let strings = [
+            ("DATABASE_URL", &mut self.database.url),
+            ("INIT_SQL_PATH", &mut self.database.init_sql_path),
+            ("BIND_ADDR", &mut self.server.bind_addr),
+            ("OLLAMA_BASE_URL", &mut self.providers.ollama_base_url),
+            ("OPENAI_BASE_URL", &mut self.providers.openai_base_url),
+            ("ALI_BASE_URL", &mut self.providers.ali_base_url),
+            ("LOG_LEVEL", &mut self.logging.level),
+            ("LOG_DIR", &mut self.logging.dir),
+        ];
+        for (name, target) in strings {
+            if let Some(value) = var(name) {
+                *target = value;
+            }
+        }
+        parse("ADMIN_ROUTE_TIMEOUT_SECS", &mut self.server.admin_timeout_secs)?;
+        parse("CHAT_ROUTE_TIMEOUT_SECS", &mut self.server.chat_timeout_secs)?;
+        parse("CACHE_TTL_SECS", &mut self.cache.ttl_secs)?;
+        parse("CACHE_MAX_ENTRIES", &mut self.cache.max_entries)?;
+        parse("DISPATCH_TIMEOUT_MS", &mut self.dispatcher.default_timeout_ms)?;
+        if let Some(value) = var("DISPATCH_RETRY_COUNT") {
+            self.dispatcher.default_retry_count = value.parse()
+                .with_context(|| format!("Invalid DISPATCH_RETRY_COUNT: `{}`", value))?;
+        }
+        if let Some(value) = var("DISPATCH_LOAD_BALANCE") {
+            self.dispatcher.load_balance_policy = value.parse()
+                .with_context(|| format!("Invalid DISPATCH_LOAD_BALANCE: `{}`", value))?;
+        }
+        if let Some(value) = var("LOG_JSON") {
+            self.logging.json = value.parse()
+                .with_context(|| format!("Invalid LOG_JSON: `{}`", value))?;
+        }
+
+        self.validate()?;
+        Ok(self)
+    }
+
+    // 检查无法在反序列化时发现的错误
+    fn validate(&self) -> Result<()> {
+        self.server.socket_addr()?;
+        if self.server.admin_timeout_secs == 0 || self.server.chat_timeout_secs == 0 {
+            bail!("server.admin_timeout_secs and server.chat_timeout_secs must be greater than 0");
+        }
+        if self.cache.max_entries == 0 {
+            bail!("cache.max_entries must be greater than 0");
+        }
+        if self.dispatcher.default_timeout_ms == 0 {
+            bail!("dispatcher.default_timeout_ms must be greater than 0");
+        }
+        self.dispatcher.validate_traffic_splits()?;
+        self.dispatcher.shadow.validate()?;
+        LogLevel::from_str(&self.logging.level)?;
+        if !["daily", "hourly"].contains(&self.logging.rotation.as_str()) {
+            bail!("logging.rotation must be `daily` or `hourly`, got `{}`", self.logging.rotation);
+        }
+        self.redaction.redactor()?;
+        Ok(())
+    }
+}
+
+/// 读取配置并设置为全局配置，返回全局配置；已经设置过时返回错误
+pub fn init_gateway_config() -> Result<&'static GatewayConfig> {
+    let config = GatewayConfig::load()?;
+    GATEWAY_CONFIG.set(config).map_err(|_| anyhow!("Gateway config already initialized"))?;
+    Ok(gateway_config())
+}
+
+/// 当前的全局配置，未调用 [`init_gateway_config`] 时读取配置
+///
+/// # Panics
+/// 配置文件或环境变量无效时 panic，不会退回默认值继续运行
+pub fn gateway_config() -> &'static GatewayConfig {
+    GATEWAY_CONFIG.get_or_init(|| {
+        GatewayConfig::load().unwrap_or_else(|e| {
+            error!("Failed to load gateway config: {:#}", e);
+            panic!("Failed to load gateway config: {:#}", e);
+        })
+    })
+}
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{SqlitePool, Result};
+
+use crate::dao::pagination::clamp_limit;
+use crate::dao::query_stats::timed_query;
+
+#[derive(Debug, Clone, sqlx::FromRow, Serialize, Deserialize)]
+pub struct AuditLog {
+    pub id: String,
+    pub actor: String,
+    pub action: String,                  // create / update / delete
+    pub resource_type: String,           // provider / model / api_key
+    pub resource_id: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub status_code: i64,
+    pub before_snapshot: Option<String>, // 变更前的资源(JSON)
+    pub after_snapshot: Option<String>,  // 变更后的资源(JSON)
+    pub created_at: Option<String>,
+}
+
+/// Filter for listing audit logs; `start` and `end` are inclusive `YYYY-MM-DD HH:MM:SS` bounds on `created_at`,
+/// `limit` is clamped to the page limit
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditLogFilter {
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    pub resource_type: Option<String>,
+    pub resource_id: Option<String>,
+    pub start: Option<String>,
+    pub end: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Record an admin mutation (async)
+pub async fn create_audit_log(pool: &SqlitePool, log: &AuditLog) -> Result<u64> {
+    let res = timed_query("audit_log.create_audit_log", r#"
+        INSERT INTO audit_logs (
+            id, actor, action, resource_type, resource_id, method, path, status_code,
+            before_snapshot, after_snapshot
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+    "#, |sql| sqlx::query(sql)
+        .bind(&log.id)
+        .bind(&log.actor)
+        .bind(&log.action)
+        .bind(&log.resource_type)
+        .bind(&log.resource_id)
+        .bind(&log.method)
+        .bind(&log.path)
+        .bind(log.status_code)
+        .bind(&log.before_snapshot)
+        .bind(&log.after_snapshot)
+        .execute(pool))
+        .await?;
+    Ok(res.rows_affected())
+}
+
+/// List audit logs matching the filter, newest first (async)
+pub async fn list_audit_logs(pool: &SqlitePool, filter: &AuditLogFilter) -> Result<Vec<AuditLog>> {
+    let logs = timed_query("audit_log.list_audit_logs", r#"
+        SELECT * FROM audit_logs
+        WHERE (?1 IS NULL OR actor = ?1)
+          AND (?2 IS NULL OR action = ?2)
+          AND (?3 IS NULL OR resource_type = ?3)
+          AND (?4 IS NULL OR resource_id = ?4)
+          AND (?5 IS NULL OR created_at >= ?5)
+          AND (?6 IS NULL OR created_at <= ?6)
+        ORDER BY created_at DESC, id DESC
+        LIMIT ?7
+    "#, |sql| sqlx::query_as::<_, AuditLog>(sql)
+        .bind(&filter.actor)
+        .bind(&filter.action)
+        .bind(&filter.resource_type)
+        .bind(&filter.resource_id)
+        .bind(&filter.start)
+        .bind(&filter.end)
+        .bind(clamp_limit(filter.limit))
+        .fetch_all(pool))
+        .await?;
+    Ok(logs)
+}
@@ -0,0 +1,8 @@
+mod audit_log;
+
+pub use audit_log::{
+    AuditLog,
+    AuditLogFilter,
+    create_audit_log,
+    list_audit_logs
+};
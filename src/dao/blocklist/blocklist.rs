@@ -0,0 +1,110 @@
+use sqlx::{SqlitePool, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::dao::query_stats::timed_query;
+
+#[derive(Debug, Clone, sqlx::FromRow, Serialize, Deserialize)]
+pub struct BlocklistEntry {
+    pub id: String,
+    pub tenant_id: Option<String>, // NULL 表示全局规则
+    pub pattern: String,
+    pub action: String,            // block, mask, flag
+    pub scope: String,             // prompt, response, both
+    pub is_active: bool,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+/// Create a new blocklist entry (async)
+pub async fn create_blocklist_entry(pool: &SqlitePool, entry: &BlocklistEntry) -> Result<u64> {
+    let res = timed_query("blocklist.create_blocklist_entry", r#"
+        INSERT INTO blocklist_entries (
+            id, tenant_id, pattern, action, scope, is_active, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, ?, datetime('now'), datetime('now'))
+    "#, |sql| sqlx::query(sql)
+        .bind(&entry.id)
+        .bind(&entry.tenant_id)
+        .bind(&entry.pattern)
+        .bind(&entry.action)
+        .bind(&entry.scope)
+        .bind(entry.is_active)
+        .execute(pool))
+        .await?;
+    Ok(res.rows_affected())
+}
+
+/// Read a blocklist entry by id (async)
+pub async fn get_blocklist_entry_by_id(pool: &SqlitePool, id: &str) -> Result<Option<BlocklistEntry>> {
+    let entry = timed_query("blocklist.get_blocklist_entry_by_id", "SELECT * FROM blocklist_entries WHERE id = ?", |sql| sqlx::query_as::<_, BlocklistEntry>(sql)
+        .bind(id)
+        .fetch_optional(pool))
+        .await?;
+    Ok(entry)
+}
+
+/// List all blocklist entries (async)
+pub async fn list_blocklist_entries(pool: &SqlitePool) -> Result<Vec<BlocklistEntry>> {
+    let entries = timed_query("blocklist.list_blocklist_entries", "SELECT * FROM blocklist_entries ORDER BY tenant_id, pattern", |sql| sqlx::query_as::<_, BlocklistEntry>(sql)
+        .fetch_all(pool))
+        .await?;
+    Ok(entries)
+}
+
+/// List blocklist entries overriding the global set for a tenant (async)
+pub async fn list_blocklist_entries_by_tenant(pool: &SqlitePool, tenant_id: &str) -> Result<Vec<BlocklistEntry>> {
+    let entries = timed_query("blocklist.list_blocklist_entries_by_tenant", "SELECT * FROM blocklist_entries WHERE tenant_id = ? ORDER BY pattern", |sql| sqlx::query_as::<_, BlocklistEntry>(sql)
+        .bind(tenant_id)
+        .fetch_all(pool))
+        .await?;
+    Ok(entries)
+}
+
+/// List global blocklist entries (async)
+pub async fn list_global_blocklist_entries(pool: &SqlitePool) -> Result<Vec<BlocklistEntry>> {
+    let entries = timed_query("blocklist.list_global_blocklist_entries", "SELECT * FROM blocklist_entries WHERE tenant_id IS NULL ORDER BY pattern", |sql| sqlx::query_as::<_, BlocklistEntry>(sql)
+        .fetch_all(pool))
+        .await?;
+    Ok(entries)
+}
+
+/// List active blocklist entries, plus inactive tenant overrides used to disable global rules (async)
+pub async fn list_active_blocklist_entries(pool: &SqlitePool) -> Result<Vec<BlocklistEntry>> {
+    let entries = timed_query("blocklist.list_active_blocklist_entries", 
+        "SELECT * FROM blocklist_entries WHERE is_active = 1 OR tenant_id IS NOT NULL ORDER BY tenant_id, pattern"
+    , |sql| sqlx::query_as::<_, BlocklistEntry>(sql)
+        .fetch_all(pool))
+        .await?;
+    Ok(entries)
+}
+
+/// Update a blocklist entry by id (async)
+pub async fn update_blocklist_entry(pool: &SqlitePool, entry: &BlocklistEntry) -> Result<u64> {
+    let res = timed_query("blocklist.update_blocklist_entry", r#"
+        UPDATE blocklist_entries SET
+            tenant_id = ?,
+            pattern = ?,
+            action = ?,
+            scope = ?,
+            is_active = ?,
+            updated_at = datetime('now')
+        WHERE id = ?
+    "#, |sql| sqlx::query(sql)
+        .bind(&entry.tenant_id)
+        .bind(&entry.pattern)
+        .bind(&entry.action)
+        .bind(&entry.scope)
+        .bind(entry.is_active)
+        .bind(&entry.id)
+        .execute(pool))
+        .await?;
+    Ok(res.rows_affected())
+}
+
+/// Delete a blocklist entry by id (async)
+pub async fn delete_blocklist_entry(pool: &SqlitePool, id: &str) -> Result<u64> {
+    let res = timed_query("blocklist.delete_blocklist_entry", "DELETE FROM blocklist_entries WHERE id = ?", |sql| sqlx::query(sql)
+        .bind(id)
+        .execute(pool))
+        .await?;
+    Ok(res.rows_affected())
+}
@@ -0,0 +1,13 @@
+mod blocklist;
+
+pub use blocklist::{
+    BlocklistEntry,
+    create_blocklist_entry,
+    get_blocklist_entry_by_id,
+    list_blocklist_entries,
+    list_blocklist_entries_by_tenant,
+    list_global_blocklist_entries,
+    list_active_blocklist_entries,
+    update_blocklist_entry,
+    delete_blocklist_entry
+};
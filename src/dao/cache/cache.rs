@@ -1,10 +1,117 @@
-use moka::future::Cache;
-use std::time::Duration;
-use std::sync::Arc;
+use futures_util::future::BoxFuture;
+use moka::{future::Cache, notification::RemovalCause, Expiry};
+use std::collections::{BTreeMap, HashSet};
+use std::time::{Duration, Instant};
+use std::sync::{Arc, Mutex};
+use serde::Serialize;
+use tracing::{debug, warn};
+
+use crate::metrics::metrics;
+
+/// 缓存命中/未命中计数的指标名，按 `cache` 标签区分缓存实例
+pub const CACHE_LOOKUPS_METRIC: &str = "llm_gateway_cache_lookups_total";
+
+/// 缓存条目被移除的计数指标名，按 `cache` 和 `cause`（expired / size / explicit）标签区分
+pub const CACHE_EVICTIONS_METRIC: &str = "llm_gateway_cache_evictions_total";
+
+/// 条目移除原因（`cause` 标签的取值）
+const EVICTION_CAUSES: [&str; 3] = ["expired", "size", "explicit"];
+
+/// 刷新回调：根据 key 从数据源重新加载值，返回 None 表示数据已不存在
+pub type CacheRefresher<K, V> = Arc<dyn Fn(K) -> BoxFuture<'static, anyhow::Result<Option<V>>> + Send + Sync>;
+
+/// 缓存条目，记录加载时间用于判断是否临近过期（使用 tokio 时钟，测试中可暂停推进）
+#[derive(Clone)]
+struct CacheEntry<V> {
+    value: V,
+    loaded_at: tokio::time::Instant,
+    /// 单个条目的 TTL，None 时使用缓存的默认 TTL
+    ttl: Option<Duration>,
+}
+
+impl<V> CacheEntry<V> {
+    fn new(value: V, ttl: Option<Duration>) -> Self {
+        Self { value, loaded_at: tokio::time::Instant::now(), ttl }
+    }
+}
+
+/// 按条目 TTL 计算过期时间，写入（包括覆盖写入）时重新计时
+struct EntryExpiry {
+    default_ttl: Duration,
+}
+
+impl<K, V> Expiry<K, CacheEntry<V>> for EntryExpiry {
+    fn expire_after_create(&self, _key: &K, entry: &CacheEntry<V>, _created_at: Instant) -> Option<Duration> {
+        Some(entry.ttl.unwrap_or(self.default_ttl))
+    }
+
+    fn expire_after_update(
+        &self,
+        _key: &K,
+        entry: &CacheEntry<V>,
+        _updated_at: Instant,
+        _duration_until_expiry: Option<Duration>,
+    ) -> Option<Duration> {
+        Some(entry.ttl.unwrap_or(self.default_ttl))
+    }
+}
+
+/// 提前刷新配置
+struct RefreshAhead<K, V> {
+    /// 条目存活超过其 TTL 的该比例即视为临近过期
+    ratio: f64,
+    refresher: CacheRefresher<K, V>,
+    /// 正在后台刷新的 key，避免重复刷新
+    in_flight: Mutex<HashSet<K>>,
+}
 
 #[derive(Clone)]
 pub struct CacheService<K, V> {
-    cache: Arc<Cache<K, V>>,
+    cache: Arc<Cache<K, CacheEntry<V>>>,
+    ttl: Duration,
+    max_capacity: u64,
+    refresh_ahead: Option<Arc<RefreshAhead<K, V>>>,
+    /// 缓存名称，用作命中率指标的 `cache` 标签
+    name: &'static str,
+}
+
+/// 缓存统计：命中率、按原因统计的移除数，以及当前条目数
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheStats {
+    #[serde(flatten)]
+    pub hits: CacheHitStats,
+    /// 按原因统计的移除数（expired / size / explicit），覆盖写入不计入
+    pub evictions: BTreeMap<&'static str, u64>,
+    pub entries: u64,
+}
+
+/// 缓存命中统计
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct CacheHitStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// 尚无查询时为 None
+    pub hit_rate: Option<f64>,
+}
+
+/// 读取指定缓存自进程启动以来按原因统计的移除数
+pub fn cache_eviction_stats(name: &str) -> BTreeMap<&'static str, u64> {
+    EVICTION_CAUSES
+        .iter()
+        .map(|cause| (*cause, metrics().counter_value(CACHE_EVICTIONS_METRIC, &[("cache", name), ("cause", cause)])))
+        .collect()
+}
+
+/// 读取指定缓存自进程启动以来的命中统计
+pub fn cache_hit_stats(name: &str) -> CacheHitStats {
+    let hits = metrics().counter_value(CACHE_LOOKUPS_METRIC, &[("cache", name), ("result", "hit")]);
+    let misses = metrics().counter_value(CACHE_LOOKUPS_METRIC, &[("cache", name), ("result", "miss")]);
+    let total = hits + misses;
+    CacheHitStats {
+        hits,
+        misses,
+        hit_rate: (total > 0).then(|| hits as f64 / total as f64),
+    }
 }
 
 impl<K, V> CacheService<K, V>
@@ -12,20 +119,81 @@ where
     K: std::hash::Hash + Eq + Clone + Send + Sync + 'static,
     V: Clone + Send + Sync + 'static,
 {
-    /// 新建缓存服务
+    /// 新建缓存服务，`ttl` 为条目的默认存活时间
     pub fn new(ttl: Duration, max_capacity: u64) -> Self {
-        let cache = Cache::builder()
-            .time_to_live(ttl)
-            .max_capacity(max_capacity)
-            .build();
+        let name = "default";
         CacheService {
-            cache: Arc::new(cache),
+            cache: Arc::new(Self::build_cache(ttl, max_capacity, name)),
+            ttl,
+            max_capacity,
+            refresh_ahead: None,
+            name,
+        }
+    }
+
+    /// 设置缓存名称（命中率和移除指标的 `cache` 标签），需在写入条目前调用
+    pub fn with_name(mut self, name: &'static str) -> Self {
+        self.name = name;
+        // 移除监听器在构建时绑定名称，重新构建尚未写入条目的底层缓存
+        self.cache = Arc::new(Self::build_cache(self.ttl, self.max_capacity, name));
+        self
+    }
+
+    fn build_cache(ttl: Duration, max_capacity: u64, name: &'static str) -> Cache<K, CacheEntry<V>> {
+        Cache::builder()
+            .expire_after(EntryExpiry { default_ttl: ttl })
+            .max_capacity(max_capacity)
+            .eviction_listener(move |_key, _entry, cause| {
+                let cause = match cause {
+                    RemovalCause::Expired => "expired",
+                    RemovalCause::Size => "size",
+                    RemovalCause::Explicit => "explicit",
+                    RemovalCause::Replaced => return,
+                };
+                metrics().incr_counter(CACHE_EVICTIONS_METRIC, &[("cache", name), ("cause", cause)]);
+            })
+            .build()
+    }
+
+    /// 缓存名称
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// 命中率、移除数和当前条目数
+    pub async fn stats(&self) -> CacheStats {
+        // 先处理待执行的淘汰，移除计数和条目数更准确
+        let entries = self.entry_count().await;
+        CacheStats {
+            hits: cache_hit_stats(self.name),
+            evictions: cache_eviction_stats(self.name),
+            entries,
         }
     }
 
-    /// 获取缓存，如果没有命中则返回 None
+    /// 开启提前刷新：条目存活超过 `ttl * ratio`（单独设置了 TTL 的条目按各自的 TTL）后被读取时，
+    /// 在后台调用 refresher 重新加载
+    pub fn with_refresh_ahead(mut self, ratio: f64, refresher: CacheRefresher<K, V>) -> Self {
+        self.refresh_ahead = Some(Arc::new(RefreshAhead {
+            ratio: ratio.clamp(0.0, 1.0),
+            refresher,
+            in_flight: Mutex::new(HashSet::new()),
+        }));
+        self
+    }
+
+    /// 获取缓存，如果没有命中则返回 None；命中临近过期的条目时触发后台刷新
     pub async fn get(&self, key: &K) -> Option<V> {
-        self.cache.get(key).await
+        let entry = self.cache.get(key).await;
+        self.record_lookup(entry.is_some());
+        let entry = entry?;
+        self.maybe_refresh(key, &entry);
+        Some(entry.value)
+    }
+
+    /// 读取缓存但不计入命中统计、不触发提前刷新，用于后台对账等内部检查
+    pub async fn peek(&self, key: &K) -> Option<V> {
+        self.cache.get(key).await.map(|entry| entry.value)
     }
 
     /// 获取缓存，如果没有命中，则调用 loader 加载
@@ -34,18 +202,144 @@ where
         F: FnOnce(K) -> Fut + Send + 'static,
         Fut: std::future::Future<Output = V> + Send,
     {
-        self.cache
-            .get_with(key.clone(), async move { loader(key).await })
-            .await
+        let entry = self.cache
+            .entry(key.clone())
+            .or_insert_with({
+                let key = key.clone();
+                async move { CacheEntry::new(loader(key).await, None) }
+            })
+            .await;
+        self.record_lookup(!entry.is_fresh());
+        let entry = entry.into_value();
+        self.maybe_refresh(&key, &entry);
+        entry.value
     }
 
     /// 强制写入缓存
     pub async fn insert(&self, key: K, value: V) {
-        self.cache.insert(key, value).await;
+        self.cache.insert(key, CacheEntry::new(value, None)).await;
+    }
+
+    /// 强制写入缓存，该条目使用单独的 TTL 而不是缓存的默认 TTL
+    pub async fn insert_with_ttl(&self, key: K, value: V, ttl: Duration) {
+        self.cache.insert(key, CacheEntry::new(value, Some(ttl))).await;
     }
 
     /// 删除某个 key
     pub async fn invalidate(&self, key: &K) {
         self.cache.invalidate(key).await;
     }
+
+    /// 当前条目数（先处理待执行的淘汰，结果更准确）
+    pub async fn entry_count(&self) -> u64 {
+        self.cache.run_pending_tasks().await;
+        self.cache.entry_count()
+    }
+
+    /// 条目是否已临近过期（未开启提前刷新或不存在时返回 false）
+    pub async fn is_stale(&self, key: &K) -> bool {
+        match (&self.refresh_ahead, self.cache.get(key).await) {
+            (Some(refresh_ahead), Some(entry)) => entry.loaded_at.elapsed() >= self.refresh_threshold(refresh_ahead, &entry),
+            _ => false,
+        }
+    }
+
+    /// 立即从数据源刷新某个 key，数据源中已不存在时从缓存删除
+    pub async fn refresh(&self, key: &K) -> anyhow::Result<()> {
+        let Some(refresh_ahead) = &self.refresh_ahead else {
+            return Ok(());
+        };
+        let ttl = self.cache.get(key).await.and_then(|entry| entry.ttl);
+        Self::reload(&self.cache, &refresh_ahead.refresher, key.clone(), ttl).await
+    }
+
+    // 条目的提前刷新阈值
+    fn refresh_threshold(&self, refresh_ahead: &RefreshAhead<K, V>, entry: &CacheEntry<V>) -> Duration {
+        entry.ttl.unwrap_or(self.ttl).mul_f64(refresh_ahead.ratio)
+    }
+
+    fn record_lookup(&self, hit: bool) {
+        let result = if hit { "hit" } else { "miss" };
+        metrics().incr_counter(CACHE_LOOKUPS_METRIC, &[("cache", self.name), ("result", result)]);
+    }
+
+    /// 条目临近过期时启动后台刷新，同一个 key 同时只有一个刷新任务
+    fn maybe_refresh(&self, key: &K, entry: &CacheEntry<V>) {
+        let Some(refresh_ahead) = &self.refresh_ahead else {
+            return;
+        };
+        if entry.loaded_at.elapsed() < self.refresh_threshold(refresh_ahead, entry) {
+            return;
+        }
+        if !refresh_ahead.in_flight.lock().unwrap().insert(key.clone()) {
+            return;
+        }
+
+        let cache = self.cache.clone();
+        let refresh_ahead = refresh_ahead.clone();
+        let key = key.clone();
+        let ttl = entry.ttl;
+        tokio::spawn(async move {
+            let result = Self::reload(&cache, &refresh_ahead.refresher, key.clone(), ttl).await;
+            if let Err(e) = result {
+                warn!(error = %e, "Background cache refresh failed, keeping stale entry");
+            }
+            refresh_ahead.in_flight.lock().unwrap().remove(&key);
+        });
+    }
+
+    // 重新加载时保留条目原有的 TTL
+    async fn reload(cache: &Cache<K, CacheEntry<V>>, refresher: &CacheRefresher<K, V>, key: K, ttl: Option<Duration>) -> anyhow::Result<()> {
+        let result = match refresher(key.clone()).await {
+            Ok(Some(value)) => {
+                cache.insert(key, CacheEntry::new(value, ttl)).await;
+                debug!("Cache entry refreshed ahead of expiry");
+                Ok("refreshed")
+            }
+            Ok(None) => {
+                cache.invalidate(&key).await;
+                debug!("Cache entry removed from source, invalidated");
+                Ok("evicted")
+            }
+            Err(e) => Err(e),
+        };
+
+        let status = result.as_ref().copied().unwrap_or("failed");
+        metrics().incr_counter("llm_gateway_cache_refresh_total", &[("result", status)]);
+        result.map(|_| ())
+    }
+}
+
+impl<V> CacheService<String, V>
+where
+    V: Clone + Send + Sync + 'static,
+{
+    /// 删除所有以 `prefix` 开头的 key，返回删除的条目数
+    pub async fn invalidate_prefix(&self, prefix: &str) -> u64 {
+        let keys = self.keys_with_prefix(prefix);
+        for key in &keys {
+            self.cache.invalidate(key).await;
+        }
+        keys.len() as u64
+    }
+
+    /// 所有以 `prefix` 开头的 key
+    pub fn keys_with_prefix(&self, prefix: &str) -> Vec<String> {
+        self.cache
+            .iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .map(|(key, _)| key.to_string())
+            .collect()
+    }
+
+    /// 按命名空间（key 中第一个冒号之前的部分）统计条目数
+    pub async fn namespace_entry_counts(&self) -> BTreeMap<String, u64> {
+        self.cache.run_pending_tasks().await;
+        let mut counts = BTreeMap::new();
+        for (key, _) in self.cache.iter() {
+            let namespace = key.split_once(':').map_or(key.as_str(), |(namespace, _)| namespace);
+            *counts.entry(namespace.to_string()).or_insert(0) += 1;
+        }
+        counts
+    }
 }
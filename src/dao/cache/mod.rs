@@ -1,37 +1,96 @@
 use once_cell::sync::OnceCell;
 use std::time::Duration;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use serde::Serialize;
 use sqlx::SqlitePool;
-use crate::dao::model::{preload_models_to_cache};
-use crate::dao::provider_key_pool::{preload_provider_key_pools_to_cache};
+use tracing::error;
+use crate::dao::model::{
+    preload_models_to_cache, load_model_cache_value, load_provider_models_cache_value,
+    MODEL_CACHE_NAMESPACE, PROVIDER_MODELS_CACHE_NAMESPACE,
+};
+use crate::dao::provider_key_pool::{preload_provider_key_pools_to_cache, load_provider_key_pool_cache_value, KEY_POOL_CACHE_NAMESPACE};
 pub mod cache;
+pub mod namespace;
 
-use cache::CacheService;
+use cache::{CacheRefresher, CacheService};
+pub use namespace::CacheNamespace;
 
-/// 全局缓存实例，使用 String 作为 key 和 value
+/// 全局缓存实例，使用 String 作为 key 和 value；按类型读写时通过 [`CacheService::namespace`] 获取命名空间句柄
 pub static GLOBAL_CACHE: OnceCell<Arc<CacheService<String, String>>> = OnceCell::new();
 
+/// 全局缓存的名称（命中率指标的 `cache` 标签）
+pub const GLOBAL_CACHE_NAME: &str = "global";
+
+/// 条目存活超过 TTL 的该比例后，读取时在后台从数据库刷新
+pub const REFRESH_AHEAD_RATIO: f64 = 0.8;
+
+// 最近一次模型和 API Key 预加载是否成功
+static CACHE_PRELOADED: AtomicBool = AtomicBool::new(false);
+
 /// 初始化全局缓存
 pub async fn init_global_cache(pool: &SqlitePool, ttl_seconds: u64, max_capacity: u64) -> anyhow::Result<()> {
     let cache_service = CacheService::new(
         Duration::from_secs(ttl_seconds),
         max_capacity,
-    );
+    )
+    .with_name(GLOBAL_CACHE_NAME)
+    .with_refresh_ahead(REFRESH_AHEAD_RATIO, database_refresher(pool.clone()));
     GLOBAL_CACHE.set(Arc::new(cache_service)).ok();
 
-    // 预加载模型
-    preload_models_to_cache(pool).await.expect("Failed to preload models");
-
-    // 预加载 Provider Key Pool
-    preload_provider_key_pools_to_cache(pool).await.expect("Failed to preload provider key pools");
+    // 预加载失败时缓存照常使用，未命中的条目从数据库加载，就绪探针报告缓存未预热
+    if let Err(e) = preload_global_cache(pool).await {
+        error!(error = %e, "Failed to preload global cache");
+    }
 
     Ok(())
 }
 
+/// 从数据库预加载模型和 Provider Key Pool 到全局缓存，并记录预加载是否成功
+pub async fn preload_global_cache(pool: &SqlitePool) -> anyhow::Result<()> {
+    let result = async {
+        preload_models_to_cache(pool).await?;
+        preload_provider_key_pools_to_cache(pool).await
+    }.await;
+    CACHE_PRELOADED.store(result.is_ok(), Ordering::SeqCst);
+    result
+}
+
+/// 全局缓存是否已初始化且最近一次预加载成功
+pub fn is_cache_preloaded() -> bool {
+    GLOBAL_CACHE.get().is_some() && CACHE_PRELOADED.load(Ordering::SeqCst)
+}
+
 /// 获取全局缓存实例
 pub fn get_global_cache() -> Arc<CacheService<String, String>> {
     GLOBAL_CACHE
         .get()
         .expect("Global cache not initialized")
         .clone()
-}
\ No newline at end of file
+}
+
+/// 根据缓存 key 的前缀从数据库重新加载模型、供应商模型索引和 provider key pool
+fn database_refresher(pool: SqlitePool) -> CacheRefresher<String, String> {
+    Arc::new(move |key: String| {
+        let pool = pool.clone();
+        Box::pin(async move { reload_cache_value(&pool, &key).await })
+    })
+}
+
+/// 按缓存 key 的命名空间重新加载，未知命名空间的 key 返回错误（保留原值直到过期）
+async fn reload_cache_value(pool: &SqlitePool, key: &str) -> anyhow::Result<Option<String>> {
+    // 模型名称中可能包含冒号（例如 llama3.1:latest），只按前两个冒号切分
+    let mut parts = key.splitn(3, ':');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(MODEL_CACHE_NAMESPACE), Some(provider), Some(name)) => to_cache_value(load_model_cache_value(pool, provider, name).await?),
+        // 没有模型的供应商也缓存（null），避免每次读取都查库
+        (Some(PROVIDER_MODELS_CACHE_NAMESPACE), Some(provider), None) => to_cache_value(Some(load_provider_models_cache_value(pool, provider).await?)),
+        (Some(KEY_POOL_CACHE_NAMESPACE), Some(_), Some(id)) => to_cache_value(load_provider_key_pool_cache_value(pool, id).await?),
+        _ => Err(anyhow::anyhow!("No refresh source for cache key {}", key)),
+    }
+}
+
+// 序列化为命名空间条目使用的 JSON 缓存值
+fn to_cache_value<T: Serialize>(value: Option<T>) -> anyhow::Result<Option<String>> {
+    Ok(value.map(|value| serde_json::to_string(&value)).transpose()?)
+}
@@ -0,0 +1,123 @@
+use std::marker::PhantomData;
+use std::time::Duration;
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::error;
+
+use super::cache::CacheService;
+
+/// 缓存命名空间中的类型化句柄
+///
+/// 条目保存在底层的 `CacheService<String, String>` 中，key 为 `{namespace}:{key}`，值序列化为 JSON，
+/// 因此与同一缓存中的其他条目共享容量、命中率指标和提前刷新
+pub struct CacheNamespace<T> {
+    cache: CacheService<String, String>,
+    name: String,
+    /// 该命名空间写入条目时的默认 TTL，None 时使用缓存的默认 TTL
+    ttl: Option<Duration>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for CacheNamespace<T> {
+    fn clone(&self) -> Self {
+        Self {
+            cache: self.cache.clone(),
+            name: self.name.clone(),
+            ttl: self.ttl,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl CacheService<String, String> {
+    /// 获取某个命名空间的类型化句柄，例如 `cache.namespace::<Model>("model")`
+    pub fn namespace<T>(&self, name: &str) -> CacheNamespace<T> {
+        CacheNamespace {
+            cache: self.clone(),
+            name: name.to_string(),
+            ttl: None,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> CacheNamespace<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// 设置该命名空间写入条目时的默认 TTL
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// 命名空间名称
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 条目在底层缓存中的完整 key
+    pub fn cache_key(&self, key: &str) -> String {
+        format!("{}:{}", self.name, key)
+    }
+
+    /// 获取缓存，未命中或反序列化失败时返回 None
+    pub async fn get(&self, key: &str) -> Option<T> {
+        let cache_key = self.cache_key(key);
+        let cached_value = self.cache.get(&cache_key).await?;
+        match serde_json::from_str::<T>(&cached_value) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                error!(cache_key = %cache_key, error = %e, "Failed to deserialize cached value");
+                None
+            }
+        }
+    }
+
+    /// 读取缓存但不计入命中统计、不触发提前刷新，未命中或反序列化失败时返回 None
+    pub async fn peek(&self, key: &str) -> Option<T> {
+        let cached_value = self.cache.peek(&self.cache_key(key)).await?;
+        serde_json::from_str::<T>(&cached_value).ok()
+    }
+
+    /// 写入缓存，使用命名空间的默认 TTL
+    pub async fn insert(&self, key: &str, value: &T) -> anyhow::Result<()> {
+        match self.ttl {
+            Some(ttl) => self.insert_with_ttl(key, value, ttl).await,
+            None => {
+                self.cache.insert(self.cache_key(key), serde_json::to_string(value)?).await;
+                Ok(())
+            }
+        }
+    }
+
+    /// 写入缓存，该条目使用单独的 TTL
+    pub async fn insert_with_ttl(&self, key: &str, value: &T, ttl: Duration) -> anyhow::Result<()> {
+        self.cache.insert_with_ttl(self.cache_key(key), serde_json::to_string(value)?, ttl).await;
+        Ok(())
+    }
+
+    /// 删除某个 key
+    pub async fn invalidate(&self, key: &str) {
+        self.cache.invalidate(&self.cache_key(key)).await;
+    }
+
+    /// 删除命名空间中所有以 `prefix` 开头的 key，返回删除的条目数
+    pub async fn invalidate_prefix(&self, prefix: &str) -> u64 {
+        self.cache.invalidate_prefix(&self.cache_key(prefix)).await
+    }
+
+    /// 命名空间中的所有 key（不含命名空间前缀）
+    pub fn keys(&self) -> Vec<String> {
+        let prefix = self.cache_key("");
+        self.cache
+            .keys_with_prefix(&prefix)
+            .into_iter()
+            .map(|key| key[prefix.len()..].to_string())
+            .collect()
+    }
+
+    /// 清空整个命名空间，返回删除的条目数
+    pub async fn clear(&self) -> u64 {
+        self.invalidate_prefix("").await
+    }
+}
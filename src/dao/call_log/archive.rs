@@ -0,0 +1,66 @@
+use futures::stream::BoxStream;
+use serde::{Deserialize, Serialize};
+use sqlx::{SqlitePool, Result};
+
+use crate::dao::query_stats::timed_query;
+use super::call_log::CallLog;
+
+/// Filter for bulk delete / archive of call logs; `end` is exclusive
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CallLogFilter {
+    pub model_id: Option<String>,
+    pub start: Option<String>,
+    pub end: Option<String>,
+}
+
+impl CallLogFilter {
+    /// Whether no condition is set (would match every call log)
+    pub fn is_empty(&self) -> bool {
+        self.model_id.is_none() && self.start.is_none() && self.end.is_none()
+    }
+}
+
+/// Count call logs matching the filter (async)
+pub async fn count_call_logs_by_filter(pool: &SqlitePool, filter: &CallLogFilter) -> Result<i64> {
+    let count: (i64,) = timed_query("call_log.count_call_logs_by_filter", r#"
+        SELECT COUNT(*) FROM call_logs
+        WHERE (?1 IS NULL OR model_id = ?1) AND (?2 IS NULL OR created_at >= ?2) AND (?3 IS NULL OR created_at < ?3)
+    "#, |sql| sqlx::query_as(sql)
+        .bind(&filter.model_id)
+        .bind(&filter.start)
+        .bind(&filter.end)
+        .fetch_one(pool))
+        .await?;
+    Ok(count.0)
+}
+
+/// Stream call logs matching the filter row by row, oldest first (async cursor)
+pub fn stream_call_logs_by_filter<'a>(pool: &'a SqlitePool, filter: &'a CallLogFilter) -> BoxStream<'a, Result<CallLog>> {
+    sqlx::query_as::<_, CallLog>(r#"
+        SELECT * FROM call_logs
+        WHERE (?1 IS NULL OR model_id = ?1) AND (?2 IS NULL OR created_at >= ?2) AND (?3 IS NULL OR created_at < ?3)
+        ORDER BY created_at ASC, id ASC
+    "#)
+        .bind(&filter.model_id)
+        .bind(&filter.start)
+        .bind(&filter.end)
+        .fetch(pool)
+}
+
+/// Delete up to `batch_size` call logs matching the filter, returns deleted rows (async)
+pub async fn delete_call_logs_by_filter_batch(pool: &SqlitePool, filter: &CallLogFilter, batch_size: i64) -> Result<u64> {
+    let res = timed_query("call_log.delete_call_logs_by_filter_batch", r#"
+        DELETE FROM call_logs WHERE id IN (
+            SELECT id FROM call_logs
+            WHERE (?1 IS NULL OR model_id = ?1) AND (?2 IS NULL OR created_at >= ?2) AND (?3 IS NULL OR created_at < ?3)
+            LIMIT ?4
+        )
+    "#, |sql| sqlx::query(sql)
+        .bind(&filter.model_id)
+        .bind(&filter.start)
+        .bind(&filter.end)
+        .bind(batch_size)
+        .execute(pool))
+        .await?;
+    Ok(res.rows_affected())
+}
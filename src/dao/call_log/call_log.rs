@@ -1,6 +1,9 @@
 use sqlx::{SqlitePool, Result};
 use serde::Serialize;
 
+use crate::dao::pagination::{Cursor, CursorKey};
+use crate::dao::query_stats::timed_query;
+
 #[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, sqlx::FromRow)]
 pub struct CallLog {
@@ -8,191 +11,440 @@ pub struct CallLog {
     pub model_id: Option<String>,    
     pub status_code: i64,
     pub total_duration: i64,
+    pub tokens_input: i64,
     pub tokens_output: i64,
+    pub cost: f64,
+    pub provider: Option<String>,
+    pub key_id: Option<String>,
+    pub request_summary: Option<String>,
+    pub finish_reason: Option<String>,
+    pub provider_request_id: Option<String>,
+    pub provider_usage: Option<String>,
     pub error_message: Option<String>,
+    pub detected_language: Option<String>,
+    pub project_id: Option<String>,  // 发起请求的项目，为空时记为 default 项目
+    pub traffic_arm: Option<String>, // A/B 流量拆分的分组（逻辑模型名:分组名）
+    pub request_id: Option<String>,  // 网关请求 ID（X-Request-Id），同一请求的多次调用共用
     pub created_at: Option<String>,
 }
 
+impl CursorKey for CallLog {
+    fn cursor(&self) -> Cursor {
+        Cursor { created_at: self.created_at.clone().unwrap_or_default(), id: self.id.clone() }
+    }
+}
+
 /// Create a new call log entry (async)
 pub async fn create_call_log(pool: &SqlitePool, call_log: &CallLog) -> Result<u64> {
-    let res = sqlx::query(r#"
+    let res = timed_query("call_log.create_call_log", r#"
         INSERT INTO call_logs (
-            id, model_id, status_code, total_duration, tokens_output, error_message, created_at
-        ) VALUES (?, ?, ?, ?, ?, ?, datetime('now'))
-    "#)
+            id, model_id, status_code, total_duration, tokens_input, tokens_output, cost,
+            provider, key_id, request_summary, finish_reason, provider_request_id, provider_usage,
+            error_message, detected_language, project_id, traffic_arm, request_id, created_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, COALESCE(?, 'default'), ?, ?, datetime('now'))
+    "#, |sql| sqlx::query(sql)
         .bind(&call_log.id)
         .bind(&call_log.model_id)
         .bind(call_log.status_code)
         .bind(call_log.total_duration)
+        .bind(call_log.tokens_input)
         .bind(call_log.tokens_output)
+        .bind(call_log.cost)
+        .bind(&call_log.provider)
+        .bind(&call_log.key_id)
+        .bind(&call_log.request_summary)
+        .bind(&call_log.finish_reason)
+        .bind(&call_log.provider_request_id)
+        .bind(&call_log.provider_usage)
         .bind(&call_log.error_message)
-        .execute(pool)
+        .bind(&call_log.detected_language)
+        .bind(&call_log.project_id)
+        .bind(&call_log.traffic_arm)
+        .bind(&call_log.request_id)
+        .execute(pool))
         .await?;
     Ok(res.rows_affected())
 }
 
 /// Read a call log entry by id (async)
 pub async fn get_call_log_by_id(pool: &SqlitePool, id: &str) -> Result<Option<CallLog>> {
-    let call_log = sqlx::query_as::<_, CallLog>("SELECT * FROM call_logs WHERE id = ?")
+    let call_log = timed_query("call_log.get_call_log_by_id", "SELECT * FROM call_logs WHERE id = ?", |sql| sqlx::query_as::<_, CallLog>(sql)
         .bind(id)
-        .fetch_optional(pool)
+        .fetch_optional(pool))
         .await?;
     Ok(call_log)
 }
 
 /// List all call log entries (async)
 pub async fn list_call_logs(pool: &SqlitePool) -> Result<Vec<CallLog>> {
-    let call_logs = sqlx::query_as::<_, CallLog>("SELECT * FROM call_logs ORDER BY created_at DESC")
-        .fetch_all(pool)
+    let call_logs = timed_query("call_log.list_call_logs", "SELECT * FROM call_logs ORDER BY created_at DESC", |sql| sqlx::query_as::<_, CallLog>(sql)
+        .fetch_all(pool))
         .await?;
     Ok(call_logs)
 }
 
 /// List call logs with pagination (async)
 pub async fn list_call_logs_paginated(pool: &SqlitePool, limit: i64, offset: i64) -> Result<Vec<CallLog>> {
-    let call_logs = sqlx::query_as::<_, CallLog>("SELECT * FROM call_logs ORDER BY created_at DESC LIMIT ? OFFSET ?")
+    let call_logs = timed_query("call_log.list_call_logs_paginated", "SELECT * FROM call_logs ORDER BY created_at DESC LIMIT ? OFFSET ?", |sql| sqlx::query_as::<_, CallLog>(sql)
         .bind(limit)
         .bind(offset)
-        .fetch_all(pool)
+        .fetch_all(pool))
         .await?;
     Ok(call_logs)
 }
 
 /// List call logs by model_id (async)
 pub async fn list_call_logs_by_model(pool: &SqlitePool, model_id: &str) -> Result<Vec<CallLog>> {
-    let call_logs = sqlx::query_as::<_, CallLog>("SELECT * FROM call_logs WHERE model_id = ? ORDER BY created_at DESC")
+    let call_logs = timed_query("call_log.list_call_logs_by_model", "SELECT * FROM call_logs WHERE model_id = ? ORDER BY created_at DESC", |sql| sqlx::query_as::<_, CallLog>(sql)
         .bind(model_id)
-        .fetch_all(pool)
+        .fetch_all(pool))
+        .await?;
+    Ok(call_logs)
+}
+
+/// List call logs by provider (async)
+pub async fn list_call_logs_by_provider(pool: &SqlitePool, provider: &str) -> Result<Vec<CallLog>> {
+    let call_logs = timed_query("call_log.list_call_logs_by_provider", "SELECT * FROM call_logs WHERE provider = ? ORDER BY created_at DESC", |sql| sqlx::query_as::<_, CallLog>(sql)
+        .bind(provider)
+        .fetch_all(pool))
+        .await?;
+    Ok(call_logs)
+}
+
+/// List call logs of a gateway request, oldest first (async)
+pub async fn list_call_logs_by_request_id(pool: &SqlitePool, request_id: &str) -> Result<Vec<CallLog>> {
+    let call_logs = timed_query("call_log.list_call_logs_by_request_id", "SELECT * FROM call_logs WHERE request_id = ? ORDER BY created_at ASC, id ASC", |sql| sqlx::query_as::<_, CallLog>(sql)
+        .bind(request_id)
+        .fetch_all(pool))
+        .await?;
+    Ok(call_logs)
+}
+
+/// List call logs served by an API key (async)
+pub async fn list_call_logs_by_key(pool: &SqlitePool, key_id: &str) -> Result<Vec<CallLog>> {
+    let call_logs = timed_query("call_log.list_call_logs_by_key", "SELECT * FROM call_logs WHERE key_id = ? ORDER BY created_at DESC", |sql| sqlx::query_as::<_, CallLog>(sql)
+        .bind(key_id)
+        .fetch_all(pool))
         .await?;
     Ok(call_logs)
 }
 
 /// List call logs by status code (async)
 pub async fn list_call_logs_by_status(pool: &SqlitePool, status_code: i64) -> Result<Vec<CallLog>> {
-    let call_logs = sqlx::query_as::<_, CallLog>("SELECT * FROM call_logs WHERE status_code = ? ORDER BY created_at DESC")
+    let call_logs = timed_query("call_log.list_call_logs_by_status", "SELECT * FROM call_logs WHERE status_code = ? ORDER BY created_at DESC", |sql| sqlx::query_as::<_, CallLog>(sql)
         .bind(status_code)
-        .fetch_all(pool)
+        .fetch_all(pool))
         .await?;
     Ok(call_logs)
 }
 
 /// List error call logs (non-200 status codes) (async)
 pub async fn list_error_call_logs(pool: &SqlitePool) -> Result<Vec<CallLog>> {
-    let call_logs = sqlx::query_as::<_, CallLog>("SELECT * FROM call_logs WHERE status_code != 200 ORDER BY created_at DESC")
-        .fetch_all(pool)
+    let call_logs = timed_query("call_log.list_error_call_logs", "SELECT * FROM call_logs WHERE status_code != 200 ORDER BY created_at DESC", |sql| sqlx::query_as::<_, CallLog>(sql)
+        .fetch_all(pool))
         .await?;
     Ok(call_logs)
 }
 
 /// List call logs within date range (async)
 pub async fn list_call_logs_by_date_range(pool: &SqlitePool, start_date: &str, end_date: &str) -> Result<Vec<CallLog>> {
-    let call_logs = sqlx::query_as::<_, CallLog>(
+    let call_logs = timed_query("call_log.list_call_logs_by_date_range", 
         "SELECT * FROM call_logs WHERE created_at >= ? AND created_at <= ? ORDER BY created_at DESC"
-    )
+    , |sql| sqlx::query_as::<_, CallLog>(sql)
         .bind(start_date)
         .bind(end_date)
-        .fetch_all(pool)
+        .fetch_all(pool))
         .await?;
     Ok(call_logs)
 }
 
 /// Get call logs statistics (async)
 pub async fn get_call_logs_stats(pool: &SqlitePool) -> Result<CallLogStats> {
-    let stats = sqlx::query_as::<_, CallLogStats>(r#"
+    let stats = timed_query("call_log.get_call_logs_stats", r#"
         SELECT 
             COUNT(*) as total_calls,
             AVG(total_duration) as avg_latency_ms,
-            0 as total_tokens_input,
-            SUM(tokens_output) as total_tokens_output,
-            0.0 as total_cost,
+            COALESCE(SUM(tokens_input), 0) as total_tokens_input,
+            COALESCE(SUM(tokens_output), 0) as total_tokens_output,
+            COALESCE(SUM(cost), 0.0) as total_cost,
             COUNT(CASE WHEN status_code != 200 THEN 1 END) as error_count
         FROM call_logs
-    "#)
-        .fetch_one(pool)
+    "#, |sql| sqlx::query_as::<_, CallLogStats>(sql)
+        .fetch_one(pool))
         .await?;
     Ok(stats)
 }
 
 /// Get call logs statistics by model (async)
 pub async fn get_call_logs_stats_by_model(pool: &SqlitePool, model_id: &str) -> Result<CallLogStats> {
-    let stats = sqlx::query_as::<_, CallLogStats>(r#"
+    let stats = timed_query("call_log.get_call_logs_stats_by_model", r#"
         SELECT 
             COUNT(*) as total_calls,
             AVG(total_duration) as avg_latency_ms,
-            0 as total_tokens_input,
-            SUM(tokens_output) as total_tokens_output,
-            0.0 as total_cost,
+            COALESCE(SUM(tokens_input), 0) as total_tokens_input,
+            COALESCE(SUM(tokens_output), 0) as total_tokens_output,
+            COALESCE(SUM(cost), 0.0) as total_cost,
             COUNT(CASE WHEN status_code != 200 THEN 1 END) as error_count
         FROM call_logs WHERE model_id = ?
-    "#)
+    "#, |sql| sqlx::query_as::<_, CallLogStats>(sql)
         .bind(model_id)
-        .fetch_one(pool)
+        .fetch_one(pool))
         .await?;
     Ok(stats)
 }
 
+/// Get call logs statistics grouped by detected prompt language (async)
+pub async fn get_call_logs_stats_by_language(pool: &SqlitePool) -> Result<Vec<LanguageCallStats>> {
+    let stats = timed_query("call_log.get_call_logs_stats_by_language", r#"
+        SELECT 
+            detected_language,
+            COUNT(*) as total_calls,
+            AVG(total_duration) as avg_latency_ms,
+            COUNT(CASE WHEN status_code != 200 THEN 1 END) as error_count
+        FROM call_logs
+        GROUP BY detected_language
+        ORDER BY total_calls DESC
+    "#, |sql| sqlx::query_as::<_, LanguageCallStats>(sql)
+        .fetch_all(pool))
+        .await?;
+    Ok(stats)
+}
+
+/// Get call logs statistics grouped by A/B traffic split arm, calls outside any split are skipped (async)
+pub async fn get_call_logs_stats_by_traffic_arm(pool: &SqlitePool) -> Result<Vec<TrafficArmCallStats>> {
+    let stats = timed_query("call_log.get_call_logs_stats_by_traffic_arm", r#"
+        SELECT
+            traffic_arm,
+            COUNT(*) as total_calls,
+            AVG(total_duration) as avg_latency_ms,
+            COALESCE(SUM(tokens_input), 0) as total_tokens_input,
+            COALESCE(SUM(tokens_output), 0) as total_tokens_output,
+            COALESCE(SUM(cost), 0.0) as total_cost,
+            COUNT(CASE WHEN status_code != 200 THEN 1 END) as error_count
+        FROM call_logs
+        WHERE traffic_arm IS NOT NULL
+        GROUP BY traffic_arm
+        ORDER BY traffic_arm
+    "#, |sql| sqlx::query_as::<_, TrafficArmCallStats>(sql)
+        .fetch_all(pool))
+        .await?;
+    Ok(stats)
+}
+
+/// Get call counts and error counts per provider for calls created at or after `since` (async)
+pub async fn get_call_logs_stats_by_provider_since(pool: &SqlitePool, since: &str) -> Result<Vec<ProviderCallStats>> {
+    let stats = timed_query("call_log.get_call_logs_stats_by_provider_since", r#"
+        SELECT
+            provider,
+            COUNT(*) as total_calls,
+            COUNT(CASE WHEN status_code != 200 THEN 1 END) as error_count
+        FROM call_logs
+        WHERE created_at >= ?
+        GROUP BY provider
+        ORDER BY total_calls DESC
+    "#, |sql| sqlx::query_as::<_, ProviderCallStats>(sql)
+        .bind(since)
+        .fetch_all(pool))
+        .await?;
+    Ok(stats)
+}
+
+/// Get call counts, error counts and average latency per provider and model for calls created at or after `since` (async)
+///
+/// Calls whose model is unknown (not in the model catalog) are skipped
+pub async fn get_call_logs_stats_by_provider_model_since(pool: &SqlitePool, since: &str) -> Result<Vec<ProviderModelCallStats>> {
+    let stats = timed_query("call_log.get_call_logs_stats_by_provider_model_since", r#"
+        SELECT
+            c.provider,
+            m.name as model,
+            COUNT(*) as total_calls,
+            COUNT(CASE WHEN c.status_code != 200 THEN 1 END) as error_count,
+            AVG(c.total_duration) as avg_latency_ms
+        FROM call_logs c
+        JOIN models m ON m.id = c.model_id
+        WHERE c.created_at >= ? AND c.provider IS NOT NULL
+        GROUP BY c.provider, m.name
+    "#, |sql| sqlx::query_as::<_, ProviderModelCallStats>(sql)
+        .bind(since)
+        .fetch_all(pool))
+        .await?;
+    Ok(stats)
+}
+
+/// Reconcile gateway usage against provider-reported usage (async)
+///
+/// `period` is a `YYYY-MM` month; provider-reported token counts are read from the raw usage JSON
+/// (`prompt_tokens`/`completion_tokens`, or Ollama's `prompt_eval_count`/`eval_count`)
+pub async fn get_billing_reconciliation(
+    pool: &SqlitePool,
+    period: Option<&str>,
+    provider: Option<&str>,
+) -> Result<Vec<BillingReconciliationRow>> {
+    let rows = timed_query("call_log.get_billing_reconciliation", r#"
+        WITH calls AS (
+            SELECT
+                strftime('%Y-%m', c.created_at) as period,
+                c.provider,
+                COALESCE(m.name, c.model_id) as model,
+                c.tokens_input,
+                c.tokens_output,
+                c.cost,
+                c.provider_usage,
+                COALESCE(json_extract(c.provider_usage, '$.prompt_tokens'), json_extract(c.provider_usage, '$.prompt_eval_count')) as provider_input,
+                COALESCE(json_extract(c.provider_usage, '$.completion_tokens'), json_extract(c.provider_usage, '$.eval_count')) as provider_output
+            FROM call_logs c
+            LEFT JOIN models m ON m.id = c.model_id
+            WHERE c.status_code = 200
+        )
+        SELECT
+            period,
+            provider,
+            model,
+            COUNT(*) as total_calls,
+            COALESCE(SUM(tokens_input), 0) as gateway_tokens_input,
+            COALESCE(SUM(tokens_output), 0) as gateway_tokens_output,
+            COALESCE(SUM(cost), 0.0) as gateway_cost,
+            COUNT(provider_usage) as calls_with_provider_usage,
+            COALESCE(SUM(provider_input), 0) as provider_tokens_input,
+            COALESCE(SUM(provider_output), 0) as provider_tokens_output,
+            COUNT(CASE WHEN provider_usage IS NOT NULL
+                AND (COALESCE(provider_input, 0) != tokens_input OR COALESCE(provider_output, 0) != tokens_output)
+                THEN 1 END) as mismatched_calls
+        FROM calls
+        WHERE (? IS NULL OR period = ?) AND (? IS NULL OR provider = ?)
+        GROUP BY period, provider, model
+        ORDER BY period DESC, provider, model
+    "#, |sql| sqlx::query_as::<_, BillingReconciliationRow>(sql)
+        .bind(period)
+        .bind(period)
+        .bind(provider)
+        .bind(provider)
+        .fetch_all(pool))
+        .await?;
+    Ok(rows)
+}
+
 /// Update a call log entry by id (async)
 pub async fn update_call_log(pool: &SqlitePool, call_log: &CallLog) -> Result<u64> {
-    let res = sqlx::query(r#"
+    let res = timed_query("call_log.update_call_log", r#"
         UPDATE call_logs SET
             model_id = ?,
             status_code = ?,
             total_duration = ?,
+            tokens_input = ?,
             tokens_output = ?,
-            error_message = ?
+            cost = ?,
+            provider = ?,
+            key_id = ?,
+            request_summary = ?,
+            finish_reason = ?,
+            provider_request_id = ?,
+            provider_usage = ?,
+            error_message = ?,
+            detected_language = ?
         WHERE id = ?
-    "#)
+    "#, |sql| sqlx::query(sql)
         .bind(&call_log.model_id)
         .bind(call_log.status_code)
         .bind(call_log.total_duration)
+        .bind(call_log.tokens_input)
         .bind(call_log.tokens_output)
+        .bind(call_log.cost)
+        .bind(&call_log.provider)
+        .bind(&call_log.key_id)
+        .bind(&call_log.request_summary)
+        .bind(&call_log.finish_reason)
+        .bind(&call_log.provider_request_id)
+        .bind(&call_log.provider_usage)
         .bind(&call_log.error_message)
+        .bind(&call_log.detected_language)
         .bind(&call_log.id)
-        .execute(pool)
+        .execute(pool))
+        .await?;
+    Ok(res.rows_affected())
+}
+
+/// Usage of a call recorded after the provider response is parsed
+#[derive(Debug, Clone, Default)]
+pub struct CallLogUsage {
+    pub model_id: Option<String>,
+    pub tokens_input: i64,
+    pub tokens_output: i64,
+    pub cost: f64,
+    pub provider: Option<String>,
+    pub finish_reason: Option<String>,
+    pub provider_request_id: Option<String>,
+    pub provider_usage: Option<String>,
+}
+
+/// Record token usage, cost and provider-reported billing data of a call log entry (async)
+pub async fn update_call_log_usage(pool: &SqlitePool, id: &str, usage: &CallLogUsage) -> Result<u64> {
+    let res = timed_query("call_log.update_call_log_usage", r#"
+        UPDATE call_logs SET
+            model_id = COALESCE(?, model_id),
+            tokens_input = ?,
+            tokens_output = ?,
+            cost = ?,
+            provider = COALESCE(?, provider),
+            finish_reason = COALESCE(?, finish_reason),
+            provider_request_id = ?,
+            provider_usage = ?
+        WHERE id = ?
+    "#, |sql| sqlx::query(sql)
+        .bind(&usage.model_id)
+        .bind(usage.tokens_input)
+        .bind(usage.tokens_output)
+        .bind(usage.cost)
+        .bind(&usage.provider)
+        .bind(&usage.finish_reason)
+        .bind(&usage.provider_request_id)
+        .bind(&usage.provider_usage)
+        .bind(id)
+        .execute(pool))
         .await?;
     Ok(res.rows_affected())
 }
 
 /// Delete a call log entry by id (async)
 pub async fn delete_call_log(pool: &SqlitePool, id: &str) -> Result<u64> {
-    let res = sqlx::query("DELETE FROM call_logs WHERE id = ?")
+    let res = timed_query("call_log.delete_call_log", "DELETE FROM call_logs WHERE id = ?", |sql| sqlx::query(sql)
         .bind(id)
-        .execute(pool)
+        .execute(pool))
         .await?;
     Ok(res.rows_affected())
 }
 
 /// Delete call logs by model_id (async)
 pub async fn delete_call_logs_by_model(pool: &SqlitePool, model_id: &str) -> Result<u64> {
-    let res = sqlx::query("DELETE FROM call_logs WHERE model_id = ?")
+    let res = timed_query("call_log.delete_call_logs_by_model", "DELETE FROM call_logs WHERE model_id = ?", |sql| sqlx::query(sql)
         .bind(model_id)
-        .execute(pool)
+        .execute(pool))
         .await?;
     Ok(res.rows_affected())
 }
 
 /// Delete call logs older than specified date (async)
 pub async fn delete_old_call_logs(pool: &SqlitePool, before_date: &str) -> Result<u64> {
-    let res = sqlx::query("DELETE FROM call_logs WHERE created_at < ?")
+    let res = timed_query("call_log.delete_old_call_logs", "DELETE FROM call_logs WHERE created_at < ?", |sql| sqlx::query(sql)
         .bind(before_date)
-        .execute(pool)
+        .execute(pool))
         .await?;
     Ok(res.rows_affected())
 }
 
 /// Get count of call logs (async)
 pub async fn count_call_logs(pool: &SqlitePool) -> Result<i64> {
-    let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM call_logs")
-        .fetch_one(pool)
+    let count: (i64,) = timed_query("call_log.count_call_logs", "SELECT COUNT(*) FROM call_logs", |sql| sqlx::query_as(sql)
+        .fetch_one(pool))
         .await?;
     Ok(count.0)
 }
 
 /// Get count of call logs by model (async)
 pub async fn count_call_logs_by_model(pool: &SqlitePool, model_id: &str) -> Result<i64> {
-    let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM call_logs WHERE model_id = ?")
+    let count: (i64,) = timed_query("call_log.count_call_logs_by_model", "SELECT COUNT(*) FROM call_logs WHERE model_id = ?", |sql| sqlx::query_as(sql)
         .bind(model_id)
-        .fetch_one(pool)
+        .fetch_one(pool))
         .await?;
     Ok(count.0)
 }
@@ -207,3 +459,58 @@ pub struct CallLogStats {
     pub total_cost: f64,
     pub error_count: i64,
 }
+
+/// Gateway vs provider-reported usage of successful calls, grouped by month, provider and model
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct BillingReconciliationRow {
+    pub period: String,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub total_calls: i64,
+    pub gateway_tokens_input: i64,
+    pub gateway_tokens_output: i64,
+    pub gateway_cost: f64,
+    pub calls_with_provider_usage: i64,
+    pub provider_tokens_input: i64,
+    pub provider_tokens_output: i64,
+    pub mismatched_calls: i64,
+}
+
+/// Statistics struct for call logs grouped by prompt language
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct LanguageCallStats {
+    pub detected_language: Option<String>,
+    pub total_calls: i64,
+    pub avg_latency_ms: Option<f64>,
+    pub error_count: i64,
+}
+
+/// Statistics struct for call logs grouped by A/B traffic split arm
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct TrafficArmCallStats {
+    pub traffic_arm: String,
+    pub total_calls: i64,
+    pub avg_latency_ms: Option<f64>,
+    pub total_tokens_input: i64,
+    pub total_tokens_output: i64,
+    pub total_cost: f64,
+    pub error_count: i64,
+}
+
+/// Call counts and error counts grouped by provider
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ProviderCallStats {
+    pub provider: Option<String>,
+    pub total_calls: i64,
+    pub error_count: i64,
+}
+
+/// Call counts, error counts and average latency grouped by provider and model
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ProviderModelCallStats {
+    pub provider: String,
+    pub model: String,
+    pub total_calls: i64,
+    pub error_count: i64,
+    pub avg_latency_ms: Option<f64>,
+}
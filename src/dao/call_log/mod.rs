@@ -1,22 +1,67 @@
 mod call_log;
+mod archive;
+mod search;
+mod rollup;
 
 pub use call_log::{
     CallLog,
     CallLogStats,
+    CallLogUsage,
+    BillingReconciliationRow,
+    LanguageCallStats,
+    TrafficArmCallStats,
+    ProviderCallStats,
+    ProviderModelCallStats,
     create_call_log,
     get_call_log_by_id,
     list_call_logs,
     list_call_logs_paginated,
     list_call_logs_by_model,
+    list_call_logs_by_provider,
+    list_call_logs_by_key,
+    list_call_logs_by_request_id,
     list_call_logs_by_status,
     list_error_call_logs,
     list_call_logs_by_date_range,
     get_call_logs_stats,
     get_call_logs_stats_by_model,
+    get_call_logs_stats_by_language,
+    get_call_logs_stats_by_traffic_arm,
+    get_call_logs_stats_by_provider_since,
+    get_call_logs_stats_by_provider_model_since,
+    get_billing_reconciliation,
     update_call_log,
+    update_call_log_usage,
     delete_call_log,
     delete_call_logs_by_model,
     delete_old_call_logs,
     count_call_logs,
     count_call_logs_by_model
-};
\ No newline at end of file
+};
+
+pub use archive::{
+    CallLogFilter,
+    count_call_logs_by_filter,
+    stream_call_logs_by_filter,
+    delete_call_logs_by_filter_batch
+};
+
+pub use search::{
+    CallLogSearch,
+    ModelCallStats,
+    StatsBucket,
+    CallLogStatsBucket,
+    search_call_logs,
+    search_call_logs_page,
+    count_call_logs_by_search,
+    get_call_logs_stats_by_search,
+    get_call_log_latency_percentile,
+    get_call_logs_stats_per_model,
+    get_call_logs_stats_timeseries
+};
+
+pub use rollup::{
+    CallLogDailyStat,
+    rollup_call_logs_before,
+    list_call_log_daily_stats
+};
@@ -0,0 +1,74 @@
+use serde::Serialize;
+use sqlx::{SqlitePool, Result};
+
+use crate::dao::query_stats::timed_query;
+
+/// Daily aggregate of call logs per provider, model and project
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct CallLogDailyStat {
+    pub day: String,                // YYYY-MM-DD（UTC，与 call_logs.created_at 一致）
+    pub provider: String,           // 空字符串表示未记录供应商
+    pub model_id: String,           // 空字符串表示未记录模型
+    pub project_id: String,
+    pub total_calls: i64,
+    pub error_count: i64,
+    pub tokens_input: i64,
+    pub tokens_output: i64,
+    pub cost: f64,
+    pub total_duration: i64,        // 所有调用耗时之和，毫秒
+    pub updated_at: Option<String>,
+}
+
+/// Roll call logs created before `before_date` up into daily aggregates (async)
+///
+/// Aggregates of a day are recomputed from the raw rows, so rolling up the same days again before they are
+/// deleted does not count them twice. Returns the number of aggregate rows written.
+pub async fn rollup_call_logs_before(pool: &SqlitePool, before_date: &str) -> Result<u64> {
+    let res = timed_query("call_log.rollup_call_logs_before", r#"
+        INSERT INTO call_log_daily_stats (
+            day, provider, model_id, project_id, total_calls, error_count,
+            tokens_input, tokens_output, cost, total_duration, updated_at
+        )
+        SELECT
+            substr(created_at, 1, 10),
+            COALESCE(provider, ''),
+            COALESCE(model_id, ''),
+            COALESCE(project_id, 'default'),
+            COUNT(*),
+            COUNT(CASE WHEN status_code != 200 THEN 1 END),
+            COALESCE(SUM(tokens_input), 0),
+            COALESCE(SUM(tokens_output), 0),
+            COALESCE(SUM(cost), 0.0),
+            COALESCE(SUM(total_duration), 0),
+            datetime('now', 'localtime')
+        FROM call_logs
+        WHERE created_at < ?
+        GROUP BY 1, 2, 3, 4
+        ON CONFLICT(day, provider, model_id, project_id) DO UPDATE SET
+            total_calls = excluded.total_calls,
+            error_count = excluded.error_count,
+            tokens_input = excluded.tokens_input,
+            tokens_output = excluded.tokens_output,
+            cost = excluded.cost,
+            total_duration = excluded.total_duration,
+            updated_at = excluded.updated_at
+    "#, |sql| sqlx::query(sql)
+        .bind(before_date)
+        .execute(pool))
+        .await?;
+    Ok(res.rows_affected())
+}
+
+/// List daily aggregates between `start` and `end` (inclusive `YYYY-MM-DD` days), newest day first (async)
+pub async fn list_call_log_daily_stats(pool: &SqlitePool, start: Option<&str>, end: Option<&str>) -> Result<Vec<CallLogDailyStat>> {
+    let stats = timed_query("call_log.list_call_log_daily_stats", r#"
+        SELECT * FROM call_log_daily_stats
+        WHERE (?1 IS NULL OR day >= ?1) AND (?2 IS NULL OR day <= ?2)
+        ORDER BY day DESC, provider, model_id, project_id
+    "#, |sql| sqlx::query_as::<_, CallLogDailyStat>(sql)
+        .bind(start)
+        .bind(end)
+        .fetch_all(pool))
+        .await?;
+    Ok(stats)
+}
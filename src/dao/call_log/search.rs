@@ -0,0 +1,254 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{SqlitePool, Result};
+
+use crate::dao::pagination::{into_page, Cursor, CursorPage};
+use crate::dao::query_stats::timed_query;
+use super::call_log::{CallLog, CallLogStats};
+
+/// Filter for querying call logs; every condition is optional and `end` is exclusive
+#[derive(Debug, Clone, Default)]
+pub struct CallLogSearch {
+    pub model_id: Option<String>,
+    pub provider: Option<String>,
+    pub status_code: Option<i64>,
+    /// Only non-200 status codes
+    pub errors_only: bool,
+    pub start: Option<String>,
+    pub end: Option<String>,
+    pub project_id: Option<String>,
+}
+
+/// List call logs matching the search with pagination, newest first (async)
+pub async fn search_call_logs(pool: &SqlitePool, search: &CallLogSearch, limit: i64, offset: i64) -> Result<Vec<CallLog>> {
+    let call_logs = timed_query("call_log.search_call_logs", r#"
+        SELECT * FROM call_logs
+        WHERE (?1 IS NULL OR model_id = ?1) AND (?2 IS NULL OR provider = ?2) AND (?3 IS NULL OR status_code = ?3)
+            AND (?4 = 0 OR status_code != 200) AND (?5 IS NULL OR created_at >= ?5) AND (?6 IS NULL OR created_at < ?6)
+            AND (?9 IS NULL OR project_id = ?9)
+        ORDER BY created_at DESC, id DESC
+        LIMIT ?7 OFFSET ?8
+    "#, |sql| sqlx::query_as::<_, CallLog>(sql)
+        .bind(&search.model_id)
+        .bind(&search.provider)
+        .bind(search.status_code)
+        .bind(search.errors_only)
+        .bind(&search.start)
+        .bind(&search.end)
+        .bind(limit)
+        .bind(offset)
+        .bind(&search.project_id)
+        .fetch_all(pool))
+        .await?;
+    Ok(call_logs)
+}
+
+/// List call logs matching the search newest first, one page after `cursor` (async)
+pub async fn search_call_logs_page(pool: &SqlitePool, search: &CallLogSearch, cursor: Option<&Cursor>, limit: i64) -> Result<CursorPage<CallLog>> {
+    let call_logs = timed_query("call_log.search_call_logs_page", r#"
+        SELECT * FROM call_logs
+        WHERE (?1 IS NULL OR model_id = ?1) AND (?2 IS NULL OR provider = ?2) AND (?3 IS NULL OR status_code = ?3)
+            AND (?4 = 0 OR status_code != 200) AND (?5 IS NULL OR created_at >= ?5) AND (?6 IS NULL OR created_at < ?6)
+            AND (?10 IS NULL OR project_id = ?10)
+            AND (?7 IS NULL OR (created_at, id) < (?7, ?8))
+        ORDER BY created_at DESC, id DESC
+        LIMIT ?9
+    "#, |sql| sqlx::query_as::<_, CallLog>(sql)
+        .bind(&search.model_id)
+        .bind(&search.provider)
+        .bind(search.status_code)
+        .bind(search.errors_only)
+        .bind(&search.start)
+        .bind(&search.end)
+        .bind(cursor.map(|c| c.created_at.as_str()))
+        .bind(cursor.map(|c| c.id.as_str()))
+        .bind(limit + 1)
+        .bind(&search.project_id)
+        .fetch_all(pool))
+        .await?;
+    Ok(into_page(call_logs, limit))
+}
+
+/// Count call logs matching the search (async)
+pub async fn count_call_logs_by_search(pool: &SqlitePool, search: &CallLogSearch) -> Result<i64> {
+    let count: (i64,) = timed_query("call_log.count_call_logs_by_search", r#"
+        SELECT COUNT(*) FROM call_logs
+        WHERE (?1 IS NULL OR model_id = ?1) AND (?2 IS NULL OR provider = ?2) AND (?3 IS NULL OR status_code = ?3)
+            AND (?4 = 0 OR status_code != 200) AND (?5 IS NULL OR created_at >= ?5) AND (?6 IS NULL OR created_at < ?6)
+            AND (?7 IS NULL OR project_id = ?7)
+    "#, |sql| sqlx::query_as(sql)
+        .bind(&search.model_id)
+        .bind(&search.provider)
+        .bind(search.status_code)
+        .bind(search.errors_only)
+        .bind(&search.start)
+        .bind(&search.end)
+        .bind(&search.project_id)
+        .fetch_one(pool))
+        .await?;
+    Ok(count.0)
+}
+
+/// Get statistics of call logs matching the search (async)
+pub async fn get_call_logs_stats_by_search(pool: &SqlitePool, search: &CallLogSearch) -> Result<CallLogStats> {
+    let stats = timed_query("call_log.get_call_logs_stats_by_search", r#"
+        SELECT
+            COUNT(*) as total_calls,
+            AVG(total_duration) as avg_latency_ms,
+            COALESCE(SUM(tokens_input), 0) as total_tokens_input,
+            COALESCE(SUM(tokens_output), 0) as total_tokens_output,
+            COALESCE(SUM(cost), 0.0) as total_cost,
+            COUNT(CASE WHEN status_code != 200 THEN 1 END) as error_count
+        FROM call_logs
+        WHERE (?1 IS NULL OR model_id = ?1) AND (?2 IS NULL OR provider = ?2) AND (?3 IS NULL OR status_code = ?3)
+            AND (?4 = 0 OR status_code != 200) AND (?5 IS NULL OR created_at >= ?5) AND (?6 IS NULL OR created_at < ?6)
+            AND (?7 IS NULL OR project_id = ?7)
+    "#, |sql| sqlx::query_as::<_, CallLogStats>(sql)
+        .bind(&search.model_id)
+        .bind(&search.provider)
+        .bind(search.status_code)
+        .bind(search.errors_only)
+        .bind(&search.start)
+        .bind(&search.end)
+        .bind(&search.project_id)
+        .fetch_one(pool))
+        .await?;
+    Ok(stats)
+}
+
+/// Get the latency (`total_duration`) at the given percentile (0.0-1.0, rounded down to a rank) of call logs matching the search,
+/// None when nothing matches (async)
+pub async fn get_call_log_latency_percentile(pool: &SqlitePool, search: &CallLogSearch, percentile: f64) -> Result<Option<i64>> {
+    let latency: Option<(i64,)> = timed_query("call_log.get_call_log_latency_percentile", r#"
+        WITH matched AS (
+            SELECT total_duration FROM call_logs
+            WHERE (?1 IS NULL OR model_id = ?1) AND (?2 IS NULL OR provider = ?2) AND (?3 IS NULL OR status_code = ?3)
+                AND (?4 = 0 OR status_code != 200) AND (?5 IS NULL OR created_at >= ?5) AND (?6 IS NULL OR created_at < ?6)
+                AND (?7 IS NULL OR project_id = ?7)
+        )
+        SELECT total_duration FROM matched
+        ORDER BY total_duration
+        LIMIT 1 OFFSET (SELECT CAST((COUNT(*) - 1) * ?8 AS INTEGER) FROM matched)
+    "#, |sql| sqlx::query_as::<_, (i64,)>(sql)
+        .bind(&search.model_id)
+        .bind(&search.provider)
+        .bind(search.status_code)
+        .bind(search.errors_only)
+        .bind(&search.start)
+        .bind(&search.end)
+        .bind(&search.project_id)
+        .bind(percentile.clamp(0.0, 1.0))
+        .fetch_optional(pool))
+        .await?;
+    Ok(latency.map(|(latency,)| latency))
+}
+
+/// Get statistics of call logs matching the search grouped by model, most calls first (async)
+pub async fn get_call_logs_stats_per_model(pool: &SqlitePool, search: &CallLogSearch) -> Result<Vec<ModelCallStats>> {
+    let stats = timed_query("call_log.get_call_logs_stats_per_model", r#"
+        SELECT
+            c.model_id,
+            m.name as model_name,
+            COALESCE(m.provider, MAX(c.provider)) as provider,
+            COUNT(*) as total_calls,
+            AVG(c.total_duration) as avg_latency_ms,
+            COALESCE(SUM(c.tokens_input), 0) as total_tokens_input,
+            COALESCE(SUM(c.tokens_output), 0) as total_tokens_output,
+            COALESCE(SUM(c.cost), 0.0) as total_cost,
+            COUNT(CASE WHEN c.status_code != 200 THEN 1 END) as error_count
+        FROM call_logs c
+        LEFT JOIN models m ON m.id = c.model_id
+        WHERE (?1 IS NULL OR c.model_id = ?1) AND (?2 IS NULL OR c.provider = ?2) AND (?3 IS NULL OR c.status_code = ?3)
+            AND (?4 = 0 OR c.status_code != 200) AND (?5 IS NULL OR c.created_at >= ?5) AND (?6 IS NULL OR c.created_at < ?6)
+            AND (?7 IS NULL OR c.project_id = ?7)
+        GROUP BY c.model_id
+        ORDER BY total_calls DESC
+    "#, |sql| sqlx::query_as::<_, ModelCallStats>(sql)
+        .bind(&search.model_id)
+        .bind(&search.provider)
+        .bind(search.status_code)
+        .bind(search.errors_only)
+        .bind(&search.start)
+        .bind(&search.end)
+        .bind(&search.project_id)
+        .fetch_all(pool))
+        .await?;
+    Ok(stats)
+}
+
+/// Get statistics of call logs matching the search per hour or per day, oldest bucket first; buckets without calls
+/// are omitted (async)
+pub async fn get_call_logs_stats_timeseries(pool: &SqlitePool, bucket: StatsBucket, range: &CallLogSearch) -> Result<Vec<CallLogStatsBucket>> {
+    let stats = timed_query("call_log.get_call_logs_stats_timeseries", r#"
+        SELECT
+            strftime(?8, created_at) as bucket,
+            COUNT(*) as total_calls,
+            COUNT(CASE WHEN status_code != 200 THEN 1 END) as error_count,
+            COALESCE(SUM(tokens_input), 0) as total_tokens_input,
+            COALESCE(SUM(tokens_output), 0) as total_tokens_output,
+            COALESCE(SUM(cost), 0.0) as total_cost,
+            AVG(total_duration) as avg_latency_ms
+        FROM call_logs
+        WHERE (?1 IS NULL OR model_id = ?1) AND (?2 IS NULL OR provider = ?2) AND (?3 IS NULL OR status_code = ?3)
+            AND (?4 = 0 OR status_code != 200) AND (?5 IS NULL OR created_at >= ?5) AND (?6 IS NULL OR created_at < ?6)
+            AND (?7 IS NULL OR project_id = ?7)
+        GROUP BY bucket
+        ORDER BY bucket
+    "#, |sql| sqlx::query_as::<_, CallLogStatsBucket>(sql)
+        .bind(&range.model_id)
+        .bind(&range.provider)
+        .bind(range.status_code)
+        .bind(range.errors_only)
+        .bind(&range.start)
+        .bind(&range.end)
+        .bind(&range.project_id)
+        .bind(bucket.strftime_format())
+        .fetch_all(pool))
+        .await?;
+    Ok(stats)
+}
+
+/// Statistics struct for call logs grouped by model
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ModelCallStats {
+    pub model_id: Option<String>,
+    pub model_name: Option<String>,
+    pub provider: Option<String>,
+    pub total_calls: i64,
+    pub avg_latency_ms: Option<f64>,
+    pub total_tokens_input: i64,
+    pub total_tokens_output: i64,
+    pub total_cost: f64,
+    pub error_count: i64,
+}
+
+/// Time bucket size for call log statistics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatsBucket {
+    #[default]
+    Hour,
+    Day,
+}
+
+impl StatsBucket {
+    /// SQLite `strftime` format of the bucket label (local time, same as `created_at`)
+    pub fn strftime_format(self) -> &'static str {
+        match self {
+            StatsBucket::Hour => "%Y-%m-%d %H:00:00",
+            StatsBucket::Day => "%Y-%m-%d",
+        }
+    }
+}
+
+/// Statistics struct for call logs in one time bucket
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct CallLogStatsBucket {
+    /// `YYYY-MM-DD HH:00:00` for hourly buckets, `YYYY-MM-DD` for daily buckets
+    pub bucket: String,
+    pub total_calls: i64,
+    pub error_count: i64,
+    pub total_tokens_input: i64,
+    pub total_tokens_output: i64,
+    pub total_cost: f64,
+    pub avg_latency_ms: Option<f64>,
+}
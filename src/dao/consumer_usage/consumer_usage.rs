@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{SqlitePool, Result};
+
+use crate::dao::query_stats::timed_query;
+
+#[derive(Debug, Clone, sqlx::FromRow, Serialize, Deserialize)]
+pub struct ConsumerUsageStat {
+    pub day: String,                // YYYY-MM-DD（本地时间）
+    pub consumer_id: String,
+    pub request_count: i64,
+    pub tokens: i64,
+    pub updated_at: Option<String>,
+}
+
+/// Filter for listing consumer usage; `start` and `end` are inclusive `YYYY-MM-DD` days
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConsumerUsageFilter {
+    pub start: Option<String>,
+    pub end: Option<String>,
+    pub consumer_id: Option<String>,
+}
+
+/// Add request and token deltas to the daily aggregate of a consumer (async)
+pub async fn add_consumer_usage(
+    pool: &SqlitePool,
+    day: &str,
+    consumer_id: &str,
+    request_count: i64,
+    tokens: i64,
+) -> Result<u64> {
+    let res = timed_query("consumer_usage.add_consumer_usage", r#"
+        INSERT INTO consumer_usage (day, consumer_id, request_count, tokens, updated_at)
+        VALUES (?, ?, ?, ?, datetime('now', 'localtime'))
+        ON CONFLICT(day, consumer_id) DO UPDATE SET
+            request_count = request_count + excluded.request_count,
+            tokens = tokens + excluded.tokens,
+            updated_at = excluded.updated_at
+    "#, |sql| sqlx::query(sql)
+        .bind(day)
+        .bind(consumer_id)
+        .bind(request_count)
+        .bind(tokens)
+        .execute(pool))
+        .await?;
+    Ok(res.rows_affected())
+}
+
+/// List daily consumer usage matching the filter, newest day first (async)
+pub async fn list_consumer_usage(pool: &SqlitePool, filter: &ConsumerUsageFilter) -> Result<Vec<ConsumerUsageStat>> {
+    let stats = timed_query("consumer_usage.list_consumer_usage", r#"
+        SELECT * FROM consumer_usage
+        WHERE (?1 IS NULL OR day >= ?1) AND (?2 IS NULL OR day <= ?2)
+            AND (?3 IS NULL OR consumer_id = ?3)
+        ORDER BY day DESC, consumer_id
+    "#, |sql| sqlx::query_as::<_, ConsumerUsageStat>(sql)
+        .bind(&filter.start)
+        .bind(&filter.end)
+        .bind(&filter.consumer_id)
+        .fetch_all(pool))
+        .await?;
+    Ok(stats)
+}
@@ -0,0 +1,8 @@
+mod consumer_usage;
+
+pub use consumer_usage::{
+    ConsumerUsageStat,
+    ConsumerUsageFilter,
+    add_consumer_usage,
+    list_consumer_usage
+};
@@ -0,0 +1,117 @@
+use sqlx::{SqlitePool, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::dao::query_stats::timed_query;
+
+#[derive(Debug, Clone, sqlx::FromRow, Serialize, Deserialize)]
+pub struct Conversation {
+    pub id: String,
+    pub title: Option<String>,
+    pub model: String,
+    pub system_prompt: Option<String>,
+    pub max_context_tokens: Option<i64>, // 发送给模型的历史 token 上限，None 表示不截断
+    pub truncation: String,              // 截断策略：drop_oldest、keep_first_turn
+    pub project_id: String,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, Serialize, Deserialize)]
+pub struct ConversationMessage {
+    pub id: String,
+    pub conversation_id: String,
+    pub seq: i64,                        // 会话内的消息顺序，写入时分配
+    pub role: String,
+    pub content: String,
+    pub tokens: i64,
+    pub created_at: Option<String>,
+}
+
+/// Create a new conversation (async)
+pub async fn create_conversation(pool: &SqlitePool, conversation: &Conversation) -> Result<u64> {
+    let res = timed_query("conversation.create_conversation", r#"
+        INSERT INTO conversations (
+            id, title, model, system_prompt, max_context_tokens, truncation, project_id, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, datetime('now'), datetime('now'))
+    "#, |sql| sqlx::query(sql)
+        .bind(&conversation.id)
+        .bind(&conversation.title)
+        .bind(&conversation.model)
+        .bind(&conversation.system_prompt)
+        .bind(conversation.max_context_tokens)
+        .bind(&conversation.truncation)
+        .bind(&conversation.project_id)
+        .execute(pool))
+        .await?;
+    Ok(res.rows_affected())
+}
+
+/// Read a conversation by id (async)
+pub async fn get_conversation_by_id(pool: &SqlitePool, id: &str) -> Result<Option<Conversation>> {
+    let conversation = timed_query("conversation.get_conversation_by_id", "SELECT * FROM conversations WHERE id = ?", |sql| sqlx::query_as::<_, Conversation>(sql)
+        .bind(id)
+        .fetch_optional(pool))
+        .await?;
+    Ok(conversation)
+}
+
+/// List conversations of a project, most recently updated first (async)
+pub async fn list_conversations_by_project(pool: &SqlitePool, project_id: &str, limit: i64) -> Result<Vec<Conversation>> {
+    let conversations = timed_query("conversation.list_conversations_by_project", "SELECT * FROM conversations WHERE project_id = ? ORDER BY updated_at DESC, id LIMIT ?", |sql| sqlx::query_as::<_, Conversation>(sql)
+        .bind(project_id)
+        .bind(limit)
+        .fetch_all(pool))
+        .await?;
+    Ok(conversations)
+}
+
+/// Delete a conversation and its messages (async)
+pub async fn delete_conversation(pool: &SqlitePool, id: &str) -> Result<u64> {
+    let mut tx = pool.begin().await?;
+    timed_query("conversation.delete_conversation_messages", "DELETE FROM conversation_messages WHERE conversation_id = ?", |sql| sqlx::query(sql)
+        .bind(id)
+        .execute(&mut *tx))
+        .await?;
+    let res = timed_query("conversation.delete_conversation", "DELETE FROM conversations WHERE id = ?", |sql| sqlx::query(sql)
+        .bind(id)
+        .execute(&mut *tx))
+        .await?;
+    tx.commit().await?;
+    Ok(res.rows_affected())
+}
+
+/// Append a message to a conversation, assigning the next sequence number (async)
+pub async fn append_conversation_message(pool: &SqlitePool, message: &ConversationMessage) -> Result<ConversationMessage> {
+    let mut tx = pool.begin().await?;
+    timed_query("conversation.append_conversation_message", r#"
+        INSERT INTO conversation_messages (id, conversation_id, seq, role, content, tokens, created_at)
+        SELECT ?1, ?2, COALESCE(MAX(seq), 0) + 1, ?3, ?4, ?5, datetime('now')
+        FROM conversation_messages WHERE conversation_id = ?2
+    "#, |sql| sqlx::query(sql)
+        .bind(&message.id)
+        .bind(&message.conversation_id)
+        .bind(&message.role)
+        .bind(&message.content)
+        .bind(message.tokens)
+        .execute(&mut *tx))
+        .await?;
+    timed_query("conversation.touch_conversation", "UPDATE conversations SET updated_at = datetime('now') WHERE id = ?", |sql| sqlx::query(sql)
+        .bind(&message.conversation_id)
+        .execute(&mut *tx))
+        .await?;
+    let stored = timed_query("conversation.get_conversation_message", "SELECT * FROM conversation_messages WHERE id = ?", |sql| sqlx::query_as::<_, ConversationMessage>(sql)
+        .bind(&message.id)
+        .fetch_one(&mut *tx))
+        .await?;
+    tx.commit().await?;
+    Ok(stored)
+}
+
+/// List messages of a conversation in order (async)
+pub async fn list_conversation_messages(pool: &SqlitePool, conversation_id: &str) -> Result<Vec<ConversationMessage>> {
+    let messages = timed_query("conversation.list_conversation_messages", "SELECT * FROM conversation_messages WHERE conversation_id = ? ORDER BY seq", |sql| sqlx::query_as::<_, ConversationMessage>(sql)
+        .bind(conversation_id)
+        .fetch_all(pool))
+        .await?;
+    Ok(messages)
+}
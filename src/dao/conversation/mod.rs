@@ -0,0 +1,12 @@
+mod conversation;
+
+pub use conversation::{
+    Conversation,
+    ConversationMessage,
+    create_conversation,
+    get_conversation_by_id,
+    list_conversations_by_project,
+    delete_conversation,
+    append_conversation_message,
+    list_conversation_messages
+};
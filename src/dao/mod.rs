@@ -17,19 +17,65 @@ pub mod provider;
 pub mod provider_key_pool;
 pub mod system_config;
 pub mod call_log;
+pub mod blocklist;
+pub mod tool_call_audit;
+pub mod usage_stats;
+pub mod consumer_usage;
+pub mod project;
+pub mod conversation;
+pub mod shadow_comparison;
+pub mod audit_log;
+pub mod seed;
+pub mod query_stats;
+pub mod pagination;
 
+use lazy_static::lazy_static;
+use sqlx::migrate::Migrator;
+use sqlx::Executor;
 use tokio::fs;
 
-/// 通过 SQLITE_POOL 获取数据库连接，并异步执行 SQL 脚本
+/// 内嵌的数据库迁移（`migrations` 目录），按版本号顺序执行，已执行的版本记录在 `_sqlx_migrations` 表中
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+lazy_static! {
+    // 同一进程内串行执行迁移，避免并发初始化时重复写入迁移记录
+    static ref MIGRATION_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::new(());
+}
+
+/// Run all pending embedded migrations (async)
+pub async fn run_migrations(pool: &SqlitePool) -> anyhow::Result<()> {
+    let _guard = MIGRATION_LOCK.lock().await;
+    MIGRATOR.run(pool).await?;
+    Ok(())
+}
+
+/// Get the latest applied migration version, None if no migration has run (async)
+pub async fn schema_version(pool: &SqlitePool) -> anyhow::Result<Option<i64>> {
+    let exists: Option<(String,)> = sqlx::query_as("SELECT name FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations'")
+        .fetch_optional(pool)
+        .await?;
+    if exists.is_none() {
+        return Ok(None);
+    }
+    let version: (Option<i64>,) = sqlx::query_as("SELECT MAX(version) FROM _sqlx_migrations WHERE success = 1")
+        .fetch_one(pool)
+        .await?;
+    Ok(version.0)
+}
+
+/// 通过 SQLITE_POOL 执行数据库迁移，再执行可选的附加 SQL 脚本
+///
+/// 脚本不存在时跳过；脚本整体交给 SQLite 执行，可以包含触发器等带分号的语句。
+/// 旧版本的 init.sql 只包含 IF NOT EXISTS 语句，继续配置也不会影响迁移后的表结构
 pub async fn init_db(sql_path: &str) -> anyhow::Result<()> {
-    let sql = fs::read_to_string(sql_path).await?;
     let pool = SQLITE_POOL.get().expect("SQLITE_POOL not initialized").clone();
-    // 支持多条 SQL 语句分号分割执行
-    for statement in sql.split(';') {
-        let stmt = statement.trim();
-        if !stmt.is_empty() {
-            sqlx::query(stmt).execute(&*pool).await?;
-        }
+    run_migrations(&pool).await?;
+    if !fs::try_exists(sql_path).await? {
+        return Ok(());
+    }
+    let sql = fs::read_to_string(sql_path).await?;
+    if !sql.trim().is_empty() {
+        pool.execute(sql.as_str()).await?;
     }
     Ok(())
-}
\ No newline at end of file
+}
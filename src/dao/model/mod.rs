@@ -1,8 +1,8 @@
 mod model;
-pub use model::{Model, create_model, list_models, update_model, delete_model, get_model_by_id, get_model_by_provider_and_name};
+pub use model::{Model, HEALTH_HEALTHY, HEALTH_UNHEALTHY, create_model, list_models, list_models_page, count_models_filtered, update_model, update_model_health, delete_model, get_model_by_id, get_model_by_provider_and_name, list_active_model_names_by_provider};
 
 mod preload;
-pub use preload::{preload_models_to_cache, get_model_from_cache, insert_model_to_cache};
+pub use preload::{MODEL_CACHE_NAMESPACE, PROVIDER_MODELS_CACHE_NAMESPACE, preload_models_to_cache, get_model_from_cache, insert_model_to_cache, get_model_id_from_cache, get_model_health_from_cache, get_model_project_from_cache, get_model_config_from_cache, sync_model_health_to_cache, load_model_cache_value, get_active_model_names_from_cache, invalidate_provider_models_cache, sync_model_cache, load_provider_models_cache_value, reconcile_model_cache, ModelCacheReconcileReport};
 
 
 
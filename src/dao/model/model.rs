@@ -1,6 +1,14 @@
 use sqlx::{SqlitePool, Result};
 use serde::{Serialize, Deserialize};
 
+use crate::dao::pagination::{into_page, Cursor, CursorKey, CursorPage};
+use crate::dao::query_stats::timed_query;
+
+/// 健康检查探测成功
+pub const HEALTH_HEALTHY: &str = "healthy";
+/// 健康检查连续失败，调度时跳过
+pub const HEALTH_UNHEALTHY: &str = "unhealthy";
+
 #[allow(dead_code)]
 #[derive(Debug, Clone, sqlx::FromRow, Serialize, Deserialize)]
 pub struct Model {
@@ -17,18 +25,25 @@ pub struct Model {
     pub cost_per_token_output: Option<f64>,
     pub function_tags: Option<String>,
     pub config: Option<String>,
+    pub project_id: Option<String>,     // 所属项目，创建时为空则归属 default 项目
     pub created_at: Option<String>,
     pub updated_at: Option<String>,
 }
 
+impl CursorKey for Model {
+	fn cursor(&self) -> Cursor {
+		Cursor { created_at: self.created_at.clone().unwrap_or_default(), id: self.id.clone() }
+	}
+}
+
 /// Create a new model (async)
 pub async fn create_model(pool: &SqlitePool, model: &Model) -> Result<u64> {
-	let res = sqlx::query(r#"
+	let res = timed_query("model.create_model", r#"
 		INSERT INTO models (
 			id, name, provider, model_type, base_url, is_active, health_status, last_health_check,
-			health_check_interval_seconds, cost_per_token_input, cost_per_token_output, function_tags, config, created_at, updated_at
-		) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, datetime('now'), datetime('now'))
-	"#)
+			health_check_interval_seconds, cost_per_token_input, cost_per_token_output, function_tags, config, project_id, created_at, updated_at
+		) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, COALESCE(?, 'default'), datetime('now'), datetime('now'))
+	"#, |sql| sqlx::query(sql)
 		.bind(&model.id)
 		.bind(&model.name)
 		.bind(&model.provider)
@@ -42,40 +57,81 @@ pub async fn create_model(pool: &SqlitePool, model: &Model) -> Result<u64> {
 		.bind(&model.cost_per_token_output)
 		.bind(&model.function_tags)
 		.bind(&model.config)
-		.execute(pool)
+		.bind(&model.project_id)
+		.execute(pool))
 		.await?;
 	Ok(res.rows_affected())
 }
 
 /// Read a model by id (async)
 pub async fn get_model_by_id(pool: &SqlitePool, id: &str) -> Result<Option<Model>> {
-	let model = sqlx::query_as::<_, Model>("SELECT * FROM models WHERE id = ?")
+	let model = timed_query("model.get_model_by_id", "SELECT * FROM models WHERE id = ?", |sql| sqlx::query_as::<_, Model>(sql)
 		.bind(id)
-		.fetch_optional(pool)
+		.fetch_optional(pool))
 		.await?;
 	Ok(model)
 }
 
 pub async fn get_model_by_provider_and_name(pool: &SqlitePool, provider: &str, name: &str) -> Result<Option<Model>> {
-    let model = sqlx::query_as::<_, Model>("SELECT * FROM models WHERE provider = ? AND name = ?")
+    let model = timed_query("model.get_model_by_provider_and_name", "SELECT * FROM models WHERE provider = ? AND name = ?", |sql| sqlx::query_as::<_, Model>(sql)
         .bind(provider)
         .bind(name)
-        .fetch_optional(pool)
+        .fetch_optional(pool))
         .await?;
     Ok(model)
 }
 
 /// List all models (async)
 pub async fn list_models(pool: &SqlitePool) -> Result<Vec<Model>> {
-	let models = sqlx::query_as::<_, Model>("SELECT * FROM models")
-		.fetch_all(pool)
+	let models = timed_query("model.list_models", "SELECT * FROM models", |sql| sqlx::query_as::<_, Model>(sql)
+		.fetch_all(pool))
 		.await?;
 	Ok(models)
 }
 
+/// List models newest first, one page after `cursor`, optionally filtered by provider, active flag and project (async)
+pub async fn list_models_page(pool: &SqlitePool, provider: Option<&str>, is_active: Option<bool>, project_id: Option<&str>, cursor: Option<&Cursor>, limit: i64) -> Result<CursorPage<Model>> {
+	let models = timed_query("model.list_models_page", r#"
+		SELECT * FROM models
+		WHERE (?1 IS NULL OR provider = ?1) AND (?2 IS NULL OR is_active = ?2) AND (?6 IS NULL OR project_id = ?6)
+			AND (?3 IS NULL OR (created_at, id) < (?3, ?4))
+		ORDER BY created_at DESC, id DESC
+		LIMIT ?5
+	"#, |sql| sqlx::query_as::<_, Model>(sql)
+		.bind(provider)
+		.bind(is_active)
+		.bind(cursor.map(|c| c.created_at.as_str()))
+		.bind(cursor.map(|c| c.id.as_str()))
+		.bind(limit + 1)
+		.bind(project_id)
+		.fetch_all(pool))
+		.await?;
+	Ok(into_page(models, limit))
+}
+
+/// Count models, optionally filtered by provider, active flag and project (async)
+pub async fn count_models_filtered(pool: &SqlitePool, provider: Option<&str>, is_active: Option<bool>, project_id: Option<&str>) -> Result<i64> {
+	let count: (i64,) = timed_query("model.count_models_filtered", "SELECT COUNT(*) FROM models WHERE (?1 IS NULL OR provider = ?1) AND (?2 IS NULL OR is_active = ?2) AND (?3 IS NULL OR project_id = ?3)", |sql| sqlx::query_as(sql)
+		.bind(provider)
+		.bind(is_active)
+		.bind(project_id)
+		.fetch_one(pool))
+		.await?;
+	Ok(count.0)
+}
+
+/// List active model names of a provider, sorted by name (async)
+pub async fn list_active_model_names_by_provider(pool: &SqlitePool, provider: &str) -> Result<Vec<String>> {
+	let names = timed_query("model.list_active_model_names_by_provider", "SELECT name FROM models WHERE provider = ? AND is_active = 1 ORDER BY name", |sql| sqlx::query_scalar::<_, String>(sql)
+		.bind(provider)
+		.fetch_all(pool))
+		.await?;
+	Ok(names)
+}
+
 /// Update a model by id (async)
 pub async fn update_model(pool: &SqlitePool, model: &Model) -> Result<u64> {
-	let res = sqlx::query(r#"
+	let res = timed_query("model.update_model", r#"
 		UPDATE models SET
 			name = ?,
 			provider = ?,
@@ -89,9 +145,10 @@ pub async fn update_model(pool: &SqlitePool, model: &Model) -> Result<u64> {
 			cost_per_token_output = ?,
 			function_tags = ?,
 			config = ?,
+			project_id = COALESCE(?, project_id),
 			updated_at = datetime('now')
 		WHERE id = ?
-	"#)
+	"#, |sql| sqlx::query(sql)
 		.bind(&model.name)
 		.bind(&model.provider)
 		.bind(&model.model_type)
@@ -104,17 +161,28 @@ pub async fn update_model(pool: &SqlitePool, model: &Model) -> Result<u64> {
 		.bind(&model.cost_per_token_output)
 		.bind(&model.function_tags)
 		.bind(&model.config)
+		.bind(&model.project_id)
 		.bind(&model.id)
-		.execute(pool)
+		.execute(pool))
+		.await?;
+	Ok(res.rows_affected())
+}
+
+/// Update health status and last health check time of a model (async)
+pub async fn update_model_health(pool: &SqlitePool, id: &str, health_status: &str) -> Result<u64> {
+	let res = timed_query("model.update_model_health", "UPDATE models SET health_status = ?, last_health_check = datetime('now') WHERE id = ?", |sql| sqlx::query(sql)
+		.bind(health_status)
+		.bind(id)
+		.execute(pool))
 		.await?;
 	Ok(res.rows_affected())
 }
 
 /// Delete a model by id (async)
 pub async fn delete_model(pool: &SqlitePool, id: &str) -> Result<u64> {
-	let res = sqlx::query("DELETE FROM models WHERE id = ?")
+	let res = timed_query("model.delete_model", "DELETE FROM models WHERE id = ?", |sql| sqlx::query(sql)
 		.bind(id)
-		.execute(pool)
+		.execute(pool))
 		.await?;
 	Ok(res.rows_affected())
 }
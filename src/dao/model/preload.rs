@@ -1,8 +1,30 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use serde::Serialize;
 use sqlx::SqlitePool;
-use crate::dao::model::{list_models, Model};
-use crate::dao::cache::get_global_cache;
+use crate::dao::model::{list_models, get_model_by_provider_and_name, list_active_model_names_by_provider, count_models_filtered, Model};
+use crate::dao::cache::{get_global_cache, GLOBAL_CACHE, cache::CacheService, namespace::CacheNamespace};
+use crate::dao::SQLITE_POOL;
 use anyhow::Result;
-use tracing::{info, error, debug, warn};
+use tracing::{info, debug, warn};
+
+/// 模型条目的缓存命名空间，key 为 `{provider}:{name}`
+pub const MODEL_CACHE_NAMESPACE: &str = "model";
+
+/// 供应商启用模型名称索引的缓存命名空间，key 为供应商名；
+/// 数据库中没有该供应商任何模型时缓存 null，与有模型但全部停用（空列表）区分
+pub const PROVIDER_MODELS_CACHE_NAMESPACE: &str = "models";
+
+fn model_cache(cache: &CacheService<String, String>) -> CacheNamespace<Model> {
+    cache.namespace(MODEL_CACHE_NAMESPACE)
+}
+
+fn provider_models_cache(cache: &CacheService<String, String>) -> CacheNamespace<Option<Vec<String>>> {
+    cache.namespace(PROVIDER_MODELS_CACHE_NAMESPACE)
+}
+
+fn model_cache_key(provider: &str, name: &str) -> String {
+    format!("{}:{}", provider, name)
+}
 /// 从数据库预加载所有模型数据到全局缓存
 pub async fn preload_models_to_cache(pool: &SqlitePool) -> anyhow::Result<()> {
     info!("Starting to preload models to cache");
@@ -13,26 +35,28 @@ pub async fn preload_models_to_cache(pool: &SqlitePool) -> anyhow::Result<()> {
     
     info!(model_count = models.len(), "Loaded models from database");
     
-    // 2. 获取全局缓存实例
+    // 2. 获取全局缓存中的模型命名空间
     let cache = get_global_cache();
+    let model_cache = model_cache(&cache);
     
-    // 3. 将每个模型数据加载到缓存中
+    // 3. 按供应商建立启用模型名称索引，模型全部停用的供应商索引为空列表
+    let active_names = active_names_by_provider(&models);
+    let provider_models_cache = provider_models_cache(&cache);
+    for (provider, names) in active_names {
+        provider_models_cache.insert(&provider, &Some(names)).await?;
+    }
+
+    // 4. 将每个模型数据加载到缓存中
     for model in models {
-        // 使用模型ID作为缓存key
-        let cache_key = format!("model:{}:{}", model.provider, model.name);
-        
-        // 将模型序列化为JSON字符串作为缓存值
-        let cache_value = serde_json::to_string(&model)
+        let cache_key = model_cache_key(&model.provider, &model.name);
+        model_cache.insert(&cache_key, &model).await
             .map_err(|e| anyhow::anyhow!("Failed to serialize model {}: {}", model.id, e))?;
         
-        // 插入到缓存
-        cache.insert(cache_key.clone(), cache_value).await;
-        
         debug!(
             model_name = %model.name,
             model_id = %model.id,
             provider = %model.provider,
-            cache_key = %cache_key,
+            cache_key = %model_cache.cache_key(&cache_key),
             "Cached model successfully"
         );
     }
@@ -41,35 +65,195 @@ pub async fn preload_models_to_cache(pool: &SqlitePool) -> anyhow::Result<()> {
     Ok(())
 }
 
+// 数据库中有模型的每个供应商的启用模型名称（按名称排序），模型全部停用时为空列表
+fn active_names_by_provider(models: &[Model]) -> BTreeMap<String, Vec<String>> {
+    let mut active_names: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for model in models {
+        let names = active_names.entry(model.provider.clone()).or_default();
+        if model.is_active {
+            names.push(model.name.clone());
+        }
+    }
+    for names in active_names.values_mut() {
+        names.sort();
+    }
+    active_names
+}
+
 /// 从缓存中获取模型（通过 provider 和 name）
 pub async fn get_model_from_cache(provider: &str, name: &str) -> Option<Model> {
-    let cache = get_global_cache();
-    let cache_key = format!("model:{}:{}", provider, name);
+    model_cache(&get_global_cache()).get(&model_cache_key(provider, name)).await
+}
 
-    // 尝试从缓存获取，如果不存在则返回None
-    let cached_value = cache.get(&cache_key).await?;
-    
-    // 反序列化JSON字符串为模型对象
-    match serde_json::from_str::<Model>(&cached_value) {
-        Ok(model) => Some(model),
+/// 将Model插入到缓存
+pub async fn insert_model_to_cache(model: &Model) -> Result<()> {
+    model_cache(&get_global_cache()).insert(&model_cache_key(&model.provider, &model.name), model).await
+}
+
+/// 获取缓存中模型的数据库 ID；缓存未初始化或模型未缓存（不在模型目录中）时返回 None
+pub async fn get_model_id_from_cache(provider: &str, name: &str) -> Option<String> {
+    let cache = GLOBAL_CACHE.get()?;
+    Some(model_cache(cache).get(&model_cache_key(provider, name)).await?.id)
+}
+
+/// 获取缓存中模型的健康状态；缓存未初始化、模型未缓存或未做过健康检查时返回 None
+pub async fn get_model_health_from_cache(provider: &str, name: &str) -> Option<String> {
+    let cache = GLOBAL_CACHE.get()?;
+    model_cache(cache).get(&model_cache_key(provider, name)).await?.health_status
+}
+
+/// 获取缓存中模型所属的项目；缓存未初始化、模型未缓存或属于 default 项目（未记录项目）时返回 None
+pub async fn get_model_project_from_cache(provider: &str, name: &str) -> Option<String> {
+    let cache = GLOBAL_CACHE.get()?;
+    model_cache(cache).get(&model_cache_key(provider, name)).await?.project_id
+}
+
+/// 获取缓存中模型的额外配置（models.config）；缓存未初始化、模型未缓存或未配置时返回 None
+pub async fn get_model_config_from_cache(provider: &str, name: &str) -> Option<String> {
+    let cache = GLOBAL_CACHE.get()?;
+    model_cache(cache).get(&model_cache_key(provider, name)).await?.config
+}
+
+/// 健康检查更新状态后同步缓存中的模型，缓存未初始化时不处理
+pub async fn sync_model_health_to_cache(model: &Model, health_status: &str) -> Result<()> {
+    if GLOBAL_CACHE.get().is_none() {
+        return Ok(());
+    }
+    let model = Model {
+        health_status: Some(health_status.to_string()),
+        ..model.clone()
+    };
+    insert_model_to_cache(&model).await
+}
+
+/// 从数据库重新加载单个模型的缓存值，模型已删除时返回 None
+pub async fn load_model_cache_value(pool: &SqlitePool, provider: &str, name: &str) -> Result<Option<Model>> {
+    Ok(get_model_by_provider_and_name(pool, provider, name).await?)
+}
+
+/// 获取供应商的启用模型名称，缓存未命中时从数据库加载；
+/// 数据库中没有该供应商的任何模型、缓存或数据库未初始化时返回 None，有模型但全部停用时返回空列表
+pub async fn get_active_model_names_from_cache(provider: &str) -> Option<Vec<String>> {
+    let cache = provider_models_cache(GLOBAL_CACHE.get()?);
+    if let Some(names) = cache.get(provider).await {
+        return names;
+    }
+
+    let pool = SQLITE_POOL.get()?;
+    match load_provider_models_cache_value(pool, provider).await {
+        Ok(names) => {
+            if let Err(e) = cache.insert(provider, &names).await {
+                warn!(provider = %provider, error = %e, "Failed to cache provider models");
+            }
+            names
+        }
         Err(e) => {
-            error!(
-                cache_key = %cache_key,
-                error = %e,
-                "Failed to deserialize cached model"
-            );
+            warn!(provider = %provider, error = %e, "Failed to load provider models");
             None
         }
     }
 }
 
-/// 将Model插入到缓存
-pub async fn insert_model_to_cache(model: &Model) -> Result<()> {
-    let cache = get_global_cache();
-    let cache_key = format!("model:{}:{}", model.provider, model.name);
-    
-    let cache_value = serde_json::to_string(model)?;
-    cache.insert(cache_key, cache_value).await;
-    
-    Ok(())
-}
\ No newline at end of file
+/// 模型新增、修改或删除后使供应商的模型名称索引失效
+pub async fn invalidate_provider_models_cache(provider: &str) {
+    if let Some(cache) = GLOBAL_CACHE.get() {
+        provider_models_cache(cache).invalidate(provider).await;
+    }
+}
+
+/// 模型新增、修改或删除后同步缓存：按数据库重新写入（已删除时移除）模型条目，并使供应商的模型名称索引失效
+pub async fn sync_model_cache(pool: &SqlitePool, provider: &str, name: &str) {
+    let Some(cache) = GLOBAL_CACHE.get() else {
+        return;
+    };
+    let model_cache = model_cache(cache);
+    let cache_key = model_cache_key(provider, name);
+    let result = match load_model_cache_value(pool, provider, name).await {
+        Ok(Some(model)) => model_cache.insert(&cache_key, &model).await,
+        Ok(None) => {
+            model_cache.invalidate(&cache_key).await;
+            Ok(())
+        }
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        warn!(cache_key = %model_cache.cache_key(&cache_key), error = %e, "Failed to reload model, invalidating cache entry");
+        model_cache.invalidate(&cache_key).await;
+    }
+    invalidate_provider_models_cache(provider).await;
+}
+
+/// 从数据库重新加载供应商的启用模型名称索引，数据库中没有该供应商的任何模型时返回 None
+pub async fn load_provider_models_cache_value(pool: &SqlitePool, provider: &str) -> Result<Option<Vec<String>>> {
+    let names = list_active_model_names_by_provider(pool, provider).await?;
+    if names.is_empty() && count_models_filtered(pool, Some(provider), None, None).await? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(names))
+}
+
+/// 模型缓存与数据库对账的结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ModelCacheReconcileReport {
+    /// 数据库中的模型数
+    pub checked: usize,
+    /// 缓存中缺失或与数据库不一致、重新写入的模型数
+    pub refreshed: usize,
+    /// 数据库中已不存在、从缓存删除的模型数
+    pub evicted: usize,
+    /// 重新写入的供应商模型名称索引数
+    pub refreshed_indexes: usize,
+}
+
+impl ModelCacheReconcileReport {
+    /// 缓存是否与数据库一致（无需修正）
+    pub fn is_consistent(&self) -> bool {
+        self.refreshed == 0 && self.evicted == 0 && self.refreshed_indexes == 0
+    }
+}
+
+/// 按数据库对账模型缓存：写入缺失或不一致的模型，删除数据库中已不存在的模型，并修正供应商的模型名称索引；
+/// 用于兜底绕过管理接口的修改（例如直接改库），缓存未初始化时返回空报告
+pub async fn reconcile_model_cache(pool: &SqlitePool) -> Result<ModelCacheReconcileReport> {
+    let Some(cache) = GLOBAL_CACHE.get() else {
+        return Ok(ModelCacheReconcileReport::default());
+    };
+    let models = list_models(pool).await?;
+    let model_cache = model_cache(cache);
+    let mut report = ModelCacheReconcileReport { checked: models.len(), ..Default::default() };
+
+    let mut model_keys = HashSet::new();
+    for model in &models {
+        let cache_key = model_cache_key(&model.provider, &model.name);
+        let stale = match model_cache.peek(&cache_key).await {
+            Some(cached) => serde_json::to_value(&cached)? != serde_json::to_value(model)?,
+            None => true,
+        };
+        if stale {
+            model_cache.insert(&cache_key, model).await?;
+            report.refreshed += 1;
+        }
+        model_keys.insert(cache_key);
+    }
+
+    for cache_key in model_cache.keys() {
+        if !model_keys.contains(&cache_key) {
+            model_cache.invalidate(&cache_key).await;
+            report.evicted += 1;
+        }
+    }
+
+    // 只修正已缓存的索引和数据库中有模型的供应商，其余索引在读取时按需加载
+    let mut active_names = active_names_by_provider(&models);
+    let provider_models_cache = provider_models_cache(cache);
+    let providers: BTreeSet<String> = provider_models_cache.keys().into_iter().chain(active_names.keys().cloned()).collect();
+    for provider in providers {
+        let names = active_names.remove(&provider);
+        if provider_models_cache.peek(&provider).await.as_ref() != Some(&names) {
+            provider_models_cache.insert(&provider, &names).await?;
+            report.refreshed_indexes += 1;
+        }
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,92 @@
+//! # 游标分页
+//!
+//! 列表按 `(created_at, id)` 倒序排列，游标记录上一页最后一行的排序键，下一页从它之后继续。
+//! 翻页期间写入新数据时不会出现重复或遗漏，深翻页也不需要扫描被跳过的行
+
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+
+/// 默认每页条数
+pub const DEFAULT_PAGE_LIMIT: i64 = 100;
+/// 每页条数上限
+pub const MAX_PAGE_LIMIT: i64 = 1000;
+
+/// 分页游标：上一页最后一行的排序键
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor {
+    pub created_at: String,
+    pub id: String,
+}
+
+impl Cursor {
+    /// 编码为对外使用的不透明字符串
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{}\n{}", self.created_at, self.id))
+    }
+
+    /// 解析 [`Cursor::encode`] 生成的字符串，格式不正确时返回 None
+    pub fn decode(value: &str) -> Option<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(value).ok()?;
+        let text = String::from_utf8(bytes).ok()?;
+        let (created_at, id) = text.split_once('\n')?;
+        Some(Self {
+            created_at: created_at.to_string(),
+            id: id.to_string(),
+        })
+    }
+}
+
+/// 可作为游标分页的行
+pub trait CursorKey {
+    fn cursor(&self) -> Cursor;
+}
+
+/// 一页数据，`next_cursor` 为 None 表示没有下一页
+#[derive(Debug, Clone)]
+pub struct CursorPage<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<Cursor>,
+}
+
+/// 把按 `limit + 1` 查询到的行截断为一页，多出的一行表示还有下一页
+pub fn into_page<T: CursorKey>(mut rows: Vec<T>, limit: i64) -> CursorPage<T> {
+    let limit = limit.max(0) as usize;
+    let has_more = rows.len() > limit;
+    rows.truncate(limit);
+    let next_cursor = if has_more { rows.last().map(CursorKey::cursor) } else { None };
+    CursorPage { items: rows, next_cursor }
+}
+
+/// 每页条数，未指定时使用默认值，并限制在 1 到 [`MAX_PAGE_LIMIT`] 之间
+pub fn clamp_limit(limit: Option<i64>) -> i64 {
+    limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_PAGE_LIMIT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Row(&'static str, &'static str);
+
+    impl CursorKey for Row {
+        fn cursor(&self) -> Cursor {
+            Cursor { created_at: self.0.to_string(), id: self.1.to_string() }
+        }
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = Cursor { created_at: "2025-03-01 10:00:00".to_string(), id: "abc".to_string() };
+        assert_eq!(Cursor::decode(&cursor.encode()), Some(cursor));
+        assert_eq!(Cursor::decode("not a cursor!"), None);
+    }
+
+    #[test]
+    fn test_into_page() {
+        let page = into_page(vec![Row("3", "c"), Row("2", "b"), Row("1", "a")], 2);
+        assert_eq!(page.items.len(), 2);
+        assert_eq!(page.next_cursor, Some(Cursor { created_at: "2".to_string(), id: "b".to_string() }));
+
+        let page = into_page(vec![Row("1", "a")], 2);
+        assert_eq!(page.next_cursor, None);
+    }
+}
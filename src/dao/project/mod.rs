@@ -0,0 +1,17 @@
+mod project;
+
+pub use project::{
+    Project,
+    ProjectGatewayKey,
+    DEFAULT_PROJECT,
+    create_project,
+    get_project_by_id,
+    list_projects,
+    update_project,
+    delete_project,
+    count_project_resources,
+    bind_gateway_key,
+    unbind_gateway_key,
+    list_gateway_keys_by_project,
+    list_gateway_key_bindings
+};
@@ -0,0 +1,141 @@
+use sqlx::{SqlitePool, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::dao::query_stats::timed_query;
+
+/// 默认项目，未指定项目的资源和未绑定项目的网关 Key 都归属该项目
+pub const DEFAULT_PROJECT: &str = "default";
+
+#[derive(Debug, Clone, sqlx::FromRow, Serialize, Deserialize)]
+pub struct Project {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub is_active: bool,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+/// 网关 Key（调用方 Key 指纹）与项目的绑定
+#[derive(Debug, Clone, sqlx::FromRow, Serialize, Deserialize)]
+pub struct ProjectGatewayKey {
+    pub consumer_id: String,
+    pub project_id: String,
+    pub created_at: Option<String>,
+}
+
+/// Create a new project (async)
+pub async fn create_project(pool: &SqlitePool, project: &Project) -> Result<u64> {
+    let res = timed_query("project.create_project", r#"
+        INSERT INTO projects (id, name, description, is_active, created_at, updated_at)
+        VALUES (?, ?, ?, ?, datetime('now'), datetime('now'))
+    "#, |sql| sqlx::query(sql)
+        .bind(&project.id)
+        .bind(&project.name)
+        .bind(&project.description)
+        .bind(project.is_active)
+        .execute(pool))
+        .await?;
+    Ok(res.rows_affected())
+}
+
+/// Read a project by id (async)
+pub async fn get_project_by_id(pool: &SqlitePool, id: &str) -> Result<Option<Project>> {
+    let project = timed_query("project.get_project_by_id", "SELECT * FROM projects WHERE id = ?", |sql| sqlx::query_as::<_, Project>(sql)
+        .bind(id)
+        .fetch_optional(pool))
+        .await?;
+    Ok(project)
+}
+
+/// List all projects, default project first (async)
+pub async fn list_projects(pool: &SqlitePool) -> Result<Vec<Project>> {
+    let projects = timed_query("project.list_projects", "SELECT * FROM projects ORDER BY id != 'default', created_at, id", |sql| sqlx::query_as::<_, Project>(sql)
+        .fetch_all(pool))
+        .await?;
+    Ok(projects)
+}
+
+/// Update a project by id (async)
+pub async fn update_project(pool: &SqlitePool, project: &Project) -> Result<u64> {
+    let res = timed_query("project.update_project", r#"
+        UPDATE projects SET name = ?, description = ?, is_active = ?, updated_at = datetime('now')
+        WHERE id = ?
+    "#, |sql| sqlx::query(sql)
+        .bind(&project.name)
+        .bind(&project.description)
+        .bind(project.is_active)
+        .bind(&project.id)
+        .execute(pool))
+        .await?;
+    Ok(res.rows_affected())
+}
+
+/// Delete a project and its gateway key bindings (async)
+pub async fn delete_project(pool: &SqlitePool, id: &str) -> Result<u64> {
+    let mut tx = pool.begin().await?;
+    timed_query("project.delete_project_gateway_keys", "DELETE FROM project_gateway_keys WHERE project_id = ?", |sql| sqlx::query(sql)
+        .bind(id)
+        .execute(&mut *tx))
+        .await?;
+    let res = timed_query("project.delete_project", "DELETE FROM projects WHERE id = ?", |sql| sqlx::query(sql)
+        .bind(id)
+        .execute(&mut *tx))
+        .await?;
+    tx.commit().await?;
+    Ok(res.rows_affected())
+}
+
+/// Count providers, models and provider keys owned by a project (async)
+pub async fn count_project_resources(pool: &SqlitePool, id: &str) -> Result<i64> {
+    let count: (i64,) = timed_query("project.count_project_resources", r#"
+        SELECT (SELECT COUNT(*) FROM providers WHERE project_id = ?1)
+             + (SELECT COUNT(*) FROM models WHERE project_id = ?1)
+             + (SELECT COUNT(*) FROM provider_key_pools WHERE project_id = ?1)
+    "#, |sql| sqlx::query_as(sql)
+        .bind(id)
+        .fetch_one(pool))
+        .await?;
+    Ok(count.0)
+}
+
+/// Bind a gateway key to a project, replacing any existing binding (async)
+pub async fn bind_gateway_key(pool: &SqlitePool, consumer_id: &str, project_id: &str) -> Result<u64> {
+    let res = timed_query("project.bind_gateway_key", r#"
+        INSERT INTO project_gateway_keys (consumer_id, project_id, created_at)
+        VALUES (?, ?, datetime('now'))
+        ON CONFLICT(consumer_id) DO UPDATE SET project_id = excluded.project_id, created_at = excluded.created_at
+    "#, |sql| sqlx::query(sql)
+        .bind(consumer_id)
+        .bind(project_id)
+        .execute(pool))
+        .await?;
+    Ok(res.rows_affected())
+}
+
+/// Remove a gateway key's binding from a project (async)
+pub async fn unbind_gateway_key(pool: &SqlitePool, project_id: &str, consumer_id: &str) -> Result<u64> {
+    let res = timed_query("project.unbind_gateway_key", "DELETE FROM project_gateway_keys WHERE project_id = ? AND consumer_id = ?", |sql| sqlx::query(sql)
+        .bind(project_id)
+        .bind(consumer_id)
+        .execute(pool))
+        .await?;
+    Ok(res.rows_affected())
+}
+
+/// List gateway keys bound to a project (async)
+pub async fn list_gateway_keys_by_project(pool: &SqlitePool, project_id: &str) -> Result<Vec<ProjectGatewayKey>> {
+    let keys = timed_query("project.list_gateway_keys_by_project", "SELECT * FROM project_gateway_keys WHERE project_id = ? ORDER BY consumer_id", |sql| sqlx::query_as::<_, ProjectGatewayKey>(sql)
+        .bind(project_id)
+        .fetch_all(pool))
+        .await?;
+    Ok(keys)
+}
+
+/// List all gateway key bindings (async)
+pub async fn list_gateway_key_bindings(pool: &SqlitePool) -> Result<Vec<ProjectGatewayKey>> {
+    let keys = timed_query("project.list_gateway_key_bindings", "SELECT * FROM project_gateway_keys", |sql| sqlx::query_as::<_, ProjectGatewayKey>(sql)
+        .fetch_all(pool))
+        .await?;
+    Ok(keys)
+}
@@ -1,6 +1,9 @@
 use sqlx::{SqlitePool, Result};
 use serde::{Deserialize, Serialize};
 
+use crate::dao::pagination::{into_page, Cursor, CursorKey, CursorPage};
+use crate::dao::query_stats::timed_query;
+
 #[derive(Debug, Clone, sqlx::FromRow, Serialize, Deserialize)]
 pub struct Provider {
     pub id: String,
@@ -8,77 +11,121 @@ pub struct Provider {
     pub display_name: String,
     pub base_url: Option<String>,
     pub description: Option<String>,
+    pub config: Option<String>,         // 供应商专属配置(JSON)
     pub is_active: bool,
+    pub project_id: Option<String>,     // 所属项目，创建时为空则归属 default 项目
+    pub max_concurrent_requests: Option<i64>, // 最大并发请求数，为空表示不限制
     pub created_at: Option<String>,
     pub updated_at: Option<String>,
 }
 
+impl CursorKey for Provider {
+    fn cursor(&self) -> Cursor {
+        Cursor { created_at: self.created_at.clone().unwrap_or_default(), id: self.id.clone() }
+    }
+}
+
 /// Create a new provider
 pub async fn create_provider(pool: &SqlitePool, provider: &Provider) -> Result<u64> {
-    let res = sqlx::query(r#"
+    let res = timed_query("provider.create_provider", r#"
         INSERT INTO providers (
-            id, name, display_name, base_url, description, is_active, created_at, updated_at
-        ) VALUES (?, ?, ?, ?, ?, ?, datetime('now'), datetime('now'))
-    "#)
+            id, name, display_name, base_url, description, config, is_active, project_id, max_concurrent_requests, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, COALESCE(?, 'default'), ?, datetime('now'), datetime('now'))
+    "#, |sql| sqlx::query(sql)
         .bind(&provider.id)
         .bind(&provider.name)
         .bind(&provider.display_name)
         .bind(&provider.base_url)
         .bind(&provider.description)
+        .bind(&provider.config)
         .bind(provider.is_active)
-        .execute(pool)
+        .bind(&provider.project_id)
+        .bind(provider.max_concurrent_requests)
+        .execute(pool))
         .await?;
     Ok(res.rows_affected())
 }
 
 /// Get provider by id
 pub async fn get_provider_by_id(pool: &SqlitePool, id: &str) -> Result<Option<Provider>> {
-    let provider = sqlx::query_as::<_, Provider>("SELECT * FROM providers WHERE id = ?")
+    let provider = timed_query("provider.get_provider_by_id", "SELECT * FROM providers WHERE id = ?", |sql| sqlx::query_as::<_, Provider>(sql)
         .bind(id)
-        .fetch_optional(pool)
+        .fetch_optional(pool))
         .await?;
     Ok(provider)
 }
 
 /// Get provider by name
 pub async fn get_provider_by_name(pool: &SqlitePool, name: &str) -> Result<Option<Provider>> {
-    let provider = sqlx::query_as::<_, Provider>("SELECT * FROM providers WHERE name = ?")
+    let provider = timed_query("provider.get_provider_by_name", "SELECT * FROM providers WHERE name = ?", |sql| sqlx::query_as::<_, Provider>(sql)
         .bind(name)
-        .fetch_optional(pool)
+        .fetch_optional(pool))
         .await?;
     Ok(provider)
 }
 
 /// Get all providers
 pub async fn get_all_providers(pool: &SqlitePool) -> Result<Vec<Provider>> {
-    let providers = sqlx::query_as::<_, Provider>("SELECT * FROM providers ORDER BY created_at DESC")
-        .fetch_all(pool)
+    let providers = timed_query("provider.get_all_providers", "SELECT * FROM providers ORDER BY created_at DESC", |sql| sqlx::query_as::<_, Provider>(sql)
+        .fetch_all(pool))
         .await?;
     Ok(providers)
 }
 
+/// List providers newest first, one page after `cursor`, optionally filtered by active flag and project
+pub async fn list_providers_page(pool: &SqlitePool, is_active: Option<bool>, project_id: Option<&str>, cursor: Option<&Cursor>, limit: i64) -> Result<CursorPage<Provider>> {
+    let providers = timed_query("provider.list_providers_page", r#"
+        SELECT * FROM providers
+        WHERE (?1 IS NULL OR is_active = ?1) AND (?2 IS NULL OR project_id = ?2) AND (?3 IS NULL OR (created_at, id) < (?3, ?4))
+        ORDER BY created_at DESC, id DESC
+        LIMIT ?5
+    "#, |sql| sqlx::query_as::<_, Provider>(sql)
+        .bind(is_active)
+        .bind(project_id)
+        .bind(cursor.map(|c| c.created_at.as_str()))
+        .bind(cursor.map(|c| c.id.as_str()))
+        .bind(limit + 1)
+        .fetch_all(pool))
+        .await?;
+    Ok(into_page(providers, limit))
+}
+
+/// Count providers, optionally filtered by active flag and project
+pub async fn count_providers(pool: &SqlitePool, is_active: Option<bool>, project_id: Option<&str>) -> Result<i64> {
+    let count: (i64,) = timed_query("provider.count_providers", "SELECT COUNT(*) FROM providers WHERE (?1 IS NULL OR is_active = ?1) AND (?2 IS NULL OR project_id = ?2)", |sql| sqlx::query_as(sql)
+        .bind(is_active)
+        .bind(project_id)
+        .fetch_one(pool))
+        .await?;
+    Ok(count.0)
+}
+
 /// Update provider
 pub async fn update_provider(pool: &SqlitePool, id: &str, provider: &Provider) -> Result<u64> {
-    let res = sqlx::query(r#"
+    let res = timed_query("provider.update_provider", r#"
         UPDATE providers 
-        SET display_name = ?, base_url = ?, description = ?, is_active = ?, updated_at = datetime('now')
+        SET display_name = ?, base_url = ?, description = ?, config = ?, is_active = ?,
+            project_id = COALESCE(?, project_id), max_concurrent_requests = ?, updated_at = datetime('now')
         WHERE id = ?
-    "#)
+    "#, |sql| sqlx::query(sql)
         .bind(&provider.display_name)
         .bind(&provider.base_url)
         .bind(&provider.description)
+        .bind(&provider.config)
         .bind(provider.is_active)
+        .bind(&provider.project_id)
+        .bind(provider.max_concurrent_requests)
         .bind(id)
-        .execute(pool)
+        .execute(pool))
         .await?;
     Ok(res.rows_affected())
 }
 
 /// Delete provider (soft delete by setting is_active = false)
 pub async fn delete_provider(pool: &SqlitePool, id: &str) -> Result<u64> {
-    let res = sqlx::query("UPDATE providers SET is_active = 0, updated_at = datetime('now') WHERE id = ?")
+    let res = timed_query("provider.delete_provider", "UPDATE providers SET is_active = 0, updated_at = datetime('now') WHERE id = ?", |sql| sqlx::query(sql)
         .bind(id)
-        .execute(pool)
+        .execute(pool))
         .await?;
     Ok(res.rows_affected())
 }
@@ -92,18 +139,18 @@ pub async fn hard_delete_provider(pool: &SqlitePool, id: &str) -> Result<u64> {
     }
     
     // If no models, proceed with deletion
-    let res = sqlx::query("DELETE FROM providers WHERE id = ?")
+    let res = timed_query("provider.hard_delete_provider", "DELETE FROM providers WHERE id = ?", |sql| sqlx::query(sql)
         .bind(id)
-        .execute(pool)
+        .execute(pool))
         .await?;
     Ok(res.rows_affected())
 }
 
 /// Count models for provider
 pub async fn count_models_for_provider(pool: &SqlitePool, provider_id: &str) -> Result<i64> {
-    let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM models WHERE provider = ? AND is_active = 1")
+    let count: (i64,) = timed_query("provider.count_models_for_provider", "SELECT COUNT(*) FROM models WHERE provider = ? AND is_active = 1", |sql| sqlx::query_as(sql)
         .bind(provider_id)
-        .fetch_one(pool)
+        .fetch_one(pool))
         .await?;
     Ok(count.0)
 }
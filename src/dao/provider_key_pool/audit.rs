@@ -0,0 +1,152 @@
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tracing::{info, warn};
+
+use crate::dao::cache::GLOBAL_CACHE;
+use crate::dao::provider_key_pool::{
+    list_provider_key_pools,
+    toggle_provider_key_pool_active,
+    crypto::{decrypt_api_key, verify_key_integrity, AuthenticationFailed},
+    preload::{reload_provider_api_keys, key_pool_cache, key_pool_cache_key},
+};
+
+/// 单个 API Key 的完整性问题
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyIntegrityFailure {
+    pub key_pool_id: String,
+    pub provider: String,
+    pub is_active: bool,
+    /// 问题类型：decrypt_failed（认证解密失败）、hash_mismatch 或 unverifiable
+    /// （主密钥不存在、密钥环不可用或密文格式错误，无法判断 Key 是否损坏）
+    pub issue: String,
+    pub detail: Option<String>,
+    /// 是否已被审计停用
+    pub deactivated: bool,
+}
+
+/// Key Pool 完整性审计报告
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyIntegrityReport {
+    pub total_keys: usize,
+    pub healthy_keys: usize,
+    pub decrypt_failures: usize,
+    pub hash_mismatches: usize,
+    /// 无法校验的 Key 数量，只报告不停用
+    pub unverifiable_keys: usize,
+    /// 被审计停用的 Key 数量
+    pub deactivated_keys: usize,
+    pub failures: Vec<KeyIntegrityFailure>,
+}
+
+impl KeyIntegrityReport {
+    /// 是否所有 Key 均通过校验
+    pub fn is_healthy(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl KeyIntegrityFailure {
+    /// 确认已损坏（认证解密失败或哈希不一致），可以停用
+    pub fn is_corrupted(&self) -> bool {
+        matches!(self.issue.as_str(), "decrypt_failed" | "hash_mismatch")
+    }
+}
+
+/// 对所有已存储的 API Key 执行完整性校验：
+/// 密文能否解密，以及解密结果是否与存储的哈希一致
+pub async fn audit_key_pool_integrity(pool: &SqlitePool) -> sqlx::Result<KeyIntegrityReport> {
+    let key_pools = list_provider_key_pools(pool).await?;
+
+    let mut report = KeyIntegrityReport {
+        total_keys: key_pools.len(),
+        healthy_keys: 0,
+        decrypt_failures: 0,
+        hash_mismatches: 0,
+        unverifiable_keys: 0,
+        deactivated_keys: 0,
+        failures: Vec::new(),
+    };
+
+    for key_pool in key_pools {
+        let failure = match decrypt_api_key(&key_pool.encrypted_key_value) {
+            Ok(decrypted) if verify_key_integrity(&decrypted, &key_pool.key_hash) => None,
+            Ok(_) => {
+                report.hash_mismatches += 1;
+                Some(("hash_mismatch", None))
+            }
+            Err(e) if e.is::<AuthenticationFailed>() => {
+                report.decrypt_failures += 1;
+                Some(("decrypt_failed", Some(e.to_string())))
+            }
+            Err(e) => {
+                report.unverifiable_keys += 1;
+                Some(("unverifiable", Some(e.to_string())))
+            }
+        };
+
+        match failure {
+            None => report.healthy_keys += 1,
+            Some((issue, detail)) => {
+                warn!(
+                    key_pool_id = %key_pool.id,
+                    provider = %key_pool.provider,
+                    issue = issue,
+                    "API key failed integrity check"
+                );
+                report.failures.push(KeyIntegrityFailure {
+                    key_pool_id: key_pool.id,
+                    provider: key_pool.provider,
+                    is_active: key_pool.is_active,
+                    issue: issue.to_string(),
+                    detail,
+                    deactivated: false,
+                });
+            }
+        }
+    }
+
+    info!(
+        total_keys = report.total_keys,
+        healthy_keys = report.healthy_keys,
+        decrypt_failures = report.decrypt_failures,
+        hash_mismatches = report.hash_mismatches,
+        unverifiable_keys = report.unverifiable_keys,
+        "Key pool integrity audit finished"
+    );
+
+    Ok(report)
+}
+
+/// 停用审计报告中仍处于活跃状态的损坏 Key，并从缓存和轮询池中移除，避免请求持续使用损坏的 Key 导致 401
+///
+/// 只停用认证解密失败或哈希不一致的 Key；主密钥缺失、密钥环不可用等无法校验的 Key 多半是配置问题，
+/// 只在报告中列出，不停用。停用结果写回报告，返回本次停用的数量
+pub async fn deactivate_corrupted_keys(pool: &SqlitePool, report: &mut KeyIntegrityReport) -> anyhow::Result<usize> {
+    let mut providers = Vec::new();
+    let mut deactivated = 0;
+    for failure in report.failures.iter_mut().filter(|f| f.is_active && !f.deactivated && f.is_corrupted()) {
+        toggle_provider_key_pool_active(pool, &failure.key_pool_id, false).await?;
+        // 损坏的 Key 无法解密，直接移除缓存而不是重新加载
+        if let Some(cache) = GLOBAL_CACHE.get() {
+            key_pool_cache(cache).invalidate(&key_pool_cache_key(&failure.provider, &failure.key_pool_id)).await;
+        }
+        warn!(
+            key_pool_id = %failure.key_pool_id,
+            provider = %failure.provider,
+            issue = %failure.issue,
+            "Deactivated API key that failed integrity check"
+        );
+        failure.is_active = false;
+        failure.deactivated = true;
+        deactivated += 1;
+        if !providers.contains(&failure.provider) {
+            providers.push(failure.provider.clone());
+        }
+    }
+
+    for provider in &providers {
+        reload_provider_api_keys(pool, provider).await?;
+    }
+    report.deactivated_keys += deactivated;
+    Ok(deactivated)
+}
@@ -1,3 +1,16 @@
+//! # API Key 加密
+//!
+//! API Key 使用 AES-256-GCM 加密后存储，密文格式为 `{主密钥ID}:{Base64(nonce + 密文)}`，
+//! 解密时按主密钥 ID 选择密钥。主密钥环可通过以下方式配置（优先级从高到低）：
+//!
+//! - `GATEWAY_MASTER_KEY_FILE`：密钥文件路径（例如 KMS 挂载的密钥文件），每行一个 `id:base64密钥`
+//! - `GATEWAY_MASTER_KEYS`：逗号分隔的 `id:base64密钥`
+//!
+//! 第一个密钥用于加密，其余密钥只用于解密，轮换时把新密钥放在最前面并调用
+//! [`rotate_master_key`](super::rotation::rotate_master_key) 重新加密。
+//! 没有 ID 前缀的旧密文使用内置的 `legacy` 密钥解密。内置密钥是公开的，未配置主密钥环时拒绝加密新的 Key，
+//! 除非设置 `GATEWAY_ALLOW_LEGACY_MASTER_KEY=true` 明确允许使用内置密钥加密（仅用于本地开发）
+
 use aes_gcm::{
     aead::{Aead, KeyInit},
     Aes256Gcm, Nonce, Key
@@ -5,10 +18,189 @@ use aes_gcm::{
 use sha2::{Sha256, Digest};
 use base64::{Engine as _, engine::general_purpose};
 use rand::Rng;
-use anyhow::{Result, anyhow};
+use anyhow::{Result, anyhow, bail, Context};
+use lazy_static::lazy_static;
+use std::sync::{Arc, RwLock};
+use tracing::warn;
+
+/// 内置的旧加密密钥，只用于兼容没有配置主密钥环时写入的密文
+const LEGACY_ENCRYPTION_KEY: &[u8; 32] = b"my_very_secure_32_byte_secret_k!";
+
+/// 内置旧密钥的 ID
+pub const LEGACY_KEY_ID: &str = "legacy";
+
+/// 未配置主密钥时允许使用内置旧密钥加密的环境变量
+pub const ALLOW_LEGACY_KEY_ENV: &str = "GATEWAY_ALLOW_LEGACY_MASTER_KEY";
+
+/// 认证解密失败：密文被篡改或不是用该主密钥加密的。
+/// 区别于主密钥不存在、密钥环不可用或密文格式错误等无法据此判断 Key 已损坏的错误
+#[derive(Debug)]
+pub struct AuthenticationFailed;
+
+impl std::fmt::Display for AuthenticationFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Decryption failed: authentication tag mismatch")
+    }
+}
+
+impl std::error::Error for AuthenticationFailed {}
+
+/// 单个主密钥
+#[derive(Clone)]
+pub struct MasterKey {
+    pub id: String,
+    key: [u8; 32],
+}
+
+impl std::fmt::Debug for MasterKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // 不输出密钥内容
+        f.debug_struct("MasterKey").field("id", &self.id).finish_non_exhaustive()
+    }
+}
+
+impl MasterKey {
+    /// 创建主密钥，ID 只能包含字母、数字、`-` 和 `_`
+    pub fn new(id: &str, key: [u8; 32]) -> Result<Self> {
+        let id = id.trim();
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            bail!("Invalid master key id `{}`", id);
+        }
+        Ok(Self { id: id.to_string(), key })
+    }
+
+    /// 解析 `id:base64密钥`，密钥必须是 32 字节
+    pub fn parse(entry: &str) -> Result<Self> {
+        let (id, encoded) = entry.trim().split_once(':')
+            .ok_or_else(|| anyhow!("Master key must be in `id:base64` format"))?;
+        let bytes = general_purpose::STANDARD.decode(encoded.trim())
+            .with_context(|| format!("Master key `{}` is not valid base64", id.trim()))?;
+        let key: [u8; 32] = bytes.try_into()
+            .map_err(|bytes: Vec<u8>| anyhow!("Master key `{}` must be 32 bytes, got {}", id.trim(), bytes.len()))?;
+        Self::new(id, key)
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.key))
+    }
+}
+
+/// 主密钥环：第一个密钥用于加密，所有密钥（包括内置旧密钥）都可用于解密
+#[derive(Debug, Clone)]
+pub struct MasterKeyring {
+    keys: Vec<MasterKey>,
+    legacy: MasterKey,
+    // 没有配置密钥时是否使用内置旧密钥加密
+    legacy_encryption: bool,
+}
+
+impl MasterKeyring {
+    /// 使用指定的密钥创建，`keys` 为空时只能解密旧密文，加密返回错误
+    pub fn new(keys: Vec<MasterKey>) -> Result<Self> {
+        for (i, key) in keys.iter().enumerate() {
+            if key.id == LEGACY_KEY_ID {
+                bail!("Master key id `{}` is reserved", LEGACY_KEY_ID);
+            }
+            if keys[..i].iter().any(|other| other.id == key.id) {
+                bail!("Duplicate master key id `{}`", key.id);
+            }
+        }
+        Ok(Self {
+            keys,
+            legacy: MasterKey { id: LEGACY_KEY_ID.to_string(), key: *LEGACY_ENCRYPTION_KEY },
+            legacy_encryption: false,
+        })
+    }
+
+    /// 只包含内置旧密钥并使用它加密，仅用于本地开发和兼容测试
+    pub fn legacy() -> Self {
+        Self {
+            keys: Vec::new(),
+            legacy: MasterKey { id: LEGACY_KEY_ID.to_string(), key: *LEGACY_ENCRYPTION_KEY },
+            legacy_encryption: true,
+        }
+    }
+
+    /// 解析多个 `id:base64密钥`，以换行或逗号分隔，忽略空行和 `#` 开头的注释
+    pub fn parse(spec: &str) -> Result<Self> {
+        let keys = spec.split(['\n', ','])
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty() && !entry.starts_with('#'))
+            .map(MasterKey::parse)
+            .collect::<Result<Vec<_>>>()?;
+        Self::new(keys)
+    }
+
+    /// 从 `GATEWAY_MASTER_KEY_FILE` 或 `GATEWAY_MASTER_KEYS` 加载；都未配置时只能解密旧密文，
+    /// 设置了 `GATEWAY_ALLOW_LEGACY_MASTER_KEY` 时才使用内置旧密钥加密
+    pub fn from_env() -> Result<Self> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+        if let Some(path) = var("GATEWAY_MASTER_KEY_FILE") {
+            let spec = std::fs::read_to_string(path.trim())
+                .with_context(|| format!("Failed to read master key file `{}`", path.trim()))?;
+            return Self::parse(&spec);
+        }
+        if let Some(spec) = var("GATEWAY_MASTER_KEYS") {
+            return Self::parse(&spec);
+        }
+        let allow_legacy = var(ALLOW_LEGACY_KEY_ENV)
+            .is_some_and(|v| matches!(v.trim().to_lowercase().as_str(), "1" | "true" | "yes"));
+        if allow_legacy {
+            warn!("No master key configured, API keys are encrypted with the built-in legacy key");
+            return Ok(Self::legacy());
+        }
+        warn!("No master key configured, new API keys cannot be stored until GATEWAY_MASTER_KEYS or GATEWAY_MASTER_KEY_FILE is set");
+        Self::new(Vec::new())
+    }
+
+    /// 用于加密的密钥，没有配置主密钥且未允许使用内置旧密钥时返回错误
+    pub fn current(&self) -> Result<&MasterKey> {
+        match self.keys.first() {
+            Some(key) => Ok(key),
+            None if self.legacy_encryption => Ok(&self.legacy),
+            None => bail!(
+                "No master key configured, set GATEWAY_MASTER_KEYS or GATEWAY_MASTER_KEY_FILE (or {}=true to use the built-in legacy key)",
+                ALLOW_LEGACY_KEY_ENV
+            ),
+        }
+    }
+
+    /// 按 ID 查找密钥
+    pub fn get(&self, id: &str) -> Option<&MasterKey> {
+        self.keys.iter().chain(std::iter::once(&self.legacy)).find(|key| key.id == id)
+    }
+
+    /// 使用当前密钥加密
+    pub fn encrypt(&self, api_key: &str) -> Result<String> {
+        encrypt_api_key_with(self.current()?, api_key)
+    }
 
-/// 固定的加密密钥 - 在生产环境中应该从环境变量或配置文件中读取
-const ENCRYPTION_KEY: &[u8; 32] = b"my_very_secure_32_byte_secret_k!";
+    /// 按密文的主密钥 ID 解密
+    pub fn decrypt(&self, encrypted_data: &str) -> Result<String> {
+        decrypt_with_keyring(self, encrypted_data)
+    }
+}
+
+lazy_static! {
+    // 全局主密钥环，首次使用时从环境变量加载，加载失败时加解密都返回错误
+    static ref MASTER_KEYRING: RwLock<std::result::Result<Arc<MasterKeyring>, String>> =
+        RwLock::new(MasterKeyring::from_env().map(Arc::new).map_err(|e| format!("{:#}", e)));
+}
+
+/// 当前的全局主密钥环
+pub fn master_keyring() -> Result<Arc<MasterKeyring>> {
+    MASTER_KEYRING.read().unwrap().clone().map_err(|e| anyhow!("Master keyring unavailable: {}", e))
+}
+
+/// 替换全局主密钥环，用于重新加载密钥文件或测试
+pub fn set_master_keyring(keyring: MasterKeyring) {
+    *MASTER_KEYRING.write().unwrap() = Ok(Arc::new(keyring));
+}
+
+/// 密文使用的主密钥 ID，没有 ID 前缀的旧密文返回 `legacy`
+pub fn encrypted_key_id(encrypted_data: &str) -> &str {
+    encrypted_data.split_once(':').map_or(LEGACY_KEY_ID, |(id, _)| id)
+}
 
 /// 从原始API密钥生成SHA-256哈希
 /// 
@@ -24,18 +216,21 @@ pub fn generate_key_hash(api_key: &str) -> String {
     format!("{:x}", result)
 }
 
-/// 使用AES-256-GCM加密API密钥
+/// 使用当前主密钥（AES-256-GCM）加密API密钥
 /// 
 /// # Arguments
 /// * `api_key` - 原始API密钥字符串
 /// 
 /// # Returns
-/// * `Ok(String)` - Base64编码的加密数据(包含nonce)
-/// * `Err(anyhow::Error)` - 加密失败
+/// * `Ok(String)` - 当前主密钥ID和Base64编码的加密数据(包含nonce)
+/// * `Err(anyhow::Error)` - 加密失败或主密钥环不可用
 pub fn encrypt_api_key(api_key: &str) -> Result<String> {
-    // 创建AES-256-GCM实例
-    let key = Key::<Aes256Gcm>::from_slice(ENCRYPTION_KEY);
-    let cipher = Aes256Gcm::new(key);
+    master_keyring()?.encrypt(api_key)
+}
+
+/// 使用指定的主密钥加密API密钥，返回 `{主密钥ID}:{Base64编码的加密数据}`
+pub fn encrypt_api_key_with(master_key: &MasterKey, api_key: &str) -> Result<String> {
+    let cipher = master_key.cipher();
     
     // 生成随机nonce
     let mut nonce_bytes = [0u8; 12];
@@ -51,21 +246,31 @@ pub fn encrypt_api_key(api_key: &str) -> Result<String> {
     let mut encrypted_data = nonce_bytes.to_vec();
     encrypted_data.extend_from_slice(&ciphertext);
     
-    Ok(general_purpose::STANDARD.encode(&encrypted_data))
+    Ok(format!("{}:{}", master_key.id, general_purpose::STANDARD.encode(&encrypted_data)))
 }
 
 /// 使用AES-256-GCM解密API密钥
 /// 
 /// # Arguments
-/// * `encrypted_data` - Base64编码的加密数据(包含nonce)
+/// * `encrypted_data` - 主密钥ID和Base64编码的加密数据(包含nonce)，没有ID前缀时使用内置旧密钥
 /// 
 /// # Returns
 /// * `Ok(String)` - 解密后的原始API密钥
-/// * `Err(anyhow::Error)` - 解密失败
+/// * `Err(anyhow::Error)` - 解密失败或主密钥不存在；认证失败时为 [`AuthenticationFailed`]
 pub fn decrypt_api_key(encrypted_data: &str) -> Result<String> {
+    master_keyring()?.decrypt(encrypted_data)
+}
+
+// 使用密文前缀对应的主密钥解密
+fn decrypt_with_keyring(keyring: &MasterKeyring, encrypted_data: &str) -> Result<String> {
+    let key_id = encrypted_key_id(encrypted_data);
+    let master_key = keyring.get(key_id)
+        .ok_or_else(|| anyhow!("Unknown master key `{}`", key_id))?;
+    let encoded = encrypted_data.split_once(':').map_or(encrypted_data, |(_, encoded)| encoded);
+
     // Base64解码
     let encrypted_bytes = general_purpose::STANDARD
-        .decode(encrypted_data)
+        .decode(encoded)
         .map_err(|e| anyhow!("Base64 decode failed: {}", e))?;
     
     if encrypted_bytes.len() < 12 {
@@ -76,14 +281,10 @@ pub fn decrypt_api_key(encrypted_data: &str) -> Result<String> {
     let (nonce_bytes, ciphertext) = encrypted_bytes.split_at(12);
     let nonce = Nonce::from_slice(nonce_bytes);
     
-    // 创建AES-256-GCM实例
-    let key = Key::<Aes256Gcm>::from_slice(ENCRYPTION_KEY);
-    let cipher = Aes256Gcm::new(key);
-    
     // 解密
-    let plaintext = cipher
+    let plaintext = master_key.cipher()
         .decrypt(nonce, ciphertext)
-        .map_err(|e| anyhow!("Decryption failed: {}", e))?;
+        .map_err(|_| anyhow::Error::new(AuthenticationFailed))?;
     
     String::from_utf8(plaintext)
         .map_err(|e| anyhow!("UTF-8 conversion failed: {}", e))
@@ -120,6 +321,11 @@ pub fn verify_key_integrity(decrypted_key: &str, stored_hash: &str) -> bool {
 mod tests {
     use super::*;
 
+    // 全局密钥环默认拒绝加密，使用全局加密函数的测试先安装内置旧密钥
+    fn install_legacy_keyring() {
+        set_master_keyring(MasterKeyring::legacy());
+    }
+
     #[test]
     fn test_key_hash_generation() {
         let api_key = "sk-1234567890abcdef";
@@ -139,6 +345,7 @@ mod tests {
 
     #[test]
     fn test_encrypt_decrypt_roundtrip() {
+        install_legacy_keyring();
         let original_key = "sk-1234567890abcdef";
         
         // 加密
@@ -153,6 +360,7 @@ mod tests {
 
     #[test]
     fn test_encrypt_produces_different_outputs() {
+        install_legacy_keyring();
         let api_key = "sk-1234567890abcdef";
         
         let encrypted1 = encrypt_api_key(api_key).expect("Encryption 1 failed");
@@ -170,6 +378,7 @@ mod tests {
 
     #[test]
     fn test_process_api_key() {
+        install_legacy_keyring();
         let api_key = "sk-1234567890abcdef";
         
         let (hash, encrypted) = process_api_key(api_key).expect("Process failed");
@@ -195,6 +404,57 @@ mod tests {
         assert!(!verify_key_integrity("wrong-key", &hash));
     }
 
+    fn keyring(spec: &str) -> MasterKeyring {
+        MasterKeyring::parse(spec).expect("keyring should parse")
+    }
+
+    fn encoded_key(byte: u8) -> String {
+        general_purpose::STANDARD.encode([byte; 32])
+    }
+
+    #[test]
+    fn test_parse_master_keyring() {
+        let spec = format!("# current key first\nk2:{}\n\nk1:{}", encoded_key(2), encoded_key(1));
+        let keys = keyring(&spec);
+        assert_eq!(keys.current().unwrap().id, "k2");
+        assert!(keys.get("k1").is_some());
+        assert!(keys.get(LEGACY_KEY_ID).is_some());
+        assert_eq!(MasterKeyring::legacy().current().unwrap().id, LEGACY_KEY_ID);
+        // 未明确允许时不使用公开的内置密钥加密，但仍能解密旧密文
+        let empty = MasterKeyring::new(Vec::new()).unwrap();
+        assert!(empty.encrypt("sk-new").is_err());
+        let legacy_ciphertext = MasterKeyring::legacy().encrypt("sk-old").unwrap();
+        assert_eq!(empty.decrypt(&legacy_ciphertext).unwrap(), "sk-old");
+
+        let error = |spec: &str| MasterKeyring::parse(spec).unwrap_err().to_string();
+        assert!(error("k1").contains("id:base64"));
+        assert!(error(&format!("k1:{}", general_purpose::STANDARD.encode(b"short"))).contains("32 bytes"));
+        assert!(error(&format!("k1:{0},k1:{0}", encoded_key(1))).contains("Duplicate"));
+        assert!(error(&format!("legacy:{}", encoded_key(1))).contains("reserved"));
+        assert!(error(&format!("bad id:{}", encoded_key(1))).contains("Invalid master key id"));
+    }
+
+    #[test]
+    fn test_keyring_versioned_ciphertext() {
+        let old = keyring(&format!("k1:{}", encoded_key(1)));
+        let new = keyring(&format!("k2:{},k1:{}", encoded_key(2), encoded_key(1)));
+
+        let encrypted = old.encrypt("sk-rotate").unwrap();
+        assert_eq!(encrypted_key_id(&encrypted), "k1");
+        // 新密钥环保留旧密钥时仍能解密
+        assert_eq!(new.decrypt(&encrypted).unwrap(), "sk-rotate");
+        assert_eq!(encrypted_key_id(&new.encrypt("sk-rotate").unwrap()), "k2");
+        // 移除旧密钥后无法解密
+        let error = keyring(&format!("k2:{}", encoded_key(2))).decrypt(&encrypted).unwrap_err();
+        assert!(error.to_string().contains("Unknown master key `k1`"));
+
+        // 没有前缀的旧密文使用内置密钥
+        let legacy = encrypt_api_key_with(new.get(LEGACY_KEY_ID).unwrap(), "sk-legacy").unwrap();
+        let unprefixed = legacy.split_once(':').unwrap().1;
+        assert_eq!(encrypted_key_id(unprefixed), LEGACY_KEY_ID);
+        assert_eq!(new.decrypt(unprefixed).unwrap(), "sk-legacy");
+    }
+
     #[test]
     fn test_decrypt_invalid_data() {
         // 测试无效的Base64数据
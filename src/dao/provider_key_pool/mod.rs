@@ -1,6 +1,10 @@
 mod provider_key_pool;
 pub mod preload;
 pub mod crypto;
+pub mod audit;
+pub mod rotation;
+pub mod usage;
+pub mod quota;
 
 pub use provider_key_pool::{
     ProviderKeyPool, 
@@ -8,26 +12,41 @@ pub use provider_key_pool::{
     get_provider_key_pool_by_id,
     list_provider_key_pools,
     list_provider_key_pools_by_provider,
+    list_provider_key_pools_page,
+    count_provider_key_pools,
     list_active_provider_key_pools,
     update_provider_key_pool,
     update_key_pool_usage,
+    add_key_pool_usage,
     delete_provider_key_pool,
     toggle_provider_key_pool_active,
+    replace_encrypted_key_value,
     create_provider_key_pool_from_raw_key
 };
 
 pub use preload::{
     CachedProviderKeyPool,
+    KEY_POOL_CACHE_NAMESPACE,
+    key_pool_cache,
+    key_pool_cache_key,
     preload_provider_key_pools_to_cache,
     get_provider_key_pool_from_cache,
     insert_provider_key_pool_to_cache,
     insert_cached_provider_key_pool_to_cache,
+    load_provider_key_pool_cache_value,
     get_decrypted_api_key_from_cache,
     get_api_key_round_robin,
+    get_project_api_key_round_robin,
     reload_provider_api_keys,
+    sync_provider_key_pool_cache,
     reset_round_robin_counter,
     get_round_robin_counter,
-    get_active_key_count
+    get_active_key_count,
+    has_available_key,
+    has_available_project_key,
+    mark_key_unavailable,
+    get_cooling_down_keys,
+    DEFAULT_KEY_COOLDOWN
 };
 
 pub use crypto::{
@@ -35,5 +54,38 @@ pub use crypto::{
     encrypt_api_key,
     decrypt_api_key,
     process_api_key,
-    verify_key_integrity
+    verify_key_integrity,
+    MasterKey,
+    MasterKeyring,
+    master_keyring,
+    set_master_keyring,
+    AuthenticationFailed,
+    LEGACY_KEY_ID
+};
+
+pub use usage::{
+    record_key_usage,
+    pending_key_usage,
+    flush_key_usage
+};
+
+pub use quota::{
+    KeyQuota,
+    record_key_quota,
+    get_key_quota,
+    is_key_near_limit,
+    clear_key_quota
+};
+
+pub use audit::{
+    KeyIntegrityFailure,
+    KeyIntegrityReport,
+    audit_key_pool_integrity,
+    deactivate_corrupted_keys
+};
+
+pub use rotation::{
+    KeyRotationFailure,
+    MasterKeyRotationReport,
+    rotate_master_key
 };
@@ -1,20 +1,47 @@
 use sqlx::{SqlitePool, Row};
-use crate::dao::provider_key_pool::{list_provider_key_pools, ProviderKeyPool};
-use crate::dao::cache::get_global_cache;
+use crate::dao::provider_key_pool::{list_provider_key_pools, get_provider_key_pool_by_id, ProviderKeyPool};
+use crate::dao::cache::{get_global_cache, GLOBAL_CACHE, cache::CacheService, namespace::CacheNamespace};
 use crate::dao::provider_key_pool::crypto::decrypt_api_key;
+use crate::dao::provider_key_pool::usage::record_key_usage;
+use crate::dao::provider_key_pool::quota::is_key_near_limit;
+use crate::metrics::metrics;
+use crate::dao::query_stats::timed_query;
+use crate::dao::project::DEFAULT_PROJECT;
+use crate::dao::SQLITE_POOL;
 use anyhow::Result;
 use tracing::{info, error, debug, warn};
 use serde::{Deserialize, Serialize};
 use std::sync::atomic::AtomicUsize;
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 use lazy_static::lazy_static;
 
+/// 被限流（429）的 API Key 默认冷却时间
+pub const DEFAULT_KEY_COOLDOWN: Duration = Duration::from_secs(60);
+
 // 全局轮询计数器，每个 provider 一个
 lazy_static! {
     static ref ROUND_ROBIN_COUNTERS: RwLock<HashMap<String, AtomicUsize>> = RwLock::new(HashMap::new());
     // 内存中的活跃 API Key 池，按 provider 分组
     static ref ACTIVE_KEY_POOLS: RwLock<HashMap<String, Vec<String>>> = RwLock::new(HashMap::new());
+    // 冷却中的 API Key 及冷却结束时间，按 provider 分组
+    static ref KEY_COOLDOWNS: RwLock<HashMap<String, HashMap<String, Instant>>> = RwLock::new(HashMap::new());
+    // API Key 所属的项目，Key ID -> 项目 ID
+    static ref KEY_PROJECTS: RwLock<HashMap<String, String>> = RwLock::new(HashMap::new());
+}
+
+/// API Key 的缓存命名空间，key 为 `{provider}:{id}`
+pub const KEY_POOL_CACHE_NAMESPACE: &str = "keys";
+
+/// 全局缓存中 API Key 命名空间的类型化句柄
+pub fn key_pool_cache(cache: &CacheService<String, String>) -> CacheNamespace<CachedProviderKeyPool> {
+    cache.namespace(KEY_POOL_CACHE_NAMESPACE)
+}
+
+/// API Key 在命名空间中的 key
+pub fn key_pool_cache_key(provider: &str, id: &str) -> String {
+    format!("{}:{}", provider, id)
 }
 
 /// 用于缓存的 Provider Key Pool 结构体，包含解密后的 API KEY
@@ -59,12 +86,13 @@ pub async fn preload_provider_key_pools_to_cache(pool: &SqlitePool) -> anyhow::R
     
     info!(key_pool_count = key_pools.len(), "Loaded provider key pools from database");
     
-    // 2. 获取全局缓存实例
-    let cache = get_global_cache();
+    // 2. 获取全局缓存中的 API Key 命名空间
+    let cache = key_pool_cache(&get_global_cache());
     
     // 3. 构建内存中的活跃 API Key 池和轮询计数器
     let mut provider_active_keys: HashMap<String, Vec<String>> = HashMap::new();
     let mut provider_counters: HashMap<String, AtomicUsize> = HashMap::new();
+    let mut key_projects: HashMap<String, String> = HashMap::new();
     
     // 4. 将每个 provider key pool 数据加载到缓存中
     for key_pool in key_pools {
@@ -86,16 +114,13 @@ pub async fn preload_provider_key_pools_to_cache(pool: &SqlitePool) -> anyhow::R
         let mut cached_key_pool = CachedProviderKeyPool::from(&key_pool);
         cached_key_pool.decrypted_api_key = decrypted_api_key;
         
-        // 使用 provider key pool ID 作为缓存key
-        let cache_key = format!("provider_key_pool:{}:{}", key_pool.provider, key_pool.id);
-        
-        // 将缓存对象序列化为JSON字符串作为缓存值
-        let cache_value = serde_json::to_string(&cached_key_pool)
+        // 使用 provider 和 key pool ID 作为缓存key
+        let cache_key = key_pool_cache_key(&key_pool.provider, &key_pool.id);
+        cache.insert(&cache_key, &cached_key_pool).await
             .map_err(|e| anyhow::anyhow!("Failed to serialize cached provider key pool {}: {}", key_pool.id, e))?;
         
-        // 插入到缓存
-        cache.insert(cache_key.clone(), cache_value).await;
-        
+        key_projects.insert(key_pool.id.clone(), key_pool.project_id.clone().unwrap_or_else(|| DEFAULT_PROJECT.to_string()));
+
         // 如果是活跃的 API Key，添加到内存池中
         if key_pool.is_active {
             provider_active_keys
@@ -113,7 +138,7 @@ pub async fn preload_provider_key_pools_to_cache(pool: &SqlitePool) -> anyhow::R
             key_pool_id = %key_pool.id,
             provider = %key_pool.provider,
             is_active = %key_pool.is_active,
-            cache_key = %cache_key,
+            cache_key = %cache.cache_key(&cache_key),
             api_key_length = %cached_key_pool.decrypted_api_key.len(),
             "Cached provider key pool with decrypted API key successfully"
         );
@@ -129,6 +154,8 @@ pub async fn preload_provider_key_pools_to_cache(pool: &SqlitePool) -> anyhow::R
         let mut counters = ROUND_ROBIN_COUNTERS.write().await;
         *counters = provider_counters;
     }
+
+    *KEY_PROJECTS.write().await = key_projects;
     
     info!("Successfully preloaded all provider key pools to cache");
     info!("Initialized round robin counters for {} providers", provider_active_keys.len());
@@ -143,31 +170,11 @@ pub async fn preload_provider_key_pools_to_cache(pool: &SqlitePool) -> anyhow::R
 /// 从缓存中获取 provider key pool（通过 provider 和 id）
 /// 返回的是包含解密后 API KEY 的缓存对象
 pub async fn get_provider_key_pool_from_cache(provider: &str, id: &str) -> Option<CachedProviderKeyPool> {
-    let cache = get_global_cache();
-    let cache_key = format!("provider_key_pool:{}:{}", provider, id);
-
-    // 尝试从缓存获取，如果不存在则返回None
-    let cached_value = cache.get(&cache_key).await?;
-    
-    // 反序列化JSON字符串为缓存的 provider key pool 对象
-    match serde_json::from_str::<CachedProviderKeyPool>(&cached_value) {
-        Ok(cached_key_pool) => Some(cached_key_pool),
-        Err(e) => {
-            error!(
-                cache_key = %cache_key,
-                error = %e,
-                "Failed to deserialize cached provider key pool"
-            );
-            None
-        }
-    }
+    key_pool_cache(&get_global_cache()).get(&key_pool_cache_key(provider, id)).await
 }
 
 /// 将 ProviderKeyPool 插入到缓存（会解密 API KEY）
 pub async fn insert_provider_key_pool_to_cache(key_pool: &ProviderKeyPool) -> Result<()> {
-    let cache = get_global_cache();
-    let cache_key = format!("provider_key_pool:{}:{}", key_pool.provider, key_pool.id);
-    
     // 解密 API KEY
     let decrypted_api_key = decrypt_api_key(&key_pool.encrypted_key_value)?;
     
@@ -175,21 +182,24 @@ pub async fn insert_provider_key_pool_to_cache(key_pool: &ProviderKeyPool) -> Re
     let mut cached_key_pool = CachedProviderKeyPool::from(key_pool);
     cached_key_pool.decrypted_api_key = decrypted_api_key;
     
-    let cache_value = serde_json::to_string(&cached_key_pool)?;
-    cache.insert(cache_key, cache_value).await;
-    
-    Ok(())
+    insert_cached_provider_key_pool_to_cache(&cached_key_pool).await
+}
+
+/// 从数据库重新加载单个 provider key pool 的缓存值（会解密 API KEY），记录已删除时返回 None
+pub async fn load_provider_key_pool_cache_value(pool: &SqlitePool, id: &str) -> Result<Option<CachedProviderKeyPool>> {
+    let Some(key_pool) = get_provider_key_pool_by_id(pool, id).await? else {
+        return Ok(None);
+    };
+
+    let mut cached_key_pool = CachedProviderKeyPool::from(&key_pool);
+    cached_key_pool.decrypted_api_key = decrypt_api_key(&key_pool.encrypted_key_value)?;
+    Ok(Some(cached_key_pool))
 }
 
 /// 直接插入已解密的 CachedProviderKeyPool 到缓存
 pub async fn insert_cached_provider_key_pool_to_cache(cached_key_pool: &CachedProviderKeyPool) -> Result<()> {
-    let cache = get_global_cache();
-    let cache_key = format!("provider_key_pool:{}:{}", cached_key_pool.provider, cached_key_pool.id);
-    
-    let cache_value = serde_json::to_string(cached_key_pool)?;
-    cache.insert(cache_key, cache_value).await;
-    
-    Ok(())
+    let cache_key = key_pool_cache_key(&cached_key_pool.provider, &cached_key_pool.id);
+    key_pool_cache(&get_global_cache()).insert(&cache_key, cached_key_pool).await
 }
 
 /// 从缓存中获取解密后的 API KEY
@@ -198,7 +208,7 @@ pub async fn get_decrypted_api_key_from_cache(provider: &str, id: &str) -> Optio
     Some(cached_key_pool.decrypted_api_key)
 }
 
-/// 使用轮询策略从内存中获取指定 provider 的一个活跃 API Key
+/// 使用轮询策略从内存中获取指定 provider 的一个活跃 API Key（不区分项目）
 /// 
 /// # Arguments
 /// * `provider` - 提供商名称
@@ -207,7 +217,23 @@ pub async fn get_decrypted_api_key_from_cache(provider: &str, id: &str) -> Optio
 /// * `Some((String, String))` - 找到的 API Key 和对应的 ID
 /// * `None` - 未找到活跃的 API Key
 pub async fn get_api_key_round_robin(provider: &str) -> Option<(String, String)> {
-    // 1. 从内存中获取该 provider 的活跃 API Key 列表
+    select_api_key_round_robin(provider, None).await
+}
+
+/// 使用轮询策略获取指定 provider 下属于某个项目的一个活跃 API Key，项目之间的 Key 互不共用
+/// 
+/// # Arguments
+/// * `provider` - 提供商名称
+/// * `project_id` - 项目 ID
+pub async fn get_project_api_key_round_robin(provider: &str, project_id: &str) -> Option<(String, String)> {
+    select_api_key_round_robin(provider, Some(project_id)).await
+}
+
+async fn select_api_key_round_robin(provider: &str, project_id: Option<&str>) -> Option<(String, String)> {
+    // 冷却结束的 Key 重新加入轮询
+    restore_expired_keys(provider).await;
+
+    // 1. 从内存中获取该 provider 的活跃 API Key 列表，指定项目时只保留该项目的 Key
     let active_key_ids = {
         let active_pools = ACTIVE_KEY_POOLS.read().await;
         match active_pools.get(provider) {
@@ -218,6 +244,10 @@ pub async fn get_api_key_round_robin(provider: &str) -> Option<(String, String)>
             }
         }
     };
+    let active_key_ids = match project_id {
+        Some(project_id) => filter_project_keys(active_key_ids, project_id).await,
+        None => active_key_ids,
+    };
 
     if active_key_ids.is_empty() {
         info!("No active API keys found for provider: {}", provider);
@@ -230,8 +260,17 @@ pub async fn get_api_key_round_robin(provider: &str) -> Option<(String, String)>
         counters.get(provider)?.load(std::sync::atomic::Ordering::Relaxed)
     };
 
-    // 3. 使用轮询策略选择 API Key
-    let selected_index = counter % active_key_ids.len();
+    // 3. 使用轮询策略选择 API Key，跳过剩余配额即将耗尽的 Key（全部耗尽时仍按轮询选择）
+    let key_count = active_key_ids.len();
+    let selected_index = (0..key_count)
+        .map(|offset| (counter + offset) % key_count)
+        .find(|&index| !is_key_near_limit(&active_key_ids[index]))
+        .unwrap_or(counter % key_count);
+    if selected_index != counter % key_count {
+        info!("API key {}:{} is near its rate limit, switching to {}",
+              provider, active_key_ids[counter % key_count], active_key_ids[selected_index]);
+        metrics().incr_counter("llm_gateway_key_preemptive_switches_total", &[("provider", provider)]);
+    }
     let selected_key_id = &active_key_ids[selected_index];
 
     // 4. 更新计数器
@@ -248,6 +287,7 @@ pub async fn get_api_key_round_robin(provider: &str) -> Option<(String, String)>
     // 5. 从缓存获取解密后的 API Key
     if let Some(cached_key_pool) = get_provider_key_pool_from_cache(provider, selected_key_id).await {
         if cached_key_pool.is_active {
+            record_key_usage(selected_key_id);
             return Some((cached_key_pool.decrypted_api_key, selected_key_id.clone()));
         } else {
             warn!("Selected API key {}:{} is not active", provider, selected_key_id);
@@ -268,15 +308,25 @@ pub async fn reload_provider_api_keys(pool: &SqlitePool, provider: &str) -> anyh
     info!("Reloading API keys for provider: {}", provider);
     
     // 查询指定 provider 的所有活跃 API Key
-    let query = "SELECT id FROM provider_key_pools WHERE provider = ? AND is_active = 1 ORDER BY id";
-    let rows = sqlx::query(query)
+    let query = "SELECT id, project_id FROM provider_key_pools WHERE provider = ? AND is_active = 1 ORDER BY id";
+    let rows = timed_query("provider_key_pool.reload_provider_api_keys", query, |sql| sqlx::query(sql)
         .bind(provider)
-        .fetch_all(pool)
+        .fetch_all(pool))
         .await
         .map_err(|e| anyhow::anyhow!("Failed to query active keys for provider {}: {}", provider, e))?;
 
+    {
+        let mut key_projects = KEY_PROJECTS.write().await;
+        for row in &rows {
+            key_projects.insert(row.get("id"), row.get("project_id"));
+        }
+    }
+
+    // 冷却中的 Key 暂不加入轮询，冷却结束后自动恢复
+    let cooling_down = get_cooling_down_keys(provider).await;
     let key_ids: Vec<String> = rows.into_iter()
         .map(|row| row.get::<String, _>("id"))
+        .filter(|id| !cooling_down.contains(id))
         .collect();
 
     // 更新内存中的活跃 key 池
@@ -289,13 +339,30 @@ pub async fn reload_provider_api_keys(pool: &SqlitePool, provider: &str) -> anyh
         }
     }
 
-    // 重置该 provider 的轮询计数器
+    // 重置该 provider 的轮询计数器，启动后才添加 Key 的 provider 在这里创建计数器
+    ROUND_ROBIN_COUNTERS.write().await
+        .entry(provider.to_string())
+        .or_insert_with(|| AtomicUsize::new(0));
     reset_round_robin_counter(provider).await;
 
     info!("Reloaded {} active API keys for provider: {}", key_ids.len(), provider);
     Ok(())
 }
 
+/// API Key 新增、修改、启停或删除后同步内存状态：按数据库重新写入（已删除时移除）该 Key 的缓存条目，
+/// 并重建 provider 的活跃 Key 轮询池，变更无需重启即可生效；缓存未初始化时只重建轮询池
+pub async fn sync_provider_key_pool_cache(pool: &SqlitePool, provider: &str, key_id: &str) -> anyhow::Result<()> {
+    if let Some(cache) = GLOBAL_CACHE.get() {
+        let cache = key_pool_cache(cache);
+        let cache_key = key_pool_cache_key(provider, key_id);
+        match load_provider_key_pool_cache_value(pool, key_id).await? {
+            Some(cached_key_pool) => cache.insert(&cache_key, &cached_key_pool).await?,
+            None => cache.invalidate(&cache_key).await,
+        }
+    }
+    reload_provider_api_keys(pool, provider).await
+}
+
 /// 重置指定 provider 的轮询计数器
 /// 
 /// # Arguments
@@ -334,4 +401,128 @@ pub async fn get_active_key_count(provider: &str) -> usize {
     active_pools.get(provider)
         .map(|keys| keys.len())
         .unwrap_or(0)
-}
\ No newline at end of file
+}
+
+/// 提供商当前是否有可用于轮询的 API Key（冷却结束的 Key 会先重新加入轮询）
+pub async fn has_available_key(provider: &str) -> bool {
+    restore_expired_keys(provider).await;
+    get_active_key_count(provider).await > 0
+}
+
+/// 提供商当前是否有属于指定项目、可用于轮询的 API Key
+pub async fn has_available_project_key(provider: &str, project_id: &str) -> bool {
+    restore_expired_keys(provider).await;
+    let active_key_ids = ACTIVE_KEY_POOLS.read().await.get(provider).cloned().unwrap_or_default();
+    !filter_project_keys(active_key_ids, project_id).await.is_empty()
+}
+
+// 只保留属于指定项目的 Key，未记录项目的 Key 属于 default 项目
+async fn filter_project_keys(key_ids: Vec<String>, project_id: &str) -> Vec<String> {
+    let key_projects = KEY_PROJECTS.read().await;
+    key_ids.into_iter()
+        .filter(|id| key_projects.get(id).map(String::as_str).unwrap_or(DEFAULT_PROJECT) == project_id)
+        .collect()
+}
+
+/// 将 API Key 暂时移出轮询池（例如收到 429 限流响应），冷却结束后自动重新加入
+///
+/// # Arguments
+/// * `provider` - 提供商名称
+/// * `key_id` - API Key ID
+/// * `duration` - 冷却时间
+pub async fn mark_key_unavailable(provider: &str, key_id: &str, duration: Duration) {
+    {
+        let mut cooldowns = KEY_COOLDOWNS.write().await;
+        cooldowns
+            .entry(provider.to_string())
+            .or_default()
+            .insert(key_id.to_string(), Instant::now() + duration);
+    }
+    {
+        let mut active_pools = ACTIVE_KEY_POOLS.write().await;
+        if let Some(keys) = active_pools.get_mut(provider) {
+            keys.retain(|id| id != key_id);
+        }
+    }
+    warn!(provider = %provider, key_id = %key_id, cooldown_secs = duration.as_secs(), "API key removed from rotation for cool-down");
+}
+
+/// 获取指定 provider 冷却中的 API Key ID
+pub async fn get_cooling_down_keys(provider: &str) -> Vec<String> {
+    let now = Instant::now();
+    let cooldowns = KEY_COOLDOWNS.read().await;
+    cooldowns.get(provider)
+        .map(|keys| keys.iter().filter(|(_, until)| **until > now).map(|(id, _)| id.clone()).collect())
+        .unwrap_or_default()
+}
+
+// 将冷却结束的 Key 重新加入轮询池；每次轮询都会调用，没有到期的 Key 时只持有读锁，
+// 确认 Key 状态的缓存和数据库查询在锁外完成
+async fn restore_expired_keys(provider: &str) {
+    let now = Instant::now();
+    let has_expired = KEY_COOLDOWNS.read().await
+        .get(provider)
+        .is_some_and(|keys| keys.values().any(|until| *until <= now));
+    if !has_expired {
+        return;
+    }
+
+    let expired: Vec<String> = {
+        let mut cooldowns = KEY_COOLDOWNS.write().await;
+        let Some(keys) = cooldowns.get_mut(provider) else {
+            return;
+        };
+        let expired = keys.iter().filter(|(_, until)| **until <= now).map(|(id, _)| id.clone()).collect();
+        keys.retain(|_, until| *until > now);
+        expired
+    };
+
+    // 冷却期间被停用或删除的 Key 不再恢复
+    let mut restored = Vec::new();
+    for key_id in expired {
+        if is_key_still_active(provider, &key_id).await {
+            restored.push(key_id);
+        }
+    }
+    if restored.is_empty() {
+        return;
+    }
+
+    let mut active_pools = ACTIVE_KEY_POOLS.write().await;
+    let keys = active_pools.entry(provider.to_string()).or_default();
+    for key_id in restored {
+        if !keys.contains(&key_id) {
+            info!(provider = %provider, key_id = %key_id, "API key cool-down expired, back in rotation");
+            keys.push(key_id);
+        }
+    }
+}
+
+// Key 是否仍处于启用状态：优先查缓存，缓存条目已过期或被淘汰时按数据库确认并重新写入缓存
+async fn is_key_still_active(provider: &str, key_id: &str) -> bool {
+    let Some(cache) = GLOBAL_CACHE.get() else {
+        return false;
+    };
+    let cache = key_pool_cache(cache);
+    let cache_key = key_pool_cache_key(provider, key_id);
+    if let Some(key_pool) = cache.get(&cache_key).await {
+        return key_pool.is_active;
+    }
+
+    let Some(pool) = SQLITE_POOL.get() else {
+        return false;
+    };
+    match load_provider_key_pool_cache_value(pool, key_id).await {
+        Ok(Some(key_pool)) if key_pool.provider == provider => {
+            if let Err(e) = cache.insert(&cache_key, &key_pool).await {
+                warn!(provider = %provider, key_id = %key_id, error = %e, "Failed to cache API key");
+            }
+            key_pool.is_active
+        }
+        Ok(_) => false,
+        Err(e) => {
+            warn!(provider = %provider, key_id = %key_id, error = %e, "Failed to load API key after cool-down");
+            false
+        }
+    }
+}
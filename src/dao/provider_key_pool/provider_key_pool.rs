@@ -1,6 +1,8 @@
 use sqlx::{SqlitePool, Result};
 use serde::{Deserialize, Serialize};
 use crate::dao::provider_key_pool::crypto::{process_api_key, verify_key_integrity};
+use crate::dao::pagination::{into_page, Cursor, CursorKey, CursorPage};
+use crate::dao::query_stats::timed_query;
 
 #[allow(dead_code)]
 #[derive(Debug, Clone, sqlx::FromRow, Serialize, Deserialize)]
@@ -14,17 +16,24 @@ pub struct ProviderKeyPool {
     pub last_used_at: Option<String>,
     pub rate_limit_per_minute: Option<i64>,
     pub rate_limit_per_hour: Option<i64>,
+    pub project_id: Option<String>,     // 所属项目，创建时为空则归属 default 项目
     pub created_at: Option<String>,
 }
 
+impl CursorKey for ProviderKeyPool {
+    fn cursor(&self) -> Cursor {
+        Cursor { created_at: self.created_at.clone().unwrap_or_default(), id: self.id.clone() }
+    }
+}
+
 /// Create a new provider key pool entry (async)
 pub async fn create_provider_key_pool(pool: &SqlitePool, key_pool: &ProviderKeyPool) -> Result<u64> {
-    let res = sqlx::query(r#"
+    let res = timed_query("provider_key_pool.create_provider_key_pool", r#"
         INSERT INTO provider_key_pools (
             id, provider, key_hash, encrypted_key_value, is_active, usage_count, 
-            last_used_at, rate_limit_per_minute, rate_limit_per_hour, created_at
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, datetime('now'))
-    "#)
+            last_used_at, rate_limit_per_minute, rate_limit_per_hour, project_id, created_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, COALESCE(?, 'default'), datetime('now'))
+    "#, |sql| sqlx::query(sql)
         .bind(&key_pool.id)
         .bind(&key_pool.provider)
         .bind(&key_pool.key_hash)
@@ -34,48 +43,80 @@ pub async fn create_provider_key_pool(pool: &SqlitePool, key_pool: &ProviderKeyP
         .bind(&key_pool.last_used_at)
         .bind(&key_pool.rate_limit_per_minute)
         .bind(&key_pool.rate_limit_per_hour)
-        .execute(pool)
+        .bind(&key_pool.project_id)
+        .execute(pool))
         .await?;
     Ok(res.rows_affected())
 }
 
 /// Read a provider key pool entry by id (async)
 pub async fn get_provider_key_pool_by_id(pool: &SqlitePool, id: &str) -> Result<Option<ProviderKeyPool>> {
-    let key_pool = sqlx::query_as::<_, ProviderKeyPool>("SELECT * FROM provider_key_pools WHERE id = ?")
+    let key_pool = timed_query("provider_key_pool.get_provider_key_pool_by_id", "SELECT * FROM provider_key_pools WHERE id = ?", |sql| sqlx::query_as::<_, ProviderKeyPool>(sql)
         .bind(id)
-        .fetch_optional(pool)
+        .fetch_optional(pool))
         .await?;
     Ok(key_pool)
 }
 
 /// List all provider key pool entries (async)
 pub async fn list_provider_key_pools(pool: &SqlitePool) -> Result<Vec<ProviderKeyPool>> {
-    let key_pools = sqlx::query_as::<_, ProviderKeyPool>("SELECT * FROM provider_key_pools")
-        .fetch_all(pool)
+    let key_pools = timed_query("provider_key_pool.list_provider_key_pools", "SELECT * FROM provider_key_pools", |sql| sqlx::query_as::<_, ProviderKeyPool>(sql)
+        .fetch_all(pool))
         .await?;
     Ok(key_pools)
 }
 
 /// List provider key pool entries by provider (async)
 pub async fn list_provider_key_pools_by_provider(pool: &SqlitePool, provider: &str) -> Result<Vec<ProviderKeyPool>> {
-    let key_pools = sqlx::query_as::<_, ProviderKeyPool>("SELECT * FROM provider_key_pools WHERE provider = ?")
+    let key_pools = timed_query("provider_key_pool.list_provider_key_pools_by_provider", "SELECT * FROM provider_key_pools WHERE provider = ?", |sql| sqlx::query_as::<_, ProviderKeyPool>(sql)
         .bind(provider)
-        .fetch_all(pool)
+        .fetch_all(pool))
         .await?;
     Ok(key_pools)
 }
 
+/// List a provider's key pool entries newest first, one page after `cursor`, optionally filtered by active flag and project (async)
+pub async fn list_provider_key_pools_page(pool: &SqlitePool, provider: &str, is_active: Option<bool>, project_id: Option<&str>, cursor: Option<&Cursor>, limit: i64) -> Result<CursorPage<ProviderKeyPool>> {
+    let key_pools = timed_query("provider_key_pool.list_provider_key_pools_page", r#"
+        SELECT * FROM provider_key_pools
+        WHERE provider = ?1 AND (?2 IS NULL OR is_active = ?2) AND (?6 IS NULL OR project_id = ?6)
+            AND (?3 IS NULL OR (created_at, id) < (?3, ?4))
+        ORDER BY created_at DESC, id DESC
+        LIMIT ?5
+    "#, |sql| sqlx::query_as::<_, ProviderKeyPool>(sql)
+        .bind(provider)
+        .bind(is_active)
+        .bind(cursor.map(|c| c.created_at.as_str()))
+        .bind(cursor.map(|c| c.id.as_str()))
+        .bind(limit + 1)
+        .bind(project_id)
+        .fetch_all(pool))
+        .await?;
+    Ok(into_page(key_pools, limit))
+}
+
+/// Count a provider's key pool entries, optionally filtered by active flag and project (async)
+pub async fn count_provider_key_pools(pool: &SqlitePool, provider: &str, is_active: Option<bool>, project_id: Option<&str>) -> Result<i64> {
+    let count: (i64,) = timed_query("provider_key_pool.count_provider_key_pools", "SELECT COUNT(*) FROM provider_key_pools WHERE provider = ?1 AND (?2 IS NULL OR is_active = ?2) AND (?3 IS NULL OR project_id = ?3)", |sql| sqlx::query_as(sql)
+        .bind(provider)
+        .bind(is_active)
+        .bind(project_id)
+        .fetch_one(pool))
+        .await?;
+    Ok(count.0)
+}
+
 /// List active provider key pool entries (async)
 pub async fn list_active_provider_key_pools(pool: &SqlitePool) -> Result<Vec<ProviderKeyPool>> {
-    let key_pools = sqlx::query_as::<_, ProviderKeyPool>("SELECT * FROM provider_key_pools WHERE is_active = 1")
-        .fetch_all(pool)
+    let key_pools = timed_query("provider_key_pool.list_active_provider_key_pools", "SELECT * FROM provider_key_pools WHERE is_active = 1", |sql| sqlx::query_as::<_, ProviderKeyPool>(sql)
+        .fetch_all(pool))
         .await?;
     Ok(key_pools)
 }
 
 /// Update a provider key pool entry by id (async)
 pub async fn update_provider_key_pool(pool: &SqlitePool, key_pool: &ProviderKeyPool) -> Result<u64> {
-    let res = sqlx::query(r#"
+    let res = timed_query("provider_key_pool.update_provider_key_pool", r#"
         UPDATE provider_key_pools SET
             provider = ?,
             key_hash = ?,
@@ -84,9 +125,10 @@ pub async fn update_provider_key_pool(pool: &SqlitePool, key_pool: &ProviderKeyP
             usage_count = ?,
             last_used_at = ?,
             rate_limit_per_minute = ?,
-            rate_limit_per_hour = ?
+            rate_limit_per_hour = ?,
+            project_id = COALESCE(?, project_id)
         WHERE id = ?
-    "#)
+    "#, |sql| sqlx::query(sql)
         .bind(&key_pool.provider)
         .bind(&key_pool.key_hash)
         .bind(&key_pool.encrypted_key_value)
@@ -95,41 +137,68 @@ pub async fn update_provider_key_pool(pool: &SqlitePool, key_pool: &ProviderKeyP
         .bind(&key_pool.last_used_at)
         .bind(&key_pool.rate_limit_per_minute)
         .bind(&key_pool.rate_limit_per_hour)
+        .bind(&key_pool.project_id)
         .bind(&key_pool.id)
-        .execute(pool)
+        .execute(pool))
         .await?;
     Ok(res.rows_affected())
 }
 
 /// Update usage count and last used time for a provider key pool entry (async)
 pub async fn update_key_pool_usage(pool: &SqlitePool, id: &str) -> Result<u64> {
-    let res = sqlx::query(r#"
+    let res = timed_query("provider_key_pool.update_key_pool_usage", r#"
         UPDATE provider_key_pools SET
             usage_count = usage_count + 1,
             last_used_at = datetime('now')
         WHERE id = ?
-    "#)
+    "#, |sql| sqlx::query(sql)
         .bind(id)
-        .execute(pool)
+        .execute(pool))
+        .await?;
+    Ok(res.rows_affected())
+}
+
+/// Add a batch of usage to a provider key pool entry and refresh last used time (async)
+pub async fn add_key_pool_usage(pool: &SqlitePool, id: &str, count: i64) -> Result<u64> {
+    let res = timed_query("provider_key_pool.add_key_pool_usage", r#"
+        UPDATE provider_key_pools SET
+            usage_count = usage_count + ?,
+            last_used_at = datetime('now')
+        WHERE id = ?
+    "#, |sql| sqlx::query(sql)
+        .bind(count)
+        .bind(id)
+        .execute(pool))
         .await?;
     Ok(res.rows_affected())
 }
 
 /// Delete a provider key pool entry by id (async)
 pub async fn delete_provider_key_pool(pool: &SqlitePool, id: &str) -> Result<u64> {
-    let res = sqlx::query("DELETE FROM provider_key_pools WHERE id = ?")
+    let res = timed_query("provider_key_pool.delete_provider_key_pool", "DELETE FROM provider_key_pools WHERE id = ?", |sql| sqlx::query(sql)
         .bind(id)
-        .execute(pool)
+        .execute(pool))
         .await?;
     Ok(res.rows_affected())
 }
 
 /// Toggle active status of a provider key pool entry (async)
 pub async fn toggle_provider_key_pool_active(pool: &SqlitePool, id: &str, is_active: bool) -> Result<u64> {
-    let res = sqlx::query("UPDATE provider_key_pools SET is_active = ? WHERE id = ?")
+    let res = timed_query("provider_key_pool.toggle_provider_key_pool_active", "UPDATE provider_key_pools SET is_active = ? WHERE id = ?", |sql| sqlx::query(sql)
         .bind(is_active)
         .bind(id)
-        .execute(pool)
+        .execute(pool))
+        .await?;
+    Ok(res.rows_affected())
+}
+
+/// Replace the encrypted value of a provider key pool entry if it still equals `expected` (async)
+pub async fn replace_encrypted_key_value(pool: &SqlitePool, id: &str, expected: &str, encrypted_key_value: &str) -> Result<u64> {
+    let res = timed_query("provider_key_pool.replace_encrypted_key_value", "UPDATE provider_key_pools SET encrypted_key_value = ? WHERE id = ? AND encrypted_key_value = ?", |sql| sqlx::query(sql)
+        .bind(encrypted_key_value)
+        .bind(id)
+        .bind(expected)
+        .execute(pool))
         .await?;
     Ok(res.rows_affected())
 }
@@ -171,6 +240,7 @@ pub async fn create_provider_key_pool_from_raw_key(
         last_used_at: None,
         rate_limit_per_minute,
         rate_limit_per_hour,
+        project_id: None,
         created_at: None,
     };
 
@@ -0,0 +1,131 @@
+//! # API Key 剩余配额跟踪
+//!
+//! 供应商在响应头中返回每个 Key 当前窗口内的剩余请求数和 token 数
+//! （`x-ratelimit-remaining-*` / `x-ratelimit-reset-*`）。记录后轮询时跳过即将耗尽
+//! 配额的 Key，在触发 429 之前切换到其他 Key；窗口重置后自动恢复
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use lazy_static::lazy_static;
+
+/// 剩余请求数不超过该值时视为即将耗尽
+pub const MIN_REMAINING_REQUESTS: u64 = 1;
+/// 剩余 token 数不超过该值时视为即将耗尽
+pub const MIN_REMAINING_TOKENS: u64 = 1000;
+/// 响应头未给出重置时间时的默认窗口
+pub const DEFAULT_QUOTA_RESET: Duration = Duration::from_secs(60);
+/// 重置时间的上限，超出的值按上限处理
+pub const MAX_QUOTA_RESET: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// 供应商响应头中的剩余配额
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KeyQuota {
+    pub remaining_requests: Option<u64>,
+    pub reset_requests: Option<Duration>,
+    pub remaining_tokens: Option<u64>,
+    pub reset_tokens: Option<Duration>,
+}
+
+impl KeyQuota {
+    /// 从响应头解析剩余配额，未返回任何剩余配额时为 None
+    pub fn from_headers<'a>(header: impl Fn(&str) -> Option<&'a str>) -> Option<Self> {
+        let number = |name: &str| header(name).and_then(|v| v.trim().parse::<u64>().ok());
+        let duration = |name: &str| header(name).and_then(parse_reset_duration);
+
+        let quota = Self {
+            remaining_requests: number("x-ratelimit-remaining-requests"),
+            reset_requests: duration("x-ratelimit-reset-requests"),
+            remaining_tokens: number("x-ratelimit-remaining-tokens"),
+            reset_tokens: duration("x-ratelimit-reset-tokens"),
+        };
+        (quota.remaining_requests.is_some() || quota.remaining_tokens.is_some()).then_some(quota)
+    }
+}
+
+/// 解析重置时间，支持纯秒数（`"60"`）和 Go 风格的时长（`"1m30s"`、`"250ms"`、`"6m0s"`），
+/// 超过 [`MAX_QUOTA_RESET`] 时取上限，负数和非有限值返回 None
+pub fn parse_reset_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<f64>() {
+        return capped_secs(secs);
+    }
+
+    let mut total = 0.0;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let split = rest.find(|c: char| !(c.is_ascii_digit() || c == '.'))?;
+        let amount: f64 = rest[..split].parse().ok()?;
+        rest = &rest[split..];
+        let unit_len = rest.find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len());
+        let scale = match &rest[..unit_len] {
+            "h" => 3600.0,
+            "m" => 60.0,
+            "s" => 1.0,
+            "ms" => 0.001,
+            _ => return None,
+        };
+        total += amount * scale;
+        rest = &rest[unit_len..];
+    }
+    if value.is_empty() {
+        return None;
+    }
+    capped_secs(total)
+}
+
+// 秒数转换为时长并限制在上限内
+fn capped_secs(secs: f64) -> Option<Duration> {
+    if secs.is_nan() || secs < 0.0 {
+        return None;
+    }
+    Some(Duration::try_from_secs_f64(secs).map_or(MAX_QUOTA_RESET, |duration| duration.min(MAX_QUOTA_RESET)))
+}
+
+// 记录的配额及记录时间
+struct QuotaEntry {
+    quota: KeyQuota,
+    recorded_at: Instant,
+}
+
+impl QuotaEntry {
+    // 配额是否即将耗尽（窗口重置后不再限制）
+    fn is_near_limit(&self, now: Instant) -> bool {
+        let exhausted = |remaining: Option<u64>, min: u64, reset: Option<Duration>| {
+            remaining.is_some_and(|r| r <= min)
+                && now < self.recorded_at + reset.unwrap_or(DEFAULT_QUOTA_RESET)
+        };
+        exhausted(self.quota.remaining_requests, MIN_REMAINING_REQUESTS, self.quota.reset_requests)
+            || exhausted(self.quota.remaining_tokens, MIN_REMAINING_TOKENS, self.quota.reset_tokens)
+    }
+}
+
+lazy_static! {
+    // 每个 Key 最近一次响应返回的剩余配额，按 Key id 分组
+    static ref KEY_QUOTAS: Mutex<HashMap<String, QuotaEntry>> = Mutex::new(HashMap::new());
+}
+
+/// 记录某个 Key 最近一次响应返回的剩余配额
+pub fn record_key_quota(key_id: &str, quota: KeyQuota) {
+    KEY_QUOTAS.lock().unwrap().insert(
+        key_id.to_string(),
+        QuotaEntry { quota, recorded_at: Instant::now() },
+    );
+}
+
+/// 某个 Key 最近记录的剩余配额
+pub fn get_key_quota(key_id: &str) -> Option<KeyQuota> {
+    KEY_QUOTAS.lock().unwrap().get(key_id).map(|entry| entry.quota.clone())
+}
+
+/// 某个 Key 的配额是否即将耗尽
+pub fn is_key_near_limit(key_id: &str) -> bool {
+    KEY_QUOTAS.lock().unwrap()
+        .get(key_id)
+        .is_some_and(|entry| entry.is_near_limit(Instant::now()))
+}
+
+/// 清除某个 Key 记录的配额
+pub fn clear_key_quota(key_id: &str) {
+    KEY_QUOTAS.lock().unwrap().remove(key_id);
+}
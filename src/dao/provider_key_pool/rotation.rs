@@ -0,0 +1,94 @@
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tracing::{info, warn};
+
+use crate::dao::provider_key_pool::{
+    list_provider_key_pools,
+    replace_encrypted_key_value,
+    crypto::{decrypt_api_key, encrypt_api_key_with, encrypted_key_id, master_keyring, verify_key_integrity},
+};
+
+/// 无法重新加密的 API Key
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyRotationFailure {
+    pub key_pool_id: String,
+    pub provider: String,
+    /// 密文原来使用的主密钥 ID
+    pub key_id: String,
+    pub detail: String,
+}
+
+/// 主密钥轮换报告
+#[derive(Debug, Clone, Serialize)]
+pub struct MasterKeyRotationReport {
+    /// 轮换后使用的主密钥 ID
+    pub current_key_id: String,
+    pub total_keys: usize,
+    pub rotated_keys: usize,
+    /// 已经使用当前主密钥的 Key
+    pub unchanged_keys: usize,
+    pub failures: Vec<KeyRotationFailure>,
+}
+
+/// 用当前主密钥重新加密所有未使用当前主密钥的 API Key
+///
+/// 逐条更新，可以重复执行；解密失败或哈希不一致的 Key 保持原样并记录在报告中。
+/// 更新时确认密文没有被并发修改，被修改的 Key 同样记为失败，重新执行即可。
+/// 缓存中只保存解密后的 Key，轮换后不需要刷新
+pub async fn rotate_master_key(pool: &SqlitePool) -> anyhow::Result<MasterKeyRotationReport> {
+    let keyring = master_keyring()?;
+    let current = keyring.current()?;
+    let key_pools = list_provider_key_pools(pool).await?;
+
+    let mut report = MasterKeyRotationReport {
+        current_key_id: current.id.clone(),
+        total_keys: key_pools.len(),
+        rotated_keys: 0,
+        unchanged_keys: 0,
+        failures: Vec::new(),
+    };
+
+    for key_pool in key_pools {
+        let key_id = encrypted_key_id(&key_pool.encrypted_key_value).to_string();
+        if key_id == current.id {
+            report.unchanged_keys += 1;
+            continue;
+        }
+
+        let result = match decrypt_api_key(&key_pool.encrypted_key_value) {
+            Ok(decrypted) if verify_key_integrity(&decrypted, &key_pool.key_hash) => {
+                match encrypt_api_key_with(current, &decrypted) {
+                    Ok(encrypted) => replace_encrypted_key_value(pool, &key_pool.id, &key_pool.encrypted_key_value, &encrypted).await
+                        .map_err(|e| e.to_string())
+                        .and_then(|rows| if rows == 1 { Ok(()) } else { Err("key was modified during rotation".to_string()) }),
+                    Err(e) => Err(e.to_string()),
+                }
+            }
+            Ok(_) => Err("decrypted key does not match stored hash".to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+
+        match result {
+            Ok(()) => report.rotated_keys += 1,
+            Err(detail) => {
+                warn!(key_pool_id = %key_pool.id, provider = %key_pool.provider, key_id = %key_id, detail = %detail, "Failed to re-encrypt API key");
+                report.failures.push(KeyRotationFailure {
+                    key_pool_id: key_pool.id,
+                    provider: key_pool.provider,
+                    key_id,
+                    detail,
+                });
+            }
+        }
+    }
+
+    info!(
+        current_key_id = %report.current_key_id,
+        total_keys = report.total_keys,
+        rotated_keys = report.rotated_keys,
+        failures = report.failures.len(),
+        "Master key rotation finished"
+    );
+
+    Ok(report)
+}
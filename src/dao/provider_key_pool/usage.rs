@@ -0,0 +1,93 @@
+//! # API Key 使用统计
+//!
+//! 请求路径上只在内存中原子累加每个 Key 的使用次数，由后台任务定期（以及服务退出时）
+//! 批量写回 `usage_count` 和 `last_used_at`，避免每个请求都写一次数据库
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicI64, Ordering};
+use lazy_static::lazy_static;
+use sqlx::SqlitePool;
+use tracing::{debug, warn};
+
+use crate::dao::provider_key_pool::add_key_pool_usage;
+
+lazy_static! {
+    // 尚未写回数据库的使用次数，按 Key id 分组；已有计数器只需读锁即可原子累加
+    static ref PENDING_KEY_USAGE: RwLock<HashMap<String, AtomicI64>> = RwLock::new(HashMap::new());
+}
+
+/// 记录一次 Key 使用（仅累加内存计数）
+pub fn record_key_usage(key_id: &str) {
+    if let Some(counter) = PENDING_KEY_USAGE.read().unwrap().get(key_id) {
+        counter.fetch_add(1, Ordering::Relaxed);
+        return;
+    }
+    PENDING_KEY_USAGE.write().unwrap()
+        .entry(key_id.to_string())
+        .or_default()
+        .fetch_add(1, Ordering::Relaxed);
+}
+
+/// 某个 Key 尚未写回数据库的使用次数
+pub fn pending_key_usage(key_id: &str) -> i64 {
+    PENDING_KEY_USAGE.read().unwrap()
+        .get(key_id)
+        .map_or(0, |counter| counter.load(Ordering::Relaxed))
+}
+
+// 取出所有计数并清零；上一轮起一直没有使用的计数器一并移除，避免已删除的 Key 常驻内存
+fn take_pending_usage() -> Vec<(String, i64)> {
+    let pending: Vec<(String, i64)> = PENDING_KEY_USAGE.read().unwrap()
+        .iter()
+        .map(|(key_id, counter)| (key_id.clone(), counter.swap(0, Ordering::AcqRel)))
+        .collect();
+
+    let idle: Vec<&String> = pending.iter().filter(|(_, count)| *count == 0).map(|(key_id, _)| key_id).collect();
+    if !idle.is_empty() {
+        // 持有写锁时不会有并发累加，重新确认仍为 0 再移除
+        let mut counters = PENDING_KEY_USAGE.write().unwrap();
+        for key_id in idle {
+            if counters.get(key_id).is_some_and(|counter| counter.load(Ordering::Relaxed) == 0) {
+                counters.remove(key_id);
+            }
+        }
+    }
+
+    pending.into_iter().filter(|(_, count)| *count > 0).collect()
+}
+
+/// 将累积的使用次数批量写回数据库，返回写回的 Key 数量；写入失败的计数保留到下一次
+pub async fn flush_key_usage(pool: &SqlitePool) -> anyhow::Result<usize> {
+    let pending = take_pending_usage();
+    if pending.is_empty() {
+        return Ok(0);
+    }
+
+    let mut flushed = 0;
+    let mut failed = HashMap::new();
+    let mut last_error = None;
+    for (key_id, count) in pending {
+        match add_key_pool_usage(pool, &key_id, count).await {
+            Ok(_) => flushed += 1,
+            Err(e) => {
+                warn!(key_id = %key_id, error = %e, "Failed to flush key usage, will retry");
+                failed.insert(key_id, count);
+                last_error = Some(e);
+            }
+        }
+    }
+
+    if !failed.is_empty() {
+        let mut counters = PENDING_KEY_USAGE.write().unwrap();
+        for (key_id, count) in failed {
+            counters.entry(key_id).or_default().fetch_add(count, Ordering::Relaxed);
+        }
+    }
+    debug!(keys = flushed, "Flushed key usage");
+
+    match last_error {
+        Some(e) => Err(e.into()),
+        None => Ok(flushed),
+    }
+}
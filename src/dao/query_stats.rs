@@ -0,0 +1,149 @@
+//! # 数据库查询耗时统计
+//!
+//! DAO 函数通过 [`timed_query`] 执行 SQL：每次查询在 `db_query` span 中运行，耗时按查询名累计，
+//! 超过慢查询阈值时输出警告日志。管理接口据此列出最耗时的查询，并用 `EXPLAIN QUERY PLAN`
+//! 检查其执行计划，提示未使用索引的全表扫描（如按未建索引的列过滤 call_logs）
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use lazy_static::lazy_static;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tracing::{warn, Instrument};
+
+use crate::metrics::metrics;
+
+/// 默认慢查询阈值（毫秒）
+pub const DEFAULT_SLOW_QUERY_THRESHOLD_MS: u64 = 200;
+
+/// 单个查询的累计耗时
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryStat {
+    /// 查询名，格式为 `模块.函数`
+    pub name: &'static str,
+    pub sql: String,
+    pub calls: u64,
+    pub errors: u64,
+    pub slow_calls: u64,
+    pub total_ms: f64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+}
+
+lazy_static! {
+    // 按查询名累计的耗时
+    static ref QUERY_STATS: Mutex<HashMap<&'static str, QueryStat>> = Mutex::new(HashMap::new());
+    static ref SLOW_QUERY_THRESHOLD_MS: AtomicU64 = AtomicU64::new(slow_query_threshold_from_env());
+}
+
+/// 慢查询阈值，可通过 `DB_SLOW_QUERY_THRESHOLD_MS` 配置
+fn slow_query_threshold_from_env() -> u64 {
+    std::env::var("DB_SLOW_QUERY_THRESHOLD_MS")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(DEFAULT_SLOW_QUERY_THRESHOLD_MS)
+}
+
+/// 当前慢查询阈值
+pub fn slow_query_threshold() -> Duration {
+    Duration::from_millis(SLOW_QUERY_THRESHOLD_MS.load(Ordering::Relaxed))
+}
+
+/// 修改慢查询阈值
+pub fn set_slow_query_threshold(threshold: Duration) {
+    SLOW_QUERY_THRESHOLD_MS.store(threshold.as_millis() as u64, Ordering::Relaxed);
+}
+
+/// 执行查询并记录耗时，`run` 接收 `sql` 构造查询
+pub async fn timed_query<'q, T, F, Fut>(name: &'static str, sql: &'q str, run: F) -> sqlx::Result<T>
+where
+    F: FnOnce(&'q str) -> Fut,
+    Fut: Future<Output = sqlx::Result<T>>,
+{
+    let span = tracing::debug_span!("db_query", query = name);
+    let started = Instant::now();
+    let result = run(sql).instrument(span).await;
+    let elapsed = started.elapsed();
+
+    let threshold = slow_query_threshold();
+    let slow = elapsed >= threshold;
+    if slow {
+        metrics().incr_counter("llm_gateway_db_slow_queries_total", &[("query", name)]);
+        warn!(query = name, elapsed_ms = elapsed.as_millis() as u64, threshold_ms = threshold.as_millis() as u64, "Slow database query");
+    }
+    record_query(name, sql, elapsed, result.is_err(), slow);
+    result
+}
+
+fn record_query(name: &'static str, sql: &str, elapsed: Duration, failed: bool, slow: bool) {
+    let elapsed_ms = elapsed.as_secs_f64() * 1000.0;
+    let mut stats = QUERY_STATS.lock().unwrap();
+    let stat = stats.entry(name).or_insert_with(|| QueryStat {
+        name,
+        sql: normalize_sql(sql),
+        calls: 0,
+        errors: 0,
+        slow_calls: 0,
+        total_ms: 0.0,
+        avg_ms: 0.0,
+        max_ms: 0.0,
+    });
+    stat.calls += 1;
+    stat.errors += failed as u64;
+    stat.slow_calls += slow as u64;
+    stat.total_ms += elapsed_ms;
+    stat.avg_ms = stat.total_ms / stat.calls as f64;
+    stat.max_ms = stat.max_ms.max(elapsed_ms);
+}
+
+// 合并多行 SQL 中的空白，便于在接口和日志中展示
+fn normalize_sql(sql: &str) -> String {
+    sql.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 所有查询的累计耗时，总耗时最多的在前
+pub fn query_stats() -> Vec<QueryStat> {
+    let mut stats: Vec<QueryStat> = QUERY_STATS.lock().unwrap().values().cloned().collect();
+    stats.sort_by(|a, b| b.total_ms.total_cmp(&a.total_ms));
+    stats
+}
+
+/// 清空累计耗时
+pub fn reset_query_stats() {
+    QUERY_STATS.lock().unwrap().clear();
+}
+
+/// 查询的执行计划（`EXPLAIN QUERY PLAN` 的 detail 列），参数按 NULL 处理
+pub async fn explain_query_plan(pool: &SqlitePool, sql: &str) -> sqlx::Result<Vec<String>> {
+    let rows: Vec<(i64, i64, i64, String)> = sqlx::query_as(&format!("EXPLAIN QUERY PLAN {}", sql))
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.into_iter().map(|row| row.3).collect())
+}
+
+/// 根据执行计划给出索引建议：没有可用于过滤的索引而扫描整张表（包括只为排序按索引顺序遍历），
+/// 以及需要临时排序的 ORDER BY
+pub fn index_hints(plan: &[String]) -> Vec<String> {
+    plan.iter()
+        .filter_map(|detail| {
+            if let Some(scan) = detail.strip_prefix("SCAN ")
+                && !scan.starts_with("CONSTANT ROW")
+            {
+                let scan = scan.strip_prefix("TABLE ").unwrap_or(scan);
+                let table = scan.split_whitespace().next().unwrap_or(scan);
+                let via = match scan.split_once(" INDEX ") {
+                    Some((_, index)) => format!(" (walking index {})", index),
+                    None => String::new(),
+                };
+                Some(format!("Full table scan on {}{}; consider an index on the filtered columns", table, via))
+            } else if detail.starts_with("USE TEMP B-TREE FOR ORDER BY") {
+                Some("Sorting with a temporary b-tree; consider an index matching the ORDER BY".to_string())
+            } else {
+                None
+            }
+        })
+        .collect()
+}
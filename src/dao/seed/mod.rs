@@ -0,0 +1,8 @@
+mod seed;
+
+pub use seed::{
+    SeedReport,
+    SEED_ENV_VAR,
+    seed_enabled_from_env,
+    seed_default_data
+};
@@ -0,0 +1,106 @@
+//! # 启动数据初始化
+//!
+//! 首次运行时写入常用供应商（本地 Ollama、阿里云 DashScope、OpenAI）和常用模型，
+//! 省去手动录入。已存在的供应商和模型保持不变，重复执行不会产生重复数据
+
+use sqlx::{SqlitePool, Result};
+use tracing::info;
+
+use crate::dao::model::{create_model, get_model_by_provider_and_name, Model};
+use crate::dao::provider::{create_provider, get_provider_by_name, Provider};
+
+/// 控制是否执行初始化的环境变量，取值 `1`/`true`/`yes` 时开启
+pub const SEED_ENV_VAR: &str = "SEED_DEFAULT_DATA";
+
+/// 供应商种子：(name, display_name, base_url, description)
+const SEED_PROVIDERS: &[(&str, &str, &str, &str)] = &[
+    ("ollama", "Ollama", "http://localhost:11434", "本地部署的开源大语言模型服务"),
+    ("ali", "阿里云通义千问", "https://dashscope.aliyuncs.com", "阿里云 DashScope 提供的商业化大语言模型服务"),
+    ("openai", "OpenAI", "https://api.openai.com/v1", "OpenAI提供的GPT系列模型"),
+];
+
+/// 模型种子：(provider, name, cost_per_token_input, cost_per_token_output)，费用单位与模型模板一致
+const SEED_MODELS: &[(&str, &str, f64, f64)] = &[
+    ("ollama", "llama3.2", 0.0, 0.0),
+    ("ollama", "qwen2.5:7b", 0.0, 0.0),
+    ("ali", "qwen-turbo", 0.0008, 0.002),
+    ("ali", "qwen-plus", 0.004, 0.012),
+    ("ali", "qwen-max", 0.02, 0.06),
+    ("openai", "gpt-4o-mini", 0.00015, 0.0006),
+    ("openai", "gpt-4o", 0.0025, 0.01),
+];
+
+/// 初始化结果
+#[derive(Debug, Clone, Default)]
+pub struct SeedReport {
+    /// 新建的供应商名称
+    pub providers_created: Vec<String>,
+    /// 新建的模型，格式为 `provider/name`
+    pub models_created: Vec<String>,
+}
+
+/// 根据环境变量 `SEED_DEFAULT_DATA` 判断是否执行初始化，默认关闭
+pub fn seed_enabled_from_env() -> bool {
+    std::env::var(SEED_ENV_VAR)
+        .map(|v| matches!(v.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// 写入缺失的常用供应商和模型
+pub async fn seed_default_data(pool: &SqlitePool) -> Result<SeedReport> {
+    let mut report = SeedReport::default();
+
+    for (name, display_name, base_url, description) in SEED_PROVIDERS {
+        if get_provider_by_name(pool, name).await?.is_some() {
+            continue;
+        }
+        let provider = Provider {
+            id: name.to_string(),
+            name: name.to_string(),
+            display_name: display_name.to_string(),
+            base_url: Some(base_url.to_string()),
+            description: Some(description.to_string()),
+            config: None,
+            is_active: true,
+            project_id: None,
+            max_concurrent_requests: None,
+            created_at: None,
+            updated_at: None,
+        };
+        create_provider(pool, &provider).await?;
+        report.providers_created.push(name.to_string());
+    }
+
+    for (provider, name, cost_input, cost_output) in SEED_MODELS {
+        if get_model_by_provider_and_name(pool, provider, name).await?.is_some() {
+            continue;
+        }
+        let model = Model {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            provider: provider.to_string(),
+            model_type: "llm".to_string(),
+            base_url: None,
+            is_active: true,
+            health_status: Some("unknown".to_string()),
+            last_health_check: None,
+            health_check_interval_seconds: Some(300),
+            cost_per_token_input: Some(*cost_input),
+            cost_per_token_output: Some(*cost_output),
+            function_tags: None,
+            config: None,
+            project_id: None,
+            created_at: None,
+            updated_at: None,
+        };
+        create_model(pool, &model).await?;
+        report.models_created.push(format!("{}/{}", provider, name));
+    }
+
+    info!(
+        providers = report.providers_created.len(),
+        models = report.models_created.len(),
+        "Default data seeded"
+    );
+    Ok(report)
+}
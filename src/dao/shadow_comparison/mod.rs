@@ -0,0 +1,8 @@
+mod shadow_comparison;
+
+pub use shadow_comparison::{
+    ShadowComparison,
+    ShadowComparisonFilter,
+    create_shadow_comparison,
+    list_shadow_comparisons
+};
@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{SqlitePool, Result};
+
+use crate::dao::pagination::clamp_limit;
+use crate::dao::query_stats::timed_query;
+
+#[derive(Debug, Clone, sqlx::FromRow, Serialize, Deserialize)]
+pub struct ShadowComparison {
+    pub id: String,
+    pub primary_provider: String,
+    pub primary_model: String,
+    pub primary_latency_ms: i64,
+    pub primary_response: Option<String>,
+    pub shadow_provider: String,     // 镜像请求发往的供应商
+    pub shadow_model: String,
+    pub shadow_latency_ms: i64,
+    pub shadow_response: Option<String>,
+    pub shadow_error: Option<String>, // 镜像请求失败时的错误信息
+    pub created_at: Option<String>,
+}
+
+/// Filter for listing shadow comparisons; `limit` is clamped to the page limit
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ShadowComparisonFilter {
+    pub primary_provider: Option<String>,
+    pub shadow_provider: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Record a primary/shadow response pair (async)
+pub async fn create_shadow_comparison(pool: &SqlitePool, comparison: &ShadowComparison) -> Result<u64> {
+    let res = timed_query("shadow_comparison.create_shadow_comparison", r#"
+        INSERT INTO shadow_comparisons (
+            id, primary_provider, primary_model, primary_latency_ms, primary_response,
+            shadow_provider, shadow_model, shadow_latency_ms, shadow_response, shadow_error
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+    "#, |sql| sqlx::query(sql)
+        .bind(&comparison.id)
+        .bind(&comparison.primary_provider)
+        .bind(&comparison.primary_model)
+        .bind(comparison.primary_latency_ms)
+        .bind(&comparison.primary_response)
+        .bind(&comparison.shadow_provider)
+        .bind(&comparison.shadow_model)
+        .bind(comparison.shadow_latency_ms)
+        .bind(&comparison.shadow_response)
+        .bind(&comparison.shadow_error)
+        .execute(pool))
+        .await?;
+    Ok(res.rows_affected())
+}
+
+/// List shadow comparisons matching the filter, newest first (async)
+pub async fn list_shadow_comparisons(pool: &SqlitePool, filter: &ShadowComparisonFilter) -> Result<Vec<ShadowComparison>> {
+    let comparisons = timed_query("shadow_comparison.list_shadow_comparisons", r#"
+        SELECT * FROM shadow_comparisons
+        WHERE (?1 IS NULL OR primary_provider = ?1) AND (?2 IS NULL OR shadow_provider = ?2)
+        ORDER BY created_at DESC, id DESC
+        LIMIT ?3
+    "#, |sql| sqlx::query_as::<_, ShadowComparison>(sql)
+        .bind(&filter.primary_provider)
+        .bind(&filter.shadow_provider)
+        .bind(clamp_limit(filter.limit))
+        .fetch_all(pool))
+        .await?;
+    Ok(comparisons)
+}
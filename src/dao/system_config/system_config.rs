@@ -1,5 +1,7 @@
 use sqlx::{SqlitePool, Result};
 
+use crate::dao::query_stats::timed_query;
+
 #[allow(dead_code)]
 #[derive(Debug, Clone, sqlx::FromRow)]
 pub struct SystemConfig {
@@ -15,69 +17,69 @@ pub struct SystemConfig {
 
 /// Create a new system config entry (async)
 pub async fn create_system_config(pool: &SqlitePool, config: &SystemConfig) -> Result<u64> {
-    let res = sqlx::query(r#"
+    let res = timed_query("system_config.create_system_config", r#"
         INSERT INTO system_configs (
             id, category, key_name, value, is_encrypted, version, created_at, updated_at
         ) VALUES (?, ?, ?, ?, ?, ?, datetime('now'), datetime('now'))
-    "#)
+    "#, |sql| sqlx::query(sql)
         .bind(&config.id)
         .bind(&config.category)
         .bind(&config.key_name)
         .bind(&config.value)
         .bind(config.is_encrypted)
         .bind(config.version)
-        .execute(pool)
+        .execute(pool))
         .await?;
     Ok(res.rows_affected())
 }
 
 /// Read a system config entry by id (async)
 pub async fn get_system_config_by_id(pool: &SqlitePool, id: &str) -> Result<Option<SystemConfig>> {
-    let config = sqlx::query_as::<_, SystemConfig>("SELECT * FROM system_configs WHERE id = ?")
+    let config = timed_query("system_config.get_system_config_by_id", "SELECT * FROM system_configs WHERE id = ?", |sql| sqlx::query_as::<_, SystemConfig>(sql)
         .bind(id)
-        .fetch_optional(pool)
+        .fetch_optional(pool))
         .await?;
     Ok(config)
 }
 
 /// Read a system config entry by category and key_name (async)
 pub async fn get_system_config_by_key(pool: &SqlitePool, category: &str, key_name: &str) -> Result<Option<SystemConfig>> {
-    let config = sqlx::query_as::<_, SystemConfig>("SELECT * FROM system_configs WHERE category = ? AND key_name = ?")
+    let config = timed_query("system_config.get_system_config_by_key", "SELECT * FROM system_configs WHERE category = ? AND key_name = ?", |sql| sqlx::query_as::<_, SystemConfig>(sql)
         .bind(category)
         .bind(key_name)
-        .fetch_optional(pool)
+        .fetch_optional(pool))
         .await?;
     Ok(config)
 }
 
 /// List all system config entries (async)
 pub async fn list_system_configs(pool: &SqlitePool) -> Result<Vec<SystemConfig>> {
-    let configs = sqlx::query_as::<_, SystemConfig>("SELECT * FROM system_configs ORDER BY category, key_name")
-        .fetch_all(pool)
+    let configs = timed_query("system_config.list_system_configs", "SELECT * FROM system_configs ORDER BY category, key_name", |sql| sqlx::query_as::<_, SystemConfig>(sql)
+        .fetch_all(pool))
         .await?;
     Ok(configs)
 }
 
 /// List system config entries by category (async)
 pub async fn list_system_configs_by_category(pool: &SqlitePool, category: &str) -> Result<Vec<SystemConfig>> {
-    let configs = sqlx::query_as::<_, SystemConfig>("SELECT * FROM system_configs WHERE category = ? ORDER BY key_name")
+    let configs = timed_query("system_config.list_system_configs_by_category", "SELECT * FROM system_configs WHERE category = ? ORDER BY key_name", |sql| sqlx::query_as::<_, SystemConfig>(sql)
         .bind(category)
-        .fetch_all(pool)
+        .fetch_all(pool))
         .await?;
     Ok(configs)
 }
 
 /// List encrypted system config entries (async)
 pub async fn list_encrypted_system_configs(pool: &SqlitePool) -> Result<Vec<SystemConfig>> {
-    let configs = sqlx::query_as::<_, SystemConfig>("SELECT * FROM system_configs WHERE is_encrypted = 1 ORDER BY category, key_name")
-        .fetch_all(pool)
+    let configs = timed_query("system_config.list_encrypted_system_configs", "SELECT * FROM system_configs WHERE is_encrypted = 1 ORDER BY category, key_name", |sql| sqlx::query_as::<_, SystemConfig>(sql)
+        .fetch_all(pool))
         .await?;
     Ok(configs)
 }
 
 /// Update a system config entry by id (async)
 pub async fn update_system_config(pool: &SqlitePool, config: &SystemConfig) -> Result<u64> {
-    let res = sqlx::query(r#"
+    let res = timed_query("system_config.update_system_config", r#"
         UPDATE system_configs SET
             category = ?,
             key_name = ?,
@@ -86,86 +88,86 @@ pub async fn update_system_config(pool: &SqlitePool, config: &SystemConfig) -> R
             version = version + 1,
             updated_at = datetime('now')
         WHERE id = ?
-    "#)
+    "#, |sql| sqlx::query(sql)
         .bind(&config.category)
         .bind(&config.key_name)
         .bind(&config.value)
         .bind(config.is_encrypted)
         .bind(&config.id)
-        .execute(pool)
+        .execute(pool))
         .await?;
     Ok(res.rows_affected())
 }
 
 /// Update system config value by category and key_name (async)
 pub async fn update_system_config_value(pool: &SqlitePool, category: &str, key_name: &str, value: &str) -> Result<u64> {
-    let res = sqlx::query(r#"
+    let res = timed_query("system_config.update_system_config_value", r#"
         UPDATE system_configs SET
             value = ?,
             version = version + 1,
             updated_at = datetime('now')
         WHERE category = ? AND key_name = ?
-    "#)
+    "#, |sql| sqlx::query(sql)
         .bind(value)
         .bind(category)
         .bind(key_name)
-        .execute(pool)
+        .execute(pool))
         .await?;
     Ok(res.rows_affected())
 }
 
 /// Update system config encryption status (async)
 pub async fn update_system_config_encryption(pool: &SqlitePool, id: &str, is_encrypted: bool, encrypted_value: &str) -> Result<u64> {
-    let res = sqlx::query(r#"
+    let res = timed_query("system_config.update_system_config_encryption", r#"
         UPDATE system_configs SET
             value = ?,
             is_encrypted = ?,
             version = version + 1,
             updated_at = datetime('now')
         WHERE id = ?
-    "#)
+    "#, |sql| sqlx::query(sql)
         .bind(encrypted_value)
         .bind(is_encrypted)
         .bind(id)
-        .execute(pool)
+        .execute(pool))
         .await?;
     Ok(res.rows_affected())
 }
 
 /// Delete a system config entry by id (async)
 pub async fn delete_system_config(pool: &SqlitePool, id: &str) -> Result<u64> {
-    let res = sqlx::query("DELETE FROM system_configs WHERE id = ?")
+    let res = timed_query("system_config.delete_system_config", "DELETE FROM system_configs WHERE id = ?", |sql| sqlx::query(sql)
         .bind(id)
-        .execute(pool)
+        .execute(pool))
         .await?;
     Ok(res.rows_affected())
 }
 
 /// Delete system config entries by category (async)
 pub async fn delete_system_configs_by_category(pool: &SqlitePool, category: &str) -> Result<u64> {
-    let res = sqlx::query("DELETE FROM system_configs WHERE category = ?")
+    let res = timed_query("system_config.delete_system_configs_by_category", "DELETE FROM system_configs WHERE category = ?", |sql| sqlx::query(sql)
         .bind(category)
-        .execute(pool)
+        .execute(pool))
         .await?;
     Ok(res.rows_affected())
 }
 
 /// Check if a system config key exists (async)
 pub async fn system_config_exists(pool: &SqlitePool, category: &str, key_name: &str) -> Result<bool> {
-    let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM system_configs WHERE category = ? AND key_name = ?")
+    let count: (i64,) = timed_query("system_config.system_config_exists", "SELECT COUNT(*) FROM system_configs WHERE category = ? AND key_name = ?", |sql| sqlx::query_as(sql)
         .bind(category)
         .bind(key_name)
-        .fetch_one(pool)
+        .fetch_one(pool))
         .await?;
     Ok(count.0 > 0)
 }
 
 /// Get system config value directly (async)
 pub async fn get_system_config_value(pool: &SqlitePool, category: &str, key_name: &str) -> Result<Option<String>> {
-    let result: Option<(String,)> = sqlx::query_as("SELECT value FROM system_configs WHERE category = ? AND key_name = ?")
+    let result: Option<(String,)> = timed_query("system_config.get_system_config_value", "SELECT value FROM system_configs WHERE category = ? AND key_name = ?", |sql| sqlx::query_as(sql)
         .bind(category)
         .bind(key_name)
-        .fetch_optional(pool)
+        .fetch_optional(pool))
         .await?;
     Ok(result.map(|r| r.0))
 }
@@ -0,0 +1,8 @@
+mod tool_call_audit;
+
+pub use tool_call_audit::{
+    ToolCallStep,
+    create_tool_call_step,
+    list_tool_call_steps_by_call_log,
+    delete_tool_call_steps_by_call_log
+};
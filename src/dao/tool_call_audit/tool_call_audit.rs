@@ -0,0 +1,57 @@
+use sqlx::{SqlitePool, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::dao::query_stats::timed_query;
+
+#[derive(Debug, Clone, sqlx::FromRow, Serialize, Deserialize)]
+pub struct ToolCallStep {
+    pub id: String,
+    pub call_log_id: String,        // 发起工具调用的模型请求
+    pub step_index: i64,
+    pub tool_call_id: Option<String>,
+    pub tool_name: String,
+    pub arguments: Option<String>,  // 工具输入(JSON)
+    pub output: Option<String>,
+    pub error_message: Option<String>,
+    pub latency_ms: i64,
+    pub created_at: Option<String>,
+}
+
+/// Record a tool call step (async)
+pub async fn create_tool_call_step(pool: &SqlitePool, step: &ToolCallStep) -> Result<u64> {
+    let res = timed_query("tool_call_audit.create_tool_call_step", r#"
+        INSERT INTO tool_call_steps (
+            id, call_log_id, step_index, tool_call_id, tool_name, arguments, output, error_message, latency_ms, created_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, datetime('now'))
+    "#, |sql| sqlx::query(sql)
+        .bind(&step.id)
+        .bind(&step.call_log_id)
+        .bind(step.step_index)
+        .bind(&step.tool_call_id)
+        .bind(&step.tool_name)
+        .bind(&step.arguments)
+        .bind(&step.output)
+        .bind(&step.error_message)
+        .bind(step.latency_ms)
+        .execute(pool))
+        .await?;
+    Ok(res.rows_affected())
+}
+
+/// List tool call steps of a call log in execution order (async)
+pub async fn list_tool_call_steps_by_call_log(pool: &SqlitePool, call_log_id: &str) -> Result<Vec<ToolCallStep>> {
+    let steps = timed_query("tool_call_audit.list_tool_call_steps_by_call_log", "SELECT * FROM tool_call_steps WHERE call_log_id = ? ORDER BY step_index", |sql| sqlx::query_as::<_, ToolCallStep>(sql)
+        .bind(call_log_id)
+        .fetch_all(pool))
+        .await?;
+    Ok(steps)
+}
+
+/// Delete tool call steps of a call log (async)
+pub async fn delete_tool_call_steps_by_call_log(pool: &SqlitePool, call_log_id: &str) -> Result<u64> {
+    let res = timed_query("tool_call_audit.delete_tool_call_steps_by_call_log", "DELETE FROM tool_call_steps WHERE call_log_id = ?", |sql| sqlx::query(sql)
+        .bind(call_log_id)
+        .execute(pool))
+        .await?;
+    Ok(res.rows_affected())
+}
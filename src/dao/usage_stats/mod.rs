@@ -0,0 +1,10 @@
+mod usage_stats;
+
+pub use usage_stats::{
+    UsageStat,
+    UsageStatFilter,
+    UsageStatKey,
+    record_usage_stat,
+    list_usage_stats,
+    get_provider_cost_since
+};
@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{SqlitePool, Result};
+
+use crate::dao::query_stats::timed_query;
+
+#[derive(Debug, Clone, sqlx::FromRow, Serialize, Deserialize)]
+pub struct UsageStat {
+    pub day: String,                // YYYY-MM-DD（本地时间）
+    pub project_id: String,
+    pub provider: String,
+    pub model: String,
+    pub request_count: i64,
+    pub tokens_input: i64,
+    pub tokens_output: i64,
+    pub cost: f64,
+    pub updated_at: Option<String>,
+}
+
+/// Filter for listing usage stats; `start` and `end` are inclusive `YYYY-MM-DD` days
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageStatFilter {
+    pub start: Option<String>,
+    pub end: Option<String>,
+    pub project_id: Option<String>,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+}
+
+/// Identifies one daily aggregate row
+#[derive(Debug, Clone, Copy)]
+pub struct UsageStatKey<'a> {
+    pub day: &'a str,
+    pub project_id: &'a str,
+    pub provider: &'a str,
+    pub model: &'a str,
+}
+
+/// Add one request's tokens and cost to the daily aggregate of a project's provider/model (async)
+pub async fn record_usage_stat(
+    pool: &SqlitePool,
+    key: &UsageStatKey<'_>,
+    tokens_input: i64,
+    tokens_output: i64,
+    cost: f64,
+) -> Result<u64> {
+    let res = timed_query("usage_stats.record_usage_stat", r#"
+        INSERT INTO usage_stats (
+            day, project_id, provider, model, request_count, tokens_input, tokens_output, cost, updated_at
+        ) VALUES (?, ?, ?, ?, 1, ?, ?, ?, datetime('now', 'localtime'))
+        ON CONFLICT(day, project_id, provider, model) DO UPDATE SET
+            request_count = request_count + 1,
+            tokens_input = tokens_input + excluded.tokens_input,
+            tokens_output = tokens_output + excluded.tokens_output,
+            cost = cost + excluded.cost,
+            updated_at = excluded.updated_at
+    "#, |sql| sqlx::query(sql)
+        .bind(key.day)
+        .bind(key.project_id)
+        .bind(key.provider)
+        .bind(key.model)
+        .bind(tokens_input)
+        .bind(tokens_output)
+        .bind(cost)
+        .execute(pool))
+        .await?;
+    Ok(res.rows_affected())
+}
+
+/// List daily usage stats matching the filter, newest day first (async)
+pub async fn list_usage_stats(pool: &SqlitePool, filter: &UsageStatFilter) -> Result<Vec<UsageStat>> {
+    let stats = timed_query("usage_stats.list_usage_stats", r#"
+        SELECT * FROM usage_stats
+        WHERE (?1 IS NULL OR day >= ?1) AND (?2 IS NULL OR day <= ?2)
+            AND (?3 IS NULL OR provider = ?3) AND (?4 IS NULL OR model = ?4)
+            AND (?5 IS NULL OR project_id = ?5)
+        ORDER BY day DESC, project_id, provider, model
+    "#, |sql| sqlx::query_as::<_, UsageStat>(sql)
+        .bind(&filter.start)
+        .bind(&filter.end)
+        .bind(&filter.provider)
+        .bind(&filter.model)
+        .bind(&filter.project_id)
+        .fetch_all(pool))
+        .await?;
+    Ok(stats)
+}
+
+/// Total cost of a project's calls to a provider from `since_day` (inclusive) onwards (async)
+pub async fn get_provider_cost_since(pool: &SqlitePool, project_id: &str, provider: &str, since_day: &str) -> Result<f64> {
+    let total: (f64,) = timed_query("usage_stats.get_provider_cost_since", "SELECT COALESCE(SUM(cost), 0.0) FROM usage_stats WHERE project_id = ? AND provider = ? AND day >= ?", |sql| sqlx::query_as(sql)
+        .bind(project_id)
+        .bind(provider)
+        .bind(since_day)
+        .fetch_one(pool))
+        .await?;
+    Ok(total.0)
+}
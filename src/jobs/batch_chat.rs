@@ -0,0 +1,163 @@
+//! # 批量 Chat 任务
+//!
+//! 离线场景（数据标注、批量翻译等）一次提交大量请求，网关在后台通过
+//! `LLMDispatcher::dispatch_batch` 按并发上限执行，完成后按任务 ID 查询全部结果。
+//! 任务只保存在当前进程内（不写入数据库），网关重启后执行中和已完成的任务都会丢失；
+//! 完成的任务保留 `BATCH_RESULT_TTL` 后清理，每个调用方同时执行的任务数不超过 `MAX_ACTIVE_BATCH_JOBS`
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use lazy_static::lazy_static;
+use tracing::info;
+
+use crate::api_types::v1::GatewayErrorCode;
+use crate::llm_api::dispatcher::{DispatchRequest, DispatchResponse, LLMDispatcher};
+use crate::llm_api::utils::client::{CallMetadata, CALL_METADATA};
+use crate::metrics::metrics;
+
+/// 任务名称（用于日志）
+pub const JOB_NAME: &str = "batch_chat";
+
+/// 单个任务的最大请求数
+pub const MAX_BATCH_SIZE: usize = 1000;
+
+/// 默认和最大并发数
+pub const DEFAULT_BATCH_CONCURRENCY: usize = 4;
+pub const MAX_BATCH_CONCURRENCY: usize = 32;
+
+/// 每个调用方同时执行中的任务数上限
+pub const MAX_ACTIVE_BATCH_JOBS: usize = 4;
+
+/// 完成的任务保留时长
+pub const BATCH_RESULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// 批量任务中的单个请求
+pub struct BatchItem {
+    pub custom_id: Option<String>,
+    /// 请求中的模型名称（解析供应商前）
+    pub requested_model: String,
+    pub request: DispatchRequest,
+}
+
+/// 单个请求失败的原因
+#[derive(Debug, Clone)]
+pub struct BatchItemError {
+    pub code: GatewayErrorCode,
+    pub message: String,
+}
+
+/// 单个请求的结果
+#[derive(Debug, Clone)]
+pub struct BatchItemResult {
+    pub custom_id: Option<String>,
+    /// 请求中的模型名称，响应没有模型名称时使用
+    pub requested_model: String,
+    pub outcome: Result<DispatchResponse, BatchItemError>,
+}
+
+/// 批量任务及其结果
+#[derive(Debug, Clone)]
+pub struct BatchJob {
+    pub id: String,
+    pub project_id: String,
+    /// 提交任务的调用方，未识别调用方时为空
+    pub consumer_id: Option<String>,
+    pub total: usize,
+    pub created_at: String,
+    pub completed_at: Option<String>,
+    /// 任务完成后按提交顺序填充
+    pub results: Vec<BatchItemResult>,
+    completed: Option<Instant>,
+}
+
+impl BatchJob {
+    pub fn is_completed(&self) -> bool {
+        self.completed.is_some()
+    }
+
+    pub fn succeeded(&self) -> usize {
+        self.results.iter().filter(|result| result.outcome.is_ok()).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.results.len() - self.succeeded()
+    }
+}
+
+lazy_static! {
+    // 批量任务，按任务 id 分组
+    static ref BATCH_JOBS: Mutex<HashMap<String, BatchJob>> = Mutex::new(HashMap::new());
+}
+
+fn now() -> String {
+    chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+// 清理超过保留时长的已完成任务
+fn purge_expired(jobs: &mut HashMap<String, BatchJob>) {
+    jobs.retain(|_, job| job.completed.is_none_or(|completed| completed.elapsed() < BATCH_RESULT_TTL));
+}
+
+/// 登记批量任务并在后台执行，立即返回任务
+///
+/// 任务归属当前调用的项目；后台执行沿用当前的调用方和项目，用量计入调用方的额度。
+/// 同一项目下的同一调用方已有 `MAX_ACTIVE_BATCH_JOBS` 个任务在执行时不登记，返回 None
+pub fn start_batch_job(dispatcher: Arc<LLMDispatcher>, items: Vec<BatchItem>, concurrency: usize) -> Option<BatchJob> {
+    let metadata = CallMetadata::current();
+    let job = BatchJob {
+        id: format!("batch_{}", uuid::Uuid::new_v4().simple()),
+        project_id: metadata.project_id().to_string(),
+        consumer_id: metadata.consumer_id.clone(),
+        total: items.len(),
+        created_at: now(),
+        completed_at: None,
+        results: Vec::new(),
+        completed: None,
+    };
+    {
+        let mut jobs = BATCH_JOBS.lock().unwrap();
+        purge_expired(&mut jobs);
+        let active = jobs.values()
+            .filter(|other| !other.is_completed() && other.project_id == job.project_id && other.consumer_id == job.consumer_id)
+            .count();
+        if active >= MAX_ACTIVE_BATCH_JOBS {
+            metrics().incr_counter("llm_gateway_batch_jobs_rejected_total", &[]);
+            return None;
+        }
+        jobs.insert(job.id.clone(), job.clone());
+    }
+
+    let id = job.id.clone();
+    let (labels, requests): (Vec<_>, Vec<_>) = items.into_iter()
+        .map(|item| ((item.custom_id, item.requested_model), item.request))
+        .unzip();
+    tokio::spawn(CALL_METADATA.scope(metadata, async move {
+        let responses = dispatcher.dispatch_batch(requests, concurrency).await;
+        let results: Vec<BatchItemResult> = labels.into_iter().zip(responses)
+            .map(|((custom_id, requested_model), response)| BatchItemResult {
+                custom_id,
+                requested_model,
+                outcome: response.map_err(|e| BatchItemError { code: e.error_code(), message: e.to_string() }),
+            })
+            .collect();
+
+        let mut jobs = BATCH_JOBS.lock().unwrap();
+        if let Some(job) = jobs.get_mut(&id) {
+            job.results = results;
+            job.completed_at = Some(now());
+            job.completed = Some(Instant::now());
+            metrics().add_counter("llm_gateway_batch_items_total", &[("result", "ok")], job.succeeded() as u64);
+            metrics().add_counter("llm_gateway_batch_items_total", &[("result", "error")], job.failed() as u64);
+            info!(job = JOB_NAME, batch_id = %id, total = job.total, failed = job.failed(), "Batch job completed");
+        }
+    }));
+    Some(job)
+}
+
+/// 查询批量任务
+pub fn get_batch_job(id: &str) -> Option<BatchJob> {
+    let mut jobs = BATCH_JOBS.lock().unwrap();
+    purge_expired(&mut jobs);
+    jobs.get(id).cloned()
+}
@@ -0,0 +1,234 @@
+//! # 调用日志归档
+//!
+//! 按模型和时间范围批量删除或归档（先导出为 JSON Lines 文件再删除）调用日志。
+//! 导出使用数据库游标逐行读取，删除按批次执行，任务在后台运行并记录进度，供管理接口查询。
+//! 结束的任务保留 `ARCHIVE_TASK_TTL` 后清理
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use futures::TryStreamExt;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::time::Instant;
+use tracing::{error, info};
+
+use crate::dao::call_log::{
+    count_call_logs_by_filter, delete_call_logs_by_filter_batch, stream_call_logs_by_filter, CallLogFilter,
+};
+use crate::metrics::metrics;
+
+/// 任务名称（用于日志）
+pub const JOB_NAME: &str = "call_log_archive";
+
+/// 默认每批删除的行数
+pub const DEFAULT_BATCH_SIZE: i64 = 1000;
+
+/// 默认归档文件目录
+pub const DEFAULT_ARCHIVE_DIR: &str = "data/archive";
+
+/// 结束（完成或失败）的任务保留时长
+pub const ARCHIVE_TASK_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// 导出时每写入多少行更新一次进度
+const PROGRESS_INTERVAL: i64 = 1000;
+
+/// 任务类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveMode {
+    /// 先导出再删除
+    Archive,
+    /// 只删除
+    Delete,
+}
+
+/// 任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// 归档/批量删除任务及其进度
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveTask {
+    pub id: String,
+    pub mode: ArchiveMode,
+    pub filter: CallLogFilter,
+    pub status: ArchiveStatus,
+    /// 任务开始时匹配的行数
+    pub total: i64,
+    pub exported: i64,
+    pub deleted: i64,
+    /// 归档文件路径（仅归档任务）
+    pub file: Option<String>,
+    pub error: Option<String>,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    #[serde(skip)]
+    finished: Option<Instant>,
+}
+
+lazy_static! {
+    // 本进程内启动过的任务，按任务 id 分组
+    static ref ARCHIVE_TASKS: Mutex<HashMap<String, ArchiveTask>> = Mutex::new(HashMap::new());
+}
+
+/// 归档目录，可通过 `CALL_LOG_ARCHIVE_DIR` 配置
+pub fn archive_dir_from_env() -> PathBuf {
+    std::env::var("CALL_LOG_ARCHIVE_DIR")
+        .ok()
+        .filter(|dir| !dir.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_ARCHIVE_DIR))
+}
+
+// 清理超过保留时长的已结束任务
+fn purge_expired(tasks: &mut HashMap<String, ArchiveTask>) {
+    tasks.retain(|_, task| task.finished.is_none_or(|finished| finished.elapsed() < ARCHIVE_TASK_TTL));
+}
+
+/// 查询任务进度
+pub fn get_archive_task(id: &str) -> Option<ArchiveTask> {
+    let mut tasks = ARCHIVE_TASKS.lock().unwrap();
+    purge_expired(&mut tasks);
+    tasks.get(id).cloned()
+}
+
+/// 列出所有任务，最近启动的在前
+pub fn list_archive_tasks() -> Vec<ArchiveTask> {
+    let mut tasks = ARCHIVE_TASKS.lock().unwrap();
+    purge_expired(&mut tasks);
+    let mut tasks: Vec<ArchiveTask> = tasks.values().cloned().collect();
+    tasks.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+    tasks
+}
+
+fn update_task(id: &str, update: impl FnOnce(&mut ArchiveTask)) {
+    if let Some(task) = ARCHIVE_TASKS.lock().unwrap().get_mut(id) {
+        update(task);
+    }
+}
+
+fn now() -> String {
+    chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// 启动后台任务并立即返回任务信息
+///
+/// 结束时间晚于当前时间时收紧到任务开始时刻，导出和删除使用同一范围，
+/// 任务运行期间新写入的日志不会在未导出的情况下被删除
+pub fn start_archive_task(
+    pool: Arc<SqlitePool>,
+    mode: ArchiveMode,
+    mut filter: CallLogFilter,
+    archive_dir: PathBuf,
+    batch_size: i64,
+) -> ArchiveTask {
+    let started_at = now();
+    if filter.end.as_ref().is_none_or(|end| *end > started_at) {
+        filter.end = Some(started_at.clone());
+    }
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let file = (mode == ArchiveMode::Archive)
+        .then(|| archive_dir.join(format!("call_logs_{}.jsonl", id)).to_string_lossy().into_owned());
+    let task = ArchiveTask {
+        id: id.clone(),
+        mode,
+        filter,
+        status: ArchiveStatus::Running,
+        total: 0,
+        exported: 0,
+        deleted: 0,
+        file,
+        error: None,
+        started_at,
+        finished_at: None,
+        finished: None,
+    };
+    {
+        let mut tasks = ARCHIVE_TASKS.lock().unwrap();
+        purge_expired(&mut tasks);
+        tasks.insert(id.clone(), task.clone());
+    }
+
+    let spawned = task.clone();
+    tokio::spawn(async move {
+        let result = run_archive_task(&pool, &spawned, batch_size.max(1)).await;
+        let mode = if spawned.mode == ArchiveMode::Archive { "archive" } else { "delete" };
+        match result {
+            Ok(()) => {
+                metrics().incr_counter("llm_gateway_call_log_archive_tasks_total", &[("mode", mode), ("result", "completed")]);
+                update_task(&spawned.id, |task| {
+                    task.status = ArchiveStatus::Completed;
+                    task.finished_at = Some(now());
+                    task.finished = Some(Instant::now());
+                });
+                info!(job = JOB_NAME, task_id = %spawned.id, mode, "Call log archive task completed");
+            }
+            Err(e) => {
+                metrics().incr_counter("llm_gateway_call_log_archive_tasks_total", &[("mode", mode), ("result", "failed")]);
+                error!(job = JOB_NAME, task_id = %spawned.id, mode, error = %e, "Call log archive task failed");
+                update_task(&spawned.id, |task| {
+                    task.status = ArchiveStatus::Failed;
+                    task.error = Some(format!("{:#}", e));
+                    task.finished_at = Some(now());
+                    task.finished = Some(Instant::now());
+                });
+            }
+        }
+    });
+
+    task
+}
+
+async fn run_archive_task(pool: &SqlitePool, task: &ArchiveTask, batch_size: i64) -> anyhow::Result<()> {
+    let total = count_call_logs_by_filter(pool, &task.filter).await?;
+    update_task(&task.id, |t| t.total = total);
+
+    if let Some(file) = &task.file {
+        let exported = export_call_logs(pool, &task.id, &task.filter, Path::new(file)).await?;
+        update_task(&task.id, |t| t.exported = exported);
+    }
+
+    let mut deleted = 0;
+    loop {
+        let rows = delete_call_logs_by_filter_batch(pool, &task.filter, batch_size).await?;
+        if rows == 0 {
+            break;
+        }
+        deleted += rows as i64;
+        update_task(&task.id, |t| t.deleted = deleted);
+        tokio::task::yield_now().await;
+    }
+    Ok(())
+}
+
+// 逐行导出为 JSON Lines，返回导出的行数
+async fn export_call_logs(pool: &SqlitePool, task_id: &str, filter: &CallLogFilter, path: &Path) -> anyhow::Result<i64> {
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let mut writer = BufWriter::new(tokio::fs::File::create(path).await?);
+
+    let mut rows = stream_call_logs_by_filter(pool, filter);
+    let mut exported = 0;
+    while let Some(call_log) = rows.try_next().await? {
+        let mut line = serde_json::to_vec(&call_log)?;
+        line.push(b'\n');
+        writer.write_all(&line).await?;
+        exported += 1;
+        if exported % PROGRESS_INTERVAL == 0 {
+            update_task(task_id, |t| t.exported = exported);
+        }
+    }
+    writer.flush().await?;
+    Ok(exported)
+}
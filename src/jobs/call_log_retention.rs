@@ -0,0 +1,125 @@
+//! # 调用日志保留期
+//!
+//! 每天定时把超过保留天数的调用日志按天汇总到 `call_log_daily_stats`，再删除这些原始日志。
+//! 保留天数配置在 system_configs 中（category 为 `call_log_retention`，key_name 为 `retention_days`），
+//! 每次执行时读取，未配置或配置为 0 时不删除任何日志
+
+use std::sync::Arc;
+use chrono::{DateTime, Duration as ChronoDuration, Local, Utc};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use crate::dao::call_log::{delete_old_call_logs, rollup_call_logs_before};
+use crate::dao::system_config::get_system_config_value;
+use crate::jobs::duration_until_next_daily_run;
+use crate::metrics::metrics;
+
+/// 任务名称（用于日志）
+pub const JOB_NAME: &str = "call_log_retention";
+
+/// 保留期配置在 system_configs 中的 category
+pub const RETENTION_CONFIG_CATEGORY: &str = "call_log_retention";
+
+/// 保留天数配置的 key_name
+pub const RETENTION_DAYS_KEY: &str = "retention_days";
+
+/// 默认执行时间：本地时间凌晨 4 点
+pub const DEFAULT_RETENTION_HOUR: u32 = 4;
+
+/// 一次保留期清理的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct RetentionReport {
+    /// 早于该日期（UTC，`YYYY-MM-DD`，不包含）的日志被汇总并删除
+    pub cutoff: String,
+    /// 写入的按天汇总行数
+    pub rolled_up_rows: u64,
+    /// 删除的原始日志行数
+    pub purged_rows: u64,
+}
+
+/// 保留 `retention_days` 天时的截止日期：今天（UTC）往前 `retention_days` 天的零点
+pub fn retention_cutoff(now: DateTime<Utc>, retention_days: u32) -> String {
+    (now.date_naive() - ChronoDuration::days(retention_days as i64))
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+/// 读取保留天数，未配置、配置为 0 或配置无效时返回 None
+pub async fn get_retention_days(pool: &SqlitePool) -> sqlx::Result<Option<u32>> {
+    let value = get_system_config_value(pool, RETENTION_CONFIG_CATEGORY, RETENTION_DAYS_KEY).await?;
+    Ok(value.and_then(|value| match value.trim().parse::<u32>() {
+        Ok(0) => None,
+        Ok(days) => Some(days),
+        Err(_) => {
+            warn!(job = JOB_NAME, value = %value, "Ignoring invalid call log retention days");
+            None
+        }
+    }))
+}
+
+/// 汇总并删除超过保留天数的调用日志；先汇总再删除，删除失败时下次执行会重新汇总同样的日期，不会重复计数
+pub async fn run_call_log_retention(pool: &SqlitePool, retention_days: u32) -> anyhow::Result<RetentionReport> {
+    let cutoff = retention_cutoff(Utc::now(), retention_days);
+    let result = async {
+        let rolled_up_rows = rollup_call_logs_before(pool, &cutoff).await?;
+        let purged_rows = delete_old_call_logs(pool, &cutoff).await?;
+        Ok::<_, sqlx::Error>(RetentionReport { cutoff: cutoff.clone(), rolled_up_rows, purged_rows })
+    }.await;
+
+    let registry = metrics();
+    match result {
+        Ok(report) => {
+            registry.incr_counter("llm_gateway_call_log_retention_runs_total", &[("result", "ok")]);
+            registry.add_counter("llm_gateway_call_log_retention_purged_rows_total", &[], report.purged_rows);
+            registry.set_gauge("llm_gateway_call_log_retention_last_purged_rows", &[], report.purged_rows as f64);
+            registry.set_gauge("llm_gateway_call_log_retention_last_run_timestamp", &[], Local::now().timestamp() as f64);
+            info!(
+                job = JOB_NAME, cutoff = %report.cutoff, rolled_up_rows = report.rolled_up_rows,
+                purged_rows = report.purged_rows, "Call log retention finished"
+            );
+            Ok(report)
+        }
+        Err(e) => {
+            registry.incr_counter("llm_gateway_call_log_retention_runs_total", &[("result", "error")]);
+            Err(e.into())
+        }
+    }
+}
+
+/// 按 system_configs 中的保留天数执行一次清理，未配置保留期时返回 None
+pub async fn run_configured_call_log_retention(pool: &SqlitePool) -> anyhow::Result<Option<RetentionReport>> {
+    match get_retention_days(pool).await? {
+        Some(retention_days) => Ok(Some(run_call_log_retention(pool, retention_days).await?)),
+        None => Ok(None),
+    }
+}
+
+/// 启动每日定时清理任务
+pub fn spawn_call_log_retention(pool: Arc<SqlitePool>, hour: u32) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let wait = duration_until_next_daily_run(Local::now(), hour);
+            info!(job = JOB_NAME, wait_secs = wait.as_secs(), "Next call log retention scheduled");
+            tokio::time::sleep(wait).await;
+
+            if let Err(e) = run_configured_call_log_retention(&pool).await {
+                error!(job = JOB_NAME, error = %e, "Call log retention failed");
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_retention_cutoff() {
+        let now = Utc.with_ymd_and_hms(2025, 3, 10, 23, 30, 0).unwrap();
+        assert_eq!(retention_cutoff(now, 30), "2025-02-08");
+        assert_eq!(retention_cutoff(now, 1), "2025-03-09");
+    }
+}
@@ -0,0 +1,38 @@
+//! # 调用方用量写回
+//!
+//! 定期把内存中累积的调用方请求数和 token 数批量写回 `consumer_usage` 表，供用量看板查询，
+//! 并清理空闲调用方的计数，避免按客户端地址计数的调用方不断累积
+
+use std::sync::Arc;
+use std::time::Duration;
+use sqlx::SqlitePool;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info};
+
+use crate::llm_api::utils::consumer_quota::{flush_consumer_usage, get_consumer_quotas};
+
+/// 任务名称（用于日志）
+pub const JOB_NAME: &str = "consumer_usage_flush";
+
+/// 默认写回间隔
+pub const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 超过该时间没有请求的计数被清理（有每日 token 额度的调用方保留到当天结束）
+pub const COUNTER_IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// 启动定期写回任务
+pub fn spawn_consumer_usage_flusher(pool: Arc<SqlitePool>, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        info!(job = JOB_NAME, interval_secs = interval.as_secs(), "Consumer usage flusher started");
+        loop {
+            tokio::time::sleep(interval).await;
+            if let Err(e) = flush_consumer_usage(&pool).await {
+                error!(job = JOB_NAME, error = %e, "Consumer usage flush failed");
+            }
+            let evicted = get_consumer_quotas().evict_idle(COUNTER_IDLE_TIMEOUT);
+            if evicted > 0 {
+                debug!(job = JOB_NAME, evicted, "Evicted idle consumer counters");
+            }
+        }
+    })
+}
@@ -0,0 +1,106 @@
+//! # Key Pool 夜间完整性审计
+//!
+//! 启动时和每天定时校验所有已存储 API Key 的密文和哈希，
+//! 用于发现损坏的密文或主密钥变更后无法解密的 Key，停用确认损坏的 Key，结果写入指标，发现问题时发送通知
+
+use std::sync::Arc;
+use chrono::Local;
+use sqlx::SqlitePool;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use crate::dao::provider_key_pool::{audit_key_pool_integrity, deactivate_corrupted_keys, KeyIntegrityReport};
+use crate::jobs::duration_until_next_daily_run;
+use crate::metrics::metrics;
+use crate::notification::{notification_center, Notification, NotificationLevel};
+
+/// 任务名称（用于日志和通知来源）
+pub const JOB_NAME: &str = "key_integrity_audit";
+
+/// 默认执行时间：本地时间凌晨 3 点
+pub const DEFAULT_AUDIT_HOUR: u32 = 3;
+
+/// 执行一次审计并上报指标，审计失败或发现损坏的 Key 时发送通知；`deactivate` 为 true 时停用未通过校验的 Key
+pub async fn run_key_integrity_audit(pool: &SqlitePool, deactivate: bool) -> anyhow::Result<KeyIntegrityReport> {
+    let mut report = match audit_key_pool_integrity(pool).await {
+        Ok(report) => report,
+        Err(e) => {
+            metrics().incr_counter("llm_gateway_key_audit_runs_total", &[("result", "error")]);
+            notification_center()
+                .notify(Notification::new(
+                    NotificationLevel::Warning,
+                    JOB_NAME,
+                    "Key pool integrity audit failed to run",
+                    format!("Failed to load key pools: {}", e),
+                ))
+                .await;
+            return Err(e.into());
+        }
+    };
+
+    if deactivate && !report.is_healthy() {
+        match deactivate_corrupted_keys(pool, &mut report).await {
+            Ok(count) => metrics().add_counter("llm_gateway_key_audit_deactivated_keys_total", &[], count as u64),
+            Err(e) => error!(job = JOB_NAME, error = %e, "Failed to deactivate corrupted keys"),
+        }
+    }
+
+    record_metrics(&report);
+
+    // 审计通过时只记录指标和日志，不发送通知
+    if report.is_healthy() {
+        metrics().incr_counter("llm_gateway_key_audit_runs_total", &[("result", "healthy")]);
+        info!(job = JOB_NAME, total_keys = report.total_keys, "Key pool integrity audit passed");
+    } else {
+        metrics().incr_counter("llm_gateway_key_audit_runs_total", &[("result", "unhealthy")]);
+        let affected: Vec<String> = report
+            .failures
+            .iter()
+            .map(|f| format!("{}:{} ({})", f.provider, f.key_pool_id, f.issue))
+            .collect();
+        notification_center()
+            .notify(Notification::new(
+                NotificationLevel::Critical,
+                JOB_NAME,
+                "Key pool integrity audit found corrupted keys",
+                format!(
+                    "{} of {} keys failed (decrypt failures: {}, hash mismatches: {}, unverifiable: {}, deactivated: {}): {}",
+                    report.failures.len(),
+                    report.total_keys,
+                    report.decrypt_failures,
+                    report.hash_mismatches,
+                    report.unverifiable_keys,
+                    report.deactivated_keys,
+                    affected.join(", ")
+                ),
+            ))
+            .await;
+    }
+
+    Ok(report)
+}
+
+fn record_metrics(report: &KeyIntegrityReport) {
+    let registry = metrics();
+    registry.set_gauge("llm_gateway_key_audit_total_keys", &[], report.total_keys as f64);
+    registry.set_gauge("llm_gateway_key_audit_healthy_keys", &[], report.healthy_keys as f64);
+    registry.set_gauge("llm_gateway_key_audit_failed_keys", &[("issue", "decrypt_failed")], report.decrypt_failures as f64);
+    registry.set_gauge("llm_gateway_key_audit_failed_keys", &[("issue", "hash_mismatch")], report.hash_mismatches as f64);
+    registry.set_gauge("llm_gateway_key_audit_failed_keys", &[("issue", "unverifiable")], report.unverifiable_keys as f64);
+    registry.set_gauge("llm_gateway_key_audit_last_run_timestamp", &[], Local::now().timestamp() as f64);
+}
+
+/// 启动每日定时审计任务
+pub fn spawn_nightly_key_integrity_audit(pool: Arc<SqlitePool>, hour: u32) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let wait = duration_until_next_daily_run(Local::now(), hour);
+            info!(job = JOB_NAME, wait_secs = wait.as_secs(), "Next key integrity audit scheduled");
+            tokio::time::sleep(wait).await;
+
+            if let Err(e) = run_key_integrity_audit(&pool, true).await {
+                error!(job = JOB_NAME, error = %e, "Key integrity audit failed");
+            }
+        }
+    })
+}
@@ -0,0 +1,30 @@
+//! # Key 使用统计写回
+//!
+//! 定期把内存中累积的 API Key 使用次数批量写回数据库
+
+use std::sync::Arc;
+use std::time::Duration;
+use sqlx::SqlitePool;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use crate::dao::provider_key_pool::flush_key_usage;
+
+/// 任务名称（用于日志）
+pub const JOB_NAME: &str = "key_usage_flush";
+
+/// 默认写回间隔
+pub const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 启动定期写回任务
+pub fn spawn_key_usage_flusher(pool: Arc<SqlitePool>, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        info!(job = JOB_NAME, interval_secs = interval.as_secs(), "Key usage flusher started");
+        loop {
+            tokio::time::sleep(interval).await;
+            if let Err(e) = flush_key_usage(&pool).await {
+                error!(job = JOB_NAME, error = %e, "Key usage flush failed");
+            }
+        }
+    })
+}
@@ -0,0 +1,47 @@
+//! # 后台定时任务
+//!
+//! 网关进程内运行的周期性维护任务
+
+pub mod batch_chat;
+pub mod call_log_archive;
+pub mod call_log_retention;
+pub mod consumer_usage_flush;
+pub mod key_integrity_audit;
+pub mod key_usage_flush;
+pub mod model_cache_reconcile;
+pub mod model_health_check;
+pub mod prompt_cache_warmup;
+pub mod route_script_reload;
+
+use chrono::{DateTime, Duration as ChronoDuration, Local, NaiveTime, TimeZone};
+use std::time::Duration;
+
+/// 计算距离下一个每日执行时间点（本地时间 `hour` 点整）的等待时长
+pub fn duration_until_next_daily_run(now: DateTime<Local>, hour: u32) -> Duration {
+    let run_time = NaiveTime::from_hms_opt(hour.min(23), 0, 0).unwrap_or(NaiveTime::MIN);
+    let mut next_date = now.date_naive();
+    if now.time() >= run_time {
+        next_date += ChronoDuration::days(1);
+    }
+
+    let next_run = Local
+        .from_local_datetime(&next_date.and_time(run_time))
+        .earliest()
+        .unwrap_or_else(|| now + ChronoDuration::days(1));
+
+    (next_run - now).to_std().unwrap_or(Duration::from_secs(24 * 60 * 60))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duration_until_next_daily_run() {
+        let now = Local.with_ymd_and_hms(2025, 3, 10, 1, 30, 0).unwrap();
+        assert_eq!(duration_until_next_daily_run(now, 3), Duration::from_secs(90 * 60));
+
+        let now = Local.with_ymd_and_hms(2025, 3, 10, 3, 0, 0).unwrap();
+        assert_eq!(duration_until_next_daily_run(now, 3), Duration::from_secs(24 * 60 * 60));
+    }
+}
@@ -0,0 +1,56 @@
+//! # 模型缓存对账
+//!
+//! 管理接口修改模型时已同步写入缓存；本任务定期按数据库对账模型缓存，
+//! 修正绕过管理接口的修改（例如直接改库或其他实例写入）造成的不一致
+
+use std::sync::Arc;
+use std::time::Duration;
+use sqlx::SqlitePool;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use crate::dao::model::{reconcile_model_cache, ModelCacheReconcileReport};
+use crate::metrics::metrics;
+
+/// 任务名称（用于日志）
+pub const JOB_NAME: &str = "model_cache_reconcile";
+
+/// 默认对账间隔
+pub const DEFAULT_RECONCILE_INTERVAL: Duration = Duration::from_secs(300);
+
+/// 执行一次对账，并记录修正的条目数
+pub async fn run_model_cache_reconcile(pool: &SqlitePool) -> anyhow::Result<ModelCacheReconcileReport> {
+    let report = match reconcile_model_cache(pool).await {
+        Ok(report) => report,
+        Err(e) => {
+            metrics().incr_counter("llm_gateway_model_cache_reconcile_runs_total", &[("result", "error")]);
+            return Err(e);
+        }
+    };
+
+    let registry = metrics();
+    registry.incr_counter("llm_gateway_model_cache_reconcile_runs_total", &[("result", "ok")]);
+    registry.add_counter("llm_gateway_model_cache_reconciled_total", &[("action", "refreshed")], report.refreshed as u64);
+    registry.add_counter("llm_gateway_model_cache_reconciled_total", &[("action", "evicted")], report.evicted as u64);
+    registry.add_counter("llm_gateway_model_cache_reconciled_total", &[("action", "refreshed_index")], report.refreshed_indexes as u64);
+    if !report.is_consistent() {
+        warn!(
+            job = JOB_NAME, refreshed = report.refreshed, evicted = report.evicted,
+            refreshed_indexes = report.refreshed_indexes, "Model cache drifted from database, re-synced"
+        );
+    }
+    Ok(report)
+}
+
+/// 启动模型缓存对账任务
+pub fn spawn_model_cache_reconciler(pool: Arc<SqlitePool>, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        info!(job = JOB_NAME, interval_secs = interval.as_secs(), "Model cache reconciler started");
+        loop {
+            tokio::time::sleep(interval).await;
+            if let Err(e) = run_model_cache_reconcile(&pool).await {
+                error!(job = JOB_NAME, error = %e, "Model cache reconcile failed");
+            }
+        }
+    })
+}
@@ -0,0 +1,169 @@
+//! # 模型健康检查
+//!
+//! 按每个模型的 `health_check_interval_seconds` 定期探测活跃模型，更新 `health_status` 和
+//! `last_health_check`；探测成功标记为 healthy，连续失败达到阈值后标记为 unhealthy 并发送通知
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use sqlx::SqlitePool;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use crate::dao::model::{list_models, update_model_health, sync_model_health_to_cache, Model, HEALTH_HEALTHY, HEALTH_UNHEALTHY};
+use crate::dao::provider::get_provider_by_name;
+use crate::dao::provider_key_pool::get_api_key_round_robin;
+use crate::llm_api::utils::health_probe::{default_base_url, probe_model, ProbeKind};
+use crate::metrics::metrics;
+use crate::notification::{notification_center, Notification, NotificationLevel};
+
+/// 任务名称（用于日志和通知来源）
+pub const JOB_NAME: &str = "model_health_check";
+
+/// 默认调度间隔，每次只探测到期的模型
+pub const DEFAULT_CHECK_TICK: Duration = Duration::from_secs(30);
+
+/// 默认连续失败多少次后标记为 unhealthy
+pub const DEFAULT_FAILURE_THRESHOLD: u32 = 3;
+
+/// 模型未配置检查间隔时的默认值（与表默认值一致）
+const DEFAULT_MODEL_CHECK_INTERVAL_SECS: i64 = 300;
+
+/// 记录每个模型的连续失败次数和上次探测时间
+pub struct ModelHealthTracker {
+    failure_threshold: u32,
+    failures: HashMap<String, u32>,
+    last_checked: HashMap<String, Instant>,
+    client: reqwest::Client,
+}
+
+impl ModelHealthTracker {
+    pub fn new(failure_threshold: u32) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            failures: HashMap::new(),
+            last_checked: HashMap::new(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// 某个模型当前的连续失败次数
+    pub fn consecutive_failures(&self, model_id: &str) -> u32 {
+        self.failures.get(model_id).copied().unwrap_or(0)
+    }
+
+    // 是否到了该模型的检查时间（从未检查过的模型立即检查）
+    fn is_due(&self, model: &Model, now: Instant) -> bool {
+        let interval = model.health_check_interval_seconds
+            .unwrap_or(DEFAULT_MODEL_CHECK_INTERVAL_SECS)
+            .max(1) as u64;
+        self.last_checked
+            .get(&model.id)
+            .is_none_or(|last| now.duration_since(*last) >= Duration::from_secs(interval))
+    }
+
+    /// 探测一个模型并更新健康状态，返回写入的状态；不支持探测的供应商返回 None
+    pub async fn check_model(&mut self, pool: &SqlitePool, model: &Model) -> anyhow::Result<Option<String>> {
+        if ProbeKind::for_provider(&model.provider).is_none() {
+            return Ok(None);
+        }
+        self.last_checked.insert(model.id.clone(), Instant::now());
+
+        let result = match resolve_base_url(pool, model).await? {
+            Some(base_url) => {
+                let api_key = match ProbeKind::for_provider(&model.provider) {
+                    Some(ProbeKind::CompatibleModels) => get_api_key_round_robin(&model.provider).await.map(|(key, _)| key),
+                    _ => None,
+                };
+                probe_model(&self.client, model, &base_url, api_key.as_deref()).await
+            }
+            None => Err("no base_url configured".to_string()),
+        };
+
+        let previous = model.health_status.clone().unwrap_or_else(|| "unknown".to_string());
+        let status = match result {
+            Ok(()) => {
+                metrics().incr_counter("llm_gateway_model_health_checks_total", &[("provider", &model.provider), ("result", "ok")]);
+                self.failures.remove(&model.id);
+                if previous == HEALTH_UNHEALTHY {
+                    notify(NotificationLevel::Info, "Model recovered", format!("{}:{} is healthy again", model.provider, model.name)).await;
+                }
+                HEALTH_HEALTHY.to_string()
+            }
+            Err(reason) => {
+                metrics().incr_counter("llm_gateway_model_health_checks_total", &[("provider", &model.provider), ("result", "failed")]);
+                let failures = self.failures.entry(model.id.clone()).or_insert(0);
+                *failures += 1;
+                warn!(job = JOB_NAME, model = %model.name, provider = %model.provider, failures = *failures, reason = %reason, "Model health check failed");
+
+                if *failures < self.failure_threshold {
+                    previous
+                } else {
+                    if previous != HEALTH_UNHEALTHY {
+                        notify(
+                            NotificationLevel::Warning,
+                            "Model marked unhealthy",
+                            format!("{}:{} failed {} consecutive health checks: {}", model.provider, model.name, failures, reason),
+                        ).await;
+                    }
+                    HEALTH_UNHEALTHY.to_string()
+                }
+            }
+        };
+
+        update_model_health(pool, &model.id, &status).await?;
+        // 调度器从缓存读取健康状态，写库后同步更新
+        sync_model_health_to_cache(model, &status).await?;
+        Ok(Some(status))
+    }
+
+    /// 探测所有到期的活跃模型，返回探测的模型数量
+    pub async fn run_due_checks(&mut self, pool: &SqlitePool) -> anyhow::Result<usize> {
+        let now = Instant::now();
+        let models: Vec<Model> = list_models(pool).await?
+            .into_iter()
+            .filter(|model| model.is_active && self.is_due(model, now))
+            .collect();
+
+        let mut checked = 0;
+        for model in &models {
+            match self.check_model(pool, model).await {
+                Ok(Some(_)) => checked += 1,
+                Ok(None) => {}
+                Err(e) => error!(job = JOB_NAME, model = %model.name, error = %e, "Failed to update model health"),
+            }
+        }
+        Ok(checked)
+    }
+}
+
+// 模型自身的 base_url 优先，其次是供应商配置，最后是供应商默认地址
+async fn resolve_base_url(pool: &SqlitePool, model: &Model) -> anyhow::Result<Option<String>> {
+    if let Some(base_url) = model.base_url.as_deref().filter(|url| !url.trim().is_empty()) {
+        return Ok(Some(base_url.trim().to_string()));
+    }
+    let provider_url = get_provider_by_name(pool, &model.provider).await?
+        .and_then(|provider| provider.base_url)
+        .filter(|url| !url.trim().is_empty());
+    Ok(provider_url.or_else(|| default_base_url(&model.provider).map(str::to_string)))
+}
+
+async fn notify(level: NotificationLevel, title: &str, message: String) {
+    notification_center()
+        .notify(Notification::new(level, JOB_NAME, title, message))
+        .await;
+}
+
+/// 启动模型健康检查任务
+pub fn spawn_model_health_checker(pool: Arc<SqlitePool>, tick: Duration, failure_threshold: u32) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        info!(job = JOB_NAME, tick_secs = tick.as_secs(), failure_threshold, "Model health checker started");
+        let mut tracker = ModelHealthTracker::new(failure_threshold);
+        loop {
+            if let Err(e) = tracker.run_due_checks(&pool).await {
+                error!(job = JOB_NAME, error = %e, "Model health check run failed");
+            }
+            tokio::time::sleep(tick).await;
+        }
+    })
+}
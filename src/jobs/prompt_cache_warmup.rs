@@ -0,0 +1,181 @@
+//! # 提示词缓存预热
+//!
+//! 支持提示词缓存的供应商会缓存相同前缀（通常是很长的静态系统提示词），命中后首 token 延迟和费用都更低。
+//! 预热在真实流量到来前用该系统提示词发送一次最小请求（只生成 1 个 token），让供应商提前建立缓存；
+//! 供应商的缓存几分钟未命中就会失效，可以按间隔定期重新预热。预热任务只保存在当前进程内
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use lazy_static::lazy_static;
+use serde::Serialize;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::llm_api::dispatcher::{DispatchRequest, LLMDispatcher, LLMError, Provider};
+use crate::llm_api::utils::msg_structure::Message;
+use crate::metrics::metrics;
+
+/// 任务名称（用于日志）
+pub const JOB_NAME: &str = "prompt_cache_warmup";
+
+/// 默认预热间隔（主流供应商的提示词缓存约 5 分钟未命中即失效）
+pub const DEFAULT_WARMUP_INTERVAL: Duration = Duration::from_secs(240);
+
+/// 允许的最短预热间隔
+pub const MIN_WARMUP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 预热请求中的用户消息
+const WARMUP_USER_MESSAGE: &str = "ok";
+
+/// 预热任务及最近一次预热结果
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptCacheWarmup {
+    pub id: String,
+    pub provider: String,
+    pub model: String,
+    /// 系统提示词长度（字符数）
+    pub prompt_chars: usize,
+    /// 定期预热的间隔，只预热一次时为 None
+    pub interval_secs: Option<u64>,
+    pub runs: u64,
+    pub last_warmed_at: Option<String>,
+    pub last_latency_ms: Option<u64>,
+    pub last_error: Option<String>,
+    pub created_at: String,
+}
+
+struct WarmupEntry {
+    warmup: PromptCacheWarmup,
+    handle: Option<JoinHandle<()>>,
+}
+
+lazy_static! {
+    // 定期预热的任务，按任务 id 分组
+    static ref WARMUPS: Mutex<HashMap<String, WarmupEntry>> = Mutex::new(HashMap::new());
+}
+
+/// 供应商是否支持提示词缓存
+pub fn supports_prompt_cache(provider: &Provider) -> bool {
+    matches!(provider, Provider::OpenAI | Provider::Azure | Provider::Ali | Provider::Claude | Provider::Ollama)
+}
+
+/// 构造预热请求：系统提示词 + 极短的用户消息，只生成 1 个 token，不重试
+pub fn warmup_request(provider: Provider, model: String, system_prompt: &str) -> DispatchRequest {
+    let mut request = DispatchRequest::new(provider, model, vec![
+        Message::system(system_prompt.to_string()),
+        Message::user(WARMUP_USER_MESSAGE.to_string()),
+    ])
+    .with_temperature(0.0)
+    .with_max_tokens(1);
+    request.retry_count = Some(0);
+    request
+}
+
+/// 发送一次预热请求，返回耗时
+pub async fn warm_prompt_cache(dispatcher: &LLMDispatcher, request: DispatchRequest) -> Result<Duration, LLMError> {
+    let provider = request.provider.as_str().to_string();
+    let started = Instant::now();
+    let result = dispatcher.dispatch(request).await.map(|_| started.elapsed());
+    let status = if result.is_ok() { "ok" } else { "error" };
+    metrics().incr_counter("llm_gateway_prompt_cache_warmups_total", &[("provider", &provider), ("result", status)]);
+    result
+}
+
+fn now() -> String {
+    chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+// 记录一次预热结果
+fn record_run(warmup: &mut PromptCacheWarmup, result: &Result<Duration, LLMError>) {
+    warmup.runs += 1;
+    warmup.last_warmed_at = Some(now());
+    match result {
+        Ok(latency) => {
+            warmup.last_latency_ms = Some(latency.as_millis() as u64);
+            warmup.last_error = None;
+        }
+        Err(e) => warmup.last_error = Some(e.to_string()),
+    }
+}
+
+/// 立即预热一次；指定间隔时登记任务并在后台按间隔重新预热
+///
+/// 首次预热失败时返回错误，不登记定期任务
+pub async fn start_prompt_cache_warmup(
+    dispatcher: Arc<LLMDispatcher>,
+    provider: Provider,
+    model: String,
+    system_prompt: String,
+    interval: Option<Duration>,
+) -> Result<PromptCacheWarmup, LLMError> {
+    let mut warmup = PromptCacheWarmup {
+        id: uuid::Uuid::new_v4().to_string(),
+        provider: provider.as_str().to_string(),
+        model: model.clone(),
+        prompt_chars: system_prompt.chars().count(),
+        interval_secs: interval.map(|interval| interval.as_secs()),
+        runs: 0,
+        last_warmed_at: None,
+        last_latency_ms: None,
+        last_error: None,
+        created_at: now(),
+    };
+
+    let request = warmup_request(provider, model, &system_prompt);
+    let result = warm_prompt_cache(&dispatcher, request.clone()).await;
+    record_run(&mut warmup, &result);
+    result?;
+    info!(job = JOB_NAME, provider = %warmup.provider, model = %warmup.model, latency_ms = ?warmup.last_latency_ms, "Prompt cache warmed");
+
+    let Some(interval) = interval else {
+        return Ok(warmup);
+    };
+
+    // 先登记再启动后台任务，任务第一次更新结果时条目已存在
+    let id = warmup.id.clone();
+    WARMUPS.lock().unwrap().insert(id.clone(), WarmupEntry { warmup: warmup.clone(), handle: None });
+    let handle = tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+            let result = warm_prompt_cache(&dispatcher, request.clone()).await;
+            if let Err(e) = &result {
+                warn!(job = JOB_NAME, warmup_id = %id, error = %e, "Scheduled prompt cache warmup failed");
+            }
+            match WARMUPS.lock().unwrap().get_mut(&id) {
+                Some(entry) => record_run(&mut entry.warmup, &result),
+                None => break,
+            }
+        }
+    });
+    if let Some(entry) = WARMUPS.lock().unwrap().get_mut(&warmup.id) {
+        entry.handle = Some(handle);
+    }
+    Ok(warmup)
+}
+
+/// 列出定期预热任务，最早创建的在前
+pub fn list_prompt_cache_warmups() -> Vec<PromptCacheWarmup> {
+    let mut warmups: Vec<PromptCacheWarmup> = WARMUPS.lock().unwrap()
+        .values()
+        .map(|entry| entry.warmup.clone())
+        .collect();
+    warmups.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)));
+    warmups
+}
+
+/// 查询定期预热任务
+pub fn get_prompt_cache_warmup(id: &str) -> Option<PromptCacheWarmup> {
+    WARMUPS.lock().unwrap().get(id).map(|entry| entry.warmup.clone())
+}
+
+/// 停止定期预热任务，任务不存在时返回 false
+pub fn cancel_prompt_cache_warmup(id: &str) -> bool {
+    let Some(entry) = WARMUPS.lock().unwrap().remove(id) else {
+        return false;
+    };
+    if let Some(handle) = entry.handle {
+        handle.abort();
+    }
+    true
+}
@@ -0,0 +1,45 @@
+//! # 路由脚本热加载
+//!
+//! 定期检查路由脚本文件的修改时间，文件变化后重新编译加载；
+//! 编译失败时保留正在使用的脚本
+
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use crate::llm_api::utils::route_script::reload_route_script;
+use crate::metrics::metrics;
+
+/// 任务名称（用于日志）
+pub const JOB_NAME: &str = "route_script_reload";
+
+/// 默认检查间隔
+pub const DEFAULT_RELOAD_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 加载路由脚本并启动热加载任务
+pub fn spawn_route_script_watcher(path: PathBuf, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        info!(job = JOB_NAME, path = %path.display(), "Watching route script for changes");
+        let mut last_modified: Option<SystemTime> = None;
+
+        loop {
+            let modified = tokio::fs::metadata(&path).await.and_then(|m| m.modified());
+            match modified {
+                Ok(modified) if last_modified != Some(modified) => {
+                    last_modified = Some(modified);
+                    let result = reload_route_script(&path);
+                    let status = if result.is_ok() { "ok" } else { "error" };
+                    metrics().incr_counter("llm_gateway_route_script_reloads_total", &[("result", status)]);
+                    if let Err(e) = result {
+                        error!(job = JOB_NAME, error = %e, "Route script reload failed, keeping previous script");
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => error!(job = JOB_NAME, error = %e, "Failed to stat route script"),
+            }
+
+            tokio::time::sleep(interval).await;
+        }
+    })
+}
@@ -1,4 +1,9 @@
+pub mod api_types;
+pub mod config;
 pub mod dao;
 pub mod llm_api;
 pub mod logger;
+pub mod metrics;
+pub mod notification;
+pub mod jobs;
 pub mod web;
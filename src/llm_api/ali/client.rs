@@ -9,12 +9,16 @@ use serde_json::Value;
 use std::collections::HashMap;
 use std::fmt;
 use anyhow::Result;
+use futures_util::stream::BoxStream;
 use reqwest::Client;
 
+use crate::llm_api::openai::client::parse_stream_data;
 use crate::llm_api::utils::{
-    client::{BaseClient, ClientConfig, ClientError, LLMClientTrait},
+    client::{parse_line_stream, BaseClient, ClientConfig, ClientError, LLMClientTrait},
+    stream_accounting::OpenAIStreamAccounting,
     chat_traits::{ChatRequestTrait, ChatResponseTrait},
-    msg_structure::Message,
+    msg_structure::{Message, ToolCallDelta},
+    tool_structure::Tool,
 };
 
 /// 阿里云 Chat 请求结构体（OpenAI 兼容格式）
@@ -23,6 +27,7 @@ pub struct AliChatRequest {
     /// 要使用的模型名称，如 "qwen-plus", "qwen-turbo", "qwen-max" 等
     pub model: String,
     /// 对话消息列表
+    #[serde(serialize_with = "crate::llm_api::utils::client::serialize_messages")]
     pub messages: Vec<Message>,
     /// 是否使用流式输出
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -42,12 +47,34 @@ pub struct AliChatRequest {
     /// 停止生成的标记
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stop: Option<Vec<String>>,
+    /// 存在惩罚，取值 [-2.0, 2.0]，正值降低重复话题的概率
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
     /// 结果格式，支持 "text" 或 "message"
     #[serde(skip_serializing_if = "Option::is_none")]
     pub result_format: Option<String>,
+    /// 结构化输出格式，例如 {"type": "json_object"}（兼容模式下 JSON 输出由该字段控制，与 result_format 无关）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<Value>,
     /// 是否启用增量输出（流式输出专用）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub incremental_output: Option<bool>,
+    /// 流式输出选项
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<AliStreamOptions>,
+    /// 可供模型调用的工具
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,
+    /// 工具选择策略："auto"、"none"、"required" 或 {"type": "function", "function": {"name": ...}}
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<Value>,
+}
+
+/// 阿里云流式输出选项
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AliStreamOptions {
+    /// 是否在最后一个块中返回 token 使用统计
+    pub include_usage: bool,
 }
 
 impl AliChatRequest {
@@ -62,8 +89,13 @@ impl AliChatRequest {
             temperature: None,
             top_p: None,
             stop: None,
+            presence_penalty: None,
             result_format: None,
+            response_format: None,
             incremental_output: None,
+            stream_options: None,
+            tools: None,
+            tool_choice: None,
         }
     }
 
@@ -96,6 +128,18 @@ impl AliChatRequest {
         self.incremental_output = Some(incremental);
         self
     }
+
+    /// 设置可供模型调用的工具
+    pub fn with_tools(mut self, tools: Vec<Tool>) -> Self {
+        self.tools = Some(tools);
+        self
+    }
+
+    /// 设置流式输出是否返回 token 使用统计
+    pub fn with_stream_usage(mut self, include_usage: bool) -> Self {
+        self.stream_options = Some(AliStreamOptions { include_usage });
+        self
+    }
 }
 
 impl ChatRequestTrait for AliChatRequest {
@@ -149,6 +193,9 @@ impl ChatRequestTrait for AliChatRequest {
         if let Some(ref stop) = self.stop {
             options.insert("stop".to_string(), Value::from(stop.clone()));
         }
+        if let Some(presence_penalty) = self.presence_penalty {
+            options.insert("presence_penalty".to_string(), Value::from(presence_penalty));
+        }
         if let Some(ref result_format) = self.result_format {
             options.insert("result_format".to_string(), Value::from(result_format.clone()));
         }
@@ -185,6 +232,9 @@ impl ChatRequestTrait for AliChatRequest {
                 self.stop = Some(stop_strings);
             }
         }
+        if let Some(presence_penalty) = options.get("presence_penalty").and_then(|v| v.as_f64()) {
+            self.presence_penalty = Some(presence_penalty as f32);
+        }
         if let Some(result_format) = options.get("result_format").and_then(|v| v.as_str()) {
             self.result_format = Some(result_format.to_string());
         }
@@ -365,6 +415,9 @@ pub struct AliDelta {
     /// 增量内容
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content: Option<String>,
+    /// 工具调用增量，参数分多块返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCallDelta>>,
 }
 
 /// 阿里云客户端错误类型
@@ -389,6 +442,20 @@ impl fmt::Display for AliError {
     }
 }
 
+impl AliError {
+    /// 是否为限流或配额错误，此时应暂停使用当前 API Key
+    pub fn is_rate_limited(&self) -> bool {
+        match self {
+            AliError::Client(e) => e.is_rate_limited(),
+            AliError::Api(msg) => {
+                let msg = msg.to_lowercase();
+                msg.contains("rate") || msg.contains("quota") || msg.contains("throttl")
+            }
+            _ => false,
+        }
+    }
+}
+
 impl std::error::Error for AliError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
@@ -441,10 +508,11 @@ impl AliClient {
 
     /// 使用自定义配置创建客户端
     pub fn new_with_config(api_key: String, base_url: String, mut config: ClientConfig) -> Result<Self> {
-        // 确保设置了正确的认证头
+        // 确保设置了正确的认证头；不同 API Key 的客户端共用连接池
         config = config
             .add_header("Authorization".to_string(), format!("Bearer {}", api_key))
-            .add_header("Content-Type".to_string(), "application/json".to_string());
+            .add_header("Content-Type".to_string(), "application/json".to_string())
+            .with_default_shared_pool("ali");
 
         let base_client = BaseClient::new(config)?;
         
@@ -519,7 +587,7 @@ impl AliClient {
         let url = format!("{}/compatible-mode/v1/chat/completions", self.base_url);
 
         // 发送流式请求
-        self.base_client.post_stream(&url, &request, |line: String| {
+        self.base_client.post_stream(&url, &request, &OpenAIStreamAccounting, |line: String| {
             // 过滤空行和非数据行
             let line = line.trim();
             if line.is_empty() || !line.starts_with("data: ") {
@@ -550,6 +618,19 @@ impl AliClient {
         Ok(())
     }
 
+    /// 发送流式聊天请求，以 Stream 返回响应块；丢弃返回的流时停止读取
+    pub fn chat_stream_iter(
+        &self,
+        mut request: AliChatRequest,
+    ) -> Result<BoxStream<'static, Result<AliStreamResponse, AliError>>, AliError> {
+        request.set_stream(true);
+        request.validate().map_err(AliError::InvalidRequest)?;
+
+        let url = format!("{}/compatible-mode/v1/chat/completions", self.base_url);
+        let lines = self.base_client.post_stream_iter(&url, request, OpenAIStreamAccounting);
+        Ok(parse_line_stream(lines, parse_stream_data))
+    }
+
     /// 获取 API Key（用于调试，生产环境中应避免暴露）
     pub fn api_key(&self) -> &str {
         &self.api_key
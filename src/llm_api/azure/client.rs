@@ -0,0 +1,205 @@
+//! # Azure OpenAI 客户端
+//!
+//! Azure OpenAI 的请求/响应格式与 OpenAI 相同，区别在于：
+//! - 使用 `api-key` 请求头认证
+//! - 按部署（deployment）寻址：`{endpoint}/openai/deployments/{deployment}/chat/completions?api-version=...`
+//!
+//! 请求中的 `model` 字段即部署名称，模型到部署的映射由适配器完成
+
+use async_trait::async_trait;
+use anyhow::Result;
+use futures_util::stream::BoxStream;
+use reqwest::Client;
+
+use crate::llm_api::openai::client::{
+    handle_stream_line, parse_chat_response, parse_stream_data, OpenAIChatRequest, OpenAIChatResponse, OpenAIError, OpenAIStreamResponse,
+};
+use crate::llm_api::utils::{
+    client::{parse_line_stream, BaseClient, ClientConfig, LLMClientTrait},
+    stream_accounting::OpenAIStreamAccounting,
+    chat_traits::ChatRequestTrait,
+};
+
+/// Azure OpenAI 客户端
+pub struct AzureOpenAIClient {
+    /// 基础 HTTP 客户端
+    base_client: BaseClient,
+    /// API Key
+    api_key: String,
+    /// 资源地址，例如 https://my-resource.openai.azure.com
+    endpoint: String,
+    /// API 版本
+    api_version: String,
+}
+
+impl AzureOpenAIClient {
+    /// 默认 API 版本
+    pub const DEFAULT_API_VERSION: &'static str = "2024-06-01";
+
+    /// 使用默认 API 版本创建客户端
+    pub fn new(api_key: String, endpoint: String) -> Result<Self> {
+        Self::new_with_api_version(api_key, endpoint, Self::DEFAULT_API_VERSION.to_string())
+    }
+
+    /// 使用指定 API 版本创建客户端
+    pub fn new_with_api_version(api_key: String, endpoint: String, api_version: String) -> Result<Self> {
+        Self::new_with_config(api_key, endpoint, api_version, ClientConfig::new())
+    }
+
+    /// 使用自定义配置创建客户端
+    pub fn new_with_config(api_key: String, endpoint: String, api_version: String, config: ClientConfig) -> Result<Self> {
+        let config = Self::with_auth_headers(config, &api_key);
+        let base_client = BaseClient::new(config)?;
+
+        Ok(Self {
+            base_client,
+            api_key,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            api_version,
+        })
+    }
+
+    /// 使用自定义配置和 HTTP 客户端创建客户端（用于测试）
+    pub fn new_with_client(
+        api_key: String,
+        endpoint: String,
+        api_version: String,
+        config: ClientConfig,
+        client: Client,
+    ) -> Result<Self> {
+        let config = Self::with_auth_headers(config, &api_key);
+        let base_client = BaseClient::new_with_client(config, Some(client))?;
+
+        Ok(Self {
+            base_client,
+            api_key,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            api_version,
+        })
+    }
+
+    // Azure 使用 api-key 请求头认证
+    fn with_auth_headers(config: ClientConfig, api_key: &str) -> ClientConfig {
+        config
+            .add_header("api-key".to_string(), api_key.to_string())
+            .add_header("Content-Type".to_string(), "application/json".to_string())
+            .with_default_shared_pool("azure")
+    }
+
+    /// 部署的 chat completions 地址，部署名称和 API 版本经过百分号编码
+    pub fn deployment_url(&self, deployment: &str) -> String {
+        format!(
+            "{}/openai/deployments/{}/chat/completions?api-version={}",
+            self.endpoint, percent_encode(deployment), percent_encode(&self.api_version)
+        )
+    }
+
+    /// 发送聊天请求（非流式），`request.model` 为部署名称
+    pub async fn chat(&self, mut request: OpenAIChatRequest) -> Result<OpenAIChatResponse, OpenAIError> {
+        request.set_stream(false);
+        request.validate().map_err(OpenAIError::InvalidRequest)?;
+
+        let url = self.deployment_url(&request.model);
+        let response = self.base_client.post(&url, &request).await?;
+        let response_text = response.text().await.map_err(|e| {
+            OpenAIError::Api(format!("Failed to read response: {}", e))
+        })?;
+
+        parse_chat_response(&response_text)
+    }
+
+    /// 发送流式聊天请求，`request.model` 为部署名称
+    pub async fn chat_stream<F>(&self, mut request: OpenAIChatRequest, mut callback: F) -> Result<(), OpenAIError>
+    where
+        F: FnMut(OpenAIStreamResponse) -> bool + Send,
+    {
+        request.set_stream(true);
+        request.validate().map_err(OpenAIError::InvalidRequest)?;
+
+        let url = self.deployment_url(&request.model);
+        self.base_client.post_stream(&url, &request, &OpenAIStreamAccounting, |line: String| {
+            handle_stream_line(&line, &mut callback)
+        }).await?;
+
+        Ok(())
+    }
+
+    /// 发送流式聊天请求，以 Stream 返回响应块，`request.model` 为部署名称
+    pub fn chat_stream_iter(
+        &self,
+        mut request: OpenAIChatRequest,
+    ) -> Result<BoxStream<'static, Result<OpenAIStreamResponse, OpenAIError>>, OpenAIError> {
+        request.set_stream(true);
+        request.validate().map_err(OpenAIError::InvalidRequest)?;
+
+        let url = self.deployment_url(&request.model);
+        let lines = self.base_client.post_stream_iter(&url, request, OpenAIStreamAccounting);
+        Ok(parse_line_stream(lines, parse_stream_data))
+    }
+
+    /// 获取 API Key（用于调试，生产环境中应避免暴露）
+    pub fn api_key(&self) -> &str {
+        &self.api_key
+    }
+
+    /// 获取资源地址
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// 获取 API 版本
+    pub fn api_version(&self) -> &str {
+        &self.api_version
+    }
+}
+
+// 按 RFC 3986 对 URL 组成部分做百分号编码，只保留非保留字符，避免部署名称中的 `/`、`?`、`#` 等改变请求路径
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[async_trait]
+impl LLMClientTrait for AzureOpenAIClient {
+    type Request = OpenAIChatRequest;
+    type Response = OpenAIChatResponse;
+    type Error = OpenAIError;
+
+    async fn send_request(&self, request: Self::Request) -> Result<Self::Response, Self::Error> {
+        self.chat(request).await
+    }
+
+    async fn send_stream_request<F>(
+        &self,
+        request: Self::Request,
+        callback: F,
+    ) -> Result<(), Self::Error>
+    where
+        F: Fn(String) -> bool + Send + Sync,
+    {
+        self.chat_stream(request, |response| {
+            match serde_json::to_string(&response) {
+                Ok(json_str) => callback(json_str),
+                Err(_) => false,
+            }
+        }).await
+    }
+
+    fn validate_request(&self, request: &Self::Request) -> Result<(), Self::Error> {
+        request.validate().map_err(OpenAIError::InvalidRequest)
+    }
+
+    fn client_name(&self) -> &'static str {
+        "AzureOpenAI"
+    }
+
+    fn base_client(&self) -> &BaseClient {
+        &self.base_client
+    }
+}
@@ -3,82 +3,285 @@
 //! 统一的LLM API调度器，支持多个供应商的智能路由和负载均衡
 //! 支持Ollama、阿里云、OpenAI等多种LLM供应商
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, Semaphore};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use once_cell::sync::OnceCell;
 use async_trait::async_trait;
 use anyhow::Result;
 use std::fmt;
-
+use std::future::Future;
+use tokio::sync::mpsc;
+use tracing::{debug, field, info, info_span, instrument, warn, Instrument, Span};
+use crate::metrics::metrics;
+
+pub use crate::api_types::v1::dispatch::{CompletionRequest, ContextStrategy, DispatchRequest, DispatchResponse, JsonSchemaFormat, Provider, RequestPriority, ResponseFormat, StreamChunk, TokenUsage};
+use crate::api_types::v1::error::GatewayErrorCode;
+use crate::api_types::v1::estimate::CostEstimate;
 use crate::llm_api::utils::{
     client::ClientError,
-    msg_structure::Message,
-    chat_traits::{ChatRequestTrait, ChatResponseTrait},
-    client_pool::{ClientPool, DynamicAliClient},
+    chat_traits::{ChatRequestTrait, ChatResponseTrait, CompletionResponseTrait},
+    client_pool::{ClientPool, DynamicAliClient, DynamicAzureOpenAIClient, DynamicOpenAIClient, DynamicOpenAICompatibleClient},
+    abuse_guard::{get_abuse_guard, UserOutcome, DEFAULT_TENANT},
+    client::{CallMetadata, ProviderBilling, RetryBudget, CALL_METADATA},
+    language_detect::{detect_prompt_language, DetectedLanguage},
+    blocklist::{get_blocklist, reload_blocklist, BlocklistStreamFilter, BlocklistTarget},
+    transform_plugin::get_transform_pipeline,
+    route_script::get_route_script_engine,
+    degradation::get_degradation_guard,
+    usage_recorder::record_call_usage,
+    budget::ensure_within_budget,
+    cost_estimate::{ensure_within_max_cost, estimate_cost},
+    load_balancer::{get_load_balancer, LoadBalancePolicy},
+    traffic_split::{arm_label, TrafficSplit},
+    shadow::{record_shadow_comparison, ShadowMirror},
+    dispatch_hook::{DispatchHook, DispatchHooks},
+    concurrency_limit::{ProviderConcurrency, ProviderConcurrencyStats, ProviderPermit},
+    system_prompt_policy::resolve_policy,
+    param_constraints::resolve_param_constraints,
+    fallback_policy::{FallbackPolicy, ModelAliasFallback},
+    trace_context::{with_trace_parent, TraceParent},
+    project_scope::is_visible_to,
+    msg_structure::{Message, ToolCallAccumulator, ToolCallDelta},
+    structured_output::{check_response, repair_messages},
+    tokenizer::{context_window, count_message_tokens, count_request_tokens, count_text_tokens, count_tool_tokens, REPLY_PRIMING_TOKENS},
+    context_window::{truncate_history_by, TruncationStrategy},
 };
-use crate::llm_api::ali::client::{AliClient, AliChatRequest};
-use crate::llm_api::ollama::client::{OllamaClient, OllamaChatRequest};
+use crate::llm_api::ali::client::{AliClient, AliChatRequest, AliError, AliStreamResponse};
+use crate::llm_api::ollama::client::{OllamaClient, OllamaChatRequest, OllamaError, OllamaChatResponse, OllamaGenerateRequest, OllamaGenerateResponse};
+use crate::llm_api::openai::client::{OpenAIChatRequest, OpenAICompletionRequest, OpenAICompletionResponse, OpenAIError, OpenAIStreamResponse, OpenAIStreamOptions};
+use crate::llm_api::provider_registry::{provider_registry, ProviderConfig};
+use crate::config::gateway_config;
 use crate::dao::{init_sqlite_pool, init_db, SQLITE_POOL};
-use crate::dao::cache::init_global_cache;
-use crate::dao::provider_key_pool::preload::preload_provider_key_pools_to_cache;
+use crate::dao::cache::{init_global_cache, GLOBAL_CACHE};
+use crate::dao::model::{get_active_model_names_from_cache, get_model_health_from_cache, get_model_id_from_cache, get_model_project_from_cache, HEALTH_UNHEALTHY};
+use crate::dao::provider_key_pool::preload::{has_available_project_key, preload_provider_key_pools_to_cache};
+use crate::dao::provider::get_all_providers;
+use sqlx::SqlitePool;
+
+// 流式输出接收端
+pub type StreamReceiver = mpsc::Receiver<Result<StreamChunk, LLMError>>;
+
+// 流式通道缓冲大小
+const STREAM_CHANNEL_SIZE: usize = 64;
+
+// 上游回调写入、尚未转发给调用方的最大块数
+const STREAM_BACKLOG_LIMIT: usize = 1024;
+
+// 流式输出发送端（可在同步回调中使用）。回调无法等待，调用方消费过慢、积压超过 STREAM_BACKLOG_LIMIT 块时
+// 写入一个错误后关闭，回调写入失败即停止上游读取，避免积压无限增长
+#[derive(Clone)]
+pub struct StreamSink {
+    tx: mpsc::UnboundedSender<Result<StreamChunk, LLMError>>,
+    backlog: Arc<AtomicUsize>,
+    overflowed: Arc<AtomicBool>,
+}
 
-// 定义供应商枚举
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
-pub enum Provider {
-    Ollama,
-    Ali,
-    OpenAI,
-    Claude,
-    Gemini,
+impl StreamSink {
+    /// 写入一块输出；接收端已关闭或积压超过上限时返回 Err
+    pub fn send(&self, item: Result<StreamChunk, LLMError>) -> Result<(), LLMError> {
+        if self.overflowed.load(Ordering::Relaxed) {
+            return Err(LLMError::Cancelled);
+        }
+        if self.backlog.fetch_add(1, Ordering::Relaxed) >= STREAM_BACKLOG_LIMIT {
+            self.overflowed.store(true, Ordering::Relaxed);
+            warn!(limit = STREAM_BACKLOG_LIMIT, "Stream consumer too slow, stopping upstream");
+            let _ = self.tx.send(Err(LLMError::Overloaded("stream consumer too slow".to_string())));
+            return Err(LLMError::Cancelled);
+        }
+        self.tx.send(item).map_err(|_| LLMError::Cancelled)
+    }
 }
 
-// 定义请求参数
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DispatchRequest {
-    pub provider: Provider,
-    pub model: String,
-    pub messages: Vec<Message>,
-    pub stream: Option<bool>,               // 是否流式，默认false
-    pub temperature: Option<f32>,           // 控制随机性，0.0-2.0
-    pub max_tokens: Option<u32>,           // 最大生成token数
-    pub top_p: Option<f32>,                // nucleus sampling参数
-    pub frequency_penalty: Option<f32>,     // 频率惩罚
-    pub presence_penalty: Option<f32>,      // 存在惩罚
-    pub stop: Option<Vec<String>>,         // 停止词
-    pub timeout_ms: Option<u64>,           // 请求超时时间(毫秒)
-    pub retry_count: Option<u32>,          // 重试次数
-    pub context_window: Option<u32>,       // 上下文窗口大小
-}
-
-// 定义响应结构
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DispatchResponse {
-    pub content: String,
-    pub provider: Provider,
-    pub model: String,
-    pub usage: Option<TokenUsage>,
-    pub finish_reason: Option<String>,
-    pub request_id: Option<String>,
-    pub created_at: String,
-    pub total_duration: Option<u64>,
+// 上游要求等待超过该时长时不再重试，交给 fallback 处理
+const MAX_RETRY_WAIT: std::time::Duration = std::time::Duration::from_secs(30);
+
+// 启动流式任务：回调同步写入 StreamSink，由转发任务写入有界通道；
+// 接收端关闭后转发任务退出，回调写入失败即可停止上游读取
+fn spawn_stream<F, Fut>(run: F) -> StreamReceiver
+where
+    F: FnOnce(StreamSink) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(STREAM_CHANNEL_SIZE);
+    let (sink_tx, mut source) = mpsc::unbounded_channel();
+    let backlog = Arc::new(AtomicUsize::new(0));
+    let sink = StreamSink { tx: sink_tx, backlog: backlog.clone(), overflowed: Arc::new(AtomicBool::new(false)) };
+    tokio::spawn(async move {
+        while let Some(item) = source.recv().await {
+            if tx.send(item).await.is_err() {
+                break;
+            }
+            backlog.fetch_sub(1, Ordering::Relaxed);
+        }
+    });
+
+    // 保留调用记录附加信息和链路上下文
+    let metadata = CallMetadata::current();
+    let trace_parent = TraceParent::current();
+    tokio::spawn(with_trace_parent(trace_parent, CALL_METADATA.scope(metadata, run(sink))).instrument(Span::current()));
+    rx
 }
 
-// Token使用统计
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TokenUsage {
-    pub prompt_tokens: u32,
-    pub completion_tokens: u32,
-    pub total_tokens: u32,
+// 转发流式输出，上游结束后（调用记录已写入）按最后一块中的用量和结束原因回填调用记录；
+// 上游没有返回用量时按提示词和输出内容估算
+fn record_stream_usage(mut receiver: StreamReceiver, metadata: CallMetadata, provider: Provider, model: String, prompt_tokens: usize, permit: Option<ProviderPermit>) -> StreamReceiver {
+    let (tx, rx) = mpsc::channel(STREAM_CHANNEL_SIZE);
+    tokio::spawn(async move {
+        // 流结束或调用方断开前一直占用供应商的并发槽位
+        let _permit = permit;
+        let mut usage = None;
+        let mut finish_reason = None;
+        let mut content = String::new();
+        while let Some(item) = receiver.recv().await {
+            if let Ok(chunk) = &item {
+                content.push_str(&chunk.content);
+                if chunk.usage.is_some() {
+                    usage = chunk.usage.clone();
+                }
+                if chunk.finish_reason.is_some() {
+                    finish_reason = chunk.finish_reason.clone();
+                }
+            }
+            if tx.send(item).await.is_err() {
+                return;
+            }
+        }
+        let usage = usage.or_else(|| finish_reason.is_some().then(|| {
+            let completion_tokens = count_text_tokens(&content, &model) as u32;
+            TokenUsage {
+                prompt_tokens: prompt_tokens as u32,
+                completion_tokens,
+                total_tokens: prompt_tokens as u32 + completion_tokens,
+            }
+        }));
+        if let Some(usage) = usage {
+            record_call_usage(&metadata, &provider, &model, &usage, finish_reason.as_deref()).await;
+        }
+    });
+    rx
+}
+
+// 按响应黑名单过滤流式输出：增量内容打码后输出，命中拦截规则时以 ContentBlocked 结束并停止读取上游
+fn filter_stream_blocklist(mut receiver: StreamReceiver, mut blocklist: BlocklistStreamFilter) -> StreamReceiver {
+    let (tx, rx) = mpsc::channel(STREAM_CHANNEL_SIZE);
+    tokio::spawn(async move {
+        while let Some(item) = receiver.recv().await {
+            let item = match item {
+                Ok(mut chunk) => {
+                    let Some(mut content) = blocklist.push(&chunk.content) else {
+                        let _ = tx.send(Err(LLMError::ContentBlocked("response matched blocklist".to_string()))).await;
+                        return;
+                    };
+                    if chunk.finish_reason.is_some() {
+                        content.push_str(&blocklist.finish());
+                    }
+                    chunk.content = content;
+                    if chunk.content.is_empty() && chunk.finish_reason.is_none() && chunk.usage.is_none() && chunk.tool_calls.is_none() {
+                        continue;
+                    }
+                    Ok(chunk)
+                }
+                // 上游出错时先输出已检查的内容
+                Err(e) => {
+                    let rest = blocklist.finish();
+                    if !rest.is_empty() && tx.send(Ok(StreamChunk::delta(rest))).await.is_err() {
+                        return;
+                    }
+                    Err(e)
+                }
+            };
+            if tx.send(item).await.is_err() {
+                return;
+            }
+        }
+        // 上游没有发送结束块时补发剩余内容
+        let rest = blocklist.finish();
+        if !rest.is_empty() {
+            let _ = tx.send(Ok(StreamChunk::delta(rest))).await;
+        }
+    });
+    rx
+}
+
+// 转发流式输出，流结束后记录终端用户的请求结果（按结束原因和流中的错误分类）；调用方提前断开时按正常结果记录
+fn record_stream_outcome(mut receiver: StreamReceiver, tenant_id: String, user: String) -> StreamReceiver {
+    let (tx, rx) = mpsc::channel(STREAM_CHANNEL_SIZE);
+    tokio::spawn(async move {
+        let mut outcome = UserOutcome::Normal;
+        while let Some(item) = receiver.recv().await {
+            match &item {
+                Ok(chunk) if chunk.finish_reason.as_deref() == Some("content_filter") => outcome = UserOutcome::ContentViolation,
+                Ok(_) => {}
+                Err(e) => outcome = LLMDispatcher::classify_error(e),
+            }
+            if tx.send(item).await.is_err() {
+                break;
+            }
+        }
+        get_abuse_guard().record_outcome(&tenant_id, &user, outcome).await;
+    });
+    rx
+}
+
+// 将供应商返回的请求 ID 和原始用量记录到当前调用，供回填调用记录时一并保存以便账单对账
+fn record_provider_billing<U: Serialize>(request_id: Option<&str>, usage: Option<&U>) {
+    let usage = usage.and_then(|u| serde_json::to_value(u).ok());
+    if request_id.is_none() && usage.is_none() {
+        return;
+    }
+    CallMetadata::current().record_provider_billing(ProviderBilling {
+        request_id: request_id.map(str::to_string),
+        usage,
+    });
+}
+
+// 历史摘要的前缀，再次超出窗口时据此找到之前的摘要合并
+const SUMMARY_PREFIX: &str = "Summary of the earlier conversation:";
+
+// 生成历史摘要的提示词和长度上限
+const SUMMARY_INSTRUCTION: &str = "Summarize the following conversation concisely. Keep facts, decisions, names and open questions the assistant needs to continue the conversation.";
+const SUMMARY_MAX_TOKENS: u32 = 512;
+
+// 调用记录中请求摘要的最大字符数
+const REQUEST_SUMMARY_MAX_CHARS: usize = 200;
+
+// 请求摘要：最后一条用户消息，超长时截断
+fn summarize_request(request: &DispatchRequest) -> Option<String> {
+    let content = &request.messages.iter().rev().find(|m| m.role == "user")?.content;
+    Some(truncate_summary(content))
+}
+
+fn truncate_summary(content: &str) -> String {
+    if content.chars().count() <= REQUEST_SUMMARY_MAX_CHARS {
+        return content.to_string();
+    }
+    let mut summary: String = content.chars().take(REQUEST_SUMMARY_MAX_CHARS).collect();
+    summary.push('…');
+    summary
 }
 
 // 定义客户端适配器trait
 #[async_trait]
 pub trait LLMClientAdapter: Send + Sync {
     async fn generate(&self, request: &DispatchRequest) -> Result<DispatchResponse, LLMError>;
-    async fn generate_stream(&self, request: &DispatchRequest) -> Result<tokio::sync::mpsc::Receiver<Result<String, LLMError>>, LLMError>;
+    async fn generate_stream(&self, request: &DispatchRequest) -> Result<StreamReceiver, LLMError>;
     fn supported_models(&self) -> Vec<String>;
     fn provider_name(&self) -> Provider;
+    /// 是否从 Key 池轮询获取 API Key，Key 池中没有可用的 Key 时无法处理请求
+    fn uses_key_pool(&self) -> bool {
+        false
+    }
+    /// 文本补全，不支持的供应商返回 UnsupportedProvider
+    async fn complete(&self, _request: &CompletionRequest) -> Result<DispatchResponse, LLMError> {
+        Err(LLMError::UnsupportedProvider(self.provider_name()))
+    }
+    /// 流式文本补全，不支持的供应商返回 UnsupportedProvider
+    async fn complete_stream(&self, _request: &CompletionRequest) -> Result<StreamReceiver, LLMError> {
+        Err(LLMError::UnsupportedProvider(self.provider_name()))
+    }
 }
 
 // 错误定义
@@ -93,6 +296,13 @@ pub enum LLMError {
     InvalidParameters(String),
     ClientError(ClientError),
     AnyhowError(anyhow::Error),
+    ContentBlocked(String),
+    ModelUnhealthy(String),
+    BudgetExceeded(String),
+    ContextLengthExceeded(String),
+    CostLimitExceeded(String),
+    Cancelled,
+    Overloaded(String),
 }
 
 impl fmt::Display for LLMError {
@@ -107,18 +317,98 @@ impl fmt::Display for LLMError {
             LLMError::InvalidParameters(msg) => write!(f, "Invalid parameters: {}", msg),
             LLMError::ClientError(e) => write!(f, "Client error: {}", e),
             LLMError::AnyhowError(e) => write!(f, "Anyhow error: {}", e),
+            LLMError::ContentBlocked(msg) => write!(f, "Content blocked: {}", msg),
+            LLMError::ModelUnhealthy(model) => write!(f, "Model unhealthy: {}", model),
+            LLMError::BudgetExceeded(msg) => write!(f, "Monthly budget exceeded: {}", msg),
+            LLMError::ContextLengthExceeded(msg) => write!(f, "Context length exceeded: {}", msg),
+            LLMError::CostLimitExceeded(msg) => write!(f, "Cost limit exceeded: {}", msg),
+            LLMError::Cancelled => write!(f, "Request cancelled"),
+            LLMError::Overloaded(msg) => write!(f, "Provider overloaded: {}", msg),
         }
     }
 }
 
 impl std::error::Error for LLMError {}
 
+impl LLMError {
+    /// 对外返回的网关错误码：上游的 4xx 参数错误原样视为请求错误，客户端不应重试
+    pub fn error_code(&self) -> GatewayErrorCode {
+        match self {
+            LLMError::InvalidParameters(_) => GatewayErrorCode::InvalidRequest,
+            LLMError::ContextLengthExceeded(_) => GatewayErrorCode::ContextLengthExceeded,
+            LLMError::CostLimitExceeded(_) => GatewayErrorCode::CostLimitExceeded,
+            LLMError::UnsupportedProvider(_) => GatewayErrorCode::UnsupportedProvider,
+            LLMError::ModelNotAvailable(_) => GatewayErrorCode::ModelNotFound,
+            LLMError::ContentBlocked(_) => GatewayErrorCode::ContentPolicyViolation,
+            LLMError::RateLimit => GatewayErrorCode::RateLimitExceeded,
+            LLMError::Timeout => GatewayErrorCode::Timeout,
+            LLMError::ModelUnhealthy(_) => GatewayErrorCode::ModelUnhealthy,
+            LLMError::BudgetExceeded(_) => GatewayErrorCode::BudgetExceeded,
+            LLMError::Overloaded(_) => GatewayErrorCode::ProviderOverloaded,
+            LLMError::Cancelled | LLMError::ClientError(ClientError::Cancelled) => GatewayErrorCode::RequestCancelled,
+            LLMError::ClientError(ClientError::Timeout { .. }) => GatewayErrorCode::Timeout,
+            LLMError::ClientError(ClientError::LLMApi { status_code: Some(status), .. })
+            | LLMError::ClientError(ClientError::RetryExhausted { status_code: Some(status), .. }) => match status {
+                429 => GatewayErrorCode::RateLimitExceeded,
+                404 => GatewayErrorCode::ModelNotFound,
+                408 => GatewayErrorCode::Timeout,
+                400 | 413 | 422 => GatewayErrorCode::InvalidRequest,
+                // 401/403 是网关配置的上游凭证问题，不是调用方的错误
+                _ => GatewayErrorCode::UpstreamError,
+            },
+            _ => GatewayErrorCode::UpstreamError,
+        }
+    }
+
+    /// 返回给调用方的 HTTP 状态码
+    pub fn status_code(&self) -> u16 {
+        self.error_code().status_code()
+    }
+
+    /// 调用方是否可以稍后重试（限流、超时、上游不可用）
+    pub fn is_retryable(&self) -> bool {
+        self.error_code().is_retryable()
+    }
+}
+
 impl From<ClientError> for LLMError {
     fn from(err: ClientError) -> Self {
         LLMError::ClientError(err)
     }
 }
 
+// 供应商客户端错误：保留底层的 ClientError（含上游状态码），参数校验错误视为请求错误
+impl From<OllamaError> for LLMError {
+    fn from(err: OllamaError) -> Self {
+        match err {
+            OllamaError::Client(e) => LLMError::ClientError(e),
+            OllamaError::InvalidRequest(msg) => LLMError::InvalidParameters(msg),
+            OllamaError::ModelNotFound(model) => LLMError::ModelNotAvailable(model),
+            e => LLMError::ApiError(e.to_string()),
+        }
+    }
+}
+
+impl From<AliError> for LLMError {
+    fn from(err: AliError) -> Self {
+        match err {
+            AliError::Client(e) => LLMError::ClientError(e),
+            AliError::InvalidRequest(msg) => LLMError::InvalidParameters(msg),
+            e => LLMError::ApiError(e.to_string()),
+        }
+    }
+}
+
+impl From<OpenAIError> for LLMError {
+    fn from(err: OpenAIError) -> Self {
+        match err {
+            OpenAIError::Client(e) => LLMError::ClientError(e),
+            OpenAIError::InvalidRequest(msg) => LLMError::InvalidParameters(msg),
+            e => LLMError::ApiError(e.to_string()),
+        }
+    }
+}
+
 impl From<anyhow::Error> for LLMError {
     fn from(err: anyhow::Error) -> Self {
         LLMError::AnyhowError(err)
@@ -127,49 +417,190 @@ impl From<anyhow::Error> for LLMError {
 
 // Ollama客户端适配器
 pub struct OllamaAdapter {
-    client: OllamaClient,
+    client: Arc<OllamaClient>,
 }
 
 impl OllamaAdapter {
     pub fn new(client: OllamaClient) -> Self {
-        Self { client }
+        Self { client: Arc::new(client) }
+    }
+}
+
+// 构建Ollama请求
+fn build_ollama_request(request: &DispatchRequest) -> OllamaChatRequest {
+    let mut ollama_request = OllamaChatRequest::new(
+        request.model.clone(),
+        request.messages.clone(),
+    );
+
+    if let Some(stream) = request.stream {
+        ollama_request.set_stream(stream);
+    }
+
+    // 设置参数
+    let mut options = std::collections::HashMap::new();
+    if let Some(temp) = request.temperature {
+        options.insert("temperature".to_string(), serde_json::Value::Number(serde_json::Number::from_f64(temp as f64).unwrap()));
+    }
+    if let Some(max_tokens) = request.max_tokens {
+        options.insert("num_predict".to_string(), serde_json::Value::Number(serde_json::Number::from(max_tokens)));
+    }
+    if let Some(top_p) = request.top_p {
+        options.insert("top_p".to_string(), serde_json::Value::Number(serde_json::Number::from_f64(top_p as f64).unwrap()));
+    }
+    if let Some(penalty) = request.frequency_penalty {
+        options.insert("frequency_penalty".to_string(), serde_json::Value::Number(serde_json::Number::from_f64(penalty as f64).unwrap()));
+    }
+    if let Some(penalty) = request.presence_penalty {
+        options.insert("presence_penalty".to_string(), serde_json::Value::Number(serde_json::Number::from_f64(penalty as f64).unwrap()));
+    }
+    if let Some(stop) = &request.stop {
+        options.insert("stop".to_string(), serde_json::json!(stop));
+    }
+    if !options.is_empty() {
+        ollama_request.set_options(options);
+    }
+    // Ollama 不支持 tool_choice，"none" 时不下发工具，其余取值由模型自行决定
+    if let Some(tools) = &request.tools
+        && request.tool_choice.as_ref().and_then(|c| c.as_str()) != Some("none")
+    {
+        ollama_request.tools = Some(tools.clone());
+    }
+    // Ollama 的 format 为 "json" 或直接传 JSON Schema
+    ollama_request.format = match &request.response_format {
+        Some(ResponseFormat::JsonObject) => Some(serde_json::Value::String("json".to_string())),
+        Some(ResponseFormat::JsonSchema { json_schema }) => Some(json_schema.schema.clone()),
+        _ => None,
+    };
+    ollama_request
+}
+
+// Ollama结束原因，旧版本不返回 done_reason 时视为 stop
+fn ollama_finish_reason(response: &OllamaChatResponse) -> Option<String> {
+    response.is_done().then(|| response.done_reason.clone().unwrap_or_else(|| "stop".to_string()))
+}
+
+// Ollama不返回请求 ID，原始用量取响应中的计数和耗时字段
+fn record_ollama_billing(response: &OllamaChatResponse) {
+    let usage = serde_json::json!({
+        "prompt_eval_count": response.prompt_eval_count,
+        "eval_count": response.eval_count,
+        "total_duration": response.total_duration,
+        "load_duration": response.load_duration,
+        "prompt_eval_duration": response.prompt_eval_duration,
+        "eval_duration": response.eval_duration,
+    });
+    record_provider_billing(None, Some(&usage));
+}
+
+// 将Ollama流式响应写入输出通道，返回是否继续读取
+fn forward_ollama_stream_chunk(sink: &StreamSink, chunk: OllamaChatResponse) -> bool {
+    if let Some(content) = chunk.get_content().filter(|c| !c.is_empty())
+        && sink.send(Ok(StreamChunk::delta(content))).is_err()
+    {
+        return false;
+    }
+    // Ollama 的工具调用一次性完整返回，不需要拼接
+    if let Some(tool_calls) = chunk.get_message().and_then(|m| m.tool_calls)
+        && sink.send(Ok(StreamChunk::delta(String::new()).with_tool_calls(Some(tool_calls)))).is_err()
+    {
+        return false;
+    }
+
+    // 最后一块携带token统计
+    if chunk.is_done() {
+        record_ollama_billing(&chunk);
+        let prompt_tokens = chunk.get_prompt_eval_count().unwrap_or(0);
+        let completion_tokens = chunk.get_eval_count().unwrap_or(0);
+        let usage = TokenUsage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+        };
+        let _ = sink.send(Ok(StreamChunk::finished(ollama_finish_reason(&chunk), Some(usage))));
+        return false;
+    }
+    true
+}
+
+// 构建Ollama补全请求
+fn build_ollama_generate_request(request: &CompletionRequest) -> OllamaGenerateRequest {
+    let mut generate_request = OllamaGenerateRequest::new(request.model.clone(), request.prompt.clone());
+    generate_request.suffix = request.suffix.clone();
+
+    let mut options = std::collections::HashMap::new();
+    if let Some(temp) = request.temperature {
+        options.insert("temperature".to_string(), serde_json::json!(temp));
+    }
+    if let Some(max_tokens) = request.max_tokens {
+        options.insert("num_predict".to_string(), serde_json::json!(max_tokens));
+    }
+    if let Some(top_p) = request.top_p {
+        options.insert("top_p".to_string(), serde_json::json!(top_p));
+    }
+    if let Some(stop) = &request.stop {
+        options.insert("stop".to_string(), serde_json::json!(stop));
+    }
+    if !options.is_empty() {
+        generate_request.options = Some(options);
+    }
+    generate_request
+}
+
+// Ollama补全响应的原始用量
+fn record_ollama_generate_billing(response: &OllamaGenerateResponse) {
+    let usage = serde_json::json!({
+        "prompt_eval_count": response.prompt_eval_count,
+        "eval_count": response.eval_count,
+        "total_duration": response.total_duration,
+        "load_duration": response.load_duration,
+        "prompt_eval_duration": response.prompt_eval_duration,
+        "eval_duration": response.eval_duration,
+    });
+    record_provider_billing(None, Some(&usage));
+}
+
+// Ollama补全响应的token统计
+fn ollama_generate_usage(response: &OllamaGenerateResponse) -> TokenUsage {
+    let prompt_tokens = response.get_prompt_eval_count().unwrap_or(0);
+    let completion_tokens = response.get_eval_count().unwrap_or(0);
+    TokenUsage {
+        prompt_tokens,
+        completion_tokens,
+        total_tokens: prompt_tokens + completion_tokens,
+    }
+}
+
+// 将Ollama流式补全响应写入输出通道，返回是否继续读取
+fn forward_ollama_generate_chunk(sink: &StreamSink, chunk: OllamaGenerateResponse) -> bool {
+    if !chunk.response.is_empty() && sink.send(Ok(StreamChunk::delta(chunk.response.clone()))).is_err() {
+        return false;
+    }
+    if chunk.is_done() {
+        record_ollama_generate_billing(&chunk);
+        let usage = ollama_generate_usage(&chunk);
+        let _ = sink.send(Ok(StreamChunk::finished(chunk.get_finish_reason(), Some(usage))));
+        return false;
     }
+    true
 }
 
 #[async_trait]
 impl LLMClientAdapter for OllamaAdapter {
     async fn generate(&self, request: &DispatchRequest) -> Result<DispatchResponse, LLMError> {
         // 构建Ollama请求
-        let mut ollama_request = OllamaChatRequest::new(
-            request.model.clone(),
-            request.messages.clone(),
-        );
-        
-        if let Some(stream) = request.stream {
-            ollama_request.set_stream(stream);
-        }
-        
-        // 设置参数
-        if request.temperature.is_some() || request.max_tokens.is_some() || request.top_p.is_some() {
-            let mut options = std::collections::HashMap::new();
-            if let Some(temp) = request.temperature {
-                options.insert("temperature".to_string(), serde_json::Value::Number(serde_json::Number::from_f64(temp as f64).unwrap()));
-            }
-            if let Some(max_tokens) = request.max_tokens {
-                options.insert("num_predict".to_string(), serde_json::Value::Number(serde_json::Number::from(max_tokens)));
-            }
-            if let Some(top_p) = request.top_p {
-                options.insert("top_p".to_string(), serde_json::Value::Number(serde_json::Number::from_f64(top_p as f64).unwrap()));
-            }
-            ollama_request.set_options(options);
-        }
+        let ollama_request = build_ollama_request(request);
 
         // 执行请求
         let response = self.client.chat(ollama_request).await
-            .map_err(|e| LLMError::ApiError(e.to_string()))?;
+            .map_err(LLMError::from)?;
 
         // 转换响应
         let content = response.get_content().unwrap_or_default();
+        let message = response.get_message();
+        let tool_calls = message.as_ref().and_then(|m| m.tool_calls.clone());
+        let raw = serde_json::to_value(&response).ok();
+        record_ollama_billing(&response);
         
         Ok(DispatchResponse {
             content,
@@ -180,18 +611,65 @@ impl LLMClientAdapter for OllamaAdapter {
                 completion_tokens: response.get_eval_count().unwrap_or(0),
                 total_tokens: response.get_prompt_eval_count().unwrap_or(0) + response.get_eval_count().unwrap_or(0),
             }),
-            finish_reason: if response.is_done() { Some("stop".to_string()) } else { None },
+            finish_reason: ollama_finish_reason(&response),
             request_id: None,
             created_at: response.get_created_at().to_string(),
             total_duration: response.get_total_duration(),
+            tool_calls,
+            message,
+            raw,
+            warnings: None,
+        })
+    }
+
+    async fn generate_stream(&self, request: &DispatchRequest) -> Result<StreamReceiver, LLMError> {
+        let ollama_request = build_ollama_request(request);
+        let client = self.client.clone();
+
+        Ok(spawn_stream(move |sink| async move {
+            let result = client
+                .chat_stream(ollama_request, |chunk| forward_ollama_stream_chunk(&sink, chunk))
+                .await;
+            if let Err(e) = result {
+                let _ = sink.send(Err(LLMError::from(e)));
+            }
+        }))
+    }
+
+    async fn complete(&self, request: &CompletionRequest) -> Result<DispatchResponse, LLMError> {
+        let response = self.client.generate(build_ollama_generate_request(request)).await
+            .map_err(LLMError::from)?;
+        record_ollama_generate_billing(&response);
+        let raw = serde_json::to_value(&response).ok();
+
+        Ok(DispatchResponse {
+            usage: Some(ollama_generate_usage(&response)),
+            finish_reason: response.get_finish_reason(),
+            provider: Provider::Ollama,
+            model: response.model,
+            content: response.response,
+            request_id: None,
+            created_at: response.created_at,
+            total_duration: response.total_duration,
+            tool_calls: None,
+            message: None,
+            raw,
+            warnings: None,
         })
     }
 
-    async fn generate_stream(&self, _request: &DispatchRequest) -> Result<tokio::sync::mpsc::Receiver<Result<String, LLMError>>, LLMError> {
-        // 简化实现，暂时不支持流式
-        let (tx, rx) = tokio::sync::mpsc::channel(1);
-        let _ = tx.send(Err(LLMError::InvalidParameters("Stream not implemented yet".to_string()))).await;
-        Ok(rx)
+    async fn complete_stream(&self, request: &CompletionRequest) -> Result<StreamReceiver, LLMError> {
+        let generate_request = build_ollama_generate_request(request);
+        let client = self.client.clone();
+
+        Ok(spawn_stream(move |sink| async move {
+            let result = client
+                .generate_stream(generate_request, |chunk| forward_ollama_generate_chunk(&sink, chunk))
+                .await;
+            if let Err(e) = result {
+                let _ = sink.send(Err(LLMError::from(e)));
+            }
+        }))
     }
 
     fn supported_models(&self) -> Vec<String> {
@@ -214,15 +692,181 @@ impl LLMClientAdapter for OllamaAdapter {
 
 // Ali客户端适配器
 pub struct AliAdapter {
-    client: AliClient,
+    client: Arc<AliClient>,
 }
 
 impl AliAdapter {
     pub fn new(client: AliClient) -> Self {
-        Self { client }
+        Self { client: Arc::new(client) }
+    }
+}
+
+// 构建Ali请求
+fn build_ali_request(request: &DispatchRequest) -> AliChatRequest {
+    let mut ali_request = AliChatRequest::new(
+        request.model.clone(),
+        request.messages.clone(),
+    );
+
+    if let Some(stream) = request.stream {
+        ali_request.set_stream(stream);
+    }
+
+    // 设置参数
+    if let Some(temp) = request.temperature {
+        ali_request.temperature = Some(temp);
+    }
+    if let Some(max_tokens) = request.max_tokens {
+        ali_request.max_tokens = Some(max_tokens);
+    }
+    if let Some(top_p) = request.top_p {
+        ali_request.top_p = Some(top_p);
+    }
+    if let Some(stop) = &request.stop {
+        ali_request.stop = Some(stop.clone());
+    }
+    if let Some(penalty) = request.presence_penalty {
+        ali_request.presence_penalty = Some(penalty);
+    }
+    ali_request.tools = request.tools.clone();
+    ali_request.tool_choice = request.tool_choice.clone();
+    ali_request.response_format = compatible_response_format(request);
+    ali_request
+}
+
+// OpenAI兼容格式的 response_format，文本格式不下发
+fn compatible_response_format(request: &DispatchRequest) -> Option<serde_json::Value> {
+    request.response_format.as_ref()
+        .filter(|format| format.is_json())
+        .and_then(|format| serde_json::to_value(format).ok())
+}
+
+// 流式块中每个选择项的 (增量内容, 工具调用增量, finish_reason)
+type StreamChoiceParts = Vec<(Option<String>, Option<Vec<ToolCallDelta>>, Option<String>)>;
+
+// OpenAI兼容格式的流式块（Ali、OpenAI）
+trait CompatibleStreamChunk {
+    // 拆分为选择项列表和token统计
+    fn into_parts(self) -> (StreamChoiceParts, Option<TokenUsage>);
+    // 携带usage的块记录供应商原始计费信息
+    fn record_billing(&self);
+}
+
+impl CompatibleStreamChunk for AliStreamResponse {
+    fn record_billing(&self) {
+        if self.usage.is_some() {
+            record_provider_billing(Some(&self.id), self.usage.as_ref());
+        }
+    }
+
+    fn into_parts(self) -> (StreamChoiceParts, Option<TokenUsage>) {
+        let choices = self.choices.into_iter().map(|c| (c.delta.content, c.delta.tool_calls, c.finish_reason)).collect();
+        let usage = self.usage.map(|u| TokenUsage {
+            prompt_tokens: u.prompt_tokens,
+            completion_tokens: u.completion_tokens,
+            total_tokens: u.total_tokens,
+        });
+        (choices, usage)
+    }
+}
+
+impl CompatibleStreamChunk for OpenAIStreamResponse {
+    fn record_billing(&self) {
+        if self.usage.is_some() {
+            record_provider_billing(Some(&self.id), self.usage.as_ref());
+        }
+    }
+
+    fn into_parts(self) -> (StreamChoiceParts, Option<TokenUsage>) {
+        let choices = self.choices.into_iter().map(|c| (c.delta.content, c.delta.tool_calls, c.finish_reason)).collect();
+        let usage = self.usage.map(|u| TokenUsage {
+            prompt_tokens: u.prompt_tokens,
+            completion_tokens: u.completion_tokens,
+            total_tokens: u.total_tokens,
+        });
+        (choices, usage)
+    }
+}
+
+impl CompatibleStreamChunk for OpenAICompletionResponse {
+    fn record_billing(&self) {
+        if self.usage.is_some() {
+            record_provider_billing(Some(&self.id), self.usage.as_ref());
+        }
+    }
+
+    fn into_parts(self) -> (StreamChoiceParts, Option<TokenUsage>) {
+        let choices = self.choices.into_iter().map(|c| (Some(c.text), None, c.finish_reason)).collect();
+        let usage = self.usage.map(|u| TokenUsage {
+            prompt_tokens: u.prompt_tokens,
+            completion_tokens: u.completion_tokens,
+            total_tokens: u.total_tokens,
+        });
+        (choices, usage)
+    }
+}
+
+// 将OpenAI兼容格式的流式响应写入输出通道。finish_reason和usage分别在不同的块中返回，
+// 合并后作为最后一块发送；工具调用参数分多块返回，拼接完整后随最后一块发送
+struct CompatibleStreamForwarder {
+    sink: StreamSink,
+    finish_reason: Option<String>,
+    tool_calls: ToolCallAccumulator,
+    finished: bool,
+}
+
+impl CompatibleStreamForwarder {
+    fn new(sink: StreamSink) -> Self {
+        Self { sink, finish_reason: None, tool_calls: ToolCallAccumulator::default(), finished: false }
+    }
+
+    // 结束块，携带拼接完成的工具调用
+    fn finished_chunk(&mut self, usage: Option<TokenUsage>) -> StreamChunk {
+        StreamChunk::finished(self.finish_reason.take(), usage).with_tool_calls(std::mem::take(&mut self.tool_calls).finish())
+    }
+
+    // 处理一个流式块，返回是否继续读取
+    fn forward<C: CompatibleStreamChunk>(&mut self, chunk: C) -> bool {
+        chunk.record_billing();
+        let (choices, usage) = chunk.into_parts();
+        for (content, tool_calls, finish_reason) in choices {
+            if let Some(content) = content.filter(|c| !c.is_empty())
+                && self.sink.send(Ok(StreamChunk::delta(content))).is_err()
+            {
+                return false;
+            }
+            for delta in tool_calls.into_iter().flatten() {
+                self.tool_calls.push(delta);
+            }
+            if finish_reason.is_some() {
+                self.finish_reason = finish_reason;
+            }
+        }
+
+        if let Some(usage) = usage {
+            self.finished = true;
+            let chunk = self.finished_chunk(Some(usage));
+            return self.sink.send(Ok(chunk)).is_ok();
+        }
+        true
+    }
+
+    // 流结束：未收到usage时补发结束块，出错时发送错误
+    fn finish<E: Into<LLMError>>(mut self, result: Result<(), E>) {
+        if let Err(e) = result {
+            let _ = self.sink.send(Err(e.into()));
+        } else if !self.finished && self.finish_reason.is_some() {
+            let chunk = self.finished_chunk(None);
+            let _ = self.sink.send(Ok(chunk));
+        }
     }
 }
 
+// 构建Ali流式请求（要求返回token统计）
+fn build_ali_stream_request(request: &DispatchRequest) -> AliChatRequest {
+    build_ali_request(request).with_stream_usage(true)
+}
+
 // Ali客户端池适配器
 pub struct AliPoolAdapter {
     pool: Arc<ClientPool<DynamicAliClient>>,
@@ -238,44 +882,27 @@ impl AliPoolAdapter {
 impl LLMClientAdapter for AliPoolAdapter {
     async fn generate(&self, request: &DispatchRequest) -> Result<DispatchResponse, LLMError> {
         // 构建Ali请求
-        let mut ali_request = AliChatRequest::new(
-            request.model.clone(),
-            request.messages.clone(),
-        );
-        
-        if let Some(stream) = request.stream {
-            ali_request.set_stream(stream);
-        }
-        
-        // 设置参数
-        if let Some(temp) = request.temperature {
-            ali_request.temperature = Some(temp);
-        }
-        if let Some(max_tokens) = request.max_tokens {
-            ali_request.max_tokens = Some(max_tokens);
-        }
-        if let Some(top_p) = request.top_p {
-            ali_request.top_p = Some(top_p);
-        }
-        if let Some(stop) = &request.stop {
-            ali_request.stop = Some(stop.clone());
-        }
+        let ali_request = build_ali_request(request);
 
         // 从池中获取客户端并执行请求
         let client_guard = self.pool.acquire().await;
         let client = client_guard.lock().await;
         
         let response = client.chat_with_auto_key(ali_request).await
-            .map_err(|e| LLMError::ApiError(e.to_string()))?;
+            .map_err(LLMError::from)?;
 
         // 转换响应
         let content = response.get_content().unwrap_or_default();
+        let message = response.get_message();
+        let tool_calls = message.as_ref().and_then(|m| m.tool_calls.clone());
+        let raw = serde_json::to_value(&response).ok();
         let model = response.model.clone();
         let usage = response.usage.as_ref().map(|u| TokenUsage {
             prompt_tokens: u.prompt_tokens,
             completion_tokens: u.completion_tokens,
             total_tokens: u.total_tokens,
         });
+        record_provider_billing(Some(&response.id), response.usage.as_ref());
         let finish_reason = response.choices.first().map(|c| c.finish_reason.clone());
         let request_id = response.id.clone();
         let created_at = response.get_created_at().to_string();
@@ -289,14 +916,27 @@ impl LLMClientAdapter for AliPoolAdapter {
             request_id: Some(request_id),
             created_at,
             total_duration: None,
+            tool_calls,
+            message,
+            raw,
+            warnings: None,
         })
     }
 
-    async fn generate_stream(&self, _request: &DispatchRequest) -> Result<tokio::sync::mpsc::Receiver<Result<String, LLMError>>, LLMError> {
-        // 简化实现，暂时不支持流式
-        let (tx, rx) = tokio::sync::mpsc::channel(1);
-        let _ = tx.send(Err(LLMError::InvalidParameters("Stream not implemented yet for pool".to_string()))).await;
-        Ok(rx)
+    async fn generate_stream(&self, request: &DispatchRequest) -> Result<StreamReceiver, LLMError> {
+        let ali_request = build_ali_stream_request(request);
+        let pool = self.pool.clone();
+
+        Ok(spawn_stream(move |sink| async move {
+            // 从池中获取客户端并执行流式请求
+            let client_guard = pool.acquire().await;
+            let client = client_guard.lock().await;
+            let mut forwarder = CompatibleStreamForwarder::new(sink);
+            let result = client
+                .chat_stream_with_auto_key(ali_request, |chunk| forwarder.forward(chunk))
+                .await;
+            forwarder.finish(result);
+        }))
     }
 
     fn supported_models(&self) -> Vec<String> {
@@ -315,47 +955,34 @@ impl LLMClientAdapter for AliPoolAdapter {
     fn provider_name(&self) -> Provider {
         Provider::Ali
     }
+
+    fn uses_key_pool(&self) -> bool {
+        true
+    }
 }
 
 #[async_trait]
 impl LLMClientAdapter for AliAdapter {
     async fn generate(&self, request: &DispatchRequest) -> Result<DispatchResponse, LLMError> {
         // 构建Ali请求
-        let mut ali_request = AliChatRequest::new(
-            request.model.clone(),
-            request.messages.clone(),
-        );
-        
-        if let Some(stream) = request.stream {
-            ali_request.set_stream(stream);
-        }
-        
-        // 设置参数
-        if let Some(temp) = request.temperature {
-            ali_request.temperature = Some(temp);
-        }
-        if let Some(max_tokens) = request.max_tokens {
-            ali_request.max_tokens = Some(max_tokens);
-        }
-        if let Some(top_p) = request.top_p {
-            ali_request.top_p = Some(top_p);
-        }
-        if let Some(stop) = &request.stop {
-            ali_request.stop = Some(stop.clone());
-        }
+        let ali_request = build_ali_request(request);
 
         // 执行请求
         let response = self.client.chat(ali_request).await
-            .map_err(|e| LLMError::ApiError(e.to_string()))?;
+            .map_err(LLMError::from)?;
 
         // 转换响应
         let content = response.get_content().unwrap_or_default();
+        let message = response.get_message();
+        let tool_calls = message.as_ref().and_then(|m| m.tool_calls.clone());
+        let raw = serde_json::to_value(&response).ok();
         let model = response.model.clone();
         let usage = response.usage.as_ref().map(|u| TokenUsage {
             prompt_tokens: u.prompt_tokens,
             completion_tokens: u.completion_tokens,
             total_tokens: u.total_tokens,
         });
+        record_provider_billing(Some(&response.id), response.usage.as_ref());
         let finish_reason = response.choices.first().map(|c| c.finish_reason.clone());
         let request_id = response.id.clone();
         let created_at = response.get_created_at().to_string();
@@ -369,14 +996,24 @@ impl LLMClientAdapter for AliAdapter {
             request_id: Some(request_id),
             created_at,
             total_duration: None,
+            tool_calls,
+            message,
+            raw,
+            warnings: None,
         })
     }
 
-    async fn generate_stream(&self, _request: &DispatchRequest) -> Result<tokio::sync::mpsc::Receiver<Result<String, LLMError>>, LLMError> {
-        // 简化实现，暂时不支持流式
-        let (tx, rx) = tokio::sync::mpsc::channel(1);
-        let _ = tx.send(Err(LLMError::InvalidParameters("Stream not implemented yet".to_string()))).await;
-        Ok(rx)
+    async fn generate_stream(&self, request: &DispatchRequest) -> Result<StreamReceiver, LLMError> {
+        let ali_request = build_ali_stream_request(request);
+        let client = self.client.clone();
+
+        Ok(spawn_stream(move |sink| async move {
+            let mut forwarder = CompatibleStreamForwarder::new(sink);
+            let result = client
+                .chat_stream(ali_request, |chunk| forwarder.forward(chunk))
+                .await;
+            forwarder.finish(result);
+        }))
     }
 
     fn supported_models(&self) -> Vec<String> {
@@ -397,46 +1034,586 @@ impl LLMClientAdapter for AliAdapter {
     }
 }
 
-// Dispatcher主体
-pub struct LLMDispatcher {
-    clients: Arc<RwLock<HashMap<Provider, Box<dyn LLMClientAdapter>>>>,
-    default_config: DispatchConfig,
+// 构建OpenAI请求
+fn build_openai_request(request: &DispatchRequest) -> OpenAIChatRequest {
+    let mut openai_request = OpenAIChatRequest::new(
+        request.model.clone(),
+        request.messages.clone(),
+    );
+
+    if let Some(stream) = request.stream {
+        openai_request.set_stream(stream);
+    }
+
+    // 设置参数
+    openai_request.temperature = request.temperature;
+    openai_request.max_tokens = request.max_tokens;
+    openai_request.top_p = request.top_p;
+    openai_request.frequency_penalty = request.frequency_penalty;
+    openai_request.presence_penalty = request.presence_penalty;
+    openai_request.stop = request.stop.clone();
+    openai_request.user = request.user.clone();
+    openai_request.tools = request.tools.clone();
+    openai_request.tool_choice = request.tool_choice.clone();
+    openai_request.response_format = compatible_response_format(request);
+    openai_request
 }
 
-#[derive(Debug, Clone)]
-pub struct DispatchConfig {
-    pub default_timeout_ms: u64,
-    pub default_retry_count: u32,
-    pub default_temperature: f32,
-    pub enable_fallback: bool,
-    pub fallback_providers: Vec<Provider>,
+// 构建OpenAI补全请求
+fn build_openai_completion_request(request: &CompletionRequest) -> OpenAICompletionRequest {
+    let mut completion_request = OpenAICompletionRequest::new(request.model.clone(), request.prompt.clone());
+    completion_request.suffix = request.suffix.clone();
+    completion_request.temperature = request.temperature;
+    completion_request.max_tokens = request.max_tokens;
+    completion_request.top_p = request.top_p;
+    completion_request.stop = request.stop.clone();
+    completion_request.user = request.user.clone();
+    completion_request
 }
 
-impl Default for DispatchConfig {
-    fn default() -> Self {
-        Self {
-            default_timeout_ms: 30000,
-            default_retry_count: 3,
-            default_temperature: 0.7,
-            enable_fallback: true,
-            fallback_providers: vec![Provider::Ollama, Provider::Ali],
-        }
-    }
+// OpenAI客户端池适配器（使用Key池轮询认证）
+pub struct OpenAIAdapter {
+    pool: Arc<ClientPool<DynamicOpenAIClient>>,
 }
 
-impl LLMDispatcher {
-    pub fn new(config: Option<DispatchConfig>) -> Self {
-        Self {
-            clients: Arc::new(RwLock::new(HashMap::new())),
-            default_config: config.unwrap_or_default(),
-        }
+impl OpenAIAdapter {
+    pub fn new(pool: Arc<ClientPool<DynamicOpenAIClient>>) -> Self {
+        Self { pool }
     }
+}
 
-    /// 创建支持数据库的dispatcher，自动初始化数据库和客户端池
-    pub async fn new_with_database(config: Option<DispatchConfig>, db_url: &str, init_sql_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        // 初始化数据库连接池
-        println!("🔧 正在初始化数据库连接池...");
-        init_sqlite_pool(db_url).await;
+#[async_trait]
+impl LLMClientAdapter for OpenAIAdapter {
+    async fn generate(&self, request: &DispatchRequest) -> Result<DispatchResponse, LLMError> {
+        let openai_request = build_openai_request(request);
+
+        // 从池中获取客户端并执行请求
+        let client_guard = self.pool.acquire().await;
+        let client = client_guard.lock().await;
+
+        let response = client.chat_with_auto_key(openai_request).await
+            .map_err(LLMError::from)?;
+
+        // 转换响应
+        let content = response.get_content().unwrap_or_default();
+        let message = response.get_message();
+        let tool_calls = message.as_ref().and_then(|m| m.tool_calls.clone());
+        let raw = serde_json::to_value(&response).ok();
+        let usage = response.usage.as_ref().map(|u| TokenUsage {
+            prompt_tokens: u.prompt_tokens,
+            completion_tokens: u.completion_tokens,
+            total_tokens: u.total_tokens,
+        });
+        record_provider_billing(Some(&response.id), response.usage.as_ref());
+        let finish_reason = response.choices.first().and_then(|c| c.finish_reason.clone());
+        let created_at = response.created.to_string();
+
+        Ok(DispatchResponse {
+            content,
+            provider: Provider::OpenAI,
+            model: response.model,
+            usage,
+            finish_reason,
+            request_id: Some(response.id),
+            created_at,
+            total_duration: None,
+            tool_calls,
+            message,
+            raw,
+            warnings: None,
+        })
+    }
+
+    async fn generate_stream(&self, request: &DispatchRequest) -> Result<StreamReceiver, LLMError> {
+        let openai_request = build_openai_request(request).with_stream_usage(true);
+        let pool = self.pool.clone();
+
+        Ok(spawn_stream(move |sink| async move {
+            // 从池中获取客户端并执行流式请求
+            let client_guard = pool.acquire().await;
+            let client = client_guard.lock().await;
+            let mut forwarder = CompatibleStreamForwarder::new(sink);
+            let result = client
+                .chat_stream_with_auto_key(openai_request, |chunk| forwarder.forward(chunk))
+                .await;
+            forwarder.finish(result);
+        }))
+    }
+
+    async fn complete(&self, request: &CompletionRequest) -> Result<DispatchResponse, LLMError> {
+        let completion_request = build_openai_completion_request(request);
+
+        let client_guard = self.pool.acquire().await;
+        let client = client_guard.lock().await;
+
+        let response = client.complete_with_auto_key(completion_request).await
+            .map_err(LLMError::from)?;
+
+        let content = response.get_text().unwrap_or_default();
+        let finish_reason = response.get_finish_reason();
+        let raw = serde_json::to_value(&response).ok();
+        let usage = response.usage.as_ref().map(|u| TokenUsage {
+            prompt_tokens: u.prompt_tokens,
+            completion_tokens: u.completion_tokens,
+            total_tokens: u.total_tokens,
+        });
+        record_provider_billing(Some(&response.id), response.usage.as_ref());
+        let created_at = response.created.to_string();
+
+        Ok(DispatchResponse {
+            content,
+            provider: Provider::OpenAI,
+            model: response.model,
+            usage,
+            finish_reason,
+            request_id: Some(response.id),
+            created_at,
+            total_duration: None,
+            tool_calls: None,
+            message: None,
+            raw,
+            warnings: None,
+        })
+    }
+
+    async fn complete_stream(&self, request: &CompletionRequest) -> Result<StreamReceiver, LLMError> {
+        let mut completion_request = build_openai_completion_request(request);
+        completion_request.stream_options = Some(OpenAIStreamOptions { include_usage: true });
+        let pool = self.pool.clone();
+
+        Ok(spawn_stream(move |sink| async move {
+            let client_guard = pool.acquire().await;
+            let client = client_guard.lock().await;
+            let mut forwarder = CompatibleStreamForwarder::new(sink);
+            let result = client
+                .complete_stream_with_auto_key(completion_request, |chunk| forwarder.forward(chunk))
+                .await;
+            forwarder.finish(result);
+        }))
+    }
+
+    fn supported_models(&self) -> Vec<String> {
+        vec![
+            "gpt-4o".to_string(),
+            "gpt-4o-mini".to_string(),
+            "gpt-4.1".to_string(),
+            "gpt-4.1-mini".to_string(),
+            "gpt-4.1-nano".to_string(),
+            "gpt-4-turbo".to_string(),
+            "gpt-3.5-turbo".to_string(),
+            "o3-mini".to_string(),
+        ]
+    }
+
+    fn provider_name(&self) -> Provider {
+        Provider::OpenAI
+    }
+
+    fn uses_key_pool(&self) -> bool {
+        true
+    }
+}
+
+// OpenAI兼容供应商适配器（vLLM、LM Studio、DeepSeek 等），以 providers 表中的供应商名称注册
+pub struct OpenAICompatibleAdapter {
+    provider: Provider,
+    pool: Arc<ClientPool<DynamicOpenAICompatibleClient>>,
+    models: Vec<String>,            // 内置模型列表，数据库中没有该供应商的模型时使用
+    requires_key: bool,
+}
+
+impl OpenAICompatibleAdapter {
+    pub fn new(provider: Provider, pool: Arc<ClientPool<DynamicOpenAICompatibleClient>>, models: Vec<String>) -> Self {
+        Self { provider, pool, models, requires_key: true }
+    }
+
+    // 是否要求 Key 池中有可用的 Key
+    pub fn with_requires_key(mut self, requires_key: bool) -> Self {
+        self.requires_key = requires_key;
+        self
+    }
+}
+
+#[async_trait]
+impl LLMClientAdapter for OpenAICompatibleAdapter {
+    async fn generate(&self, request: &DispatchRequest) -> Result<DispatchResponse, LLMError> {
+        let openai_request = build_openai_request(request);
+
+        let client_guard = self.pool.acquire().await;
+        let client = client_guard.lock().await;
+
+        let response = client.chat_with_auto_key(openai_request).await
+            .map_err(LLMError::from)?;
+
+        let content = response.get_content().unwrap_or_default();
+        let message = response.get_message();
+        let tool_calls = message.as_ref().and_then(|m| m.tool_calls.clone());
+        let raw = serde_json::to_value(&response).ok();
+        let usage = response.usage.as_ref().map(|u| TokenUsage {
+            prompt_tokens: u.prompt_tokens,
+            completion_tokens: u.completion_tokens,
+            total_tokens: u.total_tokens,
+        });
+        record_provider_billing(Some(&response.id), response.usage.as_ref());
+        let finish_reason = response.choices.first().and_then(|c| c.finish_reason.clone());
+        let created_at = response.created.to_string();
+
+        Ok(DispatchResponse {
+            content,
+            provider: self.provider.clone(),
+            model: response.model,
+            usage,
+            finish_reason,
+            request_id: Some(response.id),
+            created_at,
+            total_duration: None,
+            tool_calls,
+            message,
+            raw,
+            warnings: None,
+        })
+    }
+
+    async fn generate_stream(&self, request: &DispatchRequest) -> Result<StreamReceiver, LLMError> {
+        let openai_request = build_openai_request(request).with_stream_usage(true);
+        let pool = self.pool.clone();
+
+        Ok(spawn_stream(move |sink| async move {
+            let client_guard = pool.acquire().await;
+            let client = client_guard.lock().await;
+            let mut forwarder = CompatibleStreamForwarder::new(sink);
+            let result = client
+                .chat_stream_with_auto_key(openai_request, |chunk| forwarder.forward(chunk))
+                .await;
+            forwarder.finish(result);
+        }))
+    }
+
+    async fn complete(&self, request: &CompletionRequest) -> Result<DispatchResponse, LLMError> {
+        let completion_request = build_openai_completion_request(request);
+
+        let client_guard = self.pool.acquire().await;
+        let client = client_guard.lock().await;
+
+        let response = client.complete_with_auto_key(completion_request).await
+            .map_err(LLMError::from)?;
+
+        let content = response.get_text().unwrap_or_default();
+        let finish_reason = response.get_finish_reason();
+        let raw = serde_json::to_value(&response).ok();
+        let usage = response.usage.as_ref().map(|u| TokenUsage {
+            prompt_tokens: u.prompt_tokens,
+            completion_tokens: u.completion_tokens,
+            total_tokens: u.total_tokens,
+        });
+        record_provider_billing(Some(&response.id), response.usage.as_ref());
+        let created_at = response.created.to_string();
+
+        Ok(DispatchResponse {
+            content,
+            provider: self.provider.clone(),
+            model: response.model,
+            usage,
+            finish_reason,
+            request_id: Some(response.id),
+            created_at,
+            total_duration: None,
+            tool_calls: None,
+            message: None,
+            raw,
+            warnings: None,
+        })
+    }
+
+    async fn complete_stream(&self, request: &CompletionRequest) -> Result<StreamReceiver, LLMError> {
+        let mut completion_request = build_openai_completion_request(request);
+        completion_request.stream_options = Some(OpenAIStreamOptions { include_usage: true });
+        let pool = self.pool.clone();
+
+        Ok(spawn_stream(move |sink| async move {
+            let client_guard = pool.acquire().await;
+            let client = client_guard.lock().await;
+            let mut forwarder = CompatibleStreamForwarder::new(sink);
+            let result = client
+                .complete_stream_with_auto_key(completion_request, |chunk| forwarder.forward(chunk))
+                .await;
+            forwarder.finish(result);
+        }))
+    }
+
+    fn supported_models(&self) -> Vec<String> {
+        self.models.clone()
+    }
+
+    fn provider_name(&self) -> Provider {
+        self.provider.clone()
+    }
+
+    fn uses_key_pool(&self) -> bool {
+        self.requires_key
+    }
+}
+
+// Azure OpenAI适配器，按模型名称映射到部署
+pub struct AzureOpenAIAdapter {
+    pool: Arc<ClientPool<DynamicAzureOpenAIClient>>,
+    deployments: HashMap<String, String>,   // 模型名称 -> 部署名称
+}
+
+impl AzureOpenAIAdapter {
+    pub fn new(pool: Arc<ClientPool<DynamicAzureOpenAIClient>>, deployments: HashMap<String, String>) -> Self {
+        Self { pool, deployments }
+    }
+
+    // 构建请求，model字段替换为部署名称
+    fn build_request(&self, request: &DispatchRequest) -> Result<OpenAIChatRequest, LLMError> {
+        let deployment = self.deployments.get(&request.model)
+            .ok_or_else(|| LLMError::ModelNotAvailable(request.model.clone()))?;
+        let mut azure_request = build_openai_request(request);
+        azure_request.model = deployment.clone();
+        Ok(azure_request)
+    }
+}
+
+#[async_trait]
+impl LLMClientAdapter for AzureOpenAIAdapter {
+    async fn generate(&self, request: &DispatchRequest) -> Result<DispatchResponse, LLMError> {
+        let azure_request = self.build_request(request)?;
+
+        // 从池中获取客户端并执行请求
+        let client_guard = self.pool.acquire().await;
+        let client = client_guard.lock().await;
+
+        let response = client.chat_with_auto_key(azure_request).await
+            .map_err(LLMError::from)?;
+
+        // 转换响应，返回网关侧的模型名称而不是部署名称
+        let content = response.get_content().unwrap_or_default();
+        let message = response.get_message();
+        let tool_calls = message.as_ref().and_then(|m| m.tool_calls.clone());
+        let raw = serde_json::to_value(&response).ok();
+        let usage = response.usage.as_ref().map(|u| TokenUsage {
+            prompt_tokens: u.prompt_tokens,
+            completion_tokens: u.completion_tokens,
+            total_tokens: u.total_tokens,
+        });
+        record_provider_billing(Some(&response.id), response.usage.as_ref());
+        let finish_reason = response.choices.first().and_then(|c| c.finish_reason.clone());
+        let created_at = response.created.to_string();
+
+        Ok(DispatchResponse {
+            content,
+            provider: Provider::Azure,
+            model: request.model.clone(),
+            usage,
+            finish_reason,
+            request_id: Some(response.id),
+            created_at,
+            total_duration: None,
+            tool_calls,
+            message,
+            raw,
+            warnings: None,
+        })
+    }
+
+    async fn generate_stream(&self, request: &DispatchRequest) -> Result<StreamReceiver, LLMError> {
+        let azure_request = self.build_request(request)?.with_stream_usage(true);
+        let pool = self.pool.clone();
+
+        Ok(spawn_stream(move |sink| async move {
+            // 从池中获取客户端并执行流式请求
+            let client_guard = pool.acquire().await;
+            let client = client_guard.lock().await;
+            let mut forwarder = CompatibleStreamForwarder::new(sink);
+            let result = client
+                .chat_stream_with_auto_key(azure_request, |chunk| forwarder.forward(chunk))
+                .await;
+            forwarder.finish(result);
+        }))
+    }
+
+    fn supported_models(&self) -> Vec<String> {
+        let mut models: Vec<String> = self.deployments.keys().cloned().collect();
+        models.sort();
+        models
+    }
+
+    fn provider_name(&self) -> Provider {
+        Provider::Azure
+    }
+
+    fn uses_key_pool(&self) -> bool {
+        true
+    }
+}
+
+// Dispatcher主体
+pub struct LLMDispatcher {
+    clients: Arc<RwLock<HashMap<Provider, Box<dyn LLMClientAdapter>>>>,
+    managed_providers: RwLock<HashSet<Provider>>,   // 根据数据库providers表自动注册的供应商
+    provider_projects: RwLock<HashMap<Provider, String>>, // 自动注册的供应商所属的项目
+    hooks: RwLock<DispatchHooks>,                    // 按注册顺序执行的扩展钩子
+    database: Option<SqlitePool>,                    // 通过 from_database/builder 创建时用于重新同步供应商
+    concurrency: Arc<ProviderConcurrency>,           // 各供应商的并发上限
+    default_config: DispatchConfig,
+}
+
+/// LLMDispatcher 构建器：组合配置、手动注册的适配器和扩展钩子，
+/// 指定数据库后按 providers 表自动注册适配器，之后可通过 [`LLMDispatcher::resync_providers`] 重新同步
+#[derive(Default)]
+pub struct LLMDispatcherBuilder {
+    config: Option<DispatchConfig>,
+    clients: Vec<Box<dyn LLMClientAdapter>>,
+    hooks: Vec<Box<dyn DispatchHook>>,
+    database: Option<SqlitePool>,
+}
+
+impl LLMDispatcherBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_config(mut self, config: DispatchConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// 手动注册的适配器，数据库中同名的供应商不会覆盖它
+    pub fn with_client(mut self, client: Box<dyn LLMClientAdapter>) -> Self {
+        self.clients.push(client);
+        self
+    }
+
+    pub fn with_hook(mut self, hook: Box<dyn DispatchHook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    /// 根据该数据库的 providers 表注册适配器，models 表作为各供应商的模型目录
+    pub fn with_database(mut self, pool: SqlitePool) -> Self {
+        self.database = Some(pool);
+        self
+    }
+
+    /// 创建 dispatcher；指定了数据库时全局缓存未初始化则先初始化（预加载启用的模型），再同步供应商
+    pub async fn build(self) -> Result<LLMDispatcher> {
+        let mut dispatcher = LLMDispatcher::new(self.config);
+        for client in self.clients {
+            dispatcher.register_client(client).await;
+        }
+        for hook in self.hooks {
+            dispatcher.register_hook(hook).await;
+        }
+        if let Some(pool) = self.database {
+            if GLOBAL_CACHE.get().is_none() {
+                let cache = &gateway_config().cache;
+                init_global_cache(&pool, cache.ttl_secs, cache.max_entries).await?;
+            }
+            dispatcher.sync_providers_from_db(&pool).await?;
+            dispatcher.database = Some(pool);
+        }
+        Ok(dispatcher)
+    }
+}
+
+// 全局dispatcher实例（供Web网关接口使用）
+pub static GLOBAL_DISPATCHER: OnceCell<Arc<LLMDispatcher>> = OnceCell::new();
+
+/// 根据数据库中的providers重新注册全局dispatcher的适配器（provider变更后调用）
+pub async fn reload_provider_adapters(pool: &SqlitePool) -> Result<Vec<Provider>> {
+    match GLOBAL_DISPATCHER.get() {
+        Some(dispatcher) => dispatcher.sync_providers_from_db(pool).await,
+        None => Ok(Vec::new()),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DispatchConfig {
+    pub default_timeout_ms: u64,
+    pub default_retry_count: u32,
+    pub default_temperature: f32,
+    pub enable_fallback: bool,
+    pub fallback_providers: Vec<Provider>,
+    pub enable_locale_routing: bool,                 // 是否按提示词语言路由模型
+    pub locale_routes: HashMap<String, LocaleRoute>, // 语言代码(ISO 639-3) -> 路由目标
+    pub default_locale_route: Option<LocaleRoute>,   // 未匹配语言时的路由目标
+    pub fallback_policy: Arc<dyn FallbackPolicy>,    // 备选供应商上使用的模型
+    pub context_windows: HashMap<String, usize>,     // 模型 -> 上下文窗口，覆盖内置的常见模型窗口
+    pub summary_model: Option<(Provider, String)>,   // context_strategy 为 summarize 时生成摘要的模型，默认使用请求的模型
+    pub load_balance_policy: LoadBalancePolicy,      // 多个供应商提供同一模型时的选择策略
+    pub traffic_splits: HashMap<String, TrafficSplit>, // 逻辑模型名 -> 按百分比拆分的 A/B 分组
+    pub shadow: Option<ShadowMirror>,                // 按比例把请求异步镜像到另一个供应商，只记录不返回
+    pub concurrency_wait_ms: u64,                    // 供应商并发已满时等待槽位的最长时间，0 时立即返回 Overloaded
+}
+
+// 按语言路由的目标模型
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocaleRoute {
+    pub provider: Provider,
+    pub model: String,
+}
+
+impl LocaleRoute {
+    pub fn new(provider: Provider, model: String) -> Self {
+        Self { provider, model }
+    }
+}
+
+impl Default for DispatchConfig {
+    fn default() -> Self {
+        Self {
+            default_timeout_ms: 180_000, // 与 HTTP 客户端默认的请求超时一致
+            default_retry_count: 3,
+            default_temperature: 0.7,
+            enable_fallback: true,
+            fallback_providers: vec![Provider::Ollama, Provider::Ali],
+            enable_locale_routing: false,
+            locale_routes: HashMap::from([
+                ("cmn".to_string(), LocaleRoute::new(Provider::Ali, "qwen-turbo".to_string())),
+            ]),
+            default_locale_route: Some(LocaleRoute::new(Provider::Ollama, "llama3.2".to_string())),
+            fallback_policy: Arc::new(ModelAliasFallback::new().with_alias("qwen-plus", vec![
+                (Provider::Ali, "qwen-plus".to_string()),
+                (Provider::Ollama, "qwen2:7b".to_string()),
+            ])),
+            context_windows: HashMap::new(),
+            summary_model: None,
+            load_balance_policy: LoadBalancePolicy::default(),
+            traffic_splits: HashMap::new(),
+            shadow: None,
+            concurrency_wait_ms: 10_000,
+        }
+    }
+}
+
+impl LLMDispatcher {
+    pub fn new(config: Option<DispatchConfig>) -> Self {
+        Self {
+            clients: Arc::new(RwLock::new(HashMap::new())),
+            managed_providers: RwLock::new(HashSet::new()),
+            provider_projects: RwLock::new(HashMap::new()),
+            hooks: RwLock::new(DispatchHooks::default()),
+            database: None,
+            concurrency: Arc::new(ProviderConcurrency::new()),
+            default_config: config.unwrap_or_default(),
+        }
+    }
+
+    pub fn builder() -> LLMDispatcherBuilder {
+        LLMDispatcherBuilder::new()
+    }
+
+    /// 使用默认配置创建 dispatcher，并根据数据库中启用的供应商自动注册适配器
+    pub async fn from_database(pool: &SqlitePool) -> Result<Self> {
+        Self::builder().with_database(pool.clone()).build().await
+    }
+
+    /// 创建支持数据库的dispatcher，自动初始化数据库和客户端池
+    pub async fn new_with_database(config: Option<DispatchConfig>, db_url: &str, init_sql_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        // 初始化数据库连接池
+        println!("🔧 正在初始化数据库连接池...");
+        init_sqlite_pool(db_url).await;
         
         let pool = match SQLITE_POOL.get() {
             Some(pool) => {
@@ -458,91 +1635,900 @@ impl LLMDispatcher {
             }
         }
 
-        // 初始化缓存
-        println!("💾 正在初始化内存缓存...");
-        match init_global_cache(&pool, 3600, 1000).await {
-            Ok(_) => println!("✅ 内存缓存初始化完成"),
-            Err(e) => {
-                eprintln!("❌ 内存缓存初始化失败: {}", e);
-                return Err(e.into());
+        // 初始化缓存
+        println!("💾 正在初始化内存缓存...");
+        let cache = &gateway_config().cache;
+        match init_global_cache(&pool, cache.ttl_secs, cache.max_entries).await {
+            Ok(_) => println!("✅ 内存缓存初始化完成"),
+            Err(e) => {
+                eprintln!("❌ 内存缓存初始化失败: {}", e);
+                return Err(e.into());
+            }
+        }
+        
+        // 预加载 API Key 到内存
+        println!("🔄 正在预加载 API Key 到内存...");
+        preload_provider_key_pools_to_cache(&pool).await?;
+        println!("✅ API Key 预加载完成");
+
+        // 加载关键词黑名单
+        println!("🚫 正在加载关键词黑名单...");
+        let blocklist_count = reload_blocklist(&pool).await?;
+        println!("✅ 关键词黑名单加载完成 (规则数: {})", blocklist_count);
+
+        // 加载WASM转换插件
+        #[cfg(feature = "wasm-plugins")]
+        if let Ok(dir) = std::env::var("WASM_PLUGIN_DIR") {
+            println!("🧩 正在加载WASM转换插件...");
+            let plugin_count = crate::llm_api::utils::wasm_plugin::load_wasm_plugins(std::path::Path::new(&dir))?;
+            println!("✅ WASM转换插件加载完成 (数量: {})", plugin_count);
+        }
+
+        // 创建dispatcher
+        let dispatcher = Self::new(config);
+
+        // 根据providers表自动注册适配器
+        println!("🔌 正在根据数据库注册供应商适配器...");
+        let providers = dispatcher.sync_providers_from_db(&pool).await?;
+        println!("✅ 供应商适配器注册完成 (数量: {})", providers.len());
+        
+        Ok(dispatcher)
+    }
+
+    /// 注册Ali客户端池
+    pub async fn register_ali_pool(&self, pool_size: usize) -> Result<(), Box<dyn std::error::Error>> {
+        println!("🏊 正在初始化阿里云客户端池...");
+        
+        // 创建多个DynamicAliClient实例
+        let mut clients = Vec::new();
+        for _ in 0..pool_size {
+            let client = DynamicAliClient::new()?;
+            clients.push(client);
+        }
+        
+        let pool = Arc::new(ClientPool::new(clients));
+        let adapter = AliPoolAdapter::new(pool);
+        
+        self.register_client(Box::new(adapter)).await;
+        println!("✅ 阿里云客户端池初始化完成 (大小: {})", pool_size);
+        
+        Ok(())
+    }
+
+    // 注册客户端（手动注册的客户端不会被数据库同步覆盖或移除）
+    pub async fn register_client(&self, client: Box<dyn LLMClientAdapter>) {
+        let provider = client.provider_name();
+        self.managed_providers.write().await.remove(&provider);
+        let mut clients = self.clients.write().await;
+        clients.insert(provider, client);
+    }
+
+    // 注销客户端
+    pub async fn unregister_client(&self, provider: &Provider) -> bool {
+        self.managed_providers.write().await.remove(provider);
+        let mut clients = self.clients.write().await;
+        clients.remove(provider).is_some()
+    }
+
+    /// 读取数据库中的providers，为每个启用的供应商创建对应适配器并注册，
+    /// 之前自动注册但已停用或删除的供应商会被注销。返回当前自动注册的供应商
+    pub async fn sync_providers_from_db(&self, pool: &SqlitePool) -> Result<Vec<Provider>> {
+        let records = get_all_providers(pool).await?;
+        let manual: HashSet<Provider> = {
+            let clients = self.clients.read().await;
+            let managed = self.managed_providers.read().await;
+            clients.keys().filter(|p| !managed.contains(p)).cloned().collect()
+        };
+
+        let mut adapters = Vec::new();
+        let mut provider_projects = HashMap::new();
+        let mut concurrency_limits = HashMap::new();
+        for record in records.iter().filter(|r| r.is_active) {
+            let provider = Provider::from_name_or_custom(&record.name);
+            if manual.contains(&provider) {
+                continue;
+            }
+            let settings: Option<serde_json::Value> = match record.config.as_deref().map(serde_json::from_str).transpose() {
+                Ok(settings) => settings,
+                Err(e) => {
+                    warn!(provider = %record.name, error = %e, "Invalid provider config JSON, skipping");
+                    continue;
+                }
+            };
+            // providers.config 中的 type 指定供应商类型（例如 openai_compatible），未指定时按名称查找
+            let provider_type = settings.as_ref()
+                .and_then(|settings| settings.get("type"))
+                .and_then(|value| value.as_str())
+                .unwrap_or(provider.as_str())
+                .to_string();
+            let Some(factory) = provider_registry().get(&provider_type) else {
+                debug!(provider = %record.name, provider_type = %provider_type, "No factory registered for provider type, skipping");
+                continue;
+            };
+            let config = ProviderConfig::new(provider.clone(), &record.name, record.base_url.as_deref())
+                .with_settings(settings);
+            if let Some(project_id) = &record.project_id {
+                provider_projects.insert(provider.clone(), project_id.clone());
+            }
+            if let Some(limit) = record.max_concurrent_requests.filter(|limit| *limit > 0) {
+                concurrency_limits.insert(provider.clone(), limit as usize);
+            }
+            match factory.create_adapter(&config) {
+                Ok(adapter) => adapters.push((provider, adapter)),
+                Err(e) => warn!(provider = %record.name, error = %e, "Failed to build provider adapter"),
+            }
+        }
+
+        let mut managed = self.managed_providers.write().await;
+        let mut clients = self.clients.write().await;
+        let active: HashSet<Provider> = adapters.iter().map(|(p, _)| p.clone()).collect();
+        for stale in managed.difference(&active) {
+            clients.remove(stale);
+            info!(provider = stale.as_str(), "Unregistered provider adapter");
+        }
+        for (provider, adapter) in adapters {
+            clients.insert(provider, adapter);
+        }
+        *managed = active;
+        *self.provider_projects.write().await = provider_projects;
+        // 手动注册的供应商保留通过 set_concurrency_limit 设置的上限
+        for provider in &manual {
+            if let Some(limit) = self.concurrency.limit(provider) {
+                concurrency_limits.insert(provider.clone(), limit);
+            }
+        }
+        self.concurrency.replace_limits(concurrency_limits);
+
+        let mut registered: Vec<Provider> = managed.iter().cloned().collect();
+        registered.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        info!(providers = ?registered, "Provider adapters synchronized from database");
+        Ok(registered)
+    }
+
+    /// 设置供应商的并发上限，`None` 表示不限制；数据库同步的供应商以 providers 表为准
+    pub fn set_concurrency_limit(&self, provider: &Provider, limit: Option<usize>) {
+        self.concurrency.set_limit(provider, limit);
+    }
+
+    /// 供应商的并发上限和正在进行的请求数
+    pub fn concurrency_usage(&self, provider: &Provider) -> (Option<usize>, usize) {
+        (self.concurrency.limit(provider), self.concurrency.in_flight(provider))
+    }
+
+    /// 所有设置了并发上限的供应商的槽位占用和排队请求数
+    pub fn concurrency_stats(&self) -> Vec<ProviderConcurrencyStats> {
+        self.concurrency.stats()
+    }
+
+    /// 供应商变更后按创建时指定的数据库重新同步适配器，未指定数据库时返回错误
+    pub async fn resync_providers(&self) -> Result<Vec<Provider>> {
+        let pool = self.database.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Dispatcher was not created from a database"))?;
+        self.sync_providers_from_db(pool).await
+    }
+
+    // 批量注册客户端
+    pub async fn register_clients(&self, clients: Vec<Box<dyn LLMClientAdapter>>) {
+        for client in clients {
+            self.register_client(client).await;
+        }
+    }
+
+    // 注册扩展钩子，按注册顺序执行
+    pub async fn register_hook(&self, hook: Box<dyn DispatchHook>) {
+        info!(hook = hook.name(), "Registered dispatch hook");
+        self.hooks.write().await.push(Arc::from(hook));
+    }
+
+    // 已注册的扩展钩子名称（按执行顺序）
+    pub async fn hook_names(&self) -> Vec<String> {
+        self.hooks.read().await.names()
+    }
+
+    // 主要的dispatch方法
+    #[instrument(name = "dispatch", skip_all, fields(request_id = field::Empty, model = field::Empty, provider = field::Empty, stream = false))]
+    pub async fn dispatch(&self, mut request: DispatchRequest) -> Result<DispatchResponse, LLMError> {
+        // 应用默认配置
+        self.apply_defaults(&mut request);
+
+        // 终端用户限流
+        let end_user = Self::end_user_key(&request);
+        if let Some((tenant_id, user)) = &end_user
+            && !get_abuse_guard().check_and_record_request(tenant_id, user).await
+        {
+            return Err(LLMError::RateLimit);
+        }
+
+        // 检测提示词语言并按语言路由
+        let detected_language = detect_prompt_language(&request.messages);
+        self.apply_locale_routing(&mut request, detected_language.as_ref());
+        Self::apply_script_routing(&mut request, detected_language.as_ref());
+        let traffic_arm = self.apply_traffic_split(&mut request);
+        Self::record_route(&request);
+
+        let request_id = request.request_id.clone();
+        let metadata = CallMetadata {
+            request_id: request_id.clone(),
+            detected_language: detected_language.map(|language| language.code),
+            traffic_arm,
+            ..CallMetadata::inherited()
+        };
+        let result = CALL_METADATA.scope(metadata, self.dispatch_hooked(request)).await
+            .map(|response| DispatchResponse { request_id, ..response });
+
+        // 记录终端用户请求结果
+        if let Some((tenant_id, user)) = &end_user {
+            get_abuse_guard().record_outcome(tenant_id, user, Self::classify_outcome(&result)).await;
+        }
+
+        result
+    }
+
+    // 执行扩展钩子：on_request 可以改写或拒绝请求，之后按结果执行 on_response 或 on_error
+    async fn dispatch_hooked(&self, mut request: DispatchRequest) -> Result<DispatchResponse, LLMError> {
+        let hooks = self.hooks.read().await.clone();
+        if hooks.is_empty() {
+            return self.dispatch_filtered(request).await;
+        }
+
+        let result = match hooks.on_request(&mut request).await {
+            Ok(()) => {
+                Self::record_route(&request);
+                self.dispatch_filtered(request.clone()).await
+            }
+            Err(e) => Err(e),
+        };
+        hooks.on_result(&request, result).await
+    }
+
+    // 对提示词和响应执行黑名单检查
+    async fn dispatch_filtered(&self, request: DispatchRequest) -> Result<DispatchResponse, LLMError> {
+        let mut request = Self::apply_request_plugins(request)?;
+        Self::apply_system_prompt_policy(&mut request).await?;
+        self.apply_prompt_blocklist(&mut request).await?;
+        self.fit_context_window(&mut request).await?;
+        let tenant_id = request.tenant_id.clone();
+        let shadow_request = self.default_config.shadow.as_ref()
+            .filter(|shadow| shadow.should_mirror(&request))
+            .map(|shadow| shadow.mirror_request(&request));
+
+        let started = std::time::Instant::now();
+        let response = self.dispatch_structured(request).await?;
+        let mut response = get_transform_pipeline()
+            .apply_response(response)
+            .map_err(|e| LLMError::ApiError(format!("{:#}", e)))?;
+
+        let verdict = get_blocklist()
+            .evaluate(tenant_id.as_deref(), &response.content, BlocklistTarget::Response)
+            .await;
+        if verdict.is_blocked() {
+            return Err(LLMError::ContentBlocked("response matched blocklist".to_string()));
+        }
+        response.content = verdict.text;
+        if let Some(shadow_request) = shadow_request {
+            self.spawn_shadow_request(shadow_request, response.clone(), started.elapsed());
+        }
+        Ok(response)
+    }
+
+    // 主请求成功后在后台把镜像请求发往影子供应商，结果只写入对比记录，不影响主请求
+    fn spawn_shadow_request(&self, request: DispatchRequest, primary: DispatchResponse, primary_latency: std::time::Duration) {
+        let clients = self.clients.clone();
+        let concurrency = self.concurrency.clone();
+        let metadata = CallMetadata::default()
+            .with_attempt(request.provider.as_str(), summarize_request(&request))
+            .with_retry_budget(RetryBudget::new(1))
+            .with_timeout(request.timeout_ms.map(std::time::Duration::from_millis));
+        tokio::spawn(async move {
+            let metadata = metadata.with_model_id(get_model_id_from_cache(request.provider.as_str(), &request.model).await);
+            let started = std::time::Instant::now();
+            // 影子请求不排队，影子供应商并发已满时直接记为失败
+            let result = async {
+                let _permit = concurrency.acquire(&request.provider, RequestPriority::Batch, std::time::Duration::ZERO).await?;
+                let clients = clients.read().await;
+                match clients.get(&request.provider) {
+                    Some(client) => CALL_METADATA.scope(metadata, client.generate(&request)).await,
+                    None => Err(LLMError::UnsupportedProvider(request.provider.clone())),
+                }
+            }.await;
+            record_shadow_comparison(&primary, primary_latency, &request, &result, started.elapsed()).await;
+        }.instrument(info_span!("shadow_request")));
+    }
+
+    // 开启 validate_response 时校验结构化输出，不符合时带上错误要求模型修正一次
+    async fn dispatch_structured(&self, request: DispatchRequest) -> Result<DispatchResponse, LLMError> {
+        let format = match &request.response_format {
+            Some(format) if format.is_json() && request.validate_response == Some(true) => format.clone(),
+            _ => return self.dispatch_validated(request).await,
+        };
+
+        let response = self.dispatch_validated(request.clone()).await?;
+        // 只返回工具调用时没有内容可校验
+        if response.tool_calls.is_some() {
+            return Ok(response);
+        }
+        let Err(error) = check_response(&format, &response.content) else {
+            return Ok(response);
+        };
+        warn!(model = %request.model, error = %error, "Response does not match response_format, asking model to repair");
+
+        let mut repair_request = request;
+        repair_request.messages.extend(repair_messages(&response.content, &error));
+        let repaired = self.dispatch_validated(repair_request).await?;
+        match check_response(&format, &repaired.content) {
+            Ok(()) => {
+                metrics().incr_counter("llm_gateway_response_format_repairs_total", &[("outcome", "repaired")]);
+                Ok(repaired)
+            }
+            Err(error) => {
+                metrics().incr_counter("llm_gateway_response_format_repairs_total", &[("outcome", "failed")]);
+                Err(LLMError::ApiError(format!("Response does not match response_format after repair: {}", error)))
             }
         }
-        
-        // 预加载 API Key 到内存
-        println!("🔄 正在预加载 API Key 到内存...");
-        preload_provider_key_pools_to_cache(&pool).await?;
-        println!("✅ API Key 预加载完成");
+    }
 
-        // 创建dispatcher
-        let dispatcher = Self::new(config);
-        
-        Ok(dispatcher)
+    // 执行转换插件的请求钩子，插件拒绝视为参数错误
+    fn apply_request_plugins(request: DispatchRequest) -> Result<DispatchRequest, LLMError> {
+        get_transform_pipeline()
+            .apply_request(request)
+            .map_err(|e| LLMError::InvalidParameters(format!("{:#}", e)))
     }
 
-    /// 注册Ali客户端池
-    pub async fn register_ali_pool(&self, pool_size: usize) -> Result<(), Box<dyn std::error::Error>> {
-        println!("🏊 正在初始化阿里云客户端池...");
-        
-        // 创建多个DynamicAliClient实例
-        let mut clients = Vec::new();
-        for _ in 0..pool_size {
-            let client = DynamicAliClient::new()?;
-            clients.push(client);
-        }
-        
-        let pool = Arc::new(ClientPool::new(clients));
-        let adapter = AliPoolAdapter::new(pool);
-        
-        self.register_client(Box::new(adapter)).await;
-        println!("✅ 阿里云客户端池初始化完成 (大小: {})", pool_size);
-        
+    // 按目标模型或所属项目的系统提示词策略改写 system 消息，在映射为供应商请求格式之前执行
+    async fn apply_system_prompt_policy(request: &mut DispatchRequest) -> Result<(), LLMError> {
+        let project_id = CallMetadata::current().project_id().to_string();
+        let Some((policy, source)) = resolve_policy(request.provider.as_str(), &request.model, &project_id).await else {
+            return Ok(());
+        };
+        policy.apply(&mut request.messages).map_err(LLMError::InvalidParameters)?;
+        metrics().incr_counter("llm_gateway_system_prompt_policy_applied_total", &[("mode", policy.mode.as_str()), ("source", source)]);
+        debug!(model = %request.model, mode = policy.mode.as_str(), source, "Applied system prompt policy");
         Ok(())
     }
 
-    // 注册客户端
-    pub async fn register_client(&self, client: Box<dyn LLMClientAdapter>) {
-        let provider = client.provider_name();
-        let mut clients = self.clients.write().await;
-        clients.insert(provider, client);
+    // 提示词黑名单检查，命中mask规则时改写消息内容
+    async fn apply_prompt_blocklist(&self, request: &mut DispatchRequest) -> Result<(), LLMError> {
+        let blocklist = get_blocklist();
+        for message in request.messages.iter_mut().filter(|m| m.role == "user") {
+            let verdict = blocklist
+                .evaluate(request.tenant_id.as_deref(), &message.content, BlocklistTarget::Prompt)
+                .await;
+            if verdict.is_blocked() {
+                return Err(LLMError::ContentBlocked("prompt matched blocklist".to_string()));
+            }
+            message.content = verdict.text;
+        }
+        Ok(())
     }
 
-    // 批量注册客户端
-    pub async fn register_clients(&self, clients: Vec<Box<dyn LLMClientAdapter>>) {
-        for client in clients {
-            self.register_client(client).await;
+    // 历史超出上下文窗口时按 context_strategy 丢弃最早的非 system 消息，或把它们压缩为摘要；
+    // 默认不处理，由 validate_request 返回 context_length_exceeded
+    async fn fit_context_window(&self, request: &mut DispatchRequest) -> Result<(), LLMError> {
+        let strategy = request.context_strategy.unwrap_or_default();
+        if strategy == ContextStrategy::Error {
+            return Ok(());
         }
+        let Some(window) = self.context_window(request) else {
+            return Ok(());
+        };
+
+        // 窗口中预留回复、回复前缀和工具定义
+        let reserved = request.max_tokens.unwrap_or(0) as usize
+            + REPLY_PRIMING_TOKENS
+            + request.tools.as_deref().map_or(0, |tools| count_tool_tokens(tools, &request.model));
+        let budget = window.saturating_sub(reserved);
+        let model = request.model.clone();
+        let count = |message: &Message| count_message_tokens(message, &model);
+
+        let truncated = truncate_history_by(std::mem::take(&mut request.messages), budget, TruncationStrategy::DropOldest, count);
+        request.messages = truncated.messages;
+        if truncated.dropped.is_empty() {
+            return Ok(());
+        }
+
+        let mut outcome = "truncated";
+        if strategy == ContextStrategy::Summarize {
+            match self.summarize_history(request, truncated.dropped).await {
+                Ok(()) => {
+                    outcome = "summarized";
+                    // 摘要本身也占用窗口，必要时继续丢弃较早的消息
+                    let truncated = truncate_history_by(std::mem::take(&mut request.messages), budget, TruncationStrategy::DropOldest, count);
+                    request.messages = truncated.messages;
+                }
+                Err(e) => warn!(model = %request.model, error = %e, "Failed to summarize history, dropping oldest messages instead"),
+            }
+        }
+        metrics().incr_counter("llm_gateway_context_truncations_total", &[("outcome", outcome)]);
+        debug!(model = %request.model, window, outcome, "History exceeded context window");
+        Ok(())
     }
 
-    // 主要的dispatch方法
-    pub async fn dispatch(&self, mut request: DispatchRequest) -> Result<DispatchResponse, LLMError> {
-        // 应用默认配置
-        self.apply_defaults(&mut request);
+    // 把丢弃的消息连同之前的摘要压缩为新的摘要，作为 system 消息放在原有 system 消息之后
+    async fn summarize_history(&self, request: &mut DispatchRequest, dropped: Vec<Message>) -> Result<(), LLMError> {
+        let previous = request.messages.iter()
+            .position(|m| m.role == "system" && m.content.starts_with(SUMMARY_PREFIX))
+            .map(|index| request.messages.remove(index));
+        let transcript = previous.iter()
+            .map(|summary| summary.content.clone())
+            .chain(dropped.iter().map(|m| format!("{}: {}", m.role, m.content)))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let (provider, model) = self.default_config.summary_model.clone()
+            .unwrap_or_else(|| (request.provider.clone(), request.model.clone()));
+        let mut summary_request = DispatchRequest::new(provider, model, vec![
+            Message::system(SUMMARY_INSTRUCTION.to_string()),
+            Message::user(transcript),
+        ]);
+        summary_request.max_tokens = Some(SUMMARY_MAX_TOKENS);
+        summary_request.tenant_id = request.tenant_id.clone();
+        let summary = self.dispatch_validated(summary_request).await?;
+
+        let index = request.messages.iter().take_while(|m| m.role == "system").count();
+        request.messages.insert(index, Message::system(format!("{} {}", SUMMARY_PREFIX, summary.content.trim())));
+        Ok(())
+    }
 
-        // 验证请求参数
-        self.validate_request(&request)?;
+    // 验证请求并执行（包含fallback）
+    async fn dispatch_validated(&self, mut request: DispatchRequest) -> Result<DispatchResponse, LLMError> {
+        // 验证请求参数，按模型约束调整超出范围的参数
+        let warnings = self.validate_request(&mut request).await?;
+
+        // 降级模式下不访问上游
+        let degradation = get_degradation_guard();
+        if degradation.is_active().await {
+            return Ok(degradation.degraded_response(&request).await.0);
+        }
 
         // 获取客户端并执行
         let result = self.dispatch_internal(&request).await;
+        // 本次请求的所有候选供应商是否都因上游不可用而失败，只有这种情况计入自动降级
+        let mut all_candidates_down = matches!(&result, Err(e) if Self::is_upstream_failure(e));
 
         // 如果启用了fallback且请求失败，尝试备选供应商
-        match result {
-            Err(e) if self.default_config.enable_fallback => {
-                self.try_fallback(request, e).await
+        let result = match result {
+            Err(e) if self.default_config.enable_fallback && request.fallback != Some(false) && !matches!(e, LLMError::Cancelled) => {
+                let (result, fallbacks_down) = self.try_fallback(request.clone(), e).await;
+                all_candidates_down &= fallbacks_down;
+                result
+            }
+            // 未尝试 fallback 时，还有其他可用的候选供应商就不能认为上游全部不可用
+            Err(e) => {
+                if all_candidates_down {
+                    all_candidates_down = !self.has_fallback_candidates(&request).await;
+                }
+                Err(e)
             }
             other => other,
+        };
+        let result = result.map(|response| Self::with_warnings(Self::estimate_usage(&request, response), warnings));
+
+        match &result {
+            Ok(response) => {
+                if let Some(usage) = &response.usage {
+                    record_call_usage(
+                        &CallMetadata::current(),
+                        &response.provider,
+                        &response.model,
+                        usage,
+                        response.finish_reason.as_deref(),
+                    ).await;
+                }
+                degradation.record_success(&request, response).await
+            }
+            Err(_) if all_candidates_down => {
+                if degradation.record_outage().await {
+                    return Ok(degradation.degraded_response(&request).await.0);
+                }
+            }
+            Err(_) => {}
+        }
+        result
+    }
+
+    // 是否为上游不可用导致的失败（参数错误、内容拦截等不计入）
+    fn is_upstream_failure(error: &LLMError) -> bool {
+        match error {
+            LLMError::Timeout | LLMError::RateLimit | LLMError::Network(_) | LLMError::ApiError(_) => true,
+            LLMError::ClientError(ClientError::LLMApi { status_code: Some(code), .. }) => {
+                *code == 429 || !(400..500).contains(code)
+            }
+            LLMError::ClientError(_) => true,
+            _ => false,
+        }
+    }
+
+    // 按检测到的语言改写请求的供应商和模型；调用方固定了供应商时不改写
+    fn apply_locale_routing(&self, request: &mut DispatchRequest, detected_language: Option<&DetectedLanguage>) {
+        if !self.default_config.enable_locale_routing || request.pin_provider == Some(true) {
+            return;
+        }
+        let Some(language) = detected_language.filter(|language| language.is_reliable) else {
+            return;
+        };
+
+        let route = self.default_config.locale_routes.get(&language.code)
+            .or(self.default_config.default_locale_route.as_ref());
+        if let Some(route) = route {
+            debug!(
+                language = %language.code,
+                from_provider = ?request.provider,
+                from_model = %request.model,
+                to_provider = ?route.provider,
+                to_model = %route.model,
+                "Routing request by prompt language"
+            );
+            request.provider = route.provider.clone();
+            request.model = route.model.clone();
+        }
+    }
+
+    // 执行路由脚本改写供应商和模型，脚本出错时保持原路由；调用方固定了供应商时不执行
+    fn apply_script_routing(request: &mut DispatchRequest, detected_language: Option<&DetectedLanguage>) {
+        let engine = get_route_script_engine();
+        if !engine.is_loaded() || request.pin_provider == Some(true) {
+            return;
+        }
+
+        let language = detected_language
+            .filter(|language| language.is_reliable)
+            .map(|language| language.code.as_str());
+        let decision = match engine.evaluate(request, language) {
+            Ok(decision) => decision,
+            Err(e) => {
+                warn!(error = %e, "Route script failed, keeping original route");
+                metrics().incr_counter("llm_gateway_route_script_evaluations_total", &[("result", "error")]);
+                return;
+            }
+        };
+
+        let result = if decision.is_some() { "routed" } else { "unchanged" };
+        metrics().incr_counter("llm_gateway_route_script_evaluations_total", &[("result", result)]);
+        if let Some(decision) = decision {
+            debug!(
+                from_provider = ?request.provider,
+                from_model = %request.model,
+                to_provider = ?decision.provider,
+                to_model = ?decision.model,
+                "Routing request by script"
+            );
+            if let Some(provider) = decision.provider {
+                request.provider = provider;
+            }
+            if let Some(model) = decision.model {
+                request.model = model;
+            }
+        }
+    }
+
+    // 模型名配置了流量拆分时按百分比选择分组并改写供应商和模型，返回分组标识；调用方固定了供应商时不拆分
+    fn apply_traffic_split(&self, request: &mut DispatchRequest) -> Option<String> {
+        if request.pin_provider == Some(true) {
+            return None;
+        }
+        let split = self.default_config.traffic_splits.get(&request.model)?;
+        let arm = split.choose(request.user.as_deref());
+        let label = arm_label(&request.model, arm);
+        debug!(
+            arm = %label,
+            from_provider = ?request.provider,
+            to_provider = %arm.provider,
+            to_model = %arm.model,
+            "Routing request by traffic split"
+        );
+        metrics().incr_counter("llm_gateway_traffic_split_requests_total", &[("arm", label.as_str())]);
+        request.provider = arm.provider();
+        request.model = arm.model.clone();
+        Some(label)
+    }
+
+    // 获取终端用户标识（租户, 用户）
+    fn end_user_key(request: &DispatchRequest) -> Option<(String, String)> {
+        let user = request.user.as_ref().filter(|u| !u.trim().is_empty())?;
+        let tenant_id = request.tenant_id.clone().unwrap_or_else(|| DEFAULT_TENANT.to_string());
+        Some((tenant_id, user.clone()))
+    }
+
+    // 将dispatch结果归类为终端用户行为
+    fn classify_outcome(result: &Result<DispatchResponse, LLMError>) -> UserOutcome {
+        match result {
+            Ok(response) if response.finish_reason.as_deref() == Some("content_filter") => UserOutcome::ContentViolation,
+            Ok(_) => UserOutcome::Normal,
+            Err(e) => Self::classify_error(e),
+        }
+    }
+
+    fn classify_error(error: &LLMError) -> UserOutcome {
+        match error {
+            LLMError::ContentBlocked(_) => UserOutcome::ContentViolation,
+            LLMError::InvalidParameters(_)
+            | LLMError::ContextLengthExceeded(_)
+            | LLMError::CostLimitExceeded(_)
+            | LLMError::ModelNotAvailable(_)
+            | LLMError::UnsupportedProvider(_) => UserOutcome::ClientError,
+            LLMError::ClientError(ClientError::LLMApi { status_code: Some(code), .. })
+                if (400..500).contains(code) => UserOutcome::ClientError,
+            _ => UserOutcome::Normal,
         }
     }
 
     // 流式dispatch
-    pub async fn dispatch_stream(&self, mut request: DispatchRequest) -> Result<tokio::sync::mpsc::Receiver<Result<String, LLMError>>, LLMError> {
+    #[instrument(name = "dispatch", skip_all, fields(request_id = field::Empty, model = field::Empty, provider = field::Empty, stream = true))]
+    pub async fn dispatch_stream(&self, mut request: DispatchRequest) -> Result<StreamReceiver, LLMError> {
         self.apply_defaults(&mut request);
-        self.validate_request(&request)?;
+        request.stream = Some(true);
+
+        // 终端用户限流
+        let end_user = Self::end_user_key(&request);
+        if let Some((tenant_id, user)) = &end_user
+            && !get_abuse_guard().check_and_record_request(tenant_id, user).await
+        {
+            return Err(LLMError::RateLimit);
+        }
+
+        let detected_language = detect_prompt_language(&request.messages);
+        self.apply_locale_routing(&mut request, detected_language.as_ref());
+        Self::apply_script_routing(&mut request, detected_language.as_ref());
+        let traffic_arm = self.apply_traffic_split(&mut request);
+
+        // 扩展钩子只在建立流失败时执行 on_error
+        let hooks = self.hooks.read().await.clone();
+        let result = match hooks.on_request(&mut request).await {
+            Ok(()) => {
+                Self::record_route(&request);
+                let hooked_request = (!hooks.is_empty()).then(|| request.clone());
+                let blocklist = get_blocklist().stream_filter(request.tenant_id.as_deref()).await;
+                let result = self.open_stream(request, detected_language, traffic_arm).await;
+                if let (Some(request), Err(e)) = (&hooked_request, &result) {
+                    hooks.on_error(request, e).await;
+                }
+                match blocklist {
+                    Some(blocklist) => result.map(|receiver| filter_stream_blocklist(receiver, blocklist)),
+                    None => result,
+                }
+            }
+            Err(e) => {
+                hooks.on_error(&request, &e).await;
+                Err(e)
+            }
+        };
+
+        // 记录终端用户请求结果：建立流失败时立即记录，成功时在流结束后按结束原因和流中的错误记录
+        match (end_user, result) {
+            (Some((tenant_id, user)), Ok(receiver)) => Ok(record_stream_outcome(receiver, tenant_id, user)),
+            (Some((tenant_id, user)), Err(e)) => {
+                get_abuse_guard().record_outcome(&tenant_id, &user, Self::classify_error(&e)).await;
+                Err(e)
+            }
+            (None, result) => result,
+        }
+    }
+
+    // 执行黑名单、上下文窗口和各项检查后建立上游流
+    async fn open_stream(
+        &self,
+        request: DispatchRequest,
+        detected_language: Option<DetectedLanguage>,
+        traffic_arm: Option<String>,
+    ) -> Result<StreamReceiver, LLMError> {
+        let mut request = Self::apply_request_plugins(request)?;
+        Self::apply_system_prompt_policy(&mut request).await?;
+        self.apply_prompt_blocklist(&mut request).await?;
+        self.fit_context_window(&mut request).await?;
+        // 流式响应没有携带调整说明的位置，只记录日志和指标
+        self.validate_request(&mut request).await?;
+
+        // 降级模式下以单个增量块返回降级响应
+        let degradation = get_degradation_guard();
+        if degradation.is_active().await {
+            let (response, _) = degradation.degraded_response(&request).await;
+            return Ok(spawn_stream(move |sink| async move {
+                let _ = sink.send(Ok(StreamChunk::delta(response.content)));
+                let _ = sink.send(Ok(StreamChunk::finished(response.finish_reason, response.usage)));
+            }));
+        }
+
+        let clients = self.clients.read().await;
+        let client = clients.get(&request.provider)
+            .ok_or_else(|| LLMError::UnsupportedProvider(request.provider.clone()))?;
+        self.ensure_project_access(&request.provider, &request.model).await?;
+        Self::ensure_model_healthy(&request.provider, &request.model).await?;
+        ensure_within_budget(CallMetadata::current().project_id(), &request.provider).await?;
+        ensure_within_max_cost(&request).await?;
+
+        let retry_count = request.retry_count.unwrap_or(self.default_config.default_retry_count);
+        let metadata = CallMetadata {
+            request_id: request.request_id.clone(),
+            detected_language: detected_language.map(|language| language.code),
+            traffic_arm,
+            ..CallMetadata::inherited()
+        }
+        .with_attempt(request.provider.as_str(), summarize_request(&request))
+        .with_model_id(get_model_id_from_cache(request.provider.as_str(), &request.model).await)
+        .with_retry_budget(RetryBudget::new(retry_count + 1))
+        .with_timeout(request.timeout_ms.map(std::time::Duration::from_millis));
+        let permit = self.acquire_provider_slot(&request.provider, request.priority.unwrap_or_default(), &metadata).await?;
+        let span = info_span!("adapter.generate_stream", provider = %request.provider.as_str(), model = %request.model);
+        // 流式请求按收到响应为止的耗时计入负载均衡统计
+        let started = std::time::Instant::now();
+        let result = CALL_METADATA.scope(metadata.clone(), client.generate_stream(&request)).instrument(span).await;
+        if !metadata.cancellation.is_cancelled() {
+            get_load_balancer().record(&request.provider, &request.model, started.elapsed(), result.is_ok());
+        }
+        let receiver = result?;
+        Ok(record_stream_usage(receiver, metadata, request.provider.clone(), request.model.clone(), count_request_tokens(&request), permit))
+    }
+
+    // 预估请求费用：按语言和路由脚本改写后的供应商和模型计算，不访问上游
+    pub async fn estimate_cost(&self, mut request: DispatchRequest) -> CostEstimate {
+        let detected_language = detect_prompt_language(&request.messages);
+        self.apply_locale_routing(&mut request, detected_language.as_ref());
+        Self::apply_script_routing(&mut request, detected_language.as_ref());
+        estimate_cost(&request).await
+    }
+
+    // 批量dispatch：最多 concurrency 个请求同时执行，每个请求单独走完整的调度流程，结果按请求顺序返回
+    pub async fn dispatch_batch(&self, requests: Vec<DispatchRequest>, concurrency: usize) -> Vec<Result<DispatchResponse, LLMError>> {
+        let semaphore = &Semaphore::new(concurrency.max(1));
+        let tasks = requests.into_iter().map(|mut request| async move {
+            // 批量请求默认以 batch 优先级排队，让出槽位给交互式请求
+            request.priority.get_or_insert(RequestPriority::Batch);
+            let _permit = semaphore.acquire().await.expect("batch semaphore closed");
+            self.dispatch(request).await
+        });
+        futures::future::join_all(tasks).await
+    }
+
+    // 文本补全dispatch：提示词按原样续写，不经过对话相关的处理（上下文窗口、结构化输出、降级、fallback）
+    #[instrument(name = "dispatch_completion", skip_all, fields(model = %request.model, provider = %request.provider.as_str(), stream = false))]
+    pub async fn dispatch_completion(&self, mut request: CompletionRequest) -> Result<DispatchResponse, LLMError> {
+        self.prepare_completion(&mut request).await?;
+        let clients = self.clients.read().await;
+        let client = self.completion_client(&clients, &request).await?;
+
+        let retry_count = request.retry_count.unwrap_or(self.default_config.default_retry_count);
+        let metadata = Self::completion_metadata(&request, retry_count).await;
+        let mut last_error = None;
+        for attempt in 0..=retry_count {
+            let _permit = self.acquire_provider_slot(&request.provider, RequestPriority::Normal, &metadata).await?;
+            let span = info_span!("adapter.complete", provider = %request.provider.as_str(), model = %request.model, attempt = attempt + 1);
+            match CALL_METADATA.scope(metadata.clone(), client.complete(&request)).instrument(span).await {
+                Ok(response) => {
+                    let response = self.finish_completion(&request, &metadata, response).await?;
+                    return Ok(response);
+                }
+                // 不支持补全或请求被取消时不再重试
+                Err(e @ LLMError::UnsupportedProvider(_)) => return Err(e),
+                Err(_) if metadata.cancellation.is_cancelled() => return Err(LLMError::Cancelled),
+                Err(e) => {
+                    last_error = Some(e);
+                    if attempt < retry_count {
+                        let retry_wait = metadata.retry_budget.retry_wait();
+                        if metadata.retry_budget.is_exhausted() || retry_wait > MAX_RETRY_WAIT {
+                            break;
+                        }
+                        let backoff = tokio::time::Duration::from_millis(1000 * (attempt + 1) as u64);
+                        let wait = tokio::time::sleep(backoff.max(retry_wait));
+                        if metadata.cancellation.run_until_cancelled(wait).await.is_none() {
+                            return Err(LLMError::Cancelled);
+                        }
+                    }
+                }
+            }
+        }
+
+        Err(last_error.unwrap())
+    }
 
+    // 流式文本补全dispatch
+    #[instrument(name = "dispatch_completion", skip_all, fields(model = %request.model, provider = %request.provider.as_str(), stream = true))]
+    pub async fn dispatch_completion_stream(&self, mut request: CompletionRequest) -> Result<StreamReceiver, LLMError> {
+        self.prepare_completion(&mut request).await?;
         let clients = self.clients.read().await;
+        let client = self.completion_client(&clients, &request).await?;
+
+        let retry_count = request.retry_count.unwrap_or(self.default_config.default_retry_count);
+        let metadata = Self::completion_metadata(&request, retry_count).await;
+        let permit = self.acquire_provider_slot(&request.provider, RequestPriority::Normal, &metadata).await?;
+        let span = info_span!("adapter.complete_stream", provider = %request.provider.as_str(), model = %request.model);
+        let receiver = CALL_METADATA.scope(metadata.clone(), client.complete_stream(&request)).instrument(span).await?;
+        let prompt_tokens = count_text_tokens(&request.prompt, &request.model);
+        let receiver = record_stream_usage(receiver, metadata, request.provider.clone(), request.model.clone(), prompt_tokens, permit);
+        Ok(match get_blocklist().stream_filter(request.tenant_id.as_deref()).await {
+            Some(blocklist) => filter_stream_blocklist(receiver, blocklist),
+            None => receiver,
+        })
+    }
+
+    // 补全请求的默认值、参数校验和提示词、后缀的黑名单检查
+    async fn prepare_completion(&self, request: &mut CompletionRequest) -> Result<(), LLMError> {
+        if request.temperature.is_none() {
+            request.temperature = Some(self.default_config.default_temperature);
+        }
+        if request.timeout_ms.is_none() {
+            request.timeout_ms = Some(self.default_config.default_timeout_ms);
+        }
+        if request.model.is_empty() {
+            return Err(LLMError::InvalidParameters("Model cannot be empty".to_string()));
+        }
+        if request.prompt.is_empty() {
+            return Err(LLMError::InvalidParameters("Prompt cannot be empty".to_string()));
+        }
+        if let Some(temp) = request.temperature
+            && !(0.0..=2.0).contains(&temp)
+        {
+            return Err(LLMError::InvalidParameters("Temperature must be between 0.0 and 2.0".to_string()));
+        }
+
+        // 后缀同样会发给模型，与提示词一样检查
+        let blocklist = get_blocklist();
+        let tenant_id = request.tenant_id.as_deref();
+        let verdict = blocklist.evaluate(tenant_id, &request.prompt, BlocklistTarget::Prompt).await;
+        if verdict.is_blocked() {
+            return Err(LLMError::ContentBlocked("prompt matched blocklist".to_string()));
+        }
+        request.prompt = verdict.text;
+        if let Some(suffix) = &request.suffix {
+            let verdict = blocklist.evaluate(tenant_id, suffix, BlocklistTarget::Prompt).await;
+            if verdict.is_blocked() {
+                return Err(LLMError::ContentBlocked("suffix matched blocklist".to_string()));
+            }
+            request.suffix = Some(verdict.text);
+        }
+        Ok(())
+    }
+
+    // 补全请求的适配器：与对话请求相同的模型目录、项目可见性、健康状态和预算检查
+    async fn completion_client<'a>(
+        &self,
+        clients: &'a HashMap<Provider, Box<dyn LLMClientAdapter>>,
+        request: &CompletionRequest,
+    ) -> Result<&'a dyn LLMClientAdapter, LLMError> {
         let client = clients.get(&request.provider)
             .ok_or_else(|| LLMError::UnsupportedProvider(request.provider.clone()))?;
+        if !Self::provider_models(&request.provider, client.as_ref()).await.contains(&request.model) {
+            return Err(LLMError::ModelNotAvailable(request.model.clone()));
+        }
+        self.ensure_project_access(&request.provider, &request.model).await?;
+        Self::ensure_model_healthy(&request.provider, &request.model).await?;
+        ensure_within_budget(CallMetadata::current().project_id(), &request.provider).await?;
+        Ok(client.as_ref())
+    }
+
+    // 补全请求的调用记录附加信息，请求摘要取提示词
+    async fn completion_metadata(request: &CompletionRequest, retry_count: u32) -> CallMetadata {
+        let metadata = CallMetadata::current();
+        let request_id = metadata.request_id.clone().unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        metadata
+            .with_request_id(Some(request_id))
+            .with_attempt(request.provider.as_str(), Some(truncate_summary(&request.prompt)))
+            .with_model_id(get_model_id_from_cache(request.provider.as_str(), &request.model).await)
+            .with_retry_budget(RetryBudget::new(retry_count + 1))
+            .with_timeout(request.timeout_ms.map(std::time::Duration::from_millis))
+    }
 
-        client.generate_stream(&request).await
+    // 补全结果的响应黑名单检查和用量记录，上游没有返回用量时按提示词和输出估算
+    async fn finish_completion(&self, request: &CompletionRequest, metadata: &CallMetadata, mut response: DispatchResponse) -> Result<DispatchResponse, LLMError> {
+        let verdict = get_blocklist().evaluate(request.tenant_id.as_deref(), &response.content, BlocklistTarget::Response).await;
+        if verdict.is_blocked() {
+            return Err(LLMError::ContentBlocked("response matched blocklist".to_string()));
+        }
+        response.content = verdict.text;
+
+        if response.usage.is_none() {
+            let prompt_tokens = count_text_tokens(&request.prompt, &request.model) as u32;
+            let completion_tokens = count_text_tokens(&response.content, &response.model) as u32;
+            response.usage = Some(TokenUsage {
+                prompt_tokens,
+                completion_tokens,
+                total_tokens: prompt_tokens + completion_tokens,
+            });
+        }
+        if let Some(usage) = &response.usage {
+            record_call_usage(metadata, &response.provider, &response.model, usage, response.finish_reason.as_deref()).await;
+        }
+        response.request_id = metadata.request_id.clone();
+        Ok(response)
     }
 
     // 获取所有支持的模型
@@ -552,23 +2538,114 @@ impl LLMDispatcher {
 
         if let Some(p) = provider {
             if let Some(client) = clients.get(&p) {
-                models.insert(p, client.supported_models());
+                let supported = Self::provider_models(&p, client.as_ref()).await;
+                models.insert(p, supported);
             }
         } else {
             for (provider, client) in clients.iter() {
-                models.insert(provider.clone(), client.supported_models());
+                models.insert(provider.clone(), Self::provider_models(provider, client.as_ref()).await);
             }
         }
 
         models
     }
 
+    // 已注册适配器的供应商
+    pub async fn registered_providers(&self) -> Vec<Provider> {
+        self.clients.read().await.keys().cloned().collect()
+    }
+
     // 检查供应商是否可用
     pub async fn is_provider_available(&self, provider: &Provider) -> bool {
         let clients = self.clients.read().await;
         clients.contains_key(provider)
     }
 
+    // 根据模型名查找支持该模型的供应商，支持 "provider/model" 显式指定
+    pub async fn resolve_model(&self, model: &str) -> Option<(Provider, String)> {
+        let clients = self.clients.read().await;
+        if let Some((prefix, name)) = model.split_once('/') {
+            if let Some(provider) = Provider::from_name(prefix) {
+                return Some((provider, name.to_string()));
+            }
+            let custom = Provider::from_name_or_custom(prefix);
+            if clients.contains_key(&custom) {
+                return Some((custom, name.to_string()));
+            }
+        }
+
+        // 配置了流量拆分的逻辑模型名，调度时再按百分比选择分组
+        if let Some(split) = self.default_config.traffic_splits.get(model) {
+            return Some((split.arms()[0].provider(), model.to_string()));
+        }
+
+        let mut providers: Vec<&Provider> = clients.keys().collect();
+        providers.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        let mut candidates = Vec::new();
+        for provider in providers {
+            if Self::provider_models(provider, clients[provider].as_ref()).await.iter().any(|m| m == model)
+                && self.ensure_project_access(provider, model).await.is_ok()
+            {
+                candidates.push(provider.clone());
+            }
+        }
+        // 优先选择未被健康检查标记为 unhealthy 的供应商，全部不健康时保留原候选，由调度时的健康检查和 fallback 处理
+        let mut healthy = Vec::new();
+        for provider in &candidates {
+            if get_model_health_from_cache(provider.as_str(), model).await.as_deref() != Some(HEALTH_UNHEALTHY) {
+                healthy.push(provider.clone());
+            }
+        }
+        if !healthy.is_empty() {
+            candidates = healthy;
+        }
+        // 多个供应商提供该模型时按负载均衡策略选择
+        let provider = get_load_balancer().choose(self.default_config.load_balance_policy, model, &candidates).await?;
+        Some((provider, model.to_string()))
+    }
+
+    // 供应商可用的模型：优先使用数据库中启用的模型（经预加载缓存），只有数据库中完全没有该供应商的模型时才使用适配器的内置列表；
+    // 模型全部停用时没有可用模型
+    async fn provider_models(provider: &Provider, client: &dyn LLMClientAdapter) -> Vec<String> {
+        match get_active_model_names_from_cache(provider.as_str()).await {
+            Some(models) => models,
+            None => client.supported_models(),
+        }
+    }
+
+    // 请求所属项目看不到的供应商和模型按不可用处理，default 项目的供应商和模型所有项目共享
+    async fn ensure_project_access(&self, provider: &Provider, model: &str) -> Result<(), LLMError> {
+        let metadata = CallMetadata::current();
+        let project_id = metadata.project_id();
+        let provider_project = self.provider_projects.read().await.get(provider).cloned();
+        if !is_visible_to(provider_project.as_deref(), project_id) {
+            return Err(LLMError::UnsupportedProvider(provider.clone()));
+        }
+        let model_project = get_model_project_from_cache(provider.as_str(), model).await;
+        if !is_visible_to(model_project.as_deref(), project_id) {
+            return Err(LLMError::ModelNotAvailable(model.to_string()));
+        }
+        Ok(())
+    }
+
+    // 获取供应商的并发槽位，最多排队 concurrency_wait_ms；排队期间请求被取消时返回 Cancelled
+    async fn acquire_provider_slot(&self, provider: &Provider, priority: RequestPriority, metadata: &CallMetadata) -> Result<Option<ProviderPermit>, LLMError> {
+        let wait = std::time::Duration::from_millis(self.default_config.concurrency_wait_ms);
+        metadata.cancellation.run_until_cancelled(self.concurrency.acquire(provider, priority, wait)).await
+            .unwrap_or(Err(LLMError::Cancelled))
+    }
+
+    // 健康检查标记为 unhealthy 的模型直接失败，不再调用上游和重试，由 fallback 接管
+    async fn ensure_model_healthy(provider: &Provider, model: &str) -> Result<(), LLMError> {
+        let provider = provider.as_str();
+        if get_model_health_from_cache(provider, model).await.as_deref() == Some(HEALTH_UNHEALTHY) {
+            metrics().incr_counter("llm_gateway_unhealthy_model_skips_total", &[("provider", provider)]);
+            warn!(provider = %provider, model = %model, "Skipping unhealthy model");
+            return Err(LLMError::ModelUnhealthy(format!("{}:{}", provider, model)));
+        }
+        Ok(())
+    }
+
     // 内部dispatch实现
     async fn dispatch_internal(&self, request: &DispatchRequest) -> Result<DispatchResponse, LLMError> {
         let clients = self.clients.read().await;
@@ -576,22 +2653,51 @@ impl LLMDispatcher {
             .ok_or_else(|| LLMError::UnsupportedProvider(request.provider.clone()))?;
 
         // 检查模型是否支持
-        if !client.supported_models().contains(&request.model) {
+        if !Self::provider_models(&request.provider, client.as_ref()).await.contains(&request.model) {
             return Err(LLMError::ModelNotAvailable(request.model.clone()));
         }
+        self.ensure_project_access(&request.provider, &request.model).await?;
+        Self::ensure_model_healthy(&request.provider, &request.model).await?;
+        ensure_within_budget(CallMetadata::current().project_id(), &request.provider).await?;
+        ensure_within_max_cost(request).await?;
 
         // 执行请求，带重试逻辑
         let retry_count = request.retry_count.unwrap_or(self.default_config.default_retry_count);
         let mut last_error = None;
 
+        // 调用记录中附带供应商和请求摘要；重试预算与客户端共享，上游请求总数不超过 retry_count + 1；
+        // timeout_ms 传给 HTTP 客户端作为每次尝试的超时
+        let metadata = CallMetadata::current()
+            .with_attempt(request.provider.as_str(), summarize_request(request))
+            .with_model_id(get_model_id_from_cache(request.provider.as_str(), &request.model).await)
+            .with_retry_budget(RetryBudget::new(retry_count + 1))
+            .with_timeout(request.timeout_ms.map(std::time::Duration::from_millis));
         for attempt in 0..=retry_count {
-            match client.generate(request).await {
-                Ok(response) => return Ok(response),
+            // 每次尝试前获取并发槽位，退避等待期间不占用；并发已满时不再重试，由 fallback 接管
+            let _permit = self.acquire_provider_slot(&request.provider, request.priority.unwrap_or_default(), &metadata).await?;
+            let span = info_span!("adapter.generate", provider = %request.provider.as_str(), model = %request.model, attempt = attempt + 1);
+            let started = std::time::Instant::now();
+            match CALL_METADATA.scope(metadata.clone(), client.generate(request)).instrument(span).await {
+                Ok(response) => {
+                    get_load_balancer().record(&request.provider, &request.model, started.elapsed(), true);
+                    return Ok(response);
+                }
+                // 请求被取消时不再重试
+                Err(_) if metadata.cancellation.is_cancelled() => return Err(LLMError::Cancelled),
                 Err(e) => {
+                    get_load_balancer().record(&request.provider, &request.model, started.elapsed(), false);
                     last_error = Some(e);
                     if attempt < retry_count {
-                        // 简单的退避策略
-                        tokio::time::sleep(tokio::time::Duration::from_millis(1000 * (attempt + 1) as u64)).await;
+                        let retry_wait = metadata.retry_budget.retry_wait();
+                        if metadata.retry_budget.is_exhausted() || retry_wait > MAX_RETRY_WAIT {
+                            break;
+                        }
+                        // 简单的退避策略，上游返回 Retry-After 时至少等待到该时间
+                        let backoff = tokio::time::Duration::from_millis(1000 * (attempt + 1) as u64);
+                        let wait = tokio::time::sleep(backoff.max(retry_wait));
+                        if metadata.cancellation.run_until_cancelled(wait).await.is_none() {
+                            return Err(LLMError::Cancelled);
+                        }
                     }
                 }
             }
@@ -600,25 +2706,74 @@ impl LLMDispatcher {
         Err(last_error.unwrap())
     }
 
-    // 尝试备选供应商
-    async fn try_fallback(&self, mut request: DispatchRequest, original_error: LLMError) -> Result<DispatchResponse, LLMError> {
-        for fallback_provider in &self.default_config.fallback_providers {
-            if *fallback_provider == request.provider {
-                continue; // 跳过原始供应商
+    // 尝试备选供应商，由 fallback 策略决定每个备选供应商上使用的模型
+    // 依次尝试备选目标，同时返回尝试过的备选是否都因上游不可用而失败
+    async fn try_fallback(&self, mut request: DispatchRequest, original_error: LLMError) -> (Result<DispatchResponse, LLMError>, bool) {
+        let targets = self.default_config.fallback_policy
+            .fallback_targets(&request, &self.default_config.fallback_providers);
+        let mut all_down = true;
+        for (provider, model) in targets {
+            if let Some(reason) = self.fallback_skip_reason(&provider, &model).await {
+                metrics().incr_counter("llm_gateway_fallback_skips_total", &[("provider", provider.as_str()), ("reason", reason)]);
+                debug!(provider = %provider.as_str(), model = %model, reason, "Skipping fallback target");
+                continue;
             }
-
-            request.provider = fallback_provider.clone();
-            if let Ok(response) = self.dispatch_internal(&request).await {
-                return Ok(response);
+            debug!(provider = %provider.as_str(), model = %model, "Trying fallback target");
+            request.provider = provider;
+            request.model = model;
+            // 备选模型按自己的参数约束调整
+            let warnings = Self::clamp_to_model(&mut request).await;
+            match self.dispatch_internal(&request).await {
+                Ok(response) => return (Ok(Self::with_warnings(response, warnings)), false),
+                Err(LLMError::Cancelled) => return (Err(LLMError::Cancelled), false),
+                Err(e) => all_down &= Self::is_upstream_failure(&e),
             }
         }
 
         // 所有备选都失败，返回原始错误
-        Err(original_error)
+        (Err(original_error), all_down)
+    }
+
+    // 是否有可以处理该请求的备选目标（不含请求的供应商本身）
+    async fn has_fallback_candidates(&self, request: &DispatchRequest) -> bool {
+        let targets = self.default_config.fallback_policy
+            .fallback_targets(request, &self.default_config.fallback_providers);
+        for (provider, model) in targets {
+            if provider != request.provider && self.fallback_skip_reason(&provider, &model).await.is_none() {
+                return true;
+            }
+        }
+        false
+    }
+
+    // 备选供应商不可能处理请求的原因：未注册、模型不在目录中，或 Key 池中没有可用的 Key
+    async fn fallback_skip_reason(&self, provider: &Provider, model: &str) -> Option<&'static str> {
+        let clients = self.clients.read().await;
+        let Some(client) = clients.get(provider) else {
+            return Some("not_registered");
+        };
+        if !Self::provider_models(provider, client.as_ref()).await.iter().any(|m| m == model) {
+            return Some("model_unavailable");
+        }
+        if client.uses_key_pool() && !has_available_project_key(provider.as_str(), CallMetadata::current().project_id()).await {
+            return Some("no_active_keys");
+        }
+        None
+    }
+
+    // 在调度 span 上记录路由后的模型和供应商
+    fn record_route(request: &DispatchRequest) {
+        let span = Span::current();
+        span.record("model", request.model.as_str());
+        span.record("provider", request.provider.as_str());
     }
 
-    // 应用默认配置
+    // 应用默认配置，并确定本次请求的网关请求 ID（沿用 HTTP 请求的 X-Request-Id，都没有时生成）
     fn apply_defaults(&self, request: &mut DispatchRequest) {
+        let request_id = request.request_id.get_or_insert_with(|| {
+            CallMetadata::current().request_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+        });
+        Span::current().record("request_id", request_id.as_str());
         if request.temperature.is_none() {
             request.temperature = Some(self.default_config.default_temperature);
         }
@@ -630,8 +2785,8 @@ impl LLMDispatcher {
         }
     }
 
-    // 验证请求参数
-    fn validate_request(&self, request: &DispatchRequest) -> Result<(), LLMError> {
+    // 验证请求参数，按模型约束调整超出范围的参数，返回调整说明
+    async fn validate_request(&self, request: &mut DispatchRequest) -> Result<Vec<String>, LLMError> {
         if request.messages.is_empty() {
             return Err(LLMError::InvalidParameters("Messages cannot be empty".to_string()));
         }
@@ -640,58 +2795,99 @@ impl LLMDispatcher {
             return Err(LLMError::InvalidParameters("Model cannot be empty".to_string()));
         }
 
+        let warnings = Self::clamp_to_model(request).await;
+
         if let Some(temp) = request.temperature {
             if temp < 0.0 || temp > 2.0 {
                 return Err(LLMError::InvalidParameters("Temperature must be between 0.0 and 2.0".to_string()));
             }
         }
 
-        Ok(())
+        if request.max_cost.is_some_and(|max_cost| max_cost < 0.0) {
+            return Err(LLMError::InvalidParameters("max_cost must not be negative".to_string()));
+        }
+
+        // 提示词加上请求的回复长度不能超过模型的上下文窗口
+        if let Some(window) = self.context_window(request) {
+            let prompt_tokens = count_request_tokens(request);
+            let completion_tokens = request.max_tokens.unwrap_or(0) as usize;
+            if prompt_tokens + completion_tokens > window {
+                return Err(LLMError::ContextLengthExceeded(format!(
+                    "This model's maximum context length is {} tokens, however you requested about {} tokens ({} in the messages, {} in the completion)",
+                    window, prompt_tokens + completion_tokens, prompt_tokens, completion_tokens,
+                )));
+            }
+        }
+
+        Ok(warnings)
     }
-}
 
-// 便捷方法
-impl DispatchRequest {
-    pub fn new(provider: Provider, model: String, messages: Vec<Message>) -> Self {
-        Self {
-            provider,
-            model,
-            messages,
-            stream: None,
-            temperature: None,
-            max_tokens: None,
-            top_p: None,
-            frequency_penalty: None,
-            presence_penalty: None,
-            stop: None,
-            timeout_ms: None,
-            retry_count: None,
-            context_window: None,
-        }
-    }
-
-    pub fn with_stream(mut self, stream: bool) -> Self {
-        self.stream = Some(stream);
-        self
+    // 按模型的参数约束调整请求，返回调整说明
+    async fn clamp_to_model(request: &mut DispatchRequest) -> Vec<String> {
+        let Some(constraints) = resolve_param_constraints(request.provider.as_str(), &request.model).await else {
+            return Vec::new();
+        };
+        let warnings = constraints.clamp(request);
+        if !warnings.is_empty() {
+            warn!(model = %request.model, warnings = ?warnings, "Clamped request parameters to model constraints");
+        }
+        warnings
     }
 
-    pub fn with_temperature(mut self, temperature: f32) -> Self {
-        self.temperature = Some(temperature);
-        self
+    // 把参数调整说明附加到响应
+    fn with_warnings(mut response: DispatchResponse, warnings: Vec<String>) -> DispatchResponse {
+        if !warnings.is_empty() {
+            response.warnings.get_or_insert_with(Vec::new).extend(warnings);
+        }
+        response
     }
 
-    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
-        self.max_tokens = Some(max_tokens);
-        self
+    // 模型的上下文窗口：请求指定的窗口优先，其次是配置和内置的常见模型窗口
+    fn context_window(&self, request: &DispatchRequest) -> Option<usize> {
+        request.context_window.map(|window| window as usize)
+            .or_else(|| self.default_config.context_windows.get(&request.model).copied())
+            .or_else(|| context_window(&request.model))
     }
 
-    pub fn with_top_p(mut self, top_p: f32) -> Self {
-        self.top_p = Some(top_p);
-        self
+    // 供应商没有返回用量时按请求和回复估算
+    fn estimate_usage(request: &DispatchRequest, mut response: DispatchResponse) -> DispatchResponse {
+        if response.usage.is_none() {
+            let prompt_tokens = count_request_tokens(request) as u32;
+            let completion_tokens = count_text_tokens(&response.content, &response.model) as u32;
+            response.usage = Some(TokenUsage {
+                prompt_tokens,
+                completion_tokens,
+                total_tokens: prompt_tokens + completion_tokens,
+            });
+        }
+        response
     }
+}
 
-    pub fn with_stop(mut self, stop: Vec<String>) -> Self {
-        self.stop = Some(stop);
-        self
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_stream_sink_backlog_is_bounded() {
+        let mut receiver = spawn_stream(|sink| async move {
+            for i in 0..STREAM_BACKLOG_LIMIT * 2 {
+                if sink.send(Ok(StreamChunk::delta(i.to_string()))).is_err() {
+                    return;
+                }
+            }
+            panic!("sink accepted more chunks than the backlog limit");
+        });
+
+        let mut chunks = 0;
+        let mut error = None;
+        while let Some(item) = receiver.recv().await {
+            match item {
+                Ok(_) => chunks += 1,
+                Err(e) => error = Some(e),
+            }
+        }
+        assert_eq!(chunks, STREAM_BACKLOG_LIMIT);
+        assert!(matches!(error, Some(LLMError::Overloaded(_))));
     }
-}
\ No newline at end of file
+}
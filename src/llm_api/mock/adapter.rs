@@ -0,0 +1,216 @@
+//! # 模拟供应商
+//!
+//! 不访问任何上游，按模型名模拟常见的上游行为，供客户端在测试环境验证错误处理：
+//! 内容过滤拦截、工具调用、流式响应中途断开、返回无法解析的 JSON。
+//! 在 providers 表中添加名为 `mock` 的供应商即可启用
+
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+
+use crate::llm_api::dispatcher::{
+    DispatchRequest, DispatchResponse, LLMClientAdapter, LLMError, Provider, StreamChunk, StreamReceiver, TokenUsage,
+};
+use crate::llm_api::utils::msg_structure::{Function, Message, ToolCall};
+
+/// 流式响应中途断开前发送的增量块数量（可通过 providers.config 的 `partial_chunks` 修改）
+pub const DEFAULT_PARTIAL_CHUNKS: usize = 2;
+
+/// 模拟的上游行为，每种行为对应一个模型名
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MockScenario {
+    /// 原样返回最后一条用户消息
+    Echo,
+    /// 上游内容过滤拦截（finish_reason 为 content_filter）
+    ContentFilter,
+    /// 上游要求调用工具（finish_reason 为 tool_calls）
+    ToolCall,
+    /// 流式响应发送部分内容后连接断开
+    PartialStream,
+    /// 上游返回无法解析的 JSON
+    MalformedJson,
+}
+
+impl MockScenario {
+    pub const ALL: [MockScenario; 5] = [
+        MockScenario::Echo,
+        MockScenario::ContentFilter,
+        MockScenario::ToolCall,
+        MockScenario::PartialStream,
+        MockScenario::MalformedJson,
+    ];
+
+    /// 行为对应的模型名
+    pub fn model_name(&self) -> &'static str {
+        match self {
+            MockScenario::Echo => "mock-echo",
+            MockScenario::ContentFilter => "mock-content-filter",
+            MockScenario::ToolCall => "mock-tool-call",
+            MockScenario::PartialStream => "mock-partial-stream",
+            MockScenario::MalformedJson => "mock-malformed-json",
+        }
+    }
+
+    pub fn from_model(model: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|scenario| scenario.model_name() == model)
+    }
+}
+
+/// 模拟供应商适配器
+pub struct MockAdapter {
+    provider: Provider,
+    partial_chunks: usize,
+}
+
+impl MockAdapter {
+    pub fn new(provider: Provider) -> Self {
+        Self {
+            provider,
+            partial_chunks: DEFAULT_PARTIAL_CHUNKS,
+        }
+    }
+
+    /// 设置流式响应中途断开前发送的增量块数量
+    pub fn with_partial_chunks(mut self, partial_chunks: usize) -> Self {
+        self.partial_chunks = partial_chunks;
+        self
+    }
+
+    fn scenario(request: &DispatchRequest) -> Result<MockScenario, LLMError> {
+        MockScenario::from_model(&request.model)
+            .ok_or_else(|| LLMError::ModelNotAvailable(request.model.clone()))
+    }
+
+    fn response(&self, request: &DispatchRequest, content: String, finish_reason: &str) -> DispatchResponse {
+        DispatchResponse {
+            usage: Some(usage(request, &content)),
+            message: Some(Message::assistant(content.clone())),
+            content,
+            provider: self.provider.clone(),
+            model: request.model.clone(),
+            finish_reason: Some(finish_reason.to_string()),
+            request_id: Some(format!("mock-{}", uuid::Uuid::new_v4().simple())),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            total_duration: Some(0),
+            tool_calls: None,
+            raw: None,
+            warnings: None,
+        }
+    }
+}
+
+// 最后一条用户消息
+fn last_user_message(request: &DispatchRequest) -> String {
+    request.messages.iter()
+        .rev()
+        .find(|message| message.role == "user")
+        .map(|message| message.content.clone())
+        .unwrap_or_default()
+}
+
+// 调用请求中的第一个工具，参数为空；请求没有带工具时不返回工具调用
+fn mock_tool_calls(request: &DispatchRequest) -> Option<Vec<ToolCall>> {
+    let tool = request.tools.as_ref()?.first()?;
+    Some(vec![ToolCall {
+        id: Some(format!("call_mock_{}", uuid::Uuid::new_v4().simple())),
+        tool_type: Some("function".to_string()),
+        function: Function {
+            name: tool.function.name.clone(),
+            arguments: Default::default(),
+        },
+    }])
+}
+
+// 按字符数粗略估算 token 用量
+fn usage(request: &DispatchRequest, completion: &str) -> TokenUsage {
+    let prompt_tokens = request.messages.iter().map(|m| m.content.chars().count() as u32).sum::<u32>();
+    let completion_tokens = completion.chars().count() as u32;
+    TokenUsage {
+        prompt_tokens,
+        completion_tokens,
+        total_tokens: prompt_tokens + completion_tokens,
+    }
+}
+
+// 解析截断的响应体，得到与真实上游一致的解析错误
+fn malformed_json_error() -> LLMError {
+    let error = serde_json::from_str::<serde_json::Value>(r#"{"choices": [{"message": {"content": "Hel"#)
+        .expect_err("truncated JSON must fail to parse");
+    LLMError::ApiError(format!("Failed to parse response body: {}", error))
+}
+
+// 一次性写入所有块后关闭通道，接收端读完即结束
+fn stream_of(items: Vec<Result<StreamChunk, LLMError>>) -> StreamReceiver {
+    let (tx, rx) = mpsc::channel(items.len().max(1));
+    for item in items {
+        let _ = tx.try_send(item);
+    }
+    rx
+}
+
+// 按空白切分为增量块，保留分隔符便于客户端拼接
+fn split_deltas(content: &str) -> Vec<String> {
+    content.split_inclusive(' ').map(str::to_string).collect()
+}
+
+#[async_trait]
+impl LLMClientAdapter for MockAdapter {
+    async fn generate(&self, request: &DispatchRequest) -> Result<DispatchResponse, LLMError> {
+        match Self::scenario(request)? {
+            MockScenario::Echo => Ok(self.response(request, last_user_message(request), "stop")),
+            MockScenario::ContentFilter => Ok(self.response(request, String::new(), "content_filter")),
+            MockScenario::ToolCall => {
+                let response = self.response(request, String::new(), "tool_calls");
+                let tool_calls = mock_tool_calls(request);
+                Ok(DispatchResponse {
+                    message: response.message.clone().map(|mut message| {
+                        message.tool_calls = tool_calls.clone();
+                        message
+                    }),
+                    tool_calls,
+                    ..response
+                })
+            }
+            MockScenario::PartialStream => Err(LLMError::Network("connection closed before message completed".to_string())),
+            MockScenario::MalformedJson => Err(malformed_json_error()),
+        }
+    }
+
+    async fn generate_stream(&self, request: &DispatchRequest) -> Result<StreamReceiver, LLMError> {
+        let scenario = Self::scenario(request)?;
+        let content = last_user_message(request);
+        let deltas = split_deltas(&content);
+
+        let items = match scenario {
+            MockScenario::Echo => deltas.into_iter()
+                .map(|delta| Ok(StreamChunk::delta(delta)))
+                .chain([Ok(StreamChunk::finished(Some("stop".to_string()), Some(usage(request, &content))))])
+                .collect(),
+            MockScenario::ContentFilter => deltas.into_iter()
+                .take(1)
+                .map(|delta| Ok(StreamChunk::delta(delta)))
+                .chain([Ok(StreamChunk::finished(Some("content_filter".to_string()), None))])
+                .collect(),
+            MockScenario::ToolCall => vec![Ok(StreamChunk::finished(Some("tool_calls".to_string()), Some(usage(request, "")))
+                .with_tool_calls(mock_tool_calls(request)))],
+            MockScenario::PartialStream => deltas.into_iter()
+                .take(self.partial_chunks)
+                .map(|delta| Ok(StreamChunk::delta(delta)))
+                .chain([Err(LLMError::Network("stream closed before completion".to_string()))])
+                .collect(),
+            MockScenario::MalformedJson => deltas.into_iter()
+                .take(1)
+                .map(|delta| Ok(StreamChunk::delta(delta)))
+                .chain([Err(malformed_json_error())])
+                .collect(),
+        };
+        Ok(stream_of(items))
+    }
+
+    fn supported_models(&self) -> Vec<String> {
+        MockScenario::ALL.iter().map(|scenario| scenario.model_name().to_string()).collect()
+    }
+
+    fn provider_name(&self) -> Provider {
+        self.provider.clone()
+    }
+}
@@ -0,0 +1 @@
+pub mod adapter;
\ No newline at end of file
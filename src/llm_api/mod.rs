@@ -1,6 +1,10 @@
 pub mod utils;
 pub mod openai;
+pub mod azure;
+pub mod openai_compatible;
 pub mod ali;
 pub mod zhipu;
 pub mod ollama;
-pub mod dispatcher;
\ No newline at end of file
+pub mod mock;
+pub mod dispatcher;
+pub mod provider_registry;
\ No newline at end of file
@@ -1,19 +1,25 @@
 //! # Ollama API 客户端
 //!
-//! 实现 Ollama API 的客户端，支持 chat 和 chat_stream 功能
+//! 实现 Ollama API 的客户端，支持 chat 和 chat_stream 功能、`/api/generate` 文本补全，
+//! 以及本地模型的管理（列表、拉取、删除、查看详情）
 //! 使用 utils 模块提供的通用基础设施
 
 use async_trait::async_trait;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::fmt;
+use std::time::Duration;
 use anyhow::Result;
-use reqwest::Client;
+use futures_util::stream::{BoxStream, Stream};
+use futures_util::StreamExt;
+use reqwest::{Client, StatusCode};
+use tracing::warn;
 
 use crate::llm_api::utils::{
-    client::{BaseClient, ClientConfig, ClientError, LLMClientTrait},
-    chat_traits::{ChatRequestTrait, ChatResponseTrait},
+    client::{parse_line_stream, BaseClient, ClientConfig, ClientError, LLMClientTrait, LineBuffer},
+    stream_accounting::OllamaStreamAccounting,
+    chat_traits::{ChatClientTrait, ChatRequestTrait, ChatResponseTrait, CompletionRequestTrait, CompletionResponseTrait},
     msg_structure::Message,
     tool_structure::Tool,
 };
@@ -24,6 +30,7 @@ pub struct OllamaChatRequest {
     /// 要使用的模型名称
     pub model: String,
     /// 对话消息列表
+    #[serde(serialize_with = "crate::llm_api::utils::client::serialize_ollama_messages")]
     pub messages: Vec<Message>,
     /// 是否使用流式输出
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -31,9 +38,9 @@ pub struct OllamaChatRequest {
     /// 模型参数选项
     #[serde(skip_serializing_if = "Option::is_none")]
     pub options: Option<HashMap<String, Value>>,
-    /// 输出格式约束（如 "json"）
+    /// 输出格式约束："json" 或 JSON Schema 对象
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub format: Option<String>,
+    pub format: Option<Value>,
     /// 可用工具列表
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<Vec<Tool>>,
@@ -106,11 +113,14 @@ impl ChatRequestTrait for OllamaChatRequest {
     }
 
     fn get_format(&self) -> Option<String> {
-        self.format.clone()
+        self.format.as_ref().map(|format| match format {
+            Value::String(format) => format.clone(),
+            schema => schema.to_string(),
+        })
     }
 
     fn set_format(&mut self, format: String) {
-        self.format = Some(format);
+        self.format = Some(Value::String(format));
     }
 }
 
@@ -125,6 +135,9 @@ pub struct OllamaChatResponse {
     pub message: Option<Message>,
     /// 是否完成（流式输出中使用）
     pub done: bool,
+    /// 结束原因，如 "stop"、"length"（仅最后一块返回）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub done_reason: Option<String>,
     /// 总处理时间（纳秒）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub total_duration: Option<u64>,
@@ -177,6 +190,176 @@ impl ChatResponseTrait for OllamaChatResponse {
 
 }
 
+/// Ollama Generate（文本补全）请求结构体
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OllamaGenerateRequest {
+    /// 要使用的模型名称
+    pub model: String,
+    /// 提示词
+    pub prompt: String,
+    /// 插入到生成内容之后的文本（代码补全）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suffix: Option<String>,
+    /// 覆盖 Modelfile 中的系统提示词
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    /// 为 true 时不套用模型的提示词模板
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw: Option<bool>,
+    /// 是否使用流式输出
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream: Option<bool>,
+    /// 模型参数选项
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<HashMap<String, Value>>,
+    /// 输出格式约束："json" 或 JSON Schema 对象
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<Value>,
+}
+
+impl OllamaGenerateRequest {
+    /// 创建新的补全请求
+    pub fn new(model: String, prompt: String) -> Self {
+        Self {
+            model,
+            prompt,
+            suffix: None,
+            system: None,
+            raw: None,
+            stream: None,
+            options: None,
+            format: None,
+        }
+    }
+}
+
+impl CompletionRequestTrait for OllamaGenerateRequest {
+    fn get_model(&self) -> &str {
+        &self.model
+    }
+
+    fn get_prompt(&self) -> &str {
+        &self.prompt
+    }
+
+    fn is_stream(&self) -> Option<bool> {
+        self.stream
+    }
+
+    fn set_stream(&mut self, stream: bool) {
+        self.stream = Some(stream);
+    }
+}
+
+/// Ollama Generate 响应结构体（流式输出中的单个块结构相同）
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OllamaGenerateResponse {
+    /// 使用的模型名称
+    pub model: String,
+    /// 响应创建时间
+    pub created_at: String,
+    /// 生成的文本（流式输出中为增量）
+    #[serde(default)]
+    pub response: String,
+    /// 是否完成（流式输出中使用）
+    pub done: bool,
+    /// 结束原因，如 "stop"、"length"（仅最后一块返回）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub done_reason: Option<String>,
+    /// 总处理时间（纳秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_duration: Option<u64>,
+    /// 加载时间（纳秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub load_duration: Option<u64>,
+    /// 提示词处理时间（纳秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt_eval_duration: Option<u64>,
+    /// 生成时间（纳秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eval_duration: Option<u64>,
+    /// 提示词 token 数量
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt_eval_count: Option<u32>,
+    /// 生成的 token 数量
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eval_count: Option<u32>,
+}
+
+impl CompletionResponseTrait for OllamaGenerateResponse {
+    fn get_model(&self) -> &str {
+        &self.model
+    }
+
+    fn get_text(&self) -> Option<String> {
+        Some(self.response.clone())
+    }
+
+    fn is_done(&self) -> bool {
+        self.done
+    }
+
+    // 旧版本不返回 done_reason 时视为 stop
+    fn get_finish_reason(&self) -> Option<String> {
+        self.done.then(|| self.done_reason.clone().unwrap_or_else(|| "stop".to_string()))
+    }
+
+    fn get_eval_count(&self) -> Option<u32> {
+        self.eval_count
+    }
+
+    fn get_prompt_eval_count(&self) -> Option<u32> {
+        self.prompt_eval_count
+    }
+}
+
+/// 拉取模型时的单条进度
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OllamaPullProgress {
+    /// 当前阶段，例如 "pulling manifest"、"downloading"、"success"
+    pub status: String,
+    /// 正在下载的层
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    /// 该层的总字节数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    /// 该层已下载的字节数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed: Option<u64>,
+}
+
+impl OllamaPullProgress {
+    /// 拉取是否已完成
+    pub fn is_success(&self) -> bool {
+        self.status == "success"
+    }
+}
+
+/// 模型的基本信息
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct OllamaModelDetails {
+    pub format: Option<String>,
+    pub family: Option<String>,
+    pub families: Option<Vec<String>>,
+    pub parameter_size: Option<String>,
+    pub quantization_level: Option<String>,
+}
+
+/// `/api/show` 返回的模型详情
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct OllamaModelInfo {
+    pub modelfile: Option<String>,
+    pub parameters: Option<String>,
+    pub template: Option<String>,
+    pub details: Option<OllamaModelDetails>,
+    /// 模型架构相关的元数据，原样返回
+    pub model_info: Option<Value>,
+}
+
+/// 拉取模型的最长时间，大模型下载可能持续很久，不使用客户端的请求超时
+const MODEL_PULL_TIMEOUT: Duration = Duration::from_secs(6 * 3600);
+
 /// Ollama 客户端错误类型
 #[derive(Debug)]
 pub enum OllamaError {
@@ -184,6 +367,7 @@ pub enum OllamaError {
     Json(serde_json::Error),
     InvalidRequest(String),
     Api(String),
+    ModelNotFound(String),
 }
 
 impl fmt::Display for OllamaError {
@@ -193,6 +377,7 @@ impl fmt::Display for OllamaError {
             OllamaError::Json(e) => write!(f, "JSON serialization error: {}", e),
             OllamaError::InvalidRequest(msg) => write!(f, "Invalid request: {}", msg),
             OllamaError::Api(msg) => write!(f, "API error: {}", msg),
+            OllamaError::ModelNotFound(model) => write!(f, "Model not found: {}", model),
         }
     }
 }
@@ -219,6 +404,17 @@ impl From<serde_json::Error> for OllamaError {
     }
 }
 
+/// 解析一行 NDJSON 流式数据，空行和无法解析的行返回 None
+fn parse_stream_line<T: DeserializeOwned>(line: &str) -> Option<T> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+    serde_json::from_str::<T>(line)
+        .map_err(|e| warn!(error = %e, line = %line, "Failed to parse streaming response"))
+        .ok()
+}
+
 /// Ollama 客户端
 pub struct OllamaClient {
     /// 基础 HTTP 客户端
@@ -235,7 +431,7 @@ impl OllamaClient {
 
     /// 使用自定义配置创建客户端
     pub fn new_with_config(base_url: String, config: ClientConfig) -> Result<Self> {
-        let base_client = BaseClient::new(config)?;
+        let base_client = BaseClient::new(config.with_default_shared_pool("ollama"))?;
         
         Ok(Self {
             base_client,
@@ -292,7 +488,7 @@ impl OllamaClient {
         let url = format!("{}/api/chat", self.base_url);
 
         // 发送流式请求
-        self.base_client.post_stream(&url, &request, |line: String| {
+        self.base_client.post_stream(&url, &request, &OllamaStreamAccounting, |line: String| {
             // 过滤空行
             if line.trim().is_empty() {
                 return true;
@@ -305,7 +501,7 @@ impl OllamaClient {
                     callback(response)
                 },
                 Err(e) => {
-                    eprintln!("Failed to parse streaming response: {}: {}", e, line);
+                    warn!(error = %e, line = %line, "Failed to parse streaming chat response");
                     true // 继续处理其他行
                 }
             }
@@ -314,12 +510,64 @@ impl OllamaClient {
         Ok(())
     }
 
+    /// 发送流式聊天请求，以 Stream 返回响应块；丢弃返回的流时停止读取
+    pub fn chat_stream_iter(
+        &self,
+        mut request: OllamaChatRequest,
+    ) -> Result<BoxStream<'static, Result<OllamaChatResponse, OllamaError>>, OllamaError> {
+        request.set_stream(true);
+        request.validate().map_err(OllamaError::InvalidRequest)?;
+
+        let url = format!("{}/api/chat", self.base_url);
+        let lines = self.base_client.post_stream_iter(&url, request, OllamaStreamAccounting);
+        Ok(parse_line_stream(lines, parse_stream_line))
+    }
+
+    /// 发送文本补全请求（非流式）
+    pub async fn generate(&self, mut request: OllamaGenerateRequest) -> Result<OllamaGenerateResponse, OllamaError> {
+        request.set_stream(false);
+        request.validate().map_err(OllamaError::InvalidRequest)?;
+
+        let url = format!("{}/api/generate", self.base_url);
+        let response = self.base_client.post(&url, &request).await?;
+
+        let response_text = response.text().await.map_err(|e| {
+            OllamaError::Api(format!("Failed to read response: {}", e))
+        })?;
+        Ok(serde_json::from_str(&response_text)?)
+    }
+
+    /// 发送流式文本补全请求
+    pub async fn generate_stream<F>(&self, mut request: OllamaGenerateRequest, mut callback: F) -> Result<(), OllamaError>
+    where
+        F: FnMut(OllamaGenerateResponse) -> bool + Send,
+    {
+        request.set_stream(true);
+        request.validate().map_err(OllamaError::InvalidRequest)?;
+
+        let url = format!("{}/api/generate", self.base_url);
+        self.base_client.post_stream(&url, &request, &OllamaStreamAccounting, |line: String| {
+            if line.trim().is_empty() {
+                return true;
+            }
+            match serde_json::from_str::<OllamaGenerateResponse>(&line) {
+                Ok(response) => callback(response),
+                Err(e) => {
+                    warn!(error = %e, line = %line, "Failed to parse streaming generate response");
+                    true
+                }
+            }
+        }).await?;
+
+        Ok(())
+    }
+
     /// 获取可用模型列表
     pub async fn list_models(&self) -> Result<Vec<String>, OllamaError> {
         let url = format!("{}/api/tags", self.base_url);
         
-        let response = self.base_client.http_client()
-            .get(&url)
+        let response = self.base_client
+            .request(reqwest::Method::GET, &url)
             .send()
             .await
             .map_err(|e| OllamaError::Api(format!("Failed to get models: {}", e)))?;
@@ -348,6 +596,117 @@ impl OllamaClient {
         let models = self.list_models().await?;
         Ok(models.iter().any(|name| name == model_name))
     }
+
+    /// 拉取模型，每收到一条进度调用一次回调，回调返回 false 时停止读取（Ollama 会继续在后台下载）
+    pub async fn pull_model<F>(&self, model_name: &str, mut callback: F) -> Result<(), OllamaError>
+    where
+        F: FnMut(OllamaPullProgress) -> bool + Send,
+    {
+        if model_name.trim().is_empty() {
+            return Err(OllamaError::InvalidRequest("Model name cannot be empty".to_string()));
+        }
+        let url = format!("{}/api/pull", self.base_url);
+
+        let response = self.base_client
+            .request(reqwest::Method::POST, &url)
+            .timeout(MODEL_PULL_TIMEOUT)
+            .json(&serde_json::json!({ "model": model_name, "stream": true }))
+            .send()
+            .await
+            .map_err(|e| OllamaError::Api(format!("Failed to pull model: {}", e)))?;
+        let response = Self::check_status(response, model_name).await?;
+
+        // 按行解析进度，出错时 Ollama 返回 {"error": "..."}
+        let mut stream = response.bytes_stream();
+        let mut buffer = LineBuffer::new(self.base_client.config().max_stream_line_bytes);
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| OllamaError::Api(format!("Failed to read pull progress: {}", e)))?;
+            for line in buffer.push(&chunk)? {
+                let value: Value = serde_json::from_str(&line)?;
+                if let Some(error) = value.get("error").and_then(|v| v.as_str()) {
+                    return Err(OllamaError::Api(error.to_string()));
+                }
+                if !callback(serde_json::from_value(value)?) {
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 删除本地模型
+    pub async fn delete_model(&self, model_name: &str) -> Result<(), OllamaError> {
+        let url = format!("{}/api/delete", self.base_url);
+
+        let response = self.base_client
+            .request(reqwest::Method::DELETE, &url)
+            .json(&serde_json::json!({ "model": model_name }))
+            .send()
+            .await
+            .map_err(|e| OllamaError::Api(format!("Failed to delete model: {}", e)))?;
+        Self::check_status(response, model_name).await?;
+        Ok(())
+    }
+
+    /// 查看模型详情（Modelfile、参数、模板和架构信息）
+    pub async fn show_model(&self, model_name: &str) -> Result<OllamaModelInfo, OllamaError> {
+        let url = format!("{}/api/show", self.base_url);
+
+        let response = self.base_client
+            .request(reqwest::Method::POST, &url)
+            .json(&serde_json::json!({ "model": model_name }))
+            .send()
+            .await
+            .map_err(|e| OllamaError::Api(format!("Failed to show model: {}", e)))?;
+        let response = Self::check_status(response, model_name).await?;
+
+        let response_text = response.text().await.map_err(|e| {
+            OllamaError::Api(format!("Failed to read model info: {}", e))
+        })?;
+        Ok(serde_json::from_str(&response_text)?)
+    }
+
+    /// 模型管理接口的错误状态码：404 表示模型不存在，其余返回响应中的错误信息
+    async fn check_status(response: reqwest::Response, model_name: &str) -> Result<reqwest::Response, OllamaError> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        if status == StatusCode::NOT_FOUND {
+            return Err(OllamaError::ModelNotFound(model_name.to_string()));
+        }
+        let body = response.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<Value>(&body).ok()
+            .and_then(|value| value.get("error").and_then(|v| v.as_str()).map(str::to_string))
+            .unwrap_or(body);
+        Err(OllamaError::Api(format!("{}: {}", status, message)))
+    }
+}
+
+#[async_trait]
+impl ChatClientTrait for OllamaClient {
+    type Request = OllamaChatRequest;
+    type Response = OllamaChatResponse;
+    type Error = OllamaError;
+
+    async fn chat(&self, request: Self::Request) -> Result<Self::Response, Self::Error> {
+        OllamaClient::chat(self, request).await
+    }
+
+    async fn chat_stream(
+        &self,
+        request: Self::Request,
+    ) -> Result<Box<dyn Stream<Item = Result<Self::Response, Self::Error>> + Unpin + Send>, Self::Error> {
+        Ok(Box::new(self.chat_stream_iter(request)?))
+    }
+
+    fn get_client_type(&self) -> &'static str {
+        "ollama"
+    }
+
+    async fn health_check(&self) -> Result<bool, Self::Error> {
+        Ok(self.list_models().await.is_ok())
+    }
 }
 
 #[async_trait]
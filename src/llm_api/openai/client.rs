@@ -0,0 +1,870 @@
+//! # OpenAI API 客户端
+//!
+//! 实现 OpenAI Chat Completions API 的客户端，支持 GPT 系列模型
+//! 也可用于其它兼容 OpenAI 格式的服务（通过自定义基础 URL）
+//! 另支持旧版 Completions API（`/completions`），用于纯文本补全
+
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use anyhow::Result;
+use futures_util::stream::BoxStream;
+use reqwest::Client;
+use tracing::warn;
+
+use crate::llm_api::utils::{
+    client::{parse_line_stream, BaseClient, ClientConfig, ClientError, LLMClientTrait},
+    stream_accounting::OpenAIStreamAccounting,
+    chat_traits::{ChatRequestTrait, ChatResponseTrait, CompletionRequestTrait, CompletionResponseTrait},
+    msg_structure::{Message, ToolCallDelta},
+    tool_structure::Tool,
+};
+
+/// OpenAI Chat 请求结构体
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OpenAIChatRequest {
+    /// 要使用的模型名称，如 "gpt-4o", "gpt-4o-mini" 等
+    pub model: String,
+    /// 对话消息列表
+    #[serde(serialize_with = "crate::llm_api::utils::client::serialize_messages")]
+    pub messages: Vec<Message>,
+    /// 是否使用流式输出
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream: Option<bool>,
+    /// 生成时的随机种子
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u32>,
+    /// 输出的最大 token 数量
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// 温度参数，控制生成的随机性
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Top-p 参数，核采样
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    /// 频率惩罚
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+    /// 存在惩罚
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+    /// 停止生成的标记
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Vec<String>>,
+    /// 终端用户标识
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// 响应格式，例如 {"type": "json_object"}
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<Value>,
+    /// 流式输出选项
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<OpenAIStreamOptions>,
+    /// 可供模型调用的工具
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,
+    /// 工具选择策略："auto"、"none"、"required" 或 {"type": "function", "function": {"name": ...}}
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<Value>,
+}
+
+/// OpenAI 流式输出选项
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OpenAIStreamOptions {
+    /// 是否在最后一个块中返回 token 使用统计
+    pub include_usage: bool,
+}
+
+impl OpenAIChatRequest {
+    /// 创建新的聊天请求
+    pub fn new(model: String, messages: Vec<Message>) -> Self {
+        Self {
+            model,
+            messages,
+            stream: None,
+            seed: None,
+            max_tokens: None,
+            temperature: None,
+            top_p: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            stop: None,
+            user: None,
+            response_format: None,
+            stream_options: None,
+            tools: None,
+            tool_choice: None,
+        }
+    }
+
+    /// 设置最大 token 数量
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// 设置温度参数
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// 设置 top_p 参数
+    pub fn with_top_p(mut self, top_p: f32) -> Self {
+        self.top_p = Some(top_p);
+        self
+    }
+
+    /// 设置停止标记
+    pub fn with_stop(mut self, stop: Vec<String>) -> Self {
+        self.stop = Some(stop);
+        self
+    }
+
+    /// 设置终端用户标识
+    pub fn with_user(mut self, user: String) -> Self {
+        self.user = Some(user);
+        self
+    }
+
+    /// 设置可供模型调用的工具
+    pub fn with_tools(mut self, tools: Vec<Tool>) -> Self {
+        self.tools = Some(tools);
+        self
+    }
+
+    /// 设置流式输出是否返回 token 使用统计
+    pub fn with_stream_usage(mut self, include_usage: bool) -> Self {
+        self.stream_options = Some(OpenAIStreamOptions { include_usage });
+        self
+    }
+}
+
+impl ChatRequestTrait for OpenAIChatRequest {
+    fn get_model(&self) -> &str {
+        &self.model
+    }
+
+    fn get_messages(&self) -> Vec<Message> {
+        self.messages.clone()
+    }
+
+    fn set_messages(&mut self, messages: Vec<Message>) {
+        self.messages = messages;
+    }
+
+    fn add_message(&mut self, message: Message) {
+        self.messages.push(message);
+    }
+
+    fn message_count(&self) -> usize {
+        self.messages.len()
+    }
+
+    fn is_stream(&self) -> Option<bool> {
+        self.stream
+    }
+
+    fn set_stream(&mut self, stream: bool) {
+        self.stream = Some(stream);
+        // stream_options 只能在流式请求中出现
+        if !stream {
+            self.stream_options = None;
+        }
+    }
+
+    fn get_options(&self) -> Option<HashMap<String, Value>> {
+        let mut options = HashMap::new();
+
+        if let Some(seed) = self.seed {
+            options.insert("seed".to_string(), Value::from(seed));
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            options.insert("max_tokens".to_string(), Value::from(max_tokens));
+        }
+        if let Some(temperature) = self.temperature {
+            options.insert("temperature".to_string(), Value::from(temperature));
+        }
+        if let Some(top_p) = self.top_p {
+            options.insert("top_p".to_string(), Value::from(top_p));
+        }
+        if let Some(frequency_penalty) = self.frequency_penalty {
+            options.insert("frequency_penalty".to_string(), Value::from(frequency_penalty));
+        }
+        if let Some(presence_penalty) = self.presence_penalty {
+            options.insert("presence_penalty".to_string(), Value::from(presence_penalty));
+        }
+        if let Some(ref stop) = self.stop {
+            options.insert("stop".to_string(), Value::from(stop.clone()));
+        }
+
+        if options.is_empty() {
+            None
+        } else {
+            Some(options)
+        }
+    }
+
+    fn set_options(&mut self, options: HashMap<String, Value>) {
+        if let Some(seed) = options.get("seed").and_then(|v| v.as_u64()) {
+            self.seed = Some(seed as u32);
+        }
+        if let Some(max_tokens) = options.get("max_tokens").and_then(|v| v.as_u64()) {
+            self.max_tokens = Some(max_tokens as u32);
+        }
+        if let Some(temperature) = options.get("temperature").and_then(|v| v.as_f64()) {
+            self.temperature = Some(temperature as f32);
+        }
+        if let Some(top_p) = options.get("top_p").and_then(|v| v.as_f64()) {
+            self.top_p = Some(top_p as f32);
+        }
+        if let Some(frequency_penalty) = options.get("frequency_penalty").and_then(|v| v.as_f64()) {
+            self.frequency_penalty = Some(frequency_penalty as f32);
+        }
+        if let Some(presence_penalty) = options.get("presence_penalty").and_then(|v| v.as_f64()) {
+            self.presence_penalty = Some(presence_penalty as f32);
+        }
+        if let Some(stop) = options.get("stop").and_then(|v| v.as_array()) {
+            let stop_strings: Vec<String> = stop.iter()
+                .filter_map(|v| v.as_str())
+                .map(|s| s.to_string())
+                .collect();
+            if !stop_strings.is_empty() {
+                self.stop = Some(stop_strings);
+            }
+        }
+    }
+
+    fn get_format(&self) -> Option<String> {
+        self.response_format
+            .as_ref()
+            .and_then(|format| format.get("type"))
+            .and_then(|t| t.as_str())
+            .map(|t| t.to_string())
+    }
+
+    fn set_format(&mut self, format: String) {
+        self.response_format = Some(serde_json::json!({ "type": format }));
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.get_model().is_empty() {
+            return Err("Model name cannot be empty".to_string());
+        }
+
+        if self.message_count() == 0 {
+            return Err("Messages cannot be empty".to_string());
+        }
+
+        // 验证参数范围
+        if let Some(temperature) = self.temperature
+            && !(0.0..=2.0).contains(&temperature)
+        {
+            return Err("Temperature must be between 0.0 and 2.0".to_string());
+        }
+
+        if let Some(top_p) = self.top_p
+            && !(0.0..=1.0).contains(&top_p)
+        {
+            return Err("Top_p must be between 0.0 and 1.0".to_string());
+        }
+
+        for penalty in [self.frequency_penalty, self.presence_penalty].into_iter().flatten() {
+            if !(-2.0..=2.0).contains(&penalty) {
+                return Err("Penalties must be between -2.0 and 2.0".to_string());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// OpenAI 使用统计信息
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OpenAIUsage {
+    /// 输入 token 数量
+    pub prompt_tokens: u32,
+    /// 输出 token 数量
+    pub completion_tokens: u32,
+    /// 总 token 数量
+    pub total_tokens: u32,
+}
+
+/// OpenAI Chat 选择项
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OpenAIChoice {
+    /// 选择项索引
+    pub index: usize,
+    /// 生成的消息
+    pub message: Message,
+    /// 完成原因：stop、length、content_filter、tool_calls 等
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<String>,
+}
+
+/// OpenAI Chat 响应结构体
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OpenAIChatResponse {
+    /// 响应 ID
+    pub id: String,
+    /// 响应对象类型，通常为 "chat.completion"
+    pub object: String,
+    /// 响应创建时间戳
+    pub created: u64,
+    /// 使用的模型名称
+    pub model: String,
+    /// 响应中的选择项列表
+    pub choices: Vec<OpenAIChoice>,
+    /// 使用统计信息
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<OpenAIUsage>,
+    /// 系统指纹
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_fingerprint: Option<String>,
+}
+
+impl ChatResponseTrait for OpenAIChatResponse {
+    fn get_model(&self) -> &str {
+        &self.model
+    }
+
+    fn get_created_at(&self) -> &str {
+        // 与阿里云客户端一致，trait 要求返回 &str，这里返回 ID 作为替代
+        &self.id
+    }
+
+    fn get_message(&self) -> Option<Message> {
+        self.choices.first().map(|choice| choice.message.clone())
+    }
+
+    fn is_done(&self) -> bool {
+        // 对于非流式响应，始终为完成状态
+        true
+    }
+
+    fn get_eval_count(&self) -> Option<u32> {
+        self.usage.as_ref().map(|usage| usage.completion_tokens)
+    }
+
+    fn get_prompt_eval_count(&self) -> Option<u32> {
+        self.usage.as_ref().map(|usage| usage.prompt_tokens)
+    }
+}
+
+/// OpenAI 流式响应块
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OpenAIStreamResponse {
+    /// 响应 ID
+    pub id: String,
+    /// 响应对象类型，通常为 "chat.completion.chunk"
+    pub object: String,
+    /// 创建时间戳
+    pub created: u64,
+    /// 使用的模型名称
+    pub model: String,
+    /// 流式选择项列表（usage 块中为空）
+    pub choices: Vec<OpenAIStreamChoice>,
+    /// 使用统计（仅在设置 include_usage 后的最后一个块中出现）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<OpenAIUsage>,
+}
+
+/// OpenAI 流式选择项
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OpenAIStreamChoice {
+    /// 索引
+    pub index: usize,
+    /// 增量消息内容
+    pub delta: OpenAIDelta,
+    /// 完成原因（仅在最后出现）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<String>,
+}
+
+/// OpenAI 增量内容
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OpenAIDelta {
+    /// 角色（仅在第一个块中出现）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    /// 增量内容
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    /// 工具调用增量，参数分多块返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCallDelta>>,
+}
+
+/// OpenAI Completions（文本补全）请求结构体
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OpenAICompletionRequest {
+    /// 要使用的模型名称
+    pub model: String,
+    /// 提示词
+    pub prompt: String,
+    /// 插入到生成内容之后的文本
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suffix: Option<String>,
+    /// 是否使用流式输出
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream: Option<bool>,
+    /// 最大生成 token 数量
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// 温度参数，控制随机性
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Top-p 参数，核采样
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    /// 停止生成的标记
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Vec<String>>,
+    /// 终端用户标识
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// 流式输出选项
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<OpenAIStreamOptions>,
+}
+
+impl OpenAICompletionRequest {
+    /// 创建新的补全请求
+    pub fn new(model: String, prompt: String) -> Self {
+        Self {
+            model,
+            prompt,
+            suffix: None,
+            stream: None,
+            max_tokens: None,
+            temperature: None,
+            top_p: None,
+            stop: None,
+            user: None,
+            stream_options: None,
+        }
+    }
+}
+
+impl CompletionRequestTrait for OpenAICompletionRequest {
+    fn get_model(&self) -> &str {
+        &self.model
+    }
+
+    fn get_prompt(&self) -> &str {
+        &self.prompt
+    }
+
+    fn is_stream(&self) -> Option<bool> {
+        self.stream
+    }
+
+    fn set_stream(&mut self, stream: bool) {
+        self.stream = Some(stream);
+    }
+}
+
+/// OpenAI Completions 选择项（流式块中结构相同，text 为增量）
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OpenAICompletionChoice {
+    /// 选择项索引
+    pub index: usize,
+    /// 生成的文本
+    #[serde(default)]
+    pub text: String,
+    /// 完成原因：stop、length 等
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<String>,
+}
+
+/// OpenAI Completions 响应结构体（流式输出中的单个块结构相同）
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OpenAICompletionResponse {
+    /// 响应 ID
+    pub id: String,
+    /// 响应对象类型，通常为 "text_completion"
+    pub object: String,
+    /// 响应创建时间戳
+    pub created: u64,
+    /// 使用的模型名称
+    pub model: String,
+    /// 选择项列表（usage 块中为空）
+    pub choices: Vec<OpenAICompletionChoice>,
+    /// 使用统计信息
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<OpenAIUsage>,
+}
+
+impl CompletionResponseTrait for OpenAICompletionResponse {
+    fn get_model(&self) -> &str {
+        &self.model
+    }
+
+    fn get_text(&self) -> Option<String> {
+        self.choices.first().map(|choice| choice.text.clone())
+    }
+
+    fn is_done(&self) -> bool {
+        self.choices.first().is_some_and(|choice| choice.finish_reason.is_some())
+    }
+
+    fn get_finish_reason(&self) -> Option<String> {
+        self.choices.first().and_then(|choice| choice.finish_reason.clone())
+    }
+
+    fn get_eval_count(&self) -> Option<u32> {
+        self.usage.as_ref().map(|usage| usage.completion_tokens)
+    }
+
+    fn get_prompt_eval_count(&self) -> Option<u32> {
+        self.usage.as_ref().map(|usage| usage.prompt_tokens)
+    }
+}
+
+/// OpenAI 客户端错误类型
+#[derive(Debug)]
+pub enum OpenAIError {
+    Client(ClientError),
+    Json(serde_json::Error),
+    InvalidRequest(String),
+    Api(String),
+    Auth(String),
+}
+
+impl fmt::Display for OpenAIError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpenAIError::Client(e) => write!(f, "Client error: {}", e),
+            OpenAIError::Json(e) => write!(f, "JSON serialization error: {}", e),
+            OpenAIError::InvalidRequest(msg) => write!(f, "Invalid request: {}", msg),
+            OpenAIError::Api(msg) => write!(f, "API error: {}", msg),
+            OpenAIError::Auth(msg) => write!(f, "Authentication error: {}", msg),
+        }
+    }
+}
+
+impl OpenAIError {
+    /// 是否为限流或配额错误，此时应暂停使用当前 API Key
+    pub fn is_rate_limited(&self) -> bool {
+        match self {
+            OpenAIError::Client(e) => e.is_rate_limited(),
+            OpenAIError::Api(msg) => {
+                let msg = msg.to_lowercase();
+                msg.contains("rate limit") || msg.contains("quota")
+            }
+            _ => false,
+        }
+    }
+}
+
+impl std::error::Error for OpenAIError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OpenAIError::Client(e) => Some(e),
+            OpenAIError::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ClientError> for OpenAIError {
+    fn from(error: ClientError) -> Self {
+        OpenAIError::Client(error)
+    }
+}
+
+impl From<serde_json::Error> for OpenAIError {
+    fn from(error: serde_json::Error) -> Self {
+        OpenAIError::Json(error)
+    }
+}
+
+/// 解析非流式响应，响应体中包含 error 对象时返回对应错误（Azure OpenAI 共用）
+pub(crate) fn parse_chat_response(response_text: &str) -> Result<OpenAIChatResponse, OpenAIError> {
+    parse_response(response_text)
+}
+
+/// 解析非流式补全响应（OpenAI 兼容服务共用）
+pub(crate) fn parse_completion_response(response_text: &str) -> Result<OpenAICompletionResponse, OpenAIError> {
+    parse_response(response_text)
+}
+
+// Chat 与 Completions 共用的错误检查和反序列化
+fn parse_response<T: DeserializeOwned>(response_text: &str) -> Result<T, OpenAIError> {
+    // 尝试解析错误响应
+    if let Ok(error_response) = serde_json::from_str::<Value>(response_text)
+        && let Some(error) = error_response.get("error")
+        && let Some(message) = error.get("message").and_then(|v| v.as_str())
+    {
+        // OpenAI 使用 invalid_api_key，Azure 使用 401
+        if matches!(error.get("code").and_then(|v| v.as_str()), Some("invalid_api_key" | "401")) {
+            return Err(OpenAIError::Auth(message.to_string()));
+        }
+        return Err(OpenAIError::Api(message.to_string()));
+    }
+
+    Ok(serde_json::from_str(response_text)?)
+}
+
+/// 处理一行 SSE 数据，返回是否继续读取（Azure OpenAI 共用）
+pub(crate) fn handle_stream_line<T, F>(line: &str, callback: &mut F) -> bool
+where
+    T: DeserializeOwned,
+    F: FnMut(T) -> bool,
+{
+    // 检查是否为结束标记
+    if line.trim() == "data: [DONE]" {
+        return false; // 结束流式处理
+    }
+
+    // 解析成功时调用用户回调，其他行继续处理
+    match parse_stream_data(line) {
+        Some(response) => callback(response),
+        None => true,
+    }
+}
+
+/// 解析一行 SSE 数据，空行、非数据行、结束标记和无法解析的行返回 None（阿里云、Azure OpenAI 共用）
+pub(crate) fn parse_stream_data<T: DeserializeOwned>(line: &str) -> Option<T> {
+    let json_str = line.trim().strip_prefix("data: ")?;
+    if json_str == "[DONE]" {
+        return None;
+    }
+    serde_json::from_str::<T>(json_str)
+        .map_err(|e| warn!(error = %e, line = %json_str, "Failed to parse streaming response"))
+        .ok()
+}
+
+/// OpenAI 客户端
+pub struct OpenAIClient {
+    /// 基础 HTTP 客户端
+    base_client: BaseClient,
+    /// API Key
+    api_key: String,
+    /// API 基础 URL（包含版本路径，例如 https://api.openai.com/v1）
+    base_url: String,
+}
+
+impl OpenAIClient {
+    /// OpenAI API 的默认基础 URL
+    pub const DEFAULT_BASE_URL: &'static str = "https://api.openai.com/v1";
+
+    /// 创建新的 OpenAI 客户端
+    pub fn new(api_key: String) -> Result<Self> {
+        Self::new_with_base_url(api_key, Self::DEFAULT_BASE_URL.to_string())
+    }
+
+    /// 使用自定义基础 URL 创建客户端
+    pub fn new_with_base_url(api_key: String, base_url: String) -> Result<Self> {
+        Self::new_with_config(api_key, base_url, ClientConfig::new())
+    }
+
+    /// 使用自定义配置创建客户端
+    pub fn new_with_config(api_key: String, base_url: String, config: ClientConfig) -> Result<Self> {
+        let config = Self::with_auth_headers(config, &api_key);
+        let base_client = BaseClient::new(config)?;
+
+        Ok(Self {
+            base_client,
+            api_key,
+            base_url,
+        })
+    }
+
+    /// 使用自定义配置和 HTTP 客户端创建客户端（用于测试）
+    pub fn new_with_client(api_key: String, base_url: String, config: ClientConfig, client: Client) -> Result<Self> {
+        let config = Self::with_auth_headers(config, &api_key);
+        let base_client = BaseClient::new_with_client(config, Some(client))?;
+
+        Ok(Self {
+            base_client,
+            api_key,
+            base_url,
+        })
+    }
+
+    // 确保设置了正确的认证头
+    fn with_auth_headers(config: ClientConfig, api_key: &str) -> ClientConfig {
+        config
+            .add_header("Authorization".to_string(), format!("Bearer {}", api_key))
+            .add_header("Content-Type".to_string(), "application/json".to_string())
+            .with_default_shared_pool("openai")
+    }
+
+    /// 发送聊天请求（非流式）
+    pub async fn chat(&self, mut request: OpenAIChatRequest) -> Result<OpenAIChatResponse, OpenAIError> {
+        // 确保不是流式请求
+        request.set_stream(false);
+
+        // 验证请求
+        request.validate().map_err(OpenAIError::InvalidRequest)?;
+
+        // 构建完整的 URL
+        let url = format!("{}/chat/completions", self.base_url);
+
+        // 发送请求
+        let response = self.base_client.post(&url, &request).await?;
+
+        // 解析响应
+        let response_text = response.text().await.map_err(|e| {
+            OpenAIError::Api(format!("Failed to read response: {}", e))
+        })?;
+
+        parse_chat_response(&response_text)
+    }
+
+    /// 发送流式聊天请求
+    pub async fn chat_stream<F>(&self, mut request: OpenAIChatRequest, mut callback: F) -> Result<(), OpenAIError>
+    where
+        F: FnMut(OpenAIStreamResponse) -> bool + Send,
+    {
+        // 确保是流式请求
+        request.set_stream(true);
+
+        // 验证请求
+        request.validate().map_err(OpenAIError::InvalidRequest)?;
+
+        // 构建完整的 URL
+        let url = format!("{}/chat/completions", self.base_url);
+
+        // 发送流式请求
+        self.base_client.post_stream(&url, &request, &OpenAIStreamAccounting, |line: String| {
+            handle_stream_line(&line, &mut callback)
+        }).await?;
+
+        Ok(())
+    }
+
+    /// 发送流式聊天请求，以 Stream 返回响应块；丢弃返回的流时停止读取
+    pub fn chat_stream_iter(
+        &self,
+        mut request: OpenAIChatRequest,
+    ) -> Result<BoxStream<'static, Result<OpenAIStreamResponse, OpenAIError>>, OpenAIError> {
+        request.set_stream(true);
+        request.validate().map_err(OpenAIError::InvalidRequest)?;
+
+        let url = format!("{}/chat/completions", self.base_url);
+        let lines = self.base_client.post_stream_iter(&url, request, OpenAIStreamAccounting);
+        Ok(parse_line_stream(lines, parse_stream_data))
+    }
+
+    /// 发送文本补全请求（非流式）
+    pub async fn complete(&self, mut request: OpenAICompletionRequest) -> Result<OpenAICompletionResponse, OpenAIError> {
+        request.set_stream(false);
+        request.validate().map_err(OpenAIError::InvalidRequest)?;
+
+        let url = format!("{}/completions", self.base_url);
+        let response = self.base_client.post(&url, &request).await?;
+
+        let response_text = response.text().await.map_err(|e| {
+            OpenAIError::Api(format!("Failed to read response: {}", e))
+        })?;
+
+        parse_completion_response(&response_text)
+    }
+
+    /// 发送流式文本补全请求
+    pub async fn complete_stream<F>(&self, mut request: OpenAICompletionRequest, mut callback: F) -> Result<(), OpenAIError>
+    where
+        F: FnMut(OpenAICompletionResponse) -> bool + Send,
+    {
+        request.set_stream(true);
+        request.validate().map_err(OpenAIError::InvalidRequest)?;
+
+        let url = format!("{}/completions", self.base_url);
+        self.base_client.post_stream(&url, &request, &OpenAIStreamAccounting, |line: String| {
+            handle_stream_line(&line, &mut callback)
+        }).await?;
+
+        Ok(())
+    }
+
+    /// 获取 API Key（用于调试，生产环境中应避免暴露）
+    pub fn api_key(&self) -> &str {
+        &self.api_key
+    }
+
+    /// 获取基础 URL
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+}
+
+#[async_trait]
+impl LLMClientTrait for OpenAIClient {
+    type Request = OpenAIChatRequest;
+    type Response = OpenAIChatResponse;
+    type Error = OpenAIError;
+
+    async fn send_request(&self, request: Self::Request) -> Result<Self::Response, Self::Error> {
+        self.chat(request).await
+    }
+
+    async fn send_stream_request<F>(
+        &self,
+        request: Self::Request,
+        callback: F,
+    ) -> Result<(), Self::Error>
+    where
+        F: Fn(String) -> bool + Send + Sync,
+    {
+        self.chat_stream(request, |response| {
+            // 将响应转换为 JSON 字符串
+            match serde_json::to_string(&response) {
+                Ok(json_str) => callback(json_str),
+                Err(_) => false, // 解析失败时停止
+            }
+        }).await
+    }
+
+    fn validate_request(&self, request: &Self::Request) -> Result<(), Self::Error> {
+        request.validate().map_err(OpenAIError::InvalidRequest)
+    }
+
+    fn client_name(&self) -> &'static str {
+        "OpenAI"
+    }
+
+    fn base_client(&self) -> &BaseClient {
+        &self.base_client
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openai_chat_request_validation() {
+        let request = OpenAIChatRequest::new("gpt-4o-mini".to_string(), vec![Message::user("hi".to_string())]);
+        assert!(request.validate().is_ok());
+
+        // 测试空模型名称
+        let request = OpenAIChatRequest::new("".to_string(), vec![Message::user("hi".to_string())]);
+        assert!(request.validate().is_err());
+
+        // 测试空消息列表
+        let request = OpenAIChatRequest::new("gpt-4o-mini".to_string(), vec![]);
+        assert!(request.validate().is_err());
+
+        // 测试参数范围
+        let request = OpenAIChatRequest::new("gpt-4o-mini".to_string(), vec![Message::user("hi".to_string())])
+            .with_temperature(2.5);
+        assert!(request.validate().is_err());
+    }
+
+    #[test]
+    fn test_openai_stream_options_only_for_stream() {
+        let mut request = OpenAIChatRequest::new("gpt-4o-mini".to_string(), vec![Message::user("hi".to_string())])
+            .with_stream_usage(true);
+        request.set_stream(true);
+        assert!(request.stream_options.is_some());
+
+        request.set_stream(false);
+        let body = serde_json::to_value(&request).unwrap();
+        assert!(body.get("stream_options").is_none());
+    }
+}
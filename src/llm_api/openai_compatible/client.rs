@@ -0,0 +1,200 @@
+//! # OpenAI 兼容客户端
+//!
+//! 用于 vLLM、LM Studio、llama.cpp server、Together、DeepSeek 等提供 OpenAI 兼容接口的服务。
+//! 请求/响应格式与 OpenAI 相同，区别只在于地址和认证方式，全部由 [`OpenAICompatibleConfig`] 描述：
+//! - 接口地址为 `{base_url}{path_prefix}/chat/completions`（补全为 `/completions`）
+//! - 认证请求头名称和前缀可配置，本地部署的服务可以不带 API Key
+
+use anyhow::Result;
+use futures_util::stream::BoxStream;
+use reqwest::Client;
+
+use crate::llm_api::openai::client::{
+    handle_stream_line, parse_chat_response, parse_completion_response, parse_stream_data, OpenAIChatRequest, OpenAIChatResponse, OpenAICompletionRequest,
+    OpenAICompletionResponse, OpenAIError, OpenAIStreamResponse,
+};
+use crate::llm_api::utils::{
+    client::{parse_line_stream, BaseClient, ClientConfig},
+    stream_accounting::OpenAIStreamAccounting,
+    chat_traits::{ChatRequestTrait, CompletionRequestTrait},
+};
+
+/// 默认认证请求头
+pub const DEFAULT_AUTH_HEADER: &str = "Authorization";
+
+/// 默认认证前缀（仅用于 Authorization 请求头）
+pub const DEFAULT_AUTH_SCHEME: &str = "Bearer";
+
+/// OpenAI 兼容服务的地址和认证方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenAICompatibleConfig {
+    /// 服务地址，例如 http://localhost:8000
+    pub base_url: String,
+    /// 接口路径前缀，例如 "/v1"，为空时直接拼接在 base_url 之后
+    pub path_prefix: String,
+    /// 携带 API Key 的请求头名称
+    pub auth_header: String,
+    /// API Key 前缀，例如 "Bearer"，为 None 时请求头的值即 API Key
+    pub auth_scheme: Option<String>,
+}
+
+impl OpenAICompatibleConfig {
+    /// 使用默认的 `Authorization: Bearer <key>` 认证
+    pub fn new(base_url: String) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            path_prefix: String::new(),
+            auth_header: DEFAULT_AUTH_HEADER.to_string(),
+            auth_scheme: Some(DEFAULT_AUTH_SCHEME.to_string()),
+        }
+    }
+
+    /// 设置接口路径前缀，自动补全开头的斜杠并去除末尾的斜杠
+    pub fn with_path_prefix(mut self, path_prefix: &str) -> Self {
+        let path_prefix = path_prefix.trim().trim_matches('/');
+        self.path_prefix = if path_prefix.is_empty() { String::new() } else { format!("/{}", path_prefix) };
+        self
+    }
+
+    /// 设置认证请求头和前缀
+    pub fn with_auth_header(mut self, auth_header: String, auth_scheme: Option<String>) -> Self {
+        self.auth_header = auth_header;
+        self.auth_scheme = auth_scheme.filter(|scheme| !scheme.is_empty());
+        self
+    }
+
+    /// 接口地址
+    pub fn endpoint(&self, path: &str) -> String {
+        format!("{}{}{}", self.base_url, self.path_prefix, path)
+    }
+
+    // 认证请求头的值
+    fn auth_value(&self, api_key: &str) -> String {
+        match &self.auth_scheme {
+            Some(scheme) => format!("{} {}", scheme, api_key),
+            None => api_key.to_string(),
+        }
+    }
+}
+
+/// OpenAI 兼容客户端
+pub struct OpenAICompatibleClient {
+    /// 基础 HTTP 客户端
+    base_client: BaseClient,
+    /// 地址和认证方式
+    config: OpenAICompatibleConfig,
+}
+
+impl OpenAICompatibleClient {
+    /// 创建客户端，`api_key` 为 None 时不发送认证请求头
+    pub fn new(config: OpenAICompatibleConfig, api_key: Option<String>) -> Result<Self> {
+        Self::new_with_config(config, api_key, ClientConfig::new())
+    }
+
+    /// 使用自定义客户端配置创建客户端
+    pub fn new_with_config(config: OpenAICompatibleConfig, api_key: Option<String>, client_config: ClientConfig) -> Result<Self> {
+        let client_config = Self::with_auth_headers(client_config, &config, api_key.as_deref());
+        let base_client = BaseClient::new(client_config)?;
+        Ok(Self { base_client, config })
+    }
+
+    /// 使用自定义客户端配置和 HTTP 客户端创建客户端（用于测试）
+    pub fn new_with_client(
+        config: OpenAICompatibleConfig,
+        api_key: Option<String>,
+        client_config: ClientConfig,
+        client: Client,
+    ) -> Result<Self> {
+        let client_config = Self::with_auth_headers(client_config, &config, api_key.as_deref());
+        let base_client = BaseClient::new_with_client(client_config, Some(client))?;
+        Ok(Self { base_client, config })
+    }
+
+    fn with_auth_headers(client_config: ClientConfig, config: &OpenAICompatibleConfig, api_key: Option<&str>) -> ClientConfig {
+        let client_config = client_config
+            .add_header("Content-Type".to_string(), "application/json".to_string())
+            .with_default_shared_pool(&format!("openai_compatible:{}", config.base_url));
+        match api_key {
+            Some(api_key) => client_config.add_header(config.auth_header.clone(), config.auth_value(api_key)),
+            None => client_config,
+        }
+    }
+
+    /// 发送聊天请求（非流式）
+    pub async fn chat(&self, mut request: OpenAIChatRequest) -> Result<OpenAIChatResponse, OpenAIError> {
+        request.set_stream(false);
+        request.validate().map_err(OpenAIError::InvalidRequest)?;
+
+        let url = self.config.endpoint("/chat/completions");
+        let response = self.base_client.post(&url, &request).await?;
+        let response_text = response.text().await.map_err(|e| {
+            OpenAIError::Api(format!("Failed to read response: {}", e))
+        })?;
+
+        parse_chat_response(&response_text)
+    }
+
+    /// 发送流式聊天请求
+    pub async fn chat_stream<F>(&self, mut request: OpenAIChatRequest, mut callback: F) -> Result<(), OpenAIError>
+    where
+        F: FnMut(OpenAIStreamResponse) -> bool + Send,
+    {
+        request.set_stream(true);
+        request.validate().map_err(OpenAIError::InvalidRequest)?;
+
+        let url = self.config.endpoint("/chat/completions");
+        self.base_client.post_stream(&url, &request, &OpenAIStreamAccounting, |line: String| {
+            handle_stream_line(&line, &mut callback)
+        }).await?;
+
+        Ok(())
+    }
+
+    /// 发送流式聊天请求，以 Stream 返回响应块；丢弃返回的流时停止读取
+    pub fn chat_stream_iter(
+        &self,
+        mut request: OpenAIChatRequest,
+    ) -> Result<BoxStream<'static, Result<OpenAIStreamResponse, OpenAIError>>, OpenAIError> {
+        request.set_stream(true);
+        request.validate().map_err(OpenAIError::InvalidRequest)?;
+
+        let url = self.config.endpoint("/chat/completions");
+        let lines = self.base_client.post_stream_iter(&url, request, OpenAIStreamAccounting);
+        Ok(parse_line_stream(lines, parse_stream_data))
+    }
+
+    /// 发送文本补全请求（非流式）
+    pub async fn complete(&self, mut request: OpenAICompletionRequest) -> Result<OpenAICompletionResponse, OpenAIError> {
+        request.set_stream(false);
+        request.validate().map_err(OpenAIError::InvalidRequest)?;
+
+        let url = self.config.endpoint("/completions");
+        let response = self.base_client.post(&url, &request).await?;
+        let response_text = response.text().await.map_err(|e| {
+            OpenAIError::Api(format!("Failed to read response: {}", e))
+        })?;
+
+        parse_completion_response(&response_text)
+    }
+
+    /// 发送流式文本补全请求
+    pub async fn complete_stream<F>(&self, mut request: OpenAICompletionRequest, mut callback: F) -> Result<(), OpenAIError>
+    where
+        F: FnMut(OpenAICompletionResponse) -> bool + Send,
+    {
+        request.set_stream(true);
+        request.validate().map_err(OpenAIError::InvalidRequest)?;
+
+        let url = self.config.endpoint("/completions");
+        self.base_client.post_stream(&url, &request, &OpenAIStreamAccounting, |line: String| {
+            handle_stream_line(&line, &mut callback)
+        }).await?;
+
+        Ok(())
+    }
+
+    /// 获取地址和认证方式
+    pub fn config(&self) -> &OpenAICompatibleConfig {
+        &self.config
+    }
+}
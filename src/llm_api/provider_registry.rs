@@ -0,0 +1,364 @@
+//! # Provider Registry
+//!
+//! 供应商适配器工厂注册表。每种供应商类型（与数据库 providers.name 对应）
+//! 通过实现 [`ProviderFactory`] 创建适配器，下游 crate 或按 feature 编译的模块
+//! 可以调用 [`register_provider_factory`] 注册新的供应商类型，无需修改 dispatcher。
+//! providers.config 中的 `type` 可以指定供应商类型，例如提供 OpenAI 兼容接口的服务
+//! 使用 `openai_compatible`，只需在 providers 表中配置即可接入
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use anyhow::{anyhow, bail, Result};
+use lazy_static::lazy_static;
+use serde_json::Value;
+
+use crate::config::gateway_config;
+use crate::llm_api::azure::client::AzureOpenAIClient;
+use crate::llm_api::dispatcher::{
+    AliPoolAdapter, AzureOpenAIAdapter, LLMClientAdapter, OllamaAdapter, OpenAIAdapter, OpenAICompatibleAdapter, Provider,
+};
+use crate::llm_api::mock::adapter::{MockAdapter, DEFAULT_PARTIAL_CHUNKS};
+use crate::llm_api::ollama::client::OllamaClient;
+use crate::llm_api::openai_compatible::client::{OpenAICompatibleConfig, DEFAULT_AUTH_HEADER, DEFAULT_AUTH_SCHEME};
+use crate::llm_api::utils::client::{ClientConfig, TlsConfig};
+use crate::llm_api::utils::client_pool::{
+    ClientPool, DynamicAliClient, DynamicAzureOpenAIClient, DynamicOpenAIClient, DynamicOpenAICompatibleClient,
+};
+
+/// 自动注册时Ali客户端池大小
+pub const DEFAULT_ALI_POOL_SIZE: usize = 4;
+
+/// 自动注册时OpenAI客户端池大小
+pub const DEFAULT_OPENAI_POOL_SIZE: usize = 4;
+
+/// 自动注册时Azure OpenAI客户端池大小
+pub const DEFAULT_AZURE_POOL_SIZE: usize = 4;
+
+/// 自动注册时OpenAI兼容供应商的客户端池大小
+pub const DEFAULT_OPENAI_COMPATIBLE_POOL_SIZE: usize = 4;
+
+/// 由客户端负责设置、不允许通过供应商配置覆盖的请求头（小写）
+const RESERVED_HEADERS: &[&str] = &["authorization", "api-key", "content-type"];
+
+/// 创建适配器所需的供应商配置（来自数据库 providers 表）
+#[derive(Debug, Clone)]
+pub struct ProviderConfig {
+    /// 供应商实例，适配器的 `provider_name()` 应返回该值
+    pub provider: Provider,
+    /// 供应商类型名称，例如 "ollama"
+    pub name: String,
+    /// 已去除首尾空白和末尾斜杠的基础URL，未配置时为 None
+    pub base_url: Option<String>,
+    /// 供应商专属配置（providers.config 中的 JSON），未配置时为 None
+    pub settings: Option<Value>,
+}
+
+impl ProviderConfig {
+    pub fn new(provider: Provider, name: &str, base_url: Option<&str>) -> Self {
+        Self {
+            provider,
+            name: name.trim().to_lowercase(),
+            base_url: base_url
+                .map(str::trim)
+                .filter(|url| !url.is_empty())
+                .map(|url| url.trim_end_matches('/').to_string()),
+            settings: None,
+        }
+    }
+
+    /// 设置供应商专属配置
+    pub fn with_settings(mut self, settings: Option<Value>) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// 读取字符串类型的配置项
+    pub fn setting_str(&self, key: &str) -> Option<&str> {
+        self.settings.as_ref()?.get(key)?.as_str()
+    }
+
+    /// 附加到每个出站请求的静态请求头（providers.config 中的 `headers` 对象），
+    /// 认证相关的请求头始终由客户端设置，配置中的同名请求头会被忽略
+    pub fn extra_headers(&self) -> HashMap<String, String> {
+        self.settings.as_ref()
+            .and_then(|settings| settings.get("headers"))
+            .and_then(|headers| headers.as_object())
+            .map(|headers| {
+                headers.iter()
+                    .filter(|(name, _)| !RESERVED_HEADERS.contains(&name.to_lowercase().as_str()))
+                    .filter_map(|(name, value)| Some((name.clone(), value.as_str()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// providers.config 中的 `tls` 对象，用于使用内部 CA 或要求客户端证书的私有化部署，例如
+    /// `{"tls": {"ca_cert": "/etc/ssl/internal-ca.pem", "client_cert": "/etc/ssl/client.pem", "client_key": "/etc/ssl/client.key"}}`；
+    /// 证书文件在注册供应商时读取并检查
+    pub fn tls_config(&self) -> Result<TlsConfig> {
+        let Some(tls) = self.settings.as_ref().and_then(|settings| settings.get("tls")) else {
+            return Ok(TlsConfig::default());
+        };
+        let path = |key: &str| tls.get(key).and_then(Value::as_str).map(str::trim).filter(|p| !p.is_empty());
+        let mut config = TlsConfig::new();
+        if let Some(ca_cert) = path("ca_cert") {
+            config = config.with_ca_bundle_file(ca_cert)?;
+        }
+        match (path("client_cert"), path("client_key")) {
+            (Some(cert), Some(key)) => config = config.with_client_identity_files(cert, key)?,
+            (None, None) => {}
+            _ => bail!("provider '{}': tls.client_cert and tls.client_key must be configured together", self.name),
+        }
+        let accept_invalid = tls.get("danger_accept_invalid_certs").and_then(Value::as_bool).unwrap_or(false);
+        config = config.with_danger_accept_invalid_certs(accept_invalid);
+        config.validate().map_err(|e| anyhow!("provider '{}': {}", self.name, e))?;
+        Ok(config)
+    }
+}
+
+/// 供应商适配器工厂
+pub trait ProviderFactory: Send + Sync {
+    /// 供应商类型名称，与 providers.name 匹配（小写）
+    fn provider_type(&self) -> &str;
+
+    /// 根据配置创建适配器
+    fn create_adapter(&self, config: &ProviderConfig) -> Result<Box<dyn LLMClientAdapter>>;
+}
+
+/// 供应商工厂注册表
+pub struct ProviderRegistry {
+    factories: RwLock<HashMap<String, Arc<dyn ProviderFactory>>>,
+}
+
+impl ProviderRegistry {
+    /// 创建空的注册表
+    pub fn new() -> Self {
+        Self {
+            factories: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 创建包含内置供应商（ollama、ali、openai、azure、openai_compatible、mock）的注册表
+    pub fn with_builtin_factories() -> Self {
+        let registry = Self::new();
+        registry.register(Arc::new(OllamaFactory));
+        registry.register(Arc::new(AliFactory));
+        registry.register(Arc::new(OpenAIFactory));
+        registry.register(Arc::new(AzureOpenAIFactory));
+        registry.register(Arc::new(OpenAICompatibleFactory));
+        registry.register(Arc::new(MockFactory));
+        registry
+    }
+
+    /// 注册工厂，同类型的已有工厂会被替换
+    pub fn register(&self, factory: Arc<dyn ProviderFactory>) {
+        let provider_type = factory.provider_type().to_lowercase();
+        self.factories.write().unwrap().insert(provider_type, factory);
+    }
+
+    /// 移除某个类型的工厂
+    pub fn unregister(&self, provider_type: &str) -> bool {
+        self.factories.write().unwrap().remove(&provider_type.to_lowercase()).is_some()
+    }
+
+    /// 获取某个类型的工厂
+    pub fn get(&self, provider_type: &str) -> Option<Arc<dyn ProviderFactory>> {
+        self.factories.read().unwrap().get(&provider_type.to_lowercase()).cloned()
+    }
+
+    /// 已注册的供应商类型
+    pub fn provider_types(&self) -> Vec<String> {
+        let mut types: Vec<String> = self.factories.read().unwrap().keys().cloned().collect();
+        types.sort();
+        types
+    }
+}
+
+impl Default for ProviderRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+lazy_static! {
+    /// 全局供应商工厂注册表
+    static ref GLOBAL_PROVIDER_REGISTRY: ProviderRegistry = ProviderRegistry::with_builtin_factories();
+}
+
+/// 获取全局供应商工厂注册表
+pub fn provider_registry() -> &'static ProviderRegistry {
+    &GLOBAL_PROVIDER_REGISTRY
+}
+
+/// 向全局注册表注册供应商工厂
+pub fn register_provider_factory(factory: Arc<dyn ProviderFactory>) {
+    provider_registry().register(factory);
+}
+
+/// Ollama 工厂，base_url 未配置时使用启动配置中的 `providers.ollama_base_url`（可通过 `OLLAMA_BASE_URL` 覆盖）
+pub struct OllamaFactory;
+
+impl ProviderFactory for OllamaFactory {
+    fn provider_type(&self) -> &str {
+        "ollama"
+    }
+
+    fn create_adapter(&self, config: &ProviderConfig) -> Result<Box<dyn LLMClientAdapter>> {
+        let base_url = config.base_url.clone()
+            .unwrap_or_else(|| gateway_config().providers.ollama_base_url.clone());
+        let client_config = ClientConfig::new()
+            .add_headers(&config.extra_headers())
+            .with_tls(config.tls_config()?);
+        Ok(Box::new(OllamaAdapter::new(OllamaClient::new_with_config(base_url, client_config)?)))
+    }
+}
+
+/// 阿里云工厂，使用 Key 池轮询的客户端池
+pub struct AliFactory;
+
+impl ProviderFactory for AliFactory {
+    fn provider_type(&self) -> &str {
+        "ali"
+    }
+
+    fn create_adapter(&self, config: &ProviderConfig) -> Result<Box<dyn LLMClientAdapter>> {
+        let base_url = config.base_url.clone()
+            .unwrap_or_else(|| gateway_config().providers.ali_base_url.clone());
+        let headers = config.extra_headers();
+        let tls = config.tls_config()?;
+        let clients = (0..DEFAULT_ALI_POOL_SIZE)
+            .map(|_| Ok(DynamicAliClient::new_with_base_url(base_url.clone())?.with_headers(headers.clone()).with_tls(tls.clone())))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Box::new(AliPoolAdapter::new(Arc::new(ClientPool::new(clients)))))
+    }
+}
+
+/// OpenAI 工厂，使用 Key 池轮询的客户端池，base_url 可指向兼容 OpenAI 格式的服务
+pub struct OpenAIFactory;
+
+impl ProviderFactory for OpenAIFactory {
+    fn provider_type(&self) -> &str {
+        "openai"
+    }
+
+    fn create_adapter(&self, config: &ProviderConfig) -> Result<Box<dyn LLMClientAdapter>> {
+        let base_url = config.base_url.clone()
+            .unwrap_or_else(|| gateway_config().providers.openai_base_url.clone());
+        let headers = config.extra_headers();
+        let tls = config.tls_config()?;
+        let clients = (0..DEFAULT_OPENAI_POOL_SIZE)
+            .map(|_| Ok(DynamicOpenAIClient::new_with_base_url(base_url.clone())?.with_headers(headers.clone()).with_tls(tls.clone())))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Box::new(OpenAIAdapter::new(Arc::new(ClientPool::new(clients)))))
+    }
+}
+
+/// Azure OpenAI 工厂，base_url 为资源地址（例如 https://my-resource.openai.azure.com），
+/// providers.config 示例：`{"api_version": "2024-06-01", "deployments": {"gpt-4o": "prod-gpt4o"}}`
+pub struct AzureOpenAIFactory;
+
+impl ProviderFactory for AzureOpenAIFactory {
+    fn provider_type(&self) -> &str {
+        "azure"
+    }
+
+    fn create_adapter(&self, config: &ProviderConfig) -> Result<Box<dyn LLMClientAdapter>> {
+        let endpoint = config.base_url.clone()
+            .ok_or_else(|| anyhow!("azure provider requires base_url (resource endpoint)"))?;
+        let api_version = config.setting_str("api_version")
+            .unwrap_or(AzureOpenAIClient::DEFAULT_API_VERSION)
+            .to_string();
+
+        let deployments: HashMap<String, String> = config.settings.as_ref()
+            .and_then(|settings| settings.get("deployments"))
+            .and_then(|deployments| deployments.as_object())
+            .map(|deployments| {
+                deployments.iter()
+                    .filter_map(|(model, deployment)| Some((model.clone(), deployment.as_str()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default();
+        if deployments.is_empty() {
+            bail!("azure provider requires a model -> deployment mapping in config.deployments");
+        }
+
+        let headers = config.extra_headers();
+        let tls = config.tls_config()?;
+        let clients = (0..DEFAULT_AZURE_POOL_SIZE)
+            .map(|_| DynamicAzureOpenAIClient::new(endpoint.clone(), api_version.clone()).with_headers(headers.clone()).with_tls(tls.clone()))
+            .collect();
+        Ok(Box::new(AzureOpenAIAdapter::new(Arc::new(ClientPool::new(clients)), deployments)))
+    }
+}
+
+/// OpenAI 兼容供应商工厂，适配器以 providers 表中的名称注册，API Key 从该名称的 Key 池获取；
+/// providers.config 示例：
+/// `{"type": "openai_compatible", "path_prefix": "/v1", "auth_header": "Authorization", "auth_scheme": "Bearer", "requires_key": true, "models": ["deepseek-chat"]}`
+pub struct OpenAICompatibleFactory;
+
+impl OpenAICompatibleFactory {
+    /// 供应商类型名称
+    pub const PROVIDER_TYPE: &'static str = "openai_compatible";
+}
+
+impl ProviderFactory for OpenAICompatibleFactory {
+    fn provider_type(&self) -> &str {
+        Self::PROVIDER_TYPE
+    }
+
+    fn create_adapter(&self, config: &ProviderConfig) -> Result<Box<dyn LLMClientAdapter>> {
+        let base_url = config.base_url.clone()
+            .ok_or_else(|| anyhow!("openai_compatible provider '{}' requires base_url", config.name))?;
+        // 自定义认证请求头（例如 x-api-key）默认不带前缀
+        let auth_header = config.setting_str("auth_header").unwrap_or(DEFAULT_AUTH_HEADER).to_string();
+        let auth_scheme = match config.settings.as_ref().and_then(|settings| settings.get("auth_scheme")) {
+            Some(scheme) => scheme.as_str().map(str::to_string),
+            None if auth_header.eq_ignore_ascii_case(DEFAULT_AUTH_HEADER) => Some(DEFAULT_AUTH_SCHEME.to_string()),
+            None => None,
+        };
+        let compatible_config = OpenAICompatibleConfig::new(base_url)
+            .with_path_prefix(config.setting_str("path_prefix").unwrap_or_default())
+            .with_auth_header(auth_header, auth_scheme);
+        let requires_key = config.settings.as_ref()
+            .and_then(|settings| settings.get("requires_key"))
+            .and_then(|value| value.as_bool())
+            .unwrap_or(true);
+        let models: Vec<String> = config.settings.as_ref()
+            .and_then(|settings| settings.get("models"))
+            .and_then(|models| models.as_array())
+            .map(|models| models.iter().filter_map(|m| Some(m.as_str()?.to_string())).collect())
+            .unwrap_or_default();
+
+        let headers = config.extra_headers();
+        let tls = config.tls_config()?;
+        let clients = (0..DEFAULT_OPENAI_COMPATIBLE_POOL_SIZE)
+            .map(|_| {
+                DynamicOpenAICompatibleClient::new(config.provider.as_str().to_string(), compatible_config.clone())
+                    .with_requires_key(requires_key)
+                    .with_headers(headers.clone())
+                    .with_tls(tls.clone())
+            })
+            .collect();
+        let adapter = OpenAICompatibleAdapter::new(config.provider.clone(), Arc::new(ClientPool::new(clients)), models)
+            .with_requires_key(requires_key);
+        Ok(Box::new(adapter))
+    }
+}
+
+/// 模拟供应商工厂，不访问上游，用于客户端测试错误处理；
+/// providers.config 示例：`{"partial_chunks": 3}`
+pub struct MockFactory;
+
+impl ProviderFactory for MockFactory {
+    fn provider_type(&self) -> &str {
+        "mock"
+    }
+
+    fn create_adapter(&self, config: &ProviderConfig) -> Result<Box<dyn LLMClientAdapter>> {
+        let partial_chunks = config.settings.as_ref()
+            .and_then(|settings| settings.get("partial_chunks"))
+            .and_then(|value| value.as_u64())
+            .map_or(DEFAULT_PARTIAL_CHUNKS, |value| value as usize);
+        Ok(Box::new(MockAdapter::new(config.provider.clone()).with_partial_chunks(partial_chunks)))
+    }
+}
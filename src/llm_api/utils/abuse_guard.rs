@@ -0,0 +1,413 @@
+//! # 终端用户滥用防护
+//!
+//! 基于请求中的 `user` 元数据字段，按租户 + 终端用户维度进行限流（经 HTTP 接口的请求以所属项目作为租户），
+//! 并统计 4xx 错误、内容违规等异常行为，用于标记可疑用户和生成违规排行。
+//! 被标记的用户在封禁时长内的请求直接被拒绝；长时间没有请求的用户状态会被清理
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use serde::Serialize;
+use tokio::sync::RwLock;
+use lazy_static::lazy_static;
+use tracing::{debug, warn};
+
+/// 未指定租户时使用的默认租户标识
+pub const DEFAULT_TENANT: &str = "default";
+
+/// 滥用防护配置
+#[derive(Debug, Clone)]
+pub struct AbuseGuardConfig {
+    /// 每个终端用户每个窗口内允许的最大请求数
+    pub max_requests_per_window: usize,
+    /// 统计窗口大小
+    pub window: Duration,
+    /// 窗口内 4xx 错误达到该次数时标记为异常用户
+    pub client_error_threshold: usize,
+    /// 窗口内内容违规达到该次数时标记为异常用户
+    pub violation_threshold: usize,
+    /// 被标记的用户在该时长内的请求直接被拒绝，到期后自动解除标记
+    pub block_duration: Duration,
+    /// 超过该时长没有请求且未被封禁的用户状态被清理
+    pub idle_ttl: Duration,
+}
+
+impl Default for AbuseGuardConfig {
+    fn default() -> Self {
+        Self {
+            max_requests_per_window: 60,
+            window: Duration::from_secs(60),
+            client_error_threshold: 20,
+            violation_threshold: 3,
+            block_duration: Duration::from_secs(15 * 60),
+            idle_ttl: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+/// 单次请求的结果分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserOutcome {
+    /// 正常请求（成功，或由上游原因导致的失败）
+    Normal,
+    /// 客户端错误（参数错误、模型不存在、被限流等 4xx 类错误）
+    ClientError,
+    /// 内容违规（例如 finish_reason 为 content_filter）
+    ContentViolation,
+}
+
+/// 单个终端用户的滑动窗口状态
+#[derive(Debug)]
+struct UserWindow {
+    requests: VecDeque<Instant>,
+    client_errors: VecDeque<Instant>,
+    violations: VecDeque<Instant>,
+    total_requests: u64,
+    total_client_errors: u64,
+    total_violations: u64,
+    throttled_count: u64,
+    flagged: bool,
+    /// 被标记的时间，用于计算封禁到期
+    flagged_at: Option<Instant>,
+    /// 最近一次请求或请求结果的时间，用于清理空闲用户
+    last_seen: Instant,
+}
+
+impl UserWindow {
+    fn new(now: Instant) -> Self {
+        Self {
+            requests: VecDeque::new(),
+            client_errors: VecDeque::new(),
+            violations: VecDeque::new(),
+            total_requests: 0,
+            total_client_errors: 0,
+            total_violations: 0,
+            throttled_count: 0,
+            flagged: false,
+            flagged_at: None,
+            last_seen: now,
+        }
+    }
+
+    // 是否仍在封禁期内
+    fn is_blocked(&self, now: Instant, block_duration: Duration) -> bool {
+        self.flagged_at.is_some_and(|flagged_at| now.duration_since(flagged_at) < block_duration)
+    }
+
+    fn clear_flag(&mut self) {
+        self.flagged = false;
+        self.flagged_at = None;
+        self.client_errors.clear();
+        self.violations.clear();
+    }
+
+    fn prune(&mut self, now: Instant, window: Duration) {
+        for queue in [&mut self.requests, &mut self.client_errors, &mut self.violations] {
+            while let Some(front) = queue.front() {
+                if now.duration_since(*front) > window {
+                    queue.pop_front();
+                } else {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// 违规用户报告条目
+#[derive(Debug, Clone, Serialize)]
+pub struct OffenderReport {
+    pub tenant_id: String,
+    pub user: String,
+    pub total_requests: u64,
+    pub total_client_errors: u64,
+    pub total_violations: u64,
+    pub throttled_count: u64,
+    pub flagged: bool,
+}
+
+impl OffenderReport {
+    /// 违规评分，用于排序（内容违规权重更高）
+    pub fn score(&self) -> u64 {
+        self.total_violations * 10 + self.total_client_errors + self.throttled_count
+    }
+}
+
+/// 终端用户滥用防护器
+pub struct AbuseGuard {
+    config: RwLock<AbuseGuardConfig>,
+    users: RwLock<HashMap<(String, String), UserWindow>>,
+    /// 上一次清理空闲用户的时间
+    last_eviction: RwLock<Instant>,
+}
+
+impl AbuseGuard {
+    pub fn new(config: AbuseGuardConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            users: RwLock::new(HashMap::new()),
+            last_eviction: RwLock::new(Instant::now()),
+        }
+    }
+
+    /// 更新防护配置
+    pub async fn set_config(&self, config: AbuseGuardConfig) {
+        *self.config.write().await = config;
+    }
+
+    /// 检查终端用户是否超出限流或处于封禁期，未超出时记录本次请求
+    ///
+    /// # Returns
+    /// * `true` - 允许请求
+    /// * `false` - 已超出限流或已被封禁
+    pub async fn check_and_record_request(&self, tenant_id: &str, user: &str) -> bool {
+        self.check_and_record_request_at(tenant_id, user, Instant::now()).await
+    }
+
+    async fn check_and_record_request_at(&self, tenant_id: &str, user: &str, now: Instant) -> bool {
+        let config = self.config.read().await.clone();
+        self.maybe_evict_idle(&config, now).await;
+        let mut users = self.users.write().await;
+        let entry = users
+            .entry((tenant_id.to_string(), user.to_string()))
+            .or_insert_with(|| UserWindow::new(now));
+        entry.prune(now, config.window);
+        entry.last_seen = now;
+
+        if entry.flagged_at.is_some() {
+            if entry.is_blocked(now, config.block_duration) {
+                entry.throttled_count += 1;
+                warn!(tenant_id = %tenant_id, user = %user, "Flagged end user blocked");
+                return false;
+            }
+            entry.clear_flag();
+        }
+
+        if entry.requests.len() >= config.max_requests_per_window {
+            entry.throttled_count += 1;
+            warn!(
+                tenant_id = %tenant_id,
+                user = %user,
+                window_requests = entry.requests.len(),
+                "End user throttled"
+            );
+            return false;
+        }
+
+        entry.requests.push_back(now);
+        entry.total_requests += 1;
+        true
+    }
+
+    /// 记录请求结果，达到阈值时标记用户为异常并开始封禁
+    pub async fn record_outcome(&self, tenant_id: &str, user: &str, outcome: UserOutcome) {
+        self.record_outcome_at(tenant_id, user, outcome, Instant::now()).await
+    }
+
+    async fn record_outcome_at(&self, tenant_id: &str, user: &str, outcome: UserOutcome, now: Instant) {
+        let config = self.config.read().await.clone();
+        let mut users = self.users.write().await;
+        let entry = users
+            .entry((tenant_id.to_string(), user.to_string()))
+            .or_insert_with(|| UserWindow::new(now));
+        entry.prune(now, config.window);
+        entry.last_seen = now;
+
+        match outcome {
+            UserOutcome::Normal => return,
+            UserOutcome::ClientError => {
+                entry.client_errors.push_back(now);
+                entry.total_client_errors += 1;
+            }
+            UserOutcome::ContentViolation => {
+                entry.violations.push_back(now);
+                entry.total_violations += 1;
+            }
+        }
+
+        if !entry.flagged
+            && (entry.client_errors.len() >= config.client_error_threshold
+                || entry.violations.len() >= config.violation_threshold)
+        {
+            entry.flagged = true;
+            entry.flagged_at = Some(now);
+            warn!(
+                tenant_id = %tenant_id,
+                user = %user,
+                client_errors = entry.client_errors.len(),
+                violations = entry.violations.len(),
+                block_secs = config.block_duration.as_secs(),
+                "End user flagged as anomalous"
+            );
+        }
+    }
+
+    /// 清理超过 `idle_ttl` 没有请求且未被封禁的用户，返回清理的数量
+    pub async fn evict_idle(&self) -> usize {
+        let config = self.config.read().await.clone();
+        self.evict_idle_at(&config, Instant::now()).await
+    }
+
+    async fn evict_idle_at(&self, config: &AbuseGuardConfig, now: Instant) -> usize {
+        let mut users = self.users.write().await;
+        let before = users.len();
+        users.retain(|_, window| {
+            now.duration_since(window.last_seen) < config.idle_ttl || window.is_blocked(now, config.block_duration)
+        });
+        before - users.len()
+    }
+
+    // 距上次清理超过一个统计窗口时清理空闲用户，避免不同 user 值不断累积
+    async fn maybe_evict_idle(&self, config: &AbuseGuardConfig, now: Instant) {
+        {
+            let mut last_eviction = self.last_eviction.write().await;
+            if now.saturating_duration_since(*last_eviction) < config.window {
+                return;
+            }
+            *last_eviction = now;
+        }
+        let evicted = self.evict_idle_at(config, now).await;
+        if evicted > 0 {
+            debug!(evicted, "Evicted idle end users");
+        }
+    }
+
+    /// 查询用户是否已被标记为异常
+    pub async fn is_flagged(&self, tenant_id: &str, user: &str) -> bool {
+        let users = self.users.read().await;
+        users
+            .get(&(tenant_id.to_string(), user.to_string()))
+            .map(|w| w.flagged)
+            .unwrap_or(false)
+    }
+
+    /// 解除用户的异常标记和封禁
+    pub async fn clear_flag(&self, tenant_id: &str, user: &str) -> bool {
+        let mut users = self.users.write().await;
+        match users.get_mut(&(tenant_id.to_string(), user.to_string())) {
+            Some(window) => {
+                window.clear_flag();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 获取违规排行（可按租户过滤），按违规评分降序
+    pub async fn top_offenders(&self, tenant_id: Option<&str>, limit: usize) -> Vec<OffenderReport> {
+        let users = self.users.read().await;
+        let mut reports: Vec<OffenderReport> = users
+            .iter()
+            .filter(|((tenant, _), _)| tenant_id.is_none_or(|t| t == tenant))
+            .map(|((tenant, user), window)| OffenderReport {
+                tenant_id: tenant.clone(),
+                user: user.clone(),
+                total_requests: window.total_requests,
+                total_client_errors: window.total_client_errors,
+                total_violations: window.total_violations,
+                throttled_count: window.throttled_count,
+                flagged: window.flagged,
+            })
+            .filter(|report| report.score() > 0)
+            .collect();
+
+        reports.sort_by_key(|report| std::cmp::Reverse(report.score()));
+        reports.truncate(limit);
+        reports
+    }
+}
+
+lazy_static! {
+    /// 全局终端用户滥用防护器
+    static ref GLOBAL_ABUSE_GUARD: AbuseGuard = AbuseGuard::new(AbuseGuardConfig::default());
+}
+
+/// 获取全局滥用防护器
+pub fn get_abuse_guard() -> &'static AbuseGuard {
+    &GLOBAL_ABUSE_GUARD
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> AbuseGuardConfig {
+        AbuseGuardConfig {
+            max_requests_per_window: 2,
+            window: Duration::from_secs(60),
+            client_error_threshold: 2,
+            violation_threshold: 1,
+            block_duration: Duration::from_secs(300),
+            idle_ttl: Duration::from_secs(600),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_user_throttled_after_limit() {
+        let guard = AbuseGuard::new(test_config());
+
+        assert!(guard.check_and_record_request("t1", "alice").await);
+        assert!(guard.check_and_record_request("t1", "alice").await);
+        assert!(!guard.check_and_record_request("t1", "alice").await);
+
+        // 不同租户下的同名用户互不影响
+        assert!(guard.check_and_record_request("t2", "alice").await);
+    }
+
+    #[tokio::test]
+    async fn test_user_flagged_and_reported() {
+        let guard = AbuseGuard::new(test_config());
+
+        guard.record_outcome("t1", "bob", UserOutcome::ClientError).await;
+        assert!(!guard.is_flagged("t1", "bob").await);
+        guard.record_outcome("t1", "bob", UserOutcome::ClientError).await;
+        assert!(guard.is_flagged("t1", "bob").await);
+
+        guard.record_outcome("t1", "eve", UserOutcome::ContentViolation).await;
+        guard.record_outcome("t2", "carol", UserOutcome::Normal).await;
+
+        let offenders = guard.top_offenders(Some("t1"), 10).await;
+        assert_eq!(offenders.len(), 2);
+        assert_eq!(offenders[0].user, "eve");
+        assert!(offenders.iter().all(|o| o.tenant_id == "t1"));
+
+        assert!(guard.clear_flag("t1", "bob").await);
+        assert!(!guard.is_flagged("t1", "bob").await);
+    }
+
+    #[tokio::test]
+    async fn test_flagged_user_blocked_until_expiry() {
+        let guard = AbuseGuard::new(test_config());
+        let now = Instant::now();
+
+        guard.record_outcome_at("t1", "mallory", UserOutcome::ContentViolation, now).await;
+        assert!(guard.is_flagged("t1", "mallory").await);
+        assert!(!guard.check_and_record_request_at("t1", "mallory", now + Duration::from_secs(1)).await);
+
+        // 封禁到期后自动解除标记
+        assert!(guard.check_and_record_request_at("t1", "mallory", now + Duration::from_secs(301)).await);
+        assert!(!guard.is_flagged("t1", "mallory").await);
+
+        // 管理员解除标记后立即放行
+        guard.record_outcome_at("t1", "trent", UserOutcome::ContentViolation, now).await;
+        assert!(guard.clear_flag("t1", "trent").await);
+        assert!(guard.check_and_record_request_at("t1", "trent", now).await);
+    }
+
+    #[tokio::test]
+    async fn test_idle_users_evicted() {
+        let config = AbuseGuardConfig { block_duration: Duration::from_secs(3600), ..test_config() };
+        let guard = AbuseGuard::new(config.clone());
+        let now = Instant::now();
+
+        assert!(guard.check_and_record_request_at("t1", "idle", now).await);
+        guard.record_outcome_at("t1", "blocked", UserOutcome::ContentViolation, now).await;
+        assert!(guard.check_and_record_request_at("t1", "active", now + Duration::from_secs(500)).await);
+
+        // 空闲超过 idle_ttl 的用户被清理，仍在封禁期或最近活跃的用户保留
+        assert_eq!(guard.evict_idle_at(&config, now + Duration::from_secs(700)).await, 1);
+        assert!(guard.is_flagged("t1", "blocked").await);
+        assert_eq!(guard.users.read().await.len(), 2);
+
+        assert_eq!(guard.evict_idle_at(&config, now + Duration::from_secs(3601)).await, 2);
+    }
+}
@@ -0,0 +1,176 @@
+//! # 全局准入控制
+//!
+//! 限制网关同时处理的对话请求数，突发流量时超出上限的请求先进入有界队列排队，
+//! 等待不超过 `max_wait`，按请求优先级放行；队列已满或排队超时的请求被拒绝，由 Web 层返回 503 和 `Retry-After`。
+//!
+//! 指标：
+//! - `llm_gateway_admission_queue_depth`：当前排队的请求数
+//! - `llm_gateway_admission_wait_ms_total{priority}` / `llm_gateway_admission_admitted_total{priority}`：排队总耗时和准入次数，相除得到平均排队时间
+//! - `llm_gateway_admission_rejections_total{reason}`：按原因（`queue_full` / `wait_timeout`）统计的拒绝次数
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use tracing::warn;
+
+use crate::api_types::v1::RequestPriority;
+use crate::config::AdmissionConfig;
+use crate::llm_api::utils::priority_semaphore::{PriorityPermit, PrioritySemaphore};
+use crate::metrics::metrics;
+
+/// 准入许可，释放（drop）时归还并发槽位；未启用准入控制时不占用槽位
+pub struct AdmissionPermit {
+    _permit: Option<PriorityPermit>,
+}
+
+/// 请求被拒绝的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdmissionRejection {
+    /// 排队的请求数已达上限
+    QueueFull,
+    /// 排队超过最长等待时间
+    WaitTimeout,
+}
+
+impl AdmissionRejection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::QueueFull => "queue_full",
+            Self::WaitTimeout => "wait_timeout",
+        }
+    }
+}
+
+/// 有界的准入队列
+pub struct AdmissionQueue {
+    semaphore: PrioritySemaphore,
+    max_concurrent_requests: usize,
+    max_queue_depth: usize,
+    max_wait: Duration,
+    queued: AtomicUsize,
+}
+
+impl AdmissionQueue {
+    /// `max_concurrent_requests` 为 0 时不限制并发，所有请求直接准入
+    pub fn new(max_concurrent_requests: usize, max_queue_depth: usize, max_wait: Duration) -> Self {
+        Self {
+            semaphore: PrioritySemaphore::new(max_concurrent_requests),
+            max_concurrent_requests,
+            max_queue_depth,
+            max_wait,
+            queued: AtomicUsize::new(0),
+        }
+    }
+
+    /// 按启动配置创建
+    pub fn from_config(config: &AdmissionConfig) -> Self {
+        Self::new(config.max_concurrent_requests, config.max_queue_depth, Duration::from_millis(config.max_wait_ms))
+    }
+
+    /// 是否启用了准入控制
+    pub fn is_enabled(&self) -> bool {
+        self.max_concurrent_requests > 0
+    }
+
+    /// 正在处理的请求数
+    pub fn in_flight(&self) -> usize {
+        self.semaphore.in_use()
+    }
+
+    /// 正在排队的请求数
+    pub fn queue_depth(&self) -> usize {
+        self.queued.load(Ordering::SeqCst)
+    }
+
+    /// 建议客户端重试前等待的时间（至少 1 秒）
+    pub fn retry_after(&self) -> Duration {
+        Duration::from_secs(self.max_wait.as_secs_f64().ceil().max(1.0) as u64)
+    }
+
+    /// 获取准入许可：有空闲槽位时立即返回，否则排队最多 `max_wait`，高优先级的请求先放行
+    pub async fn admit(&self, priority: RequestPriority) -> Result<AdmissionPermit, AdmissionRejection> {
+        if !self.is_enabled() {
+            return Ok(AdmissionPermit { _permit: None });
+        }
+        if let Some(permit) = self.semaphore.acquire(priority, Duration::ZERO).await {
+            self.record_admitted(priority, Duration::ZERO);
+            return Ok(AdmissionPermit { _permit: Some(permit) });
+        }
+
+        // 排队名额不足时直接拒绝，避免队列无限增长
+        let reserved = self.queued.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |queued| {
+            (queued < self.max_queue_depth).then_some(queued + 1)
+        });
+        if reserved.is_err() {
+            return Err(self.reject(AdmissionRejection::QueueFull));
+        }
+        self.record_queue_depth();
+
+        let started = Instant::now();
+        let permit = self.semaphore.acquire(priority, self.max_wait).await;
+        self.queued.fetch_sub(1, Ordering::SeqCst);
+        self.record_queue_depth();
+
+        match permit {
+            Some(permit) => {
+                self.record_admitted(priority, started.elapsed());
+                Ok(AdmissionPermit { _permit: Some(permit) })
+            }
+            None => Err(self.reject(AdmissionRejection::WaitTimeout)),
+        }
+    }
+
+    fn record_admitted(&self, priority: RequestPriority, waited: Duration) {
+        let labels = [("priority", priority.as_str())];
+        metrics().incr_counter("llm_gateway_admission_admitted_total", &labels);
+        metrics().add_counter("llm_gateway_admission_wait_ms_total", &labels, waited.as_millis() as u64);
+    }
+
+    fn record_queue_depth(&self) {
+        metrics().set_gauge("llm_gateway_admission_queue_depth", &[], self.queue_depth() as f64);
+    }
+
+    fn reject(&self, reason: AdmissionRejection) -> AdmissionRejection {
+        metrics().incr_counter("llm_gateway_admission_rejections_total", &[("reason", reason.as_str())]);
+        warn!(
+            reason = reason.as_str(),
+            in_flight = self.in_flight(),
+            queue_depth = self.queue_depth(),
+            "Request rejected by admission control"
+        );
+        reason
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_admit_queues_then_rejects() {
+        let queue = AdmissionQueue::new(1, 1, Duration::from_millis(20));
+        let permit = queue.admit(RequestPriority::Normal).await.unwrap();
+        assert_eq!(queue.in_flight(), 1);
+
+        // 第二个请求排队等待，第三个请求因队列已满被拒绝
+        let (queued, rejected) = tokio::join!(queue.admit(RequestPriority::Normal), async {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            queue.admit(RequestPriority::High).await
+        });
+        assert_eq!(queued.err(), Some(AdmissionRejection::WaitTimeout));
+        assert_eq!(rejected.err(), Some(AdmissionRejection::QueueFull));
+        assert_eq!(queue.queue_depth(), 0);
+
+        drop(permit);
+        assert!(queue.admit(RequestPriority::Batch).await.is_ok());
+        assert_eq!(queue.retry_after(), Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_disabled_queue_admits_everything() {
+        let queue = AdmissionQueue::new(0, 0, Duration::ZERO);
+        let permits: Vec<_> = futures::future::join_all((0..3).map(|_| queue.admit(RequestPriority::Normal))).await;
+        assert!(permits.iter().all(Result::is_ok));
+        assert_eq!(queue.in_flight(), 0);
+    }
+}
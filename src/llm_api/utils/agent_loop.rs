@@ -0,0 +1,550 @@
+//! # Agent 工具调用循环
+//!
+//! 网关代为执行模型返回的工具调用：调用模型 → 执行 tool_calls → 把工具结果作为 tool 消息追加到对话 → 再次调用模型，
+//! 直到模型给出不含工具调用的最终回答或达到最大轮数。工具分两类：
+//! - Webhook 工具：网关把参数 POST 到调用方提供的 URL，响应体作为工具结果。只允许访问运维配置的
+//!   [`WebhookPolicy`] 白名单中的主机，解析到内网、回环或链路本地地址时拒绝，不跟随重定向，响应体大小有上限
+//! - 内置工具：进程内注册的 [`BuiltinTool`]，默认提供 `current_time`
+//!
+//! 工具执行失败或超时时把错误信息作为工具结果交给模型处理，不中断循环。各轮模型调用共用同一个网关请求 ID，
+//! 调用记录按 request_id 关联；每次工具执行写入 tool_call_steps，关联到发起该调用的模型请求的调用记录
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use serde_json::Value;
+use tracing::{info, warn};
+
+use crate::api_types::v1::{DispatchRequest, DispatchResponse, Message, TokenUsage, Tool, ToolCall, ToolFunction};
+use crate::dao::tool_call_audit::{create_tool_call_step, ToolCallStep};
+use crate::dao::SQLITE_POOL;
+use crate::llm_api::dispatcher::{LLMDispatcher, LLMError};
+use crate::llm_api::utils::client::{CallMetadata, CALL_METADATA};
+use crate::llm_api::utils::redaction::redact_opt;
+use crate::metrics::metrics;
+
+/// 默认最多调用模型的轮数
+pub const DEFAULT_MAX_ITERATIONS: u32 = 5;
+/// 调用方可以设置的最大轮数
+pub const MAX_ITERATIONS_LIMIT: u32 = 20;
+/// 单次工具执行的默认超时
+pub const DEFAULT_TOOL_TIMEOUT: Duration = Duration::from_secs(10);
+/// 调用方可以设置的最长工具超时
+pub const MAX_TOOL_TIMEOUT: Duration = Duration::from_secs(60);
+/// 工具结果写入对话的最大字符数，超出部分截断
+pub const MAX_TOOL_OUTPUT_CHARS: usize = 16_000;
+/// Webhook 响应体的最大字节数，超出时记为工具错误
+pub const MAX_WEBHOOK_RESPONSE_BYTES: usize = 256 * 1024;
+
+/// 进程内执行的工具
+#[async_trait]
+pub trait BuiltinTool: Send + Sync {
+    /// 工具定义（名称、描述和参数 JSON Schema），下发给模型
+    fn definition(&self) -> ToolFunction;
+
+    /// 执行工具，返回交给模型的结果文本
+    async fn call(&self, arguments: &HashMap<String, Value>) -> Result<String, String>;
+}
+
+/// 内置工具：返回当前 UTC 时间
+pub struct CurrentTimeTool;
+
+#[async_trait]
+impl BuiltinTool for CurrentTimeTool {
+    fn definition(&self) -> ToolFunction {
+        ToolFunction {
+            name: "current_time".to_string(),
+            description: "Get the current date and time in UTC (RFC 3339)".to_string(),
+            parameters: serde_json::json!({ "type": "object", "properties": {} }),
+        }
+    }
+
+    async fn call(&self, _arguments: &HashMap<String, Value>) -> Result<String, String> {
+        Ok(chrono::Utc::now().to_rfc3339())
+    }
+}
+
+lazy_static! {
+    // 工具名称 -> 内置工具
+    static ref BUILTIN_TOOLS: RwLock<HashMap<String, Arc<dyn BuiltinTool>>> = {
+        let current_time: Arc<dyn BuiltinTool> = Arc::new(CurrentTimeTool);
+        RwLock::new(HashMap::from([(current_time.definition().name, current_time)]))
+    };
+
+    // Webhook 出站策略，启动时按配置设置，默认不允许任何主机
+    static ref WEBHOOK_POLICY: RwLock<WebhookPolicy> = RwLock::new(WebhookPolicy::default());
+
+    // Webhook 工具共用的 HTTP 客户端：按出站策略解析地址，不使用代理、不跟随重定向，超时按每次调用设置
+    static ref WEBHOOK_CLIENT: reqwest::Client = reqwest::Client::builder()
+        .no_proxy()
+        .redirect(reqwest::redirect::Policy::none())
+        .dns_resolver(Arc::new(WebhookResolver))
+        .build()
+        .expect("Failed to build webhook client");
+}
+
+/// Webhook 工具的出站策略
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WebhookPolicy {
+    /// 允许访问的主机名，`*.example.com` 匹配所有子域名；为空时不接受 Webhook 工具
+    pub allowed_hosts: Vec<String>,
+    /// 是否允许解析到内网、回环或链路本地地址
+    pub allow_private_addresses: bool,
+}
+
+impl WebhookPolicy {
+    /// 主机是否在白名单中（不区分大小写）
+    pub fn allows_host(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        self.allowed_hosts.iter().any(|allowed| {
+            let allowed = allowed.trim().to_ascii_lowercase();
+            match allowed.strip_prefix("*.") {
+                Some(domain) => host.strip_suffix(domain).is_some_and(|prefix| prefix.ends_with('.')),
+                None => !allowed.is_empty() && host == allowed,
+            }
+        })
+    }
+
+    /// 校验 URL 的协议和主机；IP 地址形式的主机不经过解析，在这里检查是否为内网地址
+    pub fn check_url(&self, url: &reqwest::Url) -> Result<(), String> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!("webhook_url `{}` must use http or https", url));
+        }
+        let host = url.host_str().ok_or_else(|| format!("webhook_url `{}` has no host", url))?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if !self.allows_host(host) {
+            return Err(format!("webhook host `{}` is not in the allowed list", host));
+        }
+        if !self.allow_private_addresses
+            && let Ok(ip) = host.parse::<IpAddr>()
+            && is_private_address(ip)
+        {
+            return Err(format!("webhook host `{}` is a private address", host));
+        }
+        Ok(())
+    }
+
+    /// 解析主机地址，任一地址不允许访问时返回错误；连接只使用这里返回的地址
+    pub async fn resolve(&self, host: &str, port: u16) -> Result<Vec<SocketAddr>, String> {
+        if !self.allows_host(host) {
+            return Err(format!("webhook host `{}` is not in the allowed list", host));
+        }
+        let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port)).await
+            .map_err(|e| format!("failed to resolve webhook host `{}`: {}", host, e))?
+            .collect();
+        if addrs.is_empty() {
+            return Err(format!("webhook host `{}` did not resolve", host));
+        }
+        if !self.allow_private_addresses
+            && let Some(addr) = addrs.iter().find(|addr| is_private_address(addr.ip()))
+        {
+            return Err(format!("webhook host `{}` resolves to private address {}", host, addr.ip()));
+        }
+        Ok(addrs)
+    }
+}
+
+// Webhook 客户端的 DNS 解析，按当前出站策略检查解析结果
+struct WebhookResolver;
+
+impl reqwest::dns::Resolve for WebhookResolver {
+    fn resolve(&self, name: hyper_014::client::connect::dns::Name) -> reqwest::dns::Resolving {
+        let policy = webhook_policy();
+        Box::pin(async move {
+            let addrs = policy.resolve(name.as_str(), 0).await?;
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+/// 设置 Webhook 出站策略
+pub fn set_webhook_policy(policy: WebhookPolicy) {
+    *WEBHOOK_POLICY.write().unwrap() = policy;
+}
+
+/// 当前的 Webhook 出站策略
+pub fn webhook_policy() -> WebhookPolicy {
+    WEBHOOK_POLICY.read().unwrap().clone()
+}
+
+// 内网、回环、链路本地、未指定等不应由调用方指定的地址
+fn is_private_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || a == 0
+                || (a == 100 && (64..128).contains(&b)) // 100.64.0.0/10 运营商级 NAT
+        }
+        IpAddr::V6(ip) => {
+            let first = ip.segments()[0];
+            ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || (first & 0xfe00) == 0xfc00 // fc00::/7 唯一本地地址
+                || (first & 0xffc0) == 0xfe80 // fe80::/10 链路本地地址
+                || ip.to_ipv4_mapped().is_some_and(|ip| is_private_address(IpAddr::V4(ip)))
+        }
+    }
+}
+
+/// 注册内置工具，同名工具会被替换
+pub fn register_builtin_tool(tool: Arc<dyn BuiltinTool>) {
+    let name = tool.definition().name;
+    info!(tool = %name, "Registered builtin agent tool");
+    BUILTIN_TOOLS.write().unwrap().insert(name, tool);
+}
+
+/// 已注册的内置工具名称（按名称排序）
+pub fn builtin_tool_names() -> Vec<String> {
+    let mut names: Vec<String> = BUILTIN_TOOLS.read().unwrap().keys().cloned().collect();
+    names.sort();
+    names
+}
+
+/// 工具的执行方式
+#[derive(Clone)]
+pub enum AgentToolExecutor {
+    /// POST 到调用方提供的 URL
+    Webhook(reqwest::Url),
+    /// 进程内注册的工具
+    Builtin(Arc<dyn BuiltinTool>),
+}
+
+/// Agent 循环中可供模型调用的工具
+#[derive(Clone)]
+pub struct AgentTool {
+    pub definition: ToolFunction,
+    pub executor: AgentToolExecutor,
+}
+
+impl AgentTool {
+    /// Webhook 工具，只接受当前 [`WebhookPolicy`] 白名单中主机的 http / https URL
+    pub fn webhook(definition: ToolFunction, url: &str) -> Result<Self, String> {
+        let parsed = reqwest::Url::parse(url).map_err(|e| format!("invalid webhook_url `{}`: {}", url, e))?;
+        webhook_policy().check_url(&parsed)?;
+        Ok(Self { definition, executor: AgentToolExecutor::Webhook(parsed) })
+    }
+
+    /// 按名称查找内置工具，未注册时返回 None
+    pub fn builtin(name: &str) -> Option<Self> {
+        let tool = BUILTIN_TOOLS.read().unwrap().get(name).cloned()?;
+        Some(Self { definition: tool.definition(), executor: AgentToolExecutor::Builtin(tool) })
+    }
+
+    fn kind(&self) -> &'static str {
+        match self.executor {
+            AgentToolExecutor::Webhook(_) => "webhook",
+            AgentToolExecutor::Builtin(_) => "builtin",
+        }
+    }
+}
+
+/// 一次工具执行
+#[derive(Debug, Clone)]
+pub struct AgentStep {
+    /// 发起调用的模型轮次（从 1 开始）
+    pub iteration: u32,
+    pub tool_call_id: String,
+    pub name: String,
+    pub arguments: HashMap<String, Value>,
+    pub output: Option<String>,
+    pub error: Option<String>,
+    pub latency_ms: u64,
+    /// 发起调用的模型请求的调用记录 ID，未写入调用记录时为 None
+    pub call_log_id: Option<String>,
+}
+
+/// Agent 循环的结果
+#[derive(Debug, Clone)]
+pub struct AgentOutcome {
+    /// 最后一轮的模型响应
+    pub response: DispatchResponse,
+    /// 调用模型的轮数
+    pub iterations: u32,
+    /// 按执行顺序的工具调用
+    pub steps: Vec<AgentStep>,
+    /// 各轮用量之和，所有轮次都没有返回用量时为 None
+    pub usage: Option<TokenUsage>,
+    /// 达到最大轮数时模型仍在要求调用工具
+    pub max_iterations_reached: bool,
+}
+
+/// 服务端执行工具调用的 Agent 循环
+pub struct AgentLoop {
+    tools: Vec<AgentTool>,
+    max_iterations: u32,
+    tool_timeout: Duration,
+}
+
+impl AgentLoop {
+    pub fn new(tools: Vec<AgentTool>) -> Self {
+        Self {
+            tools,
+            max_iterations: DEFAULT_MAX_ITERATIONS,
+            tool_timeout: DEFAULT_TOOL_TIMEOUT,
+        }
+    }
+
+    /// 设置最多调用模型的轮数（至少 1 轮）
+    pub fn with_max_iterations(mut self, max_iterations: u32) -> Self {
+        self.max_iterations = max_iterations.max(1);
+        self
+    }
+
+    /// 设置单次工具执行的超时
+    pub fn with_tool_timeout(mut self, tool_timeout: Duration) -> Self {
+        self.tool_timeout = tool_timeout;
+        self
+    }
+
+    /// 执行循环：请求的 tools 替换为循环的工具，模型调用出错时直接返回错误
+    pub async fn run(&self, dispatcher: &LLMDispatcher, mut request: DispatchRequest) -> Result<AgentOutcome, LLMError> {
+        let metadata = CallMetadata::current();
+        let request_id = request.request_id.clone()
+            .or_else(|| metadata.request_id.clone())
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        request.request_id = Some(request_id.clone());
+        request.tools = Some(self.tools.iter().map(|tool| Tool {
+            tool_type: "function".to_string(),
+            function: tool.definition.clone(),
+        }).collect());
+
+        // 收集各轮写入的调用记录，工具调用关联到发起它的那次模型请求
+        let call_logs = Arc::new(Mutex::new(Vec::new()));
+        let metadata = metadata.with_request_id(Some(request_id.clone())).with_call_log_sink(call_logs.clone());
+
+        let mut steps = Vec::new();
+        let mut usage: Option<TokenUsage> = None;
+        let mut iteration = 0;
+        loop {
+            if metadata.cancellation.is_cancelled() {
+                return Err(LLMError::Cancelled);
+            }
+            iteration += 1;
+            let response = CALL_METADATA.scope(metadata.clone(), dispatcher.dispatch(request.clone())).await?;
+            let call_log_id = call_logs.lock().unwrap().last().cloned();
+            if let Some(turn) = &response.usage {
+                let total = usage.get_or_insert(TokenUsage { prompt_tokens: 0, completion_tokens: 0, total_tokens: 0 });
+                total.prompt_tokens += turn.prompt_tokens;
+                total.completion_tokens += turn.completion_tokens;
+                total.total_tokens += turn.total_tokens;
+            }
+
+            let tool_calls = response.tool_calls.clone().filter(|calls| !calls.is_empty());
+            let Some(mut tool_calls) = tool_calls else {
+                metrics().incr_counter("llm_gateway_agent_runs_total", &[("outcome", "completed")]);
+                return Ok(AgentOutcome { response, iterations: iteration, steps, usage, max_iterations_reached: false });
+            };
+            if iteration >= self.max_iterations {
+                warn!(request_id = %request_id, iterations = iteration, "Agent loop reached max iterations");
+                metrics().incr_counter("llm_gateway_agent_runs_total", &[("outcome", "max_iterations")]);
+                return Ok(AgentOutcome { response, iterations: iteration, steps, usage, max_iterations_reached: true });
+            }
+
+            // 上游没有返回调用 ID 时（Ollama）生成，工具结果消息据此对应
+            for call in tool_calls.iter_mut() {
+                call.id.get_or_insert_with(|| format!("call_{}", uuid::Uuid::new_v4().simple()));
+            }
+            let assistant = response.message.clone().unwrap_or_else(|| Message::assistant(response.content.clone()));
+            request.messages.push(assistant.with_tool_calls(tool_calls.clone()));
+
+            for call in tool_calls {
+                let step = self.execute(&request_id, iteration, call, call_log_id.clone()).await;
+                let content = match (&step.output, &step.error) {
+                    (_, Some(error)) => format!("error: {}", error),
+                    (Some(output), None) => output.clone(),
+                    (None, None) => String::new(),
+                };
+                request.messages.push(Message::tool(content, step.name.clone()).with_tool_call_id(step.tool_call_id.clone()));
+                record_step(&step, steps.len()).await;
+                steps.push(step);
+            }
+        }
+    }
+
+    // 执行一次工具调用，失败和超时记为错误
+    async fn execute(&self, request_id: &str, iteration: u32, call: ToolCall, call_log_id: Option<String>) -> AgentStep {
+        let name = call.function.name;
+        let arguments = call.function.arguments;
+        let tool_call_id = call.id.unwrap_or_default();
+        let tool = self.tools.iter().find(|tool| tool.definition.name == name);
+
+        let started = Instant::now();
+        let result = match tool {
+            None => Err(format!("unknown tool `{}`", name)),
+            Some(tool) => {
+                let call = async {
+                    match &tool.executor {
+                        AgentToolExecutor::Builtin(builtin) => builtin.call(&arguments).await,
+                        AgentToolExecutor::Webhook(url) => call_webhook(url, request_id, &tool_call_id, &name, &arguments).await,
+                    }
+                };
+                match tokio::time::timeout(self.tool_timeout, call).await {
+                    Ok(result) => result,
+                    Err(_) => Err(format!("tool timed out after {}ms", self.tool_timeout.as_millis())),
+                }
+            }
+        };
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        let kind = tool.map_or("unknown", AgentTool::kind);
+        let status = if result.is_ok() { "ok" } else { "error" };
+        metrics().incr_counter("llm_gateway_agent_tool_calls_total", &[("kind", kind), ("status", status)]);
+        if let Err(e) = &result {
+            warn!(request_id = %request_id, tool = %name, error = %e, "Agent tool call failed");
+        }
+
+        let (output, error) = match result {
+            Ok(output) => (Some(truncate_output(output)), None),
+            Err(e) => (None, Some(e)),
+        };
+        AgentStep { iteration, tool_call_id, name, arguments, output, error, latency_ms, call_log_id }
+    }
+}
+
+// POST 工具参数到 Webhook，2xx 响应体作为工具结果；每次调用按当前出站策略重新检查 URL
+async fn call_webhook(
+    url: &reqwest::Url,
+    request_id: &str,
+    tool_call_id: &str,
+    name: &str,
+    arguments: &HashMap<String, Value>,
+) -> Result<String, String> {
+    let body = serde_json::json!({
+        "request_id": request_id,
+        "tool_call_id": tool_call_id,
+        "name": name,
+        "arguments": arguments,
+    });
+    webhook_policy().check_url(url)?;
+    let mut response = WEBHOOK_CLIENT.post(url.clone()).json(&body).send().await
+        .map_err(|e| format!("webhook request failed: {}", e))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("webhook returned status {}", status));
+    }
+    if response.content_length().is_some_and(|length| length > MAX_WEBHOOK_RESPONSE_BYTES as u64) {
+        return Err(format!("webhook response exceeds {} bytes", MAX_WEBHOOK_RESPONSE_BYTES));
+    }
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| format!("failed to read webhook response: {}", e))? {
+        if bytes.len() + chunk.len() > MAX_WEBHOOK_RESPONSE_BYTES {
+            return Err(format!("webhook response exceeds {} bytes", MAX_WEBHOOK_RESPONSE_BYTES));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+// 截断过长的工具结果，避免撑满上下文
+fn truncate_output(output: String) -> String {
+    match output.char_indices().nth(MAX_TOOL_OUTPUT_CHARS) {
+        Some((index, _)) => format!("{}...[truncated]", &output[..index]),
+        None => output,
+    }
+}
+
+// 写入工具调用审计，模型请求没有写入调用记录时跳过
+async fn record_step(step: &AgentStep, step_index: usize) {
+    let (Some(pool), Some(call_log_id)) = (SQLITE_POOL.get(), &step.call_log_id) else {
+        return;
+    };
+    let record = ToolCallStep {
+        id: uuid::Uuid::new_v4().to_string(),
+        call_log_id: call_log_id.clone(),
+        step_index: step_index as i64,
+        tool_call_id: Some(step.tool_call_id.clone()),
+        tool_name: step.name.clone(),
+        // 工具输入输出可能包含敏感信息，脱敏后写入
+        arguments: redact_opt(serde_json::to_string(&step.arguments).ok()),
+        output: redact_opt(step.output.clone()),
+        error_message: redact_opt(step.error.clone()),
+        latency_ms: step.latency_ms as i64,
+        created_at: None,
+    };
+    if let Err(e) = create_tool_call_step(pool, &record).await {
+        warn!(call_log_id = %call_log_id, tool = %step.name, error = %e, "Failed to record tool call step");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_construction() {
+        let definition = ToolFunction { name: "lookup".to_string(), description: String::new(), parameters: Value::Null };
+        // 默认策略不允许任何主机
+        assert!(AgentTool::webhook(definition.clone(), "https://tools.example.com/lookup").is_err());
+        assert!(AgentTool::webhook(definition.clone(), "ftp://tools.example.com/lookup").is_err());
+        assert!(AgentTool::webhook(definition, "not a url").is_err());
+
+        let current_time = AgentTool::builtin("current_time").unwrap();
+        assert_eq!(current_time.kind(), "builtin");
+        assert!(AgentTool::builtin("no_such_tool").is_none());
+        assert!(builtin_tool_names().contains(&"current_time".to_string()));
+    }
+
+    #[test]
+    fn test_webhook_policy_hosts() {
+        let policy = WebhookPolicy {
+            allowed_hosts: vec!["tools.example.com".to_string(), "*.internal.example.org".to_string()],
+            allow_private_addresses: false,
+        };
+        let url = |url: &str| reqwest::Url::parse(url).unwrap();
+        assert!(policy.check_url(&url("https://tools.example.com/lookup")).is_ok());
+        assert!(policy.check_url(&url("https://TOOLS.example.com./lookup")).is_ok());
+        assert!(policy.check_url(&url("http://a.internal.example.org/x")).is_ok());
+        assert!(policy.check_url(&url("http://internal.example.org/x")).is_err());
+        assert!(policy.check_url(&url("http://evilinternal.example.org/x")).is_err());
+        assert!(policy.check_url(&url("https://tools.example.com.evil.com/x")).is_err());
+        assert!(policy.check_url(&url("http://169.254.169.254/latest/meta-data")).is_err());
+
+        let policy = WebhookPolicy { allowed_hosts: vec!["10.0.0.8".to_string()], allow_private_addresses: false };
+        assert!(policy.check_url(&url("http://10.0.0.8/x")).unwrap_err().contains("private address"));
+        let policy = WebhookPolicy { allow_private_addresses: true, ..policy };
+        assert!(policy.check_url(&url("http://10.0.0.8/x")).is_ok());
+        assert!(policy.check_url(&url("ftp://tools.example.com/x")).is_err());
+    }
+
+    #[test]
+    fn test_private_addresses() {
+        for ip in ["127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "100.64.0.1", "0.0.0.0",
+            "::1", "fd00::1", "fe80::1", "::ffff:127.0.0.1"] {
+            assert!(is_private_address(ip.parse().unwrap()), "{} should be private", ip);
+        }
+        for ip in ["8.8.8.8", "93.184.216.34", "2606:4700::1111"] {
+            assert!(!is_private_address(ip.parse().unwrap()), "{} should be public", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolve_rejects_private_addresses() {
+        let policy = WebhookPolicy { allowed_hosts: vec!["localhost".to_string()], allow_private_addresses: false };
+        let error = policy.resolve("localhost", 80).await.unwrap_err();
+        assert!(error.contains("private address"), "{}", error);
+        assert!(policy.resolve("example.com", 80).await.unwrap_err().contains("not in the allowed list"));
+
+        let policy = WebhookPolicy { allow_private_addresses: true, ..policy };
+        assert!(!policy.resolve("localhost", 80).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_truncate_output() {
+        assert_eq!(truncate_output("short".to_string()), "short");
+        let long = "字".repeat(MAX_TOOL_OUTPUT_CHARS + 10);
+        let truncated = truncate_output(long);
+        assert!(truncated.ends_with("...[truncated]"));
+        assert_eq!(truncated.chars().count(), MAX_TOOL_OUTPUT_CHARS + "...[truncated]".len());
+    }
+}
@@ -0,0 +1,413 @@
+//! # 关键词黑名单
+//!
+//! 对提示词和模型响应进行关键词匹配，支持全局规则与租户覆盖规则，
+//! 命中后按规则动作处理：拦截（block）、打码（mask）或仅标记（flag）。
+//! 经 HTTP 接口的请求以 project_scope 解析出的项目 ID 作为租户
+
+use std::collections::HashMap;
+use regex::Regex;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tokio::sync::RwLock;
+use lazy_static::lazy_static;
+use tracing::{info, warn};
+
+use crate::dao::blocklist::{BlocklistEntry, list_active_blocklist_entries};
+use crate::metrics::metrics;
+
+/// 打码使用的替换字符
+const MASK_CHAR: char = '*';
+
+/// 命中规则后的处理动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlocklistAction {
+    Flag,
+    Mask,
+    Block,
+}
+
+impl BlocklistAction {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "block" => Some(Self::Block),
+            "mask" => Some(Self::Mask),
+            "flag" => Some(Self::Flag),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Block => "block",
+            Self::Mask => "mask",
+            Self::Flag => "flag",
+        }
+    }
+}
+
+/// 规则作用范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlocklistScope {
+    Prompt,
+    Response,
+    Both,
+}
+
+impl BlocklistScope {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "prompt" => Some(Self::Prompt),
+            "response" => Some(Self::Response),
+            "both" => Some(Self::Both),
+            _ => None,
+        }
+    }
+
+    fn covers(&self, target: BlocklistTarget) -> bool {
+        match self {
+            Self::Both => true,
+            Self::Prompt => target == BlocklistTarget::Prompt,
+            Self::Response => target == BlocklistTarget::Response,
+        }
+    }
+}
+
+/// 被检查的内容类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlocklistTarget {
+    Prompt,
+    Response,
+}
+
+impl BlocklistTarget {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Prompt => "prompt",
+            Self::Response => "response",
+        }
+    }
+}
+
+/// 编译后的规则
+#[derive(Debug, Clone)]
+struct CompiledRule {
+    pattern: String,
+    action: BlocklistAction,
+    scope: BlocklistScope,
+    is_active: bool,
+    regex: Regex,
+}
+
+impl CompiledRule {
+    fn from_entry(entry: &BlocklistEntry) -> Option<Self> {
+        let pattern = entry.pattern.trim();
+        if pattern.is_empty() {
+            return None;
+        }
+        let action = BlocklistAction::parse(&entry.action)?;
+        let scope = BlocklistScope::parse(&entry.scope)?;
+        let regex = Regex::new(&format!("(?i){}", regex::escape(pattern))).ok()?;
+        Some(Self {
+            pattern: pattern.to_lowercase(),
+            action,
+            scope,
+            is_active: entry.is_active,
+            regex,
+        })
+    }
+}
+
+/// 单条命中记录
+#[derive(Debug, Clone, Serialize)]
+pub struct BlocklistMatch {
+    pub pattern: String,
+    pub action: BlocklistAction,
+}
+
+/// 检查结果
+#[derive(Debug, Clone)]
+pub struct BlocklistVerdict {
+    /// 命中的规则中最严重的动作，未命中时为 None
+    pub action: Option<BlocklistAction>,
+    /// 打码后的文本（仅在存在 mask 规则命中时与原文不同）
+    pub text: String,
+    pub matches: Vec<BlocklistMatch>,
+}
+
+impl BlocklistVerdict {
+    pub fn is_blocked(&self) -> bool {
+        self.action == Some(BlocklistAction::Block)
+    }
+}
+
+/// 黑名单引擎
+#[derive(Default)]
+pub struct BlocklistEngine {
+    global: RwLock<Vec<CompiledRule>>,
+    tenants: RwLock<HashMap<String, Vec<CompiledRule>>>,
+}
+
+impl BlocklistEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 使用给定规则替换当前规则集
+    pub async fn load_entries(&self, entries: &[BlocklistEntry]) {
+        let mut global = Vec::new();
+        let mut tenants: HashMap<String, Vec<CompiledRule>> = HashMap::new();
+
+        for entry in entries {
+            let Some(rule) = CompiledRule::from_entry(entry) else {
+                warn!(id = %entry.id, pattern = %entry.pattern, "Skipping invalid blocklist entry");
+                continue;
+            };
+            match &entry.tenant_id {
+                Some(tenant_id) => tenants.entry(tenant_id.clone()).or_default().push(rule),
+                None if rule.is_active => global.push(rule),
+                None => {}
+            }
+        }
+
+        *self.global.write().await = global;
+        *self.tenants.write().await = tenants;
+    }
+
+    /// 获取租户生效的规则：租户规则按 pattern 覆盖全局规则，停用的租户规则用于关闭对应全局规则
+    async fn effective_rules(&self, tenant_id: Option<&str>) -> Vec<CompiledRule> {
+        let global = self.global.read().await;
+        let tenants = self.tenants.read().await;
+        let overrides = tenant_id.and_then(|t| tenants.get(t));
+
+        let mut rules: Vec<CompiledRule> = match overrides {
+            Some(overrides) => global
+                .iter()
+                .filter(|rule| !overrides.iter().any(|o| o.pattern == rule.pattern))
+                .cloned()
+                .collect(),
+            None => global.clone(),
+        };
+        if let Some(overrides) = overrides {
+            rules.extend(overrides.iter().filter(|rule| rule.is_active).cloned());
+        }
+        rules
+    }
+
+    /// 检查文本并按规则处理
+    pub async fn evaluate(&self, tenant_id: Option<&str>, text: &str, target: BlocklistTarget) -> BlocklistVerdict {
+        let rules = self.effective_rules(tenant_id).await;
+        apply_rules(&rules, tenant_id, text, target, true)
+    }
+
+    /// 创建流式响应的过滤器，租户没有作用于响应的规则时返回 None
+    pub async fn stream_filter(&self, tenant_id: Option<&str>) -> Option<BlocklistStreamFilter> {
+        let rules: Vec<CompiledRule> = self.effective_rules(tenant_id).await
+            .into_iter()
+            .filter(|rule| rule.scope.covers(BlocklistTarget::Response))
+            .collect();
+        let longest = rules.iter().map(|rule| rule.pattern.chars().count()).max()?;
+        Some(BlocklistStreamFilter {
+            tenant_id: tenant_id.map(str::to_string),
+            rules,
+            holdback: longest.saturating_sub(1),
+            pending: String::new(),
+            content: String::new(),
+        })
+    }
+}
+
+// 按规则检查文本，`record` 为 true 时记录命中指标和日志
+fn apply_rules(rules: &[CompiledRule], tenant_id: Option<&str>, text: &str, target: BlocklistTarget, record: bool) -> BlocklistVerdict {
+    let mut verdict = BlocklistVerdict {
+        action: None,
+        text: text.to_string(),
+        matches: Vec::new(),
+    };
+
+    for rule in rules.iter().filter(|rule| rule.scope.covers(target)) {
+        if !rule.regex.is_match(&verdict.text) {
+            continue;
+        }
+        if rule.action == BlocklistAction::Mask {
+            verdict.text = rule
+                .regex
+                .replace_all(&verdict.text, |caps: &regex::Captures| {
+                    MASK_CHAR.to_string().repeat(caps[0].chars().count())
+                })
+                .into_owned();
+        }
+        verdict.action = verdict.action.max(Some(rule.action));
+        verdict.matches.push(BlocklistMatch {
+            pattern: rule.pattern.clone(),
+            action: rule.action,
+        });
+
+        if record {
+            metrics().incr_counter(
+                "llm_gateway_blocklist_matches_total",
+                &[
+                    ("tenant", tenant_id.unwrap_or("global")),
+                    ("action", rule.action.as_str()),
+                    ("target", target.as_str()),
+                ],
+            );
+        }
+    }
+
+    if record && (verdict.action == Some(BlocklistAction::Flag) || verdict.is_blocked()) {
+        warn!(
+            tenant_id = tenant_id.unwrap_or("global"),
+            target = target.as_str(),
+            patterns = ?verdict.matches.iter().map(|m| m.pattern.as_str()).collect::<Vec<_>>(),
+            "Blocklist matched content"
+        );
+    }
+
+    verdict
+}
+
+/// 流式响应的黑名单过滤器。规则可能跨越多个增量块，末尾不足最长规则长度的内容暂不输出，
+/// 其余内容打码后输出；中间检查不记录指标，拦截或流结束时按完整响应记录一次
+pub struct BlocklistStreamFilter {
+    tenant_id: Option<String>,
+    rules: Vec<CompiledRule>,
+    /// 暂不输出的尾部字符数（最长规则长度减一）
+    holdback: usize,
+    /// 已打码、尚未输出的内容
+    pending: String,
+    /// 完整的原始响应，用于记录指标
+    content: String,
+}
+
+impl BlocklistStreamFilter {
+    /// 追加一段响应，返回可以输出的内容；命中拦截规则时返回 None
+    pub fn push(&mut self, text: &str) -> Option<String> {
+        self.content.push_str(text);
+        self.pending.push_str(text);
+        let verdict = apply_rules(&self.rules, self.tenant_id.as_deref(), &self.pending, BlocklistTarget::Response, false);
+        if verdict.is_blocked() {
+            self.record();
+            return None;
+        }
+        self.pending = verdict.text;
+
+        let ready = self.pending.chars().count().saturating_sub(self.holdback);
+        let split = self.pending.char_indices().nth(ready).map_or(self.pending.len(), |(index, _)| index);
+        let tail = self.pending.split_off(split);
+        Some(std::mem::replace(&mut self.pending, tail))
+    }
+
+    /// 流结束：返回剩余的内容并记录完整响应的命中指标
+    pub fn finish(&mut self) -> String {
+        self.record();
+        std::mem::take(&mut self.pending)
+    }
+
+    fn record(&mut self) {
+        let content = std::mem::take(&mut self.content);
+        apply_rules(&self.rules, self.tenant_id.as_deref(), &content, BlocklistTarget::Response, true);
+    }
+}
+
+lazy_static! {
+    /// 全局黑名单引擎
+    static ref GLOBAL_BLOCKLIST: BlocklistEngine = BlocklistEngine::new();
+}
+
+/// 获取全局黑名单引擎
+pub fn get_blocklist() -> &'static BlocklistEngine {
+    &GLOBAL_BLOCKLIST
+}
+
+/// 从数据库重新加载黑名单规则
+pub async fn reload_blocklist(pool: &SqlitePool) -> sqlx::Result<usize> {
+    let entries = list_active_blocklist_entries(pool).await?;
+    get_blocklist().load_entries(&entries).await;
+    info!(count = entries.len(), "Blocklist entries reloaded");
+    Ok(entries.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(tenant_id: Option<&str>, pattern: &str, action: &str, is_active: bool) -> BlocklistEntry {
+        BlocklistEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            tenant_id: tenant_id.map(|t| t.to_string()),
+            pattern: pattern.to_string(),
+            action: action.to_string(),
+            scope: "both".to_string(),
+            is_active,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_block_mask_and_flag() {
+        let engine = BlocklistEngine::new();
+        engine.load_entries(&[
+            entry(None, "secret", "mask", true),
+            entry(None, "forbidden", "block", true),
+            entry(None, "suspicious", "flag", true),
+        ]).await;
+
+        let verdict = engine.evaluate(None, "my SECRET code", BlocklistTarget::Prompt).await;
+        assert_eq!(verdict.action, Some(BlocklistAction::Mask));
+        assert_eq!(verdict.text, "my ****** code");
+
+        let verdict = engine.evaluate(None, "a suspicious and forbidden text", BlocklistTarget::Response).await;
+        assert!(verdict.is_blocked());
+        assert_eq!(verdict.matches.len(), 2);
+
+        let verdict = engine.evaluate(None, "hello", BlocklistTarget::Prompt).await;
+        assert_eq!(verdict.action, None);
+    }
+
+    #[tokio::test]
+    async fn test_tenant_overrides_global_rules() {
+        let engine = BlocklistEngine::new();
+        engine.load_entries(&[
+            entry(None, "forbidden", "block", true),
+            entry(Some("t1"), "forbidden", "flag", true),
+            entry(Some("t2"), "forbidden", "block", false),
+            entry(Some("t2"), "competitor", "mask", true),
+        ]).await;
+
+        let text = "forbidden competitor";
+        assert!(engine.evaluate(None, text, BlocklistTarget::Prompt).await.is_blocked());
+        assert_eq!(engine.evaluate(Some("t1"), text, BlocklistTarget::Prompt).await.action, Some(BlocklistAction::Flag));
+
+        let verdict = engine.evaluate(Some("t2"), text, BlocklistTarget::Prompt).await;
+        assert_eq!(verdict.action, Some(BlocklistAction::Mask));
+        assert_eq!(verdict.text, "forbidden **********");
+    }
+
+    #[tokio::test]
+    async fn test_stream_filter_across_chunks() {
+        let engine = BlocklistEngine::new();
+        assert!(engine.stream_filter(None).await.is_none());
+        engine.load_entries(&[
+            entry(None, "secret", "mask", true),
+            entry(None, "forbidden", "block", true),
+        ]).await;
+
+        // 跨块的规则也会打码，输出的内容拼接后与整体检查一致
+        let mut filter = engine.stream_filter(None).await.unwrap();
+        let mut output = String::new();
+        for chunk in ["my se", "cr", "et code 你好", "!"] {
+            output.push_str(&filter.push(chunk).unwrap());
+        }
+        assert!(!output.contains("se"));
+        output.push_str(&filter.finish());
+        assert_eq!(output, "my ****** code 你好!");
+
+        let mut filter = engine.stream_filter(None).await.unwrap();
+        let first = filter.push("this is forb").unwrap();
+        assert!(!first.contains("forb"));
+        assert!(filter.push("idden").is_none());
+    }
+}
@@ -0,0 +1,249 @@
+//! # 供应商月度预算
+//!
+//! 每个项目分别为供应商配置每月费用上限，保存在 system_configs 中（category 为 `budget`，
+//! value 为金额，与模型单价同一计价单位）：default 项目的 key_name 为供应商名，其他项目为 `项目 ID/供应商名`。
+//! 当月费用按 `usage_stats` 中该项目的每日汇总累加，达到上限后调度器拒绝该项目对该供应商的请求，
+//! 启用 fallback 时由备选供应商接管；其他项目不受影响。
+//!
+//! 调度前的检查使用内存中的缓存：首次检查时读取预算配置和当月费用，之后调度拿到用量时累加费用，
+//! 每隔 `BUDGET_CACHE_TTL` 或跨月后重新从数据库核对（同步其他实例写入的用量），修改预算时立即失效
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use lazy_static::lazy_static;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tracing::warn;
+
+use crate::dao::SQLITE_POOL;
+use crate::dao::project::DEFAULT_PROJECT;
+use crate::dao::system_config::{
+    create_system_config, delete_system_config, get_system_config_by_key, get_system_config_value,
+    list_system_configs_by_category, update_system_config_value, SystemConfig,
+};
+use crate::dao::usage_stats::get_provider_cost_since;
+use crate::llm_api::dispatcher::{LLMError, Provider};
+use crate::metrics::metrics;
+
+/// 预算配置在 system_configs 中的 category
+pub const BUDGET_CONFIG_CATEGORY: &str = "budget";
+
+/// 预算缓存重新从数据库核对的间隔
+pub const BUDGET_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// 缓存的预算配置和当月已用费用
+#[derive(Debug, Clone)]
+struct CachedBudget {
+    monthly_budget: Option<f64>,
+    month: String,
+    spent: f64,
+    loaded_at: Instant,
+}
+
+lazy_static! {
+    // 按 (项目, 供应商) 缓存的预算，未配置预算的组合同样缓存，避免每次调度都查询配置
+    static ref BUDGET_CACHE: RwLock<HashMap<(String, String), CachedBudget>> = RwLock::new(HashMap::new());
+}
+
+/// 项目在供应商上的当月预算使用情况
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetStatus {
+    pub project_id: String,
+    pub provider: String,
+    /// 统计月份，格式 YYYY-MM
+    pub month: String,
+    pub monthly_budget: f64,
+    pub spent: f64,
+    pub remaining: f64,
+    pub exceeded: bool,
+}
+
+impl BudgetStatus {
+    fn new(project_id: String, provider: String, month: String, monthly_budget: f64, spent: f64) -> Self {
+        Self {
+            project_id,
+            provider,
+            month,
+            monthly_budget,
+            spent,
+            remaining: (monthly_budget - spent).max(0.0),
+            exceeded: spent >= monthly_budget,
+        }
+    }
+}
+
+// 当前月份（本地时间，与 usage_stats.day 一致）
+fn current_month() -> String {
+    chrono::Local::now().format("%Y-%m").to_string()
+}
+
+// 预算配置的 key_name：default 项目为供应商名，其他项目为 `项目 ID/供应商名`
+fn budget_key(project_id: &str, provider: &str) -> String {
+    if project_id == DEFAULT_PROJECT {
+        provider.to_string()
+    } else {
+        format!("{}/{}", project_id, provider)
+    }
+}
+
+// 从 key_name 中解析项目和供应商
+fn parse_budget_key(key: &str) -> (&str, &str) {
+    key.split_once('/').unwrap_or((DEFAULT_PROJECT, key))
+}
+
+// 解析预算金额，必须为非负数
+fn parse_budget(key: &str, value: &str) -> Option<f64> {
+    match value.trim().parse::<f64>() {
+        Ok(budget) if budget.is_finite() && budget >= 0.0 => Some(budget),
+        _ => {
+            warn!(budget = %key, value = %value, "Ignoring invalid monthly budget");
+            None
+        }
+    }
+}
+
+/// 读取项目在供应商上的月度预算，未配置或配置无效时返回 None
+pub async fn get_monthly_budget(pool: &SqlitePool, project_id: &str, provider: &str) -> sqlx::Result<Option<f64>> {
+    let key = budget_key(project_id, provider);
+    let value = get_system_config_value(pool, BUDGET_CONFIG_CATEGORY, &key).await?;
+    Ok(value.and_then(|value| parse_budget(&key, &value)))
+}
+
+/// 设置项目在供应商上的月度预算，`None` 表示取消限额
+pub async fn set_monthly_budget(
+    pool: &SqlitePool,
+    project_id: &str,
+    provider: &str,
+    monthly_budget: Option<f64>,
+) -> sqlx::Result<()> {
+    let key = budget_key(project_id, provider);
+    let existing = get_system_config_by_key(pool, BUDGET_CONFIG_CATEGORY, &key).await?;
+    match (existing, monthly_budget) {
+        (Some(config), None) => {
+            delete_system_config(pool, &config.id).await?;
+        }
+        (Some(_), Some(budget)) => {
+            update_system_config_value(pool, BUDGET_CONFIG_CATEGORY, &key, &budget.to_string()).await?;
+        }
+        (None, Some(budget)) => {
+            let config = SystemConfig {
+                id: uuid::Uuid::new_v4().to_string(),
+                category: BUDGET_CONFIG_CATEGORY.to_string(),
+                key_name: key,
+                value: budget.to_string(),
+                is_encrypted: false,
+                version: 1,
+                created_at: None,
+                updated_at: None,
+            };
+            create_system_config(pool, &config).await?;
+        }
+        (None, None) => {}
+    }
+    BUDGET_CACHE.write().unwrap().remove(&(project_id.to_string(), provider.to_string()));
+    Ok(())
+}
+
+/// 项目在供应商上的当月预算使用情况，未配置预算时返回 None
+pub async fn get_budget_status(pool: &SqlitePool, project_id: &str, provider: &str) -> sqlx::Result<Option<BudgetStatus>> {
+    let Some(monthly_budget) = get_monthly_budget(pool, project_id, provider).await? else {
+        return Ok(None);
+    };
+    let month = current_month();
+    let spent = get_provider_cost_since(pool, project_id, provider, &format!("{}-01", month)).await?;
+    Ok(Some(BudgetStatus::new(project_id.to_string(), provider.to_string(), month, monthly_budget, spent)))
+}
+
+/// 所有项目已配置的预算及当月使用情况
+pub async fn list_budget_statuses(pool: &SqlitePool) -> sqlx::Result<Vec<BudgetStatus>> {
+    let month = current_month();
+    let since = format!("{}-01", month);
+    let mut statuses = Vec::new();
+    for config in list_system_configs_by_category(pool, BUDGET_CONFIG_CATEGORY).await? {
+        let Some(monthly_budget) = parse_budget(&config.key_name, &config.value) else {
+            continue;
+        };
+        let (project_id, provider) = parse_budget_key(&config.key_name);
+        let spent = get_provider_cost_since(pool, project_id, provider, &since).await?;
+        statuses.push(BudgetStatus::new(project_id.to_string(), provider.to_string(), month.clone(), monthly_budget, spent));
+    }
+    Ok(statuses)
+}
+
+/// 调度拿到用量后累加项目在供应商上的当月费用，只更新已缓存的预算
+pub fn record_budget_spend(project_id: &str, provider: &str, cost: f64) {
+    if cost <= 0.0 {
+        return;
+    }
+    let key = (project_id.to_string(), provider.to_string());
+    if let Some(cached) = BUDGET_CACHE.write().unwrap().get_mut(&key)
+        && cached.monthly_budget.is_some()
+    {
+        cached.spent += cost;
+    }
+}
+
+// 读取缓存的预算，不存在、已跨月或超过核对间隔时从数据库重新加载
+async fn cached_budget(pool: &SqlitePool, project_id: &str, provider: &str) -> sqlx::Result<CachedBudget> {
+    let key = (project_id.to_string(), provider.to_string());
+    let month = current_month();
+    let cached = BUDGET_CACHE.read().unwrap().get(&key)
+        .filter(|cached| cached.month == month && cached.loaded_at.elapsed() < BUDGET_CACHE_TTL)
+        .cloned();
+    if let Some(cached) = cached {
+        return Ok(cached);
+    }
+
+    let monthly_budget = get_monthly_budget(pool, project_id, provider).await?;
+    let spent = match monthly_budget {
+        Some(_) => get_provider_cost_since(pool, project_id, provider, &format!("{}-01", month)).await?,
+        None => 0.0,
+    };
+    let cached = CachedBudget { monthly_budget, month, spent, loaded_at: Instant::now() };
+    BUDGET_CACHE.write().unwrap().insert(key, cached.clone());
+    Ok(cached)
+}
+
+/// 项目在供应商上的当月费用达到预算时拒绝请求；数据库不可用或查询失败时放行，避免预算统计影响正常调用
+pub async fn ensure_within_budget(project_id: &str, provider: &Provider) -> Result<(), LLMError> {
+    let Some(pool) = SQLITE_POOL.get() else {
+        return Ok(());
+    };
+    let (monthly_budget, cached) = match cached_budget(pool, project_id, provider.as_str()).await {
+        Ok(cached) => match cached.monthly_budget {
+            Some(monthly_budget) if cached.spent >= monthly_budget => (monthly_budget, cached),
+            _ => return Ok(()),
+        },
+        Err(e) => {
+            warn!(project_id = %project_id, provider = %provider.as_str(), error = %e, "Failed to check monthly budget");
+            return Ok(());
+        }
+    };
+
+    metrics().incr_counter("llm_gateway_budget_rejections_total", &[("provider", provider.as_str())]);
+    warn!(
+        project_id = %project_id,
+        provider = %provider.as_str(),
+        spent = cached.spent,
+        budget = monthly_budget,
+        "Monthly budget exceeded"
+    );
+    Err(LLMError::BudgetExceeded(format!(
+        "project {} has spent {:.4} of its {:.4} monthly budget for provider {} in {}",
+        project_id, cached.spent, monthly_budget, provider.as_str(), cached.month
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_key_round_trip() {
+        assert_eq!(budget_key(DEFAULT_PROJECT, "openai"), "openai");
+        assert_eq!(budget_key("team-a", "openai"), "team-a/openai");
+        assert_eq!(parse_budget_key("openai"), (DEFAULT_PROJECT, "openai"));
+        assert_eq!(parse_budget_key("team-a/openai"), ("team-a", "openai"));
+    }
+}
@@ -0,0 +1,191 @@
+//! # 请求取消
+//!
+//! 每个进行中的 Chat Completion 请求按请求 ID 登记一个取消令牌，`DELETE /v1/requests/{request_id}`
+//! （或 `POST /v1/requests/{request_id}/cancel`）以及客户端在流结束前断开 SSE / WebSocket 时
+//! 触发令牌，之后调度器停止重试和 fallback，HTTP 客户端中断正在等待的上游请求或流式读取，
+//! 并写入状态码为 [`CALL_STATUS_CANCELLED`] 的调用记录。
+//!
+//! 令牌随 `CallMetadata` 在调度任务（以及转发流式响应的子任务）中传递。
+//!
+//! 登记表按（所有者, 请求 ID）区分，所有者为发起请求的调用方 Key 指纹（未携带 Key 时为 None），
+//! 只有同一调用方才能取消，不同调用方使用相同的请求 ID 互不影响；同一调用方重复提交仍在进行的请求 ID 时
+//! （例如客户端超时后重试），新请求取代旧请求，旧请求被取消
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use lazy_static::lazy_static;
+use tokio::sync::Notify;
+
+use crate::metrics::metrics;
+
+/// 被取消的调用在 call_logs 中记录的状态码（与 nginx 的 499 Client Closed Request 一致）
+pub const CALL_STATUS_CANCELLED: i64 = 499;
+
+/// 取消令牌，克隆后共享同一状态
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<CancellationState>,
+}
+
+#[derive(Debug, Default)]
+struct CancellationState {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 触发取消，唤醒所有等待中的任务
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
+
+    /// 是否已取消
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// 等待取消
+    pub async fn cancelled(&self) {
+        let notified = self.inner.notify.notified();
+        tokio::pin!(notified);
+        // 先登记等待再检查状态，避免错过检查之后、等待之前的取消
+        notified.as_mut().enable();
+        if self.is_cancelled() {
+            return;
+        }
+        notified.await;
+    }
+
+    /// 执行 future，取消时丢弃它并返回 None
+    pub async fn run_until_cancelled<F: Future>(&self, future: F) -> Option<F::Output> {
+        tokio::select! {
+            biased;
+            _ = self.cancelled() => None,
+            output = future => Some(output),
+        }
+    }
+}
+
+/// 登记表的键：（所有者, 请求 ID）
+type InFlightKey = (Option<String>, String);
+
+lazy_static! {
+    /// 进行中的请求：（所有者, 请求 ID）-> 取消令牌
+    static ref IN_FLIGHT_REQUESTS: Mutex<HashMap<InFlightKey, CancellationToken>> = Mutex::new(HashMap::new());
+}
+
+/// 进行中请求的登记，drop 时从登记表中移除
+#[derive(Debug)]
+pub struct InFlightRequest {
+    owner: Option<String>,
+    request_id: String,
+    token: CancellationToken,
+}
+
+impl InFlightRequest {
+    pub fn request_id(&self) -> &str {
+        &self.request_id
+    }
+
+    pub fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+}
+
+impl Drop for InFlightRequest {
+    fn drop(&mut self) {
+        let mut requests = IN_FLIGHT_REQUESTS.lock().unwrap();
+        // 只移除自己登记的令牌，已被重试取代时保留新请求的登记
+        let key = (self.owner.take(), std::mem::take(&mut self.request_id));
+        if requests.get(&key).is_some_and(|token| Arc::ptr_eq(&token.inner, &self.token.inner)) {
+            requests.remove(&key);
+        }
+    }
+}
+
+/// 登记 `owner` 发起的进行中请求；该调用方同一 ID 的请求仍在进行时取消旧请求，由新请求取代
+pub fn register_request(owner: Option<&str>, request_id: &str) -> InFlightRequest {
+    let token = CancellationToken::new();
+    let key = (owner.map(str::to_string), request_id.to_string());
+    let replaced = IN_FLIGHT_REQUESTS.lock().unwrap().insert(key, token.clone());
+    if let Some(replaced) = replaced {
+        replaced.cancel();
+    }
+    InFlightRequest { owner: owner.map(str::to_string), request_id: request_id.to_string(), token }
+}
+
+/// 取消 `owner` 发起的进行中请求，请求不存在（未登记、已结束或属于其他调用方）时返回 false
+pub fn cancel_request(owner: Option<&str>, request_id: &str) -> bool {
+    let key = (owner.map(str::to_string), request_id.to_string());
+    let token = IN_FLIGHT_REQUESTS.lock().unwrap().get(&key).cloned();
+    let Some(token) = token else {
+        return false;
+    };
+    token.cancel();
+    metrics().incr_counter("llm_gateway_requests_cancelled_total", &[]);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_cancel_wakes_waiters() {
+        let token = CancellationToken::new();
+        let waiter = tokio::spawn({
+            let token = token.clone();
+            async move { token.run_until_cancelled(tokio::time::sleep(Duration::from_secs(30))).await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        token.cancel();
+        assert!(waiter.await.unwrap().is_none());
+        assert!(token.is_cancelled());
+
+        // 已取消的令牌立即返回
+        assert!(token.run_until_cancelled(async { 1 }).await.is_none());
+        assert_eq!(CancellationToken::new().run_until_cancelled(async { 1 }).await, Some(1));
+    }
+
+    #[test]
+    fn test_registry_removes_finished_requests() {
+        let request_id = format!("req-{}", uuid::Uuid::new_v4());
+        let in_flight = register_request(Some("ck_owner"), &request_id);
+
+        // 其他调用方（包括匿名调用方）不能取消，也不会占用该 ID
+        assert!(!cancel_request(Some("ck_other"), &request_id));
+        assert!(!cancel_request(None, &request_id));
+        let other = register_request(Some("ck_other"), &request_id);
+        assert!(!in_flight.token().is_cancelled());
+
+        assert!(cancel_request(Some("ck_owner"), &request_id));
+        assert!(in_flight.token().is_cancelled());
+        assert!(!other.token().is_cancelled());
+
+        drop(in_flight);
+        assert!(!cancel_request(Some("ck_owner"), &request_id));
+        assert!(cancel_request(Some("ck_other"), &request_id));
+    }
+
+    #[test]
+    fn test_retry_replaces_in_flight_request() {
+        let request_id = format!("req-{}", uuid::Uuid::new_v4());
+        let first = register_request(Some("ck_owner"), &request_id);
+        let retry = register_request(Some("ck_owner"), &request_id);
+        assert!(first.token().is_cancelled());
+        assert!(!retry.token().is_cancelled());
+
+        // 旧请求结束时不影响新请求的登记
+        drop(first);
+        assert!(cancel_request(Some("ck_owner"), &request_id));
+        assert!(retry.token().is_cancelled());
+    }
+}
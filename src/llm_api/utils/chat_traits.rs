@@ -1,6 +1,7 @@
 //! # 通用 Chat API 抽象结构
 //!
-//! 定义所有 LLM 客户端共用的 ChatRequest 和 ChatResponse 抽象类/trait
+//! 定义所有 LLM 客户端共用的 ChatRequest 和 ChatResponse 抽象类/trait，
+//! 文本补全（generate / completions）的 CompletionRequest 和 CompletionResponse trait，
 //! 以及相关的通用类型和方法
 
 use serde_json::Value;
@@ -123,6 +124,66 @@ pub trait ChatResponseTrait {
     }
 }
 
+/// 通用 CompletionRequest Trait
+///
+/// 文本补全请求（Ollama `/api/generate`、OpenAI `/v1/completions`）的通用接口，
+/// 直接以提示词续写，不带对话消息
+pub trait CompletionRequestTrait {
+    /// 获取要使用的模型名称
+    fn get_model(&self) -> &str;
+
+    /// 获取提示词
+    fn get_prompt(&self) -> &str;
+
+    /// 获取是否使用流式输出
+    fn is_stream(&self) -> Option<bool> {
+        None
+    }
+
+    /// 设置是否使用流式输出
+    fn set_stream(&mut self, stream: bool);
+
+    /// 验证请求参数是否有效
+    fn validate(&self) -> Result<(), String> {
+        if self.get_model().is_empty() {
+            return Err("Model name cannot be empty".to_string());
+        }
+        if self.get_prompt().is_empty() {
+            return Err("Prompt cannot be empty".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// 通用 CompletionResponse Trait
+///
+/// 文本补全响应（以及流式响应中的单个块）的通用接口
+pub trait CompletionResponseTrait {
+    /// 获取实际使用的模型名称
+    fn get_model(&self) -> &str;
+
+    /// 获取生成的文本
+    fn get_text(&self) -> Option<String>;
+
+    /// 是否为完整响应（流式模式下使用）
+    fn is_done(&self) -> bool;
+
+    /// 获取结束原因
+    fn get_finish_reason(&self) -> Option<String> {
+        None
+    }
+
+    /// 获取生成的 token 数量
+    fn get_eval_count(&self) -> Option<u32> {
+        None
+    }
+
+    /// 获取提示词 token 数量
+    fn get_prompt_eval_count(&self) -> Option<u32> {
+        None
+    }
+}
+
 /// 性能摘要结构体
 /// 
 /// 统一不同 LLM API 的性能指标格式
@@ -9,14 +9,27 @@
 
 use async_trait::async_trait;
 use reqwest::{Client as HttpClient, Response};
-use serde::Serialize;
+use serde::{Serialize, Serializer};
+use serde_json::value::RawValue;
+use hyper::body::Bytes;
 use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use tokio::time::{sleep, timeout};
-use tracing::{info, warn, error};
+use tracing::{field, info, info_span, warn, error, Instrument};
 use uuid::Uuid;
 use crate::dao::call_log::{CallLog, create_call_log};
+use crate::dao::project::DEFAULT_PROJECT;
+use crate::dao::provider_key_pool::quota::{parse_reset_duration, record_key_quota, KeyQuota};
+use crate::llm_api::utils::cancellation::{CancellationToken, CALL_STATUS_CANCELLED};
+use crate::llm_api::utils::msg_structure::{Message, MessageFormat, WireMessages};
+use crate::llm_api::utils::redaction::redact_opt;
+use crate::llm_api::utils::stream_accounting::{StreamAccounting, StreamUsage};
+use crate::llm_api::utils::trace_context::{outbound_trace_parent, with_trace_parent, TraceParent, TRACEPARENT_HEADER};
+use crate::metrics::metrics;
+use lazy_static::lazy_static;
+use regex::Regex;
 
 /// 超时配置
 #[derive(Debug, Clone)]
@@ -99,6 +112,194 @@ impl RetryConfig {
 
 }
 
+/// 连接池和 HTTP/2 配置
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ConnectionConfig {
+    /// 每个主机保留的最大空闲连接数，None 时不限制
+    pub pool_max_idle_per_host: Option<usize>,
+    /// 空闲连接的保留时间，None 时一直保留
+    pub pool_idle_timeout: Option<Duration>,
+    /// TCP keepalive 间隔，None 时不开启
+    pub tcp_keepalive: Option<Duration>,
+    /// 直接使用 HTTP/2（不经过 ALPN 协商），仅用于确定支持 HTTP/2 的上游
+    pub http2_prior_knowledge: bool,
+    /// HTTP/2 keep-alive ping 间隔，None 时不发送
+    pub http2_keep_alive_interval: Option<Duration>,
+    /// 等待 keep-alive ping 响应的超时，超时后关闭连接
+    pub http2_keep_alive_timeout: Option<Duration>,
+    /// 连接上没有进行中的请求时也发送 keep-alive ping
+    pub http2_keep_alive_while_idle: bool,
+    /// 开启 HTTP/2 自适应流控窗口
+    pub http2_adaptive_window: bool,
+}
+
+impl Default for ConnectionConfig {
+    fn default() -> Self {
+        Self {
+            pool_max_idle_per_host: None,
+            pool_idle_timeout: Some(Duration::from_secs(90)),
+            tcp_keepalive: Some(Duration::from_secs(60)),
+            http2_prior_knowledge: false,
+            http2_keep_alive_interval: None,
+            http2_keep_alive_timeout: None,
+            http2_keep_alive_while_idle: false,
+            http2_adaptive_window: false,
+        }
+    }
+}
+
+impl ConnectionConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_pool_max_idle_per_host(mut self, max_idle: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max_idle);
+        self
+    }
+
+    pub fn with_pool_idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.pool_idle_timeout = timeout;
+        self
+    }
+
+    pub fn with_tcp_keepalive(mut self, interval: Option<Duration>) -> Self {
+        self.tcp_keepalive = interval;
+        self
+    }
+
+    pub fn with_http2_prior_knowledge(mut self, enabled: bool) -> Self {
+        self.http2_prior_knowledge = enabled;
+        self
+    }
+
+    /// 设置 HTTP/2 keep-alive：每隔 `interval` 发送 ping，`timeout` 内未收到响应时关闭连接
+    pub fn with_http2_keep_alive(mut self, interval: Duration, timeout: Duration, while_idle: bool) -> Self {
+        self.http2_keep_alive_interval = Some(interval);
+        self.http2_keep_alive_timeout = Some(timeout);
+        self.http2_keep_alive_while_idle = while_idle;
+        self
+    }
+
+    pub fn with_http2_adaptive_window(mut self, enabled: bool) -> Self {
+        self.http2_adaptive_window = enabled;
+        self
+    }
+
+    fn apply(&self, mut builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        if let Some(max_idle) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max_idle);
+        }
+        builder = builder
+            .pool_idle_timeout(self.pool_idle_timeout)
+            .tcp_keepalive(self.tcp_keepalive);
+        if self.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+        if let Some(interval) = self.http2_keep_alive_interval {
+            builder = builder
+                .http2_keep_alive_interval(interval)
+                .http2_keep_alive_while_idle(self.http2_keep_alive_while_idle);
+        }
+        if let Some(timeout) = self.http2_keep_alive_timeout {
+            builder = builder.http2_keep_alive_timeout(timeout);
+        }
+        builder.http2_adaptive_window(self.http2_adaptive_window)
+    }
+}
+
+/// TLS 配置，用于使用内部 CA 或要求客户端证书的私有化部署
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct TlsConfig {
+    /// 额外信任的根证书（PEM，可包含多个证书），与系统根证书一起使用
+    pub ca_bundle_pem: Option<Vec<u8>>,
+    /// 双向 TLS 的客户端证书（PEM）
+    pub client_cert_pem: Option<Vec<u8>>,
+    /// 双向 TLS 的客户端私钥（PKCS#8 PEM）
+    pub client_key_pem: Option<Vec<u8>>,
+    /// 不校验服务端证书，仅用于测试环境，生产环境应配置根证书
+    pub danger_accept_invalid_certs: bool,
+}
+
+impl TlsConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_ca_bundle_pem(mut self, pem: Vec<u8>) -> Self {
+        self.ca_bundle_pem = Some(pem);
+        self
+    }
+
+    /// 从文件读取根证书
+    pub fn with_ca_bundle_file(self, path: &str) -> Result<Self, ClientError> {
+        Ok(self.with_ca_bundle_pem(read_pem_file(path)?))
+    }
+
+    pub fn with_client_identity_pem(mut self, cert_pem: Vec<u8>, key_pem: Vec<u8>) -> Self {
+        self.client_cert_pem = Some(cert_pem);
+        self.client_key_pem = Some(key_pem);
+        self
+    }
+
+    /// 从文件读取客户端证书和私钥
+    pub fn with_client_identity_files(self, cert_path: &str, key_path: &str) -> Result<Self, ClientError> {
+        Ok(self.with_client_identity_pem(read_pem_file(cert_path)?, read_pem_file(key_path)?))
+    }
+
+    pub fn with_danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.danger_accept_invalid_certs = accept;
+        self
+    }
+
+    /// 检查证书和私钥能否解析（客户端按需创建时才会加载，供应商注册时提前检查）
+    pub fn validate(&self) -> Result<(), ClientError> {
+        self.apply(HttpClient::builder()).map(|_| ())
+    }
+
+    fn apply(&self, mut builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder, ClientError> {
+        if let Some(pem) = &self.ca_bundle_pem {
+            let certificates = reqwest::Certificate::from_pem_bundle(pem).map_err(|e| ClientError::Config {
+                message: format!("Invalid CA bundle: {}", e),
+            })?;
+            if certificates.is_empty() {
+                return Err(ClientError::Config { message: "CA bundle contains no certificates".to_string() });
+            }
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        match (&self.client_cert_pem, &self.client_key_pem) {
+            (Some(cert), Some(key)) => {
+                let identity = reqwest::Identity::from_pkcs8_pem(cert, key).map_err(|e| ClientError::Config {
+                    message: format!("Invalid client certificate or key: {}", e),
+                })?;
+                builder = builder.identity(identity);
+            }
+            (None, None) => {}
+            _ => {
+                return Err(ClientError::Config {
+                    message: "Client certificate and key must be configured together".to_string(),
+                });
+            }
+        }
+        if self.danger_accept_invalid_certs {
+            warn!("TLS certificate verification is disabled");
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+        Ok(builder)
+    }
+}
+
+fn read_pem_file(path: &str) -> Result<Vec<u8>, ClientError> {
+    std::fs::read(path).map_err(|e| ClientError::Config {
+        message: format!("Failed to read '{}': {}", path, e),
+    })
+}
+
+/// 流式响应单行的默认最大字节数
+pub const DEFAULT_MAX_STREAM_LINE_BYTES: usize = 4 * 1024 * 1024;
+
 /// 完整的客户端配置
 #[derive(Debug, Clone)]
 pub struct ClientConfig {
@@ -106,6 +307,15 @@ pub struct ClientConfig {
     pub timeout: TimeoutConfig,
     /// 重试配置
     pub retry: RetryConfig,
+    /// 连接池和 HTTP/2 配置
+    pub connection: ConnectionConfig,
+    /// TLS 配置（自定义根证书、客户端证书）
+    pub tls: TlsConfig,
+    /// 共享连接池名称（通常为供应商名称）：名称和连接配置相同的客户端共用一个 HTTP 客户端及其连接池，
+    /// 认证等默认请求头按客户端分别发送。None 时单独创建 HTTP 客户端
+    pub shared_pool: Option<String>,
+    /// 流式响应单行的最大字节数，超出时中止读取，避免上游异常时缓冲区无限增长
+    pub max_stream_line_bytes: usize,
     /// 默认请求头
     pub default_headers: HashMap<String, String>,
     /// 用户代理
@@ -117,6 +327,10 @@ impl Default for ClientConfig {
         Self {
             timeout: TimeoutConfig::default(),
             retry: RetryConfig::default(),
+            connection: ConnectionConfig::default(),
+            tls: TlsConfig::default(),
+            shared_pool: None,
+            max_stream_line_bytes: DEFAULT_MAX_STREAM_LINE_BYTES,
             default_headers: HashMap::new(),
             user_agent: "LLM-Client/1.0".to_string(),
         }
@@ -138,11 +352,46 @@ impl ClientConfig {
         self
     }
 
+    pub fn with_max_stream_line_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_stream_line_bytes = max_bytes;
+        self
+    }
+
+    pub fn with_connection(mut self, connection: ConnectionConfig) -> Self {
+        self.connection = connection;
+        self
+    }
+
+    pub fn with_tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = tls;
+        self
+    }
+
+    /// 与同名连接池的客户端共用 HTTP 客户端
+    pub fn with_shared_pool(mut self, name: &str) -> Self {
+        self.shared_pool = Some(name.to_string());
+        self
+    }
+
+    /// 未指定共享连接池时使用 `name`（供应商客户端创建时调用）
+    pub fn with_default_shared_pool(self, name: &str) -> Self {
+        if self.shared_pool.is_some() {
+            return self;
+        }
+        self.with_shared_pool(name)
+    }
+
     pub fn add_header(mut self, key: String, value: String) -> Self {
         self.default_headers.insert(key, value);
         self
     }
 
+    /// 批量添加请求头，同名请求头会被覆盖
+    pub fn add_headers(mut self, headers: &HashMap<String, String>) -> Self {
+        self.default_headers.extend(headers.iter().map(|(k, v)| (k.clone(), v.clone())));
+        self
+    }
+
     pub fn with_user_agent(mut self, user_agent: String) -> Self {
         self.user_agent = user_agent;
         self
@@ -158,8 +407,8 @@ pub enum ClientError {
     Timeout { duration: Duration },
     /// 网络错误
     Network { source: reqwest::Error },
-    /// 重试次数耗尽
-    RetryExhausted { attempts: u32, last_error: String },
+    /// 重试次数耗尽，`status_code` 为最后一次尝试的上游状态码
+    RetryExhausted { attempts: u32, last_error: String, status_code: Option<u16> },
     /// 配置错误
     Config { message: String },
     /// LLM API 错误
@@ -168,6 +417,10 @@ pub enum ClientError {
     Serialization { source: serde_json::Error },
     /// 内部错误
     Internal { message: String },
+    /// 请求被取消
+    Cancelled,
+    /// 流式响应的单行超出长度限制
+    StreamLineTooLong { limit: usize },
 }
 
 impl std::fmt::Display for ClientError {
@@ -175,7 +428,7 @@ impl std::fmt::Display for ClientError {
         match self {
             ClientError::Timeout { duration } => write!(f, "Request timeout after {:?}", duration),
             ClientError::Network { source } => write!(f, "Network error: {}", source),
-            ClientError::RetryExhausted { attempts, last_error } => {
+            ClientError::RetryExhausted { attempts, last_error, .. } => {
                 write!(f, "Retry exhausted after {} attempts: {}", attempts, last_error)
             }
             ClientError::Config { message } => write!(f, "Configuration error: {}", message),
@@ -184,12 +437,29 @@ impl std::fmt::Display for ClientError {
             }
             ClientError::Serialization { source } => write!(f, "Serialization error: {}", source),
             ClientError::Internal { message } => write!(f, "Internal error: {}", message),
+            ClientError::Cancelled => write!(f, "Request cancelled"),
+            ClientError::StreamLineTooLong { limit } => write!(f, "Stream line exceeds {} bytes", limit),
         }
     }
 }
 
 impl std::error::Error for ClientError {}
 
+impl ClientError {
+    /// 是否为上游限流（HTTP 429）
+    pub fn is_rate_limited(&self) -> bool {
+        matches!(self, ClientError::LLMApi { status_code: Some(429), .. })
+    }
+
+    /// 上游返回的 HTTP 状态码（重试耗尽时为最后一次尝试的状态码）
+    pub fn status_code(&self) -> Option<u16> {
+        match self {
+            ClientError::LLMApi { status_code, .. } | ClientError::RetryExhausted { status_code, .. } => *status_code,
+            _ => None,
+        }
+    }
+}
+
 impl From<reqwest::Error> for ClientError {
     fn from(error: reqwest::Error) -> Self {
         ClientError::Network { source: error }
@@ -202,11 +472,364 @@ impl From<serde_json::Error> for ClientError {
     }
 }
 
+/// 调用记录附加信息
+///
+/// 由上层调度器通过 task-local 注入，客户端在创建调用记录时一并写入
+#[derive(Debug, Clone, Default)]
+pub struct CallMetadata {
+    /// 网关请求 ID（HTTP 请求的 X-Request-Id 或调度时生成），同一请求的所有调用记录和日志共用
+    pub request_id: Option<String>,
+    /// 检测到的提示词语言（ISO 639-3）
+    pub detected_language: Option<String>,
+    /// A/B 流量拆分选中的分组（逻辑模型名:分组名）
+    pub traffic_arm: Option<String>,
+    /// 本次尝试使用的供应商
+    pub provider: Option<String>,
+    /// 本次尝试使用的模型在模型目录中的 ID，模型不在目录中时为 None
+    pub model_id: Option<String>,
+    /// 本次尝试使用的 API Key ID（来自 Key 池）
+    pub key_id: Option<String>,
+    /// 请求摘要（最后一条用户消息的截断内容），便于排查问题
+    pub request_summary: Option<String>,
+    /// 本次调度中已写入的调用记录 ID（按写入顺序），调度器据此回填用量和费用
+    pub call_log_ids: Arc<Mutex<Vec<String>>>,
+    /// 跨调度收集调用记录 ID，上层需要关联多次调度的调用记录时设置（例如 Agent 循环关联工具调用和发起它的模型请求）
+    pub call_log_sink: Option<Arc<Mutex<Vec<String>>>>,
+    /// 适配器解析响应时记录的供应商原始计费信息
+    pub provider_billing: Arc<Mutex<Option<ProviderBilling>>>,
+    /// 调度器和客户端共享的重试预算
+    pub retry_budget: RetryBudget,
+    /// 调度请求指定的超时（`DispatchRequest.timeout_ms`），覆盖客户端配置的请求超时
+    pub timeout: Option<Duration>,
+    /// 本次调度中已序列化的消息列表，重试和 fallback 时复用
+    pub serialized_messages: SerializedMessages,
+    /// 请求的取消令牌，取消后不再发送或继续读取上游请求
+    pub cancellation: CancellationToken,
+    /// 发起请求的调用方 ID，调度拿到用量后累加到该调用方的 token 配额
+    pub consumer_id: Option<String>,
+    /// 发起请求的项目，None 表示 default 项目；只使用该项目的 API Key
+    pub project_id: Option<String>,
+}
+
+/// 供应商返回的原始计费信息，原样保存用于与供应商账单对账
+#[derive(Debug, Clone, Default)]
+pub struct ProviderBilling {
+    /// 供应商返回的请求 ID
+    pub request_id: Option<String>,
+    /// 供应商返回的用量对象
+    pub usage: Option<serde_json::Value>,
+}
+
+/// 一次调度内调度器和客户端共享的重试预算
+///
+/// 两层各自重试时请求次数会相乘，共享预算后上游请求总数不超过 `max_attempts`；
+/// 同时记录上游返回的 Retry-After，之后任一层重试前都至少等待到该时间点。
+/// 默认不限制次数（客户端单独使用时按自身的重试配置）
+#[derive(Debug, Clone, Default)]
+pub struct RetryBudget {
+    state: Arc<Mutex<RetryBudgetState>>,
+}
+
+#[derive(Debug, Default)]
+struct RetryBudgetState {
+    max_attempts: Option<u32>,
+    used: u32,
+    retry_not_before: Option<Instant>,
+}
+
+impl RetryBudget {
+    /// 创建最多允许 `max_attempts` 次上游请求的预算
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(RetryBudgetState {
+                max_attempts: Some(max_attempts),
+                ..Default::default()
+            })),
+        }
+    }
+
+    /// 申请一次上游请求，预算用完时返回 false
+    pub fn try_acquire(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.max_attempts.is_some_and(|max| state.used >= max) {
+            return false;
+        }
+        state.used += 1;
+        true
+    }
+
+    /// 已发送的上游请求次数
+    pub fn used(&self) -> u32 {
+        self.state.lock().unwrap().used
+    }
+
+    /// 预算是否已用完
+    pub fn is_exhausted(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.max_attempts.is_some_and(|max| state.used >= max)
+    }
+
+    /// 记录上游返回的 Retry-After
+    pub fn record_retry_after(&self, delay: Duration) {
+        self.state.lock().unwrap().retry_not_before = Some(Instant::now() + delay);
+    }
+
+    /// 距离上游允许重试还需等待的时长，没有等待要求时返回 None
+    pub fn retry_hint(&self) -> Option<Duration> {
+        let at = self.state.lock().unwrap().retry_not_before?;
+        let wait = at.saturating_duration_since(Instant::now());
+        (!wait.is_zero()).then_some(wait)
+    }
+
+    /// 距离上游允许重试还需等待的时长
+    pub fn retry_wait(&self) -> Duration {
+        self.retry_hint().unwrap_or(Duration::ZERO)
+    }
+}
+
+/// 一次调度内缓存的消息列表 JSON
+///
+/// 长上下文请求的序列化开销主要在消息列表上，而各供应商的请求体使用同一个 `Message` 结构。
+/// 缓存后调度器重试和 fallback 到其它供应商时只重新序列化模型、参数等供应商相关字段。
+/// 消息内容的哈希或请求体格式与缓存不一致时（例如同一任务中的其它请求）重新序列化
+#[derive(Debug, Clone, Default)]
+pub struct SerializedMessages {
+    cached: Arc<Mutex<Option<CachedMessages>>>,
+}
+
+// 消息内容的哈希和请求体格式，用于确认缓存对应同一组消息和同一种请求体格式
+type MessagesFingerprint = (u64, MessageFormat);
+type CachedMessages = (MessagesFingerprint, Box<RawValue>);
+
+// 逐字段哈希，比序列化成 JSON 便宜得多；工具调用参数按参数名排序后哈希
+fn messages_fingerprint(messages: &[Message], format: MessageFormat) -> MessagesFingerprint {
+    let mut hasher = DefaultHasher::new();
+    messages.len().hash(&mut hasher);
+    for message in messages {
+        (&message.role, &message.content, &message.thinking, &message.images).hash(&mut hasher);
+        (&message.tool_name, &message.tool_call_id).hash(&mut hasher);
+        message.tool_calls.as_ref().map(Vec::len).hash(&mut hasher);
+        for call in message.tool_calls.iter().flatten() {
+            (&call.id, &call.tool_type, &call.function.name).hash(&mut hasher);
+            let mut arguments: Vec<_> = call.function.arguments.iter().collect();
+            arguments.sort_by_key(|(name, _)| *name);
+            for (name, value) in arguments {
+                (name, value.to_string()).hash(&mut hasher);
+            }
+        }
+    }
+    (hasher.finish(), format)
+}
+
+impl SerializedMessages {
+    /// 序列化消息列表，缓存命中时直接写出缓存的 JSON
+    pub fn serialize<S: Serializer>(&self, messages: &[Message], format: MessageFormat, serializer: S) -> Result<S::Ok, S::Error> {
+        let fingerprint = messages_fingerprint(messages, format);
+        let mut cached = self.cached.lock().unwrap();
+        match cached.as_ref() {
+            Some((cached_fingerprint, _)) if *cached_fingerprint == fingerprint => {
+                metrics().incr_counter("llm_gateway_serialized_messages_reused_total", &[]);
+            }
+            _ => {
+                let raw = serde_json::value::to_raw_value(&WireMessages(messages, format)).map_err(serde::ser::Error::custom)?;
+                *cached = Some((fingerprint, raw));
+            }
+        }
+        cached.as_ref().map(|(_, raw)| raw).unwrap().serialize(serializer)
+    }
+}
+
+/// OpenAI 兼容请求体 `messages` 字段的序列化函数（`#[serde(serialize_with = ...)]`），
+/// 图片以 `image_url` 内容块、工具调用参数以 JSON 字符串传递，复用当前调度中已序列化的消息列表
+pub fn serialize_messages<S: Serializer>(messages: &[Message], serializer: S) -> Result<S::Ok, S::Error> {
+    serialize_messages_as(messages, MessageFormat::OpenAI, serializer)
+}
+
+/// Ollama 请求体 `messages` 字段的序列化函数，图片以 base64 列表放在 `images` 字段，工具调用参数为 JSON 对象
+pub fn serialize_ollama_messages<S: Serializer>(messages: &[Message], serializer: S) -> Result<S::Ok, S::Error> {
+    serialize_messages_as(messages, MessageFormat::Ollama, serializer)
+}
+
+fn serialize_messages_as<S: Serializer>(messages: &[Message], format: MessageFormat, serializer: S) -> Result<S::Ok, S::Error> {
+    match CALL_METADATA.try_with(|metadata| metadata.serialized_messages.clone()) {
+        Ok(serialized) => serialized.serialize(messages, format, serializer),
+        Err(_) => WireMessages(messages, format).serialize(serializer),
+    }
+}
+
+lazy_static! {
+    // 按共享连接池名称和连接配置缓存的 HTTP 客户端
+    static ref SHARED_HTTP_CLIENTS: Mutex<HashMap<SharedClientKey, HttpClient>> = Mutex::new(HashMap::new());
+
+    // 错误信息中的等待提示，例如 "Please try again in 20s"、"retry after 1.5 seconds"、"try again in 6m0s"
+    static ref RETRY_HINT_PATTERN: Regex = Regex::new(
+        r"(?i)(?:try again|retry)\s+(?:in|after)\s+(\d+(?:\.\d+)?(?:(?:ms|h|m|s)(?:\d+(?:\.\d+)?(?:ms|h|m|s))*)?)(?:\s*(milliseconds?|seconds?|secs?|minutes?|mins?)\b)?"
+    ).unwrap();
+}
+
+/// 从错误响应体中解析等待提示，超过 [`MAX_RETRY_WAIT`] 时取上限
+pub fn parse_retry_hint(body: &str) -> Option<Duration> {
+    let captures = RETRY_HINT_PATTERN.captures(body)?;
+    let amount = &captures[1];
+    let Some(unit) = captures.get(2) else {
+        return parse_reset_duration(amount);
+    };
+    let scale = match unit.as_str().to_lowercase().chars().next()? {
+        'm' if unit.as_str().to_lowercase().starts_with("mil") => 0.001,
+        'm' => 60.0,
+        _ => 1.0,
+    };
+    let value: f64 = amount.parse().ok()?;
+    Some(capped_wait(value * scale))
+}
+
+/// 解析 Retry-After 响应头：秒数或 HTTP 日期，超过 [`MAX_RETRY_WAIT`] 的秒数取上限
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<f64>() {
+        return (secs.is_finite() && secs >= 0.0).then(|| capped_wait(secs));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some((at.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().unwrap_or(Duration::ZERO))
+}
+
+/// 解析出的等待时间上限，超过重试的最长等待时间的提示本来就不会被采用，取上限避免转换溢出
+pub const MAX_RETRY_WAIT: Duration = Duration::from_secs(24 * 60 * 60);
+
+// 秒数转换为等待时间，过大时取上限
+fn capped_wait(secs: f64) -> Duration {
+    Duration::try_from_secs_f64(secs).map_or(MAX_RETRY_WAIT, |wait| wait.min(MAX_RETRY_WAIT))
+}
+
+// 响应头中的 Retry-After
+fn retry_after_header(response: &Response) -> Option<Duration> {
+    response.headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_retry_after)
+}
+
+tokio::task_local! {
+    /// 当前任务的调用记录附加信息
+    pub static CALL_METADATA: CallMetadata;
+}
+
+impl CallMetadata {
+    /// 获取当前任务的附加信息，未设置时返回默认值
+    pub fn current() -> Self {
+        CALL_METADATA.try_with(|metadata| metadata.clone()).unwrap_or_default()
+    }
+
+    /// 新一次调度的附加信息：只沿用上层（Web 处理器）设置的请求 ID、取消令牌、调用方、项目和调用记录收集器
+    pub fn inherited() -> Self {
+        let current = Self::current();
+        Self {
+            request_id: current.request_id,
+            cancellation: current.cancellation,
+            consumer_id: current.consumer_id,
+            project_id: current.project_id,
+            call_log_sink: current.call_log_sink,
+            ..Default::default()
+        }
+    }
+
+    /// 设置本次尝试使用的供应商和请求摘要（共享调用记录 ID 等状态）
+    pub fn with_attempt(mut self, provider: &str, request_summary: Option<String>) -> Self {
+        self.provider = Some(provider.to_string());
+        self.request_summary = request_summary;
+        self
+    }
+
+    /// 设置本次尝试使用的模型 ID
+    pub fn with_model_id(mut self, model_id: Option<String>) -> Self {
+        self.model_id = model_id;
+        self
+    }
+
+    /// 设置重试预算（替换而不是共享之前的预算）
+    pub fn with_retry_budget(mut self, retry_budget: RetryBudget) -> Self {
+        self.retry_budget = retry_budget;
+        self
+    }
+
+    /// 设置本次调用的请求超时
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// 设置网关请求 ID
+    pub fn with_request_id(mut self, request_id: Option<String>) -> Self {
+        self.request_id = request_id;
+        self
+    }
+
+    /// 设置取消令牌
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    /// 设置发起请求的调用方
+    pub fn with_consumer(mut self, consumer_id: Option<String>) -> Self {
+        self.consumer_id = consumer_id;
+        self
+    }
+
+    /// 设置发起请求的项目
+    pub fn with_project(mut self, project_id: Option<String>) -> Self {
+        self.project_id = project_id;
+        self
+    }
+
+    /// 发起请求的项目 ID，未设置时为 default 项目
+    pub fn project_id(&self) -> &str {
+        self.project_id.as_deref().unwrap_or(DEFAULT_PROJECT)
+    }
+
+    /// 设置本次尝试使用的 API Key ID
+    pub fn with_key_id(mut self, key_id: &str) -> Self {
+        self.key_id = Some(key_id.to_string());
+        self
+    }
+
+    /// 设置跨调度的调用记录收集器
+    pub fn with_call_log_sink(mut self, sink: Arc<Mutex<Vec<String>>>) -> Self {
+        self.call_log_sink = Some(sink);
+        self
+    }
+
+    /// 记录已写入的调用记录 ID
+    pub fn record_call_log(&self, id: &str) {
+        self.call_log_ids.lock().unwrap().push(id.to_string());
+        if let Some(sink) = &self.call_log_sink {
+            sink.lock().unwrap().push(id.to_string());
+        }
+    }
+
+    /// 最后写入的调用记录 ID（即最终返回响应的那次调用）
+    pub fn last_call_log_id(&self) -> Option<String> {
+        self.call_log_ids.lock().unwrap().last().cloned()
+    }
+
+    /// 记录供应商返回的原始计费信息（覆盖之前的记录）
+    pub fn record_provider_billing(&self, billing: ProviderBilling) {
+        *self.provider_billing.lock().unwrap() = Some(billing);
+    }
+
+    /// 最后记录的供应商原始计费信息
+    pub fn provider_billing(&self) -> Option<ProviderBilling> {
+        self.provider_billing.lock().unwrap().clone()
+    }
+}
+
 /// 请求上下文信息，用于日志记录和问题追踪
 #[derive(Debug, Clone)]
 pub struct RequestContext {
-    /// 请求唯一标识符
+    /// 网关请求 ID，沿用调度设置的请求 ID，单独使用客户端时自动生成
     pub request_id: String,
+    /// 本次调用的唯一标识符（调用记录 ID），fallback 等多次调用共用同一个请求 ID
+    pub call_id: String,
     /// 请求 URL
     pub url: String,
     /// 当前尝试次数
@@ -219,32 +842,50 @@ pub struct RequestContext {
     pub attempt_start_time: Instant,
     /// 重试原因
     pub retry_reason: Option<String>,
-    /// 模型 ID（用于调用记录）
+    /// 模型 ID（用于调用记录），默认取调度设置的模型 ID
     pub model_id: Option<String>,
+    /// 输入 token 数量（流式响应报告用量时记录）
+    pub tokens_input: i64,
     /// 输出 token 数量
     pub tokens_output: i64,
     /// 是否为流式请求
     pub is_stream: bool,
+    /// 调用记录附加信息
+    pub metadata: CallMetadata,
+    /// 序列化后的请求体，同一次调用的每次尝试复用
+    pub body: Bytes,
 }
 
 impl RequestContext {
     /// 创建新的请求上下文
     pub fn new(url: &str, max_attempts: u32, is_stream: bool) -> Self {
         let now = Instant::now();
+        let metadata = CallMetadata::current();
+        let call_id = Uuid::new_v4().to_string();
         Self {
-            request_id: Uuid::new_v4().to_string(),
+            request_id: metadata.request_id.clone().unwrap_or_else(|| call_id.clone()),
+            call_id,
             url: url.to_string(),
             attempt: 1,
             max_attempts,
             start_time: now,
             attempt_start_time: now,
             retry_reason: None,
-            model_id: None,
+            model_id: metadata.model_id.clone(),
+            tokens_input: 0,
             tokens_output: 0,
             is_stream,
+            metadata,
+            body: Bytes::new(),
         }
     }
 
+    /// 序列化并缓存请求体
+    pub fn serialize_body<T: Serialize>(&mut self, body: &T) -> Result<(), serde_json::Error> {
+        self.body = Bytes::from(serde_json::to_vec(body)?);
+        Ok(())
+    }
+
     /// 设置模型 ID
     pub fn set_model_id(&mut self, model_id: String) {
         self.model_id = Some(model_id);
@@ -255,6 +896,12 @@ impl RequestContext {
         self.tokens_output += tokens;
     }
 
+    /// 记录流式响应报告的累计用量（覆盖之前的记录）
+    pub fn set_stream_usage(&mut self, usage: StreamUsage) {
+        self.tokens_input = usage.input_tokens;
+        self.tokens_output = usage.output_tokens;
+    }
+
     /// 开始新的重试尝试
     pub fn start_retry(&mut self, reason: String) {
         self.attempt += 1;
@@ -297,13 +944,130 @@ pub struct ClientMetrics {
     pub min_response_time: Duration,
 }
 
+/// 共享 HTTP 客户端的缓存键：连接池名称相同、但超时、连接或 TLS 配置不同的客户端不共用
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct SharedClientKey {
+    pool: String,
+    request_timeout: Duration,
+    connect_timeout: Duration,
+    user_agent: String,
+    connection: ConnectionConfig,
+    tls: TlsConfig,
+}
+
+/// 按客户端配置创建 HTTP 客户端
+fn build_http_client(config: &ClientConfig) -> Result<HttpClient, ClientError> {
+    let builder = HttpClient::builder()
+        .no_proxy()
+        .timeout(config.timeout.request_timeout)
+        .connect_timeout(config.timeout.connect_timeout)
+        .user_agent(&config.user_agent);
+    let builder = config.tls.apply(config.connection.apply(builder))?;
+    builder.build().map_err(|e| ClientError::Config {
+        message: format!("Failed to build HTTP client: {}", e),
+    })
+}
+
+/// 获取共享连接池的 HTTP 客户端，不存在时创建
+fn shared_http_client(pool: &str, config: &ClientConfig) -> Result<HttpClient, ClientError> {
+    let key = SharedClientKey {
+        pool: pool.to_string(),
+        request_timeout: config.timeout.request_timeout,
+        connect_timeout: config.timeout.connect_timeout,
+        user_agent: config.user_agent.clone(),
+        connection: config.connection.clone(),
+        tls: config.tls.clone(),
+    };
+    let mut clients = SHARED_HTTP_CLIENTS.lock().unwrap();
+    if let Some(client) = clients.get(&key) {
+        return Ok(client.clone());
+    }
+    let client = build_http_client(config)?;
+    clients.insert(key, client.clone());
+    Ok(client)
+}
+
+/// 流式响应的行缓冲：按字节累积，遇到换行再解码，避免多字节字符被数据块边界截断；
+/// 单行超出长度限制时返回错误，而不是无限增长
+pub(crate) struct LineBuffer {
+    bytes: Vec<u8>,
+    max_line_bytes: usize,
+}
+
+impl LineBuffer {
+    pub(crate) fn new(max_line_bytes: usize) -> Self {
+        Self { bytes: Vec::new(), max_line_bytes }
+    }
+
+    /// 追加一个数据块，返回其中完整的非空行（已去掉首尾空白）；
+    /// 上次剩余的未换行内容超出长度限制时返回错误
+    pub(crate) fn push(&mut self, chunk: &[u8]) -> Result<Vec<String>, ClientError> {
+        self.check_pending()?;
+        self.bytes.extend_from_slice(chunk);
+        let mut lines = Vec::new();
+        let mut start = 0;
+        while let Some(offset) = self.bytes[start..].iter().position(|&b| b == b'\n') {
+            let line = &self.bytes[start..start + offset];
+            if line.len() > self.max_line_bytes {
+                return Err(ClientError::StreamLineTooLong { limit: self.max_line_bytes });
+            }
+            let line = String::from_utf8_lossy(line);
+            let line = line.trim();
+            if !line.is_empty() {
+                lines.push(line.to_string());
+            }
+            start += offset + 1;
+        }
+        self.bytes.drain(..start);
+        Ok(lines)
+    }
+
+    /// 流结束时剩余的未换行内容
+    pub(crate) fn finish(self) -> Result<Option<String>, ClientError> {
+        self.check_pending()?;
+        let rest = String::from_utf8_lossy(&self.bytes);
+        let rest = rest.trim();
+        Ok((!rest.is_empty()).then(|| rest.to_string()))
+    }
+
+    fn check_pending(&self) -> Result<(), ClientError> {
+        if self.bytes.len() > self.max_line_bytes {
+            return Err(ClientError::StreamLineTooLong { limit: self.max_line_bytes });
+        }
+        Ok(())
+    }
+}
+
+/// 逐行返回的流式响应
+pub type LineStream = futures_util::stream::BoxStream<'static, Result<String, ClientError>>;
+
+/// 把逐行的流式响应解析为响应块，`parse` 返回 None 的行（空行、结束标记等）被跳过
+pub fn parse_line_stream<T, E, P>(lines: LineStream, parse: P) -> futures_util::stream::BoxStream<'static, Result<T, E>>
+where
+    T: Send + 'static,
+    E: From<ClientError> + Send + 'static,
+    P: Fn(&str) -> Option<T> + Send + 'static,
+{
+    use futures_util::StreamExt;
+
+    lines.filter_map(move |line| {
+        let item = match line {
+            Ok(line) => parse(&line).map(Ok),
+            Err(error) => Some(Err(E::from(error))),
+        };
+        futures_util::future::ready(item)
+    }).boxed()
+}
+
 /// 通用 HTTP 客户端
 /// 
 /// 提供带有超时、重试和监控功能的 HTTP 客户端封装
 #[derive(Debug, Clone)]
 pub struct BaseClient {
-    /// HTTP 客户端
+    /// HTTP 客户端（可能与同一连接池的其他客户端共用）
     client: HttpClient,
+    /// 每个请求附带的默认请求头
+    default_headers: reqwest::header::HeaderMap,
     /// 客户端配置
     config: ClientConfig,
     /// 监控指标
@@ -318,34 +1082,26 @@ impl BaseClient {
 
     /// 创建新的基础客户端，可注入自定义 HTTP 客户端（用于测试）
     pub fn new_with_client(config: ClientConfig, custom_client: Option<HttpClient>) -> Result<Self, ClientError> {
-        let client = if let Some(client) = custom_client {
-            client
-        } else {
-            let mut client_builder = HttpClient::builder()
-                .no_proxy()
-                .timeout(config.timeout.request_timeout)
-                .connect_timeout(config.timeout.connect_timeout)
-                .user_agent(&config.user_agent);
-
-            // 添加默认请求头
-            let mut default_headers = reqwest::header::HeaderMap::new();
-            for (key, value) in &config.default_headers {
-                if let (Ok(header_name), Ok(header_value)) = (
-                    reqwest::header::HeaderName::from_bytes(key.as_bytes()),
-                    reqwest::header::HeaderValue::from_str(value),
-                ) {
-                    default_headers.insert(header_name, header_value);
-                }
+        // 默认请求头随每个请求发送，而不是设置在 HTTP 客户端上，以便不同 API Key 的客户端共用连接池
+        let mut default_headers = reqwest::header::HeaderMap::new();
+        for (key, value) in &config.default_headers {
+            if let (Ok(header_name), Ok(header_value)) = (
+                reqwest::header::HeaderName::from_bytes(key.as_bytes()),
+                reqwest::header::HeaderValue::from_str(value),
+            ) {
+                default_headers.insert(header_name, header_value);
             }
-            client_builder = client_builder.default_headers(default_headers);
+        }
 
-            client_builder.build().map_err(|e| ClientError::Config {
-                message: format!("Failed to build HTTP client: {}", e),
-            })?
+        let client = match (custom_client, &config.shared_pool) {
+            (Some(client), _) => client,
+            (None, Some(pool)) => shared_http_client(pool, &config)?,
+            (None, None) => build_http_client(&config)?,
         };
 
         Ok(Self {
             client,
+            default_headers,
             config,
             metrics: Arc::new(Mutex::new(ClientMetrics::default())),
         })
@@ -358,11 +1114,16 @@ impl BaseClient {
 
 
 
-    /// 获取内部 HTTP 客户端
+    /// 获取内部 HTTP 客户端（不带默认请求头，发送请求时使用 [`BaseClient::request`]）
     pub fn http_client(&self) -> &HttpClient {
         &self.client
     }
 
+    /// 构建带默认请求头的请求
+    pub fn request(&self, method: reqwest::Method, url: &str) -> reqwest::RequestBuilder {
+        self.client.request(method, url).headers(self.default_headers.clone())
+    }
+
     /// 获取配置
     pub fn config(&self) -> &ClientConfig {
         &self.config
@@ -375,33 +1136,63 @@ impl BaseClient {
 
     /// 发送 POST 请求（非流式）
     pub async fn post<T>(&self, url: &str, body: T) -> Result<Response, ClientError>
+    where
+        T: Serialize + Clone,
+    {
+        self.post_with_timeout(url, body, None).await
+    }
+
+    /// 发送 POST 请求（非流式），`request_timeout` 覆盖本次调用每次尝试的超时；
+    /// 为 None 时依次使用调用附加信息中的超时和客户端配置的超时
+    pub async fn post_with_timeout<T>(&self, url: &str, body: T, request_timeout: Option<Duration>) -> Result<Response, ClientError>
     where
         T: Serialize + Clone,
     {
         let mut ctx = RequestContext::new(url, self.config.retry.max_attempts, false);
+        let request_timeout = self.request_timeout(&ctx, request_timeout);
         self.log_request_start(&ctx);
+        self.serialize_body(&mut ctx, &body)?;
 
         let mut last_error: Option<ClientError> = None;
 
         for _ in 1..=self.config.retry.max_attempts {
             // 如果不是第一次尝试，计算延迟并记录重试日志
             if ctx.attempt > 1 {
-                let delay = self.calculate_backoff_delay(ctx.attempt - 1);
+                let Some(delay) = self.retry_delay(&ctx) else {
+                    break;
+                };
                 self.log_retry_attempt(&ctx, delay);
-                sleep(delay).await;
+                if ctx.metadata.cancellation.run_until_cancelled(sleep(delay)).await.is_none() {
+                    return Err(self.cancelled(&ctx).await);
+                }
+            }
+
+            // 与调度器共享的重试预算用完时不再发送请求
+            if !ctx.metadata.retry_budget.try_acquire() {
+                warn!(request_id = %ctx.request_id, attempt = ctx.attempt, "Retry budget exhausted");
+                last_error.get_or_insert(ClientError::Internal {
+                    message: "Retry budget exhausted".to_string(),
+                });
+                break;
             }
 
-            // 发送请求
-            match timeout(
-                self.config.timeout.request_timeout,
-                self.client.post(url).json(&body).send()
-            ).await {
+            // 发送请求，取消时丢弃进行中的请求
+            let attempt = ctx.metadata.cancellation
+                .run_until_cancelled(timeout(request_timeout, self.send_attempt(&ctx, url)))
+                .await;
+            let Some(attempt) = attempt else {
+                return Err(self.cancelled(&ctx).await);
+            };
+            match attempt {
                 Ok(Ok(response)) => {
+                    self.record_key_quota(&ctx, &response);
                     let status_code = response.status().as_u16();
                     
                     // 检查响应状态码，如果是错误状态码则处理为错误
                     if !response.status().is_success() {
+                        let retry_after = retry_after_header(&response);
                         let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+                        self.record_retry_hint(&ctx, status_code, retry_after, &error_text);
                         
                         // 记录 API 错误
                         self.log_api_error(&ctx, &error_text, Some(status_code));
@@ -412,7 +1203,7 @@ impl BaseClient {
                         };
                         
                         // 检查是否应该重试
-                        if !self.should_retry(&api_error, ctx.attempt) {
+                        if !self.is_retryable_api_error(&api_error, &ctx) {
                             self.log_request_failure(&ctx, &api_error);
                             self.update_failure_metrics();
                             
@@ -473,10 +1264,10 @@ impl BaseClient {
                 }
                 Err(_) => {
                     // 超时错误
-                    self.log_timeout_error(&ctx, self.config.timeout.request_timeout);
+                    self.log_timeout_error(&ctx, request_timeout);
                     
                     let timeout_error = ClientError::Timeout {
-                        duration: self.config.timeout.request_timeout,
+                        duration: request_timeout,
                     };
                     
                     // 检查是否还能重试
@@ -503,6 +1294,7 @@ impl BaseClient {
         let retry_error = ClientError::RetryExhausted {
             attempts: ctx.attempt,
             last_error: format!("{}", final_error),
+            status_code: final_error.status_code(),
         };
         
         // 创建重试耗尽的调用记录
@@ -511,8 +1303,24 @@ impl BaseClient {
         Err(retry_error)
     }
 
-    /// 发送 POST 流式请求
-    pub async fn post_stream<T, F>(&self, url: &str, body: T, mut callback: F) -> Result<(), ClientError>
+    /// 发送 POST 流式请求，`accounting` 按供应商的流格式识别结束标记和用量
+    pub async fn post_stream<T, F>(&self, url: &str, body: T, accounting: &dyn StreamAccounting, callback: F) -> Result<(), ClientError>
+    where
+        T: Serialize + Clone,
+        F: FnMut(String) -> bool + Send,
+    {
+        self.post_stream_with_timeout(url, body, None, accounting, callback).await
+    }
+
+    /// 发送 POST 流式请求，`request_timeout` 只限制收到响应头之前的等待时间，不限制流的总时长
+    pub async fn post_stream_with_timeout<T, F>(
+        &self,
+        url: &str,
+        body: T,
+        request_timeout: Option<Duration>,
+        accounting: &dyn StreamAccounting,
+        mut callback: F,
+    ) -> Result<(), ClientError>
     where
         T: Serialize + Clone,
         F: FnMut(String) -> bool + Send,
@@ -520,7 +1328,9 @@ impl BaseClient {
         use futures_util::StreamExt;
         
         let mut ctx = RequestContext::new(url, self.config.retry.max_attempts, true);
+        let request_timeout = self.request_timeout(&ctx, request_timeout);
         self.log_request_start(&ctx);
+        self.serialize_body(&mut ctx, &body)?;
         
         let mut stream_completed = false;
 
@@ -529,21 +1339,40 @@ impl BaseClient {
         for _ in 1..=self.config.retry.max_attempts {
             // 如果不是第一次尝试，计算延迟并记录重试日志
             if ctx.attempt > 1 {
-                let delay = self.calculate_backoff_delay(ctx.attempt - 1);
+                let Some(delay) = self.retry_delay(&ctx) else {
+                    break;
+                };
                 self.log_retry_attempt(&ctx, delay);
-                sleep(delay).await;
+                if ctx.metadata.cancellation.run_until_cancelled(sleep(delay)).await.is_none() {
+                    return Err(self.cancelled(&ctx).await);
+                }
+            }
+
+            // 与调度器共享的重试预算用完时不再发送请求
+            if !ctx.metadata.retry_budget.try_acquire() {
+                warn!(request_id = %ctx.request_id, attempt = ctx.attempt, "Retry budget exhausted");
+                last_error.get_or_insert(ClientError::Internal {
+                    message: "Retry budget exhausted".to_string(),
+                });
+                break;
             }
 
-            // 发送流式请求
-            match timeout(
-                self.config.timeout.request_timeout,
-                self.client.post(url).json(&body).send()
-            ).await {
+            // 发送流式请求，取消时丢弃进行中的请求
+            let attempt = ctx.metadata.cancellation
+                .run_until_cancelled(timeout(request_timeout, self.send_attempt(&ctx, url)))
+                .await;
+            let Some(attempt) = attempt else {
+                return Err(self.cancelled(&ctx).await);
+            };
+            match attempt {
                 Ok(Ok(response)) => {
+                    self.record_key_quota(&ctx, &response);
                     // 检查响应状态
                     if !response.status().is_success() {
+                        let retry_after = retry_after_header(&response);
                         let status_code = response.status().as_u16();
                         let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+                        self.record_retry_hint(&ctx, status_code, retry_after, &error_text);
                         
                         // 记录 API 错误
                         self.log_api_error(&ctx, &error_text, Some(status_code));
@@ -553,7 +1382,7 @@ impl BaseClient {
                             status_code: Some(status_code),
                         };
                         
-                        if !self.should_retry(&api_error, ctx.attempt) || ctx.is_final_attempt() {
+                        if !self.is_retryable_api_error(&api_error, &ctx) || ctx.is_final_attempt() {
                             self.log_request_failure(&ctx, &api_error);
                             self.update_failure_metrics();
                             return Err(api_error);
@@ -567,7 +1396,7 @@ impl BaseClient {
 
                     // 处理流式响应
                     let mut stream = response.bytes_stream();
-                    let mut buffer = String::new();
+                    let mut buffer = LineBuffer::new(self.config.max_stream_line_bytes);
                     let mut total_chunks = 0;
                     
                     info!(
@@ -575,48 +1404,47 @@ impl BaseClient {
                         "Starting to process stream response"
                     );
                     
-                    while let Some(chunk_result) = stream.next().await {
+                    loop {
+                        // 取消时停止读取并关闭上游连接
+                        let Some(next) = ctx.metadata.cancellation.run_until_cancelled(stream.next()).await else {
+                            return Err(self.cancelled(&ctx).await);
+                        };
+                        let Some(chunk_result) = next else {
+                            break;
+                        };
                         match chunk_result {
                             Ok(chunk) => {
                                 total_chunks += 1;
-                                let chunk_str = String::from_utf8_lossy(&chunk);
-                                buffer.push_str(&chunk_str);
+                                let lines = match buffer.push(&chunk) {
+                                    Ok(lines) => lines,
+                                    Err(error) => return Err(self.stream_aborted(&ctx, error).await),
+                                };
                                 
                                 // 按行处理数据
-                                while let Some(line_end) = buffer.find('\n') {
-                                    let line = buffer[..line_end].trim().to_string();
-                                    buffer = buffer[line_end + 1..].to_string();
+                                for line in lines {
+                                    // 按供应商的流格式检查完成标记和用量
+                                    let accounted = accounting.parse_line(&line);
+                                    stream_completed |= accounted.done;
+                                    if let Some(usage) = accounted.usage {
+                                        ctx.set_stream_usage(usage);
+                                    }
                                     
-                                    if !line.is_empty() {
-                                        // 检查是否为完成标记（针对 Ollama 等支持 done 字段的响应）
-                                        if line.contains("\"done\":true") || line.contains("\"done\": true") {
-                                            stream_completed = true;
-                                            
-                                            // 尝试解析 JSON 以获取 token 信息
-                                            if let Ok(json_value) = serde_json::from_str::<serde_json::Value>(&line) {
-                                                if let Some(eval_count) = json_value.get("eval_count").and_then(|v| v.as_i64()) {
-                                                    ctx.add_tokens(eval_count);
-                                                }
-                                            }
-                                        }
+                                    // 调用回调函数，如果返回 false 则停止
+                                    if !callback(line) {
+                                        info!(
+                                            request_id = %ctx.request_id,
+                                            total_chunks = total_chunks,
+                                            "Stream processing stopped by callback"
+                                        );
+                                        self.log_request_success(&ctx);
+                                        self.update_success_metrics(ctx.total_elapsed());
                                         
-                                        // 调用回调函数，如果返回 false 则停止
-                                        if !callback(line) {
-                                            info!(
-                                                request_id = %ctx.request_id,
-                                                total_chunks = total_chunks,
-                                                "Stream processing stopped by callback"
-                                            );
-                                            self.log_request_success(&ctx);
-                                            self.update_success_metrics(ctx.total_elapsed());
-                                            
-                                            // 如果流式请求完成，创建调用记录
-                                            if stream_completed {
-                                                self.create_call_record(&ctx, 200, None).await;
-                                            }
-                                            
-                                            return Ok(());
+                                        // 如果流式请求完成，创建调用记录
+                                        if stream_completed {
+                                            self.create_call_record(&ctx, 200, None).await;
                                         }
+                                        
+                                        return Ok(());
                                     }
                                 }
                             }
@@ -645,8 +1473,17 @@ impl BaseClient {
                     }
                     
                     // 处理剩余的缓冲区内容
-                    if !buffer.trim().is_empty() {
-                        callback(buffer.trim().to_string());
+                    let rest = match buffer.finish() {
+                        Ok(rest) => rest,
+                        Err(error) => return Err(self.stream_aborted(&ctx, error).await),
+                    };
+                    if let Some(rest) = rest {
+                        let accounted = accounting.parse_line(&rest);
+                        stream_completed |= accounted.done;
+                        if let Some(usage) = accounted.usage {
+                            ctx.set_stream_usage(usage);
+                        }
+                        callback(rest);
                     }
                     
                     info!(
@@ -682,10 +1519,10 @@ impl BaseClient {
                 }
                 Err(_) => {
                     // 超时错误
-                    self.log_timeout_error(&ctx, self.config.timeout.request_timeout);
+                    self.log_timeout_error(&ctx, request_timeout);
                     
                     let timeout_error = ClientError::Timeout {
-                        duration: self.config.timeout.request_timeout,
+                        duration: request_timeout,
                     };
                     
                     if ctx.is_final_attempt() {
@@ -712,6 +1549,7 @@ impl BaseClient {
         let retry_error = ClientError::RetryExhausted {
             attempts: ctx.attempt,
             last_error: format!("{}", final_error),
+            status_code: final_error.status_code(),
         };
         
         // 创建流式请求重试耗尽的调用记录
@@ -720,6 +1558,73 @@ impl BaseClient {
         Err(retry_error)
     }
 
+    /// 发送 POST 流式请求，以 Stream 逐行返回；丢弃返回的流时停止读取上游
+    pub fn post_stream_iter<T, A>(&self, url: &str, body: T, accounting: A) -> LineStream
+    where
+        T: Serialize + Clone + Send + 'static,
+        A: StreamAccounting + 'static,
+    {
+        self.post_stream_iter_with_timeout(url, body, None, accounting)
+    }
+
+    /// 发送 POST 流式请求，以 Stream 逐行返回，`request_timeout` 与 `post_stream_with_timeout` 相同
+    pub fn post_stream_iter_with_timeout<T, A>(
+        &self,
+        url: &str,
+        body: T,
+        request_timeout: Option<Duration>,
+        accounting: A,
+    ) -> LineStream
+    where
+        T: Serialize + Clone + Send + 'static,
+        A: StreamAccounting + 'static,
+    {
+        // 在后台任务中读取上游：回调同步写入无界通道，接收端关闭后回调返回 false 即停止读取
+        let (sink, source) = tokio::sync::mpsc::unbounded_channel();
+        let client = self.clone();
+        let url = url.to_string();
+        let run = async move {
+            let result = client.post_stream_with_timeout(&url, body, request_timeout, &accounting, |line| {
+                sink.send(Ok(line)).is_ok()
+            }).await;
+            if let Err(error) = result {
+                let _ = sink.send(Err(error));
+            }
+        };
+
+        // 保留调用记录附加信息和链路上下文
+        let metadata = CallMetadata::current();
+        let trace_parent = TraceParent::current();
+        tokio::spawn(with_trace_parent(trace_parent, CALL_METADATA.scope(metadata, run)).instrument(tracing::Span::current()));
+
+        Box::pin(futures_util::stream::unfold(source, |mut source| async move {
+            let item = source.recv().await?;
+            Some((item, source))
+        }))
+    }
+
+    /// 流式响应无法继续读取（例如单行超出长度限制）：记录失败的调用记录
+    async fn stream_aborted(&self, ctx: &RequestContext, error: ClientError) -> ClientError {
+        self.log_request_failure(ctx, &error);
+        self.update_failure_metrics();
+        self.create_call_record(ctx, 0, Some(error.to_string())).await;
+        error
+    }
+
+    /// 请求被取消：记录取消状态的调用记录
+    async fn cancelled(&self, ctx: &RequestContext) -> ClientError {
+        info!(
+            request_id = %ctx.request_id,
+            url = %ctx.url,
+            attempt = ctx.attempt,
+            elapsed_ms = ctx.total_elapsed().as_millis(),
+            "Request cancelled"
+        );
+        let error = ClientError::Cancelled;
+        self.create_call_record(ctx, CALL_STATUS_CANCELLED, Some(error.to_string())).await;
+        error
+    }
+
     /// 计算回退延迟时间
     fn calculate_backoff_delay(&self, attempt: u32) -> Duration {
         let base_delay = self.config.retry.base_delay;
@@ -735,6 +1640,67 @@ impl BaseClient {
         std::cmp::min(delay, max_delay)
     }
 
+    /// 本次调用的超时：参数指定的超时优先，其次是调度器通过调用附加信息传入的超时
+    // 请求体只序列化一次，重试时复用
+    fn serialize_body<T: Serialize>(&self, ctx: &mut RequestContext, body: &T) -> Result<(), ClientError> {
+        ctx.serialize_body(body).map_err(|source| {
+            let error = ClientError::Serialization { source };
+            self.log_request_failure(ctx, &error);
+            self.update_failure_metrics();
+            error
+        })
+    }
+
+    // 在本次尝试的 span 中发送请求，并通过 traceparent 把链路传给上游
+    async fn send_attempt(&self, ctx: &RequestContext, url: &str) -> reqwest::Result<Response> {
+        let span = info_span!(
+            "llm_client.attempt",
+            request_id = %ctx.request_id,
+            provider = ctx.metadata.provider.as_deref(),
+            attempt = ctx.attempt,
+            stream = ctx.is_stream,
+            trace_id = field::Empty,
+            span_id = field::Empty,
+            status_code = field::Empty,
+        );
+        let mut request = self.request(reqwest::Method::POST, url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(ctx.body.clone());
+        if let Some(trace_parent) = span.in_scope(outbound_trace_parent) {
+            span.record("trace_id", trace_parent.trace_id.as_str());
+            span.record("span_id", trace_parent.parent_id.as_str());
+            request = request.header(TRACEPARENT_HEADER, trace_parent.to_header());
+        }
+        let result = request.send().instrument(span.clone()).await;
+        if let Ok(response) = &result {
+            span.record("status_code", response.status().as_u16());
+        }
+        result
+    }
+
+    fn request_timeout(&self, ctx: &RequestContext, request_timeout: Option<Duration>) -> Duration {
+        request_timeout
+            .or(ctx.metadata.timeout)
+            .unwrap_or(self.config.timeout.request_timeout)
+    }
+
+    /// 重试前的等待时间：上游给出等待提示时按提示等待，否则按退避策略；
+    /// 提示超过最大延迟时放弃重试（等待上限内重试仍会被拒绝）
+    fn retry_delay(&self, ctx: &RequestContext) -> Option<Duration> {
+        match ctx.metadata.retry_budget.retry_hint() {
+            Some(wait) if wait > self.config.retry.max_delay => None,
+            Some(wait) => Some(wait),
+            None => Some(self.calculate_backoff_delay(ctx.attempt - 1)),
+        }
+    }
+
+    /// API 错误是否可以重试：5xx 可以重试，429 在上游给出等待提示时也可以重试
+    fn is_retryable_api_error(&self, error: &ClientError, ctx: &RequestContext) -> bool {
+        self.should_retry(error, ctx.attempt)
+            || matches!(error, ClientError::LLMApi { status_code: Some(429), .. })
+                && ctx.metadata.retry_budget.retry_hint().is_some()
+    }
+
     /// 判断错误类型是否可以重试（不考虑重试次数限制）
     fn should_retry(&self, error: &ClientError, _attempt: u32) -> bool {
         match error {
@@ -809,6 +1775,35 @@ impl BaseClient {
         );
     }
 
+    /// 记录响应头中返回的 Key 剩余配额，供轮询提前切换 Key
+    fn record_key_quota(&self, ctx: &RequestContext, response: &Response) {
+        let Some(key_id) = &ctx.metadata.key_id else {
+            return;
+        };
+        let headers = response.headers();
+        if let Some(quota) = KeyQuota::from_headers(|name| headers.get(name).and_then(|v| v.to_str().ok())) {
+            info!(
+                request_id = %ctx.request_id,
+                key_id = %key_id,
+                remaining_requests = ?quota.remaining_requests,
+                remaining_tokens = ?quota.remaining_tokens,
+                "Recorded API key quota"
+            );
+            record_key_quota(key_id, quota);
+        }
+    }
+
+    /// 记录错误响应中的等待提示：优先使用 Retry-After 响应头，429/503 没有响应头时从响应体中解析
+    fn record_retry_hint(&self, ctx: &RequestContext, status_code: u16, retry_after: Option<Duration>, body: &str) {
+        let hint = retry_after.or_else(|| {
+            matches!(status_code, 429 | 503).then(|| parse_retry_hint(body)).flatten()
+        });
+        if let Some(delay) = hint {
+            info!(request_id = %ctx.request_id, retry_after_ms = delay.as_millis() as u64, "Upstream requested retry delay");
+            ctx.metadata.retry_budget.record_retry_after(delay);
+        }
+    }
+
     /// 记录请求成功日志
     fn log_request_success(&self, ctx: &RequestContext) {
         info!(
@@ -901,12 +1896,25 @@ impl BaseClient {
         // 获取数据库连接池
         if let Some(pool) = SQLITE_POOL.get() {
             let call_log = CallLog {
-                id: ctx.request_id.clone(),
+                id: ctx.call_id.clone(),
+                request_id: Some(ctx.request_id.clone()),
                 model_id: ctx.model_id.clone(),
                 status_code,
                 total_duration: ctx.total_elapsed().as_millis() as i64,
+                tokens_input: ctx.tokens_input,
                 tokens_output: ctx.tokens_output,
-                error_message,
+                cost: 0.0,
+                provider: ctx.metadata.provider.clone(),
+                key_id: ctx.metadata.key_id.clone(),
+                // 请求摘要和上游错误信息可能包含敏感信息，脱敏后写入
+                request_summary: redact_opt(ctx.metadata.request_summary.clone()),
+                finish_reason: None,
+                provider_request_id: None,
+                provider_usage: None,
+                error_message: redact_opt(error_message),
+                detected_language: ctx.metadata.detected_language.clone(),
+                project_id: ctx.metadata.project_id.clone(),
+                traffic_arm: ctx.metadata.traffic_arm.clone(),
                 created_at: None, // 将在数据库中设置为当前时间
             };
 
@@ -917,6 +1925,7 @@ impl BaseClient {
                     "Failed to create call log record"
                 );
             } else {
+                ctx.metadata.record_call_log(&call_log.id);
                 info!(
                     request_id = %ctx.request_id,
                     model_id = ctx.model_id.as_deref().unwrap_or("unknown"),
@@ -967,3 +1976,34 @@ pub trait LLMClientTrait {
 }
 
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_buffer_keeps_split_utf8() {
+        let mut buffer = LineBuffer::new(DEFAULT_MAX_STREAM_LINE_BYTES);
+        let text = "data: 你好\n\ndata: 世界".as_bytes();
+        // 在“你”的第二个字节处切开
+        let (first, second) = text.split_at(7);
+        assert!(buffer.push(first).unwrap().is_empty());
+        assert_eq!(buffer.push(second).unwrap(), vec!["data: 你好".to_string()]);
+        assert_eq!(buffer.finish().unwrap().as_deref(), Some("data: 世界"));
+    }
+
+    #[test]
+    fn test_line_buffer_limits_line_length() {
+        let mut buffer = LineBuffer::new(8);
+        assert_eq!(buffer.push(b"12345678\n1234").unwrap(), vec!["12345678".to_string()]);
+        // 未换行的内容超出限制时，在下一个数据块或流结束时报错
+        assert!(buffer.push(b"56789").unwrap().is_empty());
+        assert!(matches!(buffer.push(b"0"), Err(ClientError::StreamLineTooLong { limit: 8 })));
+        let mut buffer = LineBuffer::new(8);
+        buffer.push(b"123456789").unwrap();
+        assert!(matches!(buffer.finish(), Err(ClientError::StreamLineTooLong { limit: 8 })));
+
+        let mut buffer = LineBuffer::new(8);
+        assert!(matches!(buffer.push(b"123456789\n"), Err(ClientError::StreamLineTooLong { limit: 8 })));
+    }
+}
@@ -2,20 +2,50 @@
 //!
 //! 提供客户端池管理功能，支持并发访问和 API Key 轮询
 
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use serde::Serialize;
 use tokio::sync::{Mutex, Semaphore, OnceCell};
 use anyhow::Result;
 use tracing::{info, warn, error};
 
 use crate::llm_api::ali::client::{AliClient, AliChatRequest, AliChatResponse, AliStreamResponse, AliError};
-use crate::llm_api::utils::client::{BaseClient, ClientConfig};
-use crate::dao::provider_key_pool::preload::get_api_key_round_robin;
+use crate::llm_api::openai::client::{
+    OpenAIClient, OpenAIChatRequest, OpenAIChatResponse, OpenAIStreamResponse, OpenAIError,
+    OpenAICompletionRequest, OpenAICompletionResponse,
+};
+use crate::llm_api::azure::client::AzureOpenAIClient;
+use crate::llm_api::openai_compatible::client::{OpenAICompatibleClient, OpenAICompatibleConfig};
+use crate::llm_api::utils::client::{BaseClient, CallMetadata, ClientConfig, TlsConfig, CALL_METADATA};
+use crate::dao::provider_key_pool::preload::{get_project_api_key_round_robin, mark_key_unavailable, DEFAULT_KEY_COOLDOWN};
 
 /// 客户端池管理器
 pub struct ClientPool<T> {
     clients: Vec<Arc<Mutex<T>>>,
     semaphore: Arc<Semaphore>,
     current_index: std::sync::atomic::AtomicUsize,
+    /// 正在等待空闲客户端的请求数
+    waiting: AtomicUsize,
+}
+
+/// 客户端池的占用情况
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ClientPoolStats {
+    pub size: usize,
+    /// 空闲客户端数
+    pub available: usize,
+    /// 排队等待空闲客户端的请求数
+    pub waiting: usize,
+}
+
+// 等待结束（包括等待中的请求被取消）时减少排队计数
+struct WaitingGuard<'a>(&'a AtomicUsize);
+
+impl Drop for WaitingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
 }
 
 impl<T> ClientPool<T> {
@@ -25,12 +55,17 @@ impl<T> ClientPool<T> {
             clients: clients.into_iter().map(|c| Arc::new(Mutex::new(c))).collect(),
             semaphore: Arc::new(Semaphore::new(size)),
             current_index: std::sync::atomic::AtomicUsize::new(0),
+            waiting: AtomicUsize::new(0),
         }
     }
 
     /// 获取可用的客户端
     pub async fn acquire(&self) -> ClientGuard<T> {
-        let permit = self.semaphore.clone().acquire_owned().await.unwrap();
+        let permit = {
+            self.waiting.fetch_add(1, Ordering::Relaxed);
+            let _waiting = WaitingGuard(&self.waiting);
+            self.semaphore.clone().acquire_owned().await.unwrap()
+        };
         let index = self.current_index.fetch_add(1, std::sync::atomic::Ordering::Relaxed) % self.clients.len();
         let client = self.clients[index].clone();
         
@@ -44,6 +79,15 @@ impl<T> ClientPool<T> {
     pub fn size(&self) -> usize {
         self.clients.len()
     }
+
+    /// 获取池的占用情况
+    pub fn stats(&self) -> ClientPoolStats {
+        ClientPoolStats {
+            size: self.clients.len(),
+            available: self.semaphore.available_permits(),
+            waiting: self.waiting.load(Ordering::Relaxed),
+        }
+    }
 }
 
 /// 客户端守护，自动归还到池中
@@ -58,14 +102,31 @@ impl<T> ClientGuard<T> {
     }
 }
 
+/// 在调用记录附加信息中带上本次使用的 API Key ID 执行请求
+async fn with_key_metadata<F: std::future::Future>(key_id: &str, request: F) -> F::Output {
+    CALL_METADATA.scope(CallMetadata::current().with_key_id(key_id), request).await
+}
+
+// 轮询获取当前请求所属项目的 API Key
+async fn next_api_key(provider: &str) -> Option<(String, String)> {
+    get_project_api_key_round_robin(provider, CallMetadata::current().project_id()).await
+}
+
 /// 动态 API Key 的阿里云客户端
 pub struct DynamicAliClient {
     base_client: BaseClient,
     base_url: String,
+    extra_headers: HashMap<String, String>,
+    tls: TlsConfig,
 }
 
 impl DynamicAliClient {
     pub fn new() -> Result<Self> {
+        Self::new_with_base_url(AliClient::DEFAULT_BASE_URL.to_string())
+    }
+
+    /// 使用自定义 API 地址创建客户端
+    pub fn new_with_base_url(base_url: String) -> Result<Self> {
         let config = ClientConfig::new()
             .add_header("Content-Type".to_string(), "application/json".to_string());
         
@@ -73,10 +134,29 @@ impl DynamicAliClient {
         
         Ok(Self {
             base_client,
-            base_url: AliClient::DEFAULT_BASE_URL.to_string(),
+            base_url,
+            extra_headers: HashMap::new(),
+            tls: TlsConfig::default(),
         })
     }
 
+    /// 设置附加到每个请求的静态请求头
+    pub fn with_headers(mut self, headers: HashMap<String, String>) -> Self {
+        self.extra_headers = headers;
+        self
+    }
+
+    /// 设置 TLS 配置（自定义根证书、客户端证书）
+    pub fn with_tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = tls;
+        self
+    }
+
+    fn create_client(&self, api_key: String) -> Result<AliClient> {
+        let config = ClientConfig::new().add_headers(&self.extra_headers).with_tls(self.tls.clone());
+        AliClient::new_with_config(api_key, self.base_url.clone(), config)
+    }
+
     /// 执行聊天请求（自动获取和切换 Key）
     pub async fn chat_with_auto_key(&self, request: AliChatRequest) -> Result<AliChatResponse, AliError> {
         const MAX_RETRIES: usize = 3;
@@ -84,13 +164,13 @@ impl DynamicAliClient {
 
         for attempt in 0..MAX_RETRIES {
             // 获取下一个可用的 API Key
-            if let Some((api_key, key_id)) = get_api_key_round_robin("ali").await {
+            if let Some((api_key, key_id)) = next_api_key("ali").await {
                 info!("Using API key {} for attempt {}", key_id, attempt + 1);
                 
                 // 创建临时的 Ali 客户端进行请求
-                match AliClient::new(api_key) {
+                match self.create_client(api_key) {
                     Ok(temp_client) => {
-                        match temp_client.chat(request.clone()).await {
+                        match with_key_metadata(&key_id, temp_client.chat(request.clone())).await {
                             Ok(response) => {
                                 info!("Request succeeded with API key {}", key_id);
                                 return Ok(response);
@@ -98,11 +178,10 @@ impl DynamicAliClient {
                             Err(e) => {
                                 warn!("API Key {} 调用失败 (attempt {}): {}", key_id, attempt + 1, e);
                                 
-                                // 如果是频率限制错误，标记这个 key（可以扩展实现）
-                                let error_msg = e.to_string();
-                                if error_msg.contains("rate") || error_msg.contains("quota") {
+                                // 频率限制错误：暂时移出轮询，冷却结束后自动恢复
+                                if e.is_rate_limited() {
                                     warn!("API Key {} reached rate limit", key_id);
-                                    // TODO: 可以在这里标记 key 为暂时不可用
+                                    mark_key_unavailable("ali", &key_id, DEFAULT_KEY_COOLDOWN).await;
                                 }
                                 
                                 last_error = Some(e);
@@ -130,18 +209,21 @@ impl DynamicAliClient {
         F: FnMut(AliStreamResponse) -> bool + Send,
     {
         // 获取 API Key 并创建临时客户端进行流式调用
-        if let Some((api_key, key_id)) = get_api_key_round_robin("ali").await {
+        if let Some((api_key, key_id)) = next_api_key("ali").await {
             info!("Using API key {} for stream request", key_id);
             
-            match AliClient::new(api_key) {
+            match self.create_client(api_key) {
                 Ok(temp_client) => {
-                    match temp_client.chat_stream(request, callback).await {
+                    match with_key_metadata(&key_id, temp_client.chat_stream(request, callback)).await {
                         Ok(()) => {
                             info!("Stream request succeeded with API key {}", key_id);
                             Ok(())
                         }
                         Err(e) => {
                             warn!("Stream request failed with API key {}: {}", key_id, e);
+                            if e.is_rate_limited() {
+                                mark_key_unavailable("ali", &key_id, DEFAULT_KEY_COOLDOWN).await;
+                            }
                             Err(e)
                         }
                     }
@@ -159,6 +241,354 @@ impl DynamicAliClient {
     }
 }
 
+// OpenAI 格式的 Key 池客户端共用的逻辑：按轮询到的 Key 创建上游客户端，非流式请求失败时换 Key 重试，限流时让 Key 进入冷却
+struct KeyPoolRunner {
+    /// Key 池中的 provider 名称
+    provider: &'static str,
+    extra_headers: HashMap<String, String>,
+    tls: TlsConfig,
+}
+
+impl KeyPoolRunner {
+    fn new(provider: &'static str) -> Self {
+        Self { provider, extra_headers: HashMap::new(), tls: TlsConfig::default() }
+    }
+
+    fn config(&self) -> ClientConfig {
+        ClientConfig::new().add_headers(&self.extra_headers).with_tls(self.tls.clone())
+    }
+
+    fn no_key_error(&self) -> OpenAIError {
+        error!("No available API keys for provider '{}'", self.provider);
+        OpenAIError::Auth(format!("No available API keys for provider '{}'", self.provider))
+    }
+
+    // 执行请求，失败时换 Key 重试
+    async fn run_with_auto_key<C, T, F, Fut>(&self, create: impl Fn(String, ClientConfig) -> Result<C>, run: F) -> Result<T, OpenAIError>
+    where
+        F: Fn(C) -> Fut,
+        Fut: std::future::Future<Output = Result<T, OpenAIError>>,
+    {
+        const MAX_RETRIES: usize = 3;
+        let mut last_error = None;
+
+        for attempt in 0..MAX_RETRIES {
+            // 获取下一个可用的 API Key
+            let Some((api_key, key_id)) = next_api_key(self.provider).await else {
+                return Err(self.no_key_error());
+            };
+            info!("Using API key {} for attempt {}", key_id, attempt + 1);
+
+            let client = match create(api_key, self.config()) {
+                Ok(client) => client,
+                Err(e) => {
+                    error!("Failed to create {} client with key {}: {}", self.provider, key_id, e);
+                    last_error = Some(OpenAIError::Api(format!("Failed to create client: {}", e)));
+                    continue;
+                }
+            };
+
+            match with_key_metadata(&key_id, run(client)).await {
+                Ok(response) => {
+                    info!("Request succeeded with API key {}", key_id);
+                    return Ok(response);
+                }
+                // 请求本身无效时换 Key 也无法成功
+                Err(e @ OpenAIError::InvalidRequest(_)) => return Err(e),
+                Err(e) => {
+                    warn!("API Key {} 调用失败 (attempt {}): {}", key_id, attempt + 1, e);
+                    if e.is_rate_limited() {
+                        mark_key_unavailable(self.provider, &key_id, DEFAULT_KEY_COOLDOWN).await;
+                    }
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| OpenAIError::Api("All retries failed".to_string())))
+    }
+
+    // 流式请求只执行一次，限流时让 Key 进入冷却
+    async fn run_stream<C, F, Fut>(&self, create: impl FnOnce(String, ClientConfig) -> Result<C>, run: F) -> Result<(), OpenAIError>
+    where
+        F: FnOnce(C) -> Fut,
+        Fut: std::future::Future<Output = Result<(), OpenAIError>>,
+    {
+        let Some((api_key, key_id)) = next_api_key(self.provider).await else {
+            return Err(self.no_key_error());
+        };
+        info!("Using API key {} for stream request", key_id);
+
+        let client = create(api_key, self.config())
+            .map_err(|e| OpenAIError::Api(format!("Failed to create client for stream: {}", e)))?;
+        let result = with_key_metadata(&key_id, run(client)).await;
+        if let Err(e) = &result {
+            warn!("Stream request failed with API key {}: {}", key_id, e);
+            if e.is_rate_limited() {
+                mark_key_unavailable(self.provider, &key_id, DEFAULT_KEY_COOLDOWN).await;
+            }
+        }
+        result
+    }
+}
+
+/// 动态 API Key 的 OpenAI 客户端（从 Key 池轮询获取 "openai" 的 Key）
+pub struct DynamicOpenAIClient {
+    base_url: String,
+    keys: KeyPoolRunner,
+}
+
+impl DynamicOpenAIClient {
+    /// Key 池中的 provider 名称
+    const PROVIDER: &'static str = "openai";
+
+    pub fn new() -> Result<Self> {
+        Self::new_with_base_url(OpenAIClient::DEFAULT_BASE_URL.to_string())
+    }
+
+    /// 使用自定义 API 地址创建客户端（兼容 OpenAI 格式的服务）
+    pub fn new_with_base_url(base_url: String) -> Result<Self> {
+        Ok(Self { base_url, keys: KeyPoolRunner::new(Self::PROVIDER) })
+    }
+
+    /// 设置附加到每个请求的静态请求头
+    pub fn with_headers(mut self, headers: HashMap<String, String>) -> Self {
+        self.keys.extra_headers = headers;
+        self
+    }
+
+    /// 设置 TLS 配置（自定义根证书、客户端证书）
+    pub fn with_tls(mut self, tls: TlsConfig) -> Self {
+        self.keys.tls = tls;
+        self
+    }
+
+    fn create_client(&self, api_key: String, config: ClientConfig) -> Result<OpenAIClient> {
+        OpenAIClient::new_with_config(api_key, self.base_url.clone(), config)
+    }
+
+    /// 执行聊天请求（自动获取和切换 Key）
+    pub async fn chat_with_auto_key(&self, request: OpenAIChatRequest) -> Result<OpenAIChatResponse, OpenAIError> {
+        self.keys.run_with_auto_key(|api_key, config| self.create_client(api_key, config), |client| {
+            let request = request.clone();
+            async move { client.chat(request).await }
+        }).await
+    }
+
+    /// 执行流式聊天请求（自动获取 Key）
+    pub async fn chat_stream_with_auto_key<F>(&self, request: OpenAIChatRequest, callback: F) -> Result<(), OpenAIError>
+    where
+        F: FnMut(OpenAIStreamResponse) -> bool + Send,
+    {
+        self.keys.run_stream(|api_key, config| self.create_client(api_key, config), |client| async move {
+            client.chat_stream(request, callback).await
+        }).await
+    }
+
+    /// 执行文本补全请求（自动获取和切换 Key）
+    pub async fn complete_with_auto_key(&self, request: OpenAICompletionRequest) -> Result<OpenAICompletionResponse, OpenAIError> {
+        self.keys.run_with_auto_key(|api_key, config| self.create_client(api_key, config), |client| {
+            let request = request.clone();
+            async move { client.complete(request).await }
+        }).await
+    }
+
+    /// 执行流式文本补全请求（自动获取 Key）
+    pub async fn complete_stream_with_auto_key<F>(&self, request: OpenAICompletionRequest, callback: F) -> Result<(), OpenAIError>
+    where
+        F: FnMut(OpenAICompletionResponse) -> bool + Send,
+    {
+        self.keys.run_stream(|api_key, config| self.create_client(api_key, config), |client| async move {
+            client.complete_stream(request, callback).await
+        }).await
+    }
+}
+
+/// 动态 API Key 的 OpenAI 兼容客户端（从 Key 池轮询获取该供应商名称的 Key）；
+/// 不要求 API Key 的本地服务在 Key 池为空时不带认证请求头
+pub struct DynamicOpenAICompatibleClient {
+    provider: String,
+    config: OpenAICompatibleConfig,
+    requires_key: bool,
+    extra_headers: HashMap<String, String>,
+    tls: TlsConfig,
+}
+
+impl DynamicOpenAICompatibleClient {
+    pub fn new(provider: String, config: OpenAICompatibleConfig) -> Self {
+        Self { provider, config, requires_key: true, extra_headers: HashMap::new(), tls: TlsConfig::default() }
+    }
+
+    /// 设置是否要求 API Key
+    pub fn with_requires_key(mut self, requires_key: bool) -> Self {
+        self.requires_key = requires_key;
+        self
+    }
+
+    /// 设置附加到每个请求的静态请求头
+    pub fn with_headers(mut self, headers: HashMap<String, String>) -> Self {
+        self.extra_headers = headers;
+        self
+    }
+
+    /// 设置 TLS 配置（自定义根证书、客户端证书）
+    pub fn with_tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = tls;
+        self
+    }
+
+    // 获取下一个可用的 Key 并创建客户端，不要求 Key 且 Key 池为空时返回不带认证的客户端
+    async fn next_client(&self) -> Result<(OpenAICompatibleClient, Option<String>), OpenAIError> {
+        let (api_key, key_id) = match next_api_key(&self.provider).await {
+            Some((api_key, key_id)) => (Some(api_key), Some(key_id)),
+            None if !self.requires_key => (None, None),
+            None => {
+                error!("No available API keys for provider '{}'", self.provider);
+                return Err(OpenAIError::Auth(format!("No available API keys for provider '{}'", self.provider)));
+            }
+        };
+        let config = ClientConfig::new().add_headers(&self.extra_headers).with_tls(self.tls.clone());
+        let client = OpenAICompatibleClient::new_with_config(self.config.clone(), api_key, config)
+            .map_err(|e| OpenAIError::Api(format!("Failed to create client: {}", e)))?;
+        Ok((client, key_id))
+    }
+
+    // 执行请求，失败时换 Key 重试；不带 Key 时只执行一次
+    async fn run_with_auto_key<T, F, Fut>(&self, run: F) -> Result<T, OpenAIError>
+    where
+        F: Fn(OpenAICompatibleClient) -> Fut,
+        Fut: std::future::Future<Output = Result<T, OpenAIError>>,
+    {
+        const MAX_RETRIES: usize = 3;
+        let mut last_error = None;
+
+        for attempt in 0..MAX_RETRIES {
+            let (client, key_id) = self.next_client().await?;
+            let Some(key_id) = key_id else {
+                return run(client).await;
+            };
+            info!("Using API key {} for attempt {}", key_id, attempt + 1);
+
+            match with_key_metadata(&key_id, run(client)).await {
+                Ok(response) => return Ok(response),
+                // 请求本身无效时换 Key 也无法成功
+                Err(e @ OpenAIError::InvalidRequest(_)) => return Err(e),
+                Err(e) => {
+                    warn!("API Key {} 调用失败 (attempt {}): {}", key_id, attempt + 1, e);
+                    if e.is_rate_limited() {
+                        mark_key_unavailable(&self.provider, &key_id, DEFAULT_KEY_COOLDOWN).await;
+                    }
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| OpenAIError::Api("All retries failed".to_string())))
+    }
+
+    // 流式请求只执行一次，限流时让 Key 进入冷却
+    async fn run_stream<F, Fut>(&self, run: F) -> Result<(), OpenAIError>
+    where
+        F: FnOnce(OpenAICompatibleClient) -> Fut,
+        Fut: std::future::Future<Output = Result<(), OpenAIError>>,
+    {
+        let (client, key_id) = self.next_client().await?;
+        let Some(key_id) = key_id else {
+            return run(client).await;
+        };
+        let result = with_key_metadata(&key_id, run(client)).await;
+        if let Err(e) = &result {
+            warn!("Stream request failed with API key {}: {}", key_id, e);
+            if e.is_rate_limited() {
+                mark_key_unavailable(&self.provider, &key_id, DEFAULT_KEY_COOLDOWN).await;
+            }
+        }
+        result
+    }
+
+    /// 执行聊天请求（自动获取和切换 Key）
+    pub async fn chat_with_auto_key(&self, request: OpenAIChatRequest) -> Result<OpenAIChatResponse, OpenAIError> {
+        self.run_with_auto_key(|client| {
+            let request = request.clone();
+            async move { client.chat(request).await }
+        }).await
+    }
+
+    /// 执行流式聊天请求（自动获取 Key）
+    pub async fn chat_stream_with_auto_key<F>(&self, request: OpenAIChatRequest, callback: F) -> Result<(), OpenAIError>
+    where
+        F: FnMut(OpenAIStreamResponse) -> bool + Send,
+    {
+        self.run_stream(|client| async move { client.chat_stream(request, callback).await }).await
+    }
+
+    /// 执行文本补全请求（自动获取和切换 Key）
+    pub async fn complete_with_auto_key(&self, request: OpenAICompletionRequest) -> Result<OpenAICompletionResponse, OpenAIError> {
+        self.run_with_auto_key(|client| {
+            let request = request.clone();
+            async move { client.complete(request).await }
+        }).await
+    }
+
+    /// 执行流式文本补全请求（自动获取 Key）
+    pub async fn complete_stream_with_auto_key<F>(&self, request: OpenAICompletionRequest, callback: F) -> Result<(), OpenAIError>
+    where
+        F: FnMut(OpenAICompletionResponse) -> bool + Send,
+    {
+        self.run_stream(|client| async move { client.complete_stream(request, callback).await }).await
+    }
+}
+
+/// 动态 API Key 的 Azure OpenAI 客户端（从 Key 池轮询获取 "azure" 的 Key）
+pub struct DynamicAzureOpenAIClient {
+    endpoint: String,
+    api_version: String,
+    keys: KeyPoolRunner,
+}
+
+impl DynamicAzureOpenAIClient {
+    /// Key 池中的 provider 名称
+    const PROVIDER: &'static str = "azure";
+
+    pub fn new(endpoint: String, api_version: String) -> Self {
+        Self { endpoint, api_version, keys: KeyPoolRunner::new(Self::PROVIDER) }
+    }
+
+    /// 设置附加到每个请求的静态请求头
+    pub fn with_headers(mut self, headers: HashMap<String, String>) -> Self {
+        self.keys.extra_headers = headers;
+        self
+    }
+
+    /// 设置 TLS 配置（自定义根证书、客户端证书）
+    pub fn with_tls(mut self, tls: TlsConfig) -> Self {
+        self.keys.tls = tls;
+        self
+    }
+
+    fn create_client(&self, api_key: String, config: ClientConfig) -> Result<AzureOpenAIClient> {
+        AzureOpenAIClient::new_with_config(api_key, self.endpoint.clone(), self.api_version.clone(), config)
+    }
+
+    /// 执行聊天请求（自动获取和切换 Key），`request.model` 为部署名称
+    pub async fn chat_with_auto_key(&self, request: OpenAIChatRequest) -> Result<OpenAIChatResponse, OpenAIError> {
+        self.keys.run_with_auto_key(|api_key, config| self.create_client(api_key, config), |client| {
+            let request = request.clone();
+            async move { client.chat(request).await }
+        }).await
+    }
+
+    /// 执行流式聊天请求（自动获取 Key），`request.model` 为部署名称
+    pub async fn chat_stream_with_auto_key<F>(&self, request: OpenAIChatRequest, callback: F) -> Result<(), OpenAIError>
+    where
+        F: FnMut(OpenAIStreamResponse) -> bool + Send,
+    {
+        self.keys.run_stream(|api_key, config| self.create_client(api_key, config), |client| async move {
+            client.chat_stream(request, callback).await
+        }).await
+    }
+}
+
 /// 全局阿里云客户端池
 pub struct GlobalAliClientPool {
     pool: ClientPool<DynamicAliClient>,
@@ -211,6 +641,11 @@ impl GlobalAliClientPool {
     pub fn size(&self) -> usize {
         self.pool.size()
     }
+
+    /// 获取池的占用情况
+    pub fn stats(&self) -> ClientPoolStats {
+        self.pool.stats()
+    }
 }
 
 // 全局单例
@@ -250,5 +685,11 @@ mod tests {
         
         let pool = ClientPool::new(clients);
         assert_eq!(pool.size(), 2);
+
+        let guard = pool.acquire().await;
+        let stats = pool.stats();
+        assert_eq!((stats.available, stats.waiting), (1, 0));
+        drop(guard);
+        assert_eq!(pool.stats().available, 2);
     }
 }
@@ -0,0 +1,163 @@
+//! # 供应商并发上限
+//!
+//! 每个供应商一个信号量，限制同时进行的上游请求数（providers 表的 `max_concurrent_requests`），
+//! 避免响应缓慢的供应商占满任务、拖慢其他健康的供应商。超出上限的请求最多排队等待
+//! `DispatchConfig::concurrency_wait_ms`，按请求优先级放行，仍未获得槽位时返回 `LLMError::Overloaded`，
+//! 启用 fallback 时由备选供应商接管；等待时间为 0 时立即失败
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use serde::Serialize;
+use tracing::warn;
+
+use crate::llm_api::dispatcher::{LLMError, Provider, RequestPriority};
+use crate::llm_api::utils::priority_semaphore::{PriorityPermit, PrioritySemaphore};
+use crate::metrics::metrics;
+
+/// 供应商的并发槽位，释放（drop）时归还
+pub type ProviderPermit = PriorityPermit;
+
+/// 设置了上限的供应商的槽位使用情况
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProviderConcurrencyStats {
+    pub provider: String,
+    pub limit: usize,
+    /// 正在进行的请求数
+    pub in_flight: usize,
+    /// 排队等待槽位的请求数
+    pub waiting: usize,
+}
+
+struct ProviderLimit {
+    limit: usize,
+    semaphore: PrioritySemaphore,
+}
+
+/// 按供应商限制并发请求数，未设置上限的供应商不受限制
+#[derive(Default)]
+pub struct ProviderConcurrency {
+    limits: RwLock<HashMap<Provider, ProviderLimit>>,
+}
+
+impl ProviderConcurrency {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置供应商的并发上限，`None` 或 0 表示不限制；上限不变时保留进行中的请求占用的槽位
+    pub fn set_limit(&self, provider: &Provider, limit: Option<usize>) {
+        let mut limits = self.limits.write().unwrap();
+        match limit.filter(|limit| *limit > 0) {
+            Some(limit) if limits.get(provider).is_some_and(|current| current.limit == limit) => {}
+            Some(limit) => {
+                limits.insert(provider.clone(), ProviderLimit { limit, semaphore: PrioritySemaphore::new(limit) });
+            }
+            None => {
+                limits.remove(provider);
+            }
+        }
+    }
+
+    /// 按供应商批量设置上限，不在列表中的供应商取消限制
+    pub fn replace_limits(&self, limits: HashMap<Provider, usize>) {
+        let stale: Vec<Provider> = self.limits.read().unwrap().keys()
+            .filter(|provider| !limits.contains_key(provider))
+            .cloned()
+            .collect();
+        for provider in stale {
+            self.set_limit(&provider, None);
+        }
+        for (provider, limit) in limits {
+            self.set_limit(&provider, Some(limit));
+        }
+    }
+
+    /// 供应商的并发上限
+    pub fn limit(&self, provider: &Provider) -> Option<usize> {
+        self.limits.read().unwrap().get(provider).map(|limit| limit.limit)
+    }
+
+    /// 供应商正在进行的请求数（未设置上限时为 0）
+    pub fn in_flight(&self, provider: &Provider) -> usize {
+        self.limits.read().unwrap().get(provider)
+            .map_or(0, |limit| limit.semaphore.in_use())
+    }
+
+    /// 排队等待供应商槽位的请求数（未设置上限时为 0）
+    pub fn waiting(&self, provider: &Provider) -> usize {
+        self.limits.read().unwrap().get(provider)
+            .map_or(0, |limit| limit.semaphore.waiting())
+    }
+
+    /// 所有设置了上限的供应商的槽位使用情况，按供应商名称排序
+    pub fn stats(&self) -> Vec<ProviderConcurrencyStats> {
+        let mut stats: Vec<ProviderConcurrencyStats> = self.limits.read().unwrap().iter()
+            .map(|(provider, limit)| ProviderConcurrencyStats {
+                provider: provider.as_str().to_string(),
+                limit: limit.limit,
+                in_flight: limit.semaphore.in_use(),
+                waiting: limit.semaphore.waiting(),
+            })
+            .collect();
+        stats.sort_by(|a, b| a.provider.cmp(&b.provider));
+        stats
+    }
+
+    /// 获取并发槽位，最多等待 `wait`，高优先级的请求先放行；未设置上限时返回 `None`，超时返回 `Overloaded`
+    pub async fn acquire(&self, provider: &Provider, priority: RequestPriority, wait: Duration) -> Result<Option<ProviderPermit>, LLMError> {
+        let Some((limit, semaphore)) = self.limits.read().unwrap().get(provider)
+            .map(|limit| (limit.limit, limit.semaphore.clone()))
+        else {
+            return Ok(None);
+        };
+
+        match semaphore.acquire(priority, wait).await {
+            Some(permit) => Ok(Some(permit)),
+            None => {
+                metrics().incr_counter("llm_gateway_provider_overloaded_total", &[("provider", provider.as_str())]);
+                warn!(provider = %provider.as_str(), limit, "Provider concurrency limit reached");
+                Err(LLMError::Overloaded(format!(
+                    "provider {} already has {} requests in flight",
+                    provider.as_str(), limit
+                )))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_acquire_respects_limit() {
+        let concurrency = ProviderConcurrency::new();
+        let provider = Provider::Ollama;
+        assert!(concurrency.acquire(&provider, RequestPriority::Normal, Duration::ZERO).await.unwrap().is_none());
+
+        concurrency.set_limit(&provider, Some(1));
+        let permit = concurrency.acquire(&provider, RequestPriority::Normal, Duration::ZERO).await.unwrap();
+        assert!(permit.is_some());
+        assert_eq!(concurrency.in_flight(&provider), 1);
+        let overloaded = concurrency.acquire(&provider, RequestPriority::High, Duration::from_millis(10)).await;
+        assert!(matches!(overloaded, Err(LLMError::Overloaded(_))));
+        assert_eq!(concurrency.waiting(&provider), 0);
+        assert_eq!(concurrency.stats(), vec![ProviderConcurrencyStats {
+            provider: "ollama".to_string(),
+            limit: 1,
+            in_flight: 1,
+            waiting: 0,
+        }]);
+
+        // 上限不变时保留已占用的槽位
+        concurrency.set_limit(&provider, Some(1));
+        assert_eq!(concurrency.in_flight(&provider), 1);
+        drop(permit);
+        assert_eq!(concurrency.in_flight(&provider), 0);
+
+        concurrency.replace_limits(HashMap::new());
+        assert_eq!(concurrency.limit(&provider), None);
+    }
+}
@@ -0,0 +1,518 @@
+//! # 调用方配额
+//!
+//! 按调用方 Key（`/v1/chat/completions` 请求中 `Authorization: Bearer` 携带的凭据）限制每分钟请求数
+//! 和每天 token 数。Key 只用于区分调用方，以 SHA-256 指纹作为调用方 ID，不保存原文。
+//! 只有登记过的 Key（单独配置了配额或绑定了项目）按 Key 计数；未携带 Key 或未登记的 Key 按客户端地址计数
+//! （调用方 ID 如 `ip_203.0.113.7`），同样适用默认配额，随意更换 Key 不能绕过限制。
+//!
+//! 计数保存在内存中：请求数在检查时累加，token 数在调度拿到用量后累加，
+//! 后台任务定期把增量写回 `consumer_usage` 表并清理空闲的计数，启动时从表中恢复当天已用的请求数和 token 数。
+//! token 额度在请求前检查，跨过额度的那次请求仍会完成，之后的请求被拒绝。
+//!
+//! 配额保存在 system_configs（category 为 `consumer_quota`，key_name 为调用方 ID，`default` 为未单独配置的调用方使用的配额），
+//! value 为 JSON，例如 `{"requests_per_minute": 60, "tokens_per_day": 100000}`，字段省略表示不限制
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+use chrono::{DateTime, Local, TimeZone};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use tracing::{debug, warn};
+
+use crate::dao::consumer_usage::{add_consumer_usage, list_consumer_usage, ConsumerUsageFilter};
+use crate::dao::system_config::{
+    create_system_config, delete_system_config, get_system_config_by_key, list_system_configs_by_category,
+    update_system_config_value, SystemConfig,
+};
+
+/// 配额在 system_configs 中的 category
+pub const CONSUMER_QUOTA_CATEGORY: &str = "consumer_quota";
+
+/// 未单独配置的调用方使用的配额的 key_name
+pub const DEFAULT_CONSUMER_QUOTA: &str = "default";
+
+/// 调用方配额，None 表示不限制
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsumerQuota {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_per_minute: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens_per_day: Option<u64>,
+}
+
+impl ConsumerQuota {
+    pub fn is_unlimited(&self) -> bool {
+        self.requests_per_minute.is_none() && self.tokens_per_day.is_none()
+    }
+}
+
+/// 调用方 ID：Key 的 SHA-256 指纹前 16 位
+pub fn consumer_id(api_key: &str) -> String {
+    let digest = Sha256::digest(api_key.as_bytes());
+    let hex: String = digest.iter().take(8).map(|b| format!("{:02x}", b)).collect();
+    format!("ck_{}", hex)
+}
+
+/// 按客户端地址计数时的调用方 ID，地址未知时所有此类请求共用一个计数
+pub fn client_consumer_id(ip: Option<IpAddr>) -> String {
+    match ip {
+        Some(ip) => format!("ip_{}", ip),
+        None => "ip_unknown".to_string(),
+    }
+}
+
+/// 超出的额度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaLimit {
+    Requests,
+    Tokens,
+}
+
+impl QuotaLimit {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QuotaLimit::Requests => "requests",
+            QuotaLimit::Tokens => "tokens",
+        }
+    }
+}
+
+/// 检查请求时的配额状态，用于输出 `X-RateLimit-*` 响应头
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitStatus {
+    pub quota: ConsumerQuota,
+    /// 当前分钟内剩余的请求数
+    pub remaining_requests: Option<u64>,
+    /// 距离请求数重置的时间
+    pub reset_requests: Duration,
+    /// 当天剩余的 token 数
+    pub remaining_tokens: Option<u64>,
+    /// 距离 token 数重置（本地时间零点）的时间
+    pub reset_tokens: Duration,
+    /// 被拒绝时超出的额度
+    pub exceeded: Option<QuotaLimit>,
+}
+
+impl RateLimitStatus {
+    /// 被拒绝时客户端应等待的时间
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.exceeded.map(|limit| match limit {
+            QuotaLimit::Requests => self.reset_requests,
+            QuotaLimit::Tokens => self.reset_tokens,
+        })
+    }
+}
+
+/// 调用方当前的用量和配额
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsumerUsageStatus {
+    pub consumer_id: String,
+    pub quota: ConsumerQuota,
+    /// 统计日期，格式 YYYY-MM-DD
+    pub day: String,
+    pub requests_this_minute: u64,
+    pub requests_today: u64,
+    pub tokens_today: u64,
+}
+
+// 单个调用方的计数
+#[derive(Debug, Default)]
+struct ConsumerCounters {
+    minute: i64,
+    minute_requests: u64,
+    day: String,
+    day_requests: u64,
+    day_tokens: u64,
+}
+
+impl ConsumerCounters {
+    // 进入新的分钟或新的一天时清零对应计数
+    fn roll(&mut self, minute: i64, day: &str) {
+        if self.minute != minute {
+            self.minute = minute;
+            self.minute_requests = 0;
+        }
+        if self.day != day {
+            self.day = day.to_string();
+            self.day_requests = 0;
+            self.day_tokens = 0;
+        }
+    }
+}
+
+/// 尚未写回数据库的增量（请求数, token 数），按（调用方, 天）累计
+type PendingUsage = HashMap<(String, String), (u64, u64)>;
+
+/// 调用方配额计数器
+#[derive(Debug, Default)]
+pub struct ConsumerQuotaTracker {
+    quotas: RwLock<HashMap<String, ConsumerQuota>>,
+    counters: Mutex<HashMap<String, ConsumerCounters>>,
+    pending: Mutex<PendingUsage>,
+}
+
+impl ConsumerQuotaTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 替换全部配额配置
+    pub fn set_quotas(&self, quotas: HashMap<String, ConsumerQuota>) {
+        *self.quotas.write().unwrap() = quotas;
+    }
+
+    /// 调用方的配额：单独配置的优先，其次是默认配额
+    pub fn quota_for(&self, consumer_id: &str) -> ConsumerQuota {
+        let quotas = self.quotas.read().unwrap();
+        quotas.get(consumer_id)
+            .or_else(|| quotas.get(DEFAULT_CONSUMER_QUOTA))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// 调用方是否单独配置了配额
+    pub fn has_own_quota(&self, consumer_id: &str) -> bool {
+        consumer_id != DEFAULT_CONSUMER_QUOTA && self.quotas.read().unwrap().contains_key(consumer_id)
+    }
+
+    /// 检查并记录一次请求，超出配额时返回 Err
+    pub fn check_request(&self, consumer_id: &str) -> Result<RateLimitStatus, RateLimitStatus> {
+        self.check_request_at(consumer_id, Local::now())
+    }
+
+    fn check_request_at(&self, consumer_id: &str, now: DateTime<Local>) -> Result<RateLimitStatus, RateLimitStatus> {
+        let quota = self.quota_for(consumer_id);
+        let day = day_of(now);
+        let mut counters = self.counters.lock().unwrap();
+        let counter = counters.entry(consumer_id.to_string()).or_default();
+        counter.roll(now.timestamp().div_euclid(60), &day);
+
+        let exceeded = if quota.requests_per_minute.is_some_and(|limit| counter.minute_requests >= limit) {
+            Some(QuotaLimit::Requests)
+        } else if quota.tokens_per_day.is_some_and(|limit| counter.day_tokens >= limit) {
+            Some(QuotaLimit::Tokens)
+        } else {
+            None
+        };
+        if exceeded.is_none() {
+            counter.minute_requests += 1;
+            counter.day_requests += 1;
+            self.pending.lock().unwrap().entry((consumer_id.to_string(), day)).or_default().0 += 1;
+        }
+
+        let status = RateLimitStatus {
+            quota,
+            remaining_requests: quota.requests_per_minute.map(|limit| limit.saturating_sub(counter.minute_requests)),
+            reset_requests: Duration::from_secs(60 - now.timestamp().rem_euclid(60) as u64),
+            remaining_tokens: quota.tokens_per_day.map(|limit| limit.saturating_sub(counter.day_tokens)),
+            reset_tokens: until_next_day(now),
+            exceeded,
+        };
+        match exceeded {
+            Some(_) => Err(status),
+            None => Ok(status),
+        }
+    }
+
+    /// 累加调用方当天使用的 token 数
+    pub fn record_tokens(&self, consumer_id: &str, tokens: u64) {
+        let now = Local::now();
+        let day = day_of(now);
+        let mut counters = self.counters.lock().unwrap();
+        let counter = counters.entry(consumer_id.to_string()).or_default();
+        counter.roll(now.timestamp().div_euclid(60), &day);
+        counter.day_tokens += tokens;
+        self.pending.lock().unwrap().entry((consumer_id.to_string(), day)).or_default().1 += tokens;
+    }
+
+    /// 恢复调用方某天已用的请求数和 token 数（启动时从数据库加载），不计入待写回的增量
+    pub fn restore_usage(&self, consumer_id: &str, day: &str, requests: u64, tokens: u64) {
+        let mut counters = self.counters.lock().unwrap();
+        let counter = counters.entry(consumer_id.to_string()).or_default();
+        if counter.day != day {
+            counter.day = day.to_string();
+            counter.day_requests = 0;
+            counter.day_tokens = 0;
+        }
+        counter.day_requests += requests;
+        counter.day_tokens += tokens;
+    }
+
+    /// 当天有用量的调用方及其配额，按调用方 ID 排序
+    pub fn usage_snapshot(&self) -> Vec<ConsumerUsageStatus> {
+        let now = Local::now();
+        let day = day_of(now);
+        let minute = now.timestamp().div_euclid(60);
+        let counters = self.counters.lock().unwrap();
+        let mut statuses: Vec<ConsumerUsageStatus> = counters.iter()
+            .filter(|(_, counter)| counter.day == day)
+            .map(|(consumer_id, counter)| ConsumerUsageStatus {
+                consumer_id: consumer_id.clone(),
+                quota: self.quota_for(consumer_id),
+                day: day.clone(),
+                requests_this_minute: if counter.minute == minute { counter.minute_requests } else { 0 },
+                requests_today: counter.day_requests,
+                tokens_today: counter.day_tokens,
+            })
+            .collect();
+        statuses.sort_by(|a, b| a.consumer_id.cmp(&b.consumer_id));
+        statuses
+    }
+
+    /// 清理空闲的计数，返回清理的数量：前一天的计数，以及没有每日 token 额度且超过 `idle` 没有请求的计数。
+    /// 用量已计入待写回的增量，清理不影响 `consumer_usage`
+    pub fn evict_idle(&self, idle: Duration) -> usize {
+        self.evict_idle_at(idle, Local::now())
+    }
+
+    fn evict_idle_at(&self, idle: Duration, now: DateTime<Local>) -> usize {
+        let day = day_of(now);
+        let idle_before = now.timestamp().div_euclid(60) - (idle.as_secs() / 60) as i64;
+        let mut counters = self.counters.lock().unwrap();
+        let before = counters.len();
+        counters.retain(|consumer_id, counter| {
+            counter.day == day
+                && (counter.minute > idle_before || self.quota_for(consumer_id).tokens_per_day.is_some())
+        });
+        before - counters.len()
+    }
+
+    // 取出待写回的增量
+    fn take_pending(&self) -> PendingUsage {
+        std::mem::take(&mut *self.pending.lock().unwrap())
+    }
+
+    // 写回失败的增量放回，下一次再写
+    fn restore_pending(&self, failed: PendingUsage) {
+        let mut pending = self.pending.lock().unwrap();
+        for (key, (requests, tokens)) in failed {
+            let entry = pending.entry(key).or_default();
+            entry.0 += requests;
+            entry.1 += tokens;
+        }
+    }
+}
+
+fn day_of(now: DateTime<Local>) -> String {
+    now.format("%Y-%m-%d").to_string()
+}
+
+// 距离下一个本地时间零点的时间
+fn until_next_day(now: DateTime<Local>) -> Duration {
+    let tomorrow = now.date_naive().succ_opt().and_then(|day| day.and_hms_opt(0, 0, 0));
+    tomorrow
+        .and_then(|midnight| Local.from_local_datetime(&midnight).earliest())
+        .and_then(|midnight| (midnight - now).to_std().ok())
+        .unwrap_or(Duration::from_secs(24 * 60 * 60))
+}
+
+lazy_static! {
+    /// 全局调用方配额计数器
+    static ref GLOBAL_CONSUMER_QUOTAS: ConsumerQuotaTracker = ConsumerQuotaTracker::new();
+}
+
+/// 获取全局调用方配额计数器
+pub fn get_consumer_quotas() -> &'static ConsumerQuotaTracker {
+    &GLOBAL_CONSUMER_QUOTAS
+}
+
+// 解析配额 JSON
+fn parse_quota(consumer_id: &str, value: &str) -> Option<ConsumerQuota> {
+    match serde_json::from_str(value) {
+        Ok(quota) => Some(quota),
+        Err(e) => {
+            warn!(consumer_id = %consumer_id, value = %value, error = %e, "Ignoring invalid consumer quota");
+            None
+        }
+    }
+}
+
+/// 从 system_configs 重新加载全部配额
+pub async fn reload_consumer_quotas(pool: &SqlitePool) -> sqlx::Result<usize> {
+    let quotas: HashMap<String, ConsumerQuota> = list_system_configs_by_category(pool, CONSUMER_QUOTA_CATEGORY)
+        .await?
+        .into_iter()
+        .filter_map(|config| parse_quota(&config.key_name, &config.value).map(|quota| (config.key_name, quota)))
+        .collect();
+    let count = quotas.len();
+    get_consumer_quotas().set_quotas(quotas);
+    Ok(count)
+}
+
+/// 列出已配置的配额（调用方 ID, 配额）
+pub async fn list_consumer_quotas(pool: &SqlitePool) -> sqlx::Result<Vec<(String, ConsumerQuota)>> {
+    Ok(list_system_configs_by_category(pool, CONSUMER_QUOTA_CATEGORY)
+        .await?
+        .into_iter()
+        .filter_map(|config| parse_quota(&config.key_name, &config.value).map(|quota| (config.key_name, quota)))
+        .collect())
+}
+
+/// 设置调用方的配额并立即生效，`None` 表示删除单独的配置
+pub async fn set_consumer_quota(pool: &SqlitePool, consumer_id: &str, quota: Option<ConsumerQuota>) -> sqlx::Result<()> {
+    let existing = get_system_config_by_key(pool, CONSUMER_QUOTA_CATEGORY, consumer_id).await?;
+    let value = quota.map(|quota| serde_json::to_string(&quota).unwrap_or_else(|_| "{}".to_string()));
+    match (existing, value) {
+        (Some(config), None) => {
+            delete_system_config(pool, &config.id).await?;
+        }
+        (Some(_), Some(value)) => {
+            update_system_config_value(pool, CONSUMER_QUOTA_CATEGORY, consumer_id, &value).await?;
+        }
+        (None, Some(value)) => {
+            let config = SystemConfig {
+                id: uuid::Uuid::new_v4().to_string(),
+                category: CONSUMER_QUOTA_CATEGORY.to_string(),
+                key_name: consumer_id.to_string(),
+                value,
+                is_encrypted: false,
+                version: 1,
+                created_at: None,
+                updated_at: None,
+            };
+            create_system_config(pool, &config).await?;
+        }
+        (None, None) => {}
+    }
+    reload_consumer_quotas(pool).await?;
+    Ok(())
+}
+
+/// 从 `consumer_usage` 恢复当天已用的请求数和 token 数，返回恢复的调用方数量
+pub async fn load_consumer_usage(pool: &SqlitePool) -> sqlx::Result<usize> {
+    let today = day_of(Local::now());
+    let filter = ConsumerUsageFilter {
+        start: Some(today.clone()),
+        end: Some(today),
+        consumer_id: None,
+    };
+    let stats = list_consumer_usage(pool, &filter).await?;
+    for stat in &stats {
+        get_consumer_quotas().restore_usage(&stat.consumer_id, &stat.day, stat.request_count.max(0) as u64, stat.tokens.max(0) as u64);
+    }
+    Ok(stats.len())
+}
+
+/// 将累积的用量增量批量写回数据库，返回写回的记录数；写入失败的增量保留到下一次
+pub async fn flush_consumer_usage(pool: &SqlitePool) -> anyhow::Result<usize> {
+    let tracker = get_consumer_quotas();
+    let pending = tracker.take_pending();
+    if pending.is_empty() {
+        return Ok(0);
+    }
+
+    let mut flushed = 0;
+    let mut failed = HashMap::new();
+    let mut last_error = None;
+    for ((consumer_id, day), (requests, tokens)) in pending {
+        match add_consumer_usage(pool, &day, &consumer_id, requests as i64, tokens as i64).await {
+            Ok(_) => flushed += 1,
+            Err(e) => {
+                warn!(consumer_id = %consumer_id, error = %e, "Failed to flush consumer usage, will retry");
+                failed.insert((consumer_id, day), (requests, tokens));
+                last_error = Some(e);
+            }
+        }
+    }
+
+    if !failed.is_empty() {
+        tracker.restore_pending(failed);
+    }
+    debug!(rows = flushed, "Flushed consumer usage");
+
+    match last_error {
+        Some(e) => Err(e.into()),
+        None => Ok(flushed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker(quota: ConsumerQuota) -> ConsumerQuotaTracker {
+        let tracker = ConsumerQuotaTracker::new();
+        tracker.set_quotas(HashMap::from([(DEFAULT_CONSUMER_QUOTA.to_string(), quota)]));
+        tracker
+    }
+
+    #[test]
+    fn test_requests_per_minute_window() {
+        let tracker = tracker(ConsumerQuota { requests_per_minute: Some(2), tokens_per_day: None });
+        let now = Local.with_ymd_and_hms(2025, 3, 10, 9, 30, 15).unwrap();
+
+        assert_eq!(tracker.check_request_at("ck_a", now).unwrap().remaining_requests, Some(1));
+        assert_eq!(tracker.check_request_at("ck_a", now).unwrap().remaining_requests, Some(0));
+        let rejected = tracker.check_request_at("ck_a", now).unwrap_err();
+        assert_eq!(rejected.exceeded, Some(QuotaLimit::Requests));
+        assert_eq!(rejected.retry_after(), Some(Duration::from_secs(45)));
+
+        // 其他调用方和下一分钟不受影响
+        assert!(tracker.check_request_at("ck_b", now).is_ok());
+        assert!(tracker.check_request_at("ck_a", now + chrono::Duration::seconds(45)).is_ok());
+    }
+
+    #[test]
+    fn test_tokens_per_day() {
+        let tracker = tracker(ConsumerQuota { requests_per_minute: None, tokens_per_day: Some(100) });
+        let consumer = format!("ck_{}", uuid::Uuid::new_v4().simple());
+        assert_eq!(tracker.check_request(&consumer).unwrap().remaining_tokens, Some(100));
+
+        tracker.record_tokens(&consumer, 120);
+        let rejected = tracker.check_request(&consumer).unwrap_err();
+        assert_eq!(rejected.exceeded, Some(QuotaLimit::Tokens));
+        assert_eq!(rejected.remaining_tokens, Some(0));
+
+        // 单独配置的配额优先于默认配额
+        let mut quotas = HashMap::from([(DEFAULT_CONSUMER_QUOTA.to_string(), ConsumerQuota { requests_per_minute: None, tokens_per_day: Some(100) })]);
+        quotas.insert(consumer.clone(), ConsumerQuota { requests_per_minute: None, tokens_per_day: Some(1000) });
+        tracker.set_quotas(quotas);
+        assert_eq!(tracker.check_request(&consumer).unwrap().remaining_tokens, Some(880));
+
+        let pending = tracker.take_pending();
+        assert_eq!(pending.values().copied().fold((0, 0), |acc, v| (acc.0 + v.0, acc.1 + v.1)), (2, 120));
+    }
+
+    #[test]
+    fn test_evict_idle_counters() {
+        let tracker = tracker(ConsumerQuota { requests_per_minute: Some(10), tokens_per_day: None });
+        let mut quotas = HashMap::from([(DEFAULT_CONSUMER_QUOTA.to_string(), tracker.quota_for(DEFAULT_CONSUMER_QUOTA))]);
+        quotas.insert("ck_daily".to_string(), ConsumerQuota { requests_per_minute: None, tokens_per_day: Some(100) });
+        tracker.set_quotas(quotas);
+        let now = Local.with_ymd_and_hms(2025, 3, 10, 9, 30, 15).unwrap();
+        tracker.check_request_at("ck_idle", now).unwrap();
+        tracker.check_request_at("ck_daily", now).unwrap();
+        tracker.check_request_at("ck_active", now + chrono::Duration::minutes(9)).unwrap();
+
+        // 每日 token 额度的计数保留到当天结束
+        assert_eq!(tracker.evict_idle_at(Duration::from_secs(5 * 60), now + chrono::Duration::minutes(10)), 1);
+        let counters = tracker.counters.lock().unwrap();
+        assert!(!counters.contains_key("ck_idle"));
+        assert!(counters.contains_key("ck_daily") && counters.contains_key("ck_active"));
+        drop(counters);
+        assert_eq!(tracker.evict_idle_at(Duration::from_secs(5 * 60), now + chrono::Duration::days(1)), 2);
+        assert!(tracker.has_own_quota("ck_daily"));
+        assert!(!tracker.has_own_quota("ck_idle"));
+        assert!(!tracker.has_own_quota(DEFAULT_CONSUMER_QUOTA));
+    }
+
+    #[test]
+    fn test_client_consumer_id() {
+        assert_eq!(client_consumer_id(Some("203.0.113.7".parse().unwrap())), "ip_203.0.113.7");
+        assert_eq!(client_consumer_id(None), "ip_unknown");
+    }
+
+    #[test]
+    fn test_consumer_id_is_fingerprint() {
+        let id = consumer_id("sk-test-key");
+        assert_eq!(id, consumer_id("sk-test-key"));
+        assert_ne!(id, consumer_id("sk-other-key"));
+        assert_eq!(id.len(), 19);
+        assert!(!id.contains("sk-test-key"));
+    }
+}
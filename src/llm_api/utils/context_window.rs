@@ -0,0 +1,158 @@
+//! # 对话历史截断
+//!
+//! 会话历史超过 token 上限时，按截断策略丢弃较早的消息后再发送给模型。
+//! system 消息和最后一条消息始终保留；token 数按字符粗略估算（英文约 4 个字符一个 token，
+//! 中日韩等非 ASCII 字符每个算一个 token），每条消息额外计入固定开销
+
+use serde::{Deserialize, Serialize};
+
+use crate::llm_api::utils::msg_structure::Message;
+use crate::llm_api::utils::tokenizer::Encoding;
+
+/// 每条消息的格式开销（角色、分隔符）
+const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+
+/// 超出 token 上限时的截断策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TruncationStrategy {
+    /// 从最早的消息开始丢弃
+    #[default]
+    DropOldest,
+    /// 保留第一轮问答（通常包含任务背景），从第二轮开始丢弃
+    KeepFirstTurn,
+}
+
+impl TruncationStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TruncationStrategy::DropOldest => "drop_oldest",
+            TruncationStrategy::KeepFirstTurn => "keep_first_turn",
+        }
+    }
+
+    /// 从名称解析，未知名称返回 None
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "drop_oldest" => Some(TruncationStrategy::DropOldest),
+            "keep_first_turn" => Some(TruncationStrategy::KeepFirstTurn),
+            _ => None,
+        }
+    }
+}
+
+/// 估算文本的 token 数
+pub fn estimate_tokens(text: &str) -> usize {
+    Encoding::Heuristic.count(text)
+}
+
+/// 估算一条消息的 token 数
+pub fn estimate_message_tokens(message: &Message) -> usize {
+    estimate_tokens(&message.content) + MESSAGE_OVERHEAD_TOKENS
+}
+
+/// 截断后的历史和被丢弃的消息（按原顺序）
+#[derive(Debug, Clone)]
+pub struct TruncatedHistory {
+    pub messages: Vec<Message>,
+    pub dropped: Vec<Message>,
+}
+
+/// 按策略把消息截断到 `max_tokens` 以内；只剩必须保留的消息时即使超出上限也不再丢弃
+pub fn truncate_history(messages: Vec<Message>, max_tokens: usize, strategy: TruncationStrategy) -> TruncatedHistory {
+    truncate_history_by(messages, max_tokens, strategy, estimate_message_tokens)
+}
+
+/// 与 `truncate_history` 相同，使用指定的方法计算每条消息的 token 数
+pub fn truncate_history_by<F>(messages: Vec<Message>, max_tokens: usize, strategy: TruncationStrategy, count: F) -> TruncatedHistory
+where
+    F: Fn(&Message) -> usize,
+{
+    let tokens: Vec<usize> = messages.iter().map(&count).collect();
+    let mut total: usize = tokens.iter().sum();
+    if total <= max_tokens {
+        return TruncatedHistory { messages, dropped: Vec::new() };
+    }
+
+    // 必须保留的消息：system 消息、最后一条消息，以及 KeepFirstTurn 下的第一轮问答
+    let last = messages.len().saturating_sub(1);
+    let first_turn = match strategy {
+        TruncationStrategy::DropOldest => Vec::new(),
+        TruncationStrategy::KeepFirstTurn => {
+            let mut turn: Vec<usize> = Vec::new();
+            if let Some(user) = messages.iter().position(|m| m.role == "user") {
+                turn.push(user);
+                if let Some(reply) = messages.iter().skip(user + 1).position(|m| m.role == "assistant") {
+                    turn.push(user + 1 + reply);
+                }
+            }
+            turn
+        }
+    };
+    let pinned = |i: usize, message: &Message| message.role == "system" || i == last || first_turn.contains(&i);
+
+    let mut keep = vec![true; messages.len()];
+    for (i, message) in messages.iter().enumerate() {
+        if total <= max_tokens {
+            break;
+        }
+        if !pinned(i, message) {
+            keep[i] = false;
+            total -= tokens[i];
+        }
+    }
+
+    let (kept, dropped): (Vec<_>, Vec<_>) = messages.into_iter().zip(keep).partition(|(_, kept)| *kept);
+    TruncatedHistory {
+        messages: kept.into_iter().map(|(message, _)| message).collect(),
+        dropped: dropped.into_iter().map(|(message, _)| message).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history() -> Vec<Message> {
+        let mut messages = vec![Message::system("be brief".to_string())];
+        for i in 0..5 {
+            messages.push(Message::user(format!("question {} {}", i, "x".repeat(40))));
+            messages.push(Message::assistant(format!("answer {} {}", i, "y".repeat(40))));
+        }
+        messages.push(Message::user("last question".to_string()));
+        messages
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("hello world!"), 3);
+        assert_eq!(estimate_tokens("你好世界"), 4);
+    }
+
+    #[test]
+    fn test_truncate_history() {
+        let messages = history();
+        let untouched = truncate_history(messages.clone(), 10_000, TruncationStrategy::DropOldest);
+        assert!(untouched.dropped.is_empty());
+        assert_eq!(untouched.messages.len(), messages.len());
+
+        let truncated = truncate_history(messages.clone(), 60, TruncationStrategy::DropOldest);
+        assert!(!truncated.dropped.is_empty());
+        assert_eq!(truncated.dropped.len() + truncated.messages.len(), messages.len());
+        assert!(truncated.dropped[0].content.starts_with("question 0"));
+        assert_eq!(truncated.messages[0].role, "system");
+        assert_eq!(truncated.messages.last().unwrap().content, "last question");
+        assert!(truncated.messages[1].content.starts_with("question 4") || truncated.messages[1].content.starts_with("answer 4"));
+        assert!(truncated.messages.iter().map(estimate_message_tokens).sum::<usize>() <= 60);
+
+        let kept = truncate_history(messages, 60, TruncationStrategy::KeepFirstTurn);
+        assert!(kept.messages[1].content.starts_with("question 0"));
+        assert!(kept.messages[2].content.starts_with("answer 0"));
+        assert_eq!(kept.messages.last().unwrap().content, "last question");
+
+        // 上限小于必须保留的消息时只保留它们
+        let minimal = truncate_history(history(), 1, TruncationStrategy::DropOldest);
+        assert_eq!(minimal.messages.len(), 2);
+    }
+}
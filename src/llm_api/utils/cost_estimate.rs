@@ -0,0 +1,125 @@
+//! # 费用预估
+//!
+//! 按提示词 token 数和请求的 `max_tokens` 预估请求费用，单价取自模型目录。请求指定了 `max_cost` 时，
+//! 调度器在调用上游之前拒绝预估费用超出上限的请求；模型未配置单价时按 0 计算，不会被拦截
+
+use tracing::warn;
+
+use crate::api_types::v1::CostEstimate;
+use crate::dao::SQLITE_POOL;
+use crate::dao::model::{get_model_by_provider_and_name, Model};
+use crate::llm_api::dispatcher::{DispatchRequest, LLMError, TokenUsage};
+use crate::llm_api::utils::tokenizer::count_request_tokens;
+use crate::llm_api::utils::usage_recorder::compute_cost;
+use crate::metrics::metrics;
+
+/// 按给定的模型单价预估费用，回复 token 数取 `max_tokens`
+pub fn estimate_request_cost(request: &DispatchRequest, model: Option<&Model>) -> CostEstimate {
+    let prompt_tokens = count_request_tokens(request) as u32;
+    let completion_tokens = request.max_tokens.unwrap_or(0);
+    let prompt_cost = compute_cost(model, &TokenUsage { prompt_tokens, completion_tokens: 0, total_tokens: prompt_tokens });
+    let completion_cost = compute_cost(model, &TokenUsage { prompt_tokens: 0, completion_tokens, total_tokens: completion_tokens });
+    let estimated_cost = prompt_cost + completion_cost;
+    CostEstimate {
+        object: "cost_estimate".to_string(),
+        provider: request.provider.as_str().to_string(),
+        model: request.model.clone(),
+        prompt_tokens,
+        completion_tokens,
+        prompt_cost,
+        completion_cost,
+        estimated_cost,
+        priced: model.is_some_and(|m| m.cost_per_token_input.is_some() || m.cost_per_token_output.is_some()),
+        max_cost: request.max_cost,
+        exceeds_max_cost: request.max_cost.is_some_and(|max_cost| estimated_cost > max_cost),
+    }
+}
+
+/// 读取模型单价并预估费用；数据库不可用或查询失败时按未配置单价处理
+pub async fn estimate_cost(request: &DispatchRequest) -> CostEstimate {
+    let model = match SQLITE_POOL.get() {
+        Some(pool) => match get_model_by_provider_and_name(pool, request.provider.as_str(), &request.model).await {
+            Ok(model) => model,
+            Err(e) => {
+                warn!(provider = %request.provider.as_str(), model = %request.model, error = %e, "Failed to load model pricing");
+                None
+            }
+        },
+        None => None,
+    };
+    estimate_request_cost(request, model.as_ref())
+}
+
+/// 预估费用超过请求的 `max_cost` 时拒绝请求，未指定 `max_cost` 时不检查
+pub async fn ensure_within_max_cost(request: &DispatchRequest) -> Result<(), LLMError> {
+    if request.max_cost.is_none() {
+        return Ok(());
+    }
+    let estimate = estimate_cost(request).await;
+    if !estimate.exceeds_max_cost {
+        return Ok(());
+    }
+
+    metrics().incr_counter("llm_gateway_cost_limit_rejections_total", &[("provider", request.provider.as_str())]);
+    warn!(provider = %request.provider.as_str(), model = %request.model, estimated_cost = estimate.estimated_cost, "Estimated cost exceeds max_cost");
+    Err(LLMError::CostLimitExceeded(format!(
+        "estimated cost {:.6} of {}:{} ({} prompt tokens, {} completion tokens) exceeds max_cost {:.6}",
+        estimate.estimated_cost,
+        estimate.provider,
+        estimate.model,
+        estimate.prompt_tokens,
+        estimate.completion_tokens,
+        request.max_cost.unwrap_or_default(),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm_api::dispatcher::Provider;
+    use crate::llm_api::utils::msg_structure::Message;
+
+    fn priced_model() -> Model {
+        Model {
+            id: "m1".to_string(),
+            name: "priced".to_string(),
+            provider: "openai".to_string(),
+            model_type: "llm".to_string(),
+            base_url: None,
+            is_active: true,
+            health_status: None,
+            last_health_check: None,
+            health_check_interval_seconds: None,
+            cost_per_token_input: Some(0.001),
+            cost_per_token_output: Some(0.002),
+            function_tags: None,
+            config: None,
+            project_id: None,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    #[test]
+    fn test_estimate_request_cost() {
+        let request = DispatchRequest::new(Provider::OpenAI, "priced".to_string(), vec![Message::user("hello".to_string())])
+            .with_max_tokens(100);
+        let prompt_tokens = count_request_tokens(&request) as u32;
+        let estimate = estimate_request_cost(&request, Some(&priced_model()));
+        assert_eq!(estimate.prompt_tokens, prompt_tokens);
+        assert_eq!(estimate.completion_tokens, 100);
+        assert!((estimate.completion_cost - 0.2).abs() < 1e-9);
+        assert!((estimate.estimated_cost - (prompt_tokens as f64 * 0.001 + 0.2)).abs() < 1e-9);
+        assert!(estimate.priced);
+        assert!(!estimate.exceeds_max_cost);
+
+        let estimate = estimate_request_cost(&request.clone().with_max_cost(0.1), Some(&priced_model()));
+        assert!(estimate.exceeds_max_cost);
+
+        // 未配置单价时不超限
+        let estimate = estimate_request_cost(&request.with_max_cost(0.0), None);
+        assert_eq!(estimate.estimated_cost, 0.0);
+        assert!(!estimate.priced);
+        assert!(!estimate.exceeds_max_cost);
+    }
+}
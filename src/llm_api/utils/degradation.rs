@@ -0,0 +1,365 @@
+//! # 降级模式
+//!
+//! 上游供应商全部不可用时，网关进入降级模式：同一调用方、同一模型下与缓存中的历史请求内容完全相同
+//! （只忽略大小写、标点和多余空白）的请求返回缓存的响应，未命中的请求返回可配置的兜底消息，
+//! 使依赖网关的产品在故障期间仍可部分使用。响应缓存按归一化后的文本精确匹配，换一种说法的请求不会命中。
+//!
+//! 降级模式可以手动开启；也会在连续多次请求的所有候选供应商（请求的供应商和可用的 fallback）都因上游
+//! 不可用而失败后自动开启，固定了供应商或关闭了 fallback 的请求只在没有其他候选供应商时计入。
+//! 自动开启的降级持续一个冷却时间，之后放行请求探测上游，探测成功即退出降级，失败则重新计时
+
+use std::time::{Duration, Instant};
+use lazy_static::lazy_static;
+use serde::Serialize;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+use crate::dao::cache::cache::{CacheService, CacheStats};
+use crate::llm_api::dispatcher::{DispatchRequest, DispatchResponse};
+use crate::llm_api::utils::client::CallMetadata;
+use crate::metrics::metrics;
+use crate::notification::{notification_center, Notification, NotificationLevel};
+
+/// 降级响应的 finish_reason（兜底消息）
+pub const DEGRADED_FINISH_REASON: &str = "degraded";
+/// 响应缓存的有效期
+const RESPONSE_CACHE_TTL: Duration = Duration::from_secs(3600);
+/// 响应缓存的最大条目数
+const RESPONSE_CACHE_CAPACITY: u64 = 10_000;
+/// 响应缓存的名称（命中率指标的 `cache` 标签）
+pub const RESPONSE_CACHE_NAME: &str = "degraded_responses";
+/// 通知来源
+const NOTIFICATION_SOURCE: &str = "degradation";
+
+/// 降级模式配置
+#[derive(Debug, Clone)]
+pub struct DegradationConfig {
+    /// 缓存未命中时返回的兜底消息
+    pub fallback_message: String,
+    /// 连续多少次请求的所有供应商都失败后自动开启降级
+    pub failure_threshold: u32,
+    /// 自动降级的持续时间，结束后放行请求探测上游
+    pub cooldown: Duration,
+}
+
+impl Default for DegradationConfig {
+    fn default() -> Self {
+        Self {
+            fallback_message: "服务暂时不可用，请稍后重试。".to_string(),
+            failure_threshold: 3,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+/// 降级响应的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DegradedSource {
+    /// 响应缓存命中
+    Cache,
+    /// 兜底消息
+    Fallback,
+}
+
+impl DegradedSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cache => "cache",
+            Self::Fallback => "fallback",
+        }
+    }
+}
+
+/// 降级模式状态（用于管理接口展示）
+#[derive(Debug, Clone, Serialize)]
+pub struct DegradationStatus {
+    /// 当前是否返回降级响应
+    pub active: bool,
+    /// 是否手动开启
+    pub manual: bool,
+    /// 是否处于自动降级（含冷却结束后的探测阶段）
+    pub auto_triggered: bool,
+    /// 自动降级剩余冷却时间（秒）
+    pub cooldown_remaining_secs: Option<u64>,
+    /// 连续失败次数
+    pub consecutive_failures: u32,
+    pub fallback_message: String,
+}
+
+#[derive(Debug, Default)]
+struct DegradationState {
+    manual: bool,
+    consecutive_failures: u32,
+    // 自动降级的冷却结束时间，探测成功后清除
+    auto_until: Option<Instant>,
+}
+
+impl DegradationState {
+    fn is_active(&self, now: Instant) -> bool {
+        self.manual || self.auto_until.is_some_and(|until| now < until)
+    }
+}
+
+/// 降级模式控制器
+pub struct DegradationGuard {
+    config: RwLock<DegradationConfig>,
+    state: RwLock<DegradationState>,
+    responses: CacheService<String, DispatchResponse>,
+}
+
+impl DegradationGuard {
+    pub fn new(config: DegradationConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            state: RwLock::new(DegradationState::default()),
+            responses: CacheService::new(RESPONSE_CACHE_TTL, RESPONSE_CACHE_CAPACITY).with_name(RESPONSE_CACHE_NAME),
+        }
+    }
+
+    /// 更新降级配置
+    pub async fn set_config(&self, config: DegradationConfig) {
+        *self.config.write().await = config;
+    }
+
+    /// 更新兜底消息
+    pub async fn set_fallback_message(&self, message: String) {
+        self.config.write().await.fallback_message = message;
+    }
+
+    /// 手动开启或关闭降级模式，关闭时同时清除自动降级状态
+    pub async fn set_manual(&self, enabled: bool) {
+        let mut state = self.state.write().await;
+        state.manual = enabled;
+        if !enabled {
+            state.auto_until = None;
+            state.consecutive_failures = 0;
+        }
+        info!(enabled = enabled, "Degradation mode toggled manually");
+        Self::record_active(state.is_active(Instant::now()));
+    }
+
+    /// 当前是否应返回降级响应
+    pub async fn is_active(&self) -> bool {
+        self.state.read().await.is_active(Instant::now())
+    }
+
+    /// 响应缓存的命中率、移除数和条目数
+    pub async fn response_cache_stats(&self) -> CacheStats {
+        self.responses.stats().await
+    }
+
+    /// 当前状态
+    pub async fn status(&self) -> DegradationStatus {
+        let now = Instant::now();
+        let state = self.state.read().await;
+        DegradationStatus {
+            active: state.is_active(now),
+            manual: state.manual,
+            auto_triggered: state.auto_until.is_some(),
+            cooldown_remaining_secs: state.auto_until
+                .filter(|until| now < *until)
+                .map(|until| until.duration_since(now).as_secs()),
+            consecutive_failures: state.consecutive_failures,
+            fallback_message: self.config.read().await.fallback_message.clone(),
+        }
+    }
+
+    /// 记录一次成功的上游调用：缓存响应，并退出自动降级
+    pub async fn record_success(&self, request: &DispatchRequest, response: &DispatchResponse) {
+        self.responses.insert(cache_key(request), response.clone()).await;
+
+        let mut state = self.state.write().await;
+        state.consecutive_failures = 0;
+        if state.auto_until.take().is_some() {
+            info!("Upstream recovered, leaving degradation mode");
+            Self::record_active(state.manual);
+            drop(state);
+            notification_center()
+                .notify(Notification::new(
+                    NotificationLevel::Info,
+                    NOTIFICATION_SOURCE,
+                    "Gateway left degradation mode",
+                    "Upstream providers recovered, requests are forwarded again".to_string(),
+                ))
+                .await;
+        }
+    }
+
+    /// 记录一次所有候选供应商都不可用的请求，返回是否因此（重新）进入自动降级
+    pub async fn record_outage(&self) -> bool {
+        let config = self.config.read().await.clone();
+        let mut state = self.state.write().await;
+        state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+        if state.consecutive_failures < config.failure_threshold {
+            return false;
+        }
+
+        let newly_triggered = state.auto_until.is_none();
+        state.auto_until = Some(Instant::now() + config.cooldown);
+        Self::record_active(true);
+        drop(state);
+
+        if newly_triggered {
+            warn!(threshold = config.failure_threshold, "All providers failing, entering degradation mode");
+            notification_center()
+                .notify(Notification::new(
+                    NotificationLevel::Critical,
+                    NOTIFICATION_SOURCE,
+                    "Gateway entered degradation mode",
+                    format!(
+                        "{} consecutive requests failed on all providers; serving cached and fallback responses",
+                        config.failure_threshold
+                    ),
+                ))
+                .await;
+        }
+        true
+    }
+
+    /// 生成降级响应：优先返回缓存中内容相同（精确匹配归一化后的文本）请求的响应，否则返回兜底消息
+    pub async fn degraded_response(&self, request: &DispatchRequest) -> (DispatchResponse, DegradedSource) {
+        let (response, source) = match self.responses.get(&cache_key(request)).await {
+            Some(response) => (response, DegradedSource::Cache),
+            None => {
+                let response = DispatchResponse {
+                    content: self.config.read().await.fallback_message.clone(),
+                    provider: request.provider.clone(),
+                    model: request.model.clone(),
+                    usage: None,
+                    finish_reason: Some(DEGRADED_FINISH_REASON.to_string()),
+                    request_id: None,
+                    created_at: chrono::Utc::now().to_rfc3339(),
+                    total_duration: None,
+                    tool_calls: None,
+                    message: None,
+                    raw: None,
+                    warnings: None,
+                };
+                (response, DegradedSource::Fallback)
+            }
+        };
+        metrics().incr_counter("llm_gateway_degraded_responses_total", &[("source", source.as_str())]);
+        (response, source)
+    }
+
+    fn record_active(active: bool) {
+        metrics().set_gauge("llm_gateway_degradation_active", &[], if active { 1.0 } else { 0.0 });
+    }
+}
+
+impl Default for DegradationGuard {
+    fn default() -> Self {
+        Self::new(DegradationConfig::default())
+    }
+}
+
+// 响应缓存的 key：租户、调用方、终端用户、模型 + 归一化后的对话内容，缓存的响应不会返回给其他调用方或其他模型的请求。
+// 归一化只忽略大小写、标点和多余空白，使措辞相同但格式略有差异的请求命中同一条缓存；措辞不同的请求不会命中
+fn cache_key(request: &DispatchRequest) -> String {
+    let conversation: Vec<String> = request.messages.iter()
+        .map(|m| format!("{}:{}", m.role, normalize(&m.content)))
+        .collect();
+    format!(
+        "{}|{}|{}|{}/{}|{}",
+        request.tenant_id.as_deref().unwrap_or(""),
+        CallMetadata::current().consumer_id.as_deref().unwrap_or(""),
+        request.user.as_deref().unwrap_or(""),
+        request.provider.as_str(),
+        request.model,
+        conversation.join("\n")
+    )
+}
+
+fn normalize(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+lazy_static! {
+    /// 全局降级模式控制器
+    static ref GLOBAL_DEGRADATION_GUARD: DegradationGuard = DegradationGuard::default();
+}
+
+/// 获取全局降级模式控制器
+pub fn get_degradation_guard() -> &'static DegradationGuard {
+    &GLOBAL_DEGRADATION_GUARD
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm_api::dispatcher::Provider;
+    use crate::llm_api::utils::client::CALL_METADATA;
+    use crate::llm_api::utils::msg_structure::Message;
+
+    fn request(prompt: &str) -> DispatchRequest {
+        DispatchRequest::new(Provider::Ollama, "llama3.2".to_string(), vec![Message::user(prompt.to_string())])
+    }
+
+    #[test]
+    fn test_cache_key_normalizes_prompt() {
+        assert_eq!(cache_key(&request("What is Rust?")), cache_key(&request("  what is   rust ")));
+        assert_ne!(cache_key(&request("What is Rust?")), cache_key(&request("What is Go?")));
+
+        let mut tenant_request = request("What is Rust?");
+        tenant_request.tenant_id = Some("acme".to_string());
+        assert_ne!(cache_key(&tenant_request), cache_key(&request("What is Rust?")));
+
+        let mut user_request = request("What is Rust?");
+        user_request.user = Some("alice".to_string());
+        assert_ne!(cache_key(&user_request), cache_key(&request("What is Rust?")));
+
+        let mut model_request = request("What is Rust?");
+        model_request.model = "qwen2.5".to_string();
+        assert_ne!(cache_key(&model_request), cache_key(&request("What is Rust?")));
+    }
+
+    #[test]
+    fn test_cache_key_scoped_to_consumer() {
+        let key_for = |consumer: &str| {
+            let metadata = CallMetadata::default().with_consumer(Some(consumer.to_string()));
+            CALL_METADATA.sync_scope(metadata, || cache_key(&request("What is Rust?")))
+        };
+        assert_eq!(key_for("ck_a"), key_for("ck_a"));
+        assert_ne!(key_for("ck_a"), key_for("ck_b"));
+    }
+
+    #[tokio::test]
+    async fn test_auto_degradation_and_recovery() {
+        let guard = DegradationGuard::new(DegradationConfig {
+            failure_threshold: 2,
+            cooldown: Duration::from_millis(50),
+            ..Default::default()
+        });
+
+        assert!(!guard.record_outage().await);
+        assert!(!guard.is_active().await);
+        assert!(guard.record_outage().await);
+        assert!(guard.is_active().await);
+
+        let (response, source) = guard.degraded_response(&request("hi")).await;
+        assert_eq!(source, DegradedSource::Fallback);
+        assert_eq!(response.finish_reason.as_deref(), Some(DEGRADED_FINISH_REASON));
+
+        // 冷却结束后放行探测请求，探测成功后退出降级
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(!guard.is_active().await);
+        assert!(guard.status().await.auto_triggered);
+
+        let mut cached = response.clone();
+        cached.content = "hello".to_string();
+        cached.finish_reason = Some("stop".to_string());
+        guard.record_success(&request("hi"), &cached).await;
+        assert!(!guard.status().await.auto_triggered);
+
+        guard.set_manual(true).await;
+        let (response, source) = guard.degraded_response(&request("Hi!")).await;
+        assert_eq!(source, DegradedSource::Cache);
+        assert_eq!(response.content, "hello");
+    }
+}
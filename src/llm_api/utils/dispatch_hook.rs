@@ -0,0 +1,96 @@
+//! # 调度扩展钩子
+//!
+//! 嵌入网关的调用方可以通过 `LLMDispatcher::register_hook` 注册 [`DispatchHook`]，在不修改调度器的情况下
+//! 加入自定义的日志、计费或请求/响应改写逻辑。钩子按注册顺序执行：
+//!
+//! - `on_request`：路由（语言路由、路由脚本、流量拆分）之后、黑名单和转换插件之前执行，可以改写请求；
+//!   返回错误时请求中止，后面的钩子不再执行
+//! - `on_response`：非流式请求成功后执行，可以改写响应；返回错误时请求视为失败
+//! - `on_error`：请求失败（包括被钩子拒绝）后执行，只用于观察，不能改变错误
+//!
+//! 流式请求只执行 `on_request`，以及建立流失败时的 `on_error`
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::llm_api::dispatcher::{DispatchRequest, DispatchResponse, LLMError};
+
+/// 调度扩展钩子，未实现的方法默认不做任何处理
+#[async_trait]
+pub trait DispatchHook: Send + Sync {
+    /// 钩子名称，用于日志
+    fn name(&self) -> &str;
+
+    /// 调用上游之前执行，可以改写请求，返回错误时中止请求
+    async fn on_request(&self, _request: &mut DispatchRequest) -> Result<(), LLMError> {
+        Ok(())
+    }
+
+    /// 请求成功后执行，可以改写响应，返回错误时请求视为失败
+    async fn on_response(&self, _request: &DispatchRequest, _response: &mut DispatchResponse) -> Result<(), LLMError> {
+        Ok(())
+    }
+
+    /// 请求失败后执行
+    async fn on_error(&self, _request: &DispatchRequest, _error: &LLMError) {}
+}
+
+/// 按注册顺序排列的钩子
+#[derive(Clone, Default)]
+pub struct DispatchHooks {
+    hooks: Vec<Arc<dyn DispatchHook>>,
+}
+
+impl DispatchHooks {
+    pub fn push(&mut self, hook: Arc<dyn DispatchHook>) {
+        self.hooks.push(hook);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// 钩子名称（按执行顺序）
+    pub fn names(&self) -> Vec<String> {
+        self.hooks.iter().map(|hook| hook.name().to_string()).collect()
+    }
+
+    /// 依次执行 on_request，遇到错误立即返回
+    pub async fn on_request(&self, request: &mut DispatchRequest) -> Result<(), LLMError> {
+        for hook in &self.hooks {
+            hook.on_request(request).await?;
+        }
+        Ok(())
+    }
+
+    /// 成功时依次执行 on_response（某个钩子返回错误后转为失败），失败时依次执行 on_error
+    pub async fn on_result(
+        &self,
+        request: &DispatchRequest,
+        result: Result<DispatchResponse, LLMError>,
+    ) -> Result<DispatchResponse, LLMError> {
+        let result = match result {
+            Ok(mut response) => self.on_response(request, &mut response).await.map(|()| response),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = &result {
+            self.on_error(request, e).await;
+        }
+        result
+    }
+
+    async fn on_response(&self, request: &DispatchRequest, response: &mut DispatchResponse) -> Result<(), LLMError> {
+        for hook in &self.hooks {
+            hook.on_response(request, response).await?;
+        }
+        Ok(())
+    }
+
+    /// 依次执行 on_error
+    pub async fn on_error(&self, request: &DispatchRequest, error: &LLMError) {
+        for hook in &self.hooks {
+            hook.on_error(request, error).await;
+        }
+    }
+}
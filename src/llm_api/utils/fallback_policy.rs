@@ -0,0 +1,117 @@
+//! # Fallback 路由策略
+//!
+//! 主供应商失败后由策略决定依次尝试哪些供应商和模型。默认策略按模型别名把逻辑模型映射到
+//! 各供应商上的具体模型（如阿里云 `qwen-plus` 对应 Ollama `qwen2:7b`），没有映射的供应商沿用原模型名
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::llm_api::dispatcher::{DispatchRequest, Provider};
+
+/// Fallback 路由策略
+pub trait FallbackPolicy: Send + Sync + fmt::Debug {
+    /// 按尝试顺序返回备选的供应商和模型，`fallback_providers` 为配置的备选供应商
+    fn fallback_targets(&self, request: &DispatchRequest, fallback_providers: &[Provider]) -> Vec<(Provider, String)>;
+}
+
+/// 保持模型名不变，依次切换备选供应商
+#[derive(Debug, Clone, Default)]
+pub struct SameModelFallback;
+
+impl FallbackPolicy for SameModelFallback {
+    fn fallback_targets(&self, request: &DispatchRequest, fallback_providers: &[Provider]) -> Vec<(Provider, String)> {
+        fallback_providers.iter()
+            .filter(|provider| **provider != request.provider)
+            .map(|provider| (provider.clone(), request.model.clone()))
+            .collect()
+    }
+}
+
+/// 按模型别名切换备选供应商上的模型
+#[derive(Debug, Clone, Default)]
+pub struct ModelAliasFallback {
+    // 逻辑模型名 -> 各供应商上对应的模型
+    aliases: BTreeMap<String, Vec<(Provider, String)>>,
+}
+
+impl ModelAliasFallback {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加逻辑模型及其在各供应商上对应的模型，同名别名会被替换
+    pub fn with_alias(mut self, name: impl Into<String>, targets: Vec<(Provider, String)>) -> Self {
+        self.aliases.insert(name.into(), targets);
+        self
+    }
+
+    /// 查找模型所属的别名：直接使用逻辑模型名，或供应商和模型出现在某个别名中
+    pub fn resolve(&self, provider: &Provider, model: &str) -> Option<&[(Provider, String)]> {
+        self.aliases.get(model)
+            .or_else(|| {
+                self.aliases.values()
+                    .find(|targets| targets.iter().any(|(p, m)| p == provider && m == model))
+            })
+            .map(Vec::as_slice)
+    }
+}
+
+impl FallbackPolicy for ModelAliasFallback {
+    fn fallback_targets(&self, request: &DispatchRequest, fallback_providers: &[Provider]) -> Vec<(Provider, String)> {
+        let targets = self.resolve(&request.provider, &request.model);
+        fallback_providers.iter()
+            .filter(|provider| **provider != request.provider)
+            .map(|provider| {
+                let model = targets
+                    .and_then(|targets| targets.iter().find(|(p, _)| p == provider))
+                    .map(|(_, model)| model.clone())
+                    .unwrap_or_else(|| request.model.clone());
+                (provider.clone(), model)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm_api::utils::msg_structure::Message;
+
+    fn request(provider: Provider, model: &str) -> DispatchRequest {
+        DispatchRequest::new(provider, model.to_string(), vec![Message::user("hello".to_string())])
+    }
+
+    fn qwen_aliases() -> ModelAliasFallback {
+        ModelAliasFallback::new().with_alias("qwen", vec![
+            (Provider::Ali, "qwen-plus".to_string()),
+            (Provider::Ollama, "qwen2:7b".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_same_model_fallback_skips_original_provider() {
+        let targets = SameModelFallback.fallback_targets(&request(Provider::Ali, "qwen-plus"), &[Provider::Ollama, Provider::Ali]);
+        assert_eq!(targets, vec![(Provider::Ollama, "qwen-plus".to_string())]);
+    }
+
+    #[test]
+    fn test_model_alias_fallback_maps_provider_models() {
+        let policy = qwen_aliases();
+        let providers = [Provider::Ollama, Provider::Ali, Provider::OpenAI];
+
+        // 按供应商模型反查别名
+        let targets = policy.fallback_targets(&request(Provider::Ali, "qwen-plus"), &providers);
+        assert_eq!(targets, vec![
+            (Provider::Ollama, "qwen2:7b".to_string()),
+            (Provider::OpenAI, "qwen-plus".to_string()),
+        ]);
+
+        // 直接使用逻辑模型名
+        let targets = policy.fallback_targets(&request(Provider::Ollama, "qwen"), &providers);
+        assert_eq!(targets[0], (Provider::Ali, "qwen-plus".to_string()));
+
+        // 没有别名的模型沿用原模型名
+        let targets = policy.fallback_targets(&request(Provider::Ali, "qwen-max"), &providers);
+        assert_eq!(targets[0], (Provider::Ollama, "qwen-max".to_string()));
+    }
+}
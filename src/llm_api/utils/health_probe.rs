@@ -0,0 +1,87 @@
+//! # 模型健康探测
+//!
+//! 按供应商类型向上游发送轻量请求确认模型可用：Ollama 查询 `/api/tags` 并确认模型已下载，
+//! OpenAI 兼容服务（openai、ali）查询模型列表接口，只要求返回成功状态
+
+use std::time::Duration;
+use reqwest::Client;
+use serde_json::Value;
+
+use crate::dao::model::Model;
+use crate::config::gateway_config;
+
+/// 单次探测的超时时间
+pub const DEFAULT_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 供应商的探测方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeKind {
+    /// 查询 Ollama 已下载的模型
+    OllamaTags,
+    /// 查询 OpenAI 兼容格式的模型列表（需要 API Key）
+    CompatibleModels,
+}
+
+impl ProbeKind {
+    /// 根据供应商名称选择探测方式，不支持的供应商返回 None
+    pub fn for_provider(provider: &str) -> Option<Self> {
+        match provider {
+            "ollama" => Some(Self::OllamaTags),
+            "ali" | "openai" => Some(Self::CompatibleModels),
+            _ => None,
+        }
+    }
+}
+
+/// 供应商未配置 base_url 时使用的默认地址（启动配置中的 `providers.*_base_url`）
+pub fn default_base_url(provider: &str) -> Option<&'static str> {
+    gateway_config().providers.base_url(provider)
+}
+
+// 探测请求的地址
+fn probe_url(provider: &str, base_url: &str) -> String {
+    let base_url = base_url.trim_end_matches('/');
+    match provider {
+        "ollama" => format!("{}/api/tags", base_url),
+        "ali" => format!("{}/compatible-mode/v1/models", base_url),
+        _ => format!("{}/models", base_url),
+    }
+}
+
+// Ollama 模型名未带标签时对应 latest
+fn ollama_has_model(tags: &Value, name: &str) -> bool {
+    let latest = format!("{}:latest", name);
+    tags.get("models")
+        .and_then(|models| models.as_array())
+        .is_some_and(|models| {
+            models.iter()
+                .filter_map(|model| model.get("name").and_then(|n| n.as_str()))
+                .any(|tag| tag == name || tag == latest)
+        })
+}
+
+/// 探测模型是否可用，失败时返回原因
+pub async fn probe_model(client: &Client, model: &Model, base_url: &str, api_key: Option<&str>) -> Result<(), String> {
+    let kind = ProbeKind::for_provider(&model.provider)
+        .ok_or_else(|| format!("health probe not supported for provider '{}'", model.provider))?;
+
+    let mut request = client.get(probe_url(&model.provider, base_url)).timeout(DEFAULT_PROBE_TIMEOUT);
+    if kind == ProbeKind::CompatibleModels {
+        let api_key = api_key.ok_or_else(|| format!("no API key available for provider '{}'", model.provider))?;
+        request = request.bearer_auth(api_key);
+    }
+
+    let response = request.send().await.map_err(|e| format!("request failed: {}", e))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("unexpected status {}", status.as_u16()));
+    }
+
+    if kind == ProbeKind::OllamaTags {
+        let tags: Value = response.json().await.map_err(|e| format!("invalid tags response: {}", e))?;
+        if !ollama_has_model(&tags, &model.name) {
+            return Err(format!("model '{}' is not pulled", model.name));
+        }
+    }
+    Ok(())
+}
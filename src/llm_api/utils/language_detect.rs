@@ -0,0 +1,95 @@
+//! # 提示词语言检测
+//!
+//! 基于 whatlang 对用户消息进行快速语言检测，
+//! 检测结果用于按语言路由模型以及写入调用记录做统计分析
+
+use serde::{Deserialize, Serialize};
+use whatlang::{detect, Lang};
+
+use crate::llm_api::utils::msg_structure::Message;
+
+/// 参与检测的最大字符数，避免长对话拖慢检测
+const MAX_DETECT_CHARS: usize = 2000;
+
+/// 语言检测结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DetectedLanguage {
+    /// ISO 639-3 语言代码，例如 `cmn`、`eng`
+    pub code: String,
+    /// 语言英文名称
+    pub name: String,
+    /// 置信度 0.0-1.0
+    pub confidence: f64,
+    /// 检测结果是否可靠
+    pub is_reliable: bool,
+}
+
+impl DetectedLanguage {
+    fn from_lang(lang: Lang, confidence: f64, is_reliable: bool) -> Self {
+        Self {
+            code: lang.code().to_string(),
+            name: lang.eng_name().to_string(),
+            confidence,
+            is_reliable,
+        }
+    }
+
+    /// 是否为中文
+    pub fn is_chinese(&self) -> bool {
+        self.code == Lang::Cmn.code()
+    }
+}
+
+/// 检测一段文本的语言
+pub fn detect_language(text: &str) -> Option<DetectedLanguage> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+
+    detect(text).map(|info| DetectedLanguage::from_lang(info.lang(), info.confidence(), info.is_reliable()))
+}
+
+/// 检测对话提示词的语言
+///
+/// 只使用 user 消息，从最新一条开始向前拼接，直到达到检测字符上限
+pub fn detect_prompt_language(messages: &[Message]) -> Option<DetectedLanguage> {
+    let mut sample = String::new();
+    for message in messages.iter().rev().filter(|m| m.role == "user") {
+        if sample.chars().count() >= MAX_DETECT_CHARS {
+            break;
+        }
+        sample.push_str(&message.content);
+        sample.push('\n');
+    }
+
+    let sample: String = sample.chars().take(MAX_DETECT_CHARS).collect();
+    detect_language(&sample)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_chinese_and_english() {
+        let zh = detect_language("请帮我写一首关于春天的诗，要求押韵并且不少于八句。").unwrap();
+        assert!(zh.is_chinese());
+
+        let en = detect_language("Please write a short poem about the spring and the rain.").unwrap();
+        assert_eq!(en.code, "eng");
+        assert!(!en.is_chinese());
+    }
+
+    #[test]
+    fn test_detect_prompt_uses_user_messages() {
+        let messages = vec![
+            Message::system("You are a helpful assistant that always answers briefly.".to_string()),
+            Message::user("今天北京的天气怎么样？适合出去跑步吗？".to_string()),
+        ];
+        let detected = detect_prompt_language(&messages).unwrap();
+        assert!(detected.is_chinese());
+
+        assert!(detect_prompt_language(&[]).is_none());
+    }
+}
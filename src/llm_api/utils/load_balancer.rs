@@ -0,0 +1,275 @@
+//! # 多供应商负载均衡
+//!
+//! 同一模型由多个供应商提供时（例如 qwen 同时由阿里云和本地 Ollama 提供），按 `DispatchConfig.load_balance_policy`
+//! 选择供应商。调度器每次调用上游后记录该供应商/模型的耗时和成败，以指数移动平均（EWMA）维护最近的
+//! 延迟和错误率；启动时用最近一小时的调用记录预热。错误率过高的供应商在其它供应商可用时不会被选中
+//!
+//! | 策略 | 选择方式 |
+//! |------|----------|
+//! | `first`（默认） | 按供应商名称排序的第一个，不参考统计 |
+//! | `cheapest` | 模型单价（输入 + 输出）最低，单价相同时选更快的 |
+//! | `fastest` | 按错误率折算后的平均延迟最低；还没有统计的供应商优先尝试一次 |
+//! | `sticky` | 沿用该模型上次选中的供应商，直到其错误率过高再按 `fastest` 重新选择 |
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tracing::{debug, warn};
+
+use crate::dao::SQLITE_POOL;
+use crate::dao::call_log::get_call_logs_stats_by_provider_model_since;
+use crate::dao::model::get_model_by_provider_and_name;
+use crate::llm_api::dispatcher::Provider;
+
+/// 新样本的权重
+const EWMA_ALPHA: f64 = 0.2;
+
+/// 错误率达到该值（且样本数足够）时视为降级，其它供应商可用时不再选择
+pub const DEGRADED_ERROR_RATE: f64 = 0.5;
+
+/// 判断降级所需的最少样本数
+const MIN_SAMPLES: u64 = 3;
+
+/// 启动预热读取的调用记录时间范围（分钟）
+pub const WARM_UP_WINDOW_MINUTES: i64 = 60;
+
+/// 负载均衡策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadBalancePolicy {
+    #[default]
+    First,
+    Cheapest,
+    Fastest,
+    Sticky,
+}
+
+impl FromStr for LoadBalancePolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "first" => Ok(Self::First),
+            "cheapest" => Ok(Self::Cheapest),
+            "fastest" => Ok(Self::Fastest),
+            "sticky" => Ok(Self::Sticky),
+            other => bail!("load balance policy must be first, cheapest, fastest or sticky, got `{}`", other),
+        }
+    }
+}
+
+/// 供应商/模型最近的延迟和错误率
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ProviderModelStats {
+    pub samples: u64,
+    pub avg_latency_ms: f64,
+    pub error_rate: f64,
+}
+
+impl ProviderModelStats {
+    fn record(&mut self, latency_ms: f64, success: bool) {
+        let error = if success { 0.0 } else { 1.0 };
+        if self.samples == 0 {
+            self.avg_latency_ms = latency_ms;
+            self.error_rate = error;
+        } else {
+            self.avg_latency_ms += EWMA_ALPHA * (latency_ms - self.avg_latency_ms);
+            self.error_rate += EWMA_ALPHA * (error - self.error_rate);
+        }
+        self.samples += 1;
+    }
+
+    /// 错误率过高
+    pub fn is_degraded(&self) -> bool {
+        self.samples >= MIN_SAMPLES && self.error_rate >= DEGRADED_ERROR_RATE
+    }
+
+    /// 得到一次成功响应的期望耗时：平均延迟按成功率折算
+    pub fn score(&self) -> f64 {
+        self.avg_latency_ms / (1.0 - self.error_rate).max(0.05)
+    }
+}
+
+/// 按供应商/模型统计延迟和错误率，并按策略选择供应商
+#[derive(Debug, Default)]
+pub struct LoadBalancer {
+    stats: RwLock<HashMap<(Provider, String), ProviderModelStats>>,
+    sticky: RwLock<HashMap<String, Provider>>,
+}
+
+impl LoadBalancer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一次上游调用的耗时和成败
+    pub fn record(&self, provider: &Provider, model: &str, latency: Duration, success: bool) {
+        self.stats.write().unwrap()
+            .entry((provider.clone(), model.to_string()))
+            .or_default()
+            .record(latency.as_secs_f64() * 1000.0, success);
+    }
+
+    /// 供应商/模型当前的统计
+    pub fn stats(&self, provider: &Provider, model: &str) -> Option<ProviderModelStats> {
+        self.stats.read().unwrap().get(&(provider.clone(), model.to_string())).cloned()
+    }
+
+    /// 用调用记录的汇总覆盖统计，返回预热的供应商/模型数
+    pub async fn warm_up(&self, pool: &SqlitePool, since: &str) -> Result<usize> {
+        let rows = get_call_logs_stats_by_provider_model_since(pool, since).await?;
+        let mut stats = self.stats.write().unwrap();
+        let mut warmed = 0;
+        for row in rows.into_iter().filter(|row| row.total_calls > 0) {
+            stats.insert((Provider::from_name_or_custom(&row.provider), row.model), ProviderModelStats {
+                samples: row.total_calls as u64,
+                avg_latency_ms: row.avg_latency_ms.unwrap_or_default(),
+                error_rate: row.error_count as f64 / row.total_calls as f64,
+            });
+            warmed += 1;
+        }
+        Ok(warmed)
+    }
+
+    /// 按策略从候选供应商中选择，`prices` 为各供应商上该模型的单价（仅 cheapest 使用）；没有候选时返回 None
+    pub fn select(
+        &self,
+        policy: LoadBalancePolicy,
+        model: &str,
+        candidates: &[Provider],
+        prices: &HashMap<Provider, f64>,
+    ) -> Option<Provider> {
+        if candidates.len() <= 1 {
+            return candidates.first().cloned();
+        }
+
+        let stats = self.stats.read().unwrap();
+        let stats_of = |provider: &Provider| stats.get(&(provider.clone(), model.to_string())).cloned().unwrap_or_default();
+        // 全部降级时仍在所有候选中选择
+        let healthy: Vec<&Provider> = candidates.iter().filter(|provider| !stats_of(provider).is_degraded()).collect();
+        let eligible: Vec<&Provider> = if healthy.is_empty() { candidates.iter().collect() } else { healthy };
+        // 没有样本的供应商得分为 0，优先尝试
+        let score = |provider: &Provider| {
+            let stats = stats_of(provider);
+            if stats.samples == 0 { 0.0 } else { stats.score() }
+        };
+        let fastest = || eligible.iter().copied().min_by(|a, b| score(a).total_cmp(&score(b))).cloned();
+
+        match policy {
+            LoadBalancePolicy::First => candidates.first().cloned(),
+            LoadBalancePolicy::Fastest => fastest(),
+            LoadBalancePolicy::Cheapest => {
+                let price = |provider: &Provider| prices.get(provider).copied().unwrap_or_default();
+                eligible.iter().copied()
+                    .min_by(|a, b| price(a).total_cmp(&price(b)).then(score(a).total_cmp(&score(b))))
+                    .cloned()
+            }
+            LoadBalancePolicy::Sticky => {
+                let current = self.sticky.read().unwrap().get(model).cloned();
+                if let Some(provider) = current.filter(|provider| eligible.contains(&provider)) {
+                    return Some(provider);
+                }
+                let chosen = fastest()?;
+                debug!(model = %model, provider = %chosen.as_str(), "Sticky provider changed");
+                self.sticky.write().unwrap().insert(model.to_string(), chosen.clone());
+                Some(chosen)
+            }
+        }
+    }
+
+    /// 按策略选择供应商，cheapest 策略从模型目录读取单价
+    pub async fn choose(&self, policy: LoadBalancePolicy, model: &str, candidates: &[Provider]) -> Option<Provider> {
+        let mut prices = HashMap::new();
+        if policy == LoadBalancePolicy::Cheapest && candidates.len() > 1 {
+            for provider in candidates {
+                prices.insert(provider.clone(), model_price(provider, model).await);
+            }
+        }
+        self.select(policy, model, candidates, &prices)
+    }
+}
+
+/// 模型每 token 的输入和输出单价之和，未配置单价或读取失败时按 0 计算
+async fn model_price(provider: &Provider, model: &str) -> f64 {
+    let Some(pool) = SQLITE_POOL.get() else {
+        return 0.0;
+    };
+    match get_model_by_provider_and_name(pool, provider.as_str(), model).await {
+        Ok(Some(model)) => model.cost_per_token_input.unwrap_or(0.0) + model.cost_per_token_output.unwrap_or(0.0),
+        Ok(None) => 0.0,
+        Err(e) => {
+            warn!(provider = %provider.as_str(), model = %model, error = %e, "Failed to load model pricing");
+            0.0
+        }
+    }
+}
+
+lazy_static! {
+    /// 全局负载均衡统计
+    static ref GLOBAL_LOAD_BALANCER: LoadBalancer = LoadBalancer::new();
+}
+
+/// 获取全局负载均衡器
+pub fn get_load_balancer() -> &'static LoadBalancer {
+    &GLOBAL_LOAD_BALANCER
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record_many(balancer: &LoadBalancer, provider: &Provider, latency_ms: u64, successes: &[bool]) {
+        for success in successes {
+            balancer.record(provider, "qwen", Duration::from_millis(latency_ms), *success);
+        }
+    }
+
+    #[test]
+    fn test_fastest_prefers_low_latency_and_skips_degraded() {
+        let balancer = LoadBalancer::new();
+        let candidates = vec![Provider::Ali, Provider::Ollama];
+        let prices = HashMap::new();
+
+        // 没有统计时先尝试第一个
+        assert_eq!(balancer.select(LoadBalancePolicy::Fastest, "qwen", &candidates, &prices), Some(Provider::Ali));
+
+        record_many(&balancer, &Provider::Ali, 900, &[true; 5]);
+        record_many(&balancer, &Provider::Ollama, 200, &[true; 5]);
+        assert_eq!(balancer.select(LoadBalancePolicy::Fastest, "qwen", &candidates, &prices), Some(Provider::Ollama));
+
+        record_many(&balancer, &Provider::Ollama, 50, &[false; 5]);
+        assert!(balancer.stats(&Provider::Ollama, "qwen").unwrap().is_degraded());
+        assert_eq!(balancer.select(LoadBalancePolicy::Fastest, "qwen", &candidates, &prices), Some(Provider::Ali));
+        assert_eq!(balancer.select(LoadBalancePolicy::First, "qwen", &candidates, &prices), Some(Provider::Ali));
+    }
+
+    #[test]
+    fn test_cheapest_and_sticky() {
+        let balancer = LoadBalancer::new();
+        let candidates = vec![Provider::Ali, Provider::Ollama];
+        let prices = HashMap::from([(Provider::Ali, 0.002), (Provider::Ollama, 0.0)]);
+        assert_eq!(balancer.select(LoadBalancePolicy::Cheapest, "qwen", &candidates, &prices), Some(Provider::Ollama));
+
+        record_many(&balancer, &Provider::Ali, 500, &[true; 3]);
+        record_many(&balancer, &Provider::Ollama, 100, &[true; 3]);
+        assert_eq!(balancer.select(LoadBalancePolicy::Sticky, "qwen", &candidates, &prices), Some(Provider::Ollama));
+        // 变慢但未降级时保持不变
+        record_many(&balancer, &Provider::Ollama, 5_000, &[true; 3]);
+        assert_eq!(balancer.select(LoadBalancePolicy::Sticky, "qwen", &candidates, &prices), Some(Provider::Ollama));
+        // 降级后切换
+        record_many(&balancer, &Provider::Ollama, 100, &[false; 6]);
+        assert_eq!(balancer.select(LoadBalancePolicy::Sticky, "qwen", &candidates, &prices), Some(Provider::Ali));
+    }
+
+    #[test]
+    fn test_policy_from_str() {
+        assert_eq!("Fastest".parse::<LoadBalancePolicy>().unwrap(), LoadBalancePolicy::Fastest);
+        assert!("random".parse::<LoadBalancePolicy>().is_err());
+    }
+}
@@ -4,7 +4,39 @@ pub mod msg_structure;
 pub mod tool_structure;
 pub mod chat_traits;
 pub mod client;
+pub mod stream_accounting;
 pub mod client_pool;
+pub mod abuse_guard;
+pub mod language_detect;
+pub mod blocklist;
+pub mod transform_plugin;
+pub mod route_script;
+pub mod degradation;
+pub mod usage_recorder;
+pub mod budget;
+pub mod health_probe;
+pub mod fallback_policy;
+pub mod trace_context;
+pub mod cancellation;
+pub mod consumer_quota;
+pub mod project_scope;
+pub mod structured_output;
+pub mod context_window;
+pub mod tokenizer;
+pub mod redaction;
+pub mod cost_estimate;
+pub mod load_balancer;
+pub mod traffic_split;
+pub mod shadow;
+pub mod dispatch_hook;
+pub mod concurrency_limit;
+pub mod admission;
+pub mod priority_semaphore;
+pub mod system_prompt_policy;
+pub mod param_constraints;
+pub mod agent_loop;
+#[cfg(feature = "wasm-plugins")]
+pub mod wasm_plugin;
 
 // 从 dao::provider_key_pool 重新导出轮询相关函数
 pub use crate::dao::provider_key_pool::{
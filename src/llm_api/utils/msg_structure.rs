@@ -2,8 +2,9 @@
 //!
 //! 定义所有 LLM 客户端共用的消息结构体和相关类型
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
+use std::borrow::Cow;
 use std::collections::HashMap;
 
 /// 工具调用结构体
@@ -26,12 +27,13 @@ pub struct ToolCall {
 pub struct Message {
     /// 消息角色：system、user、assistant、tool
     pub role: String,
-    /// 消息内容文本
+    /// 消息内容文本（上游返回 null 时为空字符串，例如只有工具调用的助手消息）
+    #[serde(default, deserialize_with = "deserialize_content")]
     pub content: String,
-    /// 可选的思维过程内容（Ollama Thinking 模式）
-    #[serde(skip_serializing_if = "Option::is_none")]
+    /// 可选的思维过程内容（Ollama Thinking 模式；OpenAI 兼容接口的 `reasoning_content` 也读入此字段）
+    #[serde(skip_serializing_if = "Option::is_none", alias = "reasoning_content")]
     pub thinking: Option<String>,
-    /// 可选的图像列表，支持多模态对话（Ollama/GPT-4V）
+    /// 可选的图像列表，支持多模态对话：URL、data URL 或 base64，发送时按供应商的 `MessageFormat` 转换
     #[serde(skip_serializing_if = "Option::is_none")]
     pub images: Option<Vec<String>>,
     /// 可选的工具调用列表（OpenAI/Ollama Tool Calling）
@@ -40,6 +42,9 @@ pub struct Message {
     /// 工具名称（当角色为 tool 时使用）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_name: Option<String>,
+    /// 对应的工具调用 ID（当角色为 tool 时使用，OpenAI 格式需要）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
 }
 
 /// 函数调用信息
@@ -47,10 +52,26 @@ pub struct Message {
 pub struct Function {
     /// 函数名称
     pub name: String,
-    /// 函数参数（JSON 格式）
+    /// 函数参数（JSON 格式），OpenAI 格式中以 JSON 字符串传递
+    #[serde(deserialize_with = "deserialize_arguments")]
     pub arguments: HashMap<String, Value>,
 }
 
+// 内容为 null 时按空字符串处理
+fn deserialize_content<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Ok(Option::<String>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+// 参数既可以是 JSON 对象（Ollama），也可以是 JSON 字符串（OpenAI 兼容格式），空字符串视为无参数
+fn deserialize_arguments<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<String, Value>, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::String(arguments) if arguments.trim().is_empty() => Ok(HashMap::new()),
+        Value::String(arguments) => serde_json::from_str(&arguments).map_err(serde::de::Error::custom),
+        Value::Null => Ok(HashMap::new()),
+        arguments => serde_json::from_value(arguments).map_err(serde::de::Error::custom),
+    }
+}
+
 
 impl Message {
     /// 创建系统消息
@@ -62,6 +83,7 @@ impl Message {
             images: None,
             tool_calls: None,
             tool_name: None,
+            tool_call_id: None,
         }
     }
 
@@ -74,6 +96,7 @@ impl Message {
             images: None,
             tool_calls: None,
             tool_name: None,
+            tool_call_id: None,
         }
     }
 
@@ -86,6 +109,7 @@ impl Message {
             images: None,
             tool_calls: None,
             tool_name: None,
+            tool_call_id: None,
         }
     }
 
@@ -98,6 +122,7 @@ impl Message {
             images: None,
             tool_calls: None,
             tool_name: Some(tool_name),
+            tool_call_id: None,
         }
     }
 
@@ -118,4 +143,330 @@ impl Message {
         self.tool_calls = Some(tool_calls);
         self
     }
-}
\ No newline at end of file
+
+    /// 为工具消息关联工具调用 ID（OpenAI 格式需要）
+    pub fn with_tool_call_id(mut self, tool_call_id: String) -> Self {
+        self.tool_call_id = Some(tool_call_id);
+        self
+    }
+}
+
+/// 供应商请求体中消息的格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MessageFormat {
+    /// OpenAI 兼容格式（OpenAI、Azure、阿里云）：含图片的消息 `content` 为 `text` / `image_url` 内容块数组，
+    /// 图片为 URL 或 data URL；工具调用参数为 JSON 字符串，工具消息以 `tool_call_id` 关联调用
+    OpenAI,
+    /// Ollama 格式：`content` 为文本，图片以不带 data URL 前缀的 base64 放在 `images` 字段；
+    /// 工具调用参数为 JSON 对象，工具消息以 `tool_name` 关联调用
+    Ollama,
+}
+
+// 发往供应商的消息，只在序列化时借用 Message 的字段
+#[derive(Serialize)]
+struct WireMessage<'a> {
+    role: &'a str,
+    content: WireContent<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    images: Option<Vec<&'a str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_calls: Option<WireToolCalls<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum WireContent<'a> {
+    Text(&'a str),
+    Parts(Vec<ContentPart<'a>>),
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentPart<'a> {
+    Text { text: &'a str },
+    ImageUrl { image_url: ImageUrl<'a> },
+}
+
+#[derive(Serialize)]
+struct ImageUrl<'a> {
+    url: Cow<'a, str>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum WireToolCalls<'a> {
+    Ollama(&'a [ToolCall]),
+    OpenAI(Vec<OpenAIToolCall<'a>>),
+}
+
+#[derive(Serialize)]
+struct OpenAIToolCall<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<&'a str>,
+    #[serde(rename = "type")]
+    tool_type: &'a str,
+    function: OpenAIFunctionCall<'a>,
+}
+
+#[derive(Serialize)]
+struct OpenAIFunctionCall<'a> {
+    name: &'a str,
+    arguments: String,
+}
+
+impl Message {
+    fn to_wire(&self, format: MessageFormat) -> WireMessage<'_> {
+        let images = self.images.as_deref().filter(|images| !images.is_empty());
+        let (content, images) = match (format, images) {
+            (MessageFormat::OpenAI, Some(images)) => {
+                let mut parts = Vec::with_capacity(images.len() + 1);
+                if !self.content.is_empty() {
+                    parts.push(ContentPart::Text { text: &self.content });
+                }
+                parts.extend(images.iter().map(|image| ContentPart::ImageUrl {
+                    image_url: ImageUrl { url: image_url(image) },
+                }));
+                (WireContent::Parts(parts), None)
+            }
+            (MessageFormat::Ollama, Some(images)) => {
+                let images = images.iter().map(|image| image_base64(image)).collect();
+                (WireContent::Text(&self.content), Some(images))
+            }
+            (_, None) => (WireContent::Text(&self.content), None),
+        };
+
+        match format {
+            MessageFormat::OpenAI => WireMessage {
+                role: &self.role,
+                content,
+                thinking: None,
+                images,
+                tool_calls: self.tool_calls.as_deref().map(|calls| WireToolCalls::OpenAI(
+                    calls.iter().map(OpenAIToolCall::from).collect(),
+                )),
+                tool_name: None,
+                tool_call_id: self.tool_call_id.as_deref(),
+            },
+            MessageFormat::Ollama => WireMessage {
+                role: &self.role,
+                content,
+                thinking: self.thinking.as_deref(),
+                images,
+                tool_calls: self.tool_calls.as_deref().map(WireToolCalls::Ollama),
+                tool_name: self.tool_name.as_deref(),
+                tool_call_id: None,
+            },
+        }
+    }
+}
+
+impl<'a> From<&'a ToolCall> for OpenAIToolCall<'a> {
+    fn from(call: &'a ToolCall) -> Self {
+        Self {
+            id: call.id.as_deref(),
+            tool_type: call.tool_type.as_deref().unwrap_or("function"),
+            function: OpenAIFunctionCall {
+                name: &call.function.name,
+                arguments: serde_json::to_string(&call.function.arguments).unwrap_or_else(|_| "{}".to_string()),
+            },
+        }
+    }
+}
+
+/// 按供应商格式序列化的消息列表；不含图片和工具调用的消息与 `Message` 自身的序列化结果相同
+pub struct WireMessages<'a>(pub &'a [Message], pub MessageFormat);
+
+impl Serialize for WireMessages<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(|message| message.to_wire(self.1)))
+    }
+}
+
+// 图片转换为 image_url：URL 和 data URL 原样传递，裸 base64 按文件头推断类型包装为 data URL
+fn image_url(image: &str) -> Cow<'_, str> {
+    if image.starts_with("data:") || image.starts_with("http://") || image.starts_with("https://") {
+        return Cow::Borrowed(image);
+    }
+    Cow::Owned(format!("data:{};base64,{}", base64_mime_type(image), image))
+}
+
+// 图片转换为 Ollama 接受的 base64：去掉 data URL 前缀（Ollama 不支持远程 URL，URL 原样传递由上游报错）
+fn image_base64(image: &str) -> &str {
+    match image.strip_prefix("data:").and_then(|rest| rest.split_once(";base64,")) {
+        Some((_, data)) => data,
+        None => image,
+    }
+}
+
+// 按 base64 编码后的文件头识别常见图片类型，无法识别时按 JPEG 处理
+fn base64_mime_type(data: &str) -> &'static str {
+    if data.starts_with("iVBORw0KGgo") {
+        "image/png"
+    } else if data.starts_with("R0lGOD") {
+        "image/gif"
+    } else if data.starts_with("UklGR") {
+        "image/webp"
+    } else {
+        "image/jpeg"
+    }
+}
+
+/// 流式响应中的工具调用增量（OpenAI 兼容格式），同一调用的参数按 `index` 分多块返回
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ToolCallDelta {
+    /// 工具调用在本次回复中的序号
+    #[serde(default)]
+    pub index: usize,
+    /// 工具调用 ID（只在该调用的第一块中出现）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// 工具类型
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub tool_type: Option<String>,
+    /// 函数名称和参数片段
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function: Option<FunctionDelta>,
+}
+
+/// 函数调用增量
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FunctionDelta {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// 参数 JSON 字符串片段
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<String>,
+}
+
+/// 按 `index` 拼接流式工具调用增量，流结束时得到完整的工具调用
+#[derive(Debug, Default)]
+pub struct ToolCallAccumulator {
+    // (ID, 类型, 函数名, 参数 JSON)
+    calls: Vec<(Option<String>, Option<String>, String, String)>,
+}
+
+impl ToolCallAccumulator {
+    pub fn push(&mut self, delta: ToolCallDelta) {
+        if self.calls.len() <= delta.index {
+            self.calls.resize_with(delta.index + 1, Default::default);
+        }
+        let call = &mut self.calls[delta.index];
+        if delta.id.is_some() {
+            call.0 = delta.id;
+        }
+        if delta.tool_type.is_some() {
+            call.1 = delta.tool_type;
+        }
+        if let Some(function) = delta.function {
+            if let Some(name) = function.name {
+                call.2.push_str(&name);
+            }
+            if let Some(arguments) = function.arguments {
+                call.3.push_str(&arguments);
+            }
+        }
+    }
+
+    /// 拼接完成的工具调用，没有收到任何增量时返回 None；参数不是合法 JSON 对象时按无参数处理
+    pub fn finish(self) -> Option<Vec<ToolCall>> {
+        let calls: Vec<ToolCall> = self.calls.into_iter()
+            .filter(|(_, _, name, _)| !name.is_empty())
+            .map(|(id, tool_type, name, arguments)| ToolCall {
+                id,
+                tool_type,
+                function: Function {
+                    name,
+                    arguments: serde_json::from_str(&arguments).unwrap_or_default(),
+                },
+            })
+            .collect();
+        (!calls.is_empty()).then_some(calls)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wire_json(message: &Message, format: MessageFormat) -> Value {
+        serde_json::to_value(message.to_wire(format)).unwrap()
+    }
+
+    #[test]
+    fn test_text_message_matches_plain_serialization() {
+        let message = Message::user("hello".to_string());
+        for format in [MessageFormat::OpenAI, MessageFormat::Ollama] {
+            assert_eq!(wire_json(&message, format), serde_json::to_value(&message).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_image_formats() {
+        let message = Message::user("what is this?".to_string()).with_images(vec![
+            "https://example.com/cat.png".to_string(),
+            "data:image/png;base64,iVBORw0KGgoAAA".to_string(),
+            "/9j/4AAQSkZJRg".to_string(),
+        ]);
+
+        let parts = wire_json(&message, MessageFormat::OpenAI);
+        assert!(parts.get("images").is_none());
+        assert_eq!(parts["content"][0], serde_json::json!({"type": "text", "text": "what is this?"}));
+        assert_eq!(parts["content"][1]["image_url"]["url"], "https://example.com/cat.png");
+        assert_eq!(parts["content"][2]["image_url"]["url"], "data:image/png;base64,iVBORw0KGgoAAA");
+        assert_eq!(parts["content"][3]["image_url"]["url"], "data:image/jpeg;base64,/9j/4AAQSkZJRg");
+
+        let ollama = wire_json(&message, MessageFormat::Ollama);
+        assert_eq!(ollama["content"], "what is this?");
+        assert_eq!(ollama["images"], serde_json::json!(["https://example.com/cat.png", "iVBORw0KGgoAAA", "/9j/4AAQSkZJRg"]));
+    }
+
+    #[test]
+    fn test_tool_call_formats() {
+        let response: Message = serde_json::from_value(serde_json::json!({
+            "role": "assistant",
+            "content": null,
+            "tool_calls": [{"id": "call_1", "type": "function", "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}}]
+        })).unwrap();
+        assert_eq!(response.content, "");
+        let call = &response.tool_calls.as_ref().unwrap()[0];
+        assert_eq!(call.function.arguments["city"], "Paris");
+
+        let openai = wire_json(&response, MessageFormat::OpenAI);
+        assert_eq!(openai["tool_calls"][0]["function"]["arguments"], "{\"city\":\"Paris\"}");
+        let ollama = wire_json(&response, MessageFormat::Ollama);
+        assert_eq!(ollama["tool_calls"][0]["function"]["arguments"]["city"], "Paris");
+
+        let result = Message::tool("18°C".to_string(), "get_weather".to_string()).with_tool_call_id("call_1".to_string());
+        let openai = wire_json(&result, MessageFormat::OpenAI);
+        assert_eq!(openai["tool_call_id"], "call_1");
+        assert!(openai.get("tool_name").is_none());
+        let ollama = wire_json(&result, MessageFormat::Ollama);
+        assert_eq!(ollama["tool_name"], "get_weather");
+        assert!(ollama.get("tool_call_id").is_none());
+    }
+
+    #[test]
+    fn test_tool_call_accumulator() {
+        let deltas: Vec<ToolCallDelta> = serde_json::from_value(serde_json::json!([
+            {"index": 0, "id": "call_1", "type": "function", "function": {"name": "get_weather", "arguments": ""}},
+            {"index": 0, "function": {"arguments": "{\"city\":"}},
+            {"index": 0, "function": {"arguments": "\"Paris\"}"}},
+            {"index": 1, "id": "call_2", "type": "function", "function": {"name": "get_time", "arguments": "{}"}}
+        ])).unwrap();
+        let mut accumulator = ToolCallAccumulator::default();
+        deltas.into_iter().for_each(|delta| accumulator.push(delta));
+
+        let calls = accumulator.finish().unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].id.as_deref(), Some("call_1"));
+        assert_eq!(calls[0].function.arguments["city"], "Paris");
+        assert_eq!(calls[1].function.name, "get_time");
+        assert!(ToolCallAccumulator::default().finish().is_none());
+    }
+}
@@ -0,0 +1,165 @@
+//! # 模型参数约束
+//!
+//! 不同模型能接受的参数范围不同，超出范围的请求以往要到供应商才报错。模型的 `models.config` 中可以配置
+//! `param_constraints`，调度器校验请求时把超出范围的参数调整到允许的范围内，并在响应的 `warnings` 中说明，例如
+//! `{"param_constraints": {"max_tokens": 4096, "min_temperature": 0.0, "max_temperature": 1.0, "max_stop": 4}}`：
+//! - `max_tokens`：`max_tokens` 的上限，超出时截断为上限
+//! - `min_temperature` / `max_temperature`：`temperature` 的允许范围，超出时取最近的边界
+//! - `max_stop`：停止词数量上限，超出时只保留前面的停止词
+//!
+//! 调整次数按参数计入 `llm_gateway_param_clamps_total{param}`
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::api_types::v1::DispatchRequest;
+use crate::dao::model::get_model_config_from_cache;
+use crate::metrics::metrics;
+
+/// 参数约束在 `models.config` 中的字段名
+pub const MODEL_CONFIG_KEY: &str = "param_constraints";
+
+/// 模型的参数约束，None 表示不限制
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParamConstraints {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_stop: Option<usize>,
+}
+
+impl ParamConstraints {
+    /// 检查配置：温度边界在 0.0-2.0 之间且下限不大于上限，`max_tokens` 大于 0
+    pub fn validate(&self) -> Result<(), String> {
+        let in_range = |value: Option<f32>| value.is_none_or(|value| (0.0..=2.0).contains(&value));
+        if !in_range(self.min_temperature) || !in_range(self.max_temperature) {
+            return Err("param_constraints temperature bounds must be between 0.0 and 2.0".to_string());
+        }
+        if let (Some(min), Some(max)) = (self.min_temperature, self.max_temperature)
+            && min > max
+        {
+            return Err("param_constraints min_temperature must not exceed max_temperature".to_string());
+        }
+        if self.max_tokens == Some(0) {
+            return Err("param_constraints max_tokens must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+
+    /// 把超出范围的参数调整到允许的范围内，返回每项调整的说明
+    pub fn clamp(&self, request: &mut DispatchRequest) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        if let (Some(limit), Some(max_tokens)) = (self.max_tokens, request.max_tokens)
+            && max_tokens > limit
+        {
+            request.max_tokens = Some(limit);
+            warnings.push(clamped("max_tokens", format!("max_tokens {} exceeds the limit of {} for model {}, clamped to {}", max_tokens, limit, request.model, limit)));
+        }
+
+        if let Some(temperature) = request.temperature {
+            let min = self.min_temperature.unwrap_or(f32::MIN);
+            let max = self.max_temperature.unwrap_or(f32::MAX);
+            let bounded = temperature.clamp(min, max);
+            if bounded != temperature {
+                request.temperature = Some(bounded);
+                warnings.push(clamped("temperature", format!("temperature {} is outside the allowed range for model {}, clamped to {}", temperature, request.model, bounded)));
+            }
+        }
+
+        if let (Some(limit), Some(stop)) = (self.max_stop, request.stop.as_mut())
+            && stop.len() > limit
+        {
+            let count = stop.len();
+            stop.truncate(limit);
+            warnings.push(clamped("stop", format!("{} stop sequences exceed the limit of {} for model {}, only the first {} are used", count, limit, request.model, limit)));
+        }
+
+        warnings
+    }
+}
+
+// 记录一次参数调整
+fn clamped(param: &str, warning: String) -> String {
+    metrics().incr_counter("llm_gateway_param_clamps_total", &[("param", param)]);
+    warning
+}
+
+/// 解析 `models.config` 中的参数约束；未配置时返回 `Ok(None)`，配置无效时返回错误信息
+pub fn parse_param_constraints(config: Option<&str>) -> Result<Option<ParamConstraints>, String> {
+    let Some(config) = config.filter(|config| !config.trim().is_empty()) else {
+        return Ok(None);
+    };
+    let Ok(serde_json::Value::Object(mut config)) = serde_json::from_str(config) else {
+        // 非 JSON 对象的 config 不属于本模块处理的范围
+        return Ok(None);
+    };
+    let Some(value) = config.remove(MODEL_CONFIG_KEY) else {
+        return Ok(None);
+    };
+    let constraints: ParamConstraints = serde_json::from_value(value).map_err(|e| format!("invalid param_constraints: {}", e))?;
+    constraints.validate()?;
+    Ok(Some(constraints))
+}
+
+/// 模型配置的参数约束；模型未缓存、未配置或配置无效时返回 None
+pub async fn resolve_param_constraints(provider: &str, model: &str) -> Option<ParamConstraints> {
+    let config = get_model_config_from_cache(provider, model).await;
+    match parse_param_constraints(config.as_deref()) {
+        Ok(constraints) => constraints,
+        Err(e) => {
+            warn!(provider = %provider, model = %model, error = %e, "Ignoring invalid model param constraints");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_types::v1::{Message, Provider};
+
+    fn request() -> DispatchRequest {
+        let mut request = DispatchRequest::new(Provider::OpenAI, "gpt-4o-mini".to_string(), vec![Message::user("hi".to_string())]);
+        request.max_tokens = Some(8192);
+        request.temperature = Some(1.5);
+        request.stop = Some(vec!["a".to_string(), "b".to_string(), "c".to_string()]);
+        request
+    }
+
+    #[test]
+    fn test_clamp_out_of_range_params() {
+        let constraints = ParamConstraints {
+            max_tokens: Some(4096),
+            min_temperature: Some(0.0),
+            max_temperature: Some(1.0),
+            max_stop: Some(2),
+        };
+        let mut request = request();
+        let warnings = constraints.clamp(&mut request);
+        assert_eq!(warnings.len(), 3);
+        assert_eq!(request.max_tokens, Some(4096));
+        assert_eq!(request.temperature, Some(1.0));
+        assert_eq!(request.stop, Some(vec!["a".to_string(), "b".to_string()]));
+
+        // 已在范围内的参数不调整
+        assert!(constraints.clamp(&mut request).is_empty());
+        assert!(ParamConstraints::default().clamp(&mut self::request()).is_empty());
+    }
+
+    #[test]
+    fn test_parse_param_constraints() {
+        let config = r#"{"system_prompt_policy": {"mode": "forbid_client_system"}, "param_constraints": {"max_tokens": 4096, "max_temperature": 1.0}}"#;
+        let constraints = parse_param_constraints(Some(config)).unwrap().unwrap();
+        assert_eq!(constraints.max_tokens, Some(4096));
+        assert_eq!(constraints.min_temperature, None);
+        assert_eq!(parse_param_constraints(Some(r#"{"temperature": 0.2}"#)), Ok(None));
+        assert!(parse_param_constraints(Some(r#"{"param_constraints": {"min_temperature": 1.5, "max_temperature": 1.0}}"#)).is_err());
+        assert!(parse_param_constraints(Some(r#"{"param_constraints": {"max_token": 10}}"#)).is_err());
+    }
+}
@@ -0,0 +1,202 @@
+//! # 按优先级放行的信号量
+//!
+//! 槽位用尽时等待者按 [`RequestPriority`] 分组排队，归还的槽位直接交给优先级最高的等待者，
+//! 同一优先级内按到达顺序。全局准入队列和供应商并发上限共用；严格按优先级放行，
+//! 持续有高优先级请求排队时低优先级请求会一直等到超时
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::oneshot;
+
+use crate::api_types::v1::RequestPriority;
+
+// 按优先级从高到低排列的等待队列下标
+fn queue_index(priority: RequestPriority) -> usize {
+    match priority {
+        RequestPriority::High => 0,
+        RequestPriority::Normal => 1,
+        RequestPriority::Batch => 2,
+    }
+}
+
+struct Waiter {
+    id: u64,
+    notify: oneshot::Sender<()>,
+}
+
+struct State {
+    limit: usize,
+    in_use: usize,
+    next_id: u64,
+    waiters: [VecDeque<Waiter>; 3],
+}
+
+impl State {
+    // 归还一个槽位：有等待者时直接转交，否则减少占用数
+    fn release(&mut self) {
+        for queue in self.waiters.iter_mut() {
+            while let Some(waiter) = queue.pop_front() {
+                if waiter.notify.send(()).is_ok() {
+                    return;
+                }
+            }
+        }
+        self.in_use -= 1;
+    }
+
+    // 从等待队列中移除，返回是否仍在队列中（未被转交槽位）
+    fn remove_waiter(&mut self, priority: RequestPriority, id: u64) -> bool {
+        let queue = &mut self.waiters[queue_index(priority)];
+        match queue.iter().position(|waiter| waiter.id == id) {
+            Some(index) => {
+                queue.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// 按优先级放行的信号量，克隆后共享同一组槽位
+#[derive(Clone)]
+pub struct PrioritySemaphore {
+    state: Arc<Mutex<State>>,
+}
+
+/// 占用的槽位，释放（drop）时归还
+pub struct PriorityPermit {
+    state: Arc<Mutex<State>>,
+}
+
+impl Drop for PriorityPermit {
+    fn drop(&mut self) {
+        self.state.lock().unwrap().release();
+    }
+}
+
+// 排队中的请求；获取槽位前被取消（drop）时退出队列，已转交的槽位归还给下一个等待者
+struct PendingWaiter {
+    state: Arc<Mutex<State>>,
+    priority: RequestPriority,
+    id: u64,
+    receiver: oneshot::Receiver<()>,
+    granted: bool,
+}
+
+impl Drop for PendingWaiter {
+    fn drop(&mut self) {
+        if self.granted {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        if !state.remove_waiter(self.priority, self.id) && self.receiver.try_recv().is_ok() {
+            state.release();
+        }
+    }
+}
+
+impl PrioritySemaphore {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                limit,
+                in_use: 0,
+                next_id: 0,
+                waiters: Default::default(),
+            })),
+        }
+    }
+
+    /// 已占用的槽位数
+    pub fn in_use(&self) -> usize {
+        self.state.lock().unwrap().in_use
+    }
+
+    /// 正在排队的请求数
+    pub fn waiting(&self) -> usize {
+        self.state.lock().unwrap().waiters.iter().map(VecDeque::len).sum()
+    }
+
+    /// 获取槽位，最多排队 `wait`，超时返回 `None`；`wait` 为 0 时不排队
+    pub async fn acquire(&self, priority: RequestPriority, wait: Duration) -> Option<PriorityPermit> {
+        let mut pending = {
+            let mut state = self.state.lock().unwrap();
+            if state.in_use < state.limit {
+                state.in_use += 1;
+                return Some(self.permit());
+            }
+            if wait.is_zero() {
+                return None;
+            }
+            let id = state.next_id;
+            state.next_id += 1;
+            let (notify, receiver) = oneshot::channel();
+            state.waiters[queue_index(priority)].push_back(Waiter { id, notify });
+            PendingWaiter { state: self.state.clone(), priority, id, receiver, granted: false }
+        };
+
+        match tokio::time::timeout(wait, &mut pending.receiver).await {
+            Ok(Ok(())) => {
+                pending.granted = true;
+                Some(self.permit())
+            }
+            _ => None,
+        }
+    }
+
+    fn permit(&self) -> PriorityPermit {
+        PriorityPermit { state: self.state.clone() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_higher_priority_served_first() {
+        let semaphore = PrioritySemaphore::new(1);
+        let permit = semaphore.acquire(RequestPriority::Normal, Duration::ZERO).await.unwrap();
+        assert!(semaphore.acquire(RequestPriority::High, Duration::ZERO).await.is_none());
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let waiter = |priority: RequestPriority, delay: u64| {
+            let semaphore = semaphore.clone();
+            let order = order.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                let _permit = semaphore.acquire(priority, Duration::from_secs(5)).await.unwrap();
+                order.lock().unwrap().push(priority);
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            })
+        };
+        // batch 先到，high 后到，high 仍然先获得槽位
+        let tasks = vec![
+            waiter(RequestPriority::Batch, 0),
+            waiter(RequestPriority::Normal, 10),
+            waiter(RequestPriority::High, 20),
+        ];
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert_eq!(semaphore.waiting(), 3);
+        drop(permit);
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), vec![RequestPriority::High, RequestPriority::Normal, RequestPriority::Batch]);
+        assert_eq!(semaphore.in_use(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_waiter_leaves_queue() {
+        let semaphore = PrioritySemaphore::new(1);
+        let permit = semaphore.acquire(RequestPriority::Normal, Duration::ZERO).await.unwrap();
+        assert!(semaphore.acquire(RequestPriority::High, Duration::from_millis(10)).await.is_none());
+        assert_eq!(semaphore.waiting(), 0);
+
+        drop(permit);
+        assert_eq!(semaphore.in_use(), 0);
+        assert!(semaphore.acquire(RequestPriority::Batch, Duration::ZERO).await.is_some());
+    }
+}
@@ -0,0 +1,110 @@
+//! # 项目隔离
+//!
+//! 一个网关实例按项目（工作空间）服务多个团队：供应商、模型、API Key 和调用记录都归属一个项目，
+//! 网关 Key（`Authorization: Bearer` 携带的调用方 Key，以调用方 ID 标识）绑定到项目。
+//!
+//! 调度时只使用请求所属项目的 API Key，项目之间互不共用；default 项目的供应商和模型所有项目共享，
+//! 其他项目的模型只对本项目可见。未绑定项目的网关 Key 和未携带 Key 的请求属于 default 项目；
+//! 开启 `projects.require_bound_key` 后这类请求被拒绝，只有绑定了项目的 Key 才能调用。
+//! 绑定到已停用项目的 Key 被拒绝。
+//!
+//! 绑定关系保存在 `project_gateway_keys` 表，启动时和绑定变更后加载到内存
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+use lazy_static::lazy_static;
+use sqlx::SqlitePool;
+
+use crate::dao::project::{list_gateway_key_bindings, list_projects, DEFAULT_PROJECT};
+
+#[derive(Debug, Default)]
+struct ProjectBindings {
+    // 调用方 ID -> 项目 ID
+    bindings: HashMap<String, String>,
+    // 已停用的项目
+    inactive: HashSet<String>,
+}
+
+lazy_static! {
+    static ref PROJECT_BINDINGS: RwLock<ProjectBindings> = RwLock::new(ProjectBindings::default());
+}
+
+// 是否拒绝未携带或未绑定项目的网关 Key
+static REQUIRE_BOUND_KEY: AtomicBool = AtomicBool::new(false);
+
+/// 请求不能归属任何可用的项目
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProjectRejection {
+    /// 要求绑定项目时，未携带网关 Key 或 Key 未绑定项目
+    UnboundKey,
+    /// 绑定的项目已停用
+    Disabled(String),
+}
+
+/// 设置是否只接受绑定了项目的网关 Key，启动时按 `projects.require_bound_key` 配置
+pub fn set_require_bound_key(required: bool) {
+    REQUIRE_BOUND_KEY.store(required, Ordering::Relaxed);
+}
+
+/// 是否只接受绑定了项目的网关 Key
+pub fn require_bound_key() -> bool {
+    REQUIRE_BOUND_KEY.load(Ordering::Relaxed)
+}
+
+/// 从数据库重新加载网关 Key 绑定和项目启用状态，返回绑定数量
+pub async fn reload_project_bindings(pool: &SqlitePool) -> sqlx::Result<usize> {
+    let bindings: HashMap<String, String> = list_gateway_key_bindings(pool)
+        .await?
+        .into_iter()
+        .map(|binding| (binding.consumer_id, binding.project_id))
+        .collect();
+    let inactive = list_projects(pool)
+        .await?
+        .into_iter()
+        .filter(|project| !project.is_active)
+        .map(|project| project.id)
+        .collect();
+    let count = bindings.len();
+    *PROJECT_BINDINGS.write().unwrap() = ProjectBindings { bindings, inactive };
+    Ok(count)
+}
+
+/// 调用方所属的项目：未绑定或未携带 Key 时为 default 项目（要求绑定项目时返回错误），项目已停用时返回错误
+pub fn resolve_project(consumer_id: Option<&str>) -> Result<String, ProjectRejection> {
+    let state = PROJECT_BINDINGS.read().unwrap();
+    let bound = consumer_id.and_then(|consumer_id| state.bindings.get(consumer_id));
+    if bound.is_none() && require_bound_key() {
+        return Err(ProjectRejection::UnboundKey);
+    }
+    let project_id = bound.map(String::as_str).unwrap_or(DEFAULT_PROJECT);
+    if state.inactive.contains(project_id) {
+        return Err(ProjectRejection::Disabled(project_id.to_string()));
+    }
+    Ok(project_id.to_string())
+}
+
+/// 网关 Key 是否绑定了项目
+pub fn is_bound_consumer(consumer_id: &str) -> bool {
+    PROJECT_BINDINGS.read().unwrap().bindings.contains_key(consumer_id)
+}
+
+/// 归属 `owner` 项目的供应商或模型是否对 `project_id` 项目可见：default 项目的资源所有项目共享
+pub fn is_visible_to(owner: Option<&str>, project_id: &str) -> bool {
+    let owner = owner.unwrap_or(DEFAULT_PROJECT);
+    owner == DEFAULT_PROJECT || owner == project_id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_visibility() {
+        assert!(is_visible_to(None, "team-a"));
+        assert!(is_visible_to(Some(DEFAULT_PROJECT), "team-a"));
+        assert!(is_visible_to(Some("team-a"), "team-a"));
+        assert!(!is_visible_to(Some("team-a"), "team-b"));
+        assert!(!is_visible_to(Some("team-a"), DEFAULT_PROJECT));
+    }
+}
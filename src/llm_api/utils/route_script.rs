@@ -0,0 +1,308 @@
+//! # 路由脚本
+//!
+//! 使用内嵌的 Rhai 脚本决定请求的供应商和模型，适用于声明式规则无法表达的路由逻辑。
+//! 脚本在沙箱中执行：不能导入模块或访问文件，并限制操作数、执行时间和数据大小。
+//!
+//! 脚本可以读取变量 `request`（provider、model、user、tenant_id、language、prompt、
+//! prompt_chars、message_count、max_tokens、temperature、stream），返回值：
+//! - `()`：不修改路由
+//! - `#{ provider: "ali", model: "qwen-plus" }`：改写供应商和/或模型
+//! - `#{ targets: [#{ provider: "ali", model: "qwen-plus", weight: 3 }, ...] }`：按权重随机选择
+
+use std::cell::Cell;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use anyhow::{anyhow, bail, Context, Result};
+use lazy_static::lazy_static;
+use rand::Rng;
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use rhai::module_resolvers::DummyModuleResolver;
+use tracing::info;
+
+use crate::llm_api::dispatcher::{DispatchRequest, Provider};
+
+/// 单次执行的最大操作数
+const MAX_OPERATIONS: u64 = 100_000;
+/// 单次执行的最长时间
+const MAX_EXECUTION_TIME: Duration = Duration::from_millis(50);
+/// 字符串、数组、Map 的大小上限
+const MAX_STRING_SIZE: usize = 64 * 1024;
+const MAX_COLLECTION_SIZE: usize = 1024;
+
+thread_local! {
+    // 当前线程上正在执行的脚本的截止时间（脚本同步执行，不会跨线程）
+    static EVAL_DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// 脚本返回的路由结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteDecision {
+    pub provider: Option<Provider>,
+    pub model: Option<String>,
+}
+
+/// 路由脚本引擎
+pub struct RouteScriptEngine {
+    engine: Engine,
+    script: RwLock<Option<Arc<AST>>>,
+}
+
+impl RouteScriptEngine {
+    /// 创建带沙箱限制的脚本引擎（未加载脚本）
+    pub fn new() -> Self {
+        let mut engine = Engine::new();
+        engine
+            .set_module_resolver(DummyModuleResolver::new())
+            .set_max_modules(0)
+            .set_max_operations(MAX_OPERATIONS)
+            .set_max_call_levels(32)
+            .set_max_expr_depths(64, 32)
+            .set_max_string_size(MAX_STRING_SIZE)
+            .set_max_array_size(MAX_COLLECTION_SIZE)
+            .set_max_map_size(MAX_COLLECTION_SIZE)
+            .disable_symbol("eval")
+            .on_print(|_| {})
+            .on_debug(|_, _, _| {})
+            .on_progress(|_| {
+                let expired = EVAL_DEADLINE.with(|deadline| deadline.get().is_some_and(|d| Instant::now() >= d));
+                expired.then(|| Dynamic::from("route script timed out"))
+            });
+
+        Self {
+            engine,
+            script: RwLock::new(None),
+        }
+    }
+
+    /// 编译并加载脚本，编译失败时保留原有脚本
+    pub fn load_script(&self, source: &str) -> Result<()> {
+        let ast = self.engine.compile(source).map_err(|e| anyhow!("failed to compile route script: {}", e))?;
+        *self.script.write().unwrap() = Some(Arc::new(ast));
+        Ok(())
+    }
+
+    /// 从文件加载脚本
+    pub fn load_file(&self, path: &Path) -> Result<()> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read route script {}", path.display()))?;
+        self.load_script(&source)
+    }
+
+    /// 卸载脚本
+    pub fn clear(&self) {
+        *self.script.write().unwrap() = None;
+    }
+
+    /// 是否已加载脚本
+    pub fn is_loaded(&self) -> bool {
+        self.script.read().unwrap().is_some()
+    }
+
+    /// 对请求执行脚本，未加载脚本或脚本返回 `()` 时返回 None
+    pub fn evaluate(&self, request: &DispatchRequest, language: Option<&str>) -> Result<Option<RouteDecision>> {
+        let Some(ast) = self.script.read().unwrap().clone() else {
+            return Ok(None);
+        };
+
+        let mut scope = Scope::new();
+        scope.push_constant("request", request_map(request, language));
+
+        EVAL_DEADLINE.with(|deadline| deadline.set(Some(Instant::now() + MAX_EXECUTION_TIME)));
+        let result = self.engine.eval_ast_with_scope::<Dynamic>(&mut scope, &ast);
+        EVAL_DEADLINE.with(|deadline| deadline.set(None));
+
+        let value = result.map_err(|e| anyhow!("route script failed: {}", e))?;
+        parse_decision(value)
+    }
+}
+
+impl Default for RouteScriptEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 将请求转换为脚本可读取的 Map
+fn request_map(request: &DispatchRequest, language: Option<&str>) -> Map {
+    fn optional<T: Into<Dynamic>>(value: Option<T>) -> Dynamic {
+        value.map(Into::into).unwrap_or(Dynamic::UNIT)
+    }
+
+    let prompt = request.messages.iter()
+        .rev()
+        .find(|m| m.role == "user")
+        .map(|m| m.content.clone())
+        .unwrap_or_default();
+
+    let mut map = Map::new();
+    map.insert("provider".into(), request.provider.as_str().into());
+    map.insert("model".into(), request.model.clone().into());
+    map.insert("user".into(), optional(request.user.clone()));
+    map.insert("tenant_id".into(), optional(request.tenant_id.clone()));
+    map.insert("language".into(), optional(language.map(str::to_string)));
+    map.insert("prompt_chars".into(), (prompt.chars().count() as i64).into());
+    map.insert("prompt".into(), prompt.into());
+    map.insert("message_count".into(), (request.messages.len() as i64).into());
+    map.insert("max_tokens".into(), optional(request.max_tokens.map(i64::from)));
+    map.insert("temperature".into(), optional(request.temperature.map(f64::from)));
+    map.insert("stream".into(), request.stream.unwrap_or(false).into());
+    map
+}
+
+// 解析脚本返回值
+fn parse_decision(value: Dynamic) -> Result<Option<RouteDecision>> {
+    if value.is_unit() {
+        return Ok(None);
+    }
+    let Some(map) = value.try_cast::<Map>() else {
+        bail!("route script must return () or a map");
+    };
+
+    if let Some(targets) = map.get("targets") {
+        let targets = targets.clone().into_array()
+            .map_err(|t| anyhow!("targets must be an array, got {}", t))?;
+        return pick_weighted(targets).map(Some);
+    }
+    parse_target(&map).map(Some)
+}
+
+fn parse_target(map: &Map) -> Result<RouteDecision> {
+    let field = |name: &str| -> Result<Option<String>> {
+        match map.get(name) {
+            None => Ok(None),
+            Some(value) if value.is_unit() => Ok(None),
+            Some(value) => value.clone().into_string()
+                .map(Some)
+                .map_err(|t| anyhow!("{} must be a string, got {}", name, t)),
+        }
+    };
+
+    let decision = RouteDecision {
+        provider: field("provider")?.map(|name| Provider::from_name_or_custom(&name)),
+        model: field("model")?.filter(|model| !model.trim().is_empty()),
+    };
+    if decision.provider.is_none() && decision.model.is_none() {
+        bail!("route target must set provider or model");
+    }
+    Ok(decision)
+}
+
+// 按权重随机选择一个目标，未设置权重时视为 1
+fn pick_weighted(targets: Vec<Dynamic>) -> Result<RouteDecision> {
+    let mut weighted = Vec::with_capacity(targets.len());
+    for target in targets {
+        let map = target.try_cast::<Map>().ok_or_else(|| anyhow!("each target must be a map"))?;
+        let weight = match map.get("weight") {
+            None => 1.0,
+            Some(w) => w.as_float()
+                .or_else(|_| w.as_int().map(|i| i as f64))
+                .map_err(|t| anyhow!("weight must be a number, got {}", t))?,
+        };
+        if weight > 0.0 {
+            weighted.push((parse_target(&map)?, weight));
+        }
+    }
+
+    let total: f64 = weighted.iter().map(|(_, weight)| weight).sum();
+    if weighted.is_empty() || total <= 0.0 {
+        bail!("targets must contain at least one entry with positive weight");
+    }
+
+    let mut point = rand::thread_rng().gen_range(0.0..total);
+    for (decision, weight) in &weighted {
+        if point < *weight {
+            return Ok(decision.clone());
+        }
+        point -= weight;
+    }
+    Ok(weighted.pop().map(|(decision, _)| decision).unwrap())
+}
+
+lazy_static! {
+    /// 全局路由脚本引擎
+    static ref GLOBAL_ROUTE_SCRIPT: RouteScriptEngine = RouteScriptEngine::new();
+}
+
+/// 获取全局路由脚本引擎
+pub fn get_route_script_engine() -> &'static RouteScriptEngine {
+    &GLOBAL_ROUTE_SCRIPT
+}
+
+/// 从文件重新加载全局路由脚本
+pub fn reload_route_script(path: &Path) -> Result<()> {
+    get_route_script_engine().load_file(path)?;
+    info!(path = %path.display(), "Route script reloaded");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm_api::utils::msg_structure::Message;
+
+    fn request(prompt: &str) -> DispatchRequest {
+        let mut request = DispatchRequest::new(Provider::Ollama, "llama3.2".to_string(), vec![Message::user(prompt.to_string())]);
+        request.tenant_id = Some("acme".to_string());
+        request
+    }
+
+    #[test]
+    fn test_route_script_decisions() {
+        let engine = RouteScriptEngine::new();
+        assert_eq!(engine.evaluate(&request("hi"), None).unwrap(), None);
+
+        engine.load_script(r#"
+            if request.tenant_id == "acme" && request.prompt_chars > 10 {
+                #{ provider: "ali", model: "qwen-plus" }
+            } else if request.language == "cmn" {
+                #{ model: "qwen-turbo" }
+            }
+        "#).unwrap();
+
+        let decision = engine.evaluate(&request("a much longer prompt"), None).unwrap().unwrap();
+        assert_eq!(decision.provider, Some(Provider::Ali));
+        assert_eq!(decision.model.as_deref(), Some("qwen-plus"));
+
+        let decision = engine.evaluate(&request("你好"), Some("cmn")).unwrap().unwrap();
+        assert_eq!(decision, RouteDecision { provider: None, model: Some("qwen-turbo".to_string()) });
+
+        assert_eq!(engine.evaluate(&request("hi"), Some("eng")).unwrap(), None);
+
+        // 编译失败时保留原脚本
+        assert!(engine.load_script("if {").is_err());
+        assert!(engine.evaluate(&request("a much longer prompt"), None).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_route_script_weighted_targets() {
+        let engine = RouteScriptEngine::new();
+        engine.load_script(r#"
+            #{ targets: [
+                #{ provider: "ali", model: "qwen-plus", weight: 0 },
+                #{ provider: "openai", model: "gpt-4o-mini", weight: 2.5 },
+            ] }
+        "#).unwrap();
+
+        for _ in 0..20 {
+            let decision = engine.evaluate(&request("hi"), None).unwrap().unwrap();
+            assert_eq!(decision.provider, Some(Provider::OpenAI));
+        }
+    }
+
+    #[test]
+    fn test_route_script_sandbox_limits() {
+        let engine = RouteScriptEngine::new();
+
+        engine.load_script("loop { }").unwrap();
+        assert!(engine.evaluate(&request("hi"), None).is_err());
+
+        engine.clear();
+        let import = engine.load_script(r#"import "secrets" as s; ()"#)
+            .and_then(|_| engine.evaluate(&request("hi"), None));
+        assert!(import.is_err());
+
+        engine.load_script("42").unwrap();
+        assert!(engine.evaluate(&request("hi"), None).is_err());
+    }
+}
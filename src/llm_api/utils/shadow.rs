@@ -0,0 +1,123 @@
+//! # 影子流量
+//!
+//! 按百分比把请求异步镜像到另一个供应商（例如从阿里云迁移到 OpenAI 前先做对比），镜像请求的结果不返回给调用方，
+//! 只把主请求和镜像请求的响应、耗时写入 `shadow_comparisons` 表供离线比较。镜像请求不重试、不 fallback，
+//! 不计入调用方配额；响应和错误信息脱敏后保存
+
+use std::time::Duration;
+
+use rand::Rng;
+use tracing::{debug, warn};
+
+use crate::dao::SQLITE_POOL;
+use crate::dao::shadow_comparison::{create_shadow_comparison, ShadowComparison};
+use crate::llm_api::dispatcher::{DispatchRequest, DispatchResponse, LLMError, Provider};
+use crate::llm_api::utils::redaction::redact_opt;
+use crate::metrics::metrics;
+
+/// 影子流量的目标供应商和采样比例
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShadowMirror {
+    /// 镜像请求发往的供应商
+    pub provider: Provider,
+    /// 镜像请求使用的模型，None 表示与主请求相同
+    pub model: Option<String>,
+    /// 镜像的请求百分比（0-100）
+    pub percent: u32,
+    /// 只镜像发往这些供应商的请求，为空时镜像全部
+    pub source_providers: Vec<Provider>,
+}
+
+impl ShadowMirror {
+    /// 是否镜像该请求：按来源供应商过滤后随机采样，发往目标供应商本身的请求不镜像
+    pub fn should_mirror(&self, request: &DispatchRequest) -> bool {
+        if self.percent == 0 || request.provider == self.provider {
+            return false;
+        }
+        if !self.source_providers.is_empty() && !self.source_providers.contains(&request.provider) {
+            return false;
+        }
+        rand::thread_rng().gen_range(0..100) < self.percent
+    }
+
+    /// 生成镜像请求：改写供应商和模型，不重试、不 fallback
+    pub fn mirror_request(&self, request: &DispatchRequest) -> DispatchRequest {
+        let mut mirror = request.clone();
+        mirror.provider = self.provider.clone();
+        if let Some(model) = &self.model {
+            mirror.model = model.clone();
+        }
+        mirror.retry_count = Some(0);
+        mirror.fallback = Some(false);
+        mirror.stream = Some(false);
+        mirror
+    }
+}
+
+/// 写入一条主请求与镜像请求的对比记录，写入失败只记录日志
+pub async fn record_shadow_comparison(
+    primary: &DispatchResponse,
+    primary_latency: Duration,
+    request: &DispatchRequest,
+    shadow: &Result<DispatchResponse, LLMError>,
+    shadow_latency: Duration,
+) {
+    let outcome = if shadow.is_ok() { "success" } else { "error" };
+    metrics().incr_counter("llm_gateway_shadow_requests_total", &[("provider", request.provider.as_str()), ("outcome", outcome)]);
+    debug!(
+        provider = %request.provider.as_str(),
+        model = %request.model,
+        primary_latency_ms = primary_latency.as_millis() as u64,
+        shadow_latency_ms = shadow_latency.as_millis() as u64,
+        outcome,
+        "Shadow request completed"
+    );
+
+    let Some(pool) = SQLITE_POOL.get() else {
+        return;
+    };
+    let comparison = ShadowComparison {
+        id: uuid::Uuid::new_v4().to_string(),
+        primary_provider: primary.provider.as_str().to_string(),
+        primary_model: primary.model.clone(),
+        primary_latency_ms: primary_latency.as_millis() as i64,
+        primary_response: redact_opt(Some(primary.content.clone())),
+        shadow_provider: request.provider.as_str().to_string(),
+        shadow_model: request.model.clone(),
+        shadow_latency_ms: shadow_latency.as_millis() as i64,
+        shadow_response: redact_opt(shadow.as_ref().ok().map(|response| response.content.clone())),
+        shadow_error: redact_opt(shadow.as_ref().err().map(|e| e.to_string())),
+        created_at: None,
+    };
+    if let Err(e) = create_shadow_comparison(pool, &comparison).await {
+        warn!(provider = %request.provider.as_str(), error = %e, "Failed to record shadow comparison");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm_api::utils::msg_structure::Message;
+
+    fn mirror(percent: u32, source_providers: Vec<Provider>) -> ShadowMirror {
+        ShadowMirror { provider: Provider::OpenAI, model: Some("gpt-4o-mini".to_string()), percent, source_providers }
+    }
+
+    #[test]
+    fn test_should_mirror() {
+        let request = DispatchRequest::new(Provider::Ali, "qwen-plus".to_string(), vec![Message::user("hi".to_string())]);
+        assert!(mirror(100, Vec::new()).should_mirror(&request));
+        assert!(!mirror(0, Vec::new()).should_mirror(&request));
+        assert!(mirror(100, vec![Provider::Ali]).should_mirror(&request));
+        assert!(!mirror(100, vec![Provider::Ollama]).should_mirror(&request));
+
+        let to_target = DispatchRequest::new(Provider::OpenAI, "gpt-4o".to_string(), Vec::new());
+        assert!(!mirror(100, Vec::new()).should_mirror(&to_target));
+
+        let shadow = mirror(100, Vec::new()).mirror_request(&request);
+        assert_eq!(shadow.provider, Provider::OpenAI);
+        assert_eq!(shadow.model, "gpt-4o-mini");
+        assert_eq!(shadow.retry_count, Some(0));
+        assert_eq!(shadow.fallback, Some(false));
+    }
+}
@@ -0,0 +1,75 @@
+//! # 流式响应用量统计
+//!
+//! 各供应商的流式响应格式和用量位置不同：Ollama 在 `"done": true` 的最后一行返回
+//! `prompt_eval_count` / `eval_count`；OpenAI 兼容的 SSE（OpenAI、Azure、阿里云兼容模式）以 `data: [DONE]` 结束，
+//! 开启 `stream_options.include_usage` 时在结束标记前的最后一块返回 `usage`。
+//! 客户端把对应的 [`StreamAccounting`] 传给 `BaseClient::post_stream`，用于判断流是否完整结束并写入调用记录的 token 数
+
+use serde_json::Value;
+
+/// 流式响应中报告的累计用量
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StreamUsage {
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+}
+
+/// 一行流式数据的统计结果
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StreamLine {
+    /// 该行是否为流结束标记
+    pub done: bool,
+    /// 该行携带的用量
+    pub usage: Option<StreamUsage>,
+}
+
+/// 按供应商的流式响应格式识别结束标记和用量
+pub trait StreamAccounting: Send + Sync {
+    /// 解析一行（已去掉首尾空白的）流式数据
+    fn parse_line(&self, line: &str) -> StreamLine;
+}
+
+/// Ollama 的 NDJSON 流
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OllamaStreamAccounting;
+
+impl StreamAccounting for OllamaStreamAccounting {
+    fn parse_line(&self, line: &str) -> StreamLine {
+        // 兼容以 `data: ` 开头的行
+        let payload = line.strip_prefix("data:").map_or(line, str::trim_start);
+        let Ok(value) = serde_json::from_str::<Value>(payload) else {
+            return StreamLine::default();
+        };
+        if value.get("done").and_then(Value::as_bool) != Some(true) {
+            return StreamLine::default();
+        }
+        let count = |key: &str| value.get(key).and_then(Value::as_i64);
+        let usage = (count("prompt_eval_count").is_some() || count("eval_count").is_some()).then(|| StreamUsage {
+            input_tokens: count("prompt_eval_count").unwrap_or(0),
+            output_tokens: count("eval_count").unwrap_or(0),
+        });
+        StreamLine { done: true, usage }
+    }
+}
+
+/// OpenAI 兼容的 SSE 流（OpenAI、Azure、阿里云兼容模式及插件供应商）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OpenAIStreamAccounting;
+
+impl StreamAccounting for OpenAIStreamAccounting {
+    fn parse_line(&self, line: &str) -> StreamLine {
+        let Some(payload) = line.strip_prefix("data:").map(str::trim_start) else {
+            return StreamLine::default();
+        };
+        if payload == "[DONE]" {
+            return StreamLine { done: true, usage: None };
+        }
+        let usage = serde_json::from_str::<Value>(payload).ok()
+            .and_then(|value| value.get("usage").filter(|usage| usage.is_object()).cloned())
+            .map(|usage| StreamUsage {
+                input_tokens: usage.get("prompt_tokens").and_then(Value::as_i64).unwrap_or(0),
+                output_tokens: usage.get("completion_tokens").and_then(Value::as_i64).unwrap_or(0),
+            });
+        StreamLine { done: false, usage }
+    }
+}
@@ -0,0 +1,216 @@
+//! # 结构化输出校验
+//!
+//! 请求指定 `response_format` 且开启 `validate_response` 时，网关解析模型返回的内容：
+//! `json_object` 要求是合法的 JSON 对象，`json_schema` 还要符合 Schema。
+//! 校验失败时把错误发回给模型要求修正一次，修正后仍不符合则返回错误。
+//!
+//! Schema 校验支持常用关键字：`type`、`enum`、`const`、`properties`、`required`、
+//! `additionalProperties`、`items`、`minItems`/`maxItems`、`minLength`/`maxLength`、
+//! `minimum`/`maximum`、`anyOf`；其它关键字忽略
+
+use serde_json::Value;
+
+use crate::api_types::v1::ResponseFormat;
+use crate::llm_api::utils::msg_structure::Message;
+
+/// 校验模型返回的内容是否符合结构化输出格式，返回第一处错误
+pub fn check_response(format: &ResponseFormat, content: &str) -> Result<(), String> {
+    if !format.is_json() {
+        return Ok(());
+    }
+    let value: Value = serde_json::from_str(strip_code_fence(content))
+        .map_err(|e| format!("response is not valid JSON: {}", e))?;
+    match format.schema() {
+        Some(schema) => validate(&value, schema),
+        None if value.is_object() => Ok(()),
+        None => Err("response must be a JSON object".to_string()),
+    }
+}
+
+/// 要求模型修正输出的追加消息：上一轮的回答和校验错误
+pub fn repair_messages(content: &str, error: &str) -> Vec<Message> {
+    vec![
+        Message::assistant(content.to_string()),
+        Message::user(format!(
+            "Your previous response did not match the required format: {}. \
+             Reply again with only the corrected JSON, without any explanation.",
+            error
+        )),
+    ]
+}
+
+/// 按 JSON Schema 校验，错误信息带 JSON Pointer 路径（如 `/items/0/name`）
+pub fn validate(value: &Value, schema: &Value) -> Result<(), String> {
+    validate_at(value, schema, "")
+}
+
+// 部分模型即使要求 JSON 也会用 ```json 代码块包裹
+fn strip_code_fence(content: &str) -> &str {
+    let trimmed = content.trim();
+    trimmed
+        .strip_prefix("```json")
+        .or_else(|| trimmed.strip_prefix("```"))
+        .and_then(|rest| rest.strip_suffix("```"))
+        .map(str::trim)
+        .unwrap_or(trimmed)
+}
+
+fn validate_at(value: &Value, schema: &Value, path: &str) -> Result<(), String> {
+    let Some(schema) = schema.as_object() else {
+        // true 或空 Schema 接受任意值
+        return match schema {
+            Value::Bool(false) => Err(error(path, "no value is allowed here")),
+            _ => Ok(()),
+        };
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|t| matches_type(value, t)) {
+            return Err(error(path, &format!("expected {}, got {}", types.join(" or "), type_name(value))));
+        }
+    }
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array)
+        && !allowed.contains(value)
+    {
+        return Err(error(path, &format!("{} is not one of {}", value, Value::Array(allowed.clone()))));
+    }
+    if let Some(expected) = schema.get("const")
+        && expected != value
+    {
+        return Err(error(path, &format!("expected {}", expected)));
+    }
+    if let Some(options) = schema.get("anyOf").and_then(Value::as_array)
+        && !options.iter().any(|option| validate_at(value, option, path).is_ok())
+    {
+        return Err(error(path, "does not match any of the allowed schemas"));
+    }
+
+    match value {
+        Value::Object(object) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for name in schema.get("required").and_then(Value::as_array).into_iter().flatten().filter_map(Value::as_str) {
+                if !object.contains_key(name) {
+                    return Err(error(path, &format!("missing required property \"{}\"", name)));
+                }
+            }
+            for (name, field) in object {
+                let field_path = format!("{}/{}", path, name);
+                match properties.and_then(|p| p.get(name)) {
+                    Some(field_schema) => validate_at(field, field_schema, &field_path)?,
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => {
+                            return Err(error(path, &format!("unexpected property \"{}\"", name)));
+                        }
+                        Some(additional) => validate_at(field, additional, &field_path)?,
+                        None => {}
+                    },
+                }
+            }
+        }
+        Value::Array(items) => {
+            check_bound(schema, "minItems", "maxItems", items.len() as f64, path, "items")?;
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    validate_at(item, item_schema, &format!("{}/{}", path, i))?;
+                }
+            }
+        }
+        Value::String(s) => check_bound(schema, "minLength", "maxLength", s.chars().count() as f64, path, "characters")?,
+        Value::Number(n) => check_bound(schema, "minimum", "maximum", n.as_f64().unwrap_or_default(), path, "")?,
+        _ => {}
+    }
+    Ok(())
+}
+
+fn check_bound(schema: &serde_json::Map<String, Value>, min: &str, max: &str, actual: f64, path: &str, unit: &str) -> Result<(), String> {
+    let unit = if unit.is_empty() { String::new() } else { format!(" {}", unit) };
+    if let Some(limit) = schema.get(min).and_then(Value::as_f64)
+        && actual < limit
+    {
+        return Err(error(path, &format!("must have at least {}{}", limit, unit)));
+    }
+    if let Some(limit) = schema.get(max).and_then(Value::as_f64)
+        && actual > limit
+    {
+        return Err(error(path, &format!("must have at most {}{}", limit, unit)));
+    }
+    Ok(())
+}
+
+fn matches_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Object(_) => "object",
+        Value::Array(_) => "array",
+        Value::String(_) => "string",
+        Value::Number(_) => "number",
+        Value::Bool(_) => "boolean",
+        Value::Null => "null",
+    }
+}
+
+fn error(path: &str, message: &str) -> String {
+    if path.is_empty() {
+        message.to_string()
+    } else {
+        format!("{}: {}", path, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string", "minLength": 1},
+                "age": {"type": "integer", "minimum": 0},
+                "tags": {"type": "array", "items": {"type": "string"}, "maxItems": 2},
+                "level": {"enum": ["low", "high"]}
+            },
+            "required": ["name", "age"],
+            "additionalProperties": false
+        })
+    }
+
+    #[test]
+    fn test_validate_schema() {
+        assert!(validate(&json!({"name": "Tom", "age": 3, "tags": ["a"], "level": "low"}), &schema()).is_ok());
+        assert_eq!(validate(&json!({"name": "Tom"}), &schema()).unwrap_err(), "missing required property \"age\"");
+        assert_eq!(validate(&json!({"name": "Tom", "age": "3"}), &schema()).unwrap_err(), "/age: expected integer, got string");
+        assert_eq!(validate(&json!({"name": "Tom", "age": 3, "tags": ["a", 1]}), &schema()).unwrap_err(), "/tags/1: expected string, got number");
+        assert_eq!(validate(&json!({"name": "Tom", "age": 3, "extra": 1}), &schema()).unwrap_err(), "unexpected property \"extra\"");
+        assert!(validate(&json!({"name": "Tom", "age": 3, "level": "mid"}), &schema()).is_err());
+        assert!(validate(&json!({"name": "", "age": 3}), &schema()).is_err());
+        assert!(validate(&json!({"name": "Tom", "age": -1}), &schema()).is_err());
+    }
+
+    #[test]
+    fn test_check_response() {
+        assert!(check_response(&ResponseFormat::Text, "not json").is_ok());
+        assert!(check_response(&ResponseFormat::JsonObject, "{\"ok\": true}").is_ok());
+        assert!(check_response(&ResponseFormat::JsonObject, "```json\n{\"ok\": true}\n```").is_ok());
+        assert!(check_response(&ResponseFormat::JsonObject, "[1, 2]").is_err());
+        assert!(check_response(&ResponseFormat::JsonObject, "Sure! {\"ok\": true}").is_err());
+    }
+}
@@ -0,0 +1,228 @@
+//! # 系统提示词策略
+//!
+//! 管理员按模型或项目强制使用统一的系统提示词（安全规范、品牌话术等），调度器在请求映射到供应商格式之前执行：
+//! - `prepend`：把配置的提示词作为第一条 system 消息，保留调用方自己的 system 消息
+//! - `replace`：删除调用方的 system 消息，只保留配置的提示词
+//! - `forbid_client_system`：调用方携带 system 消息时拒绝请求（400），配置了提示词时同样放在最前
+//!
+//! 模型策略写在 `models.config` 的 `system_prompt_policy` 字段，例如
+//! `{"system_prompt_policy": {"mode": "prepend", "prompt": "你是 Acme 的客服助手"}}`；
+//! 项目策略保存在 system_configs（category 为 `system_prompt_policy`，key_name 为项目 ID，value 为同样结构的 JSON），
+//! 启动时和变更后加载到内存。同时配置时模型策略优先
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tracing::warn;
+
+use crate::dao::model::get_model_config_from_cache;
+use crate::dao::system_config::{
+    create_system_config, delete_system_config, get_system_config_by_key, list_system_configs_by_category,
+    update_system_config_value, SystemConfig,
+};
+use crate::llm_api::utils::msg_structure::Message;
+
+/// 项目策略在 system_configs 中的 category
+pub const SYSTEM_PROMPT_POLICY_CATEGORY: &str = "system_prompt_policy";
+
+/// 模型策略在 `models.config` 中的字段名
+pub const MODEL_CONFIG_KEY: &str = "system_prompt_policy";
+
+/// 配置的提示词与调用方 system 消息的合并方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SystemPromptMode {
+    Prepend,
+    Replace,
+    ForbidClientSystem,
+}
+
+impl SystemPromptMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Prepend => "prepend",
+            Self::Replace => "replace",
+            Self::ForbidClientSystem => "forbid_client_system",
+        }
+    }
+}
+
+/// 系统提示词策略
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SystemPromptPolicy {
+    pub mode: SystemPromptMode,
+    /// 注入的提示词，`forbid_client_system` 时可省略
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+}
+
+impl SystemPromptPolicy {
+    /// 检查配置：`prepend` 和 `replace` 必须提供非空的提示词
+    pub fn validate(&self) -> Result<(), String> {
+        let has_prompt = self.prompt.as_deref().is_some_and(|prompt| !prompt.trim().is_empty());
+        if !has_prompt && self.mode != SystemPromptMode::ForbidClientSystem {
+            return Err(format!("system_prompt_policy mode {} requires a non-empty prompt", self.mode.as_str()));
+        }
+        Ok(())
+    }
+
+    /// 按策略改写消息列表，调用方携带了被禁止的 system 消息时返回错误信息
+    pub fn apply(&self, messages: &mut Vec<Message>) -> Result<(), String> {
+        match self.mode {
+            SystemPromptMode::Prepend => {}
+            SystemPromptMode::Replace => messages.retain(|message| message.role != "system"),
+            SystemPromptMode::ForbidClientSystem => {
+                if messages.iter().any(|message| message.role == "system") {
+                    return Err("system messages are not allowed by the system prompt policy".to_string());
+                }
+            }
+        }
+        if let Some(prompt) = self.prompt.as_deref().filter(|prompt| !prompt.trim().is_empty()) {
+            messages.insert(0, Message::system(prompt.to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// 解析 `models.config` 中的模型策略；未配置时返回 `Ok(None)`，配置无效时返回错误信息
+pub fn parse_model_policy(config: Option<&str>) -> Result<Option<SystemPromptPolicy>, String> {
+    let Some(config) = config.filter(|config| !config.trim().is_empty()) else {
+        return Ok(None);
+    };
+    let Ok(serde_json::Value::Object(mut config)) = serde_json::from_str(config) else {
+        // 非 JSON 对象的 config 不属于本模块处理的范围
+        return Ok(None);
+    };
+    let Some(value) = config.remove(MODEL_CONFIG_KEY) else {
+        return Ok(None);
+    };
+    let policy: SystemPromptPolicy = serde_json::from_value(value).map_err(|e| format!("invalid system_prompt_policy: {}", e))?;
+    policy.validate()?;
+    Ok(Some(policy))
+}
+
+lazy_static! {
+    // 项目 ID -> 策略
+    static ref PROJECT_POLICIES: RwLock<HashMap<String, SystemPromptPolicy>> = RwLock::new(HashMap::new());
+}
+
+// 解析项目策略 JSON
+fn parse_project_policy(project_id: &str, value: &str) -> Option<SystemPromptPolicy> {
+    let policy = serde_json::from_str::<SystemPromptPolicy>(value)
+        .map_err(|e| e.to_string())
+        .and_then(|policy| policy.validate().map(|()| policy));
+    match policy {
+        Ok(policy) => Some(policy),
+        Err(e) => {
+            warn!(project_id = %project_id, value = %value, error = %e, "Ignoring invalid system prompt policy");
+            None
+        }
+    }
+}
+
+/// 列出已配置的项目策略（项目 ID, 策略）
+pub async fn list_project_policies(pool: &SqlitePool) -> sqlx::Result<Vec<(String, SystemPromptPolicy)>> {
+    Ok(list_system_configs_by_category(pool, SYSTEM_PROMPT_POLICY_CATEGORY)
+        .await?
+        .into_iter()
+        .filter_map(|config| parse_project_policy(&config.key_name, &config.value).map(|policy| (config.key_name, policy)))
+        .collect())
+}
+
+/// 从 system_configs 重新加载全部项目策略
+pub async fn reload_system_prompt_policies(pool: &SqlitePool) -> sqlx::Result<usize> {
+    let policies: HashMap<String, SystemPromptPolicy> = list_project_policies(pool).await?.into_iter().collect();
+    let count = policies.len();
+    *PROJECT_POLICIES.write().unwrap() = policies;
+    Ok(count)
+}
+
+/// 项目当前生效的策略
+pub fn get_project_policy(project_id: &str) -> Option<SystemPromptPolicy> {
+    PROJECT_POLICIES.read().unwrap().get(project_id).cloned()
+}
+
+/// 设置项目的策略并立即生效，`None` 表示删除
+pub async fn set_project_policy(pool: &SqlitePool, project_id: &str, policy: Option<&SystemPromptPolicy>) -> sqlx::Result<()> {
+    let existing = get_system_config_by_key(pool, SYSTEM_PROMPT_POLICY_CATEGORY, project_id).await?;
+    let value = policy.map(|policy| serde_json::to_string(policy).unwrap_or_else(|_| "{}".to_string()));
+    match (existing, value) {
+        (Some(config), None) => {
+            delete_system_config(pool, &config.id).await?;
+        }
+        (Some(_), Some(value)) => {
+            update_system_config_value(pool, SYSTEM_PROMPT_POLICY_CATEGORY, project_id, &value).await?;
+        }
+        (None, Some(value)) => {
+            let config = SystemConfig {
+                id: uuid::Uuid::new_v4().to_string(),
+                category: SYSTEM_PROMPT_POLICY_CATEGORY.to_string(),
+                key_name: project_id.to_string(),
+                value,
+                is_encrypted: false,
+                version: 1,
+                created_at: None,
+                updated_at: None,
+            };
+            create_system_config(pool, &config).await?;
+        }
+        (None, None) => {}
+    }
+    reload_system_prompt_policies(pool).await?;
+    Ok(())
+}
+
+/// 请求的目标模型和所属项目生效的策略，模型策略优先；返回策略及其来源（`model` / `project`）
+pub async fn resolve_policy(provider: &str, model: &str, project_id: &str) -> Option<(SystemPromptPolicy, &'static str)> {
+    let config = get_model_config_from_cache(provider, model).await;
+    match parse_model_policy(config.as_deref()) {
+        Ok(Some(policy)) => return Some((policy, "model")),
+        Ok(None) => {}
+        Err(e) => warn!(provider = %provider, model = %model, error = %e, "Ignoring invalid model system prompt policy"),
+    }
+    get_project_policy(project_id).map(|policy| (policy, "project"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(mode: SystemPromptMode, prompt: Option<&str>) -> SystemPromptPolicy {
+        SystemPromptPolicy { mode, prompt: prompt.map(str::to_string) }
+    }
+
+    fn conversation() -> Vec<Message> {
+        vec![Message::system("client prompt".to_string()), Message::user("hi".to_string())]
+    }
+
+    #[test]
+    fn test_apply_modes() {
+        let mut messages = conversation();
+        policy(SystemPromptMode::Prepend, Some("org prompt")).apply(&mut messages).unwrap();
+        let contents: Vec<&str> = messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["org prompt", "client prompt", "hi"]);
+
+        let mut messages = conversation();
+        policy(SystemPromptMode::Replace, Some("org prompt")).apply(&mut messages).unwrap();
+        let contents: Vec<&str> = messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["org prompt", "hi"]);
+
+        let forbid = policy(SystemPromptMode::ForbidClientSystem, None);
+        assert!(forbid.apply(&mut conversation()).is_err());
+        let mut messages = vec![Message::user("hi".to_string())];
+        forbid.apply(&mut messages).unwrap();
+        assert_eq!(messages.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_model_policy() {
+        let config = r#"{"temperature": 0.2, "system_prompt_policy": {"mode": "replace", "prompt": "org prompt"}}"#;
+        assert_eq!(parse_model_policy(Some(config)), Ok(Some(policy(SystemPromptMode::Replace, Some("org prompt")))));
+        assert_eq!(parse_model_policy(Some(r#"{"temperature": 0.2}"#)), Ok(None));
+        assert_eq!(parse_model_policy(None), Ok(None));
+        assert!(parse_model_policy(Some(r#"{"system_prompt_policy": {"mode": "prepend"}}"#)).is_err());
+        assert!(parse_model_policy(Some(r#"{"system_prompt_policy": {"mode": "append", "prompt": "x"}}"#)).is_err());
+    }
+}
@@ -0,0 +1,196 @@
+//! # Token 计数
+//!
+//! 近似计算请求的 token 数，用于在发送前检查模型的上下文窗口，以及在供应商没有返回用量时估算用量。
+//!
+//! OpenAI 模型按 tiktoken 的方式计数：先用 `cl100k_base` / `o200k_base` 的预分词规则切分文本，
+//! 再按片段长度估算 BPE 合并后的 token 数，消息格式开销与 OpenAI 的计算方法一致（每条消息 3 个 token，
+//! 回复前缀 3 个 token）。没有打包 BPE 词表，结果与 tiktoken 接近但不完全一致。
+//! 其他模型使用启发式估算：ASCII 字符约 4 个一个 token，中日韩等非 ASCII 字符每个一个 token
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::api_types::v1::dispatch::DispatchRequest;
+use crate::api_types::v1::Tool;
+use crate::llm_api::utils::msg_structure::Message;
+
+/// 每条消息的格式开销（`<|start|>{role}\n{content}<|end|>`）
+const TOKENS_PER_MESSAGE: usize = 3;
+/// 回复前缀 `<|start|>assistant<|message|>` 的开销
+pub const REPLY_PRIMING_TOKENS: usize = 3;
+/// 每张图片按低清晰度计费的 token 数
+const TOKENS_PER_IMAGE: usize = 85;
+
+lazy_static! {
+    // tiktoken 的预分词规则（去掉了 regex crate 不支持的占有量词和前瞻）
+    static ref PRE_TOKENIZE: Regex = Regex::new(
+        r"'(?i:[sdmt]|ll|ve|re)|[^\r\n\p{L}\p{N}]?\p{L}+|\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n]*|\s*[\r\n]+|\s+"
+    ).unwrap();
+}
+
+/// 按模型选择的编码方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// GPT-4、GPT-3.5、text-embedding-3
+    Cl100kBase,
+    /// GPT-4o、GPT-4.1、o 系列
+    O200kBase,
+    /// 非 OpenAI 模型
+    Heuristic,
+}
+
+impl Encoding {
+    /// 按模型名称选择编码，可带 `provider/` 前缀
+    pub fn for_model(model: &str) -> Self {
+        let model = model.rsplit('/').next().unwrap_or(model);
+        if ["gpt-4o", "gpt-4.1", "gpt-5", "o1", "o3", "o4"].iter().any(|prefix| model.starts_with(prefix)) {
+            Encoding::O200kBase
+        } else if ["gpt-4", "gpt-3.5", "text-embedding-"].iter().any(|prefix| model.starts_with(prefix)) {
+            Encoding::Cl100kBase
+        } else {
+            Encoding::Heuristic
+        }
+    }
+
+    /// 计算文本的 token 数
+    pub fn count(&self, text: &str) -> usize {
+        match self {
+            Encoding::Heuristic => {
+                let ascii = text.chars().filter(char::is_ascii).count();
+                let other = text.chars().count() - ascii;
+                ascii.div_ceil(4) + other
+            }
+            Encoding::Cl100kBase | Encoding::O200kBase => PRE_TOKENIZE
+                .find_iter(text)
+                .map(|piece| self.count_piece(piece.as_str()))
+                .sum(),
+        }
+    }
+
+    // 预分词后的片段：常见的短单词、数字组和标点是一个 token，长单词按平均合并长度拆分，
+    // 非 ASCII 字符按词表的覆盖程度估算（o200k 的多语言词表更大）
+    fn count_piece(&self, piece: &str) -> usize {
+        if piece.is_ascii() {
+            return piece.len().div_ceil(6).max(1);
+        }
+        let ascii = piece.chars().filter(char::is_ascii).count();
+        let other = piece.chars().count() - ascii;
+        let other = match self {
+            Encoding::O200kBase => (other * 3).div_ceil(4),
+            _ => other,
+        };
+        ascii.div_ceil(6) + other.max(1)
+    }
+}
+
+/// 计算文本在指定模型下的 token 数
+pub fn count_text_tokens(text: &str, model: &str) -> usize {
+    Encoding::for_model(model).count(text)
+}
+
+/// 计算单条消息在指定模型下的 token 数，包含消息格式开销
+pub fn count_message_tokens(message: &Message, model: &str) -> usize {
+    let encoding = Encoding::for_model(model);
+    let mut tokens = TOKENS_PER_MESSAGE + encoding.count(&message.role) + encoding.count(&message.content);
+    if let Some(images) = &message.images {
+        tokens += images.len() * TOKENS_PER_IMAGE;
+    }
+    for tool_call in message.tool_calls.iter().flatten() {
+        let arguments = serde_json::to_string(&tool_call.function.arguments).unwrap_or_default();
+        tokens += encoding.count(&tool_call.function.name) + encoding.count(&arguments);
+    }
+    if let Some(tool_call_id) = &message.tool_call_id {
+        tokens += encoding.count(tool_call_id);
+    }
+    tokens
+}
+
+/// 计算消息列表在指定模型下的 token 数，包含消息格式开销和回复前缀
+pub fn count_tokens(messages: &[Message], model: &str) -> usize {
+    messages.iter().map(|message| count_message_tokens(message, model)).sum::<usize>() + REPLY_PRIMING_TOKENS
+}
+
+/// 计算工具定义的 token 数
+pub fn count_tool_tokens(tools: &[Tool], model: &str) -> usize {
+    serde_json::to_string(tools)
+        .map(|tools| count_text_tokens(&tools, model))
+        .unwrap_or(0)
+}
+
+/// 计算请求的提示词 token 数：消息和工具定义
+pub fn count_request_tokens(request: &DispatchRequest) -> usize {
+    let tools = request.tools.as_deref().map_or(0, |tools| count_tool_tokens(tools, &request.model));
+    count_tokens(&request.messages, &request.model) + tools
+}
+
+/// 常见模型的上下文窗口（提示词和回复的 token 总数上限），按最长前缀匹配
+const CONTEXT_WINDOWS: &[(&str, usize)] = &[
+    ("gpt-4o", 128_000),
+    ("gpt-4.1", 1_047_576),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-4-32k", 32_768),
+    ("gpt-4", 8_192),
+    ("gpt-3.5-turbo", 16_385),
+    ("o1", 200_000),
+    ("o3", 200_000),
+    ("o4", 200_000),
+    ("qwen-turbo", 131_072),
+    ("qwen-plus", 131_072),
+    ("qwen-max", 32_768),
+    ("qwen-max-longcontext", 30_000),
+];
+
+/// 模型的上下文窗口，未知模型返回 None
+pub fn context_window(model: &str) -> Option<usize> {
+    let model = model.rsplit('/').next().unwrap_or(model);
+    CONTEXT_WINDOWS.iter()
+        .filter(|(prefix, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, window)| *window)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoding_for_model() {
+        assert_eq!(Encoding::for_model("gpt-4o-mini"), Encoding::O200kBase);
+        assert_eq!(Encoding::for_model("openai/gpt-4"), Encoding::Cl100kBase);
+        assert_eq!(Encoding::for_model("gpt-3.5-turbo"), Encoding::Cl100kBase);
+        assert_eq!(Encoding::for_model("qwen-plus"), Encoding::Heuristic);
+    }
+
+    #[test]
+    fn test_count_text_tokens() {
+        // tiktoken cl100k_base: "hello world" = 2, "Hello, world!" = 4
+        assert_eq!(count_text_tokens("hello world", "gpt-4"), 2);
+        assert_eq!(count_text_tokens("Hello, world!", "gpt-4"), 4);
+        // 数字按 3 位一组
+        assert_eq!(count_text_tokens("1234567", "gpt-4"), 3);
+        assert_eq!(count_text_tokens("", "gpt-4o"), 0);
+        assert_eq!(count_text_tokens("hello world!", "llama3"), 3);
+        assert_eq!(count_text_tokens("你好世界", "qwen-plus"), 4);
+    }
+
+    #[test]
+    fn test_count_tokens() {
+        // tiktoken 对这组消息的计数为 16（gpt-4）
+        let messages = vec![
+            Message::system("You are helpful.".to_string()),
+            Message::user("Hi".to_string()),
+        ];
+        let tokens = count_tokens(&messages, "gpt-4");
+        assert!((14..=18).contains(&tokens), "got {}", tokens);
+        assert_eq!(count_tokens(&[], "gpt-4"), REPLY_PRIMING_TOKENS);
+    }
+
+    #[test]
+    fn test_context_window() {
+        assert_eq!(context_window("gpt-4"), Some(8_192));
+        assert_eq!(context_window("gpt-4-0613"), Some(8_192));
+        assert_eq!(context_window("gpt-4o-mini"), Some(128_000));
+        assert_eq!(context_window("qwen-max-longcontext"), Some(30_000));
+        assert_eq!(context_window("llama3.2"), None);
+    }
+}
@@ -20,7 +20,9 @@ pub struct ToolFunction {
     /// 函数名称（唯一标识符）
     pub name: String,
     /// 函数功能描述，帮助 AI 理解何时使用此工具
+    #[serde(default)]
     pub description: String,
-    /// 函数参数的 JSON Schema 定义
+    /// 函数参数的 JSON Schema 定义（可省略）
+    #[serde(default, skip_serializing_if = "Value::is_null")]
     pub parameters: Value,
 }
\ No newline at end of file
@@ -0,0 +1,196 @@
+//! # W3C Trace Context 传播
+//!
+//! Web 中间件解析入站请求的 `traceparent` 并放入任务本地变量，调度器和 HTTP 客户端在同一任务
+//! （以及转发流式响应的子任务）中读取，发往供应商的每次尝试都带上 `traceparent`，
+//! 上游服务的链路可以接到网关的链路之下。
+//!
+//! 开启 `otel` feature 并配置 OTLP 导出时，span ID 取自当前 tracing span 对应的
+//! OpenTelemetry span，传播出去的 ID 与导出的链路一致；否则沿用入站的 trace ID，
+//! 每次尝试生成新的 span ID，并记录在日志字段中
+
+use std::future::Future;
+use tracing::Span;
+
+/// W3C Trace Context 请求头
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+tokio::task_local! {
+    static TRACE_PARENT: Option<TraceParent>;
+}
+
+/// `traceparent` 请求头的内容（version 00）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceParent {
+    /// 32 位小写十六进制 trace ID
+    pub trace_id: String,
+    /// 16 位小写十六进制的父 span ID
+    pub parent_id: String,
+    /// 是否采样
+    pub sampled: bool,
+}
+
+impl TraceParent {
+    /// 开始一条新的链路
+    pub fn new_root() -> Self {
+        Self {
+            trace_id: format!("{:032x}", rand::random::<u128>().max(1)),
+            parent_id: new_span_id(),
+            sampled: true,
+        }
+    }
+
+    /// 解析 `traceparent` 请求头，格式不正确或 ID 全为 0 时返回 None
+    ///
+    /// 高于 00 的版本只读取前四段，与规范中的前向兼容要求一致
+    pub fn parse(value: &str) -> Option<Self> {
+        let parts: Vec<&str> = value.trim().split('-').collect();
+        let [version, trace_id, parent_id, flags] = parts.get(..4)? else {
+            return None;
+        };
+        let is_hex = |s: &str, len: usize| s.len() == len && s.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b));
+        if !is_hex(version, 2) || *version == "ff" || (*version == "00" && parts.len() != 4) {
+            return None;
+        }
+        if !is_hex(trace_id, 32) || !is_hex(parent_id, 16) || !is_hex(flags, 2) {
+            return None;
+        }
+        if trace_id.bytes().all(|b| b == b'0') || parent_id.bytes().all(|b| b == b'0') {
+            return None;
+        }
+        let flags = u8::from_str_radix(flags, 16).ok()?;
+        Some(Self {
+            trace_id: trace_id.to_string(),
+            parent_id: parent_id.to_string(),
+            sampled: flags & 0x01 == 0x01,
+        })
+    }
+
+    /// 同一链路下的子 span（新的 span ID）
+    pub fn child(&self) -> Self {
+        Self {
+            trace_id: self.trace_id.clone(),
+            parent_id: new_span_id(),
+            sampled: self.sampled,
+        }
+    }
+
+    /// 格式化为 `traceparent` 请求头
+    pub fn to_header(&self) -> String {
+        format!("00-{}-{}-{:02x}", self.trace_id, self.parent_id, self.sampled as u8)
+    }
+
+    /// 当前任务的链路上下文
+    pub fn current() -> Option<Self> {
+        TRACE_PARENT.try_with(|trace_parent| trace_parent.clone()).ok().flatten()
+    }
+
+    /// 作为 OpenTelemetry 的远程父上下文
+    #[cfg(feature = "otel")]
+    pub fn otel_context(&self) -> Option<opentelemetry::Context> {
+        use opentelemetry::trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState};
+
+        let flags = if self.sampled { TraceFlags::SAMPLED } else { TraceFlags::default() };
+        let span_context = SpanContext::new(
+            TraceId::from_hex(&self.trace_id).ok()?,
+            SpanId::from_hex(&self.parent_id).ok()?,
+            flags,
+            true,
+            TraceState::default(),
+        );
+        Some(opentelemetry::Context::new().with_remote_span_context(span_context))
+    }
+}
+
+fn new_span_id() -> String {
+    format!("{:016x}", rand::random::<u64>().max(1))
+}
+
+/// 在给定的链路上下文中执行 future
+pub async fn with_trace_parent<F: Future>(trace_parent: Option<TraceParent>, future: F) -> F::Output {
+    TRACE_PARENT.scope(trace_parent, future).await
+}
+
+/// 入站请求的链路上下文：沿用请求头中的 `traceparent` 并把 `span` 挂到它下面，没有时开始新的链路
+#[cfg_attr(not(feature = "otel"), allow(unused_variables))]
+pub fn inbound_trace_parent(header: Option<&str>, span: &Span) -> TraceParent {
+    let remote = header.and_then(TraceParent::parse);
+    #[cfg(feature = "otel")]
+    {
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+        if let Some(context) = remote.as_ref().and_then(TraceParent::otel_context) {
+            span.set_parent(context);
+        }
+        if let Some(local) = otel_span_trace_parent(span) {
+            return local;
+        }
+    }
+    remote.map(|remote| remote.child()).unwrap_or_else(TraceParent::new_root)
+}
+
+/// 发往上游的 `traceparent`：当前 span 的 OpenTelemetry 上下文，或当前任务链路下新的子 span
+pub fn outbound_trace_parent() -> Option<TraceParent> {
+    #[cfg(feature = "otel")]
+    if let Some(trace_parent) = otel_span_trace_parent(&Span::current()) {
+        return Some(trace_parent);
+    }
+    TraceParent::current().map(|trace_parent| trace_parent.child())
+}
+
+// span 对应的 OpenTelemetry 上下文，未安装 OTLP 导出层时无效
+#[cfg(feature = "otel")]
+fn otel_span_trace_parent(span: &Span) -> Option<TraceParent> {
+    use opentelemetry::trace::TraceContextExt;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    let context = span.context();
+    let otel_span = context.span();
+    let span_context = otel_span.span_context();
+    span_context.is_valid().then(|| TraceParent {
+        trace_id: span_context.trace_id().to_string(),
+        parent_id: span_context.span_id().to_string(),
+        sampled: span_context.is_sampled(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_traceparent() {
+        let header = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let trace_parent = TraceParent::parse(header).unwrap();
+        assert_eq!(trace_parent.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(trace_parent.parent_id, "00f067aa0ba902b7");
+        assert!(trace_parent.sampled);
+        assert_eq!(trace_parent.to_header(), header);
+
+        // 未来版本允许附加字段
+        assert!(TraceParent::parse("01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00-extra").is_some());
+
+        assert!(TraceParent::parse("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra").is_none());
+        assert!(TraceParent::parse("ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").is_none());
+        assert!(TraceParent::parse("00-00000000000000000000000000000000-00f067aa0ba902b7-01").is_none());
+        assert!(TraceParent::parse("00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01").is_none());
+        assert!(TraceParent::parse("not a traceparent").is_none());
+    }
+
+    #[test]
+    fn test_child_keeps_trace_id() {
+        let root = TraceParent::new_root();
+        let child = root.child();
+        assert_eq!(child.trace_id, root.trace_id);
+        assert_ne!(child.parent_id, root.parent_id);
+        assert_eq!(TraceParent::parse(&child.to_header()), Some(child));
+    }
+
+    #[tokio::test]
+    async fn test_outbound_trace_parent_uses_task_context() {
+        assert!(outbound_trace_parent().is_none());
+        let root = TraceParent::new_root();
+        let outbound = with_trace_parent(Some(root.clone()), async { outbound_trace_parent() }).await.unwrap();
+        assert_eq!(outbound.trace_id, root.trace_id);
+        assert_ne!(outbound.parent_id, root.parent_id);
+    }
+}
@@ -0,0 +1,128 @@
+//! # A/B 流量拆分
+//!
+//! 按百分比把同一逻辑模型名的请求分到不同的供应商/模型（例如 `qwen` 的 90% 发往 qwen-plus，10% 发往 qwen-max），
+//! 调度器把选中的分组写入调用记录的 `traffic_arm`（`逻辑模型名:分组名`），用于按分组对比质量、延迟和费用。
+//! 请求带有终端用户标识时按用户哈希分组，同一用户始终落在同一分组
+
+use std::collections::HashSet;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use anyhow::{bail, Result};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::llm_api::dispatcher::Provider;
+
+/// 流量拆分中的一个分组
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TrafficArm {
+    /// 分组名称，写入调用记录
+    pub name: String,
+    /// 供应商名称（与 providers.name 一致）
+    pub provider: String,
+    pub model: String,
+    /// 分到该分组的流量百分比
+    pub percent: u32,
+}
+
+impl TrafficArm {
+    pub fn provider(&self) -> Provider {
+        Provider::from_name_or_custom(&self.provider)
+    }
+}
+
+/// 一个逻辑模型名的流量拆分，各分组的百分比之和为 100
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrafficSplit {
+    arms: Vec<TrafficArm>,
+}
+
+impl TrafficSplit {
+    /// 校验分组：至少两个、名称不重复、百分比之和为 100
+    pub fn new(arms: Vec<TrafficArm>) -> Result<Self> {
+        if arms.len() < 2 {
+            bail!("traffic split needs at least 2 arms, got {}", arms.len());
+        }
+        let mut names = HashSet::new();
+        for arm in &arms {
+            if arm.name.trim().is_empty() || arm.model.trim().is_empty() || arm.provider.trim().is_empty() {
+                bail!("traffic split arm name, provider and model must not be empty");
+            }
+            if !names.insert(arm.name.as_str()) {
+                bail!("duplicate traffic split arm `{}`", arm.name);
+            }
+        }
+        let total: u32 = arms.iter().map(|arm| arm.percent).sum();
+        if total != 100 {
+            bail!("traffic split percentages must add up to 100, got {}", total);
+        }
+        Ok(Self { arms })
+    }
+
+    pub fn arms(&self) -> &[TrafficArm] {
+        &self.arms
+    }
+
+    /// 按 0..100 的分桶选择分组
+    pub fn pick(&self, bucket: u32) -> &TrafficArm {
+        let mut upper = 0;
+        for arm in &self.arms {
+            upper += arm.percent;
+            if bucket < upper {
+                return arm;
+            }
+        }
+        // 百分比之和为 100，分桶超出范围时落在最后一个分组
+        self.arms.last().expect("traffic split has at least 2 arms")
+    }
+
+    /// 选择请求的分组：有终端用户标识时按用户哈希分桶，否则随机
+    pub fn choose(&self, user: Option<&str>) -> &TrafficArm {
+        let bucket = match user.filter(|user| !user.trim().is_empty()) {
+            Some(user) => {
+                let mut hasher = DefaultHasher::new();
+                user.hash(&mut hasher);
+                (hasher.finish() % 100) as u32
+            }
+            None => rand::thread_rng().gen_range(0..100),
+        };
+        self.pick(bucket)
+    }
+}
+
+/// 调用记录中的分组标识
+pub fn arm_label(model: &str, arm: &TrafficArm) -> String {
+    format!("{}:{}", model, arm.name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arm(name: &str, model: &str, percent: u32) -> TrafficArm {
+        TrafficArm { name: name.to_string(), provider: "ali".to_string(), model: model.to_string(), percent }
+    }
+
+    #[test]
+    fn test_pick_by_percent() {
+        let split = TrafficSplit::new(vec![arm("plus", "qwen-plus", 90), arm("max", "qwen-max", 10)]).unwrap();
+        assert_eq!(split.pick(0).name, "plus");
+        assert_eq!(split.pick(89).name, "plus");
+        assert_eq!(split.pick(90).name, "max");
+        assert_eq!(split.pick(99).name, "max");
+        assert_eq!(split.arms()[0].provider(), Provider::Ali);
+
+        // 同一用户始终落在同一分组
+        let first = split.choose(Some("user-1")).name.clone();
+        assert!((0..10).all(|_| split.choose(Some("user-1")).name == first));
+        assert_eq!(arm_label("qwen", split.pick(95)), "qwen:max");
+    }
+
+    #[test]
+    fn test_invalid_split() {
+        assert!(TrafficSplit::new(vec![arm("plus", "qwen-plus", 100)]).is_err());
+        assert!(TrafficSplit::new(vec![arm("plus", "qwen-plus", 50), arm("max", "qwen-max", 40)]).is_err());
+        assert!(TrafficSplit::new(vec![arm("plus", "qwen-plus", 50), arm("plus", "qwen-max", 50)]).is_err());
+    }
+}
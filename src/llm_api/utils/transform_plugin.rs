@@ -0,0 +1,209 @@
+//! # 请求/响应转换插件
+//!
+//! 在调用供应商前后对请求和响应执行自定义转换，插件按注册顺序依次执行。
+//! 插件可以是 Rust 实现，也可以是开启 `wasm-plugins` feature 后加载的 WASM 模块
+//! （见 [`crate::llm_api::utils::wasm_plugin`]），业务逻辑无需修改网关代码
+
+use std::sync::{Arc, RwLock};
+use anyhow::Result;
+use lazy_static::lazy_static;
+use tracing::warn;
+
+use crate::llm_api::dispatcher::{DispatchRequest, DispatchResponse};
+use crate::metrics::metrics;
+
+/// 转换插件
+pub trait TransformPlugin: Send + Sync {
+    /// 插件名称，同名插件注册时会被替换
+    fn name(&self) -> &str;
+
+    /// 调用供应商前转换请求，返回错误表示拒绝该请求
+    fn transform_request(&self, request: DispatchRequest) -> Result<DispatchRequest> {
+        Ok(request)
+    }
+
+    /// 收到供应商响应后转换响应（流式响应不经过该钩子）
+    fn transform_response(&self, response: DispatchResponse) -> Result<DispatchResponse> {
+        Ok(response)
+    }
+}
+
+/// 转换插件管道
+pub struct TransformPipeline {
+    plugins: RwLock<Vec<Arc<dyn TransformPlugin>>>,
+}
+
+impl TransformPipeline {
+    pub fn new() -> Self {
+        Self {
+            plugins: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// 注册插件，同名插件原位替换，否则追加到末尾
+    pub fn register(&self, plugin: Arc<dyn TransformPlugin>) {
+        let mut plugins = self.plugins.write().unwrap();
+        match plugins.iter().position(|p| p.name() == plugin.name()) {
+            Some(index) => plugins[index] = plugin,
+            None => plugins.push(plugin),
+        }
+    }
+
+    /// 按名称移除插件
+    pub fn unregister(&self, name: &str) -> bool {
+        let mut plugins = self.plugins.write().unwrap();
+        let before = plugins.len();
+        plugins.retain(|p| p.name() != name);
+        plugins.len() != before
+    }
+
+    /// 移除所有插件
+    pub fn clear(&self) {
+        self.plugins.write().unwrap().clear();
+    }
+
+    /// 已注册的插件名称（按执行顺序）
+    pub fn plugin_names(&self) -> Vec<String> {
+        self.plugins.read().unwrap().iter().map(|p| p.name().to_string()).collect()
+    }
+
+    /// 依次执行所有插件的请求钩子
+    pub fn apply_request(&self, mut request: DispatchRequest) -> Result<DispatchRequest> {
+        for plugin in self.snapshot() {
+            request = Self::record(plugin.name(), "request", plugin.transform_request(request))?;
+        }
+        Ok(request)
+    }
+
+    /// 依次执行所有插件的响应钩子
+    pub fn apply_response(&self, mut response: DispatchResponse) -> Result<DispatchResponse> {
+        for plugin in self.snapshot() {
+            response = Self::record(plugin.name(), "response", plugin.transform_response(response))?;
+        }
+        Ok(response)
+    }
+
+    // 复制插件列表，执行插件时不持有锁
+    fn snapshot(&self) -> Vec<Arc<dyn TransformPlugin>> {
+        self.plugins.read().unwrap().clone()
+    }
+
+    fn record<T>(plugin: &str, hook: &str, result: Result<T>) -> Result<T> {
+        let status = if result.is_ok() { "ok" } else { "error" };
+        metrics().incr_counter(
+            "llm_gateway_transform_plugin_calls_total",
+            &[("plugin", plugin), ("hook", hook), ("result", status)],
+        );
+        result.map_err(|e| {
+            warn!(plugin = plugin, hook = hook, error = %e, "Transform plugin failed");
+            e.context(format!("transform plugin '{}' failed in {} hook", plugin, hook))
+        })
+    }
+}
+
+impl Default for TransformPipeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+lazy_static! {
+    /// 全局转换插件管道
+    static ref GLOBAL_TRANSFORM_PIPELINE: TransformPipeline = TransformPipeline::new();
+}
+
+/// 获取全局转换插件管道
+pub fn get_transform_pipeline() -> &'static TransformPipeline {
+    &GLOBAL_TRANSFORM_PIPELINE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::bail;
+    use crate::llm_api::dispatcher::Provider;
+    use crate::llm_api::utils::msg_structure::Message;
+
+    struct PrefixPlugin {
+        name: &'static str,
+        prefix: &'static str,
+    }
+
+    impl TransformPlugin for PrefixPlugin {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn transform_request(&self, mut request: DispatchRequest) -> Result<DispatchRequest> {
+            if let Some(message) = request.messages.last_mut() {
+                message.content = format!("{}{}", self.prefix, message.content);
+            }
+            Ok(request)
+        }
+
+        fn transform_response(&self, mut response: DispatchResponse) -> Result<DispatchResponse> {
+            response.content = format!("{}{}", self.prefix, response.content);
+            Ok(response)
+        }
+    }
+
+    struct RejectPlugin;
+
+    impl TransformPlugin for RejectPlugin {
+        fn name(&self) -> &str {
+            "reject"
+        }
+
+        fn transform_request(&self, _request: DispatchRequest) -> Result<DispatchRequest> {
+            bail!("request rejected by policy")
+        }
+    }
+
+    fn request() -> DispatchRequest {
+        DispatchRequest::new(Provider::Ollama, "llama3.2".to_string(), vec![Message::user("hello".to_string())])
+    }
+
+    #[test]
+    fn test_pipeline_applies_plugins_in_order() {
+        let pipeline = TransformPipeline::new();
+        pipeline.register(Arc::new(PrefixPlugin { name: "a", prefix: "A:" }));
+        pipeline.register(Arc::new(PrefixPlugin { name: "b", prefix: "B:" }));
+        // 同名插件原位替换，不改变执行顺序
+        pipeline.register(Arc::new(PrefixPlugin { name: "a", prefix: "X:" }));
+        assert_eq!(pipeline.plugin_names(), vec!["a", "b"]);
+
+        let request = pipeline.apply_request(request()).unwrap();
+        assert_eq!(request.messages[0].content, "B:X:hello");
+
+        let response = DispatchResponse {
+            content: "hi".to_string(),
+            provider: Provider::Ollama,
+            model: "llama3.2".to_string(),
+            usage: None,
+            finish_reason: None,
+            request_id: None,
+            created_at: String::new(),
+            total_duration: None,
+            tool_calls: None,
+            message: None,
+            raw: None,
+            warnings: None,
+        };
+        assert_eq!(pipeline.apply_response(response).unwrap().content, "B:X:hi");
+
+        assert!(pipeline.unregister("b"));
+        assert!(!pipeline.unregister("b"));
+        assert_eq!(pipeline.plugin_names(), vec!["a"]);
+    }
+
+    #[test]
+    fn test_pipeline_plugin_error_stops_request() {
+        let pipeline = TransformPipeline::new();
+        pipeline.register(Arc::new(RejectPlugin));
+        pipeline.register(Arc::new(PrefixPlugin { name: "a", prefix: "A:" }));
+
+        let error = pipeline.apply_request(request()).unwrap_err();
+        assert!(error.to_string().contains("reject"));
+        assert!(format!("{:#}", error).contains("request rejected by policy"));
+    }
+}
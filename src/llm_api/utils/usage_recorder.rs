@@ -0,0 +1,89 @@
+//! # 调用用量记录
+//!
+//! 客户端在 HTTP 层写入调用记录时还无法得知 token 用量，调度器拿到供应商返回的
+//! 用量后，按模型单价计算费用并回填到最终返回响应的那条调用记录；供应商返回的
+//! 请求 ID 和原始用量也一并保存，用于与供应商账单对账。用量同时按项目/供应商/模型/天
+//! 累加到 `usage_stats` 和预算缓存，作为各项目月度预算限额的依据；发起请求的调用方的 token 用量计入调用方配额
+
+use tracing::warn;
+
+use crate::dao::SQLITE_POOL;
+use crate::dao::call_log::{update_call_log_usage, CallLogUsage};
+use crate::dao::model::{get_model_by_provider_and_name, Model};
+use crate::dao::usage_stats::{record_usage_stat, UsageStatKey};
+use crate::llm_api::dispatcher::{Provider, TokenUsage};
+use crate::llm_api::utils::budget::record_budget_spend;
+use crate::llm_api::utils::client::CallMetadata;
+use crate::llm_api::utils::consumer_quota::get_consumer_quotas;
+
+/// 按模型单价（每 token）计算费用，未配置单价时按 0 计算
+pub fn compute_cost(model: Option<&Model>, usage: &TokenUsage) -> f64 {
+    let Some(model) = model else {
+        return 0.0;
+    };
+    usage.prompt_tokens as f64 * model.cost_per_token_input.unwrap_or(0.0)
+        + usage.completion_tokens as f64 * model.cost_per_token_output.unwrap_or(0.0)
+}
+
+/// 累加当天的用量统计和调用方的 token 用量，并将用量、费用和结束原因回填到当前调度最后写入的调用记录
+pub async fn record_call_usage(
+    metadata: &CallMetadata,
+    provider: &Provider,
+    model_name: &str,
+    usage: &TokenUsage,
+    finish_reason: Option<&str>,
+) {
+    if let Some(consumer_id) = &metadata.consumer_id {
+        get_consumer_quotas().record_tokens(consumer_id, usage.total_tokens as u64);
+    }
+
+    let Some(pool) = SQLITE_POOL.get() else {
+        return;
+    };
+
+    let model = match get_model_by_provider_and_name(pool, provider.as_str(), model_name).await {
+        Ok(model) => model,
+        Err(e) => {
+            warn!(provider = %provider.as_str(), model = %model_name, error = %e, "Failed to load model pricing");
+            None
+        }
+    };
+    let cost = compute_cost(model.as_ref(), usage);
+
+    let day = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let key = UsageStatKey {
+        day: &day,
+        project_id: metadata.project_id(),
+        provider: provider.as_str(),
+        model: model_name,
+    };
+    record_budget_spend(metadata.project_id(), provider.as_str(), cost);
+    if let Err(e) = record_usage_stat(
+        pool,
+        &key,
+        usage.prompt_tokens as i64,
+        usage.completion_tokens as i64,
+        cost,
+    ).await {
+        warn!(provider = %provider.as_str(), model = %model_name, error = %e, "Failed to record usage stats");
+    }
+
+    let Some(call_log_id) = metadata.last_call_log_id() else {
+        return;
+    };
+    let billing = metadata.provider_billing().unwrap_or_default();
+    let call_usage = CallLogUsage {
+        model_id: model.map(|m| m.id),
+        tokens_input: usage.prompt_tokens as i64,
+        tokens_output: usage.completion_tokens as i64,
+        cost,
+        provider: Some(provider.as_str().to_string()),
+        finish_reason: finish_reason.map(str::to_string),
+        provider_request_id: billing.request_id,
+        provider_usage: billing.usage.map(|u| u.to_string()),
+    };
+
+    if let Err(e) = update_call_log_usage(pool, &call_log_id, &call_usage).await {
+        warn!(call_log_id = %call_log_id, error = %e, "Failed to record call usage");
+    }
+}
@@ -0,0 +1,196 @@
+//! # WASM 转换插件运行时（实验性）
+//!
+//! 需要开启 `wasm-plugins` feature。运维人员提供的 WASM 模块在 wasmtime 沙箱中执行，
+//! 不提供任何宿主函数导入，并限制燃料（指令数）和线性内存大小。
+//!
+//! 模块需要导出：
+//! - `memory`：线性内存
+//! - `alloc(len: i32) -> i32`：分配 len 字节，返回指针，宿主把输入 JSON 写入该位置
+//! - `transform_request(ptr: i32, len: i32) -> i64`（可选）：输入 `DispatchRequest` JSON
+//! - `transform_response(ptr: i32, len: i32) -> i64`（可选）：输入 `DispatchResponse` JSON
+//!
+//! 钩子返回值高 32 位为输出指针、低 32 位为输出长度，输出为转换后的同结构 JSON。
+//! 输出长度为 0 表示不修改；如果输出是 `{"error": "..."}` 则拒绝本次调用
+
+use std::path::Path;
+use std::sync::Arc;
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use tracing::info;
+use wasmtime::{Config, Engine, Instance, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use crate::llm_api::dispatcher::{DispatchRequest, DispatchResponse};
+use crate::llm_api::utils::transform_plugin::{get_transform_pipeline, TransformPlugin};
+
+/// 请求钩子导出名
+const REQUEST_HOOK: &str = "transform_request";
+/// 响应钩子导出名
+const RESPONSE_HOOK: &str = "transform_response";
+/// 单次钩子调用的燃料上限
+const DEFAULT_FUEL_LIMIT: u64 = 50_000_000;
+/// 单个实例的线性内存上限（字节）
+const DEFAULT_MAX_MEMORY_BYTES: usize = 64 * 1024 * 1024;
+/// 钩子输出的最大长度（字节）
+const MAX_OUTPUT_BYTES: usize = 16 * 1024 * 1024;
+
+/// 基于 WASM 模块的转换插件，每次调用都创建新的实例，调用之间不共享状态
+pub struct WasmTransformPlugin {
+    name: String,
+    engine: Engine,
+    module: Module,
+    fuel_limit: u64,
+    max_memory_bytes: usize,
+    has_request_hook: bool,
+    has_response_hook: bool,
+}
+
+impl WasmTransformPlugin {
+    /// 从 .wasm 文件加载插件，插件名称为文件名（不含扩展名）
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| anyhow!("invalid plugin file name: {}", path.display()))?
+            .to_string();
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("failed to read plugin file {}", path.display()))?;
+        Self::from_bytes(name, &bytes)
+    }
+
+    /// 从 WASM 二进制加载插件
+    pub fn from_bytes(name: String, bytes: &[u8]) -> Result<Self> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config)?;
+        let module = Module::new(&engine, bytes)
+            .with_context(|| format!("failed to compile wasm plugin '{}'", name))?;
+
+        // 沙箱：不向插件提供任何宿主函数
+        if let Some(import) = module.imports().next() {
+            bail!(
+                "wasm plugin '{}' must not import host functions (found {}::{})",
+                name, import.module(), import.name()
+            );
+        }
+        for required in ["memory", "alloc"] {
+            if module.get_export(required).is_none() {
+                bail!("wasm plugin '{}' does not export '{}'", name, required);
+            }
+        }
+
+        let has_request_hook = module.get_export(REQUEST_HOOK).is_some();
+        let has_response_hook = module.get_export(RESPONSE_HOOK).is_some();
+        if !has_request_hook && !has_response_hook {
+            bail!("wasm plugin '{}' exports neither {} nor {}", name, REQUEST_HOOK, RESPONSE_HOOK);
+        }
+
+        Ok(Self {
+            name,
+            engine,
+            module,
+            fuel_limit: DEFAULT_FUEL_LIMIT,
+            max_memory_bytes: DEFAULT_MAX_MEMORY_BYTES,
+            has_request_hook,
+            has_response_hook,
+        })
+    }
+
+    /// 设置单次调用的燃料上限
+    pub fn with_fuel_limit(mut self, fuel_limit: u64) -> Self {
+        self.fuel_limit = fuel_limit;
+        self
+    }
+
+    /// 设置线性内存上限（字节）
+    pub fn with_max_memory_bytes(mut self, max_memory_bytes: usize) -> Self {
+        self.max_memory_bytes = max_memory_bytes;
+        self
+    }
+
+    // 以 JSON 调用钩子，输出为空时返回原值
+    fn call_json<T: Serialize + DeserializeOwned>(&self, hook: &str, value: T) -> Result<T> {
+        let input = serde_json::to_vec(&value)?;
+        let output = self.call_hook(hook, &input)?;
+        if output.is_empty() {
+            return Ok(value);
+        }
+
+        let output: Value = serde_json::from_slice(&output)
+            .with_context(|| format!("{} returned invalid JSON", hook))?;
+        if let Some(error) = output.get("error").and_then(|e| e.as_str()) {
+            bail!("{}", error);
+        }
+        Ok(serde_json::from_value(output)?)
+    }
+
+    // 在新的沙箱实例中执行一次钩子调用
+    fn call_hook(&self, hook: &str, input: &[u8]) -> Result<Vec<u8>> {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(self.max_memory_bytes)
+            .instances(1)
+            .build();
+        let mut store: Store<StoreLimits> = Store::new(&self.engine, limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(self.fuel_limit)?;
+
+        let instance = Instance::new(&mut store, &self.module, &[])?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| anyhow!("export 'memory' is not a memory"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+        let hook_fn = instance.get_typed_func::<(i32, i32), i64>(&mut store, hook)?;
+
+        let input_len = i32::try_from(input.len()).context("input too large for wasm plugin")?;
+        let input_ptr = alloc.call(&mut store, input_len)?;
+        memory.write(&mut store, input_ptr as u32 as usize, input)?;
+
+        let packed = hook_fn.call(&mut store, (input_ptr, input_len))? as u64;
+        let output_ptr = (packed >> 32) as usize;
+        let output_len = (packed & 0xffff_ffff) as usize;
+        if output_len > MAX_OUTPUT_BYTES {
+            bail!("{} output too large ({} bytes)", hook, output_len);
+        }
+
+        let mut output = vec![0u8; output_len];
+        memory.read(&store, output_ptr, &mut output)?;
+        Ok(output)
+    }
+}
+
+impl TransformPlugin for WasmTransformPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn transform_request(&self, request: DispatchRequest) -> Result<DispatchRequest> {
+        if !self.has_request_hook {
+            return Ok(request);
+        }
+        self.call_json(REQUEST_HOOK, request)
+    }
+
+    fn transform_response(&self, response: DispatchResponse) -> Result<DispatchResponse> {
+        if !self.has_response_hook {
+            return Ok(response);
+        }
+        self.call_json(RESPONSE_HOOK, response)
+    }
+}
+
+/// 加载目录下所有 .wasm 文件（按文件名排序）并注册到全局转换管道，返回加载数量
+pub fn load_wasm_plugins(dir: &Path) -> Result<usize> {
+    let mut paths: Vec<_> = std::fs::read_dir(dir)
+        .with_context(|| format!("failed to read plugin directory {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "wasm"))
+        .collect();
+    paths.sort();
+
+    for path in &paths {
+        let plugin = WasmTransformPlugin::from_file(path)?;
+        info!(plugin = plugin.name(), path = %path.display(), "WASM transform plugin loaded");
+        get_transform_pipeline().register(Arc::new(plugin));
+    }
+    Ok(paths.len())
+}
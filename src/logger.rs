@@ -4,9 +4,13 @@ use tracing_subscriber::{
     util::SubscriberInitExt,
     EnvFilter,
 };
+#[cfg(feature = "otel")]
+use tracing_subscriber::registry::LookupSpan;
 use tracing_appender::{non_blocking, rolling};
 use anyhow::Result;
 
+use crate::llm_api::utils::redaction::RedactingMakeWriter;
+
 /// 日志级别枚举
 #[derive(Debug, Clone)]
 pub enum LogLevel {
@@ -17,6 +21,21 @@ pub enum LogLevel {
     Trace,
 }
 
+impl std::str::FromStr for LogLevel {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "error" => Ok(LogLevel::Error),
+            "warn" => Ok(LogLevel::Warn),
+            "info" => Ok(LogLevel::Info),
+            "debug" => Ok(LogLevel::Debug),
+            "trace" => Ok(LogLevel::Trace),
+            other => Err(anyhow::anyhow!("Unknown log level `{}`", other)),
+        }
+    }
+}
+
 impl From<LogLevel> for &'static str {
     fn from(level: LogLevel) -> Self {
         match level {
@@ -44,6 +63,10 @@ pub struct LogConfig {
     pub json_format: bool,
     /// 日志文件滚动策略 (daily, hourly)
     pub rotation: String,
+    /// OTLP/HTTP 链路导出地址（完整的 traces 地址），为 None 时不导出；需要开启 `otel` feature
+    pub otlp_endpoint: Option<String>,
+    /// 导出链路时的服务名
+    pub service_name: String,
 }
 
 impl Default for LogConfig {
@@ -55,10 +78,27 @@ impl Default for LogConfig {
             console_output: true,
             json_format: false,
             rotation: "daily".to_string(),
+            otlp_endpoint: otlp_endpoint_from_env(),
+            service_name: service_name_from_env(),
         }
     }
 }
 
+/// 读取 `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT`，未设置时在 `OTEL_EXPORTER_OTLP_ENDPOINT` 后追加 `/v1/traces`
+fn otlp_endpoint_from_env() -> Option<String> {
+    let var = |name: &str| std::env::var(name).ok().map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    var("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT")
+        .or_else(|| var("OTEL_EXPORTER_OTLP_ENDPOINT").map(|base| format!("{}/v1/traces", base.trim_end_matches('/'))))
+}
+
+/// 读取 `OTEL_SERVICE_NAME`，默认 `llm-gateway`
+fn service_name_from_env() -> String {
+    std::env::var("OTEL_SERVICE_NAME")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| "llm-gateway".to_string())
+}
+
 /// 初始化日志系统
 pub fn init_logger(config: LogConfig) -> Result<()> {
     // 确保日志目录存在
@@ -73,12 +113,15 @@ pub fn init_logger(config: LogConfig) -> Result<()> {
 
     let (non_blocking_file, _guard) = non_blocking(file_appender);
 
+    // 配置了 OTLP 地址时导出链路
+    let otlp_layer = otlp_layer(&config)?;
+
     // 创建环境过滤器
     let env_filter = EnvFilter::new(format!("{}={}", env!("CARGO_PKG_NAME").replace("-", "_"), <&str>::from(config.level)));
 
-    // 创建格式化器
+    // 创建格式化器，文件和控制台输出都经过脱敏
     let file_layer = fmt::layer()
-        .with_writer(non_blocking_file)
+        .with_writer(RedactingMakeWriter::new(non_blocking_file))
         .with_timer(ChronoUtc::rfc_3339())
         .with_ansi(false)
         .with_target(true)
@@ -87,26 +130,23 @@ pub fn init_logger(config: LogConfig) -> Result<()> {
         .with_line_number(true);
 
     // 如果启用控制台输出
-    if config.console_output {
-        let console_layer = fmt::layer()
+    let console_layer = config.console_output.then(|| {
+        fmt::layer()
+            .with_writer(RedactingMakeWriter::new(std::io::stdout))
             .with_timer(ChronoUtc::rfc_3339())
             .with_ansi(true)
             .with_target(false)
             .with_thread_ids(false)
             .with_file(false)
-            .with_line_number(false);
-        
-        tracing_subscriber::registry()
-            .with(env_filter)
-            .with(file_layer)
-            .with(console_layer)
-            .init();
-    } else {
-        tracing_subscriber::registry()
-            .with(env_filter)
-            .with(file_layer)
-            .init();
-    }
+            .with_line_number(false)
+    });
+
+    tracing_subscriber::registry()
+        .with(env_filter)
+        .with(file_layer)
+        .with(console_layer)
+        .with(otlp_layer)
+        .init();
 
     // 防止guard被丢弃
     std::mem::forget(_guard);
@@ -114,6 +154,48 @@ pub fn init_logger(config: LogConfig) -> Result<()> {
     Ok(())
 }
 
+/// 创建通过 OTLP/HTTP 批量导出 span 的 layer，并注册为全局 TracerProvider
+///
+/// 需要在 tokio 运行时中调用
+#[cfg(feature = "otel")]
+fn otlp_layer<S>(config: &LogConfig) -> Result<Option<tracing_opentelemetry::OpenTelemetryLayer<S, opentelemetry_sdk::trace::Tracer>>>
+where
+    S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+{
+    use opentelemetry::{trace::TracerProvider as _, KeyValue};
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::{runtime, trace::TracerProvider, Resource};
+
+    let Some(endpoint) = &config.otlp_endpoint else {
+        return Ok(None);
+    };
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint.as_str())
+        .build()?;
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_resource(Resource::new([KeyValue::new("service.name", config.service_name.clone())]))
+        .build();
+    let tracer = provider.tracer(env!("CARGO_PKG_NAME"));
+    opentelemetry::global::set_tracer_provider(provider);
+    Ok(Some(tracing_opentelemetry::layer().with_tracer(tracer)))
+}
+
+#[cfg(not(feature = "otel"))]
+fn otlp_layer(config: &LogConfig) -> Result<Option<tracing_subscriber::layer::Identity>> {
+    if config.otlp_endpoint.is_some() {
+        eprintln!("OTLP endpoint is configured but the `otel` feature is disabled, traces will not be exported");
+    }
+    Ok(None)
+}
+
+/// 导出剩余的链路数据，进程退出前调用
+pub fn shutdown_tracing() {
+    #[cfg(feature = "otel")]
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
 /// 快速初始化开发环境日志
 pub fn init_dev_logger() -> Result<()> {
     let config = LogConfig {
@@ -123,6 +205,7 @@ pub fn init_dev_logger() -> Result<()> {
         console_output: true,
         json_format: false,
         rotation: "daily".to_string(),
+        ..Default::default()
     };
     init_logger(config)
 }
@@ -136,6 +219,7 @@ pub fn init_prod_logger() -> Result<()> {
         console_output: false,
         json_format: true,
         rotation: "daily".to_string(),
+        ..Default::default()
     };
     init_logger(config)
 }
@@ -1,6 +1,10 @@
+mod api_types;
+mod config;
 mod dao;
 mod llm_api;
 mod logger;
+mod metrics;
+mod notification;
 
 use dao::{SQLITE_POOL, init_sqlite_pool, init_db};
 use dao::cache::{init_global_cache};
@@ -19,16 +23,27 @@ async fn main() {
     }
     info!("Logger initialized successfully");
 
+    //*
+    //* Load gateway.toml and environment overrides
+    //*
+    let config = match config::init_gateway_config() {
+        Ok(config) => config,
+        Err(e) => {
+            error!("Config load failed: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+
     //* 
     //* Initialize database
     //* 
     info!("Initializing database...");
     // Initialize the SQLite connection pool
-    init_sqlite_pool("sqlite://data/app.db").await;
+    init_sqlite_pool(&config.database.url).await;
     // Get a reference to the connection pool
     let pool = SQLITE_POOL.get().unwrap().clone();
-    // Initialize the database using the SQL script
-    match init_db("data/init.sql").await {
+    // Run embedded migrations and the optional extra SQL script
+    match init_db(&config.database.init_sql_path).await {
         Ok(_) => info!("Database initialized successfully"),
         Err(e) => {
             error!("DB init failed: {}", e);
@@ -36,28 +51,21 @@ async fn main() {
         }
     }
     //*
-    //* Test data for Provider Key Pool
+    //* Seed default providers and models (SEED_DEFAULT_DATA=true)
     //*
-    // use dao::provider_key_pool::{create_provider_key_pool_from_raw_key};
-    
-    // create_provider_key_pool_from_raw_key(
-    //     &pool,
-    //     uuid::Uuid::new_v4().to_string(),
-    //     "openai".to_string(),
-    //     "sk-test-openai-1234567890".to_string().as_str(),
-    //     true,
-    //     Some(60),
-    //     Some(3600),
-    // ).await.expect("Failed to create provider key pool entry");
-
-    
+    if dao::seed::seed_enabled_from_env() {
+        match dao::seed::seed_default_data(&pool).await {
+            Ok(report) => info!("Seeded {} providers and {} models", report.providers_created.len(), report.models_created.len()),
+            Err(e) => warn!("Default data seeding failed: {}", e),
+        }
+    }
 
     //* 
     //* Initialize memory cache
     //* 
     info!("Initializing memory cache...");
-    // Initialize global cache with 1 hour TTL and max 1000 entries
-    match init_global_cache(&pool, 3600, 1000).await {
+    // Initialize global cache with the configured TTL and capacity
+    match init_global_cache(&pool, config.cache.ttl_secs, config.cache.max_entries).await {
         Ok(_) => info!("Global cache initialized successfully"),
         Err(e) => {
             error!("Cache init failed: {}", e);
@@ -0,0 +1,141 @@
+//! # 运行时指标
+//!
+//! 进程内的计数器/仪表盘注册表，以 Prometheus 文本格式导出，
+//! 通过 Web 服务的 `/metrics` 路由暴露
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use lazy_static::lazy_static;
+
+/// 指标键：指标名 + 排序后的标签
+type MetricKey = (String, Vec<(String, String)>);
+
+/// 指标注册表
+#[derive(Debug, Default)]
+pub struct MetricsRegistry {
+    counters: Mutex<BTreeMap<MetricKey, u64>>,
+    gauges: Mutex<BTreeMap<MetricKey, f64>>,
+}
+
+fn metric_key(name: &str, labels: &[(&str, &str)]) -> MetricKey {
+    let mut labels: Vec<(String, String)> = labels
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    labels.sort();
+    (name.to_string(), labels)
+}
+
+fn format_labels(labels: &[(String, String)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+    let parts: Vec<String> = labels
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", k, v.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")))
+        .collect();
+    format!("{{{}}}", parts.join(","))
+}
+
+impl MetricsRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 计数器加一
+    pub fn incr_counter(&self, name: &str, labels: &[(&str, &str)]) {
+        self.add_counter(name, labels, 1);
+    }
+
+    /// 计数器增加指定值
+    pub fn add_counter(&self, name: &str, labels: &[(&str, &str)], value: u64) {
+        let mut counters = self.counters.lock().unwrap();
+        *counters.entry(metric_key(name, labels)).or_insert(0) += value;
+    }
+
+    /// 设置仪表盘数值
+    pub fn set_gauge(&self, name: &str, labels: &[(&str, &str)], value: f64) {
+        let mut gauges = self.gauges.lock().unwrap();
+        gauges.insert(metric_key(name, labels), value);
+    }
+
+    /// 读取计数器当前值
+    pub fn counter_value(&self, name: &str, labels: &[(&str, &str)]) -> u64 {
+        let counters = self.counters.lock().unwrap();
+        counters.get(&metric_key(name, labels)).copied().unwrap_or(0)
+    }
+
+    /// 读取计数器所有标签组合的合计值
+    pub fn counter_total(&self, name: &str) -> u64 {
+        let counters = self.counters.lock().unwrap();
+        counters.iter()
+            .filter(|((metric, _), _)| metric == name)
+            .map(|(_, value)| *value)
+            .sum()
+    }
+
+    /// 读取仪表盘当前值
+    pub fn gauge_value(&self, name: &str, labels: &[(&str, &str)]) -> Option<f64> {
+        let gauges = self.gauges.lock().unwrap();
+        gauges.get(&metric_key(name, labels)).copied()
+    }
+
+    /// 以 Prometheus 文本格式导出所有指标
+    pub fn render_prometheus(&self) -> String {
+        let mut output = String::new();
+
+        let counters = self.counters.lock().unwrap();
+        let mut last_name = "";
+        for ((name, labels), value) in counters.iter() {
+            if name != last_name {
+                output.push_str(&format!("# TYPE {} counter\n", name));
+                last_name = name;
+            }
+            output.push_str(&format!("{}{} {}\n", name, format_labels(labels), value));
+        }
+
+        let gauges = self.gauges.lock().unwrap();
+        let mut last_name = "";
+        for ((name, labels), value) in gauges.iter() {
+            if name != last_name {
+                output.push_str(&format!("# TYPE {} gauge\n", name));
+                last_name = name;
+            }
+            output.push_str(&format!("{}{} {}\n", name, format_labels(labels), value));
+        }
+
+        output
+    }
+}
+
+lazy_static! {
+    /// 全局指标注册表
+    static ref GLOBAL_METRICS: MetricsRegistry = MetricsRegistry::new();
+}
+
+/// 获取全局指标注册表
+pub fn metrics() -> &'static MetricsRegistry {
+    &GLOBAL_METRICS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counters_and_prometheus_output() {
+        let registry = MetricsRegistry::new();
+        registry.incr_counter("requests_total", &[("provider", "ali"), ("status", "ok")]);
+        registry.add_counter("requests_total", &[("status", "ok"), ("provider", "ali")], 2);
+        registry.set_gauge("active_keys", &[], 4.0);
+
+        assert_eq!(registry.counter_value("requests_total", &[("provider", "ali"), ("status", "ok")]), 3);
+        registry.incr_counter("requests_total", &[("provider", "ollama"), ("status", "ok")]);
+        assert_eq!(registry.counter_total("requests_total"), 4);
+
+        let text = registry.render_prometheus();
+        assert!(text.contains("# TYPE requests_total counter"));
+        assert!(text.contains("requests_total{provider=\"ali\",status=\"ok\"} 3"));
+        assert!(text.contains("active_keys 4"));
+    }
+}
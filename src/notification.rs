@@ -0,0 +1,216 @@
+//! # 通知系统
+//!
+//! 统一的告警/通知出口，支持多个通知渠道（日志、Webhook），
+//! 后台任务和运行时组件通过全局通知中心发送通知
+
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::Local;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tokio::sync::RwLock;
+use tracing::{error, info, warn};
+
+use crate::dao::system_config::get_system_config_value;
+use crate::metrics::metrics;
+
+/// system_configs 中通知配置的分类名
+pub const NOTIFICATION_CONFIG_CATEGORY: &str = "notification";
+
+/// 通知级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationLevel {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl NotificationLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+/// 通知内容
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    pub level: NotificationLevel,
+    /// 通知来源（例如任务名称）
+    pub source: String,
+    pub title: String,
+    pub message: String,
+    pub created_at: String,
+}
+
+impl Notification {
+    pub fn new(level: NotificationLevel, source: &str, title: &str, message: String) -> Self {
+        Self {
+            level,
+            source: source.to_string(),
+            title: title.to_string(),
+            message,
+            created_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        }
+    }
+}
+
+/// 通知渠道
+#[async_trait]
+pub trait NotificationChannel: Send + Sync {
+    /// 渠道名称
+    fn name(&self) -> &str;
+
+    /// 发送通知
+    async fn send(&self, notification: &Notification) -> anyhow::Result<()>;
+}
+
+/// 日志渠道：将通知写入 tracing 日志
+pub struct LogChannel;
+
+#[async_trait]
+impl NotificationChannel for LogChannel {
+    fn name(&self) -> &str {
+        "log"
+    }
+
+    async fn send(&self, notification: &Notification) -> anyhow::Result<()> {
+        match notification.level {
+            NotificationLevel::Info => info!(
+                source = %notification.source,
+                title = %notification.title,
+                "{}", notification.message
+            ),
+            NotificationLevel::Warning => warn!(
+                source = %notification.source,
+                title = %notification.title,
+                "{}", notification.message
+            ),
+            NotificationLevel::Critical => error!(
+                source = %notification.source,
+                title = %notification.title,
+                "{}", notification.message
+            ),
+        }
+        Ok(())
+    }
+}
+
+/// Webhook 渠道：以 JSON 形式 POST 通知内容
+pub struct WebhookChannel {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl WebhookChannel {
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for WebhookChannel {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    async fn send(&self, notification: &Notification) -> anyhow::Result<()> {
+        let response = self.client
+            .post(&self.url)
+            .json(notification)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            anyhow::bail!("Webhook returned status {}", response.status());
+        }
+        Ok(())
+    }
+}
+
+/// 通知中心，负责将通知分发到所有已注册渠道
+pub struct NotificationCenter {
+    channels: RwLock<Vec<Arc<dyn NotificationChannel>>>,
+}
+
+impl NotificationCenter {
+    pub fn new() -> Self {
+        Self {
+            channels: RwLock::new(vec![Arc::new(LogChannel)]),
+        }
+    }
+
+    /// 注册通知渠道（同名渠道会被替换）
+    pub async fn register_channel(&self, channel: Arc<dyn NotificationChannel>) {
+        let mut channels = self.channels.write().await;
+        channels.retain(|c| c.name() != channel.name());
+        channels.push(channel);
+    }
+
+    /// 已注册的渠道名称
+    pub async fn channel_names(&self) -> Vec<String> {
+        let channels = self.channels.read().await;
+        channels.iter().map(|c| c.name().to_string()).collect()
+    }
+
+    /// 发送通知到所有渠道，单个渠道失败不影响其它渠道
+    pub async fn notify(&self, notification: Notification) {
+        let channels = self.channels.read().await.clone();
+        for channel in channels {
+            let result = channel.send(&notification).await;
+            let status = if result.is_ok() { "ok" } else { "failed" };
+            if let Err(e) = result {
+                error!(
+                    channel = channel.name(),
+                    title = %notification.title,
+                    error = %e,
+                    "Failed to deliver notification"
+                );
+            }
+            metrics().incr_counter(
+                "llm_gateway_notifications_total",
+                &[
+                    ("channel", channel.name()),
+                    ("level", notification.level.as_str()),
+                    ("status", status),
+                ],
+            );
+        }
+    }
+}
+
+impl Default for NotificationCenter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+lazy_static! {
+    /// 全局通知中心
+    static ref GLOBAL_NOTIFICATION_CENTER: NotificationCenter = NotificationCenter::new();
+}
+
+/// 获取全局通知中心
+pub fn notification_center() -> &'static NotificationCenter {
+    &GLOBAL_NOTIFICATION_CENTER
+}
+
+/// 根据 system_configs 中的 `notification.webhook_url` 注册 Webhook 渠道
+pub async fn init_notification_channels(pool: &SqlitePool) -> anyhow::Result<()> {
+    if let Some(url) = get_system_config_value(pool, NOTIFICATION_CONFIG_CATEGORY, "webhook_url").await?
+        && !url.trim().is_empty()
+    {
+        notification_center()
+            .register_channel(Arc::new(WebhookChannel::new(url.trim().to_string())))
+            .await;
+        info!("Webhook notification channel registered");
+    }
+    Ok(())
+}
@@ -1,3 +1,20 @@
-pub mod provider_dto;
-pub mod model_dto;
-pub mod api_key_dto;
+//! Web 接口 DTO，定义见 [`crate::api_types::v1`]
+
+pub use crate::api_types::v1::provider as provider_dto;
+pub use crate::api_types::v1::model as model_dto;
+pub use crate::api_types::v1::api_key as api_key_dto;
+pub use crate::api_types::v1::blocklist as blocklist_dto;
+pub use crate::api_types::v1::chat_completion as chat_completion_dto;
+pub use crate::api_types::v1::completion as completion_dto;
+pub use crate::api_types::v1::degradation as degradation_dto;
+pub use crate::api_types::v1::prompt_cache as prompt_cache_dto;
+pub use crate::api_types::v1::usage as usage_dto;
+pub use crate::api_types::v1::project as project_dto;
+pub use crate::api_types::v1::conversation as conversation_dto;
+pub use crate::api_types::v1::batch as batch_dto;
+pub use crate::api_types::v1::ws_chat as ws_chat_dto;
+pub use crate::api_types::v1::ollama as ollama_dto;
+pub use crate::api_types::v1::estimate as estimate_dto;
+pub use crate::api_types::v1::cache as cache_dto;
+pub use crate::api_types::v1::agent as agent_dto;
+pub use crate::api_types::v1::page::Page;
@@ -0,0 +1,242 @@
+//! # 流式读取请求体
+//!
+//! `StreamingJson` 边接收请求体边解析 JSON：数据块经通道交给阻塞线程上的 `serde_json::from_reader`，
+//! 不在内存中拼接完整的原始请求体，大体积的 base64 图片或长文档只保留解析后的一份。
+//! 接收时累计字节数，`Content-Length` 或已接收的字节数超过上限时立即以 413 拒绝，
+//! 分块传输（没有 `Content-Length`）的请求同样适用。
+//!
+//! 解析线程数量有上限，接收请求体也有时限：客户端迟迟不发完请求体时以 408 拒绝并释放解析线程，
+//! 避免慢速发送的请求占满阻塞线程池
+
+use std::io::{self, BufReader, Read};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use axum::{
+    async_trait,
+    body::{Body, Bytes},
+    extract::{FromRequest, Request},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use futures_util::StreamExt;
+use lazy_static::lazy_static;
+use serde::de::DeserializeOwned;
+use tokio::sync::{mpsc, Semaphore};
+use tracing::warn;
+
+use crate::api_types::v1::GatewayErrorCode;
+use crate::metrics::metrics;
+
+/// 默认请求体大小上限（32 MiB）
+pub const DEFAULT_MAX_REQUEST_BODY_BYTES: u64 = 32 * 1024 * 1024;
+
+/// 默认接收请求体的时限（30 秒）
+pub const DEFAULT_BODY_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 同时解析请求体的阻塞线程数上限，超出时排队等待（排队时间计入接收时限）
+pub const MAX_CONCURRENT_BODY_PARSERS: usize = 64;
+
+// 接收端与解析线程之间最多缓冲的数据块数
+const CHUNK_CHANNEL_CAPACITY: usize = 8;
+// 解析线程的读缓冲区大小
+const PARSE_BUFFER_SIZE: usize = 64 * 1024;
+
+static BODY_PARSERS: Semaphore = Semaphore::const_new(MAX_CONCURRENT_BODY_PARSERS);
+
+lazy_static! {
+    static ref MAX_REQUEST_BODY_BYTES: AtomicU64 = AtomicU64::new(max_request_body_bytes_from_env());
+    static ref BODY_READ_TIMEOUT_MS: AtomicU64 = AtomicU64::new(body_read_timeout_from_env().as_millis() as u64);
+}
+
+/// 请求体大小上限，可通过 `MAX_REQUEST_BODY_BYTES` 配置
+fn max_request_body_bytes_from_env() -> u64 {
+    std::env::var("MAX_REQUEST_BODY_BYTES")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .filter(|bytes| *bytes > 0)
+        .unwrap_or(DEFAULT_MAX_REQUEST_BODY_BYTES)
+}
+
+/// 当前请求体大小上限（字节）
+pub fn max_request_body_bytes() -> u64 {
+    MAX_REQUEST_BODY_BYTES.load(Ordering::Relaxed)
+}
+
+/// 修改请求体大小上限
+pub fn set_max_request_body_bytes(bytes: u64) {
+    MAX_REQUEST_BODY_BYTES.store(bytes, Ordering::Relaxed);
+}
+
+/// 接收请求体的时限，可通过 `REQUEST_BODY_READ_TIMEOUT_SECS` 配置
+fn body_read_timeout_from_env() -> Duration {
+    std::env::var("REQUEST_BODY_READ_TIMEOUT_SECS")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_BODY_READ_TIMEOUT)
+}
+
+/// 当前接收请求体的时限
+pub fn body_read_timeout() -> Duration {
+    Duration::from_millis(BODY_READ_TIMEOUT_MS.load(Ordering::Relaxed))
+}
+
+/// 修改接收请求体的时限
+pub fn set_body_read_timeout(timeout: Duration) {
+    BODY_READ_TIMEOUT_MS.store(timeout.as_millis() as u64, Ordering::Relaxed);
+}
+
+/// 读取请求体失败，以 OpenAI 格式的错误体返回
+#[derive(Debug)]
+pub struct BodyRejection {
+    pub code: GatewayErrorCode,
+    pub message: String,
+}
+
+impl BodyRejection {
+    fn new(code: GatewayErrorCode, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+
+    fn too_large(limit: u64, reason: &str) -> Self {
+        metrics().incr_counter("llm_gateway_request_body_rejections_total", &[("reason", reason)]);
+        Self::new(GatewayErrorCode::RequestTooLarge, format!("Request body exceeds the limit of {} bytes", limit))
+    }
+}
+
+impl IntoResponse for BodyRejection {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.code.status_code()).unwrap_or(StatusCode::BAD_REQUEST);
+        (status, Json(self.code.to_openai_error(self.message, None))).into_response()
+    }
+}
+
+/// 流式解析的 JSON 请求体，用法与 `axum::Json` 相同
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StreamingJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for StreamingJson<T>
+where
+    T: DeserializeOwned + Send + 'static,
+    S: Send + Sync,
+{
+    type Rejection = BodyRejection;
+
+    async fn from_request(request: Request, _state: &S) -> Result<Self, Self::Rejection> {
+        let content_length = request.headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok());
+        read_json_body(request.into_body(), content_length, max_request_body_bytes())
+            .await
+            .map(StreamingJson)
+    }
+}
+
+// 从通道逐块读取请求体的阻塞读取器，发送端关闭即读到结尾
+struct ChunkReader {
+    receiver: mpsc::Receiver<Bytes>,
+    current: Bytes,
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.current.is_empty() {
+            match self.receiver.blocking_recv() {
+                Some(chunk) => self.current = chunk,
+                None => return Ok(0),
+            }
+        }
+        let len = buf.len().min(self.current.len());
+        buf[..len].copy_from_slice(&self.current.split_to(len));
+        Ok(len)
+    }
+}
+
+/// 边接收边解析 JSON 请求体，接收的字节数超过 `limit` 时停止读取并返回 413，
+/// 超过 [`body_read_timeout`] 仍未接收完时返回 408
+pub async fn read_json_body<T>(body: Body, content_length: Option<u64>, limit: u64) -> Result<T, BodyRejection>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    read_json_body_within(body, content_length, limit, body_read_timeout()).await
+}
+
+/// 同 [`read_json_body`]，使用指定的接收时限
+pub async fn read_json_body_within<T>(
+    body: Body,
+    content_length: Option<u64>,
+    limit: u64,
+    timeout: Duration,
+) -> Result<T, BodyRejection>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    // 声明的长度已经超限时不读取请求体
+    if content_length.is_some_and(|length| length > limit) {
+        return Err(BodyRejection::too_large(limit, "content_length"));
+    }
+
+    // 超时后丢弃发送端，解析线程读到结尾随即退出并释放名额
+    match tokio::time::timeout(timeout, parse_streaming(body, limit)).await {
+        Ok(result) => result,
+        Err(_) => {
+            metrics().incr_counter("llm_gateway_request_body_rejections_total", &[("reason", "timeout")]);
+            Err(BodyRejection::new(
+                GatewayErrorCode::RequestTimeout,
+                format!("Timed out after {}ms waiting for the request body", timeout.as_millis()),
+            ))
+        }
+    }
+}
+
+async fn parse_streaming<T>(body: Body, limit: u64) -> Result<T, BodyRejection>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    let permit = BODY_PARSERS.acquire().await
+        .map_err(|_| BodyRejection::new(GatewayErrorCode::ServiceUnavailable, "Request body parser unavailable"))?;
+    let (sender, receiver) = mpsc::channel::<Bytes>(CHUNK_CHANNEL_CAPACITY);
+    let parser = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let reader = BufReader::with_capacity(PARSE_BUFFER_SIZE, ChunkReader { receiver, current: Bytes::new() });
+        serde_json::from_reader::<_, T>(reader)
+    });
+
+    let mut stream = body.into_data_stream();
+    let mut received: u64 = 0;
+    while let Some(chunk) = stream.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                warn!(error = %e, received_bytes = received, "Failed to read request body");
+                return Err(BodyRejection::new(GatewayErrorCode::InvalidRequest, "Failed to read request body"));
+            }
+        };
+        received += chunk.len() as u64;
+        if received > limit {
+            // 关闭通道后解析线程读到结尾随即退出
+            return Err(BodyRejection::too_large(limit, "streamed"));
+        }
+        // 解析线程已经结束（JSON 格式错误）时不再继续接收
+        if sender.send(chunk).await.is_err() {
+            break;
+        }
+    }
+    drop(sender);
+    metrics().add_counter("llm_gateway_request_body_bytes_total", &[], received);
+
+    match parser.await {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(e)) => Err(BodyRejection::new(
+            GatewayErrorCode::InvalidRequest,
+            format!("Failed to parse the request body as JSON: {}", e),
+        )),
+        Err(e) => {
+            warn!(error = %e, "Request body parser task failed");
+            Err(BodyRejection::new(GatewayErrorCode::InvalidRequest, "Failed to parse the request body"))
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use axum::{
+    extract::Query,
+    http::StatusCode,
+    response::Json,
+};
+use serde::Deserialize;
+
+use crate::llm_api::utils::abuse_guard::{get_abuse_guard, OffenderReport};
+
+#[derive(Debug, Deserialize)]
+pub struct OffenderQuery {
+    tenant_id: Option<String>,
+    limit: Option<usize>,
+}
+
+/// 获取终端用户违规排行
+pub async fn list_top_offenders(
+    Query(params): Query<OffenderQuery>,
+) -> Result<Json<Vec<OffenderReport>>, StatusCode> {
+    let limit = params.limit.unwrap_or(20);
+    let offenders = get_abuse_guard()
+        .top_offenders(params.tenant_id.as_deref(), limit)
+        .await;
+    Ok(Json(offenders))
+}
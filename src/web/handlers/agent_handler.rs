@@ -0,0 +1,155 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use axum::{
+    http::HeaderMap,
+    response::{IntoResponse, Json, Response},
+};
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::api_types::v1::chat_completion::{ChatCompletionMessage, ChatCompletionToolCall, ChatCompletionUsage};
+use crate::api_types::v1::{GatewayErrorCode, ToolFunction};
+use crate::llm_api::dispatcher::{DispatchRequest, GLOBAL_DISPATCHER};
+use crate::llm_api::utils::agent_loop::{
+    AgentLoop, AgentOutcome, AgentTool, DEFAULT_TOOL_TIMEOUT, DEFAULT_MAX_ITERATIONS, MAX_ITERATIONS_LIMIT,
+    MAX_TOOL_TIMEOUT,
+};
+use crate::llm_api::utils::client::{CallMetadata, CALL_METADATA};
+use crate::web::dto::agent_dto::*;
+use crate::web::dto::chat_completion_dto::ChatCompletionResponseMessage;
+use crate::web::handlers::chat_completion_handler::{api_error, map_llm_error, register_in_flight, with_request_id, ApiError};
+
+/// 由网关执行工具调用的 Agent 对话：调用模型，执行返回的 tool_calls 并把结果追加为 tool 消息，
+/// 直到模型给出最终回答或达到 `max_iterations`
+///
+/// 与 `/v1/chat/completions` 一样按 `x-request-id` 登记为进行中的请求，可以取消；各轮的调用记录共用该请求 ID
+pub async fn create_agent_chat(
+    headers: HeaderMap,
+    Json(request): Json<AgentChatRequest>,
+) -> Result<Response, ApiError> {
+    let dispatcher = GLOBAL_DISPATCHER.get()
+        .ok_or_else(|| api_error(GatewayErrorCode::ServiceUnavailable, "Dispatcher not initialized", None))?
+        .clone();
+
+    if request.messages.is_empty() {
+        return Err(api_error(GatewayErrorCode::InvalidRequest, "messages must not be empty", Some("messages")));
+    }
+    let agent = build_agent_loop(&request)?;
+    let (provider, model) = dispatcher.resolve_model(&request.model).await
+        .ok_or_else(|| api_error(
+            GatewayErrorCode::ModelNotFound,
+            &format!("The model `{}` does not exist", request.model),
+            None,
+        ))?;
+
+    let in_flight = register_in_flight(&headers);
+    let request_id = in_flight.request_id().to_string();
+    // 沿用中间件设置的调用方和项目
+    let metadata = CallMetadata::current()
+        .with_cancellation(in_flight.token().clone())
+        .with_request_id(Some(request_id.clone()));
+
+    let messages = request.messages.into_iter().map(ChatCompletionMessage::into_message).collect();
+    let mut dispatch_request = DispatchRequest::new(provider, model, messages).with_request_id(request_id.clone());
+    dispatch_request.temperature = request.temperature;
+    dispatch_request.max_tokens = request.max_tokens;
+    dispatch_request.user = request.user;
+    dispatch_request.tenant_id = metadata.project_id.clone();
+
+    let outcome = CALL_METADATA.scope(metadata, agent.run(&dispatcher, dispatch_request)).await
+        .map_err(|e| map_llm_error(&e))?;
+    let response = Json(to_agent_response(outcome, request.model)).into_response();
+    Ok(with_request_id(response, &request_id))
+}
+
+// 校验工具和循环参数
+fn build_agent_loop(request: &AgentChatRequest) -> Result<AgentLoop, ApiError> {
+    let max_iterations = request.max_iterations.unwrap_or(DEFAULT_MAX_ITERATIONS);
+    if !(1..=MAX_ITERATIONS_LIMIT).contains(&max_iterations) {
+        return Err(api_error(
+            GatewayErrorCode::InvalidRequest,
+            &format!("max_iterations must be between 1 and {}", MAX_ITERATIONS_LIMIT),
+            Some("max_iterations"),
+        ));
+    }
+    let tool_timeout = request.tool_timeout_ms.map_or(DEFAULT_TOOL_TIMEOUT, Duration::from_millis);
+    if tool_timeout.is_zero() || tool_timeout > MAX_TOOL_TIMEOUT {
+        return Err(api_error(
+            GatewayErrorCode::InvalidRequest,
+            &format!("tool_timeout_ms must be between 1 and {}", MAX_TOOL_TIMEOUT.as_millis()),
+            Some("tool_timeout_ms"),
+        ));
+    }
+
+    let mut names = HashSet::new();
+    let mut tools = Vec::with_capacity(request.tools.len());
+    for definition in &request.tools {
+        if definition.name.trim().is_empty() || !names.insert(definition.name.as_str()) {
+            return Err(api_error(GatewayErrorCode::InvalidRequest, "tool names must be non-empty and unique", Some("tools")));
+        }
+        let tool = match &definition.webhook_url {
+            Some(url) => AgentTool::webhook(ToolFunction {
+                name: definition.name.clone(),
+                description: definition.description.clone().unwrap_or_default(),
+                parameters: definition.parameters.clone().unwrap_or_default(),
+            }, url).map_err(|e| api_error(GatewayErrorCode::InvalidRequest, &e, Some("tools")))?,
+            None => {
+                let mut tool = AgentTool::builtin(&definition.name).ok_or_else(|| api_error(
+                    GatewayErrorCode::InvalidRequest,
+                    &format!("Unknown builtin tool `{}`, provide webhook_url for custom tools", definition.name),
+                    Some("tools"),
+                ))?;
+                if let Some(description) = &definition.description {
+                    tool.definition.description = description.clone();
+                }
+                if let Some(parameters) = &definition.parameters {
+                    tool.definition.parameters = parameters.clone();
+                }
+                tool
+            }
+        };
+        tools.push(tool);
+    }
+
+    Ok(AgentLoop::new(tools).with_max_iterations(max_iterations).with_tool_timeout(tool_timeout))
+}
+
+fn to_agent_response(outcome: AgentOutcome, requested_model: String) -> AgentChatResponse {
+    let response = outcome.response;
+    let finish_reason = if outcome.max_iterations_reached {
+        "max_iterations".to_string()
+    } else {
+        response.finish_reason.unwrap_or_else(|| "stop".to_string())
+    };
+    AgentChatResponse {
+        id: format!("agent-{}", Uuid::new_v4().simple()),
+        object: "agent.chat".to_string(),
+        created: Utc::now().timestamp(),
+        model: if response.model.is_empty() { requested_model } else { response.model },
+        message: ChatCompletionResponseMessage {
+            role: "assistant".to_string(),
+            content: response.content,
+            tool_calls: response.tool_calls.map(|calls| {
+                calls.into_iter().map(|call| ChatCompletionToolCall::from_tool_call(call, None)).collect()
+            }),
+        },
+        finish_reason,
+        iterations: outcome.iterations,
+        steps: outcome.steps.into_iter().map(|step| AgentStepResponse {
+            iteration: step.iteration,
+            tool_call_id: step.tool_call_id,
+            name: step.name,
+            arguments: serde_json::to_value(step.arguments).unwrap_or_default(),
+            output: step.output,
+            error: step.error,
+            latency_ms: step.latency_ms,
+            call_log_id: step.call_log_id,
+        }).collect(),
+        usage: outcome.usage.map(|usage| ChatCompletionUsage {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            total_tokens: usage.total_tokens,
+        }),
+    }
+}
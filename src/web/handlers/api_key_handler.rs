@@ -1,8 +1,9 @@
 use axum::{
-    extract::Path,
+    extract::{Path, Query},
     http::StatusCode,
     response::Json,
 };
+use serde::Deserialize;
 use serde_json::{json, Value};
 use uuid::Uuid;
 use sqlx::SqlitePool;
@@ -11,58 +12,99 @@ use crate::dao::{
     provider::{get_provider_by_id},
     provider_key_pool::{
         ProviderKeyPool, 
-        list_provider_key_pools_by_provider, 
-        create_provider_key_pool_from_raw_key,
+        list_provider_key_pools_page,
+        count_provider_key_pools,
+        create_provider_key_pool,
         get_provider_key_pool_by_id,
         update_provider_key_pool,
         delete_provider_key_pool,
-        toggle_provider_key_pool_active
+        toggle_provider_key_pool_active,
+        sync_provider_key_pool_cache,
+        KeyIntegrityReport,
+        MasterKeyRotationReport,
+        rotate_master_key,
     },
+    pagination::{clamp_limit, Cursor},
     SQLITE_POOL,
 };
+use crate::dao::project::DEFAULT_PROJECT;
 use crate::web::dto::api_key_dto::*;
+use crate::web::handlers::project_handler::ensure_project_exists;
+use crate::web::dto::Page;
+use crate::jobs::key_integrity_audit::run_key_integrity_audit;
 use crate::dao::provider_key_pool::crypto::{process_api_key, decrypt_api_key};
 
-/// 获取指定Provider的所有API Key
-pub async fn list_provider_api_keys(Path(provider_id): Path<String>) -> Result<Json<ApiKeyListResponse>, StatusCode> {
+#[derive(Debug, Deserialize)]
+pub struct ApiKeyListQuery {
+    active: Option<bool>,
+    project_id: Option<String>,
+    cursor: Option<String>,
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApiKeyAuditQuery {
+    dry_run: Option<bool>,
+}
+
+/// 获取指定Provider的API Key（游标分页），可按启用状态和所属项目过滤
+pub async fn list_provider_api_keys(
+    Path(provider_id): Path<String>,
+    Query(params): Query<ApiKeyListQuery>,
+) -> Result<Json<Page<ApiKeyResponse, ApiKeyListFilter>>, StatusCode> {
     let pool = SQLITE_POOL.get()
         .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
         .as_ref();
 
+    let cursor = params.cursor.as_deref()
+        .map(|cursor| Cursor::decode(cursor).ok_or(StatusCode::BAD_REQUEST))
+        .transpose()?;
+    let limit = clamp_limit(params.limit);
+
     // 首先获取provider信息
     let provider = match get_provider_by_id(pool, &provider_id).await {
         Ok(Some(provider)) => provider,
         Ok(None) => return Err(StatusCode::NOT_FOUND),
         Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
     };
+    let filter = ApiKeyListFilter {
+        provider_id: provider.id,
+        provider: provider.name,
+        active: params.active,
+        project_id: params.project_id,
+    };
 
-    // 获取该provider的所有API Key
-    match list_provider_key_pools_by_provider(pool, &provider.name).await {
-        Ok(keys) => {
-            let api_keys: Vec<ApiKeyResponse> = keys.into_iter().map(|key| {
-                let key_preview = generate_key_preview(&key.key_hash);
-                
-                ApiKeyResponse {
-                    id: key.id,
-                    provider: key.provider,
-                    key_preview,
-                    is_active: key.is_active,
-                    usage_count: key.usage_count,
-                    last_used_at: key.last_used_at,
-                    rate_limit_per_minute: key.rate_limit_per_minute,
-                    rate_limit_per_hour: key.rate_limit_per_hour,
-                    created_at: key.created_at,
-                }
-            }).collect();
-
-            Ok(Json(ApiKeyListResponse {
-                provider_id: provider.id,
-                provider_name: provider.display_name,
-                keys: api_keys,
-            }))
+    let total = count_provider_key_pools(pool, &filter.provider, filter.active, filter.project_id.as_deref())
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let page = list_provider_key_pools_page(pool, &filter.provider, filter.active, filter.project_id.as_deref(), cursor.as_ref(), limit)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let data = page.items.into_iter().map(|key| {
+        let key_preview = generate_key_preview(&key.key_hash);
+
+        ApiKeyResponse {
+            id: key.id,
+            provider: key.provider,
+            key_preview,
+            is_active: key.is_active,
+            usage_count: key.usage_count,
+            last_used_at: key.last_used_at,
+            rate_limit_per_minute: key.rate_limit_per_minute,
+            rate_limit_per_hour: key.rate_limit_per_hour,
+            project_id: key.project_id.unwrap_or_else(|| DEFAULT_PROJECT.to_string()),
+            created_at: key.created_at,
         }
-        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
-    }
+    }).collect();
+
+    Ok(Json(Page {
+        data,
+        total,
+        limit,
+        next_cursor: page.next_cursor.map(|cursor| cursor.encode()),
+        filter,
+    }))
 }
 
 /// 为Provider添加新的API Key
@@ -85,22 +127,40 @@ pub async fn create_api_key(Json(request): Json<CreateApiKeyRequest>) -> Result<
         Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
     };
 
+    // 未指定项目时归属供应商所在的项目
+    ensure_project_exists(pool, request.project_id.as_deref()).await?;
+    let project_id = request.project_id.or(provider.project_id);
+
     // 生成唯一ID
     let key_id = Uuid::new_v4().to_string();
 
-    match create_provider_key_pool_from_raw_key(
-        pool,
-        key_id.clone(),
-        provider.name,
-        &request.api_key,
-        true, // 默认激活
-        request.rate_limit_per_minute,
-        request.rate_limit_per_hour,
-    ).await {
-        Ok(_) => Ok(Json(json!({
-            "id": key_id,
-            "message": "API key added successfully"
-        }))),
+    let (key_hash, encrypted_key_value) = process_api_key(&request.api_key)
+        .map_err(|e| {
+            tracing::error!("Failed to encrypt API key for provider {}: {:#}", provider.name, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let key_pool = ProviderKeyPool {
+        id: key_id.clone(),
+        provider: provider.name.clone(),
+        key_hash,
+        encrypted_key_value,
+        is_active: true, // 默认激活
+        usage_count: 0,
+        last_used_at: None,
+        rate_limit_per_minute: request.rate_limit_per_minute,
+        rate_limit_per_hour: request.rate_limit_per_hour,
+        project_id,
+        created_at: None,
+    };
+
+    match create_provider_key_pool(pool, &key_pool).await {
+        Ok(_) => {
+            refresh_key_cache(pool, &provider.name, &key_id).await;
+            Ok(Json(json!({
+                "id": key_id,
+                "message": "API key added successfully"
+            })))
+        }
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
 }
@@ -121,6 +181,8 @@ pub async fn update_api_key(
         Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
     };
 
+    ensure_project_exists(pool, request.project_id.as_deref()).await?;
+
     // 构建更新后的API Key
     let updated_key = ProviderKeyPool {
         id: existing.id,
@@ -132,13 +194,17 @@ pub async fn update_api_key(
         last_used_at: existing.last_used_at,
         rate_limit_per_minute: request.rate_limit_per_minute.or(existing.rate_limit_per_minute),
         rate_limit_per_hour: request.rate_limit_per_hour.or(existing.rate_limit_per_hour),
+        project_id: request.project_id.or(existing.project_id),
         created_at: existing.created_at,
     };
 
     match update_provider_key_pool(pool, &updated_key).await {
-        Ok(rows) if rows > 0 => Ok(Json(json!({
-            "message": "API key updated successfully"
-        }))),
+        Ok(rows) if rows > 0 => {
+            refresh_key_cache(pool, &updated_key.provider, &updated_key.id).await;
+            Ok(Json(json!({
+                "message": "API key updated successfully"
+            })))
+        }
         Ok(_) => Err(StatusCode::NOT_FOUND),
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
@@ -150,10 +216,20 @@ pub async fn delete_api_key(Path(key_id): Path<String>) -> Result<Json<Value>, S
         .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
         .as_ref();
 
+    // 删除前记录所属供应商，用于重建轮询池
+    let provider = match get_provider_key_pool_by_id(pool, &key_id).await {
+        Ok(Some(key)) => key.provider,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
     match delete_provider_key_pool(pool, &key_id).await {
-        Ok(rows) if rows > 0 => Ok(Json(json!({
-            "message": "API key deleted successfully"
-        }))),
+        Ok(rows) if rows > 0 => {
+            refresh_key_cache(pool, &provider, &key_id).await;
+            Ok(Json(json!({
+                "message": "API key deleted successfully"
+            })))
+        }
         Ok(_) => Err(StatusCode::NOT_FOUND),
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
@@ -167,15 +243,31 @@ pub async fn toggle_api_key_status(
         .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
         .as_ref();
 
+    let provider = match get_provider_key_pool_by_id(pool, &key_id).await {
+        Ok(Some(key)) => key.provider,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
     match toggle_provider_key_pool_active(pool, &key_id, status).await {
-        Ok(rows) if rows > 0 => Ok(Json(json!({
-            "message": format!("API key {} successfully", if status { "activated" } else { "deactivated" })
-        }))),
+        Ok(rows) if rows > 0 => {
+            refresh_key_cache(pool, &provider, &key_id).await;
+            Ok(Json(json!({
+                "message": format!("API key {} successfully", if status { "activated" } else { "deactivated" })
+            })))
+        }
         Ok(_) => Err(StatusCode::NOT_FOUND),
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
 }
 
+/// API Key 变更后同步缓存和轮询池，失败只记录日志（数据库已写入，下次重载时生效）
+async fn refresh_key_cache(pool: &SqlitePool, provider: &str, key_id: &str) {
+    if let Err(e) = sync_provider_key_pool_cache(pool, provider, key_id).await {
+        tracing::error!("Failed to sync API key {}:{} to cache: {:?}", provider, key_id, e);
+    }
+}
+
 /// 生成密钥预览（显示前几位和后几位）
 fn generate_key_preview(key_hash: &str) -> String {
     if key_hash.len() > 8 {
@@ -184,3 +276,27 @@ fn generate_key_preview(key_hash: &str) -> String {
         format!("{}...", &key_hash[..std::cmp::min(4, key_hash.len())])
     }
 }
+
+/// 用当前主密钥重新加密所有 API Key（在密钥环最前面加入新主密钥并重启后调用）
+pub async fn rotate_api_key_master_key() -> Result<Json<MasterKeyRotationReport>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    match rotate_master_key(pool).await {
+        Ok(report) => Ok(Json(report)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// 立即执行一次API Key完整性审计，默认停用未通过校验的 Key，`dry_run=true` 时只报告
+pub async fn audit_api_keys(Query(query): Query<ApiKeyAuditQuery>) -> Result<Json<KeyIntegrityReport>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    match run_key_integrity_audit(pool, !query.dry_run.unwrap_or(false)).await {
+        Ok(report) => Ok(Json(report)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
@@ -0,0 +1,24 @@
+use axum::{
+    extract::Query,
+    http::StatusCode,
+    response::Json,
+};
+
+use crate::dao::{
+    audit_log::{list_audit_logs, AuditLog, AuditLogFilter},
+    SQLITE_POOL,
+};
+
+/// 查询管理操作审计日志（供应商、模型、API Key 的创建/更新/删除），可按操作人、操作、资源和时间过滤，按时间倒序
+pub async fn get_audit_logs(
+    Query(filter): Query<AuditLogFilter>,
+) -> Result<Json<Vec<AuditLog>>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    match list_audit_logs(pool, &filter).await {
+        Ok(logs) => Ok(Json(logs)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
@@ -0,0 +1,107 @@
+use axum::{
+    extract::Path,
+    http::StatusCode,
+    response::Json,
+};
+
+use crate::api_types::v1::GatewayErrorCode;
+use crate::jobs::batch_chat::{
+    get_batch_job, start_batch_job, BatchItem, BatchJob, DEFAULT_BATCH_CONCURRENCY, MAX_ACTIVE_BATCH_JOBS, MAX_BATCH_CONCURRENCY,
+    MAX_BATCH_SIZE,
+};
+use crate::llm_api::dispatcher::GLOBAL_DISPATCHER;
+use crate::llm_api::utils::client::CallMetadata;
+use crate::web::dto::batch_dto::*;
+use crate::web::handlers::chat_completion_handler::{api_error, build_dispatch_request, to_chat_completion, ApiError};
+
+/// 提交批量 Chat Completion 任务，立即返回任务 ID，完成后通过 `GET /v1/batch/chat/{id}` 获取结果
+///
+/// 提交时校验全部请求（消息不能为空、模型存在、不支持流式），任一请求不合法时整个任务不提交；
+/// 调用方执行中的任务数达到上限时返回 429。任务只保存在内存中，网关重启后丢失
+pub async fn create_batch_chat(
+    Json(request): Json<BatchChatRequest>,
+) -> Result<(StatusCode, Json<BatchChatJobResponse>), ApiError> {
+    let dispatcher = GLOBAL_DISPATCHER.get()
+        .ok_or_else(|| api_error(GatewayErrorCode::ServiceUnavailable, "Dispatcher not initialized", None))?
+        .clone();
+
+    if request.requests.is_empty() || request.requests.len() > MAX_BATCH_SIZE {
+        return Err(api_error(
+            GatewayErrorCode::InvalidRequest,
+            &format!("requests must contain between 1 and {} items", MAX_BATCH_SIZE),
+            Some("requests"),
+        ));
+    }
+    let concurrency = request.concurrency.unwrap_or(DEFAULT_BATCH_CONCURRENCY);
+    if !(1..=MAX_BATCH_CONCURRENCY).contains(&concurrency) {
+        return Err(api_error(
+            GatewayErrorCode::InvalidRequest,
+            &format!("concurrency must be between 1 and {}", MAX_BATCH_CONCURRENCY),
+            Some("concurrency"),
+        ));
+    }
+
+    let tenant_id = CallMetadata::current().project_id;
+    let mut items = Vec::with_capacity(request.requests.len());
+    for (index, item) in request.requests.into_iter().enumerate() {
+        let body = item.body;
+        if body.messages.is_empty() {
+            let param = format!("requests[{}].body.messages", index);
+            return Err(api_error(GatewayErrorCode::InvalidRequest, "messages must not be empty", Some(&param)));
+        }
+        if body.stream == Some(true) {
+            let param = format!("requests[{}].body.stream", index);
+            return Err(api_error(GatewayErrorCode::InvalidRequest, "stream is not supported in batch requests", Some(&param)));
+        }
+        let (provider, model) = dispatcher.resolve_model(&body.model).await
+            .ok_or_else(|| api_error(
+                GatewayErrorCode::ModelNotFound,
+                &format!("The model `{}` does not exist", body.model),
+                Some(&format!("requests[{}].body.model", index)),
+            ))?;
+        items.push(BatchItem {
+            custom_id: item.custom_id,
+            requested_model: body.model.clone(),
+            request: build_dispatch_request(body, provider, model, tenant_id.clone()),
+        });
+    }
+
+    let job = start_batch_job(dispatcher, items, concurrency)
+        .ok_or_else(|| api_error(
+            GatewayErrorCode::RateLimitExceeded,
+            &format!("Too many active batch jobs, at most {} may run at the same time", MAX_ACTIVE_BATCH_JOBS),
+            None,
+        ))?;
+    Ok((StatusCode::ACCEPTED, Json(to_response(job))))
+}
+
+/// 查询批量任务，完成后返回全部结果；其他项目的任务视为不存在
+pub async fn get_batch_chat(Path(id): Path<String>) -> Result<Json<BatchChatJobResponse>, ApiError> {
+    get_batch_job(&id)
+        .filter(|job| job.project_id == CallMetadata::current().project_id())
+        .map(|job| Json(to_response(job)))
+        .ok_or_else(|| api_error(GatewayErrorCode::BatchNotFound, &format!("No batch with id `{}`", id), None))
+}
+
+fn to_response(job: BatchJob) -> BatchChatJobResponse {
+    let completed = job.is_completed();
+    BatchChatJobResponse {
+        status: if completed { "completed" } else { "in_progress" }.to_string(),
+        succeeded: job.succeeded(),
+        failed: job.failed(),
+        id: job.id,
+        object: "batch".to_string(),
+        total: job.total,
+        created_at: job.created_at,
+        completed_at: job.completed_at,
+        results: completed.then(|| {
+            job.results.into_iter().enumerate().map(|(index, result)| {
+                let (response, error) = match result.outcome {
+                    Ok(response) => (Some(to_chat_completion(response, result.requested_model)), None),
+                    Err(error) => (None, Some(error.code.to_openai_error(&error.message, None).error)),
+                };
+                BatchChatResult { index, custom_id: result.custom_id, response, error }
+            }).collect()
+        }),
+    }
+}
@@ -0,0 +1,160 @@
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    response::Json,
+};
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::dao::{
+    blocklist::{
+        BlocklistEntry, create_blocklist_entry, get_blocklist_entry_by_id, list_blocklist_entries,
+        list_blocklist_entries_by_tenant, list_global_blocklist_entries, update_blocklist_entry,
+        delete_blocklist_entry,
+    },
+    SQLITE_POOL,
+};
+use crate::llm_api::utils::blocklist::{reload_blocklist, BlocklistAction, BlocklistScope};
+use crate::web::dto::blocklist_dto::*;
+
+/// 获取黑名单规则（可按租户过滤）
+pub async fn list_blocklist(
+    Query(params): Query<BlocklistQuery>,
+) -> Result<Json<Vec<BlocklistEntry>>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    let result = if params.global_only.unwrap_or(false) {
+        list_global_blocklist_entries(pool).await
+    } else if let Some(tenant_id) = params.tenant_id {
+        list_blocklist_entries_by_tenant(pool, &tenant_id).await
+    } else {
+        list_blocklist_entries(pool).await
+    };
+
+    match result {
+        Ok(entries) => Ok(Json(entries)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// 获取单条黑名单规则
+pub async fn get_blocklist_entry(Path(id): Path<String>) -> Result<Json<BlocklistEntry>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    match get_blocklist_entry_by_id(pool, &id).await {
+        Ok(Some(entry)) => Ok(Json(entry)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// 创建黑名单规则
+pub async fn create_blocklist(
+    Json(request): Json<CreateBlocklistEntryRequest>,
+) -> Result<Json<Value>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    let scope = request.scope.unwrap_or_else(|| "both".to_string());
+    if request.pattern.trim().is_empty()
+        || BlocklistAction::parse(&request.action).is_none()
+        || BlocklistScope::parse(&scope).is_none()
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let id = Uuid::new_v4().to_string();
+    let entry = BlocklistEntry {
+        id: id.clone(),
+        tenant_id: request.tenant_id.filter(|t| !t.trim().is_empty()),
+        pattern: request.pattern.trim().to_string(),
+        action: request.action.trim().to_lowercase(),
+        scope: scope.trim().to_lowercase(),
+        is_active: request.is_active.unwrap_or(true),
+        created_at: None, // 数据库会自动设置
+        updated_at: None,
+    };
+
+    match create_blocklist_entry(pool, &entry).await {
+        Ok(_) => {
+            reload_engine(pool).await;
+            Ok(Json(json!({
+                "id": id,
+                "message": "Blocklist entry created successfully"
+            })))
+        }
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// 更新黑名单规则
+pub async fn update_blocklist(
+    Path(id): Path<String>,
+    Json(request): Json<UpdateBlocklistEntryRequest>,
+) -> Result<Json<Value>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    let existing = match get_blocklist_entry_by_id(pool, &id).await {
+        Ok(Some(entry)) => entry,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    let updated = BlocklistEntry {
+        pattern: request.pattern.map(|p| p.trim().to_string()).unwrap_or(existing.pattern),
+        action: request.action.map(|a| a.trim().to_lowercase()).unwrap_or(existing.action),
+        scope: request.scope.map(|s| s.trim().to_lowercase()).unwrap_or(existing.scope),
+        is_active: request.is_active.unwrap_or(existing.is_active),
+        ..existing
+    };
+    if updated.pattern.is_empty()
+        || BlocklistAction::parse(&updated.action).is_none()
+        || BlocklistScope::parse(&updated.scope).is_none()
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match update_blocklist_entry(pool, &updated).await {
+        Ok(rows) if rows > 0 => {
+            reload_engine(pool).await;
+            Ok(Json(json!({
+                "message": "Blocklist entry updated successfully"
+            })))
+        }
+        Ok(_) => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// 删除黑名单规则
+pub async fn delete_blocklist(Path(id): Path<String>) -> Result<Json<Value>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    match delete_blocklist_entry(pool, &id).await {
+        Ok(rows) if rows > 0 => {
+            reload_engine(pool).await;
+            Ok(Json(json!({
+                "message": "Blocklist entry deleted successfully"
+            })))
+        }
+        Ok(_) => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// 规则变更后刷新内存中的黑名单
+async fn reload_engine(pool: &SqlitePool) {
+    if let Err(e) = reload_blocklist(pool).await {
+        tracing::error!("Failed to reload blocklist: {:?}", e);
+    }
+}
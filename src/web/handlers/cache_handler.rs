@@ -0,0 +1,68 @@
+use std::collections::BTreeMap;
+use axum::{
+    http::StatusCode,
+    response::Json,
+};
+use serde::Serialize;
+use tracing::{error, info};
+
+use crate::dao::{
+    cache::{cache::CacheStats, preload_global_cache, GLOBAL_CACHE, GLOBAL_CACHE_NAME},
+    SQLITE_POOL,
+};
+use crate::llm_api::utils::degradation::{get_degradation_guard, RESPONSE_CACHE_NAME};
+use crate::web::dto::cache_dto::{InvalidateCacheRequest, InvalidateCacheResponse};
+
+/// 各缓存实例的统计，按缓存名称排列
+#[derive(Debug, Serialize)]
+pub struct CacheStatsResponse {
+    pub caches: BTreeMap<&'static str, CacheReport>,
+}
+
+/// 单个缓存实例的统计
+#[derive(Debug, Serialize)]
+pub struct CacheReport {
+    #[serde(flatten)]
+    pub stats: CacheStats,
+    /// 按命名空间统计的条目数（仅全局缓存）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespaces: Option<BTreeMap<String, u64>>,
+}
+
+/// 获取全局缓存和降级响应缓存的命中、移除和条目统计；全局缓存未初始化时不返回该项
+pub async fn get_cache_stats() -> Json<CacheStatsResponse> {
+    let mut caches = BTreeMap::new();
+    if let Some(cache) = GLOBAL_CACHE.get() {
+        caches.insert(GLOBAL_CACHE_NAME, CacheReport {
+            stats: cache.stats().await,
+            namespaces: Some(cache.namespace_entry_counts().await),
+        });
+    }
+    caches.insert(RESPONSE_CACHE_NAME, CacheReport {
+        stats: get_degradation_guard().response_cache_stats().await,
+        namespaces: None,
+    });
+    Json(CacheStatsResponse { caches })
+}
+
+/// 按 key 前缀批量失效全局缓存；默认随后从数据库重新预加载模型和 API Key，避免路由时找不到 Key
+pub async fn invalidate_cache(
+    Json(request): Json<InvalidateCacheRequest>,
+) -> Result<Json<InvalidateCacheResponse>, StatusCode> {
+    let cache = GLOBAL_CACHE.get().ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+    let invalidated = cache.invalidate_prefix(&request.prefix).await;
+    info!(prefix = %request.prefix, invalidated, "Cache entries invalidated");
+
+    let reloaded = request.reload.unwrap_or(true);
+    if reloaded {
+        let pool = SQLITE_POOL.get()
+            .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+            .as_ref();
+        if let Err(e) = preload_global_cache(pool).await {
+            error!(error = %e, "Failed to reload models and provider key pools after cache invalidation");
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    Ok(Json(InvalidateCacheResponse { prefix: request.prefix, invalidated, reloaded }))
+}
@@ -1,5 +1,5 @@
 use axum::{
-    extract::Query,
+    extract::{Path, Query},
     http::StatusCode,
     response::Json,
 };
@@ -7,26 +7,69 @@ use serde::{Deserialize, Serialize};
 
 use crate::dao::{
     call_log::{
-        list_call_logs_paginated, list_error_call_logs, count_call_logs, CallLog, CallLogStats,
-        get_call_logs_stats,
+        search_call_logs, search_call_logs_page, count_call_logs_by_search, get_call_logs_stats_by_search, get_call_logs_stats_per_model,
+        CallLog, CallLogSearch, CallLogStats, ModelCallStats, get_call_logs_stats, get_call_logs_stats_by_language, LanguageCallStats,
+        get_call_logs_stats_by_traffic_arm, TrafficArmCallStats,
+        get_call_logs_stats_timeseries, CallLogStatsBucket, StatsBucket,
+        get_billing_reconciliation, BillingReconciliationRow, CallLogFilter,
     },
+    pagination::{clamp_limit, into_page, Cursor},
     SQLITE_POOL,
 };
+use crate::llm_api::utils::cancellation::CALL_STATUS_CANCELLED;
+use crate::web::dto::Page;
+use crate::jobs::call_log_archive::{
+    archive_dir_from_env, get_archive_task, list_archive_tasks, start_archive_task, ArchiveMode, ArchiveTask,
+    DEFAULT_BATCH_SIZE,
+};
 
 #[derive(Debug, Deserialize)]
 pub struct CallLogQuery {
+    cursor: Option<String>,
+    /// 按页码分页（OFFSET），未指定 cursor 时使用
     page: Option<u32>,
-    limit: Option<u32>,
+    limit: Option<i64>,
     error_only: Option<bool>,
+    /// success（200）、error（非 200）、cancelled（499，被取消）或具体状态码
+    status: Option<String>,
+    model_id: Option<String>,
+    provider: Option<String>,
+    /// 起始时间（包含），YYYY-MM-DD 或 YYYY-MM-DD HH:MM:SS
+    start: Option<String>,
+    /// 结束时间（不包含）
+    end: Option<String>,
+    /// 发起请求的项目
+    project_id: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
-pub struct CallLogResponse {
-    pub data: Vec<CallLog>,
-    pub total: i64,
-    pub page: u32,
-    pub limit: u32,
-    pub total_pages: u32,
+/// 调用日志列表的过滤条件
+#[derive(Debug, Clone, Serialize)]
+pub struct CallLogListFilter {
+    pub status: Option<String>,
+    pub error_only: Option<bool>,
+    pub model_id: Option<String>,
+    pub provider: Option<String>,
+    pub start: Option<String>,
+    pub end: Option<String>,
+    pub project_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TimeseriesQuery {
+    /// hour（默认）或 day
+    #[serde(default)]
+    bucket: StatsBucket,
+}
+
+/// 未指定起始时间时，按小时统计最近 24 小时，按天统计最近 30 天
+const DEFAULT_TIMESERIES_HOURS: i64 = 24;
+const DEFAULT_TIMESERIES_DAYS: i64 = 30;
+
+#[derive(Debug, Deserialize)]
+pub struct ReconciliationQuery {
+    /// 账单月份，格式 YYYY-MM
+    month: Option<String>,
+    provider: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -34,55 +77,87 @@ pub struct CallLogStatsResponse {
     pub stats: CallLogStats,
 }
 
-/// 获取调用日志列表（分页）
+/// 整体和按模型的调用统计，供管理界面绘制用量图表
+#[derive(Debug, Serialize)]
+pub struct CallLogOverviewResponse {
+    pub overall: CallLogStats,
+    pub by_model: Vec<ModelCallStats>,
+}
+
+impl CallLogQuery {
+    // 转换为 DAO 过滤条件，状态或时间格式不正确时返回 400
+    fn search(&self) -> Result<CallLogSearch, StatusCode> {
+        if [&self.start, &self.end].into_iter().flatten().any(|v| !is_valid_time_bound(v)) {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+
+        let mut search = CallLogSearch {
+            model_id: self.model_id.clone(),
+            provider: self.provider.clone(),
+            status_code: None,
+            errors_only: self.error_only.unwrap_or(false),
+            start: self.start.clone(),
+            end: self.end.clone(),
+            project_id: self.project_id.clone(),
+        };
+        match self.status.as_deref() {
+            None => {}
+            Some("success") => search.status_code = Some(200),
+            Some("error") => search.errors_only = true,
+            Some("cancelled") => search.status_code = Some(CALL_STATUS_CANCELLED),
+            Some(code) => search.status_code = Some(code.parse().map_err(|_| StatusCode::BAD_REQUEST)?),
+        }
+        Ok(search)
+    }
+
+    fn filter(&self) -> CallLogListFilter {
+        CallLogListFilter {
+            status: self.status.clone(),
+            error_only: self.error_only,
+            model_id: self.model_id.clone(),
+            provider: self.provider.clone(),
+            start: self.start.clone(),
+            end: self.end.clone(),
+            project_id: self.project_id.clone(),
+        }
+    }
+}
+
+/// 获取调用日志列表（游标分页，也支持按页码分页），可按状态、模型、供应商、项目和时间范围过滤
 pub async fn list_call_logs(
     Query(params): Query<CallLogQuery>,
-) -> Result<Json<CallLogResponse>, StatusCode> {
+) -> Result<Json<Page<CallLog, CallLogListFilter>>, StatusCode> {
     let pool = SQLITE_POOL.get()
         .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
         .as_ref();
-        
-    let page = params.page.unwrap_or(1);
-    let limit = params.limit.unwrap_or(100);
-    let error_only = params.error_only.unwrap_or(false);
-
-    // 计算偏移量
-    let offset = ((page - 1) * limit) as i64;
-    let limit_i64 = limit as i64;
-
-    // 获取总数
-    let total = match count_call_logs(pool).await {
-        Ok(count) => count,
-        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
-    };
-
-    // 获取日志数据
-    let call_logs = if error_only {
-        match list_error_call_logs(pool).await {
-            Ok(logs) => {
-                // 对于error_only，我们需要手动分页
-                logs.into_iter()
-                    .skip(offset as usize)
-                    .take(limit as usize)
-                    .collect()
-            }
-            Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
-        }
-    } else {
-        match list_call_logs_paginated(pool, limit_i64, offset).await {
-            Ok(logs) => logs,
-            Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
-        }
-    };
 
-    let total_pages = ((total as f64) / (limit as f64)).ceil() as u32;
+    let search = params.search()?;
+    let cursor = params.cursor.as_deref()
+        .map(|cursor| Cursor::decode(cursor).ok_or(StatusCode::BAD_REQUEST))
+        .transpose()?;
+    let limit = clamp_limit(params.limit);
+
+    let total = count_call_logs_by_search(pool, &search)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let page = match (cursor, params.page) {
+        (None, Some(page)) => {
+            // 多取一行判断是否还有下一页，返回的游标可以继续按游标翻页
+            let offset = (page.max(1) as i64 - 1) * limit;
+            search_call_logs(pool, &search, limit + 1, offset)
+                .await
+                .map(|rows| into_page(rows, limit))
+        }
+        (cursor, _) => search_call_logs_page(pool, &search, cursor.as_ref(), limit).await,
+    }
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    Ok(Json(CallLogResponse {
-        data: call_logs,
+    Ok(Json(Page {
+        data: page.items,
         total,
-        page,
         limit,
-        total_pages,
+        next_cursor: page.next_cursor.map(|cursor| cursor.encode()),
+        filter: params.filter(),
     }))
 }
 
@@ -97,3 +172,136 @@ pub async fn get_call_log_stats() -> Result<Json<CallLogStatsResponse>, StatusCo
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
 }
+
+/// 获取整体和按模型的调用统计，过滤条件与调用日志列表相同（忽略分页参数）
+pub async fn get_call_log_overview(
+    Query(params): Query<CallLogQuery>,
+) -> Result<Json<CallLogOverviewResponse>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    let search = params.search()?;
+    let overall = get_call_logs_stats_by_search(pool, &search)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let by_model = get_call_logs_stats_per_model(pool, &search)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(CallLogOverviewResponse { overall, by_model }))
+}
+
+/// 按小时或按天分桶的调用统计，供管理界面绘制趋势图；过滤条件与调用日志列表相同（忽略分页参数）
+pub async fn get_call_log_timeseries(
+    Query(params): Query<CallLogQuery>,
+    Query(timeseries): Query<TimeseriesQuery>,
+) -> Result<Json<Vec<CallLogStatsBucket>>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    let mut search = params.search()?;
+    if search.start.is_none() {
+        let window = match timeseries.bucket {
+            StatsBucket::Hour => chrono::Duration::hours(DEFAULT_TIMESERIES_HOURS),
+            StatsBucket::Day => chrono::Duration::days(DEFAULT_TIMESERIES_DAYS),
+        };
+        search.start = Some((chrono::Utc::now() - window).format("%Y-%m-%d %H:%M:%S").to_string());
+    }
+
+    match get_call_logs_stats_timeseries(pool, timeseries.bucket, &search).await {
+        Ok(stats) => Ok(Json(stats)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// 获取按提示词语言分组的调用统计
+pub async fn get_call_log_language_stats() -> Result<Json<Vec<LanguageCallStats>>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    match get_call_logs_stats_by_language(pool).await {
+        Ok(stats) => Ok(Json(stats)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// 获取按 A/B 流量拆分分组的调用统计（调用数、错误数、平均延迟、token 用量和费用）
+pub async fn get_call_log_traffic_arm_stats() -> Result<Json<Vec<TrafficArmCallStats>>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    match get_call_logs_stats_by_traffic_arm(pool).await {
+        Ok(stats) => Ok(Json(stats)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// 获取账单对账报表：按月份、供应商和模型对比网关统计与供应商返回的用量
+pub async fn get_billing_reconciliation_report(
+    Query(params): Query<ReconciliationQuery>,
+) -> Result<Json<Vec<BillingReconciliationRow>>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    if let Some(month) = &params.month
+        && chrono::NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d").is_err()
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match get_billing_reconciliation(pool, params.month.as_deref(), params.provider.as_deref()).await {
+        Ok(rows) => Ok(Json(rows)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+// 时间范围参数支持 YYYY-MM-DD 或 YYYY-MM-DD HH:MM:SS
+fn is_valid_time_bound(value: &str) -> bool {
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok()
+        || chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").is_ok()
+}
+
+// 校验过滤条件并启动后台任务；不允许不带任何条件，避免误删全部日志
+fn start_bulk_task(mode: ArchiveMode, filter: CallLogFilter) -> Result<(StatusCode, Json<ArchiveTask>), StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .clone();
+
+    if filter.is_empty() || [&filter.start, &filter.end].into_iter().flatten().any(|v| !is_valid_time_bound(v)) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let task = start_archive_task(pool, mode, filter, archive_dir_from_env(), DEFAULT_BATCH_SIZE);
+    Ok((StatusCode::ACCEPTED, Json(task)))
+}
+
+/// 归档调用日志：按模型和时间范围导出为 JSON Lines 文件后删除，返回后台任务
+pub async fn archive_call_logs(
+    Json(filter): Json<CallLogFilter>,
+) -> Result<(StatusCode, Json<ArchiveTask>), StatusCode> {
+    start_bulk_task(ArchiveMode::Archive, filter)
+}
+
+/// 按模型和时间范围批量删除调用日志，返回后台任务
+pub async fn bulk_delete_call_logs(
+    Json(filter): Json<CallLogFilter>,
+) -> Result<(StatusCode, Json<ArchiveTask>), StatusCode> {
+    start_bulk_task(ArchiveMode::Delete, filter)
+}
+
+/// 列出归档/批量删除任务
+pub async fn list_call_log_archive_tasks() -> Json<Vec<ArchiveTask>> {
+    Json(list_archive_tasks())
+}
+
+/// 查询归档/批量删除任务进度
+pub async fn get_call_log_archive_task(Path(id): Path<String>) -> Result<Json<ArchiveTask>, StatusCode> {
+    get_archive_task(&id)
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
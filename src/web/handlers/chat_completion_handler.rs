@@ -0,0 +1,349 @@
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, Path},
+    http::{HeaderMap, HeaderValue, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Json, Response,
+    },
+};
+use chrono::Utc;
+use futures_util::stream::{self, Stream};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::api_types::v1::GatewayErrorCode;
+use crate::llm_api::dispatcher::{
+    DispatchRequest, DispatchResponse, LLMDispatcher, LLMError, Provider, StreamChunk, StreamReceiver, GLOBAL_DISPATCHER,
+};
+use crate::llm_api::utils::cancellation::{cancel_request, register_request, InFlightRequest};
+use crate::llm_api::utils::client::{CallMetadata, CALL_METADATA};
+use crate::llm_api::utils::consumer_quota::consumer_id;
+use crate::web::dto::chat_completion_dto::*;
+use crate::web::extract::StreamingJson;
+use crate::web::middleware::quota::bearer_token;
+use crate::web::middleware::routing::RoutingOverride;
+use crate::web::middleware::timeout::REQUEST_ID_HEADER;
+
+pub(crate) type ApiError = (StatusCode, Json<OpenAIErrorResponse>);
+
+/// 网关按模型约束调整了请求参数时，非流式响应在该响应头中返回调整说明（多条以 `; ` 分隔）
+pub const WARNINGS_HEADER: &str = "x-gateway-warnings";
+
+/// OpenAI 兼容的 Chat Completion 接口，`stream: true` 时以 SSE 返回
+///
+/// 请求按 `x-request-id`（未提供时生成）登记为进行中，可通过 `DELETE /v1/requests/{request_id}` 取消；
+/// 响应头中带上该请求 ID 和参数调整说明；`X-LLM-Provider` / `X-LLM-Fallback` 请求头可以固定供应商、关闭 fallback
+pub async fn create_chat_completion(
+    headers: HeaderMap,
+    routing: Option<Extension<RoutingOverride>>,
+    StreamingJson(request): StreamingJson<ChatCompletionRequest>,
+) -> Result<Response, ApiError> {
+    let routing = routing.map(|Extension(routing)| routing).unwrap_or_default();
+    let (dispatcher, provider, model) = resolve_chat_model(&request, &routing).await?;
+
+    let in_flight = register_in_flight(&headers);
+    // 沿用中间件设置的调用方
+    let metadata = CallMetadata::current().with_cancellation(in_flight.token().clone());
+    let request_id = in_flight.request_id().to_string();
+
+    let requested_model = request.model.clone();
+    let stream = request.stream.unwrap_or(false);
+    let mut dispatch_request = build_dispatch_request(request, provider, model, metadata.project_id.clone());
+    routing.apply(&mut dispatch_request);
+
+    let response = if stream {
+        let receiver = CALL_METADATA.scope(metadata, dispatcher.dispatch_stream(dispatch_request)).await
+            .map_err(|e| map_llm_error(&e))?;
+        // 登记随 SSE 流一起释放
+        stream_chat_completion(receiver, requested_model, in_flight).into_response()
+    } else {
+        let response = CALL_METADATA.scope(metadata, dispatcher.dispatch(dispatch_request)).await
+            .map_err(|e| map_llm_error(&e))?;
+        let warnings = response.warnings.clone();
+        with_warnings(Json(to_chat_completion(response, requested_model)).into_response(), warnings.as_deref())
+    };
+    Ok(with_request_id(response, &request_id))
+}
+
+/// 校验请求并解析模型对应的供应商，请求头固定了供应商时直接使用该供应商（去掉模型名称中同名的前缀）
+pub(crate) async fn resolve_chat_model(
+    request: &ChatCompletionRequest,
+    routing: &RoutingOverride,
+) -> Result<(Arc<LLMDispatcher>, Provider, String), ApiError> {
+    let dispatcher = GLOBAL_DISPATCHER.get()
+        .ok_or_else(|| api_error(GatewayErrorCode::ServiceUnavailable, "Dispatcher not initialized", None))?
+        .clone();
+
+    if request.messages.is_empty() {
+        return Err(api_error(GatewayErrorCode::InvalidRequest, "messages must not be empty", Some("messages")));
+    }
+
+    if let Some(provider) = &routing.provider {
+        let model = request.model.split_once('/')
+            .filter(|(prefix, _)| Provider::from_name_or_custom(prefix) == *provider)
+            .map_or(request.model.as_str(), |(_, name)| name);
+        return Ok((dispatcher, provider.clone(), model.to_string()));
+    }
+
+    let (provider, model) = dispatcher.resolve_model(&request.model).await
+        .ok_or_else(|| api_error(
+            GatewayErrorCode::ModelNotFound,
+            &format!("The model `{}` does not exist", request.model),
+            None,
+        ))?;
+    Ok((dispatcher, provider, model))
+}
+
+/// 取消进行中的 Chat Completion 请求：停止重试和 fallback，中断上游调用和流式响应
+///
+/// 对应 `DELETE /v1/requests/{request_id}` 和 `POST /v1/requests/{request_id}/cancel`；
+/// 只能取消使用同一 Key 发起的请求，其他调用方的请求视为不存在
+pub async fn cancel_chat_request(
+    headers: HeaderMap,
+    Path(request_id): Path<String>,
+) -> Result<Json<CancelRequestResponse>, ApiError> {
+    if !cancel_request(request_owner(&headers).as_deref(), &request_id) {
+        return Err(api_error(
+            GatewayErrorCode::RequestNotFound,
+            &format!("No in-flight request with id `{}`", request_id),
+            None,
+        ));
+    }
+    info!(request_id = %request_id, "Request cancelled by client");
+    Ok(Json(CancelRequestResponse {
+        id: request_id,
+        object: "request".to_string(),
+        status: "cancelled".to_string(),
+    }))
+}
+
+/// 按调用方和请求头中的请求 ID 登记进行中的请求，同一调用方同一 ID 的旧请求被取消
+pub(crate) fn register_in_flight(headers: &HeaderMap) -> InFlightRequest {
+    let request_id = headers.get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    register_request(request_owner(headers).as_deref(), &request_id)
+}
+
+/// 进行中请求的所有者：请求头中 Bearer Key 的指纹，未携带 Key 时为 None
+pub(crate) fn request_owner(headers: &HeaderMap) -> Option<String> {
+    bearer_token(headers).map(consumer_id)
+}
+
+pub(crate) fn with_request_id(mut response: Response, request_id: &str) -> Response {
+    if let Ok(value) = HeaderValue::from_str(request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+pub(crate) fn with_warnings(mut response: Response, warnings: Option<&[String]>) -> Response {
+    if let Some(Ok(value)) = warnings.map(|warnings| HeaderValue::from_str(&warnings.join("; "))) {
+        response.headers_mut().insert(WARNINGS_HEADER, value);
+    }
+    response
+}
+
+/// SSE 输出所处阶段
+enum StreamPhase {
+    Streaming,
+    Done,
+    Closed,
+}
+
+/// 流式输出状态
+pub(crate) struct ChunkStream {
+    receiver: StreamReceiver,
+    id: String,
+    created: i64,
+    model: String,
+    role_sent: bool,
+    phase: StreamPhase,
+    in_flight: InFlightRequest,
+}
+
+impl ChunkStream {
+    pub(crate) fn new(receiver: StreamReceiver, model: String, in_flight: InFlightRequest) -> Self {
+        Self {
+            receiver,
+            id: format!("chatcmpl-{}", Uuid::new_v4().simple()),
+            created: Utc::now().timestamp(),
+            model,
+            role_sent: false,
+            phase: StreamPhase::Streaming,
+            in_flight,
+        }
+    }
+
+    /// 读取下一个分块，出错或被取消时返回错误响应，流结束时返回 None
+    pub(crate) async fn next_chunk(&mut self) -> Option<Result<ChatCompletionChunk, ApiError>> {
+        let next = match self.in_flight.token().clone().run_until_cancelled(self.receiver.recv()).await {
+            Some(Some(Ok(chunk))) => return Some(Ok(self.build_chunk(chunk))),
+            Some(Some(Err(e))) => Some(Err(map_llm_error(&e))),
+            Some(None) => None,
+            None => Some(Err(map_llm_error(&LLMError::Cancelled))),
+        };
+        // 出错、被取消或正常结束后不再读取
+        self.phase = StreamPhase::Done;
+        next
+    }
+
+    /// 将 dispatcher 的增量块转换为 OpenAI 格式分块
+    fn build_chunk(&mut self, chunk: StreamChunk) -> ChatCompletionChunk {
+        let role = if self.role_sent { None } else { Some("assistant".to_string()) };
+        self.role_sent = true;
+        let content = if chunk.content.is_empty() && (chunk.finish_reason.is_some() || chunk.tool_calls.is_some()) {
+            None
+        } else {
+            Some(chunk.content)
+        };
+        let tool_calls = chunk.tool_calls.map(|calls| {
+            calls.into_iter()
+                .zip(0..)
+                .map(|(call, index)| ChatCompletionToolCall::from_tool_call(call, Some(index)))
+                .collect()
+        });
+
+        ChatCompletionChunk {
+            id: self.id.clone(),
+            object: "chat.completion.chunk".to_string(),
+            created: self.created,
+            model: self.model.clone(),
+            choices: vec![ChatCompletionChunkChoice {
+                index: 0,
+                delta: ChatCompletionDelta { role, content, tool_calls },
+                finish_reason: chunk.finish_reason,
+            }],
+            usage: chunk.usage.map(|usage| ChatCompletionUsage {
+                prompt_tokens: usage.prompt_tokens,
+                completion_tokens: usage.completion_tokens,
+                total_tokens: usage.total_tokens,
+            }),
+        }
+    }
+}
+
+impl Drop for ChunkStream {
+    /// 客户端在流结束前断开（SSE 响应或 WebSocket 对话被丢弃）时取消请求，中断上游流式读取
+    fn drop(&mut self) {
+        if matches!(self.phase, StreamPhase::Streaming) {
+            info!(request_id = %self.in_flight.request_id(), "Client disconnected, cancelling stream");
+            self.in_flight.token().cancel();
+        }
+    }
+}
+
+/// 将流式结果转换为 SSE 响应，出错或被取消时先发送错误事件，最后以 `[DONE]` 结束
+fn stream_chat_completion(
+    receiver: StreamReceiver,
+    model: String,
+    in_flight: InFlightRequest,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let state = ChunkStream::new(receiver, model, in_flight);
+
+    let events = stream::unfold(state, |mut state| async move {
+        let event = match state.phase {
+            StreamPhase::Closed => return None,
+            StreamPhase::Done => {
+                state.phase = StreamPhase::Closed;
+                Event::default().data("[DONE]")
+            }
+            StreamPhase::Streaming => match state.next_chunk().await {
+                Some(Ok(chunk)) => json_event(&chunk),
+                Some(Err((_, Json(error)))) => {
+                    state.phase = StreamPhase::Done;
+                    json_event(&error)
+                }
+                None => {
+                    state.phase = StreamPhase::Closed;
+                    Event::default().data("[DONE]")
+                }
+            },
+        };
+        Some((Ok(event), state))
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+pub(crate) fn json_event<T: serde::Serialize>(data: &T) -> Event {
+    Event::default()
+        .json_data(data)
+        .unwrap_or_else(|_| Event::default().data("{}"))
+}
+
+/// 将 OpenAI 请求转换为 dispatcher 请求
+///
+/// 模型名称带供应商前缀（如 `openai/gpt-4o`）时视为调用方固定了供应商和模型，不再按语言、路由脚本或流量拆分改写；
+/// `tenant_id` 为 project_scope 解析出的项目，按租户生效的黑名单规则和终端用户限流以此区分租户
+pub(crate) fn build_dispatch_request(
+    request: ChatCompletionRequest,
+    provider: Provider,
+    model: String,
+    tenant_id: Option<String>,
+) -> DispatchRequest {
+    let pinned = request.model != model;
+    let messages = request.messages.into_iter().map(ChatCompletionMessage::into_message).collect();
+    let mut dispatch_request = DispatchRequest::new(provider, model, messages);
+    dispatch_request.temperature = request.temperature;
+    dispatch_request.max_tokens = request.max_tokens;
+    dispatch_request.top_p = request.top_p;
+    dispatch_request.frequency_penalty = request.frequency_penalty;
+    dispatch_request.presence_penalty = request.presence_penalty;
+    dispatch_request.stop = request.stop.map(StopSequence::into_vec);
+    dispatch_request.user = request.user;
+    dispatch_request.tools = request.tools;
+    dispatch_request.tool_choice = request.tool_choice;
+    dispatch_request.response_format = request.response_format;
+    dispatch_request.validate_response = request.validate_response;
+    dispatch_request.context_strategy = request.context_strategy;
+    dispatch_request.max_cost = request.max_cost;
+    dispatch_request.tenant_id = tenant_id;
+    if pinned {
+        dispatch_request.pin_provider = Some(true);
+    }
+    dispatch_request
+}
+
+/// 将 dispatcher 响应转换为 OpenAI 格式
+pub(crate) fn to_chat_completion(response: DispatchResponse, requested_model: String) -> ChatCompletionResponse {
+    ChatCompletionResponse {
+        // 响应 ID 与流式响应一致由网关生成，请求 ID 在 X-Request-Id 响应头中返回
+        id: format!("chatcmpl-{}", Uuid::new_v4().simple()),
+        object: "chat.completion".to_string(),
+        created: Utc::now().timestamp(),
+        model: if response.model.is_empty() { requested_model } else { response.model },
+        choices: vec![ChatCompletionChoice {
+            index: 0,
+            message: ChatCompletionResponseMessage {
+                role: "assistant".to_string(),
+                content: response.content,
+                tool_calls: response.tool_calls.map(|calls| {
+                    calls.into_iter().map(|call| ChatCompletionToolCall::from_tool_call(call, None)).collect()
+                }),
+            },
+            finish_reason: Some(response.finish_reason.unwrap_or_else(|| "stop".to_string())),
+        }],
+        usage: response.usage.map(|usage| ChatCompletionUsage {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            total_tokens: usage.total_tokens,
+        }),
+    }
+}
+
+/// 将 dispatcher 错误映射为 OpenAI 错误响应
+pub(crate) fn map_llm_error(error: &LLMError) -> ApiError {
+    api_error(error.error_code(), &error.to_string(), None)
+}
+
+pub(crate) fn api_error(code: GatewayErrorCode, message: &str, param: Option<&str>) -> ApiError {
+    let status = StatusCode::from_u16(code.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    (status, Json(code.to_openai_error(message, param)))
+}
@@ -0,0 +1,175 @@
+use std::convert::Infallible;
+
+use axum::{
+    http::HeaderMap,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Json, Response,
+    },
+};
+use chrono::Utc;
+use futures_util::stream::{self, Stream};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::api_types::v1::GatewayErrorCode;
+use crate::llm_api::dispatcher::{CompletionRequest, DispatchResponse, LLMError, StreamChunk, StreamReceiver, GLOBAL_DISPATCHER};
+use crate::llm_api::utils::cancellation::InFlightRequest;
+use crate::llm_api::utils::client::{CallMetadata, CALL_METADATA};
+use crate::web::dto::chat_completion_dto::ChatCompletionUsage;
+use crate::web::dto::completion_dto::*;
+use crate::web::extract::StreamingJson;
+use crate::web::handlers::chat_completion_handler::{
+    api_error, json_event, map_llm_error, register_in_flight, with_request_id, ApiError,
+};
+
+/// OpenAI 兼容的 Completions 接口（纯文本续写，不套用对话模板），`stream: true` 时以 SSE 返回
+///
+/// 目前支持 Ollama（`/api/generate`）和 OpenAI 兼容供应商（`/v1/completions`）；
+/// 与 Chat Completion 一样登记为进行中的请求，可通过请求 ID 取消
+pub async fn create_completion(
+    headers: HeaderMap,
+    StreamingJson(request): StreamingJson<CompletionCreateRequest>,
+) -> Result<Response, ApiError> {
+    let dispatcher = GLOBAL_DISPATCHER.get()
+        .ok_or_else(|| api_error(GatewayErrorCode::ServiceUnavailable, "Dispatcher not initialized", None))?
+        .clone();
+    if request.prompt.is_empty() {
+        return Err(api_error(GatewayErrorCode::InvalidRequest, "prompt must not be empty", Some("prompt")));
+    }
+    let (provider, model) = dispatcher.resolve_model(&request.model).await
+        .ok_or_else(|| api_error(
+            GatewayErrorCode::ModelNotFound,
+            &format!("The model `{}` does not exist", request.model),
+            None,
+        ))?;
+
+    let in_flight = register_in_flight(&headers);
+    let metadata = CallMetadata::current().with_cancellation(in_flight.token().clone());
+    let request_id = in_flight.request_id().to_string();
+
+    let requested_model = request.model.clone();
+    let stream = request.stream.unwrap_or(false);
+    let mut completion_request = CompletionRequest::new(provider, model, request.prompt);
+    completion_request.suffix = request.suffix;
+    completion_request.temperature = request.temperature;
+    completion_request.max_tokens = request.max_tokens;
+    completion_request.top_p = request.top_p;
+    completion_request.stop = request.stop.map(|stop| stop.into_vec());
+    completion_request.user = request.user;
+    completion_request.tenant_id = metadata.project_id.clone();
+
+    let response = if stream {
+        let receiver = CALL_METADATA.scope(metadata, dispatcher.dispatch_completion_stream(completion_request)).await
+            .map_err(|e| map_llm_error(&e))?;
+        stream_completion(receiver, requested_model, in_flight).into_response()
+    } else {
+        let response = CALL_METADATA.scope(metadata, dispatcher.dispatch_completion(completion_request)).await
+            .map_err(|e| map_llm_error(&e))?;
+        Json(to_completion(response, requested_model)).into_response()
+    };
+    Ok(with_request_id(response, &request_id))
+}
+
+/// 将 dispatcher 响应转换为 OpenAI `text_completion` 格式
+fn to_completion(response: DispatchResponse, requested_model: String) -> CompletionResponse {
+    CompletionResponse {
+        id: format!("cmpl-{}", Uuid::new_v4().simple()),
+        object: "text_completion".to_string(),
+        created: Utc::now().timestamp(),
+        model: if response.model.is_empty() { requested_model } else { response.model },
+        choices: vec![CompletionChoice {
+            text: response.content,
+            index: 0,
+            finish_reason: Some(response.finish_reason.unwrap_or_else(|| "stop".to_string())),
+        }],
+        usage: response.usage.map(|usage| ChatCompletionUsage {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            total_tokens: usage.total_tokens,
+        }),
+    }
+}
+
+/// 流式补全状态，客户端在流结束前断开时取消请求
+struct CompletionStream {
+    receiver: StreamReceiver,
+    id: String,
+    created: i64,
+    model: String,
+    finished: bool,
+    in_flight: InFlightRequest,
+}
+
+impl CompletionStream {
+    fn build_chunk(&self, chunk: StreamChunk) -> CompletionResponse {
+        CompletionResponse {
+            id: self.id.clone(),
+            object: "text_completion".to_string(),
+            created: self.created,
+            model: self.model.clone(),
+            choices: vec![CompletionChoice {
+                text: chunk.content,
+                index: 0,
+                finish_reason: chunk.finish_reason,
+            }],
+            usage: chunk.usage.map(|usage| ChatCompletionUsage {
+                prompt_tokens: usage.prompt_tokens,
+                completion_tokens: usage.completion_tokens,
+                total_tokens: usage.total_tokens,
+            }),
+        }
+    }
+}
+
+impl Drop for CompletionStream {
+    fn drop(&mut self) {
+        if !self.finished {
+            info!(request_id = %self.in_flight.request_id(), "Client disconnected, cancelling completion stream");
+            self.in_flight.token().cancel();
+        }
+    }
+}
+
+/// 将流式结果转换为 SSE 响应，出错或被取消时先发送错误事件，最后以 `[DONE]` 结束
+fn stream_completion(
+    receiver: StreamReceiver,
+    model: String,
+    in_flight: InFlightRequest,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let state = CompletionStream {
+        receiver,
+        id: format!("cmpl-{}", Uuid::new_v4().simple()),
+        created: Utc::now().timestamp(),
+        model,
+        finished: false,
+        in_flight,
+    };
+
+    // Some(state) 表示还需发送 [DONE]，None 表示流已关闭
+    let events = stream::unfold(Some(state), |state| async move {
+        let mut state = state?;
+        if state.finished {
+            return Some((Ok(Event::default().data("[DONE]")), None));
+        }
+        let next = state.in_flight.token().clone().run_until_cancelled(state.receiver.recv()).await;
+        let event = match next {
+            Some(Some(Ok(chunk))) => json_event(&state.build_chunk(chunk)),
+            Some(Some(Err(e))) => {
+                state.finished = true;
+                json_event(&map_llm_error(&e).1.0)
+            }
+            Some(None) => {
+                state.finished = true;
+                return Some((Ok(Event::default().data("[DONE]")), None));
+            }
+            None => {
+                state.finished = true;
+                json_event(&map_llm_error(&LLMError::Cancelled).1.0)
+            }
+        };
+        Some((Ok(event), Some(state)))
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
@@ -0,0 +1,90 @@
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    response::Json,
+};
+
+use crate::dao::{
+    consumer_usage::{list_consumer_usage, ConsumerUsageFilter, ConsumerUsageStat},
+    SQLITE_POOL,
+};
+use crate::llm_api::utils::consumer_quota::{
+    get_consumer_quotas, list_consumer_quotas, set_consumer_quota, ConsumerQuota, ConsumerUsageStatus,
+};
+use crate::web::dto::usage_dto::{ConsumerQuotaResponse, UpdateConsumerQuotaRequest};
+
+/// 当天有用量的调用方及其配额（内存中的实时计数）
+pub async fn list_consumers() -> Json<Vec<ConsumerUsageStatus>> {
+    Json(get_consumer_quotas().usage_snapshot())
+}
+
+/// 按天查询调用方的请求数和 token 数（已写回数据库的部分），`start`/`end` 为 YYYY-MM-DD（含）
+pub async fn list_consumer_usage_history(
+    Query(filter): Query<ConsumerUsageFilter>,
+) -> Result<Json<Vec<ConsumerUsageStat>>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    let valid_day = |day: &Option<String>| day.as_ref()
+        .is_none_or(|day| chrono::NaiveDate::parse_from_str(day, "%Y-%m-%d").is_ok());
+    if !valid_day(&filter.start) || !valid_day(&filter.end) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match list_consumer_usage(pool, &filter).await {
+        Ok(stats) => Ok(Json(stats)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// 列出已配置的调用方配额
+pub async fn list_quotas() -> Result<Json<Vec<ConsumerQuotaResponse>>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    match list_consumer_quotas(pool).await {
+        Ok(quotas) => Ok(Json(quotas.into_iter().map(|(consumer_id, quota)| to_response(consumer_id, quota)).collect())),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// 设置调用方的配额，`consumer_id` 为 `default` 时设置默认配额
+pub async fn update_quota(
+    Path(consumer_id): Path<String>,
+    Json(request): Json<UpdateConsumerQuotaRequest>,
+) -> Result<Json<ConsumerQuotaResponse>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    let quota = ConsumerQuota {
+        requests_per_minute: request.requests_per_minute,
+        tokens_per_day: request.tokens_per_day,
+    };
+    set_consumer_quota(pool, &consumer_id, Some(quota))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(to_response(consumer_id, quota)))
+}
+
+/// 删除调用方单独的配额，之后使用默认配额
+pub async fn delete_quota(Path(consumer_id): Path<String>) -> StatusCode {
+    let Some(pool) = SQLITE_POOL.get() else {
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    };
+
+    match set_consumer_quota(pool, &consumer_id, None).await {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn to_response(consumer_id: String, quota: ConsumerQuota) -> ConsumerQuotaResponse {
+    ConsumerQuotaResponse {
+        consumer_id,
+        requests_per_minute: quota.requests_per_minute,
+        tokens_per_day: quota.tokens_per_day,
+    }
+}
@@ -0,0 +1,241 @@
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    response::Json,
+};
+use serde::Deserialize;
+use sqlx::SqlitePool;
+
+use crate::api_types::v1::chat_completion::ChatCompletionUsage;
+use crate::api_types::v1::GatewayErrorCode;
+use crate::dao::{
+    conversation::{
+        Conversation, ConversationMessage, create_conversation, get_conversation_by_id, list_conversations_by_project,
+        delete_conversation, append_conversation_message, list_conversation_messages,
+    },
+    SQLITE_POOL,
+};
+use crate::llm_api::dispatcher::{DispatchRequest, GLOBAL_DISPATCHER};
+use crate::llm_api::utils::client::CallMetadata;
+use crate::llm_api::utils::context_window::{estimate_tokens, truncate_history, TruncationStrategy};
+use crate::llm_api::utils::msg_structure::Message;
+use crate::web::dto::conversation_dto::*;
+use crate::web::handlers::chat_completion_handler::{api_error, map_llm_error, ApiError};
+
+/// 会话列表默认和最大返回数量
+const DEFAULT_LIST_LIMIT: i64 = 20;
+const MAX_LIST_LIMIT: i64 = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct ListConversationsQuery {
+    pub limit: Option<i64>,
+}
+
+/// 创建会话，归属请求所在的项目
+pub async fn create_new_conversation(
+    Json(request): Json<CreateConversationRequest>,
+) -> Result<Json<ConversationResponse>, ApiError> {
+    let pool = db_pool()?;
+
+    let truncation = match request.truncation.as_deref() {
+        None => TruncationStrategy::default(),
+        Some(name) => TruncationStrategy::from_name(name).ok_or_else(|| api_error(
+            GatewayErrorCode::InvalidRequest,
+            &format!("Unknown truncation strategy `{}`, expected drop_oldest or keep_first_turn", name),
+            Some("truncation"),
+        ))?,
+    };
+    if request.max_context_tokens == Some(0) {
+        return Err(api_error(GatewayErrorCode::InvalidRequest, "max_context_tokens must be greater than 0", Some("max_context_tokens")));
+    }
+    ensure_model_exists(&request.model).await?;
+
+    let conversation = Conversation {
+        id: format!("conv-{}", uuid::Uuid::new_v4().simple()),
+        title: request.title,
+        model: request.model,
+        system_prompt: request.system_prompt.filter(|prompt| !prompt.trim().is_empty()),
+        max_context_tokens: request.max_context_tokens.map(i64::from),
+        truncation: truncation.as_str().to_string(),
+        project_id: CallMetadata::current().project_id().to_string(),
+        created_at: None, // 数据库会自动设置
+        updated_at: None,
+    };
+    create_conversation(pool, &conversation).await.map_err(db_error)?;
+
+    let created = find_conversation(pool, &conversation.id).await?;
+    Ok(Json(to_response(created)))
+}
+
+/// 列出当前项目的会话，最近更新的在前
+pub async fn list_project_conversations(
+    Query(query): Query<ListConversationsQuery>,
+) -> Result<Json<Vec<ConversationResponse>>, ApiError> {
+    let pool = db_pool()?;
+    let limit = query.limit.unwrap_or(DEFAULT_LIST_LIMIT).clamp(1, MAX_LIST_LIMIT);
+    let conversations = list_conversations_by_project(pool, CallMetadata::current().project_id(), limit)
+        .await
+        .map_err(db_error)?;
+    Ok(Json(conversations.into_iter().map(to_response).collect()))
+}
+
+/// 获取会话及全部消息
+pub async fn get_conversation(Path(id): Path<String>) -> Result<Json<ConversationDetailResponse>, ApiError> {
+    let pool = db_pool()?;
+    let conversation = find_conversation(pool, &id).await?;
+    let messages = list_conversation_messages(pool, &id).await.map_err(db_error)?;
+    Ok(Json(ConversationDetailResponse {
+        conversation: to_response(conversation),
+        messages: messages.into_iter().map(to_message_response).collect(),
+    }))
+}
+
+/// 删除会话及其消息
+pub async fn delete_existing_conversation(Path(id): Path<String>) -> Result<StatusCode, ApiError> {
+    let pool = db_pool()?;
+    find_conversation(pool, &id).await?;
+    delete_conversation(pool, &id).await.map_err(db_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 向会话发送用户消息：带上保存的历史（超出 token 上限时按会话的策略截断）调用模型，
+/// 调用成功后保存用户消息和助手回复；调用失败时历史保持不变，可以直接重发
+pub async fn send_conversation_message(
+    Path(id): Path<String>,
+    Json(request): Json<SendMessageRequest>,
+) -> Result<Json<SendMessageResponse>, ApiError> {
+    let pool = db_pool()?;
+    let dispatcher = GLOBAL_DISPATCHER.get()
+        .ok_or_else(|| api_error(GatewayErrorCode::ServiceUnavailable, "Dispatcher not initialized", None))?
+        .clone();
+
+    if request.content.trim().is_empty() {
+        return Err(api_error(GatewayErrorCode::InvalidRequest, "content must not be empty", Some("content")));
+    }
+    let conversation = find_conversation(pool, &id).await?;
+    let requested_model = request.model.as_deref().unwrap_or(&conversation.model);
+    let (provider, model) = dispatcher.resolve_model(requested_model).await
+        .ok_or_else(|| model_not_found(requested_model))?;
+
+    // system 提示词 + 历史消息 + 本次的用户消息
+    let stored = list_conversation_messages(pool, &id).await.map_err(db_error)?;
+    let mut history: Vec<Message> = conversation.system_prompt.iter()
+        .map(|prompt| Message::system(prompt.clone()))
+        .collect();
+    history.extend(stored.into_iter().map(|message| Message {
+        role: message.role,
+        ..Message::user(message.content)
+    }));
+    history.push(Message::user(request.content.clone()));
+
+    let (messages, truncated_messages) = match conversation.max_context_tokens {
+        Some(max_tokens) => {
+            let strategy = TruncationStrategy::from_name(&conversation.truncation).unwrap_or_default();
+            let truncated = truncate_history(history, max_tokens.max(0) as usize, strategy);
+            (truncated.messages, truncated.dropped.len())
+        }
+        None => (history, 0),
+    };
+
+    let mut dispatch_request = DispatchRequest::new(provider, model, messages);
+    dispatch_request.temperature = request.temperature;
+    dispatch_request.max_tokens = request.max_tokens;
+    dispatch_request.tenant_id = CallMetadata::current().project_id;
+    let response = dispatcher.dispatch(dispatch_request).await.map_err(|e| map_llm_error(&e))?;
+
+    append_conversation_message(pool, &new_message(&id, "user", request.content.clone(), estimate_tokens(&request.content) as i64))
+        .await
+        .map_err(db_error)?;
+    let reply_tokens = response.usage.as_ref()
+        .map(|usage| usage.completion_tokens as i64)
+        .unwrap_or_else(|| estimate_tokens(&response.content) as i64);
+    let reply = append_conversation_message(pool, &new_message(&id, "assistant", response.content, reply_tokens))
+        .await
+        .map_err(db_error)?;
+
+    Ok(Json(SendMessageResponse {
+        conversation_id: id,
+        message: to_message_response(reply),
+        finish_reason: response.finish_reason,
+        usage: response.usage.map(|usage| ChatCompletionUsage {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            total_tokens: usage.total_tokens,
+        }),
+        truncated_messages,
+    }))
+}
+
+fn db_pool() -> Result<&'static SqlitePool, ApiError> {
+    SQLITE_POOL.get()
+        .map(|pool| pool.as_ref())
+        .ok_or_else(|| api_error(GatewayErrorCode::ServiceUnavailable, "Database not initialized", None))
+}
+
+fn db_error(error: sqlx::Error) -> ApiError {
+    tracing::error!("Conversation query failed: {:?}", error);
+    api_error(GatewayErrorCode::ServiceUnavailable, "Conversation storage is unavailable", None)
+}
+
+fn model_not_found(model: &str) -> ApiError {
+    api_error(GatewayErrorCode::ModelNotFound, &format!("The model `{}` does not exist", model), Some("model"))
+}
+
+async fn ensure_model_exists(model: &str) -> Result<(), ApiError> {
+    let dispatcher = GLOBAL_DISPATCHER.get()
+        .ok_or_else(|| api_error(GatewayErrorCode::ServiceUnavailable, "Dispatcher not initialized", None))?;
+    match dispatcher.resolve_model(model).await {
+        Some(_) => Ok(()),
+        None => Err(model_not_found(model)),
+    }
+}
+
+// 其他项目的会话视为不存在
+async fn find_conversation(pool: &SqlitePool, id: &str) -> Result<Conversation, ApiError> {
+    let conversation = get_conversation_by_id(pool, id).await.map_err(db_error)?;
+    match conversation {
+        Some(conversation) if conversation.project_id == CallMetadata::current().project_id() => Ok(conversation),
+        _ => Err(api_error(
+            GatewayErrorCode::ConversationNotFound,
+            &format!("No conversation with id `{}`", id),
+            None,
+        )),
+    }
+}
+
+fn new_message(conversation_id: &str, role: &str, content: String, tokens: i64) -> ConversationMessage {
+    ConversationMessage {
+        id: uuid::Uuid::new_v4().to_string(),
+        conversation_id: conversation_id.to_string(),
+        seq: 0, // 写入时分配
+        role: role.to_string(),
+        content,
+        tokens,
+        created_at: None,
+    }
+}
+
+fn to_response(conversation: Conversation) -> ConversationResponse {
+    ConversationResponse {
+        id: conversation.id,
+        title: conversation.title,
+        model: conversation.model,
+        system_prompt: conversation.system_prompt,
+        max_context_tokens: conversation.max_context_tokens.map(|tokens| tokens as u32),
+        truncation: conversation.truncation,
+        project_id: conversation.project_id,
+        created_at: conversation.created_at.unwrap_or_default(),
+        updated_at: conversation.updated_at.unwrap_or_default(),
+    }
+}
+
+fn to_message_response(message: ConversationMessage) -> ConversationMessageResponse {
+    ConversationMessageResponse {
+        id: message.id,
+        seq: message.seq,
+        role: message.role,
+        content: message.content,
+        tokens: message.tokens,
+        created_at: message.created_at.unwrap_or_default(),
+    }
+}
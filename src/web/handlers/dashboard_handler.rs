@@ -0,0 +1,164 @@
+//! # 仪表盘汇总
+//!
+//! `/api/dashboard` 一次返回 Web 管理界面首页需要的数据：今天和本周的请求数、token、费用、错误率和延迟分位数，
+//! 本周调用最多的模型，各供应商的 Key 数量和健康状况，以及进程内的主要计数器。
+//! 结果缓存 [`DASHBOARD_CACHE_TTL`]，多个管理页面同时刷新时不会重复扫描调用记录
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use axum::{
+    http::StatusCode,
+    response::Json,
+};
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, Offset, TimeZone};
+use lazy_static::lazy_static;
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+use crate::dao::{
+    cache::cache::CacheService,
+    call_log::{
+        get_call_log_latency_percentile, get_call_logs_stats_by_search, get_call_logs_stats_per_model,
+        CallLogSearch, ModelCallStats,
+    },
+    SQLITE_POOL,
+};
+use crate::metrics::metrics;
+use super::status_handler::{provider_report, recent_call_stats, ProviderStatus};
+
+/// 汇总结果的缓存时间
+pub const DASHBOARD_CACHE_TTL: Duration = Duration::from_secs(10);
+/// 汇总结果缓存的名称（命中率指标的 `cache` 标签）
+pub const DASHBOARD_CACHE_NAME: &str = "dashboard";
+/// 返回的热门模型数
+const TOP_MODELS: usize = 5;
+/// 汇总的计数器（所有标签合计）
+const DASHBOARD_COUNTERS: &[&str] = &[
+    "llm_gateway_requests_cancelled_total",
+    "llm_gateway_consumer_quota_rejections_total",
+    "llm_gateway_budget_rejections_total",
+    "llm_gateway_cost_limit_rejections_total",
+    "llm_gateway_degraded_responses_total",
+    "llm_gateway_context_truncations_total",
+];
+
+lazy_static! {
+    static ref DASHBOARD_CACHE: CacheService<(), Arc<Dashboard>> =
+        CacheService::new(DASHBOARD_CACHE_TTL, 1).with_name(DASHBOARD_CACHE_NAME);
+}
+
+/// 仪表盘汇总
+#[derive(Debug, Serialize)]
+pub struct Dashboard {
+    pub generated_at: String,
+    pub today: PeriodSummary,
+    pub this_week: PeriodSummary,
+    /// 本周调用最多的模型
+    pub top_models: Vec<ModelCallStats>,
+    /// 各供应商的 Key 数量、健康状况和最近的错误率
+    pub providers: Vec<ProviderStatus>,
+    /// 进程启动以来的计数器
+    pub counters: BTreeMap<&'static str, u64>,
+}
+
+/// 一段时间内的调用汇总
+#[derive(Debug, Serialize)]
+pub struct PeriodSummary {
+    /// 起始时间（UTC，包含），与调用记录的 `created_at` 一致
+    pub since: String,
+    pub total_requests: i64,
+    pub total_tokens: i64,
+    pub total_cost: f64,
+    pub error_count: i64,
+    /// 没有调用时为 None
+    pub error_rate: Option<f64>,
+    pub p50_latency_ms: Option<i64>,
+    pub p95_latency_ms: Option<i64>,
+}
+
+/// 获取仪表盘汇总
+pub async fn get_dashboard() -> Result<Json<Arc<Dashboard>>, StatusCode> {
+    if let Some(dashboard) = DASHBOARD_CACHE.get(&()).await {
+        return Ok(Json(dashboard));
+    }
+
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+    let dashboard = Arc::new(build_dashboard(pool).await?);
+    DASHBOARD_CACHE.insert((), dashboard.clone()).await;
+    Ok(Json(dashboard))
+}
+
+/// 今天 0 点和本周一 0 点（按 `now` 所在时区）换算成的 UTC 时间，用于和以 UTC 存储的 `created_at` 比较
+pub fn period_starts<Tz: TimeZone>(now: &DateTime<Tz>) -> (NaiveDateTime, NaiveDateTime) {
+    let today = now.date_naive();
+    let week_start = today - chrono::Duration::days(today.weekday().num_days_from_monday() as i64);
+    let to_utc = |day: NaiveDate| {
+        let midnight = day.and_hms_opt(0, 0, 0).expect("midnight is a valid time");
+        // 夏令时切换使当地 0 点不存在时，按当前的 UTC 偏移换算
+        now.timezone().from_local_datetime(&midnight).earliest()
+            .map_or_else(
+                || midnight - chrono::Duration::seconds(now.offset().fix().local_minus_utc() as i64),
+                |start| start.naive_utc(),
+            )
+    };
+    (to_utc(today), to_utc(week_start))
+}
+
+async fn build_dashboard(pool: &SqlitePool) -> Result<Dashboard, StatusCode> {
+    let (today, week_start) = period_starts(&Local::now());
+
+    let week_search = search_since(week_start);
+    let mut top_models = get_call_logs_stats_per_model(pool, &week_search)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    top_models.truncate(TOP_MODELS);
+
+    let call_stats = recent_call_stats(pool).await?;
+    let providers = provider_report(pool, &call_stats).await?.providers;
+
+    let counters = DASHBOARD_COUNTERS.iter()
+        .map(|name| (*name, metrics().counter_total(name)))
+        .collect();
+
+    Ok(Dashboard {
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        today: period_summary(pool, &search_since(today)).await?,
+        this_week: period_summary(pool, &week_search).await?,
+        top_models,
+        providers,
+        counters,
+    })
+}
+
+fn search_since(since: NaiveDateTime) -> CallLogSearch {
+    CallLogSearch {
+        start: Some(since.format("%Y-%m-%d %H:%M:%S").to_string()),
+        ..CallLogSearch::default()
+    }
+}
+
+async fn period_summary(pool: &SqlitePool, search: &CallLogSearch) -> Result<PeriodSummary, StatusCode> {
+    let stats = get_call_logs_stats_by_search(pool, search)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let p50_latency_ms = get_call_log_latency_percentile(pool, search, 0.5)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let p95_latency_ms = get_call_log_latency_percentile(pool, search, 0.95)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(PeriodSummary {
+        since: search.start.clone().unwrap_or_default(),
+        total_requests: stats.total_calls,
+        total_tokens: stats.total_tokens_input + stats.total_tokens_output,
+        total_cost: stats.total_cost,
+        error_count: stats.error_count,
+        error_rate: (stats.total_calls > 0).then(|| stats.error_count as f64 / stats.total_calls as f64),
+        p50_latency_ms,
+        p95_latency_ms,
+    })
+}
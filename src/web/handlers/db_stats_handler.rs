@@ -0,0 +1,56 @@
+use axum::{
+    http::StatusCode,
+    response::Json,
+};
+use serde::Serialize;
+
+use crate::dao::{
+    query_stats::{explain_query_plan, index_hints, query_stats, reset_query_stats, slow_query_threshold, QueryStat},
+    SQLITE_POOL,
+};
+
+/// 数据库查询耗时统计
+#[derive(Debug, Serialize)]
+pub struct DbStats {
+    pub slow_query_threshold_ms: u64,
+    /// 按总耗时从多到少排列
+    pub queries: Vec<QueryReport>,
+}
+
+/// 单个查询的耗时和执行计划
+#[derive(Debug, Serialize)]
+pub struct QueryReport {
+    #[serde(flatten)]
+    pub stat: QueryStat,
+    /// `EXPLAIN QUERY PLAN` 结果，无法解析时为空
+    pub plan: Vec<String>,
+    pub index_hints: Vec<String>,
+}
+
+/// 获取各 DAO 查询的耗时统计，并附带执行计划和索引建议
+pub async fn get_db_stats() -> Result<Json<DbStats>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    let mut queries = Vec::new();
+    for stat in query_stats() {
+        let plan = explain_query_plan(pool, &stat.sql).await.unwrap_or_default();
+        queries.push(QueryReport {
+            index_hints: index_hints(&plan),
+            plan,
+            stat,
+        });
+    }
+
+    Ok(Json(DbStats {
+        slow_query_threshold_ms: slow_query_threshold().as_millis() as u64,
+        queries,
+    }))
+}
+
+/// 清空查询耗时统计
+pub async fn reset_db_stats() -> StatusCode {
+    reset_query_stats();
+    StatusCode::NO_CONTENT
+}
@@ -0,0 +1,31 @@
+use axum::{
+    http::StatusCode,
+    response::Json,
+};
+
+use crate::llm_api::utils::degradation::{get_degradation_guard, DegradationStatus};
+use crate::web::dto::degradation_dto::UpdateDegradationRequest;
+
+/// 获取降级模式状态
+pub async fn get_degradation_status() -> Json<DegradationStatus> {
+    Json(get_degradation_guard().status().await)
+}
+
+/// 手动开关降级模式或更新兜底消息
+pub async fn update_degradation(
+    Json(request): Json<UpdateDegradationRequest>,
+) -> Result<Json<DegradationStatus>, StatusCode> {
+    let guard = get_degradation_guard();
+
+    if let Some(message) = request.fallback_message {
+        if message.trim().is_empty() {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        guard.set_fallback_message(message).await;
+    }
+    if let Some(enabled) = request.enabled {
+        guard.set_manual(enabled).await;
+    }
+
+    Ok(Json(guard.status().await))
+}
@@ -0,0 +1,27 @@
+use axum::{extract::Extension, response::Json};
+
+use crate::api_types::v1::GatewayErrorCode;
+use crate::llm_api::utils::client::CallMetadata;
+use crate::web::dto::chat_completion_dto::ChatCompletionRequest;
+use crate::web::dto::estimate_dto::CostEstimate;
+use crate::web::extract::StreamingJson;
+use crate::web::handlers::chat_completion_handler::{api_error, build_dispatch_request, resolve_chat_model, ApiError};
+use crate::web::middleware::routing::RoutingOverride;
+
+/// 预估 Chat Completion 请求的提示词 token 数和费用，不访问上游
+///
+/// 请求体与 `/v1/chat/completions` 相同，回复按 `max_tokens` 计算；带 `max_cost` 时返回是否会被拒绝
+pub async fn estimate_chat_cost(
+    routing: Option<Extension<RoutingOverride>>,
+    StreamingJson(request): StreamingJson<ChatCompletionRequest>,
+) -> Result<Json<CostEstimate>, ApiError> {
+    let routing = routing.map(|Extension(routing)| routing).unwrap_or_default();
+    if request.max_cost.is_some_and(|max_cost| max_cost < 0.0) {
+        return Err(api_error(GatewayErrorCode::InvalidRequest, "max_cost must not be negative", Some("max_cost")));
+    }
+    let (dispatcher, provider, model) = resolve_chat_model(&request, &routing).await?;
+
+    let mut dispatch_request = build_dispatch_request(request, provider, model, CallMetadata::current().project_id);
+    routing.apply(&mut dispatch_request);
+    Ok(Json(dispatcher.estimate_cost(dispatch_request).await))
+}
@@ -1,9 +1,20 @@
 use axum::{
+    extract::Query,
     http::StatusCode,
     response::Json,
 };
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
+use crate::dao::{
+    cache::is_cache_preloaded,
+    model::{get_active_model_names_from_cache, get_model_health_from_cache, HEALTH_UNHEALTHY},
+    provider_key_pool::{get_active_key_count, get_cooling_down_keys},
+    SQLITE_POOL,
+};
+use crate::llm_api::dispatcher::GLOBAL_DISPATCHER;
+use crate::llm_api::utils::degradation::get_degradation_guard;
+
 /// 健康检查端点
 pub async fn health_check() -> Json<Value> {
     Json(json!({
@@ -22,3 +33,150 @@ pub async fn system_info() -> Result<Json<Value>, StatusCode> {
         "build_time": "unknown" // 可以通过build.rs添加编译时间
     })))
 }
+
+/// 就绪级别，按严重程度排序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadinessLevel {
+    /// 所有依赖正常
+    Ready,
+    /// 可以服务，但部分供应商不可用、缓存未预热或处于降级模式
+    Degraded,
+    /// 无法服务请求
+    Unready,
+}
+
+/// 未就绪或降级的原因，`code` 供负载均衡和告警规则识别
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadinessReason {
+    pub code: &'static str,
+    pub level: ReadinessLevel,
+    pub component: String,
+    pub message: String,
+}
+
+/// 就绪检查结果
+#[derive(Debug, Serialize)]
+pub struct Readiness {
+    pub status: ReadinessLevel,
+    pub reasons: Vec<ReadinessReason>,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReadinessQuery {
+    /// 降级时仍返回 200，让负载均衡保留该实例
+    allow_degraded: Option<bool>,
+}
+
+impl ReadinessReason {
+    fn new(code: &'static str, level: ReadinessLevel, component: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            level,
+            component: component.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// 检查数据库、调度器、各供应商、缓存和降级模式，汇总为就绪级别
+pub async fn check_readiness() -> Readiness {
+    let mut reasons = Vec::new();
+
+    let pool = SQLITE_POOL.get();
+    match pool {
+        None => reasons.push(ReadinessReason::new(
+            "database_unavailable", ReadinessLevel::Unready, "database", "Database pool not initialized",
+        )),
+        Some(pool) => {
+            if let Err(e) = sqlx::query("SELECT 1").execute(pool.as_ref()).await {
+                reasons.push(ReadinessReason::new(
+                    "database_unavailable", ReadinessLevel::Unready, "database", format!("Database query failed: {}", e),
+                ));
+            }
+        }
+    }
+
+    match GLOBAL_DISPATCHER.get() {
+        None => reasons.push(ReadinessReason::new(
+            "dispatcher_not_initialized", ReadinessLevel::Unready, "dispatcher", "Dispatcher not initialized",
+        )),
+        Some(dispatcher) => {
+            let providers = dispatcher.registered_providers().await;
+            if providers.is_empty() {
+                reasons.push(ReadinessReason::new(
+                    "no_providers_registered", ReadinessLevel::Unready, "dispatcher", "No provider adapters registered",
+                ));
+            } else {
+                let names: Vec<String> = providers.iter().map(|provider| provider.as_str().to_string()).collect();
+                let down = check_providers(&names, &mut reasons).await;
+                if down == names.len() {
+                    reasons.push(ReadinessReason::new(
+                        "all_providers_down", ReadinessLevel::Unready, "providers", "All registered providers are unavailable",
+                    ));
+                }
+            }
+        }
+    }
+
+    if !is_cache_preloaded() {
+        reasons.push(ReadinessReason::new(
+            "cache_cold", ReadinessLevel::Degraded, "cache", "Global cache is not initialized or preload failed",
+        ));
+    }
+
+    if get_degradation_guard().is_active().await {
+        reasons.push(ReadinessReason::new(
+            "degradation_active", ReadinessLevel::Degraded, "degradation", "Serving degraded responses",
+        ));
+    }
+
+    Readiness {
+        status: reasons.iter().map(|reason| reason.level).max().unwrap_or(ReadinessLevel::Ready),
+        reasons,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    }
+}
+
+// 供应商不可用：启用的模型全部被健康检查标记为 unhealthy，或启用的 Key 全部在冷却中；返回不可用的供应商数。
+// 只读取缓存和内存中的轮询池，探针频繁调用也不会扫描模型表和 Key 表
+async fn check_providers(providers: &[String], reasons: &mut Vec<ReadinessReason>) -> usize {
+    let mut down = 0;
+    for provider in providers {
+        let names = get_active_model_names_from_cache(provider).await.unwrap_or_default();
+        let mut models_down = !names.is_empty();
+        for name in &names {
+            if get_model_health_from_cache(provider, name).await.as_deref() != Some(HEALTH_UNHEALTHY) {
+                models_down = false;
+                break;
+            }
+        }
+        let keys_down = get_active_key_count(provider).await == 0 && !get_cooling_down_keys(provider).await.is_empty();
+
+        if models_down {
+            reasons.push(ReadinessReason::new(
+                "provider_down", ReadinessLevel::Degraded, provider.clone(), "All active models are unhealthy",
+            ));
+        } else if keys_down {
+            reasons.push(ReadinessReason::new(
+                "provider_down", ReadinessLevel::Degraded, provider.clone(), "All active API keys are cooling down",
+            ));
+        } else {
+            continue;
+        }
+        down += 1;
+    }
+    down
+}
+
+/// 就绪探针：就绪返回 200，未就绪返回 503；降级默认返回 503，带 `allow_degraded=true` 时返回 200
+pub async fn readiness_check(Query(params): Query<ReadinessQuery>) -> (StatusCode, Json<Readiness>) {
+    let readiness = check_readiness().await;
+    let status = match readiness.status {
+        ReadinessLevel::Ready => StatusCode::OK,
+        ReadinessLevel::Degraded if params.allow_degraded.unwrap_or(false) => StatusCode::OK,
+        ReadinessLevel::Degraded | ReadinessLevel::Unready => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (status, Json(readiness))
+}
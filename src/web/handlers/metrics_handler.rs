@@ -0,0 +1,14 @@
+use axum::{
+    http::header,
+    response::IntoResponse,
+};
+
+use crate::metrics::metrics;
+
+/// 以 Prometheus 文本格式导出运行时指标
+pub async fn export_metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics().render_prometheus(),
+    )
+}
@@ -3,3 +3,25 @@ pub mod model_handler;
 pub mod health_handler;
 pub mod api_key_handler;
 pub mod call_log_handler;
+pub mod abuse_handler;
+pub mod blocklist_handler;
+pub mod metrics_handler;
+pub mod chat_completion_handler;
+pub mod completion_handler;
+pub mod degradation_handler;
+pub mod status_handler;
+pub mod prompt_cache_handler;
+pub mod usage_handler;
+pub mod db_stats_handler;
+pub mod consumer_handler;
+pub mod project_handler;
+pub mod conversation_handler;
+pub mod batch_handler;
+pub mod ws_chat_handler;
+pub mod ollama_handler;
+pub mod estimate_handler;
+pub mod shadow_handler;
+pub mod audit_handler;
+pub mod dashboard_handler;
+pub mod cache_handler;
+pub mod agent_handler;
@@ -4,71 +4,88 @@ use axum::{
     response::Json,
 };
 use serde_json::{json, Value};
-use std::collections::HashMap;
+use serde::Deserialize;
 use uuid::Uuid;
 
 use crate::dao::{
-    model::{Model, get_model_by_id, create_model, update_model, delete_model},
+    model::{Model, get_model_by_id, create_model, update_model, delete_model, sync_model_cache, list_models_page, count_models_filtered},
+    pagination::{clamp_limit, Cursor},
     provider::{get_provider_by_id},
     SQLITE_POOL,
 };
+use crate::dao::project::DEFAULT_PROJECT;
+use crate::llm_api::utils::param_constraints::parse_param_constraints;
+use crate::llm_api::utils::system_prompt_policy::parse_model_policy;
 use crate::web::dto::model_dto::*;
+use crate::web::dto::Page;
+use crate::web::handlers::project_handler::ensure_project_exists;
 
-/// 获取所有models
-pub async fn list_models(Query(params): Query<HashMap<String, String>>) -> Result<Json<Vec<ModelResponse>>, StatusCode> {
+#[derive(Debug, Deserialize)]
+pub struct ModelListQuery {
+    provider: Option<String>,
+    active: Option<bool>,
+    project_id: Option<String>,
+    cursor: Option<String>,
+    limit: Option<i64>,
+}
+
+/// 获取models（游标分页），可按provider、启用状态和所属项目过滤
+pub async fn list_models(Query(params): Query<ModelListQuery>) -> Result<Json<Page<ModelResponse, ModelListFilter>>, StatusCode> {
     let pool = SQLITE_POOL.get()
         .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
         .as_ref();
 
-    match crate::dao::model::list_models(pool).await {
-        Ok(models) => {
-            let mut responses = Vec::new();
-            
-            for model in models {
-                // 过滤条件
-                if let Some(provider_filter) = params.get("provider") {
-                    if model.provider != *provider_filter {
-                        continue;
-                    }
-                }
-                
-                if let Some(active_filter) = params.get("active") {
-                    if active_filter == "true" && !model.is_active {
-                        continue;
-                    }
-                    if active_filter == "false" && model.is_active {
-                        continue;
-                    }
-                }
+    let cursor = params.cursor.as_deref()
+        .map(|cursor| Cursor::decode(cursor).ok_or(StatusCode::BAD_REQUEST))
+        .transpose()?;
+    let limit = clamp_limit(params.limit);
+    let filter = ModelListFilter {
+        provider: params.provider,
+        active: params.active,
+        project_id: params.project_id,
+    };
 
-                // 获取provider显示名称
-                let provider_name = match get_provider_by_id(pool, &model.provider).await {
-                    Ok(Some(provider)) => provider.display_name,
-                    _ => model.provider.clone(), // 如果找不到provider，使用原始名称
-                };
-                
-                responses.push(ModelResponse {
-                    id: model.id,
-                    name: model.name,
-                    display_name: None, // TODO: 添加到Model结构体
-                    provider: model.provider,
-                    provider_name,
-                    model_type: model.model_type,
-                    base_url: model.base_url,
-                    is_active: model.is_active,
-                    health_status: model.health_status,
-                    last_health_check: model.last_health_check,
-                    cost_per_token_input: model.cost_per_token_input,
-                    cost_per_token_output: model.cost_per_token_output,
-                    created_at: model.created_at,
-                    updated_at: model.updated_at,
-                });
-            }
-            
-            Ok(Json(responses))
-        }
-        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    let total = count_models_filtered(pool, filter.provider.as_deref(), filter.active, filter.project_id.as_deref())
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let page = list_models_page(pool, filter.provider.as_deref(), filter.active, filter.project_id.as_deref(), cursor.as_ref(), limit)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut data = Vec::with_capacity(page.items.len());
+    for model in page.items {
+        // 获取provider显示名称
+        let provider_name = match get_provider_by_id(pool, &model.provider).await {
+            Ok(Some(provider)) => provider.display_name,
+            _ => model.provider.clone(), // 如果找不到provider，使用原始名称
+        };
+
+        data.push(ModelResponse {
+            id: model.id,
+            name: model.name,
+            display_name: None, // TODO: 添加到Model结构体
+            provider: model.provider,
+            provider_name,
+            model_type: model.model_type,
+            base_url: model.base_url,
+            is_active: model.is_active,
+            health_status: model.health_status,
+            last_health_check: model.last_health_check,
+            cost_per_token_input: model.cost_per_token_input,
+            cost_per_token_output: model.cost_per_token_output,
+            project_id: model.project_id.unwrap_or_else(|| DEFAULT_PROJECT.to_string()),
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+        });
     }
+
+    Ok(Json(Page {
+        data,
+        total,
+        limit,
+        next_cursor: page.next_cursor.map(|cursor| cursor.encode()),
+        filter,
+    }))
 }
 
 /// 获取单个model
@@ -98,6 +115,7 @@ pub async fn get_model(Path(id): Path<String>) -> Result<Json<ModelResponse>, St
                 last_health_check: model.last_health_check,
                 cost_per_token_input: model.cost_per_token_input,
                 cost_per_token_output: model.cost_per_token_output,
+                project_id: model.project_id.unwrap_or_else(|| DEFAULT_PROJECT.to_string()),
                 created_at: model.created_at,
                 updated_at: model.updated_at,
             }))
@@ -119,13 +137,19 @@ pub async fn create_new_model(
     if request.name.trim().is_empty() || request.provider_id.trim().is_empty() {
         return Err(StatusCode::BAD_REQUEST);
     }
+    // config 中的系统提示词策略和参数约束必须有效
+    validate_model_config(request.config.as_deref())?;
 
     // 验证provider存在
-    match get_provider_by_id(pool, &request.provider_id).await {
-        Ok(Some(_)) => {},
+    let provider = match get_provider_by_id(pool, &request.provider_id).await {
+        Ok(Some(provider)) => provider,
         Ok(None) => return Err(StatusCode::BAD_REQUEST), // Provider不存在
         Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
-    }
+    };
+
+    // 未指定项目时归属供应商所在的项目
+    ensure_project_exists(pool, request.project_id.as_deref()).await?;
+    let project_id = request.project_id.or(provider.project_id);
 
     // 生成ID
     let id = Uuid::new_v4().to_string();
@@ -149,12 +173,14 @@ pub async fn create_new_model(
         cost_per_token_output: Some(request.cost_per_token_output),
         function_tags: None,
         config: request.config,
+        project_id,
         created_at: None,
         updated_at: None,
     };
 
     match create_model(pool, &model).await {
         Ok(_) => {
+            sync_model_cache(pool, &model.provider, &model.name).await;
             Ok(Json(json!({
                 "id": id,
                 "message": "Model created successfully"
@@ -180,6 +206,9 @@ pub async fn update_existing_model(
         Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
     };
 
+    ensure_project_exists(pool, request.project_id.as_deref()).await?;
+    validate_model_config(request.config.as_deref())?;
+
     // 构建更新后的model
     let updated_model = Model {
         id: existing.id,
@@ -195,12 +224,14 @@ pub async fn update_existing_model(
         cost_per_token_output: request.cost_per_token_output.or(existing.cost_per_token_output),
         function_tags: existing.function_tags,
         config: request.config.or(existing.config),
+        project_id: request.project_id.or(existing.project_id),
         created_at: existing.created_at,
         updated_at: None, // 数据库会自动更新
     };
 
     match update_model(pool, &updated_model).await {
         Ok(rows) if rows > 0 => {
+            sync_model_cache(pool, &updated_model.provider, &updated_model.name).await;
             Ok(Json(json!({
                 "message": "Model updated successfully"
             })))
@@ -216,8 +247,16 @@ pub async fn delete_existing_model(Path(id): Path<String>) -> Result<Json<Value>
         .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
         .as_ref();
 
+    // 删除前记录所属供应商和模型名，用于刷新缓存
+    let model = match get_model_by_id(pool, &id).await {
+        Ok(Some(model)) => model,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
     match delete_model(pool, &id).await {
         Ok(rows) if rows > 0 => {
+            sync_model_cache(pool, &model.provider, &model.name).await;
             Ok(Json(json!({
                 "message": "Model deleted successfully"
             })))
@@ -317,3 +356,10 @@ pub async fn get_model_templates(Path(provider): Path<String>) -> Result<Json<Mo
         templates,
     }))
 }
+
+// 校验 config 中由网关解析的配置项
+fn validate_model_config(config: Option<&str>) -> Result<(), StatusCode> {
+    parse_model_policy(config).map_err(|_| StatusCode::BAD_REQUEST)?;
+    parse_param_constraints(config).map_err(|_| StatusCode::BAD_REQUEST)?;
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::Path,
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Json,
+    },
+};
+use futures_util::stream::{self, Stream};
+use serde_json::json;
+use tokio::sync::mpsc;
+use tracing::{error, info};
+
+use crate::config::gateway_config;
+use crate::dao::{provider::get_provider_by_name, SQLITE_POOL};
+use crate::llm_api::ollama::client::{OllamaClient, OllamaError};
+use crate::web::dto::ollama_dto::*;
+
+/// Ollama 地址：优先使用 providers 表中 ollama 的 base_url，未配置时使用启动配置
+async fn ollama_base_url() -> String {
+    if let Some(pool) = SQLITE_POOL.get()
+        && let Ok(Some(provider)) = get_provider_by_name(pool, "ollama").await
+        && let Some(base_url) = provider.base_url.filter(|url| !url.trim().is_empty())
+    {
+        return base_url;
+    }
+    gateway_config().providers.ollama_base_url.clone()
+}
+
+async fn ollama_client() -> Result<(OllamaClient, String), StatusCode> {
+    let base_url = ollama_base_url().await;
+    let client = OllamaClient::new(base_url.clone()).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok((client, base_url))
+}
+
+fn status_for(error: &OllamaError) -> StatusCode {
+    match error {
+        OllamaError::ModelNotFound(_) => StatusCode::NOT_FOUND,
+        OllamaError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+        _ => StatusCode::BAD_GATEWAY,
+    }
+}
+
+/// 列出 Ollama 本地模型
+pub async fn list_ollama_models() -> Result<Json<OllamaModelListResponse>, StatusCode> {
+    let (client, base_url) = ollama_client().await?;
+    let models = client.list_models().await.map_err(|e| {
+        error!(base_url = %base_url, "Failed to list Ollama models: {}", e);
+        status_for(&e)
+    })?;
+    Ok(Json(OllamaModelListResponse { base_url, models }))
+}
+
+/// 查看模型详情
+pub async fn get_ollama_model(Path(name): Path<String>) -> Result<Json<OllamaModelInfo>, StatusCode> {
+    let (client, _) = ollama_client().await?;
+    client.show_model(&name).await
+        .map(Json)
+        .map_err(|e| status_for(&e))
+}
+
+/// 删除本地模型
+pub async fn delete_ollama_model(Path(name): Path<String>) -> Result<StatusCode, StatusCode> {
+    let (client, base_url) = ollama_client().await?;
+    client.delete_model(&name).await.map_err(|e| {
+        error!(base_url = %base_url, model = %name, "Failed to delete Ollama model: {}", e);
+        status_for(&e)
+    })?;
+    info!(base_url = %base_url, model = %name, "Deleted Ollama model");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 拉取模型，以 SSE 返回下载进度，出错时发送 `error` 事件后结束
+///
+/// 断开连接不会中止 Ollama 上的下载
+pub async fn pull_ollama_model(
+    Json(request): Json<PullOllamaModelRequest>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, StatusCode> {
+    let name = request.name.trim().to_string();
+    if name.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let (client, base_url) = ollama_client().await?;
+    info!(base_url = %base_url, model = %name, "Pulling Ollama model");
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let result = client.pull_model(&name, |progress| {
+            tx.send(Event::default().json_data(&progress).unwrap_or_default()).is_ok()
+        }).await;
+        if let Err(e) = result {
+            error!(model = %name, "Failed to pull Ollama model: {}", e);
+            let event = Event::default().event("error").json_data(json!({ "message": e.to_string() }));
+            tx.send(event.unwrap_or_default()).ok();
+        }
+    });
+
+    let events = stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|event| (Ok(event), rx))
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
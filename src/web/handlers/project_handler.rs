@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+use axum::{
+    extract::Path,
+    http::StatusCode,
+    response::Json,
+};
+use sqlx::SqlitePool;
+
+use crate::dao::{
+    project::{
+        Project, ProjectGatewayKey, DEFAULT_PROJECT, create_project, get_project_by_id, list_projects, update_project,
+        delete_project, count_project_resources, bind_gateway_key, unbind_gateway_key, list_gateway_keys_by_project,
+        list_gateway_key_bindings,
+    },
+    SQLITE_POOL,
+};
+use crate::llm_api::utils::consumer_quota::consumer_id;
+use crate::llm_api::utils::project_scope::reload_project_bindings;
+use crate::llm_api::utils::system_prompt_policy::{get_project_policy, set_project_policy, SystemPromptPolicy};
+use crate::web::dto::project_dto::*;
+
+/// 获取所有项目，default 项目排在最前
+pub async fn list_all_projects() -> Result<Json<Vec<ProjectResponse>>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    let projects = list_projects(pool).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut key_counts: HashMap<String, usize> = HashMap::new();
+    for binding in list_gateway_key_bindings(pool).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)? {
+        *key_counts.entry(binding.project_id).or_default() += 1;
+    }
+
+    Ok(Json(projects.into_iter()
+        .map(|project| {
+            let gateway_key_count = key_counts.get(&project.id).copied().unwrap_or(0);
+            to_response(project, gateway_key_count)
+        })
+        .collect()))
+}
+
+/// 获取单个项目
+pub async fn get_project(Path(id): Path<String>) -> Result<Json<ProjectResponse>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    let project = find_project(pool, &id).await?;
+    let keys = list_gateway_keys_by_project(pool, &id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(to_response(project, keys.len())))
+}
+
+/// 创建项目，ID 已存在时返回 409
+pub async fn create_new_project(
+    Json(request): Json<CreateProjectRequest>,
+) -> Result<Json<ProjectResponse>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    let id = request.id.trim();
+    if !is_valid_project_id(id) || request.name.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if get_project_by_id(pool, id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?.is_some() {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let project = Project {
+        id: id.to_string(),
+        name: request.name.trim().to_string(),
+        description: request.description,
+        is_active: true,
+        created_at: None, // 数据库会自动设置
+        updated_at: None,
+    };
+    create_project(pool, &project).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let created = find_project(pool, id).await?;
+    Ok(Json(to_response(created, 0)))
+}
+
+/// 更新项目，启停立即对绑定的网关 Key 生效
+pub async fn update_existing_project(
+    Path(id): Path<String>,
+    Json(request): Json<UpdateProjectRequest>,
+) -> Result<Json<ProjectResponse>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    let existing = find_project(pool, &id).await?;
+    let updated = Project {
+        name: request.name.map(|name| name.trim().to_string()).unwrap_or(existing.name),
+        description: request.description.or(existing.description),
+        is_active: request.is_active.unwrap_or(existing.is_active),
+        ..existing
+    };
+    if updated.name.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    update_project(pool, &updated).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    reload_bindings(pool).await;
+
+    get_project(Path(id)).await
+}
+
+/// 删除项目及其网关 Key 绑定；default 项目不能删除，仍有供应商、模型或 API Key 归属的项目返回 409
+pub async fn delete_existing_project(Path(id): Path<String>) -> StatusCode {
+    let Some(pool) = SQLITE_POOL.get() else {
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    };
+
+    if id == DEFAULT_PROJECT {
+        return StatusCode::BAD_REQUEST;
+    }
+    match count_project_resources(pool, &id).await {
+        Ok(0) => {}
+        Ok(_) => return StatusCode::CONFLICT,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR,
+    }
+
+    match delete_project(pool, &id).await {
+        Ok(rows) if rows > 0 => {
+            reload_bindings(pool).await;
+            StatusCode::NO_CONTENT
+        }
+        Ok(_) => StatusCode::NOT_FOUND,
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// 列出绑定到项目的网关 Key
+pub async fn list_project_gateway_keys(Path(id): Path<String>) -> Result<Json<Vec<GatewayKeyBindingResponse>>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    find_project(pool, &id).await?;
+    match list_gateway_keys_by_project(pool, &id).await {
+        Ok(keys) => Ok(Json(keys.into_iter().map(to_binding_response).collect())),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// 把网关 Key 绑定到项目（已绑定到其他项目时改绑），之后该 Key 的请求只使用项目内的资源
+pub async fn bind_project_gateway_key(
+    Path(id): Path<String>,
+    Json(request): Json<BindGatewayKeyRequest>,
+) -> Result<Json<GatewayKeyBindingResponse>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    let consumer = match (request.api_key.as_deref().map(str::trim), request.consumer_id.as_deref().map(str::trim)) {
+        (Some(api_key), None) if !api_key.is_empty() => consumer_id(api_key),
+        (None, Some(consumer)) if !consumer.is_empty() => consumer.to_string(),
+        _ => return Err(StatusCode::BAD_REQUEST),
+    };
+    find_project(pool, &id).await?;
+
+    bind_gateway_key(pool, &consumer, &id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    reload_bindings(pool).await;
+
+    let keys = list_gateway_keys_by_project(pool, &id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    keys.into_iter()
+        .find(|key| key.consumer_id == consumer)
+        .map(|key| Json(to_binding_response(key)))
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// 解除网关 Key 与项目的绑定，之后该 Key 属于 default 项目
+pub async fn unbind_project_gateway_key(Path((id, consumer_id)): Path<(String, String)>) -> StatusCode {
+    let Some(pool) = SQLITE_POOL.get() else {
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    };
+
+    match unbind_gateway_key(pool, &id, &consumer_id).await {
+        Ok(rows) if rows > 0 => {
+            reload_bindings(pool).await;
+            StatusCode::NO_CONTENT
+        }
+        Ok(_) => StatusCode::NOT_FOUND,
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// 查询项目的系统提示词策略，未配置时返回 404
+pub async fn get_project_system_prompt_policy(Path(id): Path<String>) -> Result<Json<SystemPromptPolicy>, StatusCode> {
+    get_project_policy(&id).map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// 设置项目的系统提示词策略，立即对项目内所有未单独配置策略的模型生效
+pub async fn update_project_system_prompt_policy(
+    Path(id): Path<String>,
+    Json(policy): Json<SystemPromptPolicy>,
+) -> Result<Json<SystemPromptPolicy>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    policy.validate().map_err(|_| StatusCode::BAD_REQUEST)?;
+    find_project(pool, &id).await?;
+    set_project_policy(pool, &id, Some(&policy)).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(policy))
+}
+
+/// 删除项目的系统提示词策略
+pub async fn delete_project_system_prompt_policy(Path(id): Path<String>) -> StatusCode {
+    let Some(pool) = SQLITE_POOL.get() else {
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    };
+
+    if get_project_policy(&id).is_none() {
+        return StatusCode::NOT_FOUND;
+    }
+    match set_project_policy(pool, &id, None).await {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// 校验请求中指定的项目存在，供创建供应商、模型和 API Key 时使用
+pub async fn ensure_project_exists(pool: &SqlitePool, project_id: Option<&str>) -> Result<(), StatusCode> {
+    match project_id {
+        Some(project_id) => find_project(pool, project_id).await.map(|_| ()).map_err(|status| match status {
+            StatusCode::NOT_FOUND => StatusCode::BAD_REQUEST,
+            status => status,
+        }),
+        None => Ok(()),
+    }
+}
+
+async fn find_project(pool: &SqlitePool, id: &str) -> Result<Project, StatusCode> {
+    match get_project_by_id(pool, id).await {
+        Ok(Some(project)) => Ok(project),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+// 重新加载内存中的绑定关系，失败时只记录日志（数据库已更新，下次加载时生效）
+async fn reload_bindings(pool: &SqlitePool) {
+    if let Err(e) = reload_project_bindings(pool).await {
+        tracing::error!("Failed to reload project bindings: {:?}", e);
+    }
+}
+
+fn is_valid_project_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 64 && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn to_response(project: Project, gateway_key_count: usize) -> ProjectResponse {
+    ProjectResponse {
+        id: project.id,
+        name: project.name,
+        description: project.description,
+        is_active: project.is_active,
+        gateway_key_count,
+        created_at: project.created_at.unwrap_or_default(),
+    }
+}
+
+fn to_binding_response(key: ProjectGatewayKey) -> GatewayKeyBindingResponse {
+    GatewayKeyBindingResponse {
+        consumer_id: key.consumer_id,
+        project_id: key.project_id,
+        created_at: key.created_at.unwrap_or_default(),
+    }
+}
@@ -0,0 +1,62 @@
+use std::time::Duration;
+use axum::{
+    extract::Path,
+    http::StatusCode,
+    response::Json,
+};
+use tracing::warn;
+
+use crate::jobs::prompt_cache_warmup::{
+    cancel_prompt_cache_warmup, get_prompt_cache_warmup, list_prompt_cache_warmups, start_prompt_cache_warmup,
+    supports_prompt_cache, PromptCacheWarmup, MIN_WARMUP_INTERVAL,
+};
+use crate::llm_api::dispatcher::{Provider, GLOBAL_DISPATCHER};
+use crate::web::dto::prompt_cache_dto::PromptCacheWarmupRequest;
+
+/// 预热供应商的提示词缓存，指定 `interval_secs` 时登记定期预热任务
+pub async fn warmup_prompt_cache(
+    Json(request): Json<PromptCacheWarmupRequest>,
+) -> Result<(StatusCode, Json<PromptCacheWarmup>), StatusCode> {
+    let dispatcher = GLOBAL_DISPATCHER.get()
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?
+        .clone();
+
+    let provider = Provider::from_name_or_custom(&request.provider);
+    if !supports_prompt_cache(&provider) || request.model.trim().is_empty() || request.system_prompt.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let interval = request.interval_secs.map(Duration::from_secs);
+    if interval.is_some_and(|interval| interval < MIN_WARMUP_INTERVAL) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let warmup = start_prompt_cache_warmup(dispatcher, provider, request.model, request.system_prompt, interval)
+        .await
+        .map_err(|e| {
+            warn!(provider = %request.provider, error = %e, "Prompt cache warmup failed");
+            StatusCode::BAD_GATEWAY
+        })?;
+    let status = if warmup.interval_secs.is_some() { StatusCode::CREATED } else { StatusCode::OK };
+    Ok((status, Json(warmup)))
+}
+
+/// 列出定期预热任务
+pub async fn list_prompt_cache_warmup_tasks() -> Json<Vec<PromptCacheWarmup>> {
+    Json(list_prompt_cache_warmups())
+}
+
+/// 查询定期预热任务
+pub async fn get_prompt_cache_warmup_task(Path(id): Path<String>) -> Result<Json<PromptCacheWarmup>, StatusCode> {
+    get_prompt_cache_warmup(&id)
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// 停止定期预热任务
+pub async fn delete_prompt_cache_warmup_task(Path(id): Path<String>) -> StatusCode {
+    if cancel_prompt_cache_warmup(&id) {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
@@ -1,51 +1,86 @@
 use axum::{
-    extract::Path,
+    extract::{Path, Query},
     http::StatusCode,
     response::Json,
 };
+use serde::Deserialize;
 use serde_json::{json, Value};
 use uuid::Uuid;
 use sqlx::SqlitePool;
 
 use crate::dao::{
-    provider::{Provider, get_all_providers, get_provider_by_id, create_provider, update_provider, hard_delete_provider, count_models_for_provider},
-    provider_key_pool::{ProviderKeyPool, create_provider_key_pool},
+    pagination::{clamp_limit, Cursor},
+    provider::{Provider, get_all_providers, get_provider_by_id, list_providers_page, count_providers, create_provider, update_provider, hard_delete_provider, count_models_for_provider},
+    provider_key_pool::{ProviderKeyPool, create_provider_key_pool, sync_provider_key_pool_cache},
     SQLITE_POOL,
 };
+use crate::dao::project::DEFAULT_PROJECT;
 use crate::dao::provider_key_pool::crypto::process_api_key;
+use crate::llm_api::dispatcher::reload_provider_adapters;
 use crate::web::dto::provider_dto::*;
+use crate::web::dto::Page;
+use crate::web::handlers::project_handler::ensure_project_exists;
 
-/// 获取所有providers
-pub async fn list_providers() -> Result<Json<Vec<ProviderResponse>>, StatusCode> {
+#[derive(Debug, Deserialize)]
+pub struct ProviderListQuery {
+    active: Option<bool>,
+    project_id: Option<String>,
+    cursor: Option<String>,
+    limit: Option<i64>,
+}
+
+/// 获取providers（游标分页），可按启用状态和所属项目过滤
+pub async fn list_providers(Query(params): Query<ProviderListQuery>) -> Result<Json<Page<ProviderResponse, ProviderListFilter>>, StatusCode> {
     let pool = SQLITE_POOL.get()
         .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
         .as_ref();
 
-    match get_all_providers(pool).await {
-        Ok(providers) => {
-            let mut responses = Vec::new();
-            
-            for provider in providers {
-                let model_count = count_models_for_provider(pool, &provider.id)
-                    .await
-                    .unwrap_or(0) as usize;
-                    
-                responses.push(ProviderResponse {
-                    id: provider.id,
-                    name: provider.name,
-                    display_name: provider.display_name,
-                    base_url: provider.base_url,
-                    description: provider.description,
-                    is_active: provider.is_active,
-                    model_count,
-                    created_at: provider.created_at.unwrap_or_default(),
-                });
-            }
-            
-            Ok(Json(responses))
-        }
-        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    let cursor = params.cursor.as_deref()
+        .map(|cursor| Cursor::decode(cursor).ok_or(StatusCode::BAD_REQUEST))
+        .transpose()?;
+    let limit = clamp_limit(params.limit);
+    let filter = ProviderListFilter { active: params.active, project_id: params.project_id };
+
+    let total = count_providers(pool, filter.active, filter.project_id.as_deref())
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let page = list_providers_page(pool, filter.active, filter.project_id.as_deref(), cursor.as_ref(), limit)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut data = Vec::with_capacity(page.items.len());
+    for provider in page.items {
+        let model_count = count_models_for_provider(pool, &provider.id)
+            .await
+            .unwrap_or(0) as usize;
+
+        data.push(ProviderResponse {
+            id: provider.id,
+            name: provider.name,
+            display_name: provider.display_name,
+            base_url: provider.base_url,
+            description: provider.description,
+            config: parse_provider_config(provider.config.as_deref()),
+            is_active: provider.is_active,
+            model_count,
+            project_id: provider.project_id.unwrap_or_else(|| DEFAULT_PROJECT.to_string()),
+            max_concurrent_requests: provider.max_concurrent_requests,
+            created_at: provider.created_at.unwrap_or_default(),
+        });
     }
+
+    Ok(Json(Page {
+        data,
+        total,
+        limit,
+        next_cursor: page.next_cursor.map(|cursor| cursor.encode()),
+        filter,
+    }))
+}
+
+// 解析数据库中的JSON配置，无效时忽略
+fn parse_provider_config(config: Option<&str>) -> Option<Value> {
+    config.and_then(|config| serde_json::from_str(config).ok())
 }
 
 /// 获取单个provider
@@ -66,8 +101,11 @@ pub async fn get_provider(Path(id): Path<String>) -> Result<Json<ProviderRespons
                 display_name: provider.display_name,
                 base_url: provider.base_url,
                 description: provider.description,
+                config: parse_provider_config(provider.config.as_deref()),
                 is_active: provider.is_active,
                 model_count,
+                project_id: provider.project_id.unwrap_or_else(|| DEFAULT_PROJECT.to_string()),
+                max_concurrent_requests: provider.max_concurrent_requests,
                 created_at: provider.created_at.unwrap_or_default(),
             }))
         }
@@ -88,6 +126,7 @@ pub async fn create_new_provider(
     if request.name.trim().is_empty() || request.display_name.trim().is_empty() {
         return Err(StatusCode::BAD_REQUEST);
     }
+    ensure_project_exists(pool, request.project_id.as_deref()).await?;
 
     // 生成ID
     let id = Uuid::new_v4().to_string();
@@ -98,7 +137,10 @@ pub async fn create_new_provider(
         display_name: request.display_name.trim().to_string(),
         base_url: request.base_url,
         description: request.description,
+        config: request.config.map(|config| config.to_string()),
         is_active: true,
+        project_id: request.project_id,
+        max_concurrent_requests: request.max_concurrent_requests.filter(|limit| *limit > 0),
         created_at: None, // 数据库会自动设置
         updated_at: None,
     };
@@ -108,7 +150,7 @@ pub async fn create_new_provider(
             // 如果提供了API Key，则添加到key pool
             if let Some(api_key) = request.api_key {
                 if !api_key.trim().is_empty() {
-                    match add_api_key_to_pool(pool, &provider.name, &api_key).await {
+                    match add_api_key_to_pool(pool, &provider.name, provider.project_id.clone(), &api_key).await {
                         Ok(_) => {},
                         Err(e) => {
                             tracing::error!("Failed to add API key to pool: {:?}", e);
@@ -118,6 +160,8 @@ pub async fn create_new_provider(
                 }
             }
             
+            refresh_dispatcher(pool).await;
+
             Ok(Json(json!({
                 "id": id,
                 "message": "Provider created successfully"
@@ -143,8 +187,11 @@ pub async fn update_existing_provider(
         Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
     };
 
-    // 保存provider名称用于后续API key操作
+    ensure_project_exists(pool, request.project_id.as_deref()).await?;
+
+    // 保存provider名称和项目用于后续API key操作
     let provider_name = existing.name.clone();
+    let project_id = request.project_id.or(existing.project_id);
     
     // 构建更新后的provider
     let updated_provider = Provider {
@@ -153,7 +200,13 @@ pub async fn update_existing_provider(
         display_name: request.display_name.unwrap_or(existing.display_name),
         base_url: request.base_url.or(existing.base_url),
         description: request.description.or(existing.description),
+        config: request.config.map(|config| config.to_string()).or(existing.config),
         is_active: request.is_active.unwrap_or(existing.is_active),
+        project_id: project_id.clone(),
+        max_concurrent_requests: match request.max_concurrent_requests {
+            Some(limit) => Some(limit).filter(|limit| *limit > 0),
+            None => existing.max_concurrent_requests,
+        },
         created_at: existing.created_at,
         updated_at: None, // 数据库会自动更新
     };
@@ -163,7 +216,7 @@ pub async fn update_existing_provider(
             // 如果提供了新的API Key，则添加到key pool
             if let Some(api_key) = request.api_key {
                 if !api_key.trim().is_empty() {
-                    match add_api_key_to_pool(pool, &provider_name, &api_key).await {
+                    match add_api_key_to_pool(pool, &provider_name, project_id, &api_key).await {
                         Ok(_) => {},
                         Err(e) => {
                             tracing::error!("Failed to add API key to pool: {:?}", e);
@@ -173,6 +226,8 @@ pub async fn update_existing_provider(
                 }
             }
             
+            refresh_dispatcher(pool).await;
+
             Ok(Json(json!({
                 "message": "Provider updated successfully"
             })))
@@ -207,6 +262,7 @@ pub async fn delete_existing_provider(Path(id): Path<String>) -> Result<Json<Val
             // No models, safe to delete
             match hard_delete_provider(pool, &id).await {
                 Ok(rows) if rows > 0 => {
+                    refresh_dispatcher(pool).await;
                     Ok(Json(json!({
                         "message": "Provider deleted successfully"
                     })))
@@ -219,10 +275,18 @@ pub async fn delete_existing_provider(Path(id): Path<String>) -> Result<Json<Val
     }
 }
 
-/// 添加API Key到provider key pool的辅助函数
+/// provider变更后重新注册dispatcher适配器，失败只记录日志
+async fn refresh_dispatcher(pool: &SqlitePool) {
+    if let Err(e) = reload_provider_adapters(pool).await {
+        tracing::error!("Failed to reload provider adapters: {:?}", e);
+    }
+}
+
+/// 添加API Key到provider key pool的辅助函数，Key 归属供应商所在的项目
 async fn add_api_key_to_pool(
     pool: &SqlitePool,
     provider_name: &str,
+    project_id: Option<String>,
     api_key: &str,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // 处理API密钥（哈希和加密）
@@ -234,7 +298,7 @@ async fn add_api_key_to_pool(
     
     // 创建ProviderKeyPool实例
     let key_pool = ProviderKeyPool {
-        id: key_id,
+        id: key_id.clone(),
         provider: provider_name.to_string(),
         key_hash,
         encrypted_key_value,
@@ -243,6 +307,7 @@ async fn add_api_key_to_pool(
         last_used_at: None,
         rate_limit_per_minute: None,
         rate_limit_per_hour: None,
+        project_id,
         created_at: None, // 数据库会自动设置
     };
     
@@ -250,6 +315,11 @@ async fn add_api_key_to_pool(
     create_provider_key_pool(pool, &key_pool)
         .await
         .map_err(|e| format!("Failed to save API key: {}", e))?;
+
+    // 新 Key 立即加入缓存和轮询池
+    sync_provider_key_pool_cache(pool, provider_name, &key_id)
+        .await
+        .map_err(|e| format!("Failed to sync API key to cache: {}", e))?;
     
     Ok(())
 }
@@ -0,0 +1,24 @@
+use axum::{
+    extract::Query,
+    http::StatusCode,
+    response::Json,
+};
+
+use crate::dao::{
+    shadow_comparison::{list_shadow_comparisons, ShadowComparison, ShadowComparisonFilter},
+    SQLITE_POOL,
+};
+
+/// 查询影子流量的对比记录（主请求与镜像请求的响应和耗时），按时间倒序
+pub async fn get_shadow_comparisons(
+    Query(filter): Query<ShadowComparisonFilter>,
+) -> Result<Json<Vec<ShadowComparison>>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    match list_shadow_comparisons(pool, &filter).await {
+        Ok(comparisons) => Ok(Json(comparisons)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
@@ -0,0 +1,299 @@
+//! # 网关状态总览
+//!
+//! `/api/status` 一次返回排查问题时最先需要的信息：各供应商健康状况、打开的熔断（降级模式、
+//! 不健康模型、冷却中的 Key）、Key 池数量、排队情况、缓存命中率、最近的错误率和版本信息
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use axum::{
+    extract::Extension,
+    http::StatusCode,
+    response::Json,
+};
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+use crate::dao::{
+    cache::{cache::{cache_hit_stats, CacheHitStats}, GLOBAL_CACHE_NAME},
+    call_log::{get_call_logs_stats_by_provider_since, ProviderCallStats},
+    model::{list_models, HEALTH_HEALTHY, HEALTH_UNHEALTHY},
+    provider::get_all_providers,
+    provider_key_pool::{get_active_key_count, get_cooling_down_keys, list_provider_key_pools},
+    SQLITE_POOL,
+};
+use crate::jobs::call_log_archive::{list_archive_tasks, ArchiveStatus};
+use crate::llm_api::dispatcher::GLOBAL_DISPATCHER;
+use crate::llm_api::utils::admission::AdmissionQueue;
+use crate::llm_api::utils::client_pool::{get_ali_client_pool, ClientPoolStats};
+use crate::llm_api::utils::concurrency_limit::ProviderConcurrencyStats;
+use crate::llm_api::utils::degradation::{get_degradation_guard, DegradationStatus, RESPONSE_CACHE_NAME};
+
+/// 统计错误率的时间窗口（分钟）
+pub const RECENT_ERROR_WINDOW_MINUTES: i64 = 15;
+
+/// 网关状态总览
+#[derive(Debug, Serialize)]
+pub struct GatewayStatus {
+    /// ok：一切正常；degraded：处于降级模式或有供应商不健康
+    pub status: &'static str,
+    pub timestamp: String,
+    pub build: BuildInfo,
+    pub providers: Vec<ProviderStatus>,
+    pub open_circuits: OpenCircuits,
+    pub queues: QueueStatus,
+    pub caches: BTreeMap<&'static str, CacheHitStats>,
+    pub recent_errors: ErrorRate,
+}
+
+/// 版本和构建信息，构建时可通过 `GIT_COMMIT` / `BUILD_TIME` 环境变量写入
+#[derive(Debug, Serialize)]
+pub struct BuildInfo {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub git_commit: Option<&'static str>,
+    pub build_time: Option<&'static str>,
+}
+
+/// 单个供应商的状态
+#[derive(Debug, Serialize)]
+pub struct ProviderStatus {
+    pub provider: String,
+    /// 网关是否已注册该供应商的适配器
+    pub registered: bool,
+    /// healthy / degraded / unhealthy / unknown，按启用模型的健康检查结果汇总
+    pub health: &'static str,
+    pub models: ModelHealthCounts,
+    pub keys: KeyPoolCounts,
+    pub recent_errors: ErrorRate,
+}
+
+/// 启用模型的健康检查结果
+#[derive(Debug, Default, Serialize)]
+pub struct ModelHealthCounts {
+    pub healthy: usize,
+    pub unhealthy: usize,
+    pub unknown: usize,
+}
+
+/// Key 池数量
+#[derive(Debug, Default, Serialize)]
+pub struct KeyPoolCounts {
+    pub total: usize,
+    pub active: usize,
+    /// 当前参与轮询的 Key
+    pub in_rotation: usize,
+    /// 因限流等原因暂时移出轮询的 Key
+    pub cooling_down: usize,
+}
+
+/// 打开的熔断
+#[derive(Debug, Serialize)]
+pub struct OpenCircuits {
+    pub degradation: DegradationStatus,
+    /// 健康检查判定为不健康、调度时跳过的模型（provider/model）
+    pub unhealthy_models: Vec<String>,
+    pub cooling_down_keys: Vec<CoolingDownKey>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CoolingDownKey {
+    pub provider: String,
+    pub key_id: String,
+}
+
+/// 排队情况
+#[derive(Debug, Serialize)]
+pub struct QueueStatus {
+    /// 全局准入队列（未启用准入控制时为 null）
+    pub admission: Option<AdmissionQueueStatus>,
+    /// 设置了并发上限的供应商的槽位占用和排队请求数
+    pub providers: Vec<ProviderConcurrencyStats>,
+    /// 全局阿里云客户端池（未初始化时为 null）
+    pub ali_client_pool: Option<ClientPoolStats>,
+    /// 正在运行的调用日志归档/批量删除任务
+    pub running_archive_tasks: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AdmissionQueueStatus {
+    /// 正在处理的请求数
+    pub in_flight: usize,
+    /// 正在排队的请求数
+    pub queue_depth: usize,
+}
+
+/// 最近一段时间的错误率
+#[derive(Debug, Default, Serialize)]
+pub struct ErrorRate {
+    pub window_minutes: i64,
+    pub total_calls: i64,
+    pub error_count: i64,
+    /// 没有调用时为 None
+    pub error_rate: Option<f64>,
+}
+
+impl ErrorRate {
+    fn new(total_calls: i64, error_count: i64) -> Self {
+        Self {
+            window_minutes: RECENT_ERROR_WINDOW_MINUTES,
+            total_calls,
+            error_count,
+            error_rate: (total_calls > 0).then(|| error_count as f64 / total_calls as f64),
+        }
+    }
+}
+
+impl ModelHealthCounts {
+    fn summary(&self) -> &'static str {
+        match (self.healthy, self.unhealthy) {
+            (0, 0) => "unknown",
+            (_, 0) => "healthy",
+            (0, _) if self.unknown == 0 => "unhealthy",
+            _ => "degraded",
+        }
+    }
+}
+
+fn build_info() -> BuildInfo {
+    BuildInfo {
+        name: env!("CARGO_PKG_NAME"),
+        version: env!("CARGO_PKG_VERSION"),
+        git_commit: option_env!("GIT_COMMIT"),
+        build_time: option_env!("BUILD_TIME"),
+    }
+}
+
+/// 各供应商的状态，以及其中打开的熔断
+pub(crate) struct ProviderReport {
+    pub providers: Vec<ProviderStatus>,
+    pub unhealthy_models: Vec<String>,
+    pub cooling_down_keys: Vec<CoolingDownKey>,
+}
+
+/// 汇总各供应商的模型健康状况、Key 池数量和最近的错误率（`call_stats` 为最近一段时间按供应商的调用统计）
+pub(crate) async fn provider_report(pool: &SqlitePool, call_stats: &[ProviderCallStats]) -> Result<ProviderReport, StatusCode> {
+    let db_providers = get_all_providers(pool).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let models = list_models(pool).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let key_pools = list_provider_key_pools(pool).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let registered: BTreeSet<String> = match GLOBAL_DISPATCHER.get() {
+        Some(dispatcher) => dispatcher.registered_providers().await
+            .iter()
+            .map(|provider| provider.as_str().to_string())
+            .collect(),
+        None => BTreeSet::new(),
+    };
+
+    // 供应商表中启用的、有模型或 Key 的、以及已注册适配器的供应商
+    let names: BTreeSet<String> = db_providers.iter()
+        .filter(|provider| provider.is_active)
+        .map(|provider| provider.name.clone())
+        .chain(models.iter().map(|model| model.provider.clone()))
+        .chain(key_pools.iter().map(|key_pool| key_pool.provider.clone()))
+        .chain(registered.iter().cloned())
+        .collect();
+
+    let mut providers = Vec::with_capacity(names.len());
+    let mut unhealthy_models = Vec::new();
+    let mut cooling_down_keys = Vec::new();
+    for name in names {
+        let mut model_health = ModelHealthCounts::default();
+        for model in models.iter().filter(|model| model.provider == name && model.is_active) {
+            match model.health_status.as_deref() {
+                Some(HEALTH_HEALTHY) => model_health.healthy += 1,
+                Some(HEALTH_UNHEALTHY) => {
+                    model_health.unhealthy += 1;
+                    unhealthy_models.push(format!("{}/{}", model.provider, model.name));
+                }
+                _ => model_health.unknown += 1,
+            }
+        }
+
+        let cooling_down = get_cooling_down_keys(&name).await;
+        let keys = KeyPoolCounts {
+            total: key_pools.iter().filter(|key_pool| key_pool.provider == name).count(),
+            active: key_pools.iter().filter(|key_pool| key_pool.provider == name && key_pool.is_active).count(),
+            in_rotation: get_active_key_count(&name).await,
+            cooling_down: cooling_down.len(),
+        };
+        cooling_down_keys.extend(cooling_down.into_iter().map(|key_id| CoolingDownKey { provider: name.clone(), key_id }));
+
+        let recent_errors = call_stats.iter()
+            .find(|stats| stats.provider.as_deref() == Some(name.as_str()))
+            .map(|stats| ErrorRate::new(stats.total_calls, stats.error_count))
+            .unwrap_or_else(|| ErrorRate::new(0, 0));
+
+        providers.push(ProviderStatus {
+            registered: registered.contains(&name),
+            provider: name,
+            health: model_health.summary(),
+            models: model_health,
+            keys,
+            recent_errors,
+        });
+    }
+
+    Ok(ProviderReport { providers, unhealthy_models, cooling_down_keys })
+}
+
+/// 最近 [`RECENT_ERROR_WINDOW_MINUTES`] 分钟按供应商的调用统计
+pub(crate) async fn recent_call_stats(pool: &SqlitePool) -> Result<Vec<ProviderCallStats>, StatusCode> {
+    let since = (chrono::Utc::now() - chrono::Duration::minutes(RECENT_ERROR_WINDOW_MINUTES))
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string();
+    get_call_logs_stats_by_provider_since(pool, &since)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// 获取网关状态总览，路由上挂载了准入队列（`Extension<Arc<AdmissionQueue>>`）时一并返回其排队情况
+pub async fn get_gateway_status(
+    admission: Option<Extension<Arc<AdmissionQueue>>>,
+) -> Result<Json<GatewayStatus>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    let call_stats = recent_call_stats(pool).await?;
+    let ProviderReport { providers, unhealthy_models, cooling_down_keys } = provider_report(pool, &call_stats).await?;
+
+    let degradation = get_degradation_guard().status().await;
+    let status = if degradation.active || providers.iter().any(|p| matches!(p.health, "unhealthy" | "degraded")) {
+        "degraded"
+    } else {
+        "ok"
+    };
+
+    let queues = QueueStatus {
+        admission: admission
+            .filter(|Extension(queue)| queue.is_enabled())
+            .map(|Extension(queue)| AdmissionQueueStatus { in_flight: queue.in_flight(), queue_depth: queue.queue_depth() }),
+        providers: GLOBAL_DISPATCHER.get().map(|dispatcher| dispatcher.concurrency_stats()).unwrap_or_default(),
+        ali_client_pool: get_ali_client_pool().await.ok().map(|pool| pool.stats()),
+        running_archive_tasks: list_archive_tasks().iter().filter(|task| task.status == ArchiveStatus::Running).count(),
+    };
+
+    let caches = BTreeMap::from([
+        (GLOBAL_CACHE_NAME, cache_hit_stats(GLOBAL_CACHE_NAME)),
+        (RESPONSE_CACHE_NAME, cache_hit_stats(RESPONSE_CACHE_NAME)),
+    ]);
+
+    let recent_errors = ErrorRate::new(
+        call_stats.iter().map(|stats| stats.total_calls).sum(),
+        call_stats.iter().map(|stats| stats.error_count).sum(),
+    );
+
+    Ok(Json(GatewayStatus {
+        status,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        build: build_info(),
+        providers,
+        open_circuits: OpenCircuits {
+            degradation,
+            unhealthy_models,
+            cooling_down_keys,
+        },
+        queues,
+        caches,
+        recent_errors,
+    }))
+}
@@ -0,0 +1,102 @@
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    response::Json,
+};
+
+use crate::dao::{
+    usage_stats::{list_usage_stats, UsageStat, UsageStatFilter},
+    SQLITE_POOL,
+};
+use crate::llm_api::utils::budget::{get_budget_status, list_budget_statuses, set_monthly_budget, BudgetStatus};
+use crate::dao::project::DEFAULT_PROJECT;
+use crate::web::dto::usage_dto::{BudgetQuery, UpdateBudgetRequest};
+
+/// 按天查询各项目、供应商/模型的用量和费用，`start`/`end` 为 YYYY-MM-DD（含）
+pub async fn list_usage(
+    Query(filter): Query<UsageStatFilter>,
+) -> Result<Json<Vec<UsageStat>>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    let valid_day = |day: &Option<String>| day.as_ref()
+        .is_none_or(|day| chrono::NaiveDate::parse_from_str(day, "%Y-%m-%d").is_ok());
+    if !valid_day(&filter.start) || !valid_day(&filter.end) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match list_usage_stats(pool, &filter).await {
+        Ok(stats) => Ok(Json(stats)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// 列出各项目已配置的供应商预算及当月使用情况
+pub async fn list_budgets() -> Result<Json<Vec<BudgetStatus>>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    match list_budget_statuses(pool).await {
+        Ok(statuses) => Ok(Json(statuses)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// 查询项目（`project_id` 查询参数，默认 default）在供应商上的预算使用情况
+pub async fn get_budget(
+    Path(provider): Path<String>,
+    Query(query): Query<BudgetQuery>,
+) -> Result<Json<BudgetStatus>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    match get_budget_status(pool, query.project_id(), &provider).await {
+        Ok(Some(status)) => Ok(Json(status)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// 设置项目在供应商上的月度预算
+pub async fn update_budget(
+    Path(provider): Path<String>,
+    Query(query): Query<BudgetQuery>,
+    Json(request): Json<UpdateBudgetRequest>,
+) -> Result<Json<BudgetStatus>, StatusCode> {
+    let pool = SQLITE_POOL.get()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref();
+
+    if !request.monthly_budget.is_finite() || request.monthly_budget < 0.0 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    set_monthly_budget(pool, query.project_id(), &provider, Some(request.monthly_budget))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    match get_budget_status(pool, query.project_id(), &provider).await {
+        Ok(Some(status)) => Ok(Json(status)),
+        _ => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// 取消项目在供应商上的月度预算
+pub async fn delete_budget(Path(provider): Path<String>, Query(query): Query<BudgetQuery>) -> StatusCode {
+    let Some(pool) = SQLITE_POOL.get() else {
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    };
+
+    match set_monthly_budget(pool, query.project_id(), &provider, None).await {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+impl BudgetQuery {
+    fn project_id(&self) -> &str {
+        self.project_id.as_deref().unwrap_or(DEFAULT_PROJECT)
+    }
+}
@@ -0,0 +1,225 @@
+//! # WebSocket 流式对话
+//!
+//! `GET /v1/ws/chat` 升级为 WebSocket 后，客户端以 JSON 文本消息发起和取消对话：
+//! - `{"type": "chat", "id": "c1", "request": {...}}`：请求体与 `/v1/chat/completions` 相同，始终以流式返回
+//! - `{"type": "cancel", "id": "c1"}`：取消进行中的对话，中断上游流式响应
+//!
+//! 同一连接上的多个对话并发执行，服务端按对话 id 交错返回 `chunk`，出错或被取消时返回 `error`，
+//! 最后以 `done` 结束。连接关闭时取消该连接上所有进行中的对话
+//!
+//! 每个对话单独计入调用方的请求配额并经过准入队列，单个连接同时进行的对话数不超过
+//! [`MAX_CONVERSATIONS_PER_CONNECTION`]；写入连接的消息缓冲有上限，客户端读取跟不上时对话暂停转发
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    extract::{
+        ws::{Message as WsMessage, WebSocket, WebSocketUpgrade},
+        Extension,
+    },
+    http::HeaderMap,
+    response::{Json, Response},
+};
+use futures_util::{SinkExt, StreamExt};
+use tokio::sync::mpsc;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+use crate::api_types::v1::GatewayErrorCode;
+use crate::llm_api::utils::admission::AdmissionQueue;
+use crate::llm_api::utils::cancellation::{register_request, CancellationToken, InFlightRequest};
+use crate::llm_api::utils::client::{CallMetadata, CALL_METADATA};
+use crate::llm_api::utils::consumer_quota::get_consumer_quotas;
+use crate::web::dto::chat_completion_dto::ChatCompletionRequest;
+use crate::web::dto::ws_chat_dto::{WsClientMessage, WsServerMessage};
+use crate::web::handlers::chat_completion_handler::{
+    api_error, build_dispatch_request, map_llm_error, request_owner, resolve_chat_model, ApiError, ChunkStream,
+};
+use crate::web::middleware::admission::rejection_message;
+use crate::web::middleware::quota::record_rejection;
+use crate::web::middleware::routing::RoutingOverride;
+
+/// 单个连接上同时进行的对话数上限
+pub const MAX_CONVERSATIONS_PER_CONNECTION: usize = 16;
+
+/// 等待写入连接的消息数上限
+const OUTBOUND_BUFFER: usize = 64;
+
+/// 连接上进行中的对话：对话 id -> 取消令牌
+type Conversations = Arc<Mutex<HashMap<String, CancellationToken>>>;
+
+/// 连接建立时确定、该连接上所有对话共用的上下文
+#[derive(Clone)]
+struct Connection {
+    metadata: CallMetadata,
+    owner: Option<String>,
+    routing: RoutingOverride,
+    admission: Option<Arc<AdmissionQueue>>,
+    conversations: Conversations,
+    tx: mpsc::Sender<WsServerMessage>,
+}
+
+/// WebSocket 流式对话接口
+///
+/// 连接升级后在独立任务中处理，因此先取出中间件设置的调用方、项目和请求头指定的路由，该连接上的所有对话都沿用；
+/// 配置了准入队列（`Extension<Arc<AdmissionQueue>>`）时每个对话开始前排队
+pub async fn chat_websocket(
+    ws: WebSocketUpgrade,
+    headers: HeaderMap,
+    routing: Option<Extension<RoutingOverride>>,
+    admission: Option<Extension<Arc<AdmissionQueue>>>,
+) -> Response {
+    let metadata = CallMetadata::inherited();
+    let owner = request_owner(&headers);
+    let routing = routing.map(|Extension(routing)| routing).unwrap_or_default();
+    let admission = admission.map(|Extension(queue)| queue);
+    ws.on_upgrade(move |socket| handle_socket(socket, metadata, owner, routing, admission))
+}
+
+async fn handle_socket(
+    socket: WebSocket,
+    metadata: CallMetadata,
+    owner: Option<String>,
+    routing: RoutingOverride,
+    admission: Option<Arc<AdmissionQueue>>,
+) {
+    let (mut sink, mut stream) = socket.split();
+    let (tx, mut rx) = mpsc::channel::<WsServerMessage>(OUTBOUND_BUFFER);
+
+    // 所有对话的消息汇总到一个任务中写入连接
+    tokio::spawn(async move {
+        while let Some(message) = rx.recv().await {
+            let Ok(text) = serde_json::to_string(&message) else {
+                continue;
+            };
+            if sink.send(WsMessage::Text(text)).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let connection = Connection { metadata, owner, routing, admission, conversations: Conversations::default(), tx };
+    let tx = &connection.tx;
+    while let Some(Ok(message)) = stream.next().await {
+        let text = match message {
+            WsMessage::Text(text) => text,
+            WsMessage::Close(_) => break,
+            // Ping/Pong 由 axum 处理，二进制消息忽略
+            _ => continue,
+        };
+        match serde_json::from_str::<WsClientMessage>(&text) {
+            Ok(WsClientMessage::Chat { id, request }) => {
+                start_conversation(&connection, id, *request).await;
+            }
+            Ok(WsClientMessage::Cancel { id }) => {
+                let token = connection.conversations.lock().unwrap().get(&id).cloned();
+                match token {
+                    Some(token) => {
+                        info!(conversation_id = %id, "WebSocket conversation cancelled by client");
+                        token.cancel();
+                    }
+                    None => send_error(tx, Some(id.clone()), api_error(
+                        GatewayErrorCode::RequestNotFound,
+                        &format!("No in-flight conversation with id `{}`", id),
+                        None,
+                    )).await,
+                }
+            }
+            Err(e) => send_error(tx, None, api_error(
+                GatewayErrorCode::InvalidRequest,
+                &format!("Invalid message: {}", e),
+                None,
+            )).await,
+        }
+    }
+
+    // 连接已关闭，停止所有进行中的对话
+    let tokens: Vec<CancellationToken> = connection.conversations.lock().unwrap().drain().map(|(_, token)| token).collect();
+    debug!(conversations = tokens.len(), "WebSocket closed, cancelling in-flight conversations");
+    for token in tokens {
+        token.cancel();
+    }
+}
+
+/// 登记对话并在独立任务中执行，同一 id 的对话仍在进行、连接上的对话数已达上限或调用方超出请求配额时拒绝
+async fn start_conversation(connection: &Connection, id: String, request: ChatCompletionRequest) {
+    let registered = {
+        let mut conversations = connection.conversations.lock().unwrap();
+        if conversations.contains_key(&id) {
+            Err(api_error(
+                GatewayErrorCode::InvalidRequest,
+                &format!("Conversation with id `{}` is already in flight", id),
+                None,
+            ))
+        } else if conversations.len() >= MAX_CONVERSATIONS_PER_CONNECTION {
+            Err(api_error(
+                GatewayErrorCode::RateLimitExceeded,
+                &format!("Too many concurrent conversations on this connection (max {})", MAX_CONVERSATIONS_PER_CONNECTION),
+                None,
+            ))
+        } else if let Some(consumer_id) = connection.metadata.consumer_id.as_deref()
+            && let Err(status) = get_consumer_quotas().check_request(consumer_id)
+        {
+            Err(api_error(GatewayErrorCode::RateLimitExceeded, &record_rejection(consumer_id, &status), None))
+        } else {
+            // 同时登记为该调用方进行中的请求，统一由取消令牌中断
+            let in_flight = register_request(connection.owner.as_deref(), &Uuid::new_v4().to_string());
+            conversations.insert(id.clone(), in_flight.token().clone());
+            Ok(in_flight)
+        }
+    };
+    let in_flight = match registered {
+        Ok(in_flight) => in_flight,
+        Err(error) => return send_error(&connection.tx, Some(id), error).await,
+    };
+
+    let connection = connection.clone();
+    tokio::spawn(async move {
+        if let Err(error) = stream_conversation(&connection, &id, request, in_flight).await {
+            send_error(&connection.tx, Some(id.clone()), error).await;
+        }
+        connection.conversations.lock().unwrap().remove(&id);
+        connection.tx.send(WsServerMessage::Done { id }).await.ok();
+    });
+}
+
+/// 排队准入后执行一次流式对话，逐块发送到连接；准入名额只占用到开始返回响应为止
+async fn stream_conversation(
+    connection: &Connection,
+    id: &str,
+    request: ChatCompletionRequest,
+    in_flight: InFlightRequest,
+) -> Result<(), ApiError> {
+    let routing = &connection.routing;
+    let permit = match &connection.admission {
+        Some(queue) => Some(queue.admit(routing.priority.unwrap_or_default()).await.map_err(|rejection| {
+            api_error(GatewayErrorCode::GatewayOverloaded, rejection_message(rejection), None)
+        })?),
+        None => None,
+    };
+
+    let (dispatcher, provider, model) = resolve_chat_model(&request, routing).await?;
+    let requested_model = request.model.clone();
+    let mut dispatch_request = build_dispatch_request(request, provider, model, connection.metadata.project_id.clone());
+    routing.apply(&mut dispatch_request);
+
+    let metadata = connection.metadata.clone().with_cancellation(in_flight.token().clone());
+    let receiver = CALL_METADATA.scope(metadata, dispatcher.dispatch_stream(dispatch_request)).await
+        .map_err(|e| map_llm_error(&e))?;
+    drop(permit);
+
+    let mut chunks = ChunkStream::new(receiver, requested_model, in_flight);
+    while let Some(chunk) = chunks.next_chunk().await {
+        let message = WsServerMessage::Chunk { id: id.to_string(), chunk: chunk? };
+        // 连接已关闭，不再转发
+        if connection.tx.send(message).await.is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+async fn send_error(tx: &mpsc::Sender<WsServerMessage>, id: Option<String>, (_, Json(error)): ApiError) {
+    tx.send(WsServerMessage::Error { id, error: error.error }).await.ok();
+}
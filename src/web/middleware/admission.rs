@@ -0,0 +1,48 @@
+use std::sync::Arc;
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+
+use crate::api_types::v1::GatewayErrorCode;
+use crate::llm_api::utils::admission::{AdmissionQueue, AdmissionRejection};
+use crate::web::middleware::routing::request_priority;
+
+/// 准入控制中间件：网关同时处理的请求数达到上限时排队等待，按 `X-Priority` 优先放行高优先级的请求，
+/// 队列已满或排队超时返回 503 和 `Retry-After`；流式请求只占用到开始返回响应为止
+///
+/// 用法：`route.route_layer(axum::middleware::from_fn_with_state(queue, admission_control))`
+pub async fn admission_control(State(queue): State<Arc<AdmissionQueue>>, request: Request, next: Next) -> Response {
+    let priority = match request_priority(request.headers()) {
+        Ok(priority) => priority.unwrap_or_default(),
+        Err(message) => {
+            let code = GatewayErrorCode::InvalidRequest;
+            let status = StatusCode::from_u16(code.status_code()).unwrap_or(StatusCode::BAD_REQUEST);
+            return (status, Json(code.to_openai_error(message, None))).into_response();
+        }
+    };
+    match queue.admit(priority).await {
+        Ok(permit) => {
+            let response = next.run(request).await;
+            drop(permit);
+            response
+        }
+        Err(rejection) => {
+            let code = GatewayErrorCode::GatewayOverloaded;
+            let status = StatusCode::from_u16(code.status_code()).unwrap_or(StatusCode::SERVICE_UNAVAILABLE);
+            let mut response = (status, Json(code.to_openai_error(rejection_message(rejection), None))).into_response();
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(queue.retry_after().as_secs()));
+            response
+        }
+    }
+}
+
+/// 准入被拒绝时返回给客户端的错误信息
+pub(crate) fn rejection_message(rejection: AdmissionRejection) -> &'static str {
+    match rejection {
+        AdmissionRejection::QueueFull => "Gateway is overloaded: request queue is full",
+        AdmissionRejection::WaitTimeout => "Gateway is overloaded: timed out waiting in the request queue",
+    }
+}
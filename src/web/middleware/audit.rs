@@ -0,0 +1,145 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{OriginalUri, Request},
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
+use serde_json::Value;
+use sqlx::SqlitePool;
+use tracing::warn;
+
+use super::quota::bearer_token;
+use crate::dao::SQLITE_POOL;
+use crate::dao::audit_log::{create_audit_log, AuditLog};
+use crate::dao::model::get_model_by_id;
+use crate::dao::provider::get_provider_by_id;
+use crate::dao::provider_key_pool::get_provider_key_pool_by_id;
+use crate::llm_api::utils::consumer_quota::consumer_id;
+use crate::llm_api::utils::redaction::redact_opt;
+
+/// 操作人请求头，未携带时使用 Bearer 凭据对应的调用方 ID
+pub const ADMIN_USER_HEADER: &str = "x-admin-user";
+
+/// 缓冲创建接口响应体（读取新资源 ID）的大小上限
+const MAX_RESPONSE_BYTES: usize = 1024 * 1024;
+
+/// 被审计的资源
+#[derive(Debug, Clone, PartialEq)]
+struct AuditTarget {
+    action: &'static str,
+    resource_type: &'static str,
+    /// 创建时为空，从响应体的 `id` 读取
+    resource_id: Option<String>,
+}
+
+/// 管理接口审计中间件：供应商、模型、API Key 的创建/更新/删除请求执行后写入 `audit_logs`，
+/// 记录操作人、状态码和变更前后的资源快照（API Key 的密文和哈希不写入快照）。其它请求直接放行
+///
+/// 用法：`api_routes.route_layer(axum::middleware::from_fn(admin_audit))`
+pub async fn admin_audit(request: Request, next: Next) -> Response {
+    let Some(target) = audit_target(request.method(), request.uri().path()) else {
+        return next.run(request).await;
+    };
+    let Some(pool) = SQLITE_POOL.get().cloned() else {
+        return next.run(request).await;
+    };
+
+    let actor = request_actor(&request);
+    let method = request.method().to_string();
+    let path = request.extensions().get::<OriginalUri>()
+        .map(|uri| uri.path().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let before_snapshot = match &target.resource_id {
+        Some(id) => snapshot(&pool, target.resource_type, id).await,
+        None => None,
+    };
+
+    let mut response = next.run(request).await;
+    let status_code = response.status();
+
+    let mut resource_id = target.resource_id.clone();
+    if resource_id.is_none() && status_code.is_success() {
+        let (parts, body) = response.into_parts();
+        let bytes = to_bytes(body, MAX_RESPONSE_BYTES).await.unwrap_or_default();
+        resource_id = serde_json::from_slice::<Value>(&bytes).ok()
+            .and_then(|body| body.get("id").and_then(Value::as_str).map(str::to_string));
+        response = Response::from_parts(parts, Body::from(bytes));
+    }
+    let after_snapshot = match &resource_id {
+        Some(id) if status_code.is_success() && target.action != "delete" => snapshot(&pool, target.resource_type, id).await,
+        _ => None,
+    };
+
+    let log = AuditLog {
+        id: uuid::Uuid::new_v4().to_string(),
+        actor,
+        action: target.action.to_string(),
+        resource_type: target.resource_type.to_string(),
+        resource_id,
+        method,
+        path,
+        status_code: status_code.as_u16() as i64,
+        before_snapshot,
+        after_snapshot,
+        created_at: None,
+    };
+    if let Err(e) = create_audit_log(&pool, &log).await {
+        warn!(path = %log.path, error = %e, "Failed to record audit log");
+    }
+    response
+}
+
+// 按方法和（去掉 /api 前缀的）路径识别被审计的资源
+fn audit_target(method: &Method, path: &str) -> Option<AuditTarget> {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let target = |action, resource_type, resource_id: Option<&str>| Some(AuditTarget {
+        action,
+        resource_type,
+        resource_id: resource_id.map(str::to_string),
+    });
+
+    match (method, segments.as_slice()) {
+        (&Method::POST, ["providers"]) => target("create", "provider", None),
+        (&Method::PUT, ["providers", id]) => target("update", "provider", Some(id)),
+        (&Method::DELETE, ["providers", id]) => target("delete", "provider", Some(id)),
+        (&Method::POST, ["providers", _, "api-keys"]) => target("create", "api_key", None),
+        (&Method::POST, ["models"]) => target("create", "model", None),
+        (&Method::PUT, ["models", id]) => target("update", "model", Some(id)),
+        (&Method::DELETE, ["models", id]) => target("delete", "model", Some(id)),
+        (&Method::PUT, ["api-keys", id]) | (&Method::PUT, ["api-keys", id, "toggle", _]) => target("update", "api_key", Some(id)),
+        (&Method::DELETE, ["api-keys", id]) => target("delete", "api_key", Some(id)),
+        _ => None,
+    }
+}
+
+// 操作人：X-Admin-User 请求头，其次是 Bearer 凭据对应的调用方 ID，都没有时为 anonymous
+fn request_actor(request: &Request) -> String {
+    request.headers().get(ADMIN_USER_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+        .or_else(|| bearer_token(request.headers()).map(consumer_id))
+        .unwrap_or_else(|| "anonymous".to_string())
+}
+
+// 资源当前状态的 JSON 快照，API Key 去掉密文和哈希，其余字段按全局规则脱敏；不存在或读取失败时为 None
+async fn snapshot(pool: &SqlitePool, resource_type: &str, id: &str) -> Option<String> {
+    let value = match resource_type {
+        "provider" => get_provider_by_id(pool, id).await.ok().flatten().and_then(|p| serde_json::to_value(p).ok()),
+        "model" => get_model_by_id(pool, id).await.ok().flatten().and_then(|m| serde_json::to_value(m).ok()),
+        "api_key" => get_provider_key_pool_by_id(pool, id).await.ok().flatten()
+            .and_then(|key| serde_json::to_value(key).ok())
+            .map(|mut value| {
+                if let Some(object) = value.as_object_mut() {
+                    object.remove("key_hash");
+                    object.remove("encrypted_key_value");
+                }
+                value
+            }),
+        _ => None,
+    }?;
+    redact_opt(Some(value.to_string()))
+}
+
@@ -1 +1,9 @@
 pub mod cors;
+pub mod timeout;
+pub mod trace;
+pub mod request_id;
+pub mod quota;
+pub mod project;
+pub mod routing;
+pub mod audit;
+pub mod admission;
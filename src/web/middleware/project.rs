@@ -0,0 +1,37 @@
+use axum::{
+    extract::Request,
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use tracing::warn;
+
+use super::quota::bearer_token;
+use crate::api_types::v1::GatewayErrorCode;
+use crate::llm_api::utils::client::{CallMetadata, CALL_METADATA};
+use crate::llm_api::utils::consumer_quota::consumer_id;
+use crate::llm_api::utils::project_scope::{resolve_project, ProjectRejection};
+
+/// 项目隔离中间件：按 `Authorization: Bearer` 携带的网关 Key 找到绑定的项目，调度时只使用该项目的资源；
+/// 未绑定或未携带 Key 的请求属于 default 项目（开启 `projects.require_bound_key` 时返回 401），绑定的项目已停用时返回 403
+///
+/// 用法：`route.route_layer(axum::middleware::from_fn(project_scope))`
+pub async fn project_scope(request: Request, next: Next) -> Response {
+    let consumer_id = bearer_token(request.headers()).map(consumer_id);
+    let (code, message) = match resolve_project(consumer_id.as_deref()) {
+        Ok(project_id) => {
+            let metadata = CallMetadata::current().with_project(Some(project_id));
+            return CALL_METADATA.scope(metadata, next.run(request)).await;
+        }
+        Err(ProjectRejection::UnboundKey) => {
+            warn!(consumer_id = consumer_id.as_deref().unwrap_or("-"), "Rejected request without a project-bound gateway key");
+            (GatewayErrorCode::InvalidApiKey, "A gateway key bound to a project is required".to_string())
+        }
+        Err(ProjectRejection::Disabled(project_id)) => {
+            warn!(project_id = %project_id, "Rejected request for disabled project");
+            (GatewayErrorCode::ProjectDisabled, format!("Project {} is disabled", project_id))
+        }
+    };
+    let status_code = StatusCode::from_u16(code.status_code()).unwrap_or(StatusCode::FORBIDDEN);
+    (status_code, Json(code.to_openai_error(message, None))).into_response()
+}
@@ -0,0 +1,91 @@
+use std::net::SocketAddr;
+
+use axum::{
+    extract::{ConnectInfo, Request},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use tracing::warn;
+
+use crate::api_types::v1::GatewayErrorCode;
+use crate::llm_api::utils::client::{CallMetadata, CALL_METADATA};
+use crate::llm_api::utils::consumer_quota::{client_consumer_id, consumer_id, get_consumer_quotas, RateLimitStatus};
+use crate::llm_api::utils::project_scope::is_bound_consumer;
+use crate::metrics::metrics;
+
+/// 调用方配额中间件：按 `Authorization: Bearer` 携带的调用方 Key 检查每分钟请求数和每天 token 数，
+/// 超出时返回 429；响应都带上 `X-RateLimit-*` 头。未携带 Key 或 Key 未登记（没有单独的配额、也没有绑定项目）时
+/// 按客户端地址计数并适用默认配额
+///
+/// 用法：`route.route_layer(axum::middleware::from_fn(consumer_quota))`
+pub async fn consumer_quota(request: Request, next: Next) -> Response {
+    let consumer_id = quota_subject(&request);
+
+    match get_consumer_quotas().check_request(&consumer_id) {
+        Ok(status) => {
+            let metadata = CallMetadata::current().with_consumer(Some(consumer_id));
+            let mut response = CALL_METADATA.scope(metadata, next.run(request)).await;
+            apply_rate_limit_headers(response.headers_mut(), &status);
+            response
+        }
+        Err(status) => {
+            let message = record_rejection(&consumer_id, &status);
+            let code = GatewayErrorCode::RateLimitExceeded;
+            let status_code = StatusCode::from_u16(code.status_code()).unwrap_or(StatusCode::TOO_MANY_REQUESTS);
+            let mut response = (status_code, Json(code.to_openai_error(message, None))).into_response();
+            apply_rate_limit_headers(response.headers_mut(), &status);
+            if let Some(retry_after) = status.retry_after() {
+                response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after.as_secs().max(1)));
+            }
+            response
+        }
+    }
+}
+
+/// 记录一次超出调用方配额的拒绝，返回给客户端的错误信息
+pub(crate) fn record_rejection(consumer_id: &str, status: &RateLimitStatus) -> String {
+    let limit = status.exceeded.map(|limit| limit.as_str()).unwrap_or_default();
+    metrics().incr_counter("llm_gateway_consumer_quota_rejections_total", &[("limit", limit)]);
+    warn!(consumer_id = %consumer_id, limit, "Consumer quota exceeded");
+    format!("Rate limit exceeded for consumer {}: {} quota used up", consumer_id, limit)
+}
+
+// 计数的调用方：登记过的 Key 按 Key，其余按客户端地址
+fn quota_subject(request: &Request) -> String {
+    if let Some(consumer_id) = bearer_token(request.headers()).map(consumer_id)
+        && (get_consumer_quotas().has_own_quota(&consumer_id) || is_bound_consumer(&consumer_id))
+    {
+        return consumer_id;
+    }
+    let client = request.extensions().get::<ConnectInfo<SocketAddr>>().map(|ConnectInfo(addr)| addr.ip());
+    client_consumer_id(client)
+}
+
+// 请求头中的 Bearer 凭据
+pub(crate) fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers.get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+// 只输出配置了额度的维度，重置时间以秒为单位（如 `45s`）
+fn apply_rate_limit_headers(headers: &mut HeaderMap, status: &RateLimitStatus) {
+    let mut insert = |name: &'static str, value: String| {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(name, value);
+        }
+    };
+    if let (Some(limit), Some(remaining)) = (status.quota.requests_per_minute, status.remaining_requests) {
+        insert("x-ratelimit-limit-requests", limit.to_string());
+        insert("x-ratelimit-remaining-requests", remaining.to_string());
+        insert("x-ratelimit-reset-requests", format!("{}s", status.reset_requests.as_secs()));
+    }
+    if let (Some(limit), Some(remaining)) = (status.quota.tokens_per_day, status.remaining_tokens) {
+        insert("x-ratelimit-limit-tokens", limit.to_string());
+        insert("x-ratelimit-remaining-tokens", remaining.to_string());
+        insert("x-ratelimit-reset-tokens", format!("{}s", status.reset_tokens.as_secs()));
+    }
+}
@@ -0,0 +1,41 @@
+use axum::{
+    extract::Request,
+    http::HeaderValue,
+    middleware::Next,
+    response::Response,
+};
+use uuid::Uuid;
+
+use crate::llm_api::utils::client::{CallMetadata, CALL_METADATA};
+use crate::web::middleware::timeout::REQUEST_ID_HEADER;
+
+/// 客户端提供的请求 ID 的最大长度，超出或包含不可见字符时改为生成新的 ID
+pub const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// 请求 ID 中间件：沿用请求头中的 `X-Request-Id`（没有或无效时生成），写回请求头供后续中间件和处理器使用，
+/// 并注入调度上下文，之后的调度响应、调用记录和日志都使用同一个 ID；响应头中返回该 ID
+///
+/// 需要放在链路追踪中间件外层，用法：`router.layer(axum::middleware::from_fn(request_id))`
+pub async fn request_id(mut request: Request, next: Next) -> Response {
+    let request_id = request.headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| is_valid_request_id(v))
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let header_value = HeaderValue::from_str(&request_id).expect("request id is a valid header value");
+    request.headers_mut().insert(REQUEST_ID_HEADER, header_value.clone());
+
+    let metadata = CallMetadata::current().with_request_id(Some(request_id));
+    let mut response = CALL_METADATA.scope(metadata, next.run(request)).await;
+    response.headers_mut().entry(REQUEST_ID_HEADER).or_insert(header_value);
+    response
+}
+
+/// 非空、不超过最大长度且只包含可见 ASCII 字符
+fn is_valid_request_id(request_id: &str) -> bool {
+    !request_id.is_empty()
+        && request_id.len() <= MAX_REQUEST_ID_LEN
+        && request_id.bytes().all(|b| b.is_ascii_graphic())
+}
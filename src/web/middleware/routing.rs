@@ -0,0 +1,93 @@
+use axum::{
+    extract::Request,
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+
+use crate::api_types::v1::GatewayErrorCode;
+use crate::llm_api::dispatcher::{DispatchRequest, Provider, RequestPriority};
+
+/// 指定供应商的请求头，例如 `X-LLM-Provider: openai`
+pub const PROVIDER_HEADER: &str = "x-llm-provider";
+
+/// 控制 fallback 的请求头，`off` 时失败后不切换备选供应商
+pub const FALLBACK_HEADER: &str = "x-llm-fallback";
+
+/// 请求优先级请求头：`high`、`normal` 或 `batch`
+pub const PRIORITY_HEADER: &str = "x-priority";
+
+/// 调用方通过请求头指定的路由，未携带请求头时保持网关的自动路由
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RoutingOverride {
+    /// 固定使用的供应商，不再按模型名称、提示词语言和路由脚本选择
+    pub provider: Option<Provider>,
+    /// 是否允许 fallback
+    pub fallback: Option<bool>,
+    /// 排队时的优先级
+    pub priority: Option<RequestPriority>,
+}
+
+impl RoutingOverride {
+    /// 解析请求头，取值不合法时返回错误信息
+    pub fn from_headers(headers: &HeaderMap) -> Result<Self, String> {
+        let provider = header_value(headers, PROVIDER_HEADER)?.map(Provider::from_name_or_custom);
+        let fallback = match header_value(headers, FALLBACK_HEADER)? {
+            None => None,
+            Some(value) => match value.to_lowercase().as_str() {
+                "on" | "true" | "1" => Some(true),
+                "off" | "false" | "0" => Some(false),
+                _ => return Err(format!("Invalid {} header `{}`, expected `on` or `off`", FALLBACK_HEADER, value)),
+            },
+        };
+        Ok(Self { provider, fallback, priority: request_priority(headers)? })
+    }
+
+    /// 将指定的路由写入 dispatcher 请求
+    pub fn apply(&self, request: &mut DispatchRequest) {
+        if let Some(provider) = &self.provider {
+            request.provider = provider.clone();
+            request.pin_provider = Some(true);
+        }
+        if self.fallback.is_some() {
+            request.fallback = self.fallback;
+        }
+        if self.priority.is_some() {
+            request.priority = self.priority;
+        }
+    }
+}
+
+/// 解析 `X-Priority` 请求头，取值不合法时返回错误信息
+pub fn request_priority(headers: &HeaderMap) -> Result<Option<RequestPriority>, String> {
+    header_value(headers, PRIORITY_HEADER)?
+        .map(|value| value.parse().map_err(|e| format!("Invalid {} header: {}", PRIORITY_HEADER, e)))
+        .transpose()
+}
+
+/// 路由请求头中间件：解析 `X-LLM-Provider`、`X-LLM-Fallback` 和 `X-Priority`，以 [`RoutingOverride`] 放入请求扩展，
+/// 由处理函数写入 dispatcher 请求；取值不合法时返回 400
+///
+/// 用法：`route.route_layer(axum::middleware::from_fn(routing_override))`
+pub async fn routing_override(mut request: Request, next: Next) -> Response {
+    match RoutingOverride::from_headers(request.headers()) {
+        Ok(routing) => {
+            request.extensions_mut().insert(routing);
+            next.run(request).await
+        }
+        Err(message) => {
+            let code = GatewayErrorCode::InvalidRequest;
+            let status_code = StatusCode::from_u16(code.status_code()).unwrap_or(StatusCode::BAD_REQUEST);
+            (status_code, Json(code.to_openai_error(message, None))).into_response()
+        }
+    }
+}
+
+// 去除首尾空白后的请求头，空值视为未携带
+fn header_value<'a>(headers: &'a HeaderMap, name: &str) -> Result<Option<&'a str>, String> {
+    let Some(value) = headers.get(name) else {
+        return Ok(None);
+    };
+    let value = value.to_str().map_err(|_| format!("Invalid {} header", name))?.trim();
+    Ok(Some(value).filter(|v| !v.is_empty()))
+}
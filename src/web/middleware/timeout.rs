@@ -0,0 +1,99 @@
+use std::time::Duration;
+use axum::{
+    extract::{Request, State},
+    http::{HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use serde_json::json;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::api_types::v1::GatewayErrorCode;
+use crate::config::ServerConfig;
+
+/// 请求 ID 请求头，客户端未提供时自动生成
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// 管理接口（CRUD、统计）的默认超时
+pub const DEFAULT_ADMIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 对话接口的默认超时（流式请求只限制到开始返回响应为止）
+pub const DEFAULT_CHAT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// 各类路由的超时配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RouteTimeouts {
+    pub admin: Duration,
+    pub chat: Duration,
+}
+
+impl Default for RouteTimeouts {
+    fn default() -> Self {
+        Self {
+            admin: DEFAULT_ADMIN_TIMEOUT,
+            chat: DEFAULT_CHAT_TIMEOUT,
+        }
+    }
+}
+
+impl RouteTimeouts {
+    /// 读取 `ADMIN_ROUTE_TIMEOUT_SECS` / `CHAT_ROUTE_TIMEOUT_SECS`，未设置或无效时使用默认值
+    pub fn from_env() -> Self {
+        let secs = |name: &str| {
+            std::env::var(name).ok()
+                .and_then(|v| v.trim().parse::<u64>().ok())
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs)
+        };
+        let defaults = Self::default();
+        Self {
+            admin: secs("ADMIN_ROUTE_TIMEOUT_SECS").unwrap_or(defaults.admin),
+            chat: secs("CHAT_ROUTE_TIMEOUT_SECS").unwrap_or(defaults.chat),
+        }
+    }
+
+    /// 使用启动配置中的超时
+    pub fn from_config(server: &ServerConfig) -> Self {
+        Self {
+            admin: Duration::from_secs(server.admin_timeout_secs),
+            chat: Duration::from_secs(server.chat_timeout_secs),
+        }
+    }
+}
+
+/// 路由超时中间件：处理器在时限内未返回时响应 504，并带上请求 ID 便于排查
+///
+/// 用法：`router.layer(axum::middleware::from_fn_with_state(timeout, route_timeout))`
+pub async fn route_timeout(State(limit): State<Duration>, request: Request, next: Next) -> Response {
+    let request_id = request.headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+
+    match tokio::time::timeout(limit, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => {
+            warn!(request_id = %request_id, method = %method, path = %path, timeout_ms = limit.as_millis() as u64, "Request timed out");
+            let code = GatewayErrorCode::Timeout;
+            let body = Json(json!({
+                "error": {
+                    "message": format!("Request timed out after {}ms", limit.as_millis()),
+                    "type": code.error_type(),
+                    "param": null,
+                    "code": code.code(),
+                    "request_id": request_id,
+                }
+            }));
+            let status = StatusCode::from_u16(code.status_code()).unwrap_or(StatusCode::GATEWAY_TIMEOUT);
+            let mut response = (status, body).into_response();
+            if let Ok(value) = HeaderValue::from_str(&request_id) {
+                response.headers_mut().insert(REQUEST_ID_HEADER, value);
+            }
+            response
+        }
+    }
+}
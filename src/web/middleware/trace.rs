@@ -0,0 +1,27 @@
+use axum::{extract::Request, middleware::Next, response::Response};
+use tracing::{field, info_span, Instrument};
+
+use crate::llm_api::utils::trace_context::{inbound_trace_parent, with_trace_parent, TRACEPARENT_HEADER};
+use crate::web::middleware::timeout::REQUEST_ID_HEADER;
+
+/// 链路追踪中间件：为每个请求创建 span，沿用请求头中的 `traceparent`（没有时开始新的链路），
+/// 之后调度器和客户端发往供应商的请求都会带上同一个 trace ID
+///
+/// 用法：`router.layer(axum::middleware::from_fn(trace_context))`
+pub async fn trace_context(request: Request, next: Next) -> Response {
+    let headers = request.headers();
+    let request_id = headers.get(REQUEST_ID_HEADER).and_then(|v| v.to_str().ok()).map(str::to_string);
+    let traceparent = headers.get(TRACEPARENT_HEADER).and_then(|v| v.to_str().ok()).map(str::to_string);
+
+    let span = info_span!(
+        "http_request",
+        method = %request.method(),
+        path = %request.uri().path(),
+        request_id = request_id.as_deref(),
+        trace_id = field::Empty,
+    );
+    let trace_parent = inbound_trace_parent(traceparent.as_deref(), &span);
+    span.record("trace_id", trace_parent.trace_id.as_str());
+
+    with_trace_parent(Some(trace_parent), next.run(request)).instrument(span).await
+}
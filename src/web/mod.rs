@@ -6,5 +6,6 @@ pub mod server;
 pub mod handlers;
 pub mod dto;
 pub mod middleware;
+pub mod extract;
 
 pub use server::WebServer;
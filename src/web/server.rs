@@ -1,20 +1,41 @@
 use axum::{
     http::StatusCode,
+    middleware::{from_fn, from_fn_with_state},
     response::{Html, IntoResponse},
     routing::{get, post, put, delete},
-    Router,
+    Extension, Router,
 };
 use tower::ServiceBuilder;
 use tower_http::{
     services::{ServeDir, ServeFile},
 };
 use std::net::SocketAddr;
+use std::sync::Arc;
 use anyhow::Result;
 
+use crate::config::{gateway_config, AdmissionConfig, AgentConfig, CacheConfig, GatewayConfig, ProjectsConfig};
 use crate::dao::init_sqlite_pool;
+use crate::dao::cache::init_global_cache;
+use crate::dao::provider_key_pool::{flush_key_usage, master_keyring};
+use crate::llm_api::dispatcher::{DispatchConfig, LLMDispatcher, GLOBAL_DISPATCHER};
+use crate::llm_api::utils::admission::AdmissionQueue;
+use crate::llm_api::utils::agent_loop::set_webhook_policy;
+use crate::llm_api::utils::blocklist::reload_blocklist;
+use crate::llm_api::utils::load_balancer::{get_load_balancer, WARM_UP_WINDOW_MINUTES};
+use crate::llm_api::utils::consumer_quota::{flush_consumer_usage, load_consumer_usage, reload_consumer_quotas};
+use crate::llm_api::utils::project_scope::{reload_project_bindings, set_require_bound_key};
+use crate::llm_api::utils::system_prompt_policy::reload_system_prompt_policies;
+use crate::notification::init_notification_channels;
+use crate::jobs::call_log_retention::{spawn_call_log_retention, DEFAULT_RETENTION_HOUR};
+use crate::jobs::consumer_usage_flush::{spawn_consumer_usage_flusher, DEFAULT_FLUSH_INTERVAL as CONSUMER_USAGE_FLUSH_INTERVAL};
+use crate::jobs::key_integrity_audit::{run_key_integrity_audit, spawn_nightly_key_integrity_audit, DEFAULT_AUDIT_HOUR};
+use crate::jobs::key_usage_flush::{spawn_key_usage_flusher, DEFAULT_FLUSH_INTERVAL};
+use crate::jobs::model_cache_reconcile::{spawn_model_cache_reconciler, DEFAULT_RECONCILE_INTERVAL};
+use crate::jobs::model_health_check::{spawn_model_health_checker, DEFAULT_CHECK_TICK, DEFAULT_FAILURE_THRESHOLD};
+use crate::jobs::route_script_reload::{spawn_route_script_watcher, DEFAULT_RELOAD_INTERVAL};
 use crate::web::{
     handlers::{
-        health_handler::{health_check, system_info},
+        health_handler::{health_check, system_info, readiness_check},
         provider_handler::{
             list_providers, get_provider, create_new_provider, 
             update_existing_provider, delete_existing_provider,
@@ -27,26 +48,119 @@ use crate::web::{
         },
         api_key_handler::{
             list_provider_api_keys, create_api_key, update_api_key,
-            delete_api_key, toggle_api_key_status,
+            delete_api_key, toggle_api_key_status, audit_api_keys, rotate_api_key_master_key,
         },
         call_log_handler::{
-            list_call_logs, get_call_log_stats,
+            list_call_logs, get_call_log_stats, get_call_log_overview, get_call_log_language_stats,
+            get_call_log_traffic_arm_stats, get_call_log_timeseries,
+            get_billing_reconciliation_report, archive_call_logs, bulk_delete_call_logs,
+            list_call_log_archive_tasks, get_call_log_archive_task,
         },
+        abuse_handler::list_top_offenders,
+        degradation_handler::{get_degradation_status, update_degradation},
+        blocklist_handler::{
+            list_blocklist, get_blocklist_entry, create_blocklist,
+            update_blocklist, delete_blocklist,
+        },
+        metrics_handler::export_metrics,
+        status_handler::get_gateway_status,
+        dashboard_handler::get_dashboard,
+        prompt_cache_handler::{
+            warmup_prompt_cache, list_prompt_cache_warmup_tasks,
+            get_prompt_cache_warmup_task, delete_prompt_cache_warmup_task,
+        },
+        db_stats_handler::{get_db_stats, reset_db_stats},
+        usage_handler::{list_usage, list_budgets, get_budget, update_budget, delete_budget},
+        shadow_handler::get_shadow_comparisons,
+        audit_handler::get_audit_logs,
+        cache_handler::{get_cache_stats, invalidate_cache},
+        chat_completion_handler::{create_chat_completion, cancel_chat_request},
+        completion_handler::create_completion,
+        estimate_handler::estimate_chat_cost,
+        consumer_handler::{list_consumers, list_consumer_usage_history, list_quotas, update_quota, delete_quota},
+        project_handler::{
+            list_all_projects, get_project, create_new_project, update_existing_project, delete_existing_project,
+            list_project_gateway_keys, bind_project_gateway_key, unbind_project_gateway_key,
+            get_project_system_prompt_policy, update_project_system_prompt_policy, delete_project_system_prompt_policy,
+        },
+        conversation_handler::{
+            create_new_conversation, list_project_conversations, get_conversation,
+            delete_existing_conversation, send_conversation_message,
+        },
+        batch_handler::{create_batch_chat, get_batch_chat},
+        agent_handler::create_agent_chat,
+        ws_chat_handler::chat_websocket,
+        ollama_handler::{list_ollama_models, pull_ollama_model, get_ollama_model, delete_ollama_model},
+    },
+    middleware::{
+        cors::cors_layer,
+        timeout::{route_timeout, RouteTimeouts},
+        trace::trace_context,
+        request_id::request_id,
+        quota::consumer_quota,
+        project::project_scope,
+        routing::routing_override,
+        audit::admin_audit,
+        admission::admission_control,
     },
-    middleware::cors::cors_layer,
 };
 
 pub struct WebServer {
     db_url: String,
     init_sql_path: String,
+    route_timeouts: RouteTimeouts,
+    admission: AdmissionConfig,
+    cache: CacheConfig,
+    dispatch_config: DispatchConfig,
+    agent: AgentConfig,
+    projects: ProjectsConfig,
 }
 
 impl WebServer {
+    /// 使用指定的数据库，其余配置（超时、缓存、调度器默认参数）来自全局启动配置
     pub fn new(db_url: String, init_sql_path: String) -> Self {
-        Self { db_url, init_sql_path }
+        let config = gateway_config();
+        Self {
+            db_url,
+            init_sql_path,
+            route_timeouts: RouteTimeouts::from_config(&config.server),
+            admission: config.admission.clone(),
+            cache: config.cache.clone(),
+            dispatch_config: config.dispatcher.dispatch_config(),
+            agent: config.agent.clone(),
+            projects: config.projects.clone(),
+        }
+    }
+
+    /// 按启动配置创建
+    pub fn from_config(config: &GatewayConfig) -> Self {
+        Self {
+            db_url: config.database.url.clone(),
+            init_sql_path: config.database.init_sql_path.clone(),
+            route_timeouts: RouteTimeouts::from_config(&config.server),
+            admission: config.admission.clone(),
+            cache: config.cache.clone(),
+            dispatch_config: config.dispatcher.dispatch_config(),
+            agent: config.agent.clone(),
+            projects: config.projects.clone(),
+        }
+    }
+
+    /// 设置各类路由的超时
+    pub fn with_route_timeouts(mut self, route_timeouts: RouteTimeouts) -> Self {
+        self.route_timeouts = route_timeouts;
+        self
     }
 
     pub async fn start(&self, addr: SocketAddr) -> Result<()> {
+        // 主密钥环配置错误时拒绝启动，避免写入无法解密的 API Key
+        master_keyring()?;
+
+        // Agent Webhook 工具只能访问白名单中的主机
+        set_webhook_policy(self.agent.webhook_policy());
+        // 多团队共用网关时只接受绑定了项目的网关 Key
+        set_require_bound_key(self.projects.require_bound_key);
+
         // 初始化数据库
         init_sqlite_pool(&self.db_url).await;
         
@@ -55,6 +169,76 @@ impl WebServer {
             eprintln!("Failed to initialize database: {}", e);
         }
 
+        // 设置了 SEED_DEFAULT_DATA 时写入常用供应商和模型
+        if crate::dao::seed::seed_enabled_from_env()
+            && let Some(pool) = crate::dao::SQLITE_POOL.get()
+            && let Err(e) = crate::dao::seed::seed_default_data(pool).await
+        {
+            eprintln!("Failed to seed default data: {}", e);
+        }
+
+        // 加载关键词黑名单
+        if let Some(pool) = crate::dao::SQLITE_POOL.get()
+            && let Err(e) = reload_blocklist(pool).await
+        {
+            eprintln!("Failed to load blocklist: {}", e);
+        }
+
+        // 加载调用方配额，并恢复当天已用的额度
+        if let Some(pool) = crate::dao::SQLITE_POOL.get() {
+            if let Err(e) = reload_consumer_quotas(pool).await {
+                eprintln!("Failed to load consumer quotas: {}", e);
+            }
+            if let Err(e) = load_consumer_usage(pool).await {
+                eprintln!("Failed to load consumer usage: {}", e);
+            }
+        }
+
+        // 加载网关 Key 与项目的绑定
+        if let Some(pool) = crate::dao::SQLITE_POOL.get()
+            && let Err(e) = reload_project_bindings(pool).await
+        {
+            eprintln!("Failed to load project bindings: {}", e);
+        }
+
+        // 加载项目的系统提示词策略
+        if let Some(pool) = crate::dao::SQLITE_POOL.get()
+            && let Err(e) = reload_system_prompt_policies(pool).await
+        {
+            eprintln!("Failed to load system prompt policies: {}", e);
+        }
+
+        // 初始化通知渠道并启动后台任务
+        if let Some(pool) = crate::dao::SQLITE_POOL.get() {
+            if let Err(e) = init_notification_channels(pool).await {
+                eprintln!("Failed to initialize notification channels: {}", e);
+            }
+            // 启动时校验所有 API Key，停用无法解密或哈希不一致的 Key
+            match run_key_integrity_audit(pool, true).await {
+                Ok(report) => println!(
+                    "🔐 API Key 完整性审计: {}/{} 通过，停用 {} 个",
+                    report.healthy_keys, report.total_keys, report.deactivated_keys
+                ),
+                Err(e) => eprintln!("Failed to run key integrity audit: {}", e),
+            }
+            spawn_nightly_key_integrity_audit(pool.clone(), DEFAULT_AUDIT_HOUR);
+            spawn_key_usage_flusher(pool.clone(), DEFAULT_FLUSH_INTERVAL);
+            spawn_consumer_usage_flusher(pool.clone(), CONSUMER_USAGE_FLUSH_INTERVAL);
+            spawn_model_health_checker(pool.clone(), DEFAULT_CHECK_TICK, DEFAULT_FAILURE_THRESHOLD);
+            spawn_call_log_retention(pool.clone(), DEFAULT_RETENTION_HOUR);
+            spawn_model_cache_reconciler(pool.clone(), DEFAULT_RECONCILE_INTERVAL);
+        }
+
+        // 配置了路由脚本时加载并监听文件变化
+        if let Ok(path) = std::env::var("ROUTE_SCRIPT_PATH") {
+            spawn_route_script_watcher(path.into(), DEFAULT_RELOAD_INTERVAL);
+        }
+
+        // 初始化网关dispatcher
+        if let Err(e) = self.init_dispatcher().await {
+            eprintln!("Failed to initialize dispatcher: {}", e);
+        }
+
         let app = self.create_app();
 
         println!("🌐 Web管理界面启动中...");
@@ -62,17 +246,63 @@ impl WebServer {
         println!("🔗 API文档: http://{}/api/health", addr);
 
         let listener = tokio::net::TcpListener::bind(addr).await?;
-        axum::serve(listener, app).await?;
+        // 调用方配额按客户端地址限制匿名请求
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+            .with_graceful_shutdown(shutdown_signal())
+            .await?;
+
+        // 退出前写回内存中尚未持久化的 Key 使用次数和调用方用量
+        if let Some(pool) = crate::dao::SQLITE_POOL.get() {
+            if let Err(e) = flush_key_usage(pool).await {
+                eprintln!("Failed to flush key usage on shutdown: {}", e);
+            }
+            if let Err(e) = flush_consumer_usage(pool).await {
+                eprintln!("Failed to flush consumer usage on shutdown: {}", e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 初始化缓存并创建全局dispatcher（根据providers表注册适配器），供 `/v1/chat/completions` 使用
+    async fn init_dispatcher(&self) -> Result<()> {
+        let pool = crate::dao::SQLITE_POOL.get()
+            .ok_or_else(|| anyhow::anyhow!("Database pool not initialized"))?;
+        init_global_cache(pool, self.cache.ttl_secs, self.cache.max_entries).await?;
+
+        let dispatcher = LLMDispatcher::builder()
+            .with_config(self.dispatch_config.clone())
+            .with_database(pool.as_ref().clone())
+            .build()
+            .await?;
+        let providers = dispatcher.registered_providers().await;
+        println!("🔌 已根据数据库注册供应商适配器: {:?}", providers);
 
+        // 用最近的调用记录预热负载均衡的延迟和错误率统计
+        let since = (chrono::Utc::now() - chrono::Duration::minutes(WARM_UP_WINDOW_MINUTES))
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string();
+        if let Err(e) = get_load_balancer().warm_up(pool, &since).await {
+            eprintln!("Failed to warm up load balancer stats: {}", e);
+        }
+
+        GLOBAL_DISPATCHER.set(Arc::new(dispatcher)).ok();
         Ok(())
     }
 
     fn create_app(&self) -> Router {
+        // 全局准入队列，状态总览接口同时返回其排队情况
+        let admission = Arc::new(AdmissionQueue::from_config(&self.admission));
+
         // API路由
         let api_routes = Router::new()
             // 健康检查
             .route("/health", get(health_check))
             .route("/system", get(system_info))
+            // 网关状态总览
+            .route("/status", get(get_gateway_status).route_layer(Extension(admission.clone())))
+            // 管理界面首页汇总
+            .route("/dashboard", get(get_dashboard))
             // Provider管理
             .route("/providers", get(list_providers).post(create_new_provider))
             .route("/providers/summary", get(list_provider_summary))
@@ -85,9 +315,83 @@ impl WebServer {
             .route("/providers/:id/api-keys", get(list_provider_api_keys).post(create_api_key))
             .route("/api-keys/:id", put(update_api_key).delete(delete_api_key))
             .route("/api-keys/:id/toggle/:status", put(toggle_api_key_status))
+            .route("/api-keys/audit", post(audit_api_keys))
+            .route("/api-keys/rotate-master-key", post(rotate_api_key_master_key))
             // Call Log管理
             .route("/call-logs", get(list_call_logs))
-            .route("/call-logs/stats", get(get_call_log_stats));
+            .route("/logs", get(list_call_logs))
+            .route("/stats", get(get_call_log_overview))
+            .route("/call-logs/stats", get(get_call_log_stats))
+            .route("/call-logs/stats/languages", get(get_call_log_language_stats))
+            .route("/call-logs/stats/timeseries", get(get_call_log_timeseries))
+            .route("/call-logs/stats/traffic-arms", get(get_call_log_traffic_arm_stats))
+            // 影子流量对比记录
+            .route("/shadow-comparisons", get(get_shadow_comparisons))
+            .route("/call-logs/reconciliation", get(get_billing_reconciliation_report))
+            .route("/call-logs/archive", post(archive_call_logs))
+            .route("/call-logs/bulk-delete", post(bulk_delete_call_logs))
+            .route("/call-logs/archive-tasks", get(list_call_log_archive_tasks))
+            .route("/call-logs/archive-tasks/:id", get(get_call_log_archive_task))
+            // 缓存统计与失效
+            .route("/cache/stats", get(get_cache_stats))
+            .route("/cache/invalidate", post(invalidate_cache))
+            // 数据库查询耗时
+            .route("/admin/db_stats", get(get_db_stats).delete(reset_db_stats))
+            // 用量统计与月度预算
+            .route("/usage-stats", get(list_usage))
+            .route("/budgets", get(list_budgets))
+            .route("/budgets/:provider", get(get_budget).put(update_budget).delete(delete_budget))
+            // 调用方配额
+            .route("/consumers", get(list_consumers))
+            .route("/consumers/usage", get(list_consumer_usage_history))
+            .route("/consumer-quotas", get(list_quotas))
+            .route("/consumer-quotas/:consumer_id", put(update_quota).delete(delete_quota))
+            // 项目（工作空间）管理
+            .route("/projects", get(list_all_projects).post(create_new_project))
+            .route("/projects/:id", get(get_project).put(update_existing_project).delete(delete_existing_project))
+            .route("/projects/:id/gateway-keys", get(list_project_gateway_keys).post(bind_project_gateway_key))
+            .route("/projects/:id/gateway-keys/:consumer_id", delete(unbind_project_gateway_key))
+            .route("/projects/:id/system-prompt-policy", get(get_project_system_prompt_policy).put(update_project_system_prompt_policy).delete(delete_project_system_prompt_policy))
+            // 终端用户滥用防护
+            .route("/abuse/offenders", get(list_top_offenders))
+            // Ollama 本地模型管理，模型名可能带命名空间（例如 `library/qwen2.5:7b`）
+            .route("/ollama/models", get(list_ollama_models).post(pull_ollama_model))
+            .route("/ollama/models/*name", get(get_ollama_model).delete(delete_ollama_model))
+            // 降级模式
+            .route("/degradation", get(get_degradation_status).put(update_degradation))
+            // 提示词缓存预热
+            .route("/prompt-cache/warmup", post(warmup_prompt_cache))
+            .route("/prompt-cache/warmups", get(list_prompt_cache_warmup_tasks))
+            .route("/prompt-cache/warmups/:id", get(get_prompt_cache_warmup_task).delete(delete_prompt_cache_warmup_task))
+            // 关键词黑名单管理
+            .route("/blocklist", get(list_blocklist).post(create_blocklist))
+            .route("/blocklist/:id", get(get_blocklist_entry).put(update_blocklist).delete(delete_blocklist))
+            // 管理操作审计日志
+            .route("/audit", get(get_audit_logs))
+            .route_layer(from_fn(admin_audit))
+            .route_layer(from_fn_with_state(self.route_timeouts.admin, route_timeout));
+
+        // OpenAI 兼容的网关接口，访问上游的接口先通过配额检查，再进入全局准入队列
+        let admit = || from_fn_with_state(admission.clone(), admission_control);
+        let chat_routes = Router::new()
+            .route("/v1/chat/completions", post(create_chat_completion).route_layer(from_fn(routing_override)).route_layer(admit()).route_layer(from_fn(consumer_quota)).route_layer(from_fn(project_scope)))
+            .route("/v1/completions", post(create_completion).route_layer(admit()).route_layer(from_fn(consumer_quota)).route_layer(from_fn(project_scope)))
+            // 费用预估不访问上游，不计入调用方配额
+            .route("/v1/estimate", post(estimate_chat_cost).route_layer(from_fn(routing_override)).route_layer(from_fn(project_scope)))
+            .route("/v1/requests/:request_id", delete(cancel_chat_request))
+            .route("/v1/requests/:request_id/cancel", post(cancel_chat_request))
+            // 会话按项目隔离，发送消息与 Chat Completion 一样受调用方配额限制
+            .route("/v1/conversations", get(list_project_conversations).post(create_new_conversation).route_layer(from_fn(project_scope)))
+            .route("/v1/conversations/:id", get(get_conversation).delete(delete_existing_conversation).route_layer(from_fn(project_scope)))
+            .route("/v1/conversations/:id/messages", post(send_conversation_message).route_layer(admit()).route_layer(from_fn(consumer_quota)).route_layer(from_fn(project_scope)))
+            // 批量任务在后台执行，提交计为一次请求并经过准入队列，用量计入调用方的 token 额度
+            .route("/v1/batch/chat", post(create_batch_chat).route_layer(admit()).route_layer(from_fn(consumer_quota)).route_layer(from_fn(project_scope)))
+            .route("/v1/batch/chat/:id", get(get_batch_chat).route_layer(from_fn(project_scope)))
+            // Agent 对话由网关执行工具调用，各轮模型调用都计入调用方的 token 额度
+            .route("/v1/agent/chat", post(create_agent_chat).route_layer(admit()).route_layer(from_fn(consumer_quota)).route_layer(from_fn(project_scope)))
+            // WebSocket 连接上的每个对话各自计入调用方的请求配额并经过准入队列
+            .route("/v1/ws/chat", get(chat_websocket).route_layer(Extension(admission.clone())).route_layer(from_fn(routing_override)).route_layer(from_fn(consumer_quota)).route_layer(from_fn(project_scope)))
+            .route_layer(from_fn_with_state(self.route_timeouts.chat, route_timeout));
 
         // 静态文件服务
         let static_routes = Router::new()
@@ -98,10 +402,15 @@ impl WebServer {
         // 组合所有路由
         Router::new()
             .nest("/api", api_routes)
+            .route("/metrics", get(export_metrics).route_layer(from_fn_with_state(self.route_timeouts.admin, route_timeout)))
+            .route("/readyz", get(readiness_check).route_layer(from_fn_with_state(self.route_timeouts.admin, route_timeout)))
+            .merge(chat_routes)
             .merge(static_routes)
             .layer(
                 ServiceBuilder::new()
                     .layer(cors_layer())
+                    .layer(from_fn(request_id))
+                    .layer(from_fn(trace_context))
             )
     }
 }
@@ -113,3 +422,35 @@ async fn static_fallback() -> impl IntoResponse {
         Err(_) => (StatusCode::NOT_FOUND, "Page not found").into_response(),
     }
 }
+
+// 收到 Ctrl+C 或 SIGTERM 时停止接受新连接，等待处理中的请求完成
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            eprintln!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                eprintln!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+    println!("🛑 收到退出信号，正在关闭服务...");
+}
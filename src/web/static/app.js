@@ -221,7 +221,8 @@ async function loadProviders() {
         errorEl.style.display = 'none';
         contentEl.style.display = 'none';
 
-        const data = await apiCall('/api/providers');
+        const page = await apiCall('/api/providers?limit=1000');
+        const data = page.data;
         providers = data;
 
         // 渲染Provider卡片
@@ -284,7 +285,8 @@ async function loadModels() {
         errorEl.style.display = 'none';
         contentEl.style.display = 'none';
 
-        const data = await apiCall('/api/models');
+        const page = await apiCall('/api/models?limit=1000');
+        const data = page.data;
         models = data;
 
         // 渲染Model表格
@@ -891,12 +893,12 @@ async function loadApiKeys(providerId) {
         errorEl.style.display = 'none';
         contentEl.style.display = 'none';
 
-        const data = await apiCall(`/api/providers/${providerId}/api-keys`);
+        const data = await apiCall(`/api/providers/${providerId}/api-keys?limit=1000`);
         
-        if (data.keys.length === 0) {
+        if (data.data.length === 0) {
             listEl.innerHTML = '<div style="text-align: center; color: #666; padding: 2rem;">No API keys found for this provider.</div>';
         } else {
-            listEl.innerHTML = data.keys.map(key => `
+            listEl.innerHTML = data.data.map(key => `
                 <div class="api-key-item" style="
                     border: 1px solid #ddd; 
                     border-radius: 8px; 
@@ -955,8 +957,8 @@ async function editApiKey(keyId) {
         // 由于安全原因，我们不能直接获取API key的详细信息
         // 这里需要从当前列表中找到对应的key信息
         const providerId = document.getElementById('api-keys-provider-id').value;
-        const data = await apiCall(`/api/providers/${providerId}/api-keys`);
-        const key = data.keys.find(k => k.id === keyId);
+        const data = await apiCall(`/api/providers/${providerId}/api-keys?limit=1000`);
+        const key = data.data.find(k => k.id === keyId);
         
         if (!key) {
             showError('API key not found');
@@ -1064,10 +1066,17 @@ function displayCallLogStats() {
         <div class="stat-card">
             <div class="stat-icon orange">🔢</div>
             <div class="stat-info">
-                <h3>${callLogStats.total_tokens_output || 0}</h3>
+                <h3>${(callLogStats.total_tokens_input || 0) + (callLogStats.total_tokens_output || 0)}</h3>
                 <p>Total Tokens</p>
             </div>
         </div>
+        <div class="stat-card">
+            <div class="stat-icon green">💰</div>
+            <div class="stat-info">
+                <h3>${(callLogStats.total_cost || 0).toFixed(4)}</h3>
+                <p>Total Cost</p>
+            </div>
+        </div>
         <div class="stat-card">
             <div class="stat-icon purple">❌</div>
             <div class="stat-info">
@@ -1096,7 +1105,7 @@ async function loadCallLogs() {
         
         const response = await apiCall(`/api/call-logs?${params}`);
         callLogs = response.data || [];
-        totalCallLogPages = response.total_pages || 1;
+        totalCallLogPages = Math.max(1, Math.ceil(response.total / response.limit));
         
         displayCallLogs(response);
     } catch (error) {
@@ -1144,7 +1153,7 @@ function displayCallLogs(response) {
                     </span>
                 </td>
                 <td class="duration-cell">${log.total_duration || 0}</td>
-                <td class="tokens-cell">${log.tokens_output || 0}</td>
+                <td class="tokens-cell" title="input / output">${log.tokens_input || 0} / ${log.tokens_output || 0}</td>
                 <td class="error-cell" title="${log.error_message || ''}">${log.error_message || '-'}</td>
                 <td>${formatDateTime(log.created_at)}</td>
             </tr>
@@ -1160,7 +1169,9 @@ function displayCallLogs(response) {
 
 // 更新分页信息
 function updatePaginationInfo(response) {
-    const { total, page, limit, total_pages } = response;
+    const { total, limit } = response;
+    const page = currentCallLogPage;
+    const total_pages = totalCallLogPages;
     const start = (page - 1) * limit + 1;
     const end = Math.min(page * limit, total);
     
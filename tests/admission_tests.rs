@@ -0,0 +1,126 @@
+//! # 全局准入控制测试
+//!
+//! 测试超出并发上限的请求排队等待、按 `X-Priority` 优先放行高优先级的请求、
+//! 队列已满或排队超时返回 503 和 `Retry-After`，以及 `[admission]` 配置项
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use axum::{http::HeaderMap, middleware::from_fn_with_state, routing::post, Router};
+use tokio::net::TcpListener;
+
+use project_rust_learn::config::GatewayConfig;
+use project_rust_learn::llm_api::utils::admission::AdmissionQueue;
+use project_rust_learn::metrics::metrics;
+use project_rust_learn::web::middleware::admission::admission_control;
+
+/// 每个请求耗时 100ms 的服务，经过准入控制；按开始处理的顺序记录请求的 `X-Priority`
+async fn start_server_with_log(queue: AdmissionQueue, log: Arc<Mutex<Vec<String>>>) -> String {
+    let app = Router::new()
+        .route("/v1/chat/completions", post(move |headers: HeaderMap| async move {
+            let priority = headers.get("x-priority").and_then(|v| v.to_str().ok()).unwrap_or("normal");
+            log.lock().unwrap().push(priority.to_string());
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            "ok"
+        }))
+        .route_layer(from_fn_with_state(Arc::new(queue), admission_control));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    format!("http://{}/v1/chat/completions", addr)
+}
+
+async fn start_server(queue: AdmissionQueue) -> String {
+    start_server_with_log(queue, Arc::default()).await
+}
+
+async fn send_burst(url: &str, count: usize) -> Vec<reqwest::Response> {
+    let client = reqwest::Client::new();
+    let requests = (0..count).map(|i| {
+        let request = client.post(url).send();
+        async move {
+            // 错开发送顺序，保证先到的请求先占用槽位
+            tokio::time::sleep(Duration::from_millis(10 * i as u64)).await;
+            request.await.unwrap()
+        }
+    });
+    futures::future::join_all(requests).await
+}
+
+#[tokio::test]
+async fn test_burst_queues_within_wait() {
+    println!("=== Testing Admission Queue ===");
+    let url = start_server(AdmissionQueue::new(1, 5, Duration::from_secs(5))).await;
+    let admitted_before = metrics().counter_total("llm_gateway_admission_admitted_total");
+
+    let responses = send_burst(&url, 3).await;
+    assert!(responses.iter().all(|r| r.status() == 200), "{:?}", responses.iter().map(|r| r.status()).collect::<Vec<_>>());
+    assert!(metrics().counter_total("llm_gateway_admission_admitted_total") >= admitted_before + 3);
+    assert!(metrics().counter_value("llm_gateway_admission_wait_ms_total", &[("priority", "normal")]) > 0);
+    println!("✅ Burst queued and served one at a time");
+}
+
+#[tokio::test]
+async fn test_full_queue_returns_503_with_retry_after() {
+    println!("=== Testing Admission Rejection ===");
+    let url = start_server(AdmissionQueue::new(1, 1, Duration::from_secs(2))).await;
+
+    // 第一个请求处理中、第二个排队，第三个因队列已满被拒绝
+    let responses = send_burst(&url, 3).await;
+    let statuses: Vec<u16> = responses.iter().map(|r| r.status().as_u16()).collect();
+    assert_eq!(statuses, vec![200, 200, 503]);
+
+    let rejected = responses.into_iter().last().unwrap();
+    assert_eq!(rejected.headers()["retry-after"], "2");
+    let body: serde_json::Value = rejected.json().await.unwrap();
+    assert_eq!(body["error"]["code"], "gateway_overloaded");
+    assert_eq!(body["error"]["type"], "server_error");
+    assert!(metrics().counter_value("llm_gateway_admission_rejections_total", &[("reason", "queue_full")]) >= 1);
+    println!("✅ Request beyond the queue depth rejected with 503 and Retry-After");
+
+    // 排队超过最长等待时间同样返回 503
+    let url = start_server(AdmissionQueue::new(1, 5, Duration::from_millis(20))).await;
+    let statuses: Vec<u16> = send_burst(&url, 2).await.iter().map(|r| r.status().as_u16()).collect();
+    assert_eq!(statuses, vec![200, 503]);
+    assert!(metrics().counter_value("llm_gateway_admission_rejections_total", &[("reason", "wait_timeout")]) >= 1);
+    println!("✅ Request waiting longer than max_wait_ms rejected");
+}
+
+#[tokio::test]
+async fn test_high_priority_admitted_first() {
+    println!("=== Testing Admission Priority ===");
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let url = start_server_with_log(AdmissionQueue::new(1, 5, Duration::from_secs(5)), log.clone()).await;
+
+    // 第一个请求占用槽位，随后依次到达 batch、normal、high，high 最先放行
+    let client = reqwest::Client::new();
+    let requests = ["normal", "batch", "normal", "high"].into_iter().enumerate().map(|(i, priority)| {
+        let request = client.post(&url).header("X-Priority", priority).send();
+        async move {
+            tokio::time::sleep(Duration::from_millis(20 * i as u64)).await;
+            request.await.unwrap().status().as_u16()
+        }
+    });
+    let statuses = futures::future::join_all(requests).await;
+    assert_eq!(statuses, vec![200; 4]);
+    assert_eq!(*log.lock().unwrap(), vec!["normal", "high", "normal", "batch"]);
+    println!("✅ Queued requests admitted by priority");
+
+    let response = client.post(&url).header("X-Priority", "urgent").send().await.unwrap();
+    assert_eq!(response.status(), 400);
+    println!("✅ Invalid X-Priority rejected with 400");
+}
+
+#[test]
+fn test_admission_config() {
+    let config = GatewayConfig::from_toml_str("[admission]\nmax_concurrent_requests = 64\nmax_wait_ms = 1_500\n").unwrap();
+    assert_eq!(config.admission.max_concurrent_requests, 64);
+    assert_eq!(config.admission.max_queue_depth, 100);
+    let queue = AdmissionQueue::from_config(&config.admission);
+    assert!(queue.is_enabled());
+    assert_eq!(queue.retry_after(), Duration::from_secs(2));
+
+    assert!(!AdmissionQueue::from_config(&GatewayConfig::default().admission).is_enabled());
+    println!("✅ Admission control disabled by default");
+}
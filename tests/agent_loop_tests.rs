@@ -0,0 +1,296 @@
+//! # Agent 工具调用循环测试
+//!
+//! 测试 `/v1/agent/chat` 执行模型返回的 Webhook 和内置工具调用、把结果作为 tool 消息继续对话直到最终回答，
+//! 工具失败和超时作为错误结果交给模型，达到最大轮数时停止，Webhook 的主机白名单、重定向和响应大小限制，
+//! 以及各轮调用记录和工具调用审计的关联
+
+mod common;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use axum::{body::to_bytes, http::{HeaderMap, HeaderValue, StatusCode}, Json};
+use mockito::{Matcher, Server};
+use serde_json::{json, Value};
+
+use project_rust_learn::dao::call_log::{create_call_log, list_call_logs_by_request_id, CallLog};
+use project_rust_learn::dao::tool_call_audit::list_tool_call_steps_by_call_log;
+use project_rust_learn::dao::{init_db, init_sqlite_pool, SQLITE_POOL};
+use project_rust_learn::llm_api::dispatcher::{
+    DispatchRequest, DispatchResponse, LLMClientAdapter, LLMDispatcher, LLMError, Provider, StreamReceiver, TokenUsage,
+    GLOBAL_DISPATCHER,
+};
+use project_rust_learn::llm_api::utils::agent_loop::{
+    register_builtin_tool, set_webhook_policy, BuiltinTool, WebhookPolicy, MAX_WEBHOOK_RESPONSE_BYTES,
+};
+use project_rust_learn::llm_api::utils::client::CallMetadata;
+use project_rust_learn::llm_api::utils::msg_structure::{Function, Message, ToolCall};
+use project_rust_learn::llm_api::utils::tool_structure::ToolFunction;
+use project_rust_learn::web::dto::agent_dto::{AgentChatRequest, AgentChatResponse, AgentToolDefinition};
+use project_rust_learn::web::dto::chat_completion_dto::ChatCompletionMessage;
+use project_rust_learn::web::handlers::agent_handler::create_agent_chat;
+use common::response;
+
+const MODEL: &str = "agent-test/agent-model";
+
+/// 没有工具结果时调用请求中的全部工具，收到工具结果后把结果拼接为最终回答；每次调用写入一条调用记录
+struct ScriptedAdapter;
+
+#[async_trait]
+impl LLMClientAdapter for ScriptedAdapter {
+    async fn generate(&self, request: &DispatchRequest) -> Result<DispatchResponse, LLMError> {
+        record_call_log(request).await;
+        let tool_results: Vec<String> = request.messages.iter()
+            .filter(|m| m.role == "tool")
+            .map(|m| format!("{}={}", m.tool_name.clone().unwrap_or_default(), m.content))
+            .collect();
+        let tool_calls = match request.tools.as_ref() {
+            Some(tools) if tool_results.is_empty() => Some(tools.iter().zip(1..).map(|(tool, index)| ToolCall {
+                id: Some(format!("call_{}", index)),
+                tool_type: Some("function".to_string()),
+                function: Function {
+                    name: tool.function.name.clone(),
+                    arguments: HashMap::from([("city".to_string(), json!("Hangzhou"))]),
+                },
+            }).collect::<Vec<_>>()),
+            _ => None,
+        };
+        let content = if tool_calls.is_some() { String::new() } else { format!("final: {}", tool_results.join(", ")) };
+        Ok(DispatchResponse {
+            message: Some(Message::assistant(content.clone())),
+            usage: Some(TokenUsage { prompt_tokens: 10, completion_tokens: 5, total_tokens: 15 }),
+            finish_reason: Some(if tool_calls.is_some() { "tool_calls" } else { "stop" }.to_string()),
+            tool_calls,
+            ..response(Provider::Custom("agent-test".to_string()), &request.model, content)
+        })
+    }
+
+    async fn generate_stream(&self, _request: &DispatchRequest) -> Result<StreamReceiver, LLMError> {
+        Err(LLMError::InvalidParameters("stream not supported".to_string()))
+    }
+
+    fn supported_models(&self) -> Vec<String> {
+        vec!["agent-model".to_string()]
+    }
+
+    fn provider_name(&self) -> Provider {
+        Provider::Custom("agent-test".to_string())
+    }
+}
+
+// 与真实客户端一样写入调用记录并登记到调用上下文
+async fn record_call_log(request: &DispatchRequest) {
+    let metadata = CallMetadata::current();
+    let call_log = CallLog {
+        total_duration: 1,
+        tokens_output: 5,
+        request_id: request.request_id.clone(),
+        ..common::call_log("agent-test", 200)
+    };
+    create_call_log(SQLITE_POOL.get().unwrap(), &call_log).await.expect("create call log failed");
+    metadata.record_call_log(&call_log.id);
+}
+
+/// 超过工具超时的内置工具
+struct SlowTool;
+
+#[async_trait]
+impl BuiltinTool for SlowTool {
+    fn definition(&self) -> ToolFunction {
+        ToolFunction { name: "slow_tool".to_string(), description: "sleeps".to_string(), parameters: Value::Null }
+    }
+
+    async fn call(&self, _arguments: &HashMap<String, Value>) -> Result<String, String> {
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        Ok("too late".to_string())
+    }
+}
+
+async fn setup() {
+    // mockito 监听在 127.0.0.1
+    set_webhook_policy(WebhookPolicy { allowed_hosts: vec!["127.0.0.1".to_string()], allow_private_addresses: true });
+    init_sqlite_pool("sqlite://data/app.db").await;
+    init_db("data/init.sql").await.expect("DB init failed");
+    if GLOBAL_DISPATCHER.get().is_none() {
+        let dispatcher = LLMDispatcher::new(None);
+        dispatcher.register_client(Box::new(ScriptedAdapter)).await;
+        GLOBAL_DISPATCHER.set(Arc::new(dispatcher)).ok();
+    }
+}
+
+fn agent_request(tools: Vec<AgentToolDefinition>) -> AgentChatRequest {
+    AgentChatRequest {
+        model: MODEL.to_string(),
+        messages: vec![ChatCompletionMessage {
+            role: "user".to_string(),
+            content: Some(json!("What's the weather in Hangzhou?")),
+            name: None,
+            tool_calls: None,
+            tool_call_id: None,
+        }],
+        tools,
+        max_iterations: None,
+        tool_timeout_ms: None,
+        temperature: None,
+        max_tokens: None,
+        user: None,
+    }
+}
+
+fn webhook_tool(name: &str, url: String) -> AgentToolDefinition {
+    AgentToolDefinition {
+        name: name.to_string(),
+        description: Some("Look up the weather".to_string()),
+        parameters: Some(json!({"type": "object", "properties": {"city": {"type": "string"}}})),
+        webhook_url: Some(url),
+    }
+}
+
+fn builtin_tool(name: &str) -> AgentToolDefinition {
+    AgentToolDefinition { name: name.to_string(), description: None, parameters: None, webhook_url: None }
+}
+
+async fn run(request: AgentChatRequest, request_id: &str) -> AgentChatResponse {
+    let mut headers = HeaderMap::new();
+    headers.insert("x-request-id", HeaderValue::from_str(request_id).unwrap());
+    let response = create_agent_chat(headers, Json(request)).await.expect("agent chat failed");
+    assert_eq!(response.headers()["x-request-id"], request_id);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn test_agent_runs_tools_until_final_answer() {
+    setup().await;
+    let pool = SQLITE_POOL.get().unwrap();
+    let request_id = format!("agent-{}", uuid::Uuid::new_v4().simple());
+
+    let mut server = Server::new_async().await;
+    let webhook = server.mock("POST", "/weather")
+        .match_body(Matcher::PartialJson(json!({
+            "request_id": request_id,
+            "tool_call_id": "call_1",
+            "name": "lookup_weather",
+            "arguments": {"city": "Hangzhou"},
+        })))
+        .with_status(200)
+        .with_body("sunny")
+        .expect(1)
+        .create_async()
+        .await;
+
+    println!("=== Testing Agent Tool Loop ===");
+    let request = agent_request(vec![
+        webhook_tool("lookup_weather", format!("{}/weather", server.url())),
+        builtin_tool("current_time"),
+    ]);
+    let response = run(request, &request_id).await;
+    webhook.assert_async().await;
+
+    assert_eq!(response.object, "agent.chat");
+    assert_eq!(response.finish_reason, "stop");
+    assert_eq!(response.iterations, 2);
+    assert!(response.message.content.starts_with("final: lookup_weather=sunny, current_time="), "{}", response.message.content);
+    assert_eq!(response.usage.map(|usage| usage.total_tokens), Some(30));
+
+    assert_eq!(response.steps.len(), 2);
+    assert_eq!(response.steps[0].output.as_deref(), Some("sunny"));
+    assert_eq!(response.steps[0].arguments, json!({"city": "Hangzhou"}));
+    let now = response.steps[1].output.as_deref().expect("missing current_time output");
+    assert!(chrono::DateTime::parse_from_rfc3339(now).is_ok(), "{}", now);
+    assert!(response.steps.iter().all(|step| step.iteration == 1 && step.error.is_none()));
+    println!("✅ Webhook and builtin tool results fed back until the final answer");
+
+    let call_logs = list_call_logs_by_request_id(pool, &request_id).await.unwrap();
+    assert_eq!(call_logs.len(), 2);
+    let issuing_call = response.steps[0].call_log_id.clone().expect("missing call log id");
+    assert!(call_logs.iter().any(|log| log.id == issuing_call));
+    assert!(response.steps.iter().all(|step| step.call_log_id.as_ref() == Some(&issuing_call)));
+    let audited = list_tool_call_steps_by_call_log(pool, &issuing_call).await.unwrap();
+    assert_eq!(audited.iter().map(|step| step.tool_name.as_str()).collect::<Vec<_>>(), vec!["lookup_weather", "current_time"]);
+    assert_eq!(audited[0].output.as_deref(), Some("sunny"));
+    println!("✅ Model calls share the request id and tool steps link to the issuing call log");
+}
+
+#[tokio::test]
+async fn test_agent_tool_failures_and_max_iterations() {
+    setup().await;
+    register_builtin_tool(Arc::new(SlowTool));
+
+    let mut server = Server::new_async().await;
+    let failing = server.mock("POST", "/broken").with_status(500).create_async().await;
+
+    println!("=== Testing Tool Failures ===");
+    let mut request = agent_request(vec![
+        webhook_tool("broken_webhook", format!("{}/broken", server.url())),
+        builtin_tool("slow_tool"),
+    ]);
+    request.tool_timeout_ms = Some(50);
+    let response = run(request, &format!("agent-{}", uuid::Uuid::new_v4().simple())).await;
+    failing.assert_async().await;
+    assert_eq!(response.finish_reason, "stop");
+    assert_eq!(response.steps[0].error.as_deref(), Some("webhook returned status 500 Internal Server Error"));
+    assert_eq!(response.steps[1].error.as_deref(), Some("tool timed out after 50ms"));
+    assert!(response.message.content.contains("slow_tool=error: tool timed out"), "{}", response.message.content);
+    println!("✅ Failed and timed out tools reported to the model as errors");
+
+    println!("=== Testing Max Iterations ===");
+    let mut request = agent_request(vec![builtin_tool("current_time")]);
+    request.max_iterations = Some(1);
+    let response = run(request, &format!("agent-{}", uuid::Uuid::new_v4().simple())).await;
+    assert_eq!(response.finish_reason, "max_iterations");
+    assert_eq!(response.iterations, 1);
+    assert!(response.steps.is_empty());
+    assert_eq!(response.message.tool_calls.map(|calls| calls.len()), Some(1));
+    println!("✅ Loop stops at max_iterations without executing further tools");
+}
+
+#[tokio::test]
+async fn test_agent_webhook_redirect_and_response_limit() {
+    setup().await;
+
+    let mut server = Server::new_async().await;
+    let redirect = server.mock("POST", "/redirect")
+        .with_status(302)
+        .with_header("location", "http://169.254.169.254/latest/meta-data")
+        .create_async().await;
+    let oversized = server.mock("POST", "/oversized")
+        .with_body("x".repeat(MAX_WEBHOOK_RESPONSE_BYTES + 1))
+        .create_async().await;
+
+    println!("=== Testing Webhook Redirect And Response Limit ===");
+    let request = agent_request(vec![
+        webhook_tool("redirecting", format!("{}/redirect", server.url())),
+        webhook_tool("oversized", format!("{}/oversized", server.url())),
+    ]);
+    let response = run(request, &format!("agent-{}", uuid::Uuid::new_v4().simple())).await;
+    redirect.assert_async().await;
+    oversized.assert_async().await;
+    assert_eq!(response.steps[0].error.as_deref(), Some("webhook returned status 302 Found"));
+    assert_eq!(
+        response.steps[1].error.as_deref(),
+        Some(format!("webhook response exceeds {} bytes", MAX_WEBHOOK_RESPONSE_BYTES).as_str())
+    );
+    println!("✅ Redirects are not followed and oversized responses are rejected");
+}
+
+#[tokio::test]
+async fn test_agent_request_validation() {
+    setup().await;
+
+    let cases = [
+        (agent_request(vec![builtin_tool("no_such_tool")]), "tools"),
+        (agent_request(vec![webhook_tool("lookup", "file:///etc/passwd".to_string())]), "tools"),
+        (agent_request(vec![webhook_tool("lookup", "https://tools.example.com/lookup".to_string())]), "tools"),
+        (agent_request(vec![builtin_tool("current_time"), builtin_tool("current_time")]), "tools"),
+        (AgentChatRequest { max_iterations: Some(0), ..agent_request(vec![]) }, "max_iterations"),
+        (AgentChatRequest { tool_timeout_ms: Some(600_000), ..agent_request(vec![]) }, "tool_timeout_ms"),
+    ];
+    for (request, param) in cases {
+        let (status, Json(error)) = create_agent_chat(HeaderMap::new(), Json(request)).await.unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error.error.param.as_deref(), Some(param));
+    }
+    println!("✅ Invalid tools and loop limits rejected with 400");
+}
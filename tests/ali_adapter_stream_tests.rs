@@ -0,0 +1,95 @@
+use mockito::Server;
+use serde_json::json;
+
+use project_rust_learn::dao::{init_sqlite_pool, init_db};
+use project_rust_learn::llm_api::ali::client::AliClient;
+use project_rust_learn::llm_api::dispatcher::{AliAdapter, DispatchRequest, LLMClientAdapter, Provider};
+use project_rust_learn::llm_api::utils::{client::ClientConfig, msg_structure::Message};
+
+/// 初始化测试环境的辅助函数
+async fn setup_test_env() {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    init_db("data/init.sql").await.expect("DB init failed");
+}
+
+fn stream_chunk(content: Option<&str>, finish_reason: Option<&str>) -> serde_json::Value {
+    json!({
+        "id": "chatcmpl-test",
+        "object": "chat.completion.chunk",
+        "created": 1757412000,
+        "model": "qwen-turbo",
+        "choices": [{
+            "index": 0,
+            "delta": {"content": content},
+            "finish_reason": finish_reason
+        }]
+    })
+}
+
+#[tokio::test]
+async fn test_ali_adapter_generate_stream() {
+    setup_test_env().await;
+
+    println!("=== Testing Ali Adapter Streaming ===");
+
+    let mut server = Server::new_async().await;
+    let usage_chunk = json!({
+        "id": "chatcmpl-test",
+        "object": "chat.completion.chunk",
+        "created": 1757412000,
+        "model": "qwen-turbo",
+        "choices": [],
+        "usage": {"prompt_tokens": 8, "completion_tokens": 4, "total_tokens": 12}
+    });
+    let body = [
+        stream_chunk(Some("你好"), None),
+        stream_chunk(Some("，世界"), None),
+        stream_chunk(Some(""), Some("stop")),
+        usage_chunk,
+    ]
+    .iter()
+    .map(|chunk| format!("data: {}\n\n", chunk))
+    .collect::<String>()
+        + "data: [DONE]\n\n";
+
+    // 流式请求必须要求返回 usage
+    let mock = server.mock("POST", "/compatible-mode/v1/chat/completions")
+        .match_body(mockito::Matcher::PartialJson(json!({
+            "stream": true,
+            "stream_options": {"include_usage": true}
+        })))
+        .with_status(200)
+        .with_header("content-type", "text/event-stream")
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let http_client = reqwest::Client::builder().no_proxy().build().unwrap();
+    let client = AliClient::new_with_client("sk-test".to_string(), server.url(), ClientConfig::default(), http_client).unwrap();
+    let adapter = AliAdapter::new(client);
+    let request = DispatchRequest::new(
+        Provider::Ali,
+        "qwen-turbo".to_string(),
+        vec![Message::user("hello".to_string())],
+    ).with_stream(true);
+
+    let mut receiver = adapter.generate_stream(&request).await.unwrap();
+    let mut chunks = Vec::new();
+    while let Some(chunk) = receiver.recv().await {
+        chunks.push(chunk.expect("stream chunk failed"));
+    }
+    println!("✅ Received {} chunks", chunks.len());
+
+    let content: String = chunks.iter().map(|c| c.content.as_str()).collect();
+    assert_eq!(content, "你好，世界");
+
+    // finish_reason 与 usage 合并到同一个结束块
+    let finished: Vec<_> = chunks.iter().filter(|c| c.finish_reason.is_some() || c.usage.is_some()).collect();
+    assert_eq!(finished.len(), 1);
+    assert_eq!(finished[0].finish_reason.as_deref(), Some("stop"));
+    assert_eq!(finished[0].usage.as_ref().unwrap().total_tokens, 12);
+
+    mock.assert_async().await;
+
+    println!("\n=== Ali Adapter Streaming Tests Completed ===");
+}
@@ -1,3 +1,5 @@
+mod common;
+
 use project_rust_learn::dao::{
     provider_key_pool::{
         create_provider_key_pool_from_raw_key,
@@ -8,6 +10,7 @@ use sqlx::{SqlitePool, Row};
 
 /// 创建内存中的测试数据库
 async fn setup_test_db() -> SqlitePool {
+    common::install_test_master_keyring();
     let pool = SqlitePool::connect("sqlite::memory:").await
         .expect("Failed to create in-memory database");
     
@@ -23,6 +26,7 @@ async fn setup_test_db() -> SqlitePool {
             last_used_at TEXT,
             rate_limit_per_minute INTEGER,
             rate_limit_per_hour INTEGER,
+            project_id TEXT NOT NULL DEFAULT 'default',
             created_at TEXT DEFAULT (datetime('now', 'localtime'))
         );
     "#;
@@ -0,0 +1,100 @@
+//! # 对外 API 类型兼容性测试
+//!
+//! 检查 v1 类型的序列化字段名保持稳定、旧版本请求体可以反序列化，以及未知字段的处理方式
+
+use serde_json::json;
+
+use project_rust_learn::api_types::v1::chat_completion::ChatCompletionRequest;
+use project_rust_learn::api_types::v1::{DispatchRequest, DispatchResponse, Message, Provider, TokenUsage};
+
+#[test]
+fn test_dispatch_request_wire_format() {
+    println!("=== Testing DispatchRequest Wire Format ===");
+
+    let request = DispatchRequest::new(Provider::Ali, "qwen-plus".to_string(), vec![Message::user("hi".to_string())])
+        .with_max_tokens(64)
+        .with_tenant_id("acme".to_string());
+    let value = serde_json::to_value(&request).unwrap();
+
+    // v1 字段名是对外契约，只允许新增
+    assert_eq!(value, json!({
+        "provider": "Ali",
+        "model": "qwen-plus",
+        "messages": [{"role": "user", "content": "hi"}],
+        "stream": null,
+        "temperature": null,
+        "max_tokens": 64,
+        "top_p": null,
+        "frequency_penalty": null,
+        "presence_penalty": null,
+        "stop": null,
+        "timeout_ms": null,
+        "retry_count": null,
+        "context_window": null,
+        "user": null,
+        "tenant_id": "acme"
+    }));
+    println!("✅ DispatchRequest field names unchanged");
+
+    let custom = serde_json::to_value(Provider::Custom("zhipu".to_string())).unwrap();
+    assert_eq!(custom, json!({"Custom": "zhipu"}));
+    println!("✅ Provider serialization unchanged");
+}
+
+#[test]
+fn test_older_payloads_deserialize() {
+    println!("=== Testing Older Payloads ===");
+
+    // 只包含必填字段的请求体，后续新增的可选字段取默认值
+    let request: DispatchRequest = serde_json::from_value(json!({
+        "provider": "Ollama",
+        "model": "llama3.2",
+        "messages": [{"role": "user", "content": "hello"}]
+    })).expect("minimal dispatch request should deserialize");
+    assert_eq!(request.provider, Provider::Ollama);
+    assert!(request.tenant_id.is_none());
+
+    let response: DispatchResponse = serde_json::from_value(json!({
+        "content": "hi",
+        "provider": "Ollama",
+        "model": "llama3.2",
+        "created_at": "2025-01-01T00:00:00Z"
+    })).expect("minimal dispatch response should deserialize");
+    assert!(response.usage.is_none());
+    println!("✅ Minimal v1 payloads deserialize");
+}
+
+#[test]
+fn test_unknown_fields_policy() {
+    println!("=== Testing Unknown Fields Policy ===");
+
+    let usage = serde_json::from_value::<TokenUsage>(json!({
+        "prompt_tokens": 1,
+        "completion_tokens": 2,
+        "total_tokens": 3,
+        "reasoning_tokens": 0
+    }));
+    let chat = serde_json::from_value::<ChatCompletionRequest>(json!({
+        "model": "qwen-plus",
+        "messages": [{"role": "user", "content": "hi"}],
+        "temprature": 0.5
+    }));
+
+    if cfg!(feature = "strict-api-types") {
+        assert!(usage.is_err());
+        assert!(chat.is_err());
+        println!("✅ Unknown fields rejected in strict mode");
+    } else {
+        assert_eq!(usage.unwrap().total_tokens, 3);
+        assert!(chat.is_ok());
+        println!("✅ Unknown fields ignored by default");
+    }
+
+    // Message 同时用于解析供应商响应，始终忽略未知字段
+    let message: Message = serde_json::from_value(json!({
+        "role": "assistant",
+        "content": "hi",
+        "reasoning_content": "..."
+    })).expect("message should ignore unknown fields");
+    assert_eq!(message.content, "hi");
+}
@@ -0,0 +1,130 @@
+//! # 管理操作审计日志测试
+//!
+//! 测试供应商和 API Key 的创建/更新/删除经过审计中间件后写入审计日志：记录操作人、资源 ID 和变更前后的快照，
+//! 快照中不包含 API Key 的明文和密文；只读请求不记录；审计日志可按资源和操作人过滤
+
+mod common;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Method, Request, StatusCode},
+    middleware::from_fn,
+    response::Response,
+    routing::{delete, get, post},
+    Router,
+};
+use serde_json::{json, Value};
+use tower::Service;
+
+use project_rust_learn::dao::cache::init_global_cache;
+use project_rust_learn::dao::audit_log::{list_audit_logs, AuditLogFilter};
+use project_rust_learn::dao::{init_db, init_sqlite_pool, SQLITE_POOL};
+use project_rust_learn::web::handlers::api_key_handler::{create_api_key, delete_api_key};
+use project_rust_learn::web::handlers::audit_handler::get_audit_logs;
+use project_rust_learn::web::handlers::provider_handler::{
+    create_new_provider, delete_existing_provider, get_provider, update_existing_provider,
+};
+use project_rust_learn::web::middleware::audit::{admin_audit, ADMIN_USER_HEADER};
+
+fn app() -> Router {
+    let api_routes = Router::new()
+        .route("/providers", post(create_new_provider))
+        .route("/providers/:id", get(get_provider).put(update_existing_provider).delete(delete_existing_provider))
+        .route("/providers/:id/api-keys", post(create_api_key))
+        .route("/api-keys/:id", delete(delete_api_key))
+        .route("/audit", get(get_audit_logs))
+        .route_layer(from_fn(admin_audit));
+    Router::new().nest("/api", api_routes)
+}
+
+async fn send(app: &mut Router, method: Method, uri: &str, body: Option<Value>) -> Response {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(ADMIN_USER_HEADER, "alice")
+        .header(header::CONTENT_TYPE, "application/json");
+    let body = body.map_or_else(Body::empty, |body| Body::from(body.to_string()));
+    app.call(request.body(body).unwrap()).await.unwrap()
+}
+
+async fn body_json(response: Response) -> Value {
+    serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn test_admin_mutations_are_audited() {
+    common::install_test_master_keyring();
+    init_sqlite_pool("sqlite://data/app.db").await;
+    init_db("data/init.sql").await.expect("DB init failed");
+    let pool = SQLITE_POOL.get().unwrap().clone();
+    init_global_cache(&pool, 3600, 1000).await.expect("Cache init failed");
+    let mut app = app();
+
+    println!("=== Testing Audit Log ===");
+    let name = format!("audit-{}", uuid::Uuid::new_v4().simple());
+    let response = send(&mut app, Method::POST, "/api/providers", Some(json!({
+        "name": name,
+        "display_name": "Audit Test",
+        "base_url": "http://localhost:1",
+    }))).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    // 中间件读取响应体后原样返回
+    let provider_id = body_json(response).await["id"].as_str().unwrap().to_string();
+
+    let response = send(&mut app, Method::GET, &format!("/api/providers/{}", provider_id), None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = send(&mut app, Method::PUT, &format!("/api/providers/{}", provider_id), Some(json!({
+        "display_name": "Audit Test Renamed",
+    }))).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let filter = AuditLogFilter { resource_id: Some(provider_id.clone()), ..AuditLogFilter::default() };
+    let logs = list_audit_logs(&pool, &filter).await.expect("list audit logs failed");
+    assert_eq!(logs.len(), 2, "GET requests are not audited");
+    let update = logs.iter().find(|log| log.action == "update").expect("update not audited");
+    assert_eq!(update.actor, "alice");
+    assert_eq!(update.path, format!("/api/providers/{}", provider_id));
+    assert!(update.before_snapshot.as_deref().unwrap().contains("\"Audit Test\""));
+    assert!(update.after_snapshot.as_deref().unwrap().contains("Audit Test Renamed"));
+    let create = logs.iter().find(|log| log.action == "create").expect("create not audited");
+    assert!(create.before_snapshot.is_none());
+    assert!(create.after_snapshot.as_deref().unwrap().contains(&name));
+    println!("✅ Provider create and update audited with before/after snapshots");
+
+    let secret = format!("sk-audit-{}", uuid::Uuid::new_v4().simple());
+    let response = send(&mut app, Method::POST, &format!("/api/providers/{}/api-keys", provider_id), Some(json!({
+        "provider_id": provider_id,
+        "api_key": secret,
+    }))).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let key_id = body_json(response).await["id"].as_str().unwrap().to_string();
+    let response = send(&mut app, Method::DELETE, &format!("/api/api-keys/{}", key_id), None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let filter = AuditLogFilter {
+        resource_type: Some("api_key".to_string()),
+        resource_id: Some(key_id.clone()),
+        ..AuditLogFilter::default()
+    };
+    let logs = list_audit_logs(&pool, &filter).await.expect("list audit logs failed");
+    assert_eq!(logs.len(), 2);
+    let created = logs.iter().find(|log| log.action == "create").unwrap().after_snapshot.clone().unwrap();
+    let deleted = logs.iter().find(|log| log.action == "delete").unwrap();
+    assert!(deleted.after_snapshot.is_none());
+    for snapshot in [created.as_str(), deleted.before_snapshot.as_deref().unwrap()] {
+        assert!(snapshot.contains(&key_id));
+        assert!(!snapshot.contains(&secret));
+        assert!(!snapshot.contains("encrypted_key_value") && !snapshot.contains("key_hash"));
+    }
+    println!("✅ API key snapshots exclude key material");
+
+    let response = send(&mut app, Method::DELETE, &format!("/api/providers/{}", provider_id), None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = send(&mut app, Method::GET, &format!("/api/audit?resource_id={}&action=delete", provider_id), None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let logs = body_json(response).await;
+    assert_eq!(logs.as_array().unwrap().len(), 1);
+    assert_eq!(logs[0]["resource_type"], "provider");
+    assert!(logs[0]["before_snapshot"].as_str().unwrap().contains("Audit Test Renamed"));
+    println!("✅ Audit logs queryable via GET /api/audit");
+}
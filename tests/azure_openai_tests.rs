@@ -0,0 +1,175 @@
+//! # Azure OpenAI 测试集
+//!
+//! 测试内容：
+//! - 按部署寻址的 URL、api-version 参数和 api-key 认证头，部署名称的百分号编码
+//! - 流式响应解析
+//! - 工厂根据 providers 表的 base_url 和 config 创建适配器
+
+use mockito::{Matcher, Server};
+use serde_json::json;
+use sqlx::SqlitePool;
+
+use project_rust_learn::dao::run_migrations;
+use project_rust_learn::dao::provider::{Provider as ProviderRecord, create_provider};
+use project_rust_learn::llm_api::azure::client::AzureOpenAIClient;
+use project_rust_learn::llm_api::dispatcher::{DispatchRequest, LLMDispatcher, LLMError, Provider};
+use project_rust_learn::llm_api::openai::client::OpenAIChatRequest;
+use project_rust_learn::llm_api::provider_registry::{AzureOpenAIFactory, ProviderConfig, ProviderFactory};
+use project_rust_learn::llm_api::utils::{chat_traits::ChatResponseTrait, msg_structure::Message};
+
+/// 初始化测试环境的辅助函数：测试需要写入名为 azure 的供应商，
+/// 使用独立的数据库，避免覆盖开发环境中真实的 Azure 配置
+async fn setup_test_env() -> (SqlitePool, std::path::PathBuf) {
+    let path = std::env::temp_dir().join(format!("azure-openai-test-{}.db", uuid::Uuid::new_v4().simple()));
+    let pool = SqlitePool::connect(&format!("sqlite://{}?mode=rwc", path.display())).await.unwrap();
+    run_migrations(&pool).await.expect("migration failed");
+    (pool, path)
+}
+
+fn create_request(deployment: &str) -> OpenAIChatRequest {
+    OpenAIChatRequest::new(deployment.to_string(), vec![Message::user("Hello!".to_string())])
+}
+
+#[tokio::test]
+async fn test_azure_chat_uses_deployment_url() {
+    let mut server = Server::new_async().await;
+    let mock = server.mock("POST", "/openai/deployments/prod-gpt4o/chat/completions")
+        .match_query(Matcher::UrlEncoded("api-version".to_string(), "2024-06-01".to_string()))
+        .match_header("api-key", "az-key")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!({
+            "id": "chatcmpl-azure",
+            "object": "chat.completion",
+            "created": 1757412000,
+            "model": "gpt-4o-2024-08-06",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "Hi from Azure"},
+                "finish_reason": "stop"
+            }],
+            "usage": {"prompt_tokens": 8, "completion_tokens": 4, "total_tokens": 12}
+        }).to_string())
+        .create_async()
+        .await;
+
+    let client = AzureOpenAIClient::new("az-key".to_string(), format!("{}/", server.url())).unwrap();
+    let response = client.chat(create_request("prod-gpt4o")).await.expect("chat failed");
+
+    assert_eq!(response.get_content().as_deref(), Some("Hi from Azure"));
+    assert_eq!(response.get_eval_count(), Some(4));
+    println!("✅ Azure 按部署寻址成功");
+
+    mock.assert_async().await;
+}
+
+#[test]
+fn test_azure_deployment_url_is_encoded() {
+    let client = AzureOpenAIClient::new("az-key".to_string(), "https://res.openai.azure.com/".to_string()).unwrap();
+    assert_eq!(
+        client.deployment_url("prod-gpt4o"),
+        "https://res.openai.azure.com/openai/deployments/prod-gpt4o/chat/completions?api-version=2024-06-01"
+    );
+    assert_eq!(
+        client.deployment_url("../models/x?y=1#z"),
+        "https://res.openai.azure.com/openai/deployments/..%2Fmodels%2Fx%3Fy%3D1%23z/chat/completions?api-version=2024-06-01"
+    );
+    println!("✅ 部署名称经过百分号编码，不会改变请求路径");
+}
+
+#[tokio::test]
+async fn test_azure_chat_stream() {
+    let mut server = Server::new_async().await;
+    let body = [
+        json!({"id": "1", "object": "chat.completion.chunk", "created": 1, "model": "gpt-4o",
+               "choices": [{"index": 0, "delta": {"content": "Hi"}, "finish_reason": null}]}),
+        json!({"id": "1", "object": "chat.completion.chunk", "created": 1, "model": "gpt-4o",
+               "choices": [{"index": 0, "delta": {}, "finish_reason": "stop"}]}),
+    ]
+        .iter()
+        .map(|c| format!("data: {}\n\n", c))
+        .collect::<String>()
+        + "data: [DONE]\n\n";
+
+    let mock = server.mock("POST", "/openai/deployments/prod-gpt4o/chat/completions")
+        .match_query(Matcher::UrlEncoded("api-version".to_string(), "2024-10-21".to_string()))
+        .match_body(Matcher::PartialJson(json!({"stream": true})))
+        .with_status(200)
+        .with_header("content-type", "text/event-stream")
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let client = AzureOpenAIClient::new_with_api_version(
+        "az-key".to_string(), server.url(), "2024-10-21".to_string(),
+    ).unwrap();
+    let mut chunks = Vec::new();
+    client.chat_stream(create_request("prod-gpt4o"), |chunk| {
+        chunks.push(chunk);
+        true
+    }).await.expect("chat_stream failed");
+
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(chunks[0].choices[0].delta.content.as_deref(), Some("Hi"));
+    assert_eq!(chunks[1].choices[0].finish_reason.as_deref(), Some("stop"));
+
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_azure_factory_config() {
+    let factory = AzureOpenAIFactory;
+
+    // 缺少资源地址
+    let config = ProviderConfig::new(Provider::Azure, "azure", None)
+        .with_settings(Some(json!({"deployments": {"gpt-4o": "prod-gpt4o"}})));
+    assert!(factory.create_adapter(&config).is_err());
+
+    // 缺少部署映射
+    let config = ProviderConfig::new(Provider::Azure, "azure", Some("https://res.openai.azure.com"));
+    assert!(factory.create_adapter(&config).is_err());
+
+    let config = ProviderConfig::new(Provider::Azure, "azure", Some("https://res.openai.azure.com/"))
+        .with_settings(Some(json!({
+            "api_version": "2024-10-21",
+            "deployments": {"gpt-4o": "prod-gpt4o", "gpt-4o-mini": "prod-mini"}
+        })));
+    let adapter = factory.create_adapter(&config).expect("create_adapter failed");
+    assert_eq!(adapter.provider_name(), Provider::Azure);
+    assert_eq!(adapter.supported_models(), vec!["gpt-4o", "gpt-4o-mini"]);
+
+    // 未映射的模型不会发出请求
+    let request = DispatchRequest::new(Provider::Azure, "gpt-4.1".to_string(), vec![Message::user("hi".to_string())]);
+    assert!(matches!(adapter.generate(&request).await, Err(LLMError::ModelNotAvailable(_))));
+    println!("✅ Azure 工厂配置校验成功");
+}
+
+#[tokio::test]
+async fn test_sync_azure_provider_from_db() {
+    let (pool, path) = setup_test_env().await;
+
+    let record = ProviderRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: "azure".to_string(),
+        display_name: "Azure OpenAI".to_string(),
+        base_url: Some("https://res.openai.azure.com".to_string()),
+        description: None,
+        config: Some(json!({"deployments": {"gpt-4o": "prod-gpt4o"}}).to_string()),
+        is_active: true,
+        project_id: None,
+        max_concurrent_requests: None,
+        created_at: None,
+        updated_at: None,
+    };
+    create_provider(&pool, &record).await.expect("create_provider failed");
+
+    let dispatcher = LLMDispatcher::new(None);
+    let registered = dispatcher.sync_providers_from_db(&pool).await.expect("sync failed");
+    assert!(registered.contains(&Provider::Azure));
+    let models = dispatcher.list_models(Some(Provider::Azure)).await;
+    assert_eq!(models.get(&Provider::Azure), Some(&vec!["gpt-4o".to_string()]));
+    println!("✅ Azure 供应商已根据数据库配置注册");
+
+    pool.close().await;
+    std::fs::remove_file(path).ok();
+}
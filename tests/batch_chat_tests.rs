@@ -0,0 +1,197 @@
+//! # 批量 Chat 测试
+//!
+//! 测试 dispatch_batch 的并发上限和结果顺序，以及 /v1/batch/chat 提交任务、
+//! 完成后查询结果、单个请求失败不影响其他请求、按项目隔离和每个调用方执行中的任务数上限
+
+mod common;
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use std::time::Duration;
+use async_trait::async_trait;
+use axum::{extract::Path, http::StatusCode, Json};
+use serde_json::json;
+
+use project_rust_learn::jobs::batch_chat::MAX_ACTIVE_BATCH_JOBS;
+use project_rust_learn::llm_api::dispatcher::{
+    DispatchConfig, DispatchRequest, DispatchResponse, LLMClientAdapter, LLMDispatcher, LLMError, Provider, StreamReceiver,
+    GLOBAL_DISPATCHER,
+};
+use project_rust_learn::llm_api::utils::client::{CallMetadata, CALL_METADATA};
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+use project_rust_learn::web::dto::batch_dto::BatchChatRequest;
+use project_rust_learn::web::handlers::batch_handler::{create_batch_chat, get_batch_chat};
+use common::response;
+
+/// 回显最后一条消息的适配器，记录同时执行的最大请求数；内容为 "fail" 时返回错误
+struct EchoAdapter {
+    provider: Provider,
+    running: AtomicUsize,
+    max_running: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl LLMClientAdapter for EchoAdapter {
+    async fn generate(&self, request: &DispatchRequest) -> Result<DispatchResponse, LLMError> {
+        let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_running.fetch_max(running, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        self.running.fetch_sub(1, Ordering::SeqCst);
+
+        let content = request.messages.last().map(|m| m.content.clone()).unwrap_or_default();
+        if content == "fail" {
+            return Err(LLMError::InvalidParameters("cannot answer".to_string()));
+        }
+        Ok(response(self.provider.clone(), &request.model, format!("echo: {}", content)))
+    }
+
+    async fn generate_stream(&self, _request: &DispatchRequest) -> Result<StreamReceiver, LLMError> {
+        Err(LLMError::InvalidParameters("stream not supported".to_string()))
+    }
+
+    fn supported_models(&self) -> Vec<String> {
+        vec!["batch-model".to_string()]
+    }
+
+    fn provider_name(&self) -> Provider {
+        self.provider.clone()
+    }
+}
+
+async fn echo_dispatcher() -> (LLMDispatcher, Provider, Arc<AtomicUsize>) {
+    // 使用唯一的自定义供应商，避免影响其他测试
+    let provider = Provider::Custom(format!("batch-{}", uuid::Uuid::new_v4().simple()));
+    let max_running = Arc::new(AtomicUsize::new(0));
+    let dispatcher = LLMDispatcher::new(Some(DispatchConfig { enable_fallback: false, default_retry_count: 0, ..Default::default() }));
+    dispatcher.register_client(Box::new(EchoAdapter {
+        provider: provider.clone(),
+        running: AtomicUsize::new(0),
+        max_running: max_running.clone(),
+    })).await;
+    (dispatcher, provider, max_running)
+}
+
+fn in_project(project_id: &str) -> CallMetadata {
+    CallMetadata::default().with_project(Some(project_id.to_string()))
+}
+
+#[tokio::test]
+async fn test_dispatch_batch_bounded_concurrency() {
+    let (dispatcher, provider, max_running) = echo_dispatcher().await;
+
+    println!("=== Testing dispatch_batch ===");
+    let requests: Vec<DispatchRequest> = ["a", "b", "fail", "d", "e", "f"].iter()
+        .map(|content| DispatchRequest::new(provider.clone(), "batch-model".to_string(), vec![Message::user(content.to_string())]))
+        .collect();
+    let results = dispatcher.dispatch_batch(requests, 2).await;
+
+    assert_eq!(results.len(), 6);
+    assert_eq!(results[0].as_ref().unwrap().content, "echo: a");
+    assert!(matches!(results[2], Err(LLMError::InvalidParameters(_))));
+    assert_eq!(results[5].as_ref().unwrap().content, "echo: f");
+    assert_eq!(max_running.load(Ordering::SeqCst), 2);
+    println!("✅ Results in order, at most 2 requests in flight");
+}
+
+#[tokio::test]
+async fn test_batch_chat_endpoint() {
+    let (dispatcher, _, _) = echo_dispatcher().await;
+    GLOBAL_DISPATCHER.set(Arc::new(dispatcher)).ok();
+    let project = format!("batch-project-{}", uuid::Uuid::new_v4().simple());
+
+    println!("=== Testing /v1/batch/chat ===");
+    let request: BatchChatRequest = serde_json::from_value(json!({
+        "concurrency": 2,
+        "requests": [
+            {"custom_id": "q1", "body": {"model": "batch-model", "messages": [{"role": "user", "content": "hello"}]}},
+            {"custom_id": "q2", "body": {"model": "batch-model", "messages": [{"role": "user", "content": "fail"}]}},
+            {"body": {"model": "batch-model", "messages": [{"role": "user", "content": "bye"}]}}
+        ]
+    })).unwrap();
+    let (status, Json(job)) = CALL_METADATA.scope(in_project(&project), create_batch_chat(Json(request)))
+        .await
+        .expect("submit failed");
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!(job.total, 3);
+    assert!(job.results.is_none());
+    println!("✅ Batch submitted: {}", job.id);
+
+    let mut completed = None;
+    for _ in 0..100 {
+        let Json(current) = CALL_METADATA.scope(in_project(&project), get_batch_chat(Path(job.id.clone())))
+            .await
+            .expect("get failed");
+        if current.status == "completed" {
+            completed = Some(current);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let completed = completed.expect("batch did not complete");
+    assert_eq!((completed.succeeded, completed.failed), (2, 1));
+    let results = completed.results.unwrap();
+    assert_eq!(results[0].custom_id.as_deref(), Some("q1"));
+    assert_eq!(results[0].response.as_ref().unwrap().choices[0].message.content, "echo: hello");
+    assert_eq!(results[1].error.as_ref().unwrap().error_type, "invalid_request_error");
+    assert_eq!(results[2].response.as_ref().unwrap().choices[0].message.content, "echo: bye");
+    println!("✅ Results retrieved after completion");
+
+    // 其他项目看不到该任务
+    let (status, Json(error)) = CALL_METADATA.scope(in_project("other-project"), get_batch_chat(Path(job.id.clone())))
+        .await
+        .unwrap_err();
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(error.error.code.as_deref(), Some("batch_not_found"));
+
+    // 任一请求的模型不存在时整个任务不提交
+    let invalid: BatchChatRequest = serde_json::from_value(json!({
+        "requests": [
+            {"body": {"model": "batch-model", "messages": [{"role": "user", "content": "ok"}]}},
+            {"body": {"model": "missing-model", "messages": [{"role": "user", "content": "ok"}]}}
+        ]
+    })).unwrap();
+    let (status, Json(error)) = CALL_METADATA.scope(in_project(&project), create_batch_chat(Json(invalid)))
+        .await
+        .unwrap_err();
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(error.error.param.as_deref(), Some("requests[1].body.model"));
+    println!("✅ Invalid batch rejected");
+}
+
+#[tokio::test]
+async fn test_active_batch_jobs_limited_per_consumer() {
+    let (dispatcher, _, _) = echo_dispatcher().await;
+    GLOBAL_DISPATCHER.set(Arc::new(dispatcher)).ok();
+    let project = format!("batch-project-{}", uuid::Uuid::new_v4().simple());
+    let consumer = |id: &str| in_project(&project).with_consumer(Some(id.to_string()));
+    // 并发为 1 时每个任务至少执行 20 x 20ms，提交期间都不会完成
+    let slow_batch = || -> BatchChatRequest {
+        let requests: Vec<_> = (0..20)
+            .map(|i| json!({"body": {"model": "batch-model", "messages": [{"role": "user", "content": format!("item {}", i)}]}}))
+            .collect();
+        serde_json::from_value(json!({"concurrency": 1, "requests": requests})).unwrap()
+    };
+
+    println!("=== Testing Active Batch Job Limit ===");
+    for _ in 0..MAX_ACTIVE_BATCH_JOBS {
+        let (status, _) = CALL_METADATA.scope(consumer("busy"), create_batch_chat(Json(slow_batch())))
+            .await
+            .expect("submit failed");
+        assert_eq!(status, StatusCode::ACCEPTED);
+    }
+    let (status, Json(error)) = CALL_METADATA.scope(consumer("busy"), create_batch_chat(Json(slow_batch())))
+        .await
+        .unwrap_err();
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(error.error.code.as_deref(), Some("rate_limit_exceeded"));
+    println!("✅ Submission beyond {} active jobs rejected", MAX_ACTIVE_BATCH_JOBS);
+
+    // 其他调用方不受影响
+    let (status, _) = CALL_METADATA.scope(consumer("idle"), create_batch_chat(Json(slow_batch())))
+        .await
+        .expect("submit failed");
+    assert_eq!(status, StatusCode::ACCEPTED);
+    println!("✅ Other consumers can still submit");
+}
@@ -0,0 +1,85 @@
+use project_rust_learn::dao::{init_sqlite_pool, init_db, SQLITE_POOL};
+use project_rust_learn::dao::blocklist::{
+    BlocklistEntry, create_blocklist_entry, get_blocklist_entry_by_id, list_blocklist_entries_by_tenant,
+    list_global_blocklist_entries, update_blocklist_entry, delete_blocklist_entry
+};
+use project_rust_learn::llm_api::utils::blocklist::{
+    get_blocklist, reload_blocklist, BlocklistAction, BlocklistTarget
+};
+use std::sync::Arc;
+use sqlx::{Pool, Sqlite};
+
+/// 初始化测试环境的辅助函数
+async fn setup_test_env() -> Arc<Pool<Sqlite>> {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+    init_db("data/init.sql").await.expect("DB init failed");
+    pool
+}
+
+#[tokio::test]
+async fn test_blocklist_crud_and_reload() {
+    let pool = setup_test_env().await;
+
+    println!("=== Testing Blocklist CRUD Operations ===");
+
+    let tenant_id = format!("tenant-{}", uuid::Uuid::new_v4());
+    let pattern = format!("badword{}", uuid::Uuid::new_v4().simple());
+
+    // Test 1: Create a global rule and a tenant override
+    let global_entry = BlocklistEntry {
+        id: uuid::Uuid::new_v4().to_string(),
+        tenant_id: None,
+        pattern: pattern.clone(),
+        action: "block".to_string(),
+        scope: "both".to_string(),
+        is_active: true,
+        created_at: None,
+        updated_at: None,
+    };
+    let tenant_entry = BlocklistEntry {
+        id: uuid::Uuid::new_v4().to_string(),
+        tenant_id: Some(tenant_id.clone()),
+        pattern: pattern.clone(),
+        action: "mask".to_string(),
+        scope: "prompt".to_string(),
+        is_active: true,
+        created_at: None,
+        updated_at: None,
+    };
+    assert_eq!(create_blocklist_entry(&pool, &global_entry).await.expect("create global entry failed"), 1);
+    assert_eq!(create_blocklist_entry(&pool, &tenant_entry).await.expect("create tenant entry failed"), 1);
+    println!("✅ Created global and tenant blocklist entries");
+
+    // Test 2: List by scope
+    let globals = list_global_blocklist_entries(&pool).await.expect("list_global_blocklist_entries failed");
+    assert!(globals.iter().any(|e| e.id == global_entry.id));
+    let tenant_entries = list_blocklist_entries_by_tenant(&pool, &tenant_id).await.expect("list_blocklist_entries_by_tenant failed");
+    assert_eq!(tenant_entries.len(), 1);
+
+    // Test 3: Reload engine and evaluate
+    reload_blocklist(&pool).await.expect("reload_blocklist failed");
+    let text = format!("hello {}", pattern);
+    let verdict = get_blocklist().evaluate(None, &text, BlocklistTarget::Prompt).await;
+    assert!(verdict.is_blocked());
+    let verdict = get_blocklist().evaluate(Some(&tenant_id), &text, BlocklistTarget::Prompt).await;
+    assert_eq!(verdict.action, Some(BlocklistAction::Mask));
+    assert!(!verdict.text.contains(&pattern));
+    println!("✅ Tenant override applied");
+
+    // Test 4: Update the global rule to flag
+    let mut updated = global_entry.clone();
+    updated.action = "flag".to_string();
+    assert_eq!(update_blocklist_entry(&pool, &updated).await.expect("update_blocklist_entry failed"), 1);
+    let fetched = get_blocklist_entry_by_id(&pool, &global_entry.id).await.expect("get failed").unwrap();
+    assert_eq!(fetched.action, "flag");
+
+    // Test 5: Delete
+    assert_eq!(delete_blocklist_entry(&pool, &global_entry.id).await.expect("delete failed"), 1);
+    assert_eq!(delete_blocklist_entry(&pool, &tenant_entry.id).await.expect("delete failed"), 1);
+    reload_blocklist(&pool).await.expect("reload_blocklist failed");
+    let verdict = get_blocklist().evaluate(None, &text, BlocklistTarget::Prompt).await;
+    assert_eq!(verdict.action, None);
+
+    println!("\n=== Blocklist Tests Completed ===");
+}
@@ -0,0 +1,79 @@
+//! # 缓存统计与失效接口测试
+//!
+//! 测试缓存移除计数、`/api/cache/stats` 的按命名空间统计，以及 `/api/cache/invalidate` 按前缀失效
+
+use std::time::Duration;
+use axum::Json;
+use serde_json::json;
+
+use project_rust_learn::dao::{init_sqlite_pool, init_db, SQLITE_POOL};
+use project_rust_learn::dao::cache::{cache::CacheService, get_global_cache, init_global_cache, GLOBAL_CACHE_NAME};
+use project_rust_learn::web::handlers::cache_handler::{get_cache_stats, invalidate_cache};
+
+async fn setup_test_env() {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+    init_db("data/init.sql").await.expect("DB init failed");
+    init_global_cache(&pool, 3600, 1000).await.expect("Cache init failed");
+}
+
+#[tokio::test]
+async fn test_cache_eviction_stats() {
+    println!("=== Testing Cache Eviction Stats ===");
+
+    let cache = CacheService::<String, String>::new(Duration::from_secs(60), 100).with_name("eviction-test");
+    cache.insert_with_ttl("short".to_string(), "value".to_string(), Duration::from_millis(50)).await;
+    cache.insert("kept".to_string(), "value".to_string()).await;
+    cache.insert("kept".to_string(), "replaced".to_string()).await;
+    cache.insert("removed".to_string(), "value".to_string()).await;
+    cache.invalidate(&"removed".to_string()).await;
+    // 过期条目由定时轮（约 1 秒粒度）清理后才计入移除数
+    tokio::time::sleep(Duration::from_millis(1200)).await;
+    assert_eq!(cache.get(&"short".to_string()).await, None);
+    assert!(cache.get(&"kept".to_string()).await.is_some());
+
+    let stats = cache.stats().await;
+    assert_eq!(stats.entries, 1);
+    assert_eq!((stats.hits.hits, stats.hits.misses), (1, 1));
+    assert_eq!(stats.evictions["expired"], 1);
+    assert_eq!(stats.evictions["explicit"], 1);
+    assert_eq!(stats.evictions["size"], 0);
+    println!("✅ Evictions counted by cause: {:?}", stats.evictions);
+}
+
+#[tokio::test]
+async fn test_cache_stats_and_invalidate_endpoints() {
+    setup_test_env().await;
+
+    println!("=== Testing Cache Endpoints ===");
+    let namespace = format!("cache-api-{}", uuid::Uuid::new_v4().simple());
+    let entries = get_global_cache().namespace::<u64>(&namespace);
+    for (key, value) in [("openai:a", 1), ("openai:b", 2), ("ollama:c", 3)] {
+        entries.insert(key, &value).await.expect("insert failed");
+    }
+
+    let stats = get_cache_stats().await.0;
+    let global = &stats.caches[GLOBAL_CACHE_NAME];
+    assert!(global.stats.entries >= 3);
+    assert_eq!(global.namespaces.as_ref().unwrap()[&namespace], 3);
+    assert!(stats.caches.contains_key("degraded_responses"));
+    let body = serde_json::to_value(&stats).unwrap();
+    assert!(body["caches"]["global"]["hits"].is_number());
+    assert!(body["caches"]["global"]["evictions"]["expired"].is_number());
+    println!("✅ Stats grouped by namespace");
+
+    let request = serde_json::from_value(json!({ "prefix": format!("{}:openai:", namespace), "reload": false })).unwrap();
+    let response = invalidate_cache(Json(request)).await.expect("invalidate failed").0;
+    assert_eq!(response.invalidated, 2);
+    assert!(!response.reloaded);
+    assert_eq!(entries.get("openai:a").await, None);
+    assert_eq!(entries.get("ollama:c").await, Some(3));
+    println!("✅ Invalidated entries by prefix");
+
+    // 默认失效后重新预加载
+    let request = serde_json::from_value(json!({ "prefix": format!("{}:", namespace) })).unwrap();
+    let response = invalidate_cache(Json(request)).await.expect("invalidate failed").0;
+    assert_eq!(response.invalidated, 1);
+    assert!(response.reloaded);
+    println!("✅ Reloaded preloaded entries after invalidation");
+}
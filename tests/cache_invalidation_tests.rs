@@ -0,0 +1,194 @@
+//! # 管理接口缓存同步测试
+//!
+//! 测试通过管理接口新增、修改、启停、删除 API Key 和模型后，缓存与轮询池立即生效，无需重启；
+//! 以及绕过管理接口的模型修改由定期对账修正
+
+mod common;
+
+use axum::{extract::Path, Json};
+use sqlx::{Pool, Sqlite};
+
+use project_rust_learn::dao::{init_sqlite_pool, init_db, SQLITE_POOL};
+use project_rust_learn::dao::cache::init_global_cache;
+use project_rust_learn::dao::model::{
+    create_model, delete_model, get_active_model_names_from_cache, get_model_from_cache, update_model, Model,
+};
+use project_rust_learn::dao::provider::{create_provider, hard_delete_provider, Provider};
+use project_rust_learn::dao::provider_key_pool::{
+    get_active_key_count, get_api_key_round_robin, get_provider_key_pool_from_cache,
+};
+use project_rust_learn::jobs::model_cache_reconcile::run_model_cache_reconcile;
+use project_rust_learn::web::dto::api_key_dto::{CreateApiKeyRequest, UpdateApiKeyRequest};
+use project_rust_learn::web::dto::model_dto::UpdateModelRequest;
+use project_rust_learn::web::handlers::api_key_handler::{
+    create_api_key, delete_api_key, toggle_api_key_status, update_api_key,
+};
+use project_rust_learn::web::handlers::model_handler::{delete_existing_model, update_existing_model};
+
+/// 初始化测试环境的辅助函数
+async fn setup_test_env() -> std::sync::Arc<Pool<Sqlite>> {
+    common::install_test_master_keyring();
+    init_sqlite_pool("sqlite://data/app.db").await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+    init_db("data/init.sql").await.expect("DB init failed");
+    init_global_cache(&pool, 3600, 1000).await.expect("Cache init failed");
+    pool
+}
+
+async fn create_test_provider(pool: &Pool<Sqlite>) -> Provider {
+    let name = format!("cache-sync-{}", uuid::Uuid::new_v4().simple());
+    let provider = Provider {
+        id: uuid::Uuid::new_v4().to_string(),
+        display_name: name.clone(),
+        name,
+        base_url: None,
+        description: None,
+        config: None,
+        is_active: true,
+        project_id: None,
+        max_concurrent_requests: None,
+        created_at: None,
+        updated_at: None,
+    };
+    create_provider(pool, &provider).await.expect("create provider failed");
+    provider
+}
+
+#[tokio::test]
+async fn test_api_key_changes_take_effect_immediately() {
+    let pool = setup_test_env().await;
+    let provider = create_test_provider(&pool).await;
+
+    println!("=== Testing API Key Cache Sync ===");
+    let created = create_api_key(Json(CreateApiKeyRequest {
+        provider_id: provider.id.clone(),
+        api_key: "sk-cache-sync-test".to_string(),
+        rate_limit_per_minute: None,
+        rate_limit_per_hour: None,
+        project_id: None,
+    })).await.expect("create failed").0;
+    let key_id = created["id"].as_str().expect("missing id").to_string();
+
+    assert_eq!(get_active_key_count(&provider.name).await, 1);
+    let (api_key, selected_id) = get_api_key_round_robin(&provider.name).await.expect("key not in rotation");
+    assert_eq!(api_key, "sk-cache-sync-test");
+    assert_eq!(selected_id, key_id);
+    println!("✅ New key available without restart");
+
+    let _ = update_api_key(Path(key_id.clone()), Json(UpdateApiKeyRequest {
+        is_active: None,
+        rate_limit_per_minute: Some(42),
+        rate_limit_per_hour: None,
+        project_id: None,
+    })).await.expect("update failed");
+    let cached = get_provider_key_pool_from_cache(&provider.name, &key_id).await.expect("key not cached");
+    assert_eq!(cached.rate_limit_per_minute, Some(42));
+    println!("✅ Updated key refreshed in cache");
+
+    let _ = toggle_api_key_status(Path((key_id.clone(), false))).await.expect("toggle failed");
+    assert_eq!(get_active_key_count(&provider.name).await, 0);
+    assert!(get_api_key_round_robin(&provider.name).await.is_none());
+    let _ = toggle_api_key_status(Path((key_id.clone(), true))).await.expect("toggle failed");
+    assert_eq!(get_active_key_count(&provider.name).await, 1);
+    println!("✅ Toggled key removed from and restored to rotation");
+
+    let _ = delete_api_key(Path(key_id.clone())).await.expect("delete failed");
+    assert_eq!(get_active_key_count(&provider.name).await, 0);
+    assert!(get_provider_key_pool_from_cache(&provider.name, &key_id).await.is_none());
+    println!("✅ Deleted key evicted from cache");
+
+    hard_delete_provider(&pool, &provider.id).await.expect("cleanup failed");
+}
+
+#[tokio::test]
+async fn test_model_changes_take_effect_immediately() {
+    let pool = setup_test_env().await;
+    let provider = format!("cache-sync-{}", uuid::Uuid::new_v4().simple());
+
+    println!("=== Testing Model Cache Sync ===");
+    let model = Model {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: "cache-sync-model".to_string(),
+        provider: provider.clone(),
+        model_type: "llm".to_string(),
+        base_url: None,
+        is_active: true,
+        health_status: None,
+        last_health_check: None,
+        health_check_interval_seconds: None,
+        cost_per_token_input: Some(0.001),
+        cost_per_token_output: Some(0.002),
+        function_tags: None,
+        config: None,
+        project_id: None,
+        created_at: None,
+        updated_at: None,
+    };
+    create_model(&pool, &model).await.expect("create model failed");
+
+    let _ = update_existing_model(Path(model.id.clone()), Json(UpdateModelRequest {
+        display_name: None,
+        base_url: None,
+        is_active: None,
+        cost_per_token_input: Some(0.005),
+        cost_per_token_output: None,
+        config: None,
+        project_id: None,
+    })).await.expect("update failed");
+    let cached = get_model_from_cache(&provider, &model.name).await.expect("model not cached");
+    assert_eq!(cached.cost_per_token_input, Some(0.005));
+    println!("✅ Updated model refreshed in cache");
+
+    let _ = delete_existing_model(Path(model.id.clone())).await.expect("delete failed");
+    assert!(get_model_from_cache(&provider, &model.name).await.is_none());
+    println!("✅ Deleted model evicted from cache");
+}
+
+#[tokio::test]
+async fn test_model_cache_reconcile() {
+    let pool = setup_test_env().await;
+    let provider = format!("cache-reconcile-{}", uuid::Uuid::new_v4().simple());
+
+    println!("=== Testing Model Cache Reconcile ===");
+    let mut model = Model {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: "cache-reconcile-model".to_string(),
+        provider: provider.clone(),
+        model_type: "llm".to_string(),
+        base_url: None,
+        is_active: true,
+        health_status: None,
+        last_health_check: None,
+        health_check_interval_seconds: None,
+        cost_per_token_input: Some(0.001),
+        cost_per_token_output: Some(0.002),
+        function_tags: None,
+        config: None,
+        project_id: None,
+        created_at: None,
+        updated_at: None,
+    };
+    // 直接写库，绕过管理接口
+    create_model(&pool, &model).await.expect("create model failed");
+    assert!(get_model_from_cache(&provider, &model.name).await.is_none());
+    let report = run_model_cache_reconcile(&pool).await.expect("reconcile failed");
+    assert!(report.refreshed >= 1);
+    assert!(get_model_from_cache(&provider, &model.name).await.is_some());
+    assert_eq!(get_active_model_names_from_cache(&provider).await, Some(vec![model.name.clone()]));
+    println!("✅ Missing model added to cache");
+
+    model.cost_per_token_input = Some(0.009);
+    model.is_active = false;
+    update_model(&pool, &model).await.expect("update model failed");
+    run_model_cache_reconcile(&pool).await.expect("reconcile failed");
+    let cached = get_model_from_cache(&provider, &model.name).await.expect("model not cached");
+    assert_eq!(cached.cost_per_token_input, Some(0.009));
+    assert_eq!(get_active_model_names_from_cache(&provider).await, Some(vec![]));
+    println!("✅ Stale model refreshed");
+
+    delete_model(&pool, &model.id).await.expect("delete model failed");
+    let report = run_model_cache_reconcile(&pool).await.expect("reconcile failed");
+    assert!(report.evicted >= 1);
+    assert!(get_model_from_cache(&provider, &model.name).await.is_none());
+    println!("✅ Deleted model evicted");
+}
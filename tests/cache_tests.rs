@@ -49,3 +49,110 @@ async fn test_cache_operations() {
     
     println!("=== Cache Operations Tests Completed ===");
 }
+
+#[tokio::test]
+async fn test_cache_refresh_ahead() {
+    use project_rust_learn::dao::cache::cache::{CacheRefresher, CacheService};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    println!("=== Testing Cache Refresh Ahead ===");
+    // 暂停时钟，临近过期的判断按虚拟时间推进
+    tokio::time::pause();
+
+    let loads = Arc::new(AtomicUsize::new(0));
+    let refresher: CacheRefresher<String, String> = {
+        let loads = loads.clone();
+        Arc::new(move |key: String| {
+            let version = loads.fetch_add(1, Ordering::SeqCst) + 1;
+            Box::pin(async move { Ok(Some(format!("{}_v{}", key, version))) })
+        })
+    };
+    let cache = CacheService::new(Duration::from_millis(1000), 100)
+        .with_refresh_ahead(0.6, refresher);
+
+    cache.insert("hot".to_string(), "hot_v0".to_string()).await;
+    assert!(!cache.is_stale(&"hot".to_string()).await);
+
+    // 超过刷新阈值：返回旧值并触发后台刷新
+    tokio::time::sleep(Duration::from_millis(650)).await;
+    assert!(cache.is_stale(&"hot".to_string()).await);
+    assert_eq!(cache.get(&"hot".to_string()).await.as_deref(), Some("hot_v0"));
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(cache.get(&"hot".to_string()).await.as_deref(), Some("hot_v1"));
+    println!("✅ Stale entry refreshed in background");
+
+    // 超过原始 TTL 后热点 key 仍然命中
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    assert!(cache.get(&"hot".to_string()).await.is_some());
+    assert_eq!(loads.load(Ordering::SeqCst), 1);
+    println!("✅ Hot key survived past original TTL");
+
+    // 数据源中已删除的 key 刷新后从缓存移除
+    let removed: CacheRefresher<String, String> = Arc::new(|_key: String| Box::pin(async { Ok(None) }));
+    let cache = CacheService::new(Duration::from_secs(60), 100).with_refresh_ahead(0.0, removed);
+    cache.insert("gone".to_string(), "value".to_string()).await;
+    cache.refresh(&"gone".to_string()).await.expect("refresh failed");
+    assert_eq!(cache.get(&"gone".to_string()).await, None);
+    println!("✅ Deleted entry evicted on refresh");
+
+    println!("=== Cache Refresh Ahead Tests Completed ===");
+}
+
+#[tokio::test]
+async fn test_cache_namespaces() {
+    use project_rust_learn::dao::cache::cache::CacheService;
+    use serde::{Deserialize, Serialize};
+    use std::time::Duration;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Entry {
+        id: u32,
+        name: String,
+    }
+
+    println!("=== Testing Cache Namespaces ===");
+
+    let cache = CacheService::<String, String>::new(Duration::from_secs(60), 100);
+    let entries = cache.namespace::<Entry>("entries");
+    let counters = cache.namespace::<u64>("counters");
+
+    // 类型化读写，底层 key 带命名空间前缀
+    let entry = Entry { id: 1, name: "first".to_string() };
+    entries.insert("openai:1", &entry).await.expect("insert failed");
+    counters.insert("openai:1", &42).await.expect("insert failed");
+    assert_eq!(entries.get("openai:1").await, Some(entry.clone()));
+    assert_eq!(counters.get("openai:1").await, Some(42));
+    assert_eq!(cache.get(&"counters:openai:1".to_string()).await.as_deref(), Some("42"));
+    println!("✅ Typed namespaces do not collide");
+
+    // 无法反序列化的值视为未命中
+    cache.insert("entries:broken".to_string(), "not json".to_string()).await;
+    assert_eq!(entries.get("broken").await, None);
+
+    // 单个条目的 TTL 覆盖缓存默认 TTL
+    entries.insert_with_ttl("short", &entry, Duration::from_millis(100)).await.expect("insert failed");
+    let short_lived = cache.namespace::<u64>("short_lived").with_ttl(Duration::from_millis(100));
+    short_lived.insert("value", &1).await.expect("insert failed");
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(entries.get("short").await, None);
+    assert_eq!(short_lived.get("value").await, None);
+    assert_eq!(entries.get("openai:1").await, Some(entry.clone()));
+    println!("✅ Per-entry TTL expires independently");
+
+    // 按前缀批量失效，只影响本命名空间
+    entries.insert("openai:2", &Entry { id: 2, name: "second".to_string() }).await.expect("insert failed");
+    entries.insert("ollama:3", &Entry { id: 3, name: "third".to_string() }).await.expect("insert failed");
+    assert_eq!(entries.invalidate_prefix("openai:").await, 2);
+    assert_eq!(entries.get("openai:1").await, None);
+    assert!(entries.get("ollama:3").await.is_some());
+    assert_eq!(counters.get("openai:1").await, Some(42));
+    entries.clear().await;
+    assert!(entries.get("ollama:3").await.is_none());
+    assert_eq!(counters.get("openai:1").await, Some(42));
+    println!("✅ Prefix invalidation scoped to namespace");
+
+    println!("=== Cache Namespace Tests Completed ===");
+}
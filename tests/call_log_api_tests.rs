@@ -0,0 +1,159 @@
+//! # 调用日志查询与统计接口测试
+//!
+//! 测试 `/api/logs` 的分页和过滤条件，`/api/stats` 的整体和按模型统计，以及按时间分桶的趋势统计
+
+mod common;
+
+use axum::{extract::Query, http::StatusCode};
+use serde_json::json;
+use sqlx::{Pool, Sqlite};
+use std::sync::Arc;
+
+use project_rust_learn::dao::{init_sqlite_pool, init_db, SQLITE_POOL};
+use project_rust_learn::dao::call_log::{create_call_log, delete_call_logs_by_model, CallLog};
+use project_rust_learn::dao::model::{create_model, delete_model, Model};
+use project_rust_learn::web::handlers::call_log_handler::{
+    get_call_log_overview, get_call_log_timeseries, list_call_logs, CallLogQuery, TimeseriesQuery,
+};
+
+async fn setup_test_env() -> Arc<Pool<Sqlite>> {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+    init_db("data/init.sql").await.expect("DB init failed");
+    pool
+}
+
+fn query(value: serde_json::Value) -> Query<CallLogQuery> {
+    Query(serde_json::from_value(value).expect("Invalid call log query"))
+}
+
+fn test_model(name: &str, provider: &str) -> Model {
+    Model {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        provider: provider.to_string(),
+        model_type: "chat".to_string(),
+        base_url: None,
+        is_active: true,
+        health_status: None,
+        last_health_check: None,
+        health_check_interval_seconds: None,
+        cost_per_token_input: None,
+        cost_per_token_output: None,
+        function_tags: None,
+        config: None,
+        project_id: None,
+        created_at: None,
+        updated_at: None,
+    }
+}
+
+fn test_call_log(model: &Model, status_code: i64) -> CallLog {
+    CallLog {
+        model_id: Some(model.id.clone()),
+        cost: 0.5,
+        ..common::call_log(&model.provider, status_code)
+    }
+}
+
+#[tokio::test]
+async fn test_call_log_list_and_stats_endpoints() {
+    let pool = setup_test_env().await;
+    println!("=== 调用日志查询与统计接口测试 ===");
+
+    let provider = format!("call_log_api_{}", uuid::Uuid::new_v4().simple());
+    let chat = test_model("call-log-api-chat", &provider);
+    let embed = test_model("call-log-api-embed", &provider);
+    create_model(&pool, &chat).await.expect("Create model failed");
+    create_model(&pool, &embed).await.expect("Create model failed");
+
+    for (model, status_code, created_at) in [
+        (&chat, 200, "2025-03-01 10:00:00"),
+        (&chat, 200, "2025-03-02 10:00:00"),
+        (&chat, 500, "2025-03-03 10:00:00"),
+        (&embed, 200, "2025-03-10 10:00:00"),
+    ] {
+        let call_log = test_call_log(model, status_code);
+        create_call_log(&pool, &call_log).await.expect("Create call log failed");
+        // created_at 由数据库生成，改写为固定时间便于按时间范围过滤
+        sqlx::query("UPDATE call_logs SET created_at = ? WHERE id = ?")
+            .bind(created_at)
+            .bind(&call_log.id)
+            .execute(pool.as_ref())
+            .await
+            .expect("Update created_at failed");
+    }
+
+    // 按供应商过滤并分页
+    let response = list_call_logs(query(json!({ "provider": provider, "limit": 3 }))).await.expect("List failed").0;
+    assert_eq!(response.total, 4);
+    assert_eq!(response.data.len(), 3);
+    assert_eq!(response.data[0].created_at.as_deref(), Some("2025-03-10 10:00:00"));
+    assert_eq!(response.filter.provider.as_deref(), Some(provider.as_str()));
+    let next_cursor = response.next_cursor.expect("next_cursor missing");
+    let response = list_call_logs(query(json!({ "provider": provider, "limit": 3, "cursor": next_cursor }))).await.expect("List failed").0;
+    assert_eq!(response.data.len(), 1);
+    assert_eq!(response.data[0].created_at.as_deref(), Some("2025-03-01 10:00:00"));
+    assert!(response.next_cursor.is_none());
+    let response = list_call_logs(query(json!({ "provider": provider, "limit": 3, "page": 2 }))).await.expect("List failed").0;
+    assert_eq!(response.data.len(), 1);
+    assert!(response.next_cursor.is_none());
+    println!("✅ 分页正确");
+
+    // 状态、模型和时间范围过滤
+    let response = list_call_logs(query(json!({ "provider": provider, "status": "error" }))).await.expect("List failed").0;
+    assert_eq!(response.total, 1);
+    assert_eq!(response.data[0].status_code, 500);
+    let response = list_call_logs(query(json!({ "provider": provider, "status": "200", "model_id": chat.id }))).await.expect("List failed").0;
+    assert_eq!(response.total, 2);
+    let response = list_call_logs(query(json!({ "provider": provider, "start": "2025-03-02", "end": "2025-03-10" }))).await.expect("List failed").0;
+    assert_eq!(response.total, 2);
+    println!("✅ 过滤条件正确");
+
+    // 无效参数
+    let status = list_call_logs(query(json!({ "status": "sometimes" }))).await.unwrap_err();
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let status = list_call_logs(query(json!({ "cursor": "not a cursor!" }))).await.unwrap_err();
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let status = get_call_log_overview(query(json!({ "start": "March" }))).await.unwrap_err();
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    println!("✅ 无效参数返回 400");
+
+    // 整体和按模型统计
+    let overview = get_call_log_overview(query(json!({ "provider": provider }))).await.expect("Stats failed").0;
+    assert_eq!(overview.overall.total_calls, 4);
+    assert_eq!(overview.overall.error_count, 1);
+    assert_eq!(overview.overall.total_tokens_output, 80);
+    assert_eq!(overview.by_model.len(), 2);
+    assert_eq!(overview.by_model[0].model_id.as_deref(), Some(chat.id.as_str()));
+    assert_eq!(overview.by_model[0].model_name.as_deref(), Some("call-log-api-chat"));
+    assert_eq!(overview.by_model[0].total_calls, 3);
+    assert_eq!(overview.by_model[0].error_count, 1);
+    assert_eq!(overview.by_model[1].total_calls, 1);
+    println!("✅ 统计正确");
+
+    // 按天和按小时分桶
+    let timeseries = |bucket: &str| Query::<TimeseriesQuery>(serde_json::from_value(json!({ "bucket": bucket })).unwrap());
+    let days = get_call_log_timeseries(query(json!({ "provider": provider, "start": "2025-03-01" })), timeseries("day"))
+        .await.expect("Timeseries failed").0;
+    let labels: Vec<&str> = days.iter().map(|bucket| bucket.bucket.as_str()).collect();
+    assert_eq!(labels, ["2025-03-01", "2025-03-02", "2025-03-03", "2025-03-10"]);
+    assert_eq!((days[2].total_calls, days[2].error_count), (1, 1));
+    assert_eq!(days[0].total_tokens_input + days[0].total_tokens_output, 30);
+    assert_eq!(days[0].avg_latency_ms, Some(100.0));
+    let hours = get_call_log_timeseries(
+        query(json!({ "provider": provider, "start": "2025-03-01", "end": "2025-03-02" })),
+        timeseries("hour"),
+    ).await.expect("Timeseries failed").0;
+    assert_eq!(hours.len(), 1);
+    assert_eq!(hours[0].bucket, "2025-03-01 10:00:00");
+    // 默认只统计最近的数据
+    let recent = get_call_log_timeseries(query(json!({ "provider": provider })), timeseries("day")).await.expect("Timeseries failed").0;
+    assert!(recent.is_empty());
+    println!("✅ 趋势统计正确");
+
+    delete_call_logs_by_model(&pool, &chat.id).await.expect("Delete call logs failed");
+    delete_call_logs_by_model(&pool, &embed.id).await.expect("Delete call logs failed");
+    delete_model(&pool, &chat.id).await.expect("Delete model failed");
+    delete_model(&pool, &embed.id).await.expect("Delete model failed");
+}
@@ -0,0 +1,132 @@
+//! # 调用日志归档测试
+//!
+//! 测试按模型批量归档（导出后删除）和批量删除调用日志，以及任务进度查询和结束任务的清理
+
+mod common;
+
+use std::time::Duration;
+use axum::{http::StatusCode, Json};
+use sqlx::{Pool, Sqlite};
+
+use project_rust_learn::dao::{init_sqlite_pool, init_db, SQLITE_POOL};
+use project_rust_learn::dao::call_log::{CallLog, CallLogFilter, create_call_log, count_call_logs_by_filter};
+use project_rust_learn::dao::model::{create_model, delete_model, Model};
+use project_rust_learn::jobs::call_log_archive::{
+    get_archive_task, list_archive_tasks, start_archive_task, ARCHIVE_TASK_TTL, ArchiveMode, ArchiveStatus, ArchiveTask,
+};
+use project_rust_learn::web::handlers::call_log_handler::bulk_delete_call_logs;
+
+/// 初始化测试环境的辅助函数
+async fn setup_test_env() -> std::sync::Arc<Pool<Sqlite>> {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+    init_db("data/init.sql").await.expect("DB init failed");
+    pool
+}
+
+fn call_log(model_id: &str) -> CallLog {
+    CallLog {
+        model_id: Some(model_id.to_string()),
+        request_summary: Some("hello".to_string()),
+        finish_reason: Some("stop".to_string()),
+        ..common::call_log("ollama", 200)
+    }
+}
+
+fn model() -> Model {
+    Model {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: format!("archive-test-{}", uuid::Uuid::new_v4().simple()),
+        provider: "ollama".to_string(),
+        model_type: "llm".to_string(),
+        base_url: None,
+        is_active: false,
+        health_status: None,
+        last_health_check: None,
+        health_check_interval_seconds: None,
+        cost_per_token_input: None,
+        cost_per_token_output: None,
+        function_tags: None,
+        config: None,
+        project_id: None,
+        created_at: None,
+        updated_at: None,
+    }
+}
+
+// 任务只处理开始之前写入的日志（精确到秒），把该模型的日志写入时间提前，不必等到下一秒
+async fn backdate_call_logs(pool: &Pool<Sqlite>, model_id: &str) {
+    sqlx::query("UPDATE call_logs SET created_at = datetime('now', '-1 minute') WHERE model_id = ?")
+        .bind(model_id)
+        .execute(pool)
+        .await
+        .expect("backdate call logs failed");
+}
+
+// 等待后台任务结束
+async fn wait_for_task(id: &str) -> ArchiveTask {
+    for _ in 0..100 {
+        let task = get_archive_task(id).expect("task not found");
+        if task.status != ArchiveStatus::Running {
+            return task;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("archive task did not finish in time");
+}
+
+#[tokio::test]
+async fn test_archive_and_bulk_delete_call_logs() {
+    let pool = setup_test_env().await;
+
+    println!("=== Testing Call Log Archive ===");
+    let model = model();
+    create_model(&pool, &model).await.expect("create_model failed");
+    for _ in 0..3 {
+        create_call_log(&pool, &call_log(&model.id)).await.expect("create_call_log failed");
+    }
+    backdate_call_logs(&pool, &model.id).await;
+
+    let filter = CallLogFilter { model_id: Some(model.id.clone()), ..Default::default() };
+    let archive_dir = std::env::temp_dir().join(format!("call_log_archive_{}", uuid::Uuid::new_v4().simple()));
+    let task = start_archive_task(pool.clone(), ArchiveMode::Archive, filter.clone(), archive_dir.clone(), 2);
+    assert!(list_archive_tasks().iter().any(|t| t.id == task.id));
+
+    let task = wait_for_task(&task.id).await;
+    println!("✅ Archive task: {:?}", task);
+    assert_eq!(task.status, ArchiveStatus::Completed);
+    assert_eq!((task.total, task.exported, task.deleted), (3, 3, 3));
+    assert_eq!(count_call_logs_by_filter(&pool, &filter).await.unwrap(), 0);
+
+    let exported = std::fs::read_to_string(task.file.as_deref().expect("archive file missing")).unwrap();
+    let lines: Vec<serde_json::Value> = exported.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines.iter().all(|line| line["model_id"] == model.id.as_str()));
+    println!("✅ Call logs exported to {}", archive_dir.display());
+
+    // 只删除不导出
+    create_call_log(&pool, &call_log(&model.id)).await.expect("create_call_log failed");
+    backdate_call_logs(&pool, &model.id).await;
+    let task = start_archive_task(pool.clone(), ArchiveMode::Delete, filter.clone(), archive_dir.clone(), 100);
+    let task = wait_for_task(&task.id).await;
+    assert_eq!(task.status, ArchiveStatus::Completed);
+    assert_eq!((task.exported, task.deleted), (0, 1));
+    assert!(task.file.is_none());
+    println!("✅ Bulk delete removed call logs without export");
+
+    // 结束的任务超过保留时长后被清理
+    tokio::time::pause();
+    tokio::time::advance(ARCHIVE_TASK_TTL).await;
+    assert!(get_archive_task(&task.id).is_none());
+    assert!(!list_archive_tasks().iter().any(|t| t.id == task.id));
+    tokio::time::resume();
+    println!("✅ Finished tasks evicted after the retention period");
+
+    // 不带任何条件的请求被拒绝
+    let result = bulk_delete_call_logs(Json(CallLogFilter::default())).await;
+    assert_eq!(result.err(), Some(StatusCode::BAD_REQUEST));
+    println!("✅ Unfiltered bulk delete rejected");
+
+    std::fs::remove_dir_all(&archive_dir).ok();
+    delete_model(&pool, &model.id).await.expect("delete_model failed");
+}
@@ -0,0 +1,119 @@
+//! # 调用日志保留期测试
+//!
+//! 测试超过保留期的调用日志先按天汇总再删除、重复执行不会重复计数，以及从 system_configs 读取保留天数
+
+mod common;
+
+use sqlx::{Pool, Sqlite, SqlitePool};
+
+use project_rust_learn::dao::run_migrations;
+use project_rust_learn::dao::call_log::{create_call_log, get_call_log_by_id, list_call_log_daily_stats, CallLog, CallLogDailyStat};
+use project_rust_learn::dao::system_config::{create_system_config, delete_system_configs_by_category, SystemConfig};
+use project_rust_learn::jobs::call_log_retention::{
+    get_retention_days, run_call_log_retention, run_configured_call_log_retention, RETENTION_CONFIG_CATEGORY, RETENTION_DAYS_KEY,
+};
+use project_rust_learn::metrics::metrics;
+
+/// 初始化测试环境的辅助函数：保留期任务会删除表中所有过期的调用日志，
+/// 使用独立的数据库，避免清理开发环境数据库中的真实记录
+async fn setup_test_env() -> (SqlitePool, std::path::PathBuf) {
+    let path = std::env::temp_dir().join(format!("call-log-retention-test-{}.db", uuid::Uuid::new_v4().simple()));
+    let pool = SqlitePool::connect(&format!("sqlite://{}?mode=rwc", path.display())).await.unwrap();
+    run_migrations(&pool).await.expect("migration failed");
+    (pool, path)
+}
+
+fn call_log(provider: &str, status_code: i64) -> CallLog {
+    CallLog {
+        cost: 0.5,
+        error_message: None,
+        ..common::call_log(provider, status_code)
+    }
+}
+
+async fn set_retention_days(pool: &Pool<Sqlite>, value: &str) {
+    delete_system_configs_by_category(pool, RETENTION_CONFIG_CATEGORY).await.expect("Delete config failed");
+    let config = SystemConfig {
+        id: uuid::Uuid::new_v4().to_string(),
+        category: RETENTION_CONFIG_CATEGORY.to_string(),
+        key_name: RETENTION_DAYS_KEY.to_string(),
+        value: value.to_string(),
+        is_encrypted: false,
+        version: 1,
+        created_at: None,
+        updated_at: None,
+    };
+    create_system_config(pool, &config).await.expect("Create config failed");
+}
+
+// 测试供应商在 2020 年 1 月的按天汇总
+async fn daily_stats(pool: &Pool<Sqlite>, provider: &str) -> Vec<CallLogDailyStat> {
+    list_call_log_daily_stats(pool, Some("2020-01-01"), Some("2020-01-31")).await
+        .expect("List daily stats failed")
+        .into_iter()
+        .filter(|stat| stat.provider == provider)
+        .collect()
+}
+
+#[tokio::test]
+async fn test_call_log_retention_rollup_and_purge() {
+    let (pool, path) = setup_test_env().await;
+
+    println!("=== Testing Call Log Retention ===");
+    let provider = format!("retention-test-{}", uuid::Uuid::new_v4().simple());
+    let old = [call_log(&provider, 200), call_log(&provider, 500), call_log(&provider, 200)];
+    let recent = call_log(&provider, 200);
+    for log in old.iter().chain([&recent]) {
+        create_call_log(&pool, log).await.expect("Create call log failed");
+    }
+    // created_at 由数据库生成，把前三条改写为很久以前的两天
+    for (log, created_at) in old.iter().zip(["2020-01-05 08:00:00", "2020-01-05 23:59:59", "2020-01-06 00:00:00"]) {
+        sqlx::query("UPDATE call_logs SET created_at = ? WHERE id = ?")
+            .bind(created_at)
+            .bind(&log.id)
+            .execute(&pool)
+            .await
+            .expect("Update created_at failed");
+    }
+
+    let purged_before = metrics().counter_total("llm_gateway_call_log_retention_purged_rows_total");
+    let report = run_call_log_retention(&pool, 30).await.expect("Retention failed");
+    assert_eq!(report.purged_rows, 3);
+    assert!(metrics().counter_total("llm_gateway_call_log_retention_purged_rows_total") >= purged_before + 3);
+    for log in &old {
+        assert!(get_call_log_by_id(&pool, &log.id).await.unwrap().is_none());
+    }
+    assert!(get_call_log_by_id(&pool, &recent.id).await.unwrap().is_some());
+    println!("✅ Purged {} rows before {}", report.purged_rows, report.cutoff);
+
+    let stats = daily_stats(&pool, &provider).await;
+    assert_eq!(stats.len(), 2);
+    assert_eq!((stats[0].day.as_str(), stats[0].total_calls, stats[0].error_count), ("2020-01-06", 1, 0));
+    assert_eq!((stats[1].day.as_str(), stats[1].total_calls, stats[1].error_count), ("2020-01-05", 2, 1));
+    assert_eq!((stats[1].tokens_input, stats[1].tokens_output, stats[1].total_duration), (20, 40, 200));
+    assert_eq!(stats[1].cost, 1.0);
+    assert_eq!(stats[1].project_id, "default");
+    println!("✅ Rolled up into daily stats");
+
+    // 再次执行不会重复计数
+    run_call_log_retention(&pool, 30).await.expect("Retention failed");
+    let again = daily_stats(&pool, &provider).await;
+    assert_eq!(again.iter().map(|stat| stat.total_calls).sum::<i64>(), 3);
+    println!("✅ Re-running is idempotent");
+
+    // 保留天数从 system_configs 读取，0 或无效值表示不清理
+    delete_system_configs_by_category(&pool, RETENTION_CONFIG_CATEGORY).await.expect("Delete config failed");
+    assert_eq!(get_retention_days(&pool).await.unwrap(), None);
+    assert!(run_configured_call_log_retention(&pool).await.unwrap().is_none());
+    for (value, expected) in [("0", None), ("abc", None), (" 90 ", Some(90))] {
+        set_retention_days(&pool, value).await;
+        assert_eq!(get_retention_days(&pool).await.unwrap(), expected);
+    }
+    let report = run_configured_call_log_retention(&pool).await.unwrap().expect("Retention should run");
+    assert_eq!(report.purged_rows, 0);
+    assert!(get_call_log_by_id(&pool, &recent.id).await.unwrap().is_some());
+    println!("✅ Retention days read from system config");
+
+    pool.close().await;
+    std::fs::remove_file(path).ok();
+}
@@ -1,27 +1,28 @@
-use project_rust_learn::dao::{init_sqlite_pool, init_db, SQLITE_POOL};
+use project_rust_learn::dao::run_migrations;
 use project_rust_learn::dao::call_log::{
     CallLog, create_call_log, get_call_log_by_id, list_call_logs,
     list_call_logs_paginated, list_call_logs_by_model, list_call_logs_by_status,
+    list_call_logs_by_provider, list_call_logs_by_key,
     list_error_call_logs, list_call_logs_by_date_range, get_call_logs_stats,
-    get_call_logs_stats_by_model, update_call_log,
+    get_call_logs_stats_by_model, get_call_logs_stats_by_language, update_call_log,
     delete_call_logs_by_model, delete_old_call_logs, count_call_logs,
     count_call_logs_by_model
 };
 use project_rust_learn::dao::model::{Model, create_model, delete_model};
-use std::sync::Arc;
-use sqlx::{Pool, Sqlite};
-
-/// 初始化测试环境的辅助函数
-async fn setup_test_env() -> Arc<Pool<Sqlite>> {
-    init_sqlite_pool("sqlite://data/app.db").await;
-    let pool = SQLITE_POOL.get().unwrap().clone();
-    init_db("data/init.sql").await.expect("DB init failed");
-    pool
+use sqlx::SqlitePool;
+
+/// 初始化测试环境的辅助函数：按状态码、日期等条件的查询会统计表中的所有记录，
+/// 使用独立的数据库，避免受到其他测试写入的调用日志影响
+async fn setup_test_env() -> (SqlitePool, std::path::PathBuf) {
+    let path = std::env::temp_dir().join(format!("call-log-test-{}.db", uuid::Uuid::new_v4().simple()));
+    let pool = SqlitePool::connect(&format!("sqlite://{}?mode=rwc", path.display())).await.unwrap();
+    run_migrations(&pool).await.expect("migration failed");
+    (pool, path)
 }
 
 #[tokio::test]
 async fn test_call_log_crud_operations() {
-    let pool = setup_test_env().await;
+    let (pool, path) = setup_test_env().await;
     
     println!("=== Testing Call Log CRUD Operations ===");
 
@@ -40,19 +41,33 @@ async fn test_call_log_crud_operations() {
         cost_per_token_output: Some(0.002),
         function_tags: None,
         config: None,
+        project_id: None,
         created_at: None,
         updated_at: None,
     };
     create_model(&pool, &test_model).await.expect("create test model failed");
 
     // Test 1: Create call log entries
+    let test_key_id = uuid::Uuid::new_v4().to_string();
     let call_log1 = CallLog {
         id: uuid::Uuid::new_v4().to_string(),
         model_id: Some(test_model.id.clone()),
         status_code: 200,
         total_duration: 150,
+        tokens_input: 20,
         tokens_output: 50,
+        cost: 0.12,
+        provider: Some("test_call_log_provider".to_string()),
+        key_id: Some(test_key_id.clone()),
+        request_summary: Some("hello".to_string()),
+        finish_reason: Some("stop".to_string()),
+        provider_request_id: None,
+        provider_usage: None,
         error_message: None,
+        detected_language: Some("eng".to_string()),
+        project_id: None,
+        traffic_arm: None,
+        request_id: None,
         created_at: None,
     };
 
@@ -61,8 +76,20 @@ async fn test_call_log_crud_operations() {
         model_id: Some(test_model.id.clone()),
         status_code: 500,
         total_duration: 5000,
+        tokens_input: 0,
         tokens_output: 0,
+        cost: 0.0,
+        provider: None,
+        key_id: None,
+        request_summary: None,
+        finish_reason: None,
+        provider_request_id: None,
+        provider_usage: None,
         error_message: Some("Internal server error".to_string()),
+        detected_language: None,
+        project_id: None,
+        traffic_arm: None,
+        request_id: None,
         created_at: None,
     };
 
@@ -71,8 +98,20 @@ async fn test_call_log_crud_operations() {
         model_id: Some(test_model.id.clone()),
         status_code: 200,
         total_duration: 300,
+        tokens_input: 30,
         tokens_output: 120,
+        cost: 0.27,
+        provider: None,
+        key_id: None,
+        request_summary: None,
+        finish_reason: None,
+        provider_request_id: None,
+        provider_usage: None,
         error_message: None,
+        detected_language: Some("cmn".to_string()),
+        project_id: None,
+        traffic_arm: None,
+        request_id: None,
         created_at: None,
     };
 
@@ -81,8 +120,20 @@ async fn test_call_log_crud_operations() {
         model_id: None,
         status_code: 404,
         total_duration: 100,
+        tokens_input: 0,
         tokens_output: 0,
+        cost: 0.0,
+        provider: None,
+        key_id: None,
+        request_summary: None,
+        finish_reason: None,
+        provider_request_id: None,
+        provider_usage: None,
         error_message: Some("Model not found".to_string()),
+        detected_language: None,
+        project_id: None,
+        traffic_arm: None,
+        request_id: None,
         created_at: None,
     };
 
@@ -107,7 +158,7 @@ async fn test_call_log_crud_operations() {
     println!("\nListing all call logs...");
     let all_call_logs = list_call_logs(&pool).await.expect("list_call_logs failed");
     println!("✅ Total call logs: {}", all_call_logs.len());
-    assert!(all_call_logs.len() >= 4);
+    assert_eq!(all_call_logs.len(), 4);
 
     // Test 3: Get call log by ID
     println!("\nGetting call log by ID...");
@@ -127,6 +178,19 @@ async fn test_call_log_crud_operations() {
     println!("✅ Call logs for model {}: {}", test_model.id, model_logs.len());
     assert_eq!(model_logs.len(), 3);
 
+    // Test 5b: List call logs by provider and key
+    println!("\nListing call logs by provider and key...");
+    let provider_logs = list_call_logs_by_provider(&pool, "test_call_log_provider").await.expect("list_call_logs_by_provider failed");
+    println!("✅ Call logs for provider: {}", provider_logs.len());
+    assert_eq!(provider_logs.len(), 1);
+    assert_eq!(provider_logs[0].request_summary.as_deref(), Some("hello"));
+    assert_eq!(provider_logs[0].finish_reason.as_deref(), Some("stop"));
+
+    let key_logs = list_call_logs_by_key(&pool, &test_key_id).await.expect("list_call_logs_by_key failed");
+    println!("✅ Call logs for key {}: {}", test_key_id, key_logs.len());
+    assert_eq!(key_logs.len(), 1);
+    assert_eq!(key_logs[0].id, call_log1.id);
+
     // Test 6: List call logs by status
     println!("\nListing call logs by status (200)...");
     let success_logs = list_call_logs_by_status(&pool, 200).await.expect("list_call_logs_by_status failed");
@@ -141,9 +205,12 @@ async fn test_call_log_crud_operations() {
 
     // Test 8: List call logs by date range
     println!("\nListing call logs by date range...");
-    let date_logs = list_call_logs_by_date_range(&pool, "2024-01-01", "2025-12-31").await.expect("list_call_logs_by_date_range failed");
+    let today = chrono::Utc::now().date_naive();
+    let yesterday = (today - chrono::Duration::days(1)).to_string();
+    let tomorrow = (today + chrono::Duration::days(1)).to_string();
+    let date_logs = list_call_logs_by_date_range(&pool, &yesterday, &tomorrow).await.expect("list_call_logs_by_date_range failed");
     println!("✅ Call logs in date range: {}", date_logs.len());
-    assert!(date_logs.len() >= 4);
+    assert_eq!(date_logs.len(), 4);
 
     // Test 9: Get call logs statistics
     println!("\nGetting call logs statistics...");
@@ -154,12 +221,20 @@ async fn test_call_log_crud_operations() {
     println!("\nGetting call logs statistics by model...");
     let model_stats = get_call_logs_stats_by_model(&pool, &test_model.id).await.expect("get_call_logs_stats_by_model failed");
     println!("✅ Model stats: {:?}", model_stats);
+    assert_eq!(model_stats.total_tokens_input, 50);
+    assert!((model_stats.total_cost - 0.39).abs() < 1e-9);
+
+    // Test 10b: Get call logs statistics by language
+    println!("\nGetting call logs statistics by language...");
+    let language_stats = get_call_logs_stats_by_language(&pool).await.expect("get_call_logs_stats_by_language failed");
+    println!("✅ Language stats: {:?}", language_stats);
+    assert!(language_stats.iter().any(|s| s.detected_language.as_deref() == Some("cmn")));
 
     // Test 11: Count call logs
     println!("\nCounting call logs...");
     let total_count = count_call_logs(&pool).await.expect("count_call_logs failed");
     println!("✅ Total call logs count: {}", total_count);
-    assert!(total_count >= 4);
+    assert_eq!(total_count, 4);
 
     let model_count = count_call_logs_by_model(&pool, &test_model.id).await.expect("count_call_logs_by_model failed");
     println!("✅ Call logs count for model: {}", model_count);
@@ -182,12 +257,14 @@ async fn test_call_log_crud_operations() {
 
     // Test 14: Delete old call logs (this will delete the remaining log without model)
     println!("\nDeleting old call logs...");
-    let delete_old_rows = delete_old_call_logs(&pool, "2025-12-31").await.expect("delete_old_call_logs failed");
+    let delete_old_rows = delete_old_call_logs(&pool, &tomorrow).await.expect("delete_old_call_logs failed");
     println!("✅ Deleted old call logs: {} row(s)", delete_old_rows);
     assert_eq!(delete_old_rows, 1);
 
     // Clean up test model
     delete_model(&pool, &test_model.id).await.expect("delete test model failed");
+    pool.close().await;
+    std::fs::remove_file(path).ok();
 
     println!("\n=== Call Log Tests Completed ===");
 }
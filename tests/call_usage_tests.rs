@@ -0,0 +1,218 @@
+//! # 调用用量与费用记录测试
+//!
+//! 测试调度器把供应商返回的 token 用量和按模型单价计算的费用回填到调用记录，
+//! 以及供应商原始用量的保存和账单对账报表
+
+use mockito::Server;
+use sqlx::{Pool, Sqlite};
+
+use project_rust_learn::dao::{init_sqlite_pool, init_db, SQLITE_POOL};
+use project_rust_learn::dao::cache::init_global_cache;
+use project_rust_learn::dao::call_log::{
+    get_call_logs_stats_by_model, list_call_logs_by_model, delete_call_logs_by_model, get_billing_reconciliation,
+};
+use project_rust_learn::dao::model::{create_model, delete_model, insert_model_to_cache, invalidate_provider_models_cache, Model};
+use project_rust_learn::llm_api::dispatcher::{DispatchRequest, LLMDispatcher, OllamaAdapter, Provider};
+use project_rust_learn::llm_api::ollama::client::OllamaClient;
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+
+/// 初始化测试环境的辅助函数
+async fn setup_test_env() -> std::sync::Arc<Pool<Sqlite>> {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+    init_db("data/init.sql").await.expect("DB init failed");
+    init_global_cache(&pool, 3600, 1000).await.expect("Cache init failed");
+    pool
+}
+
+// 创建带单价的测试模型，名称唯一以免影响其他测试
+async fn create_priced_model(pool: &Pool<Sqlite>) -> Model {
+    let model = Model {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: format!("usage-test-{}", uuid::Uuid::new_v4().simple()),
+        provider: "ollama".to_string(),
+        model_type: "llm".to_string(),
+        base_url: None,
+        is_active: true,
+        health_status: None,
+        last_health_check: None,
+        health_check_interval_seconds: None,
+        cost_per_token_input: Some(0.001),
+        cost_per_token_output: Some(0.002),
+        function_tags: None,
+        config: None,
+        project_id: None,
+        created_at: None,
+        updated_at: None,
+    };
+    create_model(pool, &model).await.expect("create_model failed");
+    invalidate_provider_models_cache("ollama").await;
+    model
+}
+
+async fn cleanup(pool: &Pool<Sqlite>, model: &Model) {
+    delete_call_logs_by_model(pool, &model.id).await.expect("delete_call_logs_by_model failed");
+    delete_model(pool, &model.id).await.expect("delete_model failed");
+    invalidate_provider_models_cache("ollama").await;
+}
+
+fn request(model: &Model) -> DispatchRequest {
+    let mut request = DispatchRequest::new(Provider::Ollama, model.name.clone(), vec![Message::user("hi".to_string())]);
+    request.retry_count = Some(0);
+    request
+}
+
+#[tokio::test]
+async fn test_dispatch_records_usage_and_cost() {
+    let pool = setup_test_env().await;
+    let model = create_priced_model(&pool).await;
+
+    println!("=== Testing Call Usage Recording ===");
+    let mut server = Server::new_async().await;
+    let _mock = server.mock("POST", "/api/chat")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(format!(
+            r#"{{"model":"{}","created_at":"2024-01-01T00:00:00Z","message":{{"role":"assistant","content":"hello"}},"done":true,"prompt_eval_count":100,"eval_count":50}}"#,
+            model.name
+        ))
+        .create_async()
+        .await;
+
+    let dispatcher = LLMDispatcher::new(None);
+    dispatcher.register_client(Box::new(OllamaAdapter::new(OllamaClient::new(server.url()).unwrap()))).await;
+    dispatcher.dispatch(request(&model)).await.expect("dispatch failed");
+
+    let logs = list_call_logs_by_model(&pool, &model.id).await.unwrap();
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].tokens_input, 100);
+    assert_eq!(logs[0].tokens_output, 50);
+    assert!((logs[0].cost - 0.2).abs() < 1e-9);
+    println!("✅ Call log records usage: in={}, out={}, cost={}", logs[0].tokens_input, logs[0].tokens_output, logs[0].cost);
+    assert_eq!(logs[0].provider.as_deref(), Some("ollama"));
+    assert_eq!(logs[0].request_summary.as_deref(), Some("hi"));
+    assert_eq!(logs[0].finish_reason.as_deref(), Some("stop"));
+    println!("✅ Call log records provider, request summary and finish reason");
+
+    let stats = get_call_logs_stats_by_model(&pool, &model.id).await.unwrap();
+    assert_eq!(stats.total_tokens_input, 100);
+    assert!((stats.total_cost - 0.2).abs() < 1e-9);
+    println!("✅ Stats include input tokens and cost: {:?}", stats);
+
+    cleanup(&pool, &model).await;
+}
+
+#[tokio::test]
+async fn test_dispatch_stream_records_usage() {
+    let pool = setup_test_env().await;
+    let model = create_priced_model(&pool).await;
+
+    println!("=== Testing Stream Usage Recording ===");
+    let mut server = Server::new_async().await;
+    let body = format!(
+        "{{\"model\":\"{name}\",\"created_at\":\"2024-01-01T00:00:00Z\",\"message\":{{\"role\":\"assistant\",\"content\":\"hel\"}},\"done\":false}}\n\
+         {{\"model\":\"{name}\",\"created_at\":\"2024-01-01T00:00:00Z\",\"message\":{{\"role\":\"assistant\",\"content\":\"lo\"}},\"done\":true,\"prompt_eval_count\":10,\"eval_count\":20}}\n",
+        name = model.name
+    );
+    let _mock = server.mock("POST", "/api/chat")
+        .with_status(200)
+        .with_header("content-type", "application/x-ndjson")
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let dispatcher = LLMDispatcher::new(None);
+    dispatcher.register_client(Box::new(OllamaAdapter::new(OllamaClient::new(server.url()).unwrap()))).await;
+    let mut receiver = dispatcher.dispatch_stream(request(&model)).await.expect("dispatch_stream failed");
+    let mut content = String::new();
+    while let Some(chunk) = receiver.recv().await {
+        content.push_str(&chunk.expect("stream chunk failed").content);
+    }
+    assert_eq!(content, "hello");
+
+    // 接收端关闭后回填任务仍在执行，稍等片刻
+    let mut logs = Vec::new();
+    for _ in 0..50 {
+        logs = list_call_logs_by_model(&pool, &model.id).await.unwrap();
+        if !logs.is_empty() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].tokens_input, 10);
+    assert_eq!(logs[0].tokens_output, 20);
+    assert!((logs[0].cost - 0.05).abs() < 1e-9);
+    println!("✅ Stream call log records usage: cost={}", logs[0].cost);
+
+    cleanup(&pool, &model).await;
+}
+
+#[tokio::test]
+async fn test_dispatch_persists_provider_usage_for_reconciliation() {
+    let pool = setup_test_env().await;
+    let model = create_priced_model(&pool).await;
+
+    println!("=== Testing Provider Usage Persistence ===");
+    let mut server = Server::new_async().await;
+    let _mock = server.mock("POST", "/api/chat")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(format!(
+            r#"{{"model":"{}","created_at":"2024-01-01T00:00:00Z","message":{{"role":"assistant","content":"hello"}},"done":true,"total_duration":1000,"prompt_eval_count":7,"eval_count":3}}"#,
+            model.name
+        ))
+        .create_async()
+        .await;
+
+    let dispatcher = LLMDispatcher::new(None);
+    dispatcher.register_client(Box::new(OllamaAdapter::new(OllamaClient::new(server.url()).unwrap()))).await;
+    dispatcher.dispatch(request(&model)).await.expect("dispatch failed");
+
+    let logs = list_call_logs_by_model(&pool, &model.id).await.unwrap();
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].provider.as_deref(), Some("ollama"));
+    let provider_usage: serde_json::Value = serde_json::from_str(logs[0].provider_usage.as_deref().expect("provider usage missing")).unwrap();
+    assert_eq!(provider_usage["prompt_eval_count"], 7);
+    assert_eq!(provider_usage["eval_count"], 3);
+    assert_eq!(provider_usage["total_duration"], 1000);
+    println!("✅ Provider usage stored verbatim: {}", provider_usage);
+
+    let rows = get_billing_reconciliation(&pool, None, Some("ollama")).await.unwrap();
+    let row = rows.iter().find(|r| r.model.as_deref() == Some(model.name.as_str())).expect("reconciliation row missing");
+    assert_eq!(row.total_calls, 1);
+    assert_eq!(row.calls_with_provider_usage, 1);
+    assert_eq!(row.provider_tokens_input, row.gateway_tokens_input);
+    assert_eq!(row.provider_tokens_output, row.gateway_tokens_output);
+    assert_eq!(row.mismatched_calls, 0);
+    println!("✅ Reconciliation row matches: {:?}", row);
+
+    cleanup(&pool, &model).await;
+}
+
+#[tokio::test]
+async fn test_failed_call_log_records_model_id() {
+    let pool = setup_test_env().await;
+    let model = create_priced_model(&pool).await;
+    insert_model_to_cache(&model).await.expect("insert_model_to_cache failed");
+
+    println!("=== Testing Model ID On Failed Calls ===");
+    let mut server = Server::new_async().await;
+    let _mock = server.mock("POST", "/api/chat")
+        .with_status(400)
+        .with_body(r#"{"error":"bad request"}"#)
+        .create_async()
+        .await;
+
+    let dispatcher = LLMDispatcher::new(None);
+    dispatcher.register_client(Box::new(OllamaAdapter::new(OllamaClient::new(server.url()).unwrap()))).await;
+    assert!(dispatcher.dispatch(request(&model)).await.is_err());
+
+    // 没有用量可回填，调用记录的模型 ID 来自调度时解析的模型
+    let logs = list_call_logs_by_model(&pool, &model.id).await.unwrap();
+    assert!(!logs.is_empty());
+    assert!(logs.iter().all(|log| log.status_code != 200 && log.tokens_input == 0));
+    println!("✅ Failed call logs attributed to model {}: {}", model.id, logs.len());
+
+    cleanup(&pool, &model).await;
+}
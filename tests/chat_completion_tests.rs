@@ -0,0 +1,178 @@
+
+mod common;
+use std::sync::Arc;
+use async_trait::async_trait;
+use axum::{body::to_bytes, http::{HeaderMap, StatusCode}, response::Response, Json};
+use serde_json::{json, Value};
+
+use project_rust_learn::api_types::v1::GatewayErrorCode;
+use project_rust_learn::llm_api::dispatcher::{
+    DispatchRequest, DispatchResponse, LLMClientAdapter, LLMDispatcher, LLMError, Provider, StreamChunk,
+    StreamReceiver, TokenUsage, GLOBAL_DISPATCHER,
+};
+use project_rust_learn::llm_api::utils::client::ClientError;
+use project_rust_learn::web::dto::chat_completion_dto::{ChatCompletionRequest, ChatCompletionResponse};
+use project_rust_learn::web::extract::StreamingJson;
+use project_rust_learn::web::handlers::chat_completion_handler::create_chat_completion;
+use common::response;
+
+/// 回显最后一条用户消息的测试适配器
+struct EchoAdapter;
+
+#[async_trait]
+impl LLMClientAdapter for EchoAdapter {
+    async fn generate(&self, request: &DispatchRequest) -> Result<DispatchResponse, LLMError> {
+        let last = request.messages.last().map(|m| m.content.clone()).unwrap_or_default();
+        Ok(DispatchResponse {
+            usage: Some(TokenUsage { prompt_tokens: 3, completion_tokens: 2, total_tokens: 5 }),
+            ..response(Provider::OpenAI, &request.model, format!("echo: {}", last))
+        })
+    }
+
+    async fn generate_stream(&self, request: &DispatchRequest) -> Result<StreamReceiver, LLMError> {
+        let last = request.messages.last().map(|m| m.content.clone()).unwrap_or_default();
+        let (tx, rx) = tokio::sync::mpsc::channel(4);
+        tx.send(Ok(StreamChunk::delta("echo: ".to_string()))).await.ok();
+        tx.send(Ok(StreamChunk::delta(last))).await.ok();
+        tx.send(Ok(StreamChunk::finished(
+            Some("stop".to_string()),
+            Some(TokenUsage { prompt_tokens: 3, completion_tokens: 2, total_tokens: 5 }),
+        ))).await.ok();
+        Ok(rx)
+    }
+
+    fn supported_models(&self) -> Vec<String> {
+        vec!["echo-model".to_string()]
+    }
+
+    fn provider_name(&self) -> Provider {
+        Provider::OpenAI
+    }
+}
+
+async fn body_text(response: Response) -> String {
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.expect("read body failed");
+    String::from_utf8(bytes.to_vec()).expect("body is not utf-8")
+}
+
+async fn setup_dispatcher() {
+    if GLOBAL_DISPATCHER.get().is_none() {
+        let dispatcher = LLMDispatcher::new(None);
+        dispatcher.register_client(Box::new(EchoAdapter)).await;
+        GLOBAL_DISPATCHER.set(Arc::new(dispatcher)).ok();
+    }
+}
+
+#[tokio::test]
+async fn test_chat_completion_openai_format() {
+    setup_dispatcher().await;
+
+    let request: ChatCompletionRequest = serde_json::from_value(json!({
+        "model": "echo-model",
+        "messages": [
+            {"role": "system", "content": "be nice"},
+            {"role": "user", "content": [{"type": "text", "text": "hello gateway"}]}
+        ],
+        "stop": "END"
+    })).unwrap();
+
+    let response = create_chat_completion(HeaderMap::new(), None, StreamingJson(request)).await.expect("chat completion failed");
+    let response: ChatCompletionResponse = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(response.object, "chat.completion");
+    assert_eq!(response.model, "echo-model");
+    assert_eq!(response.choices[0].message.role, "assistant");
+    assert_eq!(response.choices[0].message.content, "echo: hello gateway");
+    assert_eq!(response.usage.unwrap().total_tokens, 5);
+}
+
+#[tokio::test]
+async fn test_chat_completion_unknown_model() {
+    setup_dispatcher().await;
+
+    let request: ChatCompletionRequest = serde_json::from_value(json!({
+        "model": "missing-model",
+        "messages": [{"role": "user", "content": "hi"}]
+    })).unwrap();
+
+    let (status, Json(error)) = create_chat_completion(HeaderMap::new(), None, StreamingJson(request)).await.unwrap_err();
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(error.error.code.as_deref(), Some("model_not_found"));
+}
+
+#[tokio::test]
+async fn test_chat_completion_stream_sse() {
+    setup_dispatcher().await;
+
+    let request: ChatCompletionRequest = serde_json::from_value(json!({
+        "model": "echo-model",
+        "messages": [{"role": "user", "content": "hello stream"}],
+        "stream": true
+    })).unwrap();
+
+    let response = create_chat_completion(HeaderMap::new(), None, StreamingJson(request)).await.expect("stream request failed");
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+
+    let body = body_text(response).await;
+    let frames: Vec<&str> = body
+        .lines()
+        .filter_map(|line| line.strip_prefix("data: "))
+        .collect();
+    assert_eq!(frames.last(), Some(&"[DONE]"));
+
+    let chunks: Vec<Value> = frames[..frames.len() - 1]
+        .iter()
+        .map(|frame| serde_json::from_str(frame).unwrap())
+        .collect();
+    assert_eq!(chunks.len(), 3);
+    assert!(chunks.iter().all(|c| c["object"] == "chat.completion.chunk"));
+    assert_eq!(chunks[0]["choices"][0]["delta"]["role"], "assistant");
+
+    let content: String = chunks
+        .iter()
+        .filter_map(|c| c["choices"][0]["delta"]["content"].as_str())
+        .collect();
+    assert_eq!(content, "echo: hello stream");
+
+    let last = chunks.last().unwrap();
+    assert_eq!(last["choices"][0]["finish_reason"], "stop");
+    assert_eq!(last["usage"]["total_tokens"], 5);
+}
+
+#[tokio::test]
+async fn test_chat_completion_error_body_matches_openai() {
+    setup_dispatcher().await;
+
+    let request: ChatCompletionRequest = serde_json::from_value(json!({
+        "model": "echo-model",
+        "messages": []
+    })).unwrap();
+
+    let (status, Json(error)) = create_chat_completion(HeaderMap::new(), None, StreamingJson(request)).await.unwrap_err();
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(serde_json::to_value(&error).unwrap(), json!({
+        "error": {
+            "message": "messages must not be empty",
+            "type": "invalid_request_error",
+            "param": "messages",
+            "code": null
+        }
+    }));
+}
+
+#[test]
+fn test_upstream_errors_map_to_retryable_status() {
+    let upstream = |status_code| LLMError::ClientError(ClientError::LLMApi {
+        message: "upstream".to_string(),
+        status_code: Some(status_code),
+    });
+
+    // OpenAI SDK 对 429 和 5xx 自动重试，对 4xx 参数错误不重试
+    assert_eq!(upstream(429).error_code(), GatewayErrorCode::RateLimitExceeded);
+    assert_eq!(upstream(400).error_code(), GatewayErrorCode::InvalidRequest);
+    assert_eq!(upstream(401).error_code(), GatewayErrorCode::UpstreamError);
+    assert!(upstream(429).error_code().is_retryable());
+    assert!(upstream(503).error_code().is_retryable());
+    assert!(!upstream(400).error_code().is_retryable());
+    assert_eq!(LLMError::Timeout.error_code().status_code(), 504);
+    assert_eq!(GatewayErrorCode::RateLimitExceeded.to_openai_error("slow down", None).error.code.as_deref(), Some("rate_limit_exceeded"));
+}
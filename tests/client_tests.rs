@@ -10,8 +10,9 @@
 
 use project_rust_learn::llm_api::utils::client::{
     BaseClient, ClientConfig, ClientError, TimeoutConfig, RetryConfig,
-    RequestContext, ClientMetrics
+    RequestContext, ClientMetrics, DEFAULT_MAX_STREAM_LINE_BYTES
 };
+use project_rust_learn::llm_api::utils::stream_accounting::OllamaStreamAccounting;
 use project_rust_learn::dao::{init_sqlite_pool, init_db};
 use serde_json::json;
 use std::time::Duration;
@@ -128,7 +129,8 @@ mod tests {
 
         let retry_error = ClientError::RetryExhausted { 
             attempts: 3, 
-            last_error: "Network error".to_string() 
+            last_error: "Network error".to_string(),
+            status_code: None,
         };
         assert!(format!("{}", retry_error).contains("Retry exhausted after 3 attempts: Network error"));
     }
@@ -446,7 +448,7 @@ mod tests {
             true // 继续处理
         };
 
-        let result = client.post_stream(&format!("{}/api/chat/stream", server.url()), request_body, callback).await;
+        let result = client.post_stream(&format!("{}/api/chat/stream", server.url()), request_body, &OllamaStreamAccounting, callback).await;
         
         assert!(result.is_ok());
         
@@ -487,11 +489,39 @@ mod tests {
             chunk_count < 2 // 只处理前两个数据块
         };
 
-        let result = client.post_stream(&format!("{}/api/chat/stream", server.url()), request_body, callback).await;
+        let result = client.post_stream(&format!("{}/api/chat/stream", server.url()), request_body, &OllamaStreamAccounting, callback).await;
         
         assert!(result.is_ok());
         assert_eq!(chunk_count, 2);
-        
+
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_post_stream_line_too_long() {
+        let mut server = Server::new_async().await;
+
+        // 第一行正常，第二行超出长度限制且没有换行
+        let stream_data = format!("data: {{\"response\": \"你好\"}}\n\ndata: {}", "x".repeat(256));
+        let mock = server.mock("POST", "/api/chat/stream")
+            .with_status(200)
+            .with_header("content-type", "text/plain")
+            .with_body(stream_data)
+            .create_async().await;
+
+        let config = ClientConfig::new().with_max_stream_line_bytes(64);
+        assert_eq!(ClientConfig::new().max_stream_line_bytes, DEFAULT_MAX_STREAM_LINE_BYTES);
+        let client = BaseClient::new(config).unwrap();
+
+        let mut received_chunks = Vec::new();
+        let result = client.post_stream(&format!("{}/api/chat/stream", server.url()), json!({}), &OllamaStreamAccounting, |chunk| {
+            received_chunks.push(chunk);
+            true
+        }).await;
+
+        assert!(matches!(result, Err(ClientError::StreamLineTooLong { limit: 64 })));
+        assert_eq!(received_chunks, vec!["data: {\"response\": \"你好\"}".to_string()]);
+
         mock.assert_async().await;
     }
 
@@ -0,0 +1,175 @@
+//! # 集成测试公共工具
+//!
+//! 各测试文件通过 `mod common;` 引入，只使用其中的一部分
+
+#![allow(dead_code)]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use project_rust_learn::dao::call_log::CallLog;
+use project_rust_learn::dao::provider_key_pool::{set_master_keyring, MasterKeyring};
+use project_rust_learn::llm_api::dispatcher::{
+    DispatchRequest, DispatchResponse, LLMClientAdapter, LLMError, Provider, StreamReceiver,
+};
+
+/// 安装测试用的主密钥环：使用内置旧密钥加密，与共享测试数据库中已有的密文兼容。
+/// 未配置主密钥时网关拒绝加密新的 Key，写入 Key 的测试需要先调用
+pub fn install_test_master_keyring() {
+    static INSTALLED: std::sync::Once = std::sync::Once::new();
+    INSTALLED.call_once(|| set_master_keyring(MasterKeyring::legacy()));
+}
+
+/// 构造一个字段取默认值的成功响应，测试只需关心内容和来源
+pub fn response(provider: Provider, model: &str, content: impl Into<String>) -> DispatchResponse {
+    DispatchResponse {
+        content: content.into(),
+        provider,
+        model: model.to_string(),
+        usage: None,
+        finish_reason: Some("stop".to_string()),
+        request_id: None,
+        created_at: String::new(),
+        total_duration: None,
+        tool_calls: None,
+        message: None,
+        raw: None,
+        warnings: None,
+    }
+}
+
+/// 构造一条调用日志，其余字段按需用结构体更新语法覆盖：
+/// `CallLog { cost: 0.5, ..common::call_log("ollama", 200) }`
+pub fn call_log(provider: &str, status_code: i64) -> CallLog {
+    CallLog {
+        id: uuid::Uuid::new_v4().to_string(),
+        model_id: None,
+        status_code,
+        total_duration: 100,
+        tokens_input: 10,
+        tokens_output: 20,
+        cost: 0.0,
+        provider: Some(provider.to_string()),
+        key_id: None,
+        request_summary: None,
+        finish_reason: None,
+        provider_request_id: None,
+        provider_usage: None,
+        error_message: (status_code != 200).then(|| "upstream error".to_string()),
+        detected_language: None,
+        project_id: None,
+        traffic_arm: None,
+        request_id: None,
+        created_at: None,
+    }
+}
+
+type ReplyFn = dyn Fn(&DispatchRequest) -> Result<DispatchResponse, LLMError> + Send + Sync;
+
+/// 可配置的测试适配器：默认对任意请求回复 "ok"，流式请求返回错误。
+/// 每次调用都会计数并记录请求，测试通过 `calls()` / `requests()` 取得句柄后再注册
+pub struct MockAdapter {
+    provider: Provider,
+    models: Vec<String>,
+    reply: Arc<ReplyFn>,
+    delay: Option<Duration>,
+    key_pool: bool,
+    calls: Arc<AtomicUsize>,
+    requests: Arc<Mutex<Vec<DispatchRequest>>>,
+}
+
+impl MockAdapter {
+    pub fn new(provider: Provider) -> Self {
+        let reply_provider = provider.clone();
+        Self {
+            provider,
+            models: Vec::new(),
+            reply: Arc::new(move |request| Ok(response(reply_provider.clone(), &request.model, "ok"))),
+            delay: None,
+            key_pool: false,
+            calls: Arc::new(AtomicUsize::new(0)),
+            requests: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// 声明支持的模型
+    pub fn with_models(mut self, models: &[&str]) -> Self {
+        self.models = models.iter().map(|m| m.to_string()).collect();
+        self
+    }
+
+    /// 按请求生成回复内容，其余字段使用 [`response`] 的默认值
+    pub fn with_content<F>(self, content: F) -> Self
+    where
+        F: Fn(&DispatchRequest) -> String + Send + Sync + 'static,
+    {
+        let provider = self.provider.clone();
+        self.with_reply(move |request| Ok(response(provider.clone(), &request.model, content(request))))
+    }
+
+    /// 完全自定义回复，可返回错误
+    pub fn with_reply<F>(mut self, reply: F) -> Self
+    where
+        F: Fn(&DispatchRequest) -> Result<DispatchResponse, LLMError> + Send + Sync + 'static,
+    {
+        self.reply = Arc::new(reply);
+        self
+    }
+
+    /// 回复前等待一段时间，模拟慢上游
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// 声明依赖 Key 池，Key 池中没有可用的 Key 时调度器会跳过该适配器
+    pub fn with_key_pool(mut self) -> Self {
+        self.key_pool = true;
+        self
+    }
+
+    /// 使用外部持有的调用计数器
+    pub fn with_call_counter(mut self, calls: Arc<AtomicUsize>) -> Self {
+        self.calls = calls;
+        self
+    }
+
+    pub fn calls(&self) -> Arc<AtomicUsize> {
+        self.calls.clone()
+    }
+
+    pub fn requests(&self) -> Arc<Mutex<Vec<DispatchRequest>>> {
+        self.requests.clone()
+    }
+}
+
+#[async_trait]
+impl LLMClientAdapter for MockAdapter {
+    async fn generate(&self, request: &DispatchRequest) -> Result<DispatchResponse, LLMError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        self.requests.lock().unwrap().push(request.clone());
+        if let Some(delay) = self.delay {
+            tokio::time::sleep(delay).await;
+        }
+        (self.reply)(request)
+    }
+
+    async fn generate_stream(&self, _request: &DispatchRequest) -> Result<StreamReceiver, LLMError> {
+        Err(LLMError::InvalidParameters("stream not supported".to_string()))
+    }
+
+    fn supported_models(&self) -> Vec<String> {
+        self.models.clone()
+    }
+
+    fn provider_name(&self) -> Provider {
+        self.provider.clone()
+    }
+
+    fn uses_key_pool(&self) -> bool {
+        self.key_pool
+    }
+}
@@ -0,0 +1,335 @@
+//! # 文本补全测试
+//!
+//! 使用 mockito 模拟 Ollama `/api/generate` 和 OpenAI `/completions` 接口，测试客户端的
+//! 非流式和流式补全；通过测试适配器测试 `POST /v1/completions` 的响应格式、SSE 输出，
+//! 以及不支持补全的供应商返回错误、按租户的黑名单规则同样检查后缀
+
+mod common;
+
+use std::sync::Arc;
+use async_trait::async_trait;
+use axum::{body::to_bytes, http::{HeaderMap, StatusCode}, Json};
+use mockito::{Matcher, Server};
+use serde_json::{json, Value};
+
+use project_rust_learn::api_types::v1::GatewayErrorCode;
+use project_rust_learn::dao::blocklist::BlocklistEntry;
+use project_rust_learn::llm_api::dispatcher::{
+    CompletionRequest, DispatchRequest, DispatchResponse, LLMClientAdapter, LLMDispatcher, LLMError, Provider,
+    StreamChunk, StreamReceiver, TokenUsage, GLOBAL_DISPATCHER,
+};
+use project_rust_learn::llm_api::ollama::client::{OllamaClient, OllamaGenerateRequest};
+use project_rust_learn::llm_api::openai::client::{OpenAIClient, OpenAICompletionRequest};
+use project_rust_learn::llm_api::utils::{blocklist::get_blocklist, chat_traits::CompletionResponseTrait, client::ClientConfig};
+use project_rust_learn::web::dto::completion_dto::{CompletionCreateRequest, CompletionResponse};
+use project_rust_learn::web::extract::StreamingJson;
+use project_rust_learn::web::handlers::completion_handler::create_completion;
+use common::response;
+
+/// `complete-model` 续写提示词；`chat-only` 只支持对话，补全走默认实现
+struct CompletionAdapter;
+
+#[async_trait]
+impl LLMClientAdapter for CompletionAdapter {
+    async fn generate(&self, _request: &DispatchRequest) -> Result<DispatchResponse, LLMError> {
+        Err(LLMError::InvalidParameters("chat is not used in this test".to_string()))
+    }
+
+    async fn generate_stream(&self, _request: &DispatchRequest) -> Result<StreamReceiver, LLMError> {
+        Err(LLMError::InvalidParameters("chat is not used in this test".to_string()))
+    }
+
+    async fn complete(&self, request: &CompletionRequest) -> Result<DispatchResponse, LLMError> {
+        Ok(DispatchResponse {
+            usage: Some(TokenUsage { prompt_tokens: 2, completion_tokens: 1, total_tokens: 3 }),
+            ..response(Provider::Ollama, &request.model, format!("{} world", request.prompt))
+        })
+    }
+
+    async fn complete_stream(&self, _request: &CompletionRequest) -> Result<StreamReceiver, LLMError> {
+        let (tx, rx) = tokio::sync::mpsc::channel(4);
+        tx.send(Ok(StreamChunk::delta(" wor".to_string()))).await.ok();
+        tx.send(Ok(StreamChunk::delta("ld".to_string()))).await.ok();
+        tx.send(Ok(StreamChunk::finished(
+            Some("stop".to_string()),
+            Some(TokenUsage { prompt_tokens: 2, completion_tokens: 1, total_tokens: 3 }),
+        ))).await.ok();
+        Ok(rx)
+    }
+
+    fn supported_models(&self) -> Vec<String> {
+        vec!["complete-model".to_string()]
+    }
+
+    fn provider_name(&self) -> Provider {
+        Provider::Ollama
+    }
+}
+
+struct ChatOnlyAdapter;
+
+#[async_trait]
+impl LLMClientAdapter for ChatOnlyAdapter {
+    async fn generate(&self, _request: &DispatchRequest) -> Result<DispatchResponse, LLMError> {
+        Err(LLMError::InvalidParameters("chat is not used in this test".to_string()))
+    }
+
+    async fn generate_stream(&self, _request: &DispatchRequest) -> Result<StreamReceiver, LLMError> {
+        Err(LLMError::InvalidParameters("chat is not used in this test".to_string()))
+    }
+
+    fn supported_models(&self) -> Vec<String> {
+        vec!["chat-only".to_string()]
+    }
+
+    fn provider_name(&self) -> Provider {
+        Provider::Ali
+    }
+}
+
+async fn setup_dispatcher() {
+    if GLOBAL_DISPATCHER.get().is_none() {
+        let dispatcher = LLMDispatcher::new(None);
+        dispatcher.register_client(Box::new(CompletionAdapter)).await;
+        dispatcher.register_client(Box::new(ChatOnlyAdapter)).await;
+        GLOBAL_DISPATCHER.set(Arc::new(dispatcher)).ok();
+    }
+}
+
+fn create_ollama_client(base_url: String) -> OllamaClient {
+    let http_client = reqwest::Client::builder().no_proxy().build().unwrap();
+    OllamaClient::new_with_client(base_url, ClientConfig::default(), http_client).unwrap()
+}
+
+#[tokio::test]
+async fn test_ollama_generate() {
+    println!("=== 测试 Ollama /api/generate ===");
+    let mut server = Server::new_async().await;
+    let mock = server.mock("POST", "/api/generate")
+        .match_body(Matcher::PartialJson(json!({"model": "llama3", "prompt": "Once upon", "stream": false})))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!({
+            "model": "llama3",
+            "created_at": "2025-01-01T00:00:00Z",
+            "response": " a time",
+            "done": true,
+            "done_reason": "stop",
+            "prompt_eval_count": 3,
+            "eval_count": 2
+        }).to_string())
+        .create_async()
+        .await;
+
+    let client = create_ollama_client(server.url());
+    let response = client.generate(OllamaGenerateRequest::new("llama3".to_string(), "Once upon".to_string()))
+        .await
+        .expect("generate failed");
+
+    assert_eq!(response.get_text().as_deref(), Some(" a time"));
+    assert_eq!(response.get_finish_reason().as_deref(), Some("stop"));
+    assert_eq!(response.get_prompt_eval_count(), Some(3));
+    assert_eq!(response.get_eval_count(), Some(2));
+    mock.assert_async().await;
+    println!("✅ Ollama 补全响应解析正确");
+}
+
+#[tokio::test]
+async fn test_ollama_generate_stream() {
+    println!("=== 测试 Ollama /api/generate 流式输出 ===");
+    let mut server = Server::new_async().await;
+    let body = [
+        json!({"model": "llama3", "created_at": "t", "response": " a", "done": false}),
+        json!({"model": "llama3", "created_at": "t", "response": " time", "done": false}),
+        json!({"model": "llama3", "created_at": "t", "response": "", "done": true, "eval_count": 2}),
+    ].iter().map(|line| format!("{}\n", line)).collect::<String>();
+    let mock = server.mock("POST", "/api/generate")
+        .match_body(Matcher::PartialJson(json!({"stream": true})))
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let client = create_ollama_client(server.url());
+    let mut text = String::new();
+    let mut finish_reason = None;
+    client.generate_stream(OllamaGenerateRequest::new("llama3".to_string(), "Once upon".to_string()), |chunk| {
+        text.push_str(&chunk.response);
+        finish_reason = chunk.get_finish_reason();
+        true
+    }).await.expect("generate stream failed");
+
+    assert_eq!(text, " a time");
+    // 旧版本不返回 done_reason 时视为 stop
+    assert_eq!(finish_reason.as_deref(), Some("stop"));
+    mock.assert_async().await;
+    println!("✅ Ollama 流式补全拼接正确");
+}
+
+#[tokio::test]
+async fn test_openai_complete() {
+    println!("=== 测试 OpenAI /completions ===");
+    let mut server = Server::new_async().await;
+    let mock = server.mock("POST", "/completions")
+        .match_header("authorization", "Bearer sk-test")
+        .match_body(Matcher::PartialJson(json!({"model": "gpt-3.5-turbo-instruct", "prompt": "Say hi", "stream": false})))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!({
+            "id": "cmpl-123",
+            "object": "text_completion",
+            "created": 1757412000,
+            "model": "gpt-3.5-turbo-instruct",
+            "choices": [{"text": "Hi!", "index": 0, "finish_reason": "stop"}],
+            "usage": {"prompt_tokens": 2, "completion_tokens": 2, "total_tokens": 4}
+        }).to_string())
+        .create_async()
+        .await;
+
+    let client = OpenAIClient::new_with_base_url("sk-test".to_string(), server.url()).unwrap();
+    let response = client.complete(OpenAICompletionRequest::new("gpt-3.5-turbo-instruct".to_string(), "Say hi".to_string()))
+        .await
+        .expect("complete failed");
+
+    assert_eq!(response.get_text().as_deref(), Some("Hi!"));
+    assert!(response.is_done());
+    assert_eq!(response.get_eval_count(), Some(2));
+    mock.assert_async().await;
+    println!("✅ OpenAI 补全响应解析正确");
+}
+
+#[tokio::test]
+async fn test_openai_complete_stream() {
+    println!("=== 测试 OpenAI /completions 流式输出 ===");
+    let mut server = Server::new_async().await;
+    let chunk = |text: &str, finish_reason: Value| json!({
+        "id": "cmpl-1", "object": "text_completion", "created": 1, "model": "gpt-3.5-turbo-instruct",
+        "choices": [{"text": text, "index": 0, "finish_reason": finish_reason}]
+    });
+    let body = format!(
+        "data: {}\n\ndata: {}\n\ndata: [DONE]\n\n",
+        chunk("Hi", Value::Null),
+        chunk(" there", json!("stop")),
+    );
+    let mock = server.mock("POST", "/completions")
+        .match_body(Matcher::PartialJson(json!({"stream": true})))
+        .with_status(200)
+        .with_header("content-type", "text/event-stream")
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let client = OpenAIClient::new_with_base_url("sk-test".to_string(), server.url()).unwrap();
+    let mut text = String::new();
+    let mut done = false;
+    client.complete_stream(OpenAICompletionRequest::new("gpt-3.5-turbo-instruct".to_string(), "Say hi".to_string()), |chunk| {
+        text.push_str(&chunk.get_text().unwrap_or_default());
+        done = chunk.is_done();
+        true
+    }).await.expect("complete stream failed");
+
+    assert_eq!(text, "Hi there");
+    assert!(done);
+    mock.assert_async().await;
+    println!("✅ OpenAI 流式补全拼接正确");
+}
+
+#[tokio::test]
+async fn test_completion_endpoint() {
+    println!("=== 测试 POST /v1/completions ===");
+    setup_dispatcher().await;
+
+    let request: CompletionCreateRequest = serde_json::from_value(json!({
+        "model": "complete-model",
+        "prompt": "hello",
+        "stop": "\n"
+    })).unwrap();
+    let response = create_completion(HeaderMap::new(), StreamingJson(request)).await.expect("completion failed");
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let completion: CompletionResponse = serde_json::from_slice(&bytes).unwrap();
+
+    assert_eq!(completion.object, "text_completion");
+    assert!(completion.id.starts_with("cmpl-"));
+    assert_eq!(completion.choices[0].text, "hello world");
+    assert_eq!(completion.choices[0].finish_reason.as_deref(), Some("stop"));
+    assert_eq!(completion.usage.as_ref().map(|u| u.total_tokens), Some(3));
+    println!("✅ 非流式补全返回 text_completion 格式");
+}
+
+#[tokio::test]
+async fn test_completion_endpoint_stream() {
+    println!("=== 测试 POST /v1/completions 流式输出 ===");
+    setup_dispatcher().await;
+
+    let request: CompletionCreateRequest = serde_json::from_value(json!({
+        "model": "complete-model",
+        "prompt": "hello",
+        "stream": true
+    })).unwrap();
+    let response = create_completion(HeaderMap::new(), StreamingJson(request)).await.expect("stream request failed");
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body = String::from_utf8(bytes.to_vec()).unwrap();
+
+    let events: Vec<&str> = body.lines().filter_map(|line| line.strip_prefix("data: ")).collect();
+    assert_eq!(events.last(), Some(&"[DONE]"));
+    let chunks: Vec<Value> = events[..events.len() - 1].iter().map(|e| serde_json::from_str(e).unwrap()).collect();
+    let text: String = chunks.iter().filter_map(|c| c["choices"][0]["text"].as_str()).collect();
+    assert_eq!(text, " world");
+    assert!(chunks.iter().all(|c| c["object"] == "text_completion"));
+    let last = chunks.last().unwrap();
+    assert_eq!(last["choices"][0]["finish_reason"], "stop");
+    assert_eq!(last["usage"]["total_tokens"], 3);
+    println!("✅ 流式补全以 [DONE] 结束");
+}
+
+#[tokio::test]
+async fn test_completion_unsupported_provider() {
+    println!("=== 测试不支持补全的供应商 ===");
+    setup_dispatcher().await;
+
+    let request: CompletionCreateRequest = serde_json::from_value(json!({
+        "model": "chat-only",
+        "prompt": "hello"
+    })).unwrap();
+    let (status, Json(error)) = create_completion(HeaderMap::new(), StreamingJson(request)).await.unwrap_err();
+    assert_eq!(status.as_u16(), GatewayErrorCode::UnsupportedProvider.status_code());
+    assert_eq!(error.error.code.as_deref(), GatewayErrorCode::UnsupportedProvider.code());
+
+    let request: CompletionCreateRequest = serde_json::from_value(json!({
+        "model": "complete-model",
+        "prompt": ""
+    })).unwrap();
+    let (status, _) = create_completion(HeaderMap::new(), StreamingJson(request)).await.unwrap_err();
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    println!("✅ 不支持补全的供应商返回 unsupported_provider");
+}
+
+#[tokio::test]
+async fn test_completion_blocklist_checks_tenant_and_suffix() {
+    println!("=== 测试补全请求的租户黑名单 ===");
+    setup_dispatcher().await;
+    let dispatcher = GLOBAL_DISPATCHER.get().unwrap();
+    let tenant = format!("completion-tenant-{}", uuid::Uuid::new_v4().simple());
+    // 只对该租户生效的规则，不影响同一进程中的其它测试
+    get_blocklist().load_entries(&[BlocklistEntry {
+        id: uuid::Uuid::new_v4().to_string(),
+        tenant_id: Some(tenant.clone()),
+        pattern: "forbidden".to_string(),
+        action: "block".to_string(),
+        scope: "both".to_string(),
+        is_active: true,
+        created_at: None,
+        updated_at: None,
+    }]).await;
+
+    let request = || CompletionRequest::new(Provider::Ollama, "complete-model".to_string(), "def main():".to_string())
+        .with_suffix("# forbidden".to_string());
+    let error = dispatcher.dispatch_completion(request().with_tenant_id(tenant)).await.unwrap_err();
+    assert!(matches!(error, LLMError::ContentBlocked(_)), "unexpected error: {:?}", error);
+    println!("✅ 租户规则命中后缀时拒绝");
+
+    let response = dispatcher.dispatch_completion(request()).await.expect("other tenants are not affected");
+    assert_eq!(response.content, "def main(): world");
+    println!("✅ 其它租户不受影响");
+}
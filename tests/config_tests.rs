@@ -0,0 +1,122 @@
+//! # 启动配置测试
+//!
+//! 测试 gateway.toml 的解析、默认值、环境变量覆盖和无效配置的报错，
+//! 以及示例配置文件与默认值一致
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use project_rust_learn::config::GatewayConfig;
+use project_rust_learn::llm_api::utils::load_balancer::LoadBalancePolicy;
+use project_rust_learn::web::middleware::timeout::RouteTimeouts;
+
+fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+    let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    move |name| vars.get(name).cloned()
+}
+
+#[test]
+fn test_load_partial_config() {
+    println!("=== Testing gateway.toml ===");
+    let config = GatewayConfig::from_toml_str(r#"
+[database]
+url = "sqlite://data/test.db"
+
+[cache]
+max_entries = 5_000
+
+[dispatcher]
+default_retry_count = 1
+
+[dispatcher.traffic_splits]
+qwen = [
+    { name = "plus", provider = "ali", model = "qwen-plus", percent = 90 },
+    { name = "max", provider = "ali", model = "qwen-max", percent = 10 },
+]
+
+[providers]
+ollama_base_url = "http://ollama.internal:11434"
+"#).expect("config should parse");
+
+    let defaults = GatewayConfig::default();
+    assert_eq!(config.database.url, "sqlite://data/test.db");
+    assert_eq!(config.database.init_sql_path, defaults.database.init_sql_path);
+    assert_eq!(config.cache.max_entries, 5000);
+    assert_eq!(config.cache.ttl_secs, 3600);
+    assert_eq!(config.dispatcher.default_retry_count, 1);
+    assert_eq!(config.dispatcher.dispatch_config().default_retry_count, 1);
+    assert_eq!(config.dispatcher.dispatch_config().traffic_splits["qwen"].arms()[1].model, "qwen-max");
+    assert_eq!(config.dispatcher.dispatch_config().default_timeout_ms, 180_000);
+    assert_eq!(config.providers.base_url("ollama"), Some("http://ollama.internal:11434"));
+    assert_eq!(config.providers.base_url("openai"), Some("https://api.openai.com/v1"));
+    assert_eq!(config.providers.base_url("azure"), None);
+    assert_eq!(config.server, defaults.server);
+    println!("✅ Missing keys fall back to defaults");
+}
+
+#[test]
+fn test_env_overrides() {
+    println!("=== Testing Env Overrides ===");
+    let config = GatewayConfig::from_toml_str("[server]\nbind_addr = \"0.0.0.0:9000\"\nchat_timeout_secs = 60\n")
+        .unwrap()
+        .with_env_overrides(env(&[
+            ("BIND_ADDR", "127.0.0.1:7000"),
+            ("CACHE_TTL_SECS", "60"),
+            ("DISPATCH_TIMEOUT_MS", "30000"),
+            ("DISPATCH_LOAD_BALANCE", "fastest"),
+            ("LOG_LEVEL", "debug"),
+            ("LOG_JSON", "true"),
+            ("DATABASE_URL", "  "),
+        ]))
+        .expect("overrides should apply");
+
+    assert_eq!(config.server.bind_addr, "127.0.0.1:7000");
+    assert_eq!(config.server.socket_addr().unwrap().port(), 7000);
+    assert_eq!(config.cache.ttl_secs, 60);
+    assert_eq!(config.dispatcher.default_timeout_ms, 30_000);
+    assert_eq!(config.dispatcher.dispatch_config().load_balance_policy, LoadBalancePolicy::Fastest);
+    assert_eq!(config.logging.level, "debug");
+    assert!(config.logging.log_config().json_format);
+    // 空值忽略
+    assert_eq!(config.database.url, "sqlite://data/app.db");
+    // 文件中的值没有被覆盖时保留
+    assert_eq!(
+        RouteTimeouts::from_config(&config.server),
+        RouteTimeouts { admin: Duration::from_secs(10), chat: Duration::from_secs(60) },
+    );
+    println!("✅ Environment variables override file values");
+}
+
+#[test]
+fn test_invalid_config() {
+    println!("=== Testing Invalid Config ===");
+    let error = |content: &str| format!("{:#}", GatewayConfig::from_toml_str(content).unwrap_err());
+
+    assert!(error("[cache]\nttl = 10").contains("unknown field `ttl`"));
+    assert!(error("[server]\nbind_addr = \"localhost\"").contains("Invalid bind address"));
+    assert!(error("[logging]\nlevel = \"verbose\"").contains("Unknown log level"));
+    assert!(error("[cache]\nmax_entries = \"many\"").contains("invalid type"));
+    assert!(error("[database\nurl = 1").contains("line 1"));
+    assert!(error(r#"
+[dispatcher.traffic_splits]
+qwen = [
+    { name = "plus", provider = "ali", model = "qwen-plus", percent = 90 },
+    { name = "max", provider = "ali", model = "qwen-max", percent = 20 },
+]
+"#).contains("must add up to 100"));
+    assert!(error("[dispatcher.shadow]\npercent = 10").contains("dispatcher.shadow.provider must be set"));
+
+    let env_error = GatewayConfig::default()
+        .with_env_overrides(env(&[("DISPATCH_RETRY_COUNT", "three")]))
+        .unwrap_err();
+    assert!(env_error.to_string().contains("DISPATCH_RETRY_COUNT"));
+    println!("✅ Invalid config rejected");
+}
+
+#[test]
+fn test_example_config_matches_defaults() {
+    println!("=== Testing gateway.example.toml ===");
+    let config = GatewayConfig::from_file("gateway.example.toml").expect("example config should parse");
+    assert_eq!(config, GatewayConfig::default());
+    println!("✅ Example config documents the defaults");
+}
@@ -0,0 +1,71 @@
+//! # 共享连接池测试
+//!
+//! 测试连接池配置的构建，以及共用连接池的客户端按各自的 API Key 发送认证头
+
+use mockito::{Matcher, Server};
+use std::time::Duration;
+
+use project_rust_learn::dao::{init_sqlite_pool, init_db};
+use project_rust_learn::llm_api::ali::client::{AliChatRequest, AliClient};
+use project_rust_learn::llm_api::utils::client::{ClientConfig, ConnectionConfig, LLMClientTrait};
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+
+#[test]
+fn test_connection_config_builders() {
+    let connection = ConnectionConfig::default();
+    assert_eq!(connection.pool_max_idle_per_host, None);
+    assert_eq!(connection.pool_idle_timeout, Some(Duration::from_secs(90)));
+    assert_eq!(connection.tcp_keepalive, Some(Duration::from_secs(60)));
+    assert!(!connection.http2_prior_knowledge);
+
+    let connection = ConnectionConfig::new()
+        .with_pool_max_idle_per_host(16)
+        .with_pool_idle_timeout(None)
+        .with_http2_keep_alive(Duration::from_secs(30), Duration::from_secs(5), true)
+        .with_http2_adaptive_window(true);
+    assert_eq!(connection.pool_max_idle_per_host, Some(16));
+    assert_eq!(connection.pool_idle_timeout, None);
+    assert_eq!(connection.http2_keep_alive_interval, Some(Duration::from_secs(30)));
+    assert_eq!(connection.http2_keep_alive_timeout, Some(Duration::from_secs(5)));
+    assert!(connection.http2_keep_alive_while_idle && connection.http2_adaptive_window);
+    println!("✅ Connection config builders work");
+
+    // 显式指定的共享连接池不会被供应商默认值覆盖
+    let config = ClientConfig::new().with_connection(connection.clone()).with_shared_pool("custom");
+    assert_eq!(config.with_default_shared_pool("ali").shared_pool.as_deref(), Some("custom"));
+    assert_eq!(ClientConfig::new().with_default_shared_pool("ali").shared_pool.as_deref(), Some("ali"));
+    println!("✅ Explicit shared pool kept");
+}
+
+#[tokio::test]
+async fn test_shared_pool_sends_per_client_auth_header() {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    init_db("data/init.sql").await.expect("DB init failed");
+
+    println!("=== Testing Shared Pool Auth Headers ===");
+    let mut server = Server::new_async().await;
+    let body = r#"{"id":"chatcmpl-1","object":"chat.completion","created":1,"model":"qwen-plus","choices":[{"index":0,"message":{"role":"assistant","content":"ok"},"finish_reason":"stop"}],"usage":{"prompt_tokens":1,"completion_tokens":1,"total_tokens":2}}"#;
+    let mut mocks = Vec::new();
+    for key in ["key-a", "key-b"] {
+        mocks.push(server.mock("POST", "/compatible-mode/v1/chat/completions")
+            .match_header("authorization", Matcher::Exact(format!("Bearer {}", key)))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(body)
+            .expect(1)
+            .create_async()
+            .await);
+    }
+
+    let config = ClientConfig::new().with_connection(ConnectionConfig::new().with_pool_max_idle_per_host(4));
+    for key in ["key-a", "key-b"] {
+        let client = AliClient::new_with_config(key.to_string(), server.url(), config.clone()).unwrap();
+        assert_eq!(client.base_client().config().shared_pool.as_deref(), Some("ali"));
+        let request = AliChatRequest::new("qwen-plus".to_string(), vec![Message::user("hi".to_string())]);
+        client.chat(request).await.expect("chat failed");
+    }
+    for mock in mocks {
+        mock.assert_async().await;
+    }
+    println!("✅ Each client sent its own Authorization header");
+}
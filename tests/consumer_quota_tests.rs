@@ -0,0 +1,175 @@
+//! # 调用方配额测试
+//!
+//! 测试按调用方 Key 限制每分钟请求数和每天 token 数：超出时返回 429 和 `X-RateLimit-*` 头，
+//! 匿名请求和未登记的 Key 按客户端地址计数，调度记录的 token 用量计入调用方，内存中的用量定期写回 `consumer_usage`
+
+use std::net::SocketAddr;
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::ConnectInfo,
+    http::{header, Request, StatusCode},
+    middleware::from_fn,
+    response::Response,
+    routing::post,
+    Router,
+};
+use serde_json::Value;
+use tower::Service;
+
+use project_rust_learn::dao::consumer_usage::{list_consumer_usage, ConsumerUsageFilter};
+use project_rust_learn::dao::{init_db, init_sqlite_pool, SQLITE_POOL};
+use project_rust_learn::llm_api::dispatcher::{Provider, TokenUsage};
+use project_rust_learn::llm_api::utils::client::CallMetadata;
+use project_rust_learn::llm_api::utils::consumer_quota::{
+    client_consumer_id, consumer_id, flush_consumer_usage, set_consumer_quota, ConsumerQuota,
+};
+use project_rust_learn::llm_api::utils::usage_recorder::record_call_usage;
+use project_rust_learn::web::middleware::quota::consumer_quota;
+
+/// 模拟调度完成后记录 150 个 token 的用量
+async fn completion() -> &'static str {
+    let usage = TokenUsage { prompt_tokens: 100, completion_tokens: 50, total_tokens: 150 };
+    record_call_usage(&CallMetadata::current(), &Provider::OpenAI, "quota-test-model", &usage, Some("stop")).await;
+    "ok"
+}
+
+fn app() -> Router {
+    Router::new().route("/v1/chat/completions", post(completion).route_layer(from_fn(consumer_quota)))
+}
+
+async fn send(app: &mut Router, api_key: Option<&str>) -> Response {
+    send_from(app, api_key, None).await
+}
+
+// 带上服务器记录的客户端地址
+async fn send_from(app: &mut Router, api_key: Option<&str>, client: Option<SocketAddr>) -> Response {
+    let mut request = Request::post("/v1/chat/completions");
+    if let Some(api_key) = api_key {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {}", api_key));
+    }
+    let mut request = request.body(Body::empty()).unwrap();
+    if let Some(client) = client {
+        request.extensions_mut().insert(ConnectInfo(client));
+    }
+    app.call(request).await.unwrap()
+}
+
+fn header_value<'a>(response: &'a Response, name: &str) -> Option<&'a str> {
+    response.headers().get(name).and_then(|v| v.to_str().ok())
+}
+
+async fn setup() {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    init_db("data/init.sql").await.expect("DB init failed");
+}
+
+#[tokio::test]
+async fn test_requests_per_minute_quota() {
+    setup().await;
+    let pool = SQLITE_POOL.get().unwrap();
+
+    println!("=== Testing Requests Per Minute ===");
+    let api_key = format!("sk-rpm-{}", uuid::Uuid::new_v4());
+    let consumer = consumer_id(&api_key);
+    set_consumer_quota(pool, &consumer, Some(ConsumerQuota { requests_per_minute: Some(2), tokens_per_day: None }))
+        .await
+        .expect("set quota failed");
+
+    let mut app = app();
+    for remaining in ["1", "0"] {
+        let response = send(&mut app, Some(&api_key)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(header_value(&response, "x-ratelimit-limit-requests"), Some("2"));
+        assert_eq!(header_value(&response, "x-ratelimit-remaining-requests"), Some(remaining));
+        assert!(header_value(&response, "x-ratelimit-limit-tokens").is_none());
+    }
+
+    let response = send(&mut app, Some(&api_key)).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(header_value(&response, "retry-after").is_some());
+    let body: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+    assert_eq!(body["error"]["code"], "rate_limit_exceeded");
+    println!("✅ Third request in a minute rejected");
+
+    set_consumer_quota(pool, &consumer, None).await.expect("delete quota failed");
+}
+
+#[tokio::test]
+async fn test_anonymous_and_unknown_keys_share_client_quota() {
+    setup().await;
+    let pool = SQLITE_POOL.get().unwrap();
+
+    println!("=== Testing Anonymous Callers ===");
+    let bytes = uuid::Uuid::new_v4().into_bytes();
+    let client: SocketAddr = format!("[2001:db8::{:x}{:02x}]:40000", bytes[0], bytes[1]).parse().unwrap();
+    let client_id = client_consumer_id(Some(client.ip()));
+    set_consumer_quota(pool, &client_id, Some(ConsumerQuota { requests_per_minute: Some(2), tokens_per_day: None }))
+        .await
+        .expect("set quota failed");
+    let registered_key = format!("sk-registered-{}", uuid::Uuid::new_v4());
+    let registered = consumer_id(&registered_key);
+    set_consumer_quota(pool, &registered, Some(ConsumerQuota { requests_per_minute: Some(5), tokens_per_day: None }))
+        .await
+        .expect("set quota failed");
+
+    let mut app = app();
+    let response = send_from(&mut app, None, Some(client)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(header_value(&response, "x-ratelimit-remaining-requests"), Some("1"));
+
+    // 未登记的 Key 与匿名请求共用客户端地址的计数，更换 Key 不能绕过
+    let response = send_from(&mut app, Some(&format!("sk-random-{}", uuid::Uuid::new_v4())), Some(client)).await;
+    assert_eq!(header_value(&response, "x-ratelimit-remaining-requests"), Some("0"));
+    let response = send_from(&mut app, Some(&format!("sk-random-{}", uuid::Uuid::new_v4())), Some(client)).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let response = send_from(&mut app, None, Some(client)).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    println!("✅ Anonymous callers and unknown keys limited by client address");
+
+    // 登记过的 Key 按 Key 计数
+    let response = send_from(&mut app, Some(&registered_key), Some(client)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(header_value(&response, "x-ratelimit-limit-requests"), Some("5"));
+    println!("✅ Registered keys counted separately");
+
+    set_consumer_quota(pool, &client_id, None).await.expect("delete quota failed");
+    set_consumer_quota(pool, &registered, None).await.expect("delete quota failed");
+}
+
+#[tokio::test]
+async fn test_tokens_per_day_quota_and_persistence() {
+    setup().await;
+    let pool = SQLITE_POOL.get().unwrap();
+
+    println!("=== Testing Tokens Per Day ===");
+    let api_key = format!("sk-tpd-{}", uuid::Uuid::new_v4());
+    let consumer = consumer_id(&api_key);
+    set_consumer_quota(pool, &consumer, Some(ConsumerQuota { requests_per_minute: None, tokens_per_day: Some(200) }))
+        .await
+        .expect("set quota failed");
+
+    let mut app = app();
+    let response = send(&mut app, Some(&api_key)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(header_value(&response, "x-ratelimit-remaining-tokens"), Some("200"));
+
+    // 第二次请求时还剩 50 个 token，仍然放行，之后额度用完
+    let response = send(&mut app, Some(&api_key)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(header_value(&response, "x-ratelimit-remaining-tokens"), Some("50"));
+    let response = send(&mut app, Some(&api_key)).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(header_value(&response, "x-ratelimit-remaining-tokens"), Some("0"));
+    println!("✅ Requests rejected after the daily token quota is used up");
+
+    flush_consumer_usage(pool).await.expect("flush failed");
+    let filter = ConsumerUsageFilter { consumer_id: Some(consumer.clone()), ..Default::default() };
+    let stats = list_consumer_usage(pool, &filter).await.expect("list usage failed");
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].request_count, 2);
+    assert_eq!(stats[0].tokens, 300);
+    println!("✅ Consumer usage persisted: {:?}", stats[0]);
+
+    set_consumer_quota(pool, &consumer, None).await.expect("delete quota failed");
+}
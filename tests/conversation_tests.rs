@@ -0,0 +1,183 @@
+//! # 会话管理测试
+//!
+//! 测试会话和消息的存取、发送消息时网关自动带上历史调用模型并保存回复、
+//! 超出 token 上限时截断历史，以及会话只对创建它的项目可见
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+use axum::{extract::{Path, Query}, http::StatusCode, Json};
+
+use project_rust_learn::dao::conversation::{
+    append_conversation_message, create_conversation, delete_conversation, get_conversation_by_id,
+    list_conversation_messages, Conversation, ConversationMessage,
+};
+use project_rust_learn::dao::{init_db, init_sqlite_pool, SQLITE_POOL};
+use project_rust_learn::llm_api::dispatcher::{
+    DispatchRequest, DispatchResponse, LLMClientAdapter, LLMDispatcher, LLMError, Provider, StreamReceiver, TokenUsage,
+    GLOBAL_DISPATCHER,
+};
+use project_rust_learn::llm_api::utils::client::{CallMetadata, CALL_METADATA};
+use project_rust_learn::web::dto::conversation_dto::{CreateConversationRequest, SendMessageRequest};
+use project_rust_learn::web::handlers::conversation_handler::{
+    create_new_conversation, get_conversation, list_project_conversations, send_conversation_message,
+    ListConversationsQuery,
+};
+use common::response;
+
+lazy_static::lazy_static! {
+    /// 适配器收到的请求消息
+    static ref RECEIVED: Mutex<Vec<DispatchRequest>> = Mutex::new(Vec::new());
+}
+
+/// 回复 `reply: <最后一条消息>` 的测试适配器
+struct ReplyAdapter;
+
+#[async_trait]
+impl LLMClientAdapter for ReplyAdapter {
+    async fn generate(&self, request: &DispatchRequest) -> Result<DispatchResponse, LLMError> {
+        RECEIVED.lock().unwrap().push(request.clone());
+        let last = request.messages.last().map(|m| m.content.clone()).unwrap_or_default();
+        Ok(DispatchResponse {
+            usage: Some(TokenUsage { prompt_tokens: 10, completion_tokens: 5, total_tokens: 15 }),
+            ..response(Provider::OpenAI, &request.model, format!("reply: {}", last))
+        })
+    }
+
+    async fn generate_stream(&self, _request: &DispatchRequest) -> Result<StreamReceiver, LLMError> {
+        Err(LLMError::InvalidParameters("stream not supported".to_string()))
+    }
+
+    fn supported_models(&self) -> Vec<String> {
+        vec!["conversation-model".to_string()]
+    }
+
+    fn provider_name(&self) -> Provider {
+        Provider::OpenAI
+    }
+}
+
+async fn setup() {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    init_db("data/init.sql").await.expect("DB init failed");
+    if GLOBAL_DISPATCHER.get().is_none() {
+        let dispatcher = LLMDispatcher::new(None);
+        dispatcher.register_client(Box::new(ReplyAdapter)).await;
+        GLOBAL_DISPATCHER.set(Arc::new(dispatcher)).ok();
+    }
+}
+
+fn in_project(project_id: &str) -> CallMetadata {
+    CallMetadata::default().with_project(Some(project_id.to_string()))
+}
+
+fn send(content: &str) -> Json<SendMessageRequest> {
+    Json(SendMessageRequest { content: content.to_string(), model: None, temperature: None, max_tokens: None })
+}
+
+#[tokio::test]
+async fn test_conversation_crud_operations() {
+    setup().await;
+    let pool = SQLITE_POOL.get().unwrap();
+
+    println!("=== Testing Conversation CRUD ===");
+    let conversation = Conversation {
+        id: format!("conv-{}", uuid::Uuid::new_v4().simple()),
+        title: Some("CRUD".to_string()),
+        model: "conversation-model".to_string(),
+        system_prompt: None,
+        max_context_tokens: None,
+        truncation: "drop_oldest".to_string(),
+        project_id: "default".to_string(),
+        created_at: None,
+        updated_at: None,
+    };
+    create_conversation(pool, &conversation).await.expect("create conversation failed");
+
+    for (role, content) in [("user", "hi"), ("assistant", "hello")] {
+        let message = ConversationMessage {
+            id: uuid::Uuid::new_v4().to_string(),
+            conversation_id: conversation.id.clone(),
+            seq: 0,
+            role: role.to_string(),
+            content: content.to_string(),
+            tokens: 1,
+            created_at: None,
+        };
+        append_conversation_message(pool, &message).await.expect("append failed");
+    }
+    let messages = list_conversation_messages(pool, &conversation.id).await.unwrap();
+    assert_eq!(messages.iter().map(|m| m.seq).collect::<Vec<_>>(), vec![1, 2]);
+    assert_eq!(messages[1].content, "hello");
+    println!("✅ Messages stored in order");
+
+    assert_eq!(delete_conversation(pool, &conversation.id).await.unwrap(), 1);
+    assert!(get_conversation_by_id(pool, &conversation.id).await.unwrap().is_none());
+    assert!(list_conversation_messages(pool, &conversation.id).await.unwrap().is_empty());
+    println!("✅ Conversation deleted with its messages");
+}
+
+#[tokio::test]
+async fn test_send_message_uses_stored_history() {
+    setup().await;
+    let project = format!("conv-project-{}", uuid::Uuid::new_v4().simple());
+
+    println!("=== Testing Stateful Chat ===");
+    let result = CALL_METADATA.scope(in_project(&project), async {
+        let Json(conversation) = create_new_conversation(Json(CreateConversationRequest {
+            model: "conversation-model".to_string(),
+            title: None,
+            system_prompt: Some("be brief".to_string()),
+            max_context_tokens: Some(40),
+            truncation: None,
+        })).await.expect("create failed");
+        assert_eq!(conversation.project_id, project);
+        assert_eq!(conversation.truncation, "drop_oldest");
+
+        let id = conversation.id.clone();
+        let Json(first) = send_conversation_message(Path(id.clone()), send("first question")).await.expect("send failed");
+        assert_eq!(first.message.content, "reply: first question");
+        assert_eq!(first.message.seq, 2);
+        assert_eq!(first.truncated_messages, 0);
+
+        let Json(second) = send_conversation_message(Path(id.clone()), send("second question")).await.expect("send failed");
+        assert_eq!(second.message.seq, 4);
+        conversation
+    }).await;
+
+    // 第二次调用带上了 system 提示词和第一轮问答
+    let request = RECEIVED.lock().unwrap().iter()
+        .rev()
+        .find(|r| r.messages.last().is_some_and(|m| m.content == "second question"))
+        .cloned()
+        .expect("request not received");
+    let roles: Vec<&str> = request.messages.iter().map(|m| m.role.as_str()).collect();
+    assert_eq!(roles, vec!["system", "user", "assistant", "user"]);
+    println!("✅ Stored history sent with the new message");
+
+    // 历史超过 token 上限后丢弃最早的消息
+    let Json(third) = CALL_METADATA.scope(in_project(&project), send_conversation_message(
+        Path(result.id.clone()),
+        send(&"long question ".repeat(8)),
+    )).await.expect("send failed");
+    assert!(third.truncated_messages > 0);
+    println!("✅ {} old messages truncated", third.truncated_messages);
+
+    let Json(detail) = CALL_METADATA.scope(in_project(&project), get_conversation(Path(result.id.clone()))).await.expect("get failed");
+    assert_eq!(detail.messages.len(), 6);
+    let Json(listed) = CALL_METADATA.scope(in_project(&project), list_project_conversations(Query(ListConversationsQuery { limit: None })))
+        .await
+        .expect("list failed");
+    assert_eq!(listed.len(), 1);
+
+    // 其他项目看不到该会话
+    let (status, Json(error)) = CALL_METADATA.scope(in_project("other-project"), get_conversation(Path(result.id.clone())))
+        .await
+        .unwrap_err();
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(error.error.code.as_deref(), Some("conversation_not_found"));
+    println!("✅ Conversation hidden from other projects");
+
+    delete_conversation(SQLITE_POOL.get().unwrap(), &result.id).await.unwrap();
+}
@@ -0,0 +1,155 @@
+//! # 费用预估测试
+//!
+//! 测试 `POST /v1/estimate` 按模型单价返回提示词 token 数和预估费用，
+//! 以及调度器拒绝预估费用超过 `max_cost` 的请求且不访问上游
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    response::Response,
+    routing::post,
+    Router,
+};
+use serde_json::{json, Value};
+use sqlx::{Pool, Sqlite};
+use tower::Service;
+
+use project_rust_learn::api_types::v1::GatewayErrorCode;
+use project_rust_learn::dao::{init_db, init_sqlite_pool, SQLITE_POOL};
+use project_rust_learn::dao::cache::init_global_cache;
+use project_rust_learn::dao::model::{create_model, delete_model, invalidate_provider_models_cache, Model};
+use project_rust_learn::llm_api::dispatcher::{
+    DispatchConfig, DispatchRequest, LLMDispatcher, LLMError, Provider, GLOBAL_DISPATCHER,
+};
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+use project_rust_learn::llm_api::utils::tokenizer::count_request_tokens;
+use project_rust_learn::web::handlers::estimate_handler::estimate_chat_cost;
+use common::MockAdapter;
+
+
+struct TestEnv {
+    pool: Arc<Pool<Sqlite>>,
+    provider: Provider,
+    model: Model,
+    calls: Arc<AtomicUsize>,
+}
+
+/// 使用唯一的自定义供应商和带单价的模型，避免影响其他测试
+async fn setup() -> TestEnv {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+    init_db("data/init.sql").await.expect("DB init failed");
+    init_global_cache(&pool, 3600, 1000).await.expect("Cache init failed");
+
+    let provider = Provider::Custom(format!("cost-{}", uuid::Uuid::new_v4().simple()));
+    let model = Model {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: "priced-model".to_string(),
+        provider: provider.as_str().to_string(),
+        model_type: "llm".to_string(),
+        base_url: None,
+        is_active: true,
+        health_status: None,
+        last_health_check: None,
+        health_check_interval_seconds: None,
+        cost_per_token_input: Some(0.001),
+        cost_per_token_output: Some(0.002),
+        function_tags: None,
+        config: None,
+        project_id: None,
+        created_at: None,
+        updated_at: None,
+    };
+    create_model(&pool, &model).await.expect("create_model failed");
+    invalidate_provider_models_cache(provider.as_str()).await;
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    TestEnv { pool, provider, model, calls }
+}
+
+async fn cleanup(env: &TestEnv) {
+    delete_model(&env.pool, &env.model.id).await.expect("delete_model failed");
+    invalidate_provider_models_cache(env.provider.as_str()).await;
+}
+
+async fn dispatcher(env: &TestEnv) -> LLMDispatcher {
+    let dispatcher = LLMDispatcher::new(Some(DispatchConfig {
+        enable_fallback: false,
+        default_retry_count: 0,
+        ..DispatchConfig::default()
+    }));
+    dispatcher.register_client(Box::new(MockAdapter::new(env.provider.clone()).with_call_counter(env.calls.clone()))).await;
+    dispatcher
+}
+
+fn request(env: &TestEnv) -> DispatchRequest {
+    DispatchRequest::new(env.provider.clone(), env.model.name.clone(), vec![Message::user("How much does this cost?".to_string())])
+        .with_max_tokens(100)
+}
+
+#[tokio::test]
+async fn test_max_cost_guard() {
+    let env = setup().await;
+    let dispatcher = dispatcher(&env).await;
+
+    println!("=== Testing max_cost Guard ===");
+    // 回复按 max_tokens 计算：100 * 0.002 = 0.2，加上提示词费用后超过 0.2
+    let error = dispatcher.dispatch(request(&env).with_max_cost(0.2)).await.unwrap_err();
+    assert!(matches!(error, LLMError::CostLimitExceeded(_)), "unexpected error: {:?}", error);
+    assert_eq!(error.error_code(), GatewayErrorCode::CostLimitExceeded);
+    assert_eq!(error.error_code().status_code(), 400);
+    assert_eq!(env.calls.load(Ordering::SeqCst), 0);
+    println!("✅ Request over max_cost rejected before calling upstream");
+
+    let response = dispatcher.dispatch(request(&env).with_max_cost(1.0)).await.expect("dispatch failed");
+    assert_eq!(response.content, "ok");
+    assert_eq!(env.calls.load(Ordering::SeqCst), 1);
+    println!("✅ Request within max_cost dispatched");
+
+    let error = dispatcher.dispatch(request(&env).with_max_cost(-1.0)).await.unwrap_err();
+    assert!(matches!(error, LLMError::InvalidParameters(_)));
+    println!("✅ Negative max_cost rejected");
+
+    cleanup(&env).await;
+}
+
+#[tokio::test]
+async fn test_estimate_endpoint() {
+    let env = setup().await;
+    GLOBAL_DISPATCHER.set(Arc::new(dispatcher(&env).await)).ok();
+    let mut app = Router::new().route("/v1/estimate", post(estimate_chat_cost));
+
+    println!("=== Testing POST /v1/estimate ===");
+    let model = format!("{}/{}", env.provider.as_str(), env.model.name);
+    let body = json!({
+        "model": model,
+        "messages": [{"role": "user", "content": "How much does this cost?"}],
+        "max_tokens": 100,
+        "max_cost": 0.2,
+    });
+    let http_request = Request::post("/v1/estimate")
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response: Response = app.call(http_request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+
+    let prompt_tokens = count_request_tokens(&request(&env)) as f64;
+    assert_eq!(body["object"], "cost_estimate");
+    assert_eq!(body["provider"], env.provider.as_str());
+    assert_eq!(body["model"], env.model.name);
+    assert_eq!(body["prompt_tokens"].as_f64().unwrap(), prompt_tokens);
+    assert_eq!(body["completion_tokens"], 100);
+    assert!((body["estimated_cost"].as_f64().unwrap() - (prompt_tokens * 0.001 + 0.2)).abs() < 1e-9);
+    assert_eq!(body["priced"], true);
+    assert_eq!(body["exceeds_max_cost"], true);
+    assert_eq!(env.calls.load(Ordering::SeqCst), 0);
+    println!("✅ Estimate returned without calling upstream");
+
+    cleanup(&env).await;
+}
@@ -0,0 +1,113 @@
+//! # 仪表盘汇总测试
+//!
+//! 测试 `/api/dashboard` 汇总今天和本周的调用、延迟分位数、热门模型和供应商状况，以及结果的短时缓存；
+//! 非 UTC 时区的今天、本周起始时间换算成 UTC 后再与调用记录比较
+
+mod common;
+
+use chrono::{FixedOffset, NaiveDateTime, TimeZone};
+use sqlx::{Pool, Sqlite};
+
+use project_rust_learn::dao::{init_sqlite_pool, init_db, SQLITE_POOL};
+use project_rust_learn::dao::call_log::{
+    create_call_log, delete_call_logs_by_model, get_call_log_latency_percentile, CallLog, CallLogSearch,
+};
+use project_rust_learn::dao::model::{create_model, delete_model, Model, HEALTH_HEALTHY};
+use project_rust_learn::web::handlers::dashboard_handler::{get_dashboard, period_starts};
+
+/// 初始化测试环境的辅助函数
+async fn setup_test_env() -> std::sync::Arc<Pool<Sqlite>> {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+    init_db("data/init.sql").await.expect("DB init failed");
+    pool
+}
+
+fn model(provider: &str) -> Model {
+    Model {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: format!("dashboard-test-{}", uuid::Uuid::new_v4().simple()),
+        provider: provider.to_string(),
+        model_type: "llm".to_string(),
+        base_url: None,
+        is_active: true,
+        health_status: Some(HEALTH_HEALTHY.to_string()),
+        last_health_check: None,
+        health_check_interval_seconds: None,
+        cost_per_token_input: None,
+        cost_per_token_output: None,
+        function_tags: None,
+        config: None,
+        project_id: None,
+        created_at: None,
+        updated_at: None,
+    }
+}
+
+fn call_log(model: &Model, total_duration: i64) -> CallLog {
+    CallLog {
+        model_id: Some(model.id.clone()),
+        total_duration,
+        cost: 0.01,
+        ..common::call_log(&model.provider, 200)
+    }
+}
+
+#[tokio::test]
+async fn test_dashboard_summary() {
+    println!("=== Testing Dashboard Summary ===");
+    let pool = setup_test_env().await;
+    let provider = format!("dashboard-test-{}", uuid::Uuid::new_v4().simple());
+    let model = model(&provider);
+    create_model(&pool, &model).await.unwrap();
+    for latency in (1..=10).map(|i| i * 100) {
+        create_call_log(&pool, &call_log(&model, latency)).await.unwrap();
+    }
+
+    let search = CallLogSearch { provider: Some(provider.clone()), ..CallLogSearch::default() };
+    assert_eq!(get_call_log_latency_percentile(&pool, &search, 0.5).await.unwrap(), Some(500));
+    assert_eq!(get_call_log_latency_percentile(&pool, &search, 0.95).await.unwrap(), Some(900));
+    assert_eq!(get_call_log_latency_percentile(&pool, &search, 1.0).await.unwrap(), Some(1000));
+    let nothing = CallLogSearch { provider: Some(format!("{}-none", provider)), ..CallLogSearch::default() };
+    assert_eq!(get_call_log_latency_percentile(&pool, &nothing, 0.5).await.unwrap(), None);
+    println!("✅ Latency percentiles computed from call logs");
+
+    let dashboard = get_dashboard().await.unwrap().0;
+    assert!(dashboard.today.total_requests >= 10);
+    assert!(dashboard.today.total_tokens >= 300);
+    assert!(dashboard.this_week.total_requests >= dashboard.today.total_requests);
+    assert!(dashboard.this_week.since <= dashboard.today.since);
+    assert!(dashboard.today.p50_latency_ms.is_some() && dashboard.today.p95_latency_ms.is_some());
+    assert!(dashboard.top_models.len() <= 5);
+    assert!(dashboard.providers.iter().any(|p| p.provider == provider && p.health == "healthy"));
+    assert!(dashboard.counters.contains_key("llm_gateway_requests_cancelled_total"));
+    println!("✅ Today and this week summarized with providers and counters");
+
+    // 缓存期内返回同一份结果
+    let cached = get_dashboard().await.unwrap().0;
+    assert_eq!(cached.generated_at, dashboard.generated_at);
+    println!("✅ Dashboard cached for a short time");
+
+    delete_call_logs_by_model(&pool, &model.id).await.unwrap();
+    delete_model(&pool, &model.id).await.unwrap();
+}
+
+fn utc(value: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").unwrap()
+}
+
+#[test]
+fn test_period_starts_in_non_utc_timezone() {
+    // 东八区周三凌晨 3 点：UTC 仍是周二，今天从 UTC 前一天 16 点开始
+    let east = FixedOffset::east_opt(8 * 3600).unwrap();
+    let now = east.with_ymd_and_hms(2026, 10, 14, 3, 0, 0).unwrap();
+    assert_eq!(period_starts(&now), (utc("2026-10-13 16:00:00"), utc("2026-10-11 16:00:00")));
+
+    // 西五区周三晚上 10 点：UTC 已是周四，今天仍从当地 0 点（UTC 5 点）开始
+    let west = FixedOffset::west_opt(5 * 3600).unwrap();
+    let now = west.with_ymd_and_hms(2026, 10, 14, 22, 0, 0).unwrap();
+    assert_eq!(period_starts(&now), (utc("2026-10-14 05:00:00"), utc("2026-10-12 05:00:00")));
+
+    let now = chrono::Utc.with_ymd_and_hms(2026, 10, 14, 12, 0, 0).unwrap();
+    assert_eq!(period_starts(&now), (utc("2026-10-14 00:00:00"), utc("2026-10-12 00:00:00")));
+}
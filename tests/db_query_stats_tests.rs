@@ -0,0 +1,80 @@
+//! # 数据库查询耗时统计测试
+//!
+//! 测试 DAO 查询按名称累计耗时、超过阈值计为慢查询，以及执行计划中的全表扫描提示
+
+use std::time::Duration;
+
+use project_rust_learn::dao::{init_sqlite_pool, init_db, SQLITE_POOL};
+use project_rust_learn::dao::call_log::{list_call_logs_by_provider, list_call_logs_by_status};
+use project_rust_learn::dao::query_stats::{
+    explain_query_plan, index_hints, query_stats, set_slow_query_threshold, slow_query_threshold, QueryStat,
+};
+use project_rust_learn::metrics::metrics;
+
+/// 初始化测试环境的辅助函数
+async fn setup_test_env() {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    init_db("data/init.sql").await.expect("DB init failed");
+}
+
+fn stat(name: &str) -> Option<QueryStat> {
+    query_stats().into_iter().find(|stat| stat.name == name)
+}
+
+#[tokio::test]
+async fn test_query_timing_and_slow_queries() {
+    setup_test_env().await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+
+    println!("=== Testing Query Timing ===");
+    let calls_before = stat("call_log.list_call_logs_by_status").map_or(0, |stat| stat.calls);
+    list_call_logs_by_status(&pool, 599).await.expect("query failed");
+    let recorded = stat("call_log.list_call_logs_by_status").expect("query not recorded");
+    assert_eq!(recorded.calls, calls_before + 1);
+    assert_eq!(recorded.sql, "SELECT * FROM call_logs WHERE status_code = ? ORDER BY created_at DESC");
+    assert!(recorded.max_ms >= recorded.avg_ms);
+    println!("✅ Query timing recorded by name");
+
+    // 阈值为 0 时每次查询都计为慢查询
+    let previous = slow_query_threshold();
+    set_slow_query_threshold(Duration::ZERO);
+    let slow_before = metrics().counter_value("llm_gateway_db_slow_queries_total", &[("query", "call_log.list_call_logs_by_status")]);
+    list_call_logs_by_status(&pool, 599).await.expect("query failed");
+    set_slow_query_threshold(previous);
+
+    let recorded = stat("call_log.list_call_logs_by_status").expect("query not recorded");
+    assert!(recorded.slow_calls >= 1);
+    assert_eq!(
+        metrics().counter_value("llm_gateway_db_slow_queries_total", &[("query", "call_log.list_call_logs_by_status")]),
+        slow_before + 1
+    );
+    println!("✅ Slow query counted");
+}
+
+#[tokio::test]
+async fn test_index_hints_from_query_plan() {
+    setup_test_env().await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+
+    println!("=== Testing Index Hints ===");
+    list_call_logs_by_provider(&pool, "openai").await.expect("query failed");
+    let indexed = stat("call_log.list_call_logs_by_provider").expect("query not recorded");
+    let plan = explain_query_plan(&pool, &indexed.sql).await.expect("explain failed");
+    assert!(plan.iter().any(|detail| detail.contains("idx_call_logs_provider")));
+    assert!(!index_hints(&plan).iter().any(|hint| hint.starts_with("Full table scan")));
+    println!("✅ Indexed query has no full scan hint");
+
+    // status_code 没有索引，查询需要扫描整张表
+    let plan = explain_query_plan(&pool, "SELECT * FROM call_logs WHERE status_code = ? ORDER BY created_at DESC")
+        .await
+        .expect("explain failed");
+    let hints = index_hints(&plan);
+    assert!(hints.iter().any(|hint| hint.starts_with("Full table scan on call_logs")), "hints: {:?}, plan: {:?}", hints, plan);
+    println!("✅ Unindexed filter reported: {:?}", hints);
+
+    assert!(index_hints(&["SCAN call_logs".to_string()])[0].starts_with("Full table scan on call_logs"));
+    assert!(index_hints(&["SCAN call_logs USING INDEX idx_call_logs_created_at".to_string()])[0].contains("idx_call_logs_created_at"));
+    assert!(index_hints(&["SEARCH models USING INDEX idx_models_provider_active (provider=?)".to_string()]).is_empty());
+    assert_eq!(index_hints(&["USE TEMP B-TREE FOR ORDER BY".to_string()]).len(), 1);
+    println!("✅ Plan details mapped to hints");
+}
@@ -0,0 +1,107 @@
+//! # 降级模式测试
+//!
+//! 测试上游全部失败时自动进入降级模式，返回缓存响应或兜底消息，还有其他可用供应商时不进入降级，以及手动开关降级模式
+
+mod common;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use project_rust_learn::llm_api::dispatcher::{DispatchConfig, DispatchRequest, LLMDispatcher, LLMError, Provider};
+use project_rust_learn::llm_api::utils::degradation::{get_degradation_guard, DegradationConfig, DEGRADED_FINISH_REASON};
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+use common::{response, MockAdapter};
+
+/// 可切换为故障状态的适配器
+fn flaky(provider: Provider, down: Arc<AtomicBool>) -> MockAdapter {
+    let name = provider.clone();
+    MockAdapter::new(provider).with_models(&["llama3.2"]).with_reply(move |request| {
+        if down.load(Ordering::SeqCst) {
+            return Err(LLMError::ApiError("upstream unavailable".to_string()));
+        }
+        Ok(response(name.clone(), &request.model, format!("answer to {}", request.messages[0].content)))
+    })
+}
+
+fn request(prompt: &str) -> DispatchRequest {
+    let mut request = DispatchRequest::new(Provider::Ollama, "llama3.2".to_string(), vec![Message::user(prompt.to_string())]);
+    request.retry_count = Some(0);
+    request
+}
+
+#[tokio::test]
+async fn test_degradation_mode() {
+    println!("=== Testing Degradation Mode ===");
+
+    let guard = get_degradation_guard();
+    guard.set_config(DegradationConfig {
+        fallback_message: "maintenance".to_string(),
+        failure_threshold: 2,
+        cooldown: Duration::from_secs(60),
+    }).await;
+
+    // 关闭 fallback 的请求失败时，还有可用的备选供应商，不计入降级
+    let down = Arc::new(AtomicBool::new(true));
+    let with_backup = LLMDispatcher::new(Some(DispatchConfig {
+        enable_fallback: true,
+        fallback_providers: vec![Provider::OpenAI],
+        ..Default::default()
+    }));
+    with_backup.register_client(Box::new(flaky(Provider::Ollama, down.clone()))).await;
+    with_backup.register_client(Box::new(flaky(Provider::OpenAI, Arc::new(AtomicBool::new(false))))).await;
+    for _ in 0..3 {
+        let mut pinned = request("hello");
+        pinned.fallback = Some(false);
+        assert!(with_backup.dispatch(pinned).await.is_err());
+    }
+    assert!(!guard.is_active().await);
+    assert_eq!(guard.status().await.consecutive_failures, 0);
+    println!("✅ Failure of a single provider does not trigger degradation");
+
+    down.store(false, Ordering::SeqCst);
+    let dispatcher = LLMDispatcher::new(Some(DispatchConfig {
+        enable_fallback: false,
+        ..Default::default()
+    }));
+    dispatcher.register_client(Box::new(flaky(Provider::Ollama, down.clone()))).await;
+
+    // 正常响应写入缓存
+    let response = dispatcher.dispatch(request("What is Rust?")).await.expect("dispatch failed");
+    assert_eq!(response.content, "answer to What is Rust?");
+
+    // 连续失败达到阈值后自动进入降级
+    down.store(true, Ordering::SeqCst);
+    assert!(dispatcher.dispatch(request("hello")).await.is_err());
+    let response = dispatcher.dispatch(request("hello")).await.expect("degraded response expected");
+    assert_eq!(response.content, "maintenance");
+    assert_eq!(response.finish_reason.as_deref(), Some(DEGRADED_FINISH_REASON));
+    assert!(guard.is_active().await);
+    println!("✅ Auto-triggered degradation serves fallback message");
+
+    // 只有大小写、标点和空白不同的请求命中缓存
+    let response = dispatcher.dispatch(request("what is rust")).await.expect("cached response expected");
+    assert_eq!(response.content, "answer to What is Rust?");
+    // 措辞不同的请求不命中缓存
+    let response = dispatcher.dispatch(request("tell me about rust")).await.expect("degraded response expected");
+    assert_eq!(response.content, "maintenance");
+    println!("✅ Cached response served during outage");
+
+    let mut receiver = dispatcher.dispatch_stream(request("hello")).await.expect("degraded stream expected");
+    let chunk = receiver.recv().await.unwrap().unwrap();
+    assert_eq!(chunk.content, "maintenance");
+    println!("✅ Streaming requests receive degraded response");
+
+    // 手动关闭后恢复访问上游
+    down.store(false, Ordering::SeqCst);
+    guard.set_manual(false).await;
+    let response = dispatcher.dispatch(request("hello")).await.expect("dispatch failed");
+    assert_eq!(response.content, "answer to hello");
+
+    // 手动开启时不访问上游
+    guard.set_manual(true).await;
+    let response = dispatcher.dispatch(request("something new")).await.unwrap();
+    assert_eq!(response.content, "maintenance");
+    guard.set_manual(false).await;
+    println!("✅ Manual toggle works");
+}
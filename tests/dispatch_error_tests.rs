@@ -0,0 +1,134 @@
+//! # 调度器错误映射测试
+//!
+//! 测试供应商客户端错误保留上游状态码转换为 `LLMError`，按状态码区分限流、参数错误和上游故障，
+//! 并由 `/v1/chat/completions` 返回对应的 HTTP 状态码和 OpenAI 格式错误体
+
+use std::sync::Arc;
+use std::time::Duration;
+use axum::{http::{HeaderMap, StatusCode}, Extension, Json};
+use mockito::{Matcher, Server, ServerGuard};
+use serde_json::json;
+
+use project_rust_learn::dao::{init_db, init_sqlite_pool};
+use project_rust_learn::llm_api::dispatcher::{
+    DispatchConfig, DispatchRequest, LLMClientAdapter, LLMDispatcher, LLMError, OllamaAdapter, Provider, GLOBAL_DISPATCHER,
+};
+use project_rust_learn::llm_api::ollama::client::OllamaClient;
+use project_rust_learn::llm_api::utils::client::{ClientConfig, ClientError, RetryConfig};
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+use project_rust_learn::web::dto::chat_completion_dto::ChatCompletionRequest;
+use project_rust_learn::web::extract::StreamingJson;
+use project_rust_learn::web::handlers::chat_completion_handler::create_chat_completion;
+use project_rust_learn::web::middleware::routing::RoutingOverride;
+
+async fn setup_test_env() {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    init_db("data/init.sql").await.expect("DB init failed");
+}
+
+const LIMITED: &str = "llama3.2";
+const THROTTLED: &str = "gemma2";
+const BAD_REQUEST: &str = "llama3";
+const DOWN: &str = "mistral";
+
+// 按模型名返回不同上游错误的 Ollama 服务
+async fn upstream() -> ServerGuard {
+    let mut server = Server::new_async().await;
+    for (model, status, error) in [
+        (LIMITED, 429, "rate limited"),
+        (THROTTLED, 429, "Rate limit reached. Please try again in 10ms."),
+        (BAD_REQUEST, 400, "invalid options"),
+        (DOWN, 503, "overloaded"),
+    ] {
+        server.mock("POST", "/api/chat")
+            .match_body(Matcher::PartialJson(json!({"model": model})))
+            .with_status(status)
+            .with_body(json!({"error": error}).to_string())
+            .create_async()
+            .await;
+    }
+    server
+}
+
+fn adapter(server: &ServerGuard) -> OllamaAdapter {
+    let config = ClientConfig::new().with_retry(RetryConfig::new().with_max_attempts(2).with_base_delay(Duration::from_millis(10)));
+    OllamaAdapter::new(OllamaClient::new_with_config(server.url(), config).unwrap())
+}
+
+fn request(model: &str) -> DispatchRequest {
+    DispatchRequest::new(Provider::Ollama, model.to_string(), vec![Message::user("hi".to_string())])
+}
+
+#[tokio::test]
+async fn test_provider_errors_keep_upstream_status() {
+    setup_test_env().await;
+
+    println!("=== Testing Provider Error Mapping ===");
+    let server = upstream().await;
+    let adapter = adapter(&server);
+
+    let error = adapter.generate(&request(LIMITED)).await.unwrap_err();
+    assert!(matches!(error, LLMError::ClientError(ClientError::LLMApi { status_code: Some(429), .. })), "{:?}", error);
+    assert_eq!(error.status_code(), 429);
+    assert!(error.is_retryable());
+    println!("✅ Upstream 429 kept as rate limit");
+
+    // 按错误信息中的等待提示重试后仍然限流，重试耗尽时保留最后一次的状态码
+    let error = adapter.generate(&request(THROTTLED)).await.unwrap_err();
+    assert!(matches!(error, LLMError::ClientError(ClientError::RetryExhausted { status_code: Some(429), .. })), "{:?}", error);
+    assert_eq!(error.status_code(), 429);
+    println!("✅ Exhausted retries keep the last upstream status");
+
+    let error = adapter.generate(&request(BAD_REQUEST)).await.unwrap_err();
+    assert_eq!(error.status_code(), 400);
+    assert!(!error.is_retryable());
+
+    let error = adapter.generate(&request(DOWN)).await.unwrap_err();
+    assert_eq!(error.status_code(), 502);
+    assert!(error.is_retryable());
+    println!("✅ Upstream 400 is a request error, 5xx is a retryable upstream error");
+
+    let error = adapter.generate(&request("")).await.unwrap_err();
+    assert!(matches!(error, LLMError::InvalidParameters(_)), "{:?}", error);
+    assert_eq!(error.status_code(), 400);
+    println!("✅ Client-side validation errors are request errors");
+}
+
+#[tokio::test]
+async fn test_chat_completion_returns_upstream_status() {
+    setup_test_env().await;
+
+    println!("=== Testing Chat Completion Error Status ===");
+    let server = upstream().await;
+    let dispatcher = LLMDispatcher::new(Some(DispatchConfig {
+        default_retry_count: 0,
+        enable_fallback: false,
+        ..Default::default()
+    }));
+    dispatcher.register_client(Box::new(adapter(&server))).await;
+    GLOBAL_DISPATCHER.set(Arc::new(dispatcher)).ok();
+
+    let routing = RoutingOverride { provider: Some(Provider::Ollama), fallback: Some(false), ..Default::default() };
+    let chat = |model: &str| {
+        let request: ChatCompletionRequest = serde_json::from_value(json!({
+            "model": model,
+            "messages": [{"role": "user", "content": "hi"}]
+        })).unwrap();
+        create_chat_completion(HeaderMap::new(), Some(Extension(routing.clone())), StreamingJson(request))
+    };
+
+    let (status, Json(error)) = chat(LIMITED).await.unwrap_err();
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(error.error.error_type, "rate_limit_error");
+    assert_eq!(error.error.code.as_deref(), Some("rate_limit_exceeded"));
+
+    let (status, Json(error)) = chat(BAD_REQUEST).await.unwrap_err();
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error.error.error_type, "invalid_request_error");
+    assert!(error.error.message.contains("invalid options"), "{}", error.error.message);
+
+    let (status, Json(error)) = chat(DOWN).await.unwrap_err();
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+    assert_eq!(error.error.code.as_deref(), Some("upstream_error"));
+    println!("✅ 429, 400 and 502 returned with OpenAI error bodies");
+}
@@ -0,0 +1,121 @@
+//! # 调度扩展钩子测试
+//!
+//! 测试注册的钩子按顺序执行、可以改写请求和响应，
+//! 以及钩子拒绝请求或上游失败时执行 on_error
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+
+use project_rust_learn::llm_api::dispatcher::{DispatchConfig, DispatchRequest, DispatchResponse, LLMDispatcher, LLMError, Provider};
+use project_rust_learn::llm_api::utils::dispatch_hook::DispatchHook;
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+use common::{response, MockAdapter};
+
+/// 回显最后一条消息的测试适配器，内容为 "fail" 时返回错误
+fn echo_adapter(provider: &Provider) -> MockAdapter {
+    let name = provider.clone();
+    MockAdapter::new(provider.clone()).with_models(&["echo"]).with_reply(move |request| {
+        let content = request.messages.last().map(|m| m.content.clone()).unwrap_or_default();
+        if content == "fail" {
+            return Err(LLMError::ApiError("upstream failed".to_string()));
+        }
+        Ok(response(name.clone(), &request.model, content))
+    })
+}
+
+/// 记录调用顺序的钩子：给最后一条消息和响应加上标记，消息为 "reject" 时拒绝请求
+struct TaggingHook {
+    name: String,
+    events: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl DispatchHook for TaggingHook {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn on_request(&self, request: &mut DispatchRequest) -> Result<(), LLMError> {
+        self.events.lock().unwrap().push(format!("{}:request", self.name));
+        let message = request.messages.last_mut().unwrap();
+        if message.content == "reject" {
+            return Err(LLMError::InvalidParameters(format!("rejected by {}", self.name)));
+        }
+        if message.content != "fail" {
+            message.content = format!("{}[{}]", message.content, self.name);
+        }
+        Ok(())
+    }
+
+    async fn on_response(&self, _request: &DispatchRequest, response: &mut DispatchResponse) -> Result<(), LLMError> {
+        self.events.lock().unwrap().push(format!("{}:response", self.name));
+        response.content = format!("{}<{}>", response.content, self.name);
+        Ok(())
+    }
+
+    async fn on_error(&self, _request: &DispatchRequest, error: &LLMError) {
+        self.events.lock().unwrap().push(format!("{}:error:{}", self.name, error));
+    }
+}
+
+async fn setup() -> (LLMDispatcher, Provider, Arc<Mutex<Vec<String>>>) {
+    let provider = Provider::Custom(format!("hook-{}", uuid::Uuid::new_v4().simple()));
+    let dispatcher = LLMDispatcher::new(Some(DispatchConfig {
+        enable_fallback: false,
+        default_retry_count: 0,
+        ..DispatchConfig::default()
+    }));
+    dispatcher.register_client(Box::new(echo_adapter(&provider))).await;
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    for name in ["first", "second"] {
+        dispatcher.register_hook(Box::new(TaggingHook { name: name.to_string(), events: events.clone() })).await;
+    }
+    (dispatcher, provider, events)
+}
+
+fn request(provider: &Provider, content: &str) -> DispatchRequest {
+    DispatchRequest::new(provider.clone(), "echo".to_string(), vec![Message::user(content.to_string())])
+}
+
+fn take(events: &Mutex<Vec<String>>) -> Vec<String> {
+    std::mem::take(&mut *events.lock().unwrap())
+}
+
+#[tokio::test]
+async fn test_hooks_rewrite_request_and_response() {
+    let (dispatcher, provider, events) = setup().await;
+
+    println!("=== Testing Dispatch Hooks ===");
+    assert_eq!(dispatcher.hook_names().await, vec!["first", "second"]);
+    let response = dispatcher.dispatch(request(&provider, "hello")).await.expect("dispatch failed");
+    assert_eq!(response.content, "hello[first][second]<first><second>");
+    assert_eq!(take(&events), vec!["first:request", "second:request", "first:response", "second:response"]);
+    println!("✅ Hooks ran in registration order and rewrote request and response");
+}
+
+#[tokio::test]
+async fn test_hooks_on_error() {
+    let (dispatcher, provider, events) = setup().await;
+
+    println!("=== Testing Dispatch Hook Errors ===");
+    let error = dispatcher.dispatch(request(&provider, "reject")).await.unwrap_err();
+    assert!(matches!(error, LLMError::InvalidParameters(ref message) if message == "rejected by first"));
+    let recorded = take(&events);
+    assert_eq!(recorded[0], "first:request");
+    assert!(recorded[1..].iter().all(|event| event.contains(":error:")));
+    assert_eq!(recorded.len(), 3);
+    println!("✅ Rejecting hook stopped the request and on_error ran");
+
+    assert!(dispatcher.dispatch(request(&provider, "fail")).await.is_err());
+    let recorded = take(&events);
+    assert_eq!(&recorded[..2], ["first:request", "second:request"]);
+    assert!(recorded[2..].iter().all(|event| event.contains("upstream failed")));
+    println!("✅ Upstream failure reported to on_error");
+
+    assert!(dispatcher.dispatch_stream(request(&provider, "hello")).await.is_err());
+    assert!(take(&events).iter().any(|event| event.starts_with("second:error")));
+    println!("✅ Stream setup failure reported to on_error");
+}
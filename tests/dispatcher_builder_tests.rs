@@ -0,0 +1,76 @@
+//! # Dispatcher 构建器测试
+//!
+//! 测试 `LLMDispatcher::builder()` / `from_database` 根据 providers 表自动注册适配器，
+//! 手动注册的适配器不被覆盖，以及供应商变更后通过 `resync_providers` 重新同步
+
+use project_rust_learn::dao::{init_db, init_sqlite_pool, SQLITE_POOL};
+use project_rust_learn::dao::provider::{create_provider, hard_delete_provider, update_provider, Provider as ProviderRecord};
+use project_rust_learn::llm_api::dispatcher::{DispatchConfig, DispatchRequest, LLMDispatcher, OllamaAdapter, Provider};
+use project_rust_learn::llm_api::mock::adapter::MockScenario;
+use project_rust_learn::llm_api::ollama::client::OllamaClient;
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+
+const PROVIDER_NAME: &str = "builder-mock";
+
+#[tokio::test]
+async fn test_builder_registers_and_resyncs_providers() {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    init_db("data/init.sql").await.expect("DB init failed");
+    let pool = SQLITE_POOL.get().unwrap().clone();
+
+    println!("=== Testing Dispatcher Builder ===");
+    let mut record = ProviderRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: PROVIDER_NAME.to_string(),
+        display_name: "Builder Mock".to_string(),
+        base_url: None,
+        description: None,
+        config: Some(r#"{"type": "mock"}"#.to_string()),
+        is_active: true,
+        project_id: None,
+        max_concurrent_requests: None,
+        created_at: None,
+        updated_at: None,
+    };
+    // 清理上次运行残留的同名记录
+    sqlx::query("DELETE FROM providers WHERE name = ?").bind(PROVIDER_NAME).execute(pool.as_ref()).await.unwrap();
+    create_provider(&pool, &record).await.expect("create_provider failed");
+
+    let provider = Provider::Custom(PROVIDER_NAME.to_string());
+    let ollama = OllamaAdapter::new(OllamaClient::new("http://localhost:11434".to_string()).unwrap());
+    let dispatcher = LLMDispatcher::builder()
+        .with_config(DispatchConfig { enable_fallback: false, ..Default::default() })
+        .with_client(Box::new(ollama))
+        .with_database(pool.as_ref().clone())
+        .build()
+        .await
+        .expect("build failed");
+    assert!(dispatcher.is_provider_available(&provider).await);
+    assert!(dispatcher.is_provider_available(&Provider::Ollama).await);
+    println!("✅ Database provider and manual adapter registered");
+
+    let request = DispatchRequest::new(provider.clone(), MockScenario::Echo.model_name().to_string(), vec![Message::user("hi builder".to_string())]);
+    let response = dispatcher.dispatch(request).await.expect("dispatch failed");
+    assert_eq!(response.content, "hi builder");
+    println!("✅ Dispatched through the auto-registered adapter");
+
+    // 停用后重新同步会注销适配器，手动注册的适配器保留
+    record.is_active = false;
+    update_provider(&pool, &record.id, &record).await.expect("update_provider failed");
+    let registered = dispatcher.resync_providers().await.expect("resync failed");
+    assert!(!registered.contains(&provider));
+    assert!(!dispatcher.is_provider_available(&provider).await);
+    assert!(dispatcher.is_provider_available(&Provider::Ollama).await);
+    println!("✅ Deactivated provider unregistered on resync");
+
+    record.is_active = true;
+    update_provider(&pool, &record.id, &record).await.expect("update_provider failed");
+    let dispatcher = LLMDispatcher::from_database(&pool).await.expect("from_database failed");
+    assert!(dispatcher.is_provider_available(&provider).await);
+    println!("✅ from_database registers active providers");
+
+    assert!(LLMDispatcher::new(None).resync_providers().await.is_err());
+    println!("✅ resync requires a dispatcher created from a database");
+
+    hard_delete_provider(&pool, &record.id).await.expect("hard_delete_provider failed");
+}
@@ -0,0 +1,120 @@
+//! # 终端用户限流测试
+//!
+//! 测试经 HTTP 接口的请求按 project_scope 解析出的项目区分租户：
+//! 同名的终端用户在不同项目中分别计数，违规排行按项目过滤
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+    middleware::from_fn,
+    response::Response,
+    routing::{get, post},
+    Router,
+};
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+use tower::Service;
+
+use project_rust_learn::dao::project::{bind_gateway_key, create_project, Project};
+use project_rust_learn::dao::run_migrations;
+use project_rust_learn::llm_api::dispatcher::{LLMDispatcher, Provider, GLOBAL_DISPATCHER};
+use project_rust_learn::llm_api::utils::abuse_guard::{get_abuse_guard, AbuseGuardConfig};
+use project_rust_learn::llm_api::utils::consumer_quota::consumer_id;
+use project_rust_learn::llm_api::utils::project_scope::reload_project_bindings;
+use project_rust_learn::web::handlers::abuse_handler::list_top_offenders;
+use project_rust_learn::web::handlers::chat_completion_handler::create_chat_completion;
+use project_rust_learn::web::middleware::project::project_scope;
+use common::MockAdapter;
+
+fn app() -> Router {
+    Router::new()
+        .route("/v1/chat/completions", post(create_chat_completion).route_layer(from_fn(project_scope)))
+        .route("/api/abuse/offenders", get(list_top_offenders))
+}
+
+async fn send(app: &mut Router, api_key: &str, user: &str) -> Response {
+    let body = json!({"model": "throttle-model", "messages": [{"role": "user", "content": "hi"}], "user": user});
+    let request = Request::post("/v1/chat/completions")
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::AUTHORIZATION, format!("Bearer {}", api_key))
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    app.call(request).await.unwrap()
+}
+
+async fn offenders(app: &mut Router, tenant_id: &str) -> Value {
+    let request = Request::get(format!("/api/abuse/offenders?tenant_id={}", tenant_id)).body(Body::empty()).unwrap();
+    let response = app.call(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap()
+}
+
+/// 初始化测试环境的辅助函数：项目绑定从独立的数据库加载
+async fn setup_test_env() -> (SqlitePool, std::path::PathBuf) {
+    let path = std::env::temp_dir().join(format!("end-user-throttle-test-{}.db", uuid::Uuid::new_v4().simple()));
+    let pool = SqlitePool::connect(&format!("sqlite://{}?mode=rwc", path.display())).await.unwrap();
+    run_migrations(&pool).await.expect("migration failed");
+
+    let dispatcher = LLMDispatcher::new(None);
+    dispatcher.register_client(Box::new(MockAdapter::new(Provider::OpenAI).with_models(&["throttle-model"]))).await;
+    GLOBAL_DISPATCHER.set(Arc::new(dispatcher)).ok();
+    (pool, path)
+}
+
+/// 创建项目并绑定一个新的网关 Key，返回项目 ID 和 Key
+async fn bound_key(pool: &SqlitePool) -> (String, String) {
+    let project = Project {
+        id: format!("proj-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]),
+        name: "Throttle Project".to_string(),
+        description: None,
+        is_active: true,
+        created_at: None,
+        updated_at: None,
+    };
+    create_project(pool, &project).await.expect("create project failed");
+    let api_key = format!("sk-throttle-{}", uuid::Uuid::new_v4());
+    bind_gateway_key(pool, &consumer_id(&api_key), &project.id).await.expect("bind failed");
+    (project.id, api_key)
+}
+
+#[tokio::test]
+async fn test_end_user_throttling_is_per_project() {
+    let (pool, path) = setup_test_env().await;
+
+    println!("=== Testing Per-Project End User Throttling ===");
+    let (project_a, key_a) = bound_key(&pool).await;
+    let (project_b, key_b) = bound_key(&pool).await;
+    reload_project_bindings(&pool).await.expect("reload bindings failed");
+    get_abuse_guard().set_config(AbuseGuardConfig {
+        max_requests_per_window: 1,
+        window: Duration::from_secs(600),
+        ..AbuseGuardConfig::default()
+    }).await;
+
+    let mut app = app();
+    let user = format!("user-{}", uuid::Uuid::new_v4().simple());
+    assert_eq!(send(&mut app, &key_a, &user).await.status(), StatusCode::OK);
+    assert_eq!(send(&mut app, &key_a, &user).await.status(), StatusCode::TOO_MANY_REQUESTS);
+    println!("✅ Second request in project A throttled");
+
+    // 另一个项目中的同名用户单独计数
+    assert_eq!(send(&mut app, &key_b, &user).await.status(), StatusCode::OK);
+    println!("✅ Same user in project B not throttled");
+
+    let report = offenders(&mut app, &project_a).await;
+    assert_eq!(report.as_array().unwrap().len(), 1);
+    assert_eq!(report[0]["tenant_id"], project_a);
+    assert_eq!(report[0]["user"], user);
+    assert_eq!(report[0]["throttled_count"], 1);
+    // 项目 B 中的用户没有被限流，不在排行中
+    assert_eq!(offenders(&mut app, &project_b).await, json!([]));
+    println!("✅ Offender report grouped by project");
+
+    pool.close().await;
+    std::fs::remove_file(path).ok();
+}
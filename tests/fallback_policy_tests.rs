@@ -0,0 +1,104 @@
+//! # Fallback 路由策略测试
+//!
+//! 测试主供应商失败后按模型别名切换到备选供应商上对应的模型，
+//! 以及跳过 Key 池中没有可用 Key 的备选供应商
+
+mod common;
+
+use std::sync::Arc;
+
+use project_rust_learn::llm_api::dispatcher::{DispatchConfig, DispatchRequest, LLMDispatcher, LLMError, Provider};
+use project_rust_learn::llm_api::utils::fallback_policy::{ModelAliasFallback, SameModelFallback};
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+use project_rust_learn::metrics::metrics;
+use common::MockAdapter;
+
+/// 只支持指定模型、总是返回网络错误的适配器
+fn failing(provider: &Provider, model: &str) -> MockAdapter {
+    MockAdapter::new(provider.clone())
+        .with_models(&[model])
+        .with_reply(|_| Err(LLMError::Network("connection refused".to_string())))
+}
+
+/// 只支持指定模型、回复 "供应商:模型" 的适配器
+fn serving(provider: &Provider, model: &str) -> MockAdapter {
+    let name = provider.as_str().to_string();
+    MockAdapter::new(provider.clone())
+        .with_models(&[model])
+        .with_content(move |request| format!("{}:{}", name, request.model))
+}
+
+async fn dispatcher(config: DispatchConfig, primary: &Provider, backup: &Provider) -> LLMDispatcher {
+    let dispatcher = LLMDispatcher::new(Some(config));
+    dispatcher.register_client(Box::new(failing(primary, "cloud-model"))).await;
+    dispatcher.register_client(Box::new(serving(backup, "local-model:7b"))).await;
+    dispatcher
+}
+
+#[tokio::test]
+async fn test_fallback_uses_model_alias() {
+    println!("=== Testing Fallback Model Aliases ===");
+    // 使用唯一的自定义供应商，避免影响其他测试
+    let primary = Provider::Custom(format!("cloud-{}", uuid::Uuid::new_v4().simple()));
+    let backup = Provider::Custom(format!("local-{}", uuid::Uuid::new_v4().simple()));
+    let request = || {
+        let mut request = DispatchRequest::new(primary.clone(), "cloud-model".to_string(), vec![Message::user("hello".to_string())]);
+        request.retry_count = Some(0);
+        request
+    };
+
+    // 保持模型名不变时备选供应商没有该模型，返回原始错误
+    let config = DispatchConfig {
+        fallback_providers: vec![backup.clone()],
+        fallback_policy: Arc::new(SameModelFallback),
+        ..Default::default()
+    };
+    let result = dispatcher(config, &primary, &backup).await.dispatch(request()).await;
+    assert!(matches!(result, Err(LLMError::Network(_))));
+    println!("✅ Same-model fallback fails when backup lacks the model");
+
+    // 别名把主供应商的模型映射到备选供应商上的模型
+    let config = DispatchConfig {
+        fallback_providers: vec![backup.clone()],
+        fallback_policy: Arc::new(ModelAliasFallback::new().with_alias("chat", vec![
+            (primary.clone(), "cloud-model".to_string()),
+            (backup.clone(), "local-model:7b".to_string()),
+        ])),
+        ..Default::default()
+    };
+    let response = dispatcher(config, &primary, &backup).await.dispatch(request()).await.expect("fallback failed");
+    assert_eq!(response.provider, backup);
+    assert_eq!(response.model, "local-model:7b");
+    println!("✅ Fallback routed to aliased model: {}", response.content);
+}
+
+#[tokio::test]
+async fn test_fallback_skips_providers_without_keys() {
+    println!("=== Testing Key Pool Aware Fallback ===");
+    let primary = Provider::Custom(format!("cloud-{}", uuid::Uuid::new_v4().simple()));
+    let keyless = Provider::Custom(format!("pooled-{}", uuid::Uuid::new_v4().simple()));
+    let backup = Provider::Custom(format!("local-{}", uuid::Uuid::new_v4().simple()));
+
+    let config = DispatchConfig {
+        fallback_providers: vec![keyless.clone(), backup.clone()],
+        fallback_policy: Arc::new(SameModelFallback),
+        ..Default::default()
+    };
+    let dispatcher = LLMDispatcher::new(Some(config));
+    dispatcher.register_client(Box::new(failing(&primary, "chat-model"))).await;
+    // 依赖 Key 池但没有任何 Key 的供应商不会被调用
+    dispatcher.register_client(Box::new(serving(&keyless, "chat-model").with_key_pool())).await;
+    dispatcher.register_client(Box::new(serving(&backup, "chat-model"))).await;
+
+    let mut request = DispatchRequest::new(primary.clone(), "chat-model".to_string(), vec![Message::user("hello".to_string())]);
+    request.retry_count = Some(0);
+    let response = dispatcher.dispatch(request).await.expect("fallback failed");
+    assert_eq!(response.provider, backup);
+
+    let skips = metrics().counter_value(
+        "llm_gateway_fallback_skips_total",
+        &[("provider", keyless.as_str()), ("reason", "no_active_keys")],
+    );
+    assert_eq!(skips, 1);
+    println!("✅ Provider without active keys skipped, served by {}", response.content);
+}
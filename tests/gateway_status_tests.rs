@@ -0,0 +1,163 @@
+//! # 网关状态总览测试
+//!
+//! 测试 `/api/status` 汇总供应商健康状况、不健康模型、最近错误率和缓存命中率，以及准入队列和供应商并发的排队情况
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use axum::extract::Extension;
+use sqlx::{Pool, Sqlite};
+
+use project_rust_learn::dao::{init_sqlite_pool, init_db, SQLITE_POOL};
+use project_rust_learn::dao::cache::cache::{cache_hit_stats, CacheService};
+use project_rust_learn::dao::call_log::{create_call_log, delete_call_logs_by_model, CallLog};
+use project_rust_learn::dao::model::{create_model, delete_model, Model, HEALTH_HEALTHY, HEALTH_UNHEALTHY};
+use project_rust_learn::llm_api::dispatcher::{DispatchRequest, LLMDispatcher, Provider, RequestPriority, GLOBAL_DISPATCHER};
+use project_rust_learn::llm_api::utils::admission::AdmissionQueue;
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+use project_rust_learn::web::handlers::status_handler::get_gateway_status;
+use common::MockAdapter;
+
+/// 初始化测试环境的辅助函数
+async fn setup_test_env() -> std::sync::Arc<Pool<Sqlite>> {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+    init_db("data/init.sql").await.expect("DB init failed");
+    pool
+}
+
+fn model(provider: &str, health_status: &str) -> Model {
+    Model {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: format!("status-test-{}", uuid::Uuid::new_v4().simple()),
+        provider: provider.to_string(),
+        model_type: "llm".to_string(),
+        base_url: None,
+        is_active: true,
+        health_status: Some(health_status.to_string()),
+        last_health_check: None,
+        health_check_interval_seconds: None,
+        cost_per_token_input: None,
+        cost_per_token_output: None,
+        function_tags: None,
+        config: None,
+        project_id: None,
+        created_at: None,
+        updated_at: None,
+    }
+}
+
+fn call_log(model: &Model, status_code: i64) -> CallLog {
+    CallLog {
+        model_id: Some(model.id.clone()),
+        request_summary: Some("hello".to_string()),
+        ..common::call_log(&model.provider, status_code)
+    }
+}
+
+#[tokio::test]
+async fn test_gateway_status_snapshot() {
+    println!("=== Testing Gateway Status Snapshot ===");
+    let pool = setup_test_env().await;
+    let provider = format!("status-test-{}", uuid::Uuid::new_v4().simple());
+    let healthy = model(&provider, HEALTH_HEALTHY);
+    let unhealthy = model(&provider, HEALTH_UNHEALTHY);
+    create_model(&pool, &healthy).await.unwrap();
+    create_model(&pool, &unhealthy).await.unwrap();
+    create_call_log(&pool, &call_log(&healthy, 200)).await.unwrap();
+    create_call_log(&pool, &call_log(&healthy, 200)).await.unwrap();
+    create_call_log(&pool, &call_log(&unhealthy, 503)).await.unwrap();
+
+    let status = get_gateway_status(None).await.unwrap().0;
+    assert_eq!(status.status, "degraded");
+    assert_eq!(status.build.version, env!("CARGO_PKG_VERSION"));
+
+    let provider_status = status.providers.iter().find(|p| p.provider == provider).unwrap();
+    assert!(!provider_status.registered);
+    assert_eq!(provider_status.health, "degraded");
+    assert_eq!((provider_status.models.healthy, provider_status.models.unhealthy), (1, 1));
+    assert_eq!(provider_status.keys.total, 0);
+    assert_eq!(provider_status.recent_errors.total_calls, 3);
+    assert_eq!(provider_status.recent_errors.error_count, 1);
+    assert!((provider_status.recent_errors.error_rate.unwrap() - 1.0 / 3.0).abs() < 1e-9);
+    assert!(status.open_circuits.unhealthy_models.contains(&format!("{}/{}", provider, unhealthy.name)));
+    assert!(status.recent_errors.total_calls >= 3);
+    println!("✅ Provider health, open circuits and error rates reported");
+
+    for model in [&healthy, &unhealthy] {
+        delete_call_logs_by_model(&pool, &model.id).await.unwrap();
+        delete_model(&pool, &model.id).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_cache_hit_stats() {
+    let cache: CacheService<String, String> = CacheService::new(Duration::from_secs(60), 10).with_name("status-test-cache");
+    assert!(cache_hit_stats("status-test-cache").hit_rate.is_none());
+
+    assert!(cache.get(&"a".to_string()).await.is_none());
+    cache.insert("a".to_string(), "1".to_string()).await;
+    assert_eq!(cache.get(&"a".to_string()).await.as_deref(), Some("1"));
+    cache.get_or_load("a".to_string(), |_| async { "2".to_string() }).await;
+    cache.get_or_load("b".to_string(), |_| async { "3".to_string() }).await;
+
+    let stats = cache_hit_stats("status-test-cache");
+    assert_eq!((stats.hits, stats.misses), (2, 2));
+    assert_eq!(stats.hit_rate, Some(0.5));
+    println!("✅ Cache hit rate computed from lookups");
+}
+
+#[tokio::test]
+async fn test_status_reports_queue_depths() {
+    println!("=== Testing Queue Depths ===");
+    setup_test_env().await;
+    let dispatcher = LLMDispatcher::new(None);
+    dispatcher.register_client(Box::new(
+        MockAdapter::new(Provider::OpenAI).with_models(&["queue-model"]).with_delay(Duration::from_millis(500)),
+    )).await;
+    dispatcher.set_concurrency_limit(&Provider::OpenAI, Some(1));
+    GLOBAL_DISPATCHER.set(Arc::new(dispatcher)).ok();
+    let dispatcher = GLOBAL_DISPATCHER.get().unwrap().clone();
+
+    // 准入队列只有一个槽位，第二个请求排队
+    let admission = Arc::new(AdmissionQueue::new(1, 10, Duration::from_secs(5)));
+    let permit = admission.admit(RequestPriority::Normal).await.unwrap();
+    let queued = tokio::spawn({
+        let admission = admission.clone();
+        async move { admission.admit(RequestPriority::Normal).await.is_ok() }
+    });
+
+    // 供应商只有一个并发槽位，第二个请求等待槽位
+    let requests: Vec<_> = (0..2).map(|_| {
+        let dispatcher = dispatcher.clone();
+        tokio::spawn(async move {
+            let request = DispatchRequest::new(Provider::OpenAI, "queue-model".to_string(), vec![Message::user("hi".to_string())]);
+            dispatcher.dispatch(request).await
+        })
+    }).collect();
+
+    let mut status = get_gateway_status(Some(Extension(admission.clone()))).await.unwrap().0;
+    for _ in 0..40 {
+        if status.queues.admission.as_ref().is_some_and(|queue| queue.queue_depth == 1)
+            && status.queues.providers.iter().any(|stats| stats.waiting == 1)
+        {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        status = get_gateway_status(Some(Extension(admission.clone()))).await.unwrap().0;
+    }
+    let queue = status.queues.admission.as_ref().expect("admission queue reported");
+    assert_eq!((queue.in_flight, queue.queue_depth), (1, 1));
+    let openai = status.queues.providers.iter().find(|stats| stats.provider == "openai").expect("provider reported");
+    assert_eq!((openai.limit, openai.in_flight, openai.waiting), (1, 1, 1));
+    println!("✅ Admission queue depth and provider waiters reported");
+
+    drop(permit);
+    assert!(queued.await.unwrap());
+    for request in requests {
+        assert!(request.await.unwrap().is_ok());
+    }
+    let status = get_gateway_status(Some(Extension(admission))).await.unwrap().0;
+    assert_eq!(status.queues.providers[0].waiting, 0);
+}
@@ -0,0 +1,99 @@
+//! # 健康感知路由测试
+//!
+//! 测试被健康检查标记为 unhealthy 的模型不再调用上游，请求直接交给备选供应商
+
+mod common;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use project_rust_learn::dao::{init_sqlite_pool, init_db, SQLITE_POOL};
+use project_rust_learn::dao::cache::init_global_cache;
+use project_rust_learn::dao::model::{sync_model_health_to_cache, Model, HEALTH_HEALTHY, HEALTH_UNHEALTHY};
+use project_rust_learn::llm_api::dispatcher::{DispatchConfig, DispatchRequest, LLMDispatcher, LLMError, Provider};
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+use common::MockAdapter;
+
+/// 初始化测试环境的辅助函数
+async fn setup_test_env() {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+    init_db("data/init.sql").await.expect("DB init failed");
+    init_global_cache(&pool, 3600, 1000).await.expect("Cache init failed");
+}
+
+/// 只支持 shared-model 并记录调用次数的适配器
+fn counting(provider: &Provider, calls: &Arc<AtomicUsize>) -> MockAdapter {
+    MockAdapter::new(provider.clone()).with_models(&["shared-model"]).with_call_counter(calls.clone())
+}
+
+fn cached_model(provider: &Provider) -> Model {
+    Model {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: "shared-model".to_string(),
+        provider: provider.as_str().to_string(),
+        model_type: "llm".to_string(),
+        base_url: None,
+        is_active: true,
+        health_status: None,
+        last_health_check: None,
+        health_check_interval_seconds: None,
+        cost_per_token_input: None,
+        cost_per_token_output: None,
+        function_tags: None,
+        config: None,
+        project_id: None,
+        created_at: None,
+        updated_at: None,
+    }
+}
+
+#[tokio::test]
+async fn test_unhealthy_model_skipped_in_favor_of_fallback() {
+    setup_test_env().await;
+
+    println!("=== Testing Health-Aware Routing ===");
+    // 使用唯一的自定义供应商，避免影响其他测试
+    let primary = Provider::Custom(format!("primary-{}", uuid::Uuid::new_v4().simple()));
+    let backup = Provider::Custom(format!("backup-{}", uuid::Uuid::new_v4().simple()));
+    let primary_calls = Arc::new(AtomicUsize::new(0));
+    let backup_calls = Arc::new(AtomicUsize::new(0));
+
+    let dispatcher = LLMDispatcher::new(Some(DispatchConfig {
+        enable_fallback: true,
+        fallback_providers: vec![backup.clone()],
+        default_retry_count: 2,
+        ..Default::default()
+    }));
+    dispatcher.register_client(Box::new(counting(&primary, &primary_calls))).await;
+    dispatcher.register_client(Box::new(counting(&backup, &backup_calls))).await;
+
+    let request = || DispatchRequest::new(primary.clone(), "shared-model".to_string(), vec![Message::user("hello".to_string())]);
+
+    // 健康的模型正常调用
+    let model = cached_model(&primary);
+    sync_model_health_to_cache(&model, HEALTH_HEALTHY).await.expect("sync failed");
+    let response = dispatcher.dispatch(request()).await.expect("dispatch failed");
+    assert_eq!(response.provider, primary);
+    assert_eq!(primary_calls.load(Ordering::SeqCst), 1);
+    println!("✅ Healthy model served by primary provider");
+
+    // 标记为 unhealthy 后不再调用主供应商，也不重试
+    sync_model_health_to_cache(&model, HEALTH_UNHEALTHY).await.expect("sync failed");
+    let response = dispatcher.dispatch(request()).await.expect("dispatch failed");
+    assert_eq!(response.provider, backup);
+    assert_eq!(primary_calls.load(Ordering::SeqCst), 1);
+    assert_eq!(backup_calls.load(Ordering::SeqCst), 1);
+    println!("✅ Unhealthy model skipped, fallback provider used");
+
+    // 未启用 fallback 时直接返回 ModelUnhealthy
+    let strict = LLMDispatcher::new(Some(DispatchConfig {
+        enable_fallback: false,
+        ..Default::default()
+    }));
+    strict.register_client(Box::new(counting(&primary, &primary_calls))).await;
+    let result = strict.dispatch(request()).await;
+    assert!(matches!(result, Err(LLMError::ModelUnhealthy(_))));
+    assert_eq!(primary_calls.load(Ordering::SeqCst), 1);
+    println!("✅ Unhealthy model rejected without fallback");
+}
@@ -0,0 +1,74 @@
+//! # API Key 冷却测试
+//!
+//! 测试被限流的 API Key 暂时移出轮询池，冷却结束后自动恢复（缓存条目已被淘汰时按数据库确认）
+
+mod common;
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use project_rust_learn::dao::{init_sqlite_pool, init_db, SQLITE_POOL};
+use project_rust_learn::dao::cache::{init_global_cache, GLOBAL_CACHE};
+use project_rust_learn::dao::provider_key_pool::{
+    create_provider_key_pool_from_raw_key, delete_provider_key_pool, get_active_key_count,
+    get_api_key_round_robin, get_cooling_down_keys, key_pool_cache, key_pool_cache_key, mark_key_unavailable,
+    preload_provider_key_pools_to_cache, reload_provider_api_keys,
+};
+
+/// 初始化测试环境的辅助函数
+async fn setup_test_env() {
+    common::install_test_master_keyring();
+    init_sqlite_pool("sqlite://data/app.db").await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+    init_db("data/init.sql").await.expect("DB init failed");
+    init_global_cache(&pool, 3600, 1000).await.expect("Cache init failed");
+}
+
+async fn selected_keys(provider: &str, rounds: usize) -> HashSet<String> {
+    let mut selected = HashSet::new();
+    for _ in 0..rounds {
+        if let Some((_, key_id)) = get_api_key_round_robin(provider).await {
+            selected.insert(key_id);
+        }
+    }
+    selected
+}
+
+#[tokio::test]
+async fn test_rate_limited_key_cooldown() {
+    setup_test_env().await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+
+    println!("=== Testing API Key Cool-down ===");
+
+    // 使用唯一的 provider，避免影响其他测试
+    let provider = format!("cooldown-{}", uuid::Uuid::new_v4().simple());
+    let key_a = uuid::Uuid::new_v4().to_string();
+    let key_b = uuid::Uuid::new_v4().to_string();
+    for (id, raw_key) in [(&key_a, "sk-cooldown-a"), (&key_b, "sk-cooldown-b")] {
+        create_provider_key_pool_from_raw_key(&pool, id.clone(), provider.clone(), raw_key, true, None, None)
+            .await
+            .expect("Failed to create key");
+    }
+    preload_provider_key_pools_to_cache(&pool).await.expect("Failed to preload key pools");
+    assert_eq!(selected_keys(&provider, 4).await, HashSet::from([key_a.clone(), key_b.clone()]));
+
+    // 冷却中的 Key 不参与轮询，重新加载也不会恢复
+    mark_key_unavailable(&provider, &key_a, Duration::from_millis(200)).await;
+    assert_eq!(get_cooling_down_keys(&provider).await, vec![key_a.clone()]);
+    reload_provider_api_keys(&pool, &provider).await.expect("Failed to reload keys");
+    assert_eq!(get_active_key_count(&provider).await, 1);
+    assert_eq!(selected_keys(&provider, 4).await, HashSet::from([key_b.clone()]));
+    println!("✅ Rate-limited key removed from rotation");
+
+    // 冷却期间缓存条目被淘汰，冷却结束后按数据库确认仍启用，重新加入轮询
+    key_pool_cache(GLOBAL_CACHE.get().unwrap()).invalidate(&key_pool_cache_key(&provider, &key_a)).await;
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    assert!(get_cooling_down_keys(&provider).await.is_empty());
+    assert_eq!(selected_keys(&provider, 4).await, HashSet::from([key_a.clone(), key_b.clone()]));
+    println!("✅ Key restored after cool-down");
+
+    for id in [&key_a, &key_b] {
+        delete_provider_key_pool(&pool, id).await.expect("Failed to delete key");
+    }
+}
@@ -0,0 +1,144 @@
+mod common;
+
+use project_rust_learn::dao::{init_sqlite_pool, init_db, run_migrations, SQLITE_POOL};
+use project_rust_learn::dao::provider_key_pool::{
+    ProviderKeyPool, create_provider_key_pool, delete_provider_key_pool, audit_key_pool_integrity,
+    deactivate_corrupted_keys, get_provider_key_pool_by_id,
+};
+use project_rust_learn::dao::provider_key_pool::crypto::{
+    process_api_key, encrypt_api_key, encrypt_api_key_with, MasterKey, LEGACY_KEY_ID,
+};
+use std::sync::Arc;
+use sqlx::{Pool, Sqlite, SqlitePool};
+
+/// 初始化测试环境的辅助函数
+async fn setup_test_env() -> Arc<Pool<Sqlite>> {
+    common::install_test_master_keyring();
+    init_sqlite_pool("sqlite://data/app.db").await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+    init_db("data/init.sql").await.expect("DB init failed");
+    pool
+}
+
+/// 格式正确但认证解密失败的密文：使用同 ID 的另一个密钥加密
+fn tampered_ciphertext(api_key: &str) -> String {
+    encrypt_api_key_with(&MasterKey::new(LEGACY_KEY_ID, [7u8; 32]).unwrap(), api_key).expect("encrypt failed")
+}
+
+/// 引用不在密钥环中的主密钥的密文
+fn unknown_key_ciphertext(api_key: &str) -> String {
+    encrypt_api_key_with(&MasterKey::new("retired-audit-key", [7u8; 32]).unwrap(), api_key).expect("encrypt failed")
+}
+
+fn key_pool(key_hash: String, encrypted_key_value: String) -> ProviderKeyPool {
+    ProviderKeyPool {
+        id: uuid::Uuid::new_v4().to_string(),
+        provider: "audit-test".to_string(),
+        key_hash,
+        encrypted_key_value,
+        is_active: true,
+        usage_count: 0,
+        last_used_at: None,
+        rate_limit_per_minute: None,
+        rate_limit_per_hour: None,
+        project_id: None,
+        created_at: None,
+    }
+}
+
+#[tokio::test]
+async fn test_key_pool_integrity_audit() {
+    let pool = setup_test_env().await;
+
+    println!("=== Testing Key Pool Integrity Audit ===");
+
+    // 正常的 Key
+    let (hash, encrypted) = process_api_key("sk-audit-healthy").expect("process_api_key failed");
+    let healthy = key_pool(hash, encrypted);
+    // 认证解密失败的密文
+    let (hash, _) = process_api_key("sk-audit-corrupted").expect("process_api_key failed");
+    let corrupted = key_pool(hash, tampered_ciphertext("sk-audit-corrupted"));
+    // 能解密但哈希不一致
+    let (hash, _) = process_api_key("sk-audit-original").expect("process_api_key failed");
+    let mismatched = key_pool(hash, encrypt_api_key("sk-audit-replaced").expect("encrypt failed"));
+    // 主密钥不在密钥环中、密文格式错误：无法校验
+    let (hash, _) = process_api_key("sk-audit-unknown").expect("process_api_key failed");
+    let unknown = key_pool(hash, unknown_key_ciphertext("sk-audit-unknown"));
+    let (hash, _) = process_api_key("sk-audit-malformed").expect("process_api_key failed");
+    let malformed = key_pool(hash, "not-a-valid-ciphertext".to_string());
+    let keys = [&healthy, &corrupted, &mismatched, &unknown, &malformed];
+
+    for key in keys {
+        create_provider_key_pool(&pool, key).await.expect("create_provider_key_pool failed");
+    }
+
+    let report = audit_key_pool_integrity(&pool).await.expect("audit_key_pool_integrity failed");
+    println!("✅ Audit report: {:?}", report);
+
+    assert!(!report.is_healthy());
+    assert!(!report.failures.iter().any(|f| f.key_pool_id == healthy.id));
+    let corrupted_failure = report.failures.iter().find(|f| f.key_pool_id == corrupted.id).expect("corrupted key not reported");
+    assert_eq!(corrupted_failure.issue, "decrypt_failed");
+    let mismatched_failure = report.failures.iter().find(|f| f.key_pool_id == mismatched.id).expect("mismatched key not reported");
+    assert_eq!(mismatched_failure.issue, "hash_mismatch");
+    for key in [&unknown, &malformed] {
+        let failure = report.failures.iter().find(|f| f.key_pool_id == key.id).expect("unverifiable key not reported");
+        assert_eq!(failure.issue, "unverifiable");
+        assert!(!failure.is_corrupted());
+    }
+
+    for key in keys {
+        delete_provider_key_pool(&pool, &key.id).await.expect("delete_provider_key_pool failed");
+    }
+
+    println!("\n=== Key Pool Integrity Audit Tests Completed ===");
+}
+
+#[tokio::test]
+async fn test_deactivate_corrupted_keys() {
+    common::install_test_master_keyring();
+    // 使用独立的数据库，避免停用共享库中的其他 Key
+    let path = std::env::temp_dir().join(format!("key-audit-test-{}.db", uuid::Uuid::new_v4().simple()));
+    let pool = SqlitePool::connect(&format!("sqlite://{}?mode=rwc", path.display())).await.unwrap();
+    run_migrations(&pool).await.expect("migration failed");
+
+    println!("=== Testing Corrupted Key Deactivation ===");
+
+    let (hash, encrypted) = process_api_key("sk-deactivate-healthy").expect("process_api_key failed");
+    let healthy = key_pool(hash, encrypted);
+    let (hash, _) = process_api_key("sk-deactivate-corrupted").expect("process_api_key failed");
+    let corrupted = key_pool(hash, tampered_ciphertext("sk-deactivate-corrupted"));
+    let (hash, _) = process_api_key("sk-deactivate-original").expect("process_api_key failed");
+    let mismatched = key_pool(hash, encrypt_api_key("sk-deactivate-replaced").expect("encrypt failed"));
+    let (hash, _) = process_api_key("sk-deactivate-unknown").expect("process_api_key failed");
+    let unknown = key_pool(hash, unknown_key_ciphertext("sk-deactivate-unknown"));
+
+    for key in [&healthy, &corrupted, &mismatched, &unknown] {
+        create_provider_key_pool(&pool, key).await.expect("create_provider_key_pool failed");
+    }
+
+    let mut report = audit_key_pool_integrity(&pool).await.expect("audit_key_pool_integrity failed");
+    let deactivated = deactivate_corrupted_keys(&pool, &mut report).await.expect("deactivate_corrupted_keys failed");
+    assert_eq!(deactivated, 2);
+    assert_eq!(report.deactivated_keys, 2);
+    assert!(report.failures.iter().all(|f| f.deactivated == f.is_corrupted()));
+    println!("✅ Deactivated {} keys", deactivated);
+
+    let is_active = |id: String| {
+        let pool = pool.clone();
+        async move { get_provider_key_pool_by_id(&pool, &id).await.unwrap().unwrap().is_active }
+    };
+    assert!(is_active(healthy.id.clone()).await);
+    assert!(!is_active(corrupted.id.clone()).await);
+    assert!(!is_active(mismatched.id.clone()).await);
+    assert!(is_active(unknown.id.clone()).await);
+    println!("✅ Healthy and unverifiable keys left active");
+
+    // 已停用的 Key 不再重复停用
+    let mut report = audit_key_pool_integrity(&pool).await.expect("second audit failed");
+    assert_eq!(deactivate_corrupted_keys(&pool, &mut report).await.unwrap(), 0);
+    println!("✅ Re-running deactivation is a no-op");
+
+    pool.close().await;
+    std::fs::remove_file(path).ok();
+}
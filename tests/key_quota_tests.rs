@@ -0,0 +1,162 @@
+//! # API Key 剩余配额测试
+//!
+//! 测试从响应头记录 Key 的剩余配额，轮询时提前跳过即将耗尽配额的 Key
+
+mod common;
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use mockito::Server;
+use serde_json::json;
+
+use project_rust_learn::dao::{init_sqlite_pool, init_db, SQLITE_POOL};
+use project_rust_learn::dao::cache::init_global_cache;
+use project_rust_learn::dao::provider_key_pool::quota::{parse_reset_duration, MAX_QUOTA_RESET};
+use project_rust_learn::dao::provider_key_pool::{
+    create_provider_key_pool_from_raw_key, delete_provider_key_pool, get_api_key_round_robin,
+    get_key_quota, is_key_near_limit, preload_provider_key_pools_to_cache, record_key_quota, KeyQuota,
+};
+use project_rust_learn::llm_api::openai::client::{OpenAIChatRequest, OpenAIClient};
+use project_rust_learn::llm_api::utils::client::{CallMetadata, CALL_METADATA};
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+
+/// 初始化测试环境的辅助函数
+async fn setup_test_env() {
+    common::install_test_master_keyring();
+    init_sqlite_pool("sqlite://data/app.db").await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+    init_db("data/init.sql").await.expect("DB init failed");
+    init_global_cache(&pool, 3600, 1000).await.expect("Cache init failed");
+}
+
+async fn selected_keys(provider: &str, rounds: usize) -> HashSet<String> {
+    let mut selected = HashSet::new();
+    for _ in 0..rounds {
+        if let Some((_, key_id)) = get_api_key_round_robin(provider).await {
+            selected.insert(key_id);
+        }
+    }
+    selected
+}
+
+#[test]
+fn test_parse_quota_headers() {
+    assert_eq!(parse_reset_duration("60"), Some(Duration::from_secs(60)));
+    assert_eq!(parse_reset_duration("6m0s"), Some(Duration::from_secs(360)));
+    assert_eq!(parse_reset_duration("1h2m3s"), Some(Duration::from_secs(3723)));
+    assert_eq!(parse_reset_duration("250ms"), Some(Duration::from_millis(250)));
+    assert_eq!(parse_reset_duration("1.5s"), Some(Duration::from_millis(1500)));
+    assert_eq!(parse_reset_duration("soon"), None);
+    assert_eq!(parse_reset_duration(""), None);
+    assert_eq!(parse_reset_duration("-1"), None);
+    assert_eq!(parse_reset_duration("NaN"), None);
+    // 过大的值不会 panic，按上限处理
+    assert_eq!(parse_reset_duration("inf"), Some(MAX_QUOTA_RESET));
+    assert_eq!(parse_reset_duration("1e30"), Some(MAX_QUOTA_RESET));
+    assert_eq!(parse_reset_duration("99999999999999999999h"), Some(MAX_QUOTA_RESET));
+
+    let headers = [
+        ("x-ratelimit-remaining-requests", "2"),
+        ("x-ratelimit-reset-requests", "1s"),
+        ("x-ratelimit-remaining-tokens", "3500"),
+    ];
+    let quota = KeyQuota::from_headers(|name| headers.iter().find(|(n, _)| *n == name).map(|(_, v)| *v));
+    assert_eq!(quota, Some(KeyQuota {
+        remaining_requests: Some(2),
+        reset_requests: Some(Duration::from_secs(1)),
+        remaining_tokens: Some(3500),
+        reset_tokens: None,
+    }));
+    assert_eq!(KeyQuota::from_headers(|_| None), None);
+    println!("✅ Quota headers parsed");
+}
+
+#[tokio::test]
+async fn test_near_limit_key_skipped_in_rotation() {
+    setup_test_env().await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+
+    println!("=== Testing Pre-emptive Key Switching ===");
+
+    // 使用唯一的 provider，避免影响其他测试
+    let provider = format!("quota-{}", uuid::Uuid::new_v4().simple());
+    let key_a = uuid::Uuid::new_v4().to_string();
+    let key_b = uuid::Uuid::new_v4().to_string();
+    for (id, raw_key) in [(&key_a, "sk-quota-a"), (&key_b, "sk-quota-b")] {
+        create_provider_key_pool_from_raw_key(&pool, id.clone(), provider.clone(), raw_key, true, None, None)
+            .await
+            .expect("Failed to create key");
+    }
+    preload_provider_key_pools_to_cache(&pool).await.expect("Failed to preload key pools");
+
+    // 剩余请求数即将耗尽的 Key 在窗口重置前不再被选中
+    record_key_quota(&key_a, KeyQuota {
+        remaining_requests: Some(1),
+        reset_requests: Some(Duration::from_millis(200)),
+        ..Default::default()
+    });
+    assert!(is_key_near_limit(&key_a));
+    assert_eq!(selected_keys(&provider, 4).await, HashSet::from([key_b.clone()]));
+    println!("✅ Near-limit key skipped");
+
+    // 两个 Key 都即将耗尽时仍按轮询选择
+    record_key_quota(&key_b, KeyQuota {
+        remaining_tokens: Some(10),
+        reset_tokens: Some(Duration::from_millis(200)),
+        ..Default::default()
+    });
+    assert_eq!(selected_keys(&provider, 4).await, HashSet::from([key_a.clone(), key_b.clone()]));
+    println!("✅ Falls back to round robin when all keys are near limit");
+
+    // 窗口重置后恢复
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    assert!(!is_key_near_limit(&key_a));
+    record_key_quota(&key_b, KeyQuota { remaining_requests: Some(0), ..Default::default() });
+    assert_eq!(selected_keys(&provider, 4).await, HashSet::from([key_a.clone()]));
+    println!("✅ Key restored after quota window reset");
+
+    for id in [&key_a, &key_b] {
+        delete_provider_key_pool(&pool, id).await.expect("Failed to delete key");
+    }
+}
+
+#[tokio::test]
+async fn test_quota_recorded_from_response_headers() {
+    setup_test_env().await;
+
+    println!("=== Testing Quota Capture From Response Headers ===");
+    let mut server = Server::new_async().await;
+    let _mock = server.mock("POST", "/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_header("x-ratelimit-remaining-requests", "42")
+        .with_header("x-ratelimit-remaining-tokens", "9000")
+        .with_header("x-ratelimit-reset-tokens", "6m0s")
+        .with_body(json!({
+            "id": "chatcmpl-quota",
+            "object": "chat.completion",
+            "created": 1757412000,
+            "model": "gpt-4o-mini",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "ok"},
+                "finish_reason": "stop"
+            }]
+        }).to_string())
+        .create_async()
+        .await;
+
+    let key_id = uuid::Uuid::new_v4().to_string();
+    let client = OpenAIClient::new_with_base_url("sk-test".to_string(), server.url()).unwrap();
+    let request = OpenAIChatRequest::new("gpt-4o-mini".to_string(), vec![Message::user("hi".to_string())]);
+    let metadata = CallMetadata::default().with_key_id(&key_id);
+    CALL_METADATA.scope(metadata, client.chat(request)).await.expect("chat failed");
+
+    let quota = get_key_quota(&key_id).expect("quota not recorded");
+    assert_eq!(quota.remaining_requests, Some(42));
+    assert_eq!(quota.remaining_tokens, Some(9000));
+    assert_eq!(quota.reset_tokens, Some(Duration::from_secs(360)));
+    assert!(!is_key_near_limit(&key_id));
+    println!("✅ Quota recorded for key {}: {:?}", key_id, quota);
+}
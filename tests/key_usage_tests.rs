@@ -0,0 +1,79 @@
+//! # API Key 使用统计测试
+//!
+//! 测试轮询选中 Key 时累加使用次数，并批量写回数据库
+
+mod common;
+
+use project_rust_learn::dao::{init_sqlite_pool, init_db, SQLITE_POOL};
+use project_rust_learn::dao::cache::init_global_cache;
+use project_rust_learn::dao::provider_key_pool::{
+    create_provider_key_pool_from_raw_key, delete_provider_key_pool, flush_key_usage,
+    get_api_key_round_robin, get_provider_key_pool_by_id, pending_key_usage, record_key_usage,
+    preload_provider_key_pools_to_cache,
+};
+
+/// 初始化测试环境的辅助函数
+async fn setup_test_env() {
+    common::install_test_master_keyring();
+    init_sqlite_pool("sqlite://data/app.db").await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+    init_db("data/init.sql").await.expect("DB init failed");
+    init_global_cache(&pool, 3600, 1000).await.expect("Cache init failed");
+}
+
+#[tokio::test]
+async fn test_key_usage_batched_flush() {
+    setup_test_env().await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+
+    println!("=== Testing Key Usage Tracking ===");
+
+    // 使用唯一的 provider，避免影响其他测试
+    let provider = format!("usage-{}", uuid::Uuid::new_v4().simple());
+    let key_id = uuid::Uuid::new_v4().to_string();
+    create_provider_key_pool_from_raw_key(&pool, key_id.clone(), provider.clone(), "sk-usage-test", true, None, None)
+        .await
+        .expect("Failed to create key");
+    preload_provider_key_pools_to_cache(&pool).await.expect("Failed to preload key pools");
+
+    for _ in 0..3 {
+        let (_, selected) = get_api_key_round_robin(&provider).await.expect("No key selected");
+        assert_eq!(selected, key_id);
+    }
+
+    // 写回前数据库中的统计不变
+    assert_eq!(pending_key_usage(&key_id), 3);
+    let stored = get_provider_key_pool_by_id(&pool, &key_id).await.unwrap().unwrap();
+    assert_eq!(stored.usage_count, 0);
+    assert!(stored.last_used_at.is_none());
+    println!("✅ Usage accumulated in memory");
+
+    flush_key_usage(&pool).await.expect("Failed to flush key usage");
+    assert_eq!(pending_key_usage(&key_id), 0);
+    let stored = get_provider_key_pool_by_id(&pool, &key_id).await.unwrap().unwrap();
+    assert_eq!(stored.usage_count, 3);
+    assert!(stored.last_used_at.is_some());
+    println!("✅ Usage flushed: count={}, last_used_at={:?}", stored.usage_count, stored.last_used_at);
+
+    // 并发累加不丢失计数
+    let tasks: Vec<_> = (0..8)
+        .map(|_| {
+            let key_id = key_id.clone();
+            tokio::spawn(async move {
+                for _ in 0..100 {
+                    record_key_usage(&key_id);
+                }
+            })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+    assert_eq!(pending_key_usage(&key_id), 800);
+    flush_key_usage(&pool).await.expect("Failed to flush key usage");
+    let stored = get_provider_key_pool_by_id(&pool, &key_id).await.unwrap().unwrap();
+    assert_eq!(stored.usage_count, 803);
+    println!("✅ Concurrent usage counted exactly");
+
+    delete_provider_key_pool(&pool, &key_id).await.expect("Failed to delete key");
+}
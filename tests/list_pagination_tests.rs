@@ -0,0 +1,184 @@
+//! # 列表接口分页测试
+//!
+//! 测试 models、providers、API Key 列表返回统一的分页响应：按游标翻页不重复不遗漏，
+//! `total` 与过滤条件一致，`filter` 回显过滤条件
+
+mod common;
+
+use std::collections::HashSet;
+use axum::{extract::{Path, Query}, http::StatusCode};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use sqlx::{Pool, Sqlite};
+
+use project_rust_learn::dao::{init_sqlite_pool, init_db, SQLITE_POOL};
+use project_rust_learn::dao::model::{create_model, delete_model, Model};
+use project_rust_learn::dao::provider::{create_provider, hard_delete_provider, Provider};
+use project_rust_learn::dao::provider_key_pool::{create_provider_key_pool_from_raw_key, delete_provider_key_pool};
+use project_rust_learn::web::handlers::api_key_handler::list_provider_api_keys;
+use project_rust_learn::web::handlers::model_handler::list_models;
+use project_rust_learn::web::handlers::provider_handler::list_providers;
+
+/// 初始化测试环境的辅助函数
+async fn setup_test_env() -> std::sync::Arc<Pool<Sqlite>> {
+    common::install_test_master_keyring();
+    init_sqlite_pool("sqlite://data/app.db").await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+    init_db("data/init.sql").await.expect("DB init failed");
+    pool
+}
+
+fn query<T: DeserializeOwned>(value: serde_json::Value) -> Query<T> {
+    Query(serde_json::from_value(value).expect("Invalid list query"))
+}
+
+async fn create_test_provider(pool: &Pool<Sqlite>) -> Provider {
+    let name = format!("pagination-{}", uuid::Uuid::new_v4().simple());
+    let provider = Provider {
+        id: uuid::Uuid::new_v4().to_string(),
+        display_name: name.clone(),
+        name,
+        base_url: None,
+        description: None,
+        config: None,
+        is_active: true,
+        project_id: None,
+        max_concurrent_requests: None,
+        created_at: None,
+        updated_at: None,
+    };
+    create_provider(pool, &provider).await.expect("create provider failed");
+    provider
+}
+
+fn test_model(provider: &str, is_active: bool) -> Model {
+    Model {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: format!("pagination-model-{}", uuid::Uuid::new_v4().simple()),
+        provider: provider.to_string(),
+        model_type: "chat".to_string(),
+        base_url: None,
+        is_active,
+        health_status: None,
+        last_health_check: None,
+        health_check_interval_seconds: None,
+        cost_per_token_input: None,
+        cost_per_token_output: None,
+        function_tags: None,
+        config: None,
+        project_id: None,
+        created_at: None,
+        updated_at: None,
+    }
+}
+
+#[tokio::test]
+async fn test_models_cursor_pagination() {
+    let pool = setup_test_env().await;
+    let provider = create_test_provider(&pool).await;
+
+    println!("=== Testing Model List Pagination ===");
+    let models: Vec<Model> = (0..5).map(|i| test_model(&provider.name, i != 4)).collect();
+    for model in &models {
+        create_model(&pool, model).await.expect("create model failed");
+    }
+
+    // 逐页读取启用的模型，每页 2 条
+    let mut seen = HashSet::new();
+    let mut cursor: Option<String> = None;
+    let mut pages = 0;
+    loop {
+        let page = list_models(query(json!({ "provider": provider.name, "active": true, "limit": 2, "cursor": cursor })))
+            .await
+            .expect("list models failed")
+            .0;
+        assert_eq!(page.total, 4);
+        assert_eq!(page.limit, 2);
+        assert_eq!(page.filter.provider.as_deref(), Some(provider.name.as_str()));
+        assert_eq!(page.filter.active, Some(true));
+        assert!(page.data.iter().all(|model| model.is_active));
+        for model in page.data {
+            assert!(seen.insert(model.id), "model returned twice");
+        }
+        pages += 1;
+        match page.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+    assert_eq!(seen.len(), 4);
+    assert_eq!(pages, 2);
+    println!("✅ Models paged by cursor without duplicates");
+
+    let status = list_models(query(json!({ "cursor": "not a cursor!" }))).await.unwrap_err();
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    println!("✅ Invalid cursor rejected");
+
+    for model in &models {
+        delete_model(&pool, &model.id).await.expect("delete model failed");
+    }
+    hard_delete_provider(&pool, &provider.id).await.expect("delete provider failed");
+}
+
+#[tokio::test]
+async fn test_providers_and_api_keys_cursor_pagination() {
+    let pool = setup_test_env().await;
+    let provider = create_test_provider(&pool).await;
+
+    println!("=== Testing Provider List Pagination ===");
+    let mut seen = HashSet::new();
+    let mut cursor: Option<String> = None;
+    let total = loop {
+        let page = list_providers(query(json!({ "limit": 2, "cursor": cursor })))
+            .await
+            .expect("list providers failed")
+            .0;
+        assert!(page.data.len() <= 2);
+        for item in page.data {
+            assert!(seen.insert(item.id), "provider returned twice");
+        }
+        match page.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => break page.total,
+        }
+    };
+    assert!(seen.contains(&provider.id));
+    assert_eq!(seen.len() as i64, total);
+    println!("✅ Providers paged by cursor, total {}", total);
+
+    println!("=== Testing API Key List Pagination ===");
+    let mut key_ids = Vec::new();
+    for i in 0..3 {
+        let id = uuid::Uuid::new_v4().to_string();
+        create_provider_key_pool_from_raw_key(&pool, id.clone(), provider.name.clone(), &format!("sk-page-{}", i), true, None, None)
+            .await
+            .expect("create key failed");
+        key_ids.push(id);
+    }
+
+    let first = list_provider_api_keys(Path(provider.id.clone()), query(json!({ "limit": 2 })))
+        .await
+        .expect("list keys failed")
+        .0;
+    assert_eq!(first.total, 3);
+    assert_eq!(first.data.len(), 2);
+    assert_eq!(first.filter.provider_id, provider.id);
+    assert_eq!(first.filter.provider, provider.name);
+    let second = list_provider_api_keys(Path(provider.id.clone()), query(json!({ "limit": 2, "cursor": first.next_cursor })))
+        .await
+        .expect("list keys failed")
+        .0;
+    assert_eq!(second.data.len(), 1);
+    assert!(second.next_cursor.is_none());
+    let listed: HashSet<String> = first.data.into_iter().chain(second.data).map(|key| key.id).collect();
+    assert_eq!(listed, key_ids.iter().cloned().collect());
+    println!("✅ API keys paged by cursor");
+
+    let status = list_provider_api_keys(Path("missing-provider".to_string()), query(json!({}))).await.unwrap_err();
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    for id in &key_ids {
+        delete_provider_key_pool(&pool, id).await.expect("delete key failed");
+    }
+    hard_delete_provider(&pool, &provider.id).await.expect("delete provider failed");
+}
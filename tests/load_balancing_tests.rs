@@ -0,0 +1,135 @@
+//! # 多供应商负载均衡测试
+//!
+//! 测试多个供应商提供同一模型时，调度器按 `load_balance_policy` 选择供应商，
+//! 错误率过高的供应商在其它供应商可用时不再被选中，以及请求项目不可见、
+//! 健康检查标记为 unhealthy 的供应商不参与选择
+
+mod common;
+
+use std::time::Duration;
+
+use project_rust_learn::dao::{init_db, init_sqlite_pool, SQLITE_POOL};
+use project_rust_learn::dao::cache::init_global_cache;
+use project_rust_learn::dao::model::{insert_model_to_cache, sync_model_health_to_cache, Model, HEALTH_UNHEALTHY};
+use project_rust_learn::llm_api::dispatcher::{DispatchConfig, DispatchRequest, LLMDispatcher, Provider};
+use project_rust_learn::llm_api::utils::client::{CallMetadata, CALL_METADATA};
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+use project_rust_learn::llm_api::utils::load_balancer::{get_load_balancer, LoadBalancePolicy};
+use common::MockAdapter;
+
+/// 使用唯一的自定义供应商和模型名称，避免影响其他测试；返回按名称排序的供应商
+async fn setup(policy: LoadBalancePolicy) -> (LLMDispatcher, Provider, Provider, String) {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+    init_db("data/init.sql").await.expect("DB init failed");
+    init_global_cache(&pool, 3600, 1000).await.expect("Cache init failed");
+
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let first = Provider::Custom(format!("lb-a-{}", suffix));
+    let second = Provider::Custom(format!("lb-b-{}", suffix));
+    let model = format!("lb-model-{}", suffix);
+
+    let dispatcher = LLMDispatcher::new(Some(DispatchConfig {
+        load_balance_policy: policy,
+        ..DispatchConfig::default()
+    }));
+    for provider in [&first, &second] {
+        dispatcher.register_client(Box::new(MockAdapter::new(provider.clone()).with_models(&[&model]))).await;
+    }
+    (dispatcher, first, second, model)
+}
+
+fn record(provider: &Provider, model: &str, latency_ms: u64, success: bool, times: usize) {
+    for _ in 0..times {
+        get_load_balancer().record(provider, model, Duration::from_millis(latency_ms), success);
+    }
+}
+
+#[tokio::test]
+async fn test_fastest_policy() {
+    let (dispatcher, first, second, model) = setup(LoadBalancePolicy::Fastest).await;
+
+    println!("=== Testing Fastest Load Balance Policy ===");
+    record(&first, &model, 800, true, 5);
+    record(&second, &model, 100, true, 5);
+    assert_eq!(dispatcher.resolve_model(&model).await, Some((second.clone(), model.clone())));
+    println!("✅ Faster provider selected");
+
+    record(&second, &model, 100, false, 10);
+    assert!(get_load_balancer().stats(&second, &model).unwrap().is_degraded());
+    assert_eq!(dispatcher.resolve_model(&model).await, Some((first.clone(), model.clone())));
+    println!("✅ Degraded provider skipped");
+
+    // 调度成功后计入统计
+    let before = get_load_balancer().stats(&first, &model).unwrap().samples;
+    let response = dispatcher.dispatch(DispatchRequest::new(first.clone(), model.clone(), vec![Message::user("hello".to_string())])).await;
+    assert!(response.is_ok(), "dispatch failed: {:?}", response.err());
+    assert_eq!(get_load_balancer().stats(&first, &model).unwrap().samples, before + 1);
+    println!("✅ Dispatch recorded in load balancer stats");
+}
+
+#[tokio::test]
+async fn test_first_policy_ignores_stats() {
+    let (dispatcher, first, second, model) = setup(LoadBalancePolicy::First).await;
+
+    println!("=== Testing First Load Balance Policy ===");
+    record(&first, &model, 5_000, true, 5);
+    record(&second, &model, 10, true, 5);
+    assert_eq!(dispatcher.resolve_model(&model).await, Some((first, model.clone())));
+    println!("✅ First provider selected regardless of latency");
+}
+
+fn cached_model(provider: &Provider, name: &str, project_id: Option<&str>) -> Model {
+    Model {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        provider: provider.as_str().to_string(),
+        model_type: "llm".to_string(),
+        base_url: None,
+        is_active: true,
+        health_status: None,
+        last_health_check: None,
+        health_check_interval_seconds: None,
+        cost_per_token_input: None,
+        cost_per_token_output: None,
+        function_tags: None,
+        config: None,
+        project_id: project_id.map(str::to_string),
+        created_at: None,
+        updated_at: None,
+    }
+}
+
+#[tokio::test]
+async fn test_unhealthy_provider_not_chosen() {
+    let (dispatcher, first, second, model) = setup(LoadBalancePolicy::First).await;
+
+    println!("=== Testing Unhealthy Candidates Filtered ===");
+    sync_model_health_to_cache(&cached_model(&first, &model, None), HEALTH_UNHEALTHY).await.unwrap();
+    assert_eq!(dispatcher.resolve_model(&model).await, Some((second.clone(), model.clone())));
+    println!("✅ Unhealthy provider skipped");
+
+    // 全部不健康时仍然返回候选，由调度时的健康检查和 fallback 处理
+    sync_model_health_to_cache(&cached_model(&second, &model, None), HEALTH_UNHEALTHY).await.unwrap();
+    assert_eq!(dispatcher.resolve_model(&model).await, Some((first, model.clone())));
+    println!("✅ All unhealthy keeps candidates");
+}
+
+#[tokio::test]
+async fn test_invisible_provider_not_chosen() {
+    let (dispatcher, first, second, model) = setup(LoadBalancePolicy::First).await;
+
+    println!("=== Testing Project Visibility Filtered ===");
+    insert_model_to_cache(&cached_model(&first, &model, Some("other-project"))).await.unwrap();
+    assert_eq!(dispatcher.resolve_model(&model).await, Some((second.clone(), model.clone())));
+    println!("✅ Provider invisible to the project skipped");
+
+    insert_model_to_cache(&cached_model(&second, &model, Some("other-project"))).await.unwrap();
+    assert_eq!(dispatcher.resolve_model(&model).await, None);
+    println!("✅ No visible provider resolves to None");
+
+    let owner = CallMetadata::default().with_project(Some("other-project".to_string()));
+    let resolved = CALL_METADATA.scope(owner, dispatcher.resolve_model(&model)).await;
+    assert_eq!(resolved, Some((first, model.clone())));
+    println!("✅ Owning project still resolves the model");
+}
@@ -0,0 +1,87 @@
+//! # 按语言路由测试
+//!
+//! 测试开启按语言路由后，未固定供应商的请求按提示词语言改写到对应模型，
+//! 模型名称带供应商前缀或携带 `X-LLM-Provider` 请求头时保持调用方指定的供应商和模型
+
+mod common;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    middleware::from_fn,
+    response::Response,
+    routing::post,
+    Router,
+};
+use serde_json::{json, Value};
+use tower::Service;
+
+use project_rust_learn::llm_api::dispatcher::{DispatchConfig, LLMDispatcher, LocaleRoute, Provider, GLOBAL_DISPATCHER};
+use project_rust_learn::web::handlers::chat_completion_handler::create_chat_completion;
+use project_rust_learn::web::middleware::routing::routing_override;
+use common::MockAdapter;
+
+const ENGLISH_PROMPT: &str = "Please write a short poem about the spring and the rain.";
+
+/// 在回复中返回实际调用的供应商和模型
+fn echo_adapter(provider: Provider, model: &str) -> MockAdapter {
+    let name = provider.as_str().to_string();
+    MockAdapter::new(provider).with_models(&[model]).with_content(move |request| format!("{}/{}", name, request.model))
+}
+
+/// 未匹配语言的请求都路由到 Ollama 的 local-model
+async fn setup_dispatcher() {
+    if GLOBAL_DISPATCHER.get().is_some() {
+        return;
+    }
+    let config = DispatchConfig {
+        default_retry_count: 0,
+        enable_locale_routing: true,
+        locale_routes: HashMap::new(),
+        default_locale_route: Some(LocaleRoute::new(Provider::Ollama, "local-model".to_string())),
+        ..DispatchConfig::default()
+    };
+    let dispatcher = LLMDispatcher::new(Some(config));
+    dispatcher.register_client(Box::new(echo_adapter(Provider::Ollama, "local-model"))).await;
+    dispatcher.register_client(Box::new(echo_adapter(Provider::OpenAI, "gpt-4o"))).await;
+    GLOBAL_DISPATCHER.set(Arc::new(dispatcher)).ok();
+}
+
+async fn send(model: &str, headers: &[(&str, &str)]) -> (StatusCode, Value) {
+    let mut app = Router::new()
+        .route("/v1/chat/completions", post(create_chat_completion).route_layer(from_fn(routing_override)));
+    let mut request = Request::post("/v1/chat/completions").header("content-type", "application/json");
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let body = json!({"model": model, "messages": [{"role": "user", "content": ENGLISH_PROMPT}]}).to_string();
+    let response: Response = app.call(request.body(Body::from(body)).unwrap()).await.unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn test_locale_routing_respects_pinned_model() {
+    setup_dispatcher().await;
+
+    println!("=== Testing Unpinned Model ===");
+    let (status, body) = send("gpt-4o", &[]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["choices"][0]["message"]["content"], "ollama/local-model");
+    println!("✅ Request without a provider prefix is routed by language");
+
+    println!("=== Testing Provider Prefix ===");
+    let (status, body) = send("openai/gpt-4o", &[]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["choices"][0]["message"]["content"], "openai/gpt-4o");
+    println!("✅ Provider prefix keeps the requested provider and model");
+
+    println!("=== Testing X-LLM-Provider ===");
+    let (status, body) = send("gpt-4o", &[("X-LLM-Provider", "OpenAI")]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["choices"][0]["message"]["content"], "openai/gpt-4o");
+    println!("✅ Pinned provider header skips locale routing");
+}
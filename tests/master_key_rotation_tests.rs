@@ -0,0 +1,87 @@
+//! # 主密钥轮换测试
+//!
+//! 测试密文带主密钥 ID、rotate_master_key 用新主密钥重新加密旧密钥和内置密钥写入的 Key，
+//! 哈希不一致的 Key 不会被改写，以及重复执行轮换不做修改
+
+use base64::{engine::general_purpose, Engine as _};
+use sqlx::SqlitePool;
+
+use project_rust_learn::dao::provider_key_pool::{
+    create_provider_key_pool, generate_key_hash, get_provider_key_pool_by_id, rotate_master_key, set_master_keyring,
+    MasterKeyring, ProviderKeyPool, LEGACY_KEY_ID,
+};
+use project_rust_learn::dao::provider_key_pool::crypto::{decrypt_api_key, encrypt_api_key, encrypted_key_id};
+use project_rust_learn::dao::run_migrations;
+
+fn keyring(keys: &[(&str, u8)]) -> MasterKeyring {
+    let spec: Vec<String> = keys.iter()
+        .map(|(id, byte)| format!("{}:{}", id, general_purpose::STANDARD.encode([*byte; 32])))
+        .collect();
+    MasterKeyring::parse(&spec.join(",")).expect("keyring should parse")
+}
+
+fn key_pool(id: &str, raw_key: &str, encrypted_key_value: String) -> ProviderKeyPool {
+    ProviderKeyPool {
+        id: id.to_string(),
+        provider: "openai".to_string(),
+        key_hash: generate_key_hash(raw_key),
+        encrypted_key_value,
+        is_active: true,
+        usage_count: 0,
+        last_used_at: None,
+        rate_limit_per_minute: None,
+        rate_limit_per_hour: None,
+        project_id: None,
+        created_at: None,
+    }
+}
+
+async fn encrypted_value(pool: &SqlitePool, id: &str) -> String {
+    get_provider_key_pool_by_id(pool, id).await.unwrap().unwrap().encrypted_key_value
+}
+
+#[tokio::test]
+async fn test_rotate_master_key() {
+    let path = std::env::temp_dir().join(format!("rotation-test-{}.db", uuid::Uuid::new_v4().simple()));
+    let pool = SqlitePool::connect(&format!("sqlite://{}?mode=rwc", path.display())).await.unwrap();
+    run_migrations(&pool).await.expect("migration failed");
+
+    println!("=== Testing Master Key Rotation ===");
+    // 未配置主密钥时写入的旧密文没有 ID 前缀
+    set_master_keyring(MasterKeyring::legacy());
+    let legacy = encrypt_api_key("sk-legacy").unwrap();
+    assert_eq!(encrypted_key_id(&legacy), LEGACY_KEY_ID);
+    let unprefixed = legacy.split_once(':').unwrap().1.to_string();
+    create_provider_key_pool(&pool, &key_pool("legacy-key", "sk-legacy", unprefixed)).await.unwrap();
+
+    set_master_keyring(keyring(&[("k1", 1)]));
+    create_provider_key_pool(&pool, &key_pool("k1-key", "sk-k1", encrypt_api_key("sk-k1").unwrap())).await.unwrap();
+    // 密文与哈希不一致的 Key
+    create_provider_key_pool(&pool, &key_pool("bad-key", "sk-original", encrypt_api_key("sk-replaced").unwrap())).await.unwrap();
+
+    // 新密钥放在最前面，保留旧密钥用于解密
+    set_master_keyring(keyring(&[("k2", 2), ("k1", 1)]));
+    let report = rotate_master_key(&pool).await.expect("rotation failed");
+    assert_eq!(report.current_key_id, "k2");
+    assert_eq!((report.total_keys, report.rotated_keys, report.unchanged_keys), (3, 2, 0));
+    assert_eq!(report.failures.len(), 1);
+    assert_eq!(report.failures[0].key_pool_id, "bad-key");
+    assert_eq!(report.failures[0].key_id, "k1");
+    println!("✅ Rotated {} keys, {} failed", report.rotated_keys, report.failures.len());
+
+    assert_eq!(encrypted_key_id(&encrypted_value(&pool, "legacy-key").await), "k2");
+    assert_eq!(encrypted_key_id(&encrypted_value(&pool, "bad-key").await), "k1");
+
+    // 移除旧密钥后轮换过的 Key 仍能解密
+    set_master_keyring(keyring(&[("k2", 2)]));
+    assert_eq!(decrypt_api_key(&encrypted_value(&pool, "legacy-key").await).unwrap(), "sk-legacy");
+    assert_eq!(decrypt_api_key(&encrypted_value(&pool, "k1-key").await).unwrap(), "sk-k1");
+    println!("✅ Rotated keys decrypt with the new master key only");
+
+    let report = rotate_master_key(&pool).await.expect("second rotation failed");
+    assert_eq!((report.rotated_keys, report.unchanged_keys), (0, 2));
+    println!("✅ Re-running rotation leaves current keys untouched");
+
+    pool.close().await;
+    std::fs::remove_file(path).ok();
+}
@@ -0,0 +1,131 @@
+//! # 数据库迁移测试
+//!
+//! 测试内嵌迁移在新数据库和旧版 init.sql 创建的数据库上执行、重复执行不报错，
+//! 以及附加 SQL 脚本可以包含触发器
+
+use sqlx::{Executor, SqlitePool};
+
+use project_rust_learn::dao::{init_db, init_sqlite_pool, run_migrations, schema_version, MIGRATOR, SQLITE_POOL};
+
+// 临时目录中的新数据库
+async fn temp_pool() -> (SqlitePool, std::path::PathBuf) {
+    let path = std::env::temp_dir().join(format!("migration-test-{}.db", uuid::Uuid::new_v4().simple()));
+    let pool = SqlitePool::connect(&format!("sqlite://{}?mode=rwc", path.display())).await
+        .expect("Failed to create database");
+    (pool, path)
+}
+
+fn latest_version() -> i64 {
+    MIGRATOR.iter().map(|migration| migration.version).max().unwrap()
+}
+
+async fn table_exists(pool: &SqlitePool, name: &str) -> bool {
+    sqlx::query("SELECT name FROM sqlite_master WHERE type = 'table' AND name = ?")
+        .bind(name)
+        .fetch_optional(pool)
+        .await
+        .unwrap()
+        .is_some()
+}
+
+#[tokio::test]
+async fn test_migrate_fresh_database() {
+    let (pool, path) = temp_pool().await;
+
+    println!("=== Testing Fresh Database ===");
+    assert_eq!(schema_version(&pool).await.unwrap(), None);
+    run_migrations(&pool).await.expect("migration failed");
+    assert_eq!(schema_version(&pool).await.unwrap(), Some(latest_version()));
+    assert!(table_exists(&pool, "providers").await);
+    assert!(table_exists(&pool, "conversation_messages").await);
+    println!("✅ Migrated to version {}", latest_version());
+
+    // 重复执行时没有待执行的迁移
+    run_migrations(&pool).await.expect("second run failed");
+    let applied: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM _sqlx_migrations").fetch_one(&pool).await.unwrap();
+    assert_eq!(applied.0, MIGRATOR.iter().count() as i64);
+    println!("✅ Re-running migrations is a no-op");
+
+    pool.close().await;
+    std::fs::remove_file(path).ok();
+}
+
+#[tokio::test]
+async fn test_migrate_legacy_database() {
+    let (pool, path) = temp_pool().await;
+
+    println!("=== Testing Legacy Database ===");
+    // 由旧版 init.sql 创建、没有迁移记录的数据库
+    pool.execute(include_str!("fixtures/legacy/init.sql")).await.expect("legacy schema failed");
+    sqlx::query("INSERT INTO system_configs (id, category, key_name, value) VALUES ('legacy', 'test', 'key', 'value')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("INSERT INTO provider_key_pools (id, provider_, key_hash, encrypted_key_value) VALUES ('legacy-key', 'openai', 'hash', 'cipher')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("INSERT INTO call_logs (id, model_id, status_code, total_duration) VALUES ('legacy-log', NULL, 200, 10)")
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert_eq!(schema_version(&pool).await.unwrap(), None);
+
+    run_migrations(&pool).await.expect("migration over legacy schema failed");
+    assert_eq!(schema_version(&pool).await.unwrap(), Some(latest_version()));
+    let kept: (String,) = sqlx::query_as("SELECT value FROM system_configs WHERE id = 'legacy'").fetch_one(&pool).await.unwrap();
+    assert_eq!(kept.0, "value");
+
+    // 旧数据迁移到新增的列上
+    let key: (String, String) = sqlx::query_as("SELECT provider, project_id FROM provider_key_pools WHERE id = 'legacy-key'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(key, ("openai".to_string(), "default".to_string()));
+    let log: (i64, Option<String>, String) = sqlx::query_as("SELECT tokens_input, provider, project_id FROM call_logs WHERE id = 'legacy-log'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(log, (0, None, "default".to_string()));
+    assert!(table_exists(&pool, "projects").await);
+    assert!(table_exists(&pool, "conversation_messages").await);
+    println!("✅ Legacy database upgraded without losing data");
+
+    pool.close().await;
+    std::fs::remove_file(path).ok();
+}
+
+#[tokio::test]
+async fn test_init_db_runs_extra_script_with_trigger() {
+    let path = std::env::temp_dir().join(format!("migration-test-{}.db", uuid::Uuid::new_v4().simple()));
+    init_sqlite_pool(&format!("sqlite://{}?mode=rwc", path.display())).await;
+
+    println!("=== Testing Extra SQL Script ===");
+    // 触发器内部的分号不能被拆开执行
+    let script = std::env::temp_dir().join(format!("migration-test-{}.sql", uuid::Uuid::new_v4().simple()));
+    std::fs::write(&script, r#"
+CREATE TABLE IF NOT EXISTS provider_audit (name TEXT NOT NULL);
+CREATE TRIGGER IF NOT EXISTS trg_provider_audit AFTER INSERT ON providers
+BEGIN
+    INSERT INTO provider_audit (name) VALUES (NEW.name);
+    UPDATE provider_audit SET name = upper(name);
+END;
+"#).unwrap();
+    init_db(script.to_str().unwrap()).await.expect("init_db failed");
+
+    let pool = SQLITE_POOL.get().unwrap();
+    sqlx::query("INSERT INTO providers (id, name, display_name) VALUES ('p1', 'audit-test', 'Audit Test')")
+        .execute(&**pool)
+        .await
+        .unwrap();
+    let audited: (String,) = sqlx::query_as("SELECT name FROM provider_audit").fetch_one(&**pool).await.unwrap();
+    assert_eq!(audited.0, "AUDIT-TEST");
+    println!("✅ Trigger created and fired");
+
+    // 脚本不存在时只执行迁移
+    init_db("missing/extra.sql").await.expect("missing script should be skipped");
+    assert_eq!(schema_version(pool).await.unwrap(), Some(latest_version()));
+    println!("✅ Missing script skipped");
+
+    std::fs::remove_file(script).ok();
+}
@@ -0,0 +1,158 @@
+//! # 模拟供应商测试
+//!
+//! 测试 mock 供应商按模型名模拟内容过滤、工具调用、流式中断和无法解析的响应，
+//! 以及流式请求结束后记录终端用户的请求结果、流式响应经过响应黑名单
+
+use project_rust_learn::dao::blocklist::BlocklistEntry;
+use project_rust_learn::llm_api::dispatcher::{DispatchConfig, DispatchRequest, LLMDispatcher, LLMError, Provider};
+use project_rust_learn::llm_api::mock::adapter::MockScenario;
+use project_rust_learn::llm_api::provider_registry::{provider_registry, ProviderConfig};
+use project_rust_learn::llm_api::utils::abuse_guard::{get_abuse_guard, DEFAULT_TENANT};
+use project_rust_learn::llm_api::utils::blocklist::get_blocklist;
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+
+async fn mock_dispatcher(settings: Option<serde_json::Value>) -> (LLMDispatcher, Provider) {
+    let provider = Provider::from_name_or_custom("mock");
+    let config = ProviderConfig::new(provider.clone(), "mock", None).with_settings(settings);
+    let adapter = provider_registry().get("mock")
+        .expect("mock factory not registered")
+        .create_adapter(&config)
+        .expect("create_adapter failed");
+
+    let dispatcher = LLMDispatcher::new(Some(DispatchConfig {
+        enable_fallback: false,
+        default_retry_count: 0,
+        ..Default::default()
+    }));
+    dispatcher.register_client(adapter).await;
+    (dispatcher, provider)
+}
+
+fn request(provider: &Provider, scenario: MockScenario) -> DispatchRequest {
+    DispatchRequest::new(
+        provider.clone(),
+        scenario.model_name().to_string(),
+        vec![Message::user("one two three four".to_string())],
+    )
+}
+
+#[tokio::test]
+async fn test_mock_provider_scenarios() {
+    println!("=== Testing Mock Provider ===");
+    let (dispatcher, provider) = mock_dispatcher(None).await;
+
+    let response = dispatcher.dispatch(request(&provider, MockScenario::Echo)).await.expect("echo failed");
+    assert_eq!(response.content, "one two three four");
+    assert_eq!(response.finish_reason.as_deref(), Some("stop"));
+
+    let response = dispatcher.dispatch(request(&provider, MockScenario::ContentFilter)).await.expect("content filter failed");
+    assert_eq!(response.finish_reason.as_deref(), Some("content_filter"));
+    assert!(response.content.is_empty());
+
+    let response = dispatcher.dispatch(request(&provider, MockScenario::ToolCall)).await.expect("tool call failed");
+    assert_eq!(response.finish_reason.as_deref(), Some("tool_calls"));
+    println!("✅ Non-stream scenarios returned simulated finish reasons");
+
+    let result = dispatcher.dispatch(request(&provider, MockScenario::MalformedJson)).await;
+    assert!(matches!(result, Err(LLMError::ApiError(ref msg)) if msg.contains("parse")));
+    let result = dispatcher.dispatch(request(&provider, MockScenario::PartialStream)).await;
+    assert!(matches!(result, Err(LLMError::Network(_))));
+    println!("✅ Malformed JSON and dropped connections surfaced as errors");
+}
+
+#[tokio::test]
+async fn test_mock_provider_partial_stream() {
+    let (dispatcher, provider) = mock_dispatcher(Some(serde_json::json!({"partial_chunks": 3}))).await;
+
+    let mut receiver = dispatcher.dispatch_stream(request(&provider, MockScenario::PartialStream)).await.expect("stream failed");
+    let mut content = String::new();
+    let mut error = None;
+    while let Some(item) = receiver.recv().await {
+        match item {
+            Ok(chunk) => content.push_str(&chunk.content),
+            Err(e) => error = Some(e),
+        }
+    }
+    assert_eq!(content, "one two three ");
+    assert!(matches!(error, Some(LLMError::Network(_))));
+    println!("✅ Partial stream ended with an error after configured chunks");
+
+    let mut receiver = dispatcher.dispatch_stream(request(&provider, MockScenario::Echo)).await.expect("stream failed");
+    let mut finish_reason = None;
+    let mut content = String::new();
+    while let Some(Ok(chunk)) = receiver.recv().await {
+        content.push_str(&chunk.content);
+        finish_reason = chunk.finish_reason.or(finish_reason);
+    }
+    assert_eq!(content, "one two three four");
+    assert_eq!(finish_reason.as_deref(), Some("stop"));
+    println!("✅ Echo stream completed");
+}
+
+#[tokio::test]
+async fn test_stream_outcome_recorded_for_end_user() {
+    let (dispatcher, provider) = mock_dispatcher(None).await;
+    let user = format!("stream-user-{}", uuid::Uuid::new_v4().simple());
+
+    println!("=== Testing Stream Outcome Recorded For End User ===");
+    // 默认配置下窗口内 3 次内容违规标记为异常用户
+    for _ in 0..3 {
+        assert!(!get_abuse_guard().is_flagged(DEFAULT_TENANT, &user).await);
+        let request = request(&provider, MockScenario::ContentFilter).with_user(user.clone());
+        let mut receiver = dispatcher.dispatch_stream(request).await.expect("stream failed");
+        while receiver.recv().await.is_some() {}
+    }
+    assert!(get_abuse_guard().is_flagged(DEFAULT_TENANT, &user).await);
+    println!("✅ Content filtered streams counted as violations");
+}
+
+#[tokio::test]
+async fn test_stream_response_blocklist() {
+    let (dispatcher, provider) = mock_dispatcher(None).await;
+    let mask_tenant = format!("mask-{}", uuid::Uuid::new_v4().simple());
+    let block_tenant = format!("block-{}", uuid::Uuid::new_v4().simple());
+    get_blocklist().load_entries(&[
+        blocklist_entry(&mask_tenant, "three", "mask"),
+        blocklist_entry(&block_tenant, "three", "block"),
+    ]).await;
+
+    println!("=== Testing Stream Response Blocklist ===");
+    let masked = request(&provider, MockScenario::Echo).with_tenant_id(mask_tenant);
+    let mut receiver = dispatcher.dispatch_stream(masked).await.expect("stream failed");
+    let mut content = String::new();
+    let mut finish_reason = None;
+    while let Some(Ok(chunk)) = receiver.recv().await {
+        content.push_str(&chunk.content);
+        finish_reason = chunk.finish_reason.or(finish_reason);
+    }
+    assert_eq!(content, "one two ***** four");
+    assert_eq!(finish_reason.as_deref(), Some("stop"));
+    println!("✅ Streamed response masked");
+
+    let blocked = request(&provider, MockScenario::Echo).with_tenant_id(block_tenant);
+    let mut receiver = dispatcher.dispatch_stream(blocked).await.expect("stream failed");
+    let mut content = String::new();
+    let mut error = None;
+    while let Some(item) = receiver.recv().await {
+        match item {
+            Ok(chunk) => content.push_str(&chunk.content),
+            Err(e) => error = Some(e),
+        }
+    }
+    assert!(!content.contains("th"), "blocked content leaked: {}", content);
+    assert!(matches!(error, Some(LLMError::ContentBlocked(_))));
+    println!("✅ Streamed response blocked before the match is sent");
+}
+
+fn blocklist_entry(tenant_id: &str, pattern: &str, action: &str) -> BlocklistEntry {
+    BlocklistEntry {
+        id: uuid::Uuid::new_v4().to_string(),
+        tenant_id: Some(tenant_id.to_string()),
+        pattern: pattern.to_string(),
+        action: action.to_string(),
+        scope: "response".to_string(),
+        is_active: true,
+        created_at: None,
+        updated_at: None,
+    }
+}
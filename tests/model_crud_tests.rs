@@ -32,6 +32,7 @@ async fn test_model_crud_operations() {
         cost_per_token_output: None,
         function_tags: None,
         config: None,
+        project_id: None,
         created_at: None,
         updated_at: None,
     };
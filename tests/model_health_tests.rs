@@ -0,0 +1,115 @@
+//! # 模型健康检查测试
+//!
+//! 测试探测成功时标记为 healthy，连续失败达到阈值后标记为 unhealthy
+
+use mockito::Server;
+use serde_json::json;
+use sqlx::{Pool, Sqlite};
+
+use project_rust_learn::dao::{init_sqlite_pool, init_db, SQLITE_POOL};
+use project_rust_learn::dao::model::{create_model, delete_model, get_model_by_id, Model, HEALTH_HEALTHY, HEALTH_UNHEALTHY};
+use project_rust_learn::jobs::model_health_check::ModelHealthTracker;
+
+/// 初始化测试环境的辅助函数
+async fn setup_test_env() -> std::sync::Arc<Pool<Sqlite>> {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+    init_db("data/init.sql").await.expect("DB init failed");
+    pool
+}
+
+fn ollama_model(name: &str, base_url: String) -> Model {
+    Model {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        provider: "ollama".to_string(),
+        model_type: "llm".to_string(),
+        base_url: Some(base_url),
+        is_active: true,
+        health_status: None,
+        last_health_check: None,
+        health_check_interval_seconds: Some(60),
+        cost_per_token_input: None,
+        cost_per_token_output: None,
+        function_tags: None,
+        config: None,
+        project_id: None,
+        created_at: None,
+        updated_at: None,
+    }
+}
+
+#[tokio::test]
+async fn test_model_health_check_marks_status() {
+    let pool = setup_test_env().await;
+
+    println!("=== Testing Model Health Check ===");
+    let name = format!("health-test-{}", uuid::Uuid::new_v4().simple());
+    let mut server = Server::new_async().await;
+    let _tags = server.mock("GET", "/api/tags")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!({"models": [{"name": format!("{}:latest", name)}]}).to_string())
+        .create_async()
+        .await;
+
+    let mut model = ollama_model(&name, server.url());
+    create_model(&pool, &model).await.expect("create_model failed");
+
+    let mut tracker = ModelHealthTracker::new(2);
+    let status = tracker.check_model(&pool, &model).await.expect("check_model failed");
+    assert_eq!(status.as_deref(), Some(HEALTH_HEALTHY));
+    let stored = get_model_by_id(&pool, &model.id).await.unwrap().unwrap();
+    assert_eq!(stored.health_status.as_deref(), Some(HEALTH_HEALTHY));
+    assert!(stored.last_health_check.is_some());
+    println!("✅ Reachable model marked healthy");
+
+    // 上游不可用：第一次失败保持原状态，连续失败达到阈值后标记为 unhealthy
+    let mut down = Server::new_async().await;
+    let _down = down.mock("GET", "/api/tags").with_status(503).create_async().await;
+    model.base_url = Some(down.url());
+    model.health_status = stored.health_status.clone();
+
+    let status = tracker.check_model(&pool, &model).await.expect("check_model failed");
+    assert_eq!(status.as_deref(), Some(HEALTH_HEALTHY));
+    assert_eq!(tracker.consecutive_failures(&model.id), 1);
+
+    let status = tracker.check_model(&pool, &model).await.expect("check_model failed");
+    assert_eq!(status.as_deref(), Some(HEALTH_UNHEALTHY));
+    let stored = get_model_by_id(&pool, &model.id).await.unwrap().unwrap();
+    assert_eq!(stored.health_status.as_deref(), Some(HEALTH_UNHEALTHY));
+    println!("✅ Model marked unhealthy after consecutive failures");
+
+    // 恢复后重新标记为 healthy 并清零失败次数
+    model.base_url = Some(server.url());
+    model.health_status = stored.health_status;
+    let status = tracker.check_model(&pool, &model).await.expect("check_model failed");
+    assert_eq!(status.as_deref(), Some(HEALTH_HEALTHY));
+    assert_eq!(tracker.consecutive_failures(&model.id), 0);
+    println!("✅ Model recovered");
+
+    delete_model(&pool, &model.id).await.expect("delete_model failed");
+}
+
+#[tokio::test]
+async fn test_model_health_check_requires_pulled_model() {
+    let pool = setup_test_env().await;
+
+    let mut server = Server::new_async().await;
+    let _tags = server.mock("GET", "/api/tags")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!({"models": [{"name": "other:latest"}]}).to_string())
+        .create_async()
+        .await;
+
+    let model = ollama_model(&format!("health-missing-{}", uuid::Uuid::new_v4().simple()), server.url());
+    create_model(&pool, &model).await.expect("create_model failed");
+
+    let mut tracker = ModelHealthTracker::new(1);
+    let status = tracker.check_model(&pool, &model).await.expect("check_model failed");
+    assert_eq!(status.as_deref(), Some(HEALTH_UNHEALTHY));
+    println!("✅ Model missing from Ollama tags marked unhealthy");
+
+    delete_model(&pool, &model.id).await.expect("delete_model failed");
+}
@@ -0,0 +1,105 @@
+//! # 模型解析测试
+//!
+//! 测试 dispatcher 使用数据库中启用的模型判断模型是否可用，数据库中没有该供应商的模型时回退到适配器的内置列表
+
+mod common;
+
+use project_rust_learn::dao::{init_sqlite_pool, init_db, SQLITE_POOL};
+use project_rust_learn::dao::cache::init_global_cache;
+use project_rust_learn::dao::model::{Model, create_model, delete_model, invalidate_provider_models_cache};
+use project_rust_learn::llm_api::dispatcher::{DispatchConfig, DispatchRequest, LLMDispatcher, LLMError, Provider};
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+use common::MockAdapter;
+
+/// 初始化测试环境的辅助函数
+async fn setup_test_env() {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+    init_db("data/init.sql").await.expect("DB init failed");
+    init_global_cache(&pool, 3600, 1000).await.expect("Cache init failed");
+}
+
+fn model(provider: &str, name: &str, is_active: bool) -> Model {
+    Model {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        provider: provider.to_string(),
+        model_type: "llm".to_string(),
+        base_url: None,
+        is_active,
+        health_status: None,
+        last_health_check: None,
+        health_check_interval_seconds: None,
+        cost_per_token_input: None,
+        cost_per_token_output: None,
+        function_tags: None,
+        config: None,
+        project_id: None,
+        created_at: None,
+        updated_at: None,
+    }
+}
+
+#[tokio::test]
+async fn test_models_resolved_from_database() {
+    setup_test_env().await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+
+    println!("=== Testing Model Resolution From Database ===");
+
+    // 使用唯一的自定义供应商，避免影响其他测试
+    let provider_name = format!("dbtest-{}", uuid::Uuid::new_v4().simple());
+    let provider = Provider::Custom(provider_name.clone());
+    let dispatcher = LLMDispatcher::new(Some(DispatchConfig {
+        enable_fallback: false,
+        ..Default::default()
+    }));
+    dispatcher.register_client(Box::new(
+        MockAdapter::new(provider.clone())
+            .with_models(&["static-model"])
+            .with_content(|request| request.model.clone()),
+    )).await;
+
+    // 数据库中没有该供应商的模型时使用内置列表
+    let models = dispatcher.list_models(Some(provider.clone())).await;
+    assert_eq!(models[&provider], vec!["static-model"]);
+    println!("✅ Falls back to static model list when database is empty");
+
+    let active = model(&provider_name, "db-model", true);
+    let inactive = model(&provider_name, "disabled-model", false);
+    create_model(&pool, &active).await.expect("create_model failed");
+    create_model(&pool, &inactive).await.expect("create_model failed");
+    invalidate_provider_models_cache(&provider_name).await;
+
+    // 数据库中新增的启用模型可以直接使用，停用的模型被拒绝
+    let models = dispatcher.list_models(Some(provider.clone())).await;
+    assert_eq!(models[&provider], vec!["db-model"]);
+    assert_eq!(dispatcher.resolve_model("db-model").await, Some((provider.clone(), "db-model".to_string())));
+
+    let request = DispatchRequest::new(provider.clone(), "db-model".to_string(), vec![Message::user("hello".to_string())]);
+    let response = dispatcher.dispatch(request).await.expect("dispatch failed");
+    assert_eq!(response.content, "db-model");
+
+    let request = DispatchRequest::new(provider.clone(), "disabled-model".to_string(), vec![Message::user("hello".to_string())]);
+    let result = dispatcher.dispatch(request).await;
+    assert!(matches!(result, Err(LLMError::ModelNotAvailable(_))));
+    println!("✅ Active database models are used for dispatch");
+
+    // 只剩停用的模型时不回退到内置列表
+    delete_model(&pool, &active.id).await.expect("delete_model failed");
+    invalidate_provider_models_cache(&provider_name).await;
+    let models = dispatcher.list_models(Some(provider.clone())).await;
+    assert!(models[&provider].is_empty());
+    assert_eq!(dispatcher.resolve_model("static-model").await, None);
+    let request = DispatchRequest::new(provider.clone(), "static-model".to_string(), vec![Message::user("hello".to_string())]);
+    let result = dispatcher.dispatch(request).await;
+    assert!(matches!(result, Err(LLMError::ModelNotAvailable(_))));
+    println!("✅ All models disabled does not fall back to static model list");
+
+    delete_model(&pool, &inactive.id).await.expect("delete_model failed");
+    invalidate_provider_models_cache(&provider_name).await;
+
+    let models = dispatcher.list_models(Some(provider.clone())).await;
+    assert_eq!(models[&provider], vec!["static-model"]);
+    println!("✅ Static model list restored after database models are removed");
+}
@@ -4,13 +4,13 @@
 //! - 客户端创建和配置
 //! - 聊天请求和响应处理
 //! - 流式聊天处理
-//! - 模型管理（列表、可用性检查）
+//! - 模型管理（列表、可用性检查、拉取、删除、查看详情）
 //! - 错误处理和边界情况
 //! - 工具调用支持
 //! - 请求验证和格式化
 
 use project_rust_learn::llm_api::ollama::client::{
-    OllamaClient, OllamaChatRequest, OllamaChatResponse, OllamaError
+    OllamaClient, OllamaChatRequest, OllamaChatResponse, OllamaError, OllamaPullProgress
 };
     use project_rust_learn::llm_api::utils::{
     client::{ClientConfig, TimeoutConfig, RetryConfig, LLMClientTrait},
@@ -526,6 +526,54 @@ mod tests {
         mock.assert_async().await;
     }
 
+    #[tokio::test]
+    async fn test_ollama_adapter_generate_stream() {
+        use project_rust_learn::llm_api::dispatcher::{DispatchRequest, LLMClientAdapter, OllamaAdapter, Provider};
+
+        setup_database().await;
+
+        let mut server = Server::new_async().await;
+        let stream_body = [
+            json!({"model": "llama2", "created_at": "2025-09-09T10:00:00Z", "message": {"role": "assistant", "content": "Hello"}, "done": false}),
+            json!({"model": "llama2", "created_at": "2025-09-09T10:00:00Z", "message": {"role": "assistant", "content": " there!"}, "done": false}),
+            json!({"model": "llama2", "created_at": "2025-09-09T10:00:00Z", "message": {"role": "assistant", "content": ""}, "done": true, "prompt_eval_count": 10, "eval_count": 15}),
+        ]
+        .iter()
+        .map(|resp| resp.to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+        let mock = server.mock("POST", "/api/chat")
+            .with_status(200)
+            .with_header("content-type", "application/x-ndjson")
+            .with_body(&stream_body)
+            .create_async()
+            .await;
+
+        let adapter = OllamaAdapter::new(OllamaClient::new(server.url()).unwrap());
+        let request = DispatchRequest::new(Provider::Ollama, "llama2".to_string(), create_test_messages())
+            .with_stream(true);
+
+        let mut receiver = adapter.generate_stream(&request).await.unwrap();
+        let mut chunks = Vec::new();
+        while let Some(chunk) = receiver.recv().await {
+            chunks.push(chunk.expect("stream chunk failed"));
+        }
+
+        let content: String = chunks.iter().map(|c| c.content.as_str()).collect();
+        assert_eq!(content, "Hello there!");
+
+        // 最后一块携带结束原因和token统计
+        let last = chunks.last().unwrap();
+        assert_eq!(last.finish_reason.as_deref(), Some("stop"));
+        let usage = last.usage.as_ref().unwrap();
+        assert_eq!(usage.prompt_tokens, 10);
+        assert_eq!(usage.completion_tokens, 15);
+        assert_eq!(usage.total_tokens, 25);
+
+        mock.assert_async().await;
+    }
+
     #[tokio::test]
     async fn test_ollama_chat_api_error() {
         setup_database().await;
@@ -822,4 +870,130 @@ mod tests {
         mock1.assert_async().await;
         mock2.assert_async().await;
     }
+
+    // ========== 模型管理测试 ==========
+
+    #[tokio::test]
+    async fn test_ollama_pull_model_progress() {
+        let mut server = Server::new_async().await;
+        let progress = [
+            json!({"status": "pulling manifest"}),
+            json!({"status": "downloading", "digest": "sha256:abc", "total": 100, "completed": 40}),
+            json!({"status": "downloading", "digest": "sha256:abc", "total": 100, "completed": 100}),
+            json!({"status": "success"}),
+        ];
+        let body: String = progress.iter().map(|line| format!("{}\n", line)).collect();
+        let mock = server.mock("POST", "/api/pull")
+            .match_body(mockito::Matcher::PartialJson(json!({"model": "qwen2.5:7b", "stream": true})))
+            .with_status(200)
+            .with_header("content-type", "application/x-ndjson")
+            .with_body(body)
+            .create_async()
+            .await;
+
+        let client = OllamaClient::new(server.url()).unwrap();
+        let mut received: Vec<OllamaPullProgress> = Vec::new();
+        client.pull_model("qwen2.5:7b", |p| {
+            received.push(p);
+            true
+        }).await.expect("pull failed");
+
+        assert_eq!(received.len(), 4);
+        assert_eq!(received[1].completed, Some(40));
+        assert_eq!(received[1].digest.as_deref(), Some("sha256:abc"));
+        assert!(received.last().unwrap().is_success());
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_ollama_pull_model_error() {
+        let mut server = Server::new_async().await;
+        let body = format!("{}\n{}\n", json!({"status": "pulling manifest"}), json!({"error": "pull model manifest: file does not exist"}));
+        server.mock("POST", "/api/pull")
+            .with_status(200)
+            .with_body(body)
+            .create_async()
+            .await;
+
+        let client = OllamaClient::new(server.url()).unwrap();
+        let mut count = 0;
+        let result = client.pull_model("missing-model", |_| {
+            count += 1;
+            true
+        }).await;
+        assert_eq!(count, 1);
+        match result {
+            Err(OllamaError::Api(message)) => assert!(message.contains("file does not exist")),
+            other => panic!("Expected Api error, got: {:?}", other),
+        }
+
+        let result = client.pull_model("  ", |_| true).await;
+        assert!(matches!(result, Err(OllamaError::InvalidRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn test_ollama_delete_model() {
+        let mut server = Server::new_async().await;
+        let deleted = server.mock("DELETE", "/api/delete")
+            .match_body(mockito::Matcher::PartialJson(json!({"model": "llama2"})))
+            .with_status(200)
+            .create_async()
+            .await;
+        server.mock("DELETE", "/api/delete")
+            .match_body(mockito::Matcher::PartialJson(json!({"model": "nonexistent"})))
+            .with_status(404)
+            .with_body(json!({"error": "model 'nonexistent' not found"}).to_string())
+            .create_async()
+            .await;
+
+        let client = OllamaClient::new(server.url()).unwrap();
+        client.delete_model("llama2").await.expect("delete failed");
+        deleted.assert_async().await;
+
+        match client.delete_model("nonexistent").await {
+            Err(OllamaError::ModelNotFound(model)) => assert_eq!(model, "nonexistent"),
+            other => panic!("Expected ModelNotFound, got: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ollama_show_model() {
+        let mut server = Server::new_async().await;
+        server.mock("POST", "/api/show")
+            .match_body(mockito::Matcher::PartialJson(json!({"model": "llama2"})))
+            .with_status(200)
+            .with_body(json!({
+                "modelfile": "FROM llama2",
+                "parameters": "stop \"[INST]\"",
+                "template": "[INST] {{ .Prompt }} [/INST]",
+                "details": {
+                    "format": "gguf",
+                    "family": "llama",
+                    "families": ["llama"],
+                    "parameter_size": "7B",
+                    "quantization_level": "Q4_0"
+                },
+                "model_info": {"llama.context_length": 4096}
+            }).to_string())
+            .create_async()
+            .await;
+        server.mock("POST", "/api/show")
+            .match_body(mockito::Matcher::PartialJson(json!({"model": "broken"})))
+            .with_status(500)
+            .with_body(json!({"error": "failed to load model"}).to_string())
+            .create_async()
+            .await;
+
+        let client = OllamaClient::new(server.url()).unwrap();
+        let info = client.show_model("llama2").await.expect("show failed");
+        let details = info.details.unwrap();
+        assert_eq!(details.parameter_size.as_deref(), Some("7B"));
+        assert_eq!(details.quantization_level.as_deref(), Some("Q4_0"));
+        assert_eq!(info.model_info.unwrap()["llama.context_length"], 4096);
+
+        match client.show_model("broken").await {
+            Err(OllamaError::Api(message)) => assert!(message.contains("failed to load model")),
+            other => panic!("Expected Api error, got: {:?}", other),
+        }
+    }
 }
@@ -0,0 +1,133 @@
+//! # OpenAI 客户端测试集
+//!
+//! 使用 mockito 模拟 OpenAI Chat Completions 接口，测试：
+//! - 非流式聊天请求和响应解析
+//! - 错误响应处理
+//! - 流式聊天处理（包括 usage 块）
+
+use mockito::{Matcher, Server};
+use serde_json::json;
+
+use project_rust_learn::llm_api::openai::client::{OpenAIChatRequest, OpenAIClient, OpenAIError};
+use project_rust_learn::llm_api::utils::{
+    chat_traits::ChatResponseTrait,
+    msg_structure::Message,
+};
+
+fn create_client(base_url: String) -> OpenAIClient {
+    OpenAIClient::new_with_base_url("sk-test".to_string(), base_url).unwrap()
+}
+
+fn create_request() -> OpenAIChatRequest {
+    OpenAIChatRequest::new("gpt-4o-mini".to_string(), vec![
+        Message::system("You are a helpful assistant.".to_string()),
+        Message::user("Hello!".to_string()),
+    ])
+}
+
+#[tokio::test]
+async fn test_openai_chat_success() {
+    let mut server = Server::new_async().await;
+    let mock = server.mock("POST", "/chat/completions")
+        .match_header("authorization", "Bearer sk-test")
+        .match_body(Matcher::PartialJson(json!({"model": "gpt-4o-mini", "stream": false})))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!({
+            "id": "chatcmpl-123",
+            "object": "chat.completion",
+            "created": 1757412000,
+            "model": "gpt-4o-mini",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "Hi there!"},
+                "finish_reason": "stop"
+            }],
+            "usage": {"prompt_tokens": 9, "completion_tokens": 3, "total_tokens": 12}
+        }).to_string())
+        .create_async()
+        .await;
+
+    let client = create_client(server.url());
+    let response = client.chat(create_request()).await.expect("chat failed");
+
+    assert_eq!(response.get_content().as_deref(), Some("Hi there!"));
+    assert_eq!(response.choices[0].finish_reason.as_deref(), Some("stop"));
+    assert_eq!(response.get_prompt_eval_count(), Some(9));
+    assert_eq!(response.get_eval_count(), Some(3));
+
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_openai_chat_api_error() {
+    let mut server = Server::new_async().await;
+    let mock = server.mock("POST", "/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!({
+            "error": {
+                "message": "Incorrect API key provided",
+                "type": "invalid_request_error",
+                "code": "invalid_api_key"
+            }
+        }).to_string())
+        .create_async()
+        .await;
+
+    let client = create_client(server.url());
+    let result = client.chat(create_request()).await;
+    assert!(matches!(result, Err(OpenAIError::Auth(ref msg)) if msg.contains("Incorrect API key")));
+
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_openai_chat_stream_success() {
+    let mut server = Server::new_async().await;
+    let chunk = |content: Option<&str>, finish_reason: Option<&str>| json!({
+        "id": "chatcmpl-123",
+        "object": "chat.completion.chunk",
+        "created": 1757412000,
+        "model": "gpt-4o-mini",
+        "choices": [{"index": 0, "delta": {"content": content}, "finish_reason": finish_reason}]
+    });
+    let usage = json!({
+        "id": "chatcmpl-123",
+        "object": "chat.completion.chunk",
+        "created": 1757412000,
+        "model": "gpt-4o-mini",
+        "choices": [],
+        "usage": {"prompt_tokens": 9, "completion_tokens": 2, "total_tokens": 11}
+    });
+    let body = [chunk(Some("Hi"), None), chunk(Some(" there"), None), chunk(None, Some("stop")), usage]
+        .iter()
+        .map(|c| format!("data: {}\n\n", c))
+        .collect::<String>()
+        + "data: [DONE]\n\n";
+
+    let mock = server.mock("POST", "/chat/completions")
+        .match_body(Matcher::PartialJson(json!({"stream": true, "stream_options": {"include_usage": true}})))
+        .with_status(200)
+        .with_header("content-type", "text/event-stream")
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let client = create_client(server.url());
+    let mut responses = Vec::new();
+    client.chat_stream(create_request().with_stream_usage(true), |response| {
+        responses.push(response);
+        true
+    }).await.expect("chat_stream failed");
+
+    assert_eq!(responses.len(), 4);
+    let content: String = responses.iter()
+        .flat_map(|r| r.choices.iter())
+        .filter_map(|c| c.delta.content.clone())
+        .collect();
+    assert_eq!(content, "Hi there");
+    assert_eq!(responses.last().unwrap().usage.as_ref().unwrap().total_tokens, 11);
+
+    mock.assert_async().await;
+}
@@ -0,0 +1,87 @@
+//! # 模型参数约束测试
+//!
+//! 测试 `models.config` 中的 `param_constraints` 在调度时把超出范围的 max_tokens、temperature 和停止词数量
+//! 调整到允许的范围内，并在响应的 `warnings` 中说明；范围内的请求不调整
+
+mod common;
+
+use project_rust_learn::dao::cache::init_global_cache;
+use project_rust_learn::dao::model::{create_model, delete_model, sync_model_cache, Model};
+use project_rust_learn::dao::{init_db, init_sqlite_pool, SQLITE_POOL};
+use project_rust_learn::llm_api::dispatcher::{DispatchConfig, DispatchRequest, LLMDispatcher, Provider};
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+use common::MockAdapter;
+
+const MODEL: &str = "constrained-model";
+
+fn chat(provider: &Provider, max_tokens: u32, temperature: f32, stop: &[&str]) -> DispatchRequest {
+    let mut request = DispatchRequest::new(provider.clone(), MODEL.to_string(), vec![Message::user("hi".to_string())]);
+    request.max_tokens = Some(max_tokens);
+    request.temperature = Some(temperature);
+    request.stop = Some(stop.iter().map(|s| s.to_string()).collect());
+    request
+}
+
+#[tokio::test]
+async fn test_params_clamped_to_model_constraints() {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    init_db("data/init.sql").await.expect("DB init failed");
+    let pool = SQLITE_POOL.get().unwrap();
+    init_global_cache(pool, 3600, 1000).await.expect("Cache init failed");
+
+    // 使用唯一的自定义供应商，避免影响其他测试
+    let provider_name = format!("constraints-{}", &uuid::Uuid::new_v4().to_string()[..8]);
+    let provider = Provider::Custom(provider_name.clone());
+    let model = Model {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: MODEL.to_string(),
+        provider: provider_name.clone(),
+        model_type: "llm".to_string(),
+        base_url: None,
+        is_active: true,
+        health_status: None,
+        last_health_check: None,
+        health_check_interval_seconds: None,
+        cost_per_token_input: None,
+        cost_per_token_output: None,
+        function_tags: None,
+        config: Some(r#"{"param_constraints": {"max_tokens": 1024, "min_temperature": 0.1, "max_temperature": 1.0, "max_stop": 2}}"#.to_string()),
+        project_id: None,
+        created_at: None,
+        updated_at: None,
+    };
+    create_model(pool, &model).await.expect("create model failed");
+    sync_model_cache(pool, &model.provider, &model.name).await;
+
+    let adapter = MockAdapter::new(provider.clone()).with_models(&[MODEL]);
+    let sent = adapter.requests();
+    let dispatcher = LLMDispatcher::new(Some(DispatchConfig { enable_fallback: false, ..Default::default() }));
+    dispatcher.register_client(Box::new(adapter)).await;
+
+    println!("=== Testing Parameter Clamping ===");
+    let response = dispatcher.dispatch(chat(&provider, 4096, 1.8, &["a", "b", "c"])).await.expect("dispatch failed");
+    let upstream = sent.lock().unwrap().pop().unwrap();
+    assert_eq!(upstream.max_tokens, Some(1024));
+    assert_eq!(upstream.temperature, Some(1.0));
+    assert_eq!(upstream.stop, Some(vec!["a".to_string(), "b".to_string()]));
+    let warnings = response.warnings.expect("missing warnings");
+    assert_eq!(warnings.len(), 3, "{:?}", warnings);
+    assert!(warnings[0].contains("max_tokens 4096"), "{:?}", warnings);
+    println!("✅ Out-of-range parameters clamped with warnings: {:?}", warnings);
+
+    let response = dispatcher.dispatch(chat(&provider, 256, 0.0, &["a"])).await.expect("dispatch failed");
+    let upstream = sent.lock().unwrap().pop().unwrap();
+    assert_eq!(upstream.temperature, Some(0.1));
+    assert_eq!(response.warnings.map(|warnings| warnings.len()), Some(1));
+
+    let response = dispatcher.dispatch(chat(&provider, 256, 0.5, &["a"])).await.expect("dispatch failed");
+    let upstream = sent.lock().unwrap().pop().unwrap();
+    assert_eq!((upstream.max_tokens, upstream.temperature), (Some(256), Some(0.5)));
+    assert!(response.warnings.is_none());
+    let serialized = serde_json::to_value(&response).unwrap();
+    assert!(serialized.get("warnings").is_none());
+    println!("✅ In-range parameters left untouched");
+
+    delete_model(pool, &model.id).await.expect("delete model failed");
+    sync_model_cache(pool, &model.provider, &model.name).await;
+}
@@ -1,3 +1,5 @@
+mod common;
+
 use project_rust_learn::dao::{init_sqlite_pool, init_db, SQLITE_POOL};
 use project_rust_learn::dao::provider_key_pool::{
     create_provider_key_pool_from_raw_key, 
@@ -9,6 +11,7 @@ use sqlx::{Pool, Sqlite};
 
 /// 初始化测试环境的辅助函数
 async fn setup_test_env() -> Arc<Pool<Sqlite>> {
+    common::install_test_master_keyring();
     init_sqlite_pool("sqlite://data/app.db").await;
     let pool = SQLITE_POOL.get().unwrap().clone();
     init_db("data/init.sql").await.expect("DB init failed");
@@ -0,0 +1,189 @@
+//! # 项目隔离测试
+//!
+//! 测试网关 Key 绑定项目后请求只使用项目内的 API Key：绑定的 Key 解析到对应项目，
+//! 未绑定的 Key 属于 default 项目（要求绑定项目时返回 401），项目停用后返回 403；调用记录按项目归属并可按项目过滤
+
+mod common;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+    middleware::from_fn,
+    response::Response,
+    routing::post,
+    Router,
+};
+use serde_json::Value;
+use tower::Service;
+
+use project_rust_learn::dao::cache::init_global_cache;
+use project_rust_learn::dao::call_log::{create_call_log, count_call_logs_by_search, CallLog, CallLogSearch};
+use project_rust_learn::dao::project::{bind_gateway_key, create_project, delete_project, get_project_by_id, update_project, Project, DEFAULT_PROJECT};
+use project_rust_learn::dao::provider_key_pool::{
+    create_provider_key_pool, get_project_api_key_round_robin, has_available_project_key, process_api_key,
+    sync_provider_key_pool_cache, ProviderKeyPool,
+};
+use project_rust_learn::dao::{init_db, init_sqlite_pool, SQLITE_POOL};
+use project_rust_learn::llm_api::utils::client::CallMetadata;
+use project_rust_learn::llm_api::utils::consumer_quota::consumer_id;
+use project_rust_learn::llm_api::utils::project_scope::{reload_project_bindings, set_require_bound_key};
+use project_rust_learn::web::middleware::project::project_scope;
+
+/// 返回请求所属的项目
+async fn current_project() -> String {
+    CallMetadata::current().project_id().to_string()
+}
+
+fn app() -> Router {
+    Router::new().route("/v1/chat/completions", post(current_project).route_layer(from_fn(project_scope)))
+}
+
+async fn send(app: &mut Router, api_key: Option<&str>) -> Response {
+    let mut request = Request::post("/v1/chat/completions");
+    if let Some(api_key) = api_key {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {}", api_key));
+    }
+    app.call(request.body(Body::empty()).unwrap()).await.unwrap()
+}
+
+async fn body_text(response: Response) -> String {
+    String::from_utf8(to_bytes(response.into_body(), usize::MAX).await.unwrap().to_vec()).unwrap()
+}
+
+async fn setup() {
+    common::install_test_master_keyring();
+    init_sqlite_pool("sqlite://data/app.db").await;
+    init_db("data/init.sql").await.expect("DB init failed");
+    init_global_cache(SQLITE_POOL.get().unwrap(), 3600, 1000).await.expect("Cache init failed");
+}
+
+async fn create_test_project() -> Project {
+    let pool = SQLITE_POOL.get().unwrap();
+    let project = Project {
+        id: format!("proj-{}", &uuid::Uuid::new_v4().to_string()[..8]),
+        name: "Test Project".to_string(),
+        description: None,
+        is_active: true,
+        created_at: None,
+        updated_at: None,
+    };
+    create_project(pool, &project).await.expect("create project failed");
+    get_project_by_id(pool, &project.id).await.unwrap().expect("project not found")
+}
+
+async fn create_test_key(provider: &str, api_key: &str, project_id: Option<&str>) -> String {
+    let pool = SQLITE_POOL.get().unwrap();
+    let (key_hash, encrypted_key_value) = process_api_key(api_key).unwrap();
+    let key = ProviderKeyPool {
+        id: uuid::Uuid::new_v4().to_string(),
+        provider: provider.to_string(),
+        key_hash,
+        encrypted_key_value,
+        is_active: true,
+        usage_count: 0,
+        last_used_at: None,
+        rate_limit_per_minute: None,
+        rate_limit_per_hour: None,
+        project_id: project_id.map(str::to_string),
+        created_at: None,
+    };
+    create_provider_key_pool(pool, &key).await.expect("create key failed");
+    sync_provider_key_pool_cache(pool, provider, &key.id).await.expect("sync key failed");
+    key.id
+}
+
+#[tokio::test]
+async fn test_gateway_key_resolves_to_bound_project() {
+    setup().await;
+    let pool = SQLITE_POOL.get().unwrap();
+
+    println!("=== Testing Gateway Key Binding ===");
+    let project = create_test_project().await;
+    let api_key = format!("sk-project-{}", uuid::Uuid::new_v4());
+    bind_gateway_key(pool, &consumer_id(&api_key), &project.id).await.expect("bind failed");
+    reload_project_bindings(pool).await.expect("reload failed");
+
+    let mut app = app();
+    let response = send(&mut app, Some(&api_key)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_text(response).await, project.id);
+    println!("✅ Bound key scoped to {}", project.id);
+
+    // 未绑定的 Key 和未携带 Key 的请求属于 default 项目
+    let response = send(&mut app, Some("sk-unbound-key")).await;
+    assert_eq!(body_text(response).await, DEFAULT_PROJECT);
+    let response = send(&mut app, None).await;
+    assert_eq!(body_text(response).await, DEFAULT_PROJECT);
+    println!("✅ Unbound requests use the default project");
+
+    // 要求绑定项目时只接受已绑定的 Key
+    set_require_bound_key(true);
+    for unbound in [Some("sk-unbound-key"), None] {
+        let response = send(&mut app, unbound).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let body: Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(body["error"]["code"], "invalid_api_key");
+    }
+    let response = send(&mut app, Some(&api_key)).await;
+    assert_eq!(body_text(response).await, project.id);
+    set_require_bound_key(false);
+    println!("✅ Unbound requests rejected with 401 when a bound key is required");
+
+    update_project(pool, &Project { is_active: false, ..project.clone() }).await.expect("update failed");
+    reload_project_bindings(pool).await.expect("reload failed");
+    let response = send(&mut app, Some(&api_key)).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let body: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(body["error"]["code"], "project_disabled");
+    println!("✅ Disabled project rejected with 403");
+
+    delete_project(pool, &project.id).await.expect("delete failed");
+    reload_project_bindings(pool).await.expect("reload failed");
+}
+
+#[tokio::test]
+async fn test_project_api_keys_are_isolated() {
+    setup().await;
+    let pool = SQLITE_POOL.get().unwrap();
+
+    println!("=== Testing Project Key Isolation ===");
+    let project = create_test_project().await;
+    let provider = format!("project-test-{}", &uuid::Uuid::new_v4().to_string()[..8]);
+    let default_key = create_test_key(&provider, "sk-default-project-key", None).await;
+    let project_key = create_test_key(&provider, "sk-team-project-key", Some(&project.id)).await;
+
+    for _ in 0..3 {
+        let (_, key_id) = get_project_api_key_round_robin(&provider, &project.id).await.expect("no project key");
+        assert_eq!(key_id, project_key);
+        let (_, key_id) = get_project_api_key_round_robin(&provider, DEFAULT_PROJECT).await.expect("no default key");
+        assert_eq!(key_id, default_key);
+    }
+    assert!(!has_available_project_key(&provider, "proj-without-keys").await);
+    assert!(get_project_api_key_round_robin(&provider, "proj-without-keys").await.is_none());
+    println!("✅ Each project only uses its own keys");
+
+    sqlx::query("DELETE FROM provider_key_pools WHERE provider = ?").bind(&provider).execute(pool.as_ref()).await.unwrap();
+    delete_project(pool, &project.id).await.expect("delete failed");
+}
+
+#[tokio::test]
+async fn test_call_logs_filtered_by_project() {
+    setup().await;
+    let pool = SQLITE_POOL.get().unwrap();
+
+    println!("=== Testing Call Log Project Filter ===");
+    let project = create_test_project().await;
+    let call_log = CallLog {
+        cost: 0.5,
+        project_id: Some(project.id.clone()),
+        ..common::call_log("openai", 200)
+    };
+    create_call_log(pool, &call_log).await.expect("create call log failed");
+
+    let search = CallLogSearch { project_id: Some(project.id.clone()), ..Default::default() };
+    assert_eq!(count_call_logs_by_search(pool, &search).await.unwrap(), 1);
+    println!("✅ Call log attributed to {}", project.id);
+
+    sqlx::query("DELETE FROM call_logs WHERE id = ?").bind(&call_log.id).execute(pool.as_ref()).await.unwrap();
+    delete_project(pool, &project.id).await.expect("delete failed");
+}
@@ -0,0 +1,85 @@
+//! # 提示词缓存预热测试
+//!
+//! 测试预热请求只携带系统提示词并限制生成长度，以及定期预热任务的登记和停止
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use project_rust_learn::jobs::prompt_cache_warmup::{
+    cancel_prompt_cache_warmup, get_prompt_cache_warmup, list_prompt_cache_warmups, start_prompt_cache_warmup,
+    supports_prompt_cache,
+};
+use project_rust_learn::llm_api::dispatcher::{DispatchConfig, DispatchRequest, LLMDispatcher, Provider};
+use common::MockAdapter;
+
+async fn dispatcher() -> (Arc<LLMDispatcher>, Arc<Mutex<Vec<DispatchRequest>>>) {
+    let adapter = MockAdapter::new(Provider::OpenAI).with_models(&["gpt-4o-mini"]);
+    let requests = adapter.requests();
+    let dispatcher = LLMDispatcher::new(Some(DispatchConfig {
+        enable_fallback: false,
+        ..Default::default()
+    }));
+    dispatcher.register_client(Box::new(adapter)).await;
+    (Arc::new(dispatcher), requests)
+}
+
+#[tokio::test]
+async fn test_warmup_sends_minimal_request() {
+    println!("=== Testing Prompt Cache Warmup ===");
+    let (dispatcher, requests) = dispatcher().await;
+    let system_prompt = "You are a support agent. ".repeat(200);
+
+    let warmup = start_prompt_cache_warmup(dispatcher, Provider::OpenAI, "gpt-4o-mini".to_string(), system_prompt.clone(), None)
+        .await
+        .unwrap();
+    assert_eq!(warmup.runs, 1);
+    assert!(warmup.last_error.is_none());
+    assert_eq!(warmup.prompt_chars, system_prompt.chars().count());
+    // 只预热一次时不登记任务
+    assert!(get_prompt_cache_warmup(&warmup.id).is_none());
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].messages[0].role, "system");
+    assert_eq!(requests[0].messages[0].content, system_prompt);
+    assert_eq!(requests[0].max_tokens, Some(1));
+    println!("✅ Warmup sent the system prompt with max_tokens = 1");
+}
+
+#[tokio::test]
+async fn test_scheduled_warmup_repeats_until_cancelled() {
+    // 暂停时钟，sleep 在没有其他任务可执行时直接推进虚拟时间
+    tokio::time::pause();
+    let (dispatcher, requests) = dispatcher().await;
+
+    let warmup = start_prompt_cache_warmup(
+        dispatcher,
+        Provider::OpenAI,
+        "gpt-4o-mini".to_string(),
+        "static prompt".to_string(),
+        Some(Duration::from_millis(50)),
+    )
+    .await
+    .unwrap();
+    assert!(list_prompt_cache_warmups().iter().any(|w| w.id == warmup.id));
+
+    // 启动时预热一次，之后在 50ms、100ms、150ms 各一次
+    tokio::time::sleep(Duration::from_millis(180)).await;
+    assert_eq!(get_prompt_cache_warmup(&warmup.id).unwrap().runs, 4);
+
+    assert!(cancel_prompt_cache_warmup(&warmup.id));
+    assert!(!cancel_prompt_cache_warmup(&warmup.id));
+    let sent = requests.lock().unwrap().len();
+    tokio::time::sleep(Duration::from_millis(120)).await;
+    assert_eq!(requests.lock().unwrap().len(), sent);
+    println!("✅ Scheduled warmup stopped after cancellation");
+}
+
+#[test]
+fn test_supports_prompt_cache() {
+    assert!(supports_prompt_cache(&Provider::OpenAI));
+    assert!(supports_prompt_cache(&Provider::Ali));
+    assert!(!supports_prompt_cache(&Provider::Custom("mock".to_string())));
+}
@@ -0,0 +1,196 @@
+//! # 供应商并发上限测试
+//!
+//! 测试超出 `max_concurrent_requests` 的请求按 `concurrency_wait_ms` 排队或立即返回 `Overloaded`，
+//! 排队时按请求优先级放行，以及上限随 providers 表同步生效
+
+mod common;
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+use std::time::Duration;
+use async_trait::async_trait;
+
+use project_rust_learn::dao::{init_db, init_sqlite_pool, SQLITE_POOL};
+use project_rust_learn::dao::provider::{create_provider, hard_delete_provider, update_provider, Provider as ProviderRecord};
+use project_rust_learn::llm_api::dispatcher::{
+    DispatchConfig, DispatchRequest, DispatchResponse, LLMClientAdapter, LLMDispatcher, LLMError, Provider, RequestPriority,
+    StreamReceiver,
+};
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+use common::response;
+
+const MODEL: &str = "slow-model";
+const PROVIDER_NAME: &str = "concurrency-mock";
+
+/// 每次调用耗时 50ms 的适配器，记录同时执行的最大请求数和开始执行的请求优先级
+struct SlowAdapter {
+    provider: Provider,
+    running: AtomicUsize,
+    max_running: Arc<AtomicUsize>,
+    started: Arc<Mutex<Vec<RequestPriority>>>,
+}
+
+#[async_trait]
+impl LLMClientAdapter for SlowAdapter {
+    async fn generate(&self, request: &DispatchRequest) -> Result<DispatchResponse, LLMError> {
+        let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_running.fetch_max(running, Ordering::SeqCst);
+        self.started.lock().unwrap().push(request.priority.unwrap_or_default());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        self.running.fetch_sub(1, Ordering::SeqCst);
+
+        Ok(response(self.provider.clone(), &request.model, "done"))
+    }
+
+    async fn generate_stream(&self, _request: &DispatchRequest) -> Result<StreamReceiver, LLMError> {
+        Err(LLMError::InvalidParameters("stream not supported".to_string()))
+    }
+
+    fn supported_models(&self) -> Vec<String> {
+        vec![MODEL.to_string()]
+    }
+
+    fn provider_name(&self) -> Provider {
+        self.provider.clone()
+    }
+}
+
+async fn slow_dispatcher_with_log(concurrency_wait_ms: u64, started: Arc<Mutex<Vec<RequestPriority>>>) -> (Arc<LLMDispatcher>, Provider, Arc<AtomicUsize>) {
+    // 使用唯一的自定义供应商，避免影响其他测试
+    let provider = Provider::Custom(format!("slow-{}", uuid::Uuid::new_v4().simple()));
+    let max_running = Arc::new(AtomicUsize::new(0));
+    let dispatcher = LLMDispatcher::new(Some(DispatchConfig {
+        enable_fallback: false,
+        default_retry_count: 0,
+        concurrency_wait_ms,
+        ..Default::default()
+    }));
+    dispatcher.register_client(Box::new(SlowAdapter {
+        provider: provider.clone(),
+        running: AtomicUsize::new(0),
+        max_running: max_running.clone(),
+        started,
+    })).await;
+    dispatcher.set_concurrency_limit(&provider, Some(1));
+    (Arc::new(dispatcher), provider, max_running)
+}
+
+async fn slow_dispatcher(concurrency_wait_ms: u64) -> (Arc<LLMDispatcher>, Provider, Arc<AtomicUsize>) {
+    slow_dispatcher_with_log(concurrency_wait_ms, Arc::default()).await
+}
+
+fn request(provider: &Provider) -> DispatchRequest {
+    DispatchRequest::new(provider.clone(), MODEL.to_string(), vec![Message::user("hi".to_string())])
+}
+
+#[tokio::test]
+async fn test_overloaded_provider_fails_fast() {
+    println!("=== Testing Concurrency Limit Without Queueing ===");
+    let (dispatcher, provider, max_running) = slow_dispatcher(0).await;
+
+    let (first, second) = tokio::join!(
+        dispatcher.dispatch(request(&provider)),
+        async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            dispatcher.dispatch(request(&provider)).await
+        }
+    );
+    assert_eq!(first.expect("first request failed").content, "done");
+    let error = second.unwrap_err();
+    assert!(matches!(error, LLMError::Overloaded(_)), "{:?}", error);
+    assert_eq!(error.status_code(), 503);
+    assert!(error.is_retryable());
+    assert_eq!(max_running.load(Ordering::SeqCst), 1);
+    println!("✅ Request beyond the limit rejected with Overloaded (503)");
+
+    assert_eq!(dispatcher.concurrency_usage(&provider), (Some(1), 0));
+    assert!(dispatcher.dispatch(request(&provider)).await.is_ok());
+    println!("✅ Slot released after the request finished");
+}
+
+#[tokio::test]
+async fn test_requests_queue_for_a_slot() {
+    println!("=== Testing Concurrency Limit With Queueing ===");
+    let (dispatcher, provider, max_running) = slow_dispatcher(5_000).await;
+
+    let results = futures::future::join_all((0..3).map(|_| dispatcher.dispatch(request(&provider)))).await;
+    assert!(results.iter().all(Result::is_ok), "{:?}", results);
+    assert_eq!(max_running.load(Ordering::SeqCst), 1);
+    println!("✅ Queued requests ran one at a time");
+
+    dispatcher.set_concurrency_limit(&provider, None);
+    let results = futures::future::join_all((0..3).map(|_| dispatcher.dispatch(request(&provider)))).await;
+    assert!(results.iter().all(Result::is_ok));
+    assert!(max_running.load(Ordering::SeqCst) > 1);
+    println!("✅ Removing the limit lets requests run concurrently");
+}
+
+#[tokio::test]
+async fn test_queued_requests_served_by_priority() {
+    println!("=== Testing Concurrency Limit Priority ===");
+    let started = Arc::new(Mutex::new(Vec::new()));
+    let (dispatcher, provider, _) = slow_dispatcher_with_log(5_000, started.clone()).await;
+
+    let with_priority = |priority: RequestPriority| {
+        let mut request = request(&provider);
+        request.priority = Some(priority);
+        request
+    };
+    let delay = |ms: u64| tokio::time::sleep(Duration::from_millis(ms));
+    // 第一个请求占用槽位，批量任务（默认 batch 优先级）先排队，随后到达的 normal 和 high 请求仍然先执行
+    let (first, batch, normal, high) = tokio::join!(
+        dispatcher.dispatch(with_priority(RequestPriority::Normal)),
+        async { delay(10).await; dispatcher.dispatch_batch(vec![request(&provider)], 1).await },
+        async { delay(20).await; dispatcher.dispatch(with_priority(RequestPriority::Normal)).await },
+        async { delay(30).await; dispatcher.dispatch(with_priority(RequestPriority::High)).await },
+    );
+    assert!(first.is_ok() && high.is_ok() && normal.is_ok());
+    assert!(batch[0].is_ok());
+    assert_eq!(*started.lock().unwrap(), vec![
+        RequestPriority::Normal,
+        RequestPriority::High,
+        RequestPriority::Normal,
+        RequestPriority::Batch,
+    ]);
+    println!("✅ High priority requests run ahead of queued batch jobs");
+}
+
+#[tokio::test]
+async fn test_limit_synced_from_providers_table() {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    init_db("data/init.sql").await.expect("DB init failed");
+    let pool = SQLITE_POOL.get().unwrap().clone();
+
+    println!("=== Testing Concurrency Limit From Database ===");
+    let mut record = ProviderRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: PROVIDER_NAME.to_string(),
+        display_name: "Concurrency Mock".to_string(),
+        base_url: None,
+        description: None,
+        config: Some(r#"{"type": "mock"}"#.to_string()),
+        is_active: true,
+        project_id: None,
+        max_concurrent_requests: Some(2),
+        created_at: None,
+        updated_at: None,
+    };
+    // 清理上次运行残留的同名记录
+    sqlx::query("DELETE FROM providers WHERE name = ?").bind(PROVIDER_NAME).execute(pool.as_ref()).await.unwrap();
+    create_provider(&pool, &record).await.expect("create_provider failed");
+
+    let provider = Provider::Custom(PROVIDER_NAME.to_string());
+    let dispatcher = LLMDispatcher::from_database(&pool).await.expect("from_database failed");
+    assert_eq!(dispatcher.concurrency_usage(&provider), (Some(2), 0));
+    println!("✅ Limit loaded from max_concurrent_requests");
+
+    record.max_concurrent_requests = None;
+    update_provider(&pool, &record.id, &record).await.expect("update_provider failed");
+    dispatcher.resync_providers().await.expect("resync failed");
+    assert_eq!(dispatcher.concurrency_usage(&provider), (None, 0));
+    println!("✅ Clearing the column removes the limit on resync");
+
+    hard_delete_provider(&pool, &record.id).await.expect("hard_delete_provider failed");
+}
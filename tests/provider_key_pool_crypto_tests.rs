@@ -1,9 +1,12 @@
+mod common;
+
 use project_rust_learn::dao::provider_key_pool::crypto::{
     generate_key_hash, encrypt_api_key, decrypt_api_key, process_api_key, verify_key_integrity
 };
 
 #[tokio::test]
 async fn test_simple_crypto_functions() {
+    common::install_test_master_keyring();
     println!("=== Testing Simple Crypto Functions ===");
     
     // Test 1: Key hash generation
@@ -84,6 +87,7 @@ fn test_sync_hash_function() {
 
 #[test]
 fn test_data_integrity() {
+    common::install_test_master_keyring();
     println!("=== Testing Data Integrity ===");
     
     let test_data = vec![
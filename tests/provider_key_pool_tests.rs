@@ -1,3 +1,5 @@
+mod common;
+
 use project_rust_learn::dao::{init_sqlite_pool, init_db, SQLITE_POOL};
 use project_rust_learn::dao::provider_key_pool::{
     ProviderKeyPool, create_provider_key_pool, get_provider_key_pool_by_id,
@@ -12,6 +14,7 @@ use sqlx::{Pool, Sqlite};
 
 /// 初始化测试环境的辅助函数
 async fn setup_test_env() -> Arc<Pool<Sqlite>> {
+    common::install_test_master_keyring();
     init_sqlite_pool("sqlite://data/app.db").await;
     let pool = SQLITE_POOL.get().unwrap().clone();
     init_db("data/init.sql").await.expect("DB init failed");
@@ -35,6 +38,7 @@ async fn test_provider_key_pool_crud_operations() {
         last_used_at: None,
         rate_limit_per_minute: Some(60),
         rate_limit_per_hour: Some(3600),
+        project_id: None,
         created_at: None,
     };
 
@@ -48,6 +52,7 @@ async fn test_provider_key_pool_crud_operations() {
         last_used_at: Some("2024-01-01 10:00:00".to_string()),
         rate_limit_per_minute: Some(30),
         rate_limit_per_hour: Some(1800),
+        project_id: None,
         created_at: None,
     };
 
@@ -61,6 +66,7 @@ async fn test_provider_key_pool_crud_operations() {
         last_used_at: Some("2024-01-01 09:00:00".to_string()),
         rate_limit_per_minute: Some(60),
         rate_limit_per_hour: Some(3600),
+        project_id: None,
         created_at: None,
     };
 
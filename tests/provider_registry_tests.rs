@@ -0,0 +1,264 @@
+mod common;
+
+use project_rust_learn::dao::{init_sqlite_pool, init_db, SQLITE_POOL};
+use project_rust_learn::dao::provider::{
+    Provider as ProviderRecord, create_provider, get_provider_by_id, hard_delete_provider, update_provider
+};
+use project_rust_learn::llm_api::dispatcher::{
+    DispatchRequest, LLMClientAdapter, LLMDispatcher, OllamaAdapter, Provider,
+};
+use project_rust_learn::llm_api::ollama::client::OllamaClient;
+use project_rust_learn::llm_api::openai::client::OpenAIChatRequest;
+use project_rust_learn::llm_api::openai_compatible::client::{OpenAICompatibleClient, OpenAICompatibleConfig};
+use project_rust_learn::llm_api::utils::chat_traits::ChatResponseTrait;
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+use project_rust_learn::llm_api::provider_registry::{
+    ProviderConfig, ProviderFactory, provider_registry, register_provider_factory
+};
+use common::MockAdapter;
+use std::sync::Arc;
+use sqlx::{Pool, Sqlite};
+
+/// 初始化测试环境的辅助函数
+async fn setup_test_env() -> Arc<Pool<Sqlite>> {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+    init_db("data/init.sql").await.expect("DB init failed");
+    pool
+}
+
+async fn set_provider_active(pool: &Pool<Sqlite>, id: &str, is_active: bool) {
+    let mut provider = get_provider_by_id(pool, id).await
+        .expect("get_provider_by_id failed")
+        .expect("provider not found");
+    provider.is_active = is_active;
+    update_provider(pool, id, &provider).await.expect("update_provider failed");
+}
+
+#[tokio::test]
+async fn test_sync_providers_from_db() {
+    let pool = setup_test_env().await;
+
+    println!("=== Testing Provider Adapter Auto-Registration ===");
+    set_provider_active(&pool, "ollama", true).await;
+    set_provider_active(&pool, "ali", true).await;
+
+    let dispatcher = LLMDispatcher::new(None);
+    let registered = dispatcher.sync_providers_from_db(&pool).await.expect("sync failed");
+    println!("✅ Registered providers: {:?}", registered);
+    assert!(registered.contains(&Provider::Ollama));
+    assert!(registered.contains(&Provider::Ali));
+    // zhipu 没有注册工厂，不会被注册
+    assert!(registered.contains(&Provider::OpenAI));
+    assert!(!registered.contains(&Provider::Custom("zhipu".to_string())));
+
+    // 停用后重新同步会注销适配器
+    set_provider_active(&pool, "ollama", false).await;
+    let registered = dispatcher.sync_providers_from_db(&pool).await.expect("sync failed");
+    assert!(!registered.contains(&Provider::Ollama));
+    assert!(!dispatcher.is_provider_available(&Provider::Ollama).await);
+    assert!(dispatcher.is_provider_available(&Provider::Ali).await);
+    println!("✅ Deactivated provider unregistered");
+
+    // 手动注册的适配器不受同步影响
+    let client = OllamaClient::new("http://localhost:11434".to_string()).unwrap();
+    dispatcher.register_client(Box::new(OllamaAdapter::new(client))).await;
+    dispatcher.sync_providers_from_db(&pool).await.expect("sync failed");
+    assert!(dispatcher.is_provider_available(&Provider::Ollama).await);
+    println!("✅ Manually registered adapter kept");
+
+    set_provider_active(&pool, "ollama", true).await;
+
+    println!("\n=== Provider Adapter Auto-Registration Tests Completed ===");
+}
+
+struct PluginFactory;
+
+impl ProviderFactory for PluginFactory {
+    fn provider_type(&self) -> &str {
+        "test-plugin"
+    }
+
+    fn create_adapter(&self, config: &ProviderConfig) -> anyhow::Result<Box<dyn LLMClientAdapter>> {
+        let content = format!("plugin at {}", config.base_url.clone().unwrap_or_default());
+        Ok(Box::new(
+            MockAdapter::new(config.provider.clone())
+                .with_models(&["plugin-model"])
+                .with_content(move |_| content.clone()),
+        ))
+    }
+}
+
+#[tokio::test]
+async fn test_register_plugin_provider_factory() {
+    let pool = setup_test_env().await;
+
+    println!("=== Testing Provider Factory Plugins ===");
+    register_provider_factory(Arc::new(PluginFactory));
+    assert!(provider_registry().provider_types().contains(&"test-plugin".to_string()));
+
+    let record = ProviderRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: "test-plugin".to_string(),
+        display_name: "Test Plugin".to_string(),
+        base_url: Some("http://plugin.local/ ".to_string()),
+        description: None,
+        config: None,
+        is_active: true,
+        project_id: None,
+        max_concurrent_requests: None,
+        created_at: None,
+        updated_at: None,
+    };
+    // 清理上次运行残留的同名记录
+    sqlx::query("DELETE FROM providers WHERE name = ?").bind(&record.name).execute(pool.as_ref()).await.unwrap();
+    create_provider(&pool, &record).await.expect("create_provider failed");
+
+    let dispatcher = LLMDispatcher::new(None);
+    let registered = dispatcher.sync_providers_from_db(&pool).await.expect("sync failed");
+    let plugin = Provider::Custom("test-plugin".to_string());
+    assert!(registered.contains(&plugin));
+    println!("✅ Plugin provider registered: {:?}", plugin);
+
+    // 通过 "类型/模型" 前缀路由到插件供应商
+    let (provider, model) = dispatcher.resolve_model("test-plugin/plugin-model").await.expect("resolve failed");
+    assert_eq!(provider, plugin);
+    let request = DispatchRequest::new(provider, model, vec![Message::user("hi".to_string())]);
+    let response = dispatcher.dispatch(request).await.expect("dispatch failed");
+    assert_eq!(response.content, "plugin at http://plugin.local");
+    println!("✅ Dispatched through plugin adapter");
+
+    hard_delete_provider(&pool, &record.id).await.expect("hard_delete_provider failed");
+    provider_registry().unregister("test-plugin");
+
+    println!("\n=== Provider Factory Plugin Tests Completed ===");
+}
+
+#[tokio::test]
+async fn test_provider_extra_headers() {
+    setup_test_env().await;
+
+    println!("=== Testing Provider Extra Headers ===");
+    let mut server = mockito::Server::new_async().await;
+    let mock = server.mock("POST", "/api/chat")
+        .match_header("x-trace-id", "gateway-test")
+        .match_header("openai-beta", "assistants=v2")
+        .match_header("authorization", mockito::Matcher::Missing)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"model":"llama3.2","created_at":"2024-01-01T00:00:00Z","message":{"role":"assistant","content":"ok"},"done":true}"#)
+        .create_async()
+        .await;
+
+    // 认证请求头由客户端负责，配置中的同名请求头被忽略
+    let config = ProviderConfig::new(Provider::Ollama, "ollama", Some(&server.url()))
+        .with_settings(Some(serde_json::json!({
+            "headers": {"X-Trace-Id": "gateway-test", "OpenAI-Beta": "assistants=v2", "Authorization": "Bearer leaked"}
+        })));
+    let headers = config.extra_headers();
+    assert_eq!(headers.len(), 2);
+    assert!(!headers.contains_key("Authorization"));
+
+    let adapter = provider_registry().get("ollama").unwrap().create_adapter(&config).expect("create_adapter failed");
+    let mut request = DispatchRequest::new(Provider::Ollama, "llama3.2".to_string(), vec![Message::user("hi".to_string())]);
+    request.retry_count = Some(0);
+    let response = adapter.generate(&request).await.expect("request without configured headers");
+    assert_eq!(response.content, "ok");
+    mock.assert_async().await;
+    println!("✅ Configured headers sent with outbound request");
+}
+
+#[tokio::test]
+async fn test_openai_compatible_provider_from_db() {
+    let pool = setup_test_env().await;
+
+    println!("=== Testing OpenAI-Compatible Provider ===");
+    let mut server = mockito::Server::new_async().await;
+    let mock = server.mock("POST", "/v1/chat/completions")
+        .match_header("authorization", mockito::Matcher::Missing)
+        .match_body(mockito::Matcher::PartialJson(serde_json::json!({"model": "vllm-test-model"})))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "id": "chatcmpl-vllm",
+            "object": "chat.completion",
+            "created": 1757412000,
+            "model": "vllm-test-model",
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": "served by vllm"}, "finish_reason": "stop"}],
+            "usage": {"prompt_tokens": 4, "completion_tokens": 3, "total_tokens": 7}
+        }).to_string())
+        .create_async()
+        .await;
+
+    // 名称不是内置类型，由 config.type 指定为 openai_compatible；本地服务不需要 API Key
+    let record = ProviderRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: "test-vllm".to_string(),
+        display_name: "Test vLLM".to_string(),
+        base_url: Some(server.url()),
+        description: None,
+        config: Some(serde_json::json!({
+            "type": "openai_compatible",
+            "path_prefix": "v1/",
+            "requires_key": false,
+            "models": ["vllm-test-model"]
+        }).to_string()),
+        is_active: true,
+        project_id: None,
+        max_concurrent_requests: None,
+        created_at: None,
+        updated_at: None,
+    };
+    sqlx::query("DELETE FROM providers WHERE name = ?").bind(&record.name).execute(pool.as_ref()).await.unwrap();
+    create_provider(&pool, &record).await.expect("create_provider failed");
+
+    let dispatcher = LLMDispatcher::new(None);
+    let registered = dispatcher.sync_providers_from_db(&pool).await.expect("sync failed");
+    let vllm = Provider::Custom("test-vllm".to_string());
+    assert!(registered.contains(&vllm));
+    println!("✅ OpenAI-compatible provider registered: {:?}", vllm);
+
+    let (provider, model) = dispatcher.resolve_model("vllm-test-model").await.expect("resolve failed");
+    assert_eq!(provider, vllm);
+    let mut request = DispatchRequest::new(provider, model, vec![Message::user("hi".to_string())]);
+    request.retry_count = Some(0);
+    let response = dispatcher.dispatch(request).await.expect("dispatch failed");
+    assert_eq!(response.content, "served by vllm");
+    assert_eq!(response.provider, vllm);
+    mock.assert_async().await;
+    println!("✅ Dispatched to {{base_url}}/v1/chat/completions without auth header");
+
+    hard_delete_provider(&pool, &record.id).await.expect("hard_delete_provider failed");
+}
+
+#[tokio::test]
+async fn test_openai_compatible_custom_auth_header() {
+    println!("=== Testing OpenAI-Compatible Custom Auth Header ===");
+    let mut server = mockito::Server::new_async().await;
+    let mock = server.mock("POST", "/api/chat/completions")
+        .match_header("x-api-key", "sk-custom")
+        .match_header("authorization", mockito::Matcher::Missing)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 1757412000,
+            "model": "deepseek-chat",
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": "ok"}, "finish_reason": "stop"}]
+        }).to_string())
+        .create_async()
+        .await;
+
+    let config = OpenAICompatibleConfig::new(format!("{}/", server.url()))
+        .with_path_prefix("/api")
+        .with_auth_header("x-api-key".to_string(), None);
+    assert_eq!(config.endpoint("/chat/completions"), format!("{}/api/chat/completions", server.url()));
+
+    let client = OpenAICompatibleClient::new(config, Some("sk-custom".to_string())).unwrap();
+    let request = OpenAIChatRequest::new("deepseek-chat".to_string(), vec![Message::user("hi".to_string())]);
+    let response = client.chat(request).await.expect("chat failed");
+    assert_eq!(response.get_content().as_deref(), Some("ok"));
+    mock.assert_async().await;
+    println!("✅ API key sent in configured header without scheme");
+}
@@ -0,0 +1,146 @@
+//! 供应商请求/响应 JSON 结构契约测试
+//!
+//! `tests/fixtures/provider_schemas/` 下的金样文件取自供应商文档：
+//! - 请求金样：完整参数的 `DispatchRequest` 经适配器发出的请求体必须与金样完全一致，
+//!   参数被静默丢弃（例如惩罚参数）时测试失败
+//! - 响应金样：反序列化后再序列化必须保留金样中的所有字段，结构体漏掉字段时测试失败
+
+use mockito::{Matcher, Server};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use project_rust_learn::dao::{init_sqlite_pool, init_db};
+use project_rust_learn::llm_api::ali::client::{AliChatResponse, AliClient, AliStreamResponse};
+use project_rust_learn::llm_api::dispatcher::{AliAdapter, DispatchRequest, LLMClientAdapter, OllamaAdapter, Provider};
+use project_rust_learn::llm_api::ollama::client::{OllamaChatResponse, OllamaClient};
+use project_rust_learn::llm_api::utils::{client::ClientConfig, msg_structure::Message};
+
+/// 初始化测试环境的辅助函数
+async fn setup_test_env() {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    init_db("data/init.sql").await.expect("DB init failed");
+}
+
+fn fixture(name: &str) -> Value {
+    let path = format!("{}/tests/fixtures/provider_schemas/{}", env!("CARGO_MANIFEST_DIR"), name);
+    let text = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("failed to read {}: {}", path, e));
+    serde_json::from_str(&text).unwrap_or_else(|e| panic!("invalid JSON in {}: {}", path, e))
+}
+
+// 去掉值为 null 的字段，null 与字段缺省在供应商协议中等价
+fn strip_nulls(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k, strip_nulls(v)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(strip_nulls).collect()),
+        other => other,
+    }
+}
+
+// 金样反序列化后再序列化，断言没有字段丢失或改变
+fn assert_round_trip<T: Serialize + DeserializeOwned>(name: &str) -> T {
+    let golden = fixture(name);
+    let parsed: T = serde_json::from_value(golden.clone())
+        .unwrap_or_else(|e| panic!("{} does not deserialize: {}", name, e));
+    let reserialized = serde_json::to_value(&parsed).unwrap();
+    assert_eq!(strip_nulls(reserialized), strip_nulls(golden), "{} lost fields in round trip", name);
+    parsed
+}
+
+// 所有参数都设置的请求，数值选用 f32 可精确表示的值
+fn full_request(provider: Provider, model: &str) -> DispatchRequest {
+    let mut request = DispatchRequest::new(
+        provider,
+        model.to_string(),
+        vec![
+            Message::system("You are a concise assistant.".to_string()),
+            Message::user("Why is the sky blue?".to_string()),
+        ],
+    )
+    .with_temperature(0.5)
+    .with_max_tokens(128)
+    .with_top_p(0.75)
+    .with_stop(vec!["\n\n".to_string()]);
+    request.frequency_penalty = Some(0.25);
+    request.presence_penalty = Some(0.125);
+    request
+}
+
+#[tokio::test]
+async fn test_ollama_request_schema() {
+    setup_test_env().await;
+
+    println!("=== Testing Ollama Request Schema ===");
+
+    let mut server = Server::new_async().await;
+    let mock = server.mock("POST", "/api/chat")
+        .match_body(Matcher::Json(fixture("ollama_chat_request.json")))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(fixture("ollama_chat_response.json").to_string())
+        .create_async()
+        .await;
+
+    let adapter = OllamaAdapter::new(OllamaClient::new(server.url()).unwrap());
+    let response = adapter.generate(&full_request(Provider::Ollama, "llama3.2")).await
+        .expect("request body does not match ollama_chat_request.json");
+
+    mock.assert_async().await;
+    assert_eq!(response.content, "Rayleigh scattering.");
+    assert_eq!(response.finish_reason.as_deref(), Some("length"));
+    println!("✅ Ollama request body matches golden file");
+}
+
+#[tokio::test]
+async fn test_ali_request_schema() {
+    setup_test_env().await;
+
+    println!("=== Testing Ali Request Schema ===");
+
+    let mut server = Server::new_async().await;
+    // 兼容模式不支持 frequency_penalty，金样中不包含该字段
+    let mock = server.mock("POST", "/compatible-mode/v1/chat/completions")
+        .match_body(Matcher::Json(fixture("ali_chat_request.json")))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(fixture("ali_chat_response.json").to_string())
+        .create_async()
+        .await;
+
+    let http_client = reqwest::Client::builder().no_proxy().build().unwrap();
+    let client = AliClient::new_with_client("sk-test".to_string(), server.url(), ClientConfig::default(), http_client).unwrap();
+    let adapter = AliAdapter::new(client);
+    let response = adapter.generate(&full_request(Provider::Ali, "qwen-plus")).await
+        .expect("request body does not match ali_chat_request.json");
+
+    mock.assert_async().await;
+    assert_eq!(response.content, "Rayleigh scattering.");
+    assert_eq!(response.usage.map(|u| u.total_tokens), Some(27));
+    println!("✅ Ali request body matches golden file");
+}
+
+#[test]
+fn test_ollama_response_schema() {
+    println!("=== Testing Ollama Response Schema ===");
+
+    let response: OllamaChatResponse = assert_round_trip("ollama_chat_response.json");
+    assert_eq!(response.done_reason.as_deref(), Some("length"));
+    println!("✅ Ollama chat response round trip preserves all fields");
+}
+
+#[test]
+fn test_ali_response_schema() {
+    println!("=== Testing Ali Response Schema ===");
+
+    let response: AliChatResponse = assert_round_trip("ali_chat_response.json");
+    assert_eq!(response.choices[0].finish_reason, "stop");
+    println!("✅ Ali chat response round trip preserves all fields");
+
+    let chunk: AliStreamResponse = assert_round_trip("ali_stream_chunk.json");
+    assert_eq!(chunk.choices[0].delta.content.as_deref(), Some("Rayleigh"));
+    println!("✅ Ali stream chunk round trip preserves all fields");
+}
@@ -0,0 +1,105 @@
+//! # 就绪探针测试
+//!
+//! 测试 `/readyz` 区分就绪、降级和未就绪，以及 `allow_degraded` 查询参数；供应商状态读取缓存中的模型
+
+use std::sync::Arc;
+use axum::{extract::Query, http::StatusCode};
+use serde_json::json;
+
+use project_rust_learn::dao::{init_sqlite_pool, init_db, SQLITE_POOL};
+use project_rust_learn::dao::cache::init_global_cache;
+use project_rust_learn::dao::model::{create_model, delete_model, sync_model_cache, Model, HEALTH_UNHEALTHY};
+use project_rust_learn::llm_api::dispatcher::{LLMDispatcher, Provider, GLOBAL_DISPATCHER};
+use project_rust_learn::llm_api::mock::adapter::MockAdapter;
+use project_rust_learn::web::handlers::health_handler::{check_readiness, readiness_check, Readiness, ReadinessLevel, ReadinessQuery};
+
+fn query(value: serde_json::Value) -> Query<ReadinessQuery> {
+    Query(serde_json::from_value(value).expect("Invalid readiness query"))
+}
+
+fn unhealthy_model(provider: &str) -> Model {
+    Model {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: format!("readyz-model-{}", uuid::Uuid::new_v4().simple()),
+        provider: provider.to_string(),
+        model_type: "chat".to_string(),
+        base_url: None,
+        is_active: true,
+        health_status: Some(HEALTH_UNHEALTHY.to_string()),
+        last_health_check: None,
+        health_check_interval_seconds: None,
+        cost_per_token_input: None,
+        cost_per_token_output: None,
+        function_tags: None,
+        config: None,
+        project_id: None,
+        created_at: None,
+        updated_at: None,
+    }
+}
+
+fn has_reason(readiness: &Readiness, code: &str) -> bool {
+    readiness.reasons.iter().any(|reason| reason.code == code)
+}
+
+// 全局调度器和缓存在同一个测试进程内共享，按顺序在一个测试中检查各阶段
+#[tokio::test]
+async fn test_readiness_levels() {
+    println!("=== 就绪探针降级级别测试 ===");
+    init_sqlite_pool("sqlite://data/app.db").await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+    init_db("data/init.sql").await.expect("Database init failed");
+
+    // 调度器未初始化：未就绪，即使允许降级也返回 503
+    let readiness = check_readiness().await;
+    assert_eq!(readiness.status, ReadinessLevel::Unready);
+    assert!(has_reason(&readiness, "dispatcher_not_initialized"));
+    let (status, _) = readiness_check(query(json!({ "allow_degraded": true }))).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    println!("✅ 调度器未初始化时未就绪");
+
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let primary = format!("readyz-primary-{}", suffix);
+    let backup = format!("readyz-backup-{}", suffix);
+    let dispatcher = LLMDispatcher::new(None);
+    dispatcher.register_client(Box::new(MockAdapter::new(Provider::Custom(primary.clone())))).await;
+    dispatcher.register_client(Box::new(MockAdapter::new(Provider::Custom(backup.clone())))).await;
+    GLOBAL_DISPATCHER.set(Arc::new(dispatcher)).ok();
+
+    init_global_cache(&pool, 3600, 1000).await.expect("Cache init failed");
+
+    let readiness = check_readiness().await;
+    assert_eq!(readiness.status, ReadinessLevel::Ready, "unexpected reasons: {:?}", readiness.reasons);
+    let (status, _) = readiness_check(query(json!({}))).await;
+    assert_eq!(status, StatusCode::OK);
+    println!("✅ 依赖正常时就绪");
+
+    // 一个供应商的模型全部不健康：降级，默认 503，allow_degraded 时 200
+    let primary_model = unhealthy_model(&primary);
+    create_model(&pool, &primary_model).await.expect("Create model failed");
+    sync_model_cache(&pool, &primary, &primary_model.name).await;
+    let readiness = check_readiness().await;
+    assert_eq!(readiness.status, ReadinessLevel::Degraded);
+    assert!(readiness.reasons.iter().any(|reason| reason.code == "provider_down" && reason.component == primary));
+    let (status, _) = readiness_check(query(json!({}))).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    let (status, _) = readiness_check(query(json!({ "allow_degraded": true }))).await;
+    assert_eq!(status, StatusCode::OK);
+    println!("✅ 部分供应商不可用时降级");
+
+    // 所有供应商都不可用：未就绪
+    let backup_model = unhealthy_model(&backup);
+    create_model(&pool, &backup_model).await.expect("Create model failed");
+    sync_model_cache(&pool, &backup, &backup_model.name).await;
+    let readiness = check_readiness().await;
+    assert_eq!(readiness.status, ReadinessLevel::Unready);
+    assert!(has_reason(&readiness, "all_providers_down"));
+    let (status, _) = readiness_check(query(json!({ "allow_degraded": true }))).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    println!("✅ 所有供应商不可用时未就绪");
+
+    delete_model(&pool, &primary_model.id).await.expect("Delete model failed");
+    delete_model(&pool, &backup_model.id).await.expect("Delete model failed");
+    sync_model_cache(&pool, &primary, &primary_model.name).await;
+    sync_model_cache(&pool, &backup, &backup_model.name).await;
+}
@@ -0,0 +1,102 @@
+//! # 敏感信息脱敏测试
+//!
+//! 测试写入 `call_logs` 的请求摘要和上游错误信息经过脱敏、日志输出经过脱敏，
+//! 以及 `[redaction]` 配置的自定义正则和关闭开关
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use serde_json::json;
+use tracing_subscriber::fmt::MakeWriter;
+
+use project_rust_learn::config::GatewayConfig;
+use project_rust_learn::dao::{call_log::get_call_log_by_id, init_db, init_sqlite_pool, SQLITE_POOL};
+use project_rust_learn::llm_api::utils::client::{BaseClient, CallMetadata, ClientConfig, CALL_METADATA};
+use project_rust_learn::llm_api::utils::redaction::RedactingMakeWriter;
+
+/// 收集写入内容的 writer
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for Captured {
+    type Writer = Captured;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+#[tokio::test]
+async fn test_call_log_redacted() {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    init_db("data/init.sql").await.expect("DB init failed");
+
+    println!("=== Testing Call Log Redaction ===");
+    let mut server = mockito::Server::new_async().await;
+    let _mock = server.mock("POST", "/v1/chat/completions")
+        .with_status(400)
+        .with_body(r#"{"error":{"message":"Incorrect API key provided: sk-proj-abcdefghijklmnop1234"}}"#)
+        .create_async()
+        .await;
+
+    let summary = "My email is alice@example.com and my phone is 13800138000".to_string();
+    let metadata = CallMetadata::default().with_attempt("openai", Some(summary));
+    let client = BaseClient::new(ClientConfig::new()).unwrap();
+    let url = format!("{}/v1/chat/completions", server.url());
+    let result = CALL_METADATA.scope(metadata.clone(), client.post(&url, json!({ "model": "test" }))).await;
+    assert!(result.is_err());
+
+    let id = metadata.last_call_log_id().expect("call log not recorded");
+    let call_log = get_call_log_by_id(SQLITE_POOL.get().unwrap(), &id).await.unwrap().expect("call log missing");
+    let request_summary = call_log.request_summary.unwrap();
+    assert_eq!(request_summary, "My email is [REDACTED_EMAIL] and my phone is [REDACTED_PHONE]");
+    let error_message = call_log.error_message.unwrap();
+    assert!(!error_message.contains("sk-proj"), "{}", error_message);
+    assert!(error_message.contains("[REDACTED_API_KEY]"), "{}", error_message);
+    println!("✅ Summary and error message redacted before writing");
+}
+
+#[test]
+fn test_log_output_redacted() {
+    println!("=== Testing Log Output Redaction ===");
+    let captured = Captured::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(RedactingMakeWriter::new(captured.clone()))
+        .with_ansi(false)
+        .finish();
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::warn!(user = "bob@example.com", "Upstream rejected key sk-abcdefghijklmnopqrstu");
+    });
+
+    let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+    assert!(output.contains("Upstream rejected key [REDACTED_API_KEY]"), "{}", output);
+    assert!(output.contains("[REDACTED_EMAIL]"), "{}", output);
+    assert!(!output.contains("bob@example.com"));
+    println!("✅ Log line redacted");
+}
+
+#[test]
+fn test_redaction_config() {
+    println!("=== Testing [redaction] Config ===");
+    let config = GatewayConfig::from_toml_str("[redaction]\npatterns = ['\\bACCT-\\d{8}\\b']\n").unwrap();
+    let redactor = config.redaction.redactor().unwrap();
+    assert_eq!(redactor.redact("ACCT-12345678 / carol@example.com"), "[REDACTED] / [REDACTED_EMAIL]");
+
+    let config = GatewayConfig::from_toml_str("[redaction]\nenabled = false\npatterns = ['ACCT']\n").unwrap();
+    assert_eq!(config.redaction.redactor().unwrap().redact("ACCT carol@example.com"), "ACCT carol@example.com");
+
+    let error = format!("{:#}", GatewayConfig::from_toml_str("[redaction]\npatterns = ['(']\n").unwrap_err());
+    assert!(error.contains("Invalid redaction pattern"), "{}", error);
+    println!("✅ Custom patterns applied and invalid patterns rejected");
+}
@@ -0,0 +1,156 @@
+//! # 请求体流式读取测试
+//!
+//! 测试 `StreamingJson` 边接收边解析分块传输的大请求体，超过大小上限（按 `Content-Length`
+//! 或累计接收的字节数）时返回 413，格式错误的 JSON 返回 400，未能在时限内发完请求体时返回 408
+
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    http::{header, Request, StatusCode},
+    routing::post,
+    Json, Router,
+};
+use std::time::Duration;
+use futures::StreamExt;
+use serde_json::{json, Value};
+use tower::Service;
+
+use project_rust_learn::api_types::v1::GatewayErrorCode;
+use project_rust_learn::web::extract::{read_json_body, read_json_body_within, StreamingJson, MAX_CONCURRENT_BODY_PARSERS};
+
+/// 把 JSON 文本切成固定大小的块，模拟分块传输的请求体
+fn chunked_body(text: String, chunk_size: usize) -> Body {
+    let chunks: Vec<Result<Bytes, std::io::Error>> = text
+        .into_bytes()
+        .chunks(chunk_size)
+        .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+        .collect();
+    Body::from_stream(futures::stream::iter(chunks))
+}
+
+fn large_image_request(image_bytes: usize) -> String {
+    json!({
+        "model": "gpt-4o",
+        "messages": [{
+            "role": "user",
+            "content": [
+                { "type": "text", "text": "describe" },
+                { "type": "image_url", "image_url": { "url": format!("data:image/png;base64,{}", "A".repeat(image_bytes)) } }
+            ]
+        }]
+    })
+    .to_string()
+}
+
+async fn error_body(response: axum::response::Response) -> Value {
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let error: Value = serde_json::from_slice(&body).unwrap();
+    error["error"].clone()
+}
+
+#[tokio::test]
+async fn test_chunked_body_parsed_while_streaming() {
+    println!("=== Testing Chunked Body Parsing ===");
+    let text = large_image_request(4 * 1024 * 1024);
+    let len = text.len() as u64;
+    let value: Value = read_json_body(chunked_body(text, 16 * 1024), None, len)
+        .await
+        .expect("chunked body should parse");
+    let url = value["messages"][0]["content"][1]["image_url"]["url"].as_str().unwrap();
+    assert_eq!(url.len(), "data:image/png;base64,".len() + 4 * 1024 * 1024);
+    println!("✅ 4 MiB image parsed from 16 KiB chunks");
+}
+
+#[tokio::test]
+async fn test_body_over_limit_rejected() {
+    println!("=== Testing Request Body Limit ===");
+    let text = large_image_request(256 * 1024);
+
+    // 分块传输没有 Content-Length，按累计接收的字节数拒绝
+    let rejection = read_json_body::<Value>(chunked_body(text.clone(), 8 * 1024), None, 64 * 1024)
+        .await
+        .unwrap_err();
+    assert_eq!(rejection.code, GatewayErrorCode::RequestTooLarge);
+    println!("✅ Streamed body over limit rejected");
+
+    // Content-Length 超限时不读取请求体
+    let rejection = read_json_body::<Value>(Body::from(text.clone()), Some(text.len() as u64), 64 * 1024)
+        .await
+        .unwrap_err();
+    assert_eq!(rejection.code, GatewayErrorCode::RequestTooLarge);
+    println!("✅ Declared Content-Length over limit rejected");
+
+    let rejection = read_json_body::<Value>(chunked_body("{\"model\": ".to_string(), 4), None, 64 * 1024)
+        .await
+        .unwrap_err();
+    assert_eq!(rejection.code, GatewayErrorCode::InvalidRequest);
+    println!("✅ Truncated JSON rejected");
+}
+
+#[tokio::test]
+async fn test_streaming_json_extractor_responses() {
+    println!("=== Testing StreamingJson Extractor ===");
+    let mut app: Router = Router::new().route(
+        "/echo",
+        post(|StreamingJson(value): StreamingJson<Value>| async move { Json(value) }),
+    );
+
+    let response = app
+        .call(Request::post("/echo").body(chunked_body(json!({ "hello": "world" }).to_string(), 3)).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(serde_json::from_slice::<Value>(&body).unwrap(), json!({ "hello": "world" }));
+    println!("✅ Chunked JSON echoed");
+
+    let response = app
+        .call(
+            Request::post("/echo")
+                .header(header::CONTENT_LENGTH, (u64::MAX / 2).to_string())
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(error_body(response).await["code"], json!("request_too_large"));
+    println!("✅ Oversized request answered with 413");
+
+    let response = app
+        .call(Request::post("/echo").body(Body::from("not json")).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(error_body(response).await["type"], json!("invalid_request_error"));
+    println!("✅ Invalid JSON answered with 400");
+}
+
+#[tokio::test]
+async fn test_slow_body_times_out() {
+    println!("=== Testing Request Body Read Timeout ===");
+    // 发出一块后不再发送，也不结束
+    let stalled = || {
+        let first = futures::stream::iter([Ok::<_, std::io::Error>(Bytes::from_static(b"{\"model\": "))]);
+        Body::from_stream(first.chain(futures::stream::pending()))
+    };
+
+    let rejection = read_json_body_within::<Value>(stalled(), None, 64 * 1024, Duration::from_millis(100))
+        .await
+        .unwrap_err();
+    assert_eq!(rejection.code, GatewayErrorCode::RequestTimeout);
+    assert_eq!(rejection.code.status_code(), 408);
+    println!("✅ Stalled body rejected with 408");
+
+    // 超时的请求释放解析名额，慢速请求占满名额后正常请求仍能解析
+    let stalled_requests: Vec<_> = (0..MAX_CONCURRENT_BODY_PARSERS + 4)
+        .map(|_| read_json_body_within::<Value>(stalled(), None, 64 * 1024, Duration::from_millis(100)))
+        .collect();
+    for rejection in futures::future::join_all(stalled_requests).await {
+        assert!(rejection.is_err());
+    }
+    let value: Value = read_json_body_within(chunked_body(json!({ "ok": true }).to_string(), 4), None, 1024, Duration::from_secs(5))
+        .await
+        .expect("body should parse after stalled requests timed out");
+    assert_eq!(value, json!({ "ok": true }));
+    println!("✅ Timed-out requests released their parser slots");
+}
@@ -0,0 +1,264 @@
+//! # 请求取消测试
+//!
+//! 测试 `DELETE /v1/requests/{request_id}` 取消进行中的请求：非流式请求返回 499，
+//! 流式响应以取消错误事件结束，客户端断开 SSE 连接时取消上游流；只有发起请求的 Key 能取消，同一 Key 重复提交时新请求取代旧请求；
+//! BaseClient 中断等待中的上游请求并写入状态码 499 的调用记录
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use async_trait::async_trait;
+use axum::{
+    extract::Path,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    Json,
+};
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+
+use project_rust_learn::dao::{call_log::get_call_log_by_id, init_db, init_sqlite_pool, SQLITE_POOL};
+use project_rust_learn::llm_api::dispatcher::{
+    DispatchRequest, DispatchResponse, LLMClientAdapter, LLMDispatcher, LLMError, Provider, StreamChunk,
+    StreamReceiver, GLOBAL_DISPATCHER,
+};
+use project_rust_learn::llm_api::utils::cancellation::{CancellationToken, CALL_STATUS_CANCELLED};
+use project_rust_learn::llm_api::utils::client::{BaseClient, CallMetadata, ClientConfig, ClientError, CALL_METADATA};
+use project_rust_learn::web::dto::chat_completion_dto::ChatCompletionRequest;
+use project_rust_learn::web::extract::StreamingJson;
+use project_rust_learn::web::handlers::chat_completion_handler::{cancel_chat_request, create_chat_completion};
+use project_rust_learn::web::middleware::timeout::REQUEST_ID_HEADER;
+
+/// 流式调用被取消时记录的最后一条消息
+static CANCELLED_STREAMS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// 模拟一直不返回的上游调用，请求被取消后才结束
+struct HangingAdapter;
+
+#[async_trait]
+impl LLMClientAdapter for HangingAdapter {
+    async fn generate(&self, _request: &DispatchRequest) -> Result<DispatchResponse, LLMError> {
+        CallMetadata::current().cancellation.cancelled().await;
+        Err(LLMError::Network("aborted".to_string()))
+    }
+
+    async fn generate_stream(&self, request: &DispatchRequest) -> Result<StreamReceiver, LLMError> {
+        let cancellation = CallMetadata::current().cancellation;
+        let last = request.messages.last().map(|m| m.content.clone()).unwrap_or_default();
+        let (tx, rx) = tokio::sync::mpsc::channel(4);
+        tx.send(Ok(StreamChunk::delta("partial".to_string()))).await.ok();
+        tokio::spawn(async move {
+            cancellation.cancelled().await;
+            CANCELLED_STREAMS.lock().unwrap().push(last);
+            drop(tx);
+        });
+        Ok(rx)
+    }
+
+    fn supported_models(&self) -> Vec<String> {
+        vec!["hanging-model".to_string()]
+    }
+
+    fn provider_name(&self) -> Provider {
+        Provider::OpenAI
+    }
+}
+
+async fn setup_dispatcher() {
+    if GLOBAL_DISPATCHER.get().is_none() {
+        let dispatcher = LLMDispatcher::new(None);
+        dispatcher.register_client(Box::new(HangingAdapter)).await;
+        GLOBAL_DISPATCHER.set(Arc::new(dispatcher)).ok();
+    }
+}
+
+const CALLER_KEY: &str = "sk-cancel-owner";
+
+fn auth_headers(api_key: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(header::AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", api_key)).unwrap());
+    headers
+}
+
+fn chat_request(stream: bool) -> (HeaderMap, StreamingJson<ChatCompletionRequest>) {
+    let request_id = format!("req-{}", uuid::Uuid::new_v4());
+    let mut headers = auth_headers(CALLER_KEY);
+    headers.insert(REQUEST_ID_HEADER, HeaderValue::from_str(&request_id).unwrap());
+    let request: ChatCompletionRequest = serde_json::from_value(json!({
+        "model": "hanging-model",
+        "messages": [{ "role": "user", "content": "hello" }],
+        "stream": stream,
+    }))
+    .unwrap();
+    (headers, StreamingJson(request))
+}
+
+fn request_id(headers: &HeaderMap) -> String {
+    headers[REQUEST_ID_HEADER].to_str().unwrap().to_string()
+}
+
+/// 等待请求登记后取消
+async fn cancel_when_in_flight(request_id: String) {
+    for _ in 0..100 {
+        if let Ok(Json(response)) = cancel_chat_request(auth_headers(CALLER_KEY), Path(request_id.clone())).await {
+            assert_eq!(response.id, request_id);
+            assert_eq!(response.status, "cancelled");
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("request {} never became in flight", request_id);
+}
+
+#[tokio::test]
+async fn test_cancel_non_streaming_request() {
+    setup_dispatcher().await;
+
+    println!("=== Testing Non-Streaming Cancellation ===");
+    let (headers, request) = chat_request(false);
+    let id = request_id(&headers);
+    let canceller = tokio::spawn(cancel_when_in_flight(id.clone()));
+
+    let (status, Json(error)) = create_chat_completion(headers, None, request).await.unwrap_err();
+    canceller.await.unwrap();
+    assert_eq!(status.as_u16(), 499);
+    assert_eq!(error.error.code.as_deref(), Some("request_cancelled"));
+    println!("✅ Cancelled request returned {}", status);
+
+    // 请求结束后不能再取消
+    let (status, Json(error)) = cancel_chat_request(auth_headers(CALLER_KEY), Path(id)).await.unwrap_err();
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(error.error.code.as_deref(), Some("request_not_found"));
+    println!("✅ Finished request is no longer cancellable");
+}
+
+#[tokio::test]
+async fn test_cancel_streaming_request() {
+    setup_dispatcher().await;
+
+    println!("=== Testing Streaming Cancellation ===");
+    let (headers, request) = chat_request(true);
+    let id = request_id(&headers);
+    let response = create_chat_completion(headers, None, request).await.expect("stream request failed");
+    assert_eq!(response.headers()[REQUEST_ID_HEADER], id.as_str());
+
+    // 收到第一个分块后取消
+    let mut body = response.into_body().into_data_stream();
+    let first = body.next().await.expect("stream ended early").unwrap();
+    assert!(String::from_utf8_lossy(&first).contains("partial"));
+    cancel_when_in_flight(id).await;
+
+    let rest: Vec<_> = tokio::time::timeout(Duration::from_secs(5), body.collect::<Vec<_>>())
+        .await
+        .expect("stream did not end after cancellation");
+    let rest: String = rest.into_iter().map(|chunk| String::from_utf8(chunk.unwrap().to_vec()).unwrap()).collect();
+    let events: Vec<&str> = rest.lines().filter_map(|line| line.strip_prefix("data: ")).collect();
+    let error: Value = serde_json::from_str(events[events.len() - 2]).unwrap();
+    assert_eq!(error["error"]["code"], "request_cancelled");
+    assert_eq!(events.last(), Some(&"[DONE]"));
+    println!("✅ Stream ended with a cancellation error event");
+}
+
+#[tokio::test]
+async fn test_cancel_is_scoped_to_caller() {
+    setup_dispatcher().await;
+
+    println!("=== Testing Cancellation Ownership ===");
+    let (headers, request) = chat_request(false);
+    let id = request_id(&headers);
+    let pending = tokio::spawn(create_chat_completion(headers.clone(), None, request));
+
+    // 其他 Key 或匿名调用方看不到该请求，也不会占用同一请求 ID
+    for _ in 0..100 {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let (status, _) = cancel_chat_request(auth_headers("sk-someone-else"), Path(id.clone())).await.unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = cancel_chat_request(HeaderMap::new(), Path(id.clone())).await.unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+    assert!(!pending.is_finished());
+    println!("✅ Other callers cannot cancel the request");
+
+    // 同一 Key 重试同一请求 ID 时取代并取消旧请求
+    let (_, retry) = chat_request(false);
+    let retried = tokio::spawn(create_chat_completion(headers, None, retry));
+    let (status, _) = tokio::time::timeout(Duration::from_secs(5), pending).await
+        .expect("superseded request did not end")
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(status.as_u16(), 499);
+    cancel_when_in_flight(id).await;
+    let (status, _) = retried.await.unwrap().unwrap_err();
+    assert_eq!(status.as_u16(), 499);
+    println!("✅ Retry with the same id superseded the earlier request");
+}
+
+#[tokio::test]
+async fn test_client_disconnect_cancels_stream() {
+    setup_dispatcher().await;
+
+    println!("=== Testing Client Disconnect ===");
+    let prompt = format!("disconnect-{}", uuid::Uuid::new_v4());
+    let request: ChatCompletionRequest = serde_json::from_value(json!({
+        "model": "hanging-model",
+        "messages": [{ "role": "user", "content": prompt }],
+        "stream": true,
+    }))
+    .unwrap();
+    let response = create_chat_completion(HeaderMap::new(), None, StreamingJson(request)).await.expect("stream request failed");
+
+    // 读到第一个分块后断开
+    let mut body = response.into_body().into_data_stream();
+    body.next().await.expect("stream ended early").unwrap();
+    drop(body);
+
+    for _ in 0..100 {
+        if CANCELLED_STREAMS.lock().unwrap().contains(&prompt) {
+            println!("✅ Upstream stream cancelled after the client disconnected");
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("stream was not cancelled after the client disconnected");
+}
+
+#[tokio::test]
+async fn test_cancelled_upstream_call_is_logged() {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    init_db("data/init.sql").await.expect("DB init failed");
+
+    println!("=== Testing Cancelled Call Log ===");
+    // 接受连接但从不响应的上游
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/v1/chat/completions", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let mut connections = Vec::new();
+        while let Ok((socket, _)) = listener.accept().await {
+            connections.push(socket);
+        }
+    });
+
+    let cancellation = CancellationToken::new();
+    let metadata = CallMetadata::default().with_cancellation(cancellation.clone());
+    tokio::spawn({
+        let cancellation = cancellation.clone();
+        async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            cancellation.cancel();
+        }
+    });
+
+    let client = BaseClient::new(ClientConfig::new()).unwrap();
+    let started = std::time::Instant::now();
+    let result = CALL_METADATA
+        .scope(metadata.clone(), client.post(&url, json!({ "model": "test" })))
+        .await;
+    assert!(matches!(result, Err(ClientError::Cancelled)));
+    assert!(started.elapsed() < Duration::from_secs(5));
+    println!("✅ Upstream call aborted after {:?}", started.elapsed());
+
+    let id = metadata.last_call_log_id().expect("call log not recorded");
+    let call_log = get_call_log_by_id(SQLITE_POOL.get().unwrap(), &id).await.unwrap().expect("call log missing");
+    assert_eq!(call_log.status_code, CALL_STATUS_CANCELLED);
+    assert_eq!(call_log.error_message.as_deref(), Some("Request cancelled"));
+    println!("✅ Call log recorded with status {}", call_log.status_code);
+}
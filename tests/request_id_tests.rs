@@ -0,0 +1,140 @@
+//! # 请求 ID 传递测试
+//!
+//! 测试 `X-Request-Id` 中间件沿用或生成请求 ID，以及调度响应和调用记录使用同一个请求 ID
+
+use axum::{
+    body::{to_bytes, Body},
+    http::Request,
+    middleware::from_fn,
+    routing::get,
+    Router,
+};
+use mockito::Server;
+use sqlx::{Pool, Sqlite};
+use tower::Service;
+
+use project_rust_learn::dao::{init_sqlite_pool, init_db, SQLITE_POOL};
+use project_rust_learn::dao::cache::init_global_cache;
+use project_rust_learn::dao::call_log::{delete_call_logs_by_model, list_call_logs_by_request_id};
+use project_rust_learn::dao::model::{create_model, delete_model, invalidate_provider_models_cache, Model};
+use project_rust_learn::llm_api::dispatcher::{DispatchRequest, LLMDispatcher, OllamaAdapter, Provider};
+use project_rust_learn::llm_api::ollama::client::OllamaClient;
+use project_rust_learn::llm_api::utils::client::{CallMetadata, CALL_METADATA};
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+use project_rust_learn::web::middleware::request_id::{request_id, MAX_REQUEST_ID_LEN};
+use project_rust_learn::web::middleware::timeout::REQUEST_ID_HEADER;
+
+async fn setup_test_env() -> std::sync::Arc<Pool<Sqlite>> {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+    init_db("data/init.sql").await.expect("DB init failed");
+    init_global_cache(&pool, 3600, 1000).await.expect("Cache init failed");
+    pool
+}
+
+// 处理器返回调度上下文中的请求 ID
+async fn current_request_id() -> String {
+    CallMetadata::current().request_id.unwrap_or_default()
+}
+
+async fn send(header: Option<&str>) -> (String, String) {
+    let mut app = Router::new()
+        .route("/echo", get(current_request_id))
+        .layer(from_fn(request_id));
+    let mut builder = Request::builder().uri("/echo");
+    if let Some(value) = header {
+        builder = builder.header(REQUEST_ID_HEADER, value);
+    }
+    let response = app.call(builder.body(Body::empty()).unwrap()).await.unwrap();
+    let header = response.headers().get(REQUEST_ID_HEADER).unwrap().to_str().unwrap().to_string();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (header, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn test_request_id_middleware() {
+    println!("=== Testing Request ID Middleware ===");
+    let (header, body) = send(Some("req-client-1")).await;
+    assert_eq!(header, "req-client-1");
+    assert_eq!(body, "req-client-1");
+    println!("✅ Client request id reused");
+
+    let (header, body) = send(None).await;
+    assert!(uuid::Uuid::parse_str(&header).is_ok());
+    assert_eq!(body, header);
+    println!("✅ Request id generated when missing: {}", header);
+
+    let too_long = "x".repeat(MAX_REQUEST_ID_LEN + 1);
+    let (header, _) = send(Some(&too_long)).await;
+    assert_ne!(header, too_long);
+    assert!(uuid::Uuid::parse_str(&header).is_ok());
+    println!("✅ Invalid request id replaced");
+}
+
+#[tokio::test]
+async fn test_dispatch_propagates_request_id() {
+    let pool = setup_test_env().await;
+    let model = Model {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: format!("request-id-test-{}", uuid::Uuid::new_v4().simple()),
+        provider: "ollama".to_string(),
+        model_type: "llm".to_string(),
+        base_url: None,
+        is_active: true,
+        health_status: None,
+        last_health_check: None,
+        health_check_interval_seconds: None,
+        cost_per_token_input: None,
+        cost_per_token_output: None,
+        function_tags: None,
+        config: None,
+        project_id: None,
+        created_at: None,
+        updated_at: None,
+    };
+    create_model(&pool, &model).await.expect("create_model failed");
+    invalidate_provider_models_cache("ollama").await;
+
+    println!("=== Testing Request ID Propagation ===");
+    let mut server = Server::new_async().await;
+    let _mock = server.mock("POST", "/api/chat")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(format!(
+            r#"{{"model":"{}","created_at":"2024-01-01T00:00:00Z","message":{{"role":"assistant","content":"hello"}},"done":true,"prompt_eval_count":1,"eval_count":1}}"#,
+            model.name
+        ))
+        .create_async()
+        .await;
+    let dispatcher = LLMDispatcher::new(None);
+    dispatcher.register_client(Box::new(OllamaAdapter::new(OllamaClient::new(server.url()).unwrap()))).await;
+    let request = || {
+        let mut request = DispatchRequest::new(Provider::Ollama, model.name.clone(), vec![Message::user("hi".to_string())]);
+        request.retry_count = Some(0);
+        request
+    };
+
+    // 沿用 Web 层注入的请求 ID
+    let http_request_id = format!("req-{}", uuid::Uuid::new_v4().simple());
+    let metadata = CallMetadata::default().with_request_id(Some(http_request_id.clone()));
+    let response = CALL_METADATA.scope(metadata, dispatcher.dispatch(request())).await.expect("dispatch failed");
+    assert_eq!(response.request_id.as_deref(), Some(http_request_id.as_str()));
+    let logs = list_call_logs_by_request_id(&pool, &http_request_id).await.unwrap();
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].model_id.as_deref(), Some(model.id.as_str()));
+    assert_ne!(logs[0].id, http_request_id);
+    println!("✅ Response and call log share the HTTP request id");
+
+    // 请求中指定的 ID 优先，都没有时自动生成
+    let explicit_id = format!("req-{}", uuid::Uuid::new_v4().simple());
+    let response = dispatcher.dispatch(request().with_request_id(explicit_id.clone())).await.expect("dispatch failed");
+    assert_eq!(response.request_id.as_deref(), Some(explicit_id.as_str()));
+    let response = dispatcher.dispatch(request()).await.expect("dispatch failed");
+    let generated_id = response.request_id.expect("request id missing");
+    assert_eq!(list_call_logs_by_request_id(&pool, &generated_id).await.unwrap().len(), 1);
+    println!("✅ Explicit and generated request ids recorded");
+
+    delete_call_logs_by_model(&pool, &model.id).await.expect("delete_call_logs_by_model failed");
+    delete_model(&pool, &model.id).await.expect("delete_model failed");
+    invalidate_provider_models_cache("ollama").await;
+}
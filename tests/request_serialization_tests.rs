@@ -0,0 +1,146 @@
+//! # 请求体序列化复用测试
+//!
+//! 测试同一次调度中消息列表只序列化一次：重试和 fallback 到其它供应商时复用缓存的 JSON，
+//! 输出与直接序列化一致；BaseClient 重试时发送同一份请求体
+
+use mockito::{Matcher, Server};
+use serde_json::Value;
+
+use project_rust_learn::dao::{init_db, init_sqlite_pool};
+use project_rust_learn::llm_api::ali::client::AliChatRequest;
+use project_rust_learn::llm_api::ollama::client::OllamaChatRequest;
+use project_rust_learn::llm_api::openai::client::OpenAIChatRequest;
+use project_rust_learn::llm_api::utils::client::{
+    BaseClient, CallMetadata, ClientConfig, RetryConfig, CALL_METADATA,
+};
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+use project_rust_learn::metrics::metrics;
+
+const REUSED: &str = "llm_gateway_serialized_messages_reused_total";
+
+fn long_context(turns: usize) -> Vec<Message> {
+    let mut messages = vec![Message::system("You are a helpful assistant.".to_string())];
+    for i in 0..turns {
+        messages.push(Message::user(format!("question {} {}", i, "x".repeat(1024))));
+        messages.push(Message::assistant(format!("answer {} \"quoted\"\n{}", i, "y".repeat(1024))));
+    }
+    messages
+}
+
+#[tokio::test]
+async fn test_messages_serialized_once_per_dispatch() {
+    println!("=== Testing Serialized Messages Reuse ===");
+    let messages = long_context(50);
+    let openai = OpenAIChatRequest::new("gpt-4o".to_string(), messages.clone());
+    let ali = AliChatRequest::new("qwen-plus".to_string(), messages.clone());
+
+    // 调度之外直接序列化
+    let expected_openai = serde_json::to_string(&openai).unwrap();
+    let expected_ali = serde_json::to_string(&ali).unwrap();
+
+    let before = metrics().counter_value(REUSED, &[]);
+    let (openai_first, openai_retry, ali_fallback) = CALL_METADATA
+        .scope(CallMetadata::default(), async {
+            (
+                serde_json::to_string(&openai).unwrap(),
+                serde_json::to_string(&openai).unwrap(),
+                serde_json::to_string(&ali).unwrap(),
+            )
+        })
+        .await;
+    assert_eq!(openai_first, expected_openai);
+    assert_eq!(openai_retry, expected_openai);
+    assert_eq!(ali_fallback, expected_ali);
+    assert!(metrics().counter_value(REUSED, &[]) >= before + 2);
+    println!("✅ Retry and fallback reused the serialized messages");
+
+    // 同一任务中的其它消息不会误用缓存
+    let other = OpenAIChatRequest::new("gpt-4o".to_string(), long_context(3));
+    let (first, second) = CALL_METADATA
+        .scope(CallMetadata::default(), async {
+            (serde_json::to_string(&openai).unwrap(), serde_json::to_string(&other).unwrap())
+        })
+        .await;
+    assert_eq!(first, expected_openai);
+    assert_eq!(second, serde_json::to_string(&other).unwrap());
+    let value: Value = serde_json::from_str(&second).unwrap();
+    assert_eq!(value["messages"].as_array().unwrap().len(), 7);
+
+    // 条数和长度都相同、只有内容不同的消息也重新序列化
+    let edited = OpenAIChatRequest::new("gpt-4o".to_string(), vec![Message::user("answer: yes".to_string())]);
+    let original = OpenAIChatRequest::new("gpt-4o".to_string(), vec![Message::user("answer: no!".to_string())]);
+    let (first, second) = CALL_METADATA
+        .scope(CallMetadata::default(), async {
+            (serde_json::to_string(&original).unwrap(), serde_json::to_string(&edited).unwrap())
+        })
+        .await;
+    assert_eq!(first, serde_json::to_string(&original).unwrap());
+    assert_eq!(second, serde_json::to_string(&edited).unwrap());
+    println!("✅ Different messages serialized again");
+}
+
+#[tokio::test]
+async fn test_retries_send_the_same_body() {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    init_db("data/init.sql").await.expect("DB init failed");
+
+    println!("=== Testing Body Reuse Across Client Retries ===");
+    let request = OpenAIChatRequest::new("gpt-4o".to_string(), long_context(5));
+    let body = serde_json::to_string(&request).unwrap();
+
+    let mut server = Server::new_async().await;
+    let failed = server.mock("POST", "/v1/chat/completions")
+        .match_header("content-type", "application/json")
+        .match_body(Matcher::Exact(body.clone()))
+        .with_status(503)
+        .expect(1)
+        .create_async()
+        .await;
+    let succeeded = server.mock("POST", "/v1/chat/completions")
+        .match_header("content-type", "application/json")
+        .match_body(Matcher::Exact(body))
+        .with_status(200)
+        .with_body("{}")
+        .expect(1)
+        .create_async()
+        .await;
+
+    let config = ClientConfig::new()
+        .with_retry(RetryConfig::new().with_max_attempts(2).with_base_delay(std::time::Duration::from_millis(10)));
+    let client = BaseClient::new(config).unwrap();
+    let response = client.post(&format!("{}/v1/chat/completions", server.url()), &request).await.expect("request failed");
+    assert_eq!(response.status(), 200);
+    failed.assert_async().await;
+    succeeded.assert_async().await;
+    println!("✅ Retry sent the cached body");
+}
+
+#[tokio::test]
+async fn test_image_messages_use_provider_format() {
+    println!("=== Testing Image Message Formats ===");
+    let messages = vec![
+        Message::system("Describe the image.".to_string()),
+        Message::user("what is this?".to_string()).with_images(vec!["data:image/png;base64,iVBORw0KGgoAAA".to_string()]),
+    ];
+    let openai = OpenAIChatRequest::new("gpt-4o".to_string(), messages.clone());
+    let ollama = OllamaChatRequest::new("llava".to_string(), messages);
+
+    // fallback 到图片格式不同的供应商时不复用缓存
+    let (openai_body, ollama_body) = CALL_METADATA
+        .scope(CallMetadata::default(), async {
+            (serde_json::to_value(&openai).unwrap(), serde_json::to_value(&ollama).unwrap())
+        })
+        .await;
+
+    let content = &openai_body["messages"][1]["content"];
+    assert_eq!(content[0]["type"], "text");
+    assert_eq!(content[1]["type"], "image_url");
+    assert_eq!(content[1]["image_url"]["url"], "data:image/png;base64,iVBORw0KGgoAAA");
+    assert!(openai_body["messages"][1].get("images").is_none());
+    assert_eq!(openai_body["messages"][0]["content"], "Describe the image.");
+    println!("✅ OpenAI-compatible body uses image_url content parts");
+
+    assert_eq!(ollama_body["messages"][1]["content"], "what is this?");
+    assert_eq!(ollama_body["messages"][1]["images"][0], "iVBORw0KGgoAAA");
+    println!("✅ Ollama body uses base64 images");
+}
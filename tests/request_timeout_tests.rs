@@ -0,0 +1,65 @@
+//! # 请求级超时测试
+//!
+//! 测试 `DispatchRequest.timeout_ms` 经调度器传到 HTTP 客户端，覆盖客户端配置的请求超时
+
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+
+use project_rust_learn::llm_api::dispatcher::{DispatchConfig, DispatchRequest, LLMDispatcher, OllamaAdapter, Provider};
+use project_rust_learn::llm_api::ollama::client::OllamaClient;
+use project_rust_learn::llm_api::utils::client::{BaseClient, ClientConfig, ClientError, RetryConfig};
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+
+/// 接受连接但从不响应的上游
+async fn silent_upstream() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let mut connections = Vec::new();
+        while let Ok((socket, _)) = listener.accept().await {
+            connections.push(socket);
+        }
+    });
+    format!("http://{}", addr)
+}
+
+fn client_config() -> ClientConfig {
+    // 客户端默认请求超时为 3 分钟
+    ClientConfig::new().with_retry(RetryConfig::new().with_base_delay(Duration::from_millis(10)))
+}
+
+#[tokio::test]
+async fn test_dispatch_request_timeout_overrides_client_timeout() {
+    println!("=== Testing Request-level Timeout ===");
+    let base_url = silent_upstream().await;
+    let dispatcher = LLMDispatcher::new(Some(DispatchConfig {
+        enable_fallback: false,
+        ..Default::default()
+    }));
+    dispatcher.register_client(Box::new(OllamaAdapter::new(OllamaClient::new_with_config(base_url, client_config()).unwrap()))).await;
+
+    let mut request = DispatchRequest::new(Provider::Ollama, "llama3.2".to_string(), vec![Message::user("hi".to_string())]);
+    request.timeout_ms = Some(200);
+    request.retry_count = Some(0);
+
+    let started = Instant::now();
+    let result = dispatcher.dispatch(request).await;
+    assert!(result.is_err());
+    assert!(started.elapsed() < Duration::from_secs(5), "took {:?}", started.elapsed());
+    println!("✅ Request timed out after {:?}", started.elapsed());
+}
+
+#[tokio::test]
+async fn test_post_with_timeout() {
+    let base_url = silent_upstream().await;
+    let client = BaseClient::new(client_config().with_retry(RetryConfig::new().with_max_attempts(1))).unwrap();
+
+    let started = Instant::now();
+    let result = client.post_with_timeout(&base_url, serde_json::json!({}), Some(Duration::from_millis(100))).await;
+    assert!(started.elapsed() < Duration::from_secs(5));
+    match result {
+        Err(ClientError::RetryExhausted { last_error, .. }) => assert!(last_error.contains("timeout"), "{}", last_error),
+        other => panic!("unexpected result: {:?}", other.map(|r| r.status())),
+    }
+    println!("✅ post_with_timeout overrides the configured timeout");
+}
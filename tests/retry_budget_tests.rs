@@ -0,0 +1,133 @@
+//! # 重试预算测试
+//!
+//! 测试调度器和客户端共享重试预算，上游请求总数不超过 retry_count + 1，并遵守 Retry-After 和错误信息中的等待提示
+
+use std::time::Duration;
+use mockito::Server;
+use serde_json::json;
+
+use project_rust_learn::llm_api::dispatcher::{DispatchConfig, DispatchRequest, LLMDispatcher, OllamaAdapter, Provider};
+use project_rust_learn::llm_api::ollama::client::OllamaClient;
+use project_rust_learn::llm_api::utils::client::{
+    parse_retry_after, parse_retry_hint, ClientConfig, RetryBudget, RetryConfig, MAX_RETRY_WAIT,
+};
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+
+async fn dispatcher(base_url: String) -> LLMDispatcher {
+    let config = ClientConfig::new().with_retry(RetryConfig::new().with_base_delay(Duration::from_millis(10)));
+    let dispatcher = LLMDispatcher::new(Some(DispatchConfig {
+        enable_fallback: false,
+        ..Default::default()
+    }));
+    dispatcher.register_client(Box::new(OllamaAdapter::new(OllamaClient::new_with_config(base_url, config).unwrap()))).await;
+    dispatcher
+}
+
+fn request(retry_count: u32) -> DispatchRequest {
+    let mut request = DispatchRequest::new(Provider::Ollama, "llama3.2".to_string(), vec![Message::user("hi".to_string())]);
+    request.retry_count = Some(retry_count);
+    request
+}
+
+#[test]
+fn test_retry_budget_limits_attempts() {
+    let budget = RetryBudget::new(2);
+    let shared = budget.clone();
+    assert!(budget.try_acquire());
+    assert!(shared.try_acquire());
+    assert!(!budget.try_acquire());
+    assert!(shared.is_exhausted());
+    assert_eq!(budget.used(), 2);
+
+    // 默认预算不限制次数
+    let unlimited = RetryBudget::default();
+    assert!((0..10).all(|_| unlimited.try_acquire()));
+
+    assert_eq!(parse_retry_after("3"), Some(Duration::from_secs(3)));
+    assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), Some(Duration::ZERO));
+    assert_eq!(parse_retry_after("soon"), None);
+    assert_eq!(parse_retry_after("-1"), None);
+    assert_eq!(parse_retry_after("1e30"), Some(MAX_RETRY_WAIT));
+    println!("✅ Retry budget shared between clones");
+}
+
+#[tokio::test]
+async fn test_dispatcher_and_client_share_retry_budget() {
+    println!("=== Testing Shared Retry Budget ===");
+    let mut server = Server::new_async().await;
+    // 客户端最多重试 3 次、调度器 retry_count = 2：共享预算后总共只发送 3 次请求
+    let mock = server.mock("POST", "/api/chat")
+        .with_status(503)
+        .with_body("overloaded")
+        .expect(3)
+        .create_async()
+        .await;
+
+    let result = dispatcher(server.url()).await.dispatch(request(2)).await;
+    assert!(result.is_err());
+    mock.assert_async().await;
+    println!("✅ Upstream received 3 requests instead of 9");
+}
+
+#[tokio::test]
+async fn test_long_retry_after_stops_retries() {
+    let mut server = Server::new_async().await;
+    let mock = server.mock("POST", "/api/chat")
+        .with_status(429)
+        .with_header("retry-after", "120")
+        .with_body("rate limited")
+        .expect(1)
+        .create_async()
+        .await;
+
+    let started = std::time::Instant::now();
+    let result = dispatcher(server.url()).await.dispatch(request(3)).await;
+    assert!(result.is_err());
+    assert!(started.elapsed() < Duration::from_secs(5));
+    mock.assert_async().await;
+    println!("✅ Retry-After beyond the retry window stops retries immediately");
+}
+
+#[test]
+fn test_parse_retry_hint_from_error_body() {
+    let hint = |body: &str| parse_retry_hint(body);
+    assert_eq!(hint(r#"{"error":{"message":"Rate limit reached. Please try again in 20s."}}"#), Some(Duration::from_secs(20)));
+    assert_eq!(hint("Please try again in 250ms"), Some(Duration::from_millis(250)));
+    assert_eq!(hint("try again in 6m0s"), Some(Duration::from_secs(360)));
+    assert_eq!(hint("Retry after 1.5 seconds"), Some(Duration::from_millis(1500)));
+    assert_eq!(hint("please retry in 2 minutes"), Some(Duration::from_secs(120)));
+    assert_eq!(hint("service overloaded"), None);
+    assert_eq!(hint("please retry in 99999999999999999999 minutes"), Some(MAX_RETRY_WAIT));
+    println!("✅ Wait hints parsed from error bodies");
+}
+
+#[tokio::test]
+async fn test_error_body_wait_hint_is_honored() {
+    let mut server = Server::new_async().await;
+    // 429 默认不在客户端内重试，响应体带等待提示时按提示等待后重试
+    let limited = server.mock("POST", "/api/chat")
+        .with_status(429)
+        .with_body(r#"{"error":"Rate limit reached. Please try again in 300ms."}"#)
+        .expect(1)
+        .create_async()
+        .await;
+    let ok = server.mock("POST", "/api/chat")
+        .with_status(200)
+        .with_body(json!({
+            "model": "llama3.2",
+            "created_at": "2025-09-09T10:00:00Z",
+            "message": {"role": "assistant", "content": "hello"},
+            "done": true
+        }).to_string())
+        .expect(1)
+        .create_async()
+        .await;
+
+    let started = std::time::Instant::now();
+    let response = dispatcher(server.url()).await.dispatch(request(1)).await.unwrap();
+    assert_eq!(response.content, "hello");
+    assert!(started.elapsed() >= Duration::from_millis(300));
+    limited.assert_async().await;
+    ok.assert_async().await;
+    println!("✅ Retried after the wait hint from the error body");
+}
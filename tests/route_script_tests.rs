@@ -0,0 +1,86 @@
+//! # 路由脚本测试
+//!
+//! 测试 dispatcher 按 Rhai 脚本改写供应商和模型，以及脚本文件的热加载
+
+mod common;
+
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use project_rust_learn::jobs::route_script_reload::spawn_route_script_watcher;
+use project_rust_learn::llm_api::dispatcher::{DispatchConfig, DispatchRequest, LLMDispatcher, Provider};
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+use project_rust_learn::llm_api::utils::route_script::get_route_script_engine;
+use common::MockAdapter;
+
+/// 返回实际处理请求的供应商和模型
+fn echo_adapter(provider: Provider, models: &[&str]) -> MockAdapter {
+    let name = provider.as_str().to_string();
+    MockAdapter::new(provider).with_models(models).with_content(move |request| format!("{}/{}", name, request.model))
+}
+
+async fn create_dispatcher() -> LLMDispatcher {
+    let dispatcher = LLMDispatcher::new(Some(DispatchConfig {
+        enable_fallback: false,
+        ..Default::default()
+    }));
+    dispatcher.register_client(Box::new(echo_adapter(Provider::Ollama, &["llama3.2", "qwen2.5"]))).await;
+    dispatcher.register_client(Box::new(echo_adapter(Provider::Ali, &["qwen-max"]))).await;
+    dispatcher
+}
+
+// 写入脚本并设置递增的修改时间，不依赖文件系统的时间精度
+fn write_script(path: &Path, script: &str, version: u64) {
+    std::fs::write(path, script).unwrap();
+    let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(version);
+    std::fs::File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+}
+
+fn request(tenant_id: &str) -> DispatchRequest {
+    let mut request = DispatchRequest::new(Provider::Ollama, "llama3.2".to_string(), vec![Message::user("hello".to_string())]);
+    request.tenant_id = Some(tenant_id.to_string());
+    request
+}
+
+#[tokio::test]
+async fn test_dispatch_with_route_script() {
+    // 暂停时钟，sleep 在监听任务检查完文件后直接推进虚拟时间
+    tokio::time::pause();
+    let dispatcher = create_dispatcher().await;
+    let path = std::env::temp_dir().join(format!("route_script_{}.rhai", uuid::Uuid::new_v4()));
+
+    write_script(&path, r#"
+        if request.tenant_id == "vip" { #{ provider: "ali", model: "qwen-max" } }
+    "#, 1);
+    let watcher = spawn_route_script_watcher(path.clone(), Duration::from_millis(20));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(get_route_script_engine().is_loaded());
+
+    let response = dispatcher.dispatch(request("vip")).await.expect("dispatch failed");
+    assert_eq!(response.content, "ali/qwen-max");
+    let response = dispatcher.dispatch(request("free")).await.expect("dispatch failed");
+    assert_eq!(response.content, "ollama/llama3.2");
+    println!("✅ 路由脚本按租户改写供应商和模型");
+
+    // 修改脚本后自动重新加载
+    write_script(&path, r#"#{ model: "qwen2.5" }"#, 2);
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    let response = dispatcher.dispatch(request("vip")).await.expect("dispatch failed");
+    assert_eq!(response.content, "ollama/qwen2.5");
+    println!("✅ 路由脚本热加载成功");
+
+    // 编译失败时保留原脚本，运行出错时保持原路由
+    write_script(&path, "if {", 3);
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    let response = dispatcher.dispatch(request("vip")).await.expect("dispatch failed");
+    assert_eq!(response.content, "ollama/qwen2.5");
+
+    get_route_script_engine().load_script(r#"#{ provider: 42 }"#).unwrap();
+    let response = dispatcher.dispatch(request("vip")).await.expect("dispatch failed");
+    assert_eq!(response.content, "ollama/llama3.2");
+    println!("✅ 脚本出错时保持原路由");
+
+    watcher.abort();
+    get_route_script_engine().clear();
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,66 @@
+use std::time::Duration;
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    middleware::from_fn_with_state,
+    routing::get,
+    Router,
+};
+use serde_json::Value;
+use tower::Service;
+
+use project_rust_learn::web::middleware::timeout::{route_timeout, RouteTimeouts, REQUEST_ID_HEADER};
+
+async fn slow_handler() -> &'static str {
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    "done"
+}
+
+fn app(limit: Duration) -> Router {
+    Router::new()
+        .route("/slow", get(slow_handler))
+        .route_layer(from_fn_with_state(limit, route_timeout))
+}
+
+async fn send(limit: Duration, request: Request<Body>) -> axum::response::Response {
+    app(limit).call(request).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_route_timeout_returns_504_with_request_id() {
+    let request = Request::builder()
+        .uri("/slow")
+        .header(REQUEST_ID_HEADER, "req-timeout-test")
+        .body(Body::empty())
+        .unwrap();
+    let response = send(Duration::from_millis(50), request).await;
+
+    assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+    assert_eq!(response.headers().get(REQUEST_ID_HEADER).unwrap(), "req-timeout-test");
+    let body: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+    assert_eq!(body["error"]["code"], "timeout");
+    assert_eq!(body["error"]["request_id"], "req-timeout-test");
+    println!("✅ Slow handler timed out: {}", body);
+
+    // 未提供请求 ID 时自动生成
+    let request = Request::builder().uri("/slow").body(Body::empty()).unwrap();
+    let response = send(Duration::from_millis(50), request).await;
+    assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+    assert!(response.headers().get(REQUEST_ID_HEADER).is_some());
+}
+
+#[tokio::test]
+async fn test_route_within_timeout_passes_through() {
+    let request = Request::builder().uri("/slow").body(Body::empty()).unwrap();
+    let response = send(Duration::from_secs(2), request).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(&body[..], b"done");
+}
+
+#[test]
+fn test_route_timeouts_defaults() {
+    let timeouts = RouteTimeouts::default();
+    assert!(timeouts.admin < timeouts.chat);
+}
@@ -0,0 +1,151 @@
+//! # 路由请求头测试
+//!
+//! 测试 `X-LLM-Provider` 固定供应商、`X-LLM-Fallback: off` 关闭 fallback，
+//! 未携带请求头时保持自动路由，以及请求头取值不合法时返回 400
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use axum::{
+    body::{to_bytes, Body},
+    http::{HeaderMap, Request, StatusCode},
+    middleware::from_fn,
+    response::Response,
+    routing::post,
+    Router,
+};
+use serde_json::{json, Value};
+use tower::Service;
+
+use project_rust_learn::llm_api::dispatcher::{
+    DispatchConfig, DispatchRequest, LLMDispatcher, LLMError, Provider, RequestPriority, GLOBAL_DISPATCHER,
+};
+use project_rust_learn::web::handlers::chat_completion_handler::create_chat_completion;
+use project_rust_learn::web::middleware::routing::{routing_override, RoutingOverride};
+use common::{response, MockAdapter};
+
+/// 按供应商固定成功或失败的测试适配器
+fn fixed_adapter(provider: Provider, fail: bool, calls: &Arc<AtomicUsize>) -> MockAdapter {
+    let name = provider.as_str().to_string();
+    let reply_provider = provider.clone();
+    MockAdapter::new(provider)
+        .with_models(&["shared-model"])
+        .with_call_counter(calls.clone())
+        .with_reply(move |request| {
+            if fail {
+                return Err(LLMError::ApiError(format!("{} is down", name)));
+            }
+            Ok(response(reply_provider.clone(), &request.model, format!("from {}", name)))
+        })
+}
+
+/// Ollama 总是失败、OpenAI 总是成功；两者都提供 shared-model，自动路由选中 Ollama
+async fn setup_dispatcher() -> (Arc<AtomicUsize>, Arc<AtomicUsize>) {
+    static CALLS: std::sync::OnceLock<(Arc<AtomicUsize>, Arc<AtomicUsize>)> = std::sync::OnceLock::new();
+    if let Some(calls) = CALLS.get() {
+        return calls.clone();
+    }
+
+    let ollama_calls = Arc::new(AtomicUsize::new(0));
+    let openai_calls = Arc::new(AtomicUsize::new(0));
+    let config = DispatchConfig {
+        default_retry_count: 0,
+        fallback_providers: vec![Provider::Ollama, Provider::OpenAI],
+        ..DispatchConfig::default()
+    };
+    let dispatcher = LLMDispatcher::new(Some(config));
+    dispatcher.register_client(Box::new(fixed_adapter(Provider::Ollama, true, &ollama_calls))).await;
+    dispatcher.register_client(Box::new(fixed_adapter(Provider::OpenAI, false, &openai_calls))).await;
+    GLOBAL_DISPATCHER.set(Arc::new(dispatcher)).ok();
+    CALLS.get_or_init(|| (ollama_calls, openai_calls)).clone()
+}
+
+fn app() -> Router {
+    Router::new().route("/v1/chat/completions", post(create_chat_completion).route_layer(from_fn(routing_override)))
+}
+
+async fn send(app: &mut Router, model: &str, headers: &[(&str, &str)]) -> (StatusCode, Value) {
+    let mut request = Request::post("/v1/chat/completions").header("content-type", "application/json");
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let body = json!({"model": model, "messages": [{"role": "user", "content": "hello"}]}).to_string();
+    let response: Response = app.call(request.body(Body::from(body)).unwrap()).await.unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn test_routing_headers_control_provider_and_fallback() {
+    let (ollama_calls, openai_calls) = setup_dispatcher().await;
+    let mut app = app();
+
+    println!("=== Testing Automatic Routing ===");
+    let (status, body) = send(&mut app, "shared-model", &[]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["choices"][0]["message"]["content"], "from openai");
+    assert_eq!(ollama_calls.load(Ordering::SeqCst), 1);
+    println!("✅ Without headers the request falls back from ollama to openai");
+
+    println!("=== Testing X-LLM-Fallback: off ===");
+    let (status, body) = send(&mut app, "shared-model", &[("X-LLM-Fallback", "off")]).await;
+    assert_ne!(status, StatusCode::OK);
+    assert!(body["error"]["message"].as_str().unwrap().contains("ollama is down"));
+    assert_eq!(ollama_calls.load(Ordering::SeqCst), 2);
+    assert_eq!(openai_calls.load(Ordering::SeqCst), 1);
+    println!("✅ Fallback disabled, upstream error returned as is");
+
+    println!("=== Testing X-LLM-Provider ===");
+    let (status, body) = send(&mut app, "openai/shared-model", &[("X-LLM-Provider", "OpenAI"), ("X-LLM-Fallback", "off")]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["choices"][0]["message"]["content"], "from openai");
+    assert_eq!(body["model"], "shared-model");
+    assert_eq!(ollama_calls.load(Ordering::SeqCst), 2);
+    println!("✅ Pinned provider skips automatic routing");
+}
+
+#[tokio::test]
+async fn test_invalid_routing_headers_rejected() {
+    // 中间件直接拒绝，不会调用 dispatcher
+    let mut app = app();
+
+    println!("=== Testing Invalid Routing Headers ===");
+    let (status, body) = send(&mut app, "shared-model", &[("X-LLM-Fallback", "maybe")]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["type"], "invalid_request_error");
+    println!("✅ Invalid X-LLM-Fallback rejected with 400");
+}
+
+#[test]
+fn test_routing_override_from_headers() {
+    let mut headers = HeaderMap::new();
+    assert_eq!(RoutingOverride::from_headers(&headers), Ok(RoutingOverride::default()));
+
+    headers.insert("x-llm-provider", " Azure-OpenAI ".parse().unwrap());
+    headers.insert("x-llm-fallback", "OFF".parse().unwrap());
+    let routing = RoutingOverride::from_headers(&headers).unwrap();
+    assert_eq!(routing.provider, Some(Provider::Azure));
+    assert_eq!(routing.fallback, Some(false));
+
+    headers.insert("x-llm-provider", "my-vllm".parse().unwrap());
+    headers.insert("x-llm-fallback", "".parse().unwrap());
+    let routing = RoutingOverride::from_headers(&headers).unwrap();
+    assert_eq!(routing.provider, Some(Provider::Custom("my-vllm".to_string())));
+    assert_eq!(routing.fallback, None);
+
+    let mut request = DispatchRequest::new(Provider::Ollama, "shared-model".to_string(), vec![]);
+    routing.apply(&mut request);
+    assert_eq!(request.provider, Provider::Custom("my-vllm".to_string()));
+    assert_eq!(request.pin_provider, Some(true));
+    assert_eq!(request.fallback, None);
+    assert_eq!(request.priority, None);
+
+    headers.insert("x-priority", "High".parse().unwrap());
+    RoutingOverride::from_headers(&headers).unwrap().apply(&mut request);
+    assert_eq!(request.priority, Some(RequestPriority::High));
+
+    headers.insert("x-priority", "urgent".parse().unwrap());
+    assert!(RoutingOverride::from_headers(&headers).unwrap_err().contains("x-priority"));
+}
@@ -0,0 +1,37 @@
+//! # 启动数据初始化测试
+
+use sqlx::SqlitePool;
+
+use project_rust_learn::dao::model::get_model_by_provider_and_name;
+use project_rust_learn::dao::provider::get_provider_by_name;
+use project_rust_learn::dao::run_migrations;
+use project_rust_learn::dao::seed::seed_default_data;
+
+#[tokio::test]
+async fn test_seed_default_data_is_idempotent() {
+    // 内置数据使用固定的供应商和模型名称，使用独立的数据库，避免在共享库中留下数据
+    let path = std::env::temp_dir().join(format!("seed-test-{}.db", uuid::Uuid::new_v4().simple()));
+    let pool = SqlitePool::connect(&format!("sqlite://{}?mode=rwc", path.display())).await.unwrap();
+    run_migrations(&pool).await.expect("migration failed");
+
+    println!("=== Testing Default Data Seeding ===");
+
+    let report = seed_default_data(&pool).await.expect("Failed to seed default data");
+    for provider in ["ollama", "ali", "openai"] {
+        assert!(get_provider_by_name(&pool, provider).await.unwrap().is_some());
+    }
+    let qwen = get_model_by_provider_and_name(&pool, "ali", "qwen-plus").await.unwrap()
+        .expect("qwen-plus should be seeded");
+    assert!(qwen.cost_per_token_input.unwrap() > 0.0);
+    assert!(report.models_created.contains(&"ali/qwen-plus".to_string()));
+    println!("✅ Providers and models seeded: {:?}", report.models_created);
+
+    // 再次执行不会重复创建
+    let second = seed_default_data(&pool).await.expect("Failed to seed default data");
+    assert!(second.providers_created.is_empty());
+    assert!(second.models_created.is_empty());
+    println!("✅ Seeding is idempotent");
+
+    pool.close().await;
+    std::fs::remove_file(path).ok();
+}
@@ -0,0 +1,104 @@
+//! # 影子流量测试
+//!
+//! 测试请求按配置镜像到影子供应商、调用方只收到主请求的结果，
+//! 以及主请求和镜像请求的响应、耗时写入对比记录
+
+mod common;
+
+use std::time::Duration;
+
+use project_rust_learn::dao::{init_db, init_sqlite_pool, SQLITE_POOL};
+use project_rust_learn::dao::cache::init_global_cache;
+use project_rust_learn::dao::shadow_comparison::{list_shadow_comparisons, ShadowComparison, ShadowComparisonFilter};
+use project_rust_learn::llm_api::dispatcher::{DispatchConfig, DispatchRequest, LLMDispatcher, LLMError, Provider};
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+use project_rust_learn::llm_api::utils::shadow::ShadowMirror;
+use common::{response, MockAdapter};
+
+/// 返回固定内容或固定错误的测试适配器
+fn fixed_adapter(provider: Provider, reply: Result<String, String>) -> MockAdapter {
+    let reply_provider = provider.clone();
+    MockAdapter::new(provider)
+        .with_models(&["primary-model", "shadow-model"])
+        .with_reply(move |request| {
+            let content = reply.clone().map_err(LLMError::ApiError)?;
+            Ok(response(reply_provider.clone(), &request.model, content))
+        })
+}
+
+/// 使用唯一的自定义供应商，避免影响其他测试
+async fn setup(shadow_reply: Result<String, String>) -> (LLMDispatcher, Provider) {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+    init_db("data/init.sql").await.expect("DB init failed");
+    init_global_cache(&pool, 3600, 1000).await.expect("Cache init failed");
+
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let primary = Provider::Custom(format!("primary-{}", suffix));
+    let shadow = Provider::Custom(format!("shadow-{}", suffix));
+    let dispatcher = LLMDispatcher::new(Some(DispatchConfig {
+        enable_fallback: false,
+        default_retry_count: 0,
+        shadow: Some(ShadowMirror {
+            provider: shadow.clone(),
+            model: Some("shadow-model".to_string()),
+            percent: 100,
+            source_providers: vec![primary.clone()],
+        }),
+        ..DispatchConfig::default()
+    }));
+    dispatcher.register_client(Box::new(fixed_adapter(primary.clone(), Ok("primary reply".to_string())))).await;
+    dispatcher.register_client(Box::new(fixed_adapter(shadow, shadow_reply))).await;
+    (dispatcher, primary)
+}
+
+/// 镜像请求在后台执行，轮询等待对比记录写入
+async fn wait_for_comparison(primary: &Provider) -> ShadowComparison {
+    let pool = SQLITE_POOL.get().unwrap();
+    let filter = ShadowComparisonFilter { primary_provider: Some(primary.as_str().to_string()), ..Default::default() };
+    for _ in 0..50 {
+        if let Some(comparison) = list_shadow_comparisons(pool, &filter).await.expect("list failed").into_iter().next() {
+            return comparison;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("shadow comparison was not recorded");
+}
+
+fn request(provider: &Provider) -> DispatchRequest {
+    DispatchRequest::new(provider.clone(), "primary-model".to_string(), vec![Message::user("hello".to_string())])
+}
+
+#[tokio::test]
+async fn test_shadow_request_recorded() {
+    let (dispatcher, primary) = setup(Ok("shadow reply".to_string())).await;
+
+    println!("=== Testing Shadow Mirror ===");
+    let response = dispatcher.dispatch(request(&primary)).await.expect("dispatch failed");
+    assert_eq!(response.content, "primary reply");
+    assert_eq!(response.provider, primary);
+    println!("✅ Caller received primary response");
+
+    let comparison = wait_for_comparison(&primary).await;
+    assert_eq!(comparison.primary_model, "primary-model");
+    assert_eq!(comparison.primary_response.as_deref(), Some("primary reply"));
+    assert!(comparison.shadow_provider.starts_with("shadow-"));
+    assert_eq!(comparison.shadow_model, "shadow-model");
+    assert_eq!(comparison.shadow_response.as_deref(), Some("shadow reply"));
+    assert_eq!(comparison.shadow_error, None);
+    println!("✅ Primary and shadow responses recorded");
+}
+
+#[tokio::test]
+async fn test_shadow_failure_does_not_affect_caller() {
+    let (dispatcher, primary) = setup(Err("shadow down".to_string())).await;
+
+    println!("=== Testing Shadow Failure ===");
+    let response = dispatcher.dispatch(request(&primary)).await.expect("dispatch failed");
+    assert_eq!(response.content, "primary reply");
+
+    let comparison = wait_for_comparison(&primary).await;
+    assert_eq!(comparison.shadow_response, None);
+    assert!(comparison.shadow_error.unwrap().contains("shadow down"));
+    println!("✅ Shadow failure recorded without affecting the caller");
+}
@@ -0,0 +1,74 @@
+//! # 流式用量统计测试
+//!
+//! 测试按供应商的流格式识别结束标记和用量，以及阿里云流式调用的调用记录写入 token 数
+
+use mockito::Server;
+
+use project_rust_learn::dao::{init_sqlite_pool, init_db, SQLITE_POOL};
+use project_rust_learn::dao::call_log::list_call_logs_by_request_id;
+use project_rust_learn::llm_api::ali::client::{AliChatRequest, AliClient};
+use project_rust_learn::llm_api::utils::client::{CallMetadata, CALL_METADATA};
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+use project_rust_learn::llm_api::utils::stream_accounting::{
+    OllamaStreamAccounting, OpenAIStreamAccounting, StreamAccounting, StreamLine, StreamUsage,
+};
+
+#[test]
+fn test_parse_stream_lines() {
+    let ollama = OllamaStreamAccounting;
+    assert_eq!(ollama.parse_line(r#"{"message":{"content":"hi"},"done":false}"#), StreamLine::default());
+    let line = ollama.parse_line(r#"{"done":true,"prompt_eval_count":12,"eval_count":34}"#);
+    assert!(line.done);
+    assert_eq!(line.usage, Some(StreamUsage { input_tokens: 12, output_tokens: 34 }));
+    println!("✅ Ollama usage read from the done line");
+
+    let openai = OpenAIStreamAccounting;
+    assert_eq!(openai.parse_line(r#"data: {"choices":[{"delta":{"content":"hi"}}],"usage":null}"#), StreamLine::default());
+    let line = openai.parse_line(r#"data: {"choices":[],"usage":{"prompt_tokens":5,"completion_tokens":7,"total_tokens":12}}"#);
+    assert!(!line.done);
+    assert_eq!(line.usage, Some(StreamUsage { input_tokens: 5, output_tokens: 7 }));
+    assert_eq!(openai.parse_line("data: [DONE]"), StreamLine { done: true, usage: None });
+    assert_eq!(openai.parse_line(": keep-alive"), StreamLine::default());
+    println!("✅ SSE usage read from the chunk before [DONE]");
+}
+
+#[tokio::test]
+async fn test_ali_stream_call_log_records_tokens() {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    init_db("data/init.sql").await.expect("DB init failed");
+    let pool = SQLITE_POOL.get().unwrap().clone();
+
+    println!("=== Testing Ali Stream Token Accounting ===");
+    let mut server = Server::new_async().await;
+    let body = concat!(
+        "data: {\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"created\":1,\"model\":\"qwen-plus\",\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"hel\"},\"finish_reason\":null}]}\n\n",
+        "data: {\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"created\":1,\"model\":\"qwen-plus\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"lo\"},\"finish_reason\":\"stop\"}]}\n\n",
+        "data: {\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"created\":1,\"model\":\"qwen-plus\",\"choices\":[],\"usage\":{\"prompt_tokens\":9,\"completion_tokens\":2,\"total_tokens\":11}}\n\n",
+        "data: [DONE]\n\n",
+    );
+    let _mock = server.mock("POST", "/compatible-mode/v1/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "text/event-stream")
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let client = AliClient::new_with_base_url("test-key".to_string(), server.url()).unwrap();
+    let request = AliChatRequest::new("qwen-plus".to_string(), vec![Message::user("hi".to_string())]).with_stream_usage(true);
+    let request_id = format!("req-{}", uuid::Uuid::new_v4().simple());
+    let metadata = CallMetadata::default().with_request_id(Some(request_id.clone()));
+    let mut content = String::new();
+    CALL_METADATA.scope(metadata, client.chat_stream(request, |chunk| {
+        if let Some(delta) = chunk.choices.first().and_then(|choice| choice.delta.content.clone()) {
+            content.push_str(&delta);
+        }
+        true
+    })).await.expect("chat_stream failed");
+    assert_eq!(content, "hello");
+
+    let logs = list_call_logs_by_request_id(&pool, &request_id).await.unwrap();
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].status_code, 200);
+    assert_eq!((logs[0].tokens_input, logs[0].tokens_output), (9, 2));
+    println!("✅ Stream call log records usage: in={}, out={}", logs[0].tokens_input, logs[0].tokens_output);
+}
@@ -0,0 +1,142 @@
+//! # Stream 形式的流式接口测试
+//!
+//! 测试 `post_stream_iter` / `chat_stream_iter` 以 Stream 返回流式响应，
+//! 以及 Ollama 客户端实现的 `ChatClientTrait::chat_stream`
+
+use futures_util::StreamExt;
+use mockito::Server;
+
+use project_rust_learn::dao::{init_sqlite_pool, init_db};
+use project_rust_learn::llm_api::ali::client::{AliChatRequest, AliClient};
+use project_rust_learn::llm_api::ollama::client::{OllamaChatRequest, OllamaClient, OllamaError};
+use project_rust_learn::llm_api::openai::client::{OpenAIChatRequest, OpenAIClient};
+use project_rust_learn::llm_api::utils::chat_traits::{ChatClientTrait, ChatResponseTrait};
+use project_rust_learn::llm_api::utils::client::{BaseClient, ClientConfig, ClientError, RetryConfig};
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+use project_rust_learn::llm_api::utils::stream_accounting::OllamaStreamAccounting;
+
+async fn setup_database() {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    init_db("data/init.sql").await.expect("DB init failed");
+}
+
+fn ollama_stream_body() -> String {
+    [
+        r#"{"model":"llama2","created_at":"2024-01-01T00:00:00Z","message":{"role":"assistant","content":"hel"},"done":false}"#,
+        r#"{"model":"llama2","created_at":"2024-01-01T00:00:00Z","message":{"role":"assistant","content":"lo"},"done":false}"#,
+        r#"{"model":"llama2","created_at":"2024-01-01T00:00:00Z","message":{"role":"assistant","content":""},"done":true,"prompt_eval_count":3,"eval_count":2}"#,
+    ].join("\n")
+}
+
+#[tokio::test]
+async fn test_post_stream_iter_lines_and_errors() {
+    setup_database().await;
+
+    println!("=== Testing post_stream_iter ===");
+    let mut server = Server::new_async().await;
+    let _ok = server.mock("POST", "/stream")
+        .with_status(200)
+        .with_body(ollama_stream_body())
+        .create_async()
+        .await;
+    let _err = server.mock("POST", "/bad")
+        .with_status(400)
+        .with_body(r#"{"error":"bad request"}"#)
+        .create_async()
+        .await;
+
+    let config = ClientConfig::new().with_retry(RetryConfig::new().with_max_attempts(1));
+    let client = BaseClient::new(config).unwrap();
+    let lines: Vec<_> = client.post_stream_iter(&format!("{}/stream", server.url()), serde_json::json!({}), OllamaStreamAccounting)
+        .collect()
+        .await;
+    assert_eq!(lines.len(), 3);
+    assert!(lines.iter().all(|line| line.is_ok()));
+    println!("✅ Lines yielded in order");
+
+    let mut stream = client.post_stream_iter(&format!("{}/bad", server.url()), serde_json::json!({}), OllamaStreamAccounting);
+    match stream.next().await {
+        Some(Err(ClientError::LLMApi { status_code, .. })) => assert_eq!(status_code, Some(400)),
+        other => panic!("expected API error, got {:?}", other),
+    }
+    assert!(stream.next().await.is_none());
+    println!("✅ Upstream error yielded as the last item");
+}
+
+#[tokio::test]
+async fn test_ollama_chat_stream_iter() {
+    setup_database().await;
+
+    println!("=== Testing Ollama chat_stream_iter ===");
+    let mut server = Server::new_async().await;
+    let _mock = server.mock("POST", "/api/chat")
+        .with_status(200)
+        .with_header("content-type", "application/x-ndjson")
+        .with_body(ollama_stream_body())
+        .expect_at_least(1)
+        .create_async()
+        .await;
+    let client = OllamaClient::new(server.url()).unwrap();
+    let request = || OllamaChatRequest::new("llama2".to_string(), vec![Message::user("hi".to_string())]);
+
+    let chunks: Vec<_> = client.chat_stream_iter(request()).unwrap().collect().await;
+    let content: String = chunks.iter().map(|chunk| chunk.as_ref().unwrap().get_content().unwrap_or_default()).collect();
+    assert_eq!(content, "hello");
+    assert!(chunks.last().unwrap().as_ref().unwrap().is_done());
+    println!("✅ Chunks parsed from the stream");
+
+    // 通过 ChatClientTrait 使用
+    let mut stream = ChatClientTrait::chat_stream(&client, request()).await.unwrap();
+    let first = stream.next().await.unwrap().unwrap();
+    assert_eq!(first.get_content().as_deref(), Some("hel"));
+    drop(stream);
+    assert_eq!(client.get_client_type(), "ollama");
+    println!("✅ ChatClientTrait::chat_stream returns a Stream, dropping it stops reading");
+
+    // 请求校验失败时直接返回错误
+    let invalid = OllamaChatRequest::new(String::new(), vec![Message::user("hi".to_string())]);
+    assert!(matches!(client.chat_stream_iter(invalid), Err(OllamaError::InvalidRequest(_))));
+    println!("✅ Invalid request rejected before sending");
+}
+
+#[tokio::test]
+async fn test_sse_chat_stream_iter() {
+    setup_database().await;
+
+    println!("=== Testing SSE chat_stream_iter ===");
+    let mut server = Server::new_async().await;
+    let body = concat!(
+        "data: {\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"created\":1,\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"hel\"},\"finish_reason\":null}]}\n\n",
+        ": keep-alive\n\n",
+        "data: {\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"created\":1,\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"lo\"},\"finish_reason\":\"stop\"}]}\n\n",
+        "data: [DONE]\n\n",
+    );
+    let _ali = server.mock("POST", "/compatible-mode/v1/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "text/event-stream")
+        .with_body(body)
+        .create_async()
+        .await;
+    let _openai = server.mock("POST", "/v1/chat/completions")
+        .with_status(200)
+        .with_header("content-type", "text/event-stream")
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let ali = AliClient::new_with_base_url("test-key".to_string(), server.url()).unwrap();
+    let request = AliChatRequest::new("qwen-plus".to_string(), vec![Message::user("hi".to_string())]);
+    let content: String = ali.chat_stream_iter(request).unwrap()
+        .map(|chunk| chunk.unwrap().choices.first().and_then(|choice| choice.delta.content.clone()).unwrap_or_default())
+        .collect()
+        .await;
+    assert_eq!(content, "hello");
+    println!("✅ Ali chunks parsed, keep-alive and [DONE] skipped");
+
+    let openai = OpenAIClient::new_with_base_url("sk-test".to_string(), format!("{}/v1", server.url())).unwrap();
+    let request = OpenAIChatRequest::new("gpt-4o".to_string(), vec![Message::user("hi".to_string())]);
+    let chunks: Vec<_> = openai.chat_stream_iter(request).unwrap().collect().await;
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(chunks[1].as_ref().unwrap().choices[0].finish_reason.as_deref(), Some("stop"));
+    println!("✅ OpenAI chunks parsed");
+}
@@ -0,0 +1,129 @@
+//! # 结构化输出测试
+//!
+//! 测试 response_format 转换为各供应商的请求字段，以及开启 validate_response 后
+//! 返回内容不符合 Schema 时要求模型修正一次、修正后仍不符合时返回错误
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+use mockito::{Matcher, Server};
+use serde_json::json;
+
+use project_rust_learn::dao::{init_db, init_sqlite_pool};
+use project_rust_learn::llm_api::ali::client::AliClient;
+use project_rust_learn::llm_api::dispatcher::{
+    AliAdapter, DispatchConfig, DispatchRequest, JsonSchemaFormat, LLMClientAdapter, LLMDispatcher, LLMError, Provider,
+    ResponseFormat,
+};
+use project_rust_learn::llm_api::utils::client::ClientConfig;
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+use common::MockAdapter;
+
+fn person_format() -> ResponseFormat {
+    ResponseFormat::JsonSchema {
+        json_schema: JsonSchemaFormat {
+            name: "person".to_string(),
+            description: None,
+            schema: json!({
+                "type": "object",
+                "properties": {"name": {"type": "string"}, "age": {"type": "integer"}},
+                "required": ["name", "age"]
+            }),
+            strict: Some(true),
+        },
+    }
+}
+
+async fn scripted_dispatcher(replies: Vec<&'static str>) -> (LLMDispatcher, Provider, Arc<Mutex<Vec<DispatchRequest>>>) {
+    // 使用唯一的自定义供应商，避免影响其他测试
+    let provider = Provider::Custom(format!("json-{}", uuid::Uuid::new_v4().simple()));
+    // 依次返回预设内容
+    let replies = Mutex::new(replies);
+    let adapter = MockAdapter::new(provider.clone())
+        .with_models(&["json-model"])
+        .with_content(move |_| replies.lock().unwrap().remove(0).to_string());
+    let requests = adapter.requests();
+    let dispatcher = LLMDispatcher::new(Some(DispatchConfig { enable_fallback: false, ..Default::default() }));
+    dispatcher.register_client(Box::new(adapter)).await;
+    (dispatcher, provider, requests)
+}
+
+fn person_request(provider: &Provider) -> DispatchRequest {
+    DispatchRequest::new(provider.clone(), "json-model".to_string(), vec![Message::user("Describe Tom".to_string())])
+        .with_response_format(person_format())
+        .with_validate_response(true)
+}
+
+#[tokio::test]
+async fn test_invalid_response_repaired_once() {
+    println!("=== Testing Structured Output Repair ===");
+    let (dispatcher, provider, requests) = scripted_dispatcher(vec![
+        "{\"name\": \"Tom\", \"age\": \"three\"}",
+        "{\"name\": \"Tom\", \"age\": 3}",
+    ]).await;
+
+    let response = dispatcher.dispatch(person_request(&provider)).await.expect("dispatch failed");
+    assert_eq!(response.content, "{\"name\": \"Tom\", \"age\": 3}");
+
+    // 修正请求带上了上一轮的回答和校验错误
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    let repair = &requests[1].messages;
+    assert_eq!(repair.len(), 3);
+    assert_eq!(repair[1].role, "assistant");
+    assert!(repair[2].content.contains("/age: expected integer, got string"));
+    println!("✅ Invalid JSON repaired with one retry");
+}
+
+#[tokio::test]
+async fn test_repair_failure_returns_error() {
+    println!("=== Testing Structured Output Repair Failure ===");
+    let (dispatcher, provider, requests) = scripted_dispatcher(vec!["not json", "still not json"]).await;
+
+    let result = dispatcher.dispatch(person_request(&provider)).await;
+    assert!(matches!(result, Err(LLMError::ApiError(ref msg)) if msg.contains("response_format")));
+    assert_eq!(requests.lock().unwrap().len(), 2);
+    println!("✅ Repair attempted only once");
+
+    // 未开启校验时原样返回
+    let (dispatcher, provider, _) = scripted_dispatcher(vec!["not json"]).await;
+    let request = person_request(&provider).with_validate_response(false);
+    assert_eq!(dispatcher.dispatch(request).await.expect("dispatch failed").content, "not json");
+    println!("✅ Validation is opt-in");
+}
+
+#[tokio::test]
+async fn test_response_format_sent_to_provider() {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    init_db("data/init.sql").await.expect("DB init failed");
+
+    println!("=== Testing response_format Passthrough ===");
+    let mut server = Server::new_async().await;
+    let mock = server.mock("POST", "/compatible-mode/v1/chat/completions")
+        .match_body(Matcher::PartialJson(json!({
+            "response_format": {"type": "json_schema", "json_schema": {"name": "person", "strict": true}}
+        })))
+        .with_status(200)
+        .with_body(json!({
+            "id": "chatcmpl-json",
+            "object": "chat.completion",
+            "created": 1757412000,
+            "model": "qwen-plus",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "{\"name\": \"Tom\", \"age\": 3}"},
+                "finish_reason": "stop"
+            }]
+        }).to_string())
+        .create_async()
+        .await;
+
+    let http_client = reqwest::Client::builder().no_proxy().build().unwrap();
+    let client = AliClient::new_with_client("sk-test".to_string(), server.url(), ClientConfig::default(), http_client).unwrap();
+    let request = DispatchRequest::new(Provider::Ali, "qwen-plus".to_string(), vec![Message::user("Describe Tom".to_string())])
+        .with_response_format(person_format());
+    let response = AliAdapter::new(client).generate(&request).await.expect("generate failed");
+    assert_eq!(response.content, "{\"name\": \"Tom\", \"age\": 3}");
+    mock.assert_async().await;
+    println!("✅ json_schema forwarded as response_format");
+}
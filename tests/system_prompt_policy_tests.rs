@@ -0,0 +1,145 @@
+//! # 系统提示词策略测试
+//!
+//! 测试 `models.config` 中的模型策略和按项目配置的策略在调度时改写 system 消息：
+//! prepend 保留调用方的 system 消息、replace 替换、forbid_client_system 拒绝携带 system 消息的请求，
+//! 以及模型策略优先于项目策略
+
+mod common;
+
+use std::sync::Mutex;
+
+use project_rust_learn::dao::cache::init_global_cache;
+use project_rust_learn::dao::model::{create_model, delete_model, sync_model_cache, update_model, Model};
+use project_rust_learn::dao::project::{create_project, delete_project, Project};
+use project_rust_learn::dao::{init_db, init_sqlite_pool, SQLITE_POOL};
+use project_rust_learn::llm_api::dispatcher::{DispatchConfig, DispatchRequest, LLMDispatcher, LLMError, Provider};
+use project_rust_learn::llm_api::utils::client::{CallMetadata, CALL_METADATA};
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+use project_rust_learn::llm_api::utils::system_prompt_policy::{set_project_policy, SystemPromptMode, SystemPromptPolicy};
+use common::MockAdapter;
+
+const POLICY_MODEL: &str = "policy-model";
+const PLAIN_MODEL: &str = "plain-model";
+
+/// 取出每次调用发往上游的 (role, content) 列表
+fn take_sent(requests: &Mutex<Vec<DispatchRequest>>) -> Vec<Vec<(String, String)>> {
+    requests.lock().unwrap().drain(..)
+        .map(|request| request.messages.iter().map(|m| (m.role.clone(), m.content.clone())).collect())
+        .collect()
+}
+
+fn model_record(provider: &str, name: &str, config: Option<String>) -> Model {
+    Model {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        provider: provider.to_string(),
+        model_type: "llm".to_string(),
+        base_url: None,
+        is_active: true,
+        health_status: None,
+        last_health_check: None,
+        health_check_interval_seconds: None,
+        cost_per_token_input: None,
+        cost_per_token_output: None,
+        function_tags: None,
+        config,
+        project_id: None,
+        created_at: None,
+        updated_at: None,
+    }
+}
+
+fn policy_config(mode: &str, prompt: Option<&str>) -> Option<String> {
+    let policy = match prompt {
+        Some(prompt) => serde_json::json!({ "mode": mode, "prompt": prompt }),
+        None => serde_json::json!({ "mode": mode }),
+    };
+    Some(serde_json::json!({ "system_prompt_policy": policy }).to_string())
+}
+
+fn chat(provider: &Provider, model: &str) -> DispatchRequest {
+    DispatchRequest::new(provider.clone(), model.to_string(), vec![
+        Message::system("client prompt".to_string()),
+        Message::user("hi".to_string()),
+    ])
+}
+
+fn sent_pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs.iter().map(|(role, content)| (role.to_string(), content.to_string())).collect()
+}
+
+#[tokio::test]
+async fn test_model_and_project_policies() {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    init_db("data/init.sql").await.expect("DB init failed");
+    let pool = SQLITE_POOL.get().unwrap();
+    init_global_cache(pool, 3600, 1000).await.expect("Cache init failed");
+
+    // 使用唯一的自定义供应商和项目，避免影响其他测试
+    let provider_name = format!("policy-{}", &uuid::Uuid::new_v4().to_string()[..8]);
+    let provider = Provider::Custom(provider_name.clone());
+    let mut policy_model = model_record(&provider_name, POLICY_MODEL, policy_config("replace", Some("org prompt")));
+    let plain_model = model_record(&provider_name, PLAIN_MODEL, None);
+    for model in [&policy_model, &plain_model] {
+        create_model(pool, model).await.expect("create model failed");
+        sync_model_cache(pool, &model.provider, &model.name).await;
+    }
+    let project = Project {
+        id: format!("proj-{}", &uuid::Uuid::new_v4().to_string()[..8]),
+        name: "Policy Project".to_string(),
+        description: None,
+        is_active: true,
+        created_at: None,
+        updated_at: None,
+    };
+    create_project(pool, &project).await.expect("create project failed");
+
+    let adapter = MockAdapter::new(provider.clone()).with_models(&[POLICY_MODEL, PLAIN_MODEL]);
+    let sent = adapter.requests();
+    let dispatcher = LLMDispatcher::new(Some(DispatchConfig { enable_fallback: false, ..Default::default() }));
+    dispatcher.register_client(Box::new(adapter)).await;
+    let in_project = || CallMetadata::default().with_project(Some(project.id.clone()));
+
+    println!("=== Testing Model System Prompt Policy ===");
+    dispatcher.dispatch(chat(&provider, POLICY_MODEL)).await.expect("dispatch failed");
+    dispatcher.dispatch(chat(&provider, PLAIN_MODEL)).await.expect("dispatch failed");
+    assert_eq!(take_sent(&sent), vec![
+        sent_pairs(&[("system", "org prompt"), ("user", "hi")]),
+        sent_pairs(&[("system", "client prompt"), ("user", "hi")]),
+    ]);
+    println!("✅ replace policy swaps the client system prompt, other models untouched");
+
+    println!("=== Testing Project System Prompt Policy ===");
+    let prepend = SystemPromptPolicy { mode: SystemPromptMode::Prepend, prompt: Some("project prompt".to_string()) };
+    set_project_policy(pool, &project.id, Some(&prepend)).await.expect("set policy failed");
+    CALL_METADATA.scope(in_project(), async {
+        dispatcher.dispatch(chat(&provider, PLAIN_MODEL)).await.expect("dispatch failed");
+        dispatcher.dispatch(chat(&provider, POLICY_MODEL)).await.expect("dispatch failed");
+    }).await;
+    dispatcher.dispatch(chat(&provider, PLAIN_MODEL)).await.expect("dispatch failed");
+    assert_eq!(take_sent(&sent), vec![
+        sent_pairs(&[("system", "project prompt"), ("system", "client prompt"), ("user", "hi")]),
+        sent_pairs(&[("system", "org prompt"), ("user", "hi")]),
+        sent_pairs(&[("system", "client prompt"), ("user", "hi")]),
+    ]);
+    println!("✅ Project policy prepends its prompt, model policy takes precedence, other projects untouched");
+
+    println!("=== Testing Forbidden Client System Prompt ===");
+    policy_model.config = policy_config("forbid_client_system", None);
+    update_model(pool, &policy_model).await.expect("update model failed");
+    sync_model_cache(pool, &policy_model.provider, &policy_model.name).await;
+    let error = dispatcher.dispatch(chat(&provider, POLICY_MODEL)).await.unwrap_err();
+    assert!(matches!(error, LLMError::InvalidParameters(_)), "{:?}", error);
+    assert_eq!(error.status_code(), 400);
+    let request = DispatchRequest::new(provider.clone(), POLICY_MODEL.to_string(), vec![Message::user("hi".to_string())]);
+    dispatcher.dispatch(request).await.expect("dispatch failed");
+    assert_eq!(take_sent(&sent), vec![sent_pairs(&[("user", "hi")])]);
+    println!("✅ Requests with a client system prompt rejected with 400");
+
+    set_project_policy(pool, &project.id, None).await.expect("delete policy failed");
+    delete_project(pool, &project.id).await.expect("delete project failed");
+    for model in [&policy_model, &plain_model] {
+        delete_model(pool, &model.id).await.expect("delete model failed");
+        sync_model_cache(pool, &model.provider, &model.name).await;
+    }
+}
@@ -0,0 +1,114 @@
+//! # 租户黑名单测试
+//!
+//! 测试经 HTTP 接口的请求以 project_scope 解析出的项目作为租户：
+//! 绑定项目的网关 Key 发起的请求按该项目的黑名单覆盖规则检查，其他项目的请求不受影响
+
+mod common;
+
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+    middleware::from_fn,
+    response::Response,
+    routing::post,
+    Router,
+};
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+use tower::Service;
+
+use project_rust_learn::dao::blocklist::{create_blocklist_entry, BlocklistEntry};
+use project_rust_learn::dao::project::{bind_gateway_key, create_project, Project};
+use project_rust_learn::dao::run_migrations;
+use project_rust_learn::llm_api::dispatcher::{LLMDispatcher, Provider, GLOBAL_DISPATCHER};
+use project_rust_learn::llm_api::utils::blocklist::reload_blocklist;
+use project_rust_learn::llm_api::utils::consumer_quota::consumer_id;
+use project_rust_learn::llm_api::utils::project_scope::reload_project_bindings;
+use project_rust_learn::web::handlers::chat_completion_handler::create_chat_completion;
+use project_rust_learn::web::middleware::project::project_scope;
+use common::MockAdapter;
+
+fn app() -> Router {
+    Router::new().route("/v1/chat/completions", post(create_chat_completion).route_layer(from_fn(project_scope)))
+}
+
+async fn send(app: &mut Router, api_key: Option<&str>, content: &str) -> Response {
+    let mut request = Request::post("/v1/chat/completions").header(header::CONTENT_TYPE, "application/json");
+    if let Some(api_key) = api_key {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {}", api_key));
+    }
+    let body = json!({"model": "tenant-model", "messages": [{"role": "user", "content": content}]});
+    app.call(request.body(Body::from(body.to_string())).unwrap()).await.unwrap()
+}
+
+async fn body_json(response: Response) -> Value {
+    serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap()
+}
+
+/// 初始化测试环境的辅助函数：项目绑定和黑名单规则从独立的数据库加载
+async fn setup_test_env() -> (SqlitePool, std::path::PathBuf) {
+    let path = std::env::temp_dir().join(format!("tenant-blocklist-test-{}.db", uuid::Uuid::new_v4().simple()));
+    let pool = SqlitePool::connect(&format!("sqlite://{}?mode=rwc", path.display())).await.unwrap();
+    run_migrations(&pool).await.expect("migration failed");
+
+    let dispatcher = LLMDispatcher::new(None);
+    dispatcher.register_client(Box::new(
+        MockAdapter::new(Provider::OpenAI)
+            .with_models(&["tenant-model"])
+            .with_content(|request| request.messages.last().map(|m| m.content.clone()).unwrap_or_default()),
+    )).await;
+    GLOBAL_DISPATCHER.set(Arc::new(dispatcher)).ok();
+    (pool, path)
+}
+
+#[tokio::test]
+async fn test_tenant_blocklist_override_applies_over_http() {
+    let (pool, path) = setup_test_env().await;
+
+    println!("=== Testing Tenant Blocklist Over HTTP ===");
+    let project = Project {
+        id: format!("proj-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]),
+        name: "Tenant Project".to_string(),
+        description: None,
+        is_active: true,
+        created_at: None,
+        updated_at: None,
+    };
+    create_project(&pool, &project).await.expect("create project failed");
+    let api_key = format!("sk-tenant-{}", uuid::Uuid::new_v4());
+    bind_gateway_key(&pool, &consumer_id(&api_key), &project.id).await.expect("bind failed");
+    reload_project_bindings(&pool).await.expect("reload bindings failed");
+
+    // 只对该项目生效的拦截规则
+    let pattern = format!("secret{}", uuid::Uuid::new_v4().simple());
+    let entry = BlocklistEntry {
+        id: uuid::Uuid::new_v4().to_string(),
+        tenant_id: Some(project.id.clone()),
+        pattern: pattern.clone(),
+        action: "block".to_string(),
+        scope: "prompt".to_string(),
+        is_active: true,
+        created_at: None,
+        updated_at: None,
+    };
+    create_blocklist_entry(&pool, &entry).await.expect("create entry failed");
+    reload_blocklist(&pool).await.expect("reload blocklist failed");
+
+    let mut app = app();
+    let prompt = format!("tell me the {}", pattern);
+    let response = send(&mut app, Some(&api_key), &prompt).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(body_json(response).await["error"]["code"], "content_policy_violation");
+    println!("✅ Project override blocked the prompt");
+
+    // 未绑定项目的请求属于 default 租户，不受该项目的规则影响
+    let response = send(&mut app, None, &prompt).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["choices"][0]["message"]["content"], prompt);
+    println!("✅ Default tenant is not affected");
+
+    pool.close().await;
+    std::fs::remove_file(path).ok();
+}
@@ -0,0 +1,67 @@
+//! # TLS 配置测试
+//!
+//! 测试自定义根证书、客户端证书和 `danger_accept_invalid_certs` 的加载与校验，
+//! 以及从 providers.config 的 `tls` 对象创建适配器。
+//! `tests/fixtures/tls/` 下为仅用于测试的自签名 CA 和由其签发的客户端证书
+
+use serde_json::json;
+
+use project_rust_learn::llm_api::dispatcher::Provider;
+use project_rust_learn::llm_api::provider_registry::{provider_registry, ProviderConfig};
+use project_rust_learn::llm_api::utils::client::{BaseClient, ClientConfig, TlsConfig};
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/tls/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+#[test]
+fn test_tls_config_validation() {
+    let tls = TlsConfig::new()
+        .with_ca_bundle_file(&fixture("ca.pem")).unwrap()
+        .with_client_identity_files(&fixture("client.pem"), &fixture("client.key")).unwrap();
+    tls.validate().expect("valid CA and client identity rejected");
+    BaseClient::new(ClientConfig::new().with_tls(tls)).expect("client with custom TLS failed to build");
+    println!("✅ CA bundle and client identity loaded");
+
+    assert!(TlsConfig::new().with_ca_bundle_file(&fixture("missing.pem")).is_err());
+    assert!(TlsConfig::new().with_ca_bundle_pem(b"not a certificate".to_vec()).validate().is_err());
+    let mut cert_only = TlsConfig::new();
+    cert_only.client_cert_pem = Some(std::fs::read(fixture("client.pem")).unwrap());
+    assert!(cert_only.validate().is_err());
+    println!("✅ Missing files, invalid PEM and incomplete identity rejected");
+
+    let tls = TlsConfig::new().with_danger_accept_invalid_certs(true);
+    assert!(tls.danger_accept_invalid_certs);
+    BaseClient::new(ClientConfig::new().with_tls(tls)).expect("client without verification failed to build");
+    println!("✅ Certificate verification can be disabled explicitly");
+}
+
+#[test]
+fn test_provider_tls_settings() {
+    let config = |settings| ProviderConfig::new(Provider::Ollama, "ollama", Some("https://llm.internal:11434"))
+        .with_settings(Some(settings));
+
+    assert_eq!(config(json!({})).tls_config().unwrap(), TlsConfig::default());
+
+    let provider = config(json!({"tls": {
+        "ca_cert": fixture("ca.pem"),
+        "client_cert": fixture("client.pem"),
+        "client_key": fixture("client.key"),
+    }}));
+    let tls = provider.tls_config().unwrap();
+    assert!(tls.ca_bundle_pem.is_some() && tls.client_cert_pem.is_some() && tls.client_key_pem.is_some());
+    assert!(!tls.danger_accept_invalid_certs);
+    let factory = provider_registry().get("ollama").unwrap();
+    factory.create_adapter(&provider).expect("create_adapter with TLS settings failed");
+    println!("✅ Adapter created from providers.config tls");
+
+    let error = config(json!({"tls": {"client_cert": fixture("client.pem")}})).tls_config().unwrap_err();
+    assert!(error.to_string().contains("client_key"));
+    assert!(config(json!({"tls": {"ca_cert": fixture("missing.pem")}})).tls_config().is_err());
+    assert!(factory.create_adapter(&config(json!({"tls": {"ca_cert": fixture("missing.pem")}}))).is_err());
+    println!("✅ Invalid tls settings rejected at registration");
+
+    let tls = config(json!({"tls": {"danger_accept_invalid_certs": true}})).tls_config().unwrap();
+    assert!(tls.danger_accept_invalid_certs);
+    println!("✅ danger_accept_invalid_certs read from providers.config");
+}
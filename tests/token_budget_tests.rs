@@ -0,0 +1,152 @@
+//! # Token 计数测试
+//!
+//! 测试调度器拒绝超出模型上下文窗口的请求（内置窗口和配置覆盖），按 context_strategy
+//! 丢弃或摘要较早的消息，以及供应商没有返回用量时按 token 计数估算用量
+
+mod common;
+
+use std::collections::HashMap;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+use project_rust_learn::api_types::v1::GatewayErrorCode;
+use project_rust_learn::dao::{init_db, init_sqlite_pool};
+use project_rust_learn::llm_api::dispatcher::{
+    ContextStrategy, DispatchConfig, DispatchRequest, LLMDispatcher, LLMError, Provider,
+};
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+use project_rust_learn::llm_api::utils::tokenizer::{count_request_tokens, count_text_tokens};
+use common::MockAdapter;
+
+struct TestDispatcher {
+    dispatcher: LLMDispatcher,
+    provider: Provider,
+    calls: Arc<AtomicUsize>,
+    requests: Arc<Mutex<Vec<DispatchRequest>>>,
+}
+
+async fn setup(context_windows: HashMap<String, usize>) -> TestDispatcher {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    init_db("data/init.sql").await.expect("DB init failed");
+
+    // 使用唯一的自定义供应商，避免影响其他测试
+    let provider = Provider::Custom(format!("tokens-{}", uuid::Uuid::new_v4().simple()));
+    // 不返回用量，summary-model 返回固定的摘要
+    let adapter = MockAdapter::new(provider.clone())
+        .with_models(&["gpt-4", "tiny-model", "summary-model"])
+        .with_content(|request| match request.model.as_str() {
+            "summary-model" => "The user asked about tea.".to_string(),
+            _ => "The answer is forty two.".to_string(),
+        });
+    let calls = adapter.calls();
+    let requests = adapter.requests();
+    let dispatcher = LLMDispatcher::new(Some(DispatchConfig {
+        enable_fallback: false,
+        context_windows,
+        summary_model: Some((provider.clone(), "summary-model".to_string())),
+        ..Default::default()
+    }));
+    dispatcher.register_client(Box::new(adapter)).await;
+    TestDispatcher { dispatcher, provider, calls, requests }
+}
+
+// system 提示词、10 轮问答和最后一个问题
+fn long_history() -> Vec<Message> {
+    let mut messages = vec![Message::system("You are helpful.".to_string())];
+    for i in 0..10 {
+        messages.push(Message::user(format!("question {} about tea and other drinks", i)));
+        messages.push(Message::assistant(format!("answer {} about tea and other drinks", i)));
+    }
+    messages.push(Message::user("last question".to_string()));
+    messages
+}
+
+#[tokio::test]
+async fn test_reject_request_over_context_window() {
+    let TestDispatcher { dispatcher, provider, calls, .. } = setup(HashMap::from([("tiny-model".to_string(), 50)])).await;
+
+    println!("=== Testing Context Window Check ===");
+    // gpt-4 的上下文窗口为 8192
+    let long_prompt = "lorem ipsum dolor sit amet ".repeat(3000);
+    let request = DispatchRequest::new(provider.clone(), "gpt-4".to_string(), vec![Message::user(long_prompt)]);
+    let error = dispatcher.dispatch(request).await.unwrap_err();
+    assert!(matches!(error, LLMError::ContextLengthExceeded(_)), "unexpected error: {:?}", error);
+    assert_eq!(error.error_code(), GatewayErrorCode::ContextLengthExceeded);
+    assert!(error.to_string().contains("8192"));
+    println!("✅ Prompt over built-in window rejected: {}", error);
+
+    // 配置的窗口覆盖内置窗口，回复长度 max_tokens 一并计入
+    let mut request = DispatchRequest::new(provider.clone(), "tiny-model".to_string(), vec![Message::user("hi".to_string())]);
+    request.max_tokens = Some(100);
+    let error = dispatcher.dispatch(request).await.unwrap_err();
+    assert!(matches!(error, LLMError::ContextLengthExceeded(_)));
+    assert_eq!(calls.load(Ordering::SeqCst), 0, "rejected requests must not reach the provider");
+    println!("✅ max_tokens counted against configured window");
+
+    let request = DispatchRequest::new(provider, "tiny-model".to_string(), vec![Message::user("hi".to_string())]);
+    dispatcher.dispatch(request).await.expect("request within window should pass");
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    println!("✅ Request within window dispatched");
+}
+
+#[tokio::test]
+async fn test_estimate_usage_when_provider_omits_it() {
+    let TestDispatcher { dispatcher, provider, .. } = setup(HashMap::new()).await;
+
+    println!("=== Testing Usage Estimation ===");
+    let request = DispatchRequest::new(provider, "gpt-4".to_string(), vec![
+        Message::system("You are helpful.".to_string()),
+        Message::user("What is six times seven?".to_string()),
+    ]);
+    let expected_prompt = count_request_tokens(&request) as u32;
+    let response = dispatcher.dispatch(request).await.expect("dispatch failed");
+    let usage = response.usage.expect("usage should be estimated");
+    assert_eq!(usage.prompt_tokens, expected_prompt);
+    assert_eq!(usage.completion_tokens, count_text_tokens("The answer is forty two.", "gpt-4") as u32);
+    assert_eq!(usage.total_tokens, usage.prompt_tokens + usage.completion_tokens);
+    println!("✅ Estimated usage: {:?}", usage);
+}
+
+#[tokio::test]
+async fn test_truncate_oldest_strategy() {
+    let TestDispatcher { dispatcher, provider, requests, .. } = setup(HashMap::from([("tiny-model".to_string(), 120)])).await;
+
+    println!("=== Testing truncate_oldest ===");
+    let request = DispatchRequest::new(provider, "tiny-model".to_string(), long_history())
+        .with_context_strategy(ContextStrategy::TruncateOldest);
+    dispatcher.dispatch(request).await.expect("truncated request should pass");
+
+    let sent = requests.lock().unwrap().pop().unwrap();
+    assert_eq!(sent.messages[0].content, "You are helpful.");
+    assert_eq!(sent.messages.last().unwrap().content, "last question");
+    assert!(sent.messages.len() < long_history().len());
+    assert!(!sent.messages.iter().any(|m| m.content.starts_with("question 0 ")));
+    assert!(count_request_tokens(&sent) <= 120);
+    println!("✅ Sent {} of {} messages", sent.messages.len(), long_history().len());
+}
+
+#[tokio::test]
+async fn test_summarize_strategy() {
+    let TestDispatcher { dispatcher, provider, requests, .. } = setup(HashMap::from([("tiny-model".to_string(), 120)])).await;
+
+    println!("=== Testing summarize ===");
+    let request = DispatchRequest::new(provider, "tiny-model".to_string(), long_history())
+        .with_context_strategy(ContextStrategy::Summarize);
+    dispatcher.dispatch(request).await.expect("summarized request should pass");
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    // 先用摘要模型压缩被丢弃的消息
+    assert_eq!(requests[0].model, "summary-model");
+    assert!(requests[0].messages[1].content.contains("question 0 about tea"));
+
+    let sent = &requests[1];
+    assert_eq!(sent.messages[0].content, "You are helpful.");
+    assert_eq!(sent.messages[1].role, "system");
+    assert!(sent.messages[1].content.ends_with("The user asked about tea."));
+    assert_eq!(sent.messages.last().unwrap().content, "last question");
+    assert!(count_request_tokens(sent) <= 120);
+    println!("✅ Summary inserted: {}", sent.messages[1].content);
+}
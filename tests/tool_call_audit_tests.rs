@@ -0,0 +1,68 @@
+mod common;
+
+use project_rust_learn::dao::{init_sqlite_pool, init_db, SQLITE_POOL};
+use project_rust_learn::dao::call_log::{CallLog, create_call_log, delete_call_log};
+use project_rust_learn::dao::tool_call_audit::{
+    ToolCallStep, create_tool_call_step, list_tool_call_steps_by_call_log, delete_tool_call_steps_by_call_log,
+};
+use std::sync::Arc;
+use sqlx::{Pool, Sqlite};
+
+/// 初始化测试环境的辅助函数
+async fn setup_test_env() -> Arc<Pool<Sqlite>> {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+    init_db("data/init.sql").await.expect("DB init failed");
+    pool
+}
+
+fn step(call_log_id: &str, step_index: i64, tool_name: &str) -> ToolCallStep {
+    ToolCallStep {
+        id: uuid::Uuid::new_v4().to_string(),
+        call_log_id: call_log_id.to_string(),
+        step_index,
+        tool_call_id: Some(format!("call_{}", step_index)),
+        tool_name: tool_name.to_string(),
+        arguments: Some(r#"{"city":"Hangzhou"}"#.to_string()),
+        output: Some("sunny".to_string()),
+        error_message: None,
+        latency_ms: 12,
+        created_at: None,
+    }
+}
+
+#[tokio::test]
+async fn test_tool_call_steps_linked_to_call_log() {
+    let pool = setup_test_env().await;
+
+    println!("=== Testing Tool Call Audit Trail ===");
+    let call_log_id = uuid::Uuid::new_v4().to_string();
+    let call_log = CallLog {
+        id: call_log_id.clone(),
+        tokens_input: 0,
+        tokens_output: 0,
+        request_summary: Some("What's the weather in Hangzhou?".to_string()),
+        finish_reason: Some("tool_calls".to_string()),
+        ..common::call_log("openai", 200)
+    };
+    create_call_log(&pool, &call_log).await.expect("create_call_log failed");
+
+    // 按执行顺序读取，与写入顺序无关
+    create_tool_call_step(&pool, &step(&call_log_id, 1, "get_forecast")).await.expect("create_tool_call_step failed");
+    create_tool_call_step(&pool, &step(&call_log_id, 0, "get_weather")).await.expect("create_tool_call_step failed");
+
+    let steps = list_tool_call_steps_by_call_log(&pool, &call_log_id).await.expect("list_tool_call_steps_by_call_log failed");
+    println!("✅ Tool call steps: {:?}", steps);
+    assert_eq!(steps.len(), 2);
+    assert_eq!(steps[0].tool_name, "get_weather");
+    assert_eq!(steps[1].tool_name, "get_forecast");
+    assert_eq!(steps[0].arguments.as_deref(), Some(r#"{"city":"Hangzhou"}"#));
+    assert_eq!(steps[0].latency_ms, 12);
+    assert!(steps[0].created_at.is_some());
+
+    let deleted = delete_tool_call_steps_by_call_log(&pool, &call_log_id).await.expect("delete_tool_call_steps_by_call_log failed");
+    println!("✅ Deleted tool call steps: {} row(s)", deleted);
+    assert_eq!(deleted, 2);
+
+    delete_call_log(&pool, &call_log_id).await.expect("delete_call_log failed");
+}
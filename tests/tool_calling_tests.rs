@@ -0,0 +1,212 @@
+//! # 工具调用测试
+//!
+//! 测试 tools 和 tool_choice 经调度器透传给 OpenAI 兼容供应商，
+//! 上游返回的工具调用（非流式一次返回、流式分块返回参数）转换为统一的 tool_calls
+
+use mockito::{Matcher, Server};
+use serde_json::json;
+
+use project_rust_learn::dao::{init_db, init_sqlite_pool};
+use project_rust_learn::llm_api::ali::client::AliClient;
+use project_rust_learn::llm_api::dispatcher::{AliAdapter, DispatchRequest, LLMClientAdapter, Provider};
+use project_rust_learn::llm_api::utils::client::ClientConfig;
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+use project_rust_learn::llm_api::utils::tool_structure::{Tool, ToolFunction};
+
+async fn setup_test_env() {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    init_db("data/init.sql").await.expect("DB init failed");
+}
+
+fn weather_tool() -> Tool {
+    Tool {
+        tool_type: "function".to_string(),
+        function: ToolFunction {
+            name: "get_weather".to_string(),
+            description: "Get the current weather of a city".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {"city": {"type": "string"}},
+                "required": ["city"]
+            }),
+        },
+    }
+}
+
+fn adapter(server: &Server) -> AliAdapter {
+    let http_client = reqwest::Client::builder().no_proxy().build().unwrap();
+    let client = AliClient::new_with_client("sk-test".to_string(), server.url(), ClientConfig::default(), http_client).unwrap();
+    AliAdapter::new(client)
+}
+
+fn weather_request() -> DispatchRequest {
+    DispatchRequest::new(
+        Provider::Ali,
+        "qwen-plus".to_string(),
+        vec![Message::user("What's the weather in Hangzhou?".to_string())],
+    )
+    .with_tools(vec![weather_tool()])
+    .with_tool_choice(json!("auto"))
+}
+
+#[tokio::test]
+async fn test_tool_calls_round_trip() {
+    setup_test_env().await;
+
+    println!("=== Testing Tool Calls ===");
+    let mut server = Server::new_async().await;
+    let mock = server.mock("POST", "/compatible-mode/v1/chat/completions")
+        .match_body(Matcher::PartialJson(json!({
+            "tools": [{"type": "function", "function": {"name": "get_weather"}}],
+            "tool_choice": "auto"
+        })))
+        .with_status(200)
+        .with_body(json!({
+            "id": "chatcmpl-tool",
+            "object": "chat.completion",
+            "created": 1757412000,
+            "model": "qwen-plus",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_abc",
+                        "type": "function",
+                        "function": {"name": "get_weather", "arguments": "{\"city\": \"Hangzhou\"}"}
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }],
+            "usage": {"prompt_tokens": 20, "completion_tokens": 10, "total_tokens": 30}
+        }).to_string())
+        .create_async()
+        .await;
+
+    let response = adapter(&server).generate(&weather_request()).await.expect("generate failed");
+    mock.assert_async().await;
+    assert_eq!(response.finish_reason.as_deref(), Some("tool_calls"));
+    assert_eq!(response.content, "");
+    let tool_calls = response.tool_calls.expect("missing tool calls");
+    assert_eq!(tool_calls.len(), 1);
+    assert_eq!(tool_calls[0].id.as_deref(), Some("call_abc"));
+    assert_eq!(tool_calls[0].function.name, "get_weather");
+    assert_eq!(tool_calls[0].function.arguments["city"], "Hangzhou");
+    println!("✅ Tools forwarded and tool call parsed");
+}
+
+#[tokio::test]
+async fn test_streamed_tool_call_arguments_accumulated() {
+    setup_test_env().await;
+
+    println!("=== Testing Streamed Tool Calls ===");
+    let delta = |delta: serde_json::Value, finish_reason: Option<&str>| json!({
+        "id": "chatcmpl-tool",
+        "object": "chat.completion.chunk",
+        "created": 1757412000,
+        "model": "qwen-plus",
+        "choices": [{"index": 0, "delta": delta, "finish_reason": finish_reason}]
+    });
+    let body = [
+        delta(json!({"role": "assistant", "tool_calls": [{
+            "index": 0, "id": "call_abc", "type": "function",
+            "function": {"name": "get_weather", "arguments": ""}
+        }]}), None),
+        delta(json!({"tool_calls": [{"index": 0, "function": {"arguments": "{\"city\": "}}]}), None),
+        delta(json!({"tool_calls": [{"index": 0, "function": {"arguments": "\"Hangzhou\"}"}}]}), None),
+        delta(json!({}), Some("tool_calls")),
+        json!({
+            "id": "chatcmpl-tool",
+            "object": "chat.completion.chunk",
+            "created": 1757412000,
+            "model": "qwen-plus",
+            "choices": [],
+            "usage": {"prompt_tokens": 20, "completion_tokens": 10, "total_tokens": 30}
+        }),
+    ]
+    .iter()
+    .map(|chunk| format!("data: {}\n\n", chunk))
+    .collect::<String>()
+        + "data: [DONE]\n\n";
+
+    let mut server = Server::new_async().await;
+    let mock = server.mock("POST", "/compatible-mode/v1/chat/completions")
+        .match_body(Matcher::PartialJson(json!({"stream": true, "tool_choice": "auto"})))
+        .with_status(200)
+        .with_header("content-type", "text/event-stream")
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let request = weather_request().with_stream(true);
+    let mut receiver = adapter(&server).generate_stream(&request).await.expect("stream failed");
+    let mut chunks = Vec::new();
+    while let Some(chunk) = receiver.recv().await {
+        chunks.push(chunk.expect("stream chunk failed"));
+    }
+    mock.assert_async().await;
+
+    // 参数片段拼接完整后随结束块一起返回
+    let finished = chunks.last().expect("no chunks");
+    assert_eq!(finished.finish_reason.as_deref(), Some("tool_calls"));
+    assert_eq!(finished.usage.as_ref().unwrap().total_tokens, 30);
+    let tool_calls = finished.tool_calls.as_ref().expect("missing tool calls");
+    assert_eq!(tool_calls.len(), 1);
+    assert_eq!(tool_calls[0].id.as_deref(), Some("call_abc"));
+    assert_eq!(tool_calls[0].function.arguments["city"], "Hangzhou");
+    assert!(chunks[..chunks.len() - 1].iter().all(|c| c.tool_calls.is_none()));
+    println!("✅ Streamed arguments accumulated into one tool call");
+}
+
+#[tokio::test]
+async fn test_full_message_and_raw_response() {
+    setup_test_env().await;
+
+    println!("=== Testing Full Message And Raw Response ===");
+    let mut server = Server::new_async().await;
+    let _mock = server.mock("POST", "/compatible-mode/v1/chat/completions")
+        .with_status(200)
+        .with_body(json!({
+            "id": "chatcmpl-full",
+            "object": "chat.completion",
+            "created": 1757412000,
+            "model": "qwen-plus",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": "",
+                    "reasoning_content": "Need the weather tool.",
+                    "tool_calls": [{
+                        "id": "call_abc",
+                        "type": "function",
+                        "function": {"name": "get_weather", "arguments": "{\"city\": \"Hangzhou\"}"}
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }],
+            "usage": {"prompt_tokens": 20, "completion_tokens": 10, "total_tokens": 30}
+        }).to_string())
+        .create_async()
+        .await;
+
+    let response = adapter(&server).generate(&weather_request()).await.expect("generate failed");
+    let message = response.message.clone().expect("missing message");
+    assert_eq!(message.role, "assistant");
+    assert_eq!(message.thinking.as_deref(), Some("Need the weather tool."));
+    assert_eq!(message.tool_calls.unwrap()[0].id.as_deref(), Some("call_abc"));
+    println!("✅ Full assistant message carried, reasoning_content read as thinking");
+
+    let raw = response.raw.clone().expect("missing raw response");
+    assert_eq!(raw["id"], "chatcmpl-full");
+    assert_eq!(raw["choices"][0]["finish_reason"], "tool_calls");
+    assert_eq!(raw["usage"]["total_tokens"], 30);
+    println!("✅ Provider response carried as raw JSON");
+
+    let serialized = serde_json::to_value(&response).unwrap();
+    assert!(serialized.get("message").is_some() && serialized.get("raw").is_some());
+    let empty = serde_json::to_value(project_rust_learn::llm_api::dispatcher::DispatchResponse { message: None, raw: None, ..response }).unwrap();
+    assert!(empty.get("message").is_none() && empty.get("raw").is_none());
+    println!("✅ message and raw omitted when absent");
+}
@@ -0,0 +1,89 @@
+//! # 链路传播测试
+//!
+//! 测试入站请求的 `traceparent` 经 Web 中间件进入任务上下文，BaseClient 发往上游的每次尝试
+//! 都带上同一个 trace ID 和新的 span ID
+
+use axum::{
+    body::{to_bytes, Body},
+    http::Request,
+    middleware::from_fn,
+    routing::get,
+    Router,
+};
+use mockito::{Matcher, Server};
+use serde_json::json;
+use tower::Service;
+
+use project_rust_learn::dao::{init_db, init_sqlite_pool};
+use project_rust_learn::llm_api::utils::client::{BaseClient, ClientConfig};
+use project_rust_learn::llm_api::utils::trace_context::{with_trace_parent, TraceParent, TRACEPARENT_HEADER};
+use project_rust_learn::web::middleware::trace::trace_context;
+
+const INBOUND: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+async fn current_trace() -> String {
+    TraceParent::current().map(|trace_parent| trace_parent.to_header()).unwrap_or_default()
+}
+
+#[tokio::test]
+async fn test_inbound_traceparent_enters_task_context() {
+    println!("=== Testing Inbound Traceparent ===");
+    let mut app: Router = Router::new()
+        .route("/trace", get(current_trace))
+        .layer(from_fn(trace_context));
+
+    let response = app
+        .call(Request::get("/trace").header(TRACEPARENT_HEADER, INBOUND).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let trace_parent = TraceParent::parse(std::str::from_utf8(&body).unwrap()).expect("trace context missing");
+    assert_eq!(trace_parent.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+    assert_ne!(trace_parent.parent_id, "00f067aa0ba902b7");
+    println!("✅ Inbound trace ID kept");
+
+    // 没有或无效的 traceparent 时开始新的链路
+    let response = app
+        .call(Request::get("/trace").header(TRACEPARENT_HEADER, "garbage").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let trace_parent = TraceParent::parse(std::str::from_utf8(&body).unwrap()).expect("trace context missing");
+    assert_ne!(trace_parent.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+    println!("✅ New trace started for invalid traceparent");
+}
+
+#[tokio::test]
+async fn test_outbound_requests_carry_traceparent() {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    init_db("data/init.sql").await.expect("DB init failed");
+
+    println!("=== Testing Outbound Traceparent ===");
+    let mut server = Server::new_async().await;
+    let mock = server.mock("POST", "/v1/chat")
+        .match_header(TRACEPARENT_HEADER, Matcher::Regex("^00-4bf92f3577b34da6a3ce929d0e0e4736-[0-9a-f]{16}-01$".to_string()))
+        .with_status(200)
+        .with_body("{}")
+        .create_async()
+        .await;
+
+    let client = BaseClient::new(ClientConfig::new()).unwrap();
+    let inbound = TraceParent::parse(INBOUND).unwrap();
+    let response = with_trace_parent(Some(inbound), client.post(&format!("{}/v1/chat", server.url()), json!({ "model": "test" })))
+        .await
+        .expect("request failed");
+    assert_eq!(response.status(), 200);
+    mock.assert_async().await;
+    println!("✅ Upstream request carried the inbound trace ID");
+
+    // 没有链路上下文时不发送 traceparent
+    let mock = server.mock("POST", "/v1/plain")
+        .match_header(TRACEPARENT_HEADER, Matcher::Missing)
+        .with_status(200)
+        .with_body("{}")
+        .create_async()
+        .await;
+    client.post(&format!("{}/v1/plain", server.url()), json!({ "model": "test" })).await.expect("request failed");
+    mock.assert_async().await;
+    println!("✅ No traceparent without trace context");
+}
@@ -0,0 +1,115 @@
+//! # A/B 流量拆分测试
+//!
+//! 测试配置了流量拆分的逻辑模型名按分组改写供应商和模型、同一用户落在同一分组，
+//! 以及分组标识写入调用记录并可按分组统计
+
+mod common;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+
+use project_rust_learn::dao::{init_db, init_sqlite_pool, SQLITE_POOL};
+use project_rust_learn::dao::cache::init_global_cache;
+use project_rust_learn::dao::call_log::{create_call_log, delete_call_log, get_call_logs_stats_by_traffic_arm, CallLog};
+use project_rust_learn::llm_api::dispatcher::{
+    DispatchConfig, DispatchRequest, DispatchResponse, LLMClientAdapter, LLMDispatcher, LLMError, Provider,
+    StreamReceiver,
+};
+use project_rust_learn::llm_api::utils::client::CallMetadata;
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+use project_rust_learn::llm_api::utils::traffic_split::{TrafficArm, TrafficSplit};
+use common::{call_log, response};
+
+/// 像 HTTP 客户端一样按当前调用的附加信息写入调用记录的测试适配器
+struct RecordingAdapter {
+    provider: Provider,
+    call_log_ids: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl LLMClientAdapter for RecordingAdapter {
+    async fn generate(&self, request: &DispatchRequest) -> Result<DispatchResponse, LLMError> {
+        let call_log = CallLog {
+            total_duration: 10,
+            tokens_input: 0,
+            tokens_output: 0,
+            traffic_arm: CallMetadata::current().traffic_arm,
+            ..call_log(self.provider.as_str(), 200)
+        };
+        create_call_log(SQLITE_POOL.get().unwrap(), &call_log).await.expect("create_call_log failed");
+        self.call_log_ids.lock().unwrap().push(call_log.id);
+
+        Ok(response(self.provider.clone(), &request.model, request.model.clone()))
+    }
+
+    async fn generate_stream(&self, _request: &DispatchRequest) -> Result<StreamReceiver, LLMError> {
+        Err(LLMError::UnsupportedProvider(self.provider.clone()))
+    }
+
+    fn supported_models(&self) -> Vec<String> {
+        vec!["model-a".to_string(), "model-b".to_string()]
+    }
+
+    fn provider_name(&self) -> Provider {
+        self.provider.clone()
+    }
+}
+
+#[tokio::test]
+async fn test_traffic_split_tags_call_logs() {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+    init_db("data/init.sql").await.expect("DB init failed");
+    init_global_cache(&pool, 3600, 1000).await.expect("Cache init failed");
+
+    // 使用唯一的供应商和逻辑模型名，避免影响其他测试
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let provider = Provider::Custom(format!("ab-{}", suffix));
+    let logical_model = format!("ab-model-{}", suffix);
+    let arm = |name: &str, model: &str| TrafficArm {
+        name: name.to_string(),
+        provider: provider.as_str().to_string(),
+        model: model.to_string(),
+        percent: 50,
+    };
+    let split = TrafficSplit::new(vec![arm("a", "model-a"), arm("b", "model-b")]).unwrap();
+    let dispatcher = LLMDispatcher::new(Some(DispatchConfig {
+        enable_fallback: false,
+        traffic_splits: HashMap::from([(logical_model.clone(), split)]),
+        ..DispatchConfig::default()
+    }));
+    let call_log_ids = Arc::new(Mutex::new(Vec::new()));
+    dispatcher.register_client(Box::new(RecordingAdapter { provider: provider.clone(), call_log_ids: call_log_ids.clone() })).await;
+
+    println!("=== Testing Traffic Split ===");
+    assert_eq!(dispatcher.resolve_model(&logical_model).await, Some((provider.clone(), logical_model.clone())));
+
+    let request = |user: &str| {
+        DispatchRequest::new(provider.clone(), logical_model.clone(), vec![Message::user("hello".to_string())])
+            .with_user(user.to_string())
+    };
+    let mut served: HashMap<String, usize> = HashMap::new();
+    for i in 0..20 {
+        let user = format!("user-{}", i);
+        let model = dispatcher.dispatch(request(&user)).await.expect("dispatch failed").model;
+        // 同一用户再次请求落在同一分组
+        assert_eq!(dispatcher.dispatch(request(&user)).await.expect("dispatch failed").model, model);
+        *served.entry(model).or_default() += 2;
+    }
+    assert!(served.keys().all(|model| model == "model-a" || model == "model-b"));
+    println!("✅ Requests routed to split arms, sticky per user: {:?}", served);
+
+    let stats = get_call_logs_stats_by_traffic_arm(&pool).await.expect("stats failed");
+    for (name, model) in [("a", "model-a"), ("b", "model-b")] {
+        let label = format!("{}:{}", logical_model, name);
+        let calls = stats.iter().find(|row| row.traffic_arm == label).map_or(0, |row| row.total_calls);
+        assert_eq!(calls as usize, served.get(model).copied().unwrap_or(0), "arm {}", label);
+    }
+    println!("✅ Call logs tagged with traffic arm and aggregated per arm");
+
+    let ids = call_log_ids.lock().unwrap().clone();
+    for id in &ids {
+        delete_call_log(&pool, id).await.expect("delete_call_log failed");
+    }
+}
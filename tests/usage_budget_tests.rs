@@ -0,0 +1,188 @@
+//! # 用量统计与月度预算测试
+//!
+//! 测试调度成功后按项目/供应商/模型/天累加用量，以及项目在供应商上的当月费用达到预算后拒绝该项目的请求、
+//! 由备选供应商接管，其他项目不受影响
+
+mod common;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use project_rust_learn::api_types::v1::GatewayErrorCode;
+use project_rust_learn::dao::{init_sqlite_pool, init_db, SQLITE_POOL};
+use project_rust_learn::dao::cache::init_global_cache;
+use project_rust_learn::dao::project::DEFAULT_PROJECT;
+use project_rust_learn::dao::usage_stats::{list_usage_stats, record_usage_stat, UsageStatFilter, UsageStatKey};
+use project_rust_learn::llm_api::dispatcher::{
+    DispatchConfig, DispatchRequest, DispatchResponse, LLMDispatcher, LLMError, Provider, TokenUsage,
+};
+use project_rust_learn::llm_api::utils::budget::{get_budget_status, record_budget_spend, set_monthly_budget};
+use project_rust_learn::llm_api::utils::client::{CallMetadata, CALL_METADATA};
+use project_rust_learn::llm_api::utils::msg_structure::Message;
+use common::{response, MockAdapter};
+
+/// 初始化测试环境的辅助函数
+async fn setup_test_env() {
+    init_sqlite_pool("sqlite://data/app.db").await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+    init_db("data/init.sql").await.expect("DB init failed");
+    init_global_cache(&pool, 3600, 1000).await.expect("Cache init failed");
+}
+
+/// 记录调用次数并返回固定用量
+fn usage_adapter(provider: &Provider, calls: &Arc<AtomicUsize>) -> MockAdapter {
+    let reply_provider = provider.clone();
+    MockAdapter::new(provider.clone())
+        .with_models(&["budget-model"])
+        .with_call_counter(calls.clone())
+        .with_reply(move |request| Ok(DispatchResponse {
+            usage: Some(TokenUsage { prompt_tokens: 10, completion_tokens: 5, total_tokens: 15 }),
+            ..response(reply_provider.clone(), &request.model, reply_provider.as_str())
+        }))
+}
+
+fn today() -> String {
+    chrono::Local::now().format("%Y-%m-%d").to_string()
+}
+
+#[tokio::test]
+async fn test_usage_stats_aggregated_per_day() {
+    setup_test_env().await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+
+    println!("=== Testing Usage Stats Aggregation ===");
+    let provider = Provider::Custom(format!("usage-{}", uuid::Uuid::new_v4().simple()));
+    let calls = Arc::new(AtomicUsize::new(0));
+    let dispatcher = LLMDispatcher::new(None);
+    dispatcher.register_client(Box::new(usage_adapter(&provider, &calls))).await;
+
+    for _ in 0..2 {
+        let request = DispatchRequest::new(provider.clone(), "budget-model".to_string(), vec![Message::user("hello".to_string())]);
+        dispatcher.dispatch(request).await.expect("dispatch failed");
+    }
+
+    let filter = UsageStatFilter {
+        start: Some(today()),
+        provider: Some(provider.as_str().to_string()),
+        ..Default::default()
+    };
+    let stats = list_usage_stats(&pool, &filter).await.expect("list failed");
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].project_id, DEFAULT_PROJECT);
+    assert_eq!(stats[0].model, "budget-model");
+    assert_eq!(stats[0].request_count, 2);
+    assert_eq!(stats[0].tokens_input, 20);
+    assert_eq!(stats[0].tokens_output, 10);
+    println!("✅ Usage aggregated per provider/model/day");
+}
+
+#[tokio::test]
+async fn test_budget_exceeded_rejects_provider() {
+    setup_test_env().await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+
+    println!("=== Testing Monthly Budget ===");
+    let primary = Provider::Custom(format!("budget-{}", uuid::Uuid::new_v4().simple()));
+    let backup = Provider::Custom(format!("budget-backup-{}", uuid::Uuid::new_v4().simple()));
+    let primary_calls = Arc::new(AtomicUsize::new(0));
+    let backup_calls = Arc::new(AtomicUsize::new(0));
+    let request = || DispatchRequest::new(primary.clone(), "budget-model".to_string(), vec![Message::user("hello".to_string())]);
+
+    let strict = LLMDispatcher::new(Some(DispatchConfig {
+        enable_fallback: false,
+        ..Default::default()
+    }));
+    strict.register_client(Box::new(usage_adapter(&primary, &primary_calls))).await;
+
+    // 未超出预算时正常调用
+    set_monthly_budget(&pool, DEFAULT_PROJECT, primary.as_str(), Some(1.0)).await.expect("set budget failed");
+    strict.dispatch(request()).await.expect("dispatch failed");
+    assert_eq!(primary_calls.load(Ordering::SeqCst), 1);
+    println!("✅ Request allowed within budget");
+
+    // 当月费用达到预算后直接拒绝，不调用上游
+    let key = UsageStatKey { day: &today(), project_id: DEFAULT_PROJECT, provider: primary.as_str(), model: "budget-model" };
+    // 与调度记录用量一样同时累加到预算缓存
+    record_usage_stat(&pool, &key, 0, 0, 1.5).await.expect("record failed");
+    record_budget_spend(DEFAULT_PROJECT, primary.as_str(), 1.5);
+    let status = get_budget_status(&pool, DEFAULT_PROJECT, primary.as_str()).await.expect("status failed").expect("budget missing");
+    assert!(status.exceeded);
+    assert_eq!(status.remaining, 0.0);
+
+    let error = strict.dispatch(request()).await.expect_err("budget should be enforced");
+    assert!(matches!(error, LLMError::BudgetExceeded(_)));
+    assert_eq!(error.error_code(), GatewayErrorCode::BudgetExceeded);
+    assert_eq!(error.error_code().status_code(), 429);
+    assert_eq!(error.error_code().code(), Some("insufficient_quota"));
+    assert_eq!(primary_calls.load(Ordering::SeqCst), 1);
+    println!("✅ Provider over budget rejected");
+
+    // 启用 fallback 时由备选供应商接管
+    let dispatcher = LLMDispatcher::new(Some(DispatchConfig {
+        enable_fallback: true,
+        fallback_providers: vec![backup.clone()],
+        ..Default::default()
+    }));
+    dispatcher.register_client(Box::new(usage_adapter(&primary, &primary_calls))).await;
+    dispatcher.register_client(Box::new(usage_adapter(&backup, &backup_calls))).await;
+    let response = dispatcher.dispatch(request()).await.expect("dispatch failed");
+    assert_eq!(response.provider, backup);
+    assert_eq!(primary_calls.load(Ordering::SeqCst), 1);
+    assert_eq!(backup_calls.load(Ordering::SeqCst), 1);
+    println!("✅ Fallback provider used when over budget");
+
+    // 取消预算后恢复调用
+    set_monthly_budget(&pool, DEFAULT_PROJECT, primary.as_str(), None).await.expect("clear budget failed");
+    assert!(get_budget_status(&pool, DEFAULT_PROJECT, primary.as_str()).await.expect("status failed").is_none());
+    strict.dispatch(request()).await.expect("dispatch failed");
+    assert_eq!(primary_calls.load(Ordering::SeqCst), 2);
+    println!("✅ Budget removed, provider served again");
+}
+
+#[tokio::test]
+async fn test_budget_is_scoped_to_project() {
+    setup_test_env().await;
+    let pool = SQLITE_POOL.get().unwrap().clone();
+
+    println!("=== Testing Project Budget ===");
+    let provider = Provider::Custom(format!("budget-project-{}", uuid::Uuid::new_v4().simple()));
+    let team_a = format!("team-a-{}", uuid::Uuid::new_v4().simple());
+    let team_b = format!("team-b-{}", uuid::Uuid::new_v4().simple());
+    let calls = Arc::new(AtomicUsize::new(0));
+    let dispatcher = LLMDispatcher::new(Some(DispatchConfig {
+        enable_fallback: false,
+        ..Default::default()
+    }));
+    dispatcher.register_client(Box::new(usage_adapter(&provider, &calls))).await;
+    let dispatch_as = |project_id: &str| {
+        let metadata = CallMetadata::default().with_project(Some(project_id.to_string()));
+        let request = DispatchRequest::new(provider.clone(), "budget-model".to_string(), vec![Message::user("hello".to_string())]);
+        CALL_METADATA.scope(metadata, dispatcher.dispatch(request))
+    };
+
+    // 用量按项目累加
+    dispatch_as(&team_a).await.expect("dispatch failed");
+    let filter = UsageStatFilter { project_id: Some(team_a.clone()), provider: Some(provider.as_str().to_string()), ..Default::default() };
+    let stats = list_usage_stats(&pool, &filter).await.expect("list failed");
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].request_count, 1);
+    println!("✅ Usage recorded for {}", team_a);
+
+    // team-a 超出预算后只拒绝 team-a 的请求
+    set_monthly_budget(&pool, &team_a, provider.as_str(), Some(1.0)).await.expect("set budget failed");
+    let key = UsageStatKey { day: &today(), project_id: &team_a, provider: provider.as_str(), model: "budget-model" };
+    record_usage_stat(&pool, &key, 0, 0, 1.5).await.expect("record failed");
+    record_budget_spend(&team_a, provider.as_str(), 1.5);
+    assert!(get_budget_status(&pool, &team_a, provider.as_str()).await.unwrap().unwrap().exceeded);
+    assert!(get_budget_status(&pool, &team_b, provider.as_str()).await.unwrap().is_none());
+
+    let error = dispatch_as(&team_a).await.expect_err("budget should be enforced");
+    assert!(matches!(error, LLMError::BudgetExceeded(_)));
+    dispatch_as(&team_b).await.expect("other projects should not be affected");
+    dispatch_as(DEFAULT_PROJECT).await.expect("default project should not be affected");
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+    println!("✅ Over-budget project rejected, other projects served");
+
+    set_monthly_budget(&pool, &team_a, provider.as_str(), None).await.expect("clear budget failed");
+    sqlx::query("DELETE FROM usage_stats WHERE provider = ?").bind(provider.as_str()).execute(pool.as_ref()).await.unwrap();
+}
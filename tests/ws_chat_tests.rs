@@ -0,0 +1,229 @@
+//! # WebSocket 流式对话测试
+//!
+//! 测试 `/v1/ws/chat` 在同一连接上并发执行多个对话、取消单个对话中断上游流式响应、
+//! 无效消息和未知对话的错误、连接关闭时取消进行中的对话，以及单连接对话数上限和对话的准入排队
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use axum::{routing::get, Extension, Router};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+use project_rust_learn::llm_api::dispatcher::{
+    DispatchRequest, DispatchResponse, LLMClientAdapter, LLMDispatcher, LLMError, Provider, StreamChunk,
+    StreamReceiver, GLOBAL_DISPATCHER,
+};
+use project_rust_learn::llm_api::utils::admission::AdmissionQueue;
+use project_rust_learn::llm_api::utils::client::CallMetadata;
+use project_rust_learn::web::handlers::ws_chat_handler::{chat_websocket, MAX_CONVERSATIONS_PER_CONNECTION};
+
+type Socket = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// `ws-echo` 回显最后一条消息；`ws-hang` 发送一个分块后一直不结束，取消后记录次数
+struct StreamAdapter {
+    cancelled: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl LLMClientAdapter for StreamAdapter {
+    async fn generate(&self, _request: &DispatchRequest) -> Result<DispatchResponse, LLMError> {
+        Err(LLMError::InvalidParameters("only streaming is supported".to_string()))
+    }
+
+    async fn generate_stream(&self, request: &DispatchRequest) -> Result<StreamReceiver, LLMError> {
+        let (tx, rx) = tokio::sync::mpsc::channel(4);
+        if request.model == "ws-hang" {
+            tx.send(Ok(StreamChunk::delta("thinking".to_string()))).await.ok();
+            let cancellation = CallMetadata::current().cancellation;
+            let cancelled = self.cancelled.clone();
+            tokio::spawn(async move {
+                cancellation.cancelled().await;
+                cancelled.fetch_add(1, Ordering::SeqCst);
+                drop(tx);
+            });
+        } else {
+            let last = request.messages.last().map(|m| m.content.clone()).unwrap_or_default();
+            tx.send(Ok(StreamChunk::delta("echo: ".to_string()))).await.ok();
+            tx.send(Ok(StreamChunk::delta(last))).await.ok();
+            tx.send(Ok(StreamChunk::finished(Some("stop".to_string()), None))).await.ok();
+        }
+        Ok(rx)
+    }
+
+    fn supported_models(&self) -> Vec<String> {
+        vec!["ws-echo".to_string(), "ws-hang".to_string()]
+    }
+
+    fn provider_name(&self) -> Provider {
+        Provider::OpenAI
+    }
+}
+
+async fn start_server(cancelled: Arc<AtomicUsize>) -> Socket {
+    let dispatcher = LLMDispatcher::new(None);
+    dispatcher.register_client(Box::new(StreamAdapter { cancelled })).await;
+    GLOBAL_DISPATCHER.set(Arc::new(dispatcher)).ok();
+    connect(Router::new().route("/v1/ws/chat", get(chat_websocket))).await
+}
+
+async fn connect(router: Router) -> Socket {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, router).await.unwrap();
+    });
+    let (socket, _) = connect_async(format!("ws://{}/v1/ws/chat", addr)).await.expect("websocket connect failed");
+    socket
+}
+
+/// 读取消息直到收到一条错误
+async fn next_error(socket: &mut Socket) -> Value {
+    loop {
+        let message = next_message(socket).await;
+        if message["type"] == "error" {
+            return message;
+        }
+    }
+}
+
+fn chat(id: &str, model: &str, content: &str) -> Message {
+    Message::Text(json!({
+        "type": "chat",
+        "id": id,
+        "request": {"model": model, "messages": [{"role": "user", "content": content}]}
+    }).to_string())
+}
+
+async fn send(socket: &mut Socket, message: Message) {
+    socket.send(message).await.expect("send failed");
+}
+
+async fn next_message(socket: &mut Socket) -> Value {
+    loop {
+        let message = tokio::time::timeout(Duration::from_secs(5), socket.next()).await
+            .expect("timed out waiting for message")
+            .expect("connection closed")
+            .expect("websocket error");
+        if let Message::Text(text) = message {
+            return serde_json::from_str(&text).unwrap();
+        }
+    }
+}
+
+/// 读取消息直到指定对话结束，返回期间收到的所有消息
+async fn until_done(socket: &mut Socket, id: &str, received: &mut Vec<Value>) {
+    loop {
+        let message = next_message(socket).await;
+        let done = message["type"] == "done" && message["id"] == id;
+        received.push(message);
+        if done {
+            return;
+        }
+    }
+}
+
+fn content_of(received: &[Value], id: &str) -> String {
+    received.iter()
+        .filter(|m| m["type"] == "chunk" && m["id"] == id)
+        .filter_map(|m| m["chunk"]["choices"][0]["delta"]["content"].as_str())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_ws_chat_concurrent_conversations_and_cancel() {
+    let cancelled = Arc::new(AtomicUsize::new(0));
+    let mut socket = start_server(cancelled.clone()).await;
+
+    println!("=== Testing Concurrent Conversations ===");
+    send(&mut socket, chat("slow", "ws-hang", "take your time")).await;
+    send(&mut socket, chat("fast", "ws-echo", "hello ws")).await;
+    let mut received = Vec::new();
+    until_done(&mut socket, "fast", &mut received).await;
+    assert_eq!(content_of(&received, "fast"), "echo: hello ws");
+    let finish = received.iter().rev().find(|m| m["type"] == "chunk" && m["id"] == "fast").unwrap();
+    assert_eq!(finish["chunk"]["choices"][0]["finish_reason"], "stop");
+    assert!(!received.iter().any(|m| m["type"] == "done" && m["id"] == "slow"));
+    println!("✅ Second conversation finished while the first is still streaming");
+
+    println!("=== Testing Cancel ===");
+    send(&mut socket, Message::Text(json!({"type": "cancel", "id": "slow"}).to_string())).await;
+    until_done(&mut socket, "slow", &mut received).await;
+    assert_eq!(content_of(&received, "slow"), "thinking");
+    let error = received.iter().find(|m| m["type"] == "error" && m["id"] == "slow").expect("missing cancel error");
+    assert_eq!(error["error"]["code"], "request_cancelled");
+    assert_eq!(cancelled.load(Ordering::SeqCst), 1);
+    println!("✅ Cancel aborted the upstream stream");
+
+    println!("=== Testing Errors ===");
+    send(&mut socket, Message::Text(json!({"type": "cancel", "id": "missing"}).to_string())).await;
+    let error = next_message(&mut socket).await;
+    assert_eq!((error["type"].as_str(), error["id"].as_str()), (Some("error"), Some("missing")));
+    assert_eq!(error["error"]["code"], "request_not_found");
+
+    send(&mut socket, Message::Text("not json".to_string())).await;
+    let error = next_message(&mut socket).await;
+    assert_eq!(error["type"], "error");
+    assert!(error["id"].is_null());
+
+    let mut received = Vec::new();
+    send(&mut socket, chat("unknown", "no-such-model", "hi")).await;
+    until_done(&mut socket, "unknown", &mut received).await;
+    assert_eq!(received[0]["error"]["code"], "model_not_found");
+
+    send(&mut socket, chat("dup", "ws-hang", "first")).await;
+    send(&mut socket, chat("dup", "ws-hang", "second")).await;
+    let error = next_error(&mut socket).await;
+    assert_eq!(error["id"], "dup");
+    assert!(error["error"]["message"].as_str().unwrap().contains("already in flight"));
+    println!("✅ Invalid messages reported without closing the connection");
+
+    println!("=== Testing Close ===");
+    socket.close(None).await.ok();
+    for _ in 0..50 {
+        if cancelled.load(Ordering::SeqCst) == 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(cancelled.load(Ordering::SeqCst), 2);
+    println!("✅ Closing the connection cancelled in-flight conversations");
+
+    println!("=== Testing Conversation Cap ===");
+    let mut socket = connect(Router::new().route("/v1/ws/chat", get(chat_websocket))).await;
+    for i in 0..MAX_CONVERSATIONS_PER_CONNECTION {
+        send(&mut socket, chat(&format!("hang-{}", i), "ws-hang", "wait")).await;
+    }
+    send(&mut socket, chat("one-too-many", "ws-echo", "hi")).await;
+    let error = next_error(&mut socket).await;
+    assert_eq!(error["id"], "one-too-many");
+    assert_eq!(error["error"]["code"], "rate_limit_exceeded");
+    send(&mut socket, Message::Text(json!({"type": "cancel", "id": "hang-0"}).to_string())).await;
+    let mut received = Vec::new();
+    until_done(&mut socket, "hang-0", &mut received).await;
+    send(&mut socket, chat("after-cancel", "ws-echo", "room again")).await;
+    until_done(&mut socket, "after-cancel", &mut received).await;
+    assert_eq!(content_of(&received, "after-cancel"), "echo: room again");
+    socket.close(None).await.ok();
+    println!("✅ Conversations beyond the per-connection cap rejected until one finishes");
+
+    println!("=== Testing Admission ===");
+    let queue = Arc::new(AdmissionQueue::new(1, 0, Duration::from_millis(50)));
+    let router = Router::new().route("/v1/ws/chat", get(chat_websocket).route_layer(Extension(queue.clone())));
+    let mut socket = connect(router).await;
+    let held = queue.admit(Default::default()).await.unwrap();
+    let mut received = Vec::new();
+    send(&mut socket, chat("queued", "ws-echo", "hi")).await;
+    until_done(&mut socket, "queued", &mut received).await;
+    assert_eq!(received[0]["error"]["code"], "gateway_overloaded");
+    drop(held);
+    send(&mut socket, chat("admitted", "ws-echo", "hi")).await;
+    until_done(&mut socket, "admitted", &mut received).await;
+    assert_eq!(content_of(&received, "admitted"), "echo: hi");
+    // 流式响应开始后即释放准入名额
+    assert!(queue.admit(Default::default()).await.is_ok());
+    println!("✅ Each conversation waits for an admission permit");
+}